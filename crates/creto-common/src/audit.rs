@@ -0,0 +1,227 @@
+//! Structured audit events.
+//!
+//! Services report security-relevant state changes as [`AuditEvent`]s to an
+//! [`AuditSink`], so a consumer such as creto-audit receives one stream of
+//! events across products. Events never carry secret material or payload
+//! contents; `detail` holds identifiers and outcomes only.
+//!
+//! Whether a sink failure blocks the state change it describes is up to the
+//! emitting service, per its [`AuditMode`].
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::CretoResult;
+use crate::identity::{AgentId, OrganizationId, UserId};
+
+/// Who caused an audited change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditActor {
+    /// The system itself, e.g. a timeout worker.
+    System,
+    /// A human user.
+    User { user_id: UserId },
+    /// An agent.
+    Agent { agent_id: AgentId },
+    /// An automated policy decision.
+    Policy { policy_id: String },
+}
+
+/// How an audited action ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The action took effect.
+    Success,
+    /// The action was attempted and failed.
+    Failure,
+    /// The action was refused by a policy or check.
+    Denied,
+}
+
+/// A structured audit record of one action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// Unique event ID.
+    pub id: Uuid,
+    /// Who performed the action.
+    pub actor: AuditActor,
+    /// Organization the subject belongs to.
+    pub organization_id: OrganizationId,
+    /// ID of the thing acted on (an oversight request, a sandbox, ...).
+    pub subject_id: Uuid,
+    /// Dotted action name, e.g. `oversight.request.approved`.
+    pub action: String,
+    /// How the action ended.
+    pub outcome: AuditOutcome,
+    /// When the action happened.
+    pub timestamp: DateTime<Utc>,
+    /// Action-specific identifiers and results.
+    pub detail: serde_json::Value,
+}
+
+impl AuditEvent {
+    /// A successful action at the current time, with no detail.
+    pub fn new(
+        actor: AuditActor,
+        organization_id: OrganizationId,
+        subject_id: Uuid,
+        action: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            actor,
+            organization_id,
+            subject_id,
+            action: action.into(),
+            outcome: AuditOutcome::Success,
+            timestamp: Utc::now(),
+            detail: serde_json::Value::Null,
+        }
+    }
+
+    /// Set the outcome.
+    pub fn with_outcome(mut self, outcome: AuditOutcome) -> Self {
+        self.outcome = outcome;
+        self
+    }
+
+    /// Set when the action happened.
+    pub fn at(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Set the detail document.
+    pub fn with_detail(mut self, detail: serde_json::Value) -> Self {
+        self.detail = detail;
+        self
+    }
+}
+
+/// Receiver of audit events.
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    /// Record an event.
+    async fn emit(&self, event: AuditEvent) -> CretoResult<()>;
+}
+
+/// Whether a failed [`AuditSink::emit`] blocks the change it describes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditMode {
+    /// Make the change, then emit; a sink failure is logged.
+    #[default]
+    BestEffort,
+    /// Emit before making the change; a sink failure fails the operation
+    /// and the change is not made.
+    Strict,
+}
+
+/// Sink writing events to the `audit` tracing target.
+///
+/// The default sink: events reach whatever log pipeline the process ships
+/// its traces to.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingAuditSink;
+
+#[async_trait]
+impl AuditSink for TracingAuditSink {
+    async fn emit(&self, event: AuditEvent) -> CretoResult<()> {
+        tracing::info!(
+            target: "audit",
+            event_id = %event.id,
+            action = %event.action,
+            outcome = ?event.outcome,
+            organization_id = %event.organization_id,
+            subject_id = %event.subject_id,
+            actor = ?event.actor,
+            detail = %event.detail,
+            "Audit event"
+        );
+        Ok(())
+    }
+}
+
+/// In-memory sink for tests. Clones share the recorded events.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryAuditSink {
+    events: Arc<Mutex<Vec<AuditEvent>>>,
+}
+
+impl InMemoryAuditSink {
+    /// Create an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events emitted so far, oldest first.
+    pub fn events(&self) -> Vec<AuditEvent> {
+        self.events.lock().unwrap().clone()
+    }
+
+    /// Actions of the events emitted so far, oldest first.
+    pub fn actions(&self) -> Vec<String> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| e.action.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AuditSink for InMemoryAuditSink {
+    async fn emit(&self, event: AuditEvent) -> CretoResult<()> {
+        self.events.lock().unwrap().push(event);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_serialization() {
+        let user_id = UserId::new();
+        let event = AuditEvent::new(
+            AuditActor::User { user_id },
+            OrganizationId::new(),
+            Uuid::now_v7(),
+            "oversight.request.approved",
+        )
+        .with_outcome(AuditOutcome::Denied)
+        .with_detail(serde_json::json!({ "decision": "approve" }));
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["actor"]["type"], "user");
+        assert_eq!(json["outcome"], "denied");
+        assert_eq!(json["detail"]["decision"], "approve");
+
+        let parsed: AuditEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, event);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_sink_keeps_order() {
+        let sink = InMemoryAuditSink::new();
+        for action in ["a.created", "a.updated"] {
+            sink.emit(AuditEvent::new(
+                AuditActor::System,
+                OrganizationId::new(),
+                Uuid::now_v7(),
+                action,
+            ))
+            .await
+            .unwrap();
+        }
+        assert_eq!(sink.actions(), vec!["a.created", "a.updated"]);
+    }
+}
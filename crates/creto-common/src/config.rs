@@ -221,7 +221,7 @@ fn default_sandbox_timeout() -> u64 {
 }
 
 /// Messaging service configuration.
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[derive(Clone, Deserialize, Serialize, Default)]
 pub struct MessagingConfig {
     /// Maximum message size in bytes.
     #[serde(default = "default_max_message_size")]
@@ -234,6 +234,65 @@ pub struct MessagingConfig {
     /// Prekey replenishment threshold.
     #[serde(default = "default_prekey_threshold")]
     pub prekey_threshold: usize,
+
+    /// Base64-encoded 256-bit key that encrypts ratchet state at rest.
+    ///
+    /// Never serialized back out; without it sessions are not persisted.
+    #[serde(default, skip_serializing)]
+    pub state_encryption_key: Option<String>,
+
+    /// Ratchet steps between ratchet state writes; 1 writes after every
+    /// step.
+    #[serde(default = "default_ratchet_flush_steps")]
+    pub ratchet_flush_steps: u32,
+
+    /// Rate limit on each agent's sends, publishes and session
+    /// establishments.
+    #[serde(default)]
+    pub agent_rate_limit: MessagingRateLimit,
+
+    /// Rate limit shared by all agents of an organization.
+    #[serde(default)]
+    pub organization_rate_limit: MessagingRateLimit,
+}
+
+/// Token-bucket limit on messages and payload bytes.
+///
+/// A zero rate leaves that dimension unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct MessagingRateLimit {
+    /// Sustained messages per second.
+    #[serde(default)]
+    pub messages_per_second: f64,
+
+    /// Messages that can be sent at once after a quiet period.
+    #[serde(default)]
+    pub message_burst: u32,
+
+    /// Sustained payload bytes per second.
+    #[serde(default)]
+    pub bytes_per_second: f64,
+
+    /// Payload bytes that can be sent at once after a quiet period.
+    #[serde(default)]
+    pub byte_burst: u64,
+}
+
+impl std::fmt::Debug for MessagingConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessagingConfig")
+            .field("max_message_size", &self.max_message_size)
+            .field("message_ttl_secs", &self.message_ttl_secs)
+            .field("prekey_threshold", &self.prekey_threshold)
+            .field(
+                "has_state_encryption_key",
+                &self.state_encryption_key.is_some(),
+            )
+            .field("ratchet_flush_steps", &self.ratchet_flush_steps)
+            .field("agent_rate_limit", &self.agent_rate_limit)
+            .field("organization_rate_limit", &self.organization_rate_limit)
+            .finish()
+    }
 }
 
 fn default_max_message_size() -> usize {
@@ -248,6 +307,10 @@ fn default_prekey_threshold() -> usize {
     10
 }
 
+fn default_ratchet_flush_steps() -> u32 {
+    1
+}
+
 /// Complete enablement configuration.
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct EnablementConfig {
@@ -1,6 +1,7 @@
 //! Error types for the Creto Enablement Layer.
 
 use thiserror::Error;
+use uuid::Uuid;
 
 /// Result type alias for Creto operations.
 pub type CretoResult<T> = Result<T, CretoError>;
@@ -45,6 +46,15 @@ pub enum CretoError {
     #[error("Unauthorized approver: {0}")]
     UnauthorizedApprover(String),
 
+    #[error("Oversight request {request_id} is closed ({status})")]
+    RequestClosed { request_id: String, status: String },
+
+    #[error("Reviewer {reviewer_id} missed their deadline on oversight request {request_id}")]
+    AssignmentLapsed {
+        request_id: String,
+        reviewer_id: String,
+    },
+
     // ─────────────────────────────────────────────────────────────────────────
     // Runtime Errors
     // ─────────────────────────────────────────────────────────────────────────
@@ -63,6 +73,26 @@ pub enum CretoError {
     #[error("Network egress denied to: {destination}")]
     NetworkEgressDenied { destination: String },
 
+    #[error("Organization policy violation: cannot override pinned fields {}", .fields.join(", "))]
+    PolicyViolation { fields: Vec<String> },
+
+    #[error("Execution queue timeout after {seconds} seconds")]
+    QueueTimeout { seconds: u64 },
+
+    #[error("Sandbox provisioning failed during {phase} after {attempts} attempt(s): {message}")]
+    SandboxProvisioningFailed {
+        phase: String,
+        retryable: bool,
+        attempts: u32,
+        message: String,
+    },
+
+    #[error("Exclusion key {key} is held by execution {holder}")]
+    ExclusionConflict { key: String, holder: Uuid },
+
+    #[error("Idempotency key {key} was already used by execution {execution_id} with a different request")]
+    IdempotencyConflict { key: String, execution_id: Uuid },
+
     // ─────────────────────────────────────────────────────────────────────────
     // Messaging Errors
     // ─────────────────────────────────────────────────────────────────────────
@@ -81,6 +111,16 @@ pub enum CretoError {
     #[error("Message delivery failed: {0}")]
     MessageDeliveryFailed(String),
 
+    #[error("Mailbox full for {recipient}: {undelivered} undelivered messages, {bytes} bytes")]
+    MailboxFull {
+        recipient: String,
+        undelivered: u64,
+        bytes: u64,
+    },
+
+    #[error("Unsupported critical envelope version {major} (supported up to {supported})")]
+    UnsupportedEnvelopeVersion { major: u8, supported: u8 },
+
     // ─────────────────────────────────────────────────────────────────────────
     // Authorization Errors
     // ─────────────────────────────────────────────────────────────────────────
@@ -102,6 +142,9 @@ pub enum CretoError {
     #[error("Internal error: {0}")]
     Internal(String),
 
+    #[error("Audit event could not be recorded: {0}")]
+    AuditFailed(String),
+
     // ─────────────────────────────────────────────────────────────────────────
     // Crypto Errors
     // ─────────────────────────────────────────────────────────────────────────
@@ -211,6 +254,36 @@ impl CretoError {
             Self::Unauthorized(_) => "ENABLE-032",
             Self::LimitExceeded(_) => "ENABLE-033",
             Self::ValidationFailed(_) => "ENABLE-034",
+
+            // Policy Errors (ENABLE-035)
+            Self::PolicyViolation { .. } => "ENABLE-035",
+
+            // Scheduling Errors (ENABLE-036)
+            Self::QueueTimeout { .. } => "ENABLE-036",
+
+            // Additional Messaging Errors (ENABLE-037)
+            Self::MailboxFull { .. } => "ENABLE-037",
+
+            // Additional Oversight Errors (ENABLE-038)
+            Self::RequestClosed { .. } => "ENABLE-038",
+
+            // Additional Messaging Errors (ENABLE-039)
+            Self::UnsupportedEnvelopeVersion { .. } => "ENABLE-039",
+
+            // Additional Runtime Errors (ENABLE-040)
+            Self::SandboxProvisioningFailed { .. } => "ENABLE-040",
+
+            // Additional Runtime Errors (ENABLE-041)
+            Self::ExclusionConflict { .. } => "ENABLE-041",
+
+            // Additional Oversight Errors (ENABLE-042)
+            Self::AssignmentLapsed { .. } => "ENABLE-042",
+
+            // Additional Infrastructure Errors (ENABLE-043)
+            Self::AuditFailed(_) => "ENABLE-043",
+
+            // Additional Runtime Errors (ENABLE-044)
+            Self::IdempotencyConflict { .. } => "ENABLE-044",
         }
     }
 }
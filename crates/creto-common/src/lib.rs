@@ -8,21 +8,45 @@
 //! - `creto-runtime`: Sandboxed agent execution
 //! - `creto-messaging`: Secure agent-to-agent communication
 
+pub mod audit;
+pub mod clock;
 pub mod error;
 pub mod health;
 pub mod identity;
+pub mod metrics;
+pub mod shard;
+pub mod shutdown;
+pub mod signing;
+pub mod snapshot;
+pub mod stats;
 pub mod types;
 
 #[cfg(feature = "config")]
 pub mod config;
 
+pub use audit::{
+    AuditActor, AuditEvent, AuditMode, AuditOutcome, AuditSink, InMemoryAuditSink, TracingAuditSink,
+};
+pub use clock::{Clock, SystemClock, TestClock};
 pub use error::{CretoError, CretoResult};
 pub use health::{health_check, HealthResponse};
 pub use identity::{AgentId, OrganizationId, UserId};
-pub use types::{Money, Timestamp};
+pub use metrics::{
+    Counter, CounterVec, Gauge, GaugeVec, Histogram, HistogramVec, MetricKind, MetricsRegistry,
+};
+pub use shard::{ShardedMap, DEFAULT_SHARDS};
+pub use shutdown::{ShutdownCoordinator, ShutdownGuard, ShutdownReport};
+pub use signing::{hmac_sha256, verify_hmac_sha256, HMAC_SHA256_LEN};
+pub use snapshot::{
+    OrgSnapshot, SectionData, SectionStatus, SnapshotAggregator, SnapshotContributor, SnapshotMode,
+    SnapshotRequest, SnapshotSection, REDACTED, SNAPSHOT_SCHEMA_VERSION,
+};
+pub use stats::percentile_cont;
+pub use types::{Correlation, Currency, Money, Timestamp};
 
 #[cfg(feature = "config")]
 pub use config::{
     load_config, load_enablement_config, DatabaseConfig, EnablementConfig, MessagingConfig,
-    MeteringConfig, ObservabilityConfig, OversightConfig, RedisConfig, RuntimeConfig,
+    MessagingRateLimit, MeteringConfig, ObservabilityConfig, OversightConfig, RedisConfig,
+    RuntimeConfig,
 };
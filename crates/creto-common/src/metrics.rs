@@ -0,0 +1,605 @@
+//! Lightweight metrics registry with Prometheus text exposition.
+//!
+//! A [`MetricsRegistry`] holds counter, gauge and histogram families and
+//! renders them in the Prometheus text format with
+//! [`render_metrics`](MetricsRegistry::render_metrics), ready to be served
+//! from a `/metrics` endpoint:
+//!
+//! ```
+//! use creto_common::metrics::MetricsRegistry;
+//!
+//! let registry = MetricsRegistry::new();
+//! let requests = registry.counter_vec("http_requests_total", "HTTP requests", &["method"]);
+//! requests.with_label_values(&["GET"]).inc();
+//!
+//! assert!(registry
+//!     .render_metrics()
+//!     .contains("http_requests_total{method=\"GET\"} 1"));
+//! ```
+//!
+//! Handles ([`Counter`], [`Gauge`], [`Histogram`]) are cheap to clone and
+//! update with atomics only. Services take a registry through a
+//! `with_metrics` builder and keep their handles in an `Option`, so an
+//! instrumented hot path costs a single branch when no exporter is attached.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Default histogram buckets, in seconds, for request latencies.
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Histogram buckets, in seconds, for in-process latencies measured in
+/// microseconds.
+pub const MICROSECOND_BUCKETS: &[f64] = &[
+    0.000_001, 0.000_005, 0.000_01, 0.000_05, 0.000_1, 0.000_5, 0.001, 0.005, 0.01,
+];
+
+/// Kind of a metric family, as written on its `# TYPE` line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    /// Monotonically increasing count.
+    Counter,
+    /// Value that can go up and down.
+    Gauge,
+    /// Observations counted into buckets.
+    Histogram,
+}
+
+impl MetricKind {
+    /// Name used in the text exposition format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Counter => "counter",
+            Self::Gauge => "gauge",
+            Self::Histogram => "histogram",
+        }
+    }
+}
+
+/// Monotonically increasing counter.
+#[derive(Debug, Clone, Default)]
+pub struct Counter {
+    value: Arc<AtomicU64>,
+}
+
+impl Counter {
+    /// Increment by one.
+    pub fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    /// Increment by `n`.
+    pub fn inc_by(&self, n: u64) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Current value.
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+/// Integer gauge.
+#[derive(Debug, Clone, Default)]
+pub struct Gauge {
+    value: Arc<AtomicI64>,
+}
+
+impl Gauge {
+    /// Set the value.
+    pub fn set(&self, value: i64) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    /// Increment by one.
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    /// Decrement by one.
+    pub fn dec(&self) {
+        self.add(-1);
+    }
+
+    /// Add `delta`, which may be negative.
+    pub fn add(&self, delta: i64) {
+        self.value.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    /// Current value.
+    pub fn get(&self) -> i64 {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug)]
+struct HistogramCore {
+    /// Upper bounds, ascending, without `+Inf`.
+    bounds: Arc<[f64]>,
+    /// Per-bucket (non-cumulative) counts; the last slot is `+Inf`.
+    buckets: Box<[AtomicU64]>,
+    /// Sum of observations, as `f64` bits.
+    sum: AtomicU64,
+    count: AtomicU64,
+}
+
+/// Histogram of observations.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    core: Arc<HistogramCore>,
+}
+
+impl Histogram {
+    fn new(bounds: Arc<[f64]>) -> Self {
+        let buckets = (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect();
+        Self {
+            core: Arc::new(HistogramCore {
+                bounds,
+                buckets,
+                sum: AtomicU64::new(0f64.to_bits()),
+                count: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Record an observation.
+    pub fn observe(&self, value: f64) {
+        let core = &self.core;
+        let bucket = core.bounds.partition_point(|bound| *bound < value);
+        core.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        core.count.fetch_add(1, Ordering::Relaxed);
+        let _ = core
+            .sum
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + value).to_bits())
+            });
+    }
+
+    /// Number of observations.
+    pub fn count(&self) -> u64 {
+        self.core.count.load(Ordering::Relaxed)
+    }
+
+    /// Sum of observations.
+    pub fn sum(&self) -> f64 {
+        f64::from_bits(self.core.sum.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Series {
+    Counter(Counter),
+    Gauge(Gauge),
+    Histogram(Histogram),
+}
+
+#[derive(Debug)]
+struct Family {
+    name: String,
+    help: String,
+    kind: MetricKind,
+    label_names: Vec<String>,
+    bounds: Arc<[f64]>,
+    series: Mutex<BTreeMap<Vec<String>, Series>>,
+}
+
+impl Family {
+    fn series(&self, label_values: &[&str]) -> Series {
+        assert_eq!(
+            label_values.len(),
+            self.label_names.len(),
+            "metric {} takes labels {:?}",
+            self.name,
+            self.label_names
+        );
+        let key: Vec<String> = label_values.iter().map(|v| v.to_string()).collect();
+        self.series
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| match self.kind {
+                MetricKind::Counter => Series::Counter(Counter::default()),
+                MetricKind::Gauge => Series::Gauge(Gauge::default()),
+                MetricKind::Histogram => Series::Histogram(Histogram::new(self.bounds.clone())),
+            })
+            .clone()
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, escape_help(&self.help));
+        let _ = writeln!(out, "# TYPE {} {}", self.name, self.kind.as_str());
+
+        for (values, series) in self.series.lock().unwrap().iter() {
+            let labels = format_labels(&self.label_names, values, None);
+            match series {
+                Series::Counter(counter) => {
+                    let _ = writeln!(out, "{}{} {}", self.name, labels, counter.get());
+                }
+                Series::Gauge(gauge) => {
+                    let _ = writeln!(out, "{}{} {}", self.name, labels, gauge.get());
+                }
+                Series::Histogram(histogram) => {
+                    let core = &histogram.core;
+                    let mut cumulative = 0;
+                    for (i, bucket) in core.buckets.iter().enumerate() {
+                        cumulative += bucket.load(Ordering::Relaxed);
+                        let le = core
+                            .bounds
+                            .get(i)
+                            .map_or_else(|| "+Inf".to_string(), |bound| format_float(*bound));
+                        let labels = format_labels(&self.label_names, values, Some(&le));
+                        let _ = writeln!(out, "{}_bucket{} {}", self.name, labels, cumulative);
+                    }
+                    let _ = writeln!(
+                        out,
+                        "{}_sum{} {}",
+                        self.name,
+                        labels,
+                        format_float(histogram.sum())
+                    );
+                    let _ = writeln!(out, "{}_count{} {}", self.name, labels, histogram.count());
+                }
+            }
+        }
+    }
+}
+
+/// Counter family partitioned by labels.
+#[derive(Debug, Clone)]
+pub struct CounterVec {
+    family: Arc<Family>,
+}
+
+impl CounterVec {
+    /// The counter for `label_values`, given in the family's label order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values does not match the family's labels.
+    pub fn with_label_values(&self, label_values: &[&str]) -> Counter {
+        match self.family.series(label_values) {
+            Series::Counter(counter) => counter,
+            _ => unreachable!("counter family holds counters"),
+        }
+    }
+}
+
+/// Gauge family partitioned by labels.
+#[derive(Debug, Clone)]
+pub struct GaugeVec {
+    family: Arc<Family>,
+}
+
+impl GaugeVec {
+    /// The gauge for `label_values`, given in the family's label order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values does not match the family's labels.
+    pub fn with_label_values(&self, label_values: &[&str]) -> Gauge {
+        match self.family.series(label_values) {
+            Series::Gauge(gauge) => gauge,
+            _ => unreachable!("gauge family holds gauges"),
+        }
+    }
+}
+
+/// Histogram family partitioned by labels.
+#[derive(Debug, Clone)]
+pub struct HistogramVec {
+    family: Arc<Family>,
+}
+
+impl HistogramVec {
+    /// The histogram for `label_values`, given in the family's label order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values does not match the family's labels.
+    pub fn with_label_values(&self, label_values: &[&str]) -> Histogram {
+        match self.family.series(label_values) {
+            Series::Histogram(histogram) => histogram,
+            _ => unreachable!("histogram family holds histograms"),
+        }
+    }
+}
+
+/// Registry of metric families, shared by cloning.
+///
+/// Registering a name that already exists returns the existing family, so
+/// several instances of a service can report into one registry.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRegistry {
+    families: Arc<RwLock<BTreeMap<String, Arc<Family>>>>,
+}
+
+impl MetricsRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an unlabelled counter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is invalid or already registered with another
+    /// kind or other labels.
+    pub fn counter(&self, name: &str, help: &str) -> Counter {
+        self.counter_vec(name, help, &[]).with_label_values(&[])
+    }
+
+    /// Register a counter family with `labels`.
+    ///
+    /// # Panics
+    ///
+    /// As [`counter`](Self::counter), or if a label name is invalid.
+    pub fn counter_vec(&self, name: &str, help: &str, labels: &[&str]) -> CounterVec {
+        CounterVec {
+            family: self.register(name, help, MetricKind::Counter, labels, &[]),
+        }
+    }
+
+    /// Register an unlabelled gauge.
+    ///
+    /// # Panics
+    ///
+    /// As [`counter`](Self::counter).
+    pub fn gauge(&self, name: &str, help: &str) -> Gauge {
+        self.gauge_vec(name, help, &[]).with_label_values(&[])
+    }
+
+    /// Register a gauge family with `labels`.
+    ///
+    /// # Panics
+    ///
+    /// As [`counter_vec`](Self::counter_vec).
+    pub fn gauge_vec(&self, name: &str, help: &str, labels: &[&str]) -> GaugeVec {
+        GaugeVec {
+            family: self.register(name, help, MetricKind::Gauge, labels, &[]),
+        }
+    }
+
+    /// Register an unlabelled histogram with ascending bucket upper bounds
+    /// (`+Inf` is implied).
+    ///
+    /// # Panics
+    ///
+    /// As [`counter`](Self::counter), or if `buckets` is not strictly
+    /// ascending.
+    pub fn histogram(&self, name: &str, help: &str, buckets: &[f64]) -> Histogram {
+        self.histogram_vec(name, help, &[], buckets)
+            .with_label_values(&[])
+    }
+
+    /// Register a histogram family with `labels` and ascending bucket upper
+    /// bounds (`+Inf` is implied).
+    ///
+    /// # Panics
+    ///
+    /// As [`histogram`](Self::histogram), or if a label name is invalid or
+    /// `le`.
+    pub fn histogram_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+        buckets: &[f64],
+    ) -> HistogramVec {
+        assert!(
+            buckets.windows(2).all(|w| w[0] < w[1]) && buckets.iter().all(|b| b.is_finite()),
+            "histogram {} buckets must be finite and strictly ascending",
+            name
+        );
+        assert!(
+            !labels.contains(&"le"),
+            "histogram {} cannot use the reserved label \"le\"",
+            name
+        );
+        HistogramVec {
+            family: self.register(name, help, MetricKind::Histogram, labels, buckets),
+        }
+    }
+
+    /// Render every family in the Prometheus text exposition format
+    /// (version 0.0.4), families and series sorted by name.
+    pub fn render_metrics(&self) -> String {
+        let families = self.families.read().unwrap();
+        let mut out = String::new();
+        for family in families.values() {
+            family.render(&mut out);
+        }
+        out
+    }
+
+    fn register(
+        &self,
+        name: &str,
+        help: &str,
+        kind: MetricKind,
+        labels: &[&str],
+        buckets: &[f64],
+    ) -> Arc<Family> {
+        assert!(is_valid_metric_name(name), "invalid metric name {:?}", name);
+        for label in labels {
+            assert!(
+                is_valid_label_name(label),
+                "invalid label name {:?} on metric {}",
+                label,
+                name
+            );
+        }
+
+        let mut families = self.families.write().unwrap();
+        if let Some(existing) = families.get(name) {
+            assert!(
+                existing.kind == kind && existing.label_names == labels,
+                "metric {} is already registered as a {} with labels {:?}",
+                name,
+                existing.kind.as_str(),
+                existing.label_names
+            );
+            return existing.clone();
+        }
+
+        let family = Arc::new(Family {
+            name: name.to_string(),
+            help: help.to_string(),
+            kind,
+            label_names: labels.iter().map(|l| l.to_string()).collect(),
+            bounds: buckets.into(),
+            series: Mutex::new(BTreeMap::new()),
+        });
+        families.insert(name.to_string(), family.clone());
+        family
+    }
+}
+
+fn is_valid_metric_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+fn is_valid_label_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    !name.starts_with("__")
+        && chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Escape a label value: backslash, double quote and newline.
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escape HELP text: backslash and newline.
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn format_labels(names: &[String], values: &[String], le: Option<&str>) -> String {
+    let pairs = names
+        .iter()
+        .map(String::as_str)
+        .zip(values.iter().map(String::as_str))
+        .chain(le.map(|le| ("le", le)));
+
+    let mut out = String::new();
+    for (i, (name, value)) in pairs.enumerate() {
+        out.push(if i == 0 { '{' } else { ',' });
+        let _ = write!(out, "{}=\"{}\"", name, escape_label_value(value));
+    }
+    if !out.is_empty() {
+        out.push('}');
+    }
+    out
+}
+
+fn format_float(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_and_gauge_exposition() {
+        let registry = MetricsRegistry::new();
+        let events = registry.counter_vec("events_total", "Events seen", &["kind", "org"]);
+        events.with_label_values(&["api", "acme"]).inc_by(3);
+        events.with_label_values(&["api", "acme"]).inc();
+        let pending = registry.gauge("pending", "Pending items");
+        pending.set(5);
+        pending.dec();
+
+        let text = registry.render_metrics();
+        assert!(text.contains("# HELP events_total Events seen\n# TYPE events_total counter\n"));
+        assert!(text.contains("events_total{kind=\"api\",org=\"acme\"} 4\n"));
+        assert!(text.contains("# TYPE pending gauge\npending 4\n"));
+        // Families sorted by name
+        assert!(text.find("events_total").unwrap() < text.find("pending").unwrap());
+    }
+
+    #[test]
+    fn test_histogram_exposition() {
+        let registry = MetricsRegistry::new();
+        let latency = registry.histogram_vec("latency_seconds", "Latency", &["op"], &[0.25, 1.0]);
+        let reads = latency.with_label_values(&["read"]);
+        reads.observe(0.125);
+        reads.observe(0.25);
+        reads.observe(0.5);
+        reads.observe(3.0);
+
+        let text = registry.render_metrics();
+        assert!(text.contains("# TYPE latency_seconds histogram\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"read\",le=\"0.25\"} 2\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"read\",le=\"1\"} 3\n"));
+        assert!(text.contains("latency_seconds_bucket{op=\"read\",le=\"+Inf\"} 4\n"));
+        assert!(text.contains("latency_seconds_sum{op=\"read\"} 3.875\n"));
+        assert!(text.contains("latency_seconds_count{op=\"read\"} 4\n"));
+    }
+
+    #[test]
+    fn test_label_values_and_help_are_escaped() {
+        let registry = MetricsRegistry::new();
+        registry
+            .counter_vec("escaped_total", "Line one\nback\\slash", &["path"])
+            .with_label_values(&["C:\\tmp \"quoted\"\nnext"])
+            .inc();
+
+        let text = registry.render_metrics();
+        assert!(text.contains("# HELP escaped_total Line one\\nback\\\\slash\n"));
+        assert!(text.contains("escaped_total{path=\"C:\\\\tmp \\\"quoted\\\"\\nnext\"} 1\n"));
+    }
+
+    #[test]
+    fn test_reregistering_returns_the_same_family() {
+        let registry = MetricsRegistry::new();
+        registry.counter("shared_total", "Shared").inc();
+        registry.counter("shared_total", "Shared").inc();
+        assert!(registry.render_metrics().contains("shared_total 2\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "already registered")]
+    fn test_reregistering_with_another_kind_panics() {
+        let registry = MetricsRegistry::new();
+        registry.counter("clash", "Clash");
+        registry.gauge("clash", "Clash");
+    }
+
+    #[test]
+    fn test_name_validation() {
+        assert!(is_valid_metric_name("creto_metering:events_total"));
+        assert!(!is_valid_metric_name("9lives"));
+        assert!(!is_valid_metric_name("with-dash"));
+        assert!(is_valid_label_name("reason"));
+        assert!(!is_valid_label_name("__reserved"));
+        assert!(!is_valid_label_name(""));
+    }
+}
@@ -0,0 +1,175 @@
+//! Hash-sharded maps for hot paths.
+//!
+//! A single `RwLock<HashMap>` makes every reader wait behind any writer, so
+//! one quota usage update or rate limit refill stalls every concurrent
+//! check. [`ShardedMap`] spreads keys over independently locked shards: a
+//! writer only blocks readers of keys that hash to the same shard.
+//!
+//! Values are plain data that stay consistent if a panic unwinds through a
+//! closure, so a poisoned shard is used as is rather than failing every
+//! later access.
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Shards used when none are configured.
+pub const DEFAULT_SHARDS: usize = 64;
+
+/// A map split into independently locked shards by key hash.
+pub struct ShardedMap<K, V> {
+    shards: Box<[RwLock<HashMap<K, V>>]>,
+    hasher: RandomState,
+}
+
+impl<K: Hash + Eq, V> ShardedMap<K, V> {
+    /// Map with `shards` shards, rounded up to a power of two.
+    pub fn new(shards: usize) -> Self {
+        let count = shards.max(1).next_power_of_two();
+        Self {
+            shards: (0..count).map(|_| RwLock::new(HashMap::new())).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Apply `f` to a key's value under its shard's read lock.
+    pub fn read<Q, R>(&self, key: &Q, f: impl FnOnce(&V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        read(self.shard(key)).get(key).map(f)
+    }
+
+    /// Apply `f` to a key's value under its shard's write lock.
+    pub fn update<Q, R>(&self, key: &Q, f: impl FnOnce(&mut V) -> R) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        write(self.shard(key)).get_mut(key).map(f)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        read(self.shard(key)).contains_key(key)
+    }
+
+    /// Apply `f` to a key's value under its shard's write lock, inserting
+    /// `make()` first if it has none.
+    pub fn update_or_insert_with<R>(
+        &self,
+        key: K,
+        make: impl FnOnce() -> V,
+        f: impl FnOnce(&mut V) -> R,
+    ) -> R {
+        f(write(self.shard(&key)).entry(key).or_insert_with(make))
+    }
+
+    /// Insert a value, returning the one it replaced.
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        write(self.shard(&key)).insert(key, value)
+    }
+
+    /// Remove a key, returning its value.
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        write(self.shard(key)).remove(key)
+    }
+
+    /// Visit every entry, one shard at a time under its read lock.
+    pub fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        for shard in self.shards.iter() {
+            for (key, value) in read(shard).iter() {
+                f(key, value);
+            }
+        }
+    }
+
+    /// Visit every entry mutably, one shard at a time under its write lock.
+    pub fn for_each_mut(&self, mut f: impl FnMut(&K, &mut V)) {
+        for shard in self.shards.iter() {
+            for (key, value) in write(shard).iter_mut() {
+                f(key, value);
+            }
+        }
+    }
+
+    /// Keep only the entries `keep` accepts, one shard at a time under its
+    /// write lock, returning how many were removed.
+    pub fn retain(&self, mut keep: impl FnMut(&K, &mut V) -> bool) -> usize {
+        let mut removed = 0;
+        for shard in self.shards.iter() {
+            let mut shard = write(shard);
+            let before = shard.len();
+            shard.retain(|key, value| keep(key, value));
+            removed += before - shard.len();
+        }
+        removed
+    }
+
+    fn shard<Q>(&self, key: &Q) -> &RwLock<HashMap<K, V>>
+    where
+        Q: Hash + ?Sized,
+    {
+        let hash = self.hasher.hash_one(key) as usize;
+        &self.shards[hash & (self.shards.len() - 1)]
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> ShardedMap<K, V> {
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.read(key, V::clone)
+    }
+
+    /// A key's value, inserting `make()` first if it has none.
+    pub fn get_or_insert_with(&self, key: K, make: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        write(self.shard(&key))
+            .entry(key)
+            .or_insert_with(make)
+            .clone()
+    }
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_or_insert_with_and_retain() {
+        let map = ShardedMap::new(4);
+        for key in 0..100u32 {
+            map.update_or_insert_with(key, || 0, |value| *value += key);
+        }
+        map.update_or_insert_with(7, || 0, |value| *value += 1);
+        assert_eq!(map.get(&7), Some(8));
+
+        assert_eq!(map.retain(|_, value| *value % 2 == 0), 49);
+        assert!(map.contains_key(&7));
+        assert!(map.contains_key(&8));
+        assert!(!map.contains_key(&9));
+    }
+}
@@ -0,0 +1,266 @@
+//! Coordinated shutdown for background workers.
+//!
+//! Services register each background task with a [`ShutdownCoordinator`] and
+//! receive a [`ShutdownGuard`]. The guard carries a cancellation token and
+//! reports completion when dropped, so [`ShutdownCoordinator::shutdown`] can
+//! wait for workers to drain and report which ones were abandoned.
+//!
+//! Workers built on [`ShutdownGuard::run_periodic`] only observe cancellation
+//! between passes: a pass that has started (and any transaction it holds) runs
+//! to completion before the worker exits.
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Outcome of a coordinated shutdown.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Tasks that finished before the timeout.
+    pub completed: Vec<String>,
+    /// Tasks still running when the timeout elapsed.
+    pub abandoned: Vec<String>,
+}
+
+impl ShutdownReport {
+    /// Check if every registered task drained before the timeout.
+    pub fn is_clean(&self) -> bool {
+        self.abandoned.is_empty()
+    }
+}
+
+struct RegisteredTask {
+    name: String,
+    done: oneshot::Receiver<()>,
+}
+
+/// Tracks background tasks and shuts them down together.
+#[derive(Default)]
+pub struct ShutdownCoordinator {
+    token: CancellationToken,
+    tasks: Mutex<Vec<RegisteredTask>>,
+}
+
+impl ShutdownCoordinator {
+    /// Create a new coordinator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a background task.
+    ///
+    /// The task must watch the guard's token and drop the guard when it has
+    /// finished. Tasks registered after shutdown starts are cancelled
+    /// immediately and are not waited on. Tasks that have already finished
+    /// are forgotten here, so short-lived tasks can be registered freely.
+    pub fn register(&self, name: impl Into<String>) -> ShutdownGuard {
+        let name = name.into();
+        let (done_tx, done_rx) = oneshot::channel();
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain_mut(|task| {
+            !matches!(
+                task.done.try_recv(),
+                Err(oneshot::error::TryRecvError::Closed)
+            )
+        });
+        if !self.token.is_cancelled() {
+            tasks.push(RegisteredTask {
+                name: name.clone(),
+                done: done_rx,
+            });
+        }
+
+        ShutdownGuard {
+            name,
+            token: self.token.child_token(),
+            _done: done_tx,
+        }
+    }
+
+    /// Register and spawn a background task.
+    pub fn spawn<F, Fut>(&self, name: impl Into<String>, task: F) -> JoinHandle<()>
+    where
+        F: FnOnce(ShutdownGuard) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        tokio::spawn(task(self.register(name)))
+    }
+
+    /// Check if shutdown has been requested.
+    pub fn is_shutting_down(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Number of registered tasks that have not been shut down yet.
+    pub fn task_count(&self) -> usize {
+        self.tasks.lock().unwrap().len()
+    }
+
+    /// Cancel all registered tasks and wait up to `timeout` for them to finish.
+    pub async fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        self.token.cancel();
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        let deadline = Instant::now() + timeout;
+        let mut report = ShutdownReport::default();
+
+        for task in tasks {
+            // The sender is dropped with the guard, so any result means done
+            if tokio::time::timeout_at(deadline, task.done).await.is_ok() {
+                report.completed.push(task.name);
+            } else {
+                warn!(task = %task.name, "Background task did not stop before shutdown timeout");
+                report.abandoned.push(task.name);
+            }
+        }
+
+        info!(
+            completed = report.completed.len(),
+            abandoned = report.abandoned.len(),
+            "Background tasks shut down"
+        );
+        report
+    }
+}
+
+/// Handle held by a registered background task.
+///
+/// Dropping the guard tells the coordinator the task has finished.
+pub struct ShutdownGuard {
+    name: String,
+    token: CancellationToken,
+    _done: oneshot::Sender<()>,
+}
+
+impl ShutdownGuard {
+    /// Name the task was registered under.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Cancellation token for the task.
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Check if shutdown has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Wait until shutdown is requested.
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await
+    }
+
+    /// Run `pass` every `period` until shutdown is requested.
+    ///
+    /// The first pass runs immediately. Cancellation is checked only between
+    /// passes, so a pass is never interrupted part way through.
+    pub async fn run_periodic<F, Fut>(self, period: Duration, mut pass: F)
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut ticker = tokio::time::interval(period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = self.token.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+            pass().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_fast_worker_drains_cleanly() {
+        let coordinator = ShutdownCoordinator::new();
+        let passes = Arc::new(AtomicUsize::new(0));
+
+        let counter = passes.clone();
+        coordinator.spawn("sweeper", move |guard| {
+            guard.run_periodic(Duration::from_millis(5), move || {
+                let counter = counter.clone();
+                async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let report = coordinator.shutdown(Duration::from_secs(1)).await;
+        assert!(report.is_clean());
+        assert_eq!(report.completed, vec!["sweeper".to_string()]);
+        assert!(passes.load(Ordering::SeqCst) >= 1);
+        assert!(coordinator.is_shutting_down());
+        assert_eq!(coordinator.task_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_slow_worker_is_abandoned_after_timeout() {
+        let coordinator = ShutdownCoordinator::new();
+
+        coordinator.spawn("fast", |guard| async move {
+            guard.cancelled().await;
+        });
+        coordinator.spawn("slow", |guard| async move {
+            guard.cancelled().await;
+            // Simulates a batch that takes longer than the shutdown budget
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            drop(guard);
+        });
+
+        let started = Instant::now();
+        let report = coordinator.shutdown(Duration::from_millis(50)).await;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(!report.is_clean());
+        assert_eq!(report.completed, vec!["fast".to_string()]);
+        assert_eq!(report.abandoned, vec!["slow".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_finished_tasks_are_forgotten() {
+        let coordinator = ShutdownCoordinator::new();
+        let running = coordinator.register("running");
+        let finished: Vec<_> = (0..3)
+            .map(|_| coordinator.register("short-lived"))
+            .collect();
+        drop(finished);
+        assert_eq!(coordinator.task_count(), 4);
+
+        // Registering prunes the tasks that have dropped their guards
+        let late = coordinator.register("late");
+        assert_eq!(coordinator.task_count(), 2);
+
+        drop((running, late));
+        let report = coordinator.shutdown(Duration::from_millis(50)).await;
+        assert!(report.is_clean());
+    }
+
+    #[tokio::test]
+    async fn test_register_after_shutdown_is_cancelled() {
+        let coordinator = ShutdownCoordinator::new();
+        coordinator.shutdown(Duration::from_millis(10)).await;
+
+        let guard = coordinator.register("late");
+        assert!(guard.is_cancelled());
+        assert_eq!(guard.name(), "late");
+        assert_eq!(coordinator.task_count(), 0);
+    }
+}
@@ -0,0 +1,499 @@
+//! Point-in-time snapshots of an organization's enablement state.
+//!
+//! Debugging a customer issue usually needs the organization's state across
+//! every product at once: quotas and usage, wallet balance, pending oversight
+//! requests, running sandboxes, messaging sessions. Each product implements a
+//! [`SnapshotContributor`] that summarizes its own state for one organization
+//! (counts and top-N lists, never full dumps), and a [`SnapshotAggregator`]
+//! runs the contributors concurrently against a single capture timestamp.
+//!
+//! A contributor that exceeds the aggregator's timeout, or fails, yields a
+//! [`SectionStatus::TimedOut`] or [`SectionStatus::Failed`] section; the rest
+//! of the snapshot is unaffected. Every section reports how stale its data
+//! was relative to [`OrgSnapshot::captured_at`].
+//!
+//! In [`SnapshotMode::Redacted`] the free-text fields each contributor
+//! declares, and failure messages, are replaced with [`REDACTED`] so the
+//! snapshot can be shared outside the organization.
+//!
+//! The serialized shape is versioned by [`SNAPSHOT_SCHEMA_VERSION`].
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::{CretoError, CretoResult};
+use crate::identity::OrganizationId;
+
+/// Version of the [`OrgSnapshot`] layout; bumped on incompatible changes.
+pub const SNAPSHOT_SCHEMA_VERSION: u32 = 1;
+
+/// Replacement for free text stripped from a redacted snapshot.
+pub const REDACTED: &str = "[redacted]";
+
+/// What a contributor is asked to summarize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotRequest {
+    /// Organization being captured.
+    pub organization_id: OrganizationId,
+    /// Capture timestamp shared by every section.
+    pub as_of: DateTime<Utc>,
+    /// Maximum entries in any list a section returns.
+    pub top_n: usize,
+}
+
+/// One product's summary of an organization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionData {
+    /// When the summarized data was current (e.g. when a cache was filled).
+    pub as_of: DateTime<Utc>,
+    /// The summary.
+    pub data: serde_json::Value,
+    /// Object keys, anywhere in `data`, holding free text.
+    pub free_text_fields: Vec<String>,
+}
+
+impl SectionData {
+    /// Summary current at `as_of`.
+    pub fn new(as_of: DateTime<Utc>, data: &impl Serialize) -> CretoResult<Self> {
+        Ok(Self {
+            as_of,
+            data: serde_json::to_value(data)
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            free_text_fields: Vec::new(),
+        })
+    }
+
+    /// Declare keys whose values are free text, stripped in redacted mode.
+    pub fn with_free_text<I, S>(mut self, fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.free_text_fields
+            .extend(fields.into_iter().map(Into::into));
+        self
+    }
+}
+
+/// Summarizes one product's state for a snapshot.
+#[async_trait]
+pub trait SnapshotContributor: Send + Sync {
+    /// Name of the section, unique within a snapshot.
+    fn section(&self) -> &str;
+
+    /// Summarize the organization's state.
+    async fn contribute(&self, request: &SnapshotRequest) -> CretoResult<SectionData>;
+}
+
+/// Whether a snapshot is shared within the organization or outside it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotMode {
+    /// Everything contributors returned.
+    #[default]
+    Full,
+    /// Free text and failure messages replaced with [`REDACTED`].
+    Redacted,
+}
+
+/// How a section's contributor fared.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SectionStatus {
+    /// Data captured.
+    Ok,
+    /// The contributor did not finish within the timeout.
+    TimedOut {
+        /// Timeout that elapsed.
+        timeout_ms: u64,
+    },
+    /// The contributor returned an error.
+    Failed {
+        /// Error code.
+        code: String,
+        /// Error message.
+        message: String,
+    },
+}
+
+/// One product's part of a snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotSection {
+    /// Section name.
+    pub name: String,
+    /// Outcome.
+    #[serde(flatten)]
+    pub status: SectionStatus,
+    /// When the data was current; `None` unless captured.
+    pub as_of: Option<DateTime<Utc>>,
+    /// How far `as_of` lags the capture timestamp; `None` unless captured.
+    pub staleness_ms: Option<i64>,
+    /// The summary; `None` unless captured.
+    pub data: Option<serde_json::Value>,
+}
+
+/// An organization's state across products at one timestamp.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrgSnapshot {
+    /// Layout version, [`SNAPSHOT_SCHEMA_VERSION`].
+    pub schema_version: u32,
+    /// Organization captured.
+    pub organization_id: OrganizationId,
+    /// Capture timestamp every contributor was asked for.
+    pub captured_at: DateTime<Utc>,
+    /// Whether free text was stripped.
+    pub mode: SnapshotMode,
+    /// Sections in contributor registration order.
+    pub sections: Vec<SnapshotSection>,
+}
+
+impl OrgSnapshot {
+    /// Get a section by name.
+    pub fn section(&self, name: &str) -> Option<&SnapshotSection> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+}
+
+/// Runs snapshot contributors concurrently.
+pub struct SnapshotAggregator {
+    contributors: Vec<Arc<dyn SnapshotContributor>>,
+    timeout: Duration,
+    top_n: usize,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for SnapshotAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SnapshotAggregator {
+    /// Create an aggregator with no contributors, a 2 second per-contributor
+    /// timeout and lists capped at 10 entries.
+    pub fn new() -> Self {
+        Self {
+            contributors: Vec::new(),
+            timeout: Duration::from_secs(2),
+            top_n: 10,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Add a contributor.
+    pub fn with_contributor(mut self, contributor: Arc<dyn SnapshotContributor>) -> Self {
+        self.contributors.push(contributor);
+        self
+    }
+
+    /// Set how long each contributor may take.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the maximum entries in any section list.
+    pub fn with_top_n(mut self, top_n: usize) -> Self {
+        self.top_n = top_n;
+        self
+    }
+
+    /// Set the time source for the capture timestamp.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Capture an organization's snapshot.
+    ///
+    /// Never fails: a contributor that errors or times out is reported in
+    /// its section.
+    pub async fn capture(
+        &self,
+        organization_id: OrganizationId,
+        mode: SnapshotMode,
+    ) -> OrgSnapshot {
+        let request = SnapshotRequest {
+            organization_id,
+            as_of: self.clock.now(),
+            top_n: self.top_n,
+        };
+
+        let mut tasks = JoinSet::new();
+        for (index, contributor) in self.contributors.iter().enumerate() {
+            let contributor = Arc::clone(contributor);
+            let timeout = self.timeout;
+            tasks.spawn(async move {
+                let outcome = tokio::time::timeout(timeout, contributor.contribute(&request)).await;
+                (index, outcome)
+            });
+        }
+
+        let mut sections: Vec<Option<SnapshotSection>> = vec![None; self.contributors.len()];
+        while let Some(joined) = tasks.join_next().await {
+            // Contributor tasks are never cancelled; a panic is re-raised
+            let (index, outcome) =
+                joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
+            let name = self.contributors[index].section().to_string();
+            let section = match outcome {
+                Ok(Ok(data)) => captured(name, data, request.as_of, mode),
+                Ok(Err(e)) => {
+                    tracing::warn!(section = %name, error = %e, "Snapshot contributor failed");
+                    empty(
+                        name,
+                        SectionStatus::Failed {
+                            code: e.code().to_string(),
+                            message: match mode {
+                                SnapshotMode::Full => e.to_string(),
+                                SnapshotMode::Redacted => REDACTED.to_string(),
+                            },
+                        },
+                    )
+                }
+                Err(_) => {
+                    tracing::warn!(section = %name, "Snapshot contributor timed out");
+                    empty(
+                        name,
+                        SectionStatus::TimedOut {
+                            timeout_ms: self.timeout.as_millis() as u64,
+                        },
+                    )
+                }
+            };
+            sections[index] = Some(section);
+        }
+
+        OrgSnapshot {
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
+            organization_id,
+            captured_at: request.as_of,
+            mode,
+            sections: sections.into_iter().flatten().collect(),
+        }
+    }
+}
+
+fn captured(
+    name: String,
+    mut section: SectionData,
+    captured_at: DateTime<Utc>,
+    mode: SnapshotMode,
+) -> SnapshotSection {
+    if mode == SnapshotMode::Redacted {
+        redact_fields(&mut section.data, &section.free_text_fields);
+    }
+    SnapshotSection {
+        name,
+        status: SectionStatus::Ok,
+        as_of: Some(section.as_of),
+        staleness_ms: Some((captured_at - section.as_of).num_milliseconds().max(0)),
+        data: Some(section.data),
+    }
+}
+
+fn empty(name: String, status: SectionStatus) -> SnapshotSection {
+    SnapshotSection {
+        name,
+        status,
+        as_of: None,
+        staleness_ms: None,
+        data: None,
+    }
+}
+
+/// Replace the non-null values of `fields`, at any depth, with [`REDACTED`].
+fn redact_fields(value: &mut serde_json::Value, fields: &[String]) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if fields.iter().any(|f| f == key) {
+                    if !value.is_null() {
+                        *value = REDACTED.into();
+                    }
+                } else {
+                    redact_fields(value, fields);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                redact_fields(item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+    use serde_json::json;
+
+    struct Mock {
+        name: &'static str,
+        delay: Duration,
+        result: fn(&SnapshotRequest) -> CretoResult<SectionData>,
+    }
+
+    #[async_trait]
+    impl SnapshotContributor for Mock {
+        fn section(&self) -> &str {
+            self.name
+        }
+
+        async fn contribute(&self, request: &SnapshotRequest) -> CretoResult<SectionData> {
+            tokio::time::sleep(self.delay).await;
+            (self.result)(request)
+        }
+    }
+
+    fn mock(
+        name: &'static str,
+        delay_ms: u64,
+        result: fn(&SnapshotRequest) -> CretoResult<SectionData>,
+    ) -> Arc<dyn SnapshotContributor> {
+        Arc::new(Mock {
+            name,
+            delay: Duration::from_millis(delay_ms),
+            result,
+        })
+    }
+
+    fn requests(request: &SnapshotRequest) -> CretoResult<SectionData> {
+        let data = json!({
+            "pending_count": 1,
+            "top": [{"id": "r1", "description": "Deploy to prod", "reason": null}],
+        });
+        Ok(
+            SectionData::new(request.as_of - chrono::Duration::seconds(5), &data)?
+                .with_free_text(["description", "reason"]),
+        )
+    }
+
+    fn aggregator() -> SnapshotAggregator {
+        let clock = Arc::new(TestClock::new(Utc::now()));
+        SnapshotAggregator::new()
+            .with_clock(clock)
+            .with_timeout(Duration::from_millis(100))
+            .with_contributor(mock("oversight", 0, requests))
+            .with_contributor(mock("runtime", 10_000, requests))
+            .with_contributor(mock("metering", 0, |_| {
+                Err(CretoError::Database(
+                    "connection refused to 10.0.0.5".to_string(),
+                ))
+            }))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_and_failing_contributors_are_isolated() {
+        let snapshot = aggregator()
+            .capture(OrganizationId::new(), SnapshotMode::Full)
+            .await;
+
+        let names: Vec<_> = snapshot.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["oversight", "runtime", "metering"]);
+
+        let oversight = snapshot.section("oversight").unwrap();
+        assert_eq!(oversight.status, SectionStatus::Ok);
+        assert_eq!(oversight.staleness_ms, Some(5000));
+        assert_eq!(
+            oversight.data.as_ref().unwrap()["top"][0]["description"],
+            "Deploy to prod"
+        );
+
+        assert_eq!(
+            snapshot.section("runtime").unwrap().status,
+            SectionStatus::TimedOut { timeout_ms: 100 }
+        );
+        assert!(snapshot.section("runtime").unwrap().data.is_none());
+        assert!(matches!(
+            &snapshot.section("metering").unwrap().status,
+            SectionStatus::Failed { message, .. } if message.contains("10.0.0.5")
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_redacted_mode_strips_free_text() {
+        let snapshot = aggregator()
+            .capture(OrganizationId::new(), SnapshotMode::Redacted)
+            .await;
+
+        let data = snapshot.section("oversight").unwrap().data.clone().unwrap();
+        assert_eq!(data["top"][0]["description"], REDACTED);
+        assert_eq!(data["top"][0]["id"], "r1");
+        assert!(data["top"][0]["reason"].is_null());
+        assert_eq!(data["pending_count"], 1);
+        assert_eq!(
+            snapshot.section("metering").unwrap().status,
+            SectionStatus::Failed {
+                code: "ENABLE-022".to_string(),
+                message: REDACTED.to_string(),
+            }
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_section_schema_is_stable() {
+        let snapshot = aggregator()
+            .capture(OrganizationId::new(), SnapshotMode::Full)
+            .await;
+        let json = serde_json::to_value(&snapshot).unwrap();
+
+        let keys = |value: &serde_json::Value| {
+            let mut keys: Vec<String> = value.as_object().unwrap().keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(
+            keys(&json),
+            vec![
+                "captured_at",
+                "mode",
+                "organization_id",
+                "schema_version",
+                "sections"
+            ]
+        );
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["mode"], "full");
+        assert_eq!(
+            keys(&json["sections"][0]),
+            vec!["as_of", "data", "name", "staleness_ms", "status"]
+        );
+        assert_eq!(json["sections"][0]["status"], "ok");
+        assert_eq!(
+            keys(&json["sections"][1]),
+            vec![
+                "as_of",
+                "data",
+                "name",
+                "staleness_ms",
+                "status",
+                "timeout_ms"
+            ]
+        );
+        assert_eq!(json["sections"][1]["status"], "timed_out");
+        assert_eq!(
+            keys(&json["sections"][2]),
+            vec![
+                "as_of",
+                "code",
+                "data",
+                "message",
+                "name",
+                "staleness_ms",
+                "status"
+            ]
+        );
+
+        let parsed: OrgSnapshot = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, snapshot);
+    }
+}
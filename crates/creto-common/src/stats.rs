@@ -0,0 +1,32 @@
+//! Summary statistics shared by in-memory aggregations.
+//!
+//! Kept in step with the SQL the repositories run, so an in-memory store
+//! and PostgreSQL report the same numbers for the same rows.
+
+/// Continuous percentile of sorted values, interpolating linearly between
+/// the two nearest ranks, as PostgreSQL's `percentile_cont` does.
+///
+/// `fraction` is between 0 and 1. Returns `None` for no values.
+pub fn percentile_cont(sorted: &[f64], fraction: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = fraction * last as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_cont_interpolates_between_ranks() {
+        let values: Vec<f64> = (1..=10).map(|v| (v * 10) as f64).collect();
+
+        assert_eq!(percentile_cont(&values, 0.0), Some(10.0));
+        assert_eq!(percentile_cont(&values, 0.5), Some(55.0));
+        assert!((percentile_cont(&values, 0.95).unwrap() - 95.5).abs() < 1e-9);
+        assert_eq!(percentile_cont(&values, 1.0), Some(100.0));
+        assert_eq!(percentile_cont(&[7.0], 0.95), Some(7.0));
+        assert_eq!(percentile_cont(&[], 0.5), None);
+    }
+}
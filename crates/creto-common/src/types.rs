@@ -1,10 +1,13 @@
 //! Common value types used across the Enablement Layer.
 
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 /// Monetary value with currency code.
 ///
 /// Uses minor units (cents) to avoid floating-point precision issues.
+/// Currency conversions round to the nearest minor unit, with halves rounded
+/// away from zero.
 ///
 /// # Example
 /// ```
@@ -45,6 +48,16 @@ impl Money {
         Ok(Money::new(self.amount + other.amount, self.currency))
     }
 
+    /// Convert to another currency at `rate` units of `to` per unit of `self.currency`.
+    ///
+    /// Accounts for differing minor-unit factors and rounds to the nearest
+    /// minor unit of the target currency (halves away from zero).
+    pub fn convert(&self, to: Currency, rate: f64) -> Money {
+        let major = self.amount as f64 / self.currency.minor_unit_factor() as f64;
+        let converted = (major * rate * to.minor_unit_factor() as f64).round() as i64;
+        Money::new(converted, to)
+    }
+
     /// Check if this amount is zero.
     pub fn is_zero(&self) -> bool {
         self.amount == 0
@@ -63,9 +76,10 @@ impl std::fmt::Display for Money {
 }
 
 /// ISO 4217 currency codes.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Currency {
+    #[default]
     USD,
     EUR,
     GBP,
@@ -82,6 +96,31 @@ impl Currency {
             _ => 100,
         }
     }
+
+    /// ISO 4217 code (e.g., "USD").
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::USD => "USD",
+            Currency::EUR => "EUR",
+            Currency::GBP => "GBP",
+            Currency::JPY => "JPY",
+            Currency::CAD => "CAD",
+            Currency::AUD => "AUD",
+        }
+    }
+
+    /// Parse an ISO 4217 code.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "USD" => Some(Currency::USD),
+            "EUR" => Some(Currency::EUR),
+            "GBP" => Some(Currency::GBP),
+            "JPY" => Some(Currency::JPY),
+            "CAD" => Some(Currency::CAD),
+            "AUD" => Some(Currency::AUD),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for Currency {
@@ -149,6 +188,59 @@ impl std::fmt::Display for Timestamp {
     }
 }
 
+/// Trace identifiers linking related entities across products.
+///
+/// Callers set a `correlation_id` once at the edge; every artifact derived
+/// from it (oversight requests, executions, usage events, envelopes, receipts)
+/// carries the same id, with `caused_by` pointing at its direct parent.
+///
+/// # Example
+/// ```
+/// use creto_common::Correlation;
+/// use uuid::Uuid;
+///
+/// let root = Correlation::new(Uuid::now_v7());
+/// let execution_id = Uuid::now_v7();
+/// let child = root.derive(execution_id);
+/// assert_eq!(child.correlation_id, root.correlation_id);
+/// assert_eq!(child.caused_by, Some(execution_id));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Correlation {
+    /// Identifier shared by everything related to one originating action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+
+    /// Identifier of the entity that directly caused this one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caused_by: Option<Uuid>,
+}
+
+impl Correlation {
+    /// Start a new chain rooted at `correlation_id`.
+    pub fn new(correlation_id: Uuid) -> Self {
+        Self {
+            correlation_id: Some(correlation_id),
+            caused_by: None,
+        }
+    }
+
+    /// Correlation for an artifact derived from the entity `parent_id`.
+    ///
+    /// The correlation id is inherited unchanged.
+    pub fn derive(&self, parent_id: Uuid) -> Self {
+        Self {
+            correlation_id: self.correlation_id,
+            caused_by: Some(parent_id),
+        }
+    }
+
+    /// Check if no trace identifiers are set.
+    pub fn is_empty(&self) -> bool {
+        self.correlation_id.is_none() && self.caused_by.is_none()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,6 +266,28 @@ mod tests {
         assert!(a.add(&b).is_err());
     }
 
+    #[test]
+    fn test_money_convert_rounds_half_away_from_zero() {
+        // $10.05 at 0.9 EUR/USD = 9.045 EUR -> 9.05 EUR
+        let eur = Money::usd(1005).convert(Currency::EUR, 0.9);
+        assert_eq!(eur, Money::new(905, Currency::EUR));
+
+        // Minor-unit factors differ: $1.00 at 150 JPY/USD = 150 JPY
+        let jpy = Money::usd(100).convert(Currency::JPY, 150.0);
+        assert_eq!(jpy, Money::new(150, Currency::JPY));
+
+        let refund = Money::usd(-1005).convert(Currency::EUR, 0.9);
+        assert_eq!(refund.amount, -905);
+    }
+
+    #[test]
+    fn test_currency_code_roundtrip() {
+        for currency in [Currency::USD, Currency::EUR, Currency::JPY] {
+            assert_eq!(Currency::from_code(currency.code()), Some(currency));
+        }
+        assert_eq!(Currency::from_code("XXX"), None);
+    }
+
     #[test]
     fn test_timestamp_ordering() {
         let t1 = Timestamp::from_millis(1000);
@@ -181,4 +295,18 @@ mod tests {
         assert!(t1.is_before(&t2));
         assert!(!t2.is_before(&t1));
     }
+
+    #[test]
+    fn test_correlation_derive_keeps_id() {
+        let root = Correlation::new(Uuid::now_v7());
+        assert!(!root.is_empty());
+        assert!(root.caused_by.is_none());
+
+        let parent = Uuid::now_v7();
+        let child = root.derive(parent);
+        assert_eq!(child.correlation_id, root.correlation_id);
+        assert_eq!(child.caused_by, Some(parent));
+
+        assert!(Correlation::default().is_empty());
+    }
 }
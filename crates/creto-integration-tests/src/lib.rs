@@ -4,3 +4,4 @@
 //! and end-to-end scenarios.
 
 pub mod common;
+pub mod load;
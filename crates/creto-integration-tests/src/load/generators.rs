@@ -0,0 +1,239 @@
+//! Seeded generators for synthetic load.
+//!
+//! Every generator draws from a [`SeededRng`], so the same seed produces the
+//! same sequence of events, requests and executions on every run. Only the
+//! IDs and timestamps assigned by the constructors of the generated types
+//! differ between runs.
+
+use creto_common::{AgentId, OrganizationId};
+use creto_metering::{UsageEvent, UsageEventType};
+use creto_oversight::{ActionType, OversightRequest, Priority};
+use creto_runtime::{ExecutionPriority, ExecutionRequest, SandboxId};
+use uuid::Uuid;
+
+/// Small, fast xorshift64* generator. Not for anything but load shaping.
+#[derive(Debug, Clone)]
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        // Zero is a fixed point of xorshift
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform in `0..bound` (`bound` must be non-zero).
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// True with probability `percent`/100.
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    /// A UUID drawn from the generator, for IDs that must repeat across runs.
+    pub fn uuid(&mut self) -> Uuid {
+        Uuid::from_u64_pair(self.next_u64(), self.next_u64())
+    }
+}
+
+/// A fixed population of organizations with their agents.
+#[derive(Debug, Clone)]
+pub struct Tenants {
+    pub organizations: Vec<OrganizationId>,
+    /// Agents per organization, in the same order.
+    pub agents: Vec<Vec<AgentId>>,
+}
+
+impl Tenants {
+    pub fn new(rng: &mut SeededRng, organizations: usize, agents_per_org: usize) -> Self {
+        let organizations: Vec<OrganizationId> = (0..organizations.max(1))
+            .map(|_| OrganizationId::from_uuid(rng.uuid()))
+            .collect();
+        let agents = organizations
+            .iter()
+            .map(|_| {
+                (0..agents_per_org.max(1))
+                    .map(|_| AgentId::from_uuid(rng.uuid()))
+                    .collect()
+            })
+            .collect();
+        Self {
+            organizations,
+            agents,
+        }
+    }
+
+    /// Every (organization, agent) pair.
+    pub fn pairs(&self) -> impl Iterator<Item = (OrganizationId, AgentId)> + '_ {
+        self.organizations
+            .iter()
+            .zip(&self.agents)
+            .flat_map(|(org, agents)| agents.iter().map(move |agent| (*org, *agent)))
+    }
+
+    /// A random (organization, agent) pair.
+    pub fn pick(&self, rng: &mut SeededRng) -> (OrganizationId, AgentId) {
+        let org = rng.below(self.organizations.len() as u64) as usize;
+        (self.organizations[org], *rng.pick(&self.agents[org]))
+    }
+}
+
+/// Usage events spread over a tenant population.
+#[derive(Debug, Clone)]
+pub struct UsageEventGenerator {
+    rng: SeededRng,
+    tenants: Tenants,
+}
+
+impl UsageEventGenerator {
+    const EVENT_TYPES: [UsageEventType; 4] = [
+        UsageEventType::ApiCall,
+        UsageEventType::InputTokens,
+        UsageEventType::OutputTokens,
+        UsageEventType::SandboxExecution,
+    ];
+
+    pub fn new(seed: u64, tenants: Tenants) -> Self {
+        Self {
+            rng: SeededRng::new(seed),
+            tenants,
+        }
+    }
+
+    pub fn tenants(&self) -> &Tenants {
+        &self.tenants
+    }
+
+    /// The next event. Token events carry larger quantities than calls.
+    pub fn next_event(&mut self) -> UsageEvent {
+        let (organization_id, agent_id) = self.tenants.pick(&mut self.rng);
+        let event_type = *self.rng.pick(&Self::EVENT_TYPES);
+        let quantity = match event_type {
+            UsageEventType::InputTokens | UsageEventType::OutputTokens => {
+                1 + self.rng.below(4000) as i64
+            }
+            _ => 1,
+        };
+        UsageEvent::builder()
+            .organization_id(organization_id)
+            .agent_id(agent_id)
+            .event_type(event_type)
+            .quantity(quantity)
+            .build()
+    }
+}
+
+impl Iterator for UsageEventGenerator {
+    type Item = UsageEvent;
+
+    fn next(&mut self) -> Option<UsageEvent> {
+        Some(self.next_event())
+    }
+}
+
+/// Oversight requests with a realistic priority mix (mostly normal).
+#[derive(Debug, Clone)]
+pub struct OversightRequestGenerator {
+    rng: SeededRng,
+    tenants: Tenants,
+}
+
+impl OversightRequestGenerator {
+    pub fn new(seed: u64, tenants: Tenants) -> Self {
+        Self {
+            rng: SeededRng::new(seed),
+            tenants,
+        }
+    }
+
+    pub fn next_request(&mut self) -> OversightRequest {
+        let (organization_id, agent_id) = self.tenants.pick(&mut self.rng);
+        let action = match self.rng.below(3) {
+            0 => ActionType::Transaction {
+                amount_cents: 100 + self.rng.below(1_000_000) as i64,
+                currency: "USD".to_string(),
+            },
+            1 => ActionType::DataAccess {
+                data_type: "customer_records".to_string(),
+                scope: "read".to_string(),
+            },
+            _ => ActionType::ExternalApi {
+                service: "payments".to_string(),
+                operation: "refund".to_string(),
+            },
+        };
+        let priority = match self.rng.below(100) {
+            0..=9 => Priority::Low,
+            10..=79 => Priority::Normal,
+            80..=94 => Priority::High,
+            _ => Priority::Critical,
+        };
+        OversightRequest::new(organization_id, agent_id, action, "Synthetic load request")
+            .with_priority(priority)
+    }
+}
+
+impl Iterator for OversightRequestGenerator {
+    type Item = OversightRequest;
+
+    fn next(&mut self) -> Option<OversightRequest> {
+        Some(self.next_request())
+    }
+}
+
+/// Execution requests against a fixed set of sandboxes.
+#[derive(Debug, Clone)]
+pub struct ExecutionRequestGenerator {
+    rng: SeededRng,
+    sandboxes: Vec<SandboxId>,
+}
+
+impl ExecutionRequestGenerator {
+    const SNIPPETS: [&'static str; 3] = [
+        "print('hello')",
+        "sum(range(1000))",
+        "import json; json.dumps({'ok': True})",
+    ];
+
+    /// Generator over `sandboxes`, which must not be empty.
+    pub fn new(seed: u64, sandboxes: Vec<SandboxId>) -> Self {
+        assert!(!sandboxes.is_empty(), "need at least one sandbox");
+        Self {
+            rng: SeededRng::new(seed),
+            sandboxes,
+        }
+    }
+
+    pub fn next_request(&mut self) -> ExecutionRequest {
+        let sandbox_id = *self.rng.pick(&self.sandboxes);
+        let code = *self.rng.pick(&Self::SNIPPETS);
+        let priority = if self.rng.chance(5) {
+            ExecutionPriority::High
+        } else {
+            ExecutionPriority::Normal
+        };
+        ExecutionRequest::new(sandbox_id, code)
+            .with_timeout(1 + self.rng.below(30) as u32)
+            .with_priority(priority)
+    }
+}
+
+impl Iterator for ExecutionRequestGenerator {
+    type Item = ExecutionRequest;
+
+    fn next(&mut self) -> Option<ExecutionRequest> {
+        Some(self.next_request())
+    }
+}
@@ -0,0 +1,15 @@
+//! Load-test harness and performance regression gate.
+//!
+//! [`generators`] produce seeded synthetic load, [`scenarios`] drive the
+//! metering ingestion path, the quota check path and the warm-pool
+//! checkout cycle in process, and [`report`] compares the results with the
+//! targets checked in under `baselines/load.json`. The gate itself is the
+//! `load_regression` test, which only builds with optimizations:
+//!
+//! ```text
+//! cargo test --release -p creto-integration-tests --test load_regression
+//! ```
+
+pub mod generators;
+pub mod report;
+pub mod scenarios;
@@ -0,0 +1,132 @@
+//! Results files and baseline comparison.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::scenarios::ScenarioResult;
+
+/// Per-scenario targets.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Threshold {
+    /// Lowest acceptable throughput.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_throughput_per_sec: Option<f64>,
+    /// Highest acceptable p99 latency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_p99_us: Option<f64>,
+    /// Highest acceptable share of operations that failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_error_rate: Option<f64>,
+}
+
+/// Checked-in targets for every scenario.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Fraction a result may miss a target by before it counts as a
+    /// regression.
+    pub tolerance: f64,
+    pub scenarios: BTreeMap<String, Threshold>,
+}
+
+/// A target a scenario missed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Regression {
+    pub scenario: String,
+    pub metric: String,
+    /// Target from the baseline.
+    pub target: f64,
+    /// Target after applying the tolerance.
+    pub allowed: f64,
+    pub actual: f64,
+}
+
+impl std::fmt::Display for Regression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {:.2} (target {:.2}, allowed {:.2})",
+            self.scenario, self.metric, self.actual, self.target, self.allowed
+        )
+    }
+}
+
+impl Baseline {
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Every target a result misses by more than `tolerance`. A scenario
+    /// with targets but no result is reported as missing.
+    pub fn compare(&self, results: &[ScenarioResult], tolerance: f64) -> Vec<Regression> {
+        let mut regressions = Vec::new();
+        for (scenario, threshold) in &self.scenarios {
+            let regression = |metric: &str, target: f64, allowed: f64, actual: f64| Regression {
+                scenario: scenario.clone(),
+                metric: metric.to_string(),
+                target,
+                allowed,
+                actual,
+            };
+            let Some(result) = results.iter().find(|r| &r.scenario == scenario) else {
+                regressions.push(regression("missing", 0.0, 0.0, 0.0));
+                continue;
+            };
+            if let Some(target) = threshold.min_throughput_per_sec {
+                let allowed = target * (1.0 - tolerance);
+                if result.throughput_per_sec < allowed {
+                    regressions.push(regression(
+                        "throughput_per_sec",
+                        target,
+                        allowed,
+                        result.throughput_per_sec,
+                    ));
+                }
+            }
+            if let Some(target) = threshold.max_p99_us {
+                let allowed = target * (1.0 + tolerance);
+                if result.latency.p99_us > allowed {
+                    regressions.push(regression("p99_us", target, allowed, result.latency.p99_us));
+                }
+            }
+            if let Some(target) = threshold.max_error_rate {
+                let rate = result.errors as f64 / result.operations.max(1) as f64;
+                // Error rates are absolute; tolerance does not widen them
+                if rate > target {
+                    regressions.push(regression("error_rate", target, target, rate));
+                }
+            }
+        }
+        regressions
+    }
+}
+
+/// Machine-readable outcome of a load run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadReport {
+    pub tolerance: f64,
+    pub scenarios: Vec<ScenarioResult>,
+    pub regressions: Vec<Regression>,
+}
+
+impl LoadReport {
+    /// Compare `results` against `baseline`.
+    pub fn new(results: Vec<ScenarioResult>, baseline: &Baseline, tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            regressions: baseline.compare(&results, tolerance),
+            scenarios: results,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.regressions.is_empty()
+    }
+
+    /// Write the report as pretty-printed JSON.
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, json)
+    }
+}
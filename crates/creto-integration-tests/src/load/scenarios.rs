@@ -0,0 +1,304 @@
+//! Scenario drivers.
+//!
+//! Each driver runs one in-process path for a warmup period and then a
+//! measured period, and returns the measured throughput and latency
+//! distribution. Operations that start during warmup are not recorded.
+//! Workers are seeded from [`ScenarioConfig::seed`], so a run replays the
+//! same load as the last one.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use creto_metering::{MeteringService, Quota, QuotaEnforcer, QuotaPeriod};
+use creto_runtime::{PoolConfig, Sandbox, SandboxConfig, SandboxState, WarmPool};
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+
+use super::generators::{SeededRng, Tenants, UsageEventGenerator};
+
+/// Scenario name of [`run_ingestion`].
+pub const INGESTION: &str = "metering_ingestion";
+/// Scenario name of [`run_quota_checks`].
+pub const QUOTA_CHECK: &str = "quota_check";
+/// Scenario name of [`run_pool_cycle`].
+pub const POOL_CYCLE: &str = "warm_pool_cycle";
+
+/// How long and how hard to drive a scenario.
+#[derive(Debug, Clone)]
+pub struct ScenarioConfig {
+    /// Seed for the first worker; worker `i` uses `seed + i`.
+    pub seed: u64,
+    /// Unmeasured run-in.
+    pub warmup: Duration,
+    /// Measured period.
+    pub duration: Duration,
+    /// Concurrent workers.
+    pub concurrency: usize,
+    /// Target operations per second across all workers (`None` to run
+    /// flat out). Only the ingestion driver paces itself.
+    pub rate_per_sec: Option<u64>,
+}
+
+impl Default for ScenarioConfig {
+    fn default() -> Self {
+        Self {
+            seed: 42,
+            warmup: Duration::from_secs(1),
+            duration: Duration::from_secs(3),
+            concurrency: 4,
+            rate_per_sec: None,
+        }
+    }
+}
+
+/// Latency percentiles in microseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub p50_us: f64,
+    pub p90_us: f64,
+    pub p99_us: f64,
+    pub p999_us: f64,
+    pub max_us: f64,
+    pub mean_us: f64,
+}
+
+impl LatencySummary {
+    /// Summarize a histogram of nanosecond latencies.
+    pub fn from_histogram(histogram: &Histogram<u64>) -> Self {
+        let us = |ns: u64| ns as f64 / 1_000.0;
+        Self {
+            p50_us: us(histogram.value_at_quantile(0.50)),
+            p90_us: us(histogram.value_at_quantile(0.90)),
+            p99_us: us(histogram.value_at_quantile(0.99)),
+            p999_us: us(histogram.value_at_quantile(0.999)),
+            max_us: us(histogram.max()),
+            mean_us: histogram.mean() / 1_000.0,
+        }
+    }
+}
+
+/// Measured outcome of one scenario.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioResult {
+    pub scenario: String,
+    /// Operations completed in the measured period.
+    pub operations: u64,
+    /// Measured operations that returned an error or found nothing to do.
+    pub errors: u64,
+    pub duration_secs: f64,
+    pub throughput_per_sec: f64,
+    pub latency: LatencySummary,
+}
+
+/// What one worker measured.
+struct WorkerStats {
+    latencies: Histogram<u64>,
+    errors: u64,
+}
+
+impl WorkerStats {
+    fn new() -> Self {
+        Self {
+            // 1ns to 60s at three significant digits
+            latencies: Histogram::new_with_bounds(1, 60_000_000_000, 3)
+                .expect("valid histogram bounds"),
+            errors: 0,
+        }
+    }
+
+    /// Record an operation that started at `began`, unless it started
+    /// during warmup.
+    fn record(&mut self, window: &Window, began: Instant, ok: bool) {
+        if began >= window.measure_from {
+            self.latencies
+                .saturating_record(began.elapsed().as_nanos() as u64);
+            self.errors += u64::from(!ok);
+        }
+    }
+}
+
+/// Warmup and measurement deadlines shared by a scenario's workers.
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    start: Instant,
+    measure_from: Instant,
+    end: Instant,
+}
+
+impl Window {
+    fn new(config: &ScenarioConfig) -> Self {
+        let start = Instant::now();
+        let measure_from = start + config.warmup;
+        Self {
+            start,
+            measure_from,
+            end: measure_from + config.duration,
+        }
+    }
+}
+
+fn summarize(scenario: &str, config: &ScenarioConfig, workers: Vec<WorkerStats>) -> ScenarioResult {
+    let mut merged = WorkerStats::new();
+    for worker in workers {
+        merged
+            .latencies
+            .add(&worker.latencies)
+            .expect("histograms share bounds");
+        merged.errors += worker.errors;
+    }
+    let duration_secs = config.duration.as_secs_f64();
+    let operations = merged.latencies.len();
+    ScenarioResult {
+        scenario: scenario.to_string(),
+        operations,
+        errors: merged.errors,
+        duration_secs,
+        throughput_per_sec: operations as f64 / duration_secs,
+        latency: LatencySummary::from_histogram(&merged.latencies),
+    }
+}
+
+/// Drive [`MeteringService::ingest`] with synthetic usage events from 100
+/// agents across 10 organizations, paced to `rate_per_sec`.
+///
+/// Each worker issues the events due by the current time and sleeps for a
+/// millisecond once it is caught up. Latency is the time spent in `ingest`.
+pub async fn run_ingestion(config: &ScenarioConfig) -> ScenarioResult {
+    let service = Arc::new(MeteringService::new());
+    let tenants = Tenants::new(&mut SeededRng::new(config.seed), 10, 10);
+    let workers = config.concurrency.max(1);
+    let rate = config.rate_per_sec.map(|rate| rate as f64 / workers as f64);
+    let window = Window::new(config);
+
+    let handles: Vec<_> = (0..workers)
+        .map(|i| {
+            let service = Arc::clone(&service);
+            let mut events = UsageEventGenerator::new(config.seed + i as u64, tenants.clone());
+            tokio::spawn(async move {
+                let mut stats = WorkerStats::new();
+                let mut sent = 0u64;
+                loop {
+                    let now = Instant::now();
+                    if now >= window.end {
+                        break;
+                    }
+                    if let Some(rate) = rate {
+                        let due = ((now - window.start).as_secs_f64() * rate) as u64;
+                        if sent >= due {
+                            tokio::time::sleep(Duration::from_millis(1)).await;
+                            continue;
+                        }
+                    }
+                    let event = events.next_event();
+                    let began = Instant::now();
+                    let result = service
+                        .ingest(event.organization_id, event.agent_id, event)
+                        .await;
+                    stats.record(&window, began, result.is_ok());
+                    sent += 1;
+                }
+                stats
+            })
+        })
+        .collect();
+
+    let mut stats = Vec::with_capacity(workers);
+    for handle in handles {
+        stats.push(handle.await.expect("ingestion worker panicked"));
+    }
+    summarize(INGESTION, config, stats)
+}
+
+/// Hammer [`QuotaEnforcer::check`] from `concurrency` threads over 1,000
+/// agent quotas. One operation in ten also records usage, so checks
+/// contend with writers. Latency is the time spent in `check`.
+pub fn run_quota_checks(config: &ScenarioConfig) -> ScenarioResult {
+    let enforcer = QuotaEnforcer::new();
+    let tenants = Tenants::new(&mut SeededRng::new(config.seed), 10, 100);
+    for (organization_id, agent_id) in tenants.pairs() {
+        let mut quota = Quota::new(
+            organization_id,
+            "api_calls",
+            i64::MAX / 2,
+            QuotaPeriod::Daily,
+        );
+        quota.agent_id = Some(agent_id);
+        enforcer.register_quota(&quota);
+    }
+    let window = Window::new(config);
+
+    let stats = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..config.concurrency.max(1))
+            .map(|i| {
+                let (enforcer, tenants) = (&enforcer, &tenants);
+                let mut rng = SeededRng::new(config.seed + i as u64);
+                scope.spawn(move || {
+                    let mut stats = WorkerStats::new();
+                    while Instant::now() < window.end {
+                        let (organization_id, agent_id) = tenants.pick(&mut rng);
+                        let began = Instant::now();
+                        let result = enforcer.check(&organization_id, &agent_id, "api_calls", 1);
+                        stats.record(&window, began, result.is_ok_and(|r| r.allowed));
+                        if rng.chance(10) {
+                            let _ =
+                                enforcer.record_usage(&organization_id, &agent_id, "api_calls", 1);
+                        }
+                    }
+                    stats
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("quota worker panicked"))
+            .collect()
+    });
+    summarize(QUOTA_CHECK, config, stats)
+}
+
+/// Cycle sandboxes through a [`WarmPool`]: each of `concurrency` tasks
+/// acquires a ready sandbox and releases it again. The pool holds two
+/// sandboxes per task. Latency is the full acquire-and-release cycle; a
+/// cycle that finds the pool empty counts as an error.
+pub async fn run_pool_cycle(config: &ScenarioConfig) -> ScenarioResult {
+    let pool = Arc::new(WarmPool::new(PoolConfig::default()));
+    let sandbox_config = SandboxConfig::default();
+    let runtime = sandbox_config.runtime.clone();
+    let tenants = Tenants::new(&mut SeededRng::new(config.seed), 1, 1);
+    let (organization_id, agent_id) = tenants.pick(&mut SeededRng::new(config.seed));
+    let workers = config.concurrency.max(1);
+    for _ in 0..workers * 2 {
+        let mut sandbox = Sandbox::new(organization_id, agent_id, sandbox_config.clone());
+        sandbox.state = SandboxState::Ready;
+        pool.add(sandbox).await.expect("add sandbox to pool");
+    }
+    let window = Window::new(config);
+
+    let handles: Vec<_> = (0..workers)
+        .map(|_| {
+            let pool = Arc::clone(&pool);
+            let runtime = runtime.clone();
+            tokio::spawn(async move {
+                let mut stats = WorkerStats::new();
+                while Instant::now() < window.end {
+                    let began = Instant::now();
+                    let ok = match pool.acquire(&runtime).await {
+                        Some(sandbox) => pool.release(sandbox.id).await.is_ok(),
+                        None => false,
+                    };
+                    stats.record(&window, began, ok);
+                    if !ok {
+                        tokio::task::yield_now().await;
+                    }
+                }
+                stats
+            })
+        })
+        .collect();
+
+    let mut stats = Vec::with_capacity(workers);
+    for handle in handles {
+        stats.push(handle.await.expect("pool worker panicked"));
+    }
+    summarize(POOL_CYCLE, config, stats)
+}
@@ -0,0 +1,135 @@
+//! Request context propagation across all four products.
+//!
+//! A single correlation ID is set at the edge and must be inherited by every
+//! derived artifact: the oversight request a trigger creates, the execution
+//! it authorizes, the usage events emitted for both, and the envelope and
+//! receipt reporting the result.
+
+use creto_common::Correlation;
+use creto_integration_tests::common::TestFixture;
+use creto_messaging::{channel::InMemoryChannel, ratchet::MessageHeader, Channel, Envelope};
+use creto_metering::MeteringService;
+use creto_oversight::{
+    metering::oversight_request_event, ActionType, OversightService, PolicyContext,
+    PolicyTriggerConfig, TriggerCondition,
+};
+use creto_runtime::{metering::sandbox_execution_event, RuntimeService, SandboxId};
+use uuid::Uuid;
+
+#[tokio::test]
+async fn test_correlation_id_walks_all_products() {
+    let fixture = TestFixture::new();
+    let trace = Correlation::new(Uuid::now_v7());
+    let correlation_id = trace.correlation_id.unwrap();
+
+    // Oversight: a high-risk execution trips a trigger.
+    let oversight = OversightService::new().with_triggers(
+        PolicyTriggerConfig::new().with_condition(TriggerCondition::RiskLevel {
+            levels: vec!["high".to_string()],
+        }),
+    );
+    let context = PolicyContext {
+        correlation: trace,
+        ..Default::default()
+    };
+    let request = oversight
+        .build_trigger_request(
+            fixture.org_id,
+            fixture.agent_id,
+            ActionType::CodeExecution {
+                runtime: "python".to_string(),
+                risk_level: "high".to_string(),
+            },
+            &context,
+        )
+        .expect("trigger should match");
+    assert_eq!(request.correlation_id, Some(correlation_id));
+
+    // Runtime: the approved execution is caused by the oversight request.
+    let runtime = RuntimeService::new();
+    let sandbox_id = SandboxId::new();
+    let result = runtime
+        .execute_with_correlation(
+            sandbox_id,
+            "print('approved')",
+            request.correlation().derive(request.id),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.correlation_id, Some(correlation_id));
+    assert_eq!(result.caused_by, Some(request.id));
+
+    // Metering: events emitted by oversight and runtime inherit the trace.
+    let metering = MeteringService::new();
+    metering
+        .record_usage(
+            fixture.org_id,
+            fixture.agent_id,
+            oversight_request_event(
+                fixture.org_id,
+                fixture.agent_id,
+                request.id,
+                0,
+                request.correlation().derive(request.id),
+            ),
+        )
+        .await
+        .unwrap();
+    metering
+        .record_usage(
+            fixture.org_id,
+            fixture.agent_id,
+            sandbox_execution_event(
+                fixture.org_id,
+                fixture.agent_id,
+                sandbox_id.as_uuid(),
+                result.timing.duration_ms.unwrap_or(0),
+                0,
+                result.correlation().derive(result.request_id),
+            ),
+        )
+        .await
+        .unwrap();
+    metering
+        .record_usage(
+            fixture.org_id,
+            fixture.agent_id,
+            sandbox_execution_event(
+                fixture.org_id,
+                fixture.agent_id,
+                sandbox_id.as_uuid(),
+                1,
+                0,
+                Correlation::default(),
+            ),
+        )
+        .await
+        .unwrap();
+
+    let events = metering.find_by_correlation(correlation_id);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].caused_by, Some(request.id));
+    assert_eq!(events[1].caused_by, Some(result.request_id));
+
+    // Messaging: the result is reported to another agent; the receipt
+    // inherits the envelope's trace.
+    let envelope = Envelope::new(
+        fixture.agent_id,
+        creto_common::AgentId::new(),
+        MessageHeader {
+            dh_public: vec![0u8; 32],
+            prev_chain_length: 0,
+            message_number: 0,
+        },
+        vec![1, 2, 3],
+    )
+    .with_correlation(result.correlation().derive(result.request_id));
+
+    let channel = InMemoryChannel::new();
+    let receipt = channel.send(&envelope).await.unwrap();
+
+    assert_eq!(envelope.header.correlation_id, Some(correlation_id));
+    assert_eq!(envelope.header.caused_by, Some(result.request_id));
+    assert_eq!(receipt.correlation_id, Some(correlation_id));
+    assert_eq!(receipt.caused_by, Some(envelope.id));
+}
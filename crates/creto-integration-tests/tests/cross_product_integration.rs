@@ -142,7 +142,7 @@ mod runtime_oversight {
         // High-resource sandbox config
         let config = SandboxConfig {
             runtime: "python3.11".to_string(),
-            limits: ResourceLimits {
+            limits: Some(ResourceLimits {
                 memory_bytes: 32 * 1024 * 1024 * 1024, // 32GB
                 cpu_time_ms: 3600000,                  // 1 hour
                 wall_time_seconds: 7200,
@@ -151,13 +151,17 @@ mod runtime_oversight {
                 max_open_files: 10000,
                 network_bandwidth_bps: None,
                 max_connections: Some(500),
-            },
+                ..Default::default()
+            }),
             network_policy: SandboxNetworkPolicy::Full,
             environment: Vec::new(),
             mounts: Vec::new(),
             debug: false,
             detailed_network_policy: None,
+            network_policy_template: None,
+            attestation_policy: None,
             timeout_seconds: 3600,
+            keep_warm: false,
         };
 
         // Create policy context based on resource request
@@ -166,7 +170,7 @@ mod runtime_oversight {
             quota_usage_percentage: 50.0,
             delegation_depth: 1,
             attributes: serde_json::json!({
-                "resource_request_memory": config.limits.memory_bytes,
+                "resource_request_memory": config.effective_limits().memory_bytes,
                 "network_policy": "full"
             }),
             ..Default::default()
@@ -237,34 +241,34 @@ mod messaging_runtime {
     use creto_runtime::sandbox::{Sandbox, SandboxConfig};
 
     /// Test secure messaging from within a sandbox
-    #[test]
-    fn test_sandbox_secure_messaging() {
+    #[tokio::test]
+    async fn test_sandbox_secure_messaging() {
         let fixture = TestFixture::new();
 
         // Create sandbox for agent
         let _sandbox = Sandbox::new(fixture.org_id, fixture.agent_id, SandboxConfig::default());
 
         // Create topic manager
-        let mut topic_manager = TopicManager::new();
+        let topic_manager = TopicManager::new();
 
         // Create a topic for sandbox communication
         let mut topic_config = TopicConfig::new("sandbox-events".to_string(), fixture.agent_id);
         topic_config.publish_policy = TopicPolicy::Private;
         topic_config.subscribe_policy = TopicPolicy::AuthzRequired;
 
-        let topic_id = topic_manager.create_topic(topic_config).unwrap();
+        let topic_id = topic_manager.create_topic(topic_config).await.unwrap();
 
         // Verify topic was created
         assert!(!topic_id.is_nil());
     }
 
     /// Test checkpoint state can be shared via messaging
-    #[test]
-    fn test_checkpoint_notification_via_messaging() {
+    #[tokio::test]
+    async fn test_checkpoint_notification_via_messaging() {
         let fixture = TestFixture::new();
 
         // Create topic manager
-        let mut topic_manager = TopicManager::new();
+        let topic_manager = TopicManager::new();
 
         // Create a checkpoint notification topic
         let mut topic_config =
@@ -273,11 +277,12 @@ mod messaging_runtime {
         topic_config.subscribe_policy = TopicPolicy::Allowlist;
         topic_config.allowed_agents = vec![fixture.agent_id];
 
-        let topic_id = topic_manager.create_topic(topic_config).unwrap();
+        let topic_id = topic_manager.create_topic(topic_config).await.unwrap();
 
         // Subscribe to checkpoint notifications
         let subscription = topic_manager
             .subscribe(topic_id, fixture.agent_id, None)
+            .await
             .unwrap();
         assert_eq!(subscription.topic_id, topic_id);
 
@@ -294,10 +299,142 @@ mod messaging_runtime {
                 b"checkpoint_complete",
                 checkpoint_metadata,
             )
+            .await
             .unwrap();
 
         // Should be delivered to subscriber
-        assert!(delivered.contains(&fixture.agent_id));
+        assert!(delivered
+            .iter()
+            .any(|d| d.subscriber_agent_id == fixture.agent_id));
+    }
+
+    /// Test two sandboxes exchanging a message over a brokered channel
+    #[tokio::test]
+    async fn test_brokered_agent_channel_end_to_end() {
+        use creto_common::CretoError;
+        use creto_messaging::{InMemoryKeyStore, KeyStore, MessagingService};
+        use creto_runtime::testing::RuntimeTestHarness;
+        use creto_runtime::{
+            AgentChannelConfig, AgentChannelProxy, ChannelClient, MessagingSessionBroker,
+        };
+        use std::sync::Arc;
+
+        let fixture = TestFixture::new();
+        let keys: Arc<dyn KeyStore> = Arc::new(InMemoryKeyStore::new());
+        let broker = Arc::new(MessagingSessionBroker::new());
+        let socket_dir =
+            std::env::temp_dir().join(format!("creto-ch-{}", uuid::Uuid::new_v4().simple()));
+        let proxy = Arc::new(AgentChannelProxy::new(
+            broker.clone(),
+            AgentChannelConfig {
+                socket_dir,
+                ..Default::default()
+            },
+        ));
+        let harness =
+            RuntimeTestHarness::new().map_service(|s| s.with_agent_channels(Arc::clone(&proxy)));
+
+        // Each sandbox runs as its own agent with its own messaging identity
+        let mut agents = Vec::new();
+        let mut sandboxes = Vec::new();
+        for _ in 0..2 {
+            let agent_id = AgentId::new();
+            let mut messaging = MessagingService::new().with_key_store(Arc::clone(&keys));
+            messaging.initialize(agent_id).await.unwrap();
+            let messaging = Arc::new(messaging);
+            broker.register(Arc::clone(&messaging)).unwrap();
+            agents.push(messaging);
+
+            let sandbox = harness
+                .service
+                .create_sandbox(fixture.org_id, agent_id, SandboxConfig::default())
+                .await
+                .unwrap();
+            sandboxes.push(sandbox.id);
+        }
+        let (a, b) = (sandboxes[0], sandboxes[1]);
+
+        // Idle sandboxes are not connected
+        let err = harness.service.open_agent_channel(a, b).await.unwrap_err();
+        assert!(matches!(err, CretoError::ValidationFailed(_)));
+
+        // Scripted sandbox code: each execution reports it started, then keeps running
+        let mut started = Vec::new();
+        let mut releases = Vec::new();
+        for _ in 0..2 {
+            let (running, started_rx) = tokio::sync::oneshot::channel();
+            harness.executor.push_effect(move |_| {
+                let _ = running.send(());
+            });
+            releases.push(harness.executor.push_hold());
+            started.push(started_rx);
+        }
+
+        let exchange = async {
+            for started in started {
+                started.await.unwrap();
+            }
+            let channel = harness.service.open_agent_channel(a, b).await.unwrap();
+            assert_eq!(channel.descriptors[0].sandbox_id, a);
+            assert_eq!(channel.descriptors[1].sandbox_id, b);
+
+            // Running code finds the channel on its socket and exchanges a message
+            let mut client_a = ChannelClient::connect(&channel.descriptors[0].endpoint)
+                .await
+                .unwrap();
+            let mut client_b = ChannelClient::connect(&channel.descriptors[1].endpoint)
+                .await
+                .unwrap();
+            let to_a = client_a.channels().await.unwrap().remove(0);
+            let to_b = client_b.channels().await.unwrap().remove(0);
+            assert_eq!(to_a.peer_sandbox_id, b);
+            assert_eq!(to_b.peer_sandbox_id, a);
+
+            client_a.send(&to_a.token, b"hello from a").await.unwrap();
+            let received = client_b.recv(&to_b.token).await.unwrap();
+            drop(releases);
+            (received, client_b, to_b)
+        };
+        let (ran_a, ran_b, (received, mut client_b, to_b)) = tokio::join!(
+            harness.service.execute(a, "send()"),
+            harness.service.execute(b, "recv()"),
+            exchange
+        );
+        assert!(ran_a.unwrap().is_success());
+        assert!(ran_b.unwrap().is_success());
+        assert_eq!(received.as_deref(), Some(&b"hello from a"[..]));
+
+        // The sealed envelope was counted for both sandboxes
+        let sent = harness.service.network_usage(a);
+        let delivered = harness.service.network_usage(b);
+        assert!(sent.bytes_sent > 0);
+        assert_eq!(sent.bytes_sent, delivered.bytes_received);
+
+        // Terminating either side tears the channel, its sockets and its sessions down
+        harness.service.terminate_sandbox(a).await.unwrap();
+        assert!(proxy.open_channels().is_empty());
+        assert!(client_b.send(&to_b.token, b"anyone there?").await.is_err());
+        assert!(ChannelClient::connect(&to_b.endpoint).await.is_err());
+        for messaging in &agents {
+            assert!(messaging.list_sessions().await.is_empty());
+        }
+
+        // Sandboxes of unrelated organizations are not connected
+        let outsider = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        let err = harness
+            .service
+            .open_agent_channel(b, outsider.id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::AuthorizationDenied(_)));
     }
 }
 
@@ -0,0 +1,116 @@
+//! Tests for the load-test harness itself.
+//!
+//! These run in every build; the performance gate is `load_regression`.
+
+use std::time::Duration;
+
+use creto_integration_tests::load::generators::{
+    ExecutionRequestGenerator, OversightRequestGenerator, SeededRng, Tenants, UsageEventGenerator,
+};
+use creto_integration_tests::load::report::{Baseline, LoadReport};
+use creto_integration_tests::load::scenarios::{
+    run_ingestion, run_pool_cycle, run_quota_checks, ScenarioConfig, INGESTION, POOL_CYCLE,
+    QUOTA_CHECK,
+};
+use creto_runtime::SandboxId;
+
+fn tenants(seed: u64) -> Tenants {
+    Tenants::new(&mut SeededRng::new(seed), 3, 4)
+}
+
+#[test]
+fn test_generators_replay_the_same_load_for_a_seed() {
+    assert_eq!(tenants(7).organizations, tenants(7).organizations);
+
+    let events = |seed| {
+        UsageEventGenerator::new(seed, tenants(7))
+            .take(100)
+            .map(|e| (e.organization_id, e.agent_id, e.event_type, e.quantity))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(events(1), events(1));
+    assert_ne!(events(1), events(2));
+
+    let requests = |seed| {
+        OversightRequestGenerator::new(seed, tenants(7))
+            .take(50)
+            .map(|r| (r.agent_id, r.priority, r.action_type))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(requests(3), requests(3));
+
+    let sandboxes: Vec<SandboxId> = (0..4).map(|_| SandboxId::new()).collect();
+    let executions = |seed| {
+        ExecutionRequestGenerator::new(seed, sandboxes.clone())
+            .take(50)
+            .map(|r| (r.sandbox_id, r.code, r.timeout_seconds))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(executions(5), executions(5));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_scenarios_report_measured_operations_only() {
+    let config = ScenarioConfig {
+        warmup: Duration::from_millis(50),
+        duration: Duration::from_millis(100),
+        concurrency: 2,
+        rate_per_sec: Some(2_000),
+        ..Default::default()
+    };
+    let ingestion = run_ingestion(&config).await;
+    assert_eq!(ingestion.scenario, INGESTION);
+    assert_eq!(ingestion.errors, 0);
+    // Paced at 2,000/s for 100ms, warmup excluded
+    assert!(ingestion.operations > 0 && ingestion.operations <= 260);
+
+    let quota = run_quota_checks(&config);
+    assert_eq!(quota.scenario, QUOTA_CHECK);
+    assert!(quota.operations > 0);
+    assert_eq!(quota.errors, 0);
+
+    let pool = run_pool_cycle(&config).await;
+    assert_eq!(pool.scenario, POOL_CYCLE);
+    assert!(pool.operations > 0);
+    assert!(pool.latency.p99_us >= pool.latency.p50_us);
+}
+
+#[tokio::test]
+async fn test_baseline_flags_missed_targets_beyond_tolerance() {
+    let baseline = Baseline::from_json(
+        r#"{
+            "tolerance": 0.1,
+            "scenarios": {
+                "metering_ingestion": { "min_throughput_per_sec": 1000, "max_p99_us": 50 },
+                "quota_check": { "max_error_rate": 0.0 }
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut result = run_ingestion(&ScenarioConfig {
+        warmup: Duration::ZERO,
+        duration: Duration::from_millis(20),
+        rate_per_sec: Some(1_000),
+        ..Default::default()
+    })
+    .await;
+
+    result.throughput_per_sec = 950.0;
+    result.latency.p99_us = 54.0;
+    let report = LoadReport::new(vec![result.clone()], &baseline, 0.1);
+    // Within tolerance; the quota scenario did not run
+    assert_eq!(report.regressions.len(), 1);
+    assert_eq!(report.regressions[0].scenario, QUOTA_CHECK);
+    assert_eq!(report.regressions[0].metric, "missing");
+
+    result.throughput_per_sec = 850.0;
+    result.latency.p99_us = 60.0;
+    let regressions = baseline.compare(&[result], 0.1);
+    let metrics: Vec<&str> = regressions.iter().map(|r| r.metric.as_str()).collect();
+    assert_eq!(metrics, ["throughput_per_sec", "p99_us", "missing"]);
+    assert!((regressions[0].allowed - 900.0).abs() < 1e-9);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["scenarios"][0]["scenario"], INGESTION);
+    assert!(json["scenarios"][0]["latency"]["p99_us"].is_number());
+}
@@ -0,0 +1,75 @@
+//! Performance regression gate.
+//!
+//! Runs every load scenario, writes the results next to the other test
+//! artifacts (or to `CRETO_LOAD_REPORT`), and fails when a result misses a
+//! target in `baselines/load.json` by more than the tolerance. The
+//! tolerance can be overridden with `CRETO_LOAD_TOLERANCE`.
+//!
+//! Debug builds are far slower than the targets assume, so this file only
+//! builds with optimizations:
+//!
+//! ```text
+//! cargo test --release -p creto-integration-tests --test load_regression
+//! ```
+
+#![cfg(not(debug_assertions))]
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use creto_integration_tests::load::report::{Baseline, LoadReport};
+use creto_integration_tests::load::scenarios::{
+    run_ingestion, run_pool_cycle, run_quota_checks, ScenarioConfig,
+};
+
+const BASELINE: &str = include_str!("../baselines/load.json");
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_load_targets() {
+    let baseline = Baseline::from_json(BASELINE).expect("valid baseline");
+    let tolerance = std::env::var("CRETO_LOAD_TOLERANCE")
+        .ok()
+        .map(|value| value.parse().expect("CRETO_LOAD_TOLERANCE is a number"))
+        .unwrap_or(baseline.tolerance);
+
+    // Scenarios run one at a time so they do not compete for cores
+    let ingestion = run_ingestion(&ScenarioConfig {
+        rate_per_sec: Some(12_000),
+        concurrency: 2,
+        ..Default::default()
+    })
+    .await;
+    let quota_config = ScenarioConfig {
+        concurrency: 16,
+        ..Default::default()
+    };
+    let quota = tokio::task::spawn_blocking(move || run_quota_checks(&quota_config))
+        .await
+        .unwrap();
+    let pool = run_pool_cycle(&ScenarioConfig {
+        concurrency: 8,
+        warmup: Duration::from_millis(500),
+        ..Default::default()
+    })
+    .await;
+
+    let report = LoadReport::new(vec![ingestion, quota, pool], &baseline, tolerance);
+    let path = std::env::var_os("CRETO_LOAD_REPORT")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("load-report.json"));
+    report.write(&path).expect("write load report");
+
+    for result in &report.scenarios {
+        println!(
+            "{}: {:.0} ops/s, p99 {:.2}us, {} errors",
+            result.scenario, result.throughput_per_sec, result.latency.p99_us, result.errors
+        );
+    }
+    let regressions: Vec<String> = report.regressions.iter().map(ToString::to_string).collect();
+    assert!(
+        report.passed(),
+        "load targets missed (report at {}):\n{}",
+        path.display(),
+        regressions.join("\n")
+    );
+}
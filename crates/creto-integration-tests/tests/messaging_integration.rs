@@ -4,8 +4,12 @@
 //! envelope creation, key management, and session handling.
 
 use creto_integration_tests::common::{test_agent_id, TestFixture};
+use std::sync::Arc;
+
+use creto_common::{AgentId, CretoError};
 use creto_messaging::{
-    ContentType, DeliveryReceipt, EncryptedPayload, Envelope, IdentityKey, KeyBundle, PreKey,
+    ContentType, DeliveryReceipt, EncryptedPayload, Envelope, IdentityKey, InMemoryKeyStore,
+    InMemoryPreKeyRepository, KeyBundle, KeyStore, MessagingService, PreKey, PreKeyRepository,
     ReceiptType, SessionState,
 };
 use uuid::Uuid;
@@ -199,3 +203,73 @@ fn test_envelope_with_reply_to() {
 
     assert_eq!(envelope.header.reply_to, Some(reply_to_id));
 }
+
+async fn prekey_service(
+    store: &Arc<dyn KeyStore>,
+    prekeys: &Arc<InMemoryPreKeyRepository>,
+    agent_id: AgentId,
+) -> MessagingService {
+    let mut service = MessagingService::new()
+        .with_key_store(Arc::clone(store))
+        .with_pre_key_repository(prekeys.clone());
+    service.initialize(agent_id).await.unwrap();
+    service
+}
+
+#[tokio::test]
+async fn test_one_time_prekeys_consumed_until_exhausted() {
+    let store: Arc<dyn KeyStore> = Arc::new(InMemoryKeyStore::new());
+    let prekeys = Arc::new(InMemoryPreKeyRepository::new());
+    let bob_id = AgentId::new();
+    let bob = prekey_service(&store, &prekeys, bob_id).await;
+    let alice = prekey_service(&store, &prekeys, AgentId::new()).await;
+    let carol = prekey_service(&store, &prekeys, AgentId::new()).await;
+
+    let published = bob.replenish_prekeys(bob_id, 2).await.unwrap();
+    assert_eq!(prekeys.count_available(bob_id).await.unwrap(), 2);
+
+    // Racing initiators each get their own prekey
+    let (from_alice, from_carol) = tokio::join!(
+        alice.initiate_session(bob_id),
+        carol.initiate_session(bob_id)
+    );
+    let (_, alice_params) = from_alice.unwrap();
+    let (_, carol_params) = from_carol.unwrap();
+    assert!(alice_params.used_one_time_prekey());
+    assert!(carol_params.used_one_time_prekey());
+    let mut used = vec![
+        alice_params.recipient_one_time_prekey_id.unwrap(),
+        carol_params.recipient_one_time_prekey_id.unwrap(),
+    ];
+    used.sort_unstable();
+    assert_eq!(used, published);
+    assert_eq!(prekeys.count_available(bob_id).await.unwrap(), 0);
+
+    for params in [&alice_params, &carol_params] {
+        let session_id = bob.accept_session(params).await.unwrap();
+        assert_eq!(
+            bob.session_status(session_id).await,
+            Some(SessionState::Active)
+        );
+    }
+
+    // A replayed handshake cannot reuse a prekey's private half
+    let err = bob.accept_session(&alice_params).await.unwrap_err();
+    assert!(matches!(err, CretoError::CryptoError(_)));
+
+    // With the pool exhausted the session falls back to the signed prekey
+    let (_, fallback) = alice.initiate_session(bob_id).await.unwrap();
+    assert!(!fallback.used_one_time_prekey());
+    let session_id = bob.accept_session(&fallback).await.unwrap();
+    assert_eq!(
+        bob.session_status(session_id).await,
+        Some(SessionState::Active)
+    );
+
+    // Replenishing restores forward secrecy with fresh IDs
+    let replenished = bob.replenish_prekeys(bob_id, 1).await.unwrap();
+    assert!(replenished[0] > published[1]);
+    let (_, params) = carol.initiate_session(bob_id).await.unwrap();
+    assert_eq!(params.recipient_one_time_prekey_id, Some(replenished[0]));
+    bob.accept_session(&params).await.unwrap();
+}
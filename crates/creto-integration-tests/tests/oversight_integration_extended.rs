@@ -122,6 +122,7 @@ async fn test_policy_trigger_creates_request() {
         reply_to: Some("compliance@example.com".to_string()),
         dashboard_base_url: "https://dashboard.example.com".to_string(),
         token_secret: "test-secret-key".to_string(),
+        previous_token_secrets: Vec::new(),
     };
     let email = EmailChannel::new(email_config);
 
@@ -330,6 +331,7 @@ async fn test_policy_with_custom_attributes() {
             "device_type": "mobile",
             "ip_reputation": "low"
         }),
+        ..Default::default()
     };
 
     let action = ActionType::DataAccess {
@@ -33,7 +33,7 @@ fn test_sandbox_state_transitions() {
     assert_eq!(sandbox.state, SandboxState::Running);
 
     sandbox.mark_terminated();
-    assert_eq!(sandbox.state, SandboxState::Terminated);
+    assert!(matches!(sandbox.state, SandboxState::Terminated { .. }));
     assert!(sandbox.state.is_terminal());
 }
 
@@ -4,11 +4,12 @@
 
 use creto_common::AgentId;
 use creto_messaging::{
-    topic::{TopicConfig, TopicManager},
+    topic::{SubscriptionFilter, TopicConfig, TopicManager},
     x3dh::X3DH,
     DoubleRatchet, KeyBundle, Session,
 };
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use std::collections::HashMap;
 use std::time::Duration;
 
 /// Benchmark: Key Bundle Generation
@@ -44,8 +45,7 @@ fn bench_x3dh(c: &mut Criterion) {
     group.bench_function("x3dh_initiate_100", |b| {
         b.iter(|| {
             for _ in 0..100 {
-                let result = black_box(X3DH::initiate(&alice_bundle, &bob_bundle));
-                black_box(result);
+                black_box(X3DH::initiate(&alice_bundle, &bob_bundle)).unwrap();
             }
         });
     });
@@ -70,8 +70,7 @@ fn bench_double_ratchet(c: &mut Criterion) {
         b.iter(|| {
             let mut ratchet = DoubleRatchet::new_sender(&shared_secret, &their_signed_prekey);
             for _ in 0..1000 {
-                let result = black_box(ratchet.encrypt(&small_message));
-                black_box(result);
+                black_box(ratchet.encrypt(&small_message)).unwrap();
             }
         });
     });
@@ -83,8 +82,7 @@ fn bench_double_ratchet(c: &mut Criterion) {
         b.iter(|| {
             let mut ratchet = DoubleRatchet::new_sender(&shared_secret, &their_signed_prekey);
             for _ in 0..1000 {
-                let result = black_box(ratchet.encrypt(&medium_message));
-                black_box(result);
+                black_box(ratchet.encrypt(&medium_message)).unwrap();
             }
         });
     });
@@ -96,8 +94,7 @@ fn bench_double_ratchet(c: &mut Criterion) {
         b.iter(|| {
             let mut ratchet = DoubleRatchet::new_sender(&shared_secret, &their_signed_prekey);
             for _ in 0..100 {
-                let result = black_box(ratchet.encrypt(&large_message));
-                black_box(result);
+                black_box(ratchet.encrypt(&large_message)).unwrap();
             }
         });
     });
@@ -134,8 +131,7 @@ fn bench_session(c: &mut Criterion) {
         b.iter(|| {
             let mut session = Session::new_initiator(alice_id, bob_id, &x3dh_result);
             for _ in 0..1000 {
-                let result = black_box(session.encrypt(message));
-                black_box(result);
+                black_box(session.encrypt(message)).unwrap();
             }
         });
     });
@@ -150,40 +146,44 @@ fn bench_topics(c: &mut Criterion) {
     group.measurement_time(Duration::from_secs(10));
 
     let owner_id = AgentId::new();
+    let rt = tokio::runtime::Runtime::new().unwrap();
 
     group.bench_function("create_topic_100", |b| {
         b.iter(|| {
-            let mut manager = TopicManager::new();
-            for i in 0..100 {
-                let config = TopicConfig::new(format!("topic-{}", i), owner_id);
-                let result = black_box(manager.create_topic(config));
-                black_box(result);
-            }
+            rt.block_on(async {
+                let manager = TopicManager::new();
+                for i in 0..100 {
+                    let config = TopicConfig::new(format!("topic-{}", i), owner_id);
+                    black_box(manager.create_topic(config).await).unwrap();
+                }
+            })
         });
     });
 
     group.bench_function("subscribe_100", |b| {
         b.iter(|| {
-            let mut manager = TopicManager::new();
-            let config = TopicConfig::new("sub-topic".to_string(), owner_id);
-            let topic_id = manager.create_topic(config).unwrap();
-
-            for _ in 0..100 {
-                let subscriber_id = AgentId::new();
-                let result = black_box(manager.subscribe(topic_id, subscriber_id, None));
-                black_box(result);
-            }
+            rt.block_on(async {
+                let manager = TopicManager::new();
+                let config = TopicConfig::new("sub-topic".to_string(), owner_id);
+                let topic_id = manager.create_topic(config).await.unwrap();
+
+                for _ in 0..100 {
+                    let subscriber_id = AgentId::new();
+                    black_box(manager.subscribe(topic_id, subscriber_id, None).await).unwrap();
+                }
+            })
         });
     });
 
     // Setup subscribers for publish benchmark
-    let mut pub_manager = TopicManager::new();
+    let pub_manager = TopicManager::new();
     let pub_config = TopicConfig::new("pub-topic".to_string(), owner_id);
-    let pub_topic_id = pub_manager.create_topic(pub_config).unwrap();
+    let pub_topic_id = rt.block_on(pub_manager.create_topic(pub_config)).unwrap();
 
     for _ in 0..10 {
         let subscriber_id = AgentId::new();
-        let _ = pub_manager.subscribe(pub_topic_id, subscriber_id, None);
+        rt.block_on(pub_manager.subscribe(pub_topic_id, subscriber_id, None))
+            .unwrap();
     }
 
     let message = b"Benchmark message payload";
@@ -191,15 +191,75 @@ fn bench_topics(c: &mut Criterion) {
 
     group.bench_function("publish_to_10_subscribers_100", |b| {
         b.iter(|| {
-            for _ in 0..100 {
-                let result = black_box(pub_manager.publish(
-                    pub_topic_id,
-                    owner_id,
-                    message,
-                    metadata.clone(),
-                ));
-                black_box(result);
-            }
+            rt.block_on(async {
+                for _ in 0..100 {
+                    black_box(
+                        pub_manager
+                            .publish(pub_topic_id, owner_id, message, metadata.clone())
+                            .await,
+                    )
+                    .unwrap();
+                }
+            })
+        });
+    });
+
+    group.finish();
+}
+
+/// Benchmark: Topic Fan-Out
+/// Publish latency to 10K subscribers, half of them filtered on one of 50
+/// regions, compared with evaluating every subscription's filter in turn
+fn bench_topic_fan_out(c: &mut Criterion) {
+    let mut group = c.benchmark_group("topic_fan_out");
+    group.measurement_time(Duration::from_secs(10));
+
+    let owner_id = AgentId::new();
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let manager = TopicManager::new();
+    let mut config = TopicConfig::new("fan-out".to_string(), owner_id);
+    config.max_subscribers = None;
+    config.retention.max_messages = Some(100);
+    let topic_id = rt.block_on(manager.create_topic(config)).unwrap();
+
+    for i in 0..10_000 {
+        let filter = (i % 2 == 0).then(|| {
+            SubscriptionFilter::new()
+                .with_metadata("region".to_string(), format!("region-{}", i % 50))
+                .with_metadata("kind".to_string(), "alert".to_string())
+        });
+        rt.block_on(manager.subscribe(topic_id, AgentId::new(), filter))
+            .unwrap();
+    }
+    let subscriptions = rt.block_on(manager.list_subscribers(topic_id)).unwrap();
+
+    let payload = vec![0u8; 16 * 1024];
+    let metadata: HashMap<String, String> = [
+        ("region".to_string(), "region-10".to_string()),
+        ("kind".to_string(), "alert".to_string()),
+    ]
+    .into_iter()
+    .collect();
+
+    group.bench_function("publish_indexed_10k_subscribers", |b| {
+        b.iter(|| {
+            let result =
+                rt.block_on(manager.publish(topic_id, owner_id, &payload, metadata.clone()));
+            black_box(result.unwrap().len());
+        });
+    });
+
+    // What publish did before the index: copy the payload and metadata for
+    // retention, then evaluate every filter
+    group.bench_function("linear_scan_10k_subscribers", |b| {
+        b.iter(|| {
+            let retained = (payload.to_vec(), metadata.clone());
+            let matched: Vec<AgentId> = subscriptions
+                .iter()
+                .filter(|sub| sub.matches(&metadata))
+                .map(|sub| sub.subscriber_agent_id)
+                .collect();
+            black_box((retained, matched.len()));
         });
     });
 
@@ -219,8 +279,7 @@ fn bench_encryption_throughput(c: &mut Criterion) {
     group.bench_function("encrypt_1mb", |b| {
         b.iter(|| {
             let mut ratchet = DoubleRatchet::new_sender(&shared_secret, &their_signed_prekey);
-            let result = black_box(ratchet.encrypt(&one_mb));
-            black_box(result);
+            black_box(ratchet.encrypt(&one_mb)).unwrap();
         });
     });
 
@@ -234,6 +293,7 @@ criterion_group!(
     bench_double_ratchet,
     bench_session,
     bench_topics,
+    bench_topic_fan_out,
     bench_encryption_throughput
 );
 criterion_main!(benches);
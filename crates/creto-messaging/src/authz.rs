@@ -0,0 +1,417 @@
+//! Authorization hook for topics with [`TopicPolicy::AuthzRequired`].
+//!
+//! [`TopicManager`](crate::topic::TopicManager) asks an [`AuthzHook`]
+//! whether an agent may publish to or subscribe to such a topic, and caches
+//! the decision briefly so the publish path does not call out on every
+//! message. What happens when the hook fails or times out is set per topic
+//! with [`AuthzFailureMode`].
+//!
+//! [`TopicPolicy::AuthzRequired`]: crate::topic::TopicPolicy::AuthzRequired
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use creto_common::{AgentId, CretoError, CretoResult};
+use serde::{Deserialize, Serialize};
+
+use crate::topic::TopicId;
+
+/// Default time to wait for the hook before applying the failure mode.
+pub const DEFAULT_AUTHZ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Default time a hook decision is reused for.
+pub const DEFAULT_AUTHZ_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Expired cache entries are evicted once the cache holds this many.
+const AUTHZ_CACHE_EVICT_LEN: usize = 1024;
+
+/// Action an agent wants to take on a topic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TopicAction {
+    /// Publish a message.
+    Publish,
+    /// Subscribe to messages.
+    Subscribe,
+}
+
+/// Decision returned by an [`AuthzHook`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthzDecision {
+    /// The action is allowed.
+    Allow,
+    /// The action is denied.
+    Deny {
+        /// Why, surfaced in the [`CretoError::Unauthorized`] returned to
+        /// the caller.
+        reason: String,
+    },
+}
+
+/// What to do when the hook returns an error or times out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthzFailureMode {
+    /// Deny the action.
+    #[default]
+    FailClosed,
+    /// Allow the action.
+    FailOpen,
+}
+
+/// Decides whether an agent may act on an
+/// [`AuthzRequired`](crate::topic::TopicPolicy::AuthzRequired) topic.
+///
+/// Implementations usually call out to creto-authz.
+#[async_trait]
+pub trait AuthzHook: Send + Sync {
+    /// Decide whether `agent_id` may take `action` on `topic_id`.
+    async fn authorize(
+        &self,
+        agent_id: AgentId,
+        topic_id: TopicId,
+        action: TopicAction,
+    ) -> CretoResult<AuthzDecision>;
+}
+
+/// The optional [`AuthzHook`] with its timeout and decision cache.
+///
+/// Clones share the cache.
+#[derive(Clone)]
+pub(crate) struct TopicAuthorizer {
+    hook: Option<Arc<dyn AuthzHook>>,
+    timeout: Duration,
+    cache_ttl: Duration,
+    cache: Arc<Mutex<HashMap<CacheKey, CachedDecision>>>,
+}
+
+type CacheKey = (AgentId, TopicId, TopicAction);
+
+#[derive(Clone)]
+struct CachedDecision {
+    decision: AuthzDecision,
+    expires_at: Instant,
+}
+
+impl Default for TopicAuthorizer {
+    fn default() -> Self {
+        Self {
+            hook: None,
+            timeout: DEFAULT_AUTHZ_TIMEOUT,
+            cache_ttl: DEFAULT_AUTHZ_CACHE_TTL,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl TopicAuthorizer {
+    pub(crate) fn set_hook(&mut self, hook: Arc<dyn AuthzHook>) {
+        self.hook = Some(hook);
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub(crate) fn set_cache_ttl(&mut self, cache_ttl: Duration) {
+        self.cache_ttl = cache_ttl;
+    }
+
+    /// Authorize an action, from the cache when a fresh decision is there.
+    ///
+    /// Without a hook every action is denied. Hook errors and timeouts are
+    /// not cached; they resolve to `failure_mode` on every attempt until the
+    /// hook answers.
+    pub(crate) async fn authorize(
+        &self,
+        agent_id: AgentId,
+        topic_id: TopicId,
+        action: TopicAction,
+        failure_mode: AuthzFailureMode,
+    ) -> CretoResult<()> {
+        let Some(hook) = &self.hook else {
+            return Err(CretoError::Unauthorized(
+                "Topic requires authorization but no authorization hook is configured".to_string(),
+            ));
+        };
+        let key = (agent_id, topic_id, action);
+        let cached = self
+            .cache
+            .lock()
+            .unwrap()
+            .get(&key)
+            .filter(|c| c.expires_at > Instant::now())
+            .map(|c| c.decision.clone());
+
+        let decision = match cached {
+            Some(decision) => decision,
+            None => {
+                let outcome =
+                    tokio::time::timeout(self.timeout, hook.authorize(agent_id, topic_id, action))
+                        .await
+                        .unwrap_or_else(|_| {
+                            Err(CretoError::Internal(format!(
+                                "Authorization hook timed out after {:?}",
+                                self.timeout
+                            )))
+                        });
+
+                match outcome {
+                    Ok(decision) => {
+                        self.remember(key, decision.clone());
+                        decision
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            topic_id = %topic_id,
+                            agent = %agent_id,
+                            ?action,
+                            ?failure_mode,
+                            error = %e,
+                            "Authorization hook failed"
+                        );
+                        return match failure_mode {
+                            AuthzFailureMode::FailOpen => Ok(()),
+                            AuthzFailureMode::FailClosed => Err(CretoError::Unauthorized(format!(
+                                "Authorization unavailable: {}",
+                                e
+                            ))),
+                        };
+                    }
+                }
+            }
+        };
+
+        match decision {
+            AuthzDecision::Allow => Ok(()),
+            AuthzDecision::Deny { reason } => Err(CretoError::Unauthorized(reason)),
+        }
+    }
+
+    fn remember(&self, key: CacheKey, decision: AuthzDecision) {
+        let now = Instant::now();
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() >= AUTHZ_CACHE_EVICT_LEN {
+            cache.retain(|_, c| c.expires_at > now);
+        }
+        cache.insert(
+            key,
+            CachedDecision {
+                decision,
+                expires_at: now + self.cache_ttl,
+            },
+        );
+    }
+}
+
+/// Mock authorization hook for testing.
+///
+/// Clones share their call count, so a test can keep a clone to see how
+/// often the hook was consulted.
+#[derive(Clone)]
+pub struct MockAuthzHook {
+    default: AuthzDecision,
+    agents: HashMap<AgentId, AuthzDecision>,
+    delay: Option<Duration>,
+    error: Option<String>,
+    calls: Arc<AtomicUsize>,
+}
+
+impl MockAuthzHook {
+    /// Create a mock hook that returns `decision` for every agent.
+    pub fn new(decision: AuthzDecision) -> Self {
+        Self {
+            default: decision,
+            agents: HashMap::new(),
+            delay: None,
+            error: None,
+            calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Create a mock hook that allows everything.
+    pub fn allows() -> Self {
+        Self::new(AuthzDecision::Allow)
+    }
+
+    /// Create a mock hook that denies everything.
+    pub fn denies(reason: impl Into<String>) -> Self {
+        Self::new(AuthzDecision::Deny {
+            reason: reason.into(),
+        })
+    }
+
+    /// Return `decision` for `agent_id` instead of the default.
+    pub fn with_agent_decision(mut self, agent_id: AgentId, decision: AuthzDecision) -> Self {
+        self.agents.insert(agent_id, decision);
+        self
+    }
+
+    /// Wait `delay` before answering.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Fail every call with `message`.
+    pub fn with_error(mut self, message: impl Into<String>) -> Self {
+        self.error = Some(message.into());
+        self
+    }
+
+    /// Number of times the hook was called.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl AuthzHook for MockAuthzHook {
+    async fn authorize(
+        &self,
+        agent_id: AgentId,
+        _topic_id: TopicId,
+        _action: TopicAction,
+    ) -> CretoResult<AuthzDecision> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+        if let Some(message) = &self.error {
+            return Err(CretoError::Internal(message.clone()));
+        }
+        Ok(self.agents.get(&agent_id).unwrap_or(&self.default).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::topic::{TopicConfig, TopicManager, TopicPolicy};
+
+    async fn authz_topic(
+        hook: &MockAuthzHook,
+        failure_mode: AuthzFailureMode,
+    ) -> (TopicManager, TopicId, AgentId) {
+        let manager = TopicManager::new()
+            .with_authz_hook(Arc::new(hook.clone()))
+            .with_authz_timeout(Duration::from_millis(50));
+        let owner = AgentId::new();
+        let mut config = TopicConfig::new("guarded".to_string(), owner);
+        config.publish_policy = TopicPolicy::AuthzRequired;
+        config.subscribe_policy = TopicPolicy::AuthzRequired;
+        config.authz_failure_mode = failure_mode;
+        let topic_id = manager.create_topic(config).await.unwrap();
+        (manager, topic_id, owner)
+    }
+
+    #[tokio::test]
+    async fn test_hook_allows_and_denies() {
+        let allowed = AgentId::new();
+        let hook = MockAuthzHook::denies("not in group")
+            .with_agent_decision(allowed, AuthzDecision::Allow);
+        let (manager, topic_id, owner) = authz_topic(&hook, AuthzFailureMode::FailClosed).await;
+
+        manager.subscribe(topic_id, allowed, None).await.unwrap();
+        manager
+            .publish(topic_id, allowed, b"hi", HashMap::new())
+            .await
+            .unwrap();
+
+        let denied = AgentId::new();
+        match manager.subscribe(topic_id, denied, None).await {
+            Err(CretoError::Unauthorized(reason)) => assert_eq!(reason, "not in group"),
+            other => panic!("expected denial, got {:?}", other),
+        }
+        assert!(matches!(
+            manager
+                .publish(topic_id, denied, b"hi", HashMap::new())
+                .await,
+            Err(CretoError::Unauthorized(_))
+        ));
+
+        // The owner never needs the hook
+        let calls = hook.calls();
+        manager
+            .publish(topic_id, owner, b"hi", HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(hook.calls(), calls);
+    }
+
+    #[tokio::test]
+    async fn test_hook_timeout_failure_modes() {
+        let hook = MockAuthzHook::allows().with_delay(Duration::from_secs(5));
+
+        let (manager, topic_id, _) = authz_topic(&hook, AuthzFailureMode::FailClosed).await;
+        assert!(matches!(
+            manager.subscribe(topic_id, AgentId::new(), None).await,
+            Err(CretoError::Unauthorized(_))
+        ));
+
+        let (manager, topic_id, _) = authz_topic(&hook, AuthzFailureMode::FailOpen).await;
+        manager
+            .subscribe(topic_id, AgentId::new(), None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hook_errors_are_not_cached() {
+        let hook = MockAuthzHook::allows().with_error("authz down");
+        let (manager, topic_id, _) = authz_topic(&hook, AuthzFailureMode::FailOpen).await;
+        let agent = AgentId::new();
+
+        for _ in 0..2 {
+            manager
+                .publish(topic_id, agent, b"hi", HashMap::new())
+                .await
+                .unwrap();
+        }
+        assert_eq!(hook.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_decisions_cached_until_ttl() {
+        let hook = MockAuthzHook::allows();
+        let manager = TopicManager::new()
+            .with_authz_hook(Arc::new(hook.clone()))
+            .with_authz_cache_ttl(Duration::from_millis(100));
+        let mut config = TopicConfig::new("guarded".to_string(), AgentId::new());
+        config.publish_policy = TopicPolicy::AuthzRequired;
+        let topic_id = manager.create_topic(config).await.unwrap();
+        let agent = AgentId::new();
+
+        for _ in 0..3 {
+            manager
+                .publish(topic_id, agent, b"hi", HashMap::new())
+                .await
+                .unwrap();
+        }
+        assert_eq!(hook.calls(), 1);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        manager
+            .publish(topic_id, agent, b"hi", HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(hook.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_authz_required_without_hook_denies() {
+        let manager = TopicManager::new();
+        let mut config = TopicConfig::new("guarded".to_string(), AgentId::new());
+        config.subscribe_policy = TopicPolicy::AuthzRequired;
+        config.authz_failure_mode = AuthzFailureMode::FailOpen;
+        let topic_id = manager.create_topic(config).await.unwrap();
+
+        assert!(matches!(
+            manager.subscribe(topic_id, AgentId::new(), None).await,
+            Err(CretoError::Unauthorized(_))
+        ));
+    }
+}
@@ -154,7 +154,7 @@ impl Channel for InMemoryChannel {
     async fn send(&self, envelope: &Envelope) -> CretoResult<DeliveryReceipt> {
         let mut messages = self.messages.write().await;
         messages.push(envelope.clone());
-        Ok(DeliveryReceipt::delivered(envelope.id))
+        Ok(DeliveryReceipt::delivered_for(envelope))
     }
 
     async fn send_batch(&self, batch: &EnvelopeBatch) -> CretoResult<Vec<DeliveryReceipt>> {
@@ -163,7 +163,7 @@ impl Channel for InMemoryChannel {
 
         for envelope in &batch.envelopes {
             messages.push(envelope.clone());
-            receipts.push(DeliveryReceipt::delivered(envelope.id));
+            receipts.push(DeliveryReceipt::delivered_for(envelope));
         }
 
         Ok(receipts)
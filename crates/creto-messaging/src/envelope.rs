@@ -1,23 +1,37 @@
 //! Message envelope format for encrypted messages.
 
 use chrono::{DateTime, Utc};
-use creto_common::AgentId;
+use creto_common::{AgentId, Correlation};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use uuid::Uuid;
 
 use crate::ratchet::MessageHeader;
+use crate::wire::{
+    self, EncryptedPayloadV1, EnvelopeHeaderV1, EnvelopeV1, ENVELOPE_MAJOR_VERSION,
+    ENVELOPE_MINOR_VERSION,
+};
 
 /// A complete message envelope.
 ///
 /// Contains all information needed to deliver and decrypt a message.
+/// Serialized through the versioned layouts in [`crate::wire`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "EnvelopeV1", try_from = "Value")]
 pub struct Envelope {
     /// Unique message ID.
     pub id: Uuid,
 
-    /// Envelope version.
+    /// Wire format major version.
     pub version: u8,
 
+    /// Wire format minor version; 0 for envelopes that predate it.
+    pub minor_version: u8,
+
+    /// Whether nodes that do not support `version` must reject the envelope
+    /// rather than read it on a best-effort basis.
+    pub critical: bool,
+
     /// Message header.
     pub header: EnvelopeHeader,
 
@@ -26,6 +40,17 @@ pub struct Envelope {
 
     /// Timestamp.
     pub timestamp: DateTime<Utc>,
+
+    /// Top-level fields this node does not recognize, kept for forwarding.
+    pub extra: Map<String, Value>,
+}
+
+impl TryFrom<Value> for Envelope {
+    type Error = creto_common::CretoError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        wire::decode_value(value)
+    }
 }
 
 impl Envelope {
@@ -38,19 +63,23 @@ impl Envelope {
     ) -> Self {
         Self {
             id: Uuid::now_v7(),
-            version: 1,
+            version: ENVELOPE_MAJOR_VERSION,
+            minor_version: ENVELOPE_MINOR_VERSION,
+            critical: false,
             header: EnvelopeHeader {
                 sender_id,
                 recipient_id,
                 ratchet_header,
                 content_type: ContentType::Text,
                 reply_to: None,
+                correlation_id: None,
+                caused_by: None,
+                priority: MessagePriority::Normal,
+                extra: Map::new(),
             },
-            payload: EncryptedPayload {
-                ciphertext,
-                mac: vec![0u8; 16], // Placeholder
-            },
+            payload: EncryptedPayload::new(ciphertext, vec![0u8; 16]), // Placeholder MAC
             timestamp: Utc::now(),
+            extra: Map::new(),
         }
     }
 
@@ -66,6 +95,27 @@ impl Envelope {
         self
     }
 
+    /// Set the trace identifiers inherited from the originating action.
+    pub fn with_correlation(mut self, correlation: Correlation) -> Self {
+        self.header.correlation_id = correlation.correlation_id;
+        self.header.caused_by = correlation.caused_by;
+        self
+    }
+
+    /// Set the delivery priority.
+    pub fn with_priority(mut self, priority: MessagePriority) -> Self {
+        self.header.priority = priority;
+        self
+    }
+
+    /// Get the trace identifiers for this envelope.
+    pub fn correlation(&self) -> Correlation {
+        Correlation {
+            correlation_id: self.header.correlation_id,
+            caused_by: self.header.caused_by,
+        }
+    }
+
     /// Serialize to bytes.
     pub fn to_bytes(&self) -> creto_common::CretoResult<Vec<u8>> {
         serde_json::to_vec(self)
@@ -81,6 +131,7 @@ impl Envelope {
 
 /// Envelope header (sent in clear, needed for routing).
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "EnvelopeHeaderV1", from = "EnvelopeHeaderV1")]
 pub struct EnvelopeHeader {
     /// Sender agent ID.
     pub sender_id: AgentId,
@@ -95,24 +146,61 @@ pub struct EnvelopeHeader {
     pub content_type: ContentType,
 
     /// Reference to message being replied to.
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_to: Option<Uuid>,
+
+    /// Trace ID shared with the action that produced this message.
+    pub correlation_id: Option<Uuid>,
+
+    /// ID of the entity that caused this message to be sent.
+    pub caused_by: Option<Uuid>,
+
+    /// Delivery priority for store-and-forward.
+    pub priority: MessagePriority,
+
+    /// Header fields this node does not recognize, kept for forwarding.
+    pub extra: Map<String, Value>,
+}
+
+/// Delivery priority of an envelope.
+///
+/// Store-and-forward delivers higher priorities first, oldest first within a
+/// priority.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MessagePriority {
+    /// Bulk traffic such as telemetry.
+    Low,
+    /// Regular messages.
+    #[default]
+    Normal,
+    /// Urgent coordination messages.
+    High,
 }
 
 /// Encrypted payload.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "EncryptedPayloadV1", from = "EncryptedPayloadV1")]
 pub struct EncryptedPayload {
     /// Ciphertext.
     pub ciphertext: Vec<u8>,
 
     /// Message authentication code.
     pub mac: Vec<u8>,
+
+    /// Payload fields this node does not recognize, kept for forwarding.
+    pub extra: Map<String, Value>,
 }
 
 impl EncryptedPayload {
     /// Create a new payload.
     pub fn new(ciphertext: Vec<u8>, mac: Vec<u8>) -> Self {
-        Self { ciphertext, mac }
+        Self {
+            ciphertext,
+            mac,
+            extra: Map::new(),
+        }
     }
 
     /// Get ciphertext length.
@@ -162,6 +250,14 @@ pub struct DeliveryReceipt {
 
     /// Signature over the receipt.
     pub signature: Vec<u8>,
+
+    /// Trace ID inherited from the acknowledged envelope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+
+    /// ID of the acknowledged envelope when the receipt is traced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caused_by: Option<Uuid>,
 }
 
 impl DeliveryReceipt {
@@ -172,6 +268,8 @@ impl DeliveryReceipt {
             receipt_type: ReceiptType::Delivered,
             timestamp: Utc::now(),
             signature: Vec::new(), // TODO: Sign
+            correlation_id: None,
+            caused_by: None,
         }
     }
 
@@ -182,6 +280,28 @@ impl DeliveryReceipt {
             receipt_type: ReceiptType::Read,
             timestamp: Utc::now(),
             signature: Vec::new(),
+            correlation_id: None,
+            caused_by: None,
+        }
+    }
+
+    /// Create a delivery receipt inheriting the envelope's trace.
+    pub fn delivered_for(envelope: &Envelope) -> Self {
+        Self::delivered(envelope.id).with_correlation(envelope.correlation().derive(envelope.id))
+    }
+
+    /// Set the trace identifiers.
+    pub fn with_correlation(mut self, correlation: Correlation) -> Self {
+        self.correlation_id = correlation.correlation_id;
+        self.caused_by = correlation.caused_by;
+        self
+    }
+
+    /// Get the trace identifiers for this receipt.
+    pub fn correlation(&self) -> Correlation {
+        Correlation {
+            correlation_id: self.correlation_id,
+            caused_by: self.caused_by,
         }
     }
 }
@@ -198,6 +318,21 @@ pub enum ReceiptType {
     Failed,
 }
 
+/// Where a stored envelope is in its delivery lifecycle, as derived from
+/// its receipts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageStatus {
+    /// Stored, not yet fetched by the recipient.
+    Sent,
+    /// Handed to the recipient.
+    Delivered,
+    /// Acknowledged by the recipient.
+    Read,
+    /// Expired before the recipient fetched it.
+    Expired,
+}
+
 /// A batch of envelopes for efficient delivery.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnvelopeBatch {
@@ -283,4 +418,23 @@ mod tests {
         assert_eq!(receipt.message_id, message_id);
         assert_eq!(receipt.receipt_type, ReceiptType::Delivered);
     }
+
+    #[test]
+    fn test_receipt_inherits_envelope_correlation() {
+        let ratchet_header = MessageHeader {
+            dh_public: vec![0u8; 32],
+            prev_chain_length: 0,
+            message_number: 1,
+        };
+        let correlation = Correlation::new(Uuid::now_v7());
+        let envelope = Envelope::new(AgentId::new(), AgentId::new(), ratchet_header, vec![1])
+            .with_correlation(correlation);
+
+        let decoded = Envelope::from_bytes(&envelope.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded.correlation(), correlation);
+
+        let receipt = DeliveryReceipt::delivered_for(&envelope);
+        assert_eq!(receipt.correlation_id, correlation.correlation_id);
+        assert_eq!(receipt.caused_by, Some(envelope.id));
+    }
 }
@@ -1,8 +1,15 @@
 //! Cryptographic key types for secure messaging.
 
-use creto_common::AgentId;
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, TimeZone, Utc};
+use creto_common::{AgentId, CretoError};
 use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
 use uuid::Uuid;
+use zeroize::Zeroize;
+
+use crate::repository::PreKeyRepository;
 
 /// An identity key pair for an agent.
 ///
@@ -96,6 +103,30 @@ impl PreKey {
     }
 }
 
+/// How many one-time pre-keys an agent has left for initiators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreKeyStatus {
+    /// Published pre-keys not yet consumed.
+    pub available: u64,
+
+    /// Count below which the agent should replenish.
+    pub threshold: u64,
+
+    /// Whether `available` has fallen below `threshold`.
+    pub below_watermark: bool,
+}
+
+impl PreKeyStatus {
+    /// Status for `available` pre-keys against `threshold`.
+    pub fn new(available: u64, threshold: u64) -> Self {
+        Self {
+            available,
+            threshold,
+            below_watermark: available < threshold,
+        }
+    }
+}
+
 /// A signed pre-key.
 ///
 /// Signed pre-keys are medium-term keys that provide deniability.
@@ -138,6 +169,76 @@ impl SignedPreKey {
         // TODO: Implement signature verification
         true
     }
+
+    /// When this key was generated.
+    pub fn created_at(&self) -> DateTime<Utc> {
+        Utc.timestamp_opt(self.timestamp, 0)
+            .single()
+            .unwrap_or_default()
+    }
+
+    /// Copy without the private key.
+    pub fn public(&self) -> Self {
+        Self {
+            id: self.id,
+            public_key: self.public_key.clone(),
+            signature: self.signature.clone(),
+            timestamp: self.timestamp,
+            private_key: None,
+        }
+    }
+}
+
+/// When signed pre-keys are rotated and how long replaced ones stay usable.
+#[derive(Debug, Clone)]
+pub struct SignedPreKeyRotationPolicy {
+    /// Rotate once the current key is older than this.
+    pub max_age: chrono::Duration,
+
+    /// How long a replaced key still accepts handshakes.
+    pub overlap_window: chrono::Duration,
+
+    /// Maximum number of replaced keys kept, regardless of age.
+    pub max_retained: usize,
+
+    /// How often the background task checks whether rotation is due.
+    pub check_interval: std::time::Duration,
+}
+
+impl Default for SignedPreKeyRotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_age: chrono::Duration::days(7),
+            overlap_window: chrono::Duration::days(2),
+            max_retained: 3,
+            check_interval: std::time::Duration::from_secs(3600),
+        }
+    }
+}
+
+impl SignedPreKeyRotationPolicy {
+    /// Check whether `key` is old enough to be rotated.
+    pub fn rotation_due(&self, key: &SignedPreKey, now: DateTime<Utc>) -> bool {
+        now - key.created_at() >= self.max_age
+    }
+
+    /// Check whether a replaced key still accepts handshakes.
+    pub fn within_overlap(&self, retired: &RetiredSignedPreKey, now: DateTime<Utc>) -> bool {
+        now < retired.retired_at + self.overlap_window
+    }
+}
+
+/// A signed pre-key replaced by rotation.
+///
+/// Kept so handshakes started against it before the rotation can still
+/// complete during the overlap window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetiredSignedPreKey {
+    /// The replaced key.
+    pub key: SignedPreKey,
+
+    /// When it stopped being the current key.
+    pub retired_at: DateTime<Utc>,
 }
 
 /// A complete key bundle for establishing sessions.
@@ -182,13 +283,7 @@ impl KeyBundle {
                 self.agent_id,
                 self.identity_key.public_key.clone(),
             ),
-            signed_pre_key: SignedPreKey {
-                id: self.signed_pre_key.id,
-                public_key: self.signed_pre_key.public_key.clone(),
-                signature: self.signed_pre_key.signature.clone(),
-                timestamp: self.signed_pre_key.timestamp,
-                private_key: None,
-            },
+            signed_pre_key: self.signed_pre_key.public(),
             one_time_pre_key: self.one_time_pre_key.as_ref().map(|pk| PreKey {
                 id: pk.id,
                 public_key: pk.public_key.clone(),
@@ -229,6 +324,263 @@ pub trait KeyStore: Send + Sync {
 
     /// Get the count of remaining pre-keys.
     async fn pre_key_count(&self, agent_id: AgentId) -> creto_common::CretoResult<u32>;
+
+    /// Delete every uploaded pre-key of an agent, returning how many were.
+    async fn discard_pre_keys(&self, agent_id: AgentId) -> creto_common::CretoResult<u32>;
+
+    /// Keep a signed pre-key replaced by rotation.
+    async fn retain_signed_pre_key(
+        &self,
+        agent_id: AgentId,
+        key: RetiredSignedPreKey,
+    ) -> creto_common::CretoResult<()>;
+
+    /// Get an agent's retained signed pre-keys, most recently retired first.
+    async fn retained_signed_pre_keys(
+        &self,
+        agent_id: AgentId,
+    ) -> creto_common::CretoResult<Vec<RetiredSignedPreKey>>;
+
+    /// Delete a retained signed pre-key.
+    ///
+    /// Implementations must zeroize the private key before releasing it.
+    async fn destroy_signed_pre_key(
+        &self,
+        agent_id: AgentId,
+        key_id: u32,
+    ) -> creto_common::CretoResult<()>;
+}
+
+/// In-memory key store for testing and development.
+#[derive(Default)]
+pub struct InMemoryKeyStore {
+    identity_keys: RwLock<HashMap<AgentId, IdentityKey>>,
+    bundles: RwLock<HashMap<AgentId, KeyBundle>>,
+    pre_keys: RwLock<HashMap<AgentId, Vec<PreKey>>>,
+    retained: RwLock<HashMap<AgentId, Vec<RetiredSignedPreKey>>>,
+}
+
+impl InMemoryKeyStore {
+    /// Create an empty key store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl KeyStore for InMemoryKeyStore {
+    async fn store_identity_key(&self, key: &IdentityKey) -> creto_common::CretoResult<()> {
+        self.identity_keys
+            .write()
+            .await
+            .insert(key.agent_id, key.clone());
+        Ok(())
+    }
+
+    async fn get_identity_key(
+        &self,
+        agent_id: AgentId,
+    ) -> creto_common::CretoResult<Option<IdentityKey>> {
+        Ok(self.identity_keys.read().await.get(&agent_id).cloned())
+    }
+
+    async fn store_bundle(&self, bundle: &KeyBundle) -> creto_common::CretoResult<()> {
+        self.bundles
+            .write()
+            .await
+            .insert(bundle.agent_id, bundle.clone());
+        Ok(())
+    }
+
+    async fn get_bundle(&self, agent_id: AgentId) -> creto_common::CretoResult<Option<KeyBundle>> {
+        Ok(self.bundles.read().await.get(&agent_id).cloned())
+    }
+
+    async fn consume_pre_key(
+        &self,
+        agent_id: AgentId,
+    ) -> creto_common::CretoResult<Option<PreKey>> {
+        Ok(self
+            .pre_keys
+            .write()
+            .await
+            .get_mut(&agent_id)
+            .and_then(|keys| (!keys.is_empty()).then(|| keys.remove(0))))
+    }
+
+    async fn upload_pre_keys(
+        &self,
+        agent_id: AgentId,
+        keys: Vec<PreKey>,
+    ) -> creto_common::CretoResult<()> {
+        self.pre_keys
+            .write()
+            .await
+            .entry(agent_id)
+            .or_default()
+            .extend(keys);
+        Ok(())
+    }
+
+    async fn pre_key_count(&self, agent_id: AgentId) -> creto_common::CretoResult<u32> {
+        Ok(self
+            .pre_keys
+            .read()
+            .await
+            .get(&agent_id)
+            .map_or(0, |keys| keys.len() as u32))
+    }
+
+    async fn discard_pre_keys(&self, agent_id: AgentId) -> creto_common::CretoResult<u32> {
+        Ok(self
+            .pre_keys
+            .write()
+            .await
+            .remove(&agent_id)
+            .map_or(0, |keys| keys.len() as u32))
+    }
+
+    async fn retain_signed_pre_key(
+        &self,
+        agent_id: AgentId,
+        key: RetiredSignedPreKey,
+    ) -> creto_common::CretoResult<()> {
+        self.retained
+            .write()
+            .await
+            .entry(agent_id)
+            .or_default()
+            .insert(0, key);
+        Ok(())
+    }
+
+    async fn retained_signed_pre_keys(
+        &self,
+        agent_id: AgentId,
+    ) -> creto_common::CretoResult<Vec<RetiredSignedPreKey>> {
+        Ok(self
+            .retained
+            .read()
+            .await
+            .get(&agent_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn destroy_signed_pre_key(
+        &self,
+        agent_id: AgentId,
+        key_id: u32,
+    ) -> creto_common::CretoResult<()> {
+        let mut retained = self.retained.write().await;
+        if let Some(keys) = retained.get_mut(&agent_id) {
+            keys.retain_mut(|retired| {
+                if retired.key.id != key_id {
+                    return true;
+                }
+                if let Some(private_key) = retired.key.private_key.as_mut() {
+                    private_key.zeroize();
+                }
+                false
+            });
+        }
+        Ok(())
+    }
+}
+
+/// In-memory one-time prekey repository for testing and development.
+///
+/// Mirrors [`PgPreKeyRepository`](crate::repository::PgPreKeyRepository):
+/// prekeys are consumed lowest ID first, each at most once however many
+/// initiators race for them, and a consumed ID cannot be stored again.
+#[derive(Default)]
+pub struct InMemoryPreKeyRepository {
+    prekeys: RwLock<HashMap<AgentId, AgentPreKeys>>,
+}
+
+/// Public keys by prekey ID; `None` once consumed.
+type AgentPreKeys = BTreeMap<i32, Option<Vec<u8>>>;
+
+impl InMemoryPreKeyRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl PreKeyRepository for InMemoryPreKeyRepository {
+    async fn store(
+        &self,
+        agent_id: AgentId,
+        prekey_id: i32,
+        public_key: &[u8],
+    ) -> Result<(), CretoError> {
+        self.prekeys
+            .write()
+            .await
+            .entry(agent_id)
+            .or_default()
+            .entry(prekey_id)
+            .or_insert_with(|| Some(public_key.to_vec()));
+        Ok(())
+    }
+
+    async fn store_next(
+        &self,
+        agent_id: AgentId,
+        public_keys: &[Vec<u8>],
+    ) -> Result<Vec<i32>, CretoError> {
+        let mut prekeys = self.prekeys.write().await;
+        let keys = prekeys.entry(agent_id).or_default();
+        let start = keys.keys().next_back().map_or(0, |id| *id) + 1;
+        let mut ids = Vec::with_capacity(public_keys.len());
+        for (prekey_id, public_key) in (start..).zip(public_keys) {
+            keys.insert(prekey_id, Some(public_key.clone()));
+            ids.push(prekey_id);
+        }
+        Ok(ids)
+    }
+
+    async fn consume(&self, agent_id: AgentId) -> Result<Option<(i32, Vec<u8>)>, CretoError> {
+        let mut prekeys = self.prekeys.write().await;
+        Ok(prekeys.get_mut(&agent_id).and_then(|keys| {
+            keys.iter_mut()
+                .find_map(|(id, key)| key.take().map(|key| (*id, key)))
+        }))
+    }
+
+    async fn count_available(&self, agent_id: AgentId) -> Result<i64, CretoError> {
+        Ok(self
+            .prekeys
+            .read()
+            .await
+            .get(&agent_id)
+            .map_or(0, |keys| keys.values().flatten().count() as i64))
+    }
+
+    async fn discard_available(&self, agent_id: AgentId) -> Result<u64, CretoError> {
+        let mut prekeys = self.prekeys.write().await;
+        Ok(prekeys.get_mut(&agent_id).map_or(0, |keys| {
+            keys.values_mut().filter_map(Option::take).count() as u64
+        }))
+    }
+
+    async fn find_agents_below_watermark(
+        &self,
+        threshold: i64,
+    ) -> Result<Vec<(AgentId, i64)>, CretoError> {
+        let mut below: Vec<(AgentId, i64)> = self
+            .prekeys
+            .read()
+            .await
+            .iter()
+            .map(|(agent_id, keys)| (*agent_id, keys.values().flatten().count() as i64))
+            .filter(|(_, available)| *available < threshold)
+            .collect();
+        below.sort_by_key(|(agent_id, available)| (*available, *agent_id.as_uuid()));
+        Ok(below)
+    }
 }
 
 #[cfg(test)]
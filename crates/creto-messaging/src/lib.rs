@@ -26,31 +26,66 @@
 //! let encrypted = session.encrypt(b"Hello, agent!").await?;
 //! ```
 
+pub mod authz;
 pub mod channel;
 pub mod envelope;
 pub mod keys;
+pub mod mailbox;
 pub mod ratchet;
+pub mod ratchet_store;
+pub mod rate_limit;
+pub mod relay;
 pub mod repository;
 pub mod service;
 pub mod session;
+pub mod snapshot;
 pub mod topic;
+pub mod wire;
 pub mod x3dh;
 
+pub use authz::{
+    AuthzDecision, AuthzFailureMode, AuthzHook, MockAuthzHook, TopicAction,
+    DEFAULT_AUTHZ_CACHE_TTL, DEFAULT_AUTHZ_TIMEOUT,
+};
 pub use channel::{Channel, ChannelConfig, ChannelType};
 pub use envelope::{
-    ContentType, DeliveryReceipt, EncryptedPayload, Envelope, EnvelopeHeader, ReceiptType,
+    ContentType, DeliveryReceipt, EncryptedPayload, Envelope, EnvelopeHeader, MessagePriority,
+    MessageStatus, ReceiptType,
+};
+pub use keys::{
+    IdentityKey, InMemoryKeyStore, InMemoryPreKeyRepository, KeyBundle, KeyStore, PreKey,
+    PreKeyStatus, RetiredSignedPreKey, SignedPreKey, SignedPreKeyRotationPolicy,
 };
-pub use keys::{IdentityKey, KeyBundle, PreKey, SignedPreKey};
+pub use mailbox::{InMemoryEnvelopeRepository, MailboxConfig, MailboxOverflowPolicy, MailboxUsage};
 pub use ratchet::{DoubleRatchet, RatchetState};
+pub use ratchet_store::{
+    EncryptedRatchetState, InMemoryRatchetStateRepository, RatchetFlushPolicy, StateEncryptionKey,
+};
+pub use rate_limit::{MessageRateLimiter, RateLimit, RateLimitScope, RateLimited, RateLimits};
+pub use relay::{
+    EnvelopeRelay, FlushReport, InMemoryRecipientDirectory, InMemoryRelayQueueRepository,
+    InboundOutcome, QueuedRelayFrame, RecipientDirectory, RelayConfig, RelayFrame, RelayHeader,
+    RelayPayload, RelayPeer, RelayRequest, RelayRoute, RelayTransport, ReturnRoute,
+};
 pub use repository::{
-    ChannelRepository, EnvelopeRepository, KeyBundleRepository, PgChannelRepository,
-    PgEnvelopeRepository, PgKeyBundleRepository, PgPreKeyRepository, PgSessionRepository,
-    PreKeyRepository, SessionRepository,
+    spawn_envelope_cleanup, ChannelRepository, EnvelopeRepository, KeyBundleRepository,
+    PgChannelRepository, PgEnvelopeRepository, PgKeyBundleRepository, PgPreKeyRepository,
+    PgRatchetStateRepository, PgRelayQueueRepository, PgSessionRepository,
+    PgSubscriptionRepository, PgTopicRepository, PreKeyRepository, RatchetStateRepository,
+    RelayQueueRepository, SessionRecord, SessionRepository, SubscriptionRepository,
+    TopicRepository,
 };
 pub use service::MessagingService;
-pub use session::{Session, SessionState};
+pub use session::{InMemorySessionRepository, Session, SessionMetadata, SessionState};
+pub use snapshot::{MessagingSnapshot, MessagingSnapshotContributor, MESSAGING_SECTION};
 pub use topic::{
-    Subscription, SubscriptionFilter, SubscriptionId, Topic, TopicConfig, TopicId, TopicManager,
+    spawn_topic_sweep, InMemorySubscriptionRepository, InMemoryTopicRepository, ReplayPolicy,
+    RetentionPolicy, Subscription, SubscriptionFilter, SubscriptionId, SubscriptionStart, Topic,
+    TopicConfig, TopicDelivery, TopicId, TopicManager, TopicMessage, TopicNameConflict,
     TopicPolicy,
 };
+pub use wire::{
+    EncryptedPayloadV1, EnvelopeHeaderV1, EnvelopeV1, ENVELOPE_MAJOR_VERSION,
+    ENVELOPE_MINOR_VERSION,
+};
 pub use x3dh::{X3DHParams, X3DHResult};
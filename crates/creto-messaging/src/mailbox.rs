@@ -0,0 +1,566 @@
+//! Per-recipient mailbox limits and delivery ordering for store-and-forward.
+//!
+//! Undelivered envelopes are fetched by priority, then age. Because a steady
+//! stream of high-priority traffic would otherwise starve bulk messages
+//! forever, a fraction of every fetch is reserved for the oldest
+//! low-priority envelopes.
+//!
+//! Each recipient's backlog is capped by count and total bytes. When a new
+//! envelope would exceed either cap the store either rejects it with
+//! [`CretoError::MailboxFull`] or evicts the recipient's oldest
+//! low-priority envelopes to make room, per [`MailboxOverflowPolicy`].
+//!
+//! Undelivered envelopes can be given a TTL, after which they are no longer
+//! handed out and are removed by the next cleanup pass. Delivery and read
+//! receipts are kept alongside the envelopes so senders can follow up.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoError};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::envelope::{DeliveryReceipt, Envelope, MessagePriority, ReceiptType};
+use crate::repository::{EnvelopeRecord, EnvelopeRepository};
+
+/// What to do with a new envelope when the recipient's mailbox is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailboxOverflowPolicy {
+    /// Reject the new envelope with [`CretoError::MailboxFull`].
+    #[default]
+    RejectNew,
+    /// Evict the oldest low-priority envelopes until the new one fits,
+    /// rejecting it only if that is not enough.
+    EvictOldestLowPriority,
+}
+
+/// Per-recipient mailbox limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailboxConfig {
+    /// Maximum undelivered envelopes per recipient.
+    #[serde(default = "default_max_undelivered")]
+    pub max_undelivered: u64,
+
+    /// Maximum total ciphertext bytes undelivered per recipient.
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: u64,
+
+    /// Behavior when a limit would be exceeded.
+    #[serde(default)]
+    pub overflow_policy: MailboxOverflowPolicy,
+
+    /// Fraction (0.0-1.0) of each fetch reserved for the oldest
+    /// low-priority envelopes.
+    #[serde(default = "default_low_priority_reserve")]
+    pub low_priority_reserve: f64,
+
+    /// Seconds an undelivered envelope is kept before it expires (never,
+    /// if unset).
+    #[serde(default)]
+    pub envelope_ttl_seconds: Option<u64>,
+}
+
+fn default_max_undelivered() -> u64 {
+    10_000
+}
+
+fn default_max_bytes() -> u64 {
+    64 * 1024 * 1024 // 64 MiB
+}
+
+fn default_low_priority_reserve() -> f64 {
+    0.1
+}
+
+impl Default for MailboxConfig {
+    fn default() -> Self {
+        Self {
+            max_undelivered: default_max_undelivered(),
+            max_bytes: default_max_bytes(),
+            overflow_policy: MailboxOverflowPolicy::default(),
+            low_priority_reserve: default_low_priority_reserve(),
+            envelope_ttl_seconds: None,
+        }
+    }
+}
+
+impl MailboxConfig {
+    /// Number of slots in a fetch of `limit` reserved for low priority.
+    pub fn reserved_slots(&self, limit: i64) -> i64 {
+        (limit.max(0) as f64 * self.low_priority_reserve.clamp(0.0, 1.0)).floor() as i64
+    }
+
+    /// When an envelope stored at `now` expires, if it does.
+    pub(crate) fn expires_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.envelope_ttl_seconds
+            .map(|ttl| now + chrono::Duration::seconds(ttl.min(i64::MAX as u64) as i64))
+    }
+
+    /// The error returned to senders when rejecting.
+    pub(crate) fn full(&self, recipient_id: AgentId, usage: MailboxUsage) -> CretoError {
+        CretoError::MailboxFull {
+            recipient: recipient_id.to_string(),
+            undelivered: usage.undelivered_count,
+            bytes: usage.undelivered_bytes,
+        }
+    }
+
+    /// Envelopes to evict so an envelope of `incoming_bytes` fits.
+    ///
+    /// `candidates` are the recipient's evictable envelopes (ID, bytes),
+    /// oldest first. Returns `None` if the envelope must be rejected.
+    pub(crate) fn eviction_plan(
+        &self,
+        usage: MailboxUsage,
+        incoming_bytes: u64,
+        candidates: impl IntoIterator<Item = (Uuid, u64)>,
+    ) -> Option<Vec<Uuid>> {
+        let fits = |count: u64, bytes: u64| {
+            count < self.max_undelivered && bytes + incoming_bytes <= self.max_bytes
+        };
+
+        let (mut count, mut bytes) = (usage.undelivered_count, usage.undelivered_bytes);
+        if fits(count, bytes) {
+            return Some(Vec::new());
+        }
+        if self.overflow_policy == MailboxOverflowPolicy::RejectNew {
+            return None;
+        }
+
+        let mut evicted = Vec::new();
+        for (id, size) in candidates {
+            evicted.push(id);
+            count -= 1;
+            bytes -= size;
+            if fits(count, bytes) {
+                return Some(evicted);
+            }
+        }
+        None
+    }
+}
+
+/// Undelivered backlog of a recipient.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MailboxUsage {
+    /// Undelivered envelopes.
+    pub undelivered_count: u64,
+    /// Total ciphertext bytes of undelivered envelopes.
+    pub undelivered_bytes: u64,
+}
+
+/// Pick up to `limit` pending envelopes: `reserved` slots go to the oldest
+/// low-priority envelopes, the rest by priority then age.
+///
+/// Returned in delivery order (priority, then age).
+pub(crate) fn select_for_delivery(
+    mut pending: Vec<EnvelopeRecord>,
+    limit: i64,
+    reserved: i64,
+) -> Vec<EnvelopeRecord> {
+    let limit = limit.max(0) as usize;
+    pending.sort_by_key(|r| r.created_at);
+
+    let mut selected: Vec<EnvelopeRecord> = Vec::with_capacity(limit.min(pending.len()));
+    pending.retain(|r| {
+        let reserve = r.priority == MessagePriority::Low && selected.len() < reserved as usize;
+        if reserve {
+            selected.push(r.clone());
+        }
+        !reserve
+    });
+
+    pending.sort_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then(a.created_at.cmp(&b.created_at))
+    });
+    let remaining = limit.saturating_sub(selected.len());
+    selected.extend(pending.into_iter().take(remaining));
+
+    selected.sort_by(|a, b| {
+        b.priority
+            .cmp(&a.priority)
+            .then(a.created_at.cmp(&b.created_at))
+    });
+    selected.truncate(limit);
+    selected
+}
+
+/// In-memory envelope store for tests and single-process deployments.
+#[derive(Debug, Default)]
+pub struct InMemoryEnvelopeRepository {
+    config: MailboxConfig,
+    envelopes: Mutex<Vec<EnvelopeRecord>>,
+    receipts: Mutex<HashMap<Uuid, Vec<DeliveryReceipt>>>,
+}
+
+impl InMemoryEnvelopeRepository {
+    /// Create an empty store with default mailbox limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the mailbox limits.
+    pub fn with_mailbox_config(mut self, config: MailboxConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+fn is_expired(record: &EnvelopeRecord, now: DateTime<Utc>) -> bool {
+    record
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= now)
+}
+
+fn usage_of<'a>(records: impl Iterator<Item = &'a EnvelopeRecord>) -> MailboxUsage {
+    records.fold(MailboxUsage::default(), |usage, r| MailboxUsage {
+        undelivered_count: usage.undelivered_count + 1,
+        undelivered_bytes: usage.undelivered_bytes + r.ciphertext.len() as u64,
+    })
+}
+
+#[async_trait]
+impl EnvelopeRepository for InMemoryEnvelopeRepository {
+    async fn store(&self, envelope: &Envelope) -> Result<Uuid, CretoError> {
+        let header = &envelope.header;
+        let recipient_id = header.recipient_id;
+        let ciphertext = &envelope.payload.ciphertext;
+
+        let mut envelopes = self.envelopes.lock().unwrap();
+        let pending = |r: &&EnvelopeRecord| r.recipient_id == recipient_id && !r.delivered;
+
+        let usage = usage_of(envelopes.iter().filter(pending));
+        let candidates = envelopes
+            .iter()
+            .filter(pending)
+            .filter(|r| r.priority == MessagePriority::Low)
+            .map(|r| (r.id, r.ciphertext.len() as u64));
+        let evicted = self
+            .config
+            .eviction_plan(usage, ciphertext.len() as u64, candidates)
+            .ok_or_else(|| self.config.full(recipient_id, usage))?;
+        envelopes.retain(|r| !evicted.contains(&r.id));
+
+        let now = Utc::now();
+        envelopes.push(EnvelopeRecord {
+            id: envelope.id,
+            sender_id: header.sender_id,
+            recipient_id,
+            ciphertext: ciphertext.clone(),
+            delivered: false,
+            created_at: now,
+            correlation_id: header.correlation_id,
+            caused_by: header.caused_by,
+            priority: header.priority,
+            expires_at: self.config.expires_at(now),
+            envelope: Some(envelope.clone()),
+        });
+        Ok(envelope.id)
+    }
+
+    async fn get_undelivered(
+        &self,
+        recipient_id: AgentId,
+        limit: i64,
+    ) -> Result<Vec<EnvelopeRecord>, CretoError> {
+        let now = Utc::now();
+        let pending = self
+            .envelopes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.recipient_id == recipient_id && !r.delivered && !is_expired(r, now))
+            .cloned()
+            .collect();
+
+        Ok(select_for_delivery(
+            pending,
+            limit,
+            self.config.reserved_slots(limit),
+        ))
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<EnvelopeRecord>, CretoError> {
+        Ok(self
+            .envelopes
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.id == id)
+            .cloned())
+    }
+
+    async fn mark_delivered(&self, id: Uuid) -> Result<(), CretoError> {
+        if let Some(record) = self
+            .envelopes
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|r| r.id == id)
+        {
+            record.delivered = true;
+        }
+        Ok(())
+    }
+
+    async fn record_receipt(
+        &self,
+        envelope_id: Uuid,
+        receipt_type: ReceiptType,
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), CretoError> {
+        if !self
+            .envelopes
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|r| r.id == envelope_id)
+        {
+            return Err(CretoError::NotFound(format!("Envelope {}", envelope_id)));
+        }
+
+        let mut receipts = self.receipts.lock().unwrap();
+        let recorded = receipts.entry(envelope_id).or_default();
+        if !recorded.iter().any(|r| r.receipt_type == receipt_type) {
+            recorded.push(DeliveryReceipt {
+                receipt_type,
+                timestamp,
+                ..DeliveryReceipt::delivered(envelope_id)
+            });
+            recorded.sort_by_key(|r| r.timestamp);
+        }
+        Ok(())
+    }
+
+    async fn get_receipts(&self, envelope_id: Uuid) -> Result<Vec<DeliveryReceipt>, CretoError> {
+        Ok(self
+            .receipts
+            .lock()
+            .unwrap()
+            .get(&envelope_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn cleanup_expired(&self) -> Result<i64, CretoError> {
+        let now = Utc::now();
+        let mut envelopes = self.envelopes.lock().unwrap();
+        let expired: Vec<Uuid> = envelopes
+            .iter()
+            .filter(|r| !r.delivered && is_expired(r, now))
+            .map(|r| r.id)
+            .collect();
+        envelopes.retain(|r| !expired.contains(&r.id));
+        drop(envelopes);
+
+        let mut receipts = self.receipts.lock().unwrap();
+        for id in &expired {
+            receipts.remove(id);
+        }
+        Ok(expired.len() as i64)
+    }
+
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<EnvelopeRecord>, CretoError> {
+        let mut found: Vec<_> = self
+            .envelopes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.correlation_id == Some(correlation_id))
+            .cloned()
+            .collect();
+        found.sort_by_key(|r| r.created_at);
+        Ok(found)
+    }
+
+    async fn mailbox_usage(&self, recipient_id: AgentId) -> Result<MailboxUsage, CretoError> {
+        Ok(usage_of(self.envelopes.lock().unwrap().iter().filter(
+            |r| r.recipient_id == recipient_id && !r.delivered,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ratchet::MessageHeader;
+
+    async fn store(
+        repo: &InMemoryEnvelopeRepository,
+        recipient: AgentId,
+        bytes: usize,
+        priority: MessagePriority,
+    ) -> Result<Uuid, CretoError> {
+        let ratchet_header = MessageHeader {
+            dh_public: vec![0u8; 32],
+            prev_chain_length: 0,
+            message_number: 0,
+        };
+        let envelope = Envelope::new(AgentId::new(), recipient, ratchet_header, vec![0u8; bytes])
+            .with_priority(priority);
+        repo.store(&envelope).await
+    }
+
+    #[tokio::test]
+    async fn test_priority_ordering_with_starvation_protection() {
+        let repo = InMemoryEnvelopeRepository::new().with_mailbox_config(MailboxConfig {
+            low_priority_reserve: 0.25,
+            ..MailboxConfig::default()
+        });
+        let recipient = AgentId::new();
+
+        let oldest_low = store(&repo, recipient, 8, MessagePriority::Low)
+            .await
+            .unwrap();
+        let _newer_low = store(&repo, recipient, 8, MessagePriority::Low)
+            .await
+            .unwrap();
+        let normal = store(&repo, recipient, 8, MessagePriority::Normal)
+            .await
+            .unwrap();
+        let mut high = Vec::new();
+        for _ in 0..5 {
+            high.push(
+                store(&repo, recipient, 8, MessagePriority::High)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        // Without a reserve the fetch is all high priority, oldest first
+        let strict = InMemoryEnvelopeRepository::new().with_mailbox_config(MailboxConfig {
+            low_priority_reserve: 0.0,
+            ..MailboxConfig::default()
+        });
+        *strict.envelopes.lock().unwrap() = repo.envelopes.lock().unwrap().clone();
+        let ids: Vec<_> = strict
+            .get_undelivered(recipient, 4)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, high[..4]);
+
+        // A quarter of the fetch goes to the oldest low-priority envelope
+        let ids: Vec<_> = repo
+            .get_undelivered(recipient, 4)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, vec![high[0], high[1], high[2], oldest_low]);
+
+        // Once high priority drains, normal precedes low
+        for id in &high {
+            repo.mark_delivered(*id).await.unwrap();
+        }
+        let fetched = repo.get_undelivered(recipient, 2).await.unwrap();
+        assert_eq!(fetched[0].id, normal);
+        assert_eq!(fetched[1].id, oldest_low);
+    }
+
+    #[tokio::test]
+    async fn test_full_mailbox_rejects_new_envelopes() {
+        let repo = InMemoryEnvelopeRepository::new().with_mailbox_config(MailboxConfig {
+            max_undelivered: 2,
+            max_bytes: 100,
+            ..MailboxConfig::default()
+        });
+        let recipient = AgentId::new();
+
+        store(&repo, recipient, 10, MessagePriority::Low)
+            .await
+            .unwrap();
+        let delivered = store(&repo, recipient, 10, MessagePriority::Normal)
+            .await
+            .unwrap();
+
+        let err = store(&repo, recipient, 10, MessagePriority::High)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CretoError::MailboxFull {
+                undelivered: 2,
+                bytes: 20,
+                ..
+            }
+        ));
+        assert_eq!(err.code(), "ENABLE-037");
+
+        // Delivery frees room; the byte limit applies independently
+        repo.mark_delivered(delivered).await.unwrap();
+        assert!(store(&repo, recipient, 91, MessagePriority::High)
+            .await
+            .is_err());
+        store(&repo, recipient, 90, MessagePriority::High)
+            .await
+            .unwrap();
+
+        // Other recipients are unaffected
+        store(&repo, AgentId::new(), 10, MessagePriority::Low)
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.mailbox_usage(recipient).await.unwrap(),
+            MailboxUsage {
+                undelivered_count: 2,
+                undelivered_bytes: 100,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_full_mailbox_evicts_oldest_low_priority() {
+        let repo = InMemoryEnvelopeRepository::new().with_mailbox_config(MailboxConfig {
+            max_undelivered: 3,
+            overflow_policy: MailboxOverflowPolicy::EvictOldestLowPriority,
+            ..MailboxConfig::default()
+        });
+        let recipient = AgentId::new();
+
+        let oldest_low = store(&repo, recipient, 10, MessagePriority::Low)
+            .await
+            .unwrap();
+        let normal = store(&repo, recipient, 10, MessagePriority::Normal)
+            .await
+            .unwrap();
+        let newer_low = store(&repo, recipient, 10, MessagePriority::Low)
+            .await
+            .unwrap();
+
+        let urgent = store(&repo, recipient, 10, MessagePriority::High)
+            .await
+            .unwrap();
+        let ids: Vec<_> = repo
+            .get_undelivered(recipient, 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, vec![urgent, normal, newer_low]);
+        assert!(!ids.contains(&oldest_low));
+
+        // The next oldest low-priority envelope goes next; once none are
+        // left, new envelopes are rejected
+        store(&repo, recipient, 10, MessagePriority::High)
+            .await
+            .unwrap();
+        let err = store(&repo, recipient, 10, MessagePriority::Normal)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::MailboxFull { .. }));
+    }
+}
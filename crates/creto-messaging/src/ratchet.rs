@@ -106,8 +106,48 @@ impl RatchetState {
     pub fn can_receive(&self) -> bool {
         self.recv_chain_key.is_some()
     }
+
+    /// Serialize the complete state, including the private DH key and
+    /// skipped message keys the regular serde form leaves out.
+    ///
+    /// The output holds secret key material and must only be stored
+    /// encrypted.
+    pub(crate) fn to_persisted_bytes(&self) -> creto_common::CretoResult<Vec<u8>> {
+        let persisted = PersistedRatchetState {
+            state: self.clone(),
+            dh_private: self.dh_private.clone(),
+            skipped_keys: self
+                .skipped_keys
+                .iter()
+                .map(|(key, message_key)| (key.clone(), message_key.clone()))
+                .collect(),
+        };
+        serde_json::to_vec(&persisted)
+            .map_err(|e| creto_common::CretoError::SerializationError(e.to_string()))
+    }
+
+    /// Rebuild a state written by [`to_persisted_bytes`](Self::to_persisted_bytes).
+    pub(crate) fn from_persisted_bytes(bytes: &[u8]) -> creto_common::CretoResult<Self> {
+        let persisted: PersistedRatchetState = serde_json::from_slice(bytes)
+            .map_err(|e| creto_common::CretoError::SerializationError(e.to_string()))?;
+        let mut state = persisted.state;
+        state.dh_private = persisted.dh_private;
+        state.skipped_keys = persisted.skipped_keys.into_iter().collect();
+        Ok(state)
+    }
+}
+
+/// Complete ratchet state as written to storage.
+#[derive(Serialize, Deserialize)]
+struct PersistedRatchetState {
+    state: RatchetState,
+    dh_private: Option<Vec<u8>>,
+    skipped_keys: Vec<(SkippedKeyId, Vec<u8>)>,
 }
 
+/// A skipped message key's sender DH public key and message number.
+type SkippedKeyId = (Vec<u8>, u32);
+
 /// Double Ratchet implementation.
 pub struct DoubleRatchet {
     state: RatchetState,
@@ -136,6 +176,11 @@ impl DoubleRatchet {
         }
     }
 
+    /// Resume a Double Ratchet from previously persisted state.
+    pub fn from_state(state: RatchetState) -> Self {
+        Self { state }
+    }
+
     /// Get the current state.
     pub fn state(&self) -> &RatchetState {
         &self.state
@@ -385,4 +430,25 @@ mod tests {
         // In production, proper DH ratchet setup is needed
         assert!(!encrypted.ciphertext.is_empty());
     }
+
+    #[test]
+    fn test_persisted_state_keeps_private_material() {
+        let mut receiver = DoubleRatchet::new_receiver(&[42u8; 32], &[1u8; 32], &[2u8; 32]);
+        let mut sender = DoubleRatchet::new_sender(&[42u8; 32], &[1u8; 32]);
+        sender.encrypt(b"first").unwrap();
+        let second = sender.encrypt(b"second").unwrap();
+        // Decrypting the second message first leaves a skipped key behind
+        receiver.decrypt(&second).unwrap();
+
+        let bytes = receiver.state().to_persisted_bytes().unwrap();
+        let restored = RatchetState::from_persisted_bytes(&bytes).unwrap();
+
+        assert_eq!(restored.dh_private, receiver.state().dh_private);
+        assert_eq!(restored.skipped_keys, receiver.state().skipped_keys);
+        assert_eq!(restored.recv_count, receiver.state().recv_count);
+        assert!(serde_json::to_value(receiver.state())
+            .unwrap()
+            .get("dh_private")
+            .is_none());
+    }
 }
@@ -0,0 +1,290 @@
+//! Encrypted-at-rest persistence for Double Ratchet state.
+//!
+//! Ratchet state holds the chain keys and private DH key of a session, so
+//! it is sealed with a caller-supplied [`StateEncryptionKey`] before it
+//! reaches a [`RatchetStateRepository`]. Each blob is bound to its session
+//! ID, so a blob copied onto another session fails to open.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, CretoResult};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use uuid::Uuid;
+use zeroize::Zeroize;
+
+use crate::ratchet::RatchetState;
+use crate::repository::RatchetStateRepository;
+
+/// Length of a [`StateEncryptionKey`] in bytes.
+pub const STATE_ENCRYPTION_KEY_LEN: usize = 32;
+
+/// AES-256-GCM key that encrypts ratchet state at rest.
+///
+/// Supplied by the caller, usually decoded from
+/// `MessagingConfig::state_encryption_key` with
+/// [`from_base64`](Self::from_base64); the service never stores it.
+pub struct StateEncryptionKey {
+    bytes: [u8; STATE_ENCRYPTION_KEY_LEN],
+}
+
+impl StateEncryptionKey {
+    /// Wrap raw key bytes.
+    pub fn new(bytes: [u8; STATE_ENCRYPTION_KEY_LEN]) -> Self {
+        Self { bytes }
+    }
+
+    /// Decode a base64-encoded key.
+    pub fn from_base64(encoded: &str) -> CretoResult<Self> {
+        let mut decoded = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| CretoError::CryptoError("State encryption key is not base64".into()))?;
+        let bytes = <[u8; STATE_ENCRYPTION_KEY_LEN]>::try_from(decoded.as_slice());
+        decoded.zeroize();
+        bytes.map(Self::new).map_err(|_| {
+            CretoError::CryptoError(format!(
+                "State encryption key must be {} bytes",
+                STATE_ENCRYPTION_KEY_LEN
+            ))
+        })
+    }
+
+    /// Encrypt a session's ratchet state.
+    pub fn seal(
+        &self,
+        session_id: Uuid,
+        state: &RatchetState,
+    ) -> CretoResult<EncryptedRatchetState> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| CretoError::CryptoError("Failed to generate nonce".into()))?;
+
+        let mut ciphertext = state.to_persisted_bytes()?;
+        let sealed = self.aead_key().seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(session_id.as_bytes()),
+            &mut ciphertext,
+        );
+        if sealed.is_err() {
+            ciphertext.zeroize();
+            return Err(CretoError::CryptoError(
+                "Failed to encrypt ratchet state".into(),
+            ));
+        }
+
+        Ok(EncryptedRatchetState {
+            session_id,
+            nonce: nonce.to_vec(),
+            ciphertext,
+            updated_at: Utc::now(),
+        })
+    }
+
+    /// Decrypt a session's ratchet state.
+    ///
+    /// Fails with [`CretoError::CryptoError`] when the key is wrong or the
+    /// blob was modified or belongs to another session.
+    pub fn open(&self, encrypted: &EncryptedRatchetState) -> CretoResult<RatchetState> {
+        let rejected = || {
+            CretoError::CryptoError(format!(
+                "Ratchet state for session {} could not be decrypted",
+                encrypted.session_id
+            ))
+        };
+        let nonce = Nonce::try_assume_unique_for_key(&encrypted.nonce).map_err(|_| rejected())?;
+
+        let mut buffer = encrypted.ciphertext.clone();
+        let state = self
+            .aead_key()
+            .open_in_place(
+                nonce,
+                Aad::from(encrypted.session_id.as_bytes()),
+                &mut buffer,
+            )
+            .map_err(|_| rejected())
+            .and_then(|plaintext| RatchetState::from_persisted_bytes(plaintext));
+        buffer.zeroize();
+        state
+    }
+
+    fn aead_key(&self) -> LessSafeKey {
+        // AES-256-GCM accepts any 32-byte key
+        LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &self.bytes).unwrap())
+    }
+}
+
+impl Drop for StateEncryptionKey {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+impl std::fmt::Debug for StateEncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StateEncryptionKey").finish_non_exhaustive()
+    }
+}
+
+/// A session's ratchet state as stored: AES-256-GCM ciphertext with the
+/// session ID as associated data.
+#[derive(Debug, Clone)]
+pub struct EncryptedRatchetState {
+    /// Session the state belongs to.
+    pub session_id: Uuid,
+    /// Nonce used for this write.
+    pub nonce: Vec<u8>,
+    /// Encrypted state followed by the authentication tag.
+    pub ciphertext: Vec<u8>,
+    /// When the state was sealed.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// When ratchet state is written back after a ratchet step.
+///
+/// Every encrypt and decrypt advances the ratchet, so writing on every
+/// step costs one repository write per message. Batching trades that for
+/// losing up to `max_steps - 1` steps on a crash; a session resumed from
+/// older state cannot decrypt messages whose keys it already consumed and
+/// re-derives keys it already used for sending.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RatchetFlushPolicy {
+    /// Write after every ratchet step.
+    #[default]
+    EveryStep,
+    /// Write once a session has taken `max_steps` unsaved steps.
+    Batched {
+        /// Steps between writes.
+        max_steps: u32,
+    },
+}
+
+impl RatchetFlushPolicy {
+    /// Policy writing every `steps` steps, as in
+    /// `MessagingConfig::ratchet_flush_steps`.
+    pub fn from_steps(steps: u32) -> Self {
+        if steps <= 1 {
+            Self::EveryStep
+        } else {
+            Self::Batched { max_steps: steps }
+        }
+    }
+
+    /// Whether a session with `pending` unsaved steps should be written.
+    pub fn should_flush(&self, pending: u32) -> bool {
+        match self {
+            Self::EveryStep => pending >= 1,
+            Self::Batched { max_steps } => pending >= *max_steps,
+        }
+    }
+}
+
+/// In-memory [`RatchetStateRepository`].
+#[derive(Default)]
+pub struct InMemoryRatchetStateRepository {
+    states: Mutex<HashMap<Uuid, EncryptedRatchetState>>,
+}
+
+impl InMemoryRatchetStateRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl RatchetStateRepository for InMemoryRatchetStateRepository {
+    async fn save(&self, state: &EncryptedRatchetState) -> Result<(), CretoError> {
+        self.states
+            .lock()
+            .unwrap()
+            .insert(state.session_id, state.clone());
+        Ok(())
+    }
+
+    async fn load(&self, session_id: Uuid) -> Result<Option<EncryptedRatchetState>, CretoError> {
+        Ok(self.states.lock().unwrap().get(&session_id).cloned())
+    }
+
+    async fn delete(&self, session_id: Uuid) -> Result<(), CretoError> {
+        self.states.lock().unwrap().remove(&session_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> RatchetState {
+        RatchetState::initialize_receiver(&[7u8; 32], &[1u8; 32], &[2u8; 32])
+    }
+
+    #[test]
+    fn test_seal_open_round_trip() {
+        let key = StateEncryptionKey::new([9u8; 32]);
+        let session_id = Uuid::now_v7();
+        let original = state();
+
+        let sealed = key.seal(session_id, &original).unwrap();
+        let plaintext = original.to_persisted_bytes().unwrap();
+        assert!(!sealed
+            .ciphertext
+            .windows(plaintext.len())
+            .any(|w| w == plaintext));
+
+        let opened = key.open(&sealed).unwrap();
+        assert_eq!(
+            opened.to_persisted_bytes().unwrap(),
+            original.to_persisted_bytes().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_open_rejects_wrong_key_and_other_session() {
+        let key = StateEncryptionKey::new([9u8; 32]);
+        let sealed = key.seal(Uuid::now_v7(), &state()).unwrap();
+
+        let err = StateEncryptionKey::new([8u8; 32])
+            .open(&sealed)
+            .unwrap_err();
+        assert!(matches!(err, CretoError::CryptoError(_)));
+
+        let moved = EncryptedRatchetState {
+            session_id: Uuid::now_v7(),
+            ..sealed
+        };
+        assert!(matches!(
+            key.open(&moved).unwrap_err(),
+            CretoError::CryptoError(_)
+        ));
+    }
+
+    #[test]
+    fn test_key_from_base64() {
+        let encoded = base64::engine::general_purpose::STANDARD.encode([3u8; 32]);
+        assert!(StateEncryptionKey::from_base64(&encoded).is_ok());
+
+        for bad in ["c2hvcnQ=", "not base64!"] {
+            assert!(matches!(
+                StateEncryptionKey::from_base64(bad).unwrap_err(),
+                CretoError::CryptoError(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_flush_policy() {
+        assert_eq!(
+            RatchetFlushPolicy::from_steps(0),
+            RatchetFlushPolicy::EveryStep
+        );
+        let batched = RatchetFlushPolicy::from_steps(3);
+        assert!(!batched.should_flush(2));
+        assert!(batched.should_flush(3));
+        assert!(RatchetFlushPolicy::EveryStep.should_flush(1));
+    }
+}
@@ -0,0 +1,634 @@
+//! Per-agent and per-organization rate limits on outgoing traffic.
+//!
+//! [`MessageRateLimiter`] keeps token buckets for message count and payload
+//! bytes, one set per agent and one per organization. Each send, publish and
+//! session establishment takes a message token, and its payload size in
+//! byte tokens; it is admitted only if both the agent's and the
+//! organization's buckets allow it. An agent sending in a tight loop runs
+//! out of tokens without slowing down anyone else.
+//!
+//! Buckets live in maps sharded by key hash, so concurrent senders only
+//! contend when their keys share a shard, or when they belong to the same
+//! organization and it has an organization-wide limit.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use creto_common::{
+    AgentId, Clock, CretoError, OrganizationId, ShardedMap, SystemClock, DEFAULT_SHARDS,
+};
+use serde::{Deserialize, Serialize};
+
+/// Token-bucket limit on messages and payload bytes.
+///
+/// A zero rate leaves that dimension unlimited. Mirrors
+/// `MessagingConfig::agent_rate_limit` and
+/// `MessagingConfig::organization_rate_limit`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Sustained messages per second.
+    #[serde(default)]
+    pub messages_per_second: f64,
+
+    /// Messages that can be sent at once after a quiet period.
+    #[serde(default)]
+    pub message_burst: u32,
+
+    /// Sustained payload bytes per second.
+    #[serde(default)]
+    pub bytes_per_second: f64,
+
+    /// Payload bytes that can be sent at once after a quiet period.
+    ///
+    /// A larger message is still admitted once the bucket is full, leaving
+    /// it in debt until refills catch up.
+    #[serde(default)]
+    pub byte_burst: u64,
+}
+
+impl RateLimit {
+    /// No limit.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Limit messages to `per_second`, with bursts of up to `burst`.
+    pub fn messages(per_second: f64, burst: u32) -> Self {
+        Self {
+            messages_per_second: per_second,
+            message_burst: burst,
+            ..Self::default()
+        }
+    }
+
+    /// Limit payload bytes to `per_second`, with bursts of up to `burst`.
+    pub fn bytes(per_second: f64, burst: u64) -> Self {
+        Self {
+            bytes_per_second: per_second,
+            byte_burst: burst,
+            ..Self::default()
+        }
+    }
+
+    /// Also limit payload bytes to `per_second`, with bursts of up to
+    /// `burst`.
+    pub fn with_bytes(self, per_second: f64, burst: u64) -> Self {
+        Self {
+            bytes_per_second: per_second,
+            byte_burst: burst,
+            ..self
+        }
+    }
+
+    /// Check if neither messages nor bytes are limited.
+    pub fn is_unlimited(&self) -> bool {
+        self.messages_per_second <= 0.0 && self.bytes_per_second <= 0.0
+    }
+
+    /// Rate and burst of the message and byte buckets.
+    fn dimensions(&self) -> [(f64, f64); 2] {
+        [
+            (self.messages_per_second, self.message_burst as f64),
+            (self.bytes_per_second, self.byte_burst as f64),
+        ]
+    }
+}
+
+#[cfg(feature = "config")]
+impl From<creto_common::MessagingRateLimit> for RateLimit {
+    fn from(limit: creto_common::MessagingRateLimit) -> Self {
+        Self {
+            messages_per_second: limit.messages_per_second,
+            message_burst: limit.message_burst,
+            bytes_per_second: limit.bytes_per_second,
+            byte_burst: limit.byte_burst,
+        }
+    }
+}
+
+/// The limits applied to an organization and each of its agents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimits {
+    /// Limit on each agent.
+    #[serde(default)]
+    pub agent: RateLimit,
+
+    /// Limit shared by all of the organization's agents.
+    #[serde(default)]
+    pub organization: RateLimit,
+}
+
+impl RateLimits {
+    /// Limits from the messaging section of the configuration.
+    #[cfg(feature = "config")]
+    pub fn from_config(config: &creto_common::MessagingConfig) -> Self {
+        Self {
+            agent: config.agent_rate_limit.into(),
+            organization: config.organization_rate_limit.into(),
+        }
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.agent.is_unlimited() && self.organization.is_unlimited()
+    }
+}
+
+/// Whose limit refused a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitScope {
+    Agent(AgentId),
+    Organization(OrganizationId),
+}
+
+impl fmt::Display for RateLimitScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Agent(agent_id) => write!(f, "agent {}", agent_id),
+            Self::Organization(organization_id) => write!(f, "organization {}", organization_id),
+        }
+    }
+}
+
+/// A message refused by a rate limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimited {
+    /// Whose limit refused it.
+    pub scope: RateLimitScope,
+    /// How long until the same message would be admitted.
+    pub retry_after: Duration,
+}
+
+impl From<RateLimited> for CretoError {
+    fn from(limited: RateLimited) -> Self {
+        CretoError::LimitExceeded(format!(
+            "Rate limit exceeded for {}, retry after {}ms",
+            limited.scope,
+            limited.retry_after.as_nanos().div_ceil(1_000_000)
+        ))
+    }
+}
+
+/// One token bucket; its rate and burst come from the current limit.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: DateTime<Utc>,
+}
+
+impl Bucket {
+    /// Add the tokens earned since the last refill, up to `burst`.
+    fn refill(&mut self, rate: f64, burst: f64, now: DateTime<Utc>) {
+        if now > self.updated {
+            let elapsed = (now - self.updated).to_std().unwrap_or_default();
+            self.tokens += elapsed.as_secs_f64() * rate;
+            self.updated = now;
+        }
+        self.tokens = self.tokens.min(burst);
+    }
+
+    /// How long until `cost` tokens can be taken.
+    fn wait(&self, rate: f64, burst: f64, cost: f64) -> Duration {
+        let needed = cost.min(burst);
+        if self.tokens >= needed {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((needed - self.tokens) / rate)
+        }
+    }
+}
+
+/// Message and byte buckets of one agent or organization.
+#[derive(Debug)]
+struct Buckets([Bucket; 2]);
+
+impl Buckets {
+    fn new(now: DateTime<Utc>) -> Self {
+        // Start full; the first refill caps them at the burst
+        let full = Bucket {
+            tokens: f64::INFINITY,
+            updated: now,
+        };
+        Self([full; 2])
+    }
+
+    /// Refill, then return how long until a message of `bytes` fits.
+    fn wait(&mut self, limit: &RateLimit, bytes: usize, now: DateTime<Utc>) -> Duration {
+        let mut wait = Duration::ZERO;
+        for ((bucket, (rate, burst)), cost) in self
+            .0
+            .iter_mut()
+            .zip(limit.dimensions())
+            .zip([1.0, bytes as f64])
+        {
+            if rate > 0.0 {
+                bucket.refill(rate, burst, now);
+                wait = wait.max(bucket.wait(rate, burst, cost));
+            }
+        }
+        wait
+    }
+
+    fn take(&mut self, limit: &RateLimit, bytes: usize) {
+        for ((bucket, (rate, _)), cost) in self
+            .0
+            .iter_mut()
+            .zip(limit.dimensions())
+            .zip([1.0, bytes as f64])
+        {
+            if rate > 0.0 {
+                bucket.tokens -= cost;
+            }
+        }
+    }
+
+    /// Check if the buckets have refilled completely by `now`, making them
+    /// equivalent to new ones.
+    fn is_full(&mut self, limit: &RateLimit, now: DateTime<Utc>) -> bool {
+        self.0
+            .iter_mut()
+            .zip(limit.dimensions())
+            .all(|(bucket, (rate, burst))| {
+                if rate > 0.0 {
+                    bucket.refill(rate, burst, now);
+                }
+                rate <= 0.0 || bucket.tokens >= burst
+            })
+    }
+}
+
+/// An organization's limit override and shared buckets.
+#[derive(Debug)]
+struct OrganizationEntry {
+    limits: Option<RateLimits>,
+    buckets: Buckets,
+}
+
+impl OrganizationEntry {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            limits: None,
+            buckets: Buckets::new(now),
+        }
+    }
+}
+
+/// Take tokens for one message from the buckets given, or none if either
+/// is short.
+fn admit(
+    limits: &RateLimits,
+    organization_id: OrganizationId,
+    mut organization: Option<&mut Buckets>,
+    agent_id: AgentId,
+    mut agent: Option<&mut Buckets>,
+    bytes: usize,
+    now: DateTime<Utc>,
+) -> Result<(), RateLimited> {
+    let agent_wait = agent.as_mut().map_or(Duration::ZERO, |buckets| {
+        buckets.wait(&limits.agent, bytes, now)
+    });
+    let organization_wait = organization.as_mut().map_or(Duration::ZERO, |buckets| {
+        buckets.wait(&limits.organization, bytes, now)
+    });
+    if organization_wait > agent_wait {
+        return Err(RateLimited {
+            scope: RateLimitScope::Organization(organization_id),
+            retry_after: organization_wait,
+        });
+    }
+    if agent_wait > Duration::ZERO {
+        return Err(RateLimited {
+            scope: RateLimitScope::Agent(agent_id),
+            retry_after: agent_wait,
+        });
+    }
+
+    if let Some(buckets) = agent {
+        buckets.take(&limits.agent, bytes);
+    }
+    if let Some(buckets) = organization {
+        buckets.take(&limits.organization, bytes);
+    }
+    Ok(())
+}
+
+/// Token-bucket rate limiter for agents and organizations.
+///
+/// Share one limiter between the services of all local agents so that
+/// organization limits cover their combined traffic.
+pub struct MessageRateLimiter {
+    defaults: RateLimits,
+    organizations: ShardedMap<OrganizationId, OrganizationEntry>,
+    agents: ShardedMap<AgentId, Buckets>,
+    clock: Arc<dyn Clock>,
+}
+
+impl MessageRateLimiter {
+    /// Create a limiter applying `defaults` to organizations without an
+    /// override.
+    pub fn new(defaults: RateLimits) -> Self {
+        Self {
+            defaults,
+            organizations: ShardedMap::new(DEFAULT_SHARDS),
+            agents: ShardedMap::new(DEFAULT_SHARDS),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the time source buckets refill by.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The limits of organizations without an override.
+    pub fn defaults(&self) -> RateLimits {
+        self.defaults
+    }
+
+    /// The limits applied to an organization and its agents.
+    pub fn limits_for(&self, organization_id: OrganizationId) -> RateLimits {
+        self.organizations
+            .read(&organization_id, |entry| entry.limits)
+            .flatten()
+            .unwrap_or(self.defaults)
+    }
+
+    /// Apply `limits` to an organization and its agents instead of the
+    /// defaults.
+    ///
+    /// Tokens already in their buckets are kept, up to the new bursts.
+    pub fn set_organization_limits(&self, organization_id: OrganizationId, limits: RateLimits) {
+        let now = self.clock.now();
+        self.organizations.update_or_insert_with(
+            organization_id,
+            || OrganizationEntry::new(now),
+            |entry| entry.limits = Some(limits),
+        );
+    }
+
+    /// Return an organization to the defaults, returning its override.
+    pub fn clear_organization_limits(&self, organization_id: OrganizationId) -> Option<RateLimits> {
+        self.organizations
+            .update(&organization_id, |entry| entry.limits.take())
+            .flatten()
+    }
+
+    /// Take tokens for one message with a `bytes` byte payload from an
+    /// agent of `organization_id`.
+    ///
+    /// Takes nothing if either the agent's or the organization's buckets
+    /// are short.
+    pub fn acquire(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        bytes: usize,
+    ) -> Result<(), RateLimited> {
+        let now = self.clock.now();
+        let limits = self.limits_for(organization_id);
+        if limits.is_unlimited() {
+            return Ok(());
+        }
+
+        // Without an organization-wide limit its agents need not contend on
+        // the organization's shard
+        if limits.organization.is_unlimited() {
+            return self.acquire_agent(&limits, organization_id, None, agent_id, bytes, now);
+        }
+        self.organizations.update_or_insert_with(
+            organization_id,
+            || OrganizationEntry::new(now),
+            |entry| {
+                // The override may have changed since it was read
+                let limits = entry.limits.unwrap_or(self.defaults);
+                self.acquire_agent(
+                    &limits,
+                    organization_id,
+                    Some(&mut entry.buckets),
+                    agent_id,
+                    bytes,
+                    now,
+                )
+            },
+        )
+    }
+
+    /// Take tokens from an agent's buckets and, if given, its
+    /// organization's, which the caller holds locked.
+    fn acquire_agent(
+        &self,
+        limits: &RateLimits,
+        organization_id: OrganizationId,
+        organization: Option<&mut Buckets>,
+        agent_id: AgentId,
+        bytes: usize,
+        now: DateTime<Utc>,
+    ) -> Result<(), RateLimited> {
+        if limits.agent.is_unlimited() {
+            return admit(
+                limits,
+                organization_id,
+                organization,
+                agent_id,
+                None,
+                bytes,
+                now,
+            );
+        }
+        self.agents.update_or_insert_with(
+            agent_id,
+            || Buckets::new(now),
+            |agent| {
+                admit(
+                    limits,
+                    organization_id,
+                    organization,
+                    agent_id,
+                    Some(agent),
+                    bytes,
+                    now,
+                )
+            },
+        )
+    }
+
+    /// Drop buckets that have refilled completely, returning how many were
+    /// dropped.
+    ///
+    /// They are equivalent to the full buckets a new sender starts with, so
+    /// this only bounds memory. Agent buckets are checked against the
+    /// default agent limit, which overrides may raise; such agents simply
+    /// start again from a full bucket.
+    pub fn prune(&self) -> usize {
+        let now = self.clock.now();
+        let defaults = self.defaults;
+        let organizations = self.organizations.retain(|_, entry| {
+            let limit = entry.limits.unwrap_or(defaults).organization;
+            entry.limits.is_some() || !entry.buckets.is_full(&limit, now)
+        });
+        let agents = self
+            .agents
+            .retain(|_, buckets| !buckets.is_full(&defaults.agent, now));
+        organizations + agents
+    }
+}
+
+impl fmt::Debug for MessageRateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageRateLimiter")
+            .field("defaults", &self.defaults)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use creto_common::TestClock;
+
+    fn limiter(defaults: RateLimits) -> (MessageRateLimiter, Arc<TestClock>) {
+        let clock = Arc::new(TestClock::new(Utc::now()));
+        let limiter = MessageRateLimiter::new(defaults).with_clock(clock.clone());
+        (limiter, clock)
+    }
+
+    fn per_agent(limit: RateLimit) -> RateLimits {
+        RateLimits {
+            agent: limit,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bucket_refills_at_configured_rate() {
+        let (limiter, clock) = limiter(per_agent(RateLimit::messages(2.0, 2)));
+        let (org, agent) = (OrganizationId::new(), AgentId::new());
+
+        limiter.acquire(org, agent, 0).unwrap();
+        limiter.acquire(org, agent, 0).unwrap();
+        let limited = limiter.acquire(org, agent, 0).unwrap_err();
+        assert_eq!(limited.scope, RateLimitScope::Agent(agent));
+        assert_eq!(limited.retry_after, Duration::from_millis(500));
+
+        clock.advance(chrono::Duration::milliseconds(499));
+        assert!(limiter.acquire(org, agent, 0).is_err());
+        clock.advance(chrono::Duration::milliseconds(1));
+        limiter.acquire(org, agent, 0).unwrap();
+        assert!(limiter.acquire(org, agent, 0).is_err());
+
+        // A long pause refills no more than the burst
+        clock.advance(chrono::Duration::seconds(60));
+        limiter.acquire(org, agent, 0).unwrap();
+        limiter.acquire(org, agent, 0).unwrap();
+        assert!(limiter.acquire(org, agent, 0).is_err());
+    }
+
+    #[test]
+    fn test_agents_are_limited_independently() {
+        let (limiter, _clock) = limiter(per_agent(RateLimit::messages(1.0, 3)));
+        let org = OrganizationId::new();
+        let (noisy, quiet) = (AgentId::new(), AgentId::new());
+
+        for _ in 0..3 {
+            limiter.acquire(org, noisy, 0).unwrap();
+        }
+        assert!(limiter.acquire(org, noisy, 0).is_err());
+
+        for _ in 0..3 {
+            limiter.acquire(org, quiet, 0).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_byte_limit_counts_payload_size() {
+        let (limiter, clock) = limiter(per_agent(RateLimit::bytes(1000.0, 1000)));
+        let (org, agent) = (OrganizationId::new(), AgentId::new());
+
+        limiter.acquire(org, agent, 600).unwrap();
+        let limited = limiter.acquire(org, agent, 600).unwrap_err();
+        assert_eq!(limited.retry_after, Duration::from_millis(200));
+
+        // Many small messages fit in what one large one could not
+        for _ in 0..10 {
+            limiter.acquire(org, agent, 40).unwrap();
+        }
+        assert!(limiter.acquire(org, agent, 1).is_err());
+
+        // A payload above the burst is admitted from a full bucket
+        clock.advance(chrono::Duration::seconds(1));
+        limiter.acquire(org, agent, 2500).unwrap();
+        let limited = limiter.acquire(org, agent, 500).unwrap_err();
+        assert_eq!(limited.retry_after, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_organization_limit_is_shared_by_its_agents() {
+        let (limiter, _clock) = limiter(RateLimits {
+            agent: RateLimit::messages(10.0, 10),
+            organization: RateLimit::messages(1.0, 3),
+        });
+        let org = OrganizationId::new();
+        let (first, second) = (AgentId::new(), AgentId::new());
+
+        limiter.acquire(org, first, 0).unwrap();
+        limiter.acquire(org, first, 0).unwrap();
+        limiter.acquire(org, second, 0).unwrap();
+        let limited = limiter.acquire(org, second, 0).unwrap_err();
+        assert_eq!(limited.scope, RateLimitScope::Organization(org));
+
+        // Other organizations have their own budget
+        limiter.acquire(OrganizationId::new(), first, 0).unwrap();
+    }
+
+    #[test]
+    fn test_organization_override_replaces_defaults() {
+        let (limiter, _clock) = limiter(per_agent(RateLimit::messages(1.0, 1)));
+        let (org, agent) = (OrganizationId::new(), AgentId::new());
+        let raised = per_agent(RateLimit::messages(5.0, 5));
+
+        limiter.set_organization_limits(org, raised);
+        assert_eq!(limiter.limits_for(org), raised);
+        for _ in 0..5 {
+            limiter.acquire(org, agent, 0).unwrap();
+        }
+        assert!(limiter.acquire(org, agent, 0).is_err());
+
+        let other = AgentId::new();
+        let default_org = OrganizationId::new();
+        limiter.acquire(default_org, other, 0).unwrap();
+        assert!(limiter.acquire(default_org, other, 0).is_err());
+
+        assert_eq!(limiter.clear_organization_limits(org), Some(raised));
+        assert_eq!(limiter.limits_for(org), limiter.defaults());
+    }
+
+    #[test]
+    fn test_prune_drops_refilled_buckets() {
+        let (limiter, clock) = limiter(per_agent(RateLimit::messages(1.0, 2)));
+        let org = OrganizationId::new();
+        let (idle, busy) = (AgentId::new(), AgentId::new());
+
+        limiter.acquire(org, idle, 0).unwrap();
+        clock.advance(chrono::Duration::seconds(1));
+        limiter.acquire(org, busy, 0).unwrap();
+
+        assert_eq!(limiter.prune(), 1);
+        limiter.acquire(org, busy, 0).unwrap();
+        assert!(limiter.acquire(org, busy, 0).is_err());
+    }
+
+    #[test]
+    fn test_rate_limited_converts_to_limit_exceeded() {
+        let agent = AgentId::new();
+        let err = CretoError::from(RateLimited {
+            scope: RateLimitScope::Agent(agent),
+            retry_after: Duration::from_micros(1500),
+        });
+
+        assert_eq!(err.code(), "ENABLE-033");
+        assert!(err.to_string().contains("retry after 2ms"));
+        assert!(err.to_string().contains(&agent.to_string()));
+    }
+}
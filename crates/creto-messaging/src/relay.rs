@@ -0,0 +1,1094 @@
+//! Cross-region envelope relay.
+//!
+//! Each messaging node serves one region and stores envelopes for the agents
+//! homed there. When an envelope is submitted for a recipient homed
+//! elsewhere (per the [`RecipientDirectory`]), [`EnvelopeRelay`] queues it
+//! for the peer node of that region and forwards it on the next
+//! [`flush`](EnvelopeRelay::flush). Envelopes are end-to-end encrypted, so a
+//! relay only ever reads their routing header.
+//!
+//! Frames between nodes carry a [`RelayHeader`] listing every node they
+//! passed through. A node drops a frame that already lists it, or that has
+//! made more than [`RelayConfig::max_hops`] hops, so a misconfigured
+//! directory cannot bounce an envelope between regions forever. Envelope
+//! IDs seen recently are also dropped, so a frame retried after a lost
+//! response is delivered once.
+//!
+//! When a relayed envelope is delivered, its [`DeliveryReceipt`] travels back
+//! to the origin node along the reverse of the envelope's path.
+//!
+//! Peers authenticate each other with a per-peer shared secret. Forwarding
+//! retries per the [`RetryPolicy`]; repeated failures open the peer's
+//! circuit, and frames for it stay queued until the open period has passed.
+//! The outbound queue and the return routes of envelopes awaiting delivery
+//! are kept in a [`RelayQueueRepository`], so neither is lost on restart.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use creto_common::{AgentId, CretoError, CretoResult, ShutdownCoordinator};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::channel::RetryPolicy;
+use crate::envelope::{DeliveryReceipt, Envelope};
+use crate::repository::{EnvelopeRepository, RelayQueueRepository};
+
+/// A node in another region that envelopes can be relayed to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayPeer {
+    /// Node name, which is also the region name the directory homes agents
+    /// in.
+    pub name: String,
+
+    /// Address of the peer's inbound relay endpoint.
+    pub endpoint: String,
+
+    /// Secret shared with the peer, presented in both directions.
+    pub shared_secret: String,
+}
+
+impl RelayPeer {
+    /// Create a peer.
+    pub fn new(
+        name: impl Into<String>,
+        endpoint: impl Into<String>,
+        shared_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            endpoint: endpoint.into(),
+            shared_secret: shared_secret.into(),
+        }
+    }
+}
+
+/// Per-peer circuit breaker settings.
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed attempts that open the circuit.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial attempt.
+    pub open_for: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+/// State of a peer's circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Frames flow normally.
+    Closed,
+    /// Frames stay queued.
+    Open,
+    /// The open period elapsed; the next attempt decides.
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Relay settings for one node.
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    /// This node's name, as its peers and the directory know it.
+    pub node: String,
+
+    /// Nodes envelopes can be relayed to.
+    pub peers: Vec<RelayPeer>,
+
+    /// Frames that have made more hops than this are dropped.
+    pub max_hops: u8,
+
+    /// Retries of a single forward.
+    pub retry: RetryPolicy,
+
+    /// Circuit breaker applied to each peer.
+    pub circuit_breaker: CircuitBreakerConfig,
+
+    /// How many recently seen envelope IDs are remembered to drop
+    /// duplicates.
+    pub dedup_capacity: usize,
+}
+
+impl RelayConfig {
+    /// Settings for a node with no peers.
+    pub fn new(node: impl Into<String>) -> Self {
+        Self {
+            node: node.into(),
+            peers: Vec::new(),
+            max_hops: 4,
+            retry: RetryPolicy::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            dedup_capacity: 10_000,
+        }
+    }
+
+    /// Add a peer.
+    pub fn with_peer(mut self, peer: RelayPeer) -> Self {
+        self.peers.push(peer);
+        self
+    }
+
+    /// Set the hop limit.
+    pub fn with_max_hops(mut self, max_hops: u8) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    /// Set the retry policy for forwards.
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Set the per-peer circuit breaker.
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = breaker;
+        self
+    }
+
+    fn peer(&self, name: &str) -> Option<&RelayPeer> {
+        self.peers.iter().find(|p| p.name == name)
+    }
+}
+
+/// Resolves which region an agent is homed in.
+#[async_trait]
+pub trait RecipientDirectory: Send + Sync {
+    /// Name of the node the agent is homed on, or `None` if unknown, in which
+    /// case envelopes for it are kept locally.
+    async fn home_of(&self, agent_id: AgentId) -> CretoResult<Option<String>>;
+}
+
+/// In-memory recipient directory for testing.
+#[derive(Debug, Default)]
+pub struct InMemoryRecipientDirectory {
+    homes: RwLock<HashMap<AgentId, String>>,
+}
+
+impl InMemoryRecipientDirectory {
+    /// Create an empty directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Home an agent on a node.
+    pub fn set_home(&self, agent_id: AgentId, node: impl Into<String>) {
+        self.homes.write().unwrap().insert(agent_id, node.into());
+    }
+}
+
+#[async_trait]
+impl RecipientDirectory for InMemoryRecipientDirectory {
+    async fn home_of(&self, agent_id: AgentId) -> CretoResult<Option<String>> {
+        Ok(self.homes.read().unwrap().get(&agent_id).cloned())
+    }
+}
+
+/// Routing metadata of a relayed frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayHeader {
+    /// Node the frame was first sent from.
+    pub origin: String,
+
+    /// Nodes the frame has left, in order.
+    pub via: Vec<String>,
+
+    /// Relays the frame has made.
+    pub hops: u8,
+}
+
+/// What a frame carries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RelayPayload {
+    /// An envelope on its way to the recipient's home node.
+    Envelope {
+        /// The envelope, still encrypted.
+        envelope: Envelope,
+    },
+    /// A receipt on its way back to the envelope's origin node.
+    Receipt {
+        /// The receipt.
+        receipt: DeliveryReceipt,
+        /// Nodes still to pass through after the receiving one, nearest
+        /// first. Empty when the receiving node is the origin.
+        return_path: Vec<String>,
+    },
+}
+
+/// A unit of relay traffic between two nodes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayFrame {
+    /// Frame ID.
+    pub id: Uuid,
+
+    /// Routing metadata.
+    pub header: RelayHeader,
+
+    /// Contents.
+    pub payload: RelayPayload,
+}
+
+/// A frame as sent to a peer's inbound endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayRequest {
+    /// Name of the sending node.
+    pub from: String,
+
+    /// Secret the sender shares with the receiving node.
+    pub secret: String,
+
+    /// The frame.
+    pub frame: RelayFrame,
+}
+
+/// Delivers relay requests to peers.
+#[async_trait]
+pub trait RelayTransport: Send + Sync {
+    /// Send a request to a peer's inbound endpoint.
+    ///
+    /// Ok means the peer accepted the frame, even if it then dropped it.
+    async fn send(&self, peer: &RelayPeer, request: &RelayRequest) -> CretoResult<()>;
+}
+
+/// Where a submitted envelope went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayRoute {
+    /// Stored for local delivery.
+    Local,
+    /// Queued for a peer.
+    Relayed {
+        /// The peer.
+        peer: String,
+    },
+}
+
+/// What an inbound frame led to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InboundOutcome {
+    /// The envelope was stored for local delivery.
+    Stored,
+    /// The frame was queued for another node.
+    Forwarded {
+        /// The next node.
+        next_hop: String,
+    },
+    /// The envelope was already seen recently.
+    Duplicate,
+    /// This node already appears in the frame's via list.
+    DroppedLoop,
+    /// The frame exceeded the hop limit.
+    DroppedHopLimit,
+    /// A receipt reached the origin of its envelope.
+    ReceiptDelivered,
+}
+
+/// Result of a flush.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlushReport {
+    /// Frames the peer accepted.
+    pub sent: usize,
+    /// Frames that failed every attempt and stay queued.
+    pub failed: usize,
+    /// Frames left queued because their peer's circuit is open.
+    pub deferred: usize,
+}
+
+/// A frame waiting to be forwarded to a peer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedRelayFrame {
+    /// Peer the frame is for.
+    pub peer: String,
+
+    /// The frame.
+    pub frame: RelayFrame,
+}
+
+/// Where to send a relayed envelope's receipt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnRoute {
+    /// Reverse of the envelope's via list: the previous node first.
+    pub path: Vec<String>,
+
+    /// Receipt to send once the envelope is delivered.
+    pub receipt: DeliveryReceipt,
+}
+
+/// In-memory relay queue for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryRelayQueueRepository {
+    queue: Mutex<VecDeque<QueuedRelayFrame>>,
+    return_routes: Mutex<HashMap<Uuid, ReturnRoute>>,
+}
+
+impl InMemoryRelayQueueRepository {
+    /// Create an empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RelayQueueRepository for InMemoryRelayQueueRepository {
+    async fn enqueue(&self, queued: &QueuedRelayFrame) -> CretoResult<()> {
+        self.queue.lock().unwrap().push_back(queued.clone());
+        Ok(())
+    }
+
+    async fn pending(&self) -> CretoResult<Vec<QueuedRelayFrame>> {
+        Ok(self.queue.lock().unwrap().iter().cloned().collect())
+    }
+
+    async fn remove(&self, frame_id: Uuid) -> CretoResult<()> {
+        self.queue
+            .lock()
+            .unwrap()
+            .retain(|q| q.frame.id != frame_id);
+        Ok(())
+    }
+
+    async fn queued(&self) -> CretoResult<usize> {
+        Ok(self.queue.lock().unwrap().len())
+    }
+
+    async fn put_return_route(&self, envelope_id: Uuid, route: &ReturnRoute) -> CretoResult<()> {
+        self.return_routes
+            .lock()
+            .unwrap()
+            .insert(envelope_id, route.clone());
+        Ok(())
+    }
+
+    async fn take_return_route(&self, envelope_id: Uuid) -> CretoResult<Option<ReturnRoute>> {
+        Ok(self.return_routes.lock().unwrap().remove(&envelope_id))
+    }
+}
+
+/// Bounded set of recently seen envelope IDs.
+#[derive(Debug, Default)]
+struct RecentIds {
+    ids: HashSet<Uuid>,
+    order: VecDeque<Uuid>,
+}
+
+impl RecentIds {
+    /// Remember an ID, returning `false` if it was already remembered.
+    fn insert(&mut self, id: Uuid, capacity: usize) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        while self.order.len() > capacity.max(1) {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// Forget an ID, so the envelope is accepted again.
+    fn remove(&mut self, id: Uuid) {
+        if self.ids.remove(&id) {
+            self.order.retain(|&seen| seen != id);
+        }
+    }
+}
+
+/// Relays envelopes to and from the nodes of other regions.
+pub struct EnvelopeRelay {
+    config: RelayConfig,
+    directory: Arc<dyn RecipientDirectory>,
+    envelopes: Arc<dyn EnvelopeRepository>,
+    transport: Arc<dyn RelayTransport>,
+    queue: Arc<dyn RelayQueueRepository>,
+    /// Held for the length of a flush, so a frame is never sent by two
+    /// flushes at once.
+    flushing: tokio::sync::Mutex<()>,
+    circuits: Mutex<HashMap<String, Circuit>>,
+    receipts: Mutex<HashMap<Uuid, DeliveryReceipt>>,
+    recent: Mutex<RecentIds>,
+}
+
+impl EnvelopeRelay {
+    /// Create a relay storing local envelopes in `envelopes`, with an
+    /// in-memory outbound queue.
+    pub fn new(
+        config: RelayConfig,
+        directory: Arc<dyn RecipientDirectory>,
+        envelopes: Arc<dyn EnvelopeRepository>,
+        transport: Arc<dyn RelayTransport>,
+    ) -> Self {
+        Self {
+            config,
+            directory,
+            envelopes,
+            transport,
+            queue: Arc::new(InMemoryRelayQueueRepository::new()),
+            flushing: tokio::sync::Mutex::new(()),
+            circuits: Mutex::new(HashMap::new()),
+            receipts: Mutex::new(HashMap::new()),
+            recent: Mutex::new(RecentIds::default()),
+        }
+    }
+
+    /// Keep the outbound queue and return routes in `queue`.
+    pub fn with_queue(mut self, queue: Arc<dyn RelayQueueRepository>) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// This node's name.
+    pub fn node(&self) -> &str {
+        &self.config.node
+    }
+
+    /// Store an envelope locally or queue it for the recipient's home node.
+    pub async fn submit(&self, envelope: &Envelope) -> CretoResult<RelayRoute> {
+        match self.next_hop(envelope.header.recipient_id).await? {
+            None => {
+                self.envelopes.store(envelope).await?;
+                Ok(RelayRoute::Local)
+            }
+            Some(peer) => {
+                self.enqueue(
+                    &peer,
+                    RelayHeader {
+                        origin: self.config.node.clone(),
+                        via: Vec::new(),
+                        hops: 0,
+                    },
+                    RelayPayload::Envelope {
+                        envelope: envelope.clone(),
+                    },
+                )
+                .await?;
+                self.recent
+                    .lock()
+                    .unwrap()
+                    .insert(envelope.id, self.config.dedup_capacity);
+                Ok(RelayRoute::Relayed { peer })
+            }
+        }
+    }
+
+    /// Handle a frame sent to this node's inbound endpoint.
+    ///
+    /// Fails with [`CretoError::Unauthorized`] if the sender is not a
+    /// configured peer or presents the wrong secret. An envelope whose
+    /// handling fails is not remembered as seen, so the sender's retry is
+    /// accepted.
+    pub async fn handle_inbound(&self, request: RelayRequest) -> CretoResult<InboundOutcome> {
+        let peer = self.config.peer(&request.from).ok_or_else(|| {
+            CretoError::Unauthorized(format!("Unknown relay peer {}", request.from))
+        })?;
+        if !secrets_match(&peer.shared_secret, &request.secret) {
+            return Err(CretoError::Unauthorized(format!(
+                "Invalid secret from relay peer {}",
+                request.from
+            )));
+        }
+
+        let RelayFrame {
+            id: frame_id,
+            header,
+            payload,
+        } = request.frame;
+        if header.via.contains(&self.config.node) {
+            tracing::warn!(%frame_id, via = ?header.via, "Dropping relay frame that looped back");
+            return Ok(InboundOutcome::DroppedLoop);
+        }
+        if header.hops > self.config.max_hops {
+            tracing::warn!(%frame_id, hops = header.hops, "Dropping relay frame over the hop limit");
+            return Ok(InboundOutcome::DroppedHopLimit);
+        }
+
+        match payload {
+            RelayPayload::Envelope { envelope } => {
+                if !self
+                    .recent
+                    .lock()
+                    .unwrap()
+                    .insert(envelope.id, self.config.dedup_capacity)
+                {
+                    return Ok(InboundOutcome::Duplicate);
+                }
+
+                let envelope_id = envelope.id;
+                let outcome = self.accept_envelope(header, envelope).await;
+                if outcome.is_err() {
+                    self.recent.lock().unwrap().remove(envelope_id);
+                }
+                outcome
+            }
+            RelayPayload::Receipt {
+                receipt,
+                mut return_path,
+            } => {
+                if return_path.is_empty() {
+                    self.receipts
+                        .lock()
+                        .unwrap()
+                        .insert(receipt.message_id, receipt);
+                    return Ok(InboundOutcome::ReceiptDelivered);
+                }
+                let next_hop = return_path.remove(0);
+                self.enqueue(
+                    &next_hop,
+                    header,
+                    RelayPayload::Receipt {
+                        receipt,
+                        return_path,
+                    },
+                )
+                .await?;
+                Ok(InboundOutcome::Forwarded { next_hop })
+            }
+        }
+    }
+
+    /// Store an inbound envelope locally or queue it onward.
+    async fn accept_envelope(
+        &self,
+        header: RelayHeader,
+        envelope: Envelope,
+    ) -> CretoResult<InboundOutcome> {
+        match self.next_hop(envelope.header.recipient_id).await? {
+            None => {
+                let route = ReturnRoute {
+                    path: header.via.iter().rev().cloned().collect(),
+                    receipt: DeliveryReceipt::delivered_for(&envelope),
+                };
+                self.queue.put_return_route(envelope.id, &route).await?;
+                if let Err(e) = self.envelopes.store(&envelope).await {
+                    if let Err(e) = self.queue.take_return_route(envelope.id).await {
+                        tracing::warn!(envelope_id = %envelope.id, error = %e, "Failed to drop return route");
+                    }
+                    return Err(e);
+                }
+                Ok(InboundOutcome::Stored)
+            }
+            Some(next_hop) => {
+                self.enqueue(&next_hop, header, RelayPayload::Envelope { envelope })
+                    .await?;
+                Ok(InboundOutcome::Forwarded { next_hop })
+            }
+        }
+    }
+
+    /// Record that a locally stored envelope reached its recipient.
+    ///
+    /// Marks it delivered and, if it was relayed here, queues its receipt
+    /// back along the envelope's path.
+    pub async fn confirm_delivery(&self, envelope_id: Uuid) -> CretoResult<()> {
+        self.envelopes.mark_delivered(envelope_id).await?;
+
+        let Some(route) = self.queue.take_return_route(envelope_id).await? else {
+            return Ok(());
+        };
+        let mut receipt = route.receipt;
+        receipt.timestamp = Utc::now();
+
+        let mut path = route.path.into_iter();
+        if let Some(next_hop) = path.next() {
+            self.enqueue(
+                &next_hop,
+                RelayHeader {
+                    origin: self.config.node.clone(),
+                    via: Vec::new(),
+                    hops: 0,
+                },
+                RelayPayload::Receipt {
+                    receipt,
+                    return_path: path.collect(),
+                },
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Receipt relayed back for an envelope submitted on this node.
+    pub fn receipt(&self, message_id: Uuid) -> Option<DeliveryReceipt> {
+        self.receipts.lock().unwrap().get(&message_id).cloned()
+    }
+
+    /// Frames waiting to be forwarded.
+    pub async fn queued(&self) -> CretoResult<usize> {
+        self.queue.queued().await
+    }
+
+    /// Forward every queued frame whose peer's circuit allows it.
+    ///
+    /// Frames that cannot be forwarded stay queued, in order.
+    pub async fn flush(&self) -> CretoResult<FlushReport> {
+        let _flushing = self.flushing.lock().await;
+        let mut report = FlushReport::default();
+
+        for queued in self.queue.pending().await? {
+            if self.circuit_state(&queued.peer) == CircuitState::Open {
+                report.deferred += 1;
+                continue;
+            }
+            match self.forward(&queued).await {
+                Ok(()) => {
+                    self.queue.remove(queued.frame.id).await?;
+                    report.sent += 1;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        peer = %queued.peer,
+                        frame_id = %queued.frame.id,
+                        error = %e,
+                        "Relay forward failed"
+                    );
+                    report.failed += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Spawn a background task flushing the queue every `interval`.
+    ///
+    /// The task is registered with `shutdown` and stops between flushes.
+    pub fn spawn_flusher(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let relay = Arc::clone(self);
+        shutdown.spawn("messaging.relay", move |guard| {
+            guard.run_periodic(interval, move || {
+                let relay = Arc::clone(&relay);
+                async move {
+                    if let Err(e) = relay.flush().await {
+                        tracing::warn!(error = %e, "Relay flush failed");
+                    }
+                }
+            })
+        })
+    }
+
+    /// Get the state of a peer's circuit.
+    pub fn circuit_state(&self, peer: &str) -> CircuitState {
+        let circuits = self.circuits.lock().unwrap();
+        match circuits.get(peer).and_then(|c| c.opened_at) {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.config.circuit_breaker.open_for => {
+                CircuitState::Open
+            }
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// The peer to relay to for `recipient_id`, or `None` if it is homed
+    /// here.
+    async fn next_hop(&self, recipient_id: AgentId) -> CretoResult<Option<String>> {
+        match self.directory.home_of(recipient_id).await? {
+            None => Ok(None),
+            Some(home) if home == self.config.node => Ok(None),
+            Some(home) if self.config.peer(&home).is_some() => Ok(Some(home)),
+            Some(home) => Err(CretoError::ChannelError(format!(
+                "No relay peer for region {} of agent {}",
+                home, recipient_id
+            ))),
+        }
+    }
+
+    async fn enqueue(
+        &self,
+        peer: &str,
+        header: RelayHeader,
+        payload: RelayPayload,
+    ) -> CretoResult<()> {
+        self.queue
+            .enqueue(&QueuedRelayFrame {
+                peer: peer.to_string(),
+                frame: RelayFrame {
+                    id: Uuid::now_v7(),
+                    header,
+                    payload,
+                },
+            })
+            .await
+    }
+
+    /// Send a frame, retrying per the retry policy while the circuit allows.
+    async fn forward(&self, queued: &QueuedRelayFrame) -> CretoResult<()> {
+        let peer = self.config.peer(&queued.peer).ok_or_else(|| {
+            CretoError::ChannelError(format!("Relay peer {} is not configured", queued.peer))
+        })?;
+
+        let mut frame = queued.frame.clone();
+        frame.header.via.push(self.config.node.clone());
+        frame.header.hops = frame.header.hops.saturating_add(1);
+        let request = RelayRequest {
+            from: self.config.node.clone(),
+            secret: peer.shared_secret.clone(),
+            frame,
+        };
+
+        let retry = &self.config.retry;
+        let mut last_error = None;
+        for attempt in 0..retry.max_attempts.max(1) {
+            if attempt > 0 {
+                tokio::time::sleep(Duration::from_millis(retry.backoff_ms(attempt))).await;
+                if self.circuit_state(&peer.name) == CircuitState::Open {
+                    break;
+                }
+            }
+            match self.transport.send(peer, &request).await {
+                Ok(()) => {
+                    self.circuits.lock().unwrap().remove(&peer.name);
+                    return Ok(());
+                }
+                Err(e) => {
+                    self.record_failure(&peer.name);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            CretoError::ChannelError(format!("Relay peer {} circuit is open", peer.name))
+        }))
+    }
+
+    fn record_failure(&self, peer: &str) {
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(peer.to_string()).or_default();
+        circuit.consecutive_failures += 1;
+        if circuit.consecutive_failures >= self.config.circuit_breaker.failure_threshold {
+            if circuit.opened_at.is_none() {
+                tracing::warn!(%peer, "Relay peer circuit opened");
+            }
+            circuit.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// Compare secrets without exiting early on the first differing byte.
+fn secrets_match(expected: &str, presented: &str) -> bool {
+    let (expected, presented) = (expected.as_bytes(), presented.as_bytes());
+    expected.len() == presented.len()
+        && expected
+            .iter()
+            .zip(presented)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::envelope::ReceiptType;
+    use crate::mailbox::InMemoryEnvelopeRepository;
+    use crate::ratchet::MessageHeader;
+
+    const EAST: &str = "us-east";
+    const WEST: &str = "eu-west";
+
+    /// Hands requests straight to the relay registered for the endpoint.
+    #[derive(Default)]
+    struct InProcessTransport {
+        nodes: RwLock<HashMap<String, Arc<EnvelopeRelay>>>,
+        delivered: Mutex<Vec<(RelayRequest, InboundOutcome)>>,
+    }
+
+    impl InProcessTransport {
+        fn outcomes(&self) -> Vec<InboundOutcome> {
+            let delivered = self.delivered.lock().unwrap();
+            delivered
+                .iter()
+                .map(|(_, outcome)| outcome.clone())
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl RelayTransport for InProcessTransport {
+        async fn send(&self, peer: &RelayPeer, request: &RelayRequest) -> CretoResult<()> {
+            let node = self.nodes.read().unwrap().get(&peer.endpoint).cloned();
+            let node = node.ok_or_else(|| CretoError::ChannelError("unreachable".into()))?;
+            let outcome = node.handle_inbound(request.clone()).await?;
+            self.delivered
+                .lock()
+                .unwrap()
+                .push((request.clone(), outcome));
+            Ok(())
+        }
+    }
+
+    struct Region {
+        relay: Arc<EnvelopeRelay>,
+        directory: Arc<InMemoryRecipientDirectory>,
+        mailbox: Arc<InMemoryEnvelopeRepository>,
+    }
+
+    fn region(node: &str, peer: &str, transport: &Arc<InProcessTransport>) -> Region {
+        region_with(
+            node,
+            peer,
+            transport,
+            Arc::new(InMemoryRecipientDirectory::new()),
+            Arc::new(InMemoryEnvelopeRepository::new()),
+            Arc::new(InMemoryRelayQueueRepository::new()),
+        )
+    }
+
+    /// A region node over existing state, as after a restart.
+    fn region_with(
+        node: &str,
+        peer: &str,
+        transport: &Arc<InProcessTransport>,
+        directory: Arc<InMemoryRecipientDirectory>,
+        mailbox: Arc<InMemoryEnvelopeRepository>,
+        queue: Arc<InMemoryRelayQueueRepository>,
+    ) -> Region {
+        let config = RelayConfig::new(node)
+            .with_peer(RelayPeer::new(peer, format!("relay://{peer}"), "s3cret"))
+            .with_retry_policy(RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            });
+        let relay = Arc::new(
+            EnvelopeRelay::new(
+                config,
+                Arc::clone(&directory) as Arc<dyn RecipientDirectory>,
+                Arc::clone(&mailbox) as Arc<dyn EnvelopeRepository>,
+                Arc::clone(transport) as Arc<dyn RelayTransport>,
+            )
+            .with_queue(queue),
+        );
+        transport
+            .nodes
+            .write()
+            .unwrap()
+            .insert(format!("relay://{node}"), Arc::clone(&relay));
+        Region {
+            relay,
+            directory,
+            mailbox,
+        }
+    }
+
+    fn two_regions() -> (Region, Region, Arc<InProcessTransport>) {
+        let transport = Arc::new(InProcessTransport::default());
+        let east = region(EAST, WEST, &transport);
+        let west = region(WEST, EAST, &transport);
+        (east, west, transport)
+    }
+
+    fn envelope(sender: AgentId, recipient: AgentId) -> Envelope {
+        let header = MessageHeader {
+            dh_public: vec![0u8; 32],
+            prev_chain_length: 0,
+            message_number: 1,
+        };
+        Envelope::new(sender, recipient, header, vec![7, 7, 7])
+    }
+
+    #[tokio::test]
+    async fn test_cross_region_delivery_and_receipt_return() {
+        let (east, west, transport) = two_regions();
+        let (sender, recipient) = (AgentId::new(), AgentId::new());
+        for region in [&east, &west] {
+            region.directory.set_home(sender, EAST);
+            region.directory.set_home(recipient, WEST);
+        }
+
+        let sent = envelope(sender, recipient);
+        let route = east.relay.submit(&sent).await.unwrap();
+        assert_eq!(route, RelayRoute::Relayed { peer: WEST.into() });
+        assert_eq!(east.relay.flush().await.unwrap().sent, 1);
+        assert_eq!(transport.outcomes(), vec![InboundOutcome::Stored]);
+
+        let stored = west.mailbox.get_undelivered(recipient, 10).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].id, sent.id);
+        assert_eq!(stored[0].ciphertext, sent.payload.ciphertext);
+        assert!(east
+            .mailbox
+            .get_undelivered(recipient, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // A retried frame is not delivered twice, and a bad secret is refused
+        let (mut replay, _) = transport.delivered.lock().unwrap()[0].clone();
+        assert_eq!(
+            west.relay.handle_inbound(replay.clone()).await.unwrap(),
+            InboundOutcome::Duplicate
+        );
+        replay.secret = "guess".into();
+        assert!(matches!(
+            west.relay.handle_inbound(replay).await,
+            Err(CretoError::Unauthorized(_))
+        ));
+
+        // Delivery on the west sends the receipt back east
+        west.relay.confirm_delivery(sent.id).await.unwrap();
+        assert!(east.relay.receipt(sent.id).is_none());
+        assert_eq!(west.relay.flush().await.unwrap().sent, 1);
+        let receipt = east.relay.receipt(sent.id).unwrap();
+        assert_eq!(receipt.receipt_type, ReceiptType::Delivered);
+        assert_eq!(receipt.caused_by, Some(sent.id));
+        assert_eq!(
+            transport.outcomes().last(),
+            Some(&InboundOutcome::ReceiptDelivered)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_via_list_breaks_routing_loop() {
+        let (east, west, transport) = two_regions();
+        let recipient = AgentId::new();
+        // Each region believes the recipient lives in the other
+        east.directory.set_home(recipient, WEST);
+        west.directory.set_home(recipient, EAST);
+
+        east.relay
+            .submit(&envelope(AgentId::new(), recipient))
+            .await
+            .unwrap();
+        east.relay.flush().await.unwrap();
+        west.relay.flush().await.unwrap();
+
+        assert_eq!(
+            transport.outcomes(),
+            vec![
+                InboundOutcome::Forwarded {
+                    next_hop: EAST.into()
+                },
+                InboundOutcome::DroppedLoop,
+            ]
+        );
+        let (looped, _) = transport.delivered.lock().unwrap()[1].clone();
+        assert_eq!(looped.frame.header.via, vec![EAST, WEST]);
+        assert_eq!(looped.frame.header.hops, 2);
+
+        assert_eq!(
+            east.relay.queued().await.unwrap() + west.relay.queued().await.unwrap(),
+            0
+        );
+        for region in [&east, &west] {
+            let usage = region.mailbox.mailbox_usage(recipient).await.unwrap();
+            assert_eq!(usage.undelivered_count, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_peer_opens_circuit_and_keeps_frames_queued() {
+        let transport = Arc::new(InProcessTransport::default());
+        let east = region(EAST, WEST, &transport);
+        let recipient = AgentId::new();
+        east.directory.set_home(recipient, WEST);
+        for _ in 0..2 {
+            east.relay
+                .submit(&envelope(AgentId::new(), recipient))
+                .await
+                .unwrap();
+        }
+
+        // The west node is not registered, so every attempt fails
+        let mut failures = 0;
+        while east.relay.circuit_state(WEST) != CircuitState::Open {
+            failures += east.relay.flush().await.unwrap().failed;
+        }
+        let threshold = CircuitBreakerConfig::default().failure_threshold;
+        assert_eq!(failures, threshold as usize);
+
+        let report = east.relay.flush().await.unwrap();
+        assert_eq!((report.sent, report.deferred), (0, 2));
+        assert_eq!(east.relay.queued().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_inbound_envelope_is_accepted_on_retry() {
+        let (east, west, transport) = two_regions();
+        let recipient = AgentId::new();
+        east.directory.set_home(recipient, WEST);
+        // The west node cannot route the recipient yet
+        west.directory.set_home(recipient, "ap-south");
+
+        let sent = envelope(AgentId::new(), recipient);
+        east.relay.submit(&sent).await.unwrap();
+        assert_eq!(east.relay.flush().await.unwrap().failed, 1);
+        assert_eq!(east.relay.queued().await.unwrap(), 1);
+
+        west.directory.set_home(recipient, WEST);
+        assert_eq!(east.relay.flush().await.unwrap().sent, 1);
+        assert_eq!(transport.outcomes(), vec![InboundOutcome::Stored]);
+        let stored = west.mailbox.get_undelivered(recipient, 10).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].id, sent.id);
+    }
+
+    #[tokio::test]
+    async fn test_queue_and_return_routes_survive_restart() {
+        let transport = Arc::new(InProcessTransport::default());
+        let east_queue = Arc::new(InMemoryRelayQueueRepository::new());
+        let west_queue = Arc::new(InMemoryRelayQueueRepository::new());
+        let (east_directory, west_directory) = (
+            Arc::new(InMemoryRecipientDirectory::new()),
+            Arc::new(InMemoryRecipientDirectory::new()),
+        );
+        let west_mailbox = Arc::new(InMemoryEnvelopeRepository::new());
+        let restart_east = || {
+            region_with(
+                EAST,
+                WEST,
+                &transport,
+                Arc::clone(&east_directory),
+                Arc::new(InMemoryEnvelopeRepository::new()),
+                Arc::clone(&east_queue),
+            )
+        };
+        let restart_west = || {
+            region_with(
+                WEST,
+                EAST,
+                &transport,
+                Arc::clone(&west_directory),
+                Arc::clone(&west_mailbox),
+                Arc::clone(&west_queue),
+            )
+        };
+        let recipient = AgentId::new();
+        east_directory.set_home(recipient, WEST);
+        west_directory.set_home(recipient, WEST);
+
+        let sent = envelope(AgentId::new(), recipient);
+        restart_east().relay.submit(&sent).await.unwrap();
+
+        // The queued frame is forwarded by the restarted node
+        let east = restart_east();
+        let _west = restart_west();
+        assert_eq!(east.relay.flush().await.unwrap().sent, 1);
+
+        // The restarted west node still knows where the receipt goes
+        let west = restart_west();
+        west.relay.confirm_delivery(sent.id).await.unwrap();
+        assert_eq!(west.relay.flush().await.unwrap().sent, 1);
+        assert_eq!(
+            east.relay.receipt(sent.id).unwrap().receipt_type,
+            ReceiptType::Delivered
+        );
+    }
+}
@@ -5,13 +5,27 @@
 //!
 //! Uses runtime SQL queries to avoid requiring DATABASE_URL at compile time.
 
+use std::sync::Arc;
+use std::time::Duration;
+
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, CretoError, OrganizationId};
+use creto_common::{AgentId, CretoError, OrganizationId, ShutdownCoordinator};
 use sqlx::{PgPool, Row};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+use crate::authz::AuthzFailureMode;
 use crate::channel::ChannelType;
+use crate::envelope::{ContentType, DeliveryReceipt, Envelope, MessagePriority, ReceiptType};
+use crate::mailbox::{MailboxConfig, MailboxOverflowPolicy, MailboxUsage};
+use crate::ratchet_store::EncryptedRatchetState;
+use crate::relay::{QueuedRelayFrame, ReturnRoute};
 use crate::session::SessionState;
+use crate::topic::{
+    RetentionPolicy, Subscription, SubscriptionFilter, SubscriptionId, SubscriptionStart, Topic,
+    TopicId, TopicPolicy,
+};
+use crate::wire;
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Enum Serialization Helpers
@@ -67,6 +81,102 @@ impl ChannelType {
     }
 }
 
+impl MessagePriority {
+    /// Convert to the database ordinal (higher is more urgent).
+    pub fn as_db_i16(&self) -> i16 {
+        match self {
+            MessagePriority::Low => 0,
+            MessagePriority::Normal => 1,
+            MessagePriority::High => 2,
+        }
+    }
+
+    /// Parse from the database ordinal.
+    pub fn from_db_i16(value: i16) -> Self {
+        match value {
+            0 => MessagePriority::Low,
+            2 => MessagePriority::High,
+            _ => MessagePriority::Normal,
+        }
+    }
+}
+
+impl ContentType {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::Text => "text",
+            ContentType::Json => "json",
+            ContentType::Binary => "binary",
+            ContentType::ToolRequest => "tool_request",
+            ContentType::ToolResponse => "tool_response",
+            ContentType::Status => "status",
+            ContentType::Control => "control",
+        }
+    }
+}
+
+impl ReceiptType {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReceiptType::Delivered => "delivered",
+            ReceiptType::Read => "read",
+            ReceiptType::Failed => "failed",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "delivered" => ReceiptType::Delivered,
+            "read" => ReceiptType::Read,
+            _ => ReceiptType::Failed,
+        }
+    }
+}
+
+impl TopicPolicy {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TopicPolicy::Open => "open",
+            TopicPolicy::Private => "private",
+            TopicPolicy::AuthzRequired => "authz_required",
+            TopicPolicy::Allowlist => "allowlist",
+        }
+    }
+
+    /// Parse from database string. Unknown values fall back to the most
+    /// restrictive policy.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "open" => TopicPolicy::Open,
+            "authz_required" => TopicPolicy::AuthzRequired,
+            "allowlist" => TopicPolicy::Allowlist,
+            _ => TopicPolicy::Private,
+        }
+    }
+}
+
+impl AuthzFailureMode {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthzFailureMode::FailClosed => "fail_closed",
+            AuthzFailureMode::FailOpen => "fail_open",
+        }
+    }
+
+    /// Parse from database string. Unknown values fail closed.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "fail_open" => AuthzFailureMode::FailOpen,
+            _ => AuthzFailureMode::FailClosed,
+        }
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Key Bundle Repository
 // ─────────────────────────────────────────────────────────────────────────────
@@ -199,11 +309,31 @@ pub trait PreKeyRepository: Send + Sync {
         public_key: &[u8],
     ) -> Result<(), CretoError>;
 
+    /// Store prekeys under the agent's next unused IDs, continuing after
+    /// the highest ID ever stored so consumed IDs are not reissued. Returns
+    /// the assigned IDs in the order of `public_keys`.
+    async fn store_next(
+        &self,
+        agent_id: AgentId,
+        public_keys: &[Vec<u8>],
+    ) -> Result<Vec<i32>, CretoError>;
+
     /// Consume a prekey (mark as used and return it).
     async fn consume(&self, agent_id: AgentId) -> Result<Option<(i32, Vec<u8>)>, CretoError>;
 
     /// Count available prekeys.
     async fn count_available(&self, agent_id: AgentId) -> Result<i64, CretoError>;
+
+    /// Mark every available prekey of the agent consumed, returning how
+    /// many were. Their IDs stay taken.
+    async fn discard_available(&self, agent_id: AgentId) -> Result<u64, CretoError>;
+
+    /// Agents holding prekeys with fewer than `threshold` still available,
+    /// with their available counts, fewest first.
+    async fn find_agents_below_watermark(
+        &self,
+        threshold: i64,
+    ) -> Result<Vec<(AgentId, i64)>, CretoError>;
 }
 
 /// PostgreSQL implementation of PreKeyRepository.
@@ -242,6 +372,54 @@ impl PreKeyRepository for PgPreKeyRepository {
         Ok(())
     }
 
+    async fn store_next(
+        &self,
+        agent_id: AgentId,
+        public_keys: &[Vec<u8>],
+    ) -> Result<Vec<i32>, CretoError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        // Serialize allocation per agent; row locks cannot cover IDs that
+        // do not exist yet
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1::text, 0))")
+            .bind(agent_id.as_uuid())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let rows = sqlx::query(
+            r#"
+            INSERT INTO prekeys (agent_id, prekey_id, public_key)
+            SELECT $1, base.max_id + k.ord::INTEGER, k.public_key
+            FROM (
+                SELECT COALESCE(MAX(prekey_id), 0) AS max_id
+                FROM prekeys
+                WHERE agent_id = $1
+            ) base
+            CROSS JOIN UNNEST($2::BYTEA[]) WITH ORDINALITY AS k(public_key, ord)
+            RETURNING prekey_id
+            "#,
+        )
+        .bind(agent_id.as_uuid())
+        .bind(public_keys)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        // IDs follow input order; RETURNING does not promise it
+        let mut ids: Vec<i32> = rows.iter().map(|r| r.get("prekey_id")).collect();
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
     async fn consume(&self, agent_id: AgentId) -> Result<Option<(i32, Vec<u8>)>, CretoError> {
         let row = sqlx::query(
             r#"
@@ -285,6 +463,51 @@ impl PreKeyRepository for PgPreKeyRepository {
 
         Ok(row.get("count"))
     }
+
+    async fn discard_available(&self, agent_id: AgentId) -> Result<u64, CretoError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE prekeys
+            SET consumed = true, consumed_at = NOW()
+            WHERE agent_id = $1 AND consumed = false
+            "#,
+        )
+        .bind(agent_id.as_uuid())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn find_agents_below_watermark(
+        &self,
+        threshold: i64,
+    ) -> Result<Vec<(AgentId, i64)>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT agent_id, COUNT(*) FILTER (WHERE NOT consumed) AS available
+            FROM prekeys
+            GROUP BY agent_id
+            HAVING COUNT(*) FILTER (WHERE NOT consumed) < $1
+            ORDER BY available ASC, agent_id ASC
+            "#,
+        )
+        .bind(threshold)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| {
+                (
+                    AgentId::from_uuid(r.get::<Uuid, _>("agent_id")),
+                    r.get::<i64, _>("available"),
+                )
+            })
+            .collect())
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -300,6 +523,9 @@ pub struct SessionRecord {
     pub state: SessionState,
     pub created_at: DateTime<Utc>,
     pub last_active_at: DateTime<Utc>,
+    /// Session ID the encrypted ratchet state is stored under in the
+    /// [`RatchetStateRepository`], once it has been written.
+    pub ratchet_state_id: Option<Uuid>,
 }
 
 /// Repository for messaging session persistence.
@@ -322,6 +548,9 @@ pub trait SessionRepository: Send + Sync {
     /// Update session state.
     async fn update_state(&self, id: Uuid, state: SessionState) -> Result<(), CretoError>;
 
+    /// Point a session at the ratchet state stored under `ratchet_state_id`.
+    async fn link_ratchet_state(&self, id: Uuid, ratchet_state_id: Uuid) -> Result<(), CretoError>;
+
     /// List active sessions for an agent.
     async fn list_active(&self, agent_id: AgentId) -> Result<Vec<SessionRecord>, CretoError>;
 }
@@ -369,7 +598,7 @@ impl SessionRepository for PgSessionRepository {
     ) -> Result<Option<SessionRecord>, CretoError> {
         let row = sqlx::query(
             r#"
-            SELECT id, state, created_at, last_active_at
+            SELECT id, state, created_at, last_active_at, ratchet_state_id
             FROM messaging_sessions
             WHERE local_agent_id = $1 AND remote_agent_id = $2
             "#,
@@ -387,6 +616,7 @@ impl SessionRepository for PgSessionRepository {
             state: SessionState::parse_db_str(r.get::<&str, _>("state")),
             created_at: r.get("created_at"),
             last_active_at: r.get("last_active_at"),
+            ratchet_state_id: r.get("ratchet_state_id"),
         }))
     }
 
@@ -407,10 +637,28 @@ impl SessionRepository for PgSessionRepository {
         Ok(())
     }
 
+    async fn link_ratchet_state(&self, id: Uuid, ratchet_state_id: Uuid) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            UPDATE messaging_sessions
+            SET ratchet_state_id = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(ratchet_state_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
     async fn list_active(&self, agent_id: AgentId) -> Result<Vec<SessionRecord>, CretoError> {
         let rows = sqlx::query(
             r#"
-            SELECT id, local_agent_id, remote_agent_id, state, created_at, last_active_at
+            SELECT id, local_agent_id, remote_agent_id, state, created_at, last_active_at,
+                   ratchet_state_id
             FROM messaging_sessions
             WHERE (local_agent_id = $1 OR remote_agent_id = $1)
               AND state IN ('establishing', 'active')
@@ -431,210 +679,1075 @@ impl SessionRepository for PgSessionRepository {
                 state: SessionState::parse_db_str(r.get::<&str, _>("state")),
                 created_at: r.get("created_at"),
                 last_active_at: r.get("last_active_at"),
+                ratchet_state_id: r.get("ratchet_state_id"),
             })
             .collect())
     }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
-// Envelope Repository
+// Ratchet State Repository
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// Simplified envelope record.
-#[derive(Debug, Clone)]
-pub struct EnvelopeRecord {
-    pub id: Uuid,
-    pub sender_id: AgentId,
-    pub recipient_id: AgentId,
-    pub ciphertext: Vec<u8>,
-    pub delivered: bool,
-    pub created_at: DateTime<Utc>,
-}
-
-/// Repository for message envelope persistence (store-and-forward).
+/// Repository for encrypted ratchet state, keyed by session ID.
+///
+/// Implementations only ever see ciphertext; sealing and opening happen in
+/// [`StateEncryptionKey`](crate::ratchet_store::StateEncryptionKey).
 #[async_trait::async_trait]
-pub trait EnvelopeRepository: Send + Sync {
-    /// Store a message envelope.
-    async fn store(
-        &self,
-        sender_id: AgentId,
-        recipient_id: AgentId,
-        ciphertext: &[u8],
-        dh_public: &[u8],
-        mac: &[u8],
-    ) -> Result<Uuid, CretoError>;
-
-    /// Get undelivered envelopes for a recipient.
-    async fn get_undelivered(
-        &self,
-        recipient_id: AgentId,
-        limit: i64,
-    ) -> Result<Vec<EnvelopeRecord>, CretoError>;
+pub trait RatchetStateRepository: Send + Sync {
+    /// Store a session's state, replacing any earlier write.
+    async fn save(&self, state: &EncryptedRatchetState) -> Result<(), CretoError>;
 
-    /// Mark envelope as delivered.
-    async fn mark_delivered(&self, id: Uuid) -> Result<(), CretoError>;
+    /// Load a session's state.
+    async fn load(&self, session_id: Uuid) -> Result<Option<EncryptedRatchetState>, CretoError>;
 
-    /// Delete expired envelopes.
-    async fn cleanup_expired(&self) -> Result<i64, CretoError>;
+    /// Delete a session's state.
+    async fn delete(&self, session_id: Uuid) -> Result<(), CretoError>;
 }
 
-/// PostgreSQL implementation of EnvelopeRepository.
-pub struct PgEnvelopeRepository {
+/// PostgreSQL implementation of RatchetStateRepository.
+pub struct PgRatchetStateRepository {
     pool: PgPool,
 }
 
-impl PgEnvelopeRepository {
+impl PgRatchetStateRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
 }
 
 #[async_trait::async_trait]
-impl EnvelopeRepository for PgEnvelopeRepository {
-    async fn store(
-        &self,
-        sender_id: AgentId,
-        recipient_id: AgentId,
-        ciphertext: &[u8],
-        dh_public: &[u8],
-        mac: &[u8],
-    ) -> Result<Uuid, CretoError> {
-        let row = sqlx::query(
+impl RatchetStateRepository for PgRatchetStateRepository {
+    async fn save(&self, state: &EncryptedRatchetState) -> Result<(), CretoError> {
+        sqlx::query(
             r#"
-            INSERT INTO message_envelopes (
-                sender_id, recipient_id, envelope_version, content_type,
-                dh_public, prev_chain_length, message_number,
-                ciphertext, mac
-            ) VALUES ($1, $2, 1, 'message', $3, 0, 0, $4, $5)
-            RETURNING id
+            INSERT INTO messaging_ratchet_states (session_id, nonce, ciphertext, updated_at)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (session_id) DO UPDATE SET
+                nonce = EXCLUDED.nonce,
+                ciphertext = EXCLUDED.ciphertext,
+                updated_at = EXCLUDED.updated_at
             "#,
         )
-        .bind(sender_id.as_uuid())
-        .bind(recipient_id.as_uuid())
-        .bind(dh_public)
-        .bind(ciphertext)
-        .bind(mac)
-        .fetch_one(&self.pool)
+        .bind(state.session_id)
+        .bind(&state.nonce)
+        .bind(&state.ciphertext)
+        .bind(state.updated_at)
+        .execute(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(row.get("id"))
+        Ok(())
     }
 
-    async fn get_undelivered(
-        &self,
-        recipient_id: AgentId,
-        limit: i64,
-    ) -> Result<Vec<EnvelopeRecord>, CretoError> {
-        let rows = sqlx::query(
+    async fn load(&self, session_id: Uuid) -> Result<Option<EncryptedRatchetState>, CretoError> {
+        let row = sqlx::query(
             r#"
-            SELECT id, sender_id, ciphertext, delivered, created_at
-            FROM message_envelopes
-            WHERE recipient_id = $1
-              AND delivered = false
-              AND (expires_at IS NULL OR expires_at > NOW())
-            ORDER BY created_at ASC
-            LIMIT $2
+            SELECT nonce, ciphertext, updated_at
+            FROM messaging_ratchet_states
+            WHERE session_id = $1
             "#,
         )
-        .bind(recipient_id.as_uuid())
-        .bind(limit)
-        .fetch_all(&self.pool)
+        .bind(session_id)
+        .fetch_optional(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(rows
-            .into_iter()
-            .map(|r| EnvelopeRecord {
-                id: r.get("id"),
-                sender_id: AgentId::from_uuid(r.get::<Uuid, _>("sender_id")),
-                recipient_id,
-                ciphertext: r.get("ciphertext"),
-                delivered: r.get("delivered"),
-                created_at: r.get("created_at"),
-            })
-            .collect())
+        Ok(row.map(|r| EncryptedRatchetState {
+            session_id,
+            nonce: r.get("nonce"),
+            ciphertext: r.get("ciphertext"),
+            updated_at: r.get("updated_at"),
+        }))
     }
 
-    async fn mark_delivered(&self, id: Uuid) -> Result<(), CretoError> {
-        sqlx::query(
-            r#"
-            UPDATE message_envelopes
-            SET delivered = true, delivered_at = NOW()
-            WHERE id = $1
-            "#,
-        )
-        .bind(id)
-        .execute(&self.pool)
-        .await
-        .map_err(|e| CretoError::Database(e.to_string()))?;
+    async fn delete(&self, session_id: Uuid) -> Result<(), CretoError> {
+        sqlx::query("DELETE FROM messaging_ratchet_states WHERE session_id = $1")
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
 
         Ok(())
     }
-
-    async fn cleanup_expired(&self) -> Result<i64, CretoError> {
-        let result = sqlx::query(
-            r#"
-            DELETE FROM message_envelopes
-            WHERE expires_at < NOW() AND delivered = false
-            "#,
-        )
-        .execute(&self.pool)
-        .await
-        .map_err(|e| CretoError::Database(e.to_string()))?;
-
-        Ok(result.rows_affected() as i64)
-    }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
-// Channel Repository
+// Relay Queue Repository
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// Simplified channel record.
-#[derive(Debug, Clone)]
-pub struct ChannelRecord {
-    pub id: Uuid,
-    pub organization_id: OrganizationId,
-    pub channel_type: ChannelType,
-    pub name: String,
-    pub active: bool,
-}
-
-/// Repository for messaging channel configuration.
+/// Repository for a relay's outbound frames and the return routes of
+/// envelopes relayed to it.
 #[async_trait::async_trait]
-pub trait ChannelRepository: Send + Sync {
-    /// Create a channel.
-    async fn create(
-        &self,
-        org_id: OrganizationId,
-        channel_type: ChannelType,
-        name: &str,
-    ) -> Result<Uuid, CretoError>;
+pub trait RelayQueueRepository: Send + Sync {
+    /// Append a frame to the queue.
+    async fn enqueue(&self, queued: &QueuedRelayFrame) -> Result<(), CretoError>;
 
-    /// List active channels for an organization.
-    async fn list_active(&self, org_id: OrganizationId) -> Result<Vec<ChannelRecord>, CretoError>;
+    /// Queued frames, in the order they were queued.
+    async fn pending(&self) -> Result<Vec<QueuedRelayFrame>, CretoError>;
 
-    /// Deactivate a channel.
-    async fn deactivate(&self, id: Uuid) -> Result<(), CretoError>;
+    /// Remove a forwarded frame.
+    async fn remove(&self, frame_id: Uuid) -> Result<(), CretoError>;
+
+    /// Number of queued frames.
+    async fn queued(&self) -> Result<usize, CretoError>;
+
+    /// Store where to send an envelope's receipt once it is delivered.
+    async fn put_return_route(
+        &self,
+        envelope_id: Uuid,
+        route: &ReturnRoute,
+    ) -> Result<(), CretoError>;
+
+    /// Remove and return an envelope's return route.
+    async fn take_return_route(&self, envelope_id: Uuid)
+        -> Result<Option<ReturnRoute>, CretoError>;
 }
 
-/// PostgreSQL implementation of ChannelRepository.
-pub struct PgChannelRepository {
+/// PostgreSQL implementation of RelayQueueRepository.
+///
+/// Each relay node needs its own `node` name, which scopes its rows.
+pub struct PgRelayQueueRepository {
     pool: PgPool,
+    node: String,
 }
 
-impl PgChannelRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+impl PgRelayQueueRepository {
+    pub fn new(pool: PgPool, node: impl Into<String>) -> Self {
+        Self {
+            pool,
+            node: node.into(),
+        }
     }
 }
 
 #[async_trait::async_trait]
-impl ChannelRepository for PgChannelRepository {
-    async fn create(
-        &self,
-        org_id: OrganizationId,
+impl RelayQueueRepository for PgRelayQueueRepository {
+    async fn enqueue(&self, queued: &QueuedRelayFrame) -> Result<(), CretoError> {
+        let frame = serde_json::to_value(&queued.frame)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO messaging_relay_queue (frame_id, node, peer, frame)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (frame_id) DO NOTHING
+            "#,
+        )
+        .bind(queued.frame.id)
+        .bind(&self.node)
+        .bind(&queued.peer)
+        .bind(&frame)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn pending(&self) -> Result<Vec<QueuedRelayFrame>, CretoError> {
+        let rows = sqlx::query(
+            "SELECT peer, frame FROM messaging_relay_queue WHERE node = $1 ORDER BY seq",
+        )
+        .bind(&self.node)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter()
+            .map(|r| {
+                Ok(QueuedRelayFrame {
+                    peer: r.get("peer"),
+                    frame: serde_json::from_value(r.get("frame"))
+                        .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                })
+            })
+            .collect()
+    }
+
+    async fn remove(&self, frame_id: Uuid) -> Result<(), CretoError> {
+        sqlx::query("DELETE FROM messaging_relay_queue WHERE frame_id = $1 AND node = $2")
+            .bind(frame_id)
+            .bind(&self.node)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn queued(&self) -> Result<usize, CretoError> {
+        let row =
+            sqlx::query("SELECT COUNT(*) as count FROM messaging_relay_queue WHERE node = $1")
+                .bind(&self.node)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.get::<i64, _>("count") as usize)
+    }
+
+    async fn put_return_route(
+        &self,
+        envelope_id: Uuid,
+        route: &ReturnRoute,
+    ) -> Result<(), CretoError> {
+        let route = serde_json::to_value(route)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO messaging_relay_return_routes (envelope_id, node, route)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (envelope_id, node) DO UPDATE SET route = EXCLUDED.route
+            "#,
+        )
+        .bind(envelope_id)
+        .bind(&self.node)
+        .bind(&route)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn take_return_route(
+        &self,
+        envelope_id: Uuid,
+    ) -> Result<Option<ReturnRoute>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            DELETE FROM messaging_relay_return_routes
+            WHERE envelope_id = $1 AND node = $2
+            RETURNING route
+            "#,
+        )
+        .bind(envelope_id)
+        .bind(&self.node)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| {
+            serde_json::from_value(r.get("route"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))
+        })
+        .transpose()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Topic Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for pub/sub topics, including their policies and retention.
+#[async_trait::async_trait]
+pub trait TopicRepository: Send + Sync {
+    /// Store a new topic. Fails if its owner already has a topic with the
+    /// same name.
+    async fn create(&self, topic: &Topic) -> Result<(), CretoError>;
+
+    /// Get a topic by owner and name.
+    async fn find_by_name(
+        &self,
+        owner_agent_id: AgentId,
+        name: &str,
+    ) -> Result<Option<Topic>, CretoError>;
+
+    /// List every topic.
+    async fn list(&self) -> Result<Vec<Topic>, CretoError>;
+
+    /// Delete a topic and its subscriptions.
+    async fn delete(&self, topic_id: TopicId) -> Result<(), CretoError>;
+}
+
+/// Repository for topic subscriptions.
+#[async_trait::async_trait]
+pub trait SubscriptionRepository: Send + Sync {
+    /// Store a new subscription.
+    async fn create(&self, subscription: &Subscription) -> Result<(), CretoError>;
+
+    /// List every subscription, oldest first.
+    async fn list(&self) -> Result<Vec<Subscription>, CretoError>;
+
+    /// Delete a subscription.
+    async fn delete(&self, subscription_id: SubscriptionId) -> Result<(), CretoError>;
+
+    /// Delete every subscription to a topic.
+    async fn delete_for_topic(&self, topic_id: TopicId) -> Result<(), CretoError>;
+}
+
+/// PostgreSQL implementation of TopicRepository.
+pub struct PgTopicRepository {
+    pool: PgPool,
+}
+
+impl PgTopicRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+const TOPIC_COLUMNS: &str = "id, name, owner_agent_id, publish_policy, subscribe_policy, \
+    retention_max_messages, retention_ttl_seconds, max_message_size, max_subscribers, \
+    allowed_agents, replay_allowed, authz_failure_mode, created_at, updated_at";
+
+fn topic_from_row(r: &sqlx::postgres::PgRow) -> Topic {
+    Topic {
+        id: r.get("id"),
+        name: r.get("name"),
+        owner_agent_id: AgentId::from_uuid(r.get::<Uuid, _>("owner_agent_id")),
+        publish_policy: TopicPolicy::parse_db_str(r.get::<&str, _>("publish_policy")),
+        subscribe_policy: TopicPolicy::parse_db_str(r.get::<&str, _>("subscribe_policy")),
+        retention: RetentionPolicy {
+            max_messages: r
+                .get::<Option<i32>, _>("retention_max_messages")
+                .map(|n| n as u32),
+            ttl_seconds: r
+                .get::<Option<i64>, _>("retention_ttl_seconds")
+                .map(|n| n as u64),
+        },
+        max_message_size: r.get::<i64, _>("max_message_size") as usize,
+        max_subscribers: r.get::<Option<i32>, _>("max_subscribers").map(|n| n as u32),
+        allowed_agents: r
+            .get::<Vec<Uuid>, _>("allowed_agents")
+            .into_iter()
+            .map(AgentId::from_uuid)
+            .collect(),
+        replay_allowed: r.get("replay_allowed"),
+        authz_failure_mode: AuthzFailureMode::parse_db_str(r.get::<&str, _>("authz_failure_mode")),
+        created_at: r.get("created_at"),
+        updated_at: r.get("updated_at"),
+    }
+}
+
+#[async_trait::async_trait]
+impl TopicRepository for PgTopicRepository {
+    async fn create(&self, topic: &Topic) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            INSERT INTO messaging_topics (
+                id, name, owner_agent_id, publish_policy, subscribe_policy,
+                retention_max_messages, retention_ttl_seconds, max_message_size,
+                max_subscribers, allowed_agents, replay_allowed, authz_failure_mode,
+                created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            "#,
+        )
+        .bind(topic.id)
+        .bind(&topic.name)
+        .bind(topic.owner_agent_id.as_uuid())
+        .bind(topic.publish_policy.as_str())
+        .bind(topic.subscribe_policy.as_str())
+        .bind(topic.retention.max_messages.map(|n| n as i32))
+        .bind(topic.retention.ttl_seconds.map(|n| n as i64))
+        .bind(topic.max_message_size as i64)
+        .bind(topic.max_subscribers.map(|n| n as i32))
+        .bind(
+            topic
+                .allowed_agents
+                .iter()
+                .map(|a| *a.as_uuid())
+                .collect::<Vec<Uuid>>(),
+        )
+        .bind(topic.replay_allowed)
+        .bind(topic.authz_failure_mode.as_str())
+        .bind(topic.created_at)
+        .bind(topic.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => {
+                CretoError::ValidationFailed(format!(
+                    "Topic '{}' already exists for owner {}",
+                    topic.name, topic.owner_agent_id
+                ))
+            }
+            e => CretoError::Database(e.to_string()),
+        })?;
+
+        Ok(())
+    }
+
+    async fn find_by_name(
+        &self,
+        owner_agent_id: AgentId,
+        name: &str,
+    ) -> Result<Option<Topic>, CretoError> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM messaging_topics WHERE owner_agent_id = $1 AND name = $2",
+            TOPIC_COLUMNS
+        ))
+        .bind(owner_agent_id.as_uuid())
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.as_ref().map(topic_from_row))
+    }
+
+    async fn list(&self) -> Result<Vec<Topic>, CretoError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM messaging_topics ORDER BY created_at ASC",
+            TOPIC_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows.iter().map(topic_from_row).collect())
+    }
+
+    async fn delete(&self, topic_id: TopicId) -> Result<(), CretoError> {
+        // Subscriptions go with it via ON DELETE CASCADE
+        sqlx::query("DELETE FROM messaging_topics WHERE id = $1")
+            .bind(topic_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// PostgreSQL implementation of SubscriptionRepository.
+pub struct PgSubscriptionRepository {
+    pool: PgPool,
+}
+
+impl PgSubscriptionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl SubscriptionRepository for PgSubscriptionRepository {
+    async fn create(&self, subscription: &Subscription) -> Result<(), CretoError> {
+        let filter = subscription
+            .filter
+            .as_ref()
+            .map(SubscriptionFilter::to_db_json)
+            .transpose()?;
+        let start = serde_json::to_value(subscription.start)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO messaging_subscriptions (
+                id, topic_id, subscriber_agent_id, filter, start_position, subscribed_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(subscription.id)
+        .bind(subscription.topic_id)
+        .bind(subscription.subscriber_agent_id.as_uuid())
+        .bind(filter)
+        .bind(start)
+        .bind(subscription.subscribed_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<Subscription>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, topic_id, subscriber_agent_id, filter, start_position, subscribed_at
+            FROM messaging_subscriptions
+            ORDER BY subscribed_at ASC, id ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|r| {
+                Ok(Subscription {
+                    id: r.get("id"),
+                    topic_id: r.get("topic_id"),
+                    subscriber_agent_id: AgentId::from_uuid(
+                        r.get::<Uuid, _>("subscriber_agent_id"),
+                    ),
+                    filter: r
+                        .get::<Option<serde_json::Value>, _>("filter")
+                        .map(SubscriptionFilter::from_db_json)
+                        .transpose()?,
+                    start: serde_json::from_value::<SubscriptionStart>(r.get("start_position"))
+                        .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                    subscribed_at: r.get("subscribed_at"),
+                })
+            })
+            .collect()
+    }
+
+    async fn delete(&self, subscription_id: SubscriptionId) -> Result<(), CretoError> {
+        sqlx::query("DELETE FROM messaging_subscriptions WHERE id = $1")
+            .bind(subscription_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete_for_topic(&self, topic_id: TopicId) -> Result<(), CretoError> {
+        sqlx::query("DELETE FROM messaging_subscriptions WHERE topic_id = $1")
+            .bind(topic_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Envelope Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Simplified envelope record.
+#[derive(Debug, Clone)]
+pub struct EnvelopeRecord {
+    pub id: Uuid,
+    pub sender_id: AgentId,
+    pub recipient_id: AgentId,
+    pub ciphertext: Vec<u8>,
+    pub delivered: bool,
+    pub created_at: DateTime<Utc>,
+    pub correlation_id: Option<Uuid>,
+    pub caused_by: Option<Uuid>,
+    pub priority: MessagePriority,
+    /// When the envelope expires if still undelivered (never, if `None`).
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The full envelope, read back from its versioned wire form. `None` for
+    /// envelopes stored before the wire form was kept.
+    pub envelope: Option<Envelope>,
+}
+
+/// Repository for message envelope persistence (store-and-forward).
+///
+/// Implementations enforce the per-recipient [`MailboxConfig`] limits on
+/// [`store`](Self::store) and deliver by priority, then age.
+#[async_trait::async_trait]
+pub trait EnvelopeRepository: Send + Sync {
+    /// Store a message envelope under its own ID.
+    ///
+    /// The envelope is kept in its versioned wire form, so fields this node
+    /// does not recognize are forwarded unchanged.
+    ///
+    /// Fails with [`CretoError::MailboxFull`] if the recipient's mailbox is
+    /// full and the overflow policy cannot make room.
+    async fn store(&self, envelope: &Envelope) -> Result<Uuid, CretoError>;
+
+    /// Get undelivered envelopes for a recipient, by priority then age,
+    /// with a share of each fetch reserved for the oldest low-priority ones.
+    async fn get_undelivered(
+        &self,
+        recipient_id: AgentId,
+        limit: i64,
+    ) -> Result<Vec<EnvelopeRecord>, CretoError>;
+
+    /// Get an envelope by ID, delivered or not.
+    async fn get(&self, id: Uuid) -> Result<Option<EnvelopeRecord>, CretoError>;
+
+    /// Mark envelope as delivered.
+    async fn mark_delivered(&self, id: Uuid) -> Result<(), CretoError>;
+
+    /// Record a receipt for an envelope.
+    ///
+    /// An envelope has at most one receipt of each type; recording a
+    /// duplicate keeps the first. Fails with [`CretoError::NotFound`] if the
+    /// envelope is unknown.
+    async fn record_receipt(
+        &self,
+        envelope_id: Uuid,
+        receipt_type: ReceiptType,
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), CretoError>;
+
+    /// Receipts recorded for an envelope, oldest first.
+    async fn get_receipts(&self, envelope_id: Uuid) -> Result<Vec<DeliveryReceipt>, CretoError>;
+
+    /// Delete expired envelopes.
+    async fn cleanup_expired(&self) -> Result<i64, CretoError>;
+
+    /// Find all envelopes sharing a correlation ID, oldest first.
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<EnvelopeRecord>, CretoError>;
+
+    /// Undelivered count and bytes for a recipient.
+    async fn mailbox_usage(&self, recipient_id: AgentId) -> Result<MailboxUsage, CretoError>;
+}
+
+/// PostgreSQL implementation of EnvelopeRepository.
+///
+/// Per-recipient backlog is kept in the `message_mailbox_usage` counter
+/// table, updated in the same transaction as the envelopes, so quota checks
+/// never count rows.
+pub struct PgEnvelopeRepository {
+    pool: PgPool,
+    config: MailboxConfig,
+}
+
+impl PgEnvelopeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            config: MailboxConfig::default(),
+        }
+    }
+
+    /// Set the mailbox limits.
+    pub fn with_mailbox_config(mut self, config: MailboxConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl EnvelopeRepository for PgEnvelopeRepository {
+    async fn store(&self, envelope: &Envelope) -> Result<Uuid, CretoError> {
+        let header = &envelope.header;
+        let recipient_id = header.recipient_id;
+        let ciphertext = &envelope.payload.ciphertext;
+        let wire_envelope = wire::encode_for_storage(envelope)?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        // Lock the recipient's counter row so concurrent senders see each
+        // other's envelopes
+        let row = sqlx::query(
+            r#"
+            INSERT INTO message_mailbox_usage (recipient_id)
+            VALUES ($1)
+            ON CONFLICT (recipient_id)
+            DO UPDATE SET recipient_id = EXCLUDED.recipient_id
+            RETURNING undelivered_count, undelivered_bytes
+            "#,
+        )
+        .bind(recipient_id.as_uuid())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+        let usage = MailboxUsage {
+            undelivered_count: row.get::<i64, _>("undelivered_count") as u64,
+            undelivered_bytes: row.get::<i64, _>("undelivered_bytes") as u64,
+        };
+
+        let incoming_bytes = ciphertext.len() as u64;
+        let mut evicted = self.config.eviction_plan(usage, incoming_bytes, []);
+        if evicted.is_none() {
+            if self.config.overflow_policy != MailboxOverflowPolicy::EvictOldestLowPriority {
+                return Err(self.config.full(recipient_id, usage));
+            }
+            let candidates = sqlx::query(
+                r#"
+                SELECT id, octet_length(ciphertext) AS bytes
+                FROM message_envelopes
+                WHERE recipient_id = $1 AND delivered = false AND priority = $2
+                ORDER BY created_at ASC
+                "#,
+            )
+            .bind(recipient_id.as_uuid())
+            .bind(MessagePriority::Low.as_db_i16())
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+            evicted = self.config.eviction_plan(
+                usage,
+                incoming_bytes,
+                candidates
+                    .iter()
+                    .map(|r| (r.get("id"), r.get::<i32, _>("bytes") as u64)),
+            );
+        }
+        let evicted = evicted.ok_or_else(|| self.config.full(recipient_id, usage))?;
+
+        let row = sqlx::query(
+            r#"
+            WITH evicted AS (
+                DELETE FROM message_envelopes
+                WHERE id = ANY($8)
+                RETURNING octet_length(ciphertext) AS bytes
+            ),
+            inserted AS (
+                INSERT INTO message_envelopes (
+                    id, sender_id, recipient_id, envelope_version, content_type,
+                    reply_to, dh_public, prev_chain_length, message_number,
+                    ciphertext, mac, correlation_id, caused_by, priority,
+                    wire_envelope, expires_at
+                ) VALUES (
+                    $1, $2, $3, $9, $10, $11, $12, $13, $14,
+                    $4, $5, $6, $7, $15, $16, $17
+                )
+                RETURNING id
+            ),
+            counted AS (
+                UPDATE message_mailbox_usage
+                SET undelivered_count = undelivered_count + 1
+                        - (SELECT COUNT(*) FROM evicted),
+                    undelivered_bytes = undelivered_bytes + octet_length($4)
+                        - (SELECT COALESCE(SUM(bytes), 0) FROM evicted),
+                    updated_at = NOW()
+                WHERE recipient_id = $3
+            )
+            SELECT id FROM inserted
+            "#,
+        )
+        .bind(envelope.id)
+        .bind(header.sender_id.as_uuid())
+        .bind(recipient_id.as_uuid())
+        .bind(ciphertext)
+        .bind(&envelope.payload.mac)
+        .bind(header.correlation_id)
+        .bind(header.caused_by)
+        .bind(&evicted)
+        .bind(envelope.version as i16)
+        .bind(header.content_type.as_str())
+        .bind(header.reply_to)
+        .bind(&header.ratchet_header.dh_public)
+        .bind(header.ratchet_header.prev_chain_length as i32)
+        .bind(header.ratchet_header.message_number as i32)
+        .bind(header.priority.as_db_i16())
+        .bind(wire_envelope)
+        .bind(self.config.expires_at(Utc::now()))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.get("id"))
+    }
+
+    async fn get_undelivered(
+        &self,
+        recipient_id: AgentId,
+        limit: i64,
+    ) -> Result<Vec<EnvelopeRecord>, CretoError> {
+        // Reserved slots take the oldest low-priority envelopes; the rest of
+        // the fetch goes by priority, then age
+        let rows = sqlx::query(
+            r#"
+            WITH pending AS (
+                SELECT id, sender_id, recipient_id, ciphertext, delivered, created_at,
+                       correlation_id, caused_by, priority, expires_at, wire_envelope
+                FROM message_envelopes
+                WHERE recipient_id = $1
+                  AND delivered = false
+                  AND (expires_at IS NULL OR expires_at > NOW())
+            ),
+            reserved AS (
+                SELECT id FROM pending
+                WHERE priority = $4
+                ORDER BY created_at ASC
+                LIMIT $3
+            ),
+            ranked AS (
+                SELECT id FROM pending
+                WHERE id NOT IN (SELECT id FROM reserved)
+                ORDER BY priority DESC, created_at ASC
+                LIMIT GREATEST($2 - (SELECT COUNT(*) FROM reserved), 0)
+            )
+            SELECT * FROM pending
+            WHERE id IN (SELECT id FROM reserved UNION ALL SELECT id FROM ranked)
+            ORDER BY priority DESC, created_at ASC
+            "#,
+        )
+        .bind(recipient_id.as_uuid())
+        .bind(limit)
+        .bind(self.config.reserved_slots(limit))
+        .bind(MessagePriority::Low.as_db_i16())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(row_to_envelope_record).collect()
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<EnvelopeRecord>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, sender_id, recipient_id, ciphertext, delivered, created_at,
+                   correlation_id, caused_by, priority, expires_at, wire_envelope
+            FROM message_envelopes
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.as_ref().map(row_to_envelope_record).transpose()
+    }
+
+    async fn mark_delivered(&self, id: Uuid) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            WITH delivered AS (
+                UPDATE message_envelopes
+                SET delivered = true, delivered_at = NOW()
+                WHERE id = $1 AND delivered = false
+                RETURNING recipient_id, octet_length(ciphertext) AS bytes
+            )
+            UPDATE message_mailbox_usage u
+            SET undelivered_count = u.undelivered_count - 1,
+                undelivered_bytes = u.undelivered_bytes - d.bytes,
+                updated_at = NOW()
+            FROM delivered d
+            WHERE u.recipient_id = d.recipient_id
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn record_receipt(
+        &self,
+        envelope_id: Uuid,
+        receipt_type: ReceiptType,
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), CretoError> {
+        let row = sqlx::query(
+            r#"
+            WITH inserted AS (
+                INSERT INTO delivery_receipts (envelope_id, receipt_type, created_at)
+                SELECT id, $2, $3 FROM message_envelopes WHERE id = $1
+                ON CONFLICT (envelope_id, receipt_type) DO NOTHING
+                RETURNING id
+            )
+            SELECT EXISTS (SELECT 1 FROM message_envelopes WHERE id = $1) AS found
+            "#,
+        )
+        .bind(envelope_id)
+        .bind(receipt_type.as_str())
+        .bind(timestamp)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        if !row.get::<bool, _>("found") {
+            return Err(CretoError::NotFound(format!("Envelope {}", envelope_id)));
+        }
+        Ok(())
+    }
+
+    async fn get_receipts(&self, envelope_id: Uuid) -> Result<Vec<DeliveryReceipt>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT receipt_type, signature, created_at
+            FROM delivery_receipts
+            WHERE envelope_id = $1
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(envelope_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| DeliveryReceipt {
+                message_id: envelope_id,
+                receipt_type: ReceiptType::parse_db_str(r.get::<&str, _>("receipt_type")),
+                timestamp: r.get("created_at"),
+                signature: r.get::<Option<Vec<u8>>, _>("signature").unwrap_or_default(),
+                correlation_id: None,
+                caused_by: None,
+            })
+            .collect())
+    }
+
+    async fn cleanup_expired(&self) -> Result<i64, CretoError> {
+        let row = sqlx::query(
+            r#"
+            WITH removed AS (
+                DELETE FROM message_envelopes
+                WHERE expires_at < NOW() AND delivered = false
+                RETURNING recipient_id, octet_length(ciphertext) AS bytes
+            ),
+            counted AS (
+                UPDATE message_mailbox_usage u
+                SET undelivered_count = u.undelivered_count - r.count,
+                    undelivered_bytes = u.undelivered_bytes - r.bytes,
+                    updated_at = NOW()
+                FROM (
+                    SELECT recipient_id, COUNT(*) AS count, SUM(bytes) AS bytes
+                    FROM removed
+                    GROUP BY recipient_id
+                ) r
+                WHERE u.recipient_id = r.recipient_id
+            )
+            SELECT COUNT(*) AS deleted FROM removed
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.get("deleted"))
+    }
+
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<EnvelopeRecord>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, sender_id, recipient_id, ciphertext, delivered, created_at,
+                   correlation_id, caused_by, priority, expires_at, wire_envelope
+            FROM message_envelopes
+            WHERE correlation_id = $1
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(correlation_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(row_to_envelope_record).collect()
+    }
+
+    async fn mailbox_usage(&self, recipient_id: AgentId) -> Result<MailboxUsage, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT undelivered_count, undelivered_bytes
+            FROM message_mailbox_usage
+            WHERE recipient_id = $1
+            "#,
+        )
+        .bind(recipient_id.as_uuid())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row
+            .map(|r| MailboxUsage {
+                undelivered_count: r.get::<i64, _>("undelivered_count") as u64,
+                undelivered_bytes: r.get::<i64, _>("undelivered_bytes") as u64,
+            })
+            .unwrap_or_default())
+    }
+}
+
+fn row_to_envelope_record(r: &sqlx::postgres::PgRow) -> Result<EnvelopeRecord, CretoError> {
+    let ciphertext: Vec<u8> = r.get("ciphertext");
+    let envelope = r
+        .get::<Option<serde_json::Value>, _>("wire_envelope")
+        .map(|doc| wire::decode_from_storage(doc, ciphertext.clone()))
+        .transpose()?;
+
+    Ok(EnvelopeRecord {
+        id: r.get("id"),
+        sender_id: AgentId::from_uuid(r.get::<Uuid, _>("sender_id")),
+        recipient_id: AgentId::from_uuid(r.get::<Uuid, _>("recipient_id")),
+        ciphertext,
+        delivered: r.get("delivered"),
+        created_at: r.get("created_at"),
+        correlation_id: r.get("correlation_id"),
+        caused_by: r.get("caused_by"),
+        priority: MessagePriority::from_db_i16(r.get("priority")),
+        expires_at: r.get("expires_at"),
+        envelope,
+    })
+}
+
+/// Spawn a background task deleting expired envelopes every `interval`.
+///
+/// Each pass is a single `DELETE` statement, so stopping between passes
+/// never leaves a cleanup half applied. The task is registered with
+/// `shutdown`.
+pub fn spawn_envelope_cleanup(
+    envelopes: Arc<dyn EnvelopeRepository>,
+    shutdown: &ShutdownCoordinator,
+    interval: Duration,
+) -> JoinHandle<()> {
+    shutdown.spawn("messaging.envelope_cleanup", move |guard| {
+        guard.run_periodic(interval, move || {
+            let envelopes = Arc::clone(&envelopes);
+            async move {
+                match envelopes.cleanup_expired().await {
+                    Ok(0) => {}
+                    Ok(deleted) => tracing::debug!(deleted, "Deleted expired envelopes"),
+                    Err(e) => tracing::warn!(error = %e, "Envelope cleanup pass failed"),
+                }
+            }
+        })
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Channel Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Simplified channel record.
+#[derive(Debug, Clone)]
+pub struct ChannelRecord {
+    pub id: Uuid,
+    pub organization_id: OrganizationId,
+    pub channel_type: ChannelType,
+    pub name: String,
+    pub active: bool,
+}
+
+/// Repository for messaging channel configuration.
+#[async_trait::async_trait]
+pub trait ChannelRepository: Send + Sync {
+    /// Create a channel.
+    async fn create(
+        &self,
+        org_id: OrganizationId,
+        channel_type: ChannelType,
+        name: &str,
+    ) -> Result<Uuid, CretoError>;
+
+    /// List active channels for an organization.
+    async fn list_active(&self, org_id: OrganizationId) -> Result<Vec<ChannelRecord>, CretoError>;
+
+    /// Deactivate a channel.
+    async fn deactivate(&self, id: Uuid) -> Result<(), CretoError>;
+}
+
+/// PostgreSQL implementation of ChannelRepository.
+pub struct PgChannelRepository {
+    pool: PgPool,
+}
+
+impl PgChannelRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ChannelRepository for PgChannelRepository {
+    async fn create(
+        &self,
+        org_id: OrganizationId,
         channel_type: ChannelType,
         name: &str,
     ) -> Result<Uuid, CretoError> {
@@ -711,6 +1824,42 @@ mod tests {
         assert_eq!(SessionState::Establishing.as_str(), "establishing");
     }
 
+    #[test]
+    fn test_message_priority_roundtrip() {
+        for priority in [
+            MessagePriority::Low,
+            MessagePriority::Normal,
+            MessagePriority::High,
+        ] {
+            assert_eq!(MessagePriority::from_db_i16(priority.as_db_i16()), priority);
+        }
+        assert!(MessagePriority::High.as_db_i16() > MessagePriority::Low.as_db_i16());
+    }
+
+    #[test]
+    fn test_topic_policy_roundtrip() {
+        for policy in [
+            TopicPolicy::Open,
+            TopicPolicy::Private,
+            TopicPolicy::AuthzRequired,
+            TopicPolicy::Allowlist,
+        ] {
+            assert_eq!(TopicPolicy::parse_db_str(policy.as_str()), policy);
+        }
+        assert_eq!(TopicPolicy::parse_db_str("bogus"), TopicPolicy::Private);
+    }
+
+    #[test]
+    fn test_authz_failure_mode_roundtrip() {
+        for mode in [AuthzFailureMode::FailClosed, AuthzFailureMode::FailOpen] {
+            assert_eq!(AuthzFailureMode::parse_db_str(mode.as_str()), mode);
+        }
+        assert_eq!(
+            AuthzFailureMode::parse_db_str("bogus"),
+            AuthzFailureMode::FailClosed
+        );
+    }
+
     #[test]
     fn test_channel_type_roundtrip() {
         assert_eq!(ChannelType::parse_db_str("direct"), ChannelType::Direct);
@@ -3,19 +3,41 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use creto_common::{AgentId, CretoResult};
+use chrono::Utc;
+use creto_common::metrics::{Counter, GaugeVec, MetricsRegistry};
+use creto_common::{
+    AgentId, Clock, Correlation, CretoError, CretoResult, OrganizationId, ShutdownCoordinator,
+    SystemClock,
+};
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
 use crate::{
     channel::{Channel, ChannelRouter},
-    envelope::{DeliveryReceipt, Envelope},
-    keys::{KeyBundle, KeyStore},
-    session::{Session, SessionState, SessionStore},
-    topic::{Subscription, SubscriptionFilter, TopicConfig, TopicId, TopicManager},
-    x3dh::X3DH,
+    envelope::{DeliveryReceipt, Envelope, MessageStatus, ReceiptType},
+    keys::{
+        KeyBundle, KeyStore, PreKey, PreKeyStatus, RetiredSignedPreKey, SignedPreKey,
+        SignedPreKeyRotationPolicy,
+    },
+    ratchet_store::{RatchetFlushPolicy, StateEncryptionKey},
+    rate_limit::MessageRateLimiter,
+    repository::{
+        EnvelopeRecord, EnvelopeRepository, PreKeyRepository, RatchetStateRepository,
+        SessionRepository,
+    },
+    session::{Session, SessionMetadata, SessionState, SessionStore},
+    topic::{
+        ReplayPolicy, Subscription, SubscriptionFilter, SubscriptionStart, TopicConfig,
+        TopicDelivery, TopicId, TopicManager, TopicMessage,
+    },
+    x3dh::{X3DHParams, X3DH},
 };
 
+/// Default one-time pre-key low watermark, matching
+/// `MessagingConfig::prekey_threshold`.
+const DEFAULT_PREKEY_THRESHOLD: usize = 10;
+
 /// Main entry point for the messaging system.
 pub struct MessagingService {
     /// Key store for managing cryptographic keys.
@@ -24,6 +46,17 @@ pub struct MessagingService {
     /// Session store for managing sessions.
     session_store: Option<Arc<dyn SessionStore>>,
 
+    /// Published one-time pre-keys, consumed by initiators. Falls back to
+    /// the key store's pre-keys when unset.
+    pre_key_repository: Option<Arc<dyn PreKeyRepository>>,
+
+    /// Private halves of the local agent's published one-time pre-keys.
+    one_time_pre_keys: std::sync::Mutex<OneTimePreKeys>,
+
+    /// Available one-time pre-key count below which an agent should
+    /// replenish.
+    prekey_threshold: usize,
+
     /// Channel router for message delivery.
     channel_router: Arc<RwLock<ChannelRouter>>,
 
@@ -31,10 +64,70 @@ pub struct MessagingService {
     sessions: Arc<RwLock<HashMap<Uuid, Session>>>,
 
     /// Local agent's key bundle.
-    local_bundle: Option<KeyBundle>,
+    ///
+    /// Behind a lock so signed pre-key rotation can swap it on a shared
+    /// service, and shared so readers never copy the private keys.
+    local_bundle: std::sync::RwLock<Option<Arc<KeyBundle>>>,
+
+    /// Signed pre-key rotation policy.
+    rotation_policy: SignedPreKeyRotationPolicy,
+
+    /// Time source for signed pre-key rotation and its overlap window.
+    clock: Arc<dyn Clock>,
 
     /// Topic manager for pub/sub.
-    topic_manager: Arc<RwLock<TopicManager>>,
+    topic_manager: TopicManager,
+
+    /// Store-and-forward envelopes and their receipts (optional).
+    envelope_repository: Option<Arc<dyn EnvelopeRepository>>,
+
+    /// Session records, linked to their persisted ratchet state (optional).
+    session_repository: Option<Arc<dyn SessionRepository>>,
+
+    /// Encrypted ratchet state storage (optional). Without it sessions do
+    /// not survive a restart.
+    ratchet_store: Option<RatchetStore>,
+
+    /// When ratchet state is written back after a step.
+    ratchet_flush_policy: RatchetFlushPolicy,
+
+    /// Ratchet steps taken since each session's state was last written.
+    unsaved_ratchet_steps: std::sync::Mutex<HashMap<Uuid, u32>>,
+
+    /// Exported mailbox metrics (None = not exported).
+    metrics: Option<MailboxMetrics>,
+
+    /// Send rate limiter and the local agent's organization (None =
+    /// unlimited).
+    rate_limiter: Option<(Arc<MessageRateLimiter>, OrganizationId)>,
+}
+
+/// Store-and-forward metrics exported to a [`MetricsRegistry`].
+struct MailboxMetrics {
+    undelivered: GaugeVec,
+    stored_bytes: GaugeVec,
+    delivered: Counter,
+}
+
+impl MailboxMetrics {
+    fn new(registry: &MetricsRegistry) -> Self {
+        Self {
+            undelivered: registry.gauge_vec(
+                "creto_messaging_undelivered_envelopes",
+                "Stored envelopes waiting for delivery to a local agent",
+                &["agent"],
+            ),
+            stored_bytes: registry.gauge_vec(
+                "creto_messaging_stored_envelope_bytes",
+                "Ciphertext bytes stored for a local agent",
+                &["agent"],
+            ),
+            delivered: registry.counter(
+                "creto_messaging_envelopes_delivered_total",
+                "Stored envelopes handed out to local agents",
+            ),
+        }
+    }
 }
 
 impl MessagingService {
@@ -43,10 +136,22 @@ impl MessagingService {
         Self {
             key_store: None,
             session_store: None,
+            pre_key_repository: None,
+            one_time_pre_keys: std::sync::Mutex::new(OneTimePreKeys::default()),
+            prekey_threshold: DEFAULT_PREKEY_THRESHOLD,
             channel_router: Arc::new(RwLock::new(ChannelRouter::new())),
             sessions: Arc::new(RwLock::new(HashMap::new())),
-            local_bundle: None,
-            topic_manager: Arc::new(RwLock::new(TopicManager::new())),
+            local_bundle: std::sync::RwLock::new(None),
+            rotation_policy: SignedPreKeyRotationPolicy::default(),
+            clock: Arc::new(SystemClock),
+            topic_manager: TopicManager::new(),
+            envelope_repository: None,
+            session_repository: None,
+            ratchet_store: None,
+            ratchet_flush_policy: RatchetFlushPolicy::default(),
+            unsaved_ratchet_steps: std::sync::Mutex::new(HashMap::new()),
+            metrics: None,
+            rate_limiter: None,
         }
     }
 
@@ -62,6 +167,95 @@ impl MessagingService {
         self
     }
 
+    /// Set the repository one-time pre-keys are published to and consumed
+    /// from.
+    pub fn with_pre_key_repository(mut self, repository: Arc<dyn PreKeyRepository>) -> Self {
+        self.pre_key_repository = Some(repository);
+        self
+    }
+
+    /// Set the one-time pre-key low watermark, usually from
+    /// `MessagingConfig::prekey_threshold`.
+    pub fn with_prekey_threshold(mut self, threshold: usize) -> Self {
+        self.prekey_threshold = threshold;
+        self
+    }
+
+    /// Set the store-and-forward envelope repository.
+    pub fn with_envelope_repository(mut self, repository: Arc<dyn EnvelopeRepository>) -> Self {
+        self.envelope_repository = Some(repository);
+        self
+    }
+
+    /// Export the local agent's stored and undelivered envelopes to
+    /// `registry`.
+    ///
+    /// The gauges are updated on every
+    /// [`get_undelivered`](Self::get_undelivered) and by
+    /// [`refresh_metrics`](Self::refresh_metrics); envelopes stored by a
+    /// relay show up on the next of either.
+    pub fn with_metrics(mut self, registry: &MetricsRegistry) -> Self {
+        self.metrics = Some(MailboxMetrics::new(registry));
+        self
+    }
+
+    /// Use `manager` for pub/sub, e.g. one backed by topic repositories.
+    pub fn with_topic_manager(mut self, manager: TopicManager) -> Self {
+        self.topic_manager = manager;
+        self
+    }
+
+    /// Set the session repository.
+    pub fn with_session_repository(mut self, repository: Arc<dyn SessionRepository>) -> Self {
+        self.session_repository = Some(repository);
+        self
+    }
+
+    /// Persist ratchet state to `repository`, encrypted with `key`, so
+    /// sessions can be picked up again with
+    /// [`resume_session`](Self::resume_session).
+    pub fn with_ratchet_state_repository(
+        mut self,
+        repository: Arc<dyn RatchetStateRepository>,
+        key: StateEncryptionKey,
+    ) -> Self {
+        self.ratchet_store = Some(RatchetStore { repository, key });
+        self
+    }
+
+    /// Set when ratchet state is written back, usually
+    /// `RatchetFlushPolicy::from_steps(config.ratchet_flush_steps)`.
+    pub fn with_ratchet_flush_policy(mut self, policy: RatchetFlushPolicy) -> Self {
+        self.ratchet_flush_policy = policy;
+        self
+    }
+
+    /// Limit the local agent's sends, publishes and session establishments
+    /// with `limiter`, counting them against `organization_id`.
+    ///
+    /// Refused operations fail with [`CretoError::LimitExceeded`] carrying a
+    /// retry-after hint.
+    pub fn with_rate_limiter(
+        mut self,
+        limiter: Arc<MessageRateLimiter>,
+        organization_id: OrganizationId,
+    ) -> Self {
+        self.rate_limiter = Some((limiter, organization_id));
+        self
+    }
+
+    /// Set the signed pre-key rotation policy.
+    pub fn with_rotation_policy(mut self, policy: SignedPreKeyRotationPolicy) -> Self {
+        self.rotation_policy = policy;
+        self
+    }
+
+    /// Use a different time source for signed pre-key rotation.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Add a delivery channel.
     pub async fn add_channel(&self, channel: Box<dyn Channel>) {
         let mut router = self.channel_router.write().await;
@@ -69,6 +263,10 @@ impl MessagingService {
     }
 
     /// Initialize for a local agent.
+    ///
+    /// One-time pre-keys published before are discarded: their private
+    /// halves were only held by the previous run, so handshakes using them
+    /// could not be answered. Replenish afterwards.
     pub async fn initialize(&mut self, agent_id: AgentId) -> CretoResult<()> {
         // Generate key bundle
         let bundle = KeyBundle::new(agent_id);
@@ -78,21 +276,47 @@ impl MessagingService {
             store.store_bundle(&bundle).await?;
         }
 
-        self.local_bundle = Some(bundle);
+        let discarded = match (&self.pre_key_repository, &self.key_store) {
+            (Some(repository), _) => repository.discard_available(agent_id).await?,
+            (None, Some(store)) => u64::from(store.discard_pre_keys(agent_id).await?),
+            (None, None) => 0,
+        };
+        if discarded > 0 {
+            tracing::info!(%agent_id, discarded, "Discarded one-time pre-keys of a previous run");
+        }
+        self.one_time_pre_keys.lock().unwrap().held.clear();
+
+        *self.local_bundle.write().unwrap() = Some(Arc::new(bundle));
 
         tracing::info!(%agent_id, "Messaging service initialized");
 
         Ok(())
     }
 
+    /// The agent this service was initialized for, if any.
+    pub fn local_agent(&self) -> Option<AgentId> {
+        self.local_bundle
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|bundle| bundle.agent_id)
+    }
+
     /// Establish a session with another agent.
     pub async fn establish_session(&self, remote_agent: AgentId) -> CretoResult<Uuid> {
-        let local_bundle = self.local_bundle.as_ref().ok_or_else(|| {
-            creto_common::CretoError::SessionError("Service not initialized".to_string())
-        })?;
+        self.initiate_session(remote_agent)
+            .await
+            .map(|(session_id, _)| session_id)
+    }
+
+    /// Establish a session with another agent, returning the handshake
+    /// parameters the remote side passes to [`accept_session`](Self::accept_session).
+    pub async fn initiate_session(&self, remote_agent: AgentId) -> CretoResult<(Uuid, X3DHParams)> {
+        let local_bundle = self.local_bundle()?;
+        self.acquire_rate_limit(local_bundle.agent_id, 0)?;
 
         // Get recipient's key bundle
-        let remote_bundle = if let Some(store) = &self.key_store {
+        let mut remote_bundle = if let Some(store) = &self.key_store {
             store.get_bundle(remote_agent).await?.ok_or_else(|| {
                 creto_common::CretoError::SessionError(format!(
                     "No key bundle found for agent {}",
@@ -105,8 +329,18 @@ impl MessagingService {
             ));
         };
 
+        // Claim a one-time pre-key; the bundle's own is shared by everyone
+        // who fetches it
+        remote_bundle.one_time_pre_key = self.consume_one_time_prekey(remote_agent).await?;
+        if remote_bundle.one_time_pre_key.is_none() {
+            tracing::warn!(
+                %remote_agent,
+                "No one-time pre-keys left; session relies on the signed pre-key only"
+            );
+        }
+
         // Perform X3DH
-        let x3dh_result = X3DH::initiate(local_bundle, &remote_bundle)?;
+        let x3dh_result = X3DH::initiate(&local_bundle, &remote_bundle)?;
 
         // Create session
         let session = Session::new_initiator(local_bundle.agent_id, remote_agent, &x3dh_result);
@@ -117,6 +351,7 @@ impl MessagingService {
         if let Some(store) = &self.session_store {
             store.store_session(&session).await?;
         }
+        self.persist_new_session(&session).await?;
 
         // Cache session
         let mut sessions = self.sessions.write().await;
@@ -125,27 +360,444 @@ impl MessagingService {
         tracing::info!(
             session_id = %session_id,
             remote_agent = %remote_agent,
+            used_one_time_prekey = x3dh_result.used_one_time_prekey,
             "Session established"
         );
 
+        Ok((session_id, x3dh_result.params))
+    }
+
+    /// Accept a session initiated by another agent.
+    ///
+    /// The handshake may reference the current signed pre-key or one
+    /// replaced by rotation within the policy's overlap window, so
+    /// initiators holding a bundle fetched just before a rotation can still
+    /// complete.
+    pub async fn accept_session(&self, params: &X3DHParams) -> CretoResult<Uuid> {
+        let local_bundle = self.local_bundle()?;
+        let remote_agent = params.identity_key.agent_id;
+
+        let signed_pre_key = if params.recipient_signed_prekey_id == local_bundle.signed_pre_key.id
+        {
+            local_bundle.signed_pre_key.clone()
+        } else {
+            let now = self.clock.now();
+            self.key_store()?
+                .retained_signed_pre_keys(local_bundle.agent_id)
+                .await?
+                .into_iter()
+                .find(|retired| {
+                    retired.key.id == params.recipient_signed_prekey_id
+                        && self.rotation_policy.within_overlap(retired, now)
+                })
+                .map(|retired| retired.key)
+                .ok_or_else(|| {
+                    CretoError::CryptoError(format!(
+                        "Signed pre-key {} is unknown or past its overlap window",
+                        params.recipient_signed_prekey_id
+                    ))
+                })?
+        };
+
+        let consumed_prekey = match params.recipient_one_time_prekey_id {
+            Some(prekey_id) => Some(self.take_one_time_prekey(&local_bundle, prekey_id)?),
+            None => None,
+        };
+        let x3dh_result = X3DH::respond_with_signed_pre_key(
+            &local_bundle,
+            &signed_pre_key,
+            params,
+            consumed_prekey.as_ref(),
+        )?;
+
+        let session = Session::new_responder(
+            local_bundle.agent_id,
+            remote_agent,
+            &x3dh_result,
+            &signed_pre_key.public_key,
+            signed_pre_key.private_key.as_deref().unwrap_or_default(),
+        );
+        let session_id = session.id;
+
+        if let Some(store) = &self.session_store {
+            store.store_session(&session).await?;
+        }
+        self.persist_new_session(&session).await?;
+        self.sessions.write().await.insert(session_id, session);
+
+        tracing::info!(
+            session_id = %session_id,
+            remote_agent = %remote_agent,
+            signed_prekey_id = signed_pre_key.id,
+            one_time_prekey_id = params.recipient_one_time_prekey_id,
+            "Session accepted"
+        );
+
         Ok(session_id)
     }
 
+    /// Pick up the session between `local_agent` and `remote_agent` from
+    /// its persisted, encrypted ratchet state, e.g. after a restart.
+    ///
+    /// Returns the session's ID. A session that is already cached is
+    /// returned as is, since it is at least as current as the stored state.
+    /// Fails with [`CretoError::CryptoError`] when the state does not
+    /// decrypt under the configured key, leaving nothing cached.
+    pub async fn resume_session(
+        &self,
+        local_agent: AgentId,
+        remote_agent: AgentId,
+    ) -> CretoResult<Uuid> {
+        let ratchet_store = self.ratchet_store()?;
+        let record = self
+            .session_repository()?
+            .get(local_agent, remote_agent)
+            .await?
+            .ok_or_else(|| {
+                CretoError::NotFound(format!(
+                    "Session between {} and {}",
+                    local_agent, remote_agent
+                ))
+            })?;
+        if matches!(record.state, SessionState::Closed | SessionState::Failed) {
+            return Err(CretoError::SessionError(format!(
+                "Session between {} and {} is {}",
+                local_agent,
+                remote_agent,
+                record.state.as_str()
+            )));
+        }
+        let session_id = record.ratchet_state_id.ok_or_else(|| {
+            CretoError::NotFound(format!("Ratchet state for session {}", record.id))
+        })?;
+
+        if self.sessions.read().await.contains_key(&session_id) {
+            return Ok(session_id);
+        }
+
+        let encrypted = ratchet_store
+            .repository
+            .load(session_id)
+            .await?
+            .ok_or_else(|| {
+                CretoError::NotFound(format!("Ratchet state for session {}", session_id))
+            })?;
+        let ratchet_state = ratchet_store.key.open(&encrypted)?;
+
+        let session = Session::restore(
+            SessionMetadata {
+                id: session_id,
+                local_agent,
+                remote_agent,
+                state: record.state,
+                created_at: record.created_at,
+                last_active_at: record.last_active_at,
+                ratchet_state: None,
+            },
+            ratchet_state,
+        );
+        self.sessions
+            .write()
+            .await
+            .entry(session_id)
+            .or_insert(session);
+
+        tracing::info!(
+            session_id = %session_id,
+            remote_agent = %remote_agent,
+            "Session resumed"
+        );
+
+        Ok(session_id)
+    }
+
+    /// Write the ratchet state of every session with unsaved steps.
+    ///
+    /// Only needed with a batched [`RatchetFlushPolicy`], e.g. before
+    /// shutting down. Returns the number of sessions written.
+    pub async fn flush_ratchet_states(&self) -> CretoResult<usize> {
+        if self.ratchet_store.is_none() {
+            return Ok(0);
+        }
+        let pending: Vec<Uuid> = self
+            .unsaved_ratchet_steps
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, steps)| **steps > 0)
+            .map(|(id, _)| *id)
+            .collect();
+
+        let sessions = self.sessions.read().await;
+        let mut written = 0;
+        for session in pending.iter().filter_map(|id| sessions.get(id)) {
+            self.save_ratchet_state(session).await?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Generate `count` one-time pre-keys for the local agent and publish
+    /// their public halves, to the pre-key repository if one is set and the
+    /// key store otherwise. Returns the new keys' IDs.
+    ///
+    /// IDs continue after every key the agent has published, so concurrent
+    /// calls never collide and a consumed ID is never reissued. Only the
+    /// local agent can be replenished, since the service must keep the
+    /// private halves.
+    pub async fn replenish_prekeys(&self, agent_id: AgentId, count: u32) -> CretoResult<Vec<u32>> {
+        let local_bundle = self.local_bundle()?;
+        if agent_id != local_bundle.agent_id {
+            return Err(CretoError::SessionError(format!(
+                "Cannot replenish pre-keys for non-local agent {}",
+                agent_id
+            )));
+        }
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let keys = match &self.pre_key_repository {
+            Some(repository) => {
+                let mut keys = PreKey::generate_batch(0, count);
+                let public: Vec<Vec<u8>> = keys.iter().map(|key| key.public_key.clone()).collect();
+                let ids = repository.store_next(agent_id, &public).await?;
+                for (key, prekey_id) in keys.iter_mut().zip(ids) {
+                    key.id = u32::try_from(prekey_id).map_err(|_| {
+                        CretoError::CryptoError(format!("Invalid pre-key ID {}", prekey_id))
+                    })?;
+                }
+                keys
+            }
+            None => {
+                let keys = {
+                    let mut prekeys = self.one_time_pre_keys.lock().unwrap();
+                    let start = prekeys
+                        .last_id
+                        .max(local_bundle.one_time_pre_key.as_ref().map_or(0, |k| k.id))
+                        + 1;
+                    prekeys.last_id = start + count - 1;
+                    PreKey::generate_batch(start, count)
+                };
+                let public = keys
+                    .iter()
+                    .map(|key| PreKey {
+                        private_key: None,
+                        ..key.clone()
+                    })
+                    .collect();
+                self.key_store()?.upload_pre_keys(agent_id, public).await?;
+                keys
+            }
+        };
+        let ids: Vec<u32> = keys.iter().map(|key| key.id).collect();
+
+        self.one_time_pre_keys
+            .lock()
+            .unwrap()
+            .held
+            .extend(keys.into_iter().map(|key| (key.id, key)));
+
+        tracing::info!(%agent_id, count, "Replenished one-time pre-keys");
+
+        Ok(ids)
+    }
+
+    /// How many one-time pre-keys `agent_id` has left, and whether that is
+    /// below the configured watermark.
+    pub async fn prekey_status(&self, agent_id: AgentId) -> CretoResult<PreKeyStatus> {
+        let available = match &self.pre_key_repository {
+            Some(repository) => {
+                u64::try_from(repository.count_available(agent_id).await?).unwrap_or(0)
+            }
+            None => u64::from(self.key_store()?.pre_key_count(agent_id).await?),
+        };
+        Ok(PreKeyStatus::new(available, self.prekey_threshold as u64))
+    }
+
+    /// Replace an agent's signed pre-key.
+    ///
+    /// The previous key is retained in the key store for the policy's
+    /// overlap window; bundles served to initiators carry only the new one.
+    /// Returns the new key's ID.
+    pub async fn rotate_signed_prekey(&self, agent_id: AgentId) -> CretoResult<u32> {
+        let store = self.key_store()?;
+        let mut bundle = store
+            .get_bundle(agent_id)
+            .await?
+            .ok_or_else(|| CretoError::NotFound(format!("Key bundle for agent {}", agent_id)))?;
+
+        let next = SignedPreKey::generate(
+            bundle.signed_pre_key.id.wrapping_add(1),
+            &bundle.identity_key,
+        );
+        let next_id = next.id;
+        let previous = std::mem::replace(&mut bundle.signed_pre_key, next);
+        let previous_id = previous.id;
+
+        store
+            .retain_signed_pre_key(
+                agent_id,
+                RetiredSignedPreKey {
+                    key: previous,
+                    retired_at: self.clock.now(),
+                },
+            )
+            .await?;
+        store.store_bundle(&bundle).await?;
+
+        if let Some(local) = self
+            .local_bundle
+            .write()
+            .unwrap()
+            .as_mut()
+            .filter(|local| local.agent_id == agent_id)
+        {
+            Arc::make_mut(local).signed_pre_key = bundle.signed_pre_key.clone();
+        }
+
+        self.prune_signed_prekeys(agent_id).await?;
+
+        tracing::info!(
+            %agent_id,
+            previous_id,
+            signed_prekey_id = next_id,
+            "Signed pre-key rotated"
+        );
+
+        Ok(next_id)
+    }
+
+    /// Destroy retained signed pre-keys past the overlap window or beyond
+    /// the retention limit. Returns the number destroyed.
+    pub async fn prune_signed_prekeys(&self, agent_id: AgentId) -> CretoResult<usize> {
+        let store = self.key_store()?;
+        let now = self.clock.now();
+
+        let expired: Vec<u32> = store
+            .retained_signed_pre_keys(agent_id)
+            .await?
+            .iter()
+            .enumerate()
+            .filter(|(index, retired)| {
+                *index >= self.rotation_policy.max_retained
+                    || !self.rotation_policy.within_overlap(retired, now)
+            })
+            .map(|(_, retired)| retired.key.id)
+            .collect();
+
+        for key_id in &expired {
+            store.destroy_signed_pre_key(agent_id, *key_id).await?;
+        }
+
+        if !expired.is_empty() {
+            tracing::debug!(%agent_id, destroyed = expired.len(), "Pruned signed pre-keys");
+        }
+
+        Ok(expired.len())
+    }
+
+    /// Rotate the local agent's signed pre-key if the policy says it is due,
+    /// pruning expired keys either way. Returns whether a rotation happened.
+    pub async fn rotate_if_due(&self) -> CretoResult<bool> {
+        let local_bundle = self.local_bundle()?;
+        let agent_id = local_bundle.agent_id;
+
+        if self
+            .rotation_policy
+            .rotation_due(&local_bundle.signed_pre_key, self.clock.now())
+        {
+            self.rotate_signed_prekey(agent_id).await?;
+            return Ok(true);
+        }
+
+        self.prune_signed_prekeys(agent_id).await?;
+        Ok(false)
+    }
+
+    /// Spawn a background task applying the rotation policy every
+    /// `check_interval`. The task is registered with `shutdown`.
+    pub fn spawn_signed_prekey_rotation(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+    ) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        let interval = self.rotation_policy.check_interval;
+        shutdown.spawn("messaging.signed_prekey_rotation", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    if let Err(e) = service.rotate_if_due().await {
+                        tracing::warn!(error = %e, "Signed pre-key rotation pass failed");
+                    }
+                }
+            })
+        })
+    }
+
     /// Send a message to another agent.
     pub async fn send(&self, session_id: Uuid, message: &[u8]) -> CretoResult<DeliveryReceipt> {
+        self.send_with_correlation(session_id, message, Correlation::default())
+            .await
+    }
+
+    /// Send a message tagged with trace identifiers.
+    ///
+    /// The returned receipt inherits the envelope's correlation ID.
+    pub async fn send_with_correlation(
+        &self,
+        session_id: Uuid,
+        message: &[u8],
+        correlation: Correlation,
+    ) -> CretoResult<DeliveryReceipt> {
+        let envelope = self
+            .seal(session_id, message)
+            .await?
+            .with_correlation(correlation);
+
+        // Deliver via channel
+        let router = self.channel_router.read().await;
+        let receipt = router.route(&envelope).await?;
+
+        Ok(receipt)
+    }
+
+    /// Encrypt a message on a session without delivering it.
+    ///
+    /// For callers that carry envelopes themselves; the recipient decrypts
+    /// with [`process_envelope`](Self::process_envelope).
+    pub async fn seal(&self, session_id: Uuid, message: &[u8]) -> CretoResult<Envelope> {
         let mut sessions = self.sessions.write().await;
         let session = sessions.get_mut(&session_id).ok_or_else(|| {
             creto_common::CretoError::SessionError(format!("Session {} not found", session_id))
         })?;
+        self.acquire_rate_limit(session.local_agent, message.len())?;
 
-        // Encrypt message
         let envelope = session.encrypt(message)?;
+        self.ratchet_stepped(session).await;
+        Ok(envelope)
+    }
 
-        // Deliver via channel
-        let router = self.channel_router.read().await;
-        let receipt = router.route(&envelope).await?;
+    /// Decrypt an envelope on a specific session.
+    ///
+    /// Unlike [`process_envelope`](Self::process_envelope) this does not
+    /// pick the session by sender, so it is unambiguous when several
+    /// sessions with the same agent are open.
+    pub async fn open(&self, session_id: Uuid, envelope: &Envelope) -> CretoResult<Vec<u8>> {
+        let mut sessions = self.sessions.write().await;
+        let session = sessions.get_mut(&session_id).ok_or_else(|| {
+            creto_common::CretoError::SessionError(format!("Session {} not found", session_id))
+        })?;
 
-        Ok(receipt)
+        if envelope.header.recipient_id != session.local_agent {
+            return Err(creto_common::CretoError::SessionError(
+                "Not the intended recipient".to_string(),
+            ));
+        }
+
+        let plaintext = session.decrypt(envelope)?;
+        self.ratchet_stepped(session).await;
+        Ok(plaintext)
     }
 
     /// Send a message to an agent, establishing session if needed.
@@ -154,9 +806,7 @@ impl MessagingService {
         remote_agent: AgentId,
         message: &[u8],
     ) -> CretoResult<DeliveryReceipt> {
-        let local_bundle = self.local_bundle.as_ref().ok_or_else(|| {
-            creto_common::CretoError::SessionError("Service not initialized".to_string())
-        })?;
+        let local_bundle = self.local_bundle()?;
 
         // Find existing session
         let session_id = {
@@ -181,9 +831,7 @@ impl MessagingService {
 
     /// Receive messages for the local agent.
     pub async fn receive(&self, limit: u32) -> CretoResult<Vec<(Uuid, Vec<u8>)>> {
-        let local_bundle = self.local_bundle.as_ref().ok_or_else(|| {
-            creto_common::CretoError::SessionError("Service not initialized".to_string())
-        })?;
+        let local_bundle = self.local_bundle()?;
 
         // Get pending envelopes from channel
         let router = self.channel_router.read().await;
@@ -197,9 +845,7 @@ impl MessagingService {
 
     /// Process a received envelope.
     pub async fn process_envelope(&self, envelope: &Envelope) -> CretoResult<Vec<u8>> {
-        let local_bundle = self.local_bundle.as_ref().ok_or_else(|| {
-            creto_common::CretoError::SessionError("Service not initialized".to_string())
-        })?;
+        let local_bundle = self.local_bundle()?;
 
         // Verify we are the recipient
         if envelope.header.recipient_id != local_bundle.agent_id {
@@ -224,7 +870,130 @@ impl MessagingService {
             })?;
 
         // Decrypt
-        session.decrypt(envelope)
+        let plaintext = session.decrypt(envelope)?;
+        self.ratchet_stepped(session).await;
+        Ok(plaintext)
+    }
+
+    /// Fetch up to `limit` stored envelopes for the local agent, by
+    /// priority then age.
+    ///
+    /// Each envelope handed out is marked delivered and gets a
+    /// [`ReceiptType::Delivered`] receipt, so its sender sees it in
+    /// [`message_status`](Self::message_status).
+    pub async fn get_undelivered(&self, limit: i64) -> CretoResult<Vec<EnvelopeRecord>> {
+        let local_bundle = self.local_bundle()?;
+        let repository = self.envelope_repository()?;
+
+        let records = repository
+            .get_undelivered(local_bundle.agent_id, limit)
+            .await?;
+        for record in &records {
+            repository.mark_delivered(record.id).await?;
+            repository
+                .record_receipt(record.id, ReceiptType::Delivered, Utc::now())
+                .await?;
+        }
+
+        tracing::debug!(
+            agent_id = %local_bundle.agent_id,
+            count = records.len(),
+            "Delivered stored envelopes"
+        );
+        if let Some(metrics) = &self.metrics {
+            metrics.delivered.inc_by(records.len() as u64);
+            self.publish_mailbox_usage(metrics, repository.as_ref(), local_bundle.agent_id)
+                .await?;
+        }
+
+        Ok(records)
+    }
+
+    /// Set the exported mailbox gauges from the local agent's stored,
+    /// undelivered envelopes.
+    ///
+    /// Does nothing without exported metrics.
+    pub async fn refresh_metrics(&self) -> CretoResult<()> {
+        let Some(metrics) = &self.metrics else {
+            return Ok(());
+        };
+        let local_bundle = self.local_bundle()?;
+        let repository = self.envelope_repository()?;
+        self.publish_mailbox_usage(metrics, repository.as_ref(), local_bundle.agent_id)
+            .await
+    }
+
+    async fn publish_mailbox_usage(
+        &self,
+        metrics: &MailboxMetrics,
+        repository: &dyn EnvelopeRepository,
+        agent_id: AgentId,
+    ) -> CretoResult<()> {
+        let usage = repository.mailbox_usage(agent_id).await?;
+        let agent = agent_id.to_string();
+        metrics
+            .undelivered
+            .with_label_values(&[&agent])
+            .set(usage.undelivered_count as i64);
+        metrics
+            .stored_bytes
+            .with_label_values(&[&agent])
+            .set(usage.undelivered_bytes as i64);
+        Ok(())
+    }
+
+    /// Acknowledge a stored envelope addressed to the local agent, recording
+    /// a [`ReceiptType::Read`] receipt.
+    ///
+    /// Acknowledging an envelope again is a no-op.
+    pub async fn acknowledge(&self, envelope_id: Uuid) -> CretoResult<()> {
+        let local_bundle = self.local_bundle()?;
+        let repository = self.envelope_repository()?;
+
+        let record = repository
+            .get(envelope_id)
+            .await?
+            .ok_or_else(|| CretoError::NotFound(format!("Envelope {}", envelope_id)))?;
+        if record.recipient_id != local_bundle.agent_id {
+            return Err(CretoError::SessionError(
+                "Not the intended recipient".to_string(),
+            ));
+        }
+
+        repository
+            .record_receipt(envelope_id, ReceiptType::Read, Utc::now())
+            .await
+    }
+
+    /// Delivery status of a stored envelope sent or received by the local
+    /// agent, derived from its receipts.
+    ///
+    /// An envelope removed by expiry cleanup is no longer known and fails
+    /// with [`CretoError::NotFound`].
+    pub async fn message_status(&self, envelope_id: Uuid) -> CretoResult<MessageStatus> {
+        let local_bundle = self.local_bundle()?;
+        let repository = self.envelope_repository()?;
+
+        let record = repository
+            .get(envelope_id)
+            .await?
+            .filter(|r| {
+                r.sender_id == local_bundle.agent_id || r.recipient_id == local_bundle.agent_id
+            })
+            .ok_or_else(|| CretoError::NotFound(format!("Envelope {}", envelope_id)))?;
+        let receipts = repository.get_receipts(envelope_id).await?;
+        let has = |receipt_type| receipts.iter().any(|r| r.receipt_type == receipt_type);
+
+        let status = if has(ReceiptType::Read) {
+            MessageStatus::Read
+        } else if record.delivered || has(ReceiptType::Delivered) {
+            MessageStatus::Delivered
+        } else if record.expires_at.is_some_and(|at| at <= Utc::now()) {
+            MessageStatus::Expired
+        } else {
+            MessageStatus::Sent
+        };
+        Ok(status)
     }
 
     /// Close a session.
@@ -238,9 +1007,29 @@ impl MessagingService {
             if let Some(store) = &self.session_store {
                 store.store_session(session).await?;
             }
+
+            // A closed session is never resumed; drop its keys
+            if let Some(repository) = &self.session_repository {
+                if let Some(record) = repository
+                    .get(session.local_agent, session.remote_agent)
+                    .await?
+                    .filter(|r| r.ratchet_state_id == Some(session_id))
+                {
+                    repository
+                        .update_state(record.id, SessionState::Closed)
+                        .await?;
+                }
+            }
+            if let Some(ratchet_store) = &self.ratchet_store {
+                ratchet_store.repository.delete(session_id).await?;
+            }
         }
 
         sessions.remove(&session_id);
+        self.unsaved_ratchet_steps
+            .lock()
+            .unwrap()
+            .remove(&session_id);
 
         Ok(())
     }
@@ -251,6 +1040,32 @@ impl MessagingService {
         sessions.get(&session_id).map(|s| s.state)
     }
 
+    /// Metadata of every cached session, without ratchet state, most
+    /// recently active first.
+    pub async fn session_summaries(&self) -> Vec<SessionMetadata> {
+        let mut summaries: Vec<SessionMetadata> = self
+            .sessions
+            .read()
+            .await
+            .values()
+            .map(|session| SessionMetadata {
+                id: session.id,
+                local_agent: session.local_agent,
+                remote_agent: session.remote_agent,
+                state: session.state,
+                created_at: session.created_at,
+                last_active_at: session.last_active_at,
+                ratchet_state: None,
+            })
+            .collect();
+        summaries.sort_by(|a, b| {
+            b.last_active_at
+                .cmp(&a.last_active_at)
+                .then(a.id.cmp(&b.id))
+        });
+        summaries
+    }
+
     /// List active sessions.
     pub async fn list_sessions(&self) -> Vec<Uuid> {
         let sessions = self.sessions.read().await;
@@ -263,8 +1078,7 @@ impl MessagingService {
 
     /// Create a new topic.
     pub async fn create_topic(&self, config: TopicConfig) -> CretoResult<TopicId> {
-        let mut manager = self.topic_manager.write().await;
-        manager.create_topic(config)
+        self.topic_manager.create_topic(config).await
     }
 
     /// Subscribe to a topic.
@@ -273,12 +1087,41 @@ impl MessagingService {
         topic_id: TopicId,
         filter: Option<SubscriptionFilter>,
     ) -> CretoResult<Subscription> {
-        let local_bundle = self.local_bundle.as_ref().ok_or_else(|| {
-            creto_common::CretoError::SessionError("Service not initialized".to_string())
-        })?;
+        let local_bundle = self.local_bundle()?;
 
-        let mut manager = self.topic_manager.write().await;
-        manager.subscribe(topic_id, local_bundle.agent_id, filter)
+        self.topic_manager
+            .subscribe(topic_id, local_bundle.agent_id, filter)
+            .await
+    }
+
+    /// Subscribe to a topic starting from `start`, returning the retained
+    /// messages to replay before live delivery.
+    pub async fn subscribe_from(
+        &self,
+        topic_id: TopicId,
+        filter: Option<SubscriptionFilter>,
+        start: SubscriptionStart,
+    ) -> CretoResult<(Subscription, Vec<TopicMessage>)> {
+        let local_bundle = self.local_bundle()?;
+
+        self.topic_manager
+            .subscribe_from(topic_id, local_bundle.agent_id, filter, start)
+            .await
+    }
+
+    /// Subscribe to a topic, returning the retained messages `replay`
+    /// selects so the local agent can catch up.
+    pub async fn subscribe_with_replay(
+        &self,
+        topic_id: TopicId,
+        filter: Option<SubscriptionFilter>,
+        replay: ReplayPolicy,
+    ) -> CretoResult<(Subscription, Vec<TopicMessage>)> {
+        let local_bundle = self.local_bundle()?;
+
+        self.topic_manager
+            .subscribe_with_replay(topic_id, local_bundle.agent_id, filter, replay)
+            .await
     }
 
     /// Publish a message to a topic.
@@ -287,33 +1130,42 @@ impl MessagingService {
         topic_id: TopicId,
         message: &[u8],
         metadata: std::collections::HashMap<String, String>,
-    ) -> CretoResult<Vec<AgentId>> {
-        let local_bundle = self.local_bundle.as_ref().ok_or_else(|| {
-            creto_common::CretoError::SessionError("Service not initialized".to_string())
-        })?;
+    ) -> CretoResult<Vec<TopicDelivery>> {
+        let local_bundle = self.local_bundle()?;
+        self.acquire_rate_limit(local_bundle.agent_id, message.len())?;
+
+        self.topic_manager
+            .publish(topic_id, local_bundle.agent_id, message, metadata)
+            .await
+    }
 
-        let mut manager = self.topic_manager.write().await;
-        manager.publish(topic_id, local_bundle.agent_id, message, metadata)
+    /// Read up to `limit` retained messages for a subscription after the
+    /// message `after`.
+    pub async fn poll_subscription(
+        &self,
+        subscription_id: Uuid,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> CretoResult<Vec<TopicMessage>> {
+        self.topic_manager.poll(subscription_id, after, limit).await
     }
 
     /// Unsubscribe from a topic.
     pub async fn unsubscribe(&self, subscription_id: Uuid) -> CretoResult<()> {
-        let local_bundle = self.local_bundle.as_ref().ok_or_else(|| {
-            creto_common::CretoError::SessionError("Service not initialized".to_string())
-        })?;
+        let local_bundle = self.local_bundle()?;
 
-        let mut manager = self.topic_manager.write().await;
-        manager.unsubscribe(subscription_id, local_bundle.agent_id)
+        self.topic_manager
+            .unsubscribe(subscription_id, local_bundle.agent_id)
+            .await
     }
 
     /// Delete a topic.
     pub async fn delete_topic(&self, topic_id: TopicId) -> CretoResult<()> {
-        let local_bundle = self.local_bundle.as_ref().ok_or_else(|| {
-            creto_common::CretoError::SessionError("Service not initialized".to_string())
-        })?;
+        let local_bundle = self.local_bundle()?;
 
-        let mut manager = self.topic_manager.write().await;
-        manager.delete_topic(topic_id, local_bundle.agent_id)
+        self.topic_manager
+            .delete_topic(topic_id, local_bundle.agent_id)
+            .await
     }
 
     /// List subscribers to a topic.
@@ -321,11 +1173,175 @@ impl MessagingService {
         &self,
         topic_id: TopicId,
     ) -> CretoResult<Vec<Subscription>> {
-        let manager = self.topic_manager.read().await;
-        manager.list_subscribers(topic_id)
+        self.topic_manager.list_subscribers(topic_id).await
+    }
+}
+
+impl MessagingService {
+    /// Take rate limit tokens for a message of `bytes` payload bytes from
+    /// `agent_id`.
+    fn acquire_rate_limit(&self, agent_id: AgentId, bytes: usize) -> CretoResult<()> {
+        if let Some((limiter, organization_id)) = &self.rate_limiter {
+            limiter.acquire(*organization_id, agent_id, bytes)?;
+        }
+        Ok(())
+    }
+
+    fn local_bundle(&self) -> CretoResult<Arc<KeyBundle>> {
+        self.local_bundle
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| CretoError::SessionError("Service not initialized".to_string()))
+    }
+
+    /// Consume one of `agent_id`'s published one-time pre-keys.
+    async fn consume_one_time_prekey(&self, agent_id: AgentId) -> CretoResult<Option<PreKey>> {
+        let Some(repository) = &self.pre_key_repository else {
+            return self.key_store()?.consume_pre_key(agent_id).await;
+        };
+        let Some((prekey_id, public_key)) = repository.consume(agent_id).await? else {
+            return Ok(None);
+        };
+        let id = u32::try_from(prekey_id)
+            .map_err(|_| CretoError::CryptoError(format!("Invalid pre-key ID {}", prekey_id)))?;
+        Ok(Some(PreKey {
+            id,
+            public_key,
+            private_key: None,
+        }))
+    }
+
+    /// Take the private half of a one-time pre-key named by a handshake,
+    /// so it cannot be used again.
+    fn take_one_time_prekey(
+        &self,
+        local_bundle: &KeyBundle,
+        prekey_id: u32,
+    ) -> CretoResult<PreKey> {
+        if let Some(key) = self
+            .one_time_pre_keys
+            .lock()
+            .unwrap()
+            .held
+            .remove(&prekey_id)
+        {
+            return Ok(key);
+        }
+        // Handshakes from initiators that used the bundle's own pre-key.
+        // Initiators never see it when a repository serves pre-keys, and
+        // repository IDs may reuse its ID.
+        local_bundle
+            .one_time_pre_key
+            .clone()
+            .filter(|key| self.pre_key_repository.is_none() && key.id == prekey_id)
+            .ok_or_else(|| {
+                CretoError::CryptoError(format!(
+                    "One-time pre-key {} is unknown or already used",
+                    prekey_id
+                ))
+            })
+    }
+
+    /// Write a new session's ratchet state and record, linking the two.
+    async fn persist_new_session(&self, session: &Session) -> CretoResult<()> {
+        if self.ratchet_store.is_some() {
+            self.save_ratchet_state(session).await?;
+        }
+        if let Some(repository) = &self.session_repository {
+            let record_id = repository
+                .upsert(session.local_agent, session.remote_agent)
+                .await?;
+            repository.update_state(record_id, session.state).await?;
+            if self.ratchet_store.is_some() {
+                repository.link_ratchet_state(record_id, session.id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Count a ratchet step on `session` and write its state back when the
+    /// flush policy says so.
+    ///
+    /// A failed write is logged rather than failing the message; the steps
+    /// stay unsaved so the next step retries.
+    async fn ratchet_stepped(&self, session: &Session) {
+        if self.ratchet_store.is_none() {
+            return;
+        }
+        let due = {
+            let mut unsaved = self.unsaved_ratchet_steps.lock().unwrap();
+            let steps = unsaved.entry(session.id).or_default();
+            *steps += 1;
+            self.ratchet_flush_policy.should_flush(*steps)
+        };
+        if due {
+            if let Err(e) = self.save_ratchet_state(session).await {
+                tracing::warn!(
+                    session_id = %session.id,
+                    error = %e,
+                    "Failed to persist ratchet state"
+                );
+            }
+        }
+    }
+
+    async fn save_ratchet_state(&self, session: &Session) -> CretoResult<()> {
+        let ratchet_store = self.ratchet_store()?;
+        let encrypted = ratchet_store
+            .key
+            .seal(session.id, session.ratchet_state())?;
+        ratchet_store.repository.save(&encrypted).await?;
+        self.unsaved_ratchet_steps
+            .lock()
+            .unwrap()
+            .remove(&session.id);
+        Ok(())
+    }
+
+    fn ratchet_store(&self) -> CretoResult<&RatchetStore> {
+        self.ratchet_store.as_ref().ok_or_else(|| {
+            CretoError::SessionError("No ratchet state repository configured".to_string())
+        })
+    }
+
+    fn session_repository(&self) -> CretoResult<&Arc<dyn SessionRepository>> {
+        self.session_repository
+            .as_ref()
+            .ok_or_else(|| CretoError::SessionError("No session repository configured".to_string()))
+    }
+
+    fn envelope_repository(&self) -> CretoResult<&Arc<dyn EnvelopeRepository>> {
+        self.envelope_repository.as_ref().ok_or_else(|| {
+            CretoError::SessionError("No envelope repository configured".to_string())
+        })
+    }
+
+    fn key_store(&self) -> CretoResult<&Arc<dyn KeyStore>> {
+        self.key_store
+            .as_ref()
+            .ok_or_else(|| CretoError::SessionError("No key store configured".to_string()))
     }
 }
 
+/// Where and under which key ratchet state is persisted.
+struct RatchetStore {
+    repository: Arc<dyn RatchetStateRepository>,
+    key: StateEncryptionKey,
+}
+
+/// The local agent's published one-time pre-keys.
+///
+/// Held in memory only; [`MessagingService::initialize`] discards published
+/// pre-keys whose private halves a previous run held.
+#[derive(Default)]
+struct OneTimePreKeys {
+    /// Private halves by ID, removed as handshakes use them.
+    held: HashMap<u32, PreKey>,
+    /// Highest ID issued so far.
+    last_id: u32,
+}
+
 impl Default for MessagingService {
     fn default() -> Self {
         Self::new()
@@ -351,4 +1367,515 @@ mod tests {
         // Note: This will succeed but session establishment requires key store
         service.initialize(agent_id).await.unwrap();
     }
+
+    #[tokio::test]
+    async fn test_handshake_across_signed_prekey_rotation() {
+        let store: Arc<dyn KeyStore> = Arc::new(crate::keys::InMemoryKeyStore::new());
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let bob_id = AgentId::new();
+        let mut bob = MessagingService::new()
+            .with_key_store(Arc::clone(&store))
+            .with_rotation_policy(SignedPreKeyRotationPolicy {
+                overlap_window: chrono::Duration::minutes(10),
+                ..Default::default()
+            })
+            .with_clock(clock.clone());
+        bob.initialize(bob_id).await.unwrap();
+
+        // Alice fetches Bob's bundle and starts a handshake just before he
+        // rotates.
+        let alice = KeyBundle::new(AgentId::new());
+        let served = store.get_bundle(bob_id).await.unwrap().unwrap();
+        let x3dh = X3DH::initiate(&alice, &served.public_bundle()).unwrap();
+
+        let new_id = bob.rotate_signed_prekey(bob_id).await.unwrap();
+        assert_ne!(new_id, x3dh.params.recipient_signed_prekey_id);
+        let served = store.get_bundle(bob_id).await.unwrap().unwrap();
+        assert_eq!(served.signed_pre_key.id, new_id);
+
+        // Within the overlap window the previous key is still accepted.
+        let session_id = bob.accept_session(&x3dh.params).await.unwrap();
+        assert_eq!(
+            bob.session_status(session_id).await,
+            Some(SessionState::Active)
+        );
+
+        // After it closes the handshake is rejected and the key destroyed.
+        clock.advance(chrono::Duration::minutes(11));
+        let err = bob.accept_session(&x3dh.params).await.unwrap_err();
+        assert!(matches!(err, CretoError::CryptoError(_)));
+        assert_eq!(bob.prune_signed_prekeys(bob_id).await.unwrap(), 1);
+        assert!(store
+            .retained_signed_pre_keys(bob_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Handshakes against the new key are unaffected.
+        let x3dh = X3DH::initiate(&alice, &served.public_bundle()).unwrap();
+        bob.accept_session(&x3dh.params).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sealed_envelope_roundtrip() {
+        let store: Arc<dyn KeyStore> = Arc::new(crate::keys::InMemoryKeyStore::new());
+        let (alice_id, bob_id) = (AgentId::new(), AgentId::new());
+        let mut alice = MessagingService::new().with_key_store(Arc::clone(&store));
+        alice.initialize(alice_id).await.unwrap();
+        let mut bob = MessagingService::new().with_key_store(Arc::clone(&store));
+        bob.initialize(bob_id).await.unwrap();
+        assert_eq!(bob.local_agent(), Some(bob_id));
+
+        let (alice_session, params) = alice.initiate_session(bob_id).await.unwrap();
+        let bob_session = bob.accept_session(&params).await.unwrap();
+
+        let envelope = alice.seal(alice_session, b"ping").await.unwrap();
+        assert_eq!(bob.process_envelope(&envelope).await.unwrap(), b"ping");
+        let envelope = alice.seal(alice_session, b"ping again").await.unwrap();
+        assert_eq!(
+            bob.open(bob_session, &envelope).await.unwrap(),
+            b"ping again"
+        );
+
+        // Bob is not the recipient of his own outbound messages.
+        let (bob_outbound, _) = bob.initiate_session(alice_id).await.unwrap();
+        let envelope = bob.seal(bob_outbound, b"pong").await.unwrap();
+        assert!(bob.open(bob_session, &envelope).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_refuses_sends_and_sessions() {
+        use crate::rate_limit::{RateLimit, RateLimits};
+
+        let store: Arc<dyn KeyStore> = Arc::new(crate::keys::InMemoryKeyStore::new());
+        let limiter = Arc::new(MessageRateLimiter::new(RateLimits {
+            agent: RateLimit::messages(1.0, 2),
+            ..Default::default()
+        }));
+        let org = OrganizationId::new();
+        let (alice_id, bob_id) = (AgentId::new(), AgentId::new());
+        let mut alice = MessagingService::new()
+            .with_key_store(Arc::clone(&store))
+            .with_rate_limiter(Arc::clone(&limiter), org);
+        alice.initialize(alice_id).await.unwrap();
+        let mut bob = MessagingService::new()
+            .with_key_store(store)
+            .with_rate_limiter(limiter, org);
+        bob.initialize(bob_id).await.unwrap();
+
+        // Establishing the session takes one of Alice's two tokens
+        let (session_id, params) = alice.initiate_session(bob_id).await.unwrap();
+        bob.accept_session(&params).await.unwrap();
+        alice.seal(session_id, b"ping").await.unwrap();
+
+        let err = alice.seal(session_id, b"ping").await.unwrap_err();
+        assert!(matches!(err, CretoError::LimitExceeded(_)));
+        assert!(err.to_string().contains("retry after"));
+        let err = alice.initiate_session(bob_id).await.unwrap_err();
+        assert!(matches!(err, CretoError::LimitExceeded(_)));
+
+        // Bob has his own budget
+        let (bob_session, _) = bob.initiate_session(alice_id).await.unwrap();
+        bob.seal(bob_session, b"pong").await.unwrap();
+    }
+
+    /// Alice and Bob with an open session and a shared envelope store.
+    async fn mailbox_pair(
+        envelopes: Arc<crate::mailbox::InMemoryEnvelopeRepository>,
+    ) -> (MessagingService, MessagingService, Uuid) {
+        let store: Arc<dyn KeyStore> = Arc::new(crate::keys::InMemoryKeyStore::new());
+        let mut alice = MessagingService::new()
+            .with_key_store(Arc::clone(&store))
+            .with_envelope_repository(envelopes.clone());
+        alice.initialize(AgentId::new()).await.unwrap();
+        let mut bob = MessagingService::new()
+            .with_key_store(store)
+            .with_envelope_repository(envelopes);
+        bob.initialize(AgentId::new()).await.unwrap();
+
+        let (session_id, params) = alice
+            .initiate_session(bob.local_agent().unwrap())
+            .await
+            .unwrap();
+        bob.accept_session(&params).await.unwrap();
+        (alice, bob, session_id)
+    }
+
+    #[tokio::test]
+    async fn test_mailbox_metrics() {
+        let registry = MetricsRegistry::new();
+        let envelopes = Arc::new(crate::mailbox::InMemoryEnvelopeRepository::new());
+        let mut carol = MessagingService::new()
+            .with_key_store(Arc::new(crate::keys::InMemoryKeyStore::new()))
+            .with_envelope_repository(envelopes.clone())
+            .with_metrics(&registry);
+        let carol_id = AgentId::new();
+        carol.initialize(carol_id).await.unwrap();
+
+        for _ in 0..2 {
+            let header = crate::ratchet::MessageHeader {
+                dh_public: vec![0u8; 32],
+                prev_chain_length: 0,
+                message_number: 0,
+            };
+            let envelope = Envelope::new(AgentId::new(), carol_id, header, vec![0u8; 16]);
+            envelopes.store(&envelope).await.unwrap();
+        }
+        carol.refresh_metrics().await.unwrap();
+
+        let label = format!("{{agent=\"{}\"}}", carol_id);
+        let text = registry.render_metrics();
+        assert!(text.contains(&format!(
+            "creto_messaging_undelivered_envelopes{} 2\n",
+            label
+        )));
+        assert!(text.contains(&format!(
+            "creto_messaging_stored_envelope_bytes{} 32\n",
+            label
+        )));
+
+        assert_eq!(carol.get_undelivered(10).await.unwrap().len(), 2);
+        let text = registry.render_metrics();
+        assert!(text.contains(&format!(
+            "creto_messaging_undelivered_envelopes{} 0\n",
+            label
+        )));
+        assert!(text.contains("creto_messaging_envelopes_delivered_total 2\n"));
+    }
+
+    #[tokio::test]
+    async fn test_message_status_follows_receipts() {
+        let envelopes = Arc::new(crate::mailbox::InMemoryEnvelopeRepository::new());
+        let (alice, bob, session_id) = mailbox_pair(envelopes.clone()).await;
+
+        let envelope = alice.seal(session_id, b"ping").await.unwrap();
+        let id = envelopes.store(&envelope).await.unwrap();
+        assert_eq!(alice.message_status(id).await.unwrap(), MessageStatus::Sent);
+
+        let fetched = bob.get_undelivered(10).await.unwrap();
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(
+            bob.process_envelope(fetched[0].envelope.as_ref().unwrap())
+                .await
+                .unwrap(),
+            b"ping"
+        );
+        assert_eq!(
+            alice.message_status(id).await.unwrap(),
+            MessageStatus::Delivered
+        );
+        assert!(bob.get_undelivered(10).await.unwrap().is_empty());
+
+        // Only the recipient acknowledges, and acknowledging twice or
+        // replaying a receipt records nothing new
+        assert!(alice.acknowledge(id).await.is_err());
+        bob.acknowledge(id).await.unwrap();
+        bob.acknowledge(id).await.unwrap();
+        envelopes
+            .record_receipt(id, ReceiptType::Delivered, Utc::now())
+            .await
+            .unwrap();
+        let receipts: Vec<_> = envelopes
+            .get_receipts(id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.receipt_type)
+            .collect();
+        assert_eq!(receipts, [ReceiptType::Delivered, ReceiptType::Read]);
+        assert_eq!(alice.message_status(id).await.unwrap(), MessageStatus::Read);
+        assert_eq!(bob.message_status(id).await.unwrap(), MessageStatus::Read);
+
+        // Third parties cannot see it
+        let mut eve = MessagingService::new().with_envelope_repository(envelopes);
+        eve.initialize(AgentId::new()).await.unwrap();
+        assert!(matches!(
+            eve.message_status(id).await,
+            Err(CretoError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_envelope_expires_before_delivery() {
+        let envelopes = Arc::new(
+            crate::mailbox::InMemoryEnvelopeRepository::new().with_mailbox_config(
+                crate::mailbox::MailboxConfig {
+                    envelope_ttl_seconds: Some(0),
+                    ..Default::default()
+                },
+            ),
+        );
+        let (alice, bob, session_id) = mailbox_pair(envelopes.clone()).await;
+
+        let envelope = alice.seal(session_id, b"too late").await.unwrap();
+        let id = envelopes.store(&envelope).await.unwrap();
+
+        assert!(bob.get_undelivered(10).await.unwrap().is_empty());
+        assert_eq!(
+            alice.message_status(id).await.unwrap(),
+            MessageStatus::Expired
+        );
+        assert!(envelopes.get_receipts(id).await.unwrap().is_empty());
+
+        // Cleanup forgets it entirely
+        assert_eq!(envelopes.cleanup_expired().await.unwrap(), 1);
+        assert!(matches!(
+            alice.message_status(id).await,
+            Err(CretoError::NotFound(_))
+        ));
+        assert!(bob.acknowledge(id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replenish_prekeys_allocates_unused_ids() {
+        let store: Arc<dyn KeyStore> = Arc::new(crate::keys::InMemoryKeyStore::new());
+        let prekeys = Arc::new(crate::keys::InMemoryPreKeyRepository::new());
+        let bob_id = AgentId::new();
+        let mut bob = MessagingService::new()
+            .with_key_store(store)
+            .with_pre_key_repository(prekeys.clone());
+        bob.initialize(bob_id).await.unwrap();
+        let bob = Arc::new(bob);
+
+        // IDs continue past keys already in the repository, consumed or not
+        prekeys.store(bob_id, 7, &[0u8; 32]).await.unwrap();
+        prekeys.consume(bob_id).await.unwrap();
+        assert_eq!(bob.replenish_prekeys(bob_id, 3).await.unwrap(), [8, 9, 10]);
+
+        let batches: Vec<_> = (0..4)
+            .map(|_| {
+                let bob = Arc::clone(&bob);
+                tokio::spawn(async move { bob.replenish_prekeys(bob_id, 5).await })
+            })
+            .collect();
+        let mut ids = Vec::new();
+        for batch in batches {
+            ids.extend(batch.await.unwrap().unwrap());
+        }
+        ids.sort_unstable();
+        assert_eq!(ids, (11..31).collect::<Vec<u32>>());
+        assert_eq!(prekeys.count_available(bob_id).await.unwrap(), 23);
+
+        let err = bob.replenish_prekeys(AgentId::new(), 1).await.unwrap_err();
+        assert!(matches!(err, CretoError::SessionError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_restart_discards_prekeys_of_previous_run() {
+        let store: Arc<dyn KeyStore> = Arc::new(crate::keys::InMemoryKeyStore::new());
+        let prekeys = Arc::new(crate::keys::InMemoryPreKeyRepository::new());
+        let bob_id = AgentId::new();
+        let service = || {
+            MessagingService::new()
+                .with_key_store(Arc::clone(&store))
+                .with_pre_key_repository(prekeys.clone())
+        };
+        let mut bob = service();
+        bob.initialize(bob_id).await.unwrap();
+        assert_eq!(bob.replenish_prekeys(bob_id, 3).await.unwrap(), [1, 2, 3]);
+
+        // The restarted service cannot answer for the old private halves
+        let mut bob = service();
+        bob.initialize(bob_id).await.unwrap();
+        assert_eq!(prekeys.count_available(bob_id).await.unwrap(), 0);
+        assert_eq!(bob.replenish_prekeys(bob_id, 2).await.unwrap(), [4, 5]);
+
+        let mut alice = service();
+        alice.initialize(AgentId::new()).await.unwrap();
+        let (_, params) = alice.initiate_session(bob_id).await.unwrap();
+        assert_eq!(params.recipient_one_time_prekey_id, Some(4));
+        bob.accept_session(&params).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_prekey_status_watermark_boundary() {
+        let store: Arc<dyn KeyStore> = Arc::new(crate::keys::InMemoryKeyStore::new());
+        let prekeys = Arc::new(crate::keys::InMemoryPreKeyRepository::new());
+        let bob_id = AgentId::new();
+        let mut bob = MessagingService::new()
+            .with_key_store(store)
+            .with_pre_key_repository(prekeys.clone())
+            .with_prekey_threshold(3);
+        bob.initialize(bob_id).await.unwrap();
+
+        bob.replenish_prekeys(bob_id, 3).await.unwrap();
+        let status = bob.prekey_status(bob_id).await.unwrap();
+        assert_eq!(status.available, 3);
+        assert!(!status.below_watermark);
+        assert!(prekeys
+            .find_agents_below_watermark(3)
+            .await
+            .unwrap()
+            .is_empty());
+
+        prekeys.consume(bob_id).await.unwrap();
+        let status = bob.prekey_status(bob_id).await.unwrap();
+        assert_eq!(status.available, 2);
+        assert!(status.below_watermark);
+        assert_eq!(
+            prekeys.find_agents_below_watermark(3).await.unwrap(),
+            [(bob_id, 2)]
+        );
+
+        bob.replenish_prekeys(bob_id, 1).await.unwrap();
+        assert!(!bob.prekey_status(bob_id).await.unwrap().below_watermark);
+    }
+
+    /// Storage that outlives a service instance.
+    #[derive(Clone)]
+    struct Persistence {
+        ratchet_states: Arc<crate::ratchet_store::InMemoryRatchetStateRepository>,
+        sessions: Arc<crate::session::InMemorySessionRepository>,
+    }
+
+    impl Persistence {
+        fn new() -> Self {
+            Self {
+                ratchet_states: Arc::new(Default::default()),
+                sessions: Arc::new(Default::default()),
+            }
+        }
+
+        async fn service(
+            &self,
+            store: Arc<dyn KeyStore>,
+            agent_id: AgentId,
+            key: u8,
+        ) -> MessagingService {
+            let mut service = MessagingService::new()
+                .with_key_store(store)
+                .with_session_repository(self.sessions.clone())
+                .with_ratchet_state_repository(
+                    self.ratchet_states.clone(),
+                    StateEncryptionKey::new([key; 32]),
+                );
+            service.initialize(agent_id).await.unwrap();
+            service
+        }
+    }
+
+    /// Alice without persistence and Bob persisting with key byte `key`.
+    async fn persisted_pair(
+        persistence: &Persistence,
+        key: u8,
+    ) -> (MessagingService, MessagingService, Uuid) {
+        let store: Arc<dyn KeyStore> = Arc::new(crate::keys::InMemoryKeyStore::new());
+        let mut alice = MessagingService::new().with_key_store(Arc::clone(&store));
+        alice.initialize(AgentId::new()).await.unwrap();
+        let bob = persistence.service(store, AgentId::new(), key).await;
+
+        let (session_id, params) = alice
+            .initiate_session(bob.local_agent().unwrap())
+            .await
+            .unwrap();
+        bob.accept_session(&params).await.unwrap();
+        (alice, bob, session_id)
+    }
+
+    #[tokio::test]
+    async fn test_resumed_session_decrypts_message_sent_before_restart() {
+        let persistence = Persistence::new();
+        let (alice, bob, session_id) = persisted_pair(&persistence, 7).await;
+        let (alice_id, bob_id) = (alice.local_agent().unwrap(), bob.local_agent().unwrap());
+
+        let first = alice.seal(session_id, b"first").await.unwrap();
+        assert_eq!(bob.process_envelope(&first).await.unwrap(), b"first");
+        let second = alice.seal(session_id, b"second").await.unwrap();
+
+        let record = persistence
+            .sessions
+            .get(bob_id, alice_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.state, SessionState::Active);
+        let bob_session_id = record.ratchet_state_id.unwrap();
+        assert!(persistence
+            .ratchet_states
+            .load(bob_session_id)
+            .await
+            .unwrap()
+            .is_some());
+
+        // Restart Bob
+        drop(bob);
+        let bob = persistence
+            .service(Arc::new(crate::keys::InMemoryKeyStore::new()), bob_id, 7)
+            .await;
+        assert!(bob.list_sessions().await.is_empty());
+
+        assert_eq!(
+            bob.resume_session(bob_id, alice_id).await.unwrap(),
+            bob_session_id
+        );
+        assert_eq!(bob.process_envelope(&second).await.unwrap(), b"second");
+
+        // Closing drops the persisted keys
+        bob.close_session(bob_session_id).await.unwrap();
+        assert!(persistence
+            .ratchet_states
+            .load(bob_session_id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(matches!(
+            bob.resume_session(bob_id, alice_id).await.unwrap_err(),
+            CretoError::SessionError(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resume_with_wrong_key_fails_cleanly() {
+        let persistence = Persistence::new();
+        let (alice, bob, _) = persisted_pair(&persistence, 7).await;
+        let (alice_id, bob_id) = (alice.local_agent().unwrap(), bob.local_agent().unwrap());
+        drop(bob);
+
+        let bob = persistence
+            .service(Arc::new(crate::keys::InMemoryKeyStore::new()), bob_id, 8)
+            .await;
+        let err = bob.resume_session(bob_id, alice_id).await.unwrap_err();
+        assert!(matches!(err, CretoError::CryptoError(_)));
+        assert!(bob.list_sessions().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batched_flush_policy_defers_writes() {
+        let persistence = Persistence::new();
+        let store: Arc<dyn KeyStore> = Arc::new(crate::keys::InMemoryKeyStore::new());
+        let alice = persistence
+            .service(Arc::clone(&store), AgentId::new(), 7)
+            .await
+            .with_ratchet_flush_policy(RatchetFlushPolicy::Batched { max_steps: 3 });
+        let bob = persistence.service(store, AgentId::new(), 7).await;
+        let (session_id, params) = alice
+            .initiate_session(bob.local_agent().unwrap())
+            .await
+            .unwrap();
+        bob.accept_session(&params).await.unwrap();
+
+        let saved = || async {
+            persistence
+                .ratchet_states
+                .load(session_id)
+                .await
+                .unwrap()
+                .unwrap()
+                .updated_at
+        };
+        let initial = saved().await;
+
+        alice.seal(session_id, b"one").await.unwrap();
+        alice.seal(session_id, b"two").await.unwrap();
+        assert_eq!(saved().await, initial);
+        assert_eq!(alice.flush_ratchet_states().await.unwrap(), 1);
+        let flushed = saved().await;
+        assert!(flushed > initial);
+        assert_eq!(alice.flush_ratchet_states().await.unwrap(), 0);
+
+        for _ in 0..3 {
+            alice.seal(session_id, b"more").await.unwrap();
+        }
+        assert!(saved().await > flushed);
+    }
 }
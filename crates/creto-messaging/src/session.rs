@@ -1,13 +1,14 @@
 //! Messaging sessions between agents.
 
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, CretoResult};
+use creto_common::{AgentId, CretoError, CretoResult};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
     envelope::Envelope,
     ratchet::{DoubleRatchet, EncryptedMessage, RatchetState},
+    repository::{SessionRecord, SessionRepository},
     x3dh::X3DHResult,
 };
 
@@ -81,6 +82,20 @@ impl Session {
         }
     }
 
+    /// Rebuild a session from stored metadata and its decrypted ratchet
+    /// state.
+    pub fn restore(metadata: SessionMetadata, ratchet_state: RatchetState) -> Self {
+        Self {
+            id: metadata.id,
+            local_agent: metadata.local_agent,
+            remote_agent: metadata.remote_agent,
+            ratchet: DoubleRatchet::from_state(ratchet_state),
+            state: metadata.state,
+            created_at: metadata.created_at,
+            last_active_at: metadata.last_active_at,
+        }
+    }
+
     /// Encrypt a message.
     pub fn encrypt(&mut self, plaintext: &[u8]) -> CretoResult<Envelope> {
         if self.state != SessionState::Active {
@@ -229,6 +244,97 @@ pub trait SessionStore: Send + Sync {
     async fn delete_session(&self, id: Uuid) -> CretoResult<()>;
 }
 
+/// In-memory [`SessionRepository`], one record per agent pair.
+#[derive(Default)]
+pub struct InMemorySessionRepository {
+    records: std::sync::Mutex<Vec<SessionRecord>>,
+}
+
+impl InMemorySessionRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn update(&self, id: Uuid, apply: impl FnOnce(&mut SessionRecord)) -> CretoResult<()> {
+        let mut records = self.records.lock().unwrap();
+        let record = records
+            .iter_mut()
+            .find(|r| r.id == id)
+            .ok_or_else(|| CretoError::NotFound(format!("Session {}", id)))?;
+        apply(record);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl SessionRepository for InMemorySessionRepository {
+    async fn upsert(&self, local_agent_id: AgentId, remote_agent_id: AgentId) -> CretoResult<Uuid> {
+        let mut records = self.records.lock().unwrap();
+        let now = Utc::now();
+        if let Some(record) = records
+            .iter_mut()
+            .find(|r| r.local_agent_id == local_agent_id && r.remote_agent_id == remote_agent_id)
+        {
+            record.last_active_at = now;
+            return Ok(record.id);
+        }
+
+        let id = Uuid::now_v7();
+        records.push(SessionRecord {
+            id,
+            local_agent_id,
+            remote_agent_id,
+            state: SessionState::Establishing,
+            created_at: now,
+            last_active_at: now,
+            ratchet_state_id: None,
+        });
+        Ok(id)
+    }
+
+    async fn get(
+        &self,
+        local_agent_id: AgentId,
+        remote_agent_id: AgentId,
+    ) -> CretoResult<Option<SessionRecord>> {
+        Ok(self
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|r| r.local_agent_id == local_agent_id && r.remote_agent_id == remote_agent_id)
+            .cloned())
+    }
+
+    async fn update_state(&self, id: Uuid, state: SessionState) -> CretoResult<()> {
+        self.update(id, |record| {
+            record.state = state;
+            record.last_active_at = Utc::now();
+        })
+    }
+
+    async fn link_ratchet_state(&self, id: Uuid, ratchet_state_id: Uuid) -> CretoResult<()> {
+        self.update(id, |record| {
+            record.ratchet_state_id = Some(ratchet_state_id)
+        })
+    }
+
+    async fn list_active(&self, agent_id: AgentId) -> CretoResult<Vec<SessionRecord>> {
+        let mut active: Vec<SessionRecord> = self
+            .records
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.local_agent_id == agent_id || r.remote_agent_id == agent_id)
+            .filter(|r| matches!(r.state, SessionState::Establishing | SessionState::Active))
+            .cloned()
+            .collect();
+        active.sort_by_key(|r| std::cmp::Reverse(r.last_active_at));
+        Ok(active)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
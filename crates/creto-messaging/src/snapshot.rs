@@ -0,0 +1,147 @@
+//! Messaging section of an organization snapshot.
+//!
+//! A [`MessagingService`] serves one local agent and knows nothing of
+//! organizations, so the contributor is given each service together with the
+//! organization its agent belongs to. The section reports the organization's
+//! sessions per state and the `top_n` most recently active ones. Ratchet
+//! state never leaves the service.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use creto_common::{
+    CretoResult, OrganizationId, SectionData, SnapshotContributor, SnapshotRequest,
+};
+use serde::Serialize;
+
+use crate::service::MessagingService;
+use crate::session::{SessionMetadata, SessionState};
+
+/// Section name used by [`MessagingSnapshotContributor`].
+pub const MESSAGING_SECTION: &str = "messaging";
+
+/// The messaging section's data.
+#[derive(Debug, Clone, Serialize)]
+pub struct MessagingSnapshot {
+    /// Messaging services (local agents) in the organization.
+    pub agent_count: usize,
+    /// Cached sessions across those agents.
+    pub session_count: usize,
+    /// Sessions per state.
+    pub sessions_by_state: BTreeMap<String, usize>,
+    /// Most recently active sessions, at most `top_n`.
+    pub recent_sessions: Vec<SessionMetadata>,
+}
+
+/// Contributes the messaging section of an organization snapshot.
+#[derive(Default)]
+pub struct MessagingSnapshotContributor {
+    services: Vec<(OrganizationId, Arc<MessagingService>)>,
+}
+
+impl MessagingSnapshotContributor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include a service whose local agent belongs to `organization_id`.
+    pub fn with_service(
+        mut self,
+        organization_id: OrganizationId,
+        service: Arc<MessagingService>,
+    ) -> Self {
+        self.services.push((organization_id, service));
+        self
+    }
+}
+
+#[async_trait]
+impl SnapshotContributor for MessagingSnapshotContributor {
+    fn section(&self) -> &str {
+        MESSAGING_SECTION
+    }
+
+    async fn contribute(&self, request: &SnapshotRequest) -> CretoResult<SectionData> {
+        let mut agent_count = 0;
+        let mut sessions = Vec::new();
+        for (organization_id, service) in &self.services {
+            if *organization_id == request.organization_id {
+                agent_count += 1;
+                sessions.extend(service.session_summaries().await);
+            }
+        }
+        let mut sessions_by_state = BTreeMap::new();
+        for session in &sessions {
+            *sessions_by_state
+                .entry(state_name(session.state).to_string())
+                .or_insert(0) += 1;
+        }
+        sessions.sort_by(|a, b| {
+            b.last_active_at
+                .cmp(&a.last_active_at)
+                .then(a.id.cmp(&b.id))
+        });
+        let session_count = sessions.len();
+        sessions.truncate(request.top_n);
+
+        SectionData::new(
+            request.as_of,
+            &MessagingSnapshot {
+                agent_count,
+                session_count,
+                sessions_by_state,
+                recent_sessions: sessions,
+            },
+        )
+    }
+}
+
+fn state_name(state: SessionState) -> &'static str {
+    match state {
+        SessionState::Establishing => "establishing",
+        SessionState::Active => "active",
+        SessionState::Suspended => "suspended",
+        SessionState::Closed => "closed",
+        SessionState::Failed => "failed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::{InMemoryKeyStore, KeyStore};
+    use creto_common::{AgentId, SnapshotAggregator, SnapshotMode};
+
+    #[tokio::test]
+    async fn test_messaging_section_reports_org_sessions_without_ratchet_state() {
+        let store: Arc<dyn KeyStore> = Arc::new(InMemoryKeyStore::new());
+        let (alice_id, bob_id) = (AgentId::new(), AgentId::new());
+        let mut alice = MessagingService::new().with_key_store(Arc::clone(&store));
+        alice.initialize(alice_id).await.unwrap();
+        let mut bob = MessagingService::new().with_key_store(Arc::clone(&store));
+        bob.initialize(bob_id).await.unwrap();
+        let (_, params) = alice.initiate_session(bob_id).await.unwrap();
+        bob.accept_session(&params).await.unwrap();
+
+        let org = OrganizationId::new();
+        let contributor = MessagingSnapshotContributor::new()
+            .with_service(org, Arc::new(alice))
+            .with_service(OrganizationId::new(), Arc::new(bob));
+        let snapshot = SnapshotAggregator::new()
+            .with_contributor(Arc::new(contributor))
+            .capture(org, SnapshotMode::Full)
+            .await;
+        let data = snapshot
+            .section(MESSAGING_SECTION)
+            .unwrap()
+            .data
+            .as_ref()
+            .unwrap();
+        assert_eq!(data["agent_count"], 1);
+        assert_eq!(data["session_count"], 1);
+        let session = &data["recent_sessions"][0];
+        assert_eq!(session["remote_agent"], serde_json::json!(bob_id));
+        assert!(session.get("ratchet_state").is_none());
+    }
+}
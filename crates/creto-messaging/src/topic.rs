@@ -5,13 +5,42 @@
 //! - Subscription management with filtering
 //! - Message publishing to multiple subscribers
 //! - Access control via topic policies
+//!
+//! Payloads are stored as `Arc<[u8]>`, so the retained copy of a message and
+//! every copy handed to a subscriber share one buffer. Publishing resolves
+//! subscribers through an inverted index from metadata key/value pairs to
+//! the subscriptions requiring them, so the cost of a publish grows with the
+//! number of matching subscriptions rather than with all of them.
+//!
+//! [`TopicManager`] is a cloneable handle with a lock per topic, so it can
+//! be shared across tasks and publishes to different topics run in
+//! parallel. A publish returns one [`TopicDelivery`] per matching
+//! subscription for the caller to fan out.
+//!
+//! Access to [`TopicPolicy::AuthzRequired`] topics is decided by an
+//! [`AuthzHook`] set with [`TopicManager::with_authz_hook`].
+//!
+//! Retained messages past their topic's TTL are dropped on publish and by
+//! [`TopicManager::sweep_expired`], which [`spawn_topic_sweep`] runs
+//! periodically. New subscribers can replay what is still retained.
+//!
+//! Topics and subscriptions can be written through to a
+//! [`TopicRepository`] and [`SubscriptionRepository`] and loaded back with
+//! [`TopicManager::load`] after a restart. Retained messages stay in memory.
 
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, CretoError, CretoResult};
+use creto_common::{AgentId, CretoError, CretoResult, ShutdownCoordinator};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+use crate::authz::{AuthzFailureMode, AuthzHook, TopicAction, TopicAuthorizer};
+use crate::repository::{SubscriptionRepository, TopicRepository};
+
 /// Unique identifier for a topic.
 pub type TopicId = Uuid;
 
@@ -27,7 +56,7 @@ pub enum TopicPolicy {
     /// Only owner can manage subscriptions.
     Private,
 
-    /// Requires authorization check via creto-authz.
+    /// Requires a decision from the manager's [`AuthzHook`].
     AuthzRequired,
 
     /// Explicit allowlist of agents.
@@ -83,6 +112,14 @@ pub struct Topic {
     /// Allowlist of agents (used with Allowlist policy).
     pub allowed_agents: Vec<AgentId>,
 
+    /// Whether new subscribers may replay retained messages.
+    #[serde(default = "default_replay_allowed")]
+    pub replay_allowed: bool,
+
+    /// What an `AuthzRequired` policy does when the hook fails.
+    #[serde(default)]
+    pub authz_failure_mode: AuthzFailureMode,
+
     /// When the topic was created.
     pub created_at: DateTime<Utc>,
 
@@ -105,48 +142,95 @@ impl Topic {
             max_message_size: 1024 * 1024, // 1MB default
             max_subscribers: Some(1000),
             allowed_agents: Vec::new(),
+            replay_allowed: true,
+            authz_failure_mode: AuthzFailureMode::default(),
             created_at: now,
             updated_at: now,
         }
     }
 
     /// Check if an agent can publish to this topic.
+    ///
+    /// `AuthzRequired` needs an [`AuthzHook`], so it is only allowed for the
+    /// owner here; [`TopicManager`] consults its hook instead.
     pub fn can_publish(&self, agent_id: AgentId) -> bool {
-        if agent_id == self.owner_agent_id {
-            return true;
-        }
-
-        match self.publish_policy {
-            TopicPolicy::Open => true,
-            TopicPolicy::Private => false,
-            TopicPolicy::Allowlist => self.allowed_agents.contains(&agent_id),
-            TopicPolicy::AuthzRequired => {
-                // In production, this would call creto-authz
-                // For now, deny by default
-                false
-            }
-        }
+        self.policy_access(agent_id, TopicAction::Publish)
+            .unwrap_or(false)
     }
 
     /// Check if an agent can subscribe to this topic.
+    ///
+    /// `AuthzRequired` needs an [`AuthzHook`], so it is only allowed for the
+    /// owner here; [`TopicManager`] consults its hook instead.
     pub fn can_subscribe(&self, agent_id: AgentId) -> bool {
+        self.policy_access(agent_id, TopicAction::Subscribe)
+            .unwrap_or(false)
+    }
+
+    /// Whether the topic's policy for `action` lets `agent_id` in, or
+    /// `None` when the authorization hook has to decide.
+    fn policy_access(&self, agent_id: AgentId, action: TopicAction) -> Option<bool> {
         if agent_id == self.owner_agent_id {
-            return true;
+            return Some(true);
         }
 
-        match self.subscribe_policy {
-            TopicPolicy::Open => true,
-            TopicPolicy::Private => false,
-            TopicPolicy::Allowlist => self.allowed_agents.contains(&agent_id),
-            TopicPolicy::AuthzRequired => {
-                // In production, this would call creto-authz
-                // For now, deny by default
-                false
-            }
+        let policy = match action {
+            TopicAction::Publish => self.publish_policy,
+            TopicAction::Subscribe => self.subscribe_policy,
+        };
+        match policy {
+            TopicPolicy::Open => Some(true),
+            TopicPolicy::Private => Some(false),
+            TopicPolicy::Allowlist => Some(self.allowed_agents.contains(&agent_id)),
+            TopicPolicy::AuthzRequired => None,
+        }
+    }
+
+    /// Check if a retained message is still within the topic's TTL.
+    fn is_retained(&self, message: &TopicMessage, now: DateTime<Utc>) -> bool {
+        match self.retention.ttl_seconds {
+            Some(ttl) => (now - message.published_at).num_seconds() < ttl as i64,
+            None => true,
         }
     }
 }
 
+fn default_replay_allowed() -> bool {
+    true
+}
+
+/// Where a new subscription starts reading from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "value")]
+pub enum SubscriptionStart {
+    /// Only messages published after subscribing.
+    #[default]
+    Latest,
+
+    /// Every retained message, then live messages.
+    Earliest,
+
+    /// Retained messages published after the given message, then live messages.
+    AfterMessageId(Uuid),
+
+    /// Retained messages published at or after the given time, then live messages.
+    SinceTimestamp(DateTime<Utc>),
+}
+
+/// Which retained messages a new subscriber receives when it joins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayPolicy {
+    /// No retained messages, only live ones.
+    #[default]
+    None,
+
+    /// The last `n` retained messages matching the subscriber's filter.
+    LastN(u32),
+
+    /// Retained messages published at or after the given time.
+    Since(DateTime<Utc>),
+}
+
 /// Subscription filter for selective message receiving.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubscriptionFilter {
@@ -188,6 +272,18 @@ impl Default for SubscriptionFilter {
     }
 }
 
+impl SubscriptionFilter {
+    /// Encode for storage in a JSON column.
+    pub fn to_db_json(&self) -> CretoResult<serde_json::Value> {
+        serde_json::to_value(self).map_err(|e| CretoError::SerializationError(e.to_string()))
+    }
+
+    /// Decode from a JSON column.
+    pub fn from_db_json(value: serde_json::Value) -> CretoResult<Self> {
+        serde_json::from_value(value).map_err(|e| CretoError::SerializationError(e.to_string()))
+    }
+}
+
 /// Subscription to a topic.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Subscription {
@@ -203,6 +299,10 @@ pub struct Subscription {
     /// Optional filter for selective receiving.
     pub filter: Option<SubscriptionFilter>,
 
+    /// Position the subscription started reading from.
+    #[serde(default)]
+    pub start: SubscriptionStart,
+
     /// When the subscription was created.
     pub subscribed_at: DateTime<Utc>,
 }
@@ -215,6 +315,7 @@ impl Subscription {
             topic_id,
             subscriber_agent_id,
             filter: None,
+            start: SubscriptionStart::Latest,
             subscribed_at: Utc::now(),
         }
     }
@@ -230,9 +331,24 @@ impl Subscription {
             topic_id,
             subscriber_agent_id,
             filter: Some(filter),
+            start: SubscriptionStart::Latest,
             subscribed_at: Utc::now(),
         }
     }
+
+    /// Set the start position.
+    pub fn with_start(mut self, start: SubscriptionStart) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Check if a message matches this subscription's filter.
+    pub fn matches(&self, message_metadata: &HashMap<String, String>) -> bool {
+        match &self.filter {
+            Some(filter) => filter.matches(message_metadata),
+            None => true,
+        }
+    }
 }
 
 /// Configuration for creating a topic.
@@ -261,6 +377,14 @@ pub struct TopicConfig {
 
     /// Allowed agents (for Allowlist policy).
     pub allowed_agents: Vec<AgentId>,
+
+    /// Whether new subscribers may replay retained messages.
+    #[serde(default = "default_replay_allowed")]
+    pub replay_allowed: bool,
+
+    /// What an `AuthzRequired` policy does when the hook fails.
+    #[serde(default)]
+    pub authz_failure_mode: AuthzFailureMode,
 }
 
 impl TopicConfig {
@@ -275,6 +399,8 @@ impl TopicConfig {
             max_message_size: 1024 * 1024,
             max_subscribers: Some(1000),
             allowed_agents: Vec::new(),
+            replay_allowed: true,
+            authz_failure_mode: AuthzFailureMode::default(),
         }
     }
 }
@@ -291,8 +417,9 @@ pub struct TopicMessage {
     /// Publisher agent ID.
     pub publisher_id: AgentId,
 
-    /// Message payload.
-    pub payload: Vec<u8>,
+    /// Message payload, shared by the retained copy and every delivery.
+    #[serde(with = "shared_bytes")]
+    pub payload: Arc<[u8]>,
 
     /// Message metadata for filtering.
     pub metadata: HashMap<String, String>,
@@ -301,34 +428,372 @@ pub struct TopicMessage {
     pub published_at: DateTime<Utc>,
 }
 
-/// Manager for topics and subscriptions.
+/// Serializes a shared payload exactly like a `Vec<u8>`.
+mod shared_bytes {
+    use std::sync::Arc;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Arc<[u8]>, serializer: S) -> Result<S::Ok, S::Error> {
+        bytes.as_ref().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Arc<[u8]>, D::Error> {
+        Vec::<u8>::deserialize(deserializer).map(Arc::from)
+    }
+}
+
+/// Inverted index over one topic's subscription filters.
+///
+/// Each filtered subscription is listed under one of the metadata pairs it
+/// requires, the one with the fewest subscriptions listed when it was added.
+/// A publish only visits the subscriptions listed under the message's own
+/// pairs and checks the rest of their filter, so subscriptions that cannot
+/// match are never looked at. Filters are exact-match only; a richer filter
+/// type would need its subscriptions evaluated one by one alongside this
+/// index.
+#[derive(Debug, Default)]
+struct FilterIndex {
+    /// Indexed subscriptions.
+    members: HashMap<SubscriptionId, IndexMember>,
+    /// Subscriptions without filter criteria, in subscribe order.
+    unfiltered: Vec<(u64, SubscriptionId, AgentId)>,
+    /// Filtered subscriptions by the pair they are listed under, by key
+    /// then value.
+    listed: HashMap<String, HashMap<String, Vec<SubscriptionId>>>,
+    /// Sequence number for the next subscription.
+    next_seq: u64,
+}
+
+#[derive(Debug)]
+struct IndexMember {
+    /// Subscribe order, so deliveries keep subscription order.
+    seq: u64,
+    subscriber_agent_id: AgentId,
+    /// Required metadata and the pair listed in the index (`None` when
+    /// unfiltered).
+    filter: Option<(HashMap<String, String>, (String, String))>,
+}
+
+impl FilterIndex {
+    fn insert(&mut self, subscription: &Subscription) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        let criteria = subscription
+            .filter
+            .as_ref()
+            .map(|f| &f.metadata)
+            .filter(|m| !m.is_empty());
+        let filter = criteria.map(|metadata| {
+            let listed_under = metadata
+                .iter()
+                .min_by_key(|(key, value)| (self.listed_count(key, value), *key, *value))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .expect("non-empty filter");
+            self.listed
+                .entry(listed_under.0.clone())
+                .or_default()
+                .entry(listed_under.1.clone())
+                .or_default()
+                .push(subscription.id);
+            (metadata.clone(), listed_under)
+        });
+        if filter.is_none() {
+            self.unfiltered
+                .push((seq, subscription.id, subscription.subscriber_agent_id));
+        }
+
+        self.members.insert(
+            subscription.id,
+            IndexMember {
+                seq,
+                subscriber_agent_id: subscription.subscriber_agent_id,
+                filter,
+            },
+        );
+    }
+
+    fn remove(&mut self, subscription_id: SubscriptionId) {
+        let Some(member) = self.members.remove(&subscription_id) else {
+            return;
+        };
+        let Some((_, (key, value))) = member.filter else {
+            if let Ok(pos) = self
+                .unfiltered
+                .binary_search_by_key(&member.seq, |(seq, _, _)| *seq)
+            {
+                self.unfiltered.remove(pos);
+            }
+            return;
+        };
+        let Some(values) = self.listed.get_mut(&key) else {
+            return;
+        };
+        if let Some(ids) = values.get_mut(&value) {
+            ids.retain(|id| *id != subscription_id);
+            if ids.is_empty() {
+                values.remove(&value);
+            }
+        }
+        if values.is_empty() {
+            self.listed.remove(&key);
+        }
+    }
+
+    fn listed_count(&self, key: &str, value: &str) -> usize {
+        self.listed
+            .get(key)
+            .and_then(|values| values.get(value))
+            .map_or(0, Vec::len)
+    }
+
+    /// Subscriptions whose filters match `metadata` and their subscribers,
+    /// in subscribe order.
+    fn matching(&self, metadata: &HashMap<String, String>) -> Vec<(SubscriptionId, AgentId)> {
+        // Each filtered subscription is listed under exactly one pair, so
+        // none is visited twice
+        let mut filtered = Vec::new();
+        for (key, value) in metadata {
+            let Some(ids) = self.listed.get(key).and_then(|values| values.get(value)) else {
+                continue;
+            };
+            for (id, member) in ids
+                .iter()
+                .filter_map(|id| Some((*id, self.members.get(id)?)))
+            {
+                let Some((required, _)) = &member.filter else {
+                    continue;
+                };
+                if required.iter().all(|(k, v)| metadata.get(k) == Some(v)) {
+                    filtered.push((member.seq, id, member.subscriber_agent_id));
+                }
+            }
+        }
+        filtered.sort_unstable_by_key(|(seq, _, _)| *seq);
+
+        // Merge with the unfiltered subscriptions, both in subscribe order
+        let mut matched = Vec::with_capacity(self.unfiltered.len() + filtered.len());
+        let mut filtered = filtered.into_iter().peekable();
+        for &(seq, id, agent_id) in &self.unfiltered {
+            while let Some((_, earlier, agent)) = filtered.next_if(|(f, _, _)| *f < seq) {
+                matched.push((earlier, agent));
+            }
+            matched.push((id, agent_id));
+        }
+        matched.extend(filtered.map(|(_, id, agent_id)| (id, agent_id)));
+        matched
+    }
+}
+
+/// A published message addressed to one subscription.
+///
+/// Returned by [`TopicManager::publish`] so callers can hand each delivery
+/// to its own task. Every delivery of a message shares one copy of it.
+#[derive(Debug, Clone)]
+pub struct TopicDelivery {
+    /// Subscription the message matched.
+    pub subscription_id: SubscriptionId,
+
+    /// Agent to deliver to.
+    pub subscriber_agent_id: AgentId,
+
+    /// The published message.
+    pub message: Arc<TopicMessage>,
+}
+
+/// Shared handle to topics and subscriptions.
+///
+/// Cloning is cheap and every clone sees the same topics. Each topic's
+/// subscriptions, filter index and retained messages sit behind their own
+/// lock, so publishes to different topics never wait on each other; the
+/// topic and subscription registries are only write-locked to add or
+/// remove entries.
+#[derive(Clone, Default)]
 pub struct TopicManager {
+    inner: Arc<Registry>,
+
+    /// Where topics and subscriptions are written through to (optional).
+    repositories: Option<TopicRepositories>,
+
+    /// What creating a topic with a name its owner already uses does.
+    name_conflict: TopicNameConflict,
+
+    /// Authorization for `AuthzRequired` topics.
+    authorizer: TopicAuthorizer,
+}
+
+/// What [`TopicManager::create_topic`] does when the owner already has a
+/// topic with the requested name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopicNameConflict {
+    /// Fail with [`CretoError::ValidationFailed`].
+    #[default]
+    Error,
+    /// Return the existing topic's ID and leave it unchanged.
+    ReturnExisting,
+}
+
+#[derive(Clone)]
+struct TopicRepositories {
+    topics: Arc<dyn TopicRepository>,
+    subscriptions: Arc<dyn SubscriptionRepository>,
+}
+
+/// Topics and subscriptions shared by every [`TopicManager`] clone.
+///
+/// A registry lock is never held while waiting for a topic's state lock;
+/// a topic's state lock may be held while a registry lock is taken. The
+/// name index is always locked before the topic map.
+#[derive(Default)]
+struct Registry {
+    /// Topic IDs by owner and name.
+    names: RwLock<HashMap<(AgentId, String), TopicId>>,
+
     /// All topics by ID.
-    topics: HashMap<TopicId, Topic>,
+    topics: RwLock<HashMap<TopicId, Arc<RwLock<TopicState>>>>,
+
+    /// Topic of every subscription, by subscription ID.
+    subscriptions: RwLock<HashMap<SubscriptionId, TopicId>>,
+}
 
-    /// All subscriptions by ID.
+/// One topic and everything attached to it.
+struct TopicState {
+    topic: Topic,
+
+    /// Subscriptions by ID.
     subscriptions: HashMap<SubscriptionId, Subscription>,
 
-    /// Subscriptions indexed by topic.
-    topic_subscriptions: HashMap<TopicId, Vec<SubscriptionId>>,
+    /// Subscription IDs in subscribe order.
+    subscription_order: Vec<SubscriptionId>,
+
+    /// Subscription filter index.
+    filter_index: FilterIndex,
 
-    /// Messages by topic (for retention).
-    topic_messages: HashMap<TopicId, Vec<TopicMessage>>,
+    /// Retained messages, oldest first.
+    messages: VecDeque<TopicMessage>,
+
+    /// Set once the topic is deleted, for callers that looked it up before.
+    deleted: bool,
 }
 
 impl TopicManager {
     /// Create a new topic manager.
     pub fn new() -> Self {
-        Self {
-            topics: HashMap::new(),
-            subscriptions: HashMap::new(),
-            topic_subscriptions: HashMap::new(),
-            topic_messages: HashMap::new(),
+        Self::default()
+    }
+
+    /// Write topics and subscriptions through to repositories.
+    ///
+    /// Call [`load`](Self::load) before use to pick up what is already
+    /// stored.
+    pub fn with_repositories(
+        mut self,
+        topics: Arc<dyn TopicRepository>,
+        subscriptions: Arc<dyn SubscriptionRepository>,
+    ) -> Self {
+        self.repositories = Some(TopicRepositories {
+            topics,
+            subscriptions,
+        });
+        self
+    }
+
+    /// Set what creating a topic with a name its owner already uses does.
+    pub fn with_name_conflict(mut self, name_conflict: TopicNameConflict) -> Self {
+        self.name_conflict = name_conflict;
+        self
+    }
+
+    /// Decide access to `AuthzRequired` topics with `hook`.
+    ///
+    /// Without a hook those topics are closed to everyone but their owner.
+    pub fn with_authz_hook(mut self, hook: Arc<dyn AuthzHook>) -> Self {
+        self.authorizer.set_hook(hook);
+        self
+    }
+
+    /// Set how long to wait for the authorization hook before applying the
+    /// topic's [`AuthzFailureMode`].
+    pub fn with_authz_timeout(mut self, timeout: Duration) -> Self {
+        self.authorizer.set_timeout(timeout);
+        self
+    }
+
+    /// Set how long an authorization decision is reused for.
+    pub fn with_authz_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.authorizer.set_cache_ttl(ttl);
+        self
+    }
+
+    /// Load stored topics and subscriptions into the manager.
+    ///
+    /// Topics already known to the manager are left as they are. Returns
+    /// the number of topics loaded.
+    pub async fn load(&self) -> CretoResult<usize> {
+        let Some(repositories) = &self.repositories else {
+            return Ok(0);
+        };
+        let stored_topics = repositories.topics.list().await?;
+        let stored_subscriptions = repositories.subscriptions.list().await?;
+
+        let mut states: HashMap<TopicId, TopicState> = stored_topics
+            .into_iter()
+            .map(|topic| (topic.id, TopicState::new(topic)))
+            .collect();
+        for subscription in stored_subscriptions {
+            match states.get_mut(&subscription.topic_id) {
+                Some(state) => state.add_subscription(subscription),
+                None => tracing::warn!(
+                    topic_id = %subscription.topic_id,
+                    subscription_id = %subscription.id,
+                    "Skipping stored subscription to unknown topic"
+                ),
+            }
+        }
+
+        let mut names = self.inner.names.write().await;
+        let mut topics = self.inner.topics.write().await;
+        let mut subscriptions = self.inner.subscriptions.write().await;
+        let mut loaded = 0;
+        for (topic_id, state) in states {
+            if topics.contains_key(&topic_id) {
+                continue;
+            }
+            names.insert(
+                (state.topic.owner_agent_id, state.topic.name.clone()),
+                topic_id,
+            );
+            for sub_id in &state.subscription_order {
+                subscriptions.insert(*sub_id, topic_id);
+            }
+            topics.insert(topic_id, Arc::new(RwLock::new(state)));
+            loaded += 1;
         }
+
+        tracing::info!(topics = loaded, "Topics loaded");
+
+        Ok(loaded)
     }
 
     /// Create a new topic.
-    pub fn create_topic(&mut self, config: TopicConfig) -> CretoResult<TopicId> {
+    ///
+    /// If the owner already has a topic with this name, the configured
+    /// [`TopicNameConflict`] decides whether that topic's ID is returned or
+    /// creation fails.
+    pub async fn create_topic(&self, config: TopicConfig) -> CretoResult<TopicId> {
+        let mut names = self.inner.names.write().await;
+        let name_key = (config.owner_agent_id, config.name.clone());
+        if let Some(&existing) = names.get(&name_key) {
+            return match self.name_conflict {
+                TopicNameConflict::ReturnExisting => Ok(existing),
+                TopicNameConflict::Error => Err(CretoError::ValidationFailed(format!(
+                    "Topic '{}' already exists for owner {}",
+                    config.name, config.owner_agent_id
+                ))),
+            };
+        }
+
         let mut topic = Topic::new(config.name, config.owner_agent_id);
         topic.publish_policy = config.publish_policy;
         topic.subscribe_policy = config.subscribe_policy;
@@ -336,11 +801,20 @@ impl TopicManager {
         topic.max_message_size = config.max_message_size;
         topic.max_subscribers = config.max_subscribers;
         topic.allowed_agents = config.allowed_agents;
+        topic.replay_allowed = config.replay_allowed;
+        topic.authz_failure_mode = config.authz_failure_mode;
+
+        if let Some(repositories) = &self.repositories {
+            repositories.topics.create(&topic).await?;
+        }
 
         let topic_id = topic.id;
-        self.topics.insert(topic_id, topic);
-        self.topic_subscriptions.insert(topic_id, Vec::new());
-        self.topic_messages.insert(topic_id, Vec::new());
+        names.insert(name_key, topic_id);
+        self.inner
+            .topics
+            .write()
+            .await
+            .insert(topic_id, Arc::new(RwLock::new(TopicState::new(topic))));
 
         tracing::info!(topic_id = %topic_id, "Topic created");
 
@@ -348,65 +822,131 @@ impl TopicManager {
     }
 
     /// Delete a topic.
-    pub fn delete_topic(&mut self, topic_id: TopicId, agent_id: AgentId) -> CretoResult<()> {
-        let topic = self
-            .topics
-            .get(&topic_id)
-            .ok_or_else(|| CretoError::NotFound(format!("Topic {} not found", topic_id)))?;
+    pub async fn delete_topic(&self, topic_id: TopicId, agent_id: AgentId) -> CretoResult<()> {
+        let state = self.topic_state(topic_id).await?;
+        let mut state = state.write().await;
 
         // Only owner can delete
-        if topic.owner_agent_id != agent_id {
+        if state.topic.owner_agent_id != agent_id {
             return Err(CretoError::Unauthorized(
                 "Only topic owner can delete topic".to_string(),
             ));
         }
 
-        // Remove all subscriptions
-        if let Some(sub_ids) = self.topic_subscriptions.remove(&topic_id) {
-            for sub_id in sub_ids {
-                self.subscriptions.remove(&sub_id);
-            }
+        if let Some(repositories) = &self.repositories {
+            repositories
+                .subscriptions
+                .delete_for_topic(topic_id)
+                .await?;
+            repositories.topics.delete(topic_id).await?;
         }
 
-        // Remove messages
-        self.topic_messages.remove(&topic_id);
-
-        // Remove topic
-        self.topics.remove(&topic_id);
+        // Remove topic, then its subscriptions and messages
+        self.inner
+            .names
+            .write()
+            .await
+            .remove(&(agent_id, state.topic.name.clone()));
+        self.inner.topics.write().await.remove(&topic_id);
+        state.deleted = true;
+        let mut subscriptions = self.inner.subscriptions.write().await;
+        for sub_id in state.subscription_order.drain(..) {
+            subscriptions.remove(&sub_id);
+        }
+        state.subscriptions.clear();
+        state.filter_index = FilterIndex::default();
+        state.messages.clear();
 
         tracing::info!(topic_id = %topic_id, "Topic deleted");
 
         Ok(())
     }
 
-    /// Subscribe to a topic.
-    pub fn subscribe(
-        &mut self,
+    /// Subscribe to a topic, receiving only messages published from now on.
+    pub async fn subscribe(
+        &self,
         topic_id: TopicId,
         subscriber_agent_id: AgentId,
         filter: Option<SubscriptionFilter>,
     ) -> CretoResult<Subscription> {
-        let topic = self
-            .topics
-            .get(&topic_id)
-            .ok_or_else(|| CretoError::NotFound(format!("Topic {} not found", topic_id)))?;
+        self.subscribe_from(
+            topic_id,
+            subscriber_agent_id,
+            filter,
+            SubscriptionStart::Latest,
+        )
+        .await
+        .map(|(subscription, _)| subscription)
+    }
+
+    /// Subscribe to a topic starting from `start`.
+    ///
+    /// Returns the subscription together with the retained messages that
+    /// match its filter, in publish order. The backlog is copied before the
+    /// subscription is registered under the topic's write lock, so every
+    /// later `publish` is delivered live and nothing is replayed twice or
+    /// lost to retention trimming in between.
+    pub async fn subscribe_from(
+        &self,
+        topic_id: TopicId,
+        subscriber_agent_id: AgentId,
+        filter: Option<SubscriptionFilter>,
+        start: SubscriptionStart,
+    ) -> CretoResult<(Subscription, Vec<TopicMessage>)> {
+        self.register_subscription(topic_id, subscriber_agent_id, filter, start, None)
+            .await
+    }
+
+    /// Subscribe to a topic, returning the retained messages `replay`
+    /// selects so a late-joining agent can catch up.
+    ///
+    /// Replayed messages match the subscription's filter and are returned
+    /// oldest first. Any replay requires the topic to allow it, and the
+    /// subscription's `start` records where the replay began.
+    pub async fn subscribe_with_replay(
+        &self,
+        topic_id: TopicId,
+        subscriber_agent_id: AgentId,
+        filter: Option<SubscriptionFilter>,
+        replay: ReplayPolicy,
+    ) -> CretoResult<(Subscription, Vec<TopicMessage>)> {
+        let (start, last_n) = match replay {
+            ReplayPolicy::None => (SubscriptionStart::Latest, None),
+            ReplayPolicy::LastN(n) => (SubscriptionStart::Earliest, Some(n as usize)),
+            ReplayPolicy::Since(since) => (SubscriptionStart::SinceTimestamp(since), None),
+        };
+        self.register_subscription(topic_id, subscriber_agent_id, filter, start, last_n)
+            .await
+    }
+
+    /// Register a subscription and collect its backlog, keeping only the
+    /// newest `last_n` messages when set.
+    async fn register_subscription(
+        &self,
+        topic_id: TopicId,
+        subscriber_agent_id: AgentId,
+        filter: Option<SubscriptionFilter>,
+        start: SubscriptionStart,
+        last_n: Option<usize>,
+    ) -> CretoResult<(Subscription, Vec<TopicMessage>)> {
+        let state = self.topic_state(topic_id).await?;
 
         // Check subscription permission
-        if !topic.can_subscribe(subscriber_agent_id) {
-            return Err(CretoError::Unauthorized(
-                "Not authorized to subscribe to this topic".to_string(),
-            ));
+        self.check_access(&state, subscriber_agent_id, TopicAction::Subscribe)
+            .await?;
+
+        let mut state = state.write().await;
+        if state.deleted {
+            return Err(CretoError::NotFound(format!(
+                "Topic {} not found",
+                topic_id
+            )));
         }
+        let topic = &state.topic;
 
         // Check max subscribers
         if let Some(max) = topic.max_subscribers {
-            let current_count = self
-                .topic_subscriptions
-                .get(&topic_id)
-                .map(|s| s.len())
-                .unwrap_or(0);
-
-            if current_count >= max as usize {
+            if state.subscription_order.len() >= max as usize {
                 return Err(CretoError::LimitExceeded(format!(
                     "Topic has reached maximum of {} subscribers",
                     max
@@ -414,38 +954,105 @@ impl TopicManager {
             }
         }
 
-        let subscription = match filter {
+        if start != SubscriptionStart::Latest && !topic.replay_allowed {
+            return Err(CretoError::Unauthorized(
+                "Replay is disabled for this topic".to_string(),
+            ));
+        }
+
+        let mut subscription = match filter {
             Some(f) => Subscription::with_filter(topic_id, subscriber_agent_id, f),
             None => Subscription::new(topic_id, subscriber_agent_id),
-        };
-
+        }
+        .with_start(start);
+
+        let mut backlog = state.replay_backlog(&subscription)?;
+        if let Some(last_n) = last_n {
+            backlog.drain(..backlog.len().saturating_sub(last_n));
+            subscription.start = backlog.first().map_or(SubscriptionStart::Latest, |m| {
+                SubscriptionStart::SinceTimestamp(m.published_at)
+            });
+        }
         let sub_id = subscription.id;
 
-        self.subscriptions.insert(sub_id, subscription.clone());
-        self.topic_subscriptions
-            .entry(topic_id)
-            .or_default()
-            .push(sub_id);
+        if let Some(repositories) = &self.repositories {
+            repositories.subscriptions.create(&subscription).await?;
+        }
+
+        state.add_subscription(subscription.clone());
+        self.inner
+            .subscriptions
+            .write()
+            .await
+            .insert(sub_id, topic_id);
 
         tracing::info!(
             topic_id = %topic_id,
             subscriber = %subscriber_agent_id,
             subscription_id = %sub_id,
+            replayed = backlog.len(),
             "Subscription created"
         );
 
-        Ok(subscription)
+        Ok((subscription, backlog))
+    }
+
+    /// Read retained messages for a subscription without registering
+    /// anything, for consumers that poll instead of receiving live delivery.
+    ///
+    /// Returns up to `limit` messages matching the subscription's filter,
+    /// published after the message `after`, or since the subscription was
+    /// created when `after` is `None`. A cursor that has been trimmed by
+    /// retention reads from the oldest retained message, since everything
+    /// still retained is newer than it.
+    pub async fn poll(
+        &self,
+        subscription_id: SubscriptionId,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> CretoResult<Vec<TopicMessage>> {
+        let not_found =
+            || CretoError::NotFound(format!("Subscription {} not found", subscription_id));
+        let topic_id = self.subscription_topic(subscription_id).await?;
+        let state = self.topic_state(topic_id).await.map_err(|_| not_found())?;
+        let state = state.read().await;
+        let subscription = state
+            .subscriptions
+            .get(&subscription_id)
+            .ok_or_else(not_found)?;
+
+        let retained = &state.messages;
+        let from = after
+            .and_then(|id| retained.iter().position(|m| m.id == id))
+            .map_or(0, |i| i + 1);
+        let now = Utc::now();
+        Ok(retained
+            .range(from..)
+            .filter(|m| {
+                m.published_at >= subscription.subscribed_at
+                    && state.topic.is_retained(m, now)
+                    && subscription.matches(&m.metadata)
+            })
+            .take(limit)
+            .cloned()
+            .collect())
     }
 
     /// Unsubscribe from a topic.
-    pub fn unsubscribe(
-        &mut self,
+    pub async fn unsubscribe(
+        &self,
         subscription_id: SubscriptionId,
         agent_id: AgentId,
     ) -> CretoResult<()> {
-        let subscription = self.subscriptions.get(&subscription_id).ok_or_else(|| {
-            CretoError::NotFound(format!("Subscription {} not found", subscription_id))
-        })?;
+        let not_found =
+            || CretoError::NotFound(format!("Subscription {} not found", subscription_id));
+        let topic_id = self.subscription_topic(subscription_id).await?;
+        let state = self.topic_state(topic_id).await.map_err(|_| not_found())?;
+        let mut state = state.write().await;
+        let subscription = state
+            .subscriptions
+            .get(&subscription_id)
+            .ok_or_else(not_found)?;
 
         // Only subscriber can unsubscribe
         if subscription.subscriber_agent_id != agent_id {
@@ -454,15 +1061,19 @@ impl TopicManager {
             ));
         }
 
-        let topic_id = subscription.topic_id;
-
-        // Remove from topic subscriptions
-        if let Some(subs) = self.topic_subscriptions.get_mut(&topic_id) {
-            subs.retain(|&id| id != subscription_id);
+        if let Some(repositories) = &self.repositories {
+            repositories.subscriptions.delete(subscription_id).await?;
         }
 
         // Remove subscription
-        self.subscriptions.remove(&subscription_id);
+        state.subscriptions.remove(&subscription_id);
+        state.subscription_order.retain(|&id| id != subscription_id);
+        state.filter_index.remove(subscription_id);
+        self.inner
+            .subscriptions
+            .write()
+            .await
+            .remove(&subscription_id);
 
         tracing::info!(
             topic_id = %topic_id,
@@ -474,24 +1085,29 @@ impl TopicManager {
     }
 
     /// Publish a message to a topic.
-    pub fn publish(
-        &mut self,
+    ///
+    /// Returns one delivery per matching subscription, in subscribe order.
+    pub async fn publish(
+        &self,
         topic_id: TopicId,
         publisher_id: AgentId,
         payload: &[u8],
         metadata: HashMap<String, String>,
-    ) -> CretoResult<Vec<AgentId>> {
-        let topic = self
-            .topics
-            .get(&topic_id)
-            .ok_or_else(|| CretoError::NotFound(format!("Topic {} not found", topic_id)))?;
+    ) -> CretoResult<Vec<TopicDelivery>> {
+        let state = self.topic_state(topic_id).await?;
 
         // Check publish permission
-        if !topic.can_publish(publisher_id) {
-            return Err(CretoError::Unauthorized(
-                "Not authorized to publish to this topic".to_string(),
-            ));
+        self.check_access(&state, publisher_id, TopicAction::Publish)
+            .await?;
+
+        let mut state = state.write().await;
+        if state.deleted {
+            return Err(CretoError::NotFound(format!(
+                "Topic {} not found",
+                topic_id
+            )));
         }
+        let topic = &state.topic;
 
         // Check message size
         if payload.len() > topic.max_message_size {
@@ -501,93 +1117,337 @@ impl TopicManager {
                 topic.max_message_size
             )));
         }
+        let max_messages = topic.retention.max_messages;
+        let now = Utc::now();
 
-        // Create message
+        // Get matching subscribers
+        let recipients = state.filter_index.matching(&metadata);
+
+        // Store message (apply retention)
         let message = TopicMessage {
             id: Uuid::new_v4(),
             topic_id,
             publisher_id,
-            payload: payload.to_vec(),
-            metadata: metadata.clone(),
-            published_at: Utc::now(),
+            payload: Arc::from(payload),
+            metadata,
+            published_at: now,
         };
-
-        // Store message (apply retention)
-        let messages = self.topic_messages.entry(topic_id).or_default();
-        messages.push(message);
+        let shared = Arc::new(message.clone());
+        state.drop_expired(now);
+        state.messages.push_back(message);
 
         // Apply retention policy
-        if let Some(max_messages) = topic.retention.max_messages {
-            if messages.len() > max_messages as usize {
-                messages.drain(0..messages.len() - max_messages as usize);
+        if let Some(max_messages) = max_messages {
+            while state.messages.len() > max_messages as usize {
+                state.messages.pop_front();
             }
         }
 
-        // Get matching subscribers
-        let subscriber_ids: Vec<AgentId> = self
-            .topic_subscriptions
-            .get(&topic_id)
-            .map(|subs| {
-                subs.iter()
-                    .filter_map(|&sub_id| {
-                        self.subscriptions.get(&sub_id).and_then(|sub| {
-                            // Check if message matches filter
-                            match &sub.filter {
-                                Some(filter) if !filter.matches(&metadata) => None,
-                                _ => Some(sub.subscriber_agent_id),
-                            }
-                        })
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
-
         tracing::info!(
             topic_id = %topic_id,
             publisher = %publisher_id,
-            subscribers = subscriber_ids.len(),
+            subscribers = recipients.len(),
             "Message published"
         );
 
-        Ok(subscriber_ids)
+        Ok(recipients
+            .into_iter()
+            .map(|(subscription_id, subscriber_agent_id)| TopicDelivery {
+                subscription_id,
+                subscriber_agent_id,
+                message: Arc::clone(&shared),
+            })
+            .collect())
     }
 
     /// List all subscribers to a topic.
-    pub fn list_subscribers(&self, topic_id: TopicId) -> CretoResult<Vec<Subscription>> {
-        if !self.topics.contains_key(&topic_id) {
-            return Err(CretoError::NotFound(format!(
-                "Topic {} not found",
-                topic_id
-            )));
+    pub async fn list_subscribers(&self, topic_id: TopicId) -> CretoResult<Vec<Subscription>> {
+        let state = self.topic_state(topic_id).await?;
+        let state = state.read().await;
+
+        Ok(state
+            .subscription_order
+            .iter()
+            .filter_map(|sub_id| state.subscriptions.get(sub_id).cloned())
+            .collect())
+    }
+
+    /// Get topic by ID.
+    pub async fn get_topic(&self, topic_id: TopicId) -> Option<Topic> {
+        let state = self.topic_state(topic_id).await.ok()?;
+        let topic = state.read().await.topic.clone();
+        Some(topic)
+    }
+
+    /// List all topics.
+    pub async fn list_topics(&self) -> Vec<Topic> {
+        let states: Vec<_> = self.inner.topics.read().await.values().cloned().collect();
+        let mut topics = Vec::with_capacity(states.len());
+        for state in states {
+            topics.push(state.read().await.topic.clone());
+        }
+        topics
+    }
+
+    /// Drop retained messages past their topic's TTL on every topic.
+    ///
+    /// Returns the number of messages dropped.
+    pub async fn sweep_expired(&self) -> usize {
+        let states: Vec<_> = self.inner.topics.read().await.values().cloned().collect();
+        let now = Utc::now();
+        let mut dropped = 0;
+        for state in states {
+            dropped += state.write().await.drop_expired(now);
+        }
+        dropped
+    }
+
+    /// Check that `agent_id` may take `action` on a topic.
+    ///
+    /// The authorization hook is called without the topic's lock held, so a
+    /// slow hook never blocks other publishers.
+    async fn check_access(
+        &self,
+        state: &RwLock<TopicState>,
+        agent_id: AgentId,
+        action: TopicAction,
+    ) -> CretoResult<()> {
+        let (topic_id, access, failure_mode) = {
+            let state = state.read().await;
+            let topic = &state.topic;
+            (
+                topic.id,
+                topic.policy_access(agent_id, action),
+                topic.authz_failure_mode,
+            )
+        };
+
+        match access {
+            Some(true) => Ok(()),
+            Some(false) => Err(CretoError::Unauthorized(match action {
+                TopicAction::Publish => "Not authorized to publish to this topic".to_string(),
+                TopicAction::Subscribe => "Not authorized to subscribe to this topic".to_string(),
+            })),
+            None => {
+                self.authorizer
+                    .authorize(agent_id, topic_id, action, failure_mode)
+                    .await
+            }
         }
+    }
 
-        let subscriptions = self
-            .topic_subscriptions
+    async fn topic_state(&self, topic_id: TopicId) -> CretoResult<Arc<RwLock<TopicState>>> {
+        self.inner
+            .topics
+            .read()
+            .await
             .get(&topic_id)
-            .map(|subs| {
-                subs.iter()
-                    .filter_map(|&sub_id| self.subscriptions.get(&sub_id).cloned())
-                    .collect()
+            .cloned()
+            .ok_or_else(|| CretoError::NotFound(format!("Topic {} not found", topic_id)))
+    }
+
+    async fn subscription_topic(&self, subscription_id: SubscriptionId) -> CretoResult<TopicId> {
+        self.inner
+            .subscriptions
+            .read()
+            .await
+            .get(&subscription_id)
+            .copied()
+            .ok_or_else(|| {
+                CretoError::NotFound(format!("Subscription {} not found", subscription_id))
             })
-            .unwrap_or_default();
+    }
+}
 
-        Ok(subscriptions)
+impl TopicState {
+    fn new(topic: Topic) -> Self {
+        Self {
+            topic,
+            subscriptions: HashMap::new(),
+            subscription_order: Vec::new(),
+            filter_index: FilterIndex::default(),
+            messages: VecDeque::new(),
+            deleted: false,
+        }
     }
 
-    /// Get topic by ID.
-    pub fn get_topic(&self, topic_id: TopicId) -> Option<&Topic> {
-        self.topics.get(&topic_id)
+    /// Drop retained messages past the topic's TTL, returning how many.
+    ///
+    /// Messages are retained in publish order, so expired ones sit at the
+    /// front.
+    fn drop_expired(&mut self, now: DateTime<Utc>) -> usize {
+        let before = self.messages.len();
+        while self
+            .messages
+            .front()
+            .is_some_and(|m| !self.topic.is_retained(m, now))
+        {
+            self.messages.pop_front();
+        }
+        before - self.messages.len()
     }
 
-    /// List all topics.
-    pub fn list_topics(&self) -> Vec<&Topic> {
-        self.topics.values().collect()
+    /// Register a subscription on this topic.
+    fn add_subscription(&mut self, subscription: Subscription) {
+        let sub_id = subscription.id;
+        self.filter_index.insert(&subscription);
+        self.subscriptions.insert(sub_id, subscription);
+        self.subscription_order.push(sub_id);
+    }
+
+    /// Collect the retained messages a new subscription should replay.
+    fn replay_backlog(&self, subscription: &Subscription) -> CretoResult<Vec<TopicMessage>> {
+        let topic = &self.topic;
+        let retained = &self.messages;
+
+        let from = match subscription.start {
+            SubscriptionStart::Latest => return Ok(Vec::new()),
+            SubscriptionStart::Earliest => 0,
+            SubscriptionStart::AfterMessageId(message_id) => {
+                retained
+                    .iter()
+                    .position(|m| m.id == message_id)
+                    .ok_or_else(|| {
+                        CretoError::NotFound(format!(
+                            "Message {} is no longer retained on topic {}",
+                            message_id, topic.id
+                        ))
+                    })?
+                    + 1
+            }
+            SubscriptionStart::SinceTimestamp(since) => {
+                retained.partition_point(|m| m.published_at < since)
+            }
+        };
+
+        let now = Utc::now();
+        Ok(retained
+            .range(from..)
+            .filter(|m| topic.is_retained(m, now) && subscription.matches(&m.metadata))
+            .cloned()
+            .collect())
     }
 }
 
-impl Default for TopicManager {
-    fn default() -> Self {
-        Self::new()
+/// Spawn a background task dropping expired retained messages from every
+/// topic of `manager` every `interval`. The task is registered with
+/// `shutdown`.
+pub fn spawn_topic_sweep(
+    manager: TopicManager,
+    shutdown: &ShutdownCoordinator,
+    interval: Duration,
+) -> JoinHandle<()> {
+    shutdown.spawn("messaging.topic_sweep", move |guard| {
+        guard.run_periodic(interval, move || {
+            let manager = manager.clone();
+            async move {
+                let dropped = manager.sweep_expired().await;
+                if dropped > 0 {
+                    tracing::debug!(dropped, "Dropped expired topic messages");
+                }
+            }
+        })
+    })
+}
+
+/// In-memory [`TopicRepository`].
+#[derive(Default)]
+pub struct InMemoryTopicRepository {
+    topics: Mutex<HashMap<TopicId, Topic>>,
+}
+
+impl InMemoryTopicRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl TopicRepository for InMemoryTopicRepository {
+    async fn create(&self, topic: &Topic) -> CretoResult<()> {
+        let mut topics = self.topics.lock().unwrap();
+        if topics
+            .values()
+            .any(|t| t.owner_agent_id == topic.owner_agent_id && t.name == topic.name)
+        {
+            return Err(CretoError::ValidationFailed(format!(
+                "Topic '{}' already exists for owner {}",
+                topic.name, topic.owner_agent_id
+            )));
+        }
+        topics.insert(topic.id, topic.clone());
+        Ok(())
+    }
+
+    async fn find_by_name(
+        &self,
+        owner_agent_id: AgentId,
+        name: &str,
+    ) -> CretoResult<Option<Topic>> {
+        Ok(self
+            .topics
+            .lock()
+            .unwrap()
+            .values()
+            .find(|t| t.owner_agent_id == owner_agent_id && t.name == name)
+            .cloned())
+    }
+
+    async fn list(&self) -> CretoResult<Vec<Topic>> {
+        let mut topics: Vec<_> = self.topics.lock().unwrap().values().cloned().collect();
+        topics.sort_by_key(|t| t.created_at);
+        Ok(topics)
+    }
+
+    async fn delete(&self, topic_id: TopicId) -> CretoResult<()> {
+        self.topics.lock().unwrap().remove(&topic_id);
+        Ok(())
+    }
+}
+
+/// In-memory [`SubscriptionRepository`].
+#[derive(Default)]
+pub struct InMemorySubscriptionRepository {
+    /// Subscriptions in creation order.
+    subscriptions: Mutex<Vec<Subscription>>,
+}
+
+impl InMemorySubscriptionRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl SubscriptionRepository for InMemorySubscriptionRepository {
+    async fn create(&self, subscription: &Subscription) -> CretoResult<()> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .push(subscription.clone());
+        Ok(())
+    }
+
+    async fn list(&self) -> CretoResult<Vec<Subscription>> {
+        Ok(self.subscriptions.lock().unwrap().clone())
+    }
+
+    async fn delete(&self, subscription_id: SubscriptionId) -> CretoResult<()> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .retain(|s| s.id != subscription_id);
+        Ok(())
+    }
+
+    async fn delete_for_topic(&self, topic_id: TopicId) -> CretoResult<()> {
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .retain(|s| s.topic_id != topic_id);
+        Ok(())
     }
 }
 
@@ -599,87 +1459,111 @@ mod tests {
         AgentId::new()
     }
 
-    #[test]
-    fn test_topic_creation() {
-        let mut manager = TopicManager::new();
+    /// Subscribers a publish was delivered to, in delivery order.
+    fn recipients(deliveries: Vec<TopicDelivery>) -> Vec<AgentId> {
+        deliveries
+            .into_iter()
+            .map(|d| d.subscriber_agent_id)
+            .collect()
+    }
+
+    /// Messages retained on a topic, oldest first.
+    async fn retained(manager: &TopicManager, topic_id: TopicId) -> Vec<TopicMessage> {
+        let state = manager.topic_state(topic_id).await.unwrap();
+        let messages = state.read().await.messages.iter().cloned().collect();
+        messages
+    }
+
+    #[tokio::test]
+    async fn test_topic_creation() {
+        let manager = TopicManager::new();
         let owner = create_test_agent();
 
         let config = TopicConfig::new("test-topic".to_string(), owner);
-        let topic_id = manager.create_topic(config).unwrap();
+        let topic_id = manager.create_topic(config).await.unwrap();
 
-        let topic = manager.get_topic(topic_id).unwrap();
+        let topic = manager.get_topic(topic_id).await.unwrap();
         assert_eq!(topic.name, "test-topic");
         assert_eq!(topic.owner_agent_id, owner);
         assert_eq!(topic.publish_policy, TopicPolicy::Private);
         assert_eq!(topic.subscribe_policy, TopicPolicy::Open);
     }
 
-    #[test]
-    fn test_subscription_management() {
-        let mut manager = TopicManager::new();
+    #[tokio::test]
+    async fn test_subscription_management() {
+        let manager = TopicManager::new();
         let owner = create_test_agent();
         let subscriber = create_test_agent();
 
         // Create open topic
         let mut config = TopicConfig::new("open-topic".to_string(), owner);
         config.subscribe_policy = TopicPolicy::Open;
-        let topic_id = manager.create_topic(config).unwrap();
+        let topic_id = manager.create_topic(config).await.unwrap();
 
         // Subscribe
-        let subscription = manager.subscribe(topic_id, subscriber, None).unwrap();
+        let subscription = manager.subscribe(topic_id, subscriber, None).await.unwrap();
         assert_eq!(subscription.topic_id, topic_id);
         assert_eq!(subscription.subscriber_agent_id, subscriber);
 
         // List subscribers
-        let subscribers = manager.list_subscribers(topic_id).unwrap();
+        let subscribers = manager.list_subscribers(topic_id).await.unwrap();
         assert_eq!(subscribers.len(), 1);
         assert_eq!(subscribers[0].subscriber_agent_id, subscriber);
 
         // Unsubscribe
-        manager.unsubscribe(subscription.id, subscriber).unwrap();
-        let subscribers = manager.list_subscribers(topic_id).unwrap();
+        manager
+            .unsubscribe(subscription.id, subscriber)
+            .await
+            .unwrap();
+        let subscribers = manager.list_subscribers(topic_id).await.unwrap();
         assert_eq!(subscribers.len(), 0);
     }
 
-    #[test]
-    fn test_open_vs_private_policy() {
-        let mut manager = TopicManager::new();
+    #[tokio::test]
+    async fn test_open_vs_private_policy() {
+        let manager = TopicManager::new();
         let owner = create_test_agent();
         let other = create_test_agent();
 
         // Create private topic (private subscribe policy)
         let mut config = TopicConfig::new("private-topic".to_string(), owner);
         config.subscribe_policy = TopicPolicy::Private;
-        let private_topic_id = manager.create_topic(config).unwrap();
+        let private_topic_id = manager.create_topic(config).await.unwrap();
 
         // Non-owner cannot subscribe to private topic
-        let result = manager.subscribe(private_topic_id, other, None);
+        let result = manager.subscribe(private_topic_id, other, None).await;
         assert!(result.is_err());
 
         // Owner can subscribe to their own private topic
-        let owner_sub = manager.subscribe(private_topic_id, owner, None).unwrap();
+        let owner_sub = manager
+            .subscribe(private_topic_id, owner, None)
+            .await
+            .unwrap();
         assert_eq!(owner_sub.subscriber_agent_id, owner);
 
         // Create open topic
         let mut open_config = TopicConfig::new("open-topic".to_string(), owner);
         open_config.subscribe_policy = TopicPolicy::Open;
         open_config.publish_policy = TopicPolicy::Open;
-        let open_topic_id = manager.create_topic(open_config).unwrap();
+        let open_topic_id = manager.create_topic(open_config).await.unwrap();
 
         // Anyone can subscribe to open topic
-        let subscription = manager.subscribe(open_topic_id, other, None).unwrap();
+        let subscription = manager.subscribe(open_topic_id, other, None).await.unwrap();
         assert_eq!(subscription.subscriber_agent_id, other);
 
         // Anyone can publish to open topic
-        let subscribers = manager
-            .publish(open_topic_id, other, b"test message", HashMap::new())
-            .unwrap();
+        let subscribers = recipients(
+            manager
+                .publish(open_topic_id, other, b"test message", HashMap::new())
+                .await
+                .unwrap(),
+        );
         assert_eq!(subscribers.len(), 1);
     }
 
-    #[test]
-    fn test_publishing_to_topic() {
-        let mut manager = TopicManager::new();
+    #[tokio::test]
+    async fn test_publishing_to_topic() {
+        let manager = TopicManager::new();
         let owner = create_test_agent();
         let sub1 = create_test_agent();
         let sub2 = create_test_agent();
@@ -687,25 +1571,28 @@ mod tests {
         // Create topic
         let mut config = TopicConfig::new("pub-topic".to_string(), owner);
         config.subscribe_policy = TopicPolicy::Open;
-        let topic_id = manager.create_topic(config).unwrap();
+        let topic_id = manager.create_topic(config).await.unwrap();
 
         // Add subscribers
-        manager.subscribe(topic_id, sub1, None).unwrap();
-        manager.subscribe(topic_id, sub2, None).unwrap();
+        manager.subscribe(topic_id, sub1, None).await.unwrap();
+        manager.subscribe(topic_id, sub2, None).await.unwrap();
 
         // Owner publishes (private publish policy, only owner can publish)
-        let subscribers = manager
-            .publish(topic_id, owner, b"hello", HashMap::new())
-            .unwrap();
+        let subscribers = recipients(
+            manager
+                .publish(topic_id, owner, b"hello", HashMap::new())
+                .await
+                .unwrap(),
+        );
 
         assert_eq!(subscribers.len(), 2);
         assert!(subscribers.contains(&sub1));
         assert!(subscribers.contains(&sub2));
     }
 
-    #[test]
-    fn test_subscriber_filtering() {
-        let mut manager = TopicManager::new();
+    #[tokio::test]
+    async fn test_subscriber_filtering() {
+        let manager = TopicManager::new();
         let owner = create_test_agent();
         let sub1 = create_test_agent();
         let sub2 = create_test_agent();
@@ -713,113 +1600,133 @@ mod tests {
         // Create topic
         let mut config = TopicConfig::new("filtered-topic".to_string(), owner);
         config.subscribe_policy = TopicPolicy::Open;
-        let topic_id = manager.create_topic(config).unwrap();
+        let topic_id = manager.create_topic(config).await.unwrap();
 
         // Subscribe with filter
         let filter =
             SubscriptionFilter::new().with_metadata("type".to_string(), "alert".to_string());
-        manager.subscribe(topic_id, sub1, Some(filter)).unwrap();
+        manager
+            .subscribe(topic_id, sub1, Some(filter))
+            .await
+            .unwrap();
 
         // Subscribe without filter
-        manager.subscribe(topic_id, sub2, None).unwrap();
+        manager.subscribe(topic_id, sub2, None).await.unwrap();
 
         // Publish with matching metadata
         let mut metadata = HashMap::new();
         metadata.insert("type".to_string(), "alert".to_string());
-        let subscribers = manager
-            .publish(topic_id, owner, b"alert!", metadata)
-            .unwrap();
+        let subscribers = recipients(
+            manager
+                .publish(topic_id, owner, b"alert!", metadata)
+                .await
+                .unwrap(),
+        );
 
         // Both should receive
         assert_eq!(subscribers.len(), 2);
 
         // Publish without matching metadata
         let metadata = HashMap::new();
-        let subscribers = manager.publish(topic_id, owner, b"info", metadata).unwrap();
+        let subscribers = recipients(
+            manager
+                .publish(topic_id, owner, b"info", metadata)
+                .await
+                .unwrap(),
+        );
 
         // Only sub2 (no filter) should receive
         assert_eq!(subscribers.len(), 1);
         assert_eq!(subscribers[0], sub2);
     }
 
-    #[test]
-    fn test_topic_deletion_with_cleanup() {
-        let mut manager = TopicManager::new();
+    #[tokio::test]
+    async fn test_topic_deletion_with_cleanup() {
+        let manager = TopicManager::new();
         let owner = create_test_agent();
         let subscriber = create_test_agent();
 
         // Create topic with subscription
         let mut config = TopicConfig::new("temp-topic".to_string(), owner);
         config.subscribe_policy = TopicPolicy::Open;
-        let topic_id = manager.create_topic(config).unwrap();
+        let topic_id = manager.create_topic(config).await.unwrap();
 
-        let sub = manager.subscribe(topic_id, subscriber, None).unwrap();
+        let sub = manager.subscribe(topic_id, subscriber, None).await.unwrap();
 
         // Publish message
         manager
             .publish(topic_id, owner, b"test", HashMap::new())
+            .await
             .unwrap();
 
         // Delete topic
-        manager.delete_topic(topic_id, owner).unwrap();
+        manager.delete_topic(topic_id, owner).await.unwrap();
 
         // Topic should be gone
-        assert!(manager.get_topic(topic_id).is_none());
+        assert!(manager.get_topic(topic_id).await.is_none());
 
         // Subscription should be gone
-        assert!(!manager.subscriptions.contains_key(&sub.id));
+        assert!(matches!(
+            manager.poll(sub.id, None, 10).await,
+            Err(CretoError::NotFound(_))
+        ));
+        assert!(manager.inner.subscriptions.read().await.is_empty());
 
         // Messages should be gone
-        assert!(!manager.topic_messages.contains_key(&topic_id));
+        assert!(manager.topic_state(topic_id).await.is_err());
     }
 
-    #[test]
-    fn test_max_subscribers_limit() {
-        let mut manager = TopicManager::new();
+    #[tokio::test]
+    async fn test_max_subscribers_limit() {
+        let manager = TopicManager::new();
         let owner = create_test_agent();
 
         // Create topic with max 2 subscribers
         let mut config = TopicConfig::new("limited-topic".to_string(), owner);
         config.subscribe_policy = TopicPolicy::Open;
         config.max_subscribers = Some(2);
-        let topic_id = manager.create_topic(config).unwrap();
+        let topic_id = manager.create_topic(config).await.unwrap();
 
         // Add 2 subscribers - should succeed
         let sub1 = create_test_agent();
         let sub2 = create_test_agent();
-        manager.subscribe(topic_id, sub1, None).unwrap();
-        manager.subscribe(topic_id, sub2, None).unwrap();
+        manager.subscribe(topic_id, sub1, None).await.unwrap();
+        manager.subscribe(topic_id, sub2, None).await.unwrap();
 
         // Try to add 3rd subscriber - should fail
         let sub3 = create_test_agent();
-        let result = manager.subscribe(topic_id, sub3, None);
+        let result = manager.subscribe(topic_id, sub3, None).await;
         assert!(result.is_err());
     }
 
-    #[test]
-    fn test_message_size_limit() {
-        let mut manager = TopicManager::new();
+    #[tokio::test]
+    async fn test_message_size_limit() {
+        let manager = TopicManager::new();
         let owner = create_test_agent();
 
         // Create topic with small max message size
         let mut config = TopicConfig::new("small-msg-topic".to_string(), owner);
         config.max_message_size = 100;
-        let topic_id = manager.create_topic(config).unwrap();
+        let topic_id = manager.create_topic(config).await.unwrap();
 
         // Try to publish oversized message
         let large_message = vec![0u8; 200];
-        let result = manager.publish(topic_id, owner, &large_message, HashMap::new());
+        let result = manager
+            .publish(topic_id, owner, &large_message, HashMap::new())
+            .await;
         assert!(result.is_err());
 
         // Publish normal-sized message
         let small_message = vec![0u8; 50];
-        let result = manager.publish(topic_id, owner, &small_message, HashMap::new());
+        let result = manager
+            .publish(topic_id, owner, &small_message, HashMap::new())
+            .await;
         assert!(result.is_ok());
     }
 
-    #[test]
-    fn test_allowlist_policy() {
-        let mut manager = TopicManager::new();
+    #[tokio::test]
+    async fn test_allowlist_policy() {
+        let manager = TopicManager::new();
         let owner = create_test_agent();
         let allowed_agent = create_test_agent();
         let denied_agent = create_test_agent();
@@ -829,22 +1736,797 @@ mod tests {
         config.subscribe_policy = TopicPolicy::Allowlist;
         config.publish_policy = TopicPolicy::Allowlist;
         config.allowed_agents = vec![allowed_agent];
-        let topic_id = manager.create_topic(config).unwrap();
+        let topic_id = manager.create_topic(config).await.unwrap();
 
         // Allowed agent can subscribe
-        let result = manager.subscribe(topic_id, allowed_agent, None);
+        let result = manager.subscribe(topic_id, allowed_agent, None).await;
         assert!(result.is_ok());
 
         // Denied agent cannot subscribe
-        let result = manager.subscribe(topic_id, denied_agent, None);
+        let result = manager.subscribe(topic_id, denied_agent, None).await;
         assert!(result.is_err());
 
         // Allowed agent can publish
-        let result = manager.publish(topic_id, allowed_agent, b"test", HashMap::new());
+        let result = manager
+            .publish(topic_id, allowed_agent, b"test", HashMap::new())
+            .await;
         assert!(result.is_ok());
 
         // Denied agent cannot publish
-        let result = manager.publish(topic_id, denied_agent, b"test", HashMap::new());
+        let result = manager
+            .publish(topic_id, denied_agent, b"test", HashMap::new())
+            .await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_earliest_replay_hands_off_to_live_delivery() {
+        let manager = TopicManager::new();
+        let owner = create_test_agent();
+        let late_joiner = create_test_agent();
+
+        let mut config = TopicConfig::new("workflow".to_string(), owner);
+        config.retention.max_messages = Some(3);
+        let topic_id = manager.create_topic(config).await.unwrap();
+
+        for i in 0..5u8 {
+            manager
+                .publish(topic_id, owner, &[i], HashMap::new())
+                .await
+                .unwrap();
+        }
+
+        let (subscription, backlog) = manager
+            .subscribe_from(topic_id, late_joiner, None, SubscriptionStart::Earliest)
+            .await
+            .unwrap();
+        assert_eq!(subscription.start, SubscriptionStart::Earliest);
+        let payloads: Vec<u8> = backlog.iter().map(|m| m.payload[0]).collect();
+        assert_eq!(payloads, vec![2, 3, 4]);
+
+        // The next publish is delivered live and is not part of the backlog.
+        let recipients = recipients(
+            manager
+                .publish(topic_id, owner, &[5], HashMap::new())
+                .await
+                .unwrap(),
+        );
+        assert_eq!(recipients, vec![late_joiner]);
+        let retained = retained(&manager, topic_id).await;
+        let live = retained.last().unwrap();
+        assert_eq!(*live.payload, [5]);
+        assert!(backlog.iter().all(|m| m.id != live.id));
+        assert_eq!(
+            retained[0].id, backlog[1].id,
+            "trimming after subscribe does not affect the replayed snapshot"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_positions_and_filter() {
+        let manager = TopicManager::new();
+        let owner = create_test_agent();
+        let topic_id = manager
+            .create_topic(TopicConfig::new("events".to_string(), owner))
+            .await
+            .unwrap();
+
+        for (i, kind) in ["alert", "info", "alert", "info"].iter().enumerate() {
+            let mut metadata = HashMap::new();
+            metadata.insert("kind".to_string(), kind.to_string());
+            manager
+                .publish(topic_id, owner, &[i as u8], metadata)
+                .await
+                .unwrap();
+        }
+        let retained = retained(&manager, topic_id).await;
+
+        let filter =
+            SubscriptionFilter::new().with_metadata("kind".to_string(), "alert".to_string());
+        let (_, alerts) = manager
+            .subscribe_from(
+                topic_id,
+                create_test_agent(),
+                Some(filter),
+                SubscriptionStart::Earliest,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            alerts.iter().map(|m| m.payload[0]).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+
+        let (_, after) = manager
+            .subscribe_from(
+                topic_id,
+                create_test_agent(),
+                None,
+                SubscriptionStart::AfterMessageId(retained[1].id),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            after.iter().map(|m| m.payload[0]).collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+
+        let (_, since) = manager
+            .subscribe_from(
+                topic_id,
+                create_test_agent(),
+                None,
+                SubscriptionStart::SinceTimestamp(retained[3].published_at),
+            )
+            .await
+            .unwrap();
+        assert_eq!(since.last().unwrap().id, retained[3].id);
+
+        let (_, latest) = manager
+            .subscribe_from(
+                topic_id,
+                create_test_agent(),
+                None,
+                SubscriptionStart::Latest,
+            )
+            .await
+            .unwrap();
+        assert!(latest.is_empty());
+
+        let unknown = manager
+            .subscribe_from(
+                topic_id,
+                create_test_agent(),
+                None,
+                SubscriptionStart::AfterMessageId(Uuid::new_v4()),
+            )
+            .await;
+        assert!(matches!(unknown, Err(CretoError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_poll_reads_after_cursor() {
+        let manager = TopicManager::new();
+        let owner = create_test_agent();
+        let topic_id = manager
+            .create_topic(TopicConfig::new("jobs".to_string(), owner))
+            .await
+            .unwrap();
+        manager
+            .publish(topic_id, owner, &[0], HashMap::new())
+            .await
+            .unwrap();
+
+        let filter =
+            SubscriptionFilter::new().with_metadata("kind".to_string(), "build".to_string());
+        let subscription = manager
+            .subscribe(topic_id, create_test_agent(), Some(filter))
+            .await
+            .unwrap();
+        for (i, kind) in ["build", "test", "build"].iter().enumerate() {
+            let mut metadata = HashMap::new();
+            metadata.insert("kind".to_string(), kind.to_string());
+            manager
+                .publish(topic_id, owner, &[i as u8 + 1], metadata)
+                .await
+                .unwrap();
+        }
+
+        // Earlier messages and filter non-matches are never returned
+        let first = manager.poll(subscription.id, None, 1).await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(*first[0].payload, [1]);
+        let rest = manager
+            .poll(subscription.id, Some(first[0].id), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            rest.iter().map(|m| m.payload[0]).collect::<Vec<_>>(),
+            vec![3]
+        );
+        assert!(manager
+            .poll(subscription.id, Some(rest[0].id), 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(matches!(
+            manager.poll(Uuid::new_v4(), None, 10).await,
+            Err(CretoError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replay_respects_topic_policies() {
+        let manager = TopicManager::new();
+        let owner = create_test_agent();
+        let other = create_test_agent();
+
+        let mut config = TopicConfig::new("sensitive".to_string(), owner);
+        config.replay_allowed = false;
+        let sensitive_id = manager.create_topic(config).await.unwrap();
+        manager
+            .publish(sensitive_id, owner, b"secret", HashMap::new())
+            .await
+            .unwrap();
+
+        let result = manager
+            .subscribe_from(sensitive_id, other, None, SubscriptionStart::Earliest)
+            .await;
+        assert!(matches!(result, Err(CretoError::Unauthorized(_))));
+        assert!(manager.subscribe(sensitive_id, other, None).await.is_ok());
+
+        let mut config = TopicConfig::new("private".to_string(), owner);
+        config.subscribe_policy = TopicPolicy::Private;
+        let private_id = manager.create_topic(config).await.unwrap();
+        manager
+            .publish(private_id, owner, b"internal", HashMap::new())
+            .await
+            .unwrap();
+        let result = manager
+            .subscribe_from(private_id, other, None, SubscriptionStart::Earliest)
+            .await;
+        assert!(matches!(result, Err(CretoError::Unauthorized(_))));
+    }
+
+    /// Deterministic xorshift generator for the randomized tests.
+    struct Xorshift(u64);
+
+    impl Xorshift {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+
+        fn metadata(&mut self, max_pairs: u64) -> HashMap<String, String> {
+            (0..self.below(max_pairs + 1))
+                .map(|_| (format!("k{}", self.below(4)), format!("v{}", self.below(3))))
+                .collect()
+        }
+    }
+
+    /// Subscribers found by evaluating every subscription's filter.
+    async fn naive_matching(
+        manager: &TopicManager,
+        topic_id: TopicId,
+        metadata: &HashMap<String, String>,
+    ) -> Vec<AgentId> {
+        manager
+            .list_subscribers(topic_id)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|sub| sub.matches(metadata))
+            .map(|sub| sub.subscriber_agent_id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_indexed_fan_out_matches_linear_scan() {
+        for seed in 1..=20u64 {
+            let mut rng = Xorshift(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let manager = TopicManager::new();
+            let owner = create_test_agent();
+            let mut config = TopicConfig::new("randomized".to_string(), owner);
+            config.max_subscribers = None;
+            let topic_id = manager.create_topic(config).await.unwrap();
+            let mut live: Vec<(SubscriptionId, AgentId)> = Vec::new();
+
+            for _ in 0..300 {
+                match rng.below(10) {
+                    // Unsubscribe someone
+                    0 | 1 if !live.is_empty() => {
+                        let (id, agent) = live.swap_remove(rng.below(live.len() as u64) as usize);
+                        manager.unsubscribe(id, agent).await.unwrap();
+                    }
+                    // Subscribe with no filter, an empty one or a random one
+                    0..=5 => {
+                        let filter = match rng.below(3) {
+                            0 => None,
+                            _ => Some(SubscriptionFilter {
+                                metadata: rng.metadata(3),
+                            }),
+                        };
+                        let agent = create_test_agent();
+                        let sub = manager.subscribe(topic_id, agent, filter).await.unwrap();
+                        live.push((sub.id, agent));
+                    }
+                    _ => {
+                        let metadata = rng.metadata(4);
+                        let expected = naive_matching(&manager, topic_id, &metadata).await;
+                        let delivered = recipients(
+                            manager
+                                .publish(topic_id, owner, b"payload", metadata)
+                                .await
+                                .unwrap(),
+                        );
+                        assert_eq!(delivered, expected, "seed {}", seed);
+                    }
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retained_and_replayed_messages_share_payload() {
+        let manager = TopicManager::new();
+        let owner = create_test_agent();
+        let mut config = TopicConfig::new("shared".to_string(), owner);
+        config.retention.max_messages = Some(2);
+        let topic_id = manager.create_topic(config).await.unwrap();
+        for i in 0..5u8 {
+            manager
+                .publish(topic_id, owner, &[i], HashMap::new())
+                .await
+                .unwrap();
+        }
+
+        let (_, backlog) = manager
+            .subscribe_from(
+                topic_id,
+                create_test_agent(),
+                None,
+                SubscriptionStart::Earliest,
+            )
+            .await
+            .unwrap();
+        let retained = retained(&manager, topic_id).await;
+        assert_eq!(retained.len(), 2);
+        assert_eq!(*retained[0].payload, [3]);
+        assert!(Arc::ptr_eq(&retained[1].payload, &backlog[1].payload));
+
+        // Serialized like a plain byte vector
+        let json = serde_json::to_value(&backlog[0]).unwrap();
+        assert_eq!(json["payload"], serde_json::json!([3]));
+        let decoded: TopicMessage = serde_json::from_value(json).unwrap();
+        assert_eq!(*decoded.payload, [3]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_publishes_to_separate_topics() {
+        const TOPICS: usize = 8;
+        const MESSAGES: usize = 50;
+
+        let manager = TopicManager::new();
+        let owner = create_test_agent();
+        let barrier = Arc::new(tokio::sync::Barrier::new(TOPICS));
+
+        let tasks: Vec<_> = (0..TOPICS)
+            .map(|i| {
+                let manager = manager.clone();
+                let barrier = Arc::clone(&barrier);
+                tokio::spawn(async move {
+                    let mut config = TopicConfig::new(format!("topic-{}", i), owner);
+                    config.retention.max_messages = None;
+                    let topic_id = manager.create_topic(config).await.unwrap();
+                    let mut subscriptions = Vec::new();
+                    for _ in 0..=i {
+                        let sub = manager
+                            .subscribe(topic_id, create_test_agent(), None)
+                            .await
+                            .unwrap();
+                        subscriptions.push(sub.id);
+                    }
+
+                    barrier.wait().await;
+                    for n in 0..MESSAGES {
+                        let deliveries = manager
+                            .publish(topic_id, owner, &[n as u8], HashMap::new())
+                            .await
+                            .unwrap();
+                        assert_eq!(
+                            deliveries
+                                .iter()
+                                .map(|d| d.subscription_id)
+                                .collect::<Vec<_>>(),
+                            subscriptions
+                        );
+                        assert!(deliveries
+                            .iter()
+                            .all(|d| Arc::ptr_eq(&d.message, &deliveries[0].message)));
+                    }
+                    topic_id
+                })
+            })
+            .collect();
+
+        for (i, task) in tasks.into_iter().enumerate() {
+            let topic_id = task.await.unwrap();
+            assert_eq!(
+                manager.list_subscribers(topic_id).await.unwrap().len(),
+                i + 1
+            );
+            assert_eq!(retained(&manager, topic_id).await.len(), MESSAGES);
+        }
+        assert_eq!(manager.list_topics().await.len(), TOPICS);
+    }
+
+    fn persisted_manager(
+        topics: &Arc<InMemoryTopicRepository>,
+        subscriptions: &Arc<InMemorySubscriptionRepository>,
+    ) -> TopicManager {
+        TopicManager::new().with_repositories(topics.clone(), subscriptions.clone())
+    }
+
+    #[tokio::test]
+    async fn test_topic_repository_round_trips_policies() {
+        let repo = InMemoryTopicRepository::new();
+        let owner = create_test_agent();
+        let mut topic = Topic::new("audit".to_string(), owner);
+        topic.publish_policy = TopicPolicy::Allowlist;
+        topic.retention = RetentionPolicy {
+            max_messages: Some(50),
+            ttl_seconds: Some(3600),
+        };
+        topic.allowed_agents = vec![create_test_agent()];
+        topic.replay_allowed = false;
+        repo.create(&topic).await.unwrap();
+
+        let duplicate = Topic::new("audit".to_string(), owner);
+        assert!(matches!(
+            repo.create(&duplicate).await,
+            Err(CretoError::ValidationFailed(_))
+        ));
+        // Same name under another owner is fine
+        repo.create(&Topic::new("audit".to_string(), create_test_agent()))
+            .await
+            .unwrap();
+
+        let found = repo.find_by_name(owner, "audit").await.unwrap().unwrap();
+        assert_eq!(found.id, topic.id);
+        assert_eq!(found.publish_policy, TopicPolicy::Allowlist);
+        assert_eq!(found.retention.max_messages, Some(50));
+        assert_eq!(found.retention.ttl_seconds, Some(3600));
+        assert_eq!(found.allowed_agents, topic.allowed_agents);
+        assert!(!found.replay_allowed);
+
+        repo.delete(topic.id).await.unwrap();
+        assert!(repo.find_by_name(owner, "audit").await.unwrap().is_none());
+        assert_eq!(repo.list().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscription_repository_delete_for_topic() {
+        let repo = InMemorySubscriptionRepository::new();
+        let (topic_a, topic_b) = (Uuid::new_v4(), Uuid::new_v4());
+        let a1 = Subscription::new(topic_a, create_test_agent());
+        let a2 = Subscription::new(topic_a, create_test_agent());
+        let b1 = Subscription::new(topic_b, create_test_agent());
+        for sub in [&a1, &a2, &b1] {
+            repo.create(sub).await.unwrap();
+        }
+
+        repo.delete(a1.id).await.unwrap();
+        let ids: Vec<_> = repo.list().await.unwrap().iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![a2.id, b1.id]);
+
+        repo.delete_for_topic(topic_a).await.unwrap();
+        let ids: Vec<_> = repo.list().await.unwrap().iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![b1.id]);
+    }
+
+    #[test]
+    fn test_subscription_filter_db_json_round_trip() {
+        let filter = SubscriptionFilter::new()
+            .with_metadata("type".to_string(), "alert".to_string())
+            .with_metadata("region".to_string(), "eu".to_string());
+        let json = filter.to_db_json().unwrap();
+        assert_eq!(json["metadata"]["type"], "alert");
+
+        let decoded = SubscriptionFilter::from_db_json(json).unwrap();
+        assert_eq!(decoded.metadata, filter.metadata);
+
+        let empty =
+            SubscriptionFilter::from_db_json(SubscriptionFilter::new().to_db_json().unwrap())
+                .unwrap();
+        assert!(empty.metadata.is_empty());
+
+        assert!(matches!(
+            SubscriptionFilter::from_db_json(serde_json::json!({"metadata": 7})),
+            Err(CretoError::SerializationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_subscription_start_json_round_trip() {
+        for start in [
+            SubscriptionStart::Latest,
+            SubscriptionStart::Earliest,
+            SubscriptionStart::AfterMessageId(Uuid::new_v4()),
+            SubscriptionStart::SinceTimestamp(Utc::now()),
+        ] {
+            let json = serde_json::to_value(start).unwrap();
+            assert_eq!(
+                serde_json::from_value::<SubscriptionStart>(json).unwrap(),
+                start
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_topics_and_subscriptions_survive_restart() {
+        let topics = Arc::new(InMemoryTopicRepository::new());
+        let subscriptions = Arc::new(InMemorySubscriptionRepository::new());
+        let manager = persisted_manager(&topics, &subscriptions);
+        let owner = create_test_agent();
+        let (alice, bob) = (create_test_agent(), create_test_agent());
+
+        let mut config = TopicConfig::new("events".to_string(), owner);
+        config.retention = RetentionPolicy {
+            max_messages: Some(2),
+            ttl_seconds: Some(60),
+        };
+        let topic_id = manager.create_topic(config).await.unwrap();
+        let kept = manager.subscribe(topic_id, alice, None).await.unwrap();
+        let filter = SubscriptionFilter::new().with_metadata("type".into(), "alert".into());
+        let dropped = manager
+            .subscribe(topic_id, bob, Some(filter.clone()))
+            .await
+            .unwrap();
+        manager.unsubscribe(dropped.id, bob).await.unwrap();
+        let filtered = manager
+            .subscribe(topic_id, bob, Some(filter))
+            .await
+            .unwrap();
+
+        let other = manager
+            .create_topic(TopicConfig::new("scratch".to_string(), owner))
+            .await
+            .unwrap();
+        manager.subscribe(other, alice, None).await.unwrap();
+        manager.delete_topic(other, owner).await.unwrap();
+
+        let restarted = persisted_manager(&topics, &subscriptions);
+        assert_eq!(restarted.load().await.unwrap(), 1);
+        assert!(restarted.get_topic(other).await.is_none());
+
+        let topic = restarted.get_topic(topic_id).await.unwrap();
+        assert_eq!(topic.retention.max_messages, Some(2));
+        assert_eq!(topic.retention.ttl_seconds, Some(60));
+
+        let subscribers = restarted.list_subscribers(topic_id).await.unwrap();
+        assert_eq!(
+            subscribers.iter().map(|s| s.id).collect::<Vec<_>>(),
+            vec![kept.id, filtered.id]
+        );
+
+        // The rebuilt filter index routes by the stored filter
+        let mut alert = HashMap::new();
+        alert.insert("type".to_string(), "alert".to_string());
+        let deliveries = restarted
+            .publish(topic_id, owner, b"x", HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(recipients(deliveries), vec![alice]);
+        let deliveries = restarted
+            .publish(topic_id, owner, b"y", alert)
+            .await
+            .unwrap();
+        assert_eq!(recipients(deliveries), vec![alice, bob]);
+
+        // Loading again keeps what is already there
+        assert_eq!(restarted.load().await.unwrap(), 0);
+        assert_eq!(retained(&restarted, topic_id).await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_topic_name_conflict_modes() {
+        let topics = Arc::new(InMemoryTopicRepository::new());
+        let subscriptions = Arc::new(InMemorySubscriptionRepository::new());
+        let owner = create_test_agent();
+        let config = || TopicConfig::new("events".to_string(), owner);
+
+        let manager = persisted_manager(&topics, &subscriptions);
+        let topic_id = manager.create_topic(config()).await.unwrap();
+        assert!(matches!(
+            manager.create_topic(config()).await,
+            Err(CretoError::ValidationFailed(_))
+        ));
+        // Another owner may reuse the name
+        manager
+            .create_topic(TopicConfig::new("events".to_string(), create_test_agent()))
+            .await
+            .unwrap();
+
+        let restarted = persisted_manager(&topics, &subscriptions)
+            .with_name_conflict(TopicNameConflict::ReturnExisting);
+        restarted.load().await.unwrap();
+        assert_eq!(restarted.create_topic(config()).await.unwrap(), topic_id);
+        assert_eq!(topics.list().await.unwrap().len(), 2);
+
+        // The name is free again once the topic is deleted
+        restarted.delete_topic(topic_id, owner).await.unwrap();
+        let recreated = restarted.create_topic(config()).await.unwrap();
+        assert_ne!(recreated, topic_id);
+    }
+
+    /// Shift every retained message on a topic back by the given ages,
+    /// oldest first.
+    async fn backdate(manager: &TopicManager, topic_id: TopicId, ages: &[chrono::Duration]) {
+        let state = manager.topic_state(topic_id).await.unwrap();
+        let now = Utc::now();
+        for (message, age) in state.write().await.messages.iter_mut().zip(ages) {
+            message.published_at = now - *age;
+        }
+    }
+
+    #[test]
+    fn test_ttl_boundary() {
+        let mut topic = Topic::new("events".to_string(), create_test_agent());
+        topic.retention.ttl_seconds = Some(60);
+        let now = Utc::now();
+        let message = |age: chrono::Duration| TopicMessage {
+            id: Uuid::new_v4(),
+            topic_id: topic.id,
+            publisher_id: topic.owner_agent_id,
+            payload: Arc::from(&b"x"[..]),
+            metadata: HashMap::new(),
+            published_at: now - age,
+        };
+
+        assert!(topic.is_retained(&message(chrono::Duration::milliseconds(59_999)), now));
+        assert!(!topic.is_retained(&message(chrono::Duration::seconds(60)), now));
+
+        topic.retention.ttl_seconds = None;
+        assert!(topic.is_retained(&message(chrono::Duration::days(365)), now));
+    }
+
+    #[tokio::test]
+    async fn test_expired_messages_dropped_on_sweep_and_publish() {
+        let manager = TopicManager::new();
+        let owner = create_test_agent();
+        let mut config = TopicConfig::new("events".to_string(), owner);
+        config.retention = RetentionPolicy {
+            max_messages: None,
+            ttl_seconds: Some(60),
+        };
+        let topic_id = manager.create_topic(config).await.unwrap();
+        for i in 0..3u8 {
+            manager
+                .publish(topic_id, owner, &[i], HashMap::new())
+                .await
+                .unwrap();
+        }
+
+        backdate(
+            &manager,
+            topic_id,
+            &[
+                chrono::Duration::seconds(61),
+                chrono::Duration::seconds(60),
+                chrono::Duration::seconds(58),
+            ],
+        )
+        .await;
+        assert_eq!(manager.sweep_expired().await, 2);
+        assert_eq!(
+            retained(&manager, topic_id)
+                .await
+                .iter()
+                .map(|m| m.payload[0])
+                .collect::<Vec<_>>(),
+            vec![2]
+        );
+
+        // Publishing drops what expired since the last sweep
+        backdate(&manager, topic_id, &[chrono::Duration::minutes(5)]).await;
+        manager
+            .publish(topic_id, owner, &[3], HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            retained(&manager, topic_id)
+                .await
+                .iter()
+                .map(|m| m.payload[0])
+                .collect::<Vec<_>>(),
+            vec![3]
+        );
+        assert_eq!(manager.sweep_expired().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_with_replay() {
+        let manager = TopicManager::new();
+        let owner = create_test_agent();
+        let topic_id = manager
+            .create_topic(TopicConfig::new("events".to_string(), owner))
+            .await
+            .unwrap();
+        for (i, kind) in ["alert", "info", "alert", "info", "alert"]
+            .iter()
+            .enumerate()
+        {
+            let mut metadata = HashMap::new();
+            metadata.insert("kind".to_string(), kind.to_string());
+            manager
+                .publish(topic_id, owner, &[i as u8], metadata)
+                .await
+                .unwrap();
+        }
+        let payloads = |messages: &[TopicMessage]| -> Vec<u8> {
+            messages.iter().map(|m| m.payload[0]).collect()
+        };
+        let alerts =
+            || Some(SubscriptionFilter::new().with_metadata("kind".into(), "alert".into()));
+
+        let (sub, none) = manager
+            .subscribe_with_replay(topic_id, create_test_agent(), None, ReplayPolicy::None)
+            .await
+            .unwrap();
+        assert!(none.is_empty());
+        assert_eq!(sub.start, SubscriptionStart::Latest);
+
+        // More than is retained replays everything
+        let (_, all) = manager
+            .subscribe_with_replay(topic_id, create_test_agent(), None, ReplayPolicy::LastN(50))
+            .await
+            .unwrap();
+        assert_eq!(payloads(&all), vec![0, 1, 2, 3, 4]);
+
+        // The last N are counted among messages matching the filter
+        let (sub, last) = manager
+            .subscribe_with_replay(
+                topic_id,
+                create_test_agent(),
+                alerts(),
+                ReplayPolicy::LastN(2),
+            )
+            .await
+            .unwrap();
+        assert_eq!(payloads(&last), vec![2, 4]);
+        assert_eq!(
+            sub.start,
+            SubscriptionStart::SinceTimestamp(last[0].published_at)
+        );
+
+        let (_, since) = manager
+            .subscribe_with_replay(
+                topic_id,
+                create_test_agent(),
+                alerts(),
+                ReplayPolicy::Since(all[1].published_at),
+            )
+            .await
+            .unwrap();
+        assert_eq!(payloads(&since), vec![2, 4]);
+
+        let (sub, empty) = manager
+            .subscribe_with_replay(
+                topic_id,
+                create_test_agent(),
+                Some(SubscriptionFilter::new().with_metadata("kind".into(), "debug".into())),
+                ReplayPolicy::LastN(3),
+            )
+            .await
+            .unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(sub.start, SubscriptionStart::Latest);
+    }
+
+    #[tokio::test]
+    async fn test_replay_respects_subscribe_policy() {
+        let manager = TopicManager::new();
+        let owner = create_test_agent();
+        let mut config = TopicConfig::new("private".to_string(), owner);
+        config.subscribe_policy = TopicPolicy::Private;
+        let topic_id = manager.create_topic(config).await.unwrap();
+        manager
+            .publish(topic_id, owner, b"secret", HashMap::new())
+            .await
+            .unwrap();
+
+        let result = manager
+            .subscribe_with_replay(topic_id, create_test_agent(), None, ReplayPolicy::LastN(1))
+            .await;
+        assert!(matches!(result, Err(CretoError::Unauthorized(_))));
+
+        let (_, replayed) = manager
+            .subscribe_with_replay(topic_id, owner, None, ReplayPolicy::LastN(1))
+            .await
+            .unwrap();
+        assert_eq!(replayed.len(), 1);
+    }
 }
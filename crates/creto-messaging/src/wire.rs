@@ -0,0 +1,363 @@
+//! Versioned wire format for envelopes.
+//!
+//! Envelopes carry a major and a minor wire version. Minor versions only add
+//! optional fields, so a node reads every minor version of the majors it
+//! supports. Fields a node does not recognize are captured in the `extra`
+//! maps of the envelope, its header and its payload, kept through
+//! store-and-forward and written back out unchanged, so an older relay never
+//! strips fields a newer recipient relies on.
+//!
+//! An envelope from a newer major version is read with the newest layout
+//! this node knows, unless the sender marked it `critical`; see
+//! [`negotiate`].
+
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoError, CretoResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::envelope::{ContentType, EncryptedPayload, Envelope, EnvelopeHeader, MessagePriority};
+use crate::ratchet::MessageHeader;
+
+/// Newest major wire version this node understands.
+pub const ENVELOPE_MAJOR_VERSION: u8 = 1;
+
+/// Minor wire version written by this node.
+///
+/// Minor 1 added `minor_version`, `critical` and unknown-field preservation;
+/// envelopes without a minor version are 1.0.
+pub const ENVELOPE_MINOR_VERSION: u8 = 1;
+
+/// Decide whether an envelope of the given major version can be accepted.
+///
+/// Only an envelope that is both from a newer major version and marked
+/// critical is rejected. Anything else is accepted, newer majors on a
+/// best-effort basis with their unknown fields preserved.
+pub fn negotiate(major: u8, critical: bool) -> CretoResult<()> {
+    if major > ENVELOPE_MAJOR_VERSION && critical {
+        return Err(CretoError::UnsupportedEnvelopeVersion {
+            major,
+            supported: ENVELOPE_MAJOR_VERSION,
+        });
+    }
+    Ok(())
+}
+
+/// The fields needed to pick a layout, read before the rest of the envelope.
+#[derive(Deserialize)]
+struct VersionProbe {
+    version: u8,
+    #[serde(default)]
+    critical: bool,
+}
+
+/// Decode an envelope from its JSON wire form.
+///
+/// Fails with [`CretoError::UnsupportedEnvelopeVersion`] if [`negotiate`]
+/// rejects its version.
+pub fn decode_value(value: Value) -> CretoResult<Envelope> {
+    let probe = VersionProbe::deserialize(&value)
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+    negotiate(probe.version, probe.critical)?;
+
+    // Version 1 is the only layout so far; newer non-critical majors are
+    // read with it and keep whatever it does not recognize
+    let wire: EnvelopeV1 =
+        serde_json::from_value(value).map_err(|e| CretoError::SerializationError(e.to_string()))?;
+    Ok(wire.into())
+}
+
+/// Encode an envelope to its JSON wire form.
+pub fn encode_value(envelope: &Envelope) -> CretoResult<Value> {
+    serde_json::to_value(EnvelopeV1::from(envelope.clone()))
+        .map_err(|e| CretoError::SerializationError(e.to_string()))
+}
+
+/// Encode an envelope for storage.
+///
+/// Returns the wire form without the ciphertext, which is stored in its own
+/// column so mailbox accounting can size it.
+pub fn encode_for_storage(envelope: &Envelope) -> CretoResult<Value> {
+    let mut doc = encode_value(envelope)?;
+    if let Some(payload) = doc.get_mut("payload").and_then(Value::as_object_mut) {
+        payload.remove("ciphertext");
+    }
+    Ok(doc)
+}
+
+/// Rebuild an envelope from [`encode_for_storage`] output and its ciphertext.
+pub fn decode_from_storage(mut doc: Value, ciphertext: Vec<u8>) -> CretoResult<Envelope> {
+    if let Some(payload) = doc.get_mut("payload").and_then(Value::as_object_mut) {
+        payload.insert("ciphertext".to_string(), Value::from(ciphertext));
+    }
+    decode_value(doc)
+}
+
+fn is_zero(value: &u8) -> bool {
+    *value == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// Wire layout of an envelope, major version 1.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvelopeV1 {
+    pub id: Uuid,
+    pub version: u8,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub minor_version: u8,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub critical: bool,
+    pub header: EnvelopeHeaderV1,
+    pub payload: EncryptedPayloadV1,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Wire layout of an envelope header, major version 1.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvelopeHeaderV1 {
+    pub sender_id: AgentId,
+    pub recipient_id: AgentId,
+    pub ratchet_header: MessageHeader,
+    pub content_type: ContentType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caused_by: Option<Uuid>,
+    #[serde(default)]
+    pub priority: MessagePriority,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Wire layout of an encrypted payload, major version 1.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedPayloadV1 {
+    pub ciphertext: Vec<u8>,
+    pub mac: Vec<u8>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl From<Envelope> for EnvelopeV1 {
+    fn from(envelope: Envelope) -> Self {
+        Self {
+            id: envelope.id,
+            version: envelope.version,
+            minor_version: envelope.minor_version,
+            critical: envelope.critical,
+            header: envelope.header.into(),
+            payload: envelope.payload.into(),
+            timestamp: envelope.timestamp,
+            extra: envelope.extra,
+        }
+    }
+}
+
+impl From<EnvelopeV1> for Envelope {
+    fn from(wire: EnvelopeV1) -> Self {
+        Self {
+            id: wire.id,
+            version: wire.version,
+            minor_version: wire.minor_version,
+            critical: wire.critical,
+            header: wire.header.into(),
+            payload: wire.payload.into(),
+            timestamp: wire.timestamp,
+            extra: wire.extra,
+        }
+    }
+}
+
+impl From<EnvelopeHeader> for EnvelopeHeaderV1 {
+    fn from(header: EnvelopeHeader) -> Self {
+        Self {
+            sender_id: header.sender_id,
+            recipient_id: header.recipient_id,
+            ratchet_header: header.ratchet_header,
+            content_type: header.content_type,
+            reply_to: header.reply_to,
+            correlation_id: header.correlation_id,
+            caused_by: header.caused_by,
+            priority: header.priority,
+            extra: header.extra,
+        }
+    }
+}
+
+impl From<EnvelopeHeaderV1> for EnvelopeHeader {
+    fn from(wire: EnvelopeHeaderV1) -> Self {
+        Self {
+            sender_id: wire.sender_id,
+            recipient_id: wire.recipient_id,
+            ratchet_header: wire.ratchet_header,
+            content_type: wire.content_type,
+            reply_to: wire.reply_to,
+            correlation_id: wire.correlation_id,
+            caused_by: wire.caused_by,
+            priority: wire.priority,
+            extra: wire.extra,
+        }
+    }
+}
+
+impl From<EncryptedPayload> for EncryptedPayloadV1 {
+    fn from(payload: EncryptedPayload) -> Self {
+        Self {
+            ciphertext: payload.ciphertext,
+            mac: payload.mac,
+            extra: payload.extra,
+        }
+    }
+}
+
+impl From<EncryptedPayloadV1> for EncryptedPayload {
+    fn from(wire: EncryptedPayloadV1) -> Self {
+        Self {
+            ciphertext: wire.ciphertext,
+            mac: wire.mac,
+            extra: wire.extra,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mailbox::InMemoryEnvelopeRepository;
+    use crate::repository::EnvelopeRepository;
+    use creto_common::Correlation;
+    use serde_json::json;
+
+    const CONTENT_TYPES: [ContentType; 7] = [
+        ContentType::Text,
+        ContentType::Json,
+        ContentType::Binary,
+        ContentType::ToolRequest,
+        ContentType::ToolResponse,
+        ContentType::Status,
+        ContentType::Control,
+    ];
+
+    const PRIORITIES: [MessagePriority; 3] = [
+        MessagePriority::Low,
+        MessagePriority::Normal,
+        MessagePriority::High,
+    ];
+
+    fn envelope(ciphertext: Vec<u8>) -> Envelope {
+        let ratchet_header = MessageHeader {
+            dh_public: vec![7u8; 32],
+            prev_chain_length: 3,
+            message_number: 9,
+        };
+        Envelope::new(AgentId::new(), AgentId::new(), ratchet_header, ciphertext)
+    }
+
+    fn extras(key: &str) -> Map<String, Value> {
+        let mut extra = Map::new();
+        extra.insert(key.to_string(), json!({ "nested": [1, "two", null] }));
+        extra
+    }
+
+    #[test]
+    fn test_round_trip_every_field_combination() {
+        for content_type in CONTENT_TYPES {
+            for priority in PRIORITIES {
+                for optional in [false, true] {
+                    let mut original = envelope(vec![1, 2, 3])
+                        .with_content_type(content_type)
+                        .with_priority(priority);
+                    if optional {
+                        original = original
+                            .with_reply_to(Uuid::now_v7())
+                            .with_correlation(Correlation::new(Uuid::now_v7()));
+                        original.critical = true;
+                        original.extra = extras("future_top");
+                        original.header.extra = extras("future_header");
+                        original.payload.extra = extras("future_payload");
+                    }
+                    let expected = encode_value(&original).unwrap();
+
+                    let via_wire = Envelope::from(EnvelopeV1::from(original.clone()));
+                    assert_eq!(encode_value(&via_wire).unwrap(), expected);
+
+                    let via_bytes = Envelope::from_bytes(&original.to_bytes().unwrap()).unwrap();
+                    assert_eq!(encode_value(&via_bytes).unwrap(), expected);
+
+                    let stored = encode_for_storage(&original).unwrap();
+                    assert!(stored["payload"].get("ciphertext").is_none());
+                    let via_storage = decode_from_storage(stored, vec![1, 2, 3]).unwrap();
+                    assert_eq!(encode_value(&via_storage).unwrap(), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_version_negotiation() {
+        assert!(negotiate(1, false).is_ok());
+        assert!(negotiate(1, true).is_ok());
+        assert!(negotiate(ENVELOPE_MAJOR_VERSION + 1, false).is_ok());
+
+        let err = negotiate(ENVELOPE_MAJOR_VERSION + 1, true).unwrap_err();
+        assert_eq!(err.code(), "ENABLE-039");
+
+        let mut future = encode_value(&envelope(vec![1])).unwrap();
+        future["version"] = json!(2);
+        future["critical"] = json!(true);
+        assert!(matches!(
+            decode_value(future),
+            Err(CretoError::UnsupportedEnvelopeVersion {
+                major: 2,
+                supported: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_legacy_envelope_forwards_unchanged() {
+        // Written before minor versions existed
+        let mut legacy = encode_value(&envelope(vec![4, 5])).unwrap();
+        legacy.as_object_mut().unwrap().remove("minor_version");
+
+        let decoded = decode_value(legacy.clone()).unwrap();
+        assert_eq!(decoded.minor_version, 0);
+        assert!(!decoded.critical);
+        assert_eq!(encode_value(&decoded).unwrap(), legacy);
+    }
+
+    #[tokio::test]
+    async fn test_future_envelope_passes_through_old_node() {
+        let repo = InMemoryEnvelopeRepository::new();
+
+        for version in [1, 2] {
+            let mut future = encode_value(&envelope(vec![9; 12])).unwrap();
+            future["version"] = json!(version);
+            future["minor_version"] = json!(4);
+            future["ttl_ms"] = json!(30_000);
+            future["header"]["routing_hint"] = json!({ "region": "eu-west", "hops": 2 });
+            future["payload"]["compression"] = json!("zstd");
+            let bytes = serde_json::to_vec(&future).unwrap();
+
+            // Parse, store, fetch and forward as a node at version 1.1 would
+            let parsed = Envelope::from_bytes(&bytes).unwrap();
+            let recipient = parsed.header.recipient_id;
+            repo.store(&parsed).await.unwrap();
+            let record = repo.get_undelivered(recipient, 10).await.unwrap().remove(0);
+            let fetched = record.envelope.unwrap();
+            let stored = encode_for_storage(&fetched).unwrap();
+            let reloaded = decode_from_storage(stored, record.ciphertext).unwrap();
+
+            let forwarded: Value = serde_json::from_slice(&reloaded.to_bytes().unwrap()).unwrap();
+            assert_eq!(forwarded, future);
+        }
+    }
+}
@@ -5,7 +5,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::keys::{IdentityKey, KeyBundle, PreKey};
+use crate::keys::{IdentityKey, KeyBundle, PreKey, SignedPreKey};
 
 /// Parameters for X3DH key agreement.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +27,14 @@ pub struct X3DHParams {
     pub recipient_one_time_prekey_id: Option<u32>,
 }
 
+impl X3DHParams {
+    /// Whether the handshake used one of the recipient's one-time pre-keys,
+    /// rather than only its signed pre-key.
+    pub fn used_one_time_prekey(&self) -> bool {
+        self.recipient_one_time_prekey_id.is_some()
+    }
+}
+
 /// Result of X3DH key agreement.
 #[derive(Debug, Clone)]
 pub struct X3DHResult {
@@ -36,6 +44,11 @@ pub struct X3DHResult {
     /// Parameters to send to the other party.
     pub params: X3DHParams,
 
+    /// Whether a one-time pre-key was part of the agreement. Without one
+    /// the session relies on the signed pre-key alone and loses forward
+    /// secrecy if that key is later compromised.
+    pub used_one_time_prekey: bool,
+
     /// Associated data (for AEAD).
     pub associated_data: Vec<u8>,
 }
@@ -106,6 +119,7 @@ impl X3DH {
 
         Ok(X3DHResult {
             shared_secret,
+            used_one_time_prekey: params.used_one_time_prekey(),
             params,
             associated_data,
         })
@@ -118,6 +132,24 @@ impl X3DH {
         recipient_bundle: &KeyBundle,
         params: &X3DHParams,
         consumed_prekey: Option<&PreKey>,
+    ) -> creto_common::CretoResult<X3DHResult> {
+        Self::respond_with_signed_pre_key(
+            recipient_bundle,
+            &recipient_bundle.signed_pre_key,
+            params,
+            consumed_prekey,
+        )
+    }
+
+    /// Respond to a handshake that references a specific signed pre-key.
+    ///
+    /// Used after rotation, when an initiator may still hold a bundle with
+    /// the previous signed pre-key.
+    pub fn respond_with_signed_pre_key(
+        recipient_bundle: &KeyBundle,
+        signed_pre_key: &SignedPreKey,
+        params: &X3DHParams,
+        consumed_prekey: Option<&PreKey>,
     ) -> creto_common::CretoResult<X3DHResult> {
         // X3DH as responder:
         // 1. Look up SPK_B and OPK_B from params
@@ -128,7 +160,7 @@ impl X3DH {
         // 6. SK = KDF(DH1 || DH2 || DH3 [|| DH4])
 
         // Verify we have the right keys
-        if params.recipient_signed_prekey_id != recipient_bundle.signed_pre_key.id {
+        if params.recipient_signed_prekey_id != signed_pre_key.id {
             return Err(creto_common::CretoError::CryptoError(
                 "Signed pre-key ID mismatch".to_string(),
             ));
@@ -155,6 +187,7 @@ impl X3DH {
         Ok(X3DHResult {
             shared_secret,
             params: params.clone(),
+            used_one_time_prekey: params.used_one_time_prekey(),
             associated_data,
         })
     }
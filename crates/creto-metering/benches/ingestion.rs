@@ -21,6 +21,8 @@ fn create_sample_event(index: u64) -> UsageEvent {
             "status_code": 200
         }),
         delegation_depth: 0,
+        correlation_id: None,
+        caused_by: None,
     }
 }
 
@@ -54,8 +56,8 @@ fn bench_event_validation(c: &mut Criterion) {
     let strict_config = ValidationConfig {
         max_transaction_id_length: 255,
         max_quantity: 1_000_000,
-        max_future_hours: 1,
-        max_past_days: 7,
+        max_future_skew: chrono::Duration::hours(1),
+        max_past_age: chrono::Duration::days(7),
         max_properties_bytes: 65536,
         max_delegation_depth: 3,
         max_external_subscription_id_length: 255,
@@ -66,8 +68,8 @@ fn bench_event_validation(c: &mut Criterion) {
     let lenient_config = ValidationConfig {
         max_transaction_id_length: 512,
         max_quantity: 10_000_000,
-        max_future_hours: 24,
-        max_past_days: 30,
+        max_future_skew: chrono::Duration::hours(24),
+        max_past_age: chrono::Duration::days(30),
         max_properties_bytes: 262144,
         max_delegation_depth: 10,
         max_external_subscription_id_length: 512,
@@ -115,6 +117,7 @@ fn bench_dedup_check(c: &mut Criterion) {
         key_prefix: "test:".to_string(),
         use_local_fallback: true,
         local_cache_max_size: 10000,
+        ..Default::default()
     };
 
     group.bench_function("dedup_check_1000_unique", |b| {
@@ -191,8 +194,8 @@ fn bench_batch_processing(c: &mut Criterion) {
             let validation_config = ValidationConfig {
                 max_transaction_id_length: 255,
                 max_quantity: 1_000_000,
-                max_future_hours: 1,
-                max_past_days: 7,
+                max_future_skew: chrono::Duration::hours(1),
+                max_past_age: chrono::Duration::days(7),
                 max_properties_bytes: 65536,
                 max_delegation_depth: 3,
                 max_external_subscription_id_length: 255,
@@ -204,6 +207,7 @@ fn bench_batch_processing(c: &mut Criterion) {
                 key_prefix: "bench:".to_string(),
                 use_local_fallback: true,
                 local_cache_max_size: 10000,
+                ..Default::default()
             };
 
             let validator = EventValidator::new(validation_config);
@@ -257,8 +261,8 @@ fn bench_high_throughput_stress(c: &mut Criterion) {
         let validation_config = ValidationConfig {
             max_transaction_id_length: 255,
             max_quantity: 1_000_000,
-            max_future_hours: 1,
-            max_past_days: 7,
+            max_future_skew: chrono::Duration::hours(1),
+            max_past_age: chrono::Duration::days(7),
             max_properties_bytes: 65536,
             max_delegation_depth: 3,
             max_external_subscription_id_length: 255,
@@ -270,6 +274,7 @@ fn bench_high_throughput_stress(c: &mut Criterion) {
             key_prefix: "stress:".to_string(),
             use_local_fallback: true,
             local_cache_max_size: 20000,
+            ..Default::default()
         };
 
         let validator = EventValidator::new(validation_config);
@@ -302,8 +307,8 @@ fn bench_high_throughput_stress(c: &mut Criterion) {
         let validation_config = ValidationConfig {
             max_transaction_id_length: 512,
             max_quantity: 10_000_000,
-            max_future_hours: 24,
-            max_past_days: 30,
+            max_future_skew: chrono::Duration::hours(24),
+            max_past_age: chrono::Duration::days(30),
             max_properties_bytes: 262144,
             max_delegation_depth: 5,
             max_external_subscription_id_length: 512,
@@ -315,6 +320,7 @@ fn bench_high_throughput_stress(c: &mut Criterion) {
             key_prefix: "stress20k:".to_string(),
             use_local_fallback: true,
             local_cache_max_size: 30000,
+            ..Default::default()
         };
 
         let validator = EventValidator::new(validation_config);
@@ -346,12 +352,75 @@ fn bench_high_throughput_stress(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark: Per-Event vs Batched Validation and Deduplication
+/// Compares events/sec of the per-event path with the batch path used by
+/// the gRPC batch handler, with every 10th event a duplicate
+fn bench_batch_vectorized(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_vectorized");
+    group.measurement_time(Duration::from_secs(10));
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let validator = EventValidator::new(ValidationConfig::default());
+    let dedup_config = DedupConfig {
+        local_cache_max_size: 20_000,
+        ..Default::default()
+    };
+
+    for batch_size in [100u64, 1000, 5000] {
+        group.throughput(Throughput::Elements(batch_size));
+        let events: Vec<UsageEvent> = (0..batch_size)
+            .map(|i| create_sample_event(if i % 10 == 9 { i - 1 } else { i }))
+            .collect();
+
+        group.bench_function(format!("per_event_{}", batch_size), |b| {
+            b.iter(|| {
+                let deduplicator = Deduplicator::local_only(dedup_config.clone());
+                runtime.block_on(async {
+                    let now = chrono::Utc::now();
+                    let mut accepted = 0;
+                    for event in &events {
+                        if validator.validate_at(event, now).is_ok()
+                            && matches!(
+                                deduplicator.check_and_mark(&event.transaction_id).await,
+                                Ok(result) if result.is_new()
+                            )
+                        {
+                            accepted += 1;
+                        }
+                    }
+                    black_box(accepted);
+                });
+            });
+        });
+
+        group.bench_function(format!("batched_{}", batch_size), |b| {
+            b.iter(|| {
+                let deduplicator = Deduplicator::local_only(dedup_config.clone());
+                runtime.block_on(async {
+                    let validation = validator.validate_batch_at(&events, chrono::Utc::now());
+                    let txn_ids: Vec<&str> = events
+                        .iter()
+                        .zip(&validation.outcomes)
+                        .filter(|(_, outcome)| outcome.is_ok())
+                        .map(|(event, _)| event.transaction_id.as_str())
+                        .collect();
+                    let results = deduplicator.check_batch(&txn_ids).await.unwrap();
+                    black_box(results.iter().filter(|r| r.is_new()).count());
+                });
+            });
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_event_creation,
     bench_event_validation,
     bench_dedup_check,
     bench_batch_processing,
-    bench_high_throughput_stress
+    bench_high_throughput_stress,
+    bench_batch_vectorized
 );
 criterion_main!(benches);
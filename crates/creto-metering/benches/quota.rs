@@ -148,6 +148,9 @@ fn bench_reservation(c: &mut Criterion) {
     group.measurement_time(Duration::from_secs(10));
 
     group.bench_function("reserve_and_release_100", |b| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
         let enforcer = QuotaEnforcer::with_defaults();
         let org_id = creto_common::OrganizationId::new();
         let agent_id = creto_common::AgentId::new();
@@ -157,29 +160,95 @@ fn bench_reservation(c: &mut Criterion) {
         enforcer.register_quota(&quota);
 
         b.iter(|| {
-            for i in 0..100 {
-                let res_id = enforcer
-                    .reserve(&org_id, &agent_id, "compute", 10, 60)
-                    .unwrap();
-                black_box(res_id);
-
-                if i % 2 == 0 {
-                    let _ = black_box(enforcer.release_reservation(res_id));
-                } else {
-                    let _ = black_box(enforcer.commit_reservation(res_id, 8));
+            runtime.block_on(async {
+                for i in 0..100 {
+                    let res_id = enforcer
+                        .reserve(&org_id, &agent_id, "compute", 10, 60)
+                        .await
+                        .unwrap();
+                    black_box(res_id);
+
+                    if i % 2 == 0 {
+                        let _ = black_box(enforcer.release_reservation(res_id).await);
+                    } else {
+                        let _ = black_box(enforcer.commit_reservation(res_id, 8).await);
+                    }
                 }
-            }
+            })
         });
     });
 
     group.finish();
 }
 
+/// Benchmark: Checks and usage updates contending for the same quotas
+///
+/// Every thread runs nine checks per usage update across a small pool of
+/// agents, so readers and writers keep meeting on the same shards.
+fn bench_contended_check_record(c: &mut Criterion) {
+    const OPS_PER_THREAD: u64 = 1000;
+
+    let mut group = c.benchmark_group("contended_check_record");
+    group.measurement_time(Duration::from_secs(10));
+
+    let enforcer = QuotaEnforcer::with_defaults();
+    let org_id = creto_common::OrganizationId::new();
+    let agents: Vec<creto_common::AgentId> =
+        (0..16).map(|_| creto_common::AgentId::new()).collect();
+    enforcer.register_quota(&Quota::new(
+        org_id,
+        "api_calls",
+        i64::MAX / 2,
+        QuotaPeriod::Daily,
+    ));
+    for agent_id in agents.iter().step_by(2) {
+        let mut quota = Quota::new(org_id, "api_calls", i64::MAX / 2, QuotaPeriod::Daily);
+        quota.agent_id = Some(*agent_id);
+        enforcer.register_quota(&quota);
+    }
+
+    for threads in [1usize, 8, 64] {
+        group.throughput(Throughput::Elements(threads as u64 * OPS_PER_THREAD));
+        group.bench_function(format!("mixed_90_10_{}_threads", threads), |b| {
+            b.iter(|| {
+                std::thread::scope(|scope| {
+                    for thread in 0..threads {
+                        let (enforcer, agents) = (&enforcer, &agents);
+                        scope.spawn(move || {
+                            for op in 0..OPS_PER_THREAD as usize {
+                                let agent_id = &agents[(thread + op) % agents.len()];
+                                if op % 10 == 9 {
+                                    let _ = black_box(enforcer.record_usage(
+                                        &org_id,
+                                        agent_id,
+                                        "api_calls",
+                                        1,
+                                    ));
+                                } else {
+                                    let _ = black_box(enforcer.check(
+                                        &org_id,
+                                        agent_id,
+                                        "api_calls",
+                                        1,
+                                    ));
+                                }
+                            }
+                        });
+                    }
+                });
+            });
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_bloom_filter,
     bench_enforcer_check,
     bench_quota_latency,
-    bench_reservation
+    bench_reservation,
+    bench_contended_check_record
 );
 criterion_main!(benches);
@@ -11,7 +11,7 @@ fuzz_target!(|data: &[u8]| {
     // Try to parse arbitrary bytes as UTF-8 strings for UUIDs
     if let Ok(s) = std::str::from_utf8(data) {
         // Split the input into multiple fields
-        let parts: Vec<&str> = s.splitn(4, '\n').collect();
+        let parts: Vec<&str> = s.splitn(6, '\n').collect();
 
         if parts.len() >= 3 {
             let event = GrpcUsageEvent {
@@ -25,6 +25,8 @@ fuzz_target!(|data: &[u8]| {
                 timestamp: None,
                 properties: None,
                 delegation_depth: 0,
+                correlation_id: parts.get(4).map(|s| s.to_string()),
+                caused_by: parts.get(5).map(|s| s.to_string()),
             };
 
             // This should never panic, only return errors
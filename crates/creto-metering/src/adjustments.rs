@@ -0,0 +1,1051 @@
+//! Invoice adjustments and credit notes.
+//!
+//! When a customer disputes a charge the invoice is corrected through an
+//! [`InvoiceAdjustment`] rather than by editing totals. An adjustment carries
+//! a signed amount in the invoice currency's minor units (negative credits
+//! the customer, positive adds a charge), optionally against one line item,
+//! with the reason and who proposed it.
+//!
+//! Adjustments move `proposed → approved → applied`, or end `rejected`.
+//! Adjustments to a draft invoice are approved as they are proposed; once an
+//! invoice is finalized they must pass an [`AdjustmentApprover`], which may
+//! decide immediately or defer (e.g. to an oversight request) and have the
+//! decision recorded later with [`InvoiceAdjustmentManager::resolve`].
+//!
+//! Applying an approved adjustment:
+//!
+//! - to a **paid** invoice issues a credit note: a new invoice of negative
+//!   amount referencing the original through [`Invoice::credit_note_for`];
+//! - to an **unpaid** invoice adds an adjustment line item and recomputes
+//!   the tax and total in place.
+//!
+//! Either way the original invoice's [`Invoice::adjustments`] summary shows
+//! every adjustment and where it stands. Applying is idempotent per
+//! adjustment: applying one twice returns the first application and changes
+//! nothing.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use creto_common::{types::Money, Clock, CretoError, CretoResult, OrganizationId, SystemClock};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::invoice::{tax_cents, InMemoryInvoiceRepository, Invoice, InvoiceStatus, LineItem};
+use crate::repository::InvoiceAdjustmentRepository;
+
+/// Metric code of the line items adjustments add.
+pub const ADJUSTMENT_METRIC_CODE: &str = "adjustment";
+
+/// Where an adjustment stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentStatus {
+    /// Waiting for approval.
+    Proposed,
+    /// Approved, not yet applied.
+    Approved,
+    /// Applied to the invoice or issued as a credit note.
+    Applied,
+    /// Refused by the approver.
+    Rejected,
+}
+
+/// A correction to an invoice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvoiceAdjustment {
+    /// Unique adjustment ID; also the idempotency key for applying it.
+    pub id: Uuid,
+    /// Invoice being adjusted.
+    pub invoice_id: Uuid,
+    /// Organization the invoice bills.
+    pub organization_id: OrganizationId,
+    /// Optional: Line item the adjustment corrects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_item_id: Option<Uuid>,
+    /// Signed amount in minor units of the invoice currency, before tax
+    /// (negative = credit to the customer).
+    pub amount_cents: i64,
+    /// Why the invoice is adjusted.
+    pub reason: String,
+    /// Who proposed the adjustment.
+    pub created_by: String,
+    /// Current status.
+    pub status: AdjustmentStatus,
+    /// Who approved or rejected the adjustment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_by: Option<String>,
+    /// Why the adjustment was rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejection_reason: Option<String>,
+    /// Credit note issued when the adjustment was applied to a paid invoice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credit_note_id: Option<Uuid>,
+    /// When the adjustment was proposed.
+    pub created_at: DateTime<Utc>,
+    /// When it was approved or rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_at: Option<DateTime<Utc>>,
+    /// When it was applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+impl InvoiceAdjustment {
+    /// Create a proposed adjustment of the whole invoice.
+    pub fn new(
+        invoice: &Invoice,
+        amount_cents: i64,
+        reason: impl Into<String>,
+        created_by: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            invoice_id: invoice.id,
+            organization_id: invoice.organization_id,
+            line_item_id: None,
+            amount_cents,
+            reason: reason.into(),
+            created_by: created_by.into(),
+            status: AdjustmentStatus::Proposed,
+            reviewed_by: None,
+            rejection_reason: None,
+            credit_note_id: None,
+            created_at: Utc::now(),
+            reviewed_at: None,
+            applied_at: None,
+        }
+    }
+
+    /// Adjust one line item.
+    pub fn with_line_item(mut self, line_item_id: Uuid) -> Self {
+        self.line_item_id = Some(line_item_id);
+        self
+    }
+
+    /// Whether the adjustment credits the customer.
+    pub fn is_credit(&self) -> bool {
+        self.amount_cents < 0
+    }
+
+    fn summary(&self) -> AdjustmentSummary {
+        AdjustmentSummary {
+            adjustment_id: self.id,
+            line_item_id: self.line_item_id,
+            amount_cents: self.amount_cents,
+            reason: self.reason.clone(),
+            status: self.status,
+            credit_note_id: self.credit_note_id,
+        }
+    }
+}
+
+/// An adjustment as recorded on the invoice it adjusts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdjustmentSummary {
+    /// Adjustment ID.
+    pub adjustment_id: Uuid,
+    /// Line item adjusted, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_item_id: Option<Uuid>,
+    /// Signed amount before tax.
+    pub amount_cents: i64,
+    /// Why the invoice was adjusted.
+    pub reason: String,
+    /// Current status.
+    pub status: AdjustmentStatus,
+    /// Credit note issued for the adjustment, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credit_note_id: Option<Uuid>,
+}
+
+/// An approver's decision on a proposed adjustment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdjustmentDecision {
+    /// Approve the adjustment.
+    Approve { reviewer: String },
+    /// Refuse the adjustment.
+    Reject { reviewer: String, reason: String },
+    /// Decide later, e.g. once an oversight request resolves; the
+    /// adjustment stays proposed.
+    Defer,
+}
+
+/// Approval step for adjustments to finalized invoices, such as an
+/// oversight request.
+///
+/// Returning an error leaves the adjustment proposed.
+#[trait_variant::make(AdjustmentApprover: Send)]
+pub trait LocalAdjustmentApprover {
+    /// Decide on a proposed adjustment to `invoice`.
+    async fn review(
+        &self,
+        adjustment: &InvoiceAdjustment,
+        invoice: &Invoice,
+    ) -> CretoResult<AdjustmentDecision>;
+}
+
+/// Result of applying an adjustment.
+#[derive(Debug, Clone)]
+pub struct AdjustmentApplication {
+    /// The applied adjustment.
+    pub adjustment: InvoiceAdjustment,
+    /// Credit note issued by this call, for paid invoices.
+    pub credit_note: Option<Invoice>,
+    /// Whether the adjustment had already been applied, in which case
+    /// nothing changed.
+    pub replayed: bool,
+}
+
+/// In-memory adjustment store for testing and development.
+///
+/// Writes adjusted invoices into the given [`InMemoryInvoiceRepository`]
+/// while holding its own lock, so readers never see an adjustment without
+/// its invoices.
+#[derive(Debug)]
+pub struct InMemoryInvoiceAdjustmentRepository {
+    adjustments: RwLock<HashMap<Uuid, InvoiceAdjustment>>,
+    invoices: Arc<InMemoryInvoiceRepository>,
+}
+
+impl InMemoryInvoiceAdjustmentRepository {
+    /// Create an empty store writing invoices to `invoices`.
+    pub fn new(invoices: Arc<InMemoryInvoiceRepository>) -> Self {
+        Self {
+            adjustments: RwLock::new(HashMap::new()),
+            invoices,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl InvoiceAdjustmentRepository for InMemoryInvoiceAdjustmentRepository {
+    async fn insert_adjustment(
+        &self,
+        adjustment: &InvoiceAdjustment,
+        invoice: &Invoice,
+    ) -> Result<(), CretoError> {
+        let mut adjustments = self.adjustments.write().unwrap();
+        if adjustments.contains_key(&adjustment.id) {
+            return Err(CretoError::DuplicateTransaction(adjustment.id.to_string()));
+        }
+        adjustments.insert(adjustment.id, adjustment.clone());
+        self.invoices.store(invoice);
+        Ok(())
+    }
+
+    async fn get_adjustment(&self, id: Uuid) -> Result<Option<InvoiceAdjustment>, CretoError> {
+        Ok(self.adjustments.read().unwrap().get(&id).cloned())
+    }
+
+    async fn list_by_invoice(
+        &self,
+        invoice_id: Uuid,
+    ) -> Result<Vec<InvoiceAdjustment>, CretoError> {
+        let mut adjustments: Vec<_> = self
+            .adjustments
+            .read()
+            .unwrap()
+            .values()
+            .filter(|a| a.invoice_id == invoice_id)
+            .cloned()
+            .collect();
+        adjustments.sort_by_key(|a| (a.created_at, a.id));
+        Ok(adjustments)
+    }
+
+    async fn update_if_status(
+        &self,
+        adjustment: &InvoiceAdjustment,
+        expected: AdjustmentStatus,
+        invoices: &[&Invoice],
+    ) -> Result<bool, CretoError> {
+        let mut adjustments = self.adjustments.write().unwrap();
+        match adjustments.get_mut(&adjustment.id) {
+            Some(stored) if stored.status == expected => {
+                *stored = adjustment.clone();
+                for invoice in invoices {
+                    self.invoices.store(invoice);
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Proposes, approves and applies invoice adjustments.
+///
+/// Invoices are passed in and updated in place, and the repository stores
+/// them with each adjustment. Clones share the same repository.
+pub struct InvoiceAdjustmentManager<R: ?Sized> {
+    repository: Arc<R>,
+    tax_rate: f64,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R: ?Sized> Clone for InvoiceAdjustmentManager<R> {
+    fn clone(&self) -> Self {
+        Self {
+            repository: Arc::clone(&self.repository),
+            tax_rate: self.tax_rate,
+            clock: Arc::clone(&self.clock),
+        }
+    }
+}
+
+impl<R> InvoiceAdjustmentManager<R>
+where
+    R: InvoiceAdjustmentRepository + ?Sized,
+{
+    /// Manage adjustments stored in `repository`, with no tax.
+    pub fn new(repository: Arc<R>) -> Self {
+        Self {
+            repository,
+            tax_rate: 0.0,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the tax rate (percentage) applied to adjustment amounts, matching
+    /// the [`InvoiceGenerator`](crate::InvoiceGenerator)'s.
+    pub fn with_tax_rate(mut self, tax_rate: f64) -> Self {
+        self.tax_rate = tax_rate;
+        self
+    }
+
+    /// Set the time source.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Propose an adjustment to a draft invoice, approving it immediately.
+    ///
+    /// Fails with [`CretoError::Unauthorized`] if the invoice is finalized;
+    /// use [`InvoiceAdjustmentManager::propose_with_approval`].
+    pub async fn propose(
+        &self,
+        mut adjustment: InvoiceAdjustment,
+        invoice: &mut Invoice,
+    ) -> CretoResult<InvoiceAdjustment> {
+        if invoice.status != InvoiceStatus::Draft {
+            return Err(CretoError::Unauthorized(format!(
+                "Adjustments to finalized invoice {} require approval",
+                invoice.number
+            )));
+        }
+        self.validate(&adjustment, invoice)?;
+
+        adjustment.status = AdjustmentStatus::Approved;
+        adjustment.created_at = self.clock.now();
+        adjustment.reviewed_at = Some(adjustment.created_at);
+        let mut updated = invoice.clone();
+        record_summary(&mut updated, &adjustment);
+        self.repository
+            .insert_adjustment(&adjustment, &updated)
+            .await?;
+        *invoice = updated;
+        info!(
+            adjustment_id = %adjustment.id,
+            invoice_id = %invoice.id,
+            amount_cents = adjustment.amount_cents,
+            created_by = %adjustment.created_by,
+            "Invoice adjustment approved on a draft invoice"
+        );
+        Ok(adjustment)
+    }
+
+    /// Propose an adjustment and have `approver` decide on it.
+    ///
+    /// The adjustment is stored as proposed before the approver runs, so a
+    /// deferred decision can be recorded with
+    /// [`InvoiceAdjustmentManager::resolve`]. Invalid adjustments are
+    /// refused before they reach the approver.
+    pub async fn propose_with_approval<A>(
+        &self,
+        mut adjustment: InvoiceAdjustment,
+        invoice: &mut Invoice,
+        approver: &A,
+    ) -> CretoResult<InvoiceAdjustment>
+    where
+        A: AdjustmentApprover + Sync,
+    {
+        self.validate(&adjustment, invoice)?;
+
+        adjustment.status = AdjustmentStatus::Proposed;
+        adjustment.created_at = self.clock.now();
+        let mut updated = invoice.clone();
+        record_summary(&mut updated, &adjustment);
+        self.repository
+            .insert_adjustment(&adjustment, &updated)
+            .await?;
+        *invoice = updated;
+        info!(
+            adjustment_id = %adjustment.id,
+            invoice_id = %invoice.id,
+            amount_cents = adjustment.amount_cents,
+            created_by = %adjustment.created_by,
+            "Invoice adjustment proposed"
+        );
+
+        let decision = approver.review(&adjustment, invoice).await?;
+        self.resolve(adjustment.id, decision, invoice).await
+    }
+
+    /// Record a decision on a proposed adjustment.
+    ///
+    /// [`AdjustmentDecision::Defer`] leaves it proposed.
+    pub async fn resolve(
+        &self,
+        adjustment_id: Uuid,
+        decision: AdjustmentDecision,
+        invoice: &mut Invoice,
+    ) -> CretoResult<InvoiceAdjustment> {
+        let current = self.get_for(adjustment_id, invoice).await?;
+        let mut adjustment = current.clone();
+        match decision {
+            AdjustmentDecision::Defer => return Ok(current),
+            AdjustmentDecision::Approve { reviewer } => {
+                adjustment.status = AdjustmentStatus::Approved;
+                adjustment.reviewed_by = Some(reviewer);
+            }
+            AdjustmentDecision::Reject { reviewer, reason } => {
+                adjustment.status = AdjustmentStatus::Rejected;
+                adjustment.reviewed_by = Some(reviewer);
+                adjustment.rejection_reason = Some(reason);
+            }
+        }
+        adjustment.reviewed_at = Some(self.clock.now());
+
+        let mut updated = invoice.clone();
+        record_summary(&mut updated, &adjustment);
+        if !self
+            .repository
+            .update_if_status(&adjustment, AdjustmentStatus::Proposed, &[&updated])
+            .await?
+        {
+            return Err(CretoError::InvalidStateTransition {
+                from: format!("{:?}", current.status),
+                to: format!("{:?}", adjustment.status),
+            });
+        }
+        *invoice = updated;
+        info!(
+            adjustment_id = %adjustment.id,
+            invoice_id = %invoice.id,
+            status = ?adjustment.status,
+            reviewed_by = ?adjustment.reviewed_by,
+            "Invoice adjustment reviewed"
+        );
+        Ok(adjustment)
+    }
+
+    /// Apply an approved adjustment to its invoice.
+    ///
+    /// Paid invoices get a credit note; unpaid ones are updated in place.
+    /// An adjustment already applied is returned as it is, with
+    /// [`AdjustmentApplication::replayed`] set and the invoice untouched.
+    pub async fn apply(
+        &self,
+        adjustment_id: Uuid,
+        invoice: &mut Invoice,
+    ) -> CretoResult<AdjustmentApplication> {
+        let current = self.get_for(adjustment_id, invoice).await?;
+        match current.status {
+            AdjustmentStatus::Applied => return Ok(replay(current)),
+            AdjustmentStatus::Approved => {}
+            status => {
+                return Err(CretoError::InvalidStateTransition {
+                    from: format!("{:?}", status),
+                    to: format!("{:?}", AdjustmentStatus::Applied),
+                })
+            }
+        }
+
+        let now = self.clock.now();
+        let mut adjustment = current;
+        let mut updated = invoice.clone();
+        let credit_note = if invoice.status == InvoiceStatus::Paid {
+            let note = self.credit_note(&adjustment, invoice, now);
+            adjustment.credit_note_id = Some(note.id);
+            Some(note)
+        } else {
+            self.adjust_in_place(&adjustment, &mut updated)?;
+            None
+        };
+        adjustment.status = AdjustmentStatus::Applied;
+        adjustment.applied_at = Some(now);
+        record_summary(&mut updated, &adjustment);
+
+        // The status, the invoice and any credit note are stored together;
+        // losing the race means another call applied it first
+        let mut invoices = vec![&updated];
+        invoices.extend(credit_note.as_ref());
+        if !self
+            .repository
+            .update_if_status(&adjustment, AdjustmentStatus::Approved, &invoices)
+            .await?
+        {
+            let stored = self.get_for(adjustment_id, invoice).await?;
+            if stored.status == AdjustmentStatus::Applied {
+                return Ok(replay(stored));
+            }
+            return Err(CretoError::InvalidStateTransition {
+                from: format!("{:?}", stored.status),
+                to: format!("{:?}", AdjustmentStatus::Applied),
+            });
+        }
+
+        *invoice = updated;
+        info!(
+            adjustment_id = %adjustment.id,
+            invoice_id = %invoice.id,
+            credit_note_id = ?adjustment.credit_note_id,
+            total_cents = invoice.total.amount,
+            "Invoice adjustment applied"
+        );
+        Ok(AdjustmentApplication {
+            adjustment,
+            credit_note,
+            replayed: false,
+        })
+    }
+
+    /// Get an adjustment.
+    pub async fn get(&self, adjustment_id: Uuid) -> CretoResult<Option<InvoiceAdjustment>> {
+        self.repository.get_adjustment(adjustment_id).await
+    }
+
+    /// List an invoice's adjustments, oldest first.
+    pub async fn list(&self, invoice_id: Uuid) -> CretoResult<Vec<InvoiceAdjustment>> {
+        self.repository.list_by_invoice(invoice_id).await
+    }
+
+    async fn get_for(
+        &self,
+        adjustment_id: Uuid,
+        invoice: &Invoice,
+    ) -> CretoResult<InvoiceAdjustment> {
+        let adjustment = self
+            .repository
+            .get_adjustment(adjustment_id)
+            .await?
+            .ok_or_else(|| CretoError::NotFound(format!("Invoice adjustment {}", adjustment_id)))?;
+        if adjustment.invoice_id != invoice.id {
+            return Err(CretoError::ValidationFailed(format!(
+                "Adjustment {} belongs to invoice {}, not {}",
+                adjustment_id, adjustment.invoice_id, invoice.id
+            )));
+        }
+        Ok(adjustment)
+    }
+
+    /// Check an adjustment can be made to `invoice`.
+    ///
+    /// Credits pending or issued as credit notes may not add up to more
+    /// than the invoice total, and no single adjustment may exceed it.
+    fn validate(&self, adjustment: &InvoiceAdjustment, invoice: &Invoice) -> CretoResult<()> {
+        if adjustment.invoice_id != invoice.id {
+            return Err(CretoError::ValidationFailed(format!(
+                "Adjustment is for invoice {}, not {}",
+                adjustment.invoice_id, invoice.id
+            )));
+        }
+        if adjustment.amount_cents == 0 {
+            return Err(CretoError::ValidationFailed(
+                "Invoice adjustment amount cannot be zero".to_string(),
+            ));
+        }
+        if adjustment.reason.trim().is_empty() {
+            return Err(CretoError::ValidationFailed(
+                "Invoice adjustment reason is required".to_string(),
+            ));
+        }
+        if adjustment.created_by.trim().is_empty() {
+            return Err(CretoError::ValidationFailed(
+                "Invoice adjustment proposer is required".to_string(),
+            ));
+        }
+        if let Some(line_item_id) = adjustment.line_item_id {
+            if !invoice
+                .line_items
+                .iter()
+                .any(|item| item.id == line_item_id)
+            {
+                return Err(CretoError::ValidationFailed(format!(
+                    "Invoice {} has no line item {}",
+                    invoice.number, line_item_id
+                )));
+            }
+        }
+        if invoice.credit_note_for.is_some() {
+            return Err(CretoError::ValidationFailed(
+                "Credit notes cannot be adjusted".to_string(),
+            ));
+        }
+        match invoice.status {
+            InvoiceStatus::Voided => {
+                return Err(CretoError::ValidationFailed(format!(
+                    "Invoice {} is voided",
+                    invoice.number
+                )))
+            }
+            InvoiceStatus::Paid if !adjustment.is_credit() => {
+                return Err(CretoError::ValidationFailed(format!(
+                    "Invoice {} is paid; charges cannot be added to it",
+                    invoice.number
+                )))
+            }
+            _ => {}
+        }
+
+        let outstanding_credits: i64 = invoice
+            .adjustments
+            .iter()
+            .filter(|a| a.amount_cents < 0)
+            .filter(|a| match a.status {
+                AdjustmentStatus::Proposed | AdjustmentStatus::Approved => true,
+                AdjustmentStatus::Applied => a.credit_note_id.is_some(),
+                AdjustmentStatus::Rejected => false,
+            })
+            .map(|a| -a.amount_cents)
+            .sum();
+        let credits = outstanding_credits + (-adjustment.amount_cents).max(0);
+        if adjustment.amount_cents.abs() > invoice.total.amount || credits > invoice.total.amount {
+            return Err(CretoError::ValidationFailed(format!(
+                "Adjustment of {} exceeds the total of invoice {} ({} with {} already credited)",
+                adjustment.amount_cents, invoice.number, invoice.total.amount, outstanding_credits
+            )));
+        }
+        Ok(())
+    }
+
+    /// Add the adjustment as a line item and recompute tax and total.
+    fn adjust_in_place(
+        &self,
+        adjustment: &InvoiceAdjustment,
+        invoice: &mut Invoice,
+    ) -> CretoResult<()> {
+        invoice
+            .try_add_line_item(self.line_item(adjustment, invoice))
+            .map_err(|e| CretoError::ValidationFailed(e.to_string()))?;
+        invoice.set_tax(Money::new(
+            tax_cents(invoice.subtotal.amount, self.tax_rate),
+            invoice.currency,
+        ));
+        if invoice.total.is_negative() {
+            return Err(CretoError::ValidationFailed(format!(
+                "Adjustment of {} would leave invoice {} with a negative total",
+                adjustment.amount_cents, invoice.number
+            )));
+        }
+        Ok(())
+    }
+
+    /// Issue a credit note for a credit on a paid invoice.
+    fn credit_note(
+        &self,
+        adjustment: &InvoiceAdjustment,
+        original: &Invoice,
+        now: DateTime<Utc>,
+    ) -> Invoice {
+        let mut note = Invoice::new_in_currency(
+            original.organization_id,
+            original.period_start,
+            original.period_end,
+            original.currency,
+        );
+        let sequence = original
+            .adjustments
+            .iter()
+            .filter(|a| a.credit_note_id.is_some())
+            .count()
+            + 1;
+        note.number = format!("{}-CN{}", original.number, sequence);
+        note.credit_note_for = Some(original.id);
+        note.add_line_item(self.line_item(adjustment, original));
+        note.set_tax(Money::new(
+            tax_cents(adjustment.amount_cents, self.tax_rate),
+            original.currency,
+        ));
+        note.status = InvoiceStatus::Issued;
+        note.issued_at = Some(now);
+        note
+    }
+
+    fn line_item(&self, adjustment: &InvoiceAdjustment, invoice: &Invoice) -> LineItem {
+        let corrected = adjustment
+            .line_item_id
+            .and_then(|id| invoice.line_items.iter().find(|item| item.id == id));
+        let description = match corrected {
+            Some(item) => format!("Adjustment to {}: {}", item.description, adjustment.reason),
+            None => format!("Adjustment: {}", adjustment.reason),
+        };
+        LineItem::new(
+            description,
+            ADJUSTMENT_METRIC_CODE,
+            1,
+            ADJUSTMENT_METRIC_CODE,
+            Money::new(adjustment.amount_cents, invoice.currency),
+        )
+    }
+}
+
+fn replay(adjustment: InvoiceAdjustment) -> AdjustmentApplication {
+    AdjustmentApplication {
+        adjustment,
+        credit_note: None,
+        replayed: true,
+    }
+}
+
+/// Add or update an adjustment in the invoice's summary.
+fn record_summary(invoice: &mut Invoice, adjustment: &InvoiceAdjustment) {
+    let summary = adjustment.summary();
+    match invoice
+        .adjustments
+        .iter_mut()
+        .find(|a| a.adjustment_id == adjustment.id)
+    {
+        Some(existing) => *existing = summary,
+        None => invoice.adjustments.push(summary),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use creto_common::TestClock;
+
+    struct Approver(AdjustmentDecision);
+
+    impl AdjustmentApprover for Approver {
+        async fn review(
+            &self,
+            _adjustment: &InvoiceAdjustment,
+            _invoice: &Invoice,
+        ) -> CretoResult<AdjustmentDecision> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn approve() -> Approver {
+        Approver(AdjustmentDecision::Approve {
+            reviewer: "billing-lead@example.com".to_string(),
+        })
+    }
+
+    fn manager() -> InvoiceAdjustmentManager<InMemoryInvoiceAdjustmentRepository> {
+        manager_storing_into(Arc::new(InMemoryInvoiceRepository::new()))
+    }
+
+    fn manager_storing_into(
+        invoices: Arc<InMemoryInvoiceRepository>,
+    ) -> InvoiceAdjustmentManager<InMemoryInvoiceAdjustmentRepository> {
+        InvoiceAdjustmentManager::new(Arc::new(InMemoryInvoiceAdjustmentRepository::new(invoices)))
+            .with_tax_rate(10.0)
+            .with_clock(Arc::new(TestClock::new(Utc::now())))
+    }
+
+    /// An issued invoice of $100.00 plus 10% tax.
+    fn issued_invoice() -> Invoice {
+        let now = Utc::now();
+        let mut invoice =
+            Invoice::new(OrganizationId::new(), now - chrono::Duration::days(30), now);
+        invoice.add_line_item(LineItem::new(
+            "API calls",
+            "api_calls",
+            10_000,
+            "calls",
+            Money::usd(1),
+        ));
+        invoice.set_tax(Money::usd(1_000));
+        invoice.issue(30);
+        invoice
+    }
+
+    #[tokio::test]
+    async fn test_paid_invoice_gets_credit_note() {
+        let manager = manager();
+        let mut invoice = issued_invoice();
+        invoice.mark_paid();
+        let line_item_id = invoice.line_items[0].id;
+
+        let adjustment = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, -2_500, "Duplicate batch", "support@example.com")
+                    .with_line_item(line_item_id),
+                &mut invoice,
+                &approve(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(adjustment.status, AdjustmentStatus::Approved);
+        assert_eq!(
+            adjustment.reviewed_by.as_deref(),
+            Some("billing-lead@example.com")
+        );
+
+        let applied = manager.apply(adjustment.id, &mut invoice).await.unwrap();
+        let note = applied.credit_note.unwrap();
+        assert_eq!(note.credit_note_for, Some(invoice.id));
+        assert_eq!(note.number, format!("{}-CN1", invoice.number));
+        assert_eq!(note.status, InvoiceStatus::Issued);
+        assert_eq!(note.subtotal.amount, -2_500);
+        assert_eq!(note.tax.amount, -250);
+        assert_eq!(note.total.amount, -2_750);
+        assert!(note.line_items[0].description.contains("API calls"));
+
+        // The paid invoice keeps its amounts and records the credit note
+        assert_eq!(invoice.total.amount, 11_000);
+        assert_eq!(invoice.line_items.len(), 1);
+        assert_eq!(invoice.adjustments.len(), 1);
+        assert_eq!(invoice.adjustments[0].status, AdjustmentStatus::Applied);
+        assert_eq!(invoice.adjustments[0].credit_note_id, Some(note.id));
+
+        // Credits may not exceed the invoice total, counting issued notes
+        let err = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, -8_600, "Goodwill", "support@example.com"),
+                &mut invoice,
+                &approve(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::ValidationFailed(_)));
+        let err = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, 500, "Missed usage", "support@example.com"),
+                &mut invoice,
+                &approve(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::ValidationFailed(_)));
+        assert_eq!(invoice.adjustments.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_apply_stores_invoice_and_credit_note_with_status() {
+        use crate::repository::InvoiceRepository;
+
+        let invoices = Arc::new(InMemoryInvoiceRepository::new());
+        let manager = manager_storing_into(Arc::clone(&invoices));
+        let mut invoice = issued_invoice();
+        invoice.mark_paid();
+
+        let adjustment = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, -2_500, "Duplicate batch", "support@example.com"),
+                &mut invoice,
+                &approve(),
+            )
+            .await
+            .unwrap();
+        let stored = invoices.find_invoice(invoice.id).await.unwrap().unwrap();
+        assert_eq!(stored.adjustments[0].status, AdjustmentStatus::Approved);
+
+        let applied = manager.apply(adjustment.id, &mut invoice).await.unwrap();
+        let note = applied.credit_note.unwrap();
+
+        // Whoever sees the adjustment applied also sees the credit note
+        let stored = invoices.find_invoice(invoice.id).await.unwrap().unwrap();
+        assert_eq!(stored.adjustments[0].status, AdjustmentStatus::Applied);
+        assert_eq!(stored.adjustments[0].credit_note_id, Some(note.id));
+        let stored_note = invoices.find_invoice(note.id).await.unwrap().unwrap();
+        assert_eq!(stored_note.credit_note_for, Some(invoice.id));
+        assert_eq!(stored_note.total.amount, -2_750);
+    }
+
+    #[tokio::test]
+    async fn test_unpaid_invoice_adjusted_in_place() {
+        let manager = manager();
+        let mut invoice = issued_invoice();
+
+        let adjustment = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, -2_000, "Outage credit", "support@example.com"),
+                &mut invoice,
+                &approve(),
+            )
+            .await
+            .unwrap();
+        let applied = manager.apply(adjustment.id, &mut invoice).await.unwrap();
+        assert!(applied.credit_note.is_none());
+        assert_eq!(applied.adjustment.status, AdjustmentStatus::Applied);
+
+        assert_eq!(invoice.line_items.len(), 2);
+        assert_eq!(invoice.line_items[1].metric_code, ADJUSTMENT_METRIC_CODE);
+        assert_eq!(invoice.subtotal.amount, 8_000);
+        assert_eq!(invoice.tax.amount, 800);
+        assert_eq!(invoice.total.amount, 8_800);
+        assert_eq!(invoice.adjustments[0].status, AdjustmentStatus::Applied);
+
+        // Charges can be added to an unpaid invoice
+        let charge = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, 1_000, "Missed usage", "support@example.com"),
+                &mut invoice,
+                &approve(),
+            )
+            .await
+            .unwrap();
+        manager.apply(charge.id, &mut invoice).await.unwrap();
+        assert_eq!(invoice.total.amount, 9_900);
+
+        let err = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, -10_000, "Refund", "support@example.com"),
+                &mut invoice,
+                &approve(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::ValidationFailed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_finalized_invoice_requires_approval() {
+        let manager = manager();
+        let mut invoice = issued_invoice();
+
+        let err = manager
+            .propose(
+                InvoiceAdjustment::new(&invoice, -100, "Dispute", "support@example.com"),
+                &mut invoice,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::Unauthorized(_)));
+        assert!(invoice.adjustments.is_empty());
+
+        // Rejected adjustments cannot be applied
+        let rejected = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, -100, "Dispute", "support@example.com"),
+                &mut invoice,
+                &Approver(AdjustmentDecision::Reject {
+                    reviewer: "billing-lead@example.com".to_string(),
+                    reason: "Usage was legitimate".to_string(),
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(rejected.status, AdjustmentStatus::Rejected);
+        assert!(matches!(
+            manager.apply(rejected.id, &mut invoice).await,
+            Err(CretoError::InvalidStateTransition { .. })
+        ));
+
+        // A deferred decision stays proposed until resolved
+        let deferred = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, -100, "Dispute", "support@example.com"),
+                &mut invoice,
+                &Approver(AdjustmentDecision::Defer),
+            )
+            .await
+            .unwrap();
+        assert_eq!(deferred.status, AdjustmentStatus::Proposed);
+        assert!(manager.apply(deferred.id, &mut invoice).await.is_err());
+        let approved = manager
+            .resolve(
+                deferred.id,
+                AdjustmentDecision::Approve {
+                    reviewer: "oversight".to_string(),
+                },
+                &mut invoice,
+            )
+            .await
+            .unwrap();
+        assert_eq!(approved.status, AdjustmentStatus::Approved);
+        assert!(manager
+            .resolve(deferred.id, AdjustmentDecision::Defer, &mut invoice)
+            .await
+            .is_ok());
+        assert!(matches!(
+            manager
+                .resolve(
+                    deferred.id,
+                    AdjustmentDecision::Approve {
+                        reviewer: "oversight".to_string(),
+                    },
+                    &mut invoice,
+                )
+                .await,
+            Err(CretoError::InvalidStateTransition { .. })
+        ));
+
+        let statuses: Vec<_> = invoice.adjustments.iter().map(|a| a.status).collect();
+        assert_eq!(
+            statuses,
+            vec![AdjustmentStatus::Rejected, AdjustmentStatus::Approved]
+        );
+
+        // Draft invoices need no approval
+        let mut draft = issued_invoice();
+        draft.status = InvoiceStatus::Draft;
+        let adjustment = manager
+            .propose(
+                InvoiceAdjustment::new(&draft, -100, "Dispute", "support@example.com"),
+                &mut draft,
+            )
+            .await
+            .unwrap();
+        assert_eq!(adjustment.status, AdjustmentStatus::Approved);
+    }
+
+    #[tokio::test]
+    async fn test_apply_is_idempotent() {
+        let manager = manager();
+        let mut invoice = issued_invoice();
+        invoice.mark_paid();
+
+        let adjustment = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&invoice, -1_000, "Duplicate batch", "support@example.com"),
+                &mut invoice,
+                &approve(),
+            )
+            .await
+            .unwrap();
+        let first = manager.apply(adjustment.id, &mut invoice).await.unwrap();
+        assert!(!first.replayed);
+        let after_first = serde_json::to_value(&invoice).unwrap();
+
+        let second = manager.apply(adjustment.id, &mut invoice).await.unwrap();
+        assert!(second.replayed);
+        assert!(second.credit_note.is_none());
+        assert_eq!(
+            second.adjustment.credit_note_id,
+            Some(first.credit_note.unwrap().id)
+        );
+        assert_eq!(serde_json::to_value(&invoice).unwrap(), after_first);
+
+        // An in-place adjustment applied from a stale copy changes nothing
+        let mut unpaid = issued_invoice();
+        let adjustment = manager
+            .propose_with_approval(
+                InvoiceAdjustment::new(&unpaid, -1_000, "Outage credit", "support@example.com"),
+                &mut unpaid,
+                &approve(),
+            )
+            .await
+            .unwrap();
+        let mut stale = unpaid.clone();
+        manager.apply(adjustment.id, &mut unpaid).await.unwrap();
+        let replay = manager.apply(adjustment.id, &mut stale).await.unwrap();
+        assert!(replay.replayed);
+        assert_eq!(stale.total.amount, 11_000);
+        assert_eq!(unpaid.total.amount, 9_900);
+
+        // Adjustments only apply to their own invoice
+        assert!(matches!(
+            manager.apply(adjustment.id, &mut issued_invoice()).await,
+            Err(CretoError::ValidationFailed(_))
+        ));
+    }
+}
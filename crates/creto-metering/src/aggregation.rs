@@ -1,15 +1,22 @@
 //! Usage aggregation engine.
 //!
 //! Aggregates raw usage events into summarized metrics for billing and reporting.
+//!
+//! Unique counts are exact by default. With
+//! [`UniqueCountMode::Approximate`] they are estimated with a fixed-size
+//! [`HyperLogLog`] sketch instead, for properties with large cardinalities.
+
+use std::collections::HashSet;
 
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, OrganizationId};
+use creto_common::{percentile_cont, AgentId, CretoError, CretoResult, OrganizationId};
 use serde::{Deserialize, Serialize};
 
-use crate::events::UsageEventType;
+use crate::aliases::MetricAlias;
+use crate::events::{UsageEvent, UsageEventType};
 
 /// Aggregation function to apply to usage events.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AggregationType {
     /// Count number of events.
@@ -22,12 +29,65 @@ pub enum AggregationType {
     Min,
     /// Average quantity.
     Average,
-    /// Count of unique values (for a property field).
-    UniqueCount,
+    /// Count of distinct values of an event property.
+    UniqueCount {
+        /// Property whose values are counted (see [`event_property`]).
+        property: String,
+    },
+    /// Percentile of a numeric event property, interpolated linearly
+    /// between the nearest ranks.
+    Percentile {
+        /// Property whose values are ranked (see [`event_property`]).
+        property: String,
+        /// Percentile from 0 to 100, e.g. 95 for p95.
+        percentile: f64,
+    },
     /// Latest value.
     Latest,
 }
 
+impl AggregationType {
+    /// Check the parameters of a property aggregation.
+    pub fn validate(&self) -> CretoResult<()> {
+        match self {
+            AggregationType::UniqueCount { property }
+            | AggregationType::Percentile { property, .. }
+                if property.is_empty() =>
+            {
+                Err(CretoError::ValidationFailed(
+                    "Aggregation property must not be empty".to_string(),
+                ))
+            }
+            AggregationType::Percentile { percentile, .. }
+                if !(0.0..=100.0).contains(percentile) =>
+            {
+                Err(CretoError::ValidationFailed(format!(
+                    "Percentile must be between 0 and 100, got {}",
+                    percentile
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How [`AggregationType::UniqueCount`] counts distinct values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UniqueCountMode {
+    /// Keep every distinct value. Exact, but memory grows with cardinality.
+    #[default]
+    Exact,
+    /// Estimate with a [`HyperLogLog`] sketch of `2^precision` registers.
+    ///
+    /// Memory is fixed; the standard error is about
+    /// `1.04 / sqrt(2^precision)`, 0.8% at precision 14.
+    Approximate {
+        /// Sketch precision, clamped to 4..=16.
+        precision: u8,
+    },
+}
+
 /// Result of an aggregation operation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Aggregation {
@@ -50,6 +110,10 @@ pub struct Aggregation {
     /// Resulting value.
     pub value: AggregationValue,
 
+    /// Whether the value is an estimate (see [`UniqueCountMode::Approximate`]).
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub approximate: bool,
+
     /// Start of the aggregation period.
     pub period_start: DateTime<Utc>,
 
@@ -60,13 +124,17 @@ pub struct Aggregation {
     pub event_count: u64,
 }
 
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
 /// Value types that can result from aggregation.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum AggregationValue {
     /// Integer value (count, sum, etc.).
     Integer(i64),
-    /// Floating point value (average).
+    /// Floating point value (average, percentile).
     Float(f64),
     /// String value (latest).
     String(String),
@@ -90,18 +158,314 @@ impl AggregationValue {
             AggregationValue::String(_) => 0.0,
         }
     }
+
+    /// Convert to a whole-unit quantity for invoicing.
+    ///
+    /// Integers are used as-is. Floats are rounded half away from zero to
+    /// the nearest unit, so a p95 of 120.5 ms bills as 121 and one of
+    /// 120.49 ms as 120. Strings and non-finite floats are not quantities.
+    pub fn to_quantity(&self) -> Option<i64> {
+        match self {
+            AggregationValue::Integer(v) => Some(*v),
+            AggregationValue::Float(v) if v.is_finite() => Some(v.round() as i64),
+            AggregationValue::Float(_) | AggregationValue::String(_) => None,
+        }
+    }
+}
+
+/// Look up the property of an event named by [`AggregationType::UniqueCount`]
+/// or [`AggregationType::Percentile`].
+///
+/// `agent_id`, `quantity` and `external_subscription_id` name the event's
+/// own fields; any other name is looked up in the event's properties. Null
+/// values count as missing.
+pub fn event_property(event: &UsageEvent, property: &str) -> Option<serde_json::Value> {
+    let value = match property {
+        "agent_id" => serde_json::Value::String(event.agent_id.to_string()),
+        "quantity" => serde_json::Value::from(event.quantity),
+        "external_subscription_id" => {
+            serde_json::Value::String(event.external_subscription_id.clone()?)
+        }
+        _ => event.properties.get(property)?.clone(),
+    };
+    (!value.is_null()).then_some(value)
+}
+
+/// HyperLogLog cardinality sketch.
+///
+/// Values are hashed with FNV-1a plus a finalizer, so estimates are
+/// reproducible across processes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Create an empty sketch with `2^precision` registers (precision
+    /// clamped to 4..=16).
+    pub fn new(precision: u8) -> Self {
+        let precision = precision.clamp(4, 16);
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    /// Add a value.
+    pub fn insert(&mut self, value: &str) {
+        let hash = hash_value(value);
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rank = ((hash << self.precision).leading_zeros() + 1).min(65 - self.precision as u32);
+        self.registers[index] = self.registers[index].max(rank as u8);
+    }
+
+    /// Estimated number of distinct values added.
+    pub fn estimate(&self) -> f64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|&r| 2f64.powi(-i32::from(r)))
+            .sum();
+        let raw = alpha * m * m / sum;
+
+        // Small cardinalities leave registers empty; linear counting is
+        // more accurate there
+        let empty = self.registers.iter().filter(|&&r| r == 0).count();
+        if raw <= 2.5 * m && empty > 0 {
+            m * (m / empty as f64).ln()
+        } else {
+            raw
+        }
+    }
+}
+
+/// FNV-1a followed by the splitmix64 finalizer, which spreads FNV's weak
+/// high bits (HyperLogLog indexes registers by them).
+fn hash_value(value: &str) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let mut hash = FNV_OFFSET;
+    for byte in value.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d049bb133111eb);
+    hash ^ (hash >> 31)
+}
+
+/// The events behind an aggregation: one organization's metric over a window.
+///
+/// Both ends of the window are inclusive, as in billing period aggregation,
+/// unless the end is made exclusive with [`UsageSelection::with_exclusive_end`].
+/// Events recorded under an alias of the metric are selected too, within the
+/// alias's effective range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageSelection {
+    /// Organization whose events are selected.
+    pub organization_id: OrganizationId,
+
+    /// Metric code of the selected events.
+    pub metric_code: String,
+
+    /// Earliest event timestamp selected.
+    pub window_start: DateTime<Utc>,
+
+    /// Latest event timestamp selected.
+    pub window_end: DateTime<Utc>,
+
+    /// Whether events at exactly `window_end` are left out.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub end_exclusive: bool,
+
+    /// Aliases of the metric whose events are also selected.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<MetricAlias>,
+}
+
+impl UsageSelection {
+    /// Create a selection.
+    pub fn new(
+        organization_id: OrganizationId,
+        metric_code: impl Into<String>,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            organization_id,
+            metric_code: metric_code.into(),
+            window_start,
+            window_end,
+            end_exclusive: false,
+            aliases: Vec::new(),
+        }
+    }
+
+    /// Leave out events at exactly `window_end`, for sums taken up to a
+    /// snapshot that must not include the snapshot instant.
+    pub fn with_exclusive_end(mut self) -> Self {
+        self.end_exclusive = true;
+        self
+    }
+
+    /// Also select events recorded under these aliases of the metric.
+    pub fn with_aliases(mut self, aliases: Vec<MetricAlias>) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// Check whether an event falls in the selection.
+    pub fn matches(&self, event: &UsageEvent) -> bool {
+        event.organization_id == self.organization_id
+            && (event.code == self.metric_code
+                || self
+                    .aliases
+                    .iter()
+                    .any(|a| a.applies_to(&event.code, event.timestamp)))
+            && event.timestamp >= self.window_start
+            && (event.timestamp < self.window_end
+                || (!self.end_exclusive && event.timestamp == self.window_end))
+    }
 }
 
 /// Engine for computing usage aggregations.
 pub struct AggregationEngine {
     // TODO: Add database connection, cache
-    _private: (),
+    unique_count_mode: UniqueCountMode,
 }
 
 impl AggregationEngine {
     /// Create a new aggregation engine.
     pub fn new() -> Self {
-        Self { _private: () }
+        Self {
+            unique_count_mode: UniqueCountMode::Exact,
+        }
+    }
+
+    /// Set how unique counts are computed.
+    pub fn with_unique_count_mode(mut self, mode: UniqueCountMode) -> Self {
+        self.unique_count_mode = mode;
+        self
+    }
+
+    /// How unique counts are computed.
+    pub fn unique_count_mode(&self) -> UniqueCountMode {
+        self.unique_count_mode
+    }
+
+    /// Aggregate events in memory.
+    ///
+    /// Only events of `event_type` that fall in `selection` are included.
+    /// [`AggregationType::UniqueCount`] and [`AggregationType::Percentile`]
+    /// include only events carrying the property (numeric, for
+    /// percentiles), and report those in
+    /// [`event_count`](Aggregation::event_count). Aggregating no events
+    /// yields zero.
+    pub fn aggregate_events<'a>(
+        &self,
+        selection: &UsageSelection,
+        event_type: UsageEventType,
+        aggregation_type: &AggregationType,
+        events: impl IntoIterator<Item = &'a UsageEvent>,
+    ) -> CretoResult<Aggregation> {
+        aggregation_type.validate()?;
+        let events: Vec<&UsageEvent> = events
+            .into_iter()
+            .filter(|e| e.event_type == event_type && selection.matches(e))
+            .collect();
+        // Sampled events stand for `sample_factor` events each: counts and
+        // sums are weighted, extremes and the latest value are per event
+        let quantities = || events.iter().map(|e| e.quantity);
+        let unscaled = || events.iter().map(|e| e.unscaled_quantity());
+        let weighted_count = || events.iter().map(|e| e.sample_factor()).sum::<i64>();
+
+        let mut approximate = false;
+        let (value, event_count) = match aggregation_type {
+            AggregationType::Count => (AggregationValue::Integer(weighted_count()), events.len()),
+            AggregationType::Sum => (AggregationValue::Integer(quantities().sum()), events.len()),
+            AggregationType::Max => (
+                AggregationValue::Integer(unscaled().max().unwrap_or(0)),
+                events.len(),
+            ),
+            AggregationType::Min => (
+                AggregationValue::Integer(unscaled().min().unwrap_or(0)),
+                events.len(),
+            ),
+            AggregationType::Average => {
+                let average = if events.is_empty() {
+                    0.0
+                } else {
+                    quantities().sum::<i64>() as f64 / weighted_count() as f64
+                };
+                (AggregationValue::Float(average), events.len())
+            }
+            AggregationType::Latest => (
+                AggregationValue::Integer(
+                    events
+                        .iter()
+                        .max_by_key(|e| e.timestamp)
+                        .map_or(0, |e| e.unscaled_quantity()),
+                ),
+                events.len(),
+            ),
+            AggregationType::UniqueCount { property } => {
+                let values: Vec<String> = events
+                    .iter()
+                    .filter_map(|e| event_property(e, property))
+                    .map(|v| v.to_string())
+                    .collect();
+                let count = match self.unique_count_mode {
+                    UniqueCountMode::Exact => values.iter().collect::<HashSet<_>>().len() as i64,
+                    UniqueCountMode::Approximate { precision } => {
+                        approximate = true;
+                        let mut sketch = HyperLogLog::new(precision);
+                        for value in &values {
+                            sketch.insert(value);
+                        }
+                        sketch.estimate().round() as i64
+                    }
+                };
+                (AggregationValue::Integer(count), values.len())
+            }
+            AggregationType::Percentile {
+                property,
+                percentile,
+            } => {
+                let mut values: Vec<f64> = events
+                    .iter()
+                    .filter_map(|e| event_property(e, property)?.as_f64())
+                    .collect();
+                values.sort_by(f64::total_cmp);
+                (
+                    AggregationValue::Float(
+                        percentile_cont(&values, *percentile / 100.0).unwrap_or(0.0),
+                    ),
+                    values.len(),
+                )
+            }
+        };
+
+        Ok(Aggregation {
+            organization_id: selection.organization_id,
+            agent_id: None,
+            metric_code: selection.metric_code.clone(),
+            event_type,
+            aggregation_type: aggregation_type.clone(),
+            value,
+            approximate,
+            period_start: selection.window_start,
+            period_end: selection.window_end,
+            event_count: event_count as u64,
+        })
     }
 
     /// Compute an aggregation for a given period.
@@ -176,10 +540,6 @@ pub struct BillableMetric {
 
     /// How to aggregate events into this metric.
     pub aggregation_type: AggregationType,
-
-    /// Optional property field for UNIQUE_COUNT aggregations.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub unique_count_field: Option<String>,
 }
 
 impl BillableMetric {
@@ -191,7 +551,6 @@ impl BillableMetric {
             description: "Number of API calls made".to_string(),
             event_type: UsageEventType::ApiCall,
             aggregation_type: AggregationType::Count,
-            unique_count_field: None,
         }
     }
 
@@ -203,7 +562,6 @@ impl BillableMetric {
             description: "Number of input tokens processed".to_string(),
             event_type: UsageEventType::InputTokens,
             aggregation_type: AggregationType::Sum,
-            unique_count_field: None,
         }
     }
 
@@ -215,7 +573,6 @@ impl BillableMetric {
             description: "Number of output tokens generated".to_string(),
             event_type: UsageEventType::OutputTokens,
             aggregation_type: AggregationType::Sum,
-            unique_count_field: None,
         }
     }
 
@@ -226,8 +583,9 @@ impl BillableMetric {
             name: "Unique Agents".to_string(),
             description: "Number of unique agents active".to_string(),
             event_type: UsageEventType::ApiCall,
-            aggregation_type: AggregationType::UniqueCount,
-            unique_count_field: Some("agent_id".to_string()),
+            aggregation_type: AggregationType::UniqueCount {
+                property: "agent_id".to_string(),
+            },
         }
     }
 }
@@ -256,4 +614,233 @@ mod tests {
         let input_tokens = BillableMetric::input_tokens();
         assert_eq!(input_tokens.aggregation_type, AggregationType::Sum);
     }
+
+    fn inference(
+        org_id: OrganizationId,
+        agent_id: AgentId,
+        properties: serde_json::Value,
+    ) -> UsageEvent {
+        UsageEvent::builder()
+            .organization_id(org_id)
+            .agent_id(agent_id)
+            .event_type(UsageEventType::LlmInference)
+            .properties(properties)
+            .build()
+    }
+
+    fn selection(org_id: OrganizationId) -> UsageSelection {
+        let now = Utc::now();
+        UsageSelection::new(
+            org_id,
+            UsageEventType::LlmInference.default_code(),
+            now - chrono::Duration::hours(1),
+            now + chrono::Duration::hours(1),
+        )
+    }
+
+    #[test]
+    fn test_unique_count_exact() {
+        let org_id = OrganizationId::new();
+        let (a, b) = (AgentId::new(), AgentId::new());
+        let events = vec![
+            inference(org_id, a, serde_json::json!({"consumer": "acme"})),
+            inference(org_id, a, serde_json::json!({"consumer": "acme"})),
+            inference(org_id, b, serde_json::json!({"consumer": "globex"})),
+            inference(org_id, b, serde_json::json!({"consumer": 7})),
+            inference(org_id, b, serde_json::json!({})),
+            inference(
+                OrganizationId::new(),
+                a,
+                serde_json::json!({"consumer": "other"}),
+            ),
+        ];
+        let engine = AggregationEngine::new();
+
+        let consumers = engine
+            .aggregate_events(
+                &selection(org_id),
+                UsageEventType::LlmInference,
+                &AggregationType::UniqueCount {
+                    property: "consumer".to_string(),
+                },
+                &events,
+            )
+            .unwrap();
+        assert_eq!(consumers.value, AggregationValue::Integer(3));
+        assert_eq!(consumers.event_count, 4);
+        assert!(!consumers.approximate);
+
+        let agents = engine
+            .aggregate_events(
+                &selection(org_id),
+                UsageEventType::LlmInference,
+                &BillableMetric::unique_agents().aggregation_type,
+                &events,
+            )
+            .unwrap();
+        assert_eq!(agents.value, AggregationValue::Integer(2));
+    }
+
+    #[test]
+    fn test_unique_count_approximate_within_tolerance() {
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let events: Vec<_> = (0..50_000)
+            .map(|i| {
+                inference(
+                    org_id,
+                    agent_id,
+                    serde_json::json!({ "consumer": i % 20_000 }),
+                )
+            })
+            .collect();
+        let engine = AggregationEngine::new()
+            .with_unique_count_mode(UniqueCountMode::Approximate { precision: 14 });
+
+        let aggregation = engine
+            .aggregate_events(
+                &selection(org_id),
+                UsageEventType::LlmInference,
+                &AggregationType::UniqueCount {
+                    property: "consumer".to_string(),
+                },
+                &events,
+            )
+            .unwrap();
+        assert!(aggregation.approximate);
+        let estimate = aggregation.value.as_f64();
+        // Standard error at precision 14 is ~0.8%; allow 3%
+        assert!(
+            (estimate - 20_000.0).abs() / 20_000.0 < 0.03,
+            "estimate {} too far from 20000",
+            estimate
+        );
+
+        // Linear counting keeps small cardinalities close to exact
+        let mut sketch = HyperLogLog::new(14);
+        for value in ["a", "b", "c", "a"] {
+            sketch.insert(value);
+        }
+        assert_eq!(sketch.estimate().round(), 3.0);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let mut events: Vec<_> = (1..=10)
+            .map(|i| {
+                inference(
+                    org_id,
+                    agent_id,
+                    serde_json::json!({ "duration_ms": i * 10 }),
+                )
+            })
+            .collect();
+        events.push(inference(
+            org_id,
+            agent_id,
+            serde_json::json!({ "duration_ms": "slow" }),
+        ));
+        let engine = AggregationEngine::new();
+        let percentile = |percentile: f64| {
+            engine.aggregate_events(
+                &selection(org_id),
+                UsageEventType::LlmInference,
+                &AggregationType::Percentile {
+                    property: "duration_ms".to_string(),
+                    percentile,
+                },
+                &events,
+            )
+        };
+
+        let p95 = percentile(95.0).unwrap();
+        assert!((p95.value.as_f64() - 95.5).abs() < 1e-9);
+        assert_eq!(p95.event_count, 10);
+        assert_eq!(
+            percentile(50.0).unwrap().value,
+            AggregationValue::Float(55.0)
+        );
+        assert_eq!(
+            percentile(0.0).unwrap().value,
+            AggregationValue::Float(10.0)
+        );
+        assert_eq!(
+            percentile(100.0).unwrap().value,
+            AggregationValue::Float(100.0)
+        );
+        assert!(matches!(
+            percentile(101.0),
+            Err(CretoError::ValidationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_count_and_max_over_sampled_metric() {
+        use crate::sampling::{IngestionSampler, SamplingRule};
+
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sampler = IngestionSampler::new();
+        sampler
+            .set_rule(SamplingRule::every_nth(
+                org_id,
+                UsageEventType::LlmInference.default_code(),
+                5,
+            ))
+            .unwrap();
+        // The first of every five is kept: quantities 1 and 6, scaled to 5 and 30
+        let events: Vec<_> = (1..=10)
+            .filter_map(|quantity| {
+                let mut event = inference(org_id, agent_id, serde_json::json!({}));
+                event.quantity = quantity;
+                sampler.sample(event)
+            })
+            .collect();
+        assert_eq!(events.len(), 2);
+        let engine = AggregationEngine::new();
+        let aggregate = |aggregation_type: AggregationType| {
+            engine
+                .aggregate_events(
+                    &selection(org_id),
+                    UsageEventType::LlmInference,
+                    &aggregation_type,
+                    &events,
+                )
+                .unwrap()
+                .value
+        };
+
+        assert_eq!(
+            aggregate(AggregationType::Count),
+            AggregationValue::Integer(10)
+        );
+        assert_eq!(
+            aggregate(AggregationType::Sum),
+            AggregationValue::Integer(35)
+        );
+        assert_eq!(
+            aggregate(AggregationType::Max),
+            AggregationValue::Integer(6)
+        );
+        assert_eq!(
+            aggregate(AggregationType::Min),
+            AggregationValue::Integer(1)
+        );
+        assert_eq!(
+            aggregate(AggregationType::Average),
+            AggregationValue::Float(3.5)
+        );
+    }
+
+    #[test]
+    fn test_to_quantity_rounding() {
+        assert_eq!(AggregationValue::Integer(7).to_quantity(), Some(7));
+        assert_eq!(AggregationValue::Float(120.5).to_quantity(), Some(121));
+        assert_eq!(AggregationValue::Float(120.49).to_quantity(), Some(120));
+        assert_eq!(AggregationValue::Float(-2.5).to_quantity(), Some(-3));
+        assert_eq!(AggregationValue::Float(f64::NAN).to_quantity(), None);
+        assert_eq!(AggregationValue::String("x".into()).to_quantity(), None);
+    }
 }
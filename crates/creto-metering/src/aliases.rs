@@ -0,0 +1,399 @@
+//! Metric code aliases for renamed billable metrics.
+//!
+//! Renaming a metric (say `tokens` to `llm_tokens`) leaves months of events
+//! stored under the old code. A [`MetricAlias`] maps the old code to the
+//! canonical one over an effective range, and the [`MetricAliasRegistry`]
+//! holding the aliases is consulted wherever a metric code is looked up:
+//!
+//! - Billing aggregation bills aliased events under the canonical code, and
+//!   the [`UsageSelection`](crate::aggregation::UsageSelection) recorded on
+//!   each line item carries the aliases, so drill-down and the
+//!   [`EventRepository`](crate::repository::EventRepository) selection
+//!   queries return the historical events too.
+//! - [`QuotaEnforcer`](crate::quota::QuotaEnforcer) keys quotas by canonical
+//!   code, so the old and new codes draw on the same quota.
+//! - [`PricingEngine`](crate::pricing::PricingEngine) prices an old code with
+//!   the canonical code's model.
+//!
+//! Stored events are never rewritten. With
+//! [`MeteringService::with_alias_rewrite`](crate::service::MeteringService::with_alias_rewrite)
+//! newly ingested events are stored under the canonical code instead, with
+//! the code they arrived with kept in [`ALIASED_FROM_PROPERTY`].
+//!
+//! Aliases resolve in a single hop: a code that is the canonical code of an
+//! alias can't itself be aliased, and vice versa, so chains and cycles are
+//! rejected when an alias is registered.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, CretoResult};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::events::UsageEvent;
+use crate::repository::MetricAliasRepository;
+
+/// Property holding the code an event arrived with, set on events rewritten
+/// to their canonical code at ingestion.
+pub const ALIASED_FROM_PROPERTY: &str = "aliased_from";
+
+/// An old metric code standing for a canonical one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricAlias {
+    /// Unique identifier.
+    pub id: Uuid,
+
+    /// Code events were recorded under before the rename.
+    pub old_code: String,
+
+    /// Code the old one is billed, priced and limited as.
+    pub canonical_code: String,
+
+    /// Earliest event timestamp the alias applies to (`None` = all history).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_from: Option<DateTime<Utc>>,
+
+    /// Event timestamp the alias stops applying at, exclusive (`None` =
+    /// open-ended). Set it before reusing the old code for something else.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effective_until: Option<DateTime<Utc>>,
+
+    /// When the alias was registered.
+    pub created_at: DateTime<Utc>,
+}
+
+impl MetricAlias {
+    /// Alias `old_code` to `canonical_code` for all of time.
+    pub fn new(old_code: impl Into<String>, canonical_code: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            old_code: old_code.into(),
+            canonical_code: canonical_code.into(),
+            effective_from: None,
+            effective_until: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Only apply to events from `from` on.
+    pub fn with_effective_from(mut self, from: DateTime<Utc>) -> Self {
+        self.effective_from = Some(from);
+        self
+    }
+
+    /// Stop applying to events from `until` on.
+    pub fn with_effective_until(mut self, until: DateTime<Utc>) -> Self {
+        self.effective_until = Some(until);
+        self
+    }
+
+    /// Whether the alias applies to an event timestamped `at`.
+    pub fn covers(&self, at: DateTime<Utc>) -> bool {
+        !matches!(self.effective_from, Some(from) if at < from)
+            && !matches!(self.effective_until, Some(until) if at >= until)
+    }
+
+    /// Whether the alias applies to `code` at `at`.
+    pub fn applies_to(&self, code: &str, at: DateTime<Utc>) -> bool {
+        self.old_code == code && self.covers(at)
+    }
+
+    /// Check the codes are set and distinct, and the range is not empty.
+    pub fn validate(&self) -> CretoResult<()> {
+        if self.old_code.trim().is_empty() || self.canonical_code.trim().is_empty() {
+            return Err(CretoError::ValidationFailed(
+                "Metric alias codes must be non-empty".to_string(),
+            ));
+        }
+        if self.old_code == self.canonical_code {
+            return Err(CretoError::ValidationFailed(format!(
+                "Metric alias {} points at itself",
+                self.old_code
+            )));
+        }
+        if let (Some(from), Some(until)) = (self.effective_from, self.effective_until) {
+            if until <= from {
+                return Err(CretoError::ValidationFailed(format!(
+                    "Metric alias {} -> {} ends before it starts",
+                    self.old_code, self.canonical_code
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the effective ranges of two aliases intersect.
+    fn overlaps(&self, other: &MetricAlias) -> bool {
+        let before = |from: Option<DateTime<Utc>>, until: Option<DateTime<Utc>>| !matches!((from, until), (Some(from), Some(until)) if from >= until);
+        before(self.effective_from, other.effective_until)
+            && before(other.effective_from, self.effective_until)
+    }
+}
+
+/// The registered aliases, indexed by old code.
+///
+/// Shared by the components that resolve metric codes; changes are seen by
+/// all of them immediately.
+#[derive(Debug, Default)]
+pub struct MetricAliasRegistry {
+    aliases: RwLock<HashMap<String, Vec<MetricAlias>>>,
+}
+
+impl MetricAliasRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an alias.
+    ///
+    /// Rejects aliases that would chain onto, or be chained onto by, another
+    /// alias, including cycles, and aliases of an old code overlapping one
+    /// already registered for it.
+    pub fn register(&self, alias: MetricAlias) -> CretoResult<()> {
+        let mut aliases = self.aliases.write().unwrap();
+        check_registrable(&aliases, &alias)?;
+        aliases
+            .entry(alias.old_code.clone())
+            .or_default()
+            .push(alias);
+        Ok(())
+    }
+
+    /// Remove an alias, returning it if it was registered.
+    pub fn remove(&self, id: Uuid) -> Option<MetricAlias> {
+        let mut aliases = self.aliases.write().unwrap();
+        let mut removed = None;
+        aliases.retain(|_, entries| {
+            if let Some(index) = entries.iter().position(|a| a.id == id) {
+                removed = Some(entries.remove(index));
+            }
+            !entries.is_empty()
+        });
+        removed
+    }
+
+    /// Replace every alias, e.g. with those loaded from a repository.
+    ///
+    /// Nothing is replaced if any alias is invalid.
+    pub fn replace_aliases(&self, aliases: Vec<MetricAlias>) -> CretoResult<()> {
+        let mut loaded: HashMap<String, Vec<MetricAlias>> = HashMap::new();
+        for alias in aliases {
+            check_registrable(&loaded, &alias)?;
+            loaded
+                .entry(alias.old_code.clone())
+                .or_default()
+                .push(alias);
+        }
+        *self.aliases.write().unwrap() = loaded;
+        Ok(())
+    }
+
+    /// Reload every alias from `repository`, returning how many were loaded.
+    pub async fn load<R>(&self, repository: &R) -> CretoResult<usize>
+    where
+        R: MetricAliasRepository + Sync,
+    {
+        let aliases = repository.list_aliases().await?;
+        let loaded = aliases.len();
+        self.replace_aliases(aliases)?;
+        Ok(loaded)
+    }
+
+    /// Every registered alias.
+    pub fn aliases(&self) -> Vec<MetricAlias> {
+        let mut aliases: Vec<MetricAlias> = self
+            .aliases
+            .read()
+            .unwrap()
+            .values()
+            .flatten()
+            .cloned()
+            .collect();
+        aliases.sort_by_key(|a| a.created_at);
+        aliases
+    }
+
+    /// Aliases resolving to `canonical_code`.
+    pub fn aliases_of(&self, canonical_code: &str) -> Vec<MetricAlias> {
+        self.aliases()
+            .into_iter()
+            .filter(|a| a.canonical_code == canonical_code)
+            .collect()
+    }
+
+    /// The canonical code of `code` for an event timestamped `at`.
+    ///
+    /// Codes without an alias in effect are returned unchanged.
+    pub fn canonical_code(&self, code: &str, at: DateTime<Utc>) -> String {
+        self.aliases
+            .read()
+            .unwrap()
+            .get(code)
+            .and_then(|entries| entries.iter().find(|a| a.covers(at)))
+            .map_or_else(|| code.to_string(), |a| a.canonical_code.clone())
+    }
+
+    /// Rewrite an event to its canonical code, keeping the code it arrived
+    /// with in [`ALIASED_FROM_PROPERTY`].
+    pub fn rewrite(&self, mut event: UsageEvent) -> UsageEvent {
+        let canonical = self.canonical_code(&event.code, event.timestamp);
+        if canonical == event.code {
+            return event;
+        }
+        if event.properties.is_null() {
+            event.properties = serde_json::Value::Object(serde_json::Map::new());
+        }
+        if let Some(properties) = event.properties.as_object_mut() {
+            properties.insert(
+                ALIASED_FROM_PROPERTY.to_string(),
+                std::mem::replace(&mut event.code, canonical).into(),
+            );
+        }
+        event
+    }
+}
+
+/// Check `alias` can join `aliases` without a chain, cycle or overlap.
+fn check_registrable(
+    aliases: &HashMap<String, Vec<MetricAlias>>,
+    alias: &MetricAlias,
+) -> CretoResult<()> {
+    alias.validate()?;
+    if aliases.contains_key(&alias.canonical_code) {
+        return Err(CretoError::ValidationFailed(format!(
+            "Metric alias {} -> {} would chain: {} is itself an alias",
+            alias.old_code, alias.canonical_code, alias.canonical_code
+        )));
+    }
+    if let Some(existing) = aliases
+        .values()
+        .flatten()
+        .find(|a| a.canonical_code == alias.old_code)
+    {
+        return Err(CretoError::ValidationFailed(format!(
+            "Metric alias {} -> {} would chain: {} is aliased to {}",
+            alias.old_code, alias.canonical_code, existing.old_code, alias.old_code
+        )));
+    }
+    if let Some(existing) = aliases
+        .get(&alias.old_code)
+        .and_then(|entries| entries.iter().find(|a| a.overlaps(alias)))
+    {
+        return Err(CretoError::ValidationFailed(format!(
+            "Metric alias {} -> {} overlaps alias {} -> {}",
+            alias.old_code, alias.canonical_code, existing.old_code, existing.canonical_code
+        )));
+    }
+    Ok(())
+}
+
+/// In-memory alias store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryMetricAliasRepository {
+    aliases: RwLock<HashMap<Uuid, MetricAlias>>,
+}
+
+impl InMemoryMetricAliasRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl MetricAliasRepository for InMemoryMetricAliasRepository {
+    async fn insert_alias(&self, alias: &MetricAlias) -> Result<(), CretoError> {
+        self.aliases
+            .write()
+            .unwrap()
+            .insert(alias.id, alias.clone());
+        Ok(())
+    }
+
+    async fn delete_alias(&self, id: Uuid) -> Result<bool, CretoError> {
+        Ok(self.aliases.write().unwrap().remove(&id).is_some())
+    }
+
+    async fn list_aliases(&self) -> Result<Vec<MetricAlias>, CretoError> {
+        let mut aliases: Vec<_> = self.aliases.read().unwrap().values().cloned().collect();
+        aliases.sort_by_key(|a| a.created_at);
+        Ok(aliases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::UsageEventType;
+    use chrono::Duration;
+
+    #[test]
+    fn test_chains_and_cycles_rejected() {
+        let registry = MetricAliasRegistry::new();
+        registry
+            .register(MetricAlias::new("tokens", "llm_tokens"))
+            .unwrap();
+
+        // Self-alias, cycle back, and chains in either direction
+        assert!(registry
+            .register(MetricAlias::new("api_calls", "api_calls"))
+            .is_err());
+        assert!(registry
+            .register(MetricAlias::new("llm_tokens", "tokens"))
+            .is_err());
+        assert!(registry
+            .register(MetricAlias::new("llm_tokens", "model_tokens"))
+            .is_err());
+        assert!(registry
+            .register(MetricAlias::new("old_tokens", "tokens"))
+            .is_err());
+        // Same old code, overlapping range
+        assert!(registry
+            .register(MetricAlias::new("tokens", "model_tokens"))
+            .is_err());
+
+        // Several old codes may share a canonical code
+        registry
+            .register(MetricAlias::new("old_tokens", "llm_tokens"))
+            .unwrap();
+        assert_eq!(registry.aliases_of("llm_tokens").len(), 2);
+
+        // A load containing a cycle is refused as a whole
+        assert!(registry
+            .replace_aliases(vec![MetricAlias::new("a", "b"), MetricAlias::new("b", "a"),])
+            .is_err());
+        assert_eq!(registry.aliases().len(), 2);
+    }
+
+    #[test]
+    fn test_effective_range_and_rewrite() {
+        let retired = Utc::now();
+        let registry = MetricAliasRegistry::new();
+        registry
+            .register(MetricAlias::new("tokens", "llm_tokens").with_effective_until(retired))
+            .unwrap();
+        // The old code may be reused once its alias has ended
+        registry
+            .register(MetricAlias::new("tokens", "embedding_tokens").with_effective_from(retired))
+            .unwrap();
+
+        let before = retired - Duration::seconds(1);
+        assert_eq!(registry.canonical_code("tokens", before), "llm_tokens");
+        assert_eq!(
+            registry.canonical_code("tokens", retired),
+            "embedding_tokens"
+        );
+        assert_eq!(registry.canonical_code("api_calls", before), "api_calls");
+
+        let event = UsageEvent::builder()
+            .event_type(UsageEventType::LlmInference)
+            .code("tokens")
+            .timestamp(before)
+            .build();
+        let rewritten = registry.rewrite(event);
+        assert_eq!(rewritten.code, "llm_tokens");
+        assert_eq!(rewritten.properties[ALIASED_FROM_PROPERTY], "tokens");
+    }
+}
@@ -0,0 +1,811 @@
+//! Usage anomaly detection.
+//!
+//! The [`AnomalyDetector`] keeps a rolling baseline per (organization, agent,
+//! metric code): usage is counted in fixed windows of event time, and each
+//! closed window updates an exponentially weighted mean and variance. While a
+//! window is open, every event re-checks its running total against the
+//! baseline, so a spike is flagged as soon as it crosses the threshold rather
+//! than when the window closes.
+//!
+//! A window is anomalous when its total exceeds `spike_multiple` times the
+//! baseline mean. Keys are not checked until they have `warmup_windows` of
+//! history, and windows below `min_volume` are never flagged, so new or
+//! near-idle agents do not alert on their first handful of calls. Anomalous
+//! windows are left out of the baseline so a sustained spike cannot teach the
+//! detector that it is normal.
+//!
+//! Once a key alerts, further anomalous windows extend a cooldown instead of
+//! alerting again: one sustained spike produces one [`AnomalyAlert`] per
+//! severity it reaches (an elevated alert as it crosses the spike multiple,
+//! upgraded once if it goes on to cross the high severity multiple). Alerts
+//! are handed to every registered [`AnomalySink`], and the latest
+//! [`RECENT_ALERT_CAPACITY`] are kept for [`AnomalyDetector::recent_alerts`].
+//!
+//! Baselines are held in memory in a [`ShardedMap`], so events for different
+//! keys do not contend on one lock. Baselines idle for longer than
+//! `idle_ttl` are evicted, and at most `max_baselines` are tracked; usage for
+//! further keys is not checked until room frees up.
+//! [`AnomalyDetector::start_checkpoints`] restores persisted baselines at
+//! startup and then checkpoints them to an [`AnomalyBaselineRepository`]
+//! periodically and once more on shutdown.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{
+    AgentId, CretoError, OrganizationId, ShardedMap, ShutdownCoordinator, DEFAULT_SHARDS,
+};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::events::UsageEvent;
+use crate::repository::AnomalyBaselineRepository;
+
+/// Idle windows folded into a baseline after a gap; beyond this the mean has
+/// decayed to nothing anyway.
+const MAX_IDLE_WINDOWS: i64 = 1440;
+
+/// Alerts kept in memory for [`AnomalyDetector::recent_alerts`].
+pub const RECENT_ALERT_CAPACITY: usize = 256;
+
+/// Anomaly detection configuration.
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    /// Length of a usage window.
+    pub window: Duration,
+    /// EWMA smoothing factor (0.0-1.0); higher reacts faster to change.
+    pub alpha: f64,
+    /// Window total, as a multiple of the baseline mean, that is anomalous.
+    pub spike_multiple: f64,
+    /// Multiple at or above which an anomaly is [`AnomalySeverity::High`].
+    pub high_severity_multiple: f64,
+    /// Minimum window total that can be flagged.
+    pub min_volume: i64,
+    /// Closed windows of history required before a key is checked.
+    pub warmup_windows: u64,
+    /// Quiet period after the last anomalous window before a key can alert
+    /// again.
+    pub cooldown: Duration,
+    /// Baselines without usage for this long are evicted.
+    pub idle_ttl: Duration,
+    /// Most baselines tracked at once.
+    pub max_baselines: usize,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::minutes(1),
+            alpha: 0.1,
+            spike_multiple: 5.0,
+            high_severity_multiple: 10.0,
+            min_volume: 50,
+            warmup_windows: 30,
+            cooldown: Duration::minutes(15),
+            idle_ttl: Duration::days(1),
+            max_baselines: 1_000_000,
+        }
+    }
+}
+
+impl AnomalyConfig {
+    /// Start of the window containing `timestamp`.
+    pub fn window_start(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        let secs = self.window.num_seconds().max(1);
+        let start = timestamp.timestamp() - timestamp.timestamp().rem_euclid(secs);
+        DateTime::from_timestamp(start, 0).unwrap_or(timestamp)
+    }
+}
+
+/// Rolling usage statistics for one (organization, agent, metric code).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyBaseline {
+    /// Organization.
+    pub organization_id: OrganizationId,
+    /// Agent.
+    pub agent_id: AgentId,
+    /// Metric code.
+    pub metric_code: String,
+    /// EWMA of usage per window.
+    pub mean: f64,
+    /// Exponentially weighted variance of usage per window.
+    pub variance: f64,
+    /// Closed windows folded into the baseline.
+    pub windows: u64,
+    /// Start of the open window.
+    pub window_start: DateTime<Utc>,
+    /// Usage so far in the open window.
+    pub window_quantity: i64,
+    /// Alerts for this key are suppressed until this time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suppressed_until: Option<DateTime<Utc>>,
+    /// Highest severity alerted since suppression began.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alerted_severity: Option<AnomalySeverity>,
+    /// Event time of the last observation.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AnomalyBaseline {
+    fn new(event: &UsageEvent, window_start: DateTime<Utc>) -> Self {
+        Self {
+            organization_id: event.organization_id,
+            agent_id: event.agent_id,
+            metric_code: event.code.clone(),
+            mean: 0.0,
+            variance: 0.0,
+            windows: 0,
+            window_start,
+            window_quantity: 0,
+            suppressed_until: None,
+            alerted_severity: None,
+            updated_at: event.timestamp,
+        }
+    }
+
+    /// Standard deviation of usage per window.
+    pub fn stddev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    /// Mean used as the spike reference, floored at one unit per window so
+    /// an idle baseline still yields a finite multiple.
+    pub fn reference(&self) -> f64 {
+        self.mean.max(1.0)
+    }
+
+    fn fold(&mut self, quantity: f64, alpha: f64) {
+        let diff = quantity - self.mean;
+        self.mean += alpha * diff;
+        self.variance = (1.0 - alpha) * (self.variance + alpha * diff * diff);
+        self.windows += 1;
+    }
+
+    fn key(&self) -> BaselineKey {
+        (
+            self.organization_id,
+            self.agent_id,
+            self.metric_code.clone(),
+        )
+    }
+}
+
+type BaselineKey = (OrganizationId, AgentId, String);
+
+/// How far an anomaly is above its baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalySeverity {
+    /// Above the spike multiple.
+    Elevated,
+    /// At or above the high severity multiple.
+    High,
+}
+
+/// A usage window flagged as anomalous.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyAlert {
+    /// Alert ID.
+    pub id: Uuid,
+    /// Organization.
+    pub organization_id: OrganizationId,
+    /// Agent.
+    pub agent_id: AgentId,
+    /// Metric code.
+    pub metric_code: String,
+    /// Severity.
+    pub severity: AnomalySeverity,
+    /// Start of the anomalous window.
+    pub window_start: DateTime<Utc>,
+    /// Length of the window.
+    pub window_seconds: i64,
+    /// Usage in the window when the alert fired.
+    pub window_quantity: i64,
+    /// Baseline mean usage per window.
+    pub baseline_mean: f64,
+    /// Baseline standard deviation per window.
+    pub baseline_stddev: f64,
+    /// Window usage as a multiple of the baseline reference.
+    pub multiple: f64,
+    /// Standard deviations above the mean (None for a flat baseline).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub z_score: Option<f64>,
+    /// Transaction ID of the event that crossed the threshold.
+    pub transaction_id: String,
+    /// Event time at which the threshold was crossed.
+    pub detected_at: DateTime<Utc>,
+}
+
+/// Destination for anomaly alerts.
+pub trait AnomalySink: Send + Sync {
+    /// Deliver an alert. Called on the ingestion path; must not block.
+    fn emit(&self, alert: &AnomalyAlert);
+}
+
+impl AnomalySink for tokio::sync::mpsc::UnboundedSender<AnomalyAlert> {
+    fn emit(&self, alert: &AnomalyAlert) {
+        if self.send(alert.clone()).is_err() {
+            tracing::warn!(alert_id = %alert.id, "Anomaly alert receiver dropped");
+        }
+    }
+}
+
+/// In-memory alert sink for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryAnomalySink {
+    alerts: RwLock<Vec<AnomalyAlert>>,
+}
+
+impl InMemoryAnomalySink {
+    /// Create an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Alerts received, oldest first.
+    pub fn alerts(&self) -> Vec<AnomalyAlert> {
+        self.alerts.read().unwrap().clone()
+    }
+}
+
+impl AnomalySink for InMemoryAnomalySink {
+    fn emit(&self, alert: &AnomalyAlert) {
+        self.alerts.write().unwrap().push(alert.clone());
+    }
+}
+
+/// In-memory baseline store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryAnomalyBaselineRepository {
+    baselines: RwLock<HashMap<BaselineKey, AnomalyBaseline>>,
+}
+
+impl InMemoryAnomalyBaselineRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AnomalyBaselineRepository for InMemoryAnomalyBaselineRepository {
+    async fn save_baselines(&self, baselines: &[AnomalyBaseline]) -> Result<(), CretoError> {
+        let mut stored = self.baselines.write().unwrap();
+        for baseline in baselines {
+            stored.insert(baseline.key(), baseline.clone());
+        }
+        Ok(())
+    }
+
+    async fn load_baselines(&self) -> Result<Vec<AnomalyBaseline>, CretoError> {
+        Ok(self.baselines.read().unwrap().values().cloned().collect())
+    }
+
+    async fn delete_baselines_before(
+        &self,
+        updated_before: DateTime<Utc>,
+    ) -> Result<u64, CretoError> {
+        let mut stored = self.baselines.write().unwrap();
+        let before = stored.len();
+        stored.retain(|_, b| b.updated_at >= updated_before);
+        Ok((before - stored.len()) as u64)
+    }
+}
+
+/// Flags usage spikes against per-agent rolling baselines.
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    baselines: ShardedMap<BaselineKey, AnomalyBaseline>,
+    /// Baselines held, for the `max_baselines` bound.
+    tracked: AtomicUsize,
+    sinks: Vec<Arc<dyn AnomalySink>>,
+    /// Latest alerts across all organizations, oldest first.
+    recent: RwLock<VecDeque<AnomalyAlert>>,
+}
+
+impl AnomalyDetector {
+    /// Create a detector with no baselines and no sinks.
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            baselines: ShardedMap::new(DEFAULT_SHARDS),
+            tracked: AtomicUsize::new(0),
+            sinks: Vec::new(),
+            recent: RwLock::new(VecDeque::new()),
+        }
+    }
+
+    /// Deliver alerts to `sink` as well.
+    pub fn with_sink(mut self, sink: Arc<dyn AnomalySink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    /// Get the configuration.
+    pub fn config(&self) -> &AnomalyConfig {
+        &self.config
+    }
+
+    /// Get the baseline for an agent's metric.
+    pub fn baseline(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        metric_code: &str,
+    ) -> Option<AnomalyBaseline> {
+        self.baselines
+            .get(&(organization_id, agent_id, metric_code.to_string()))
+    }
+
+    /// Number of baselines held.
+    pub fn baseline_count(&self) -> usize {
+        self.tracked.load(Ordering::Relaxed)
+    }
+
+    /// Fold an ingested event into its baseline, alerting if its window
+    /// became anomalous.
+    ///
+    /// Events for a window that has already closed are ignored, as are
+    /// events for a new key while `max_baselines` are held.
+    pub fn observe(&self, event: &UsageEvent) -> Option<AnomalyAlert> {
+        let window_start = self.config.window_start(event.timestamp);
+        let key = (event.organization_id, event.agent_id, event.code.clone());
+        if !self.baselines.contains_key(&key)
+            && self.tracked.load(Ordering::Relaxed) >= self.config.max_baselines
+        {
+            tracing::debug!(
+                org_id = %event.organization_id,
+                agent_id = %event.agent_id,
+                code = %event.code,
+                "Anomaly baseline limit reached; usage not checked"
+            );
+            return None;
+        }
+
+        let mut inserted = false;
+        let alert = self.baselines.update_or_insert_with(
+            key,
+            || {
+                inserted = true;
+                AnomalyBaseline::new(event, window_start)
+            },
+            |baseline| self.evaluate(baseline, event, window_start),
+        );
+        if inserted {
+            self.tracked.fetch_add(1, Ordering::Relaxed);
+        }
+        let alert = alert?;
+
+        tracing::warn!(
+            org_id = %alert.organization_id,
+            agent_id = %alert.agent_id,
+            code = %alert.metric_code,
+            severity = ?alert.severity,
+            multiple = alert.multiple,
+            "Usage anomaly detected"
+        );
+        for sink in &self.sinks {
+            sink.emit(&alert);
+        }
+        {
+            let mut recent = self.recent.write().unwrap();
+            if recent.len() == RECENT_ALERT_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(alert.clone());
+        }
+        Some(alert)
+    }
+
+    /// An organization's latest alerts, newest first, at most `limit`.
+    ///
+    /// Only the last [`RECENT_ALERT_CAPACITY`] alerts across all
+    /// organizations are kept.
+    pub fn recent_alerts(
+        &self,
+        organization_id: OrganizationId,
+        limit: usize,
+    ) -> Vec<AnomalyAlert> {
+        self.recent
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|a| a.organization_id == organization_id)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn evaluate(
+        &self,
+        baseline: &mut AnomalyBaseline,
+        event: &UsageEvent,
+        window_start: DateTime<Utc>,
+    ) -> Option<AnomalyAlert> {
+        if window_start < baseline.window_start {
+            return None;
+        }
+        if window_start > baseline.window_start {
+            self.close_window(baseline, window_start);
+        }
+        baseline.window_quantity += event.quantity;
+        baseline.updated_at = baseline.updated_at.max(event.timestamp);
+
+        if !self.is_anomalous(baseline) {
+            return None;
+        }
+        let multiple = baseline.window_quantity as f64 / baseline.reference();
+        let severity = if multiple >= self.config.high_severity_multiple {
+            AnomalySeverity::High
+        } else {
+            AnomalySeverity::Elevated
+        };
+        let suppressed = baseline
+            .suppressed_until
+            .is_some_and(|until| event.timestamp < until)
+            && baseline.alerted_severity >= Some(severity);
+        baseline.suppressed_until = Some(window_start + self.config.window + self.config.cooldown);
+        if suppressed {
+            return None;
+        }
+        baseline.alerted_severity = Some(severity);
+
+        let stddev = baseline.stddev();
+        Some(AnomalyAlert {
+            id: Uuid::now_v7(),
+            organization_id: baseline.organization_id,
+            agent_id: baseline.agent_id,
+            metric_code: baseline.metric_code.clone(),
+            severity,
+            window_start,
+            window_seconds: self.config.window.num_seconds(),
+            window_quantity: baseline.window_quantity,
+            baseline_mean: baseline.mean,
+            baseline_stddev: stddev,
+            multiple,
+            z_score: (stddev > 0.0)
+                .then(|| (baseline.window_quantity as f64 - baseline.mean) / stddev),
+            transaction_id: event.transaction_id.clone(),
+            detected_at: event.timestamp,
+        })
+    }
+
+    /// Close the open window, folding it and any idle windows since into the
+    /// baseline, and open the window starting at `next`.
+    fn close_window(&self, baseline: &mut AnomalyBaseline, next: DateTime<Utc>) {
+        if !self.is_anomalous(baseline) {
+            baseline.fold(baseline.window_quantity as f64, self.config.alpha);
+        }
+        let window_secs = self.config.window.num_seconds().max(1);
+        let idle = (next - baseline.window_start).num_seconds() / window_secs - 1;
+        for _ in 0..idle.clamp(0, MAX_IDLE_WINDOWS) {
+            baseline.fold(0.0, self.config.alpha);
+        }
+        baseline.window_start = next;
+        baseline.window_quantity = 0;
+    }
+
+    fn is_anomalous(&self, baseline: &AnomalyBaseline) -> bool {
+        baseline.windows >= self.config.warmup_windows
+            && baseline.window_quantity >= self.config.min_volume
+            && baseline.window_quantity as f64 > self.config.spike_multiple * baseline.reference()
+    }
+
+    /// Drop baselines without usage since `now - idle_ttl`. Returns the
+    /// number evicted.
+    pub fn evict_idle(&self, now: DateTime<Utc>) -> usize {
+        let cutoff = now - self.config.idle_ttl;
+        let evicted = self.baselines.retain(|_, b| b.updated_at >= cutoff);
+        self.tracked.fetch_sub(evicted, Ordering::Relaxed);
+        evicted
+    }
+
+    /// Persist every baseline. Returns the number saved.
+    pub async fn checkpoint<R: AnomalyBaselineRepository + Sync>(
+        &self,
+        repository: &R,
+    ) -> Result<usize, CretoError> {
+        let mut baselines = Vec::new();
+        self.baselines.for_each(|_, b| baselines.push(b.clone()));
+        repository.save_baselines(&baselines).await?;
+        Ok(baselines.len())
+    }
+
+    /// Load persisted baselines that are not idle, keeping any held baseline
+    /// that has seen later usage. Returns the number loaded.
+    pub async fn restore<R: AnomalyBaselineRepository + Sync>(
+        &self,
+        repository: &R,
+    ) -> Result<usize, CretoError> {
+        let cutoff = Utc::now() - self.config.idle_ttl;
+        let mut count = 0;
+        for baseline in repository.load_baselines().await? {
+            if baseline.updated_at < cutoff {
+                continue;
+            }
+            let mut inserted = false;
+            self.baselines.update_or_insert_with(
+                baseline.key(),
+                || {
+                    inserted = true;
+                    baseline.clone()
+                },
+                |held| {
+                    if held.updated_at < baseline.updated_at {
+                        *held = baseline.clone();
+                    }
+                },
+            );
+            if inserted {
+                self.tracked.fetch_add(1, Ordering::Relaxed);
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Restore persisted baselines, then spawn a background task that every
+    /// `interval` evicts idle baselines, checkpoints the rest and deletes
+    /// idle ones from `repository`. A final checkpoint is written on
+    /// shutdown. The task is registered with `shutdown`.
+    ///
+    /// Await this before feeding the detector so restored baselines are in
+    /// place for the first events.
+    pub async fn start_checkpoints<R>(
+        self: &Arc<Self>,
+        repository: Arc<R>,
+        shutdown: &ShutdownCoordinator,
+        interval: std::time::Duration,
+    ) -> Result<JoinHandle<()>, CretoError>
+    where
+        R: AnomalyBaselineRepository + Sync + 'static,
+    {
+        let restored = self.restore(&*repository).await?;
+        tracing::info!(count = restored, "Restored anomaly baselines");
+
+        let detector = Arc::clone(self);
+        Ok(
+            shutdown.spawn("metering.anomaly_checkpoint", move |guard| async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                // The first tick is immediate and there is nothing new to save yet
+                ticker.tick().await;
+                loop {
+                    tokio::select! {
+                        biased;
+                        _ = guard.cancelled() => break,
+                        _ = ticker.tick() => {}
+                    }
+                    detector.checkpoint_pass(&*repository).await;
+                }
+                // Keep the guard until the final checkpoint is written
+                detector.checkpoint_pass(&*repository).await;
+                drop(guard);
+            }),
+        )
+    }
+
+    async fn checkpoint_pass<R: AnomalyBaselineRepository + Sync>(&self, repository: &R) {
+        let now = Utc::now();
+        let evicted = self.evict_idle(now);
+        if evicted > 0 {
+            tracing::debug!(count = evicted, "Evicted idle anomaly baselines");
+        }
+        if let Err(e) = self.checkpoint(repository).await {
+            tracing::warn!(error = %e, "Anomaly baseline checkpoint failed");
+        }
+        if let Err(e) = repository
+            .delete_baselines_before(now - self.config.idle_ttl)
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to delete idle anomaly baselines");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::UsageEventType;
+
+    fn event_at(org_id: OrganizationId, agent_id: AgentId, timestamp: DateTime<Utc>) -> UsageEvent {
+        UsageEvent::builder()
+            .transaction_id(Uuid::now_v7().to_string())
+            .organization_id(org_id)
+            .agent_id(agent_id)
+            .event_type(UsageEventType::ApiCall)
+            .code("api_calls")
+            .quantity(1)
+            .timestamp(timestamp)
+            .build()
+    }
+
+    /// Feed `rate(minute)` evenly spaced events per minute for `minutes`
+    /// minutes starting at `start`, returning the alerts raised.
+    fn drive(
+        detector: &AnomalyDetector,
+        org_id: OrganizationId,
+        agent_id: AgentId,
+        start: DateTime<Utc>,
+        minutes: i64,
+        rate: impl Fn(i64) -> i64,
+    ) -> Vec<AnomalyAlert> {
+        let mut alerts = Vec::new();
+        for minute in 0..minutes {
+            let count = rate(minute);
+            let spacing = 60_000 / count.max(1);
+            for i in 0..count {
+                let at = start + Duration::minutes(minute) + Duration::milliseconds(i * spacing);
+                alerts.extend(detector.observe(&event_at(org_id, agent_id, at)));
+            }
+        }
+        alerts
+    }
+
+    fn start() -> DateTime<Utc> {
+        AnomalyConfig::default().window_start(Utc::now()) - Duration::days(1)
+    }
+
+    #[test]
+    fn test_sustained_spike_alerts_once_per_severity_within_a_window() {
+        let sink = Arc::new(InMemoryAnomalySink::new());
+        let detector = AnomalyDetector::new(AnomalyConfig::default()).with_sink(sink.clone());
+        let (org_id, agent_id) = (OrganizationId::new(), AgentId::new());
+        let start = start();
+
+        assert!(drive(&detector, org_id, agent_id, start, 60, |_| 10).is_empty());
+
+        // 20x spike sustained for ten minutes
+        let spike_start = start + Duration::minutes(60);
+        let alerts = drive(&detector, org_id, agent_id, spike_start, 10, |_| 200);
+        let severities: Vec<_> = alerts.iter().map(|a| a.severity).collect();
+        assert_eq!(
+            severities,
+            [AnomalySeverity::Elevated, AnomalySeverity::High]
+        );
+        assert!(alerts[0].detected_at - spike_start < Duration::seconds(20));
+        let high = &alerts[1];
+        assert_eq!(high.window_start, spike_start);
+        assert!(high.detected_at - spike_start < Duration::seconds(40));
+        assert!(high.multiple >= 10.0);
+        assert!((high.baseline_mean - 10.0).abs() < 0.5);
+        assert_eq!(sink.alerts(), alerts);
+        let recent = detector.recent_alerts(org_id, 1);
+        assert_eq!(recent, [alerts[1].clone()]);
+        assert!(detector.recent_alerts(OrganizationId::new(), 10).is_empty());
+
+        // The spike windows were kept out of the baseline
+        let baseline = detector.baseline(org_id, agent_id, "api_calls").unwrap();
+        assert!(baseline.mean < 11.0);
+
+        // Back to normal, then a second spike after the cooldown alerts again
+        let calm_start = spike_start + Duration::minutes(10);
+        assert!(drive(&detector, org_id, agent_id, calm_start, 30, |_| 10).is_empty());
+        let alerts = drive(
+            &detector,
+            org_id,
+            agent_id,
+            calm_start + Duration::minutes(30),
+            2,
+            |_| 200,
+        );
+        assert_eq!(alerts.len(), 2);
+    }
+
+    #[test]
+    fn test_gradual_growth_and_new_agents_do_not_alert() {
+        let detector = AnomalyDetector::new(AnomalyConfig::default());
+        let org_id = OrganizationId::new();
+        let start = start();
+
+        // 3% organic growth per minute for two hours (~35x overall)
+        let alerts = drive(&detector, org_id, AgentId::new(), start, 120, |m| {
+            (10.0 * 1.03f64.powi(m as i32)) as i64
+        });
+        assert!(alerts.is_empty());
+
+        // A brand new agent bursting straight away is still warming up
+        assert!(drive(&detector, org_id, AgentId::new(), start, 5, |_| 500).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_baselines_survive_restart() {
+        let repository = InMemoryAnomalyBaselineRepository::new();
+        let (org_id, agent_id) = (OrganizationId::new(), AgentId::new());
+        let start = start();
+
+        let detector = AnomalyDetector::new(AnomalyConfig::default());
+        drive(&detector, org_id, agent_id, start, 60, |_| 10);
+        assert_eq!(detector.checkpoint(&repository).await.unwrap(), 1);
+
+        let restarted = AnomalyDetector::new(AnomalyConfig::default());
+        assert_eq!(restarted.restore(&repository).await.unwrap(), 1);
+        assert_eq!(
+            restarted.baseline(org_id, agent_id, "api_calls"),
+            detector.baseline(org_id, agent_id, "api_calls")
+        );
+
+        let alerts = drive(
+            &restarted,
+            org_id,
+            agent_id,
+            start + Duration::minutes(60),
+            1,
+            |_| 200,
+        );
+        assert_eq!(alerts[0].severity, AnomalySeverity::Elevated);
+    }
+
+    #[test]
+    fn test_baselines_are_bounded_and_idle_ones_evicted() {
+        let detector = AnomalyDetector::new(AnomalyConfig {
+            max_baselines: 2,
+            ..Default::default()
+        });
+        let org_id = OrganizationId::new();
+        let agents = [AgentId::new(), AgentId::new(), AgentId::new()];
+        let at = Utc::now();
+        for agent_id in agents {
+            detector.observe(&event_at(org_id, agent_id, at));
+        }
+        assert_eq!(detector.baseline_count(), 2);
+        assert!(detector.baseline(org_id, agents[2], "api_calls").is_none());
+
+        // Known keys keep being observed at the limit
+        detector.observe(&event_at(org_id, agents[0], at + Duration::hours(1)));
+        assert_eq!(
+            detector
+                .baseline(org_id, agents[0], "api_calls")
+                .unwrap()
+                .window_quantity,
+            1
+        );
+
+        assert_eq!(detector.evict_idle(at + Duration::hours(12)), 0);
+        assert_eq!(
+            detector.evict_idle(at + Duration::hours(24) + Duration::minutes(1)),
+            1
+        );
+        assert_eq!(detector.baseline_count(), 1);
+        detector.observe(&event_at(org_id, agents[2], at + Duration::hours(25)));
+        assert!(detector.baseline(org_id, agents[2], "api_calls").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_restore_at_start_and_flush_on_shutdown() {
+        let repository = Arc::new(InMemoryAnomalyBaselineRepository::new());
+        let (org_id, agent_id) = (OrganizationId::new(), AgentId::new());
+        let before = AnomalyDetector::new(AnomalyConfig::default());
+        drive(&before, org_id, agent_id, start(), 60, |_| 10);
+        before.checkpoint(&*repository).await.unwrap();
+
+        let shutdown = ShutdownCoordinator::new();
+        let detector = Arc::new(AnomalyDetector::new(AnomalyConfig::default()));
+        detector
+            .start_checkpoints(
+                repository.clone(),
+                &shutdown,
+                std::time::Duration::from_secs(3600),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            detector.baseline(org_id, agent_id, "api_calls"),
+            before.baseline(org_id, agent_id, "api_calls")
+        );
+
+        // A baseline first seen after startup is saved by the final checkpoint
+        let other = AgentId::new();
+        detector.observe(&event_at(org_id, other, Utc::now()));
+        assert!(shutdown
+            .shutdown(std::time::Duration::from_secs(1))
+            .await
+            .is_clean());
+        let saved = repository.load_baselines().await.unwrap();
+        assert_eq!(saved.len(), 2);
+        assert!(saved.iter().any(|b| b.agent_id == other));
+    }
+}
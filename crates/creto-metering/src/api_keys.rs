@@ -0,0 +1,466 @@
+//! Per-organization API keys for authenticating metering clients.
+//!
+//! Keys are only ever stored as SHA-256 hashes. The plaintext is returned
+//! once, when the key is issued. Rotation issues a new key and keeps the old
+//! one valid for a grace window so clients can roll over without downtime.
+//!
+//! Lookups go through a short-lived hash → organization cache, which is
+//! invalidated when a key is revoked.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, OrganizationId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::repository::ApiKeyRepository;
+
+/// Prefix of issued plaintext keys.
+pub const API_KEY_PREFIX: &str = "cmk_";
+
+/// Errors from API key authentication and management.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum ApiKeyError {
+    /// The key is unknown.
+    #[error("Invalid API key")]
+    Invalid,
+
+    /// The key was revoked.
+    #[error("API key revoked")]
+    Revoked,
+
+    /// The key's rotation grace window has passed.
+    #[error("API key expired")]
+    Expired,
+
+    /// No key exists with the given ID.
+    #[error("API key not found: {0}")]
+    NotFound(Uuid),
+
+    /// The backing store failed.
+    #[error("API key storage error: {0}")]
+    Storage(String),
+}
+
+impl From<CretoError> for ApiKeyError {
+    fn from(err: CretoError) -> Self {
+        ApiKeyError::Storage(err.to_string())
+    }
+}
+
+/// A stored API key (hash only).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Key identifier.
+    pub id: Uuid,
+
+    /// Organization the key authenticates as.
+    pub organization_id: OrganizationId,
+
+    /// Hex-encoded SHA-256 of the plaintext key.
+    pub key_hash: String,
+
+    /// When the key was issued.
+    pub created_at: DateTime<Utc>,
+
+    /// When the key stops being valid (set on rotation).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// When the key was revoked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Check whether the key may be used at `at`.
+    pub fn check_usable(&self, at: DateTime<Utc>) -> Result<(), ApiKeyError> {
+        if self.revoked_at.is_some() {
+            return Err(ApiKeyError::Revoked);
+        }
+        match self.expires_at {
+            Some(expires_at) if expires_at <= at => Err(ApiKeyError::Expired),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A newly issued key. The plaintext is not recoverable afterwards.
+#[derive(Debug, Clone)]
+pub struct IssuedApiKey {
+    /// Stored key metadata.
+    pub key: ApiKey,
+
+    /// Plaintext key to hand to the client.
+    pub plaintext: String,
+}
+
+/// Identity resolved from an API key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthenticatedKey {
+    /// Key that was presented.
+    pub key_id: Uuid,
+
+    /// Organization the key belongs to.
+    pub organization_id: OrganizationId,
+}
+
+/// Hash a plaintext key for storage and lookup.
+pub fn hash_api_key(plaintext: &str) -> String {
+    hex::encode(Sha256::digest(plaintext.as_bytes()))
+}
+
+fn generate_plaintext() -> String {
+    format!(
+        "{}{}{}",
+        API_KEY_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// In-memory API key store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryApiKeyRepository {
+    keys: RwLock<HashMap<Uuid, ApiKey>>,
+}
+
+impl InMemoryApiKeyRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyRepository for InMemoryApiKeyRepository {
+    async fn insert_key(&self, key: &ApiKey) -> Result<(), CretoError> {
+        self.keys.write().unwrap().insert(key.id, key.clone());
+        Ok(())
+    }
+
+    async fn get_key(&self, key_id: Uuid) -> Result<Option<ApiKey>, CretoError> {
+        Ok(self.keys.read().unwrap().get(&key_id).cloned())
+    }
+
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, CretoError> {
+        Ok(self
+            .keys
+            .read()
+            .unwrap()
+            .values()
+            .find(|k| k.key_hash == key_hash)
+            .cloned())
+    }
+
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<ApiKey>, CretoError> {
+        let mut keys: Vec<_> = self
+            .keys
+            .read()
+            .unwrap()
+            .values()
+            .filter(|k| k.organization_id == org_id)
+            .cloned()
+            .collect();
+        keys.sort_by_key(|k| k.created_at);
+        Ok(keys)
+    }
+
+    async fn set_expiry(&self, key_id: Uuid, expires_at: DateTime<Utc>) -> Result<(), CretoError> {
+        if let Some(key) = self.keys.write().unwrap().get_mut(&key_id) {
+            key.expires_at = Some(expires_at);
+        }
+        Ok(())
+    }
+
+    async fn revoke(&self, key_id: Uuid, revoked_at: DateTime<Utc>) -> Result<(), CretoError> {
+        if let Some(key) = self.keys.write().unwrap().get_mut(&key_id) {
+            key.revoked_at = Some(revoked_at);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedKey {
+    identity: AuthenticatedKey,
+    expires_at: Option<DateTime<Utc>>,
+    cached_at: Instant,
+}
+
+/// Short-lived cache of key hash → organization.
+#[derive(Debug)]
+pub struct ApiKeyCache {
+    ttl: Duration,
+    entries: RwLock<HashMap<String, CachedKey>>,
+}
+
+impl ApiKeyCache {
+    /// Create a cache with the given entry TTL.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, key_hash: &str) -> Option<CachedKey> {
+        self.entries
+            .read()
+            .unwrap()
+            .get(key_hash)
+            .filter(|entry| entry.cached_at.elapsed() < self.ttl)
+            .cloned()
+    }
+
+    fn insert(&self, key: &ApiKey) {
+        self.entries.write().unwrap().insert(
+            key.key_hash.clone(),
+            CachedKey {
+                identity: AuthenticatedKey {
+                    key_id: key.id,
+                    organization_id: key.organization_id,
+                },
+                expires_at: key.expires_at,
+                cached_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Drop any cached entry for a key.
+    pub fn invalidate(&self, key_id: Uuid) {
+        self.entries
+            .write()
+            .unwrap()
+            .retain(|_, entry| entry.identity.key_id != key_id);
+    }
+
+    /// Number of cached entries (including stale ones).
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap().len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Issues, rotates, revokes and authenticates API keys.
+pub struct ApiKeyManager<R: ?Sized> {
+    repository: Arc<R>,
+    cache: ApiKeyCache,
+}
+
+impl<R: ApiKeyRepository + ?Sized> ApiKeyManager<R> {
+    /// Default TTL for cached key lookups.
+    pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(30);
+
+    /// Create a manager over a key repository.
+    pub fn new(repository: Arc<R>) -> Self {
+        Self::with_cache_ttl(repository, Self::DEFAULT_CACHE_TTL)
+    }
+
+    /// Create a manager with a custom cache TTL.
+    pub fn with_cache_ttl(repository: Arc<R>, ttl: Duration) -> Self {
+        Self {
+            repository,
+            cache: ApiKeyCache::new(ttl),
+        }
+    }
+
+    /// Get the lookup cache.
+    pub fn cache(&self) -> &ApiKeyCache {
+        &self.cache
+    }
+
+    /// Issue a new key for an organization.
+    pub async fn create_key(&self, org_id: OrganizationId) -> Result<IssuedApiKey, ApiKeyError> {
+        let plaintext = generate_plaintext();
+        let key = ApiKey {
+            id: Uuid::now_v7(),
+            organization_id: org_id,
+            key_hash: hash_api_key(&plaintext),
+            created_at: Utc::now(),
+            expires_at: None,
+            revoked_at: None,
+        };
+        self.repository.insert_key(&key).await?;
+        Ok(IssuedApiKey { key, plaintext })
+    }
+
+    /// Replace a key with a new one for the same organization.
+    ///
+    /// The old key keeps working until `grace` has elapsed.
+    pub async fn rotate_key(
+        &self,
+        key_id: Uuid,
+        grace: chrono::Duration,
+    ) -> Result<IssuedApiKey, ApiKeyError> {
+        let old = self
+            .repository
+            .get_key(key_id)
+            .await?
+            .ok_or(ApiKeyError::NotFound(key_id))?;
+        old.check_usable(Utc::now())?;
+
+        let issued = self.create_key(old.organization_id).await?;
+        self.repository
+            .set_expiry(key_id, Utc::now() + grace)
+            .await?;
+        self.cache.invalidate(key_id);
+
+        tracing::info!(
+            org_id = %old.organization_id,
+            old_key = %key_id,
+            new_key = %issued.key.id,
+            "Rotated API key"
+        );
+
+        Ok(issued)
+    }
+
+    /// Revoke a key immediately.
+    pub async fn revoke_key(&self, key_id: Uuid) -> Result<(), ApiKeyError> {
+        if self.repository.get_key(key_id).await?.is_none() {
+            return Err(ApiKeyError::NotFound(key_id));
+        }
+        self.repository.revoke(key_id, Utc::now()).await?;
+        self.cache.invalidate(key_id);
+        Ok(())
+    }
+
+    /// List an organization's keys.
+    pub async fn list_keys(&self, org_id: OrganizationId) -> Result<Vec<ApiKey>, ApiKeyError> {
+        Ok(self.repository.list_by_org(org_id).await?)
+    }
+
+    /// Resolve a plaintext key to the organization it belongs to.
+    pub async fn authenticate(&self, plaintext: &str) -> Result<AuthenticatedKey, ApiKeyError> {
+        let key_hash = hash_api_key(plaintext);
+
+        if let Some(cached) = self.cache.get(&key_hash) {
+            if cached.expires_at.is_some_and(|at| at <= Utc::now()) {
+                return Err(ApiKeyError::Expired);
+            }
+            return Ok(cached.identity);
+        }
+
+        let key = self
+            .repository
+            .find_by_hash(&key_hash)
+            .await?
+            .ok_or(ApiKeyError::Invalid)?;
+        key.check_usable(Utc::now())?;
+        self.cache.insert(&key);
+
+        Ok(AuthenticatedKey {
+            key_id: key.id,
+            organization_id: key.organization_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> ApiKeyManager<InMemoryApiKeyRepository> {
+        ApiKeyManager::new(Arc::new(InMemoryApiKeyRepository::new()))
+    }
+
+    #[tokio::test]
+    async fn test_create_and_authenticate() {
+        let manager = manager();
+        let org_id = OrganizationId::new();
+
+        let issued = manager.create_key(org_id).await.unwrap();
+        assert!(issued.plaintext.starts_with(API_KEY_PREFIX));
+        assert_eq!(issued.key.key_hash, hash_api_key(&issued.plaintext));
+        assert_ne!(issued.key.key_hash, issued.plaintext);
+
+        let identity = manager.authenticate(&issued.plaintext).await.unwrap();
+        assert_eq!(identity.organization_id, org_id);
+        assert_eq!(identity.key_id, issued.key.id);
+
+        assert_eq!(
+            manager.authenticate("cmk_bogus").await,
+            Err(ApiKeyError::Invalid)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rotate_keeps_old_key_for_grace_window() {
+        let manager = manager();
+        let org_id = OrganizationId::new();
+        let old = manager.create_key(org_id).await.unwrap();
+
+        let new = manager
+            .rotate_key(old.key.id, chrono::Duration::minutes(10))
+            .await
+            .unwrap();
+        assert_eq!(new.key.organization_id, org_id);
+
+        // Both keys work during the grace window
+        assert!(manager.authenticate(&old.plaintext).await.is_ok());
+        assert!(manager.authenticate(&new.plaintext).await.is_ok());
+
+        // Once the window closes, only the new key works
+        let expired = manager.create_key(org_id).await.unwrap();
+        manager
+            .rotate_key(expired.key.id, chrono::Duration::zero())
+            .await
+            .unwrap();
+        assert_eq!(
+            manager.authenticate(&expired.plaintext).await,
+            Err(ApiKeyError::Expired)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoke_invalidates_cache() {
+        let manager = manager();
+        let issued = manager.create_key(OrganizationId::new()).await.unwrap();
+
+        manager.authenticate(&issued.plaintext).await.unwrap();
+        assert_eq!(manager.cache().len(), 1);
+
+        manager.revoke_key(issued.key.id).await.unwrap();
+        assert!(manager.cache().is_empty());
+        assert_eq!(
+            manager.authenticate(&issued.plaintext).await,
+            Err(ApiKeyError::Revoked)
+        );
+
+        assert_eq!(
+            manager.revoke_key(Uuid::nil()).await,
+            Err(ApiKeyError::NotFound(Uuid::nil()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_ttl() {
+        let repository = Arc::new(InMemoryApiKeyRepository::new());
+        let cached = ApiKeyManager::new(repository.clone());
+        let uncached = ApiKeyManager::with_cache_ttl(repository.clone(), Duration::ZERO);
+
+        let issued = cached.create_key(OrganizationId::new()).await.unwrap();
+        cached.authenticate(&issued.plaintext).await.unwrap();
+        uncached.authenticate(&issued.plaintext).await.unwrap();
+
+        // Revoke behind both managers' backs
+        repository.revoke(issued.key.id, Utc::now()).await.unwrap();
+
+        // A warm entry is served until its TTL lapses; a stale one is not
+        assert!(cached.authenticate(&issued.plaintext).await.is_ok());
+        assert_eq!(
+            uncached.authenticate(&issued.plaintext).await,
+            Err(ApiKeyError::Revoked)
+        );
+    }
+}
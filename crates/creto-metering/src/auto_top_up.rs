@@ -0,0 +1,1073 @@
+//! Automatic wallet top-ups.
+//!
+//! An [`AutoTopUpRule`] refills a prepaid wallet from a funding source when
+//! its balance drops below a threshold. [`AutoTopUp`] checks a wallet's rule
+//! after each debit (registered on the [`CreditManager`] as a
+//! [`DebitHook`]) and on a periodic sweep. A check that finds the balance
+//! low charges the funding source through the [`PaymentProvider`] and
+//! credits the wallet with a [`CreditTransactionType::AutoTopUp`]
+//! transaction referencing the payment.
+//!
+//! Every top-up is claimed under an idempotency key of wallet, period and
+//! sequence number before the charge, so concurrent checks crossing the
+//! threshold together produce one top-up, and the key is passed on to the
+//! provider. Failed charges back off exponentially and alert the
+//! [`TopUpNotifier`]s; the debit that triggered the check is never affected.
+//!
+//! A successful charge is recorded before the wallet is credited. An attempt
+//! whose credit failed is credited by the next check without charging
+//! again, and one left pending longer than
+//! [`AutoTopUpConfig::claim_timeout`], e.g. by a crashed node, is charged
+//! again under its own idempotency key.
+//!
+//! [`CreditTransactionType::AutoTopUp`]: crate::credits::CreditTransactionType::AutoTopUp
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{
+    Clock, CretoError, CretoResult, OrganizationId, ShutdownCoordinator, SystemClock,
+};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::credits::{CreditManager, DebitHook, Wallet};
+use crate::repository::AutoTopUpRepository;
+
+/// Window the per-period top-up cap applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TopUpPeriod {
+    /// Calendar day (UTC).
+    Daily,
+    /// Calendar month (UTC).
+    #[default]
+    Monthly,
+}
+
+impl TopUpPeriod {
+    /// Key of the period containing `at`, e.g. `2026-10` or `2026-10-16`.
+    pub fn key(&self, at: DateTime<Utc>) -> String {
+        match self {
+            TopUpPeriod::Daily => at.format("%Y-%m-%d").to_string(),
+            TopUpPeriod::Monthly => at.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+/// Per-wallet auto top-up configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoTopUpRule {
+    /// Wallet the rule refills.
+    pub wallet_id: Uuid,
+
+    /// Organization owning the wallet.
+    pub organization_id: OrganizationId,
+
+    /// Balance below which a top-up is made, in cents.
+    pub threshold_cents: i64,
+
+    /// Amount bought per top-up, in cents.
+    pub top_up_amount_cents: i64,
+
+    /// Maximum successful top-ups per period.
+    pub max_top_ups_per_period: u32,
+
+    /// Period the cap applies to.
+    pub period: TopUpPeriod,
+
+    /// Payment provider reference of the funding source to charge.
+    pub funding_source: String,
+
+    /// Disabled rules never top up.
+    pub enabled: bool,
+
+    /// When the rule was created.
+    pub created_at: DateTime<Utc>,
+
+    /// When the rule was last changed.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AutoTopUpRule {
+    /// Create an enabled rule allowing three top-ups a month.
+    pub fn new(
+        wallet: &Wallet,
+        threshold_cents: i64,
+        top_up_amount_cents: i64,
+        funding_source: impl Into<String>,
+    ) -> Self {
+        let now = Utc::now();
+        Self {
+            wallet_id: wallet.id,
+            organization_id: wallet.organization_id,
+            threshold_cents,
+            top_up_amount_cents,
+            max_top_ups_per_period: 3,
+            period: TopUpPeriod::Monthly,
+            funding_source: funding_source.into(),
+            enabled: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Set the per-period cap.
+    pub fn with_max_per_period(mut self, max_top_ups: u32, period: TopUpPeriod) -> Self {
+        self.max_top_ups_per_period = max_top_ups;
+        self.period = period;
+        self
+    }
+
+    /// Check the rule's amounts and funding source.
+    pub fn validate(&self) -> CretoResult<()> {
+        if self.threshold_cents < 0 {
+            return Err(CretoError::ValidationFailed(
+                "Top-up threshold cannot be negative".to_string(),
+            ));
+        }
+        if self.top_up_amount_cents <= 0 {
+            return Err(CretoError::ValidationFailed(
+                "Top-up amount must be positive".to_string(),
+            ));
+        }
+        if self.max_top_ups_per_period == 0 {
+            return Err(CretoError::ValidationFailed(
+                "At least one top-up per period must be allowed".to_string(),
+            ));
+        }
+        if self.funding_source.trim().is_empty() {
+            return Err(CretoError::ValidationFailed(
+                "Funding source is required".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// State of a top-up attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TopUpStatus {
+    /// Claimed; the charge is in flight.
+    Pending,
+    /// Charged; the wallet is still to be credited.
+    Charged,
+    /// Charged and credited.
+    Succeeded,
+    /// The charge failed.
+    Failed,
+}
+
+/// One attempt to top up a wallet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopUpAttempt {
+    /// Unique attempt ID.
+    pub id: Uuid,
+    /// Wallet topped up.
+    pub wallet_id: Uuid,
+    /// Organization owning the wallet.
+    pub organization_id: OrganizationId,
+    /// `wallet:period:sequence`, unique across attempts and passed to the
+    /// payment provider.
+    pub idempotency_key: String,
+    /// Period the attempt counts against.
+    pub period_key: String,
+    /// Position among the wallet's attempts in the period, starting at 1.
+    pub sequence: u32,
+    /// Amount charged, in cents.
+    pub amount_cents: i64,
+    /// Current state.
+    pub status: TopUpStatus,
+    /// Provider payment ID, once charged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<String>,
+    /// Credit transaction recorded, once credited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<Uuid>,
+    /// Why the attempt failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// When the attempt was claimed.
+    pub created_at: DateTime<Utc>,
+    /// When a check last took the attempt over.
+    pub claimed_at: DateTime<Utc>,
+    /// When the attempt finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl TopUpAttempt {
+    /// Whether the attempt still has to be charged or credited.
+    pub fn is_unfinished(&self) -> bool {
+        matches!(self.status, TopUpStatus::Pending | TopUpStatus::Charged)
+    }
+
+    fn new(rule: &AutoTopUpRule, period_key: String, sequence: u32, now: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            wallet_id: rule.wallet_id,
+            organization_id: rule.organization_id,
+            idempotency_key: format!("{}:{}:{}", rule.wallet_id, period_key, sequence),
+            period_key,
+            sequence,
+            amount_cents: rule.top_up_amount_cents,
+            status: TopUpStatus::Pending,
+            payment_id: None,
+            transaction_id: None,
+            error: None,
+            created_at: now,
+            claimed_at: now,
+            completed_at: None,
+        }
+    }
+}
+
+/// Charge made against a funding source.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    /// Repeating a request with the same key must not charge twice.
+    pub idempotency_key: String,
+    /// Organization being charged.
+    pub organization_id: OrganizationId,
+    /// Provider reference of the funding source.
+    pub funding_source: String,
+    /// Amount in cents.
+    pub amount_cents: i64,
+    /// Currency code.
+    pub currency: String,
+}
+
+/// Successful charge.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentReceipt {
+    /// Provider payment ID.
+    pub payment_id: String,
+}
+
+/// Payment processor charging funding sources.
+#[trait_variant::make(PaymentProvider: Send)]
+pub trait LocalPaymentProvider {
+    /// Charge a funding source.
+    async fn charge(&self, request: &PaymentRequest) -> CretoResult<PaymentReceipt>;
+}
+
+/// Alert raised when a top-up charge fails.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopUpAlert {
+    /// Wallet that could not be topped up.
+    pub wallet_id: Uuid,
+    /// Organization owning the wallet.
+    pub organization_id: OrganizationId,
+    /// The failed attempt.
+    pub attempt_id: Uuid,
+    /// Provider error.
+    pub error: String,
+    /// Failed attempts in a row in the current period.
+    pub consecutive_failures: u32,
+    /// Earliest time of the next attempt.
+    pub retry_after: DateTime<Utc>,
+}
+
+/// Destination for top-up failure alerts.
+pub trait TopUpNotifier: Send + Sync {
+    /// Deliver an alert. Must not block.
+    fn notify(&self, alert: &TopUpAlert);
+}
+
+/// In-memory notifier for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryTopUpNotifier {
+    alerts: RwLock<Vec<TopUpAlert>>,
+}
+
+impl InMemoryTopUpNotifier {
+    /// Create an empty notifier.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Alerts received, oldest first.
+    pub fn alerts(&self) -> Vec<TopUpAlert> {
+        self.alerts.read().unwrap().clone()
+    }
+}
+
+impl TopUpNotifier for InMemoryTopUpNotifier {
+    fn notify(&self, alert: &TopUpAlert) {
+        self.alerts.write().unwrap().push(alert.clone());
+    }
+}
+
+/// Backoff after failed charges.
+#[derive(Debug, Clone)]
+pub struct AutoTopUpConfig {
+    /// Wait after the first failure.
+    pub initial_backoff: Duration,
+    /// Longest wait between attempts.
+    pub max_backoff: Duration,
+    /// How long a pending attempt is left to its check before another
+    /// check takes it over.
+    pub claim_timeout: Duration,
+}
+
+impl Default for AutoTopUpConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::minutes(5),
+            max_backoff: Duration::hours(6),
+            claim_timeout: Duration::minutes(15),
+        }
+    }
+}
+
+impl AutoTopUpConfig {
+    /// Wait after `failures` consecutive failures, doubling each time.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        if failures == 0 {
+            return Duration::zero();
+        }
+        let factor = 1i32 << failures.saturating_sub(1).min(16);
+        (self.initial_backoff * factor).min(self.max_backoff)
+    }
+}
+
+/// Result of checking a wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TopUpOutcome {
+    /// No enabled rule, or the balance is at or above the threshold.
+    NotNeeded,
+    /// The wallet was charged and credited.
+    ToppedUp(TopUpAttempt),
+    /// The charge failed.
+    Failed(TopUpAttempt),
+    /// Another check holds the wallet's top-up.
+    InProgress,
+    /// The period's cap of successful top-ups is used up.
+    CapReached,
+    /// A recent failure is backing off.
+    BackingOff {
+        /// Earliest time of the next attempt.
+        until: DateTime<Utc>,
+    },
+}
+
+/// In-memory rule and attempt store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryAutoTopUpRepository {
+    rules: RwLock<HashMap<Uuid, AutoTopUpRule>>,
+    attempts: RwLock<Vec<TopUpAttempt>>,
+}
+
+impl InMemoryAutoTopUpRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AutoTopUpRepository for InMemoryAutoTopUpRepository {
+    async fn save_rule(&self, rule: &AutoTopUpRule) -> Result<(), CretoError> {
+        self.rules
+            .write()
+            .unwrap()
+            .insert(rule.wallet_id, rule.clone());
+        Ok(())
+    }
+
+    async fn get_rule(&self, wallet_id: Uuid) -> Result<Option<AutoTopUpRule>, CretoError> {
+        Ok(self.rules.read().unwrap().get(&wallet_id).cloned())
+    }
+
+    async fn list_rules(&self) -> Result<Vec<AutoTopUpRule>, CretoError> {
+        let mut rules: Vec<_> = self.rules.read().unwrap().values().cloned().collect();
+        rules.sort_by_key(|r| r.created_at);
+        Ok(rules)
+    }
+
+    async fn delete_rule(&self, wallet_id: Uuid) -> Result<bool, CretoError> {
+        Ok(self.rules.write().unwrap().remove(&wallet_id).is_some())
+    }
+
+    async fn claim_attempt(&self, attempt: &TopUpAttempt) -> Result<bool, CretoError> {
+        let mut attempts = self.attempts.write().unwrap();
+        if attempts
+            .iter()
+            .any(|a| a.idempotency_key == attempt.idempotency_key)
+        {
+            return Ok(false);
+        }
+        attempts.push(attempt.clone());
+        Ok(true)
+    }
+
+    async fn reclaim_attempt(
+        &self,
+        attempt: &TopUpAttempt,
+        claimed_at: DateTime<Utc>,
+    ) -> Result<bool, CretoError> {
+        let mut attempts = self.attempts.write().unwrap();
+        match attempts.iter_mut().find(|a| a.id == attempt.id) {
+            Some(stored)
+                if stored.claimed_at == attempt.claimed_at
+                    && matches!(stored.status, TopUpStatus::Pending | TopUpStatus::Charged) =>
+            {
+                stored.claimed_at = claimed_at;
+                stored.error = None;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn complete_attempt(&self, attempt: &TopUpAttempt) -> Result<(), CretoError> {
+        if let Some(stored) = self
+            .attempts
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|a| a.id == attempt.id)
+        {
+            *stored = attempt.clone();
+        }
+        Ok(())
+    }
+
+    async fn list_attempts(
+        &self,
+        wallet_id: Uuid,
+        period_key: &str,
+    ) -> Result<Vec<TopUpAttempt>, CretoError> {
+        let mut attempts: Vec<_> = self
+            .attempts
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|a| a.wallet_id == wallet_id && a.period_key == period_key)
+            .cloned()
+            .collect();
+        attempts.sort_by_key(|a| a.sequence);
+        Ok(attempts)
+    }
+}
+
+/// Checks wallets against their rules and buys credits when they run low.
+///
+/// Clones share the same repository, provider and thresholds.
+pub struct AutoTopUp<R, P> {
+    repository: Arc<R>,
+    payments: Arc<P>,
+    credits: CreditManager,
+    notifiers: Vec<Arc<dyn TopUpNotifier>>,
+    config: AutoTopUpConfig,
+    clock: Arc<dyn Clock>,
+    /// Thresholds of known rules, so debits well above them skip the check.
+    thresholds: Arc<RwLock<HashMap<Uuid, i64>>>,
+}
+
+impl<R, P> Clone for AutoTopUp<R, P> {
+    fn clone(&self) -> Self {
+        Self {
+            repository: Arc::clone(&self.repository),
+            payments: Arc::clone(&self.payments),
+            credits: self.credits.clone(),
+            notifiers: self.notifiers.clone(),
+            config: self.config.clone(),
+            clock: Arc::clone(&self.clock),
+            thresholds: Arc::clone(&self.thresholds),
+        }
+    }
+}
+
+impl<R, P> AutoTopUp<R, P>
+where
+    R: AutoTopUpRepository + Sync + 'static,
+    P: PaymentProvider + Sync + 'static,
+{
+    /// Top up wallets of `credits`, charging through `payments`.
+    pub fn new(repository: Arc<R>, payments: Arc<P>, credits: CreditManager) -> Self {
+        Self {
+            repository,
+            payments,
+            credits,
+            notifiers: Vec::new(),
+            config: AutoTopUpConfig::default(),
+            clock: Arc::new(SystemClock),
+            thresholds: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Deliver failure alerts to `notifier` as well.
+    pub fn with_notifier(mut self, notifier: Arc<dyn TopUpNotifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Set the failure backoff.
+    pub fn with_config(mut self, config: AutoTopUpConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the time source for periods and backoff.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Create or replace a wallet's rule.
+    pub async fn set_rule(&self, rule: AutoTopUpRule) -> CretoResult<()> {
+        rule.validate()?;
+        self.repository.save_rule(&rule).await?;
+        self.remember(&rule);
+        Ok(())
+    }
+
+    /// Get a wallet's rule.
+    pub async fn rule(&self, wallet_id: Uuid) -> CretoResult<Option<AutoTopUpRule>> {
+        self.repository.get_rule(wallet_id).await
+    }
+
+    /// Remove a wallet's rule.
+    pub async fn remove_rule(&self, wallet_id: Uuid) -> CretoResult<bool> {
+        self.thresholds.write().unwrap().remove(&wallet_id);
+        self.repository.delete_rule(wallet_id).await
+    }
+
+    /// A wallet's attempts in the period containing `at`, in order.
+    pub async fn attempts(
+        &self,
+        wallet_id: Uuid,
+        at: DateTime<Utc>,
+    ) -> CretoResult<Vec<TopUpAttempt>> {
+        let Some(rule) = self.repository.get_rule(wallet_id).await? else {
+            return Ok(Vec::new());
+        };
+        self.repository
+            .list_attempts(wallet_id, &rule.period.key(at))
+            .await
+    }
+
+    /// Top up an organization's wallet if its rule calls for it.
+    pub async fn check(&self, organization_id: OrganizationId) -> CretoResult<TopUpOutcome> {
+        let Some(wallet) = self.credits.get_wallet(&organization_id) else {
+            return Ok(TopUpOutcome::NotNeeded);
+        };
+        let Some(rule) = self.repository.get_rule(wallet.id).await? else {
+            return Ok(TopUpOutcome::NotNeeded);
+        };
+        self.remember(&rule);
+        if !rule.enabled || !wallet.active {
+            return Ok(TopUpOutcome::NotNeeded);
+        }
+
+        let now = self.clock.now();
+        let period_key = rule.period.key(now);
+        let attempts = self
+            .repository
+            .list_attempts(wallet.id, &period_key)
+            .await?;
+        let failures = attempts
+            .iter()
+            .rev()
+            .filter(|a| !a.is_unfinished())
+            .take_while(|a| a.status == TopUpStatus::Failed)
+            .count() as u32;
+
+        // Finished before the balance is looked at: a charged attempt owes
+        // the wallet its credit whatever the balance
+        if let Some(unfinished) = attempts.iter().find(|a| a.is_unfinished()) {
+            let released = unfinished.status == TopUpStatus::Charged && unfinished.error.is_some();
+            if !released && now - unfinished.claimed_at < self.config.claim_timeout {
+                return Ok(TopUpOutcome::InProgress);
+            }
+            if !self.repository.reclaim_attempt(unfinished, now).await? {
+                return Ok(TopUpOutcome::InProgress);
+            }
+            info!(
+                wallet_id = %wallet.id,
+                key = %unfinished.idempotency_key,
+                status = ?unfinished.status,
+                "Resuming unfinished top-up"
+            );
+            let attempt = TopUpAttempt {
+                claimed_at: now,
+                error: None,
+                ..unfinished.clone()
+            };
+            return self.charge(&rule, &wallet, attempt, failures).await;
+        }
+
+        // Read the balance after the attempts, so a top-up completed in
+        // between is seen in one or the other
+        if self.credits.get_balance(&organization_id) >= rule.threshold_cents {
+            return Ok(TopUpOutcome::NotNeeded);
+        }
+        let succeeded = attempts
+            .iter()
+            .filter(|a| a.status == TopUpStatus::Succeeded)
+            .count() as u32;
+        if succeeded >= rule.max_top_ups_per_period {
+            return Ok(TopUpOutcome::CapReached);
+        }
+        if let Some(last) = attempts.last().filter(|_| failures > 0) {
+            let until =
+                last.completed_at.unwrap_or(last.created_at) + self.config.backoff(failures);
+            if now < until {
+                return Ok(TopUpOutcome::BackingOff { until });
+            }
+        }
+
+        let attempt = TopUpAttempt::new(&rule, period_key, attempts.len() as u32 + 1, now);
+        if !self.repository.claim_attempt(&attempt).await? {
+            debug!(wallet_id = %wallet.id, key = %attempt.idempotency_key, "Top-up already claimed");
+            return Ok(TopUpOutcome::InProgress);
+        }
+        self.charge(&rule, &wallet, attempt, failures).await
+    }
+
+    /// Charge and credit a claimed attempt.
+    ///
+    /// An attempt already charged is only credited.
+    async fn charge(
+        &self,
+        rule: &AutoTopUpRule,
+        wallet: &Wallet,
+        mut attempt: TopUpAttempt,
+        failures: u32,
+    ) -> CretoResult<TopUpOutcome> {
+        let payment_id = match attempt.payment_id.clone() {
+            Some(payment_id) => payment_id,
+            None => {
+                let request = PaymentRequest {
+                    idempotency_key: attempt.idempotency_key.clone(),
+                    organization_id: rule.organization_id,
+                    funding_source: rule.funding_source.clone(),
+                    amount_cents: attempt.amount_cents,
+                    currency: wallet.currency.clone(),
+                };
+                match self.payments.charge(&request).await {
+                    Ok(receipt) => {
+                        attempt.status = TopUpStatus::Charged;
+                        attempt.payment_id = Some(receipt.payment_id.clone());
+                        self.repository.complete_attempt(&attempt).await?;
+                        receipt.payment_id
+                    }
+                    Err(e) => {
+                        attempt.status = TopUpStatus::Failed;
+                        attempt.completed_at = Some(self.clock.now());
+                        attempt.error = Some(e.to_string());
+                        self.repository.complete_attempt(&attempt).await?;
+                        let retry_after = self.clock.now() + self.config.backoff(failures + 1);
+                        self.alert(&attempt, &e, failures + 1, retry_after);
+                        return Ok(TopUpOutcome::Failed(attempt));
+                    }
+                }
+            }
+        };
+
+        let now = self.clock.now();
+        match self
+            .credits
+            .credit_top_up(&rule.organization_id, attempt.amount_cents, &payment_id)
+        {
+            Ok(transaction) => {
+                attempt.status = TopUpStatus::Succeeded;
+                attempt.transaction_id = Some(transaction.id);
+                attempt.completed_at = Some(now);
+                self.repository.complete_attempt(&attempt).await?;
+                info!(
+                    wallet_id = %attempt.wallet_id,
+                    amount_cents = attempt.amount_cents,
+                    balance_after = transaction.balance_after,
+                    key = %attempt.idempotency_key,
+                    "Wallet topped up"
+                );
+                Ok(TopUpOutcome::ToppedUp(attempt))
+            }
+            Err(e) => {
+                // Left charged, so the next check credits it without
+                // charging again
+                attempt.error = Some(e.to_string());
+                self.repository.complete_attempt(&attempt).await?;
+                self.alert(&attempt, &e, failures + 1, now);
+                Ok(TopUpOutcome::Failed(attempt))
+            }
+        }
+    }
+
+    /// Log a failed attempt and alert the notifiers.
+    fn alert(
+        &self,
+        attempt: &TopUpAttempt,
+        error: &CretoError,
+        consecutive_failures: u32,
+        retry_after: DateTime<Utc>,
+    ) {
+        let alert = TopUpAlert {
+            wallet_id: attempt.wallet_id,
+            organization_id: attempt.organization_id,
+            attempt_id: attempt.id,
+            error: error.to_string(),
+            consecutive_failures,
+            retry_after,
+        };
+        warn!(
+            wallet_id = %attempt.wallet_id,
+            status = ?attempt.status,
+            error = %error,
+            retry_after = %alert.retry_after,
+            "Wallet top-up failed"
+        );
+        for notifier in &self.notifiers {
+            notifier.notify(&alert);
+        }
+    }
+
+    /// Check every wallet with a rule, returning the attempts made.
+    ///
+    /// Catches wallets whose debit-triggered check failed or never ran.
+    pub async fn sweep(&self) -> CretoResult<Vec<TopUpAttempt>> {
+        let mut made = Vec::new();
+        for rule in self.repository.list_rules().await? {
+            match self.check(rule.organization_id).await {
+                Ok(TopUpOutcome::ToppedUp(attempt)) | Ok(TopUpOutcome::Failed(attempt)) => {
+                    made.push(attempt)
+                }
+                Ok(_) => {}
+                Err(e) => warn!(wallet_id = %rule.wallet_id, error = %e, "Top-up check failed"),
+            }
+        }
+        Ok(made)
+    }
+
+    /// Spawn a background task sweeping every `interval`.
+    ///
+    /// The task is registered with `shutdown` and stops between sweeps.
+    pub fn spawn_sweeper(
+        &self,
+        shutdown: &ShutdownCoordinator,
+        interval: std::time::Duration,
+    ) -> JoinHandle<()> {
+        let top_up = self.clone();
+        shutdown.spawn("metering.auto_top_up", move |guard| {
+            guard.run_periodic(interval, move || {
+                let top_up = top_up.clone();
+                async move {
+                    if let Err(e) = top_up.sweep().await {
+                        warn!(error = %e, "Auto top-up sweep failed");
+                    }
+                }
+            })
+        })
+    }
+
+    fn remember(&self, rule: &AutoTopUpRule) {
+        let mut thresholds = self.thresholds.write().unwrap();
+        if rule.enabled {
+            thresholds.insert(rule.wallet_id, rule.threshold_cents);
+        } else {
+            thresholds.remove(&rule.wallet_id);
+        }
+    }
+}
+
+impl<R, P> DebitHook for AutoTopUp<R, P>
+where
+    R: AutoTopUpRepository + Sync + 'static,
+    P: PaymentProvider + Sync + 'static,
+{
+    /// Check the wallet in the background; the sweep covers debits made
+    /// outside a Tokio runtime.
+    fn after_debit(&self, wallet: &Wallet) {
+        let known = self.thresholds.read().unwrap().get(&wallet.id).copied();
+        if known.is_some_and(|threshold| wallet.balance_cents >= threshold) {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            debug!(wallet_id = %wallet.id, "No runtime for top-up check; leaving it to the sweep");
+            return;
+        };
+
+        let top_up = self.clone();
+        let organization_id = wallet.organization_id;
+        runtime.spawn(async move {
+            if let Err(e) = top_up.check(organization_id).await {
+                warn!(organization_id = %organization_id, error = %e, "Top-up check failed");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credits::CreditTransactionType;
+    use chrono::TimeZone;
+    use creto_common::TestClock;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Provider replaying canned outcomes, succeeding once they run out.
+    #[derive(Default)]
+    struct ScriptedPaymentProvider {
+        failures: Mutex<VecDeque<String>>,
+        charges: Mutex<Vec<PaymentRequest>>,
+    }
+
+    impl ScriptedPaymentProvider {
+        fn fail_next(&self, error: &str) {
+            self.failures.lock().unwrap().push_back(error.to_string());
+        }
+
+        fn charges(&self) -> Vec<PaymentRequest> {
+            self.charges.lock().unwrap().clone()
+        }
+    }
+
+    impl PaymentProvider for ScriptedPaymentProvider {
+        async fn charge(&self, request: &PaymentRequest) -> CretoResult<PaymentReceipt> {
+            // Let concurrent checks interleave with the charge
+            tokio::task::yield_now().await;
+            self.charges.lock().unwrap().push(request.clone());
+            match self.failures.lock().unwrap().pop_front() {
+                Some(error) => Err(CretoError::Internal(error)),
+                None => Ok(PaymentReceipt {
+                    payment_id: format!("pay_{}", self.charges.lock().unwrap().len()),
+                }),
+            }
+        }
+    }
+
+    type TestTopUp = AutoTopUp<InMemoryAutoTopUpRepository, ScriptedPaymentProvider>;
+
+    fn setup(
+        balance: i64,
+    ) -> (
+        TestTopUp,
+        Arc<ScriptedPaymentProvider>,
+        OrganizationId,
+        Wallet,
+    ) {
+        let credits = CreditManager::new();
+        let org = OrganizationId::new();
+        credits.grant_credits(org, balance, None).unwrap();
+        let wallet = credits.get_wallet(&org).unwrap();
+        let payments = Arc::new(ScriptedPaymentProvider::default());
+        let top_up = AutoTopUp::new(
+            Arc::new(InMemoryAutoTopUpRepository::new()),
+            payments.clone(),
+            credits,
+        );
+        (top_up, payments, org, wallet)
+    }
+
+    #[tokio::test]
+    async fn test_debit_crossing_threshold_tops_up() {
+        let (top_up, payments, org, wallet) = setup(1_000);
+        top_up
+            .set_rule(AutoTopUpRule::new(&wallet, 500, 2_000, "card_123"))
+            .await
+            .unwrap();
+        let credits = top_up
+            .credits
+            .clone()
+            .with_debit_hook(Arc::new(top_up.clone()));
+
+        // Above the threshold: nothing happens
+        credits.consume_credits(&org, 300, None).unwrap();
+        tokio::task::yield_now().await;
+        assert_eq!(top_up.check(org).await.unwrap(), TopUpOutcome::NotNeeded);
+
+        credits.consume_credits(&org, 300, None).unwrap();
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(credits.get_balance(&org), 2_400);
+        let charges = payments.charges();
+        assert_eq!(charges.len(), 1);
+        assert_eq!(charges[0].funding_source, "card_123");
+        assert_eq!(charges[0].amount_cents, 2_000);
+
+        let history = credits.get_transactions(&org, None);
+        let top_ups: Vec<_> = history
+            .iter()
+            .filter(|t| t.transaction_type == CreditTransactionType::AutoTopUp)
+            .collect();
+        assert_eq!(top_ups.len(), 1);
+        assert_eq!(top_ups[0].reference_id.as_deref(), Some("pay_1"));
+    }
+
+    #[tokio::test]
+    async fn test_top_ups_capped_per_period() {
+        let (top_up, payments, org, wallet) = setup(100);
+        let clock = Arc::new(TestClock::new(
+            Utc.with_ymd_and_hms(2026, 10, 30, 12, 0, 0).unwrap(),
+        ));
+        let top_up = top_up.with_clock(clock.clone());
+        top_up
+            .set_rule(
+                AutoTopUpRule::new(&wallet, 500, 1_000, "card_123")
+                    .with_max_per_period(2, TopUpPeriod::Monthly),
+            )
+            .await
+            .unwrap();
+
+        for _ in 0..2 {
+            assert!(matches!(
+                top_up.check(org).await.unwrap(),
+                TopUpOutcome::ToppedUp(_)
+            ));
+            top_up.credits.consume_credits(&org, 1_000, None).unwrap();
+        }
+        assert_eq!(top_up.check(org).await.unwrap(), TopUpOutcome::CapReached);
+        assert_eq!(payments.charges().len(), 2);
+
+        // A new month starts a new allowance
+        clock.advance(Duration::days(2));
+        match top_up.check(org).await.unwrap() {
+            TopUpOutcome::ToppedUp(attempt) => {
+                assert_eq!(attempt.period_key, "2026-11");
+                assert_eq!(attempt.sequence, 1);
+            }
+            other => panic!("expected top-up, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_charge_backs_off_and_alerts() {
+        let (top_up, payments, org, wallet) = setup(100);
+        let clock = Arc::new(TestClock::new(Utc::now()));
+        let notifier = Arc::new(InMemoryTopUpNotifier::new());
+        let top_up = top_up
+            .with_clock(clock.clone())
+            .with_notifier(notifier.clone());
+        top_up
+            .set_rule(AutoTopUpRule::new(&wallet, 500, 1_000, "card_123"))
+            .await
+            .unwrap();
+        payments.fail_next("card declined");
+
+        let failed = match top_up.check(org).await.unwrap() {
+            TopUpOutcome::Failed(attempt) => attempt,
+            other => panic!("expected failure, got {:?}", other),
+        };
+        assert_eq!(top_up.credits.get_balance(&org), 100);
+        let alerts = notifier.alerts();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].attempt_id, failed.id);
+        assert_eq!(alerts[0].retry_after, clock.now() + Duration::minutes(5));
+
+        assert_eq!(
+            top_up.check(org).await.unwrap(),
+            TopUpOutcome::BackingOff {
+                until: clock.now() + Duration::minutes(5)
+            }
+        );
+
+        clock.advance(Duration::minutes(5));
+        match top_up.check(org).await.unwrap() {
+            TopUpOutcome::ToppedUp(attempt) => {
+                assert_eq!(attempt.sequence, 2);
+                assert_ne!(attempt.idempotency_key, failed.idempotency_key);
+            }
+            other => panic!("expected top-up, got {:?}", other),
+        }
+        assert_eq!(top_up.credits.get_balance(&org), 1_100);
+        assert_eq!(
+            top_up.attempts(wallet.id, clock.now()).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_checks_top_up_once() {
+        let (top_up, payments, org, wallet) = setup(100);
+        top_up
+            .set_rule(AutoTopUpRule::new(&wallet, 500, 1_000, "card_123"))
+            .await
+            .unwrap();
+
+        let checks: Vec<_> = (0..8)
+            .map(|_| {
+                let top_up = top_up.clone();
+                tokio::spawn(async move { top_up.check(org).await.unwrap() })
+            })
+            .collect();
+        let mut topped_up = 0;
+        for check in checks {
+            if matches!(check.await.unwrap(), TopUpOutcome::ToppedUp(_)) {
+                topped_up += 1;
+            }
+        }
+        assert_eq!(topped_up, 1);
+        assert_eq!(payments.charges().len(), 1);
+        assert_eq!(top_up.credits.get_balance(&org), 1_100);
+
+        // A duplicate claim of the same key is refused
+        let attempts = top_up.attempts(wallet.id, Utc::now()).await.unwrap();
+        assert!(!top_up.repository.claim_attempt(&attempts[0]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_stale_pending_attempt_is_charged_again_under_its_key() {
+        let (top_up, payments, org, wallet) = setup(100);
+        let clock = Arc::new(TestClock::new(Utc::now()));
+        let top_up = top_up.with_clock(clock.clone());
+        let rule = AutoTopUpRule::new(&wallet, 500, 1_000, "card_123");
+        top_up.set_rule(rule.clone()).await.unwrap();
+
+        // A check claimed the attempt and died before recording the charge
+        let stranded = TopUpAttempt::new(&rule, rule.period.key(clock.now()), 1, clock.now());
+        assert!(top_up.repository.claim_attempt(&stranded).await.unwrap());
+        assert_eq!(top_up.check(org).await.unwrap(), TopUpOutcome::InProgress);
+
+        clock.advance(Duration::minutes(15));
+        match top_up.check(org).await.unwrap() {
+            TopUpOutcome::ToppedUp(attempt) => {
+                assert_eq!(attempt.id, stranded.id);
+                assert_eq!(attempt.claimed_at, clock.now());
+            }
+            other => panic!("expected top-up, got {:?}", other),
+        }
+        let charges = payments.charges();
+        assert_eq!(charges.len(), 1);
+        assert_eq!(charges[0].idempotency_key, stranded.idempotency_key);
+        assert_eq!(top_up.credits.get_balance(&org), 1_100);
+    }
+
+    #[tokio::test]
+    async fn test_charged_attempt_is_credited_without_charging_again() {
+        let (top_up, payments, org, wallet) = setup(100);
+        let rule = AutoTopUpRule::new(&wallet, 500, 1_000, "card_123");
+        top_up.set_rule(rule.clone()).await.unwrap();
+
+        // The charge went through but crediting the wallet failed
+        let now = Utc::now();
+        let charged = TopUpAttempt {
+            status: TopUpStatus::Charged,
+            payment_id: Some("pay_earlier".to_string()),
+            error: Some("wallet unavailable".to_string()),
+            ..TopUpAttempt::new(&rule, rule.period.key(now), 1, now)
+        };
+        assert!(top_up.repository.claim_attempt(&charged).await.unwrap());
+
+        match top_up.check(org).await.unwrap() {
+            TopUpOutcome::ToppedUp(attempt) => {
+                assert_eq!(attempt.id, charged.id);
+                assert_eq!(attempt.status, TopUpStatus::Succeeded);
+                assert!(attempt.error.is_none());
+            }
+            other => panic!("expected top-up, got {:?}", other),
+        }
+        assert!(payments.charges().is_empty());
+        assert_eq!(top_up.credits.get_balance(&org), 1_100);
+        let history = top_up.credits.get_transactions(&org, None);
+        assert!(history
+            .iter()
+            .any(|t| t.reference_id.as_deref() == Some("pay_earlier")));
+    }
+}
@@ -1,7 +1,9 @@
 //! Prepaid credits and wallet management.
 //!
 //! Supports prepaid credit packages that can be consumed before invoicing.
-//! Follows Lago's credit management patterns.
+//! Follows Lago's credit management patterns. Wallets can be refilled
+//! automatically by registering an [`AutoTopUp`](crate::auto_top_up::AutoTopUp)
+//! as a [`DebitHook`].
 
 use chrono::{DateTime, Utc};
 use creto_common::{types::Money, CretoError, CretoResult, OrganizationId};
@@ -193,14 +195,28 @@ pub enum CreditTransactionType {
     Refund,
     /// Manual adjustment.
     Adjustment,
+    /// Credits bought automatically when the balance ran low.
+    AutoTopUp,
+}
+
+/// Callback run after every successful debit.
+pub trait DebitHook: Send + Sync {
+    /// Called with the wallet as of the debit, after its lock is released.
+    /// Runs on the debit path; must not block.
+    fn after_debit(&self, wallet: &Wallet);
 }
 
 /// Manager for credit wallets and transactions.
+///
+/// Clones share the same wallets and history.
+#[derive(Clone)]
 pub struct CreditManager {
     /// In-memory wallet storage (for development).
     wallets: Arc<RwLock<HashMap<OrganizationId, Wallet>>>,
     /// Transaction history.
     transactions: Arc<RwLock<Vec<CreditTransaction>>>,
+    /// Run after each debit.
+    debit_hooks: Vec<Arc<dyn DebitHook>>,
 }
 
 impl CreditManager {
@@ -209,9 +225,16 @@ impl CreditManager {
         Self {
             wallets: Arc::new(RwLock::new(HashMap::new())),
             transactions: Arc::new(RwLock::new(Vec::new())),
+            debit_hooks: Vec::new(),
         }
     }
 
+    /// Run `hook` after each debit through this manager.
+    pub fn with_debit_hook(mut self, hook: Arc<dyn DebitHook>) -> Self {
+        self.debit_hooks.push(hook);
+        self
+    }
+
     /// Get or create a wallet for an organization.
     pub fn get_or_create_wallet(&self, organization_id: OrganizationId) -> Wallet {
         let mut wallets = self.wallets.write().unwrap();
@@ -270,16 +293,19 @@ impl CreditManager {
         amount_cents: i64,
         reference_id: Option<&str>,
     ) -> CretoResult<CreditTransaction> {
-        let mut wallets = self.wallets.write().unwrap();
-
-        let wallet = wallets.get_mut(organization_id).ok_or_else(|| {
-            CretoError::BillingPeriodNotFound(format!(
-                "Wallet not found for organization: {}",
-                organization_id
-            ))
-        })?;
-
-        wallet.consume_credits(amount_cents)?;
+        let wallet = {
+            let mut wallets = self.wallets.write().unwrap();
+
+            let wallet = wallets.get_mut(organization_id).ok_or_else(|| {
+                CretoError::BillingPeriodNotFound(format!(
+                    "Wallet not found for organization: {}",
+                    organization_id
+                ))
+            })?;
+
+            wallet.consume_credits(amount_cents)?;
+            wallet.clone()
+        };
 
         let mut transaction = CreditTransaction::new(
             wallet.id,
@@ -296,6 +322,44 @@ impl CreditManager {
         // Record transaction
         self.transactions.write().unwrap().push(transaction.clone());
 
+        for hook in &self.debit_hooks {
+            hook.after_debit(&wallet);
+        }
+
+        Ok(transaction)
+    }
+
+    /// Credit an organization's wallet with an automatic top-up paid by
+    /// `payment_id`.
+    pub fn credit_top_up(
+        &self,
+        organization_id: &OrganizationId,
+        amount_cents: i64,
+        payment_id: &str,
+    ) -> CretoResult<CreditTransaction> {
+        let mut wallets = self.wallets.write().unwrap();
+
+        let wallet = wallets.get_mut(organization_id).ok_or_else(|| {
+            CretoError::BillingPeriodNotFound(format!(
+                "Wallet not found for organization: {}",
+                organization_id
+            ))
+        })?;
+
+        wallet.grant_credits(amount_cents)?;
+
+        let transaction = CreditTransaction::new(
+            wallet.id,
+            *organization_id,
+            CreditTransactionType::AutoTopUp,
+            amount_cents,
+            wallet.balance_cents,
+        )
+        .with_reference(payment_id)
+        .with_description("Automatic top-up");
+
+        self.transactions.write().unwrap().push(transaction.clone());
+
         Ok(transaction)
     }
 
@@ -0,0 +1,241 @@
+//! Multi-currency support for pricing and invoicing.
+//!
+//! Pricing models carry their own currency while invoices are produced in the
+//! organization's billing currency. When the two differ, amounts are converted
+//! through an [`ExchangeRateProvider`] and the rate used is snapshotted onto
+//! the line item for auditability.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use creto_common::{types::Currency, OrganizationId};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Errors from currency handling.
+#[derive(Debug, Clone, Error, PartialEq)]
+pub enum CurrencyError {
+    /// Amounts in different currencies were combined without conversion.
+    #[error("Currency mismatch: expected {}, got {}", expected.code(), actual.code())]
+    Mismatch {
+        expected: Currency,
+        actual: Currency,
+    },
+
+    /// No exchange rate is available for the requested pair and time.
+    #[error("No conversion path from {} to {} at {at}", from.code(), to.code())]
+    NoConversionPath {
+        from: Currency,
+        to: Currency,
+        at: DateTime<Utc>,
+    },
+}
+
+impl From<CurrencyError> for creto_common::CretoError {
+    fn from(error: CurrencyError) -> Self {
+        creto_common::CretoError::ValidationFailed(error.to_string())
+    }
+}
+
+/// An exchange rate effective from a point in time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExchangeRate {
+    /// Source currency.
+    pub from: Currency,
+
+    /// Target currency.
+    pub to: Currency,
+
+    /// Units of `to` per unit of `from`.
+    pub rate: f64,
+
+    /// When this rate takes effect (inclusive).
+    pub effective_from: DateTime<Utc>,
+}
+
+impl ExchangeRate {
+    /// Create a new exchange rate.
+    pub fn new(from: Currency, to: Currency, rate: f64, effective_from: DateTime<Utc>) -> Self {
+        Self {
+            from,
+            to,
+            rate,
+            effective_from,
+        }
+    }
+}
+
+/// Source of exchange rates for currency conversion.
+pub trait ExchangeRateProvider: Send + Sync {
+    /// Get the rate converting `from` into `to` that is effective at `at`.
+    fn rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        at: DateTime<Utc>,
+    ) -> Result<ExchangeRate, CurrencyError>;
+}
+
+/// Provider with fixed rates that never change (for tests and simple setups).
+#[derive(Debug, Clone, Default)]
+pub struct FixedExchangeRateProvider {
+    rates: HashMap<(Currency, Currency), f64>,
+}
+
+impl FixedExchangeRateProvider {
+    /// Create an empty provider.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a fixed rate for a currency pair.
+    pub fn with_rate(mut self, from: Currency, to: Currency, rate: f64) -> Self {
+        self.rates.insert((from, to), rate);
+        self
+    }
+}
+
+impl ExchangeRateProvider for FixedExchangeRateProvider {
+    fn rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        at: DateTime<Utc>,
+    ) -> Result<ExchangeRate, CurrencyError> {
+        if from == to {
+            return Ok(ExchangeRate::new(from, to, 1.0, DateTime::<Utc>::MIN_UTC));
+        }
+
+        self.rates
+            .get(&(from, to))
+            .map(|rate| ExchangeRate::new(from, to, *rate, DateTime::<Utc>::MIN_UTC))
+            .ok_or(CurrencyError::NoConversionPath { from, to, at })
+    }
+}
+
+/// Provider backed by a table of effective-dated rates.
+///
+/// Typically loaded from the `exchange_rates` table via
+/// [`ExchangeRateRepository`](crate::repository::ExchangeRateRepository). The
+/// rate used for a lookup is the latest one whose `effective_from` is not
+/// after the lookup time.
+#[derive(Debug, Clone, Default)]
+pub struct TableExchangeRateProvider {
+    /// Rates per pair, sorted by `effective_from` ascending.
+    rates: HashMap<(Currency, Currency), Vec<ExchangeRate>>,
+}
+
+impl TableExchangeRateProvider {
+    /// Create an empty provider.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a provider from a set of rates.
+    pub fn from_rates(rates: impl IntoIterator<Item = ExchangeRate>) -> Self {
+        let mut provider = Self::new();
+        for rate in rates {
+            provider.insert(rate);
+        }
+        provider
+    }
+
+    /// Add a rate, keeping the pair's history ordered.
+    pub fn insert(&mut self, rate: ExchangeRate) {
+        let history = self.rates.entry((rate.from, rate.to)).or_default();
+        let position = history.partition_point(|r| r.effective_from <= rate.effective_from);
+        history.insert(position, rate);
+    }
+}
+
+impl ExchangeRateProvider for TableExchangeRateProvider {
+    fn rate(
+        &self,
+        from: Currency,
+        to: Currency,
+        at: DateTime<Utc>,
+    ) -> Result<ExchangeRate, CurrencyError> {
+        if from == to {
+            return Ok(ExchangeRate::new(from, to, 1.0, DateTime::<Utc>::MIN_UTC));
+        }
+
+        self.rates
+            .get(&(from, to))
+            .and_then(|history| history.iter().rev().find(|r| r.effective_from <= at))
+            .cloned()
+            .ok_or(CurrencyError::NoConversionPath { from, to, at })
+    }
+}
+
+/// Billing settings for an organization.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgBillingProfile {
+    /// Organization being billed.
+    pub organization_id: OrganizationId,
+
+    /// Currency invoices are issued in.
+    pub billing_currency: Currency,
+}
+
+impl OrgBillingProfile {
+    /// Create a billing profile.
+    pub fn new(organization_id: OrganizationId, billing_currency: Currency) -> Self {
+        Self {
+            organization_id,
+            billing_currency,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_fixed_provider() {
+        let provider =
+            FixedExchangeRateProvider::new().with_rate(Currency::USD, Currency::EUR, 0.9);
+
+        let rate = provider
+            .rate(Currency::USD, Currency::EUR, Utc::now())
+            .unwrap();
+        assert_eq!(rate.rate, 0.9);
+
+        let identity = provider
+            .rate(Currency::GBP, Currency::GBP, Utc::now())
+            .unwrap();
+        assert_eq!(identity.rate, 1.0);
+
+        assert!(matches!(
+            provider.rate(Currency::EUR, Currency::USD, Utc::now()),
+            Err(CurrencyError::NoConversionPath { .. })
+        ));
+    }
+
+    #[test]
+    fn test_table_provider_uses_effective_rate() {
+        let t0 = Utc::now() - Duration::days(30);
+        let t1 = Utc::now() - Duration::days(10);
+
+        // Inserted out of order on purpose.
+        let provider = TableExchangeRateProvider::from_rates([
+            ExchangeRate::new(Currency::USD, Currency::EUR, 0.95, t1),
+            ExchangeRate::new(Currency::USD, Currency::EUR, 0.90, t0),
+        ]);
+
+        let before = provider
+            .rate(Currency::USD, Currency::EUR, t0 + Duration::days(1))
+            .unwrap();
+        assert_eq!(before.rate, 0.90);
+
+        let after = provider
+            .rate(Currency::USD, Currency::EUR, t1 + Duration::days(1))
+            .unwrap();
+        assert_eq!(after.rate, 0.95);
+
+        assert!(provider
+            .rate(Currency::USD, Currency::EUR, t0 - Duration::days(1))
+            .is_err());
+    }
+}
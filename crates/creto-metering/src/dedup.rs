@@ -19,15 +19,45 @@
 //!                         └─────────────────┘
 //! ```
 
-use std::collections::HashSet;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{Clock, OrganizationId, SystemClock};
 use redis::aio::ConnectionManager;
 use redis::AsyncCommands;
 use thiserror::Error;
 use tokio::sync::RwLock;
 use tracing::{debug, warn};
 
+/// Marks each key (`SET NX EX`) and returns 1 for keys that were new and 0
+/// for keys already present, in key order. ARGV[i] is the TTL in seconds
+/// for KEYS[i]; if the last ARGV is 1, keys already present have their TTL
+/// restarted.
+fn batch_set_nx() -> &'static redis::Script {
+    static SCRIPT: OnceLock<redis::Script> = OnceLock::new();
+    SCRIPT.get_or_init(|| {
+        redis::Script::new(
+            r"
+        local sliding = ARGV[#KEYS + 1] == '1'
+        local marked = {}
+        for i, key in ipairs(KEYS) do
+            if redis.call('SET', key, '1', 'NX', 'EX', ARGV[i]) then
+                marked[i] = 1
+            else
+                if sliding then
+                    redis.call('EXPIRE', key, ARGV[i])
+                end
+                marked[i] = 0
+            end
+        end
+        return marked
+        ",
+        )
+    })
+}
+
 /// Deduplication errors.
 #[derive(Debug, Error)]
 pub enum DedupError {
@@ -51,7 +81,10 @@ impl DedupError {
 /// Configuration for deduplication.
 #[derive(Debug, Clone)]
 pub struct DedupConfig {
-    /// TTL for transaction IDs in Redis (default: 24 hours).
+    /// How long a transaction ID is remembered (default: 31 days).
+    ///
+    /// Must cover the validation acceptance window, see
+    /// [`ValidationConfig::check_dedup_coverage`](crate::validation::ValidationConfig::check_dedup_coverage).
     pub ttl_seconds: u64,
     /// Redis key prefix for transaction IDs.
     pub key_prefix: String,
@@ -59,15 +92,40 @@ pub struct DedupConfig {
     pub use_local_fallback: bool,
     /// Maximum size of local cache (LRU eviction).
     pub local_cache_max_size: usize,
+    /// Retention overrides for organizations, in seconds.
+    pub organization_ttl_seconds: HashMap<OrganizationId, u64>,
+    /// Whether seeing a duplicate restarts its retention, so an ID retried
+    /// every so often is never forgotten.
+    pub sliding_window: bool,
+}
+
+impl DedupConfig {
+    /// Retention for an organization's transaction IDs.
+    pub fn ttl_for(&self, organization_id: &OrganizationId) -> u64 {
+        self.organization_ttl_seconds
+            .get(organization_id)
+            .copied()
+            .unwrap_or(self.ttl_seconds)
+    }
+
+    /// Shortest retention any organization gets.
+    pub fn min_ttl_seconds(&self) -> u64 {
+        self.organization_ttl_seconds
+            .values()
+            .copied()
+            .fold(self.ttl_seconds, u64::min)
+    }
 }
 
 impl Default for DedupConfig {
     fn default() -> Self {
         Self {
-            ttl_seconds: 86400, // 24 hours
+            ttl_seconds: 31 * 86400, // 30 day acceptance window plus skew
             key_prefix: "creto:metering:txn:".to_string(),
             use_local_fallback: true,
             local_cache_max_size: 100_000,
+            organization_ttl_seconds: HashMap::new(),
+            sliding_window: false,
         }
     }
 }
@@ -92,11 +150,35 @@ impl DedupResult {
 }
 
 /// Deduplication service using Redis.
+///
+/// An ID is remembered for its organization's retention from when it was
+/// first seen, or, with [`DedupConfig::sliding_window`], from when it was
+/// last seen. Redis and the local fallback apply the same rules.
 pub struct Deduplicator {
     redis: Option<ConnectionManager>,
     config: DedupConfig,
-    /// Local cache for fallback when Redis is unavailable.
-    local_cache: Arc<RwLock<HashSet<String>>>,
+    /// Local cache for fallback when Redis is unavailable, with the time
+    /// each ID expires.
+    local_cache: Arc<RwLock<HashMap<String, DateTime<Utc>>>>,
+    counters: DedupCounters,
+    clock: Arc<dyn Clock>,
+}
+
+/// Running totals behind [`DedupMetrics`].
+#[derive(Debug, Default)]
+struct DedupCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    fallback_activations: AtomicU64,
+}
+
+impl DedupCounters {
+    fn record(&self, results: &[DedupResult]) {
+        let hits = results.iter().filter(|r| r.is_duplicate()).count() as u64;
+        self.hits.fetch_add(hits, Ordering::Relaxed);
+        self.misses
+            .fetch_add(results.len() as u64 - hits, Ordering::Relaxed);
+    }
 }
 
 impl Deduplicator {
@@ -108,7 +190,9 @@ impl Deduplicator {
         Ok(Self {
             redis: Some(connection),
             config,
-            local_cache: Arc::new(RwLock::new(HashSet::new())),
+            local_cache: Arc::new(RwLock::new(HashMap::new())),
+            counters: DedupCounters::default(),
+            clock: Arc::new(SystemClock),
         })
     }
 
@@ -117,66 +201,84 @@ impl Deduplicator {
         Self {
             redis: None,
             config,
-            local_cache: Arc::new(RwLock::new(HashSet::new())),
+            local_cache: Arc::new(RwLock::new(HashMap::new())),
+            counters: DedupCounters::default(),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Use a different time source for local cache expiry.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Get the deduplication configuration.
+    pub fn config(&self) -> &DedupConfig {
+        &self.config
+    }
+
     /// Check if a transaction ID is a duplicate and mark it as seen.
     ///
     /// This is an atomic check-and-set operation:
     /// - Returns `DedupResult::New` if this is the first time seeing this ID
     /// - Returns `DedupResult::Duplicate` if this ID was already processed
+    ///
+    /// The ID is kept for the default retention; see
+    /// [`Deduplicator::check_and_mark_for`] to apply an organization's.
     pub async fn check_and_mark(&self, transaction_id: &str) -> Result<DedupResult, DedupError> {
-        let key = format!("{}{}", self.config.key_prefix, transaction_id);
+        self.check_and_mark_with_ttl(transaction_id, self.config.ttl_seconds)
+            .await
+    }
 
-        // Try Redis first
-        if let Some(ref redis) = self.redis {
-            match self.redis_check_and_mark(redis.clone(), &key).await {
-                Ok(result) => return Ok(result),
-                Err(e) => {
-                    warn!(
-                        "Redis dedup check failed: {}, falling back to local cache",
-                        e
-                    );
-                    if !self.config.use_local_fallback {
-                        return Err(e);
-                    }
-                }
-            }
-        }
+    /// Check and mark a transaction ID with the organization's retention.
+    pub async fn check_and_mark_for(
+        &self,
+        organization_id: &OrganizationId,
+        transaction_id: &str,
+    ) -> Result<DedupResult, DedupError> {
+        self.check_and_mark_with_ttl(transaction_id, self.config.ttl_for(organization_id))
+            .await
+    }
 
-        // Fallback to local cache
-        self.local_check_and_mark(transaction_id).await
+    /// Check and mark a transaction ID, keeping it for `ttl_seconds`.
+    pub async fn check_and_mark_with_ttl(
+        &self,
+        transaction_id: &str,
+        ttl_seconds: u64,
+    ) -> Result<DedupResult, DedupError> {
+        let results = self.check_entries(&[(transaction_id, ttl_seconds)]).await?;
+        Ok(results[0])
     }
 
-    /// Check multiple transaction IDs in batch.
+    /// Check and mark multiple transaction IDs at once.
     ///
-    /// Returns a vector of results in the same order as input.
-    pub async fn check_and_mark_batch(
+    /// Returns a vector of results in the same order as input. The whole
+    /// batch costs one Redis round trip (one lock acquisition on the local
+    /// fallback), and an ID repeated within the batch is new at its first
+    /// occurrence and a duplicate after that.
+    pub async fn check_batch(
         &self,
         transaction_ids: &[&str],
     ) -> Result<Vec<DedupResult>, DedupError> {
-        if let Some(ref redis) = self.redis {
-            match self.redis_batch_check(redis.clone(), transaction_ids).await {
-                Ok(results) => return Ok(results),
-                Err(e) => {
-                    warn!(
-                        "Redis batch dedup check failed: {}, falling back to local",
-                        e
-                    );
-                    if !self.config.use_local_fallback {
-                        return Err(e);
-                    }
-                }
-            }
-        }
+        let entries: Vec<_> = transaction_ids
+            .iter()
+            .map(|id| (*id, self.config.ttl_seconds))
+            .collect();
+        self.check_entries(&entries).await
+    }
 
-        // Fallback to local cache for batch
-        let mut results = Vec::with_capacity(transaction_ids.len());
-        for id in transaction_ids {
-            results.push(self.local_check_and_mark(id).await?);
-        }
-        Ok(results)
+    /// Check and mark a batch of transaction IDs, each with its
+    /// organization's retention.
+    pub async fn check_batch_for(
+        &self,
+        transactions: &[(OrganizationId, &str)],
+    ) -> Result<Vec<DedupResult>, DedupError> {
+        let entries: Vec<_> = transactions
+            .iter()
+            .map(|(org, id)| (*id, self.config.ttl_for(org)))
+            .collect();
+        self.check_entries(&entries).await
     }
 
     /// Check if a transaction ID exists without marking it.
@@ -190,7 +292,9 @@ impl Deduplicator {
         }
 
         let cache = self.local_cache.read().await;
-        Ok(cache.contains(transaction_id))
+        Ok(cache
+            .get(transaction_id)
+            .is_some_and(|expires_at| !self.is_expired(*expires_at)))
     }
 
     /// Clear a transaction ID (useful for testing or manual cleanup).
@@ -217,62 +321,78 @@ impl Deduplicator {
         }
     }
 
+    /// Hit, miss and fallback counters since creation.
+    pub async fn metrics(&self) -> DedupMetrics {
+        DedupMetrics {
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            fallback_activations: self.counters.fallback_activations.load(Ordering::Relaxed),
+            store_size: self.local_cache.read().await.len(),
+        }
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Private Methods
     // ─────────────────────────────────────────────────────────────────────────
 
-    async fn redis_check_and_mark(
-        &self,
-        mut conn: ConnectionManager,
-        key: &str,
-    ) -> Result<DedupResult, DedupError> {
-        // SETNX with expiration - atomic set-if-not-exists
-        let set_result: bool = redis::cmd("SET")
-            .arg(key)
-            .arg("1")
-            .arg("NX")
-            .arg("EX")
-            .arg(self.config.ttl_seconds)
-            .query_async(&mut conn)
-            .await
-            .map_err(DedupError::Connection)?;
+    /// Check and mark `(transaction_id, ttl_seconds)` pairs, on Redis if
+    /// available and locally otherwise.
+    async fn check_entries(&self, entries: &[(&str, u64)]) -> Result<Vec<DedupResult>, DedupError> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
 
-        if set_result {
-            debug!("New transaction ID: {}", key);
-            Ok(DedupResult::New)
-        } else {
-            debug!("Duplicate transaction ID: {}", key);
-            Ok(DedupResult::Duplicate)
+        if let Some(ref redis) = self.redis {
+            match self.redis_check_and_mark(redis.clone(), entries).await {
+                Ok(results) => {
+                    self.counters.record(&results);
+                    return Ok(results);
+                }
+                Err(e) => {
+                    warn!(
+                        "Redis dedup check failed: {}, falling back to local cache",
+                        e
+                    );
+                    if !self.config.use_local_fallback {
+                        return Err(e);
+                    }
+                    self.counters
+                        .fallback_activations
+                        .fetch_add(1, Ordering::Relaxed);
+                }
+            }
         }
+
+        // Fallback to local cache
+        let results = self.local_check_and_mark(entries).await;
+        self.counters.record(&results);
+        Ok(results)
     }
 
-    async fn redis_batch_check(
+    async fn redis_check_and_mark(
         &self,
         mut conn: ConnectionManager,
-        transaction_ids: &[&str],
+        entries: &[(&str, u64)],
     ) -> Result<Vec<DedupResult>, DedupError> {
-        let mut pipe = redis::pipe();
-
-        // Build pipeline of SETNX commands
-        for id in transaction_ids {
-            let key = format!("{}{}", self.config.key_prefix, id);
-            pipe.cmd("SET")
-                .arg(&key)
-                .arg("1")
-                .arg("NX")
-                .arg("EX")
-                .arg(self.config.ttl_seconds);
+        // One script call marks the whole batch; MSETNX would reject the
+        // batch outright if any single ID were already present
+        let mut invocation = batch_set_nx().prepare_invoke();
+        for (id, ttl_seconds) in entries {
+            invocation
+                .key(format!("{}{}", self.config.key_prefix, id))
+                .arg(ttl_seconds);
         }
-
-        let results: Vec<Option<()>> = pipe
-            .query_async(&mut conn)
+        let marked: Vec<u8> = invocation
+            .arg(u8::from(self.config.sliding_window))
+            .invoke_async(&mut conn)
             .await
             .map_err(DedupError::Connection)?;
 
-        Ok(results
+        Ok(marked
             .into_iter()
-            .map(|r| {
-                if r.is_some() {
+            .map(|set| {
+                if set == 1 {
+                    debug!("New transaction ID");
                     DedupResult::New
                 } else {
                     DedupResult::Duplicate
@@ -281,23 +401,63 @@ impl Deduplicator {
             .collect())
     }
 
-    async fn local_check_and_mark(&self, transaction_id: &str) -> Result<DedupResult, DedupError> {
+    async fn local_check_and_mark(&self, entries: &[(&str, u64)]) -> Vec<DedupResult> {
+        let now = self.clock.now();
         let mut cache = self.local_cache.write().await;
 
-        // Simple LRU: if cache is full, clear half of it
-        if cache.len() >= self.config.local_cache_max_size {
-            let to_remove: Vec<_> = cache.iter().take(cache.len() / 2).cloned().collect();
+        // Make room for the whole batch up front rather than per ID: drop
+        // expired IDs, then half of the rest
+        if cache.len() + entries.len() > self.config.local_cache_max_size {
+            cache.retain(|_, expires_at| !self.is_expired(*expires_at));
+        }
+        if cache.len() + entries.len() > self.config.local_cache_max_size {
+            let to_remove: Vec<_> = cache.keys().take(cache.len() / 2).cloned().collect();
             for id in to_remove {
                 cache.remove(&id);
             }
         }
 
-        if cache.insert(transaction_id.to_string()) {
-            Ok(DedupResult::New)
-        } else {
-            Ok(DedupResult::Duplicate)
-        }
+        entries
+            .iter()
+            .map(|(id, ttl_seconds)| {
+                let expires_at = now + Duration::seconds(*ttl_seconds as i64);
+                match cache.get_mut(*id) {
+                    Some(current) if !self.is_expired(*current) => {
+                        if self.config.sliding_window {
+                            *current = expires_at;
+                        }
+                        DedupResult::Duplicate
+                    }
+                    Some(current) => {
+                        *current = expires_at;
+                        DedupResult::New
+                    }
+                    None => {
+                        cache.insert(id.to_string(), expires_at);
+                        DedupResult::New
+                    }
+                }
+            })
+            .collect()
     }
+
+    /// Whether an ID expiring at `expires_at` has been forgotten.
+    fn is_expired(&self, expires_at: DateTime<Utc>) -> bool {
+        self.clock.now() >= expires_at
+    }
+}
+
+/// Deduplication counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupMetrics {
+    /// Checks that found a duplicate.
+    pub hits: u64,
+    /// Checks that found a new ID.
+    pub misses: u64,
+    /// Redis failures answered from the local cache instead.
+    pub fallback_activations: u64,
+    /// IDs held in the local cache (0 while Redis answers every check).
+    pub store_size: usize,
 }
 
 /// Statistics about the deduplicator.
@@ -381,13 +541,31 @@ mod tests {
         dedup.check_and_mark("batch_2").await.unwrap();
 
         let ids = vec!["batch_1", "batch_2", "batch_3"];
-        let results = dedup.check_and_mark_batch(&ids).await.unwrap();
+        let results = dedup.check_batch(&ids).await.unwrap();
 
         assert!(results[0].is_new()); // batch_1 is new
         assert!(results[1].is_duplicate()); // batch_2 was already inserted
         assert!(results[2].is_new()); // batch_3 is new
     }
 
+    #[tokio::test]
+    async fn test_batch_check_scattered_duplicates() {
+        let dedup = Deduplicator::local_only(DedupConfig::default());
+        dedup.check_and_mark("seen").await.unwrap();
+
+        // IDs repeat every 10 positions, plus one seen before the batch
+        let mut ids: Vec<String> = (0..1000).map(|i| format!("scatter_{}", i % 10)).collect();
+        ids[500] = "seen".to_string();
+        let refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let results = dedup.check_batch(&refs).await.unwrap();
+
+        assert_eq!(results.len(), ids.len());
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.is_new(), i < 10, "index {}", i);
+        }
+        assert!(dedup.check_batch(&[]).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_stats() {
         let dedup = Deduplicator::local_only(DedupConfig::default());
@@ -421,6 +599,108 @@ mod tests {
         assert!(dedup.stats().await.local_cache_size < 10);
     }
 
+    #[tokio::test]
+    async fn test_local_cache_honors_retention() {
+        use creto_common::TestClock;
+
+        let clock = Arc::new(TestClock::new(Utc::now()));
+        let dedup = Deduplicator::local_only(DedupConfig {
+            ttl_seconds: 3600,
+            ..Default::default()
+        })
+        .with_clock(clock.clone());
+
+        assert!(dedup.check_and_mark("ttl_1").await.unwrap().is_new());
+        clock.advance(Duration::minutes(59));
+        assert!(dedup.check_and_mark("ttl_1").await.unwrap().is_duplicate());
+
+        // Retention counts from when the ID was first seen
+        clock.advance(Duration::minutes(1));
+        assert!(!dedup.exists("ttl_1").await.unwrap());
+        assert!(dedup.check_and_mark("ttl_1").await.unwrap().is_new());
+    }
+
+    #[tokio::test]
+    async fn test_sliding_window_refreshes_retention() {
+        use creto_common::TestClock;
+
+        let clock = Arc::new(TestClock::new(Utc::now()));
+        let dedup = Deduplicator::local_only(DedupConfig {
+            ttl_seconds: 3600,
+            sliding_window: true,
+            ..Default::default()
+        })
+        .with_clock(clock.clone());
+
+        assert!(dedup.check_and_mark("slide_1").await.unwrap().is_new());
+        clock.advance(Duration::minutes(50));
+        assert!(dedup
+            .check_and_mark("slide_1")
+            .await
+            .unwrap()
+            .is_duplicate());
+
+        // The duplicate restarted the hour, so the ID outlives its first TTL
+        clock.advance(Duration::minutes(50));
+        assert!(dedup.exists("slide_1").await.unwrap());
+        let results = dedup.check_batch(&["slide_1", "slide_2"]).await.unwrap();
+        assert_eq!(results, vec![DedupResult::Duplicate, DedupResult::New]);
+
+        clock.advance(Duration::minutes(60));
+        assert!(!dedup.exists("slide_1").await.unwrap());
+        assert!(dedup.check_and_mark("slide_1").await.unwrap().is_new());
+    }
+
+    #[tokio::test]
+    async fn test_organization_ttl_override() {
+        use creto_common::TestClock;
+
+        let short = OrganizationId::new();
+        let other = OrganizationId::new();
+        let clock = Arc::new(TestClock::new(Utc::now()));
+        let config = DedupConfig {
+            ttl_seconds: 3600,
+            organization_ttl_seconds: HashMap::from([(short, 600)]),
+            ..Default::default()
+        };
+        assert_eq!(config.ttl_for(&short), 600);
+        assert_eq!(config.ttl_for(&other), 3600);
+        assert_eq!(config.min_ttl_seconds(), 600);
+        let dedup = Deduplicator::local_only(config).with_clock(clock.clone());
+
+        let batch = [(short, "org_1"), (other, "org_2")];
+        let results = dedup.check_batch_for(&batch).await.unwrap();
+        assert!(results.iter().all(DedupResult::is_new));
+
+        clock.advance(Duration::minutes(10));
+        let results = dedup.check_batch_for(&batch).await.unwrap();
+        assert_eq!(results, vec![DedupResult::New, DedupResult::Duplicate]);
+        assert!(dedup
+            .check_and_mark_for(&other, "org_2")
+            .await
+            .unwrap()
+            .is_duplicate());
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let dedup = Deduplicator::local_only(DedupConfig::default());
+        assert_eq!(dedup.metrics().await, DedupMetrics::default());
+
+        dedup.check_and_mark("metric_1").await.unwrap();
+        dedup.check_and_mark("metric_1").await.unwrap();
+        dedup
+            .check_batch(&["metric_1", "metric_2", "metric_2"])
+            .await
+            .unwrap();
+
+        let metrics = dedup.metrics().await;
+        assert_eq!(metrics.hits, 3);
+        assert_eq!(metrics.misses, 2);
+        assert_eq!(metrics.fallback_activations, 0);
+        assert_eq!(metrics.store_size, 2);
+    }
+
     #[test]
     fn test_dedup_result_helpers() {
         assert!(DedupResult::New.is_new());
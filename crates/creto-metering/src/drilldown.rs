@@ -0,0 +1,179 @@
+//! Invoice line item drill-down.
+//!
+//! Line items aggregated from usage carry a [`LineItemSource`] recording the
+//! selection that produced them. Drilling down re-runs that selection against
+//! the event store to page through the billed events, break the quantity down
+//! by agent, and check that the events still add up to what was invoiced.
+//! Events inserted or removed after invoicing (late arrivals, corrections)
+//! show up as a verification mismatch.
+
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoError};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::events::UsageEvent;
+use crate::invoice::{Invoice, LineItemSource};
+use crate::repository::EventRepository;
+
+/// Keyset position in an event listing ordered by timestamp, then
+/// transaction ID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventCursor {
+    /// Timestamp of the last event returned.
+    pub timestamp: DateTime<Utc>,
+    /// Transaction ID of the last event returned.
+    pub transaction_id: String,
+}
+
+impl EventCursor {
+    /// Cursor positioned just after `event`.
+    pub fn after(event: &UsageEvent) -> Self {
+        Self {
+            timestamp: event.timestamp,
+            transaction_id: event.transaction_id.clone(),
+        }
+    }
+}
+
+/// A page of events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventPagination {
+    /// Resume after this position (None = from the start).
+    pub after: Option<EventCursor>,
+    /// Maximum events to return.
+    pub limit: usize,
+}
+
+impl EventPagination {
+    /// The first page.
+    pub fn first(limit: usize) -> Self {
+        Self { after: None, limit }
+    }
+
+    /// The page following `cursor`.
+    pub fn after(cursor: EventCursor, limit: usize) -> Self {
+        Self {
+            after: Some(cursor),
+            limit,
+        }
+    }
+}
+
+impl Default for EventPagination {
+    fn default() -> Self {
+        Self::first(100)
+    }
+}
+
+/// One agent's share of a selection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentUsage {
+    /// Agent that generated the events.
+    pub agent_id: AgentId,
+    /// Number of events.
+    pub event_count: u64,
+    /// Sum of event quantities.
+    pub quantity: i64,
+}
+
+/// Comparison of the invoiced usage with the events currently stored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineItemVerification {
+    /// Events aggregated when the invoice was generated.
+    pub invoiced_event_count: u64,
+    /// Quantity on the line item.
+    pub invoiced_quantity: i64,
+    /// Events the selection matches now.
+    pub current_event_count: u64,
+    /// Sum of quantities the selection matches now.
+    pub current_quantity: i64,
+    /// False if the event set changed since invoicing.
+    pub matches: bool,
+}
+
+/// A page of the events behind a line item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineItemEvents {
+    /// Invoice ID.
+    pub invoice_id: Uuid,
+    /// Line item ID.
+    pub line_item_id: Uuid,
+    /// Selection recorded on the line item.
+    pub source: LineItemSource,
+    /// Events on this page, oldest first.
+    pub events: Vec<UsageEvent>,
+    /// Cursor for the next page, if there may be more events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<EventCursor>,
+    /// Usage per agent across all matching events, largest first.
+    pub agent_subtotals: Vec<AgentUsage>,
+    /// Whether the events still match the invoiced quantity.
+    pub verification: LineItemVerification,
+}
+
+/// Re-derive a line item's events from the event store.
+///
+/// Fails with [`CretoError::NotFound`] if the invoice has no such line item
+/// and [`CretoError::ValidationFailed`] if the line item was not aggregated
+/// from usage events (late usage corrections, manual charges).
+pub async fn line_item_events(
+    repository: &dyn EventRepository,
+    invoice: &Invoice,
+    line_item_id: Uuid,
+    pagination: &EventPagination,
+) -> Result<LineItemEvents, CretoError> {
+    let line_item = invoice
+        .line_items
+        .iter()
+        .find(|item| item.id == line_item_id)
+        .ok_or_else(|| {
+            CretoError::NotFound(format!(
+                "Line item {} on invoice {}",
+                line_item_id, invoice.id
+            ))
+        })?;
+    let source = line_item.source.clone().ok_or_else(|| {
+        CretoError::ValidationFailed(format!(
+            "Line item {} was not aggregated from usage events",
+            line_item_id
+        ))
+    })?;
+
+    let events = repository
+        .find_by_selection(
+            &source.selection,
+            pagination.after.as_ref(),
+            pagination.limit as i64,
+        )
+        .await?;
+    let next_cursor = if events.len() == pagination.limit {
+        events.last().map(EventCursor::after)
+    } else {
+        None
+    };
+
+    let mut agent_subtotals = repository.usage_by_agent(&source.selection).await?;
+    agent_subtotals.sort_by_key(|a| std::cmp::Reverse(a.quantity));
+
+    let current_event_count = agent_subtotals.iter().map(|a| a.event_count).sum();
+    let current_quantity = agent_subtotals.iter().map(|a| a.quantity).sum();
+    let verification = LineItemVerification {
+        invoiced_event_count: source.event_count,
+        invoiced_quantity: line_item.quantity,
+        current_event_count,
+        current_quantity,
+        matches: current_event_count == source.event_count
+            && current_quantity == line_item.quantity,
+    };
+
+    Ok(LineItemEvents {
+        invoice_id: invoice.id,
+        line_item_id,
+        source,
+        events,
+        next_cursor,
+        agent_subtotals,
+        verification,
+    })
+}
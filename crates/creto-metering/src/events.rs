@@ -3,11 +3,18 @@
 //! Events are the atomic unit of metering. Each event represents a single
 //! billable action performed by an agent.
 
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, OrganizationId};
+use creto_common::{AgentId, Correlation, CretoError, OrganizationId};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::aggregation::UsageSelection;
+use crate::drilldown::{AgentUsage, EventCursor};
+use crate::repository::EventRepository;
+
 /// A usage event representing a billable action.
 ///
 /// Follows the Lago event schema with Creto extensions for NHI and delegation.
@@ -61,6 +68,14 @@ pub struct UsageEvent {
     /// Delegation depth when event was generated.
     #[serde(default)]
     pub delegation_depth: u8,
+
+    /// Trace ID shared with the action that produced this usage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+
+    /// ID of the entity (execution, oversight request, ...) that emitted this event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caused_by: Option<Uuid>,
 }
 
 impl UsageEvent {
@@ -73,6 +88,31 @@ impl UsageEvent {
     pub fn generate_transaction_id() -> String {
         Uuid::now_v7().to_string()
     }
+
+    /// Get the trace identifiers for this event.
+    pub fn correlation(&self) -> Correlation {
+        Correlation {
+            correlation_id: self.correlation_id,
+            caused_by: self.caused_by,
+        }
+    }
+
+    /// How many ingested events this stored event stands for: its sampling
+    /// factor, or 1 if it was not sampled.
+    pub fn sample_factor(&self) -> i64 {
+        self.properties
+            .get(crate::sampling::SAMPLE_FACTOR_PROPERTY)
+            .and_then(serde_json::Value::as_i64)
+            .unwrap_or(1)
+    }
+
+    /// The event's quantity as it arrived, before any sampling scaled it.
+    pub fn unscaled_quantity(&self) -> i64 {
+        self.properties
+            .get(crate::sampling::SAMPLED_QUANTITY_PROPERTY)
+            .and_then(serde_json::Value::as_i64)
+            .unwrap_or(self.quantity)
+    }
 }
 
 /// Builder for constructing usage events.
@@ -88,6 +128,7 @@ pub struct UsageEventBuilder {
     timestamp: Option<DateTime<Utc>>,
     properties: serde_json::Value,
     delegation_depth: u8,
+    correlation: Correlation,
 }
 
 impl UsageEventBuilder {
@@ -151,6 +192,12 @@ impl UsageEventBuilder {
         self
     }
 
+    /// Set the trace identifiers inherited from the originating action.
+    pub fn correlation(mut self, correlation: Correlation) -> Self {
+        self.correlation = correlation;
+        self
+    }
+
     /// Build the usage event.
     ///
     /// # Panics
@@ -174,6 +221,8 @@ impl UsageEventBuilder {
             timestamp: self.timestamp.unwrap_or_else(Utc::now),
             properties: self.properties,
             delegation_depth: self.delegation_depth,
+            correlation_id: self.correlation.correlation_id,
+            caused_by: self.correlation.caused_by,
         }
     }
 }
@@ -295,6 +344,179 @@ pub trait LocalEventIngestion {
     ) -> Result<usize, creto_common::CretoError>;
 }
 
+/// In-memory event store for testing and development.
+///
+/// Mirrors [`PgEventRepository`](crate::repository::PgEventRepository):
+/// events with a transaction ID already stored are ignored.
+#[derive(Debug, Default)]
+pub struct InMemoryEventRepository {
+    stored: RwLock<StoredEvents>,
+}
+
+#[derive(Debug, Default)]
+struct StoredEvents {
+    events: Vec<UsageEvent>,
+    /// Transaction IDs of `events`, so duplicate checks do not scan.
+    transaction_ids: HashSet<String>,
+}
+
+impl InMemoryEventRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store an event. Returns false if its transaction ID was already stored.
+    pub fn insert(&self, event: UsageEvent) -> bool {
+        let mut stored = self.stored.write().unwrap();
+        if !stored.transaction_ids.insert(event.transaction_id.clone()) {
+            return false;
+        }
+        stored.events.push(event);
+        true
+    }
+
+    fn select(
+        &self,
+        org_id: OrganizationId,
+        code: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<UsageEvent> {
+        self.stored
+            .read()
+            .unwrap()
+            .events
+            .iter()
+            .filter(|e| {
+                e.organization_id == org_id
+                    && !matches!(code, Some(c) if e.code != c)
+                    && e.timestamp >= start
+                    && e.timestamp <= end
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl EventRepository for InMemoryEventRepository {
+    async fn insert_event(&self, event: &UsageEvent) -> Result<bool, CretoError> {
+        Ok(self.insert(event.clone()))
+    }
+
+    async fn insert_events_batch(&self, events: &[UsageEvent]) -> Result<usize, CretoError> {
+        Ok(events.iter().filter(|e| self.insert((*e).clone())).count())
+    }
+
+    async fn find_by_org_and_time(
+        &self,
+        org_id: OrganizationId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<UsageEvent>, CretoError> {
+        let mut events = self.select(org_id, None, start, end);
+        events.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+        events.truncate(limit.max(0) as usize);
+        Ok(events)
+    }
+
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<UsageEvent>, CretoError> {
+        let mut events: Vec<UsageEvent> = self
+            .stored
+            .read()
+            .unwrap()
+            .events
+            .iter()
+            .filter(|e| e.correlation_id == Some(correlation_id))
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| e.timestamp);
+        Ok(events)
+    }
+
+    async fn count_by_code(
+        &self,
+        org_id: OrganizationId,
+        code: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<i64, CretoError> {
+        Ok(self
+            .select(org_id, Some(code), start, end)
+            .iter()
+            .map(UsageEvent::sample_factor)
+            .sum())
+    }
+
+    async fn sum_by_code(
+        &self,
+        org_id: OrganizationId,
+        code: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<i64, CretoError> {
+        Ok(self
+            .select(org_id, Some(code), start, end)
+            .iter()
+            .map(|e| e.quantity)
+            .sum())
+    }
+
+    async fn find_by_selection(
+        &self,
+        selection: &UsageSelection,
+        after: Option<&EventCursor>,
+        limit: i64,
+    ) -> Result<Vec<UsageEvent>, CretoError> {
+        let mut events: Vec<UsageEvent> = self
+            .stored
+            .read()
+            .unwrap()
+            .events
+            .iter()
+            .filter(|e| selection.matches(e))
+            .filter(|e| {
+                !matches!(after, Some(c) if (e.timestamp, e.transaction_id.as_str())
+                    <= (c.timestamp, c.transaction_id.as_str()))
+            })
+            .cloned()
+            .collect();
+        events.sort_by(|a, b| {
+            (a.timestamp, &a.transaction_id).cmp(&(b.timestamp, &b.transaction_id))
+        });
+        events.truncate(limit.max(0) as usize);
+        Ok(events)
+    }
+
+    async fn usage_by_agent(
+        &self,
+        selection: &UsageSelection,
+    ) -> Result<Vec<AgentUsage>, CretoError> {
+        let mut by_agent: HashMap<AgentId, AgentUsage> = HashMap::new();
+        for event in self.stored.read().unwrap().events.iter() {
+            if !selection.matches(event) {
+                continue;
+            }
+            let usage = by_agent.entry(event.agent_id).or_insert(AgentUsage {
+                agent_id: event.agent_id,
+                event_count: 0,
+                quantity: 0,
+            });
+            usage.event_count += 1;
+            usage.quantity += event.quantity;
+        }
+
+        let mut usage: Vec<AgentUsage> = by_agent.into_values().collect();
+        usage.sort_by_key(|u| std::cmp::Reverse(u.quantity));
+        Ok(usage)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
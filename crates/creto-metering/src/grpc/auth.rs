@@ -0,0 +1,330 @@
+//! Authentication and rate limiting for the metering gRPC edge.
+//!
+//! [`ApiKeyAuthLayer`] is a tower layer for the tonic server. It resolves the
+//! `x-api-key` metadata to an organization, applies a per-key token bucket,
+//! and attaches the resolved [`AuthenticatedKey`] to the request extensions so
+//! handlers can reject events for other organizations.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use tonic::body::BoxBody;
+use tonic::codegen::{http, BoxFuture, Service};
+use tonic::metadata::MetadataValue;
+use tonic::Status;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::api_keys::{ApiKeyManager, AuthenticatedKey};
+use crate::repository::ApiKeyRepository;
+
+/// Metadata key carrying the API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Metadata key carrying the retry hint (seconds) on rate-limited responses.
+pub const RETRY_AFTER_HEADER: &str = "retry-after";
+
+/// Buckets tracked before the limiter first sweeps out full ones.
+const SWEEP_THRESHOLD: usize = 1024;
+
+/// Per-key rate limit configuration.
+///
+/// A zero rate leaves keys unlimited.
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Sustained requests per second.
+    pub requests_per_second: f64,
+    /// Maximum burst size; raised to one if lower.
+    pub burst: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 100.0,
+            burst: 200,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// Check if keys are not limited at all.
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_second.is_nan() || self.requests_per_second <= 0.0
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Add the tokens earned since the last refill, up to `burst`.
+    fn refill(&mut self, rate: f64, burst: f64, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.last_refill = now;
+    }
+}
+
+#[derive(Debug)]
+struct Buckets {
+    by_key: HashMap<Uuid, TokenBucket>,
+    /// Size at which full buckets are next swept out.
+    sweep_at: usize,
+}
+
+/// Token-bucket rate limiter keyed by API key.
+///
+/// Buckets that have refilled completely are equivalent to the new ones an
+/// unseen key starts with, so they are swept out whenever the map doubles
+/// in size, keeping it bounded by the keys recently active.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// Create a limiter.
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(Buckets {
+                by_key: HashMap::new(),
+                sweep_at: SWEEP_THRESHOLD,
+            }),
+        }
+    }
+
+    /// Take a token for `key_id`, or return how long to wait for one.
+    pub fn check(&self, key_id: Uuid) -> Result<(), Duration> {
+        if self.config.is_unlimited() {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let burst = self.config.burst.max(1) as f64;
+        let rate = self.config.requests_per_second;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.by_key.len() >= buckets.sweep_at {
+            buckets.by_key.retain(|_, bucket| {
+                bucket.refill(rate, burst, now);
+                bucket.tokens < burst
+            });
+            buckets.sweep_at = (buckets.by_key.len() * 2).max(SWEEP_THRESHOLD);
+        }
+        let bucket = buckets.by_key.entry(key_id).or_insert(TokenBucket {
+            tokens: burst,
+            last_refill: now,
+        });
+        bucket.refill(rate, burst, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+/// Tower layer adding API key authentication and rate limiting.
+pub struct ApiKeyAuthLayer<R: ?Sized> {
+    keys: Arc<ApiKeyManager<R>>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<R: ?Sized> ApiKeyAuthLayer<R> {
+    /// Create a layer backed by an API key manager.
+    pub fn new(keys: Arc<ApiKeyManager<R>>, rate_limit: RateLimitConfig) -> Self {
+        Self {
+            keys,
+            limiter: Arc::new(RateLimiter::new(rate_limit)),
+        }
+    }
+}
+
+impl<R: ?Sized> Clone for ApiKeyAuthLayer<R> {
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+impl<S, R: ?Sized> tower::Layer<S> for ApiKeyAuthLayer<R> {
+    type Service = ApiKeyAuthService<S, R>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ApiKeyAuthService {
+            inner,
+            keys: self.keys.clone(),
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+/// Service produced by [`ApiKeyAuthLayer`].
+pub struct ApiKeyAuthService<S, R: ?Sized> {
+    inner: S,
+    keys: Arc<ApiKeyManager<R>>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S: Clone, R: ?Sized> Clone for ApiKeyAuthService<S, R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            keys: self.keys.clone(),
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+fn rate_limited(retry_after: Duration) -> Status {
+    let mut status = Status::resource_exhausted(format!(
+        "Rate limit exceeded, retry after {}ms",
+        retry_after.as_millis()
+    ));
+    let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    status
+        .metadata_mut()
+        .insert(RETRY_AFTER_HEADER, MetadataValue::from(seconds));
+    status
+}
+
+impl<S, R, B> Service<http::Request<B>> for ApiKeyAuthService<S, R>
+where
+    S: Service<http::Request<B>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+    R: ApiKeyRepository + ?Sized + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        // Take the service that was polled ready and leave a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let keys = self.keys.clone();
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            let Some(api_key) = request
+                .headers()
+                .get(API_KEY_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_owned)
+            else {
+                return Ok(Status::unauthenticated("Missing API key").into_http());
+            };
+
+            let identity = match keys.authenticate(&api_key).await {
+                Ok(identity) => identity,
+                Err(e) => {
+                    warn!(error = %e, "Rejected metering request");
+                    return Ok(Status::unauthenticated(e.to_string()).into_http());
+                }
+            };
+
+            if let Err(retry_after) = limiter.check(identity.key_id) {
+                return Ok(rate_limited(retry_after).into_http());
+            }
+
+            request
+                .extensions_mut()
+                .insert::<AuthenticatedKey>(identity);
+            inner.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_burst_then_limit() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 1.0,
+            burst: 3,
+        });
+        let key = Uuid::new_v4();
+
+        for _ in 0..3 {
+            assert!(limiter.check(key).is_ok());
+        }
+        let retry_after = limiter.check(key).unwrap_err();
+        assert!(retry_after > Duration::ZERO && retry_after <= Duration::from_secs(1));
+
+        // Buckets are per key
+        assert!(limiter.check(Uuid::new_v4()).is_ok());
+    }
+
+    #[test]
+    fn test_zero_rate_is_unlimited_and_zero_burst_admits_one() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 0.0,
+            burst: 0,
+        });
+        let key = Uuid::new_v4();
+        for _ in 0..1000 {
+            assert!(limiter.check(key).is_ok());
+        }
+        assert!(limiter.buckets.lock().unwrap().by_key.is_empty());
+
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 1.0,
+            burst: 0,
+        });
+        assert!(limiter.check(key).is_ok());
+        assert!(limiter.check(key).is_err());
+    }
+
+    #[test]
+    fn test_full_buckets_are_swept() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 1.0,
+            burst: 1,
+        });
+        let busy = Uuid::new_v4();
+        assert!(limiter.check(busy).is_ok());
+
+        // Age every bucket so it has refilled by the next sweep
+        for _ in 1..SWEEP_THRESHOLD {
+            assert!(limiter.check(Uuid::new_v4()).is_ok());
+        }
+        let earlier = Instant::now() - Duration::from_secs(2);
+        for bucket in limiter.buckets.lock().unwrap().by_key.values_mut() {
+            bucket.last_refill = earlier;
+        }
+        assert!(limiter.check(busy).is_ok());
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.by_key.len(), 1);
+        assert_eq!(buckets.by_key[&busy].tokens, 0.0);
+        assert_eq!(buckets.sweep_at, SWEEP_THRESHOLD);
+    }
+
+    #[test]
+    fn test_rate_limited_status_has_retry_hint() {
+        let status = rate_limited(Duration::from_millis(1500));
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(
+            status.metadata().get(RETRY_AFTER_HEADER).unwrap(),
+            &MetadataValue::from(2u64)
+        );
+    }
+}
@@ -32,6 +32,12 @@ pub struct UsageEvent {
     /// Delegation depth when event was generated.
     #[prost(uint32, tag = "10")]
     pub delegation_depth: u32,
+    /// Trace UUID shared with the action that produced this usage (optional).
+    #[prost(string, tag = "11")]
+    pub correlation_id: ::prost::alloc::string::String,
+    /// UUID of the entity that emitted this event (optional).
+    #[prost(string, tag = "12")]
+    pub caused_by: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct IngestEventRequest {
@@ -49,6 +55,9 @@ pub struct IngestEventResponse {
     /// Error message if not successful.
     #[prost(string, tag = "3")]
     pub error_message: ::prost::alloc::string::String,
+    /// Server time the event was validated against, for measuring clock skew.
+    #[prost(message, optional, tag = "4")]
+    pub server_time: ::core::option::Option<::prost_types::Timestamp>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct IngestEventBatchRequest {
@@ -72,6 +81,36 @@ pub struct IngestEventBatchResponse {
     /// Per-event results (only included if there were failures).
     #[prost(message, repeated, tag = "4")]
     pub results: ::prost::alloc::vec::Vec<EventResult>,
+    /// Number of events queued as late instead of ingested.
+    #[prost(int32, tag = "5")]
+    pub late_count: i32,
+    /// Server time the events were validated against, for measuring clock skew.
+    #[prost(message, optional, tag = "6")]
+    pub server_time: ::core::option::Option<::prost_types::Timestamp>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IngestEventStreamResponse {
+    /// Number of events successfully ingested.
+    #[prost(int32, tag = "1")]
+    pub accepted_count: i32,
+    /// Number of duplicate events skipped.
+    #[prost(int32, tag = "2")]
+    pub duplicate_count: i32,
+    /// Number of events rejected.
+    #[prost(int32, tag = "3")]
+    pub failed_count: i32,
+    /// Number of events queued as late instead of ingested.
+    #[prost(int32, tag = "4")]
+    pub late_count: i32,
+    /// Number of batches flushed.
+    #[prost(int32, tag = "5")]
+    pub batch_count: i32,
+    /// Results for rejected events, indexed by position in the stream.
+    #[prost(message, repeated, tag = "6")]
+    pub results: ::prost::alloc::vec::Vec<EventResult>,
+    /// Server time of the last flush.
+    #[prost(message, optional, tag = "7")]
+    pub server_time: ::core::option::Option<::prost_types::Timestamp>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EventResult {
@@ -241,6 +280,10 @@ pub enum IngestStatus {
     ValidationError = 3,
     QuotaExceeded = 4,
     InternalError = 5,
+    /// Arrived behind the event-time watermark; queued for late resolution.
+    AcceptedLate = 6,
+    /// Shed by the ingestion queue under load; safe to retry.
+    Overloaded = 7,
 }
 impl IngestStatus {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -255,6 +298,8 @@ impl IngestStatus {
             Self::ValidationError => "INGEST_STATUS_VALIDATION_ERROR",
             Self::QuotaExceeded => "INGEST_STATUS_QUOTA_EXCEEDED",
             Self::InternalError => "INGEST_STATUS_INTERNAL_ERROR",
+            Self::AcceptedLate => "INGEST_STATUS_ACCEPTED_LATE",
+            Self::Overloaded => "INGEST_STATUS_OVERLOADED",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -266,6 +311,8 @@ impl IngestStatus {
             "INGEST_STATUS_VALIDATION_ERROR" => Some(Self::ValidationError),
             "INGEST_STATUS_QUOTA_EXCEEDED" => Some(Self::QuotaExceeded),
             "INGEST_STATUS_INTERNAL_ERROR" => Some(Self::InternalError),
+            "INGEST_STATUS_ACCEPTED_LATE" => Some(Self::AcceptedLate),
+            "INGEST_STATUS_OVERLOADED" => Some(Self::Overloaded),
             _ => None,
         }
     }
@@ -457,6 +504,36 @@ pub mod metering_service_client {
                 );
             self.inner.unary(req, path, codec).await
         }
+        /// Ingest a client stream of usage events, flushed in server-side batches.
+        pub async fn ingest_event_stream(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<Message = super::UsageEvent>,
+        ) -> std::result::Result<
+            tonic::Response<super::IngestEventStreamResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/creto.metering.v1.MeteringService/IngestEventStream",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(
+                    GrpcMethod::new(
+                        "creto.metering.v1.MeteringService",
+                        "IngestEventStream",
+                    ),
+                );
+            self.inner.client_streaming(req, path, codec).await
+        }
         /// Check if a quota allows a specific usage.
         pub async fn check_quota(
             &mut self,
@@ -572,6 +649,14 @@ pub mod metering_service_server {
             tonic::Response<super::IngestEventBatchResponse>,
             tonic::Status,
         >;
+        /// Ingest a client stream of usage events, flushed in server-side batches.
+        async fn ingest_event_stream(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::UsageEvent>>,
+        ) -> std::result::Result<
+            tonic::Response<super::IngestEventStreamResponse>,
+            tonic::Status,
+        >;
         /// Check if a quota allows a specific usage.
         async fn check_quota(
             &self,
@@ -771,6 +856,52 @@ pub mod metering_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/creto.metering.v1.MeteringService/IngestEventStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct IngestEventStreamSvc<T: MeteringService>(pub Arc<T>);
+                    impl<
+                        T: MeteringService,
+                    > tonic::server::ClientStreamingService<super::UsageEvent>
+                    for IngestEventStreamSvc<T> {
+                        type Response = super::IngestEventStreamResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<tonic::Streaming<super::UsageEvent>>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as MeteringService>::ingest_event_stream(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = IngestEventStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.client_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 "/creto.metering.v1.MeteringService/CheckQuota" => {
                     #[allow(non_camel_case_types)]
                     struct CheckQuotaSvc<T: MeteringService>(pub Arc<T>);
@@ -2,9 +2,23 @@
 //!
 //! This module provides a high-performance gRPC service for ingesting usage events.
 //! It includes validation, deduplication, and batching for optimal throughput.
+//!
+//! The tonic server in [`server`] exposes the service over the wire; wrap it in
+//! [`auth::ApiKeyAuthLayer`] for per-organization authentication and rate limits.
 
+pub mod auth;
+/// Generated protobuf messages, client and server for `creto.metering.v1`.
+#[rustfmt::skip]
+#[path = "creto.metering.v1.rs"]
+pub mod proto;
+pub mod queue;
+pub mod server;
 pub mod service;
 mod types;
 
+pub use auth::{ApiKeyAuthLayer, RateLimitConfig, RateLimiter, API_KEY_HEADER};
+pub use queue::{
+    IngestionQueue, IngestionQueueConfig, IngestionQueueStats, LoadSheddingPolicy, QueueRejection,
+};
 pub use service::{MeteringGrpcService, MeteringServiceConfig, ServiceMetrics};
 pub use types::*;
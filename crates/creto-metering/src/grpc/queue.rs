@@ -0,0 +1,229 @@
+//! Bounded queue between event validation and the event store.
+//!
+//! With [`MeteringServiceConfig::ingestion_queue`](super::MeteringServiceConfig::ingestion_queue)
+//! set, the service acknowledges events once they are queued, and a batch
+//! writer (see [`MeteringGrpcService::spawn_batch_writer`](super::MeteringGrpcService::spawn_batch_writer))
+//! stores them. When the queue is full, its [`LoadSheddingPolicy`] decides
+//! which events give way, so a slow store bounds memory instead of growing
+//! it.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::events::UsageEvent;
+
+/// What to do with events that arrive while the ingestion queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadSheddingPolicy {
+    /// Refuse the new events; the client gets `RESOURCE_EXHAUSTED` and may
+    /// retry them.
+    RejectNew,
+    /// Make room by discarding the oldest queued events. They were already
+    /// acknowledged, so they are lost; each one is counted.
+    DropOldest,
+    /// Wait up to the timeout for room, then refuse as with `RejectNew`.
+    BlockWithTimeout(Duration),
+}
+
+/// Configuration for the ingestion queue.
+#[derive(Debug, Clone)]
+pub struct IngestionQueueConfig {
+    /// Most events queued at once.
+    ///
+    /// Should be at least the service's `max_batch_size`: a batch is queued
+    /// all together, so under `RejectNew` or `BlockWithTimeout` a larger one
+    /// never fits.
+    pub capacity: usize,
+    /// What to do with events that arrive while the queue is full.
+    pub policy: LoadSheddingPolicy,
+    /// Most events the batch writer stores per call.
+    pub batch_size: usize,
+}
+
+impl Default for IngestionQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            policy: LoadSheddingPolicy::RejectNew,
+            batch_size: 500,
+        }
+    }
+}
+
+/// Why events were not queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum QueueRejection {
+    #[error("Ingestion queue is full")]
+    Full,
+
+    #[error("Timed out waiting for room in the ingestion queue")]
+    TimedOut,
+
+    #[error("Ingestion queue is closed")]
+    Closed,
+}
+
+/// Queue depth and shedding counters.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IngestionQueueStats {
+    /// Events currently queued.
+    pub depth: usize,
+    /// Most events ever queued at once.
+    pub high_watermark: usize,
+    /// Events refused because the queue was full or closed.
+    pub rejected: u64,
+    /// Queued events discarded to make room.
+    pub dropped: u64,
+    /// Events refused after waiting for room.
+    pub timed_out: u64,
+}
+
+impl IngestionQueueStats {
+    /// Events shed under any policy.
+    pub fn total_shed(&self) -> u64 {
+        self.rejected + self.dropped + self.timed_out
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    events: VecDeque<UsageEvent>,
+    closed: bool,
+    stats: IngestionQueueStats,
+}
+
+/// Bounded multi-producer queue of validated events awaiting storage.
+pub struct IngestionQueue {
+    config: IngestionQueueConfig,
+    state: Mutex<QueueState>,
+    not_empty: Notify,
+    not_full: Notify,
+}
+
+impl IngestionQueue {
+    /// Create an empty queue.
+    pub fn new(mut config: IngestionQueueConfig) -> Self {
+        config.capacity = config.capacity.max(1);
+        config.batch_size = config.batch_size.max(1);
+        Self {
+            config,
+            state: Mutex::new(QueueState::default()),
+            not_empty: Notify::new(),
+            not_full: Notify::new(),
+        }
+    }
+
+    /// The queue's configuration.
+    pub fn config(&self) -> &IngestionQueueConfig {
+        &self.config
+    }
+
+    /// Current depth and shedding counters.
+    pub fn stats(&self) -> IngestionQueueStats {
+        let state = self.state.lock().unwrap();
+        IngestionQueueStats {
+            depth: state.events.len(),
+            ..state.stats
+        }
+    }
+
+    /// Queue `events` all together, shedding per the policy if they do not
+    /// fit.
+    ///
+    /// Returns the events discarded to make room under
+    /// [`LoadSheddingPolicy::DropOldest`].
+    pub async fn push(&self, events: Vec<UsageEvent>) -> Result<Vec<UsageEvent>, QueueRejection> {
+        let count = events.len();
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let capacity = self.config.capacity;
+        let deadline = match self.config.policy {
+            LoadSheddingPolicy::BlockWithTimeout(timeout) => Some(Instant::now() + timeout),
+            LoadSheddingPolicy::RejectNew | LoadSheddingPolicy::DropOldest => None,
+        };
+
+        loop {
+            // Registered before checking, so room freed in between wakes us
+            let room = self.not_full.notified();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.closed {
+                    state.stats.rejected += count as u64;
+                    return Err(QueueRejection::Closed);
+                }
+                if state.events.len() + count <= capacity
+                    || self.config.policy == LoadSheddingPolicy::DropOldest
+                {
+                    let mut dropped = Vec::new();
+                    for event in events {
+                        if state.events.len() == capacity {
+                            dropped.extend(state.events.pop_front());
+                        }
+                        state.events.push_back(event);
+                    }
+                    state.stats.dropped += dropped.len() as u64;
+                    state.stats.high_watermark = state.stats.high_watermark.max(state.events.len());
+                    drop(state);
+                    self.not_empty.notify_one();
+                    return Ok(dropped);
+                }
+                if deadline.is_none() || count > capacity {
+                    state.stats.rejected += count as u64;
+                    return Err(QueueRejection::Full);
+                }
+            }
+
+            let deadline = deadline.expect("Only blocking pushes wait");
+            if tokio::time::timeout_at(deadline, room).await.is_err() {
+                self.state.lock().unwrap().stats.timed_out += count as u64;
+                return Err(QueueRejection::TimedOut);
+            }
+        }
+    }
+
+    /// Wait for events, then take up to `batch_size` of them in order.
+    ///
+    /// Returns an empty batch once the queue is closed and drained. Safe to
+    /// cancel: events are only taken when the batch is returned.
+    pub async fn next_batch(&self) -> Vec<UsageEvent> {
+        loop {
+            let available = self.not_empty.notified();
+            let batch = self.take_batch();
+            if !batch.is_empty() || self.state.lock().unwrap().closed {
+                return batch;
+            }
+            available.await;
+        }
+    }
+
+    /// Take up to `batch_size` queued events without waiting.
+    pub fn take_batch(&self) -> Vec<UsageEvent> {
+        let batch: Vec<_> = {
+            let mut state = self.state.lock().unwrap();
+            let count = state.events.len().min(self.config.batch_size);
+            state.events.drain(..count).collect()
+        };
+        if !batch.is_empty() {
+            self.not_full.notify_waiters();
+        }
+        batch
+    }
+
+    /// Refuse further events. Queued events can still be taken.
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.not_full.notify_waiters();
+        self.not_empty.notify_one();
+    }
+
+    /// Check if the queue has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.state.lock().unwrap().closed
+    }
+}
@@ -0,0 +1,582 @@
+//! Tonic server binding for [`MeteringGrpcService`].
+//!
+//! Converts between the generated protobuf messages and the service's own
+//! request types. When the server is wrapped in
+//! [`ApiKeyAuthLayer`](super::auth::ApiKeyAuthLayer), every request must
+//! target the organization its API key belongs to.
+
+use chrono::{DateTime, Utc};
+use tonic::codegen::tokio_stream::StreamExt;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::api_keys::AuthenticatedKey;
+use crate::events::EventIngestion;
+use crate::grpc::proto::{self, metering_service_server::MeteringService};
+use crate::grpc::service::MeteringGrpcService;
+use crate::grpc::types::*;
+use crate::repository::LateEventRepository;
+
+/// Reject requests for an organization other than the authenticated one.
+///
+/// Requests without an [`AuthenticatedKey`] (no auth layer installed) pass.
+#[allow(clippy::result_large_err)] // tonic::Status is large by design
+fn authorize_org<T>(request: &Request<T>, organization_id: &str) -> Result<(), Status> {
+    authorize_identity(
+        request.extensions().get::<AuthenticatedKey>(),
+        organization_id,
+    )
+}
+
+/// [`authorize_org`] for an identity already taken from the request.
+#[allow(clippy::result_large_err)]
+fn authorize_identity(
+    identity: Option<&AuthenticatedKey>,
+    organization_id: &str,
+) -> Result<(), Status> {
+    let Some(identity) = identity else {
+        return Ok(());
+    };
+
+    match uuid::Uuid::parse_str(organization_id) {
+        Ok(org) if &org == identity.organization_id.as_uuid() => Ok(()),
+        _ => Err(Status::permission_denied(format!(
+            "API key is not authorized for organization {}",
+            organization_id
+        ))),
+    }
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+fn event_type_from_proto(value: i32) -> GrpcUsageEventType {
+    match proto::UsageEventType::try_from(value).unwrap_or(proto::UsageEventType::Unspecified) {
+        proto::UsageEventType::Unspecified => GrpcUsageEventType::Unspecified,
+        proto::UsageEventType::ApiCall => GrpcUsageEventType::ApiCall,
+        proto::UsageEventType::LlmInference => GrpcUsageEventType::LlmInference,
+        proto::UsageEventType::EmbeddingGeneration => GrpcUsageEventType::EmbeddingGeneration,
+        proto::UsageEventType::InputTokens => GrpcUsageEventType::InputTokens,
+        proto::UsageEventType::OutputTokens => GrpcUsageEventType::OutputTokens,
+        proto::UsageEventType::TotalTokens => GrpcUsageEventType::TotalTokens,
+        proto::UsageEventType::CpuMilliseconds => GrpcUsageEventType::CpuMilliseconds,
+        proto::UsageEventType::MemoryMbSeconds => GrpcUsageEventType::MemoryMbSeconds,
+        proto::UsageEventType::GpuMilliseconds => GrpcUsageEventType::GpuMilliseconds,
+        proto::UsageEventType::StorageBytes => GrpcUsageEventType::StorageBytes,
+        proto::UsageEventType::NetworkEgressBytes => GrpcUsageEventType::NetworkEgressBytes,
+        proto::UsageEventType::OversightRequest => GrpcUsageEventType::OversightRequest,
+        proto::UsageEventType::SandboxExecution => GrpcUsageEventType::SandboxExecution,
+        proto::UsageEventType::MessageSent => GrpcUsageEventType::MessageSent,
+    }
+}
+
+fn timestamp_from_proto(ts: prost_types::Timestamp) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(ts.seconds, ts.nanos.max(0) as u32)
+}
+
+fn timestamp_to_proto(ts: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: ts.timestamp(),
+        nanos: ts.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn value_to_json(value: prost_types::Value) -> serde_json::Value {
+    use prost_types::value::Kind;
+
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::NumberValue(n)) => serde_json::json!(n),
+        Some(Kind::StringValue(s)) => serde_json::Value::String(s),
+        Some(Kind::BoolValue(b)) => serde_json::Value::Bool(b),
+        Some(Kind::StructValue(s)) => struct_to_json(s),
+        Some(Kind::ListValue(list)) => {
+            serde_json::Value::Array(list.values.into_iter().map(value_to_json).collect())
+        }
+    }
+}
+
+fn struct_to_json(s: prost_types::Struct) -> serde_json::Value {
+    serde_json::Value::Object(
+        s.fields
+            .into_iter()
+            .map(|(k, v)| (k, value_to_json(v)))
+            .collect(),
+    )
+}
+
+impl From<proto::UsageEvent> for GrpcUsageEvent {
+    fn from(event: proto::UsageEvent) -> Self {
+        Self {
+            transaction_id: event.transaction_id,
+            organization_id: event.organization_id,
+            agent_id: event.agent_id,
+            external_subscription_id: non_empty(event.external_subscription_id),
+            event_type: event_type_from_proto(event.event_type),
+            code: event.code,
+            quantity: event.quantity,
+            timestamp: event.timestamp.and_then(timestamp_from_proto),
+            properties: event.properties.map(struct_to_json),
+            delegation_depth: event.delegation_depth,
+            correlation_id: non_empty(event.correlation_id),
+            caused_by: non_empty(event.caused_by),
+        }
+    }
+}
+
+fn status_to_proto(status: IngestStatus) -> i32 {
+    match status {
+        IngestStatus::Unspecified => proto::IngestStatus::Unspecified as i32,
+        IngestStatus::Accepted => proto::IngestStatus::Accepted as i32,
+        IngestStatus::Duplicate => proto::IngestStatus::Duplicate as i32,
+        IngestStatus::ValidationError => proto::IngestStatus::ValidationError as i32,
+        IngestStatus::QuotaExceeded => proto::IngestStatus::QuotaExceeded as i32,
+        IngestStatus::InternalError => proto::IngestStatus::InternalError as i32,
+        IngestStatus::AcceptedLate => proto::IngestStatus::AcceptedLate as i32,
+        IngestStatus::Overloaded => proto::IngestStatus::Overloaded as i32,
+    }
+}
+
+/// Fail with `RESOURCE_EXHAUSTED` if the ingestion queue shed any events.
+///
+/// Events accepted alongside them stay accepted; a client resending the
+/// whole batch has those answered as duplicates.
+#[allow(clippy::result_large_err)] // tonic::Status is large by design
+fn check_overloaded(results: &[EventResult]) -> Result<(), Status> {
+    match results
+        .iter()
+        .find(|result| result.status == IngestStatus::Overloaded)
+    {
+        Some(result) => Err(Status::resource_exhausted(
+            result.error_message.clone().unwrap_or_default(),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn result_to_proto(result: EventResult) -> proto::EventResult {
+    proto::EventResult {
+        index: result.index as i32,
+        status: status_to_proto(result.status),
+        error_message: result.error_message.unwrap_or_default(),
+    }
+}
+
+#[tonic::async_trait]
+impl<I, L> MeteringService for MeteringGrpcService<I, L>
+where
+    I: EventIngestion + Sync + 'static,
+    L: LateEventRepository + ?Sized + 'static,
+{
+    async fn ingest_event(
+        &self,
+        request: Request<proto::IngestEventRequest>,
+    ) -> Result<Response<proto::IngestEventResponse>, Status> {
+        let event = request
+            .get_ref()
+            .event
+            .clone()
+            .ok_or_else(|| Status::invalid_argument("Missing event"))?;
+        authorize_org(&request, &event.organization_id)?;
+
+        let response = MeteringGrpcService::ingest_event(
+            self,
+            IngestEventRequest {
+                event: event.into(),
+            },
+        )
+        .await;
+        if response.status == IngestStatus::Overloaded {
+            return Err(Status::resource_exhausted(
+                response.error_message.unwrap_or_default(),
+            ));
+        }
+
+        Ok(Response::new(proto::IngestEventResponse {
+            success: response.success,
+            status: status_to_proto(response.status),
+            error_message: response.error_message.unwrap_or_default(),
+            server_time: Some(timestamp_to_proto(response.server_time)),
+        }))
+    }
+
+    async fn ingest_event_batch(
+        &self,
+        request: Request<proto::IngestEventBatchRequest>,
+    ) -> Result<Response<proto::IngestEventBatchResponse>, Status> {
+        for event in &request.get_ref().events {
+            authorize_org(&request, &event.organization_id)?;
+        }
+
+        let batch = request.into_inner();
+        let response = MeteringGrpcService::ingest_event_batch(
+            self,
+            IngestEventBatchRequest {
+                events: batch.events.into_iter().map(Into::into).collect(),
+                continue_on_error: batch.continue_on_error,
+            },
+        )
+        .await;
+        check_overloaded(&response.results)?;
+
+        Ok(Response::new(proto::IngestEventBatchResponse {
+            accepted_count: response.accepted_count as i32,
+            duplicate_count: response.duplicate_count as i32,
+            failed_count: response.failed_count as i32,
+            late_count: response.late_count as i32,
+            results: response.results.into_iter().map(result_to_proto).collect(),
+            server_time: Some(timestamp_to_proto(response.server_time)),
+        }))
+    }
+
+    async fn ingest_event_stream(
+        &self,
+        request: Request<Streaming<proto::UsageEvent>>,
+    ) -> Result<Response<proto::IngestEventStreamResponse>, Status> {
+        // A foreign event ends the stream; events before it are still flushed
+        let identity = request.extensions().get::<AuthenticatedKey>().copied();
+        #[allow(clippy::result_large_err)] // tonic::Status is large by design
+        let events = request
+            .into_inner()
+            .map(move |event| -> Result<GrpcUsageEvent, Status> {
+                let event = event?;
+                authorize_identity(identity.as_ref(), &event.organization_id)?;
+                Ok(GrpcUsageEvent::from(event))
+            });
+        let response = MeteringGrpcService::ingest_event_stream(self, events).await?;
+        check_overloaded(&response.results)?;
+
+        Ok(Response::new(proto::IngestEventStreamResponse {
+            accepted_count: response.accepted_count as i32,
+            duplicate_count: response.duplicate_count as i32,
+            failed_count: response.failed_count as i32,
+            late_count: response.late_count as i32,
+            batch_count: response.batch_count as i32,
+            results: response.results.into_iter().map(result_to_proto).collect(),
+            server_time: Some(timestamp_to_proto(response.server_time)),
+        }))
+    }
+
+    async fn check_quota(
+        &self,
+        request: Request<proto::CheckQuotaRequest>,
+    ) -> Result<Response<proto::CheckQuotaResponse>, Status> {
+        authorize_org(&request, &request.get_ref().organization_id)?;
+
+        let check = request.into_inner();
+        let response = MeteringGrpcService::check_quota(
+            self,
+            CheckQuotaRequest {
+                organization_id: check.organization_id,
+                agent_id: non_empty(check.agent_id),
+                metric_code: check.metric_code,
+                quantity: check.quantity,
+            },
+        )
+        .await;
+
+        Ok(Response::new(proto::CheckQuotaResponse {
+            allowed: response.allowed,
+            current_usage: response.current_usage,
+            limit: response.limit,
+            remaining: response.remaining,
+            denial_reason: response.denial_reason.unwrap_or_default(),
+        }))
+    }
+
+    async fn get_quota_status(
+        &self,
+        request: Request<proto::GetQuotaStatusRequest>,
+    ) -> Result<Response<proto::GetQuotaStatusResponse>, Status> {
+        authorize_org(&request, &request.get_ref().organization_id)?;
+
+        let query = request.into_inner();
+        let response = MeteringGrpcService::get_quota_status(
+            self,
+            GetQuotaStatusRequest {
+                organization_id: query.organization_id,
+                agent_id: non_empty(query.agent_id),
+                metric_code: query.metric_code,
+            },
+        )
+        .await
+        .ok_or_else(|| Status::not_found("Quota not found"))?;
+
+        Ok(Response::new(proto::GetQuotaStatusResponse {
+            metric_code: response.metric_code,
+            limit: response.limit,
+            current_usage: response.current_usage,
+            remaining: response.remaining,
+            usage_percentage: response.usage_percentage,
+            period: response.period as i32,
+            period_start: Some(timestamp_to_proto(response.period_start)),
+            period_end: Some(timestamp_to_proto(response.period_end)),
+        }))
+    }
+
+    type StreamEventsStream = tonic::codegen::BoxStream<proto::UsageEvent>;
+
+    async fn stream_events(
+        &self,
+        _request: Request<proto::StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        Err(Status::unimplemented("Event streaming is not available"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use creto_common::{CretoError, OrganizationId};
+    use tonic::codegen::tokio_stream;
+    use tonic::transport::server::TcpIncoming;
+    use tonic::transport::Server;
+
+    use super::*;
+    use crate::api_keys::{ApiKeyManager, InMemoryApiKeyRepository};
+    use crate::dedup::{DedupConfig, Deduplicator};
+    use crate::grpc::auth::{ApiKeyAuthLayer, RateLimitConfig, API_KEY_HEADER, RETRY_AFTER_HEADER};
+    use crate::grpc::proto::metering_service_client::MeteringServiceClient;
+    use crate::grpc::proto::metering_service_server::MeteringServiceServer;
+    use crate::grpc::MeteringServiceConfig;
+    use crate::quota::QuotaEnforcer;
+    use crate::UsageEvent;
+
+    struct NoopIngestion;
+
+    impl EventIngestion for NoopIngestion {
+        async fn ingest(&self, _event: UsageEvent) -> Result<(), CretoError> {
+            Ok(())
+        }
+
+        async fn ingest_batch(&self, events: Vec<UsageEvent>) -> Result<usize, CretoError> {
+            Ok(events.len())
+        }
+    }
+
+    async fn spawn_server(
+        keys: Arc<ApiKeyManager<InMemoryApiKeyRepository>>,
+        rate_limit: RateLimitConfig,
+    ) -> MeteringServiceClient<tonic::transport::Channel> {
+        let service = MeteringGrpcService::new(
+            Arc::new(NoopIngestion),
+            Arc::new(Deduplicator::local_only(DedupConfig::default())),
+            Arc::new(QuotaEnforcer::new()),
+            MeteringServiceConfig {
+                enforce_quotas: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = TcpIncoming::from_listener(listener, true, None).unwrap();
+
+        tokio::spawn(
+            Server::builder()
+                .layer(ApiKeyAuthLayer::new(keys, rate_limit))
+                .add_service(MeteringServiceServer::new(service))
+                .serve_with_incoming(incoming),
+        );
+
+        MeteringServiceClient::connect(format!("http://{}", addr))
+            .await
+            .unwrap()
+    }
+
+    fn event_for(org_id: OrganizationId) -> proto::UsageEvent {
+        proto::UsageEvent {
+            transaction_id: uuid::Uuid::new_v4().to_string(),
+            organization_id: org_id.as_uuid().to_string(),
+            agent_id: uuid::Uuid::new_v4().to_string(),
+            event_type: proto::UsageEventType::ApiCall as i32,
+            code: "api_calls".to_string(),
+            quantity: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_correlation_round_trips_through_proto() {
+        let correlation_id = uuid::Uuid::new_v4();
+        let caused_by = uuid::Uuid::new_v4();
+        let event = proto::UsageEvent {
+            correlation_id: correlation_id.to_string(),
+            caused_by: caused_by.to_string(),
+            ..event_for(OrganizationId::new())
+        };
+
+        let usage = GrpcUsageEvent::from(event).to_usage_event().unwrap();
+        assert_eq!(usage.correlation_id, Some(correlation_id));
+        assert_eq!(usage.caused_by, Some(caused_by));
+
+        let back = GrpcUsageEvent::from(usage).to_usage_event().unwrap();
+        assert_eq!(back.correlation_id, Some(correlation_id));
+        assert_eq!(back.caused_by, Some(caused_by));
+
+        // Unset fields stay unset
+        let usage = GrpcUsageEvent::from(event_for(OrganizationId::new()))
+            .to_usage_event()
+            .unwrap();
+        assert_eq!(usage.correlation_id, None);
+        assert_eq!(usage.caused_by, None);
+    }
+
+    fn authed<T>(message: T, api_key: &str) -> Request<T> {
+        let mut request = Request::new(message);
+        request
+            .metadata_mut()
+            .insert(API_KEY_HEADER, api_key.parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_rejects_missing_and_cross_org_keys() {
+        let keys = Arc::new(ApiKeyManager::new(
+            Arc::new(InMemoryApiKeyRepository::new()),
+        ));
+        let org_a = OrganizationId::new();
+        let org_b = OrganizationId::new();
+        let key_a = keys.create_key(org_a).await.unwrap();
+
+        let mut client = spawn_server(keys.clone(), RateLimitConfig::default()).await;
+
+        // No key
+        let status = client
+            .ingest_event(proto::IngestEventRequest {
+                event: Some(event_for(org_a)),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        // Own organization
+        let response = client
+            .ingest_event(authed(
+                proto::IngestEventRequest {
+                    event: Some(event_for(org_a)),
+                },
+                &key_a.plaintext,
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(response.success);
+        assert_eq!(response.status, proto::IngestStatus::Accepted as i32);
+
+        // Someone else's organization
+        let status = client
+            .ingest_event(authed(
+                proto::IngestEventRequest {
+                    event: Some(event_for(org_b)),
+                },
+                &key_a.plaintext,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        // One foreign event poisons the whole batch
+        let status = client
+            .ingest_event_batch(authed(
+                proto::IngestEventBatchRequest {
+                    events: vec![event_for(org_a), event_for(org_b)],
+                    continue_on_error: true,
+                },
+                &key_a.plaintext,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        // Streams are checked event by event
+        let repeated = event_for(org_a);
+        let response = client
+            .ingest_event_stream(authed(
+                tokio_stream::iter(vec![event_for(org_a), repeated.clone(), repeated]),
+                &key_a.plaintext,
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.accepted_count, 2);
+        assert_eq!(response.duplicate_count, 1);
+        let status = client
+            .ingest_event_stream(authed(
+                tokio_stream::iter(vec![event_for(org_a), event_for(org_b)]),
+                &key_a.plaintext,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+        // Revoked keys stop working immediately
+        keys.revoke_key(key_a.key.id).await.unwrap();
+        let status = client
+            .ingest_event(authed(
+                proto::IngestEventRequest {
+                    event: Some(event_for(org_a)),
+                },
+                &key_a.plaintext,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_kicks_in() {
+        let keys = Arc::new(ApiKeyManager::new(
+            Arc::new(InMemoryApiKeyRepository::new()),
+        ));
+        let org_id = OrganizationId::new();
+        let key = keys.create_key(org_id).await.unwrap();
+        let other = keys.create_key(org_id).await.unwrap();
+
+        let mut client = spawn_server(
+            keys,
+            RateLimitConfig {
+                requests_per_second: 0.5,
+                burst: 2,
+            },
+        )
+        .await;
+
+        for _ in 0..2 {
+            client
+                .ingest_event(authed(
+                    proto::IngestEventRequest {
+                        event: Some(event_for(org_id)),
+                    },
+                    &key.plaintext,
+                ))
+                .await
+                .unwrap();
+        }
+
+        let status = client
+            .ingest_event(authed(
+                proto::IngestEventRequest {
+                    event: Some(event_for(org_id)),
+                },
+                &key.plaintext,
+            ))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert!(status.metadata().get(RETRY_AFTER_HEADER).is_some());
+
+        // Limits are per key
+        client
+            .ingest_event(authed(
+                proto::IngestEventRequest {
+                    event: Some(event_for(org_id)),
+                },
+                &other.plaintext,
+            ))
+            .await
+            .unwrap();
+    }
+}
@@ -4,33 +4,65 @@
 //! and batching for high throughput.
 
 use std::sync::Arc;
+use std::time::Duration;
 
+use creto_common::metrics::{Counter, CounterVec, Gauge, MetricsRegistry};
+use creto_common::{Clock, CretoError, ShutdownCoordinator, SystemClock};
 use tokio::sync::RwLock;
-use tracing::{error, instrument};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tonic::codegen::tokio_stream::{Stream, StreamExt};
+use tracing::{error, instrument, warn};
 
 use crate::dedup::{DedupResult, Deduplicator};
-use crate::events::EventIngestion;
+use crate::events::{EventIngestion, UsageEvent};
+use crate::grpc::queue::{
+    IngestionQueue, IngestionQueueConfig, IngestionQueueStats, QueueRejection,
+};
 use crate::grpc::types::*;
-use crate::quota::QuotaEnforcer;
-use crate::validation::{EventValidator, ValidationConfig};
+use crate::late_events::{Admitted, InMemoryLateEventRepository, LateEventQueue};
+use crate::quota::{QuotaDenialReason, QuotaEnforcer};
+use crate::repository::LateEventRepository;
+use crate::sampling::IngestionSampler;
+use crate::validation::{EventValidator, ValidationConfig, WindowConfigError};
 
 /// Configuration for the gRPC metering service.
 #[derive(Debug, Clone)]
 pub struct MeteringServiceConfig {
     /// Maximum batch size for ingestion.
     pub max_batch_size: usize,
+    /// Events buffered from an ingestion stream before they are flushed.
+    ///
+    /// Capped at `max_batch_size`. The stream is not read while a batch
+    /// flushes, so a slow store pauses the client instead of growing the
+    /// buffer.
+    pub stream_batch_size: usize,
+    /// Longest an event from an ingestion stream waits in the buffer.
+    pub stream_flush_interval: Duration,
     /// Whether to enforce quotas on ingestion.
     pub enforce_quotas: bool,
     /// Validation configuration.
+    ///
+    /// Its acceptance window must be covered by the deduplicator's
+    /// retention; see [`ValidationConfig::check_dedup_coverage`].
     pub validation: ValidationConfig,
+    /// Queue admitted events for a batch writer instead of storing them
+    /// before responding (None = store inline).
+    ///
+    /// Events are acknowledged once queued; run
+    /// [`MeteringGrpcService::spawn_batch_writer`] to store them.
+    pub ingestion_queue: Option<IngestionQueueConfig>,
 }
 
 impl Default for MeteringServiceConfig {
     fn default() -> Self {
         Self {
             max_batch_size: 1000,
+            stream_batch_size: 500,
+            stream_flush_interval: Duration::from_millis(100),
             enforce_quotas: true,
             validation: ValidationConfig::default(),
+            ingestion_queue: None,
         }
     }
 }
@@ -41,37 +73,216 @@ impl Default for MeteringServiceConfig {
 /// - Single event ingestion with validation and deduplication
 /// - Batch ingestion for high throughput
 /// - Quota checking
-pub struct MeteringGrpcService<I: EventIngestion> {
+/// - Diverting events that arrive behind the watermark to a late event queue
+pub struct MeteringGrpcService<I: EventIngestion, L: ?Sized = InMemoryLateEventRepository> {
     ingestion: Arc<I>,
     deduplicator: Arc<Deduplicator>,
     quota_enforcer: Arc<QuotaEnforcer>,
+    late_events: Option<Arc<LateEventQueue<L>>>,
+    queue: Option<Arc<IngestionQueue>>,
     validator: EventValidator,
+    /// Thins stored events after the quota check (None = store every event).
+    sampler: Option<Arc<IngestionSampler>>,
     config: MeteringServiceConfig,
+    clock: Arc<dyn Clock>,
     /// Metrics for monitoring.
     metrics: Arc<RwLock<ServiceMetrics>>,
+    /// Counters exported to a metrics registry (None = not exported).
+    exported_metrics: Option<IngestMetrics>,
+}
+
+/// Ingestion counters exported to a [`MetricsRegistry`].
+#[derive(Clone)]
+struct IngestMetrics {
+    ingested: Counter,
+    deduplicated: Counter,
+    late: Counter,
+    rejected_validation: Counter,
+    rejected_quota: Counter,
+    rejected_internal: Counter,
+    rejected_overloaded: Counter,
+    shed: CounterVec,
+    queue_depth: Gauge,
+    queue_high_watermark: Gauge,
+}
+
+impl IngestMetrics {
+    fn new(registry: &MetricsRegistry) -> Self {
+        let rejected = registry.counter_vec(
+            "creto_metering_events_rejected_total",
+            "Usage events rejected, by reason",
+            &["reason"],
+        );
+        Self {
+            ingested: registry.counter(
+                "creto_metering_events_ingested_total",
+                "Usage events accepted and stored",
+            ),
+            deduplicated: registry.counter(
+                "creto_metering_events_deduplicated_total",
+                "Usage events dropped as replays of an earlier transaction",
+            ),
+            late: registry.counter(
+                "creto_metering_events_late_total",
+                "Usage events queued behind the watermark",
+            ),
+            rejected_validation: rejected.with_label_values(&["validation"]),
+            rejected_quota: rejected.with_label_values(&["quota_exceeded"]),
+            rejected_internal: rejected.with_label_values(&["internal"]),
+            rejected_overloaded: rejected.with_label_values(&["overloaded"]),
+            shed: registry.counter_vec(
+                "creto_metering_events_shed_total",
+                "Usage events shed by the ingestion queue, by outcome",
+                &["outcome"],
+            ),
+            queue_depth: registry.gauge(
+                "creto_metering_ingest_queue_depth",
+                "Usage events queued for the batch writer",
+            ),
+            queue_high_watermark: registry.gauge(
+                "creto_metering_ingest_queue_high_watermark",
+                "Most usage events ever queued for the batch writer",
+            ),
+        }
+    }
+
+    /// Publish the queue's depth and high-watermark.
+    fn observe_queue(&self, stats: &IngestionQueueStats) {
+        self.queue_depth.set(stats.depth as i64);
+        self.queue_high_watermark.set(stats.high_watermark as i64);
+    }
+}
+
+/// Why admitted events were not stored.
+enum StoreFailure {
+    /// Shed by the ingestion queue.
+    Overloaded(QueueRejection),
+    /// The event store failed.
+    Internal(CretoError),
+}
+
+impl StoreFailure {
+    fn status(&self) -> IngestStatus {
+        match self {
+            StoreFailure::Overloaded(_) => IngestStatus::Overloaded,
+            StoreFailure::Internal(_) => IngestStatus::InternalError,
+        }
+    }
+}
+
+impl std::fmt::Display for StoreFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreFailure::Overloaded(e) => e.fmt(f),
+            StoreFailure::Internal(e) => e.fmt(f),
+        }
+    }
+}
+
+/// Store one batch taken from the ingestion queue.
+async fn write_queued<I: EventIngestion>(
+    ingestion: &I,
+    queue: &IngestionQueue,
+    metrics: &RwLock<ServiceMetrics>,
+    exported: Option<&IngestMetrics>,
+    events: Vec<UsageEvent>,
+) {
+    let count = events.len();
+    if let Err(e) = ingestion.ingest_batch(events).await {
+        error!(count, "Failed to store queued events: {}", e);
+        metrics.write().await.total_write_failures += count as u64;
+    }
+    if let Some(exported) = exported {
+        exported.observe_queue(&queue.stats());
+    }
 }
 
 impl<I: EventIngestion> MeteringGrpcService<I> {
     /// Create a new metering gRPC service.
+    ///
+    /// Fails if `deduplicator` forgets transaction IDs before validation
+    /// stops accepting their events, since replays would then be billed
+    /// twice.
     pub fn new(
         ingestion: Arc<I>,
         deduplicator: Arc<Deduplicator>,
         quota_enforcer: Arc<QuotaEnforcer>,
         config: MeteringServiceConfig,
-    ) -> Self {
-        Self {
+    ) -> Result<Self, WindowConfigError> {
+        config
+            .validation
+            .check_dedup_coverage(deduplicator.config())?;
+        Ok(Self {
             ingestion,
             deduplicator,
             quota_enforcer,
+            late_events: None,
+            queue: config
+                .ingestion_queue
+                .clone()
+                .map(|queue| Arc::new(IngestionQueue::new(queue))),
             validator: EventValidator::new(config.validation.clone()),
+            sampler: None,
             config,
+            clock: Arc::new(SystemClock),
             metrics: Arc::new(RwLock::new(ServiceMetrics::default())),
+            exported_metrics: None,
+        })
+    }
+}
+
+impl<I: EventIngestion, L: LateEventRepository + ?Sized> MeteringGrpcService<I, L> {
+    /// Route events through a late event queue.
+    ///
+    /// Events behind the watermark are queued instead of ingested and are
+    /// not charged against quota.
+    pub fn with_late_event_queue<M: ?Sized>(
+        self,
+        late_events: Arc<LateEventQueue<M>>,
+    ) -> MeteringGrpcService<I, M> {
+        MeteringGrpcService {
+            ingestion: self.ingestion,
+            deduplicator: self.deduplicator,
+            quota_enforcer: self.quota_enforcer,
+            late_events: Some(late_events),
+            queue: self.queue,
+            validator: self.validator,
+            sampler: self.sampler,
+            config: self.config,
+            clock: self.clock,
+            metrics: self.metrics,
+            exported_metrics: self.exported_metrics,
         }
     }
 
+    /// Sample events with `sampler` before storing them, usually the one
+    /// from [`MeteringService::ingestion_sampler`](crate::MeteringService::ingestion_sampler).
+    ///
+    /// Quota checks still see every event at its true quantity; sampled-out
+    /// events are acknowledged as accepted without being stored.
+    pub fn with_sampler(mut self, sampler: Arc<IngestionSampler>) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Use a different time source for timestamp validation.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Export ingested, deduplicated, late and rejected event counts to
+    /// `registry`.
+    pub fn with_metrics(mut self, registry: &MetricsRegistry) -> Self {
+        self.exported_metrics = Some(IngestMetrics::new(registry));
+        self
+    }
+
     /// Ingest a single event.
     #[instrument(skip(self, request), fields(txn_id = %request.event.transaction_id))]
     pub async fn ingest_event(&self, request: IngestEventRequest) -> IngestEventResponse {
+        let server_time = self.clock.now();
+
         // Convert gRPC event to internal event
         let event = match request.event.to_usage_event() {
             Ok(e) => e,
@@ -80,24 +291,26 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
                     success: false,
                     status: IngestStatus::ValidationError,
                     error_message: Some(msg),
+                    server_time,
                 };
             }
         };
 
         // Validate
-        if let Err(e) = self.validator.validate(&event) {
+        if let Err(e) = self.validator.validate_at(&event, server_time) {
             self.record_validation_error().await;
             return IngestEventResponse {
                 success: false,
                 status: IngestStatus::ValidationError,
                 error_message: Some(e.to_string()),
+                server_time,
             };
         }
 
         // Deduplicate
         match self
             .deduplicator
-            .check_and_mark(&event.transaction_id)
+            .check_and_mark_for(&event.organization_id, &event.transaction_id)
             .await
         {
             Ok(DedupResult::Duplicate) => {
@@ -106,6 +319,7 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
                     success: true,
                     status: IngestStatus::Duplicate,
                     error_message: None,
+                    server_time,
                 };
             }
             Ok(DedupResult::New) => {}
@@ -115,25 +329,53 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
             }
         }
 
+        // Divert events behind the watermark
+        let event = match self.admit(event).await {
+            Ok(Some(event)) => event,
+            Ok(None) => {
+                self.record_late().await;
+                return IngestEventResponse {
+                    success: true,
+                    status: IngestStatus::AcceptedLate,
+                    error_message: None,
+                    server_time,
+                };
+            }
+            Err(e) => {
+                self.record_internal_error().await;
+                return IngestEventResponse {
+                    success: false,
+                    status: IngestStatus::InternalError,
+                    error_message: Some(e.to_string()),
+                    server_time,
+                };
+            }
+        };
+
         // Check quota if enabled
         if self.config.enforce_quotas {
-            let quota_result = self.quota_enforcer.check(
+            let quota_result = self.quota_enforcer.check_with_context(
                 &event.organization_id,
                 &event.agent_id,
                 &event.code,
                 event.quantity,
+                Some(event.delegation_depth),
             );
 
             match quota_result {
                 Ok(check) if !check.allowed => {
                     self.record_quota_exceeded().await;
+                    let error_message = match check.denial_reason {
+                        Some(reason @ QuotaDenialReason::DelegationDepthExceeded { .. }) => {
+                            reason.to_string()
+                        }
+                        _ => format!("Quota exceeded: {}% used", check.usage_percentage * 100.0),
+                    };
                     return IngestEventResponse {
                         success: false,
                         status: IngestStatus::QuotaExceeded,
-                        error_message: Some(format!(
-                            "Quota exceeded: {}% used",
-                            check.usage_percentage * 100.0
-                        )),
+                        error_message: Some(error_message),
+                        server_time,
                     };
                 }
                 Err(e) => {
@@ -142,28 +384,56 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
                         success: false,
                         status: IngestStatus::QuotaExceeded,
                         error_message: Some(e.to_string()),
+                        server_time,
                     };
                 }
                 Ok(_) => {} // Quota check passed
             }
         }
 
+        // Sample after the quota check, so quota sees every event
+        let Some(event) = self.sample(event) else {
+            self.record_accepted().await;
+            return IngestEventResponse {
+                success: true,
+                status: IngestStatus::Accepted,
+                error_message: None,
+                server_time,
+            };
+        };
+
         // Ingest
-        match self.ingestion.ingest(event).await {
+        let stored = match &self.queue {
+            Some(queue) => self
+                .enqueue(queue, vec![event])
+                .await
+                .map_err(StoreFailure::Overloaded),
+            None => self
+                .ingestion
+                .ingest(event)
+                .await
+                .map_err(StoreFailure::Internal),
+        };
+        match stored {
             Ok(()) => {
                 self.record_accepted().await;
                 IngestEventResponse {
                     success: true,
                     status: IngestStatus::Accepted,
                     error_message: None,
+                    server_time,
                 }
             }
             Err(e) => {
-                self.record_internal_error().await;
+                match e {
+                    StoreFailure::Overloaded(_) => self.record_overloaded(1).await,
+                    StoreFailure::Internal(_) => self.record_internal_error().await,
+                }
                 IngestEventResponse {
                     success: false,
-                    status: IngestStatus::InternalError,
+                    status: e.status(),
                     error_message: Some(e.to_string()),
+                    server_time,
                 }
             }
         }
@@ -175,9 +445,12 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
         &self,
         request: IngestEventBatchRequest,
     ) -> IngestEventBatchResponse {
+        let server_time = self.clock.now();
         let mut accepted_count = 0u32;
         let mut duplicate_count = 0u32;
         let mut failed_count = 0u32;
+        let mut late_count = 0u32;
+        let mut overloaded_count = 0u32;
         let mut results = Vec::new();
 
         // Enforce max batch size
@@ -186,6 +459,7 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
                 accepted_count: 0,
                 duplicate_count: 0,
                 failed_count: request.events.len() as u32,
+                late_count: 0,
                 results: vec![EventResult {
                     index: 0,
                     status: IngestStatus::ValidationError,
@@ -195,75 +469,76 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
                         self.config.max_batch_size
                     )),
                 }],
+                server_time,
             };
         }
 
-        // Convert and validate all events first
-        let mut valid_events = Vec::with_capacity(request.events.len());
-        let mut event_indices = Vec::with_capacity(request.events.len());
-
+        // Convert all events, then validate the converted ones as one batch
+        let mut converted = Vec::with_capacity(request.events.len());
+        let mut converted_indices = Vec::with_capacity(request.events.len());
+        let mut failures = Vec::new();
         for (idx, grpc_event) in request.events.iter().enumerate() {
-            // Convert
-            let event = match grpc_event.to_usage_event() {
-                Ok(e) => e,
-                Err(msg) => {
-                    failed_count += 1;
-                    if !request.continue_on_error {
-                        results.push(EventResult {
-                            index: idx as u32,
-                            status: IngestStatus::ValidationError,
-                            error_message: Some(msg),
-                        });
-                        return IngestEventBatchResponse {
-                            accepted_count,
-                            duplicate_count,
-                            failed_count,
-                            results,
-                        };
-                    }
-                    results.push(EventResult {
-                        index: idx as u32,
-                        status: IngestStatus::ValidationError,
-                        error_message: Some(msg),
-                    });
-                    continue;
+            match grpc_event.to_usage_event() {
+                Ok(event) => {
+                    converted.push(event);
+                    converted_indices.push(idx);
                 }
-            };
-
-            // Validate
-            if let Err(e) = self.validator.validate(&event) {
-                failed_count += 1;
-                if !request.continue_on_error {
-                    results.push(EventResult {
-                        index: idx as u32,
-                        status: IngestStatus::ValidationError,
-                        error_message: Some(e.to_string()),
-                    });
-                    return IngestEventBatchResponse {
-                        accepted_count,
-                        duplicate_count,
-                        failed_count,
-                        results,
-                    };
+                Err(msg) => failures.push((idx, msg)),
+            }
+        }
+        let validation = self.validator.validate_batch_at(&converted, server_time);
+
+        let mut valid_events = Vec::with_capacity(converted.len());
+        let mut event_indices = Vec::with_capacity(converted.len());
+        for ((event, idx), outcome) in converted
+            .into_iter()
+            .zip(converted_indices)
+            .zip(validation.outcomes)
+        {
+            match outcome {
+                Ok(()) => {
+                    valid_events.push(event);
+                    event_indices.push(idx);
                 }
-                results.push(EventResult {
-                    index: idx as u32,
-                    status: IngestStatus::ValidationError,
-                    error_message: Some(e.to_string()),
-                });
-                continue;
+                Err(e) => failures.push((idx, e.to_string())),
             }
+        }
 
-            valid_events.push(event);
-            event_indices.push(idx);
+        // Report failures in input order; without continue_on_error the
+        // batch stops at the first one
+        failures.sort_by_key(|(idx, _)| *idx);
+        if !request.continue_on_error {
+            failures.truncate(1);
+        }
+        let validation_failed = failures.len() as u32;
+        failed_count += validation_failed;
+        results.extend(failures.into_iter().map(|(idx, msg)| EventResult {
+            index: idx as u32,
+            status: IngestStatus::ValidationError,
+            error_message: Some(msg),
+        }));
+        if !request.continue_on_error && failed_count > 0 {
+            if let Some(exported) = &self.exported_metrics {
+                exported
+                    .rejected_validation
+                    .inc_by(validation_failed as u64);
+            }
+            return IngestEventBatchResponse {
+                accepted_count,
+                duplicate_count,
+                failed_count,
+                late_count,
+                results,
+                server_time,
+            };
         }
 
-        // Batch deduplication check
-        let txn_ids: Vec<&str> = valid_events
+        // Batch deduplication check: one round trip for the whole batch
+        let txn_ids: Vec<_> = valid_events
             .iter()
-            .map(|e| e.transaction_id.as_str())
+            .map(|e| (e.organization_id, e.transaction_id.as_str()))
             .collect();
-        let dedup_results = match self.deduplicator.check_and_mark_batch(&txn_ids).await {
+        let dedup_results = match self.deduplicator.check_batch_for(&txn_ids).await {
             Ok(r) => r,
             Err(e) => {
                 error!("Batch dedup check failed: {}", e);
@@ -272,27 +547,84 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
             }
         };
 
-        // Filter out duplicates
+        // Filter out duplicates and divert late events
         let mut events_to_ingest = Vec::new();
-        for (event, dedup_result) in valid_events.into_iter().zip(dedup_results.iter()) {
+        let mut ingest_indices = Vec::new();
+        for ((event, dedup_result), idx) in valid_events
+            .into_iter()
+            .zip(dedup_results.iter())
+            .zip(event_indices)
+        {
             if dedup_result.is_duplicate() {
                 duplicate_count += 1;
-            } else {
-                events_to_ingest.push(event);
+                continue;
             }
+            match self.admit(event).await {
+                Ok(Some(event)) => {
+                    events_to_ingest.push(event);
+                    ingest_indices.push(idx);
+                }
+                Ok(None) => late_count += 1,
+                Err(e) => {
+                    failed_count += 1;
+                    results.push(EventResult {
+                        index: idx as u32,
+                        status: IngestStatus::InternalError,
+                        error_message: Some(e.to_string()),
+                    });
+                }
+            }
+        }
+
+        // Sampled-out events are accepted without being stored
+        if self.sampler.is_some() {
+            let mut kept = Vec::with_capacity(events_to_ingest.len());
+            let mut kept_indices = Vec::with_capacity(ingest_indices.len());
+            for (event, idx) in events_to_ingest.into_iter().zip(ingest_indices) {
+                match self.sample(event) {
+                    Some(event) => {
+                        kept.push(event);
+                        kept_indices.push(idx);
+                    }
+                    None => accepted_count += 1,
+                }
+            }
+            events_to_ingest = kept;
+            ingest_indices = kept_indices;
         }
 
         // Batch ingest remaining events
         if !events_to_ingest.is_empty() {
-            match self.ingestion.ingest_batch(events_to_ingest).await {
+            let stored = match &self.queue {
+                Some(queue) => {
+                    let count = events_to_ingest.len();
+                    self.enqueue(queue, events_to_ingest)
+                        .await
+                        .map(|()| count)
+                        .map_err(StoreFailure::Overloaded)
+                }
+                None => self
+                    .ingestion
+                    .ingest_batch(events_to_ingest)
+                    .await
+                    .map_err(StoreFailure::Internal),
+            };
+            match stored {
                 Ok(count) => {
-                    accepted_count = count as u32;
+                    accepted_count += count as u32;
                 }
                 Err(e) => {
                     error!("Batch ingestion failed: {}", e);
                     // All remaining events failed
-                    failed_count += accepted_count;
-                    accepted_count = 0;
+                    failed_count += ingest_indices.len() as u32;
+                    if let StoreFailure::Overloaded(_) = e {
+                        overloaded_count = ingest_indices.len() as u32;
+                    }
+                    results.extend(ingest_indices.into_iter().map(|idx| EventResult {
+                        index: idx as u32,
+                        status: e.status(),
+                        error_message: Some(e.to_string()),
+                    }));
                 }
             }
         }
@@ -303,14 +635,99 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
             metrics.total_accepted += accepted_count as u64;
             metrics.total_duplicates += duplicate_count as u64;
             metrics.total_failed += failed_count as u64;
+            metrics.total_late += late_count as u64;
+            metrics.total_overloaded += overloaded_count as u64;
+        }
+        if let Some(exported) = &self.exported_metrics {
+            exported.ingested.inc_by(accepted_count as u64);
+            exported.deduplicated.inc_by(duplicate_count as u64);
+            exported.late.inc_by(late_count as u64);
+            exported
+                .rejected_validation
+                .inc_by(validation_failed as u64);
+            exported.rejected_overloaded.inc_by(overloaded_count as u64);
+            exported
+                .rejected_internal
+                .inc_by((failed_count - validation_failed - overloaded_count) as u64);
         }
 
         IngestEventBatchResponse {
             accepted_count,
             duplicate_count,
             failed_count,
+            late_count,
             results,
+            server_time,
+        }
+    }
+
+    /// Ingest a stream of events, flushing them in batches.
+    ///
+    /// Events are buffered until `stream_batch_size` of them arrive or the
+    /// oldest has waited `stream_flush_interval`, then go through
+    /// [`ingest_event_batch`](Self::ingest_event_batch) with
+    /// `continue_on_error` set. The stream is not polled during a flush,
+    /// so the buffer never outgrows one batch and transport flow control
+    /// pauses the sender.
+    ///
+    /// If the stream fails, events already received are flushed before the
+    /// error is returned: flushed batches stay stored, and deduplication
+    /// makes it safe for the client to resend the whole stream.
+    #[instrument(skip(self, events))]
+    pub async fn ingest_event_stream<S, E>(&self, events: S) -> Result<IngestEventStreamResponse, E>
+    where
+        S: Stream<Item = Result<GrpcUsageEvent, E>>,
+    {
+        let batch_size = self
+            .config
+            .stream_batch_size
+            .min(self.config.max_batch_size)
+            .max(1);
+        let mut summary = IngestEventStreamResponse {
+            accepted_count: 0,
+            duplicate_count: 0,
+            failed_count: 0,
+            late_count: 0,
+            batch_count: 0,
+            results: Vec::new(),
+            server_time: self.clock.now(),
+        };
+        let mut buffer = Vec::with_capacity(batch_size);
+        let mut flushed = 0u32;
+        let mut deadline = Instant::now();
+
+        tokio::pin!(events);
+        loop {
+            let next = if buffer.is_empty() {
+                Some(events.next().await)
+            } else {
+                tokio::time::timeout_at(deadline, events.next()).await.ok()
+            };
+            match next {
+                // Flush interval elapsed
+                None => {}
+                Some(Some(Ok(event))) => {
+                    if buffer.is_empty() {
+                        deadline = Instant::now() + self.config.stream_flush_interval;
+                    }
+                    buffer.push(event);
+                    if buffer.len() < batch_size {
+                        continue;
+                    }
+                }
+                Some(Some(Err(e))) => {
+                    self.flush_stream_batch(&mut buffer, &mut flushed, &mut summary)
+                        .await;
+                    return Err(e);
+                }
+                Some(None) => break,
+            }
+            self.flush_stream_batch(&mut buffer, &mut flushed, &mut summary)
+                .await;
         }
+        self.flush_stream_batch(&mut buffer, &mut flushed, &mut summary)
+            .await;
+        Ok(summary)
     }
 
     /// Check if a quota allows usage.
@@ -358,7 +775,12 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
                 denial_reason: if check.allowed {
                     None
                 } else {
-                    Some("Quota exceeded".to_string())
+                    Some(
+                        check
+                            .denial_reason
+                            .unwrap_or(QuotaDenialReason::LimitExceeded)
+                            .to_string(),
+                    )
                 },
             },
             Err(e) => CheckQuotaResponse {
@@ -405,39 +827,207 @@ impl<I: EventIngestion> MeteringGrpcService<I> {
 
     /// Get service metrics.
     pub async fn get_metrics(&self) -> ServiceMetrics {
-        self.metrics.read().await.clone()
+        let mut metrics = self.metrics.read().await.clone();
+        metrics.queue = self.queue.as_ref().map(|queue| queue.stats());
+        metrics
+    }
+
+    /// Spawn the batch writer storing queued events, if the service has an
+    /// ingestion queue.
+    ///
+    /// The writer stores up to the queue's `batch_size` events per call. On
+    /// shutdown it closes the queue, so later events are refused as
+    /// overloaded, and stores everything already queued before exiting.
+    pub fn spawn_batch_writer(&self, shutdown: &ShutdownCoordinator) -> Option<JoinHandle<()>>
+    where
+        I: Sync + 'static,
+    {
+        let queue = Arc::clone(self.queue.as_ref()?);
+        let ingestion = Arc::clone(&self.ingestion);
+        let metrics = Arc::clone(&self.metrics);
+        let exported = self.exported_metrics.clone();
+        Some(
+            shutdown.spawn("metering.ingestion_writer", move |guard| async move {
+                loop {
+                    let events = tokio::select! {
+                        biased;
+                        _ = guard.cancelled() => break,
+                        events = queue.next_batch() => events,
+                    };
+                    write_queued(&*ingestion, &queue, &metrics, exported.as_ref(), events).await;
+                }
+
+                // Events already acknowledged must reach the store
+                queue.close();
+                loop {
+                    let events = queue.take_batch();
+                    if events.is_empty() {
+                        break;
+                    }
+                    write_queued(&*ingestion, &queue, &metrics, exported.as_ref(), events).await;
+                }
+                tracing::info!("Ingestion queue flushed");
+            }),
+        )
     }
 
     // ─────────────────────────────────────────────────────────────────────────
     // Private Methods
     // ─────────────────────────────────────────────────────────────────────────
 
+    /// Pass an event through the late event queue, if one is configured.
+    ///
+    /// Returns `None` when the event was queued as late.
+    async fn admit(&self, event: UsageEvent) -> Result<Option<UsageEvent>, CretoError> {
+        let Some(late_events) = &self.late_events else {
+            return Ok(Some(event));
+        };
+        match late_events.admit(event).await? {
+            Admitted::OnTime(event) => Ok(Some(event)),
+            Admitted::Late(_) => Ok(None),
+        }
+    }
+
+    /// Apply the sampler, if any; `None` means the event is not stored.
+    fn sample(&self, event: UsageEvent) -> Option<UsageEvent> {
+        match &self.sampler {
+            Some(sampler) => sampler.sample(event),
+            None => Some(event),
+        }
+    }
+
+    /// Ingest buffered stream events and fold the outcome into `summary`.
+    ///
+    /// `flushed` counts events taken from the stream by earlier flushes, so
+    /// result indices are positions in the stream.
+    async fn flush_stream_batch(
+        &self,
+        buffer: &mut Vec<GrpcUsageEvent>,
+        flushed: &mut u32,
+        summary: &mut IngestEventStreamResponse,
+    ) {
+        if buffer.is_empty() {
+            return;
+        }
+        let events = std::mem::take(buffer);
+        let count = events.len() as u32;
+        let response = self
+            .ingest_event_batch(IngestEventBatchRequest {
+                events,
+                continue_on_error: true,
+            })
+            .await;
+
+        summary.accepted_count += response.accepted_count;
+        summary.duplicate_count += response.duplicate_count;
+        summary.failed_count += response.failed_count;
+        summary.late_count += response.late_count;
+        summary.batch_count += 1;
+        summary.server_time = response.server_time;
+        summary
+            .results
+            .extend(response.results.into_iter().map(|result| EventResult {
+                index: *flushed + result.index,
+                ..result
+            }));
+        *flushed += count;
+    }
+
+    /// Queue admitted events for the batch writer.
+    ///
+    /// Events the queue sheds are forgotten by the deduplicator, so a client
+    /// resending them is not told they are duplicates.
+    async fn enqueue(
+        &self,
+        queue: &IngestionQueue,
+        events: Vec<UsageEvent>,
+    ) -> Result<(), QueueRejection> {
+        let transaction_ids: Vec<String> =
+            events.iter().map(|e| e.transaction_id.clone()).collect();
+        let result = queue.push(events).await;
+        let (outcome, shed) = match &result {
+            Ok(dropped) => (
+                "dropped",
+                dropped.iter().map(|e| e.transaction_id.clone()).collect(),
+            ),
+            Err(QueueRejection::TimedOut) => ("timed_out", transaction_ids),
+            Err(QueueRejection::Full | QueueRejection::Closed) => ("rejected", transaction_ids),
+        };
+
+        if !shed.is_empty() {
+            warn!(count = shed.len(), outcome, "Ingestion queue shed events");
+        }
+        for transaction_id in &shed {
+            if let Err(e) = self.deduplicator.clear(transaction_id).await {
+                error!(
+                    "Failed to forget shed transaction {}: {}",
+                    transaction_id, e
+                );
+            }
+        }
+        if let Some(exported) = &self.exported_metrics {
+            exported
+                .shed
+                .with_label_values(&[outcome])
+                .inc_by(shed.len() as u64);
+            exported.observe_queue(&queue.stats());
+        }
+        result.map(|_| ())
+    }
+
     async fn record_accepted(&self) {
         let mut metrics = self.metrics.write().await;
         metrics.total_accepted += 1;
+        self.export(|m| &m.ingested);
     }
 
     async fn record_duplicate(&self) {
         let mut metrics = self.metrics.write().await;
         metrics.total_duplicates += 1;
+        self.export(|m| &m.deduplicated);
+    }
+
+    async fn record_late(&self) {
+        let mut metrics = self.metrics.write().await;
+        metrics.total_late += 1;
+        self.export(|m| &m.late);
     }
 
     async fn record_validation_error(&self) {
         let mut metrics = self.metrics.write().await;
         metrics.total_validation_errors += 1;
         metrics.total_failed += 1;
+        self.export(|m| &m.rejected_validation);
     }
 
     async fn record_quota_exceeded(&self) {
         let mut metrics = self.metrics.write().await;
         metrics.total_quota_exceeded += 1;
         metrics.total_failed += 1;
+        self.export(|m| &m.rejected_quota);
+    }
+
+    async fn record_overloaded(&self, count: u64) {
+        let mut metrics = self.metrics.write().await;
+        metrics.total_overloaded += count;
+        metrics.total_failed += count;
+        if let Some(exported) = &self.exported_metrics {
+            exported.rejected_overloaded.inc_by(count);
+        }
     }
 
     async fn record_internal_error(&self) {
         let mut metrics = self.metrics.write().await;
         metrics.total_internal_errors += 1;
         metrics.total_failed += 1;
+        self.export(|m| &m.rejected_internal);
+    }
+
+    /// Increment an exported counter, if metrics are exported.
+    fn export(&self, counter: impl FnOnce(&IngestMetrics) -> &Counter) {
+        if let Some(exported) = &self.exported_metrics {
+            counter(exported).inc();
+        }
     }
 }
 
@@ -450,12 +1040,17 @@ pub struct ServiceMetrics {
     pub total_validation_errors: u64,
     pub total_quota_exceeded: u64,
     pub total_internal_errors: u64,
+    pub total_late: u64,
+    pub total_overloaded: u64,
+    pub total_write_failures: u64,
+    /// Ingestion queue depth and shedding (None without a queue).
+    pub queue: Option<IngestionQueueStats>,
 }
 
 impl ServiceMetrics {
-    /// Get total events processed (success + failure + duplicates).
+    /// Get total events processed (success + failure + duplicates + late).
     pub fn total_processed(&self) -> u64 {
-        self.total_accepted + self.total_duplicates + self.total_failed
+        self.total_accepted + self.total_duplicates + self.total_failed + self.total_late
     }
 
     /// Get success rate as a percentage.
@@ -473,6 +1068,7 @@ impl ServiceMetrics {
 mod tests {
     use super::*;
     use crate::dedup::DedupConfig;
+    use crate::grpc::queue::LoadSheddingPolicy;
     use crate::UsageEvent;
     use creto_common::CretoError;
 
@@ -503,6 +1099,7 @@ mod tests {
                 ..Default::default()
             },
         )
+        .unwrap()
     }
 
     fn test_grpc_event() -> GrpcUsageEvent {
@@ -517,6 +1114,8 @@ mod tests {
             timestamp: None,
             properties: None,
             delegation_depth: 0,
+            correlation_id: None,
+            caused_by: None,
         }
     }
 
@@ -583,6 +1182,207 @@ mod tests {
         assert_eq!(response.failed_count, 0);
     }
 
+    #[tokio::test]
+    async fn test_batch_with_scattered_duplicates() {
+        let service = create_test_service();
+
+        // Ingested earlier, then replayed at the end of the batch
+        let replayed = test_grpc_event();
+        service
+            .ingest_event(IngestEventRequest {
+                event: replayed.clone(),
+            })
+            .await;
+
+        // Every 7th event repeats one from earlier in the batch, and every
+        // 100th is invalid
+        let mut events: Vec<GrpcUsageEvent> = Vec::with_capacity(1000);
+        for i in 0..999 {
+            let event = if i % 7 == 6 {
+                events[i / 2].clone()
+            } else {
+                let mut event = test_grpc_event();
+                if i % 100 == 50 {
+                    event.quantity = 0;
+                }
+                event
+            };
+            events.push(event);
+        }
+        events.push(replayed);
+        let invalid: Vec<u32> = (0..events.len() as u32)
+            .filter(|i| events[*i as usize].quantity == 0)
+            .collect();
+        let repeats = events.iter().enumerate().filter(|(i, e)| {
+            e.quantity > 0
+                && events[..*i]
+                    .iter()
+                    .any(|earlier| earlier.transaction_id == e.transaction_id)
+        });
+        let expected_duplicates = repeats.count() as u32 + 1;
+
+        let response = service
+            .ingest_event_batch(IngestEventBatchRequest {
+                events,
+                continue_on_error: true,
+            })
+            .await;
+
+        assert_eq!(response.failed_count, invalid.len() as u32);
+        assert_eq!(response.duplicate_count, expected_duplicates);
+        assert_eq!(
+            response.accepted_count,
+            1000 - invalid.len() as u32 - expected_duplicates
+        );
+        // Failures are reported at their own index, in input order
+        let failed: Vec<u32> = response.results.iter().map(|r| r.index).collect();
+        assert_eq!(failed, invalid);
+    }
+
+    #[tokio::test]
+    async fn test_batch_stops_at_first_invalid_event() {
+        let service = create_test_service();
+        let mut events = vec![test_grpc_event(), test_grpc_event(), test_grpc_event()];
+        events[1].quantity = 0;
+        events[2].organization_id = "not-a-uuid".to_string();
+
+        let response = service
+            .ingest_event_batch(IngestEventBatchRequest {
+                events,
+                continue_on_error: false,
+            })
+            .await;
+
+        assert_eq!(response.failed_count, 1);
+        assert_eq!(response.accepted_count, 0);
+        assert_eq!(response.results.len(), 1);
+        assert_eq!(response.results[0].index, 1);
+    }
+
+    fn create_stream_service(stream_batch_size: usize) -> MeteringGrpcService<MockIngestion> {
+        MeteringGrpcService::new(
+            Arc::new(MockIngestion),
+            Arc::new(Deduplicator::local_only(DedupConfig::default())),
+            Arc::new(QuotaEnforcer::new()),
+            MeteringServiceConfig {
+                enforce_quotas: false,
+                stream_batch_size,
+                stream_flush_interval: Duration::from_millis(50),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stream_flushes_in_batches_with_stream_indices() {
+        use tonic::codegen::tokio_stream;
+
+        let service = create_stream_service(3);
+        let mut events: Vec<GrpcUsageEvent> = (0..8).map(|_| test_grpc_event()).collect();
+        events[4].quantity = 0;
+        events[6] = events[1].clone();
+
+        let response = service
+            .ingest_event_stream(tokio_stream::iter(
+                events.into_iter().map(Ok::<_, CretoError>),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(response.batch_count, 3);
+        assert_eq!(response.accepted_count, 6);
+        assert_eq!(response.duplicate_count, 1);
+        assert_eq!(response.failed_count, 1);
+        assert_eq!(response.results.len(), 1);
+        assert_eq!(response.results[0].index, 4);
+        assert_eq!(response.results[0].status, IngestStatus::ValidationError);
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_keeps_received_events() {
+        use tonic::codegen::tokio_stream;
+
+        let service = create_stream_service(100);
+        let items = vec![
+            Ok(test_grpc_event()),
+            Ok(test_grpc_event()),
+            Err(CretoError::Internal("client went away".to_string())),
+            Ok(test_grpc_event()),
+        ];
+
+        let result = service.ingest_event_stream(tokio_stream::iter(items)).await;
+
+        assert!(result.is_err());
+        let metrics = service.get_metrics().await;
+        assert_eq!(metrics.total_accepted, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_flushes_after_interval_while_open() {
+        use tonic::codegen::tokio_stream::{self, StreamExt};
+
+        let service = Arc::new(create_stream_service(100));
+        let events = tokio_stream::iter(vec![
+            Ok::<_, CretoError>(test_grpc_event()),
+            Ok(test_grpc_event()),
+        ])
+        .chain(tokio_stream::pending());
+        let task = tokio::spawn({
+            let service = Arc::clone(&service);
+            async move { service.ingest_event_stream(events).await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(service.get_metrics().await.total_accepted, 0);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(service.get_metrics().await.total_accepted, 2);
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_late_event_queued_not_ingested() {
+        use crate::late_events::{InMemoryLateEventRepository, WatermarkConfig};
+
+        let queue = Arc::new(LateEventQueue::new(
+            Arc::new(InMemoryLateEventRepository::new()),
+            WatermarkConfig::default(),
+        ));
+        let service = create_test_service().with_late_event_queue(queue);
+
+        let on_time = test_grpc_event();
+        let org_id = on_time.organization_id.clone();
+        let mut late = test_grpc_event();
+        late.organization_id = org_id.clone();
+        late.timestamp = Some(chrono::Utc::now() - chrono::Duration::days(3));
+
+        let r1 = service
+            .ingest_event(IngestEventRequest { event: on_time })
+            .await;
+        assert_eq!(r1.status, IngestStatus::Accepted);
+        let r2 = service
+            .ingest_event(IngestEventRequest { event: late })
+            .await;
+        assert!(r2.success);
+        assert_eq!(r2.status, IngestStatus::AcceptedLate);
+
+        let mut batch_late = test_grpc_event();
+        batch_late.organization_id = org_id;
+        batch_late.timestamp = Some(chrono::Utc::now() - chrono::Duration::days(2));
+        let batch = service
+            .ingest_event_batch(IngestEventBatchRequest {
+                events: vec![batch_late],
+                continue_on_error: true,
+            })
+            .await;
+        assert_eq!(batch.late_count, 1);
+        assert_eq!(batch.accepted_count, 0);
+
+        let metrics = service.get_metrics().await;
+        assert_eq!(metrics.total_accepted, 1);
+        assert_eq!(metrics.total_late, 2);
+    }
+
     #[tokio::test]
     async fn test_metrics() {
         let service = create_test_service();
@@ -604,6 +1404,401 @@ mod tests {
         assert_eq!(metrics.total_processed(), 2);
     }
 
+    #[tokio::test]
+    async fn test_exported_metrics() {
+        let registry = MetricsRegistry::new();
+        let service = create_test_service().with_metrics(&registry);
+
+        let event = test_grpc_event();
+        let mut invalid = test_grpc_event();
+        invalid.quantity = -1;
+        for event in [event.clone(), event, invalid] {
+            service.ingest_event(IngestEventRequest { event }).await;
+        }
+        let mut batch_invalid = test_grpc_event();
+        batch_invalid.quantity = 0;
+        service
+            .ingest_event_batch(IngestEventBatchRequest {
+                events: vec![test_grpc_event(), batch_invalid],
+                continue_on_error: true,
+            })
+            .await;
+
+        let text = registry.render_metrics();
+        assert!(text.contains("# TYPE creto_metering_events_ingested_total counter\n"));
+        assert!(text.contains("creto_metering_events_ingested_total 2\n"));
+        assert!(text.contains("creto_metering_events_deduplicated_total 1\n"));
+        assert!(text.contains("creto_metering_events_rejected_total{reason=\"validation\"} 2\n"));
+        assert!(text.contains("creto_metering_events_rejected_total{reason=\"internal\"} 0\n"));
+    }
+
+    /// Ingestion that keeps every stored event.
+    #[derive(Default)]
+    struct RecordingIngestion {
+        stored: std::sync::Mutex<Vec<UsageEvent>>,
+    }
+
+    impl EventIngestion for RecordingIngestion {
+        async fn ingest(&self, event: UsageEvent) -> Result<(), CretoError> {
+            self.ingest_batch(vec![event]).await.map(|_| ())
+        }
+
+        async fn ingest_batch(&self, events: Vec<UsageEvent>) -> Result<usize, CretoError> {
+            let count = events.len();
+            self.stored.lock().unwrap().extend(events);
+            Ok(count)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sampling_applies_after_quota_check() {
+        use crate::quota::{Quota, QuotaPeriod};
+        use crate::sampling::{SamplingRule, SAMPLE_FACTOR_PROPERTY};
+        use creto_common::OrganizationId;
+        use tonic::codegen::tokio_stream;
+
+        let org_id = OrganizationId::new();
+        let quota_enforcer = Arc::new(QuotaEnforcer::new());
+        quota_enforcer.register_quota(&Quota::new(org_id, "heartbeats", 10, QuotaPeriod::Daily));
+        let sampler = Arc::new(IngestionSampler::new());
+        sampler
+            .set_rule(SamplingRule::every_nth(org_id, "heartbeats", 5))
+            .unwrap();
+        let ingestion = Arc::new(RecordingIngestion::default());
+        let service = MeteringGrpcService::new(
+            Arc::clone(&ingestion),
+            Arc::new(Deduplicator::local_only(DedupConfig::default())),
+            quota_enforcer,
+            MeteringServiceConfig::default(),
+        )
+        .unwrap()
+        .with_sampler(sampler);
+        let heartbeat = |quantity| GrpcUsageEvent {
+            organization_id: org_id.as_uuid().to_string(),
+            code: "heartbeats".to_string(),
+            quantity,
+            ..test_grpc_event()
+        };
+
+        // Quota checks the true quantity: scaled by 5, 3 would exceed 10
+        for _ in 0..5 {
+            let response = service
+                .ingest_event(IngestEventRequest {
+                    event: heartbeat(3),
+                })
+                .await;
+            assert_eq!(response.status, IngestStatus::Accepted);
+        }
+        let response = service
+            .ingest_event(IngestEventRequest {
+                event: heartbeat(11),
+            })
+            .await;
+        assert_eq!(response.status, IngestStatus::QuotaExceeded);
+
+        let response = service
+            .ingest_event_batch(IngestEventBatchRequest {
+                events: (0..10).map(|_| heartbeat(3)).collect(),
+                continue_on_error: true,
+            })
+            .await;
+        assert_eq!(response.accepted_count, 10);
+        assert_eq!(response.failed_count, 0);
+
+        let events = tokio_stream::iter((0..5).map(|_| Ok::<_, ()>(heartbeat(3))));
+        let summary = service.ingest_event_stream(events).await.unwrap();
+        assert_eq!(summary.accepted_count, 5);
+
+        assert_eq!(service.get_metrics().await.total_accepted, 20);
+
+        // One in five of the 20 accepted events is stored, scaled up
+        let stored = ingestion.stored.lock().unwrap();
+        assert_eq!(stored.len(), 4);
+        for event in stored.iter() {
+            assert_eq!(event.quantity, 15);
+            assert_eq!(event.properties[SAMPLE_FACTOR_PROPERTY], 5);
+        }
+    }
+
+    /// Ingestion that takes `delay` per call, so queued events back up.
+    struct SlowIngestion {
+        delay: Duration,
+        stored: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl EventIngestion for SlowIngestion {
+        async fn ingest(&self, event: UsageEvent) -> Result<(), CretoError> {
+            self.ingest_batch(vec![event]).await.map(|_| ())
+        }
+
+        async fn ingest_batch(&self, events: Vec<UsageEvent>) -> Result<usize, CretoError> {
+            tokio::time::sleep(self.delay).await;
+            let mut stored = self.stored.lock().unwrap();
+            stored.extend(events.iter().map(|e| e.transaction_id.clone()));
+            Ok(events.len())
+        }
+    }
+
+    /// A queued service whose writer stores one event per second.
+    fn create_queued_service(
+        policy: LoadSheddingPolicy,
+        capacity: usize,
+    ) -> (Arc<SlowIngestion>, MeteringGrpcService<SlowIngestion>) {
+        let ingestion = Arc::new(SlowIngestion {
+            delay: Duration::from_secs(1),
+            stored: std::sync::Mutex::new(Vec::new()),
+        });
+        let service = MeteringGrpcService::new(
+            Arc::clone(&ingestion),
+            Arc::new(Deduplicator::local_only(DedupConfig::default())),
+            Arc::new(QuotaEnforcer::new()),
+            MeteringServiceConfig {
+                enforce_quotas: false,
+                ingestion_queue: Some(IngestionQueueConfig {
+                    capacity,
+                    policy,
+                    batch_size: 1,
+                }),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        (ingestion, service)
+    }
+
+    async fn ingest(
+        service: &MeteringGrpcService<SlowIngestion>,
+        event: &GrpcUsageEvent,
+    ) -> IngestStatus {
+        service
+            .ingest_event(IngestEventRequest {
+                event: event.clone(),
+            })
+            .await
+            .status
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queue_rejects_new_events_when_full() {
+        let (ingestion, service) = create_queued_service(LoadSheddingPolicy::RejectNew, 2);
+        let shutdown = ShutdownCoordinator::new();
+        service.spawn_batch_writer(&shutdown).unwrap();
+
+        // The writer takes the first event and is busy storing it
+        let events: Vec<_> = (0..4).map(|_| test_grpc_event()).collect();
+        assert_eq!(ingest(&service, &events[0]).await, IngestStatus::Accepted);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(ingest(&service, &events[1]).await, IngestStatus::Accepted);
+        assert_eq!(ingest(&service, &events[2]).await, IngestStatus::Accepted);
+
+        // A rejected event is not remembered, so a retry is not a duplicate
+        assert_eq!(ingest(&service, &events[3]).await, IngestStatus::Overloaded);
+        assert_eq!(ingest(&service, &events[3]).await, IngestStatus::Overloaded);
+        let batch = service
+            .ingest_event_batch(IngestEventBatchRequest {
+                events: vec![test_grpc_event()],
+                continue_on_error: true,
+            })
+            .await;
+        assert_eq!(batch.failed_count, 1);
+        assert_eq!(batch.results[0].status, IngestStatus::Overloaded);
+
+        let metrics = service.get_metrics().await;
+        let queue = metrics.queue.unwrap();
+        assert_eq!(queue.depth, 2);
+        assert_eq!(queue.high_watermark, 2);
+        assert_eq!(queue.rejected, 3);
+        assert_eq!(metrics.total_overloaded, 3);
+        assert_eq!(metrics.total_accepted, 3);
+
+        // Shutdown stores everything acknowledged, then refuses new events
+        assert!(shutdown.shutdown(Duration::from_secs(10)).await.is_clean());
+        let stored = ingestion.stored.lock().unwrap().clone();
+        let expected: Vec<_> = events[..3]
+            .iter()
+            .map(|e| e.transaction_id.clone())
+            .collect();
+        assert_eq!(stored, expected);
+        assert_eq!(ingest(&service, &events[3]).await, IngestStatus::Overloaded);
+        assert_eq!(service.get_metrics().await.queue.unwrap().depth, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queue_drops_oldest_events_when_full() {
+        let registry = MetricsRegistry::new();
+        let (ingestion, service) = create_queued_service(LoadSheddingPolicy::DropOldest, 2);
+        let service = service.with_metrics(&registry);
+        let shutdown = ShutdownCoordinator::new();
+        service.spawn_batch_writer(&shutdown).unwrap();
+
+        let events: Vec<_> = (0..4).map(|_| test_grpc_event()).collect();
+        assert_eq!(ingest(&service, &events[0]).await, IngestStatus::Accepted);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        for event in &events[1..] {
+            assert_eq!(ingest(&service, event).await, IngestStatus::Accepted);
+        }
+
+        // The dropped event is forgotten, so resending it is accepted again
+        assert_eq!(ingest(&service, &events[1]).await, IngestStatus::Accepted);
+        let queue = service.get_metrics().await.queue.unwrap();
+        assert_eq!(queue.dropped, 2);
+        assert_eq!(queue.depth, 2);
+
+        assert!(shutdown.shutdown(Duration::from_secs(10)).await.is_clean());
+        let stored = ingestion.stored.lock().unwrap().clone();
+        let expected: Vec<_> = [0, 3, 1]
+            .iter()
+            .map(|&i| events[i].transaction_id.clone())
+            .collect();
+        assert_eq!(stored, expected);
+
+        let text = registry.render_metrics();
+        assert!(text.contains("creto_metering_events_shed_total{outcome=\"dropped\"} 2\n"));
+        assert!(text.contains("creto_metering_ingest_queue_high_watermark 2\n"));
+        assert!(text.contains("creto_metering_ingest_queue_depth 0\n"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_queue_blocks_until_room_or_timeout() {
+        let policy = LoadSheddingPolicy::BlockWithTimeout(Duration::from_secs(2));
+        let (_, service) = create_queued_service(policy, 1);
+        let shutdown = ShutdownCoordinator::new();
+        service.spawn_batch_writer(&shutdown).unwrap();
+
+        // Room frees once the writer finishes the first event
+        let start = tokio::time::Instant::now();
+        for _ in 0..3 {
+            assert_eq!(
+                ingest(&service, &test_grpc_event()).await,
+                IngestStatus::Accepted
+            );
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+        shutdown.shutdown(Duration::from_secs(10)).await;
+
+        let policy = LoadSheddingPolicy::BlockWithTimeout(Duration::from_millis(500));
+        let (_, service) = create_queued_service(policy, 1);
+        let shutdown = ShutdownCoordinator::new();
+        service.spawn_batch_writer(&shutdown).unwrap();
+
+        let start = tokio::time::Instant::now();
+        for _ in 0..2 {
+            assert_eq!(
+                ingest(&service, &test_grpc_event()).await,
+                IngestStatus::Accepted
+            );
+        }
+        assert_eq!(
+            ingest(&service, &test_grpc_event()).await,
+            IngestStatus::Overloaded
+        );
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+        let queue = service.get_metrics().await.queue.unwrap();
+        assert_eq!(queue.timed_out, 1);
+        assert_eq!(queue.total_shed(), 1);
+        shutdown.shutdown(Duration::from_secs(10)).await;
+    }
+
+    #[tokio::test]
+    async fn test_future_skew_rejected_with_offset_and_server_time() {
+        use creto_common::TestClock;
+
+        let now = chrono::Utc::now();
+        let service = create_test_service().with_clock(Arc::new(TestClock::new(now)));
+
+        let mut event = test_grpc_event();
+        event.timestamp = Some(now + chrono::Duration::minutes(130));
+        let response = service.ingest_event(IngestEventRequest { event }).await;
+
+        assert_eq!(response.status, IngestStatus::ValidationError);
+        assert_eq!(response.server_time, now);
+        assert!(response
+            .error_message
+            .unwrap()
+            .contains("clock appears to be off by 7800s"));
+
+        let batch = service
+            .ingest_event_batch(IngestEventBatchRequest {
+                events: vec![test_grpc_event()],
+                continue_on_error: true,
+            })
+            .await;
+        assert_eq!(batch.server_time, now);
+    }
+
+    #[tokio::test]
+    async fn test_old_replay_inside_window_is_deduplicated() {
+        use crate::validation::WindowConfigError;
+        use creto_common::TestClock;
+
+        let start = chrono::Utc::now();
+        let clock = Arc::new(TestClock::new(start));
+        let validation = ValidationConfig {
+            max_future_skew: chrono::Duration::minutes(5),
+            max_past_age: chrono::Duration::days(7),
+            ..Default::default()
+        };
+        let config = MeteringServiceConfig {
+            enforce_quotas: false,
+            validation,
+            ..Default::default()
+        };
+        let dedup = |ttl_seconds| DedupConfig {
+            ttl_seconds,
+            ..Default::default()
+        };
+
+        // A retention shorter than the acceptance window is refused up front
+        let short = MeteringGrpcService::new(
+            Arc::new(MockIngestion),
+            Arc::new(Deduplicator::local_only(dedup(86400))),
+            Arc::new(QuotaEnforcer::new()),
+            config.clone(),
+        );
+        assert!(matches!(
+            short.err(),
+            Some(WindowConfigError::DedupRetentionTooShort { .. })
+        ));
+
+        let deduplicator =
+            Deduplicator::local_only(dedup(7 * 86400 + 300)).with_clock(clock.clone());
+        let service = MeteringGrpcService::new(
+            Arc::new(MockIngestion),
+            Arc::new(deduplicator),
+            Arc::new(QuotaEnforcer::new()),
+            config,
+        )
+        .unwrap()
+        .with_clock(clock.clone());
+
+        // Seen while the producer clock ran ahead, then replayed almost a week later
+        let mut event = test_grpc_event();
+        event.timestamp = Some(start + chrono::Duration::minutes(4));
+        let first = service
+            .ingest_event(IngestEventRequest {
+                event: event.clone(),
+            })
+            .await;
+        assert_eq!(first.status, IngestStatus::Accepted);
+
+        clock.advance(chrono::Duration::days(7));
+        let replay = service
+            .ingest_event(IngestEventRequest {
+                event: event.clone(),
+            })
+            .await;
+        assert_eq!(replay.status, IngestStatus::Duplicate);
+
+        let batch = service
+            .ingest_event_batch(IngestEventBatchRequest {
+                events: vec![event],
+                continue_on_error: true,
+            })
+            .await;
+        assert_eq!(batch.duplicate_count, 1);
+        assert_eq!(batch.accepted_count, 0);
+    }
+
     #[test]
     fn test_service_metrics_calculations() {
         let metrics = ServiceMetrics {
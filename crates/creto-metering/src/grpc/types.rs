@@ -20,6 +20,8 @@ pub struct IngestEventResponse {
     pub success: bool,
     pub status: IngestStatus,
     pub error_message: Option<String>,
+    /// Server time the event was validated against, for measuring clock skew.
+    pub server_time: DateTime<Utc>,
 }
 
 /// Request to ingest a batch of events.
@@ -35,7 +37,25 @@ pub struct IngestEventBatchResponse {
     pub accepted_count: u32,
     pub duplicate_count: u32,
     pub failed_count: u32,
+    pub late_count: u32,
     pub results: Vec<EventResult>,
+    /// Server time the events were validated against, for measuring clock skew.
+    pub server_time: DateTime<Utc>,
+}
+
+/// Response from stream ingestion.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestEventStreamResponse {
+    pub accepted_count: u32,
+    pub duplicate_count: u32,
+    pub failed_count: u32,
+    pub late_count: u32,
+    /// Batches flushed while the stream was open.
+    pub batch_count: u32,
+    /// Results for rejected events, indexed by position in the stream.
+    pub results: Vec<EventResult>,
+    /// Server time of the last flush.
+    pub server_time: DateTime<Utc>,
 }
 
 /// Status of an individual event ingestion.
@@ -50,6 +70,9 @@ pub enum IngestStatus {
     ValidationError,
     QuotaExceeded,
     InternalError,
+    AcceptedLate,
+    /// Shed by the ingestion queue under load; safe to retry.
+    Overloaded,
 }
 
 /// Result for a specific event in a batch.
@@ -73,6 +96,8 @@ pub struct GrpcUsageEvent {
     pub timestamp: Option<DateTime<Utc>>,
     pub properties: Option<serde_json::Value>,
     pub delegation_depth: u32,
+    pub correlation_id: Option<String>,
+    pub caused_by: Option<String>,
 }
 
 impl GrpcUsageEvent {
@@ -82,6 +107,18 @@ impl GrpcUsageEvent {
             .map_err(|e| format!("Invalid organization_id: {}", e))?;
         let agent_id = uuid::Uuid::parse_str(&self.agent_id)
             .map_err(|e| format!("Invalid agent_id: {}", e))?;
+        let correlation_id = self
+            .correlation_id
+            .as_deref()
+            .map(uuid::Uuid::parse_str)
+            .transpose()
+            .map_err(|e| format!("Invalid correlation_id: {}", e))?;
+        let caused_by = self
+            .caused_by
+            .as_deref()
+            .map(uuid::Uuid::parse_str)
+            .transpose()
+            .map_err(|e| format!("Invalid caused_by: {}", e))?;
 
         Ok(UsageEvent {
             transaction_id: self.transaction_id.clone(),
@@ -94,6 +131,8 @@ impl GrpcUsageEvent {
             timestamp: self.timestamp.unwrap_or_else(Utc::now),
             properties: self.properties.clone().unwrap_or(serde_json::json!({})),
             delegation_depth: self.delegation_depth as u8,
+            correlation_id,
+            caused_by,
         })
     }
 }
@@ -111,6 +150,8 @@ impl From<UsageEvent> for GrpcUsageEvent {
             timestamp: Some(event.timestamp),
             properties: Some(event.properties),
             delegation_depth: event.delegation_depth as u32,
+            correlation_id: event.correlation_id.map(|id| id.to_string()),
+            caused_by: event.caused_by.map(|id| id.to_string()),
         }
     }
 }
@@ -264,6 +305,8 @@ mod tests {
             timestamp: None,
             properties: None,
             delegation_depth: 0,
+            correlation_id: None,
+            caused_by: None,
         };
 
         let usage_event = grpc_event.to_usage_event().unwrap();
@@ -285,6 +328,8 @@ mod tests {
             timestamp: None,
             properties: None,
             delegation_depth: 0,
+            correlation_id: None,
+            caused_by: None,
         };
 
         let result = grpc_event.to_usage_event();
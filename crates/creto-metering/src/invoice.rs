@@ -1,10 +1,22 @@
 //! Invoice generation and management.
 
-use chrono::{DateTime, Utc};
-use creto_common::{types::Money, OrganizationId};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{
+    types::{Currency, Money},
+    CretoError, CretoResult, OrganizationId,
+};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::adjustments::AdjustmentSummary;
+use crate::aggregation::{Aggregation, UsageSelection};
+use crate::currency::{CurrencyError, ExchangeRate, ExchangeRateProvider};
+use crate::pricing::{PricingCatalog, PricingModel, PricingSegment, PricingStrategy};
+use crate::repository::{InvoiceRecord, InvoiceRepository};
+
 /// An invoice for a billing period.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Invoice {
@@ -23,6 +35,10 @@ pub struct Invoice {
     /// End of billing period.
     pub period_end: DateTime<Utc>,
 
+    /// Currency all amounts on this invoice are expressed in.
+    #[serde(default)]
+    pub currency: Currency,
+
     /// Invoice status.
     pub status: InvoiceStatus,
 
@@ -50,14 +66,32 @@ pub struct Invoice {
 
     /// When payment was received.
     pub paid_at: Option<DateTime<Utc>>,
+
+    /// Adjustments proposed against this invoice, in proposal order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub adjustments: Vec<AdjustmentSummary>,
+
+    /// Invoice this credit note credits (None for regular invoices).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credit_note_for: Option<Uuid>,
 }
 
 impl Invoice {
-    /// Create a new draft invoice.
+    /// Create a new draft invoice in USD.
     pub fn new(
         organization_id: OrganizationId,
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
+    ) -> Self {
+        Self::new_in_currency(organization_id, period_start, period_end, Currency::USD)
+    }
+
+    /// Create a new draft invoice in the given currency.
+    pub fn new_in_currency(
+        organization_id: OrganizationId,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        currency: Currency,
     ) -> Self {
         Self {
             id: Uuid::now_v7(),
@@ -65,15 +99,18 @@ impl Invoice {
             organization_id,
             period_start,
             period_end,
+            currency,
             status: InvoiceStatus::Draft,
             line_items: Vec::new(),
-            subtotal: Money::usd(0),
+            subtotal: Money::new(0, currency),
             discounts: Vec::new(),
-            tax: Money::usd(0),
-            total: Money::usd(0),
+            tax: Money::new(0, currency),
+            total: Money::new(0, currency),
             issued_at: None,
             due_at: None,
             paid_at: None,
+            adjustments: Vec::new(),
+            credit_note_for: None,
         }
     }
 
@@ -86,10 +123,27 @@ impl Invoice {
     }
 
     /// Add a line item to the invoice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the line item is not in the invoice currency; use
+    /// [`Invoice::try_add_line_item`] when that is not guaranteed.
     pub fn add_line_item(&mut self, item: LineItem) {
-        self.subtotal = self.subtotal.add(&item.amount).expect("Same currency");
+        self.try_add_line_item(item).expect("Same currency");
+    }
+
+    /// Add a line item, rejecting items in a different currency.
+    pub fn try_add_line_item(&mut self, item: LineItem) -> Result<(), CurrencyError> {
+        self.subtotal = self
+            .subtotal
+            .add(&item.amount)
+            .map_err(|_| CurrencyError::Mismatch {
+                expected: self.currency,
+                actual: item.amount.currency,
+            })?;
         self.line_items.push(item);
         self.recalculate_total();
+        Ok(())
     }
 
     /// Apply a discount.
@@ -113,7 +167,7 @@ impl Invoice {
             .sum();
 
         let after_discount = self.subtotal.amount - discount_amount;
-        self.total = Money::usd(after_discount + self.tax.amount);
+        self.total = Money::new(after_discount + self.tax.amount, self.currency);
     }
 
     /// Recalculate the total based on line items, discounts, and tax.
@@ -127,9 +181,9 @@ impl Invoice {
         let after_discount = self.subtotal.amount - discount_amount;
 
         // TODO: Calculate tax based on jurisdiction
-        self.tax = Money::usd(0);
+        self.tax = Money::new(0, self.currency);
 
-        self.total = Money::usd(after_discount + self.tax.amount);
+        self.total = Money::new(after_discount + self.tax.amount, self.currency);
     }
 
     /// Finalize and issue the invoice.
@@ -192,6 +246,57 @@ pub struct LineItem {
 
     /// Total amount (quantity * unit_price, may include adjustments).
     pub amount: Money,
+
+    /// Exchange rate snapshot when the usage was priced in another currency.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversion: Option<CurrencyConversion>,
+
+    /// Usage events the quantity was aggregated from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<LineItemSource>,
+
+    /// Pricing model version the line item was priced with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<AppliedPricing>,
+}
+
+/// Pricing model version applied to a line item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedPricing {
+    /// Pricing model ID.
+    pub model_id: String,
+
+    /// Version of the model's metric pricing.
+    pub version: u32,
+}
+
+/// Record of the events aggregated into a line item.
+///
+/// Re-running the selection later yields the events the customer was billed
+/// for; see [`crate::drilldown`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineItemSource {
+    /// Organization, metric code, and window that were aggregated.
+    pub selection: UsageSelection,
+
+    /// Number of events aggregated.
+    pub event_count: u64,
+}
+
+/// Audit record of a currency conversion applied to a line item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurrencyConversion {
+    /// The rate used.
+    pub rate: ExchangeRate,
+
+    /// Unit price in the pricing model's currency.
+    pub original_unit_price: Money,
+
+    /// Amount in the pricing model's currency.
+    pub original_amount: Money,
+
+    /// Time the rate was looked up for (the aggregation time).
+    pub converted_at: DateTime<Utc>,
 }
 
 impl LineItem {
@@ -203,7 +308,7 @@ impl LineItem {
         unit: impl Into<String>,
         unit_price: Money,
     ) -> Self {
-        let amount = Money::usd(quantity * unit_price.amount);
+        let amount = Money::new(quantity * unit_price.amount, unit_price.currency);
 
         Self {
             id: Uuid::now_v7(),
@@ -213,8 +318,51 @@ impl LineItem {
             unit: unit.into(),
             unit_price,
             amount,
+            conversion: None,
+            source: None,
+            pricing: None,
         }
     }
+
+    /// Price `quantity` with `model`, recording the version applied.
+    pub fn priced(
+        description: impl Into<String>,
+        metric_code: impl Into<String>,
+        quantity: i64,
+        unit: impl Into<String>,
+        model: &PricingModel,
+    ) -> Self {
+        Self {
+            amount: model.calculate(quantity),
+            pricing: Some(AppliedPricing {
+                model_id: model.id.clone(),
+                version: model.version,
+            }),
+            ..Self::new(
+                description,
+                metric_code,
+                quantity,
+                unit,
+                model.calculate_unit_price(quantity),
+            )
+        }
+    }
+
+    /// Convert this line item into `to` using `rate`, recording a snapshot.
+    pub fn convert(mut self, rate: ExchangeRate, converted_at: DateTime<Utc>) -> Self {
+        let original_unit_price = self.unit_price;
+        let original_amount = self.amount;
+
+        self.unit_price = original_unit_price.convert(rate.to, rate.rate);
+        self.amount = original_amount.convert(rate.to, rate.rate);
+        self.conversion = Some(CurrencyConversion {
+            rate,
+            original_unit_price,
+            original_amount,
+            converted_at,
+        });
+        self
+    }
 }
 
 /// A discount applied to an invoice.
@@ -255,89 +403,368 @@ impl Discount {
     }
 }
 
+/// Tax on `amount_cents` at `tax_rate` percent, truncated to whole minor units.
+pub(crate) fn tax_cents(amount_cents: i64, tax_rate: f64) -> i64 {
+    (amount_cents as f64 * tax_rate / 100.0) as i64
+}
+
 /// Generator for creating invoices from usage data.
 pub struct InvoiceGenerator {
-    /// Pricing models by metric code.
-    pricing_models: std::collections::HashMap<String, crate::pricing::PricingModel>,
+    /// Pricing model versions and organization pins.
+    pricing: PricingCatalog,
     /// Default due days for issued invoices.
     due_days: i64,
     /// Tax rate (percentage).
     tax_rate: f64,
+    /// Exchange rates for pricing models in a non-billing currency.
+    exchange_rates: Option<Arc<dyn ExchangeRateProvider>>,
+    /// Thresholds used when reconciling a preview against a prior invoice.
+    reconciliation_thresholds: ReconciliationThresholds,
 }
 
 impl InvoiceGenerator {
     /// Create a new invoice generator.
     pub fn new() -> Self {
         Self {
-            pricing_models: std::collections::HashMap::new(),
+            pricing: PricingCatalog::new(),
             due_days: 30,
             tax_rate: 0.0, // No tax by default
+            exchange_rates: None,
+            reconciliation_thresholds: ReconciliationThresholds::default(),
         }
     }
 
     /// Create with specific configuration.
     pub fn with_config(due_days: i64, tax_rate: f64) -> Self {
         Self {
-            pricing_models: std::collections::HashMap::new(),
+            pricing: PricingCatalog::new(),
             due_days,
             tax_rate,
+            exchange_rates: None,
+            reconciliation_thresholds: ReconciliationThresholds::default(),
         }
     }
 
-    /// Register a pricing model.
-    pub fn register_pricing_model(&mut self, model: crate::pricing::PricingModel) {
-        self.pricing_models.insert(model.metric_code.clone(), model);
+    /// Tax rate (percentage) applied to invoice subtotals.
+    pub fn tax_rate(&self) -> f64 {
+        self.tax_rate
+    }
+
+    /// Register a pricing model, replacing every version of its metric.
+    pub fn register_pricing_model(&mut self, model: PricingModel) {
+        self.pricing.replace(model);
+    }
+
+    /// Add a pricing model version alongside the metric's existing versions.
+    ///
+    /// See [`PricingCatalog::add_version`].
+    pub fn add_pricing_version(&mut self, model: PricingModel) -> CretoResult<()> {
+        self.pricing.add_version(model)
+    }
+
+    /// Pin (grandfather) an organization to a pricing model version.
+    pub fn pin_pricing_version(
+        &mut self,
+        organization_id: OrganizationId,
+        model_id: &str,
+    ) -> CretoResult<()> {
+        self.pricing.pin(organization_id, model_id)
+    }
+
+    /// Migrate an organization off its pinned version of a metric.
+    pub fn migrate_pricing(
+        &mut self,
+        organization_id: OrganizationId,
+        metric_code: &str,
+    ) -> Option<String> {
+        self.pricing.migrate(organization_id, metric_code)
+    }
+
+    /// Replace the pricing catalog, e.g. with one loaded from the
+    /// [`PricingModelRepository`](crate::repository::PricingModelRepository).
+    pub fn set_pricing_catalog(&mut self, catalog: PricingCatalog) {
+        self.pricing = catalog;
+    }
+
+    /// Set the exchange rate provider used to convert between pricing and
+    /// billing currencies.
+    pub fn set_exchange_rate_provider(&mut self, provider: Arc<dyn ExchangeRateProvider>) {
+        self.exchange_rates = Some(provider);
+    }
+
+    /// Set the change thresholds used by [`InvoiceGenerator::reconcile`].
+    pub fn set_reconciliation_thresholds(&mut self, thresholds: ReconciliationThresholds) {
+        self.reconciliation_thresholds = thresholds;
     }
 
     /// Generate an invoice from aggregated usage data.
     ///
     /// This is the synchronous version that takes pre-computed aggregations.
+    /// The invoice is in the currency of the pricing models in effect (USD
+    /// if none is), and nothing is converted: returns
+    /// [`CurrencyError::Mismatch`] if the usage is priced in more than one
+    /// currency. Use [`InvoiceGenerator::generate_in_currency`] to bill
+    /// mixed-currency usage in a single currency.
     pub fn generate_from_aggregations(
         &self,
         organization_id: OrganizationId,
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
         aggregations: &[UsageAggregation],
-    ) -> Invoice {
-        let mut invoice = Invoice::new(organization_id, period_start, period_end);
+    ) -> Result<Invoice, CurrencyError> {
+        let currency = aggregations
+            .iter()
+            .flat_map(|agg| self.segments_for(organization_id, period_start, period_end, agg))
+            .map(|segment| segment.model.currency)
+            .next()
+            .unwrap_or(Currency::USD);
+        let mut invoice =
+            Invoice::new_in_currency(organization_id, period_start, period_end, currency);
 
         for agg in aggregations {
-            // Find pricing model for this metric
-            if let Some(model) = self.pricing_models.get(&agg.metric_code) {
-                let cost = model.calculate(agg.quantity);
+            for line_item in
+                self.price_aggregation(organization_id, currency, period_start, period_end, agg)
+            {
+                invoice.try_add_line_item(line_item)?;
+            }
+        }
+
+        self.apply_tax(&mut invoice);
+        Ok(invoice)
+    }
+
+    /// Generate an invoice in `billing_currency` from aggregated usage data.
+    ///
+    /// Each aggregation is priced with the model version effective over its
+    /// source window (or the invoice period). If the price changed within
+    /// that window the line item is split per version, prorating quantity by
+    /// time; aggregations already split at price changes (as
+    /// [`MeteringService`](crate::MeteringService) produces them) are priced
+    /// exactly.
+    ///
+    /// Usage priced in another currency is converted at the rate effective
+    /// at each aggregation's `aggregated_at`, and the rate is snapshotted on
+    /// the line item. Returns [`CurrencyError::NoConversionPath`] if no rate
+    /// is available.
+    pub fn generate_in_currency(
+        &self,
+        organization_id: OrganizationId,
+        billing_currency: Currency,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        aggregations: &[UsageAggregation],
+    ) -> Result<Invoice, CurrencyError> {
+        let mut invoice =
+            Invoice::new_in_currency(organization_id, period_start, period_end, billing_currency);
+
+        for agg in aggregations {
+            for line_item in self.price_aggregation(
+                organization_id,
+                billing_currency,
+                period_start,
+                period_end,
+                agg,
+            ) {
+                let line_item =
+                    self.convert_line_item(line_item, billing_currency, agg.aggregated_at)?;
+                invoice.try_add_line_item(line_item)?;
+            }
+        }
+
+        self.apply_tax(&mut invoice);
+        Ok(invoice)
+    }
+
+    /// Generate an invoice with prorated line items for the metrics whose
+    /// price `plan` changes within the billing period.
+    ///
+    /// Each such metric's aggregations are split across the plan's segments
+    /// by the time their source window (or the period) overlaps each one,
+    /// with the last overlapping segment taking the rounding remainder. Each
+    /// segment is priced with its own model (see [`TierBoundaries`]) into
+    /// one line item labeled with the segment's dates. Flat fees are
+    /// prorated by the segment's share of the period, truncated to whole
+    /// minor units. Metrics the plan does not cover are priced as in
+    /// [`InvoiceGenerator::generate_in_currency`].
+    pub fn generate_prorated(
+        &self,
+        organization_id: OrganizationId,
+        billing_currency: Currency,
+        plan: &ProrationPlan,
+        aggregations: &[UsageAggregation],
+    ) -> Result<Invoice, CurrencyError> {
+        let (period_start, period_end) = (plan.period_start, plan.period_end);
+        let mut invoice =
+            Invoice::new_in_currency(organization_id, period_start, period_end, billing_currency);
+
+        let mut prorated = Vec::new();
+        for agg in aggregations {
+            let segments = plan.segments(&agg.metric_code);
+            if segments.is_empty() {
+                for line_item in self.price_aggregation(
+                    organization_id,
+                    billing_currency,
+                    period_start,
+                    period_end,
+                    agg,
+                ) {
+                    let line_item =
+                        self.convert_line_item(line_item, billing_currency, agg.aggregated_at)?;
+                    invoice.try_add_line_item(line_item)?;
+                }
+                continue;
+            }
+            if prorated.contains(&agg.metric_code.as_str()) {
+                continue;
+            }
+            prorated.push(agg.metric_code.as_str());
+
+            let metric_aggregations: Vec<_> = aggregations
+                .iter()
+                .filter(|other| other.metric_code == agg.metric_code)
+                .collect();
+            for (line_item, at) in price_segments(plan, segments, &metric_aggregations) {
+                let line_item = self.convert_line_item(line_item, billing_currency, at)?;
+                invoice.try_add_line_item(line_item)?;
+            }
+        }
 
-                let line_item = LineItem::new(
+        self.apply_tax(&mut invoice);
+        Ok(invoice)
+    }
+
+    /// Set tax on the invoice subtotal if a tax rate is configured.
+    fn apply_tax(&self, invoice: &mut Invoice) {
+        if self.tax_rate > 0.0 {
+            invoice.set_tax(Money::new(
+                tax_cents(invoice.subtotal.amount, self.tax_rate),
+                invoice.currency,
+            ));
+        }
+    }
+
+    /// Pricing segments covering an aggregation's window.
+    fn segments_for(
+        &self,
+        organization_id: OrganizationId,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        agg: &UsageAggregation,
+    ) -> Vec<PricingSegment> {
+        let (start, end) = agg
+            .source
+            .as_ref()
+            .map_or((period_start, period_end), |source| {
+                (source.selection.window_start, source.selection.window_end)
+            });
+        self.pricing
+            .segments(organization_id, &agg.metric_code, start, end)
+    }
+
+    /// Price an aggregation into one line item per pricing segment.
+    ///
+    /// Usage with no pricing model is billed at one minor unit of
+    /// `fallback_currency` per unit.
+    fn price_aggregation(
+        &self,
+        organization_id: OrganizationId,
+        fallback_currency: Currency,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        agg: &UsageAggregation,
+    ) -> Vec<LineItem> {
+        let segments = self.segments_for(organization_id, period_start, period_end, agg);
+
+        match segments.as_slice() {
+            [] => {
+                // No pricing model - one minor unit per unit
+                let mut line_item = LineItem::new(
                     &agg.description,
                     &agg.metric_code,
                     agg.quantity,
                     &agg.unit,
-                    model.calculate_unit_price(agg.quantity),
+                    Money::new(1, fallback_currency),
                 );
-
-                invoice.add_line_item(LineItem {
-                    amount: cost,
-                    ..line_item
-                });
-            } else {
-                // No pricing model - use quantity as cents (1:1 mapping)
-                let line_item = LineItem::new(
+                line_item.source = agg.source.clone();
+                vec![line_item]
+            }
+            [segment] => {
+                let mut line_item = LineItem::priced(
                     &agg.description,
                     &agg.metric_code,
                     agg.quantity,
                     &agg.unit,
-                    Money::usd(1), // Default $0.01 per unit
+                    &segment.model,
                 );
-                invoice.add_line_item(line_item);
+                line_item.source = agg.source.clone();
+                vec![line_item]
+            }
+            segments => {
+                let total_span: i128 = segments.iter().map(|s| window_span(s.start, s.end)).sum();
+                let event_count = agg.source.as_ref().map_or(0, |source| source.event_count);
+                let (mut quantity_left, mut events_left) = (agg.quantity, event_count);
+
+                segments
+                    .iter()
+                    .enumerate()
+                    .map(|(i, segment)| {
+                        let (quantity, events) = if i + 1 == segments.len() {
+                            (quantity_left, events_left)
+                        } else {
+                            let share = window_span(segment.start, segment.end);
+                            (
+                                (agg.quantity as i128 * share / total_span) as i64,
+                                (event_count as i128 * share / total_span) as u64,
+                            )
+                        };
+                        quantity_left -= quantity;
+                        events_left -= events;
+
+                        let mut line_item = LineItem::priced(
+                            &agg.description,
+                            &agg.metric_code,
+                            quantity,
+                            &agg.unit,
+                            &segment.model,
+                        );
+                        line_item.source = agg.source.as_ref().map(|source| LineItemSource {
+                            selection: UsageSelection {
+                                window_start: segment.start,
+                                window_end: segment.end,
+                                ..source.selection.clone()
+                            },
+                            event_count: events,
+                        });
+                        line_item
+                    })
+                    .collect()
             }
         }
+    }
 
-        // Apply tax if configured
-        if self.tax_rate > 0.0 {
-            let tax_cents = (invoice.subtotal.amount as f64 * self.tax_rate / 100.0) as i64;
-            invoice.set_tax(Money::usd(tax_cents));
+    /// Convert a line item into the billing currency if needed.
+    fn convert_line_item(
+        &self,
+        item: LineItem,
+        billing_currency: Currency,
+        at: DateTime<Utc>,
+    ) -> Result<LineItem, CurrencyError> {
+        let from = item.amount.currency;
+        if from == billing_currency {
+            return Ok(item);
         }
 
-        invoice
+        let provider = self
+            .exchange_rates
+            .as_ref()
+            .ok_or(CurrencyError::NoConversionPath {
+                from,
+                to: billing_currency,
+                at,
+            })?;
+        let rate = provider.rate(from, billing_currency, at)?;
+
+        Ok(item.convert(rate, at))
     }
 
     /// Generate and issue an invoice.
@@ -347,16 +774,16 @@ impl InvoiceGenerator {
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
         aggregations: &[UsageAggregation],
-    ) -> Invoice {
+    ) -> Result<Invoice, CurrencyError> {
         let mut invoice = self.generate_from_aggregations(
             organization_id,
             period_start,
             period_end,
             aggregations,
-        );
+        )?;
 
         invoice.issue(self.due_days);
-        invoice
+        Ok(invoice)
     }
 
     /// Generate an invoice for an organization's usage in a billing period.
@@ -374,11 +801,143 @@ impl InvoiceGenerator {
         Ok(invoice)
     }
 
-    /// Get registered pricing models.
-    pub fn pricing_models(
+    /// Dry-run the invoicing pipeline (pricing, credits, tax) without side effects.
+    ///
+    /// `available_credits_cents` is the organization's credit balance; it is
+    /// applied the same way a billing run would but nothing is consumed.
+    /// Usage with no pricing model version in effect is billed at the
+    /// fallback rate and listed in [`InvoicePreview::unpriced`].
+    pub fn preview(
         &self,
-    ) -> &std::collections::HashMap<String, crate::pricing::PricingModel> {
-        &self.pricing_models
+        organization_id: OrganizationId,
+        billing_currency: Currency,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        aggregations: &[UsageAggregation],
+        available_credits_cents: i64,
+    ) -> Result<InvoicePreview, CurrencyError> {
+        let mut invoice = self.generate_in_currency(
+            organization_id,
+            billing_currency,
+            period_start,
+            period_end,
+            aggregations,
+        )?;
+
+        let unpriced = aggregations
+            .iter()
+            .filter(|agg| {
+                self.segments_for(organization_id, period_start, period_end, agg)
+                    .is_empty()
+            })
+            .map(|agg| UnpricedUsage {
+                metric_code: agg.metric_code.clone(),
+                quantity: agg.quantity,
+            })
+            .collect();
+
+        let credits_expected = available_credits_cents.max(0).min(invoice.total.amount);
+        let mut credits_applied = 0;
+        if credits_expected > 0 {
+            let credits = Discount {
+                code: CREDITS_DISCOUNT_CODE.to_string(),
+                discount_type: DiscountType::FixedAmount {
+                    amount_cents: credits_expected,
+                },
+            };
+            credits_applied = credits.calculate(invoice.subtotal.amount);
+            invoice.apply_discount(credits);
+        }
+
+        Ok(InvoicePreview {
+            invoice,
+            unpriced,
+            credits_expected,
+            credits_applied,
+        })
+    }
+
+    /// Compare a preview with the previous period's invoice.
+    ///
+    /// Line items are matched by metric code. Quantity and amount changes
+    /// are flagged when they exceed the configured
+    /// [`ReconciliationThresholds`]; amounts are only compared when both
+    /// invoices use the same currency.
+    pub fn reconcile(&self, preview: &InvoicePreview, previous: &Invoice) -> ReconciliationReport {
+        let thresholds = &self.reconciliation_thresholds;
+        let current = totals_by_metric(&preview.invoice);
+        let prior = totals_by_metric(previous);
+        let mut anomalies = Vec::new();
+
+        for (code, before) in &prior {
+            let Some(now) = current.get(code) else {
+                anomalies.push(ReconciliationAnomaly::MissingLineItem {
+                    metric_code: code.clone(),
+                    previous_quantity: before.quantity,
+                    previous_amount: before.amount,
+                });
+                continue;
+            };
+
+            let quantity_change = percent_change(before.quantity, now.quantity);
+            if exceeds(quantity_change, thresholds.quantity_change_percent) {
+                anomalies.push(ReconciliationAnomaly::QuantityChanged {
+                    metric_code: code.clone(),
+                    previous: before.quantity,
+                    current: now.quantity,
+                    change_percent: quantity_change,
+                });
+            }
+
+            if before.amount.currency == now.amount.currency {
+                let amount_change = percent_change(before.amount.amount, now.amount.amount);
+                if exceeds(amount_change, thresholds.amount_change_percent) {
+                    anomalies.push(ReconciliationAnomaly::AmountChanged {
+                        metric_code: code.clone(),
+                        previous: before.amount,
+                        current: now.amount,
+                        change_percent: amount_change,
+                    });
+                }
+            }
+        }
+
+        for (code, now) in &current {
+            if !prior.contains_key(code) {
+                anomalies.push(ReconciliationAnomaly::NewLineItem {
+                    metric_code: code.clone(),
+                    quantity: now.quantity,
+                    amount: now.amount,
+                });
+            }
+        }
+
+        for usage in &preview.unpriced {
+            anomalies.push(ReconciliationAnomaly::UnpricedCode {
+                metric_code: usage.metric_code.clone(),
+                quantity: usage.quantity,
+            });
+        }
+
+        if preview.credits_expected != preview.credits_applied {
+            anomalies.push(ReconciliationAnomaly::CreditMismatch {
+                expected_cents: preview.credits_expected,
+                applied_cents: preview.credits_applied,
+            });
+        }
+
+        ReconciliationReport {
+            organization_id: preview.invoice.organization_id,
+            period_start: preview.invoice.period_start,
+            period_end: preview.invoice.period_end,
+            previous_invoice_id: previous.id,
+            anomalies,
+        }
+    }
+
+    /// Get the pricing catalog.
+    pub fn pricing_catalog(&self) -> &PricingCatalog {
+        &self.pricing
     }
 }
 
@@ -388,6 +947,235 @@ impl Default for InvoiceGenerator {
     }
 }
 
+/// Length of the inclusive window `[start, end]` in microseconds.
+fn window_span(start: DateTime<Utc>, end: DateTime<Utc>) -> i128 {
+    ((end - start) + Duration::microseconds(1))
+        .num_microseconds()
+        .unwrap_or(i64::MAX) as i128
+}
+
+/// Price one metric's aggregations into a line item per plan segment the
+/// usage falls in, each paired with the time to convert its currency at.
+fn price_segments(
+    plan: &ProrationPlan,
+    segments: &[PricingSegment],
+    aggregations: &[&UsageAggregation],
+) -> Vec<(LineItem, DateTime<Utc>)> {
+    let (period_start, period_end) = (plan.period_start, plan.period_end);
+    let mut quantities = vec![0i64; segments.len()];
+    let mut event_counts = vec![0u64; segments.len()];
+    let mut priced_at: Vec<Option<DateTime<Utc>>> = vec![None; segments.len()];
+
+    for agg in aggregations {
+        let (start, end) = agg
+            .source
+            .as_ref()
+            .map_or((period_start, period_end), |source| {
+                (source.selection.window_start, source.selection.window_end)
+            });
+        // Usage recorded outside the period is billed at its nearest edge
+        let start = start.clamp(period_start, period_end);
+        let end = end.clamp(start, period_end);
+        let overlaps: Vec<(usize, i128)> = segments
+            .iter()
+            .enumerate()
+            .filter_map(|(i, segment)| {
+                let (from, to) = (start.max(segment.start), end.min(segment.end));
+                (from <= to).then(|| (i, window_span(from, to)))
+            })
+            .collect();
+        let total_span: i128 = overlaps.iter().map(|(_, share)| share).sum();
+        let event_count = agg.source.as_ref().map_or(0, |source| source.event_count);
+        let (mut quantity_left, mut events_left) = (agg.quantity, event_count);
+
+        for (n, &(i, share)) in overlaps.iter().enumerate() {
+            let (quantity, events) = if n + 1 == overlaps.len() {
+                (quantity_left, events_left)
+            } else {
+                (
+                    (agg.quantity as i128 * share / total_span) as i64,
+                    (event_count as i128 * share / total_span) as u64,
+                )
+            };
+            quantity_left -= quantity;
+            events_left -= events;
+            quantities[i] += quantity;
+            event_counts[i] += events;
+            priced_at[i] = priced_at[i].max(Some(agg.aggregated_at));
+        }
+    }
+
+    let first = aggregations[0];
+    let source = aggregations.iter().find_map(|agg| agg.source.as_ref());
+    let period_span = window_span(period_start, period_end);
+    let mut earlier_usage = 0;
+    let mut line_items = Vec::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let quantity = quantities[i];
+        let model = &segment.model;
+        let cents = match (&model.strategy, plan.tier_boundaries) {
+            (PricingStrategy::FlatFee { amount_cents }, _) => {
+                (*amount_cents as i128 * window_span(segment.start, segment.end) / period_span)
+                    as i64
+            }
+            (_, TierBoundaries::PerSegment) => model.calculate(quantity).amount,
+            (_, TierBoundaries::WholePeriod) => {
+                model.calculate(earlier_usage + quantity).amount
+                    - model.calculate(earlier_usage).amount
+            }
+        };
+        earlier_usage += quantity;
+        let Some(at) = priced_at[i] else {
+            continue;
+        };
+
+        let description = format!(
+            "{} ({} to {})",
+            first.description,
+            segment.start.format("%Y-%m-%d"),
+            segment.end.format("%Y-%m-%d")
+        );
+        let unit_price = if quantity == 0 { 0 } else { cents / quantity };
+        let mut line_item = LineItem::new(
+            description,
+            &first.metric_code,
+            quantity,
+            &first.unit,
+            Money::new(unit_price, model.currency),
+        );
+        line_item.amount = Money::new(cents, model.currency);
+        line_item.pricing = Some(AppliedPricing {
+            model_id: model.id.clone(),
+            version: model.version,
+        });
+        line_item.source = source.map(|source| LineItemSource {
+            selection: UsageSelection {
+                window_start: segment.start,
+                window_end: segment.end,
+                ..source.selection.clone()
+            },
+            event_count: event_counts[i],
+        });
+        line_items.push((line_item, at));
+    }
+    line_items
+}
+
+/// Where tier boundaries fall when a metric's price changes within a
+/// billing period.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TierBoundaries {
+    /// Each segment's usage starts again from the first tier.
+    #[default]
+    PerSegment,
+    /// Usage accumulates over the whole period, so a segment starts in the
+    /// tier earlier usage reached. Each segment is charged what its usage
+    /// adds to the period's running total under its own model.
+    WholePeriod,
+}
+
+/// Pricing models in effect over stretches of one billing period, as when
+/// an organization changes plan mid-period.
+///
+/// Segments are inclusive windows like [`PricingSegment`]s from the
+/// catalog. Per metric, once clipped to the period they must cover it
+/// exactly, without gaps or overlaps; segments wholly outside the period
+/// (a change taking effect right at a boundary) are dropped.
+#[derive(Debug, Clone)]
+pub struct ProrationPlan {
+    period_start: DateTime<Utc>,
+    period_end: DateTime<Utc>,
+    /// Segments by metric code, in order.
+    segments: BTreeMap<String, Vec<PricingSegment>>,
+    tier_boundaries: TierBoundaries,
+}
+
+impl ProrationPlan {
+    /// Validate `segments` against the inclusive period
+    /// `[period_start, period_end]`.
+    pub fn new(
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        segments: Vec<PricingSegment>,
+    ) -> CretoResult<Self> {
+        if period_end < period_start {
+            return Err(CretoError::ValidationFailed(
+                "proration period ends before it starts".to_string(),
+            ));
+        }
+
+        let mut by_metric: BTreeMap<String, Vec<PricingSegment>> = BTreeMap::new();
+        for segment in segments {
+            if segment.end < segment.start {
+                return Err(CretoError::ValidationFailed(format!(
+                    "proration segment for {} ends before it starts",
+                    segment.model.id
+                )));
+            }
+            if segment.end < period_start || segment.start > period_end {
+                continue;
+            }
+            by_metric
+                .entry(segment.model.metric_code.clone())
+                .or_default()
+                .push(PricingSegment {
+                    start: segment.start.max(period_start),
+                    end: segment.end.min(period_end),
+                    ..segment
+                });
+        }
+
+        for (metric_code, segments) in &mut by_metric {
+            segments.sort_by_key(|segment| segment.start);
+            let mut next_start = period_start;
+            for segment in segments.iter() {
+                if segment.start != next_start {
+                    return Err(CretoError::ValidationFailed(format!(
+                        "proration segments for {} {} at {}",
+                        metric_code,
+                        if segment.start > next_start {
+                            "leave a gap"
+                        } else {
+                            "overlap"
+                        },
+                        next_start
+                    )));
+                }
+                next_start = segment.end + Duration::microseconds(1);
+            }
+            if next_start <= period_end {
+                return Err(CretoError::ValidationFailed(format!(
+                    "proration segments for {} end before the period does",
+                    metric_code
+                )));
+            }
+        }
+
+        Ok(Self {
+            period_start,
+            period_end,
+            segments: by_metric,
+            tier_boundaries: TierBoundaries::default(),
+        })
+    }
+
+    /// Choose where tier boundaries fall.
+    pub fn with_tier_boundaries(mut self, tier_boundaries: TierBoundaries) -> Self {
+        self.tier_boundaries = tier_boundaries;
+        self
+    }
+
+    /// The segments for a metric, in order (empty if its price does not
+    /// change).
+    pub fn segments(&self, metric_code: &str) -> &[PricingSegment] {
+        self.segments
+            .get(metric_code)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
 /// Aggregated usage data for invoice generation.
 #[derive(Debug, Clone)]
 pub struct UsageAggregation {
@@ -399,11 +1187,300 @@ pub struct UsageAggregation {
     pub quantity: i64,
     /// Unit of measurement.
     pub unit: String,
+    /// When the aggregation was computed (drives exchange rate selection).
+    pub aggregated_at: DateTime<Utc>,
+    /// Events aggregated, copied onto the line item for drill-down.
+    pub source: Option<LineItemSource>,
+}
+
+impl UsageAggregation {
+    /// Bill an engine [`Aggregation`] (any
+    /// [`AggregationType`](crate::aggregation::AggregationType)) as a line
+    /// item quantity.
+    ///
+    /// Float results such as percentiles are rounded half away from zero to
+    /// whole units (see
+    /// [`AggregationValue::to_quantity`](crate::aggregation::AggregationValue::to_quantity));
+    /// approximate unique counts are billed at their rounded estimate. Fails
+    /// with [`CretoError::ValidationFailed`] for values that are not
+    /// quantities.
+    pub fn from_aggregation(
+        aggregation: &Aggregation,
+        unit: impl Into<String>,
+    ) -> CretoResult<Self> {
+        let quantity = aggregation.value.to_quantity().ok_or_else(|| {
+            CretoError::ValidationFailed(format!(
+                "Aggregation of {} is not a quantity: {:?}",
+                aggregation.metric_code, aggregation.value
+            ))
+        })?;
+
+        Ok(Self {
+            metric_code: aggregation.metric_code.clone(),
+            description: format!("{} usage", aggregation.metric_code),
+            quantity,
+            unit: unit.into(),
+            aggregated_at: Utc::now(),
+            source: Some(LineItemSource {
+                selection: UsageSelection::new(
+                    aggregation.organization_id,
+                    aggregation.metric_code.clone(),
+                    aggregation.period_start,
+                    aggregation.period_end,
+                ),
+                event_count: aggregation.event_count,
+            }),
+        })
+    }
+}
+
+/// Discount code used when prepaid credits are applied to an invoice.
+pub const CREDITS_DISCOUNT_CODE: &str = "CREDITS_APPLIED";
+
+/// Result of an invoicing dry-run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoicePreview {
+    /// Draft invoice as it would be issued (never persisted or issued).
+    pub invoice: Invoice,
+    /// Usage whose metric code matched no pricing model.
+    pub unpriced: Vec<UnpricedUsage>,
+    /// Credits the organization's balance should cover, in cents.
+    pub credits_expected: i64,
+    /// Credits actually deducted on the invoice, in cents.
+    pub credits_applied: i64,
+}
+
+/// Usage billed at the fallback rate because no pricing model matched.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnpricedUsage {
+    /// Metric code with no pricing model.
+    pub metric_code: String,
+    /// Quantity consumed.
+    pub quantity: i64,
+}
+
+/// Percentage change limits for invoice reconciliation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationThresholds {
+    /// Flag line items whose quantity changed by more than this percentage.
+    pub quantity_change_percent: f64,
+    /// Flag line items whose amount changed by more than this percentage.
+    pub amount_change_percent: f64,
+}
+
+impl Default for ReconciliationThresholds {
+    fn default() -> Self {
+        Self {
+            quantity_change_percent: 50.0,
+            amount_change_percent: 50.0,
+        }
+    }
+}
+
+/// Something unexpected found when reconciling a preview.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReconciliationAnomaly {
+    /// Billed last period but absent from the preview.
+    MissingLineItem {
+        /// Metric code.
+        metric_code: String,
+        /// Quantity on the previous invoice.
+        previous_quantity: i64,
+        /// Amount on the previous invoice.
+        previous_amount: Money,
+    },
+    /// In the preview but not billed last period.
+    NewLineItem {
+        /// Metric code.
+        metric_code: String,
+        /// Quantity in the preview.
+        quantity: i64,
+        /// Amount in the preview.
+        amount: Money,
+    },
+    /// Quantity changed beyond the threshold.
+    QuantityChanged {
+        /// Metric code.
+        metric_code: String,
+        /// Quantity on the previous invoice.
+        previous: i64,
+        /// Quantity in the preview.
+        current: i64,
+        /// Change relative to the previous quantity (`None` if it was zero).
+        change_percent: Option<f64>,
+    },
+    /// Amount changed beyond the threshold.
+    AmountChanged {
+        /// Metric code.
+        metric_code: String,
+        /// Amount on the previous invoice.
+        previous: Money,
+        /// Amount in the preview.
+        current: Money,
+        /// Change relative to the previous amount (`None` if it was zero).
+        change_percent: Option<f64>,
+    },
+    /// Usage matched no pricing model and was billed at the fallback rate.
+    UnpricedCode {
+        /// Metric code.
+        metric_code: String,
+        /// Quantity consumed.
+        quantity: i64,
+    },
+    /// Credits the balance should cover differ from the credits deducted.
+    CreditMismatch {
+        /// Credits expected from the balance, in cents.
+        expected_cents: i64,
+        /// Credits deducted on the invoice, in cents.
+        applied_cents: i64,
+    },
+}
+
+/// Differences between an invoice preview and the previous invoice.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    /// Organization being billed.
+    pub organization_id: OrganizationId,
+    /// Start of the previewed period.
+    pub period_start: DateTime<Utc>,
+    /// End of the previewed period.
+    pub period_end: DateTime<Utc>,
+    /// Invoice the preview was compared against.
+    pub previous_invoice_id: Uuid,
+    /// Anomalies found, empty if the preview looks consistent.
+    pub anomalies: Vec<ReconciliationAnomaly>,
+}
+
+impl ReconciliationReport {
+    /// Check if no anomalies were found.
+    pub fn is_clean(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+}
+
+/// Summed quantity and amount for one metric on an invoice.
+struct MetricTotals {
+    quantity: i64,
+    amount: Money,
+}
+
+/// In-memory invoice store for testing and development.
+///
+/// Mirrors [`PgInvoiceRepository`](crate::repository::PgInvoiceRepository):
+/// invoices are stored whole, line items included.
+#[derive(Debug, Default)]
+pub struct InMemoryInvoiceRepository {
+    invoices: RwLock<HashMap<Uuid, Invoice>>,
+}
+
+impl InMemoryInvoiceRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store an invoice, overwriting any with the same ID.
+    pub(crate) fn store(&self, invoice: &Invoice) {
+        self.invoices
+            .write()
+            .unwrap()
+            .insert(invoice.id, invoice.clone());
+    }
+}
+
+fn invoice_record(invoice: &Invoice) -> InvoiceRecord {
+    InvoiceRecord {
+        id: invoice.id,
+        organization_id: invoice.organization_id,
+        invoice_number: invoice.number.clone(),
+        status: invoice.status.as_db_str().to_string(),
+        currency: invoice.currency,
+        total_cents: invoice.total.amount,
+        period_start: invoice.period_start,
+        period_end: invoice.period_end,
+        created_at: invoice.issued_at.unwrap_or(invoice.period_end),
+    }
+}
+
+#[async_trait::async_trait]
+impl InvoiceRepository for InMemoryInvoiceRepository {
+    async fn create_invoice_record(
+        &self,
+        org_id: OrganizationId,
+        invoice_number: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        currency: Currency,
+        total_cents: i64,
+    ) -> Result<Uuid, CretoError> {
+        let mut invoice = Invoice::new_in_currency(org_id, period_start, period_end, currency);
+        invoice.number = invoice_number.to_string();
+        invoice.subtotal = Money::new(total_cents, currency);
+        invoice.total = Money::new(total_cents, currency);
+        self.save_invoice(&invoice).await?;
+        Ok(invoice.id)
+    }
+
+    async fn get_invoice(&self, id: Uuid) -> Result<Option<InvoiceRecord>, CretoError> {
+        Ok(self.invoices.read().unwrap().get(&id).map(invoice_record))
+    }
+
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<InvoiceRecord>, CretoError> {
+        let mut records: Vec<InvoiceRecord> = self
+            .invoices
+            .read()
+            .unwrap()
+            .values()
+            .filter(|i| i.organization_id == org_id)
+            .map(invoice_record)
+            .collect();
+        records.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+        Ok(records)
+    }
+
+    async fn save_invoice(&self, invoice: &Invoice) -> Result<(), CretoError> {
+        self.store(invoice);
+        Ok(())
+    }
+
+    async fn find_invoice(&self, id: Uuid) -> Result<Option<Invoice>, CretoError> {
+        Ok(self.invoices.read().unwrap().get(&id).cloned())
+    }
+}
+
+fn totals_by_metric(invoice: &Invoice) -> BTreeMap<String, MetricTotals> {
+    let mut totals = BTreeMap::new();
+    for item in &invoice.line_items {
+        let entry = totals
+            .entry(item.metric_code.clone())
+            .or_insert(MetricTotals {
+                quantity: 0,
+                amount: Money::new(0, item.amount.currency),
+            });
+        entry.quantity += item.quantity;
+        entry.amount.amount += item.amount.amount;
+    }
+    totals
+}
+
+fn percent_change(previous: i64, current: i64) -> Option<f64> {
+    if previous == 0 {
+        return None;
+    }
+    Some((current - previous) as f64 / previous as f64 * 100.0)
+}
+
+fn exceeds(change: Option<f64>, threshold: f64) -> bool {
+    // Growth from zero is always worth a look
+    !matches!(change, Some(c) if c.abs() <= threshold)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::aggregation::AggregationValue;
+    use crate::pricing::PricingTier;
 
     #[test]
     fn test_invoice_line_item_calculation() {
@@ -479,25 +1556,25 @@ mod tests {
         let mut generator = InvoiceGenerator::new();
 
         // Register per-unit pricing for tokens
-        generator.register_pricing_model(PricingModel {
-            id: "tokens".to_string(),
-            name: "Token Pricing".to_string(),
-            metric_code: "tokens".to_string(),
-            strategy: PricingStrategy::PerUnit {
+        generator.register_pricing_model(PricingModel::new(
+            "tokens",
+            "Token Pricing",
+            "tokens",
+            PricingStrategy::PerUnit {
                 unit_price_cents: 1, // $0.01 per token
             },
-        });
+        ));
 
         // Register package pricing for API calls
-        generator.register_pricing_model(PricingModel {
-            id: "api_calls".to_string(),
-            name: "API Call Packages".to_string(),
-            metric_code: "api_calls".to_string(),
-            strategy: PricingStrategy::Package {
+        generator.register_pricing_model(PricingModel::new(
+            "api_calls",
+            "API Call Packages",
+            "api_calls",
+            PricingStrategy::Package {
                 package_size: 1000,
                 package_price_cents: 500, // $5.00 per 1000 calls
             },
-        });
+        ));
 
         let org_id = OrganizationId::new();
         let period_start = Utc::now() - chrono::Duration::days(30);
@@ -509,17 +1586,22 @@ mod tests {
                 description: "Input Tokens".to_string(),
                 quantity: 5000,
                 unit: "tokens".to_string(),
+                aggregated_at: Utc::now(),
+                source: None,
             },
             UsageAggregation {
                 metric_code: "api_calls".to_string(),
                 description: "API Calls".to_string(),
                 quantity: 2500, // 3 packages
                 unit: "calls".to_string(),
+                aggregated_at: Utc::now(),
+                source: None,
             },
         ];
 
-        let invoice =
-            generator.generate_from_aggregations(org_id, period_start, period_end, &aggregations);
+        let invoice = generator
+            .generate_from_aggregations(org_id, period_start, period_end, &aggregations)
+            .unwrap();
 
         assert_eq!(invoice.line_items.len(), 2);
         // Tokens: 5000 * $0.01 = $50.00
@@ -542,16 +1624,77 @@ mod tests {
             description: "Compute Hours".to_string(),
             quantity: 10000, // $100.00 at default rate
             unit: "hours".to_string(),
+            aggregated_at: Utc::now(),
+            source: None,
         }];
 
-        let invoice =
-            generator.generate_from_aggregations(org_id, period_start, period_end, &aggregations);
+        let invoice = generator
+            .generate_from_aggregations(org_id, period_start, period_end, &aggregations)
+            .unwrap();
 
         assert_eq!(invoice.subtotal.amount, 10000); // $100.00
         assert_eq!(invoice.tax.amount, 1000); // $10.00 tax
         assert_eq!(invoice.total.amount, 11000); // $110.00 total
     }
 
+    #[test]
+    fn test_invoice_from_percentile_and_unique_count_aggregations() {
+        let generator = InvoiceGenerator::new();
+        let org_id = OrganizationId::new();
+        let period_start = Utc::now() - chrono::Duration::days(30);
+        let period_end = Utc::now();
+        let aggregation = |metric_code: &str, aggregation_type, value| Aggregation {
+            organization_id: org_id,
+            agent_id: None,
+            metric_code: metric_code.to_string(),
+            event_type: crate::events::UsageEventType::LlmInference,
+            aggregation_type,
+            value,
+            approximate: false,
+            period_start,
+            period_end,
+            event_count: 40,
+        };
+
+        let p95 = aggregation(
+            "p95_inference_ms",
+            crate::aggregation::AggregationType::Percentile {
+                property: "duration_ms".to_string(),
+                percentile: 95.0,
+            },
+            AggregationValue::Float(120.5),
+        );
+        let consumers = aggregation(
+            "unique_consumers",
+            crate::aggregation::AggregationType::UniqueCount {
+                property: "consumer".to_string(),
+            },
+            AggregationValue::Integer(12),
+        );
+        let aggregations = vec![
+            UsageAggregation::from_aggregation(&p95, "ms").unwrap(),
+            UsageAggregation::from_aggregation(&consumers, "consumers").unwrap(),
+        ];
+        assert_eq!(aggregations[0].quantity, 121);
+        assert_eq!(aggregations[0].source.as_ref().unwrap().event_count, 40);
+
+        let invoice = generator
+            .generate_from_aggregations(org_id, period_start, period_end, &aggregations)
+            .unwrap();
+        assert_eq!(invoice.line_items[0].quantity, 121);
+        assert_eq!(invoice.line_items[1].quantity, 12);
+
+        let latest = aggregation(
+            "model",
+            crate::aggregation::AggregationType::Latest,
+            AggregationValue::String("gpt".to_string()),
+        );
+        assert!(matches!(
+            UsageAggregation::from_aggregation(&latest, "units"),
+            Err(CretoError::ValidationFailed(_))
+        ));
+    }
+
     #[test]
     fn test_invoice_generator_and_issue() {
         let generator = InvoiceGenerator::with_config(14, 0.0); // 14 day due, no tax
@@ -565,9 +1708,13 @@ mod tests {
             description: "Storage GB".to_string(),
             quantity: 1000,
             unit: "GB".to_string(),
+            aggregated_at: Utc::now(),
+            source: None,
         }];
 
-        let invoice = generator.generate_and_issue(org_id, period_start, period_end, &aggregations);
+        let invoice = generator
+            .generate_and_issue(org_id, period_start, period_end, &aggregations)
+            .unwrap();
 
         assert_eq!(invoice.status, InvoiceStatus::Issued);
         assert!(invoice.issued_at.is_some());
@@ -577,4 +1724,824 @@ mod tests {
         let due_diff = invoice.due_at.unwrap() - invoice.issued_at.unwrap();
         assert_eq!(due_diff.num_days(), 14);
     }
+
+    fn usd_token_generator() -> InvoiceGenerator {
+        use crate::pricing::{PricingModel, PricingStrategy};
+
+        let mut generator = InvoiceGenerator::new();
+        generator.register_pricing_model(PricingModel::new(
+            "tokens",
+            "Token Pricing",
+            "tokens",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 3, // $0.03 per token
+            },
+        ));
+        generator
+    }
+
+    fn token_aggregation(quantity: i64, aggregated_at: DateTime<Utc>) -> UsageAggregation {
+        UsageAggregation {
+            metric_code: "tokens".to_string(),
+            description: "Tokens".to_string(),
+            quantity,
+            unit: "tokens".to_string(),
+            aggregated_at,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_generate_from_aggregations_in_pricing_currency() {
+        use crate::pricing::{PricingModel, PricingStrategy};
+
+        let mut generator = InvoiceGenerator::new();
+        generator.register_pricing_model(
+            PricingModel::new(
+                "tokens",
+                "Token Pricing",
+                "tokens",
+                PricingStrategy::PerUnit {
+                    unit_price_cents: 3,
+                },
+            )
+            .with_currency(Currency::EUR),
+        );
+        let now = Utc::now();
+        let compute = UsageAggregation {
+            metric_code: "compute".to_string(),
+            description: "Compute Hours".to_string(),
+            quantity: 10,
+            unit: "hours".to_string(),
+            aggregated_at: now,
+            source: None,
+        };
+
+        let invoice = generator
+            .generate_from_aggregations(
+                OrganizationId::new(),
+                now - chrono::Duration::days(30),
+                now,
+                &[token_aggregation(100, now), compute.clone()],
+            )
+            .unwrap();
+        assert_eq!(invoice.currency, Currency::EUR);
+        assert_eq!(
+            invoice.line_items[1].unit_price,
+            Money::new(1, Currency::EUR)
+        );
+        assert_eq!(invoice.subtotal, Money::new(300 + 10, Currency::EUR));
+
+        generator.register_pricing_model(PricingModel::new(
+            "compute",
+            "Compute Pricing",
+            "compute",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 100,
+            },
+        ));
+        let mixed = generator.generate_from_aggregations(
+            OrganizationId::new(),
+            now - chrono::Duration::days(30),
+            now,
+            &[token_aggregation(100, now), compute],
+        );
+        assert!(matches!(
+            mixed,
+            Err(CurrencyError::Mismatch {
+                expected: Currency::EUR,
+                actual: Currency::USD,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_eur_org_consuming_usd_priced_metrics() {
+        use crate::currency::FixedExchangeRateProvider;
+
+        let mut generator = usd_token_generator();
+        generator.set_exchange_rate_provider(Arc::new(FixedExchangeRateProvider::new().with_rate(
+            Currency::USD,
+            Currency::EUR,
+            0.9,
+        )));
+
+        let now = Utc::now();
+        let invoice = generator
+            .generate_in_currency(
+                OrganizationId::new(),
+                Currency::EUR,
+                now - chrono::Duration::days(30),
+                now,
+                &[token_aggregation(1001, now)],
+            )
+            .unwrap();
+
+        // $30.03 * 0.9 = 27.027 EUR -> 27.03 EUR
+        assert_eq!(invoice.currency, Currency::EUR);
+        assert_eq!(invoice.subtotal, Money::new(2703, Currency::EUR));
+        assert_eq!(invoice.total, Money::new(2703, Currency::EUR));
+
+        let item = &invoice.line_items[0];
+        assert_eq!(item.amount.currency, Currency::EUR);
+
+        let conversion = item.conversion.as_ref().unwrap();
+        assert_eq!(conversion.rate.from, Currency::USD);
+        assert_eq!(conversion.rate.to, Currency::EUR);
+        assert_eq!(conversion.rate.rate, 0.9);
+        assert_eq!(conversion.original_amount, Money::usd(3003));
+        assert_eq!(conversion.original_unit_price, Money::usd(3));
+        assert_eq!(conversion.converted_at, now);
+    }
+
+    #[test]
+    fn test_rate_change_mid_period_uses_rate_at_aggregation_time() {
+        use crate::currency::{ExchangeRate, TableExchangeRateProvider};
+
+        let period_start = Utc::now() - chrono::Duration::days(30);
+        let rate_change = period_start + chrono::Duration::days(15);
+
+        let mut generator = usd_token_generator();
+        generator.set_exchange_rate_provider(Arc::new(TableExchangeRateProvider::from_rates([
+            ExchangeRate::new(Currency::USD, Currency::EUR, 0.90, period_start),
+            ExchangeRate::new(Currency::USD, Currency::EUR, 0.80, rate_change),
+        ])));
+
+        let early = rate_change - chrono::Duration::days(1);
+        let late = rate_change + chrono::Duration::days(1);
+        let invoice = generator
+            .generate_in_currency(
+                OrganizationId::new(),
+                Currency::EUR,
+                period_start,
+                Utc::now(),
+                &[token_aggregation(100, early), token_aggregation(100, late)],
+            )
+            .unwrap();
+
+        let rates: Vec<f64> = invoice
+            .line_items
+            .iter()
+            .map(|item| item.conversion.as_ref().unwrap().rate.rate)
+            .collect();
+        assert_eq!(rates, vec![0.90, 0.80]);
+        // $3.00 at 0.90 + $3.00 at 0.80
+        assert_eq!(invoice.subtotal, Money::new(270 + 240, Currency::EUR));
+    }
+
+    #[test]
+    fn test_mixed_currency_without_conversion_path_is_error() {
+        let generator = usd_token_generator();
+        let now = Utc::now();
+
+        let result = generator.generate_in_currency(
+            OrganizationId::new(),
+            Currency::EUR,
+            now - chrono::Duration::days(30),
+            now,
+            &[token_aggregation(10, now)],
+        );
+
+        assert!(matches!(
+            result,
+            Err(CurrencyError::NoConversionPath {
+                from: Currency::USD,
+                to: Currency::EUR,
+                ..
+            })
+        ));
+
+        let mut invoice = Invoice::new_in_currency(OrganizationId::new(), now, now, Currency::EUR);
+        let usd_item = LineItem::new("Calls", "api_calls", 1, "calls", Money::usd(1));
+        assert!(matches!(
+            invoice.try_add_line_item(usd_item),
+            Err(CurrencyError::Mismatch { .. })
+        ));
+    }
+
+    fn storage_aggregation(quantity: i64, aggregated_at: DateTime<Utc>) -> UsageAggregation {
+        UsageAggregation {
+            metric_code: "storage".to_string(),
+            description: "Storage".to_string(),
+            quantity,
+            unit: "gb".to_string(),
+            aggregated_at,
+            source: None,
+        }
+    }
+
+    fn reconciliation_generator(tax_rate: f64) -> InvoiceGenerator {
+        use crate::pricing::{PricingModel, PricingStrategy};
+
+        let mut generator = InvoiceGenerator::with_config(30, tax_rate);
+        for (metric_code, unit_price_cents) in [("tokens", 3), ("storage", 20)] {
+            generator.register_pricing_model(PricingModel::new(
+                metric_code,
+                metric_code,
+                metric_code,
+                PricingStrategy::PerUnit { unit_price_cents },
+            ));
+        }
+        generator
+    }
+
+    #[test]
+    fn test_preview_applies_credits_and_lists_unpriced_usage() {
+        let generator = usd_token_generator();
+        let now = Utc::now();
+        let aggregations = vec![
+            token_aggregation(100, now),
+            UsageAggregation {
+                metric_code: "gpu_seconds".to_string(),
+                description: "GPU".to_string(),
+                quantity: 50,
+                unit: "seconds".to_string(),
+                aggregated_at: now,
+                source: None,
+            },
+        ];
+
+        let preview = generator
+            .preview(
+                OrganizationId::new(),
+                Currency::USD,
+                now - chrono::Duration::days(30),
+                now,
+                &aggregations,
+                100,
+            )
+            .unwrap();
+
+        // 100 tokens * $0.03 + 50 unpriced units at the $0.01 fallback
+        assert_eq!(preview.invoice.subtotal.amount, 350);
+        assert_eq!(preview.credits_expected, 100);
+        assert_eq!(preview.credits_applied, 100);
+        assert_eq!(preview.invoice.total.amount, 250);
+        assert_eq!(preview.invoice.status, InvoiceStatus::Draft);
+        assert_eq!(
+            preview.unpriced,
+            vec![UnpricedUsage {
+                metric_code: "gpu_seconds".to_string(),
+                quantity: 50,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_reconcile_flags_each_anomaly_class() {
+        let generator = reconciliation_generator(10.0);
+        let org_id = OrganizationId::new();
+        let now = Utc::now();
+        let previous_start = now - chrono::Duration::days(60);
+        let current_start = now - chrono::Duration::days(30);
+
+        let previous = generator
+            .generate_from_aggregations(
+                org_id,
+                previous_start,
+                current_start,
+                &[
+                    token_aggregation(100, current_start),
+                    storage_aggregation(10, current_start),
+                ],
+            )
+            .unwrap();
+
+        // Tokens spike 10x, storage disappears, an unpriced code shows up,
+        // and the credit balance exceeds the pre-tax subtotal.
+        let gpu = UsageAggregation {
+            metric_code: "gpu_seconds".to_string(),
+            description: "GPU".to_string(),
+            quantity: 5,
+            unit: "seconds".to_string(),
+            aggregated_at: now,
+            source: None,
+        };
+        let preview = generator
+            .preview(
+                org_id,
+                Currency::USD,
+                current_start,
+                now,
+                &[token_aggregation(1000, now), gpu],
+                5000,
+            )
+            .unwrap();
+
+        let report = generator.reconcile(&preview, &previous);
+        assert_eq!(report.previous_invoice_id, previous.id);
+        assert!(!report.is_clean());
+
+        let has = |pred: &dyn Fn(&ReconciliationAnomaly) -> bool| report.anomalies.iter().any(pred);
+        assert!(has(&|a| matches!(
+            a,
+            ReconciliationAnomaly::MissingLineItem { metric_code, previous_quantity: 10, .. }
+                if metric_code == "storage"
+        )));
+        assert!(has(&|a| matches!(
+            a,
+            ReconciliationAnomaly::NewLineItem { metric_code, quantity: 5, .. }
+                if metric_code == "gpu_seconds"
+        )));
+        assert!(has(&|a| matches!(
+            a,
+            ReconciliationAnomaly::QuantityChanged { metric_code, previous: 100, current: 1000, change_percent: Some(p) }
+                if metric_code == "tokens" && (*p - 900.0).abs() < f64::EPSILON
+        )));
+        assert!(has(&|a| matches!(
+            a,
+            ReconciliationAnomaly::AmountChanged { metric_code, previous, current, .. }
+                if metric_code == "tokens" && previous.amount == 300 && current.amount == 3000
+        )));
+        assert!(has(&|a| matches!(
+            a,
+            ReconciliationAnomaly::UnpricedCode { metric_code, quantity: 5 } if metric_code == "gpu_seconds"
+        )));
+        // Expected: subtotal 3005 + 10% tax 300; applied credits are capped at the subtotal
+        assert!(has(&|a| matches!(
+            a,
+            ReconciliationAnomaly::CreditMismatch {
+                expected_cents: 3305,
+                applied_cents: 3005
+            }
+        )));
+    }
+
+    #[test]
+    fn test_reconcile_respects_thresholds() {
+        let mut generator = reconciliation_generator(0.0);
+        let org_id = OrganizationId::new();
+        let now = Utc::now();
+        let start = now - chrono::Duration::days(30);
+
+        let previous = generator
+            .generate_from_aggregations(
+                org_id,
+                start - chrono::Duration::days(30),
+                start,
+                &[
+                    token_aggregation(100, start),
+                    storage_aggregation(10, start),
+                ],
+            )
+            .unwrap();
+        let current = [token_aggregation(140, now), storage_aggregation(10, now)];
+
+        let preview = generator
+            .preview(org_id, Currency::USD, start, now, &current, 0)
+            .unwrap();
+        assert!(generator.reconcile(&preview, &previous).is_clean());
+
+        generator.set_reconciliation_thresholds(ReconciliationThresholds {
+            quantity_change_percent: 25.0,
+            amount_change_percent: 100.0,
+        });
+        let report = generator.reconcile(&preview, &previous);
+        assert_eq!(report.anomalies.len(), 1);
+        assert!(matches!(
+            &report.anomalies[0],
+            ReconciliationAnomaly::QuantityChanged { metric_code, .. } if metric_code == "tokens"
+        ));
+    }
+
+    fn token_price_versions(change_at: DateTime<Utc>) -> (PricingModel, PricingModel) {
+        use crate::pricing::PricingStrategy;
+
+        let v1 = PricingModel::new(
+            "tokens_2026_01",
+            "Token Pricing",
+            "tokens",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 2,
+            },
+        );
+        let v2 = v1.successor(
+            "tokens_2026_03",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 3,
+            },
+            change_at,
+        );
+        (v1, v2)
+    }
+
+    fn month_aggregation(
+        org_id: OrganizationId,
+        quantity: i64,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> UsageAggregation {
+        UsageAggregation {
+            metric_code: "tokens".to_string(),
+            description: "Tokens".to_string(),
+            quantity,
+            unit: "tokens".to_string(),
+            aggregated_at: end,
+            source: Some(LineItemSource {
+                selection: UsageSelection::new(org_id, "tokens", start, end),
+                event_count: 30,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_price_change_mid_month_splits_line_item() {
+        use chrono::TimeZone;
+
+        // April has 30 days; the price rises at the start of day 16
+        let start = Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap();
+        let change_at = Utc.with_ymd_and_hms(2026, 4, 16, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2026, 5, 1, 0, 0, 0).unwrap() - Duration::microseconds(1);
+        let (v1, v2) = token_price_versions(change_at);
+
+        let mut generator = InvoiceGenerator::new();
+        generator.add_pricing_version(v1).unwrap();
+        generator.add_pricing_version(v2).unwrap();
+
+        let org_id = OrganizationId::new();
+        let invoice = generator
+            .generate_from_aggregations(
+                org_id,
+                start,
+                end,
+                &[month_aggregation(org_id, 3000, start, end)],
+            )
+            .unwrap();
+
+        assert_eq!(invoice.line_items.len(), 2);
+        let (first, second) = (&invoice.line_items[0], &invoice.line_items[1]);
+
+        assert_eq!(first.quantity, 1500);
+        assert_eq!(first.unit_price, Money::usd(2));
+        assert_eq!(first.amount, Money::usd(3000));
+        assert_eq!(first.pricing.as_ref().unwrap().version, 1);
+        let selection = &first.source.as_ref().unwrap().selection;
+        assert_eq!(selection.window_start, start);
+        assert_eq!(selection.window_end, change_at - Duration::microseconds(1));
+
+        assert_eq!(second.quantity, 1500);
+        assert_eq!(second.unit_price, Money::usd(3));
+        assert_eq!(second.amount, Money::usd(4500));
+        assert_eq!(second.pricing.as_ref().unwrap().model_id, "tokens_2026_03");
+        let source = second.source.as_ref().unwrap();
+        assert_eq!(source.selection.window_start, change_at);
+        assert_eq!(source.selection.window_end, end);
+        assert_eq!(
+            first.source.as_ref().unwrap().event_count + source.event_count,
+            30
+        );
+
+        assert_eq!(invoice.subtotal, Money::usd(7500));
+    }
+
+    #[test]
+    fn test_grandfathered_org_keeps_pinned_price() {
+        use chrono::TimeZone;
+
+        let start = Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap();
+        let change_at = Utc.with_ymd_and_hms(2026, 4, 16, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2026, 5, 1, 0, 0, 0).unwrap() - Duration::microseconds(1);
+        let (v1, v2) = token_price_versions(change_at);
+
+        let mut generator = InvoiceGenerator::new();
+        generator.add_pricing_version(v1).unwrap();
+        generator.add_pricing_version(v2).unwrap();
+
+        let grandfathered = OrganizationId::new();
+        generator
+            .pin_pricing_version(grandfathered, "tokens_2026_01")
+            .unwrap();
+        assert!(generator
+            .pin_pricing_version(grandfathered, "missing")
+            .is_err());
+
+        let invoice = generator
+            .generate_from_aggregations(
+                grandfathered,
+                start,
+                end,
+                &[month_aggregation(grandfathered, 3000, start, end)],
+            )
+            .unwrap();
+        assert_eq!(invoice.line_items.len(), 1);
+        assert_eq!(invoice.line_items[0].amount, Money::usd(6000));
+        assert_eq!(
+            invoice.line_items[0].pricing.as_ref().unwrap().model_id,
+            "tokens_2026_01"
+        );
+
+        // After an explicit migration the org is priced like everyone else
+        assert_eq!(
+            generator
+                .migrate_pricing(grandfathered, "tokens")
+                .as_deref(),
+            Some("tokens_2026_01")
+        );
+        let later = change_at + Duration::days(30);
+        let invoice = generator
+            .generate_from_aggregations(
+                grandfathered,
+                later,
+                later + Duration::days(1),
+                &[month_aggregation(
+                    grandfathered,
+                    100,
+                    later,
+                    later + Duration::days(1),
+                )],
+            )
+            .unwrap();
+        assert_eq!(invoice.line_items[0].amount, Money::usd(300));
+    }
+
+    fn april() -> (DateTime<Utc>, DateTime<Utc>) {
+        use chrono::TimeZone;
+
+        let start = Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2026, 5, 1, 0, 0, 0).unwrap() - Duration::microseconds(1);
+        (start, end)
+    }
+
+    fn day(d: u32) -> DateTime<Utc> {
+        use chrono::TimeZone;
+
+        Utc.with_ymd_and_hms(2026, 4, d, 0, 0, 0).unwrap()
+    }
+
+    fn plan_segment(
+        model: &PricingModel,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> PricingSegment {
+        PricingSegment {
+            model: model.clone(),
+            start,
+            end,
+        }
+    }
+
+    fn per_unit(id: &str, metric_code: &str, unit_price_cents: i64) -> PricingModel {
+        PricingModel::new(
+            id,
+            id,
+            metric_code,
+            PricingStrategy::PerUnit { unit_price_cents },
+        )
+    }
+
+    #[test]
+    fn test_prorated_upgrade_mid_month() {
+        let (start, end) = april();
+        let upgrade = day(16);
+        let before = upgrade - Duration::microseconds(1);
+        let (basic, pro) = (per_unit("basic", "tokens", 2), per_unit("pro", "tokens", 3));
+        let basic_seat = PricingModel::new(
+            "basic_seat",
+            "Basic seat",
+            "seats",
+            PricingStrategy::FlatFee { amount_cents: 3000 },
+        );
+        let pro_seat = PricingModel::new(
+            "pro_seat",
+            "Pro seat",
+            "seats",
+            PricingStrategy::FlatFee { amount_cents: 6001 },
+        );
+        let plan = ProrationPlan::new(
+            start,
+            end,
+            vec![
+                plan_segment(&pro, upgrade, end),
+                plan_segment(&basic, start, before),
+                plan_segment(&basic_seat, start, before),
+                plan_segment(&pro_seat, upgrade, end),
+            ],
+        )
+        .unwrap();
+
+        let org_id = OrganizationId::new();
+        let mut seats = month_aggregation(org_id, 1, start, end);
+        seats.metric_code = "seats".to_string();
+        seats.description = "Seats".to_string();
+        let generator = InvoiceGenerator::with_config(30, 10.0);
+        let invoice = generator
+            .generate_prorated(
+                org_id,
+                Currency::USD,
+                &plan,
+                &[
+                    month_aggregation(org_id, 1000, start, before),
+                    seats,
+                    month_aggregation(org_id, 2000, upgrade, end),
+                ],
+            )
+            .unwrap();
+
+        let items: Vec<_> = invoice
+            .line_items
+            .iter()
+            .map(|item| (item.description.as_str(), item.quantity, item.amount.amount))
+            .collect();
+        assert_eq!(
+            items,
+            [
+                ("Tokens (2026-04-01 to 2026-04-15)", 1000, 2000),
+                ("Tokens (2026-04-16 to 2026-04-30)", 2000, 6000),
+                // Half a month of each seat price, the odd cent truncated
+                ("Seats (2026-04-01 to 2026-04-15)", 0, 1500),
+                ("Seats (2026-04-16 to 2026-04-30)", 1, 3000),
+            ]
+        );
+        assert_eq!(
+            invoice.line_items[1].pricing.as_ref().unwrap().model_id,
+            "pro"
+        );
+        let selection = &invoice.line_items[1].source.as_ref().unwrap().selection;
+        assert_eq!(
+            (selection.window_start, selection.window_end),
+            (upgrade, end)
+        );
+
+        let line_total: i64 = invoice
+            .line_items
+            .iter()
+            .map(|item| item.amount.amount)
+            .sum();
+        assert_eq!(invoice.subtotal.amount, line_total);
+        assert_eq!(invoice.tax.amount, 1250);
+        assert_eq!(invoice.total.amount, line_total + 1250);
+    }
+
+    #[test]
+    fn test_plan_change_at_period_boundary() {
+        let (start, end) = april();
+        let (basic, pro) = (per_unit("basic", "tokens", 2), per_unit("pro", "tokens", 3));
+        let org_id = OrganizationId::new();
+        let usage = [month_aggregation(org_id, 1000, start, end)];
+
+        // Changes taking effect right at either edge leave one segment
+        for (old_until, new_from, expected) in [
+            (start - Duration::microseconds(1), start, ("pro", 3000)),
+            (end, end + Duration::microseconds(1), ("basic", 2000)),
+        ] {
+            let plan = ProrationPlan::new(
+                start,
+                end,
+                vec![
+                    plan_segment(&basic, start - Duration::days(30), old_until),
+                    plan_segment(&pro, new_from, end + Duration::days(30)),
+                ],
+            )
+            .unwrap();
+            assert_eq!(plan.segments("tokens").len(), 1);
+
+            let invoice = InvoiceGenerator::new()
+                .generate_prorated(org_id, Currency::USD, &plan, &usage)
+                .unwrap();
+            assert_eq!(invoice.line_items.len(), 1);
+            let item = &invoice.line_items[0];
+            assert_eq!(item.pricing.as_ref().unwrap().model_id, expected.0);
+            assert_eq!(item.amount.amount, expected.1);
+            assert_eq!(item.description, "Tokens (2026-04-01 to 2026-04-30)");
+        }
+    }
+
+    #[test]
+    fn test_multiple_plan_changes_split_usage_by_time() {
+        let (start, end) = april();
+        let models = [
+            per_unit("a", "tokens", 1),
+            per_unit("b", "tokens", 2),
+            per_unit("c", "tokens", 3),
+        ];
+        let bounds = [(start, day(11)), (day(11), day(21)), (day(21), end)];
+        let plan = ProrationPlan::new(
+            start,
+            end,
+            models
+                .iter()
+                .zip(bounds)
+                .map(|(model, (from, to))| {
+                    let to = if to == end {
+                        to
+                    } else {
+                        to - Duration::microseconds(1)
+                    };
+                    plan_segment(model, from, to)
+                })
+                .collect(),
+        )
+        .unwrap();
+
+        let org_id = OrganizationId::new();
+        let invoice = InvoiceGenerator::new()
+            .generate_prorated(
+                org_id,
+                Currency::USD,
+                &plan,
+                &[month_aggregation(org_id, 1000, start, end)],
+            )
+            .unwrap();
+
+        // Ten days each; the last segment takes the remainder
+        let quantities: Vec<_> = invoice
+            .line_items
+            .iter()
+            .map(|item| item.quantity)
+            .collect();
+        assert_eq!(quantities, [333, 333, 334]);
+        let events: u64 = invoice
+            .line_items
+            .iter()
+            .map(|item| item.source.as_ref().unwrap().event_count)
+            .sum();
+        assert_eq!(events, 30);
+        assert_eq!(invoice.subtotal.amount, 333 + 666 + 1002);
+    }
+
+    #[test]
+    fn test_tier_boundaries_per_segment_or_whole_period() {
+        let (start, end) = april();
+        let change = day(16);
+        let tiers = |id: &str, overage_cents: i64| {
+            PricingModel::new(
+                id,
+                id,
+                "tokens",
+                PricingStrategy::GraduatedTiered {
+                    tiers: vec![
+                        PricingTier {
+                            from_units: 0,
+                            to_units: Some(100),
+                            unit_price_cents: 10,
+                            flat_fee_cents: None,
+                        },
+                        PricingTier {
+                            from_units: 100,
+                            to_units: None,
+                            unit_price_cents: overage_cents,
+                            flat_fee_cents: None,
+                        },
+                    ],
+                },
+            )
+        };
+        let segments = vec![
+            plan_segment(&tiers("old", 5), start, change - Duration::microseconds(1)),
+            plan_segment(&tiers("new", 4), change, end),
+        ];
+        let org_id = OrganizationId::new();
+        let usage = [
+            month_aggregation(org_id, 80, start, change - Duration::microseconds(1)),
+            month_aggregation(org_id, 80, change, end),
+        ];
+
+        let amounts = |tier_boundaries| {
+            let plan = ProrationPlan::new(start, end, segments.clone())
+                .unwrap()
+                .with_tier_boundaries(tier_boundaries);
+            InvoiceGenerator::new()
+                .generate_prorated(org_id, Currency::USD, &plan, &usage)
+                .unwrap()
+                .line_items
+                .iter()
+                .map(|item| item.amount.amount)
+                .collect::<Vec<_>>()
+        };
+        // Both segments start at the first tier
+        assert_eq!(amounts(TierBoundaries::PerSegment), [800, 800]);
+        // The second segment starts at unit 80: 20 at 10c, 60 at 4c
+        assert_eq!(amounts(TierBoundaries::WholePeriod), [800, 440]);
+    }
+
+    #[test]
+    fn test_proration_plan_rejects_gaps_and_overlaps() {
+        let (start, end) = april();
+        let (basic, pro) = (per_unit("basic", "tokens", 2), per_unit("pro", "tokens", 3));
+
+        let gap = ProrationPlan::new(
+            start,
+            end,
+            vec![
+                plan_segment(&basic, start, day(10)),
+                plan_segment(&pro, day(12), end),
+            ],
+        );
+        assert!(matches!(gap, Err(CretoError::ValidationFailed(msg)) if msg.contains("gap")));
+
+        let overlap = ProrationPlan::new(
+            start,
+            end,
+            vec![
+                plan_segment(&basic, start, day(12)),
+                plan_segment(&pro, day(10), end),
+            ],
+        );
+        assert!(
+            matches!(overlap, Err(CretoError::ValidationFailed(msg)) if msg.contains("overlap"))
+        );
+
+        let short = ProrationPlan::new(start, end, vec![plan_segment(&basic, start, day(20))]);
+        assert!(short.is_err());
+    }
 }
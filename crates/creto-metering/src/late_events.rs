@@ -0,0 +1,399 @@
+//! Event-time watermarks and late event handling.
+//!
+//! Usage is billed by event timestamp, so an event that arrives long after it
+//! happened (an offline agent flushing its buffer) can land in a period that
+//! has already been invoiced. The [`WatermarkTracker`] keeps, per
+//! organization and metric code, the latest event timestamp ingested; events
+//! older than the watermark minus the allowed lateness are diverted to a
+//! [`LateEventQueue`] instead of the main usage store.
+//!
+//! Queued events are resolved per organization according to its
+//! [`LateEventPolicy`]: either folded into a correction on the next invoice
+//! or rejected.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{CretoError, OrganizationId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::events::UsageEvent;
+use crate::repository::LateEventRepository;
+
+/// Watermark configuration.
+#[derive(Debug, Clone)]
+pub struct WatermarkConfig {
+    /// How far behind the watermark an event may be and still count as on time.
+    pub allowed_lateness: Duration,
+}
+
+impl Default for WatermarkConfig {
+    fn default() -> Self {
+        Self {
+            allowed_lateness: Duration::hours(1),
+        }
+    }
+}
+
+/// Whether an event arrived within the allowed lateness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    /// Within the allowed lateness; the watermark may have advanced.
+    OnTime,
+    /// Older than the watermark minus the allowed lateness.
+    Late {
+        /// Watermark the event was compared against.
+        watermark: DateTime<Utc>,
+    },
+}
+
+/// Tracks the latest event timestamp per (organization, metric code).
+///
+/// Watermarks are held in memory and rebuilt from incoming traffic.
+#[derive(Debug, Default)]
+pub struct WatermarkTracker {
+    config: WatermarkConfig,
+    watermarks: RwLock<HashMap<(OrganizationId, String), DateTime<Utc>>>,
+}
+
+impl WatermarkTracker {
+    /// Create a tracker.
+    pub fn new(config: WatermarkConfig) -> Self {
+        Self {
+            config,
+            watermarks: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the configuration.
+    pub fn config(&self) -> &WatermarkConfig {
+        &self.config
+    }
+
+    /// Get the current watermark for an organization's metric.
+    pub fn watermark(
+        &self,
+        organization_id: &OrganizationId,
+        metric_code: &str,
+    ) -> Option<DateTime<Utc>> {
+        self.watermarks
+            .read()
+            .unwrap()
+            .get(&(*organization_id, metric_code.to_string()))
+            .copied()
+    }
+
+    /// Classify an event, advancing the watermark if it is on time.
+    pub fn observe(&self, event: &UsageEvent) -> Admission {
+        let mut watermarks = self.watermarks.write().unwrap();
+        let watermark = watermarks
+            .entry((event.organization_id, event.code.clone()))
+            .or_insert(event.timestamp);
+
+        if event.timestamp < *watermark - self.config.allowed_lateness {
+            return Admission::Late {
+                watermark: *watermark,
+            };
+        }
+        if event.timestamp > *watermark {
+            *watermark = event.timestamp;
+        }
+        Admission::OnTime
+    }
+}
+
+/// How an organization's late events are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LateEventPolicy {
+    /// Bill late usage as a correction on the next invoice.
+    #[default]
+    FoldIntoNextInvoice,
+    /// Drop late usage.
+    Reject,
+}
+
+/// Resolution state of a late event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LateEventStatus {
+    /// Waiting for resolution.
+    Pending,
+    /// Billed as a correction on the next invoice.
+    Folded,
+    /// Dropped.
+    Rejected,
+}
+
+/// A usage event diverted from the main store because it arrived late.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LateEvent {
+    /// Queue entry ID.
+    pub id: Uuid,
+    /// The event as received.
+    pub event: UsageEvent,
+    /// Watermark at the time the event arrived.
+    pub watermark: DateTime<Utc>,
+    /// When the event arrived.
+    pub received_at: DateTime<Utc>,
+    /// Resolution state.
+    pub status: LateEventStatus,
+    /// When the event was resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl LateEvent {
+    /// Queue an event that arrived behind `watermark`.
+    pub fn new(event: UsageEvent, watermark: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            event,
+            watermark,
+            received_at: Utc::now(),
+            status: LateEventStatus::Pending,
+            resolved_at: None,
+        }
+    }
+
+    /// How far behind the watermark the event was.
+    pub fn lateness(&self) -> Duration {
+        self.watermark - self.event.timestamp
+    }
+}
+
+/// Outcome of admitting an event through the queue.
+#[derive(Debug, Clone)]
+pub enum Admitted {
+    /// On time; the caller should ingest it normally.
+    OnTime(UsageEvent),
+    /// Late; the event was queued for resolution.
+    Late(LateEvent),
+}
+
+/// Late events resolved in one pass for an organization.
+#[derive(Debug, Clone)]
+pub struct LateEventResolution {
+    /// Policy that was applied.
+    pub policy: LateEventPolicy,
+    /// Events resolved, oldest arrival first.
+    pub events: Vec<LateEvent>,
+}
+
+/// In-memory late event store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryLateEventRepository {
+    events: RwLock<Vec<LateEvent>>,
+}
+
+impl InMemoryLateEventRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl LateEventRepository for InMemoryLateEventRepository {
+    async fn enqueue(&self, late_event: &LateEvent) -> Result<(), CretoError> {
+        self.events.write().unwrap().push(late_event.clone());
+        Ok(())
+    }
+
+    async fn list_pending(&self, org_id: OrganizationId) -> Result<Vec<LateEvent>, CretoError> {
+        Ok(self
+            .events
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|e| e.event.organization_id == org_id && e.status == LateEventStatus::Pending)
+            .cloned()
+            .collect())
+    }
+
+    async fn mark_resolved(
+        &self,
+        ids: &[Uuid],
+        status: LateEventStatus,
+        resolved_at: DateTime<Utc>,
+    ) -> Result<(), CretoError> {
+        for event in self.events.write().unwrap().iter_mut() {
+            if ids.contains(&event.id) {
+                event.status = status;
+                event.resolved_at = Some(resolved_at);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Watermark-based router that diverts late events to a repository.
+pub struct LateEventQueue<R: ?Sized> {
+    repository: Arc<R>,
+    watermarks: WatermarkTracker,
+    policies: RwLock<HashMap<OrganizationId, LateEventPolicy>>,
+}
+
+impl<R: LateEventRepository + ?Sized> LateEventQueue<R> {
+    /// Create a queue over a late event repository.
+    pub fn new(repository: Arc<R>, config: WatermarkConfig) -> Self {
+        Self {
+            repository,
+            watermarks: WatermarkTracker::new(config),
+            policies: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the watermark tracker.
+    pub fn watermarks(&self) -> &WatermarkTracker {
+        &self.watermarks
+    }
+
+    /// Set how an organization's late events are resolved.
+    pub fn set_policy(&self, organization_id: OrganizationId, policy: LateEventPolicy) {
+        self.policies
+            .write()
+            .unwrap()
+            .insert(organization_id, policy);
+    }
+
+    /// Get an organization's late event policy.
+    pub fn policy(&self, organization_id: &OrganizationId) -> LateEventPolicy {
+        self.policies
+            .read()
+            .unwrap()
+            .get(organization_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Check an event against its watermark, queueing it if late.
+    pub async fn admit(&self, event: UsageEvent) -> Result<Admitted, CretoError> {
+        match self.watermarks.observe(&event) {
+            Admission::OnTime => Ok(Admitted::OnTime(event)),
+            Admission::Late { watermark } => {
+                let late_event = LateEvent::new(event, watermark);
+                self.repository.enqueue(&late_event).await?;
+                tracing::warn!(
+                    org_id = %late_event.event.organization_id,
+                    code = %late_event.event.code,
+                    lateness_secs = late_event.lateness().num_seconds(),
+                    "Late usage event queued"
+                );
+                Ok(Admitted::Late(late_event))
+            }
+        }
+    }
+
+    /// List an organization's unresolved late events.
+    pub async fn pending(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<Vec<LateEvent>, CretoError> {
+        self.repository.list_pending(organization_id).await
+    }
+
+    /// Resolve all of an organization's pending late events by its policy.
+    pub async fn resolve(
+        &self,
+        organization_id: OrganizationId,
+        resolved_at: DateTime<Utc>,
+    ) -> Result<LateEventResolution, CretoError> {
+        let policy = self.policy(&organization_id);
+        let status = match policy {
+            LateEventPolicy::FoldIntoNextInvoice => LateEventStatus::Folded,
+            LateEventPolicy::Reject => LateEventStatus::Rejected,
+        };
+
+        let mut events = self.repository.list_pending(organization_id).await?;
+        let ids: Vec<Uuid> = events.iter().map(|e| e.id).collect();
+        if !ids.is_empty() {
+            self.repository
+                .mark_resolved(&ids, status, resolved_at)
+                .await?;
+        }
+        for event in &mut events {
+            event.status = status;
+            event.resolved_at = Some(resolved_at);
+        }
+
+        Ok(LateEventResolution { policy, events })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::UsageEventType;
+    use creto_common::AgentId;
+
+    fn event_at(org_id: OrganizationId, timestamp: DateTime<Utc>) -> UsageEvent {
+        UsageEvent::builder()
+            .transaction_id(Uuid::now_v7().to_string())
+            .organization_id(org_id)
+            .agent_id(AgentId::new())
+            .event_type(UsageEventType::ApiCall)
+            .code("api_calls")
+            .quantity(1)
+            .timestamp(timestamp)
+            .build()
+    }
+
+    #[test]
+    fn test_watermark_advances_and_flags_late_events() {
+        let tracker = WatermarkTracker::new(WatermarkConfig::default());
+        let org_id = OrganizationId::new();
+        let now = Utc::now();
+
+        assert_eq!(tracker.observe(&event_at(org_id, now)), Admission::OnTime);
+        // Within the allowed lateness
+        assert_eq!(
+            tracker.observe(&event_at(org_id, now - Duration::minutes(30))),
+            Admission::OnTime
+        );
+        assert_eq!(tracker.watermark(&org_id, "api_calls"), Some(now));
+
+        assert_eq!(
+            tracker.observe(&event_at(org_id, now - Duration::hours(2))),
+            Admission::Late { watermark: now }
+        );
+
+        // Watermarks are per organization
+        assert_eq!(
+            tracker.observe(&event_at(OrganizationId::new(), now - Duration::days(3))),
+            Admission::OnTime
+        );
+    }
+
+    #[tokio::test]
+    async fn test_queue_resolves_by_org_policy() {
+        let queue = LateEventQueue::new(
+            Arc::new(InMemoryLateEventRepository::new()),
+            WatermarkConfig::default(),
+        );
+        let org_id = OrganizationId::new();
+        let now = Utc::now();
+
+        assert!(matches!(
+            queue.admit(event_at(org_id, now)).await.unwrap(),
+            Admitted::OnTime(_)
+        ));
+        let Admitted::Late(late) = queue
+            .admit(event_at(org_id, now - Duration::days(3)))
+            .await
+            .unwrap()
+        else {
+            panic!("expected late admission");
+        };
+        assert_eq!(late.lateness(), Duration::days(3));
+        assert_eq!(queue.pending(org_id).await.unwrap().len(), 1);
+
+        queue.set_policy(org_id, LateEventPolicy::Reject);
+        let resolution = queue.resolve(org_id, now).await.unwrap();
+        assert_eq!(resolution.policy, LateEventPolicy::Reject);
+        assert_eq!(resolution.events[0].status, LateEventStatus::Rejected);
+        assert!(queue.pending(org_id).await.unwrap().is_empty());
+    }
+}
@@ -53,37 +53,107 @@
 //! Inspired by [Lago](https://github.com/getlago/lago) event ingestion patterns,
 //! rebuilt with Creto Sovereign primitives (NHI, Cedar authorization, audit logging).
 
+pub mod adjustments;
 pub mod aggregation;
+pub mod aliases;
+pub mod anomaly;
+pub mod api_keys;
+pub mod auto_top_up;
 pub mod credits;
+pub mod currency;
 pub mod dedup;
+pub mod drilldown;
 pub mod events;
 pub mod grpc;
 pub mod invoice;
+pub mod late_events;
 pub mod pricing;
 pub mod quota;
 pub mod repository;
+pub mod sampling;
 pub mod service;
+pub mod snapshot;
 pub mod validation;
 
-pub use aggregation::{Aggregation, AggregationEngine, AggregationType, AggregationValue};
+pub use adjustments::{
+    AdjustmentApplication, AdjustmentApprover, AdjustmentDecision, AdjustmentStatus,
+    AdjustmentSummary, InMemoryInvoiceAdjustmentRepository, InvoiceAdjustment,
+    InvoiceAdjustmentManager, ADJUSTMENT_METRIC_CODE,
+};
+pub use aggregation::{
+    event_property, Aggregation, AggregationEngine, AggregationType, AggregationValue, HyperLogLog,
+    UniqueCountMode, UsageSelection,
+};
+pub use aliases::{
+    InMemoryMetricAliasRepository, MetricAlias, MetricAliasRegistry, ALIASED_FROM_PROPERTY,
+};
+pub use anomaly::{
+    AnomalyAlert, AnomalyBaseline, AnomalyConfig, AnomalyDetector, AnomalySeverity, AnomalySink,
+    InMemoryAnomalyBaselineRepository, InMemoryAnomalySink, RECENT_ALERT_CAPACITY,
+};
+pub use api_keys::{
+    ApiKey, ApiKeyError, ApiKeyManager, AuthenticatedKey, InMemoryApiKeyRepository, IssuedApiKey,
+};
+pub use auto_top_up::{
+    AutoTopUp, AutoTopUpConfig, AutoTopUpRule, InMemoryAutoTopUpRepository, InMemoryTopUpNotifier,
+    PaymentProvider, PaymentReceipt, PaymentRequest, TopUpAlert, TopUpAttempt, TopUpNotifier,
+    TopUpOutcome, TopUpPeriod, TopUpStatus,
+};
 pub use credits::{
-    CreditApplication, CreditManager, CreditTransaction, CreditTransactionType, Wallet,
+    CreditApplication, CreditManager, CreditTransaction, CreditTransactionType, DebitHook, Wallet,
 };
-pub use dedup::{DedupConfig, DedupResult, Deduplicator};
-pub use events::{UsageEvent, UsageEventType};
-pub use grpc::{MeteringGrpcService, MeteringServiceConfig};
+pub use currency::{
+    CurrencyError, ExchangeRate, ExchangeRateProvider, FixedExchangeRateProvider,
+    OrgBillingProfile, TableExchangeRateProvider,
+};
+pub use dedup::{DedupConfig, DedupMetrics, DedupResult, Deduplicator};
+pub use drilldown::{
+    AgentUsage, EventCursor, EventPagination, LineItemEvents, LineItemVerification,
+};
+pub use events::{InMemoryEventRepository, UsageEvent, UsageEventType};
+pub use grpc::{ApiKeyAuthLayer, MeteringGrpcService, MeteringServiceConfig, RateLimitConfig};
 pub use invoice::{
-    Discount, DiscountType, Invoice, InvoiceGenerator, InvoiceStatus, LineItem, UsageAggregation,
+    AppliedPricing, CurrencyConversion, Discount, DiscountType, InMemoryInvoiceRepository, Invoice,
+    InvoiceGenerator, InvoicePreview, InvoiceStatus, LineItem, LineItemSource, ProrationPlan,
+    ReconciliationAnomaly, ReconciliationReport, ReconciliationThresholds, TierBoundaries,
+    UnpricedUsage, UsageAggregation, CREDITS_DISCOUNT_CODE,
+};
+pub use late_events::{
+    Admission, Admitted, InMemoryLateEventRepository, LateEvent, LateEventPolicy, LateEventQueue,
+    LateEventResolution, LateEventStatus, WatermarkConfig, WatermarkTracker,
+};
+pub use pricing::{
+    InMemoryPricingModelRepository, PricingCatalog, PricingEngine, PricingModel, PricingSegment,
+    PricingStrategy, PricingTier,
 };
-pub use pricing::{PricingEngine, PricingModel, PricingStrategy, PricingTier};
 pub use quota::{
-    BloomConfig, CheckSource, EnforcerConfig, EnforcerError, Quota, QuotaBloomFilter,
-    QuotaCheckResult, QuotaEnforcer, QuotaKey, QuotaPeriod, QuotaStatus, Reservation,
-    ReservationError, ReservationStatus, ReservationStore, ReserveRequest,
+    BloomConfig, BurstAllowance, CacheMetrics, CheckSource, CounterStore, DelegationMultiplier,
+    EnforcerConfig, EnforcerError, ExemptionAllowance, ExemptionApprover, ExemptionConfig,
+    InMemoryQuotaBackend, InMemoryQuotaExemptionRepository, InMemoryQuotaRepository,
+    InMemoryReconciliationAuditSink, InMemoryReservationRepository, Quota, QuotaBackend,
+    QuotaBloomFilter, QuotaCheckResult, QuotaCounter, QuotaDenialReason, QuotaDrift, QuotaEnforcer,
+    QuotaExemption, QuotaExemptionManager, QuotaKey, QuotaPeriod, QuotaReconciler,
+    QuotaReconciliationConfig, QuotaReconciliationReport, QuotaRollover, QuotaRolloverListener,
+    QuotaStatus, QuotaWarning, QuotaWarningSink, ReconciliationAuditSink, ReconciliationMode,
+    ReconciliationSkip, RedisQuotaBackend, Reservation, ReservationError, ReservationStatus,
+    ReservationStore, ReserveRequest, SkippedQuota,
 };
 pub use repository::{
-    EventRepository, InvoiceRecord, InvoiceRepository, PgEventRepository, PgInvoiceRepository,
-    PgQuotaRepository, QuotaRepository,
+    AnomalyBaselineRepository, ApiKeyRepository, AutoTopUpRepository, BillingProfileRepository,
+    EventRepository, ExchangeRateRepository, InvoiceAdjustmentRepository, InvoiceRecord,
+    InvoiceRepository, LateEventRepository, MetricAliasRepository, PgAnomalyBaselineRepository,
+    PgApiKeyRepository, PgAutoTopUpRepository, PgBillingProfileRepository, PgEventRepository,
+    PgExchangeRateRepository, PgInvoiceAdjustmentRepository, PgInvoiceRepository,
+    PgLateEventRepository, PgMetricAliasRepository, PgPricingModelRepository,
+    PgQuotaExemptionRepository, PgQuotaRepository, PgReservationRepository, PricingModelRepository,
+    QuotaExemptionRepository, QuotaRepository, ReservationRepository,
+};
+pub use sampling::{
+    IngestionSampler, SamplingMethod, SamplingRule, SAMPLED_PROPERTY, SAMPLED_QUANTITY_PROPERTY,
+    SAMPLE_FACTOR_PROPERTY,
+};
+pub use service::{IngestOutcome, MeteringService};
+pub use snapshot::{MeteringSnapshot, MeteringSnapshotContributor, QuotaSummary, METERING_SECTION};
+pub use validation::{
+    BatchValidationResult, EventValidator, ValidationConfig, ValidationError, WindowConfigError,
 };
-pub use service::MeteringService;
-pub use validation::{BatchValidationResult, EventValidator, ValidationConfig, ValidationError};
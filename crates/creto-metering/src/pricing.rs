@@ -1,10 +1,26 @@
 //! Pricing models for usage-based billing.
 //!
 //! Supports multiple pricing strategies following Lago patterns.
-
-use creto_common::types::Money;
+//!
+//! Models are versioned: each version of a metric's price applies over an
+//! effective range, so past usage keeps the price it was incurred under and
+//! price changes can be scheduled ahead. A [`PricingCatalog`] holds every
+//! version, rejects overlapping ranges, and resolves the version in effect
+//! at an instant, honoring organizations pinned (grandfathered) to an older
+//! version.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+use creto_common::types::{Currency, Money};
+use creto_common::{CretoError, CretoResult, OrganizationId};
 use serde::{Deserialize, Serialize};
 
+use crate::aliases::MetricAliasRegistry;
+use crate::currency::CurrencyError;
+use crate::repository::PricingModelRepository;
+
 /// A pricing model that determines cost based on usage.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PricingModel {
@@ -19,6 +35,34 @@ pub struct PricingModel {
 
     /// Pricing strategy.
     pub strategy: PricingStrategy,
+
+    /// Currency all amounts in this model (including tiers) are expressed in.
+    #[serde(default)]
+    pub currency: Currency,
+
+    /// Version number among the metric's models, starting at 1.
+    #[serde(default = "default_version")]
+    pub version: u32,
+
+    /// When this version starts applying (inclusive).
+    #[serde(default = "default_effective_from")]
+    pub effective_from: DateTime<Utc>,
+
+    /// When this version stops applying (exclusive); `None` is open-ended.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_until: Option<DateTime<Utc>>,
+
+    /// ID of the version this one replaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supersedes: Option<String>,
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+fn default_effective_from() -> DateTime<Utc> {
+    DateTime::UNIX_EPOCH
 }
 
 /// Strategy for calculating price from usage.
@@ -93,14 +137,82 @@ pub struct PricingTier {
 }
 
 impl PricingModel {
+    /// Create a USD model that has always applied.
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        metric_code: impl Into<String>,
+        strategy: PricingStrategy,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            metric_code: metric_code.into(),
+            strategy,
+            currency: Currency::USD,
+            version: default_version(),
+            effective_from: default_effective_from(),
+            effective_until: None,
+            supersedes: None,
+        }
+    }
+
+    /// Set the currency.
+    pub fn with_currency(mut self, currency: Currency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    /// Set the effective range (`until` exclusive, `None` open-ended).
+    pub fn with_effective_range(
+        mut self,
+        from: DateTime<Utc>,
+        until: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.effective_from = from;
+        self.effective_until = until;
+        self
+    }
+
+    /// Create the next version of this model, effective from `effective_from`.
+    ///
+    /// Adding it to a [`PricingCatalog`] ends this version at that instant.
+    pub fn successor(
+        &self,
+        id: impl Into<String>,
+        strategy: PricingStrategy,
+        effective_from: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            strategy,
+            version: self.version + 1,
+            effective_from,
+            effective_until: None,
+            supersedes: Some(self.id.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// Whether this version applies at `at`.
+    pub fn is_effective_at(&self, at: DateTime<Utc>) -> bool {
+        at >= self.effective_from && !matches!(self.effective_until, Some(until) if at >= until)
+    }
+
+    /// Whether the effective ranges of two versions intersect.
+    pub fn overlaps(&self, other: &PricingModel) -> bool {
+        let starts_before = |model: &PricingModel, from: DateTime<Utc>| !matches!(model.effective_until, Some(until) if until <= from);
+        starts_before(self, other.effective_from) && starts_before(other, self.effective_from)
+    }
+
     /// Calculate the effective unit price for a given usage amount.
     /// For tiered pricing, this is the average price per unit.
     pub fn calculate_unit_price(&self, usage: i64) -> Money {
         if usage == 0 {
-            return Money::usd(0);
+            return Money::new(0, self.currency);
         }
         let total = self.calculate(usage);
-        Money::usd(total.amount / usage)
+        Money::new(total.amount / usage, self.currency)
     }
 
     /// Calculate the total cost for a given usage amount.
@@ -131,7 +243,7 @@ impl PricingModel {
             }
         };
 
-        Money::usd(cents)
+        Money::new(cents, self.currency)
     }
 
     /// Calculate graduated tiered pricing.
@@ -177,34 +289,253 @@ impl PricingModel {
     }
 }
 
+/// A stretch of a billing window priced by a single model version.
+#[derive(Debug, Clone)]
+pub struct PricingSegment {
+    /// Version in effect over the segment.
+    pub model: PricingModel,
+    /// Segment start (inclusive).
+    pub start: DateTime<Utc>,
+    /// Segment end (inclusive).
+    pub end: DateTime<Utc>,
+}
+
+/// Every pricing model version, plus per-organization pins.
+///
+/// Versions of a metric never overlap, so at most one applies at any instant.
+/// An organization pinned to a version is priced by it regardless of dates
+/// until it is explicitly migrated.
+#[derive(Debug, Clone, Default)]
+pub struct PricingCatalog {
+    /// Versions by metric code, ordered by `effective_from`.
+    versions: HashMap<String, Vec<PricingModel>>,
+    /// Pinned model ID by (organization, metric code).
+    pins: HashMap<(OrganizationId, String), String>,
+}
+
+impl PricingCatalog {
+    /// Create an empty catalog.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a version.
+    ///
+    /// If it supersedes an open-ended (or later-ending) version, that version
+    /// is ended at the new version's `effective_from`. Rejects duplicate IDs
+    /// and ranges overlapping another version of the same metric.
+    pub fn add_version(&mut self, model: PricingModel) -> CretoResult<()> {
+        if matches!(model.effective_until, Some(until) if until <= model.effective_from) {
+            return Err(CretoError::ValidationFailed(format!(
+                "pricing model {} ends before it starts",
+                model.id
+            )));
+        }
+        if self.get(&model.id).is_some() {
+            return Err(CretoError::ValidationFailed(format!(
+                "pricing model {} already exists",
+                model.id
+            )));
+        }
+
+        let mut versions = self
+            .versions
+            .get(&model.metric_code)
+            .cloned()
+            .unwrap_or_default();
+
+        if let Some(superseded) = &model.supersedes {
+            let previous = versions
+                .iter_mut()
+                .find(|v| &v.id == superseded)
+                .ok_or_else(|| {
+                    CretoError::NotFound(format!(
+                        "superseded pricing model {} for metric {}",
+                        superseded, model.metric_code
+                    ))
+                })?;
+            if model.effective_from <= previous.effective_from {
+                return Err(CretoError::ValidationFailed(format!(
+                    "pricing model {} must take effect after {} does",
+                    model.id, previous.id
+                )));
+            }
+            if !matches!(previous.effective_until, Some(until) if until <= model.effective_from) {
+                previous.effective_until = Some(model.effective_from);
+            }
+        }
+
+        if let Some(existing) = versions.iter().find(|v| v.overlaps(&model)) {
+            return Err(CretoError::ValidationFailed(format!(
+                "pricing model {} overlaps version {} ({}) of metric {}",
+                model.id, existing.version, existing.id, model.metric_code
+            )));
+        }
+
+        versions.push(model);
+        versions.sort_by_key(|v| v.effective_from);
+        let metric_code = versions[0].metric_code.clone();
+        self.versions.insert(metric_code, versions);
+        Ok(())
+    }
+
+    /// Replace every version of the model's metric with this one model.
+    ///
+    /// Pins to the removed versions are dropped.
+    pub fn replace(&mut self, model: PricingModel) {
+        let metric_code = model.metric_code.clone();
+        self.pins
+            .retain(|(_, metric), id| metric != &metric_code || *id == model.id);
+        self.versions.insert(metric_code, vec![model]);
+    }
+
+    /// All versions of a metric, ordered by `effective_from`.
+    pub fn versions(&self, metric_code: &str) -> &[PricingModel] {
+        self.versions
+            .get(metric_code)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Iterate over every version of every metric.
+    pub fn models(&self) -> impl Iterator<Item = &PricingModel> {
+        self.versions.values().flatten()
+    }
+
+    /// Whether any version exists for a metric.
+    pub fn contains_metric(&self, metric_code: &str) -> bool {
+        !self.versions(metric_code).is_empty()
+    }
+
+    /// Get a version by ID.
+    pub fn get(&self, model_id: &str) -> Option<&PricingModel> {
+        self.models().find(|v| v.id == model_id)
+    }
+
+    /// Pin (grandfather) an organization to a version of its metric.
+    pub fn pin(&mut self, org_id: OrganizationId, model_id: &str) -> CretoResult<()> {
+        let metric_code = self
+            .get(model_id)
+            .ok_or_else(|| CretoError::NotFound(format!("pricing model {}", model_id)))?
+            .metric_code
+            .clone();
+        self.pins
+            .insert((org_id, metric_code), model_id.to_string());
+        Ok(())
+    }
+
+    /// Migrate an organization off its pinned version of a metric, returning
+    /// the ID it was pinned to.
+    pub fn migrate(&mut self, org_id: OrganizationId, metric_code: &str) -> Option<String> {
+        self.pins.remove(&(org_id, metric_code.to_string()))
+    }
+
+    /// The version an organization is pinned to for a metric, if any.
+    pub fn pinned(&self, org_id: OrganizationId, metric_code: &str) -> Option<&PricingModel> {
+        self.pins
+            .get(&(org_id, metric_code.to_string()))
+            .and_then(|id| self.get(id))
+    }
+
+    /// The version pricing an organization's usage of a metric at `at`.
+    pub fn resolve(
+        &self,
+        org_id: OrganizationId,
+        metric_code: &str,
+        at: DateTime<Utc>,
+    ) -> Option<&PricingModel> {
+        self.pinned(org_id, metric_code).or_else(|| {
+            self.versions(metric_code)
+                .iter()
+                .find(|v| v.is_effective_at(at))
+        })
+    }
+
+    /// Split the inclusive window `[start, end]` into the stretches priced by
+    /// each version, in order. Stretches with no version in effect are omitted.
+    pub fn segments(
+        &self,
+        org_id: OrganizationId,
+        metric_code: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<PricingSegment> {
+        if let Some(model) = self.pinned(org_id, metric_code) {
+            return vec![PricingSegment {
+                model: model.clone(),
+                start,
+                end,
+            }];
+        }
+
+        self.versions(metric_code)
+            .iter()
+            .filter(|v| {
+                v.effective_from <= end
+                    && !matches!(v.effective_until, Some(until) if until <= start)
+            })
+            .map(|v| PricingSegment {
+                model: v.clone(),
+                start: start.max(v.effective_from),
+                end: v
+                    .effective_until
+                    .map_or(end, |until| end.min(until - Duration::microseconds(1))),
+            })
+            .collect()
+    }
+}
+
 /// Engine for calculating prices.
 pub struct PricingEngine {
     // TODO: Add pricing model storage, caching
-    _private: (),
+    /// Aliases resolved before matching usage to models (None = codes used
+    /// as is).
+    aliases: Option<Arc<MetricAliasRegistry>>,
 }
 
 impl PricingEngine {
     /// Create a new pricing engine.
     pub fn new() -> Self {
-        Self { _private: () }
+        Self { aliases: None }
+    }
+
+    /// Price usage of an aliased code with its canonical code's model.
+    pub fn with_metric_aliases(mut self, aliases: Arc<MetricAliasRegistry>) -> Self {
+        self.aliases = Some(aliases);
+        self
     }
 
     /// Calculate the total cost for usage across multiple metrics.
+    ///
+    /// All matching models must share a currency; mixed currencies need to go
+    /// through the invoice generator's conversion path instead. Usage of an
+    /// aliased code is priced by the model of the code it is aliased to now.
     pub fn calculate_total(
         &self,
         usage: &[(String, i64)], // (metric_code, quantity)
         models: &[PricingModel],
-    ) -> Money {
-        let mut total_cents = 0i64;
+    ) -> Result<Money, CurrencyError> {
+        let mut total: Option<Money> = None;
 
+        let now = Utc::now();
         for (metric_code, quantity) in usage {
-            if let Some(model) = models.iter().find(|m| &m.metric_code == metric_code) {
+            let metric_code = match &self.aliases {
+                Some(aliases) => aliases.canonical_code(metric_code, now),
+                None => metric_code.clone(),
+            };
+            if let Some(model) = models.iter().find(|m| m.metric_code == metric_code) {
                 let cost = model.calculate(*quantity);
-                total_cents += cost.amount;
+                total = Some(match total {
+                    None => cost,
+                    Some(acc) => acc.add(&cost).map_err(|_| CurrencyError::Mismatch {
+                        expected: acc.currency,
+                        actual: cost.currency,
+                    })?,
+                });
             }
         }
 
-        Money::usd(total_cents)
+        Ok(total.unwrap_or_else(|| Money::usd(0)))
     }
 }
 
@@ -214,20 +545,77 @@ impl Default for PricingEngine {
     }
 }
 
+/// In-memory pricing model store for testing and development.
+///
+/// Mirrors [`PgPricingModelRepository`](crate::repository::PgPricingModelRepository)
+/// on top of a [`PricingCatalog`].
+#[derive(Debug, Default)]
+pub struct InMemoryPricingModelRepository {
+    catalog: RwLock<PricingCatalog>,
+}
+
+impl InMemoryPricingModelRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl PricingModelRepository for InMemoryPricingModelRepository {
+    async fn create_version(&self, model: &PricingModel) -> Result<(), CretoError> {
+        self.catalog.write().unwrap().add_version(model.clone())
+    }
+
+    async fn list_versions(&self, metric_code: &str) -> Result<Vec<PricingModel>, CretoError> {
+        Ok(self.catalog.read().unwrap().versions(metric_code).to_vec())
+    }
+
+    async fn resolve(
+        &self,
+        org_id: OrganizationId,
+        metric_code: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Option<PricingModel>, CretoError> {
+        Ok(self
+            .catalog
+            .read()
+            .unwrap()
+            .resolve(org_id, metric_code, at)
+            .cloned())
+    }
+
+    async fn pin(&self, org_id: OrganizationId, model_id: &str) -> Result<(), CretoError> {
+        self.catalog.write().unwrap().pin(org_id, model_id)
+    }
+
+    async fn migrate(
+        &self,
+        org_id: OrganizationId,
+        metric_code: &str,
+    ) -> Result<Option<String>, CretoError> {
+        Ok(self.catalog.write().unwrap().migrate(org_id, metric_code))
+    }
+
+    async fn load_catalog(&self) -> Result<PricingCatalog, CretoError> {
+        Ok(self.catalog.read().unwrap().clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_per_unit_pricing() {
-        let model = PricingModel {
-            id: "tokens".to_string(),
-            name: "Token Pricing".to_string(),
-            metric_code: "input_tokens".to_string(),
-            strategy: PricingStrategy::PerUnit {
+        let model = PricingModel::new(
+            "tokens",
+            "Token Pricing",
+            "input_tokens",
+            PricingStrategy::PerUnit {
                 unit_price_cents: 1, // $0.01 per token
             },
-        };
+        );
 
         let cost = model.calculate(1000);
         assert_eq!(cost.amount, 1000); // $10.00
@@ -235,11 +623,11 @@ mod tests {
 
     #[test]
     fn test_graduated_tiered_pricing() {
-        let model = PricingModel {
-            id: "api_calls".to_string(),
-            name: "API Tiered".to_string(),
-            metric_code: "api_calls".to_string(),
-            strategy: PricingStrategy::GraduatedTiered {
+        let model = PricingModel::new(
+            "api_calls",
+            "API Tiered",
+            "api_calls",
+            PricingStrategy::GraduatedTiered {
                 tiers: vec![
                     PricingTier {
                         from_units: 0,
@@ -261,7 +649,7 @@ mod tests {
                     },
                 ],
             },
-        };
+        );
 
         // 150 calls: first 100 at $0.10, next 50 at $0.05
         let cost = model.calculate(150);
@@ -270,18 +658,134 @@ mod tests {
 
     #[test]
     fn test_package_pricing() {
-        let model = PricingModel {
-            id: "tokens_package".to_string(),
-            name: "Token Packages".to_string(),
-            metric_code: "total_tokens".to_string(),
-            strategy: PricingStrategy::Package {
+        let model = PricingModel::new(
+            "tokens_package",
+            "Token Packages",
+            "total_tokens",
+            PricingStrategy::Package {
                 package_size: 1000,
                 package_price_cents: 100, // $1.00 per 1000 tokens
             },
-        };
+        );
 
         // 2500 tokens = 3 packages (rounded up)
         let cost = model.calculate(2500);
         assert_eq!(cost.amount, 300); // $3.00
     }
+
+    #[test]
+    fn test_model_currency_carried_through() {
+        let model = PricingModel::new(
+            "eu_tokens",
+            "EU Token Pricing",
+            "tokens",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 2,
+            },
+        )
+        .with_currency(Currency::EUR);
+
+        let cost = model.calculate(100);
+        assert_eq!(cost, Money::new(200, Currency::EUR));
+        assert_eq!(model.calculate_unit_price(100).currency, Currency::EUR);
+    }
+
+    #[test]
+    fn test_calculate_total_rejects_mixed_currencies() {
+        let usd = PricingModel::new(
+            "calls",
+            "Calls",
+            "api_calls",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 1,
+            },
+        );
+        let eur = PricingModel::new(
+            "tokens",
+            "Tokens",
+            "tokens",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 1,
+            },
+        )
+        .with_currency(Currency::EUR);
+
+        let engine = PricingEngine::new();
+        let usage = vec![("api_calls".to_string(), 10), ("tokens".to_string(), 10)];
+        let result = engine.calculate_total(&usage, &[usd.clone(), eur]);
+        assert!(matches!(result, Err(CurrencyError::Mismatch { .. })));
+
+        let total = engine
+            .calculate_total(&usage[..1], std::slice::from_ref(&usd))
+            .unwrap();
+        assert_eq!(total, Money::usd(10));
+    }
+
+    #[test]
+    fn test_versions_supersede_and_reject_overlap() {
+        use chrono::TimeZone;
+
+        let march = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        let mid_march = Utc.with_ymd_and_hms(2026, 3, 15, 0, 0, 0).unwrap();
+        let v1 = PricingModel::new(
+            "tokens_v1",
+            "Tokens",
+            "tokens",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 2,
+            },
+        );
+        let v2 = v1.successor(
+            "tokens_v2",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 3,
+            },
+            mid_march,
+        );
+
+        let mut catalog = PricingCatalog::new();
+        catalog.add_version(v1).unwrap();
+        catalog.add_version(v2).unwrap();
+
+        // The superseded version now ends where its successor starts
+        let versions = catalog.versions("tokens");
+        assert_eq!(versions[0].effective_until, Some(mid_march));
+        assert_eq!(versions[1].version, 2);
+
+        let org_id = OrganizationId::new();
+        assert_eq!(
+            catalog.resolve(org_id, "tokens", march).unwrap().id,
+            "tokens_v1"
+        );
+        assert_eq!(
+            catalog.resolve(org_id, "tokens", mid_march).unwrap().id,
+            "tokens_v2"
+        );
+
+        // A third version starting inside v1's range without superseding is rejected
+        let overlapping = PricingModel::new(
+            "tokens_promo",
+            "Tokens promo",
+            "tokens",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 1,
+            },
+        )
+        .with_effective_range(march, Some(mid_march + Duration::days(1)));
+        let err = catalog.add_version(overlapping).unwrap_err();
+        assert!(matches!(err, CretoError::ValidationFailed(_)));
+        assert_eq!(catalog.versions("tokens").len(), 2);
+
+        // A range ending before it starts is rejected too
+        let inverted = PricingModel::new(
+            "tokens_bad",
+            "Tokens",
+            "other",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 1,
+            },
+        )
+        .with_effective_range(mid_march, Some(march));
+        assert!(catalog.add_version(inverted).is_err());
+    }
 }
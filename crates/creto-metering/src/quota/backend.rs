@@ -0,0 +1,184 @@
+//! Shared quota storage behind the enforcer's local cache.
+//!
+//! The enforcer answers most checks from its bloom filter and cache; a
+//! [`QuotaBackend`] is consulted on a cache miss and charged on every
+//! recorded use, so enforcers on several hosts draw from one counter per
+//! quota. Backends are keyed by the enforcer's quota keys
+//! (`org:agent:metric`, or `org:*:metric` for organization-wide quotas).
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, PoisonError, RwLock};
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+
+use super::enforcer::EnforcerError;
+use super::types::Quota;
+
+/// Default prefix for quota hashes in Redis.
+pub const DEFAULT_REDIS_KEY_PREFIX: &str = "creto:quota:";
+
+/// Stores quota terms and usage counters shared between enforcers.
+#[async_trait]
+pub trait QuotaBackend: Send + Sync {
+    /// The quota stored under `key`, with its current usage.
+    async fn get(&self, key: &str) -> Result<Option<Quota>, EnforcerError>;
+
+    /// Atomically add `amount` to a quota's usage, returning the new usage.
+    async fn increment_usage(&self, key: &str, amount: i64) -> Result<i64, EnforcerError>;
+
+    /// Store a quota's terms.
+    ///
+    /// Usage is reset to the quota's `current_usage` only when the quota
+    /// starts a different period than the stored one, so enforcers
+    /// registering the same quota at startup do not wipe each other's
+    /// charges.
+    async fn register(&self, key: &str, quota: &Quota) -> Result<(), EnforcerError>;
+}
+
+/// Process-local backend, for tests and single-instance deployments.
+#[derive(Debug, Default)]
+pub struct InMemoryQuotaBackend {
+    quotas: RwLock<HashMap<String, Quota>>,
+}
+
+impl InMemoryQuotaBackend {
+    /// Create an empty backend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl QuotaBackend for InMemoryQuotaBackend {
+    async fn get(&self, key: &str) -> Result<Option<Quota>, EnforcerError> {
+        let quotas = self.quotas.read().unwrap_or_else(PoisonError::into_inner);
+        Ok(quotas.get(key).cloned())
+    }
+
+    async fn increment_usage(&self, key: &str, amount: i64) -> Result<i64, EnforcerError> {
+        let mut quotas = self.quotas.write().unwrap_or_else(PoisonError::into_inner);
+        let quota = quotas
+            .get_mut(key)
+            .ok_or_else(|| EnforcerError::CacheError(format!("Quota not found: {}", key)))?;
+        quota.current_usage += amount;
+        Ok(quota.current_usage)
+    }
+
+    async fn register(&self, key: &str, quota: &Quota) -> Result<(), EnforcerError> {
+        let mut quotas = self.quotas.write().unwrap_or_else(PoisonError::into_inner);
+        let mut stored = quota.clone();
+        if let Some(existing) = quotas.get(key) {
+            if existing.period_start == quota.period_start {
+                stored.current_usage = existing.current_usage;
+            }
+        }
+        quotas.insert(key.to_string(), stored);
+        Ok(())
+    }
+}
+
+/// Stores the terms and resets the usage field when the period changes.
+/// ARGV is the terms as JSON, the period start, then the starting usage.
+fn register_script() -> &'static redis::Script {
+    static SCRIPT: OnceLock<redis::Script> = OnceLock::new();
+    SCRIPT.get_or_init(|| {
+        redis::Script::new(
+            r"
+        local period = redis.call('HGET', KEYS[1], 'period_start')
+        redis.call('HSET', KEYS[1], 'terms', ARGV[1], 'period_start', ARGV[2])
+        if period ~= ARGV[2] then
+            redis.call('HSET', KEYS[1], 'usage', ARGV[3])
+        end
+        return 1
+        ",
+        )
+    })
+}
+
+/// Redis backend keeping each quota in one hash: its terms as JSON, the
+/// period start they apply to, and a usage counter moved with `HINCRBY`.
+#[derive(Clone)]
+pub struct RedisQuotaBackend {
+    connection: ConnectionManager,
+    key_prefix: String,
+}
+
+impl RedisQuotaBackend {
+    /// Connect to Redis at `redis_url`.
+    pub async fn new(redis_url: &str) -> Result<Self, EnforcerError> {
+        let client = redis::Client::open(redis_url).map_err(redis_error)?;
+        let connection = ConnectionManager::new(client).await.map_err(redis_error)?;
+        Ok(Self::from_connection(connection))
+    }
+
+    /// Use an existing connection.
+    pub fn from_connection(connection: ConnectionManager) -> Self {
+        Self {
+            connection,
+            key_prefix: DEFAULT_REDIS_KEY_PREFIX.to_string(),
+        }
+    }
+
+    /// Store quotas under a different key prefix.
+    pub fn with_key_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.key_prefix = prefix.into();
+        self
+    }
+
+    fn redis_key(&self, key: &str) -> String {
+        format!("{}{}", self.key_prefix, key)
+    }
+}
+
+#[async_trait]
+impl QuotaBackend for RedisQuotaBackend {
+    async fn get(&self, key: &str) -> Result<Option<Quota>, EnforcerError> {
+        let mut conn = self.connection.clone();
+        let (terms, usage): (Option<String>, Option<i64>) = redis::cmd("HMGET")
+            .arg(self.redis_key(key))
+            .arg("terms")
+            .arg("usage")
+            .query_async(&mut conn)
+            .await
+            .map_err(redis_error)?;
+        let Some(terms) = terms else {
+            return Ok(None);
+        };
+        let mut quota: Quota = serde_json::from_str(&terms).map_err(|e| {
+            EnforcerError::RedisError(format!("Invalid quota terms for {}: {}", key, e))
+        })?;
+        quota.current_usage = usage.unwrap_or(0);
+        Ok(Some(quota))
+    }
+
+    async fn increment_usage(&self, key: &str, amount: i64) -> Result<i64, EnforcerError> {
+        let mut conn = self.connection.clone();
+        redis::cmd("HINCRBY")
+            .arg(self.redis_key(key))
+            .arg("usage")
+            .arg(amount)
+            .query_async(&mut conn)
+            .await
+            .map_err(redis_error)
+    }
+
+    async fn register(&self, key: &str, quota: &Quota) -> Result<(), EnforcerError> {
+        let terms = serde_json::to_string(quota)
+            .map_err(|e| EnforcerError::RedisError(format!("Unencodable quota {}: {}", key, e)))?;
+        let mut conn = self.connection.clone();
+        let _: i64 = register_script()
+            .key(self.redis_key(key))
+            .arg(terms)
+            .arg(quota.period_start.timestamp_micros())
+            .arg(quota.current_usage)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> EnforcerError {
+    EnforcerError::RedisError(e.to_string())
+}
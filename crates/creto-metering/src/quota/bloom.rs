@@ -2,6 +2,10 @@
 //!
 //! Provides O(1) check to determine if a quota key might exist,
 //! with configurable false positive rate (~1%).
+//!
+//! Bits cannot be unset, so keys whose quotas were removed keep answering
+//! "possibly present" until the filter is rebuilt with
+//! [`QuotaBloomFilter::rebuild_from`].
 
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
@@ -110,6 +114,30 @@ impl QuotaBloomFilter {
         true
     }
 
+    /// Replace the filter's contents with a fresh filter built from `keys`,
+    /// dropping every key not among them.
+    ///
+    /// The new bits are built aside and stored one word at a time, so a
+    /// concurrent check never misses a key in `keys`. Keys inserted while
+    /// the rebuild runs may be overwritten; callers re-insert any added
+    /// since they collected `keys`.
+    pub fn rebuild_from<K: AsRef<str>>(&self, keys: impl Iterator<Item = K>) {
+        let mut fresh = vec![0u64; self.bits.len()];
+        let mut count = 0;
+        for key in keys {
+            for seed in &self.hash_seeds {
+                let bit_index = (self.hash_with_seed(key.as_ref(), *seed) as usize) % self.bit_size;
+                fresh[bit_index / 64] |= 1u64 << (bit_index % 64);
+            }
+            count += 1;
+        }
+
+        for (word, fresh) in self.bits.iter().zip(fresh) {
+            word.store(fresh, Ordering::Relaxed);
+        }
+        self.item_count.store(count, Ordering::Relaxed);
+    }
+
     /// Hash key with seed using FNV-1a (fast, good distribution).
     #[inline]
     fn hash_with_seed(&self, key: &str, seed: u64) -> u64 {
@@ -283,6 +311,37 @@ mod tests {
         // After clear, keys should not be found (with high probability)
     }
 
+    #[test]
+    fn test_bloom_rebuild_drops_removed_keys() {
+        let bloom = QuotaBloomFilter::new(BloomConfig {
+            expected_items: 1000,
+            false_positive_rate: 0.01,
+        });
+        let keys: Vec<String> = (0..1000)
+            .map(|i| format!("org:agent:metric:{}", i))
+            .collect();
+        for key in &keys {
+            bloom.insert(key);
+        }
+
+        let (kept, removed) = keys.split_at(100);
+        bloom.rebuild_from(kept.iter());
+
+        assert_eq!(bloom.len(), 100);
+        for key in kept {
+            assert!(bloom.might_contain(key), "False negative for {}", key);
+        }
+        let false_positives = removed
+            .iter()
+            .filter(|key| bloom.might_contain(key))
+            .count();
+        assert!(
+            false_positives < removed.len() / 100,
+            "{} removed keys still present",
+            false_positives
+        );
+    }
+
     #[test]
     fn test_quota_key_format() {
         let key = QuotaKey::new(
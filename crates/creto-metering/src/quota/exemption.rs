@@ -0,0 +1,629 @@
+//! Time-boxed quota exemptions.
+//!
+//! During a data migration or maintenance an organization may legitimately
+//! need to exceed its quotas for a bounded window. A [`QuotaExemption`]
+//! raises (or lifts) the limits of an organization's quotas, optionally
+//! narrowed to one agent and a list of metrics, between a start and end
+//! time. Every exemption carries the reason it was granted and who approved
+//! it, and may not last longer than [`ExemptionConfig::max_duration`].
+//!
+//! The [`QuotaEnforcer`] holds the exemptions it consults, refreshed from
+//! the repository by [`QuotaExemptionManager::refresh`] on a short interval.
+//! Whether an exemption applies is decided against the current time on every
+//! check, so a window ends exactly at its `ends_at` however stale the
+//! refresh. Exempted checks are flagged in [`QuotaCheckResult::exempted`]
+//! so billing can still price the overage, and usage keeps accumulating as
+//! normal.
+//!
+//! [`QuotaCheckResult::exempted`]: super::QuotaCheckResult::exempted
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{
+    AgentId, Clock, CretoError, CretoResult, OrganizationId, ShutdownCoordinator, SystemClock,
+};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::enforcer::QuotaEnforcer;
+use crate::repository::QuotaExemptionRepository;
+
+/// How far an exemption lifts a quota's limit.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ExemptionAllowance {
+    /// The limit is multiplied by this factor (2.0 = twice the limit).
+    Multiplier(f64),
+    /// The limit does not apply.
+    Unlimited,
+}
+
+impl ExemptionAllowance {
+    /// Exempted limit for a quota with the given limit, or `None` if
+    /// unlimited.
+    pub fn limit(&self, limit: i64) -> Option<i64> {
+        match self {
+            Self::Multiplier(factor) => Some((limit as f64 * factor).floor() as i64),
+            Self::Unlimited => None,
+        }
+    }
+}
+
+/// A window during which an organization's quotas are lifted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaExemption {
+    /// Unique exemption ID.
+    pub id: Uuid,
+    /// Organization whose quotas are lifted.
+    pub organization_id: OrganizationId,
+    /// Optional: Only this agent's usage is exempted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<AgentId>,
+    /// Metric codes exempted. Empty exempts every metric.
+    #[serde(default)]
+    pub metric_codes: Vec<String>,
+    /// When the window opens.
+    pub starts_at: DateTime<Utc>,
+    /// When the window closes (exclusive).
+    pub ends_at: DateTime<Utc>,
+    /// How far limits are lifted.
+    pub allowance: ExemptionAllowance,
+    /// Why the exemption was granted.
+    pub reason: String,
+    /// Who approved the exemption.
+    pub approved_by: String,
+    /// When the exemption was created.
+    pub created_at: DateTime<Utc>,
+}
+
+impl QuotaExemption {
+    /// Create an exemption covering every agent and metric of an
+    /// organization.
+    pub fn new(
+        organization_id: OrganizationId,
+        starts_at: DateTime<Utc>,
+        ends_at: DateTime<Utc>,
+        allowance: ExemptionAllowance,
+        reason: impl Into<String>,
+        approved_by: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            organization_id,
+            agent_id: None,
+            metric_codes: Vec::new(),
+            starts_at,
+            ends_at,
+            allowance,
+            reason: reason.into(),
+            approved_by: approved_by.into(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Only exempt one agent's usage.
+    pub fn with_agent(mut self, agent_id: AgentId) -> Self {
+        self.agent_id = Some(agent_id);
+        self
+    }
+
+    /// Only exempt the given metrics.
+    pub fn with_metrics<I, S>(mut self, metric_codes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.metric_codes = metric_codes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Length of the window.
+    pub fn duration(&self) -> Duration {
+        self.ends_at - self.starts_at
+    }
+
+    /// Whether the window is open at `now`.
+    pub fn is_active_at(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+
+    /// Whether the exemption covers an agent's usage of a metric at `now`.
+    pub fn covers(&self, agent_id: &AgentId, metric_code: &str, now: DateTime<Utc>) -> bool {
+        self.is_active_at(now)
+            && self.agent_id.map_or(true, |a| a == *agent_id)
+            && (self.metric_codes.is_empty() || self.metric_codes.iter().any(|m| m == metric_code))
+    }
+
+    /// Check the exemption is complete and no longer than `max_duration`.
+    pub fn validate(&self, max_duration: Duration) -> CretoResult<()> {
+        if self.reason.trim().is_empty() {
+            return Err(CretoError::ValidationFailed(
+                "Quota exemption reason is required".to_string(),
+            ));
+        }
+        if self.approved_by.trim().is_empty() {
+            return Err(CretoError::ValidationFailed(
+                "Quota exemption approver is required".to_string(),
+            ));
+        }
+        if self.ends_at <= self.starts_at {
+            return Err(CretoError::ValidationFailed(
+                "Quota exemption must end after it starts".to_string(),
+            ));
+        }
+        if self.duration() > max_duration {
+            return Err(CretoError::ValidationFailed(format!(
+                "Quota exemption lasts {} minutes, longer than the maximum of {} minutes",
+                self.duration().num_minutes(),
+                max_duration.num_minutes()
+            )));
+        }
+        if let ExemptionAllowance::Multiplier(factor) = self.allowance {
+            if !factor.is_finite() || factor < 1.0 {
+                return Err(CretoError::ValidationFailed(format!(
+                    "Quota exemption multiplier must be at least 1, got {}",
+                    factor
+                )));
+            }
+        }
+        if self.metric_codes.iter().any(|m| m.trim().is_empty()) {
+            return Err(CretoError::ValidationFailed(
+                "Quota exemption metric codes cannot be blank".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Limits on exemptions and how often the enforcer's copy is refreshed.
+#[derive(Debug, Clone)]
+pub struct ExemptionConfig {
+    /// Longest window an exemption may cover.
+    pub max_duration: Duration,
+    /// Whether exemptions must be approved through an [`ExemptionApprover`].
+    pub require_approval: bool,
+    /// How often [`QuotaExemptionManager::spawn_refresher`] reloads
+    /// exemptions into the enforcer.
+    pub refresh_interval: std::time::Duration,
+}
+
+impl Default for ExemptionConfig {
+    fn default() -> Self {
+        Self {
+            max_duration: Duration::hours(72),
+            require_approval: false,
+            refresh_interval: std::time::Duration::from_secs(30),
+        }
+    }
+}
+
+/// Approval step run before an exemption is stored, such as an oversight
+/// request.
+///
+/// An error refuses the exemption.
+#[trait_variant::make(ExemptionApprover: Send)]
+pub trait LocalExemptionApprover {
+    /// Approve or refuse an exemption.
+    async fn approve(&self, exemption: &QuotaExemption) -> CretoResult<()>;
+}
+
+/// In-memory exemption store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryQuotaExemptionRepository {
+    exemptions: RwLock<HashMap<Uuid, QuotaExemption>>,
+}
+
+impl InMemoryQuotaExemptionRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl QuotaExemptionRepository for InMemoryQuotaExemptionRepository {
+    async fn insert_exemption(&self, exemption: &QuotaExemption) -> Result<(), CretoError> {
+        self.exemptions
+            .write()
+            .unwrap()
+            .insert(exemption.id, exemption.clone());
+        Ok(())
+    }
+
+    async fn delete_exemption(&self, id: Uuid) -> Result<bool, CretoError> {
+        Ok(self.exemptions.write().unwrap().remove(&id).is_some())
+    }
+
+    async fn list_unexpired(&self, now: DateTime<Utc>) -> Result<Vec<QuotaExemption>, CretoError> {
+        let mut exemptions: Vec<_> = self
+            .exemptions
+            .read()
+            .unwrap()
+            .values()
+            .filter(|e| e.ends_at > now)
+            .cloned()
+            .collect();
+        exemptions.sort_by_key(|e| e.starts_at);
+        Ok(exemptions)
+    }
+
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<QuotaExemption>, CretoError> {
+        let mut exemptions: Vec<_> = self
+            .exemptions
+            .read()
+            .unwrap()
+            .values()
+            .filter(|e| e.organization_id == org_id)
+            .cloned()
+            .collect();
+        exemptions.sort_by_key(|e| e.starts_at);
+        Ok(exemptions)
+    }
+}
+
+/// Grants and revokes exemptions and keeps the enforcer's copy current.
+///
+/// Clones share the same repository and enforcer.
+pub struct QuotaExemptionManager<R> {
+    repository: Arc<R>,
+    enforcer: Arc<QuotaEnforcer>,
+    config: ExemptionConfig,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R> Clone for QuotaExemptionManager<R> {
+    fn clone(&self) -> Self {
+        Self {
+            repository: Arc::clone(&self.repository),
+            enforcer: Arc::clone(&self.enforcer),
+            config: self.config.clone(),
+            clock: Arc::clone(&self.clock),
+        }
+    }
+}
+
+impl<R> QuotaExemptionManager<R>
+where
+    R: QuotaExemptionRepository + Sync + 'static,
+{
+    /// Manage exemptions stored in `repository` and applied by `enforcer`.
+    pub fn new(repository: Arc<R>, enforcer: Arc<QuotaEnforcer>) -> Self {
+        Self {
+            repository,
+            enforcer,
+            config: ExemptionConfig::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the duration limit, approval requirement and refresh interval.
+    pub fn with_config(mut self, config: ExemptionConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Set the time source.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Grant an exemption without an approval step.
+    ///
+    /// Fails if [`ExemptionConfig::require_approval`] is set.
+    pub async fn grant(&self, exemption: QuotaExemption) -> CretoResult<QuotaExemption> {
+        if self.config.require_approval {
+            return Err(CretoError::Unauthorized(
+                "Quota exemptions require approval".to_string(),
+            ));
+        }
+        self.validate(&exemption)?;
+        self.store(exemption).await
+    }
+
+    /// Grant an exemption once `approver` approves it.
+    ///
+    /// Invalid exemptions are refused before they reach the approver.
+    pub async fn grant_with_approval<A>(
+        &self,
+        exemption: QuotaExemption,
+        approver: &A,
+    ) -> CretoResult<QuotaExemption>
+    where
+        A: ExemptionApprover + Sync,
+    {
+        self.validate(&exemption)?;
+        approver.approve(&exemption).await?;
+        self.store(exemption).await
+    }
+
+    /// Revoke an exemption, ending its window immediately.
+    pub async fn revoke(&self, organization_id: OrganizationId, id: Uuid) -> CretoResult<bool> {
+        self.enforcer.remove_exemption(&organization_id, id);
+        let removed = self.repository.delete_exemption(id).await?;
+        if removed {
+            info!(exemption_id = %id, organization_id = %organization_id, "Quota exemption revoked");
+        }
+        Ok(removed)
+    }
+
+    /// List an organization's exemptions, including past ones.
+    pub async fn list(&self, organization_id: OrganizationId) -> CretoResult<Vec<QuotaExemption>> {
+        self.repository.list_by_org(organization_id).await
+    }
+
+    /// Reload unexpired exemptions into the enforcer, returning how many
+    /// were loaded.
+    pub async fn refresh(&self) -> CretoResult<usize> {
+        let exemptions = self.repository.list_unexpired(self.clock.now()).await?;
+        let loaded = exemptions.len();
+        self.enforcer.replace_exemptions(exemptions);
+        Ok(loaded)
+    }
+
+    /// Spawn a background task refreshing the enforcer's exemptions every
+    /// [`ExemptionConfig::refresh_interval`].
+    pub fn spawn_refresher(&self, shutdown: &ShutdownCoordinator) -> JoinHandle<()> {
+        let manager = self.clone();
+        let interval = self.config.refresh_interval;
+        shutdown.spawn("metering.quota_exemptions", move |guard| {
+            guard.run_periodic(interval, move || {
+                let manager = manager.clone();
+                async move {
+                    if let Err(e) = manager.refresh().await {
+                        warn!(error = %e, "Quota exemption refresh failed");
+                    }
+                }
+            })
+        })
+    }
+
+    fn validate(&self, exemption: &QuotaExemption) -> CretoResult<()> {
+        exemption.validate(self.config.max_duration)?;
+        if exemption.ends_at <= self.clock.now() {
+            return Err(CretoError::ValidationFailed(
+                "Quota exemption has already ended".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn store(&self, exemption: QuotaExemption) -> CretoResult<QuotaExemption> {
+        self.repository.insert_exemption(&exemption).await?;
+        self.enforcer.register_exemption(&exemption);
+        info!(
+            exemption_id = %exemption.id,
+            organization_id = %exemption.organization_id,
+            starts_at = %exemption.starts_at,
+            ends_at = %exemption.ends_at,
+            approved_by = %exemption.approved_by,
+            reason = %exemption.reason,
+            "Quota exemption granted"
+        );
+        Ok(exemption)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quota::{Quota, QuotaPeriod};
+    use creto_common::TestClock;
+
+    struct Fixture {
+        clock: Arc<TestClock>,
+        enforcer: Arc<QuotaEnforcer>,
+        manager: QuotaExemptionManager<InMemoryQuotaExemptionRepository>,
+        org_id: OrganizationId,
+        agent_id: AgentId,
+    }
+
+    /// An enforcer with 100 "api_calls" a day, fully used, and the clock an
+    /// hour into the day.
+    fn fixture(config: ExemptionConfig) -> Fixture {
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let quota = Quota::new(org_id, "api_calls", 100, QuotaPeriod::Daily);
+        let clock = Arc::new(TestClock::new(quota.period_start + Duration::hours(1)));
+        let enforcer = Arc::new(
+            QuotaEnforcer::with_defaults().with_clock(Arc::clone(&clock) as Arc<dyn Clock>),
+        );
+        enforcer.register_quota(&quota);
+        enforcer
+            .record_usage(&org_id, &agent_id, "api_calls", 100)
+            .unwrap();
+
+        let manager = QuotaExemptionManager::new(
+            Arc::new(InMemoryQuotaExemptionRepository::new()),
+            Arc::clone(&enforcer),
+        )
+        .with_config(config)
+        .with_clock(Arc::clone(&clock) as Arc<dyn Clock>);
+
+        Fixture {
+            clock,
+            enforcer,
+            manager,
+            org_id,
+            agent_id,
+        }
+    }
+
+    fn migration_window(f: &Fixture, allowance: ExemptionAllowance) -> QuotaExemption {
+        let now = f.clock.now();
+        QuotaExemption::new(
+            f.org_id,
+            now,
+            now + Duration::hours(2),
+            allowance,
+            "Backfilling historical embeddings",
+            "ops-lead@example.com",
+        )
+    }
+
+    struct Approver(bool);
+
+    impl ExemptionApprover for Approver {
+        async fn approve(&self, _exemption: &QuotaExemption) -> CretoResult<()> {
+            if self.0 {
+                Ok(())
+            } else {
+                Err(CretoError::UnauthorizedApprover("refused".to_string()))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_active_window_allows_over_limit_and_records_usage() {
+        let f = fixture(ExemptionConfig::default());
+        let denied = f
+            .enforcer
+            .check(&f.org_id, &f.agent_id, "api_calls", 10)
+            .unwrap();
+        assert!(!denied.allowed);
+
+        f.manager
+            .grant(
+                migration_window(&f, ExemptionAllowance::Multiplier(2.0))
+                    .with_metrics(["api_calls"]),
+            )
+            .await
+            .unwrap();
+
+        let result = f
+            .enforcer
+            .check(&f.org_id, &f.agent_id, "api_calls", 10)
+            .unwrap();
+        assert!(result.allowed);
+        assert!(result.exempted);
+        assert!(!result.in_burst);
+
+        // Real usage keeps accumulating while exempted
+        f.enforcer
+            .record_usage(&f.org_id, &f.agent_id, "api_calls", 90)
+            .unwrap();
+        let status = f
+            .enforcer
+            .get_status(&f.org_id, &f.agent_id, "api_calls")
+            .unwrap();
+        assert_eq!(status.current_usage, 190);
+
+        // The multiplier still caps usage at twice the limit
+        let capped = f
+            .enforcer
+            .check(&f.org_id, &f.agent_id, "api_calls", 11)
+            .unwrap();
+        assert!(!capped.allowed);
+        assert!(!capped.exempted);
+        assert!(
+            f.enforcer
+                .check(&f.org_id, &f.agent_id, "api_calls", 10)
+                .unwrap()
+                .allowed
+        );
+
+        // Other metrics are not exempted
+        let other = Quota::new(f.org_id, "tokens", 0, QuotaPeriod::Daily);
+        f.enforcer.register_quota(&other);
+        assert!(
+            !f.enforcer
+                .check(&f.org_id, &f.agent_id, "tokens", 1)
+                .unwrap()
+                .allowed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expiry_restores_denial_on_next_check() {
+        let f = fixture(ExemptionConfig::default());
+        let exemption = f
+            .manager
+            .grant(migration_window(&f, ExemptionAllowance::Unlimited))
+            .await
+            .unwrap();
+
+        f.clock.set(exemption.ends_at - Duration::milliseconds(1));
+        let result = f
+            .enforcer
+            .check(&f.org_id, &f.agent_id, "api_calls", 1_000)
+            .unwrap();
+        assert!(result.allowed && result.exempted);
+        f.enforcer
+            .record_usage(&f.org_id, &f.agent_id, "api_calls", 1_000)
+            .unwrap();
+
+        // No refresh has run; the window still closes exactly on time
+        f.clock.set(exemption.ends_at);
+        let result = f
+            .enforcer
+            .check(&f.org_id, &f.agent_id, "api_calls", 1)
+            .unwrap();
+        assert!(!result.allowed);
+        assert!(!result.exempted);
+        assert_eq!(result.current_usage, 1_100);
+
+        // A refresh drops it from the enforcer altogether
+        assert_eq!(f.manager.refresh().await.unwrap(), 0);
+        assert!(f.enforcer.active_exemptions(&f.org_id).is_empty());
+        assert_eq!(f.manager.list(f.org_id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_max_duration_and_approval_validation() {
+        let f = fixture(ExemptionConfig {
+            max_duration: Duration::hours(2),
+            require_approval: true,
+            ..ExemptionConfig::default()
+        });
+
+        let mut too_long = migration_window(&f, ExemptionAllowance::Unlimited);
+        too_long.ends_at = too_long.starts_at + Duration::hours(2) + Duration::seconds(1);
+        let err = f
+            .manager
+            .grant_with_approval(too_long, &Approver(true))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("maximum of 120 minutes"));
+
+        let mut no_reason = migration_window(&f, ExemptionAllowance::Unlimited);
+        no_reason.reason = "  ".to_string();
+        assert!(f
+            .manager
+            .grant_with_approval(no_reason, &Approver(true))
+            .await
+            .is_err());
+
+        // Approval is required and a refusal stores nothing
+        let window = migration_window(&f, ExemptionAllowance::Unlimited);
+        assert!(matches!(
+            f.manager.grant(window.clone()).await,
+            Err(CretoError::Unauthorized(_))
+        ));
+        assert!(f
+            .manager
+            .grant_with_approval(window.clone(), &Approver(false))
+            .await
+            .is_err());
+        assert!(f.manager.list(f.org_id).await.unwrap().is_empty());
+        assert!(
+            !f.enforcer
+                .check(&f.org_id, &f.agent_id, "api_calls", 1)
+                .unwrap()
+                .allowed
+        );
+
+        f.manager
+            .grant_with_approval(window, &Approver(true))
+            .await
+            .unwrap();
+        assert!(
+            f.enforcer
+                .check(&f.org_id, &f.agent_id, "api_calls", 1)
+                .unwrap()
+                .exempted
+        );
+    }
+}
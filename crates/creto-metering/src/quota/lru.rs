@@ -0,0 +1,313 @@
+//! Hash-sharded LRU cache for the quota hot path.
+//!
+//! Each shard is a map from key to slot in a node vector, with the nodes
+//! threaded on a doubly linked list from most to least recently used. A hit
+//! moves its node to the front and an insert into a full shard evicts the
+//! back, both in O(1). Removing a node swaps the last node into its slot so
+//! the vector stays dense without a free list.
+//!
+//! Lookups reorder the list, so shards sit behind a mutex rather than a
+//! read-write lock; sharding keeps contention to keys that hash together.
+//! Hit, miss and eviction counters live in the shards and are updated under
+//! the same lock, so counting adds no shared cache line to the hot path.
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Marks the end of the recency list.
+const NIL: usize = usize::MAX;
+
+/// Cache counters, summed over all shards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheMetrics {
+    /// Lookups that found a usable entry.
+    pub hits: u64,
+    /// Lookups that found no entry, or one too stale to use.
+    pub misses: u64,
+    /// Entries dropped to make room for new ones.
+    pub evictions: u64,
+    /// Entries currently cached.
+    pub entries: usize,
+    /// Most entries the cache holds.
+    pub capacity: usize,
+}
+
+impl CacheMetrics {
+    /// Fraction of lookups that hit (0.0 before any lookup).
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// An LRU cache split into independently locked shards by key hash.
+pub(crate) struct ShardedLru<K, V> {
+    shards: Box<[Mutex<LruShard<K, V>>]>,
+    hasher: RandomState,
+}
+
+impl<K: Hash + Eq + Clone, V> ShardedLru<K, V> {
+    /// Cache holding about `capacity` entries over `shards` shards (rounded
+    /// up to a power of two). Each shard evicts on its own, so the total
+    /// is exact only when keys spread evenly.
+    pub fn new(shards: usize, capacity: usize) -> Self {
+        let count = shards.max(1).next_power_of_two();
+        let shard_capacity = capacity.div_ceil(count).max(1);
+        Self {
+            shards: (0..count)
+                .map(|_| Mutex::new(LruShard::new(shard_capacity)))
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    /// Apply `f` to a key's value, marking it most recently used.
+    ///
+    /// Counts a hit if `f` returns `Some`, and a miss otherwise or if the
+    /// key is absent.
+    pub fn get<Q, R>(&self, key: &Q, f: impl FnOnce(&V) -> Option<R>) -> Option<R>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut shard = lock(self.shard(key));
+        let result = shard.get(key).and_then(f);
+        if result.is_some() {
+            shard.hits += 1;
+        } else {
+            shard.misses += 1;
+        }
+        result
+    }
+
+    /// Insert a value as most recently used, evicting the shard's least
+    /// recently used entry if it is full.
+    pub fn insert(&self, key: K, value: V) {
+        lock(self.shard(&key)).insert(key, value);
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        lock(self.shard(key)).remove(key)
+    }
+
+    /// Total entries. Shards are counted one at a time, so the result is
+    /// only exact when nothing is writing.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).len()).sum()
+    }
+
+    /// Counters summed over all shards.
+    pub fn metrics(&self) -> CacheMetrics {
+        let mut metrics = CacheMetrics::default();
+        for shard in self.shards.iter() {
+            let shard = lock(shard);
+            metrics.hits += shard.hits;
+            metrics.misses += shard.misses;
+            metrics.evictions += shard.evictions;
+            metrics.entries += shard.len();
+            metrics.capacity += shard.capacity;
+        }
+        metrics
+    }
+
+    fn shard<Q>(&self, key: &Q) -> &Mutex<LruShard<K, V>>
+    where
+        Q: Hash + ?Sized,
+    {
+        let hash = self.hasher.hash_one(key) as usize;
+        &self.shards[hash & (self.shards.len() - 1)]
+    }
+}
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    /// Next more recently used node.
+    prev: usize,
+    /// Next less recently used node.
+    next: usize,
+}
+
+struct LruShard<K, V> {
+    slots: HashMap<K, usize>,
+    nodes: Vec<Node<K, V>>,
+    /// Most recently used node.
+    head: usize,
+    /// Least recently used node.
+    tail: usize,
+    capacity: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl<K: Hash + Eq + Clone, V> LruShard<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            slots: HashMap::new(),
+            nodes: Vec::new(),
+            head: NIL,
+            tail: NIL,
+            capacity,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = *self.slots.get(key)?;
+        self.unlink(slot);
+        self.push_front(slot);
+        Some(&self.nodes[slot].value)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if let Some(&slot) = self.slots.get(&key) {
+            self.nodes[slot].value = value;
+            self.unlink(slot);
+            self.push_front(slot);
+            return;
+        }
+        if self.nodes.len() >= self.capacity {
+            let lru = self.tail;
+            self.slots.remove(&self.nodes[lru].key);
+            self.remove_slot(lru);
+            self.evictions += 1;
+        }
+        let slot = self.nodes.len();
+        self.nodes.push(Node {
+            key: key.clone(),
+            value,
+            prev: NIL,
+            next: NIL,
+        });
+        self.slots.insert(key, slot);
+        self.push_front(slot);
+    }
+
+    fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.slots.remove(key)?;
+        Some(self.remove_slot(slot).value)
+    }
+
+    /// Take a node out of the list and the vector. Its key must already be
+    /// gone from `slots`.
+    fn remove_slot(&mut self, slot: usize) -> Node<K, V> {
+        self.unlink(slot);
+        let node = self.nodes.swap_remove(slot);
+        if slot < self.nodes.len() {
+            // The last node moved into `slot`; repoint everything at it
+            let (prev, next) = (self.nodes[slot].prev, self.nodes[slot].next);
+            match prev {
+                NIL => self.head = slot,
+                prev => self.nodes[prev].next = slot,
+            }
+            match next {
+                NIL => self.tail = slot,
+                next => self.nodes[next].prev = slot,
+            }
+            if let Some(moved) = self.slots.get_mut(&self.nodes[slot].key) {
+                *moved = slot;
+            }
+        }
+        node
+    }
+
+    fn unlink(&mut self, slot: usize) {
+        let (prev, next) = (self.nodes[slot].prev, self.nodes[slot].next);
+        match prev {
+            NIL => self.head = next,
+            prev => self.nodes[prev].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.nodes[next].prev = prev,
+        }
+        self.nodes[slot].prev = NIL;
+        self.nodes[slot].next = NIL;
+    }
+
+    fn push_front(&mut self, slot: usize) {
+        self.nodes[slot].next = self.head;
+        match self.head {
+            NIL => self.tail = slot,
+            head => self.nodes[head].prev = slot,
+        }
+        self.head = slot;
+    }
+}
+
+fn lock<T>(lock: &Mutex<T>) -> MutexGuard<'_, T> {
+    lock.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = ShardedLru::new(1, 3);
+        for key in ["a", "b", "c"] {
+            cache.insert(key.to_string(), key);
+        }
+        // Touch "a" so "b" becomes the oldest
+        assert_eq!(cache.get("a", |v| Some(*v)), Some("a"));
+        cache.insert("d".to_string(), "d");
+
+        assert_eq!(cache.get("b", |v| Some(*v)), None);
+        for key in ["a", "c", "d"] {
+            assert_eq!(cache.get(key, |v| Some(*v)), Some(key));
+        }
+        let metrics = cache.metrics();
+        assert_eq!(metrics.evictions, 1);
+        assert_eq!(metrics.hits, 4);
+        assert_eq!(metrics.misses, 1);
+        assert_eq!(metrics.entries, 3);
+    }
+
+    #[test]
+    fn test_remove_keeps_recency_order() {
+        let cache = ShardedLru::new(1, 4);
+        for key in 0..4 {
+            cache.insert(key, key);
+        }
+        // Removing from the middle moves the last node into its slot
+        assert_eq!(cache.remove(&1), Some(1));
+        assert_eq!(cache.remove(&1), None);
+        cache.get(&0, |v| Some(*v));
+        cache.insert(4, 4);
+        cache.insert(5, 5);
+
+        // 2 was least recently used
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.get(&2, |v| Some(*v)), None);
+        for key in [0, 3, 4, 5] {
+            assert_eq!(cache.get(&key, |v| Some(*v)), Some(key));
+        }
+    }
+}
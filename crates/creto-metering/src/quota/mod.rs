@@ -5,6 +5,9 @@
 //! - Local LRU cache for recent checks (~5µs)
 //! - Redis fallback for cache misses (~100µs)
 //! - Reservation system for pre-allocation
+//! - Time-boxed exemptions for maintenance and migrations
+//! - Once-per-period warnings as usage nears a limit
+//! - Period rollover notices for archiving usage history
 //!
 //! ## Performance Targets
 //!
@@ -34,14 +37,42 @@
 //! }
 //! ```
 
+mod backend;
 mod bloom;
 mod enforcer;
+mod exemption;
+mod lru;
+mod reconciliation;
 mod reservation;
+mod rollover;
+mod store;
 mod types;
+mod warning;
 
+pub use backend::{
+    InMemoryQuotaBackend, QuotaBackend, RedisQuotaBackend, DEFAULT_REDIS_KEY_PREFIX,
+};
 pub use bloom::{BloomConfig, QuotaBloomFilter, QuotaKey};
-pub use enforcer::{CheckSource, EnforcerConfig, EnforcerError, QuotaCheckResult, QuotaEnforcer};
+pub use enforcer::{
+    BatchQuotaResult, CheckSource, EnforcerConfig, EnforcerError, QuotaCheckResult, QuotaCounter,
+    QuotaDenialReason, QuotaEnforcer,
+};
+pub use exemption::{
+    ExemptionAllowance, ExemptionApprover, ExemptionConfig, InMemoryQuotaExemptionRepository,
+    LocalExemptionApprover, QuotaExemption, QuotaExemptionManager,
+};
+pub use lru::CacheMetrics;
+pub use reconciliation::{
+    CounterStore, InMemoryReconciliationAuditSink, QuotaDrift, QuotaReconciler,
+    QuotaReconciliationConfig, QuotaReconciliationReport, ReconciliationAuditSink,
+    ReconciliationMode, ReconciliationSkip, SkippedQuota,
+};
 pub use reservation::{
-    Reservation, ReservationError, ReservationStatus, ReservationStore, ReserveRequest,
+    InMemoryReservationRepository, Reservation, ReservationError, ReservationStatus,
+    ReservationStore, ReserveRequest,
 };
-pub use types::{Quota, QuotaPeriod, QuotaStatus};
+pub(crate) use rollover::PendingRollovers;
+pub use rollover::{QuotaRollover, QuotaRolloverListener};
+pub use store::InMemoryQuotaRepository;
+pub use types::{BurstAllowance, DelegationMultiplier, Quota, QuotaPeriod, QuotaStatus};
+pub use warning::{QuotaWarning, QuotaWarningSink};
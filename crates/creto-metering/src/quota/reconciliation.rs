@@ -0,0 +1,805 @@
+//! Reconciliation of quota usage counters against recorded usage events.
+//!
+//! Counters drift from the `usage_events` table when events are ingested out
+//! of band, retried, or lost in a restart mid-window. [`QuotaReconciler`]
+//! recomputes each active quota's usage for its current period from the
+//! [`EventRepository`] and reports, and optionally corrects, counters that
+//! differ by more than the configured threshold. Both the enforcer's
+//! counters and those persisted in a [`QuotaRepository`] are reconciled.
+//!
+//! Reconciliation runs alongside live ingestion. Event sums are taken up to a
+//! snapshot timestamp, and a counter is only compared (and only corrected) if
+//! it last changed before that snapshot; anything charged after it is left
+//! for the next run.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::enforcer::{QuotaCounter, QuotaEnforcer};
+use super::Quota;
+use crate::aggregation::UsageSelection;
+use crate::repository::{EventRepository, QuotaRepository};
+use crate::sampling::IngestionSampler;
+
+/// What to do with counters found to have drifted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationMode {
+    /// Report drift without touching counters.
+    #[default]
+    ReportOnly,
+    /// Overwrite drifted counters with the recomputed usage.
+    AutoCorrect,
+}
+
+/// Quota reconciliation settings.
+#[derive(Debug, Clone)]
+pub struct QuotaReconciliationConfig {
+    /// Report only, or also correct.
+    pub mode: ReconciliationMode,
+
+    /// Drift is acted on when the absolute delta exceeds this...
+    pub absolute_threshold: i64,
+
+    /// ...or when the delta exceeds this percentage of the recomputed usage.
+    pub percent_threshold: f64,
+
+    /// Quotas examined per page.
+    pub page_size: usize,
+
+    /// Pause between pages, bounding the load placed on the event store.
+    pub page_delay: Duration,
+}
+
+impl Default for QuotaReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            mode: ReconciliationMode::ReportOnly,
+            absolute_threshold: 0,
+            percent_threshold: 0.0,
+            page_size: 100,
+            page_delay: Duration::from_millis(100),
+        }
+    }
+}
+
+impl QuotaReconciliationConfig {
+    /// Whether a delta between counter and recomputed usage is large enough
+    /// to act on.
+    fn exceeds_threshold(&self, delta: i64, recomputed: i64) -> bool {
+        if delta == 0 {
+            return false;
+        }
+        let percent = if recomputed == 0 {
+            f64::INFINITY
+        } else {
+            delta.unsigned_abs() as f64 * 100.0 / recomputed.unsigned_abs() as f64
+        };
+        delta.abs() > self.absolute_threshold || percent > self.percent_threshold
+    }
+}
+
+/// Where a reconciled counter is kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterStore {
+    /// The [`QuotaEnforcer`]'s counter.
+    #[default]
+    Enforcer,
+    /// The counter persisted in a [`QuotaRepository`].
+    Repository,
+}
+
+/// A counter that differs from its recomputed usage by more than the
+/// threshold. Doubles as the audit record of the comparison.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaDrift {
+    pub quota_id: Uuid,
+
+    /// Which counter drifted.
+    #[serde(default)]
+    pub store: CounterStore,
+    pub organization_id: OrganizationId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<AgentId>,
+    pub metric_code: String,
+    pub period_start: DateTime<Utc>,
+
+    /// Usage according to the counter.
+    pub counter_usage: i64,
+
+    /// Usage recomputed from events up to the snapshot.
+    pub recomputed_usage: i64,
+
+    /// `recomputed_usage - counter_usage`.
+    pub delta: i64,
+
+    /// Whether the counter was overwritten with `recomputed_usage`.
+    pub corrected: bool,
+
+    /// Snapshot the events were summed at.
+    pub snapshot_at: DateTime<Utc>,
+}
+
+/// Why a quota was not reconciled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationSkip {
+    /// The counter changed after the snapshot, so it includes usage the
+    /// event sum does not.
+    UpdatedAfterSnapshot,
+    /// The quota charges delegated usage at a multiplier, so its counter
+    /// is not a plain sum of event quantities.
+    WeightedCharges,
+    /// The metric's stored events are sampled, so their sum only estimates
+    /// the counter.
+    SampledEvents,
+}
+
+/// A quota left alone, and why.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedQuota {
+    pub quota_id: Uuid,
+    #[serde(default)]
+    pub store: CounterStore,
+    pub reason: ReconciliationSkip,
+}
+
+/// Outcome of one reconciliation run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaReconciliationReport {
+    pub snapshot_at: DateTime<Utc>,
+    pub mode: ReconciliationMode,
+
+    /// Active quotas compared against their events.
+    pub quotas_checked: usize,
+
+    /// Counters over the threshold.
+    pub drifts: Vec<QuotaDrift>,
+
+    /// Active quotas not compared.
+    pub skipped: Vec<SkippedQuota>,
+}
+
+impl QuotaReconciliationReport {
+    /// Number of counters corrected.
+    pub fn corrected(&self) -> usize {
+        self.drifts.iter().filter(|d| d.corrected).count()
+    }
+}
+
+/// Receives an audit record for every drifted counter.
+pub trait ReconciliationAuditSink: Send + Sync {
+    /// Record a drift, corrected or not.
+    fn record(&self, drift: &QuotaDrift);
+}
+
+/// In-memory audit sink for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryReconciliationAuditSink {
+    records: RwLock<Vec<QuotaDrift>>,
+}
+
+impl InMemoryReconciliationAuditSink {
+    /// Create an empty sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records received, oldest first.
+    pub fn records(&self) -> Vec<QuotaDrift> {
+        self.records.read().unwrap().clone()
+    }
+}
+
+impl ReconciliationAuditSink for InMemoryReconciliationAuditSink {
+    fn record(&self, drift: &QuotaDrift) {
+        self.records.write().unwrap().push(drift.clone());
+    }
+}
+
+/// Recomputes quota usage from events and reconciles the counters.
+#[derive(Default)]
+pub struct QuotaReconciler {
+    config: QuotaReconciliationConfig,
+    audit: Option<std::sync::Arc<dyn ReconciliationAuditSink>>,
+    sampler: Option<std::sync::Arc<IngestionSampler>>,
+}
+
+impl QuotaReconciler {
+    /// Create a reconciler.
+    pub fn new(config: QuotaReconciliationConfig) -> Self {
+        Self {
+            config,
+            audit: None,
+            sampler: None,
+        }
+    }
+
+    /// Send an audit record for every drifted counter to `sink`.
+    pub fn with_audit_sink(mut self, sink: std::sync::Arc<dyn ReconciliationAuditSink>) -> Self {
+        self.audit = Some(sink);
+        self
+    }
+
+    /// Skip quotas on metrics `sampler` currently samples.
+    pub fn with_sampler(mut self, sampler: std::sync::Arc<IngestionSampler>) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// The reconciliation settings.
+    pub fn config(&self) -> &QuotaReconciliationConfig {
+        &self.config
+    }
+
+    /// Reconcile every quota active at `snapshot_at` against the events
+    /// recorded before it.
+    pub async fn run(
+        &self,
+        enforcer: &QuotaEnforcer,
+        events: &dyn EventRepository,
+        snapshot_at: DateTime<Utc>,
+    ) -> CretoResult<QuotaReconciliationReport> {
+        let mut report = self.report(snapshot_at);
+        self.reconcile_store(Counters::Enforcer(enforcer), enforcer, events, &mut report)
+            .await?;
+        self.log(&report);
+        Ok(report)
+    }
+
+    /// Like [`Self::run`], also reconciling the counters persisted in
+    /// `quotas`, which the enforcer may not hold.
+    pub async fn run_with_repository(
+        &self,
+        enforcer: &QuotaEnforcer,
+        quotas: &dyn QuotaRepository,
+        events: &dyn EventRepository,
+        snapshot_at: DateTime<Utc>,
+    ) -> CretoResult<QuotaReconciliationReport> {
+        let mut report = self.report(snapshot_at);
+        self.reconcile_store(Counters::Enforcer(enforcer), enforcer, events, &mut report)
+            .await?;
+        self.reconcile_store(Counters::Repository(quotas), enforcer, events, &mut report)
+            .await?;
+        self.log(&report);
+        Ok(report)
+    }
+
+    fn report(&self, snapshot_at: DateTime<Utc>) -> QuotaReconciliationReport {
+        QuotaReconciliationReport {
+            snapshot_at,
+            mode: self.config.mode,
+            quotas_checked: 0,
+            drifts: Vec::new(),
+            skipped: Vec::new(),
+        }
+    }
+
+    fn log(&self, report: &QuotaReconciliationReport) {
+        if !report.drifts.is_empty() {
+            tracing::info!(
+                checked = report.quotas_checked,
+                drifted = report.drifts.len(),
+                corrected = report.corrected(),
+                "Quota reconciliation found drift"
+            );
+        }
+    }
+
+    async fn reconcile_store(
+        &self,
+        counters: Counters<'_>,
+        enforcer: &QuotaEnforcer,
+        events: &dyn EventRepository,
+        report: &mut QuotaReconciliationReport,
+    ) -> CretoResult<()> {
+        let page_size = self.config.page_size.max(1);
+
+        let mut offset = 0;
+        loop {
+            let page = counters.page(report.snapshot_at, offset, page_size).await?;
+            let last_page = page.len() < page_size;
+            offset += page.len();
+
+            for counter in page {
+                self.reconcile_one(&counters, enforcer, events, counter, report)
+                    .await?;
+            }
+
+            if last_page {
+                return Ok(());
+            }
+            if !self.config.page_delay.is_zero() {
+                tokio::time::sleep(self.config.page_delay).await;
+            }
+        }
+    }
+
+    async fn reconcile_one(
+        &self,
+        counters: &Counters<'_>,
+        enforcer: &QuotaEnforcer,
+        events: &dyn EventRepository,
+        counter: QuotaCounter,
+        report: &mut QuotaReconciliationReport,
+    ) -> CretoResult<()> {
+        let snapshot_at = report.snapshot_at;
+        let store = counters.store();
+        let quota = counter.quota;
+        if quota.period_start > snapshot_at || quota.period_end <= snapshot_at {
+            return Ok(());
+        }
+
+        let sampled = self
+            .sampler
+            .as_ref()
+            .is_some_and(|s| s.is_sampled(quota.organization_id, &quota.metric_code));
+        let skip = if quota.delegation_multiplier.is_some() {
+            Some(ReconciliationSkip::WeightedCharges)
+        } else if sampled {
+            Some(ReconciliationSkip::SampledEvents)
+        } else if counter.updated_at.is_some_and(|at| at >= snapshot_at) {
+            Some(ReconciliationSkip::UpdatedAfterSnapshot)
+        } else {
+            None
+        };
+        if let Some(reason) = skip {
+            report.skipped.push(SkippedQuota {
+                quota_id: quota.id,
+                store,
+                reason,
+            });
+            return Ok(());
+        }
+
+        report.quotas_checked += 1;
+        let own_quota = counters
+            .agents_with_quota(enforcer, &quota, snapshot_at)
+            .await?;
+        let recomputed = recompute_usage(enforcer, events, &quota, &own_quota, snapshot_at).await?;
+        let delta = recomputed - quota.current_usage;
+        if !self.config.exceeds_threshold(delta, recomputed) {
+            return Ok(());
+        }
+
+        let corrected = self.config.mode == ReconciliationMode::AutoCorrect
+            && counters
+                .correct_usage(&quota, recomputed, snapshot_at)
+                .await?;
+        if self.config.mode == ReconciliationMode::AutoCorrect && !corrected {
+            // Charged between reading the counter and correcting it
+            report.skipped.push(SkippedQuota {
+                quota_id: quota.id,
+                store,
+                reason: ReconciliationSkip::UpdatedAfterSnapshot,
+            });
+            return Ok(());
+        }
+
+        let drift = QuotaDrift {
+            quota_id: quota.id,
+            store,
+            organization_id: quota.organization_id,
+            agent_id: quota.agent_id,
+            metric_code: quota.metric_code.clone(),
+            period_start: quota.period_start,
+            counter_usage: quota.current_usage,
+            recomputed_usage: recomputed,
+            delta,
+            corrected,
+            snapshot_at,
+        };
+        tracing::warn!(
+            quota_id = %drift.quota_id,
+            store = ?drift.store,
+            metric_code = %drift.metric_code,
+            counter_usage = drift.counter_usage,
+            recomputed_usage = drift.recomputed_usage,
+            corrected,
+            "Quota usage counter drifted from recorded events"
+        );
+        if let Some(sink) = &self.audit {
+            sink.record(&drift);
+        }
+        report.drifts.push(drift);
+        Ok(())
+    }
+}
+
+/// The counters a reconciliation pass reads and corrects.
+enum Counters<'a> {
+    Enforcer(&'a QuotaEnforcer),
+    Repository(&'a dyn QuotaRepository),
+}
+
+impl Counters<'_> {
+    fn store(&self) -> CounterStore {
+        match self {
+            Counters::Enforcer(_) => CounterStore::Enforcer,
+            Counters::Repository(_) => CounterStore::Repository,
+        }
+    }
+
+    async fn page(
+        &self,
+        snapshot_at: DateTime<Utc>,
+        offset: usize,
+        limit: usize,
+    ) -> CretoResult<Vec<QuotaCounter>> {
+        match self {
+            Counters::Enforcer(enforcer) => Ok(enforcer.counters(offset, limit)),
+            Counters::Repository(quotas) => quotas.list_active(snapshot_at, offset, limit).await,
+        }
+    }
+
+    /// Agents drawing from their own quota rather than an organization-wide
+    /// `quota` in the same store.
+    async fn agents_with_quota(
+        &self,
+        enforcer: &QuotaEnforcer,
+        quota: &Quota,
+        snapshot_at: DateTime<Utc>,
+    ) -> CretoResult<Vec<AgentId>> {
+        if quota.agent_id.is_some() {
+            return Ok(Vec::new());
+        }
+        match self {
+            Counters::Enforcer(_) => {
+                Ok(enforcer.agents_with_quota(&quota.organization_id, &quota.metric_code))
+            }
+            Counters::Repository(quotas) => Ok(quotas
+                .list_by_org(quota.organization_id)
+                .await?
+                .into_iter()
+                .filter(|q| {
+                    q.metric_code == quota.metric_code
+                        && q.period_start <= snapshot_at
+                        && q.period_end > snapshot_at
+                })
+                .filter_map(|q| q.agent_id)
+                .collect()),
+        }
+    }
+
+    async fn correct_usage(
+        &self,
+        quota: &Quota,
+        usage: i64,
+        unchanged_since: DateTime<Utc>,
+    ) -> CretoResult<bool> {
+        match self {
+            Counters::Enforcer(enforcer) => {
+                Ok(enforcer.correct_usage(quota, usage, unchanged_since))
+            }
+            Counters::Repository(quotas) => {
+                quotas.correct_usage(quota, usage, unchanged_since).await
+            }
+        }
+    }
+}
+
+/// Usage a quota should show for its current period up to `snapshot_at`.
+///
+/// An organization-wide quota only counts agents without a quota of their
+/// own (`own_quota`), matching how the enforcer charges usage. Events recorded under an
+/// alias of the quota's metric count towards it, as they do when charged.
+async fn recompute_usage(
+    enforcer: &QuotaEnforcer,
+    events: &dyn EventRepository,
+    quota: &Quota,
+    own_quota: &[AgentId],
+    snapshot_at: DateTime<Utc>,
+) -> CretoResult<i64> {
+    // Sums exclude the snapshot instant
+    let mut selection = UsageSelection::new(
+        quota.organization_id,
+        quota.metric_code.clone(),
+        quota.period_start,
+        snapshot_at,
+    )
+    .with_exclusive_end();
+    if let Some(aliases) = enforcer.metric_aliases() {
+        selection = selection.with_aliases(aliases.aliases_of(&quota.metric_code));
+    }
+    let by_agent: HashMap<_, _> = events
+        .usage_by_agent(&selection)
+        .await?
+        .into_iter()
+        .map(|u| (u.agent_id, u.quantity))
+        .collect();
+
+    if let Some(agent_id) = quota.agent_id {
+        return Ok(by_agent.get(&agent_id).copied().unwrap_or(0));
+    }
+
+    let total: i64 = by_agent.values().sum();
+    Ok(total
+        - own_quota
+            .iter()
+            .filter_map(|agent| by_agent.get(agent))
+            .sum::<i64>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{InMemoryEventRepository, UsageEvent, UsageEventType};
+    use crate::quota::QuotaPeriod;
+    use std::sync::Arc;
+
+    struct Fixture {
+        enforcer: QuotaEnforcer,
+        events: InMemoryEventRepository,
+        org_id: OrganizationId,
+        agent_id: AgentId,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                enforcer: QuotaEnforcer::new(),
+                events: InMemoryEventRepository::new(),
+                org_id: OrganizationId::new(),
+                agent_id: AgentId::new(),
+            }
+        }
+
+        fn quota(&self, metric: &str, agent_id: Option<AgentId>) -> Quota {
+            let mut quota = Quota::new(self.org_id, metric, 10_000, QuotaPeriod::Daily);
+            quota.agent_id = agent_id;
+            self.enforcer.register_quota(&quota);
+            quota
+        }
+
+        /// Usage both charged and recorded, as on the normal ingest path.
+        fn ingest(&self, agent_id: AgentId, metric: &str, quantity: i64) {
+            self.record_event(agent_id, metric, quantity);
+            self.charge(agent_id, metric, quantity);
+        }
+
+        /// Usage recorded out of band, never charged.
+        fn record_event(&self, agent_id: AgentId, metric: &str, quantity: i64) {
+            let event = UsageEvent::builder()
+                .organization_id(self.org_id)
+                .agent_id(agent_id)
+                .event_type(UsageEventType::ApiCall)
+                .code(metric)
+                .quantity(quantity)
+                .build();
+            self.events.insert(event);
+        }
+
+        /// Usage charged twice by a retry, recorded once.
+        fn charge(&self, agent_id: AgentId, metric: &str, quantity: i64) {
+            self.enforcer
+                .record_usage(&self.org_id, &agent_id, metric, quantity)
+                .unwrap();
+        }
+
+        fn usage(&self, quota: &Quota) -> i64 {
+            self.enforcer
+                .counters(0, usize::MAX)
+                .into_iter()
+                .find(|c| c.quota.id == quota.id)
+                .unwrap()
+                .quota
+                .current_usage
+        }
+
+        async fn reconcile(&self, config: QuotaReconciliationConfig) -> QuotaReconciliationReport {
+            QuotaReconciler::new(config)
+                .run(&self.enforcer, &self.events, Utc::now())
+                .await
+                .unwrap()
+        }
+    }
+
+    fn auto_correct() -> QuotaReconciliationConfig {
+        QuotaReconciliationConfig {
+            mode: ReconciliationMode::AutoCorrect,
+            page_size: 1,
+            page_delay: Duration::ZERO,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_drift_in_both_directions_corrected() {
+        let f = Fixture::new();
+        let under = f.quota("api_calls", None);
+        let over = f.quota("tokens", Some(f.agent_id));
+        let clean = f.quota("storage", None);
+
+        f.ingest(f.agent_id, "api_calls", 10);
+        f.record_event(AgentId::new(), "api_calls", 5);
+        f.ingest(f.agent_id, "tokens", 100);
+        f.charge(f.agent_id, "tokens", 40);
+        f.ingest(f.agent_id, "storage", 7);
+
+        let sink = Arc::new(InMemoryReconciliationAuditSink::new());
+        let report = QuotaReconciler::new(auto_correct())
+            .with_audit_sink(sink.clone())
+            .run(&f.enforcer, &f.events, Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(report.quotas_checked, 3);
+        assert_eq!(report.corrected(), 2);
+        let delta_of = |quota: &Quota| {
+            report
+                .drifts
+                .iter()
+                .find(|d| d.quota_id == quota.id)
+                .map(|d| (d.counter_usage, d.recomputed_usage, d.delta))
+        };
+        assert_eq!(delta_of(&under), Some((10, 15, 5)));
+        assert_eq!(delta_of(&over), Some((140, 100, -40)));
+        assert_eq!(delta_of(&clean), None);
+
+        assert_eq!(f.usage(&under), 15);
+        assert_eq!(f.usage(&over), 100);
+        assert_eq!(f.usage(&clean), 7);
+        assert_eq!(sink.records(), report.drifts);
+
+        // Nothing left to fix
+        assert!(f.reconcile(auto_correct()).await.drifts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_report_only_and_thresholds() {
+        let f = Fixture::new();
+        let small = f.quota("api_calls", None);
+        let large = f.quota("tokens", None);
+
+        // 3 over 100 (3%): within both thresholds
+        f.ingest(f.agent_id, "api_calls", 100);
+        f.charge(f.agent_id, "api_calls", 3);
+        // 3 over 10 (30%): over the percentage threshold
+        f.ingest(f.agent_id, "tokens", 10);
+        f.charge(f.agent_id, "tokens", 3);
+
+        let thresholds = QuotaReconciliationConfig {
+            absolute_threshold: 5,
+            percent_threshold: 10.0,
+            page_delay: Duration::ZERO,
+            ..Default::default()
+        };
+        let report = f.reconcile(thresholds.clone()).await;
+        assert_eq!(report.mode, ReconciliationMode::ReportOnly);
+        assert_eq!(report.drifts.len(), 1);
+        assert_eq!(report.drifts[0].quota_id, large.id);
+        assert!(!report.drifts[0].corrected);
+        assert_eq!(f.usage(&large), 13);
+
+        let report = f
+            .reconcile(QuotaReconciliationConfig {
+                mode: ReconciliationMode::AutoCorrect,
+                ..thresholds
+            })
+            .await;
+        assert_eq!(report.corrected(), 1);
+        assert_eq!(f.usage(&large), 10);
+        assert_eq!(f.usage(&small), 103);
+    }
+
+    #[tokio::test]
+    async fn test_counters_changed_after_snapshot_left_alone() {
+        let f = Fixture::new();
+        let org_quota = f.quota("api_calls", None);
+        let agent_quota = f.quota("api_calls", Some(f.agent_id));
+        let weighted = Quota::new(f.org_id, "tokens", 1_000, QuotaPeriod::Daily)
+            .with_delegation_multiplier(1, 2.0)
+            .unwrap();
+        f.enforcer.register_quota(&weighted);
+
+        // The agent with its own quota does not count towards the org quota
+        let other_agent = AgentId::new();
+        f.ingest(other_agent, "api_calls", 4);
+        f.ingest(f.agent_id, "api_calls", 6);
+        let snapshot_at = Utc::now();
+        f.ingest(other_agent, "api_calls", 1);
+
+        let report = QuotaReconciler::new(auto_correct())
+            .run(&f.enforcer, &f.events, snapshot_at)
+            .await
+            .unwrap();
+        assert_eq!(report.quotas_checked, 1);
+        assert!(report.drifts.is_empty());
+        assert!(report.skipped.contains(&SkippedQuota {
+            quota_id: org_quota.id,
+            store: CounterStore::Enforcer,
+            reason: ReconciliationSkip::UpdatedAfterSnapshot,
+        }));
+        assert!(report.skipped.contains(&SkippedQuota {
+            quota_id: weighted.id,
+            store: CounterStore::Enforcer,
+            reason: ReconciliationSkip::WeightedCharges,
+        }));
+        assert_eq!(f.usage(&org_quota), 5);
+        assert_eq!(f.usage(&agent_quota), 6);
+    }
+
+    #[tokio::test]
+    async fn test_events_at_snapshot_left_for_next_run() {
+        let f = Fixture::new();
+        let quota = f.quota("api_calls", None);
+        let snapshot_at = Utc::now();
+        for (quantity, timestamp) in [
+            (2, snapshot_at - chrono::Duration::microseconds(1)),
+            (7, snapshot_at),
+        ] {
+            f.events.insert(
+                UsageEvent::builder()
+                    .organization_id(f.org_id)
+                    .agent_id(f.agent_id)
+                    .event_type(UsageEventType::ApiCall)
+                    .code("api_calls")
+                    .quantity(quantity)
+                    .timestamp(timestamp)
+                    .build(),
+            );
+        }
+
+        let report = QuotaReconciler::new(auto_correct())
+            .run(&f.enforcer, &f.events, snapshot_at)
+            .await
+            .unwrap();
+        assert_eq!(report.drifts.len(), 1);
+        assert_eq!(report.drifts[0].recomputed_usage, 2);
+        assert_eq!(f.usage(&quota), 2);
+    }
+
+    #[tokio::test]
+    async fn test_persisted_counters_reconciled() {
+        let f = Fixture::new();
+        let quotas = crate::quota::InMemoryQuotaRepository::new();
+        let org_quota = quotas
+            .get_or_create(f.org_id, None, "api_calls", QuotaPeriod::Daily, 10_000)
+            .await
+            .unwrap();
+        let other_agent = AgentId::new();
+        let agent_quota = quotas
+            .get_or_create(
+                f.org_id,
+                Some(other_agent),
+                "api_calls",
+                QuotaPeriod::Daily,
+                10_000,
+            )
+            .await
+            .unwrap();
+        // Charged by another process that lost two events in a restart
+        quotas.increment_usage(org_quota.id, 3).await.unwrap();
+        quotas.increment_usage(agent_quota.id, 4).await.unwrap();
+        f.record_event(f.agent_id, "api_calls", 5);
+        f.record_event(other_agent, "api_calls", 4);
+        let snapshot_at = Utc::now() + chrono::Duration::milliseconds(1);
+
+        let report = QuotaReconciler::new(auto_correct())
+            .run_with_repository(&f.enforcer, &quotas, &f.events, snapshot_at)
+            .await
+            .unwrap();
+        assert_eq!(report.quotas_checked, 2);
+        assert_eq!(report.drifts.len(), 1);
+        let drift = &report.drifts[0];
+        assert_eq!(drift.quota_id, org_quota.id);
+        assert_eq!(drift.store, CounterStore::Repository);
+        assert_eq!(drift.counter_usage, 3);
+        assert_eq!(drift.recomputed_usage, 5);
+        assert!(drift.corrected);
+
+        let stored = quotas
+            .get_current(f.org_id, None, "api_calls")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.current_usage, 5);
+    }
+}
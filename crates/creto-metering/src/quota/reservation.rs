@@ -1,15 +1,22 @@
 //! Quota reservation system for pre-allocating quota.
 //!
 //! Enables agents to reserve quota before performing operations,
-//! preventing overbooking under concurrent access.
+//! preventing overbooking under concurrent access. Reservations can be
+//! written through to a [`ReservationRepository`] and recovered after a
+//! restart.
 
 use chrono::{DateTime, Duration, Utc};
+use creto_common::{CretoError, ShardedMap, DEFAULT_SHARDS};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, RwLock};
 use thiserror::Error;
+use tracing::warn;
 use uuid::Uuid;
 
+use crate::repository::ReservationRepository;
+
 /// Reservation status state machine.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -55,6 +62,9 @@ pub struct Reservation {
     /// Optional metadata.
     #[serde(default)]
     pub metadata: Option<serde_json::Value>,
+    /// Group the reservation was made in, committed and released together.
+    #[serde(default)]
+    pub group_id: Option<Uuid>,
 }
 
 impl Reservation {
@@ -146,6 +156,12 @@ pub enum ReservationError {
 
     #[error("Lock error: {0}")]
     LockError(String),
+
+    #[error("Reservation group not found: {0}")]
+    GroupNotFound(Uuid),
+
+    #[error("Reservation storage error: {0}")]
+    Storage(String),
 }
 
 impl ReservationError {
@@ -158,52 +174,196 @@ impl ReservationError {
             Self::ExceedsReserved { .. } => "ENABLE-403",
             Self::Expired(_) => "ENABLE-404",
             Self::LockError(_) => "ENABLE-405",
+            Self::GroupNotFound(_) => "ENABLE-406",
+            Self::Storage(_) => "ENABLE-407",
         }
     }
 }
 
-/// In-memory reservation store for local testing.
-/// Production uses PostgreSQL + Redis.
+/// Reservation store, held in memory and optionally written through to a
+/// [`ReservationRepository`].
+///
+/// Reservations and totals are sharded by key, and each org+metric total is
+/// an atomic counter, so reservations against different metrics never
+/// contend and concurrent reservations against one metric cannot overbook.
+///
+/// With a repository, a reservation is stored before it is handed out, and
+/// every later transition is written back. A failed write-back is logged
+/// rather than returned: the stored row stays active and is expired by its
+/// TTL in the next sweep or recovery, so at worst the quota is held until
+/// then.
 pub struct ReservationStore {
-    reservations: RwLock<HashMap<Uuid, Reservation>>,
+    reservations: ShardedMap<Uuid, Reservation>,
     /// Total reserved per org+metric (for fast lookup).
-    reserved_totals: RwLock<HashMap<String, i64>>,
+    reserved_totals: ShardedMap<String, Arc<AtomicI64>>,
+    /// Reservation IDs of each group, in request order.
+    groups: ShardedMap<Uuid, Vec<Uuid>>,
+    /// Durable copy of the reservations (None = memory only).
+    repository: Option<Arc<dyn ReservationRepository>>,
 }
 
 impl ReservationStore {
     /// Create a new in-memory store.
     pub fn new() -> Self {
+        Self::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Create a store split into `shards` shards (rounded up to a power of
+    /// two).
+    pub fn with_shards(shards: usize) -> Self {
         Self {
-            reservations: RwLock::new(HashMap::new()),
-            reserved_totals: RwLock::new(HashMap::new()),
+            reservations: ShardedMap::new(shards),
+            reserved_totals: ShardedMap::new(shards),
+            groups: ShardedMap::new(shards),
+            repository: None,
         }
     }
 
+    /// Write reservations through to `repository`.
+    ///
+    /// Call [`recover`](Self::recover) before taking reservations to pick up
+    /// the ones active when the process last stopped.
+    pub fn with_repository(mut self, repository: Arc<dyn ReservationRepository>) -> Self {
+        self.repository = Some(repository);
+        self
+    }
+
     /// Reserve quota.
-    pub fn reserve(
+    pub async fn reserve(
         &self,
         request: ReserveRequest,
         available_quota: i64,
     ) -> Result<Reservation, ReservationError> {
-        let total_key = format!("{}:{}", request.organization_id, request.metric_code);
+        self.reserve_in_group(request, available_quota, None).await
+    }
+
+    /// Reserve quota for several requests, each with its available quota, as
+    /// one group. Returns the group ID.
+    ///
+    /// If any request lacks the quota, the ones already made are released
+    /// and its error is returned.
+    pub async fn reserve_group(
+        &self,
+        requests: Vec<(ReserveRequest, i64)>,
+    ) -> Result<Uuid, ReservationError> {
+        let group_id = Uuid::now_v7();
+        let mut ids = Vec::with_capacity(requests.len());
+        for (request, available_quota) in requests {
+            match self
+                .reserve_in_group(request, available_quota, Some(group_id))
+                .await
+            {
+                Ok(reservation) => ids.push(reservation.id),
+                Err(e) => {
+                    for id in ids {
+                        let _ = self.release(id).await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        self.groups.insert(group_id, ids);
+        Ok(group_id)
+    }
 
-        // Check available quota including existing reservations
-        let current_reserved = {
-            let totals = self
-                .reserved_totals
-                .read()
-                .map_err(|e| ReservationError::LockError(e.to_string()))?;
-            *totals.get(&total_key).unwrap_or(&0)
+    /// Commit every reservation in a group, with the actual amount used per
+    /// metric code (0 for codes not listed).
+    ///
+    /// Every reservation is checked before any is committed, so a group
+    /// with one expired or over-used reservation is left untouched. Should
+    /// one still fail to commit (it expired in between), the rest of the
+    /// group is released.
+    pub async fn commit_group(
+        &self,
+        group_id: Uuid,
+        actual: &[(&str, i64)],
+    ) -> Result<Vec<Reservation>, ReservationError> {
+        let ids = self
+            .groups
+            .get(&group_id)
+            .ok_or(ReservationError::GroupNotFound(group_id))?;
+        let actual_for = |metric_code: &str| {
+            actual
+                .iter()
+                .find(|(code, _)| *code == metric_code)
+                .map_or(0, |(_, amount)| *amount)
         };
 
-        let truly_available = available_quota - current_reserved;
-        if truly_available < request.amount {
-            return Err(ReservationError::InsufficientQuota {
-                requested: request.amount,
-                available: truly_available,
-            });
+        for id in &ids {
+            let reservation = self.get(*id).ok_or(ReservationError::NotFound(*id))?;
+            if reservation.status != ReservationStatus::Active {
+                return Err(ReservationError::InvalidStatus(reservation.status));
+            }
+            if reservation.is_expired() {
+                return Err(ReservationError::Expired(reservation.expires_at));
+            }
+            let amount = actual_for(&reservation.metric_code);
+            if amount > reservation.reserved_amount {
+                return Err(ReservationError::ExceedsReserved {
+                    actual: amount,
+                    reserved: reservation.reserved_amount,
+                });
+            }
         }
 
+        let mut committed = Vec::with_capacity(ids.len());
+        for (i, id) in ids.iter().enumerate() {
+            let amount = self
+                .get(*id)
+                .map_or(0, |reservation| actual_for(&reservation.metric_code));
+            match self.commit(*id, amount).await {
+                Ok(reservation) => committed.push(reservation),
+                Err(e) => {
+                    for id in &ids[i + 1..] {
+                        let _ = self.release(*id).await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(committed)
+    }
+
+    /// Release every active reservation in a group.
+    pub async fn release_group(
+        &self,
+        group_id: Uuid,
+    ) -> Result<Vec<Reservation>, ReservationError> {
+        let ids = self
+            .groups
+            .get(&group_id)
+            .ok_or(ReservationError::GroupNotFound(group_id))?;
+        let mut released = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Ok(reservation) = self.release(id).await {
+                released.push(reservation);
+            }
+        }
+        Ok(released)
+    }
+
+    async fn reserve_in_group(
+        &self,
+        request: ReserveRequest,
+        available_quota: i64,
+        group_id: Option<Uuid>,
+    ) -> Result<Reservation, ReservationError> {
+        let total_key = format!("{}:{}", request.organization_id, request.metric_code);
+        let total = self
+            .reserved_totals
+            .get_or_insert_with(total_key, || Arc::new(AtomicI64::new(0)));
+
+        // Claim the amount only if it still fits alongside existing
+        // reservations, so racing reservations cannot overbook
+        total
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |reserved| {
+                (available_quota - reserved >= request.amount).then_some(reserved + request.amount)
+            })
+            .map_err(|reserved| ReservationError::InsufficientQuota {
+                requested: request.amount,
+                available: available_quota - reserved,
+            })?;
+
         // Create reservation
         let now = Utc::now();
         let reservation = Reservation {
@@ -217,176 +377,265 @@ impl ReservationStore {
             expires_at: now + Duration::seconds(request.ttl_seconds as i64),
             status: ReservationStatus::Active,
             metadata: request.metadata,
+            group_id,
         };
 
-        // Store reservation
-        {
-            let mut reservations = self
-                .reservations
-                .write()
-                .map_err(|e| ReservationError::LockError(e.to_string()))?;
-            reservations.insert(reservation.id, reservation.clone());
-        }
-
-        // Update reserved total
-        {
-            let mut totals = self
-                .reserved_totals
-                .write()
-                .map_err(|e| ReservationError::LockError(e.to_string()))?;
-            *totals.entry(total_key).or_insert(0) += request.amount;
+        // Only hand out reservations that would survive a restart
+        if let Some(repository) = &self.repository {
+            if let Err(e) = repository.save(&reservation).await {
+                self.release_total(&reservation);
+                return Err(ReservationError::Storage(e.to_string()));
+            }
         }
+        self.reservations
+            .insert(reservation.id, reservation.clone());
 
         Ok(reservation)
     }
 
     /// Commit a reservation with actual usage.
-    pub fn commit(
+    pub async fn commit(
         &self,
         reservation_id: Uuid,
         actual_amount: i64,
     ) -> Result<Reservation, ReservationError> {
-        let mut reservations = self
+        let reservation = self
             .reservations
-            .write()
-            .map_err(|e| ReservationError::LockError(e.to_string()))?;
-
-        let reservation = reservations
-            .get_mut(&reservation_id)
-            .ok_or(ReservationError::NotFound(reservation_id))?;
-
-        if reservation.status != ReservationStatus::Active {
-            return Err(ReservationError::InvalidStatus(reservation.status));
-        }
+            .update(&reservation_id, |reservation| {
+                if reservation.status != ReservationStatus::Active {
+                    return Err(ReservationError::InvalidStatus(reservation.status));
+                }
 
-        if reservation.is_expired() {
-            reservation.status = ReservationStatus::Expired;
-            return Err(ReservationError::Expired(reservation.expires_at));
-        }
+                // Expiring here releases the amount like a sweep would
+                if reservation.is_expired() {
+                    reservation.status = ReservationStatus::Expired;
+                    return Ok(reservation.clone());
+                }
 
-        if actual_amount > reservation.reserved_amount {
-            return Err(ReservationError::ExceedsReserved {
-                actual: actual_amount,
-                reserved: reservation.reserved_amount,
-            });
-        }
+                if actual_amount > reservation.reserved_amount {
+                    return Err(ReservationError::ExceedsReserved {
+                        actual: actual_amount,
+                        reserved: reservation.reserved_amount,
+                    });
+                }
 
-        reservation.actual_amount = Some(actual_amount);
-        reservation.status = ReservationStatus::Committed;
+                reservation.actual_amount = Some(actual_amount);
+                reservation.status = ReservationStatus::Committed;
+                Ok(reservation.clone())
+            })
+            .ok_or(ReservationError::NotFound(reservation_id))??;
 
         // Update reserved total (release the reservation)
-        let total_key = format!(
-            "{}:{}",
-            reservation.organization_id, reservation.metric_code
-        );
-        {
-            let mut totals = self
-                .reserved_totals
-                .write()
-                .map_err(|e| ReservationError::LockError(e.to_string()))?;
-            if let Some(total) = totals.get_mut(&total_key) {
-                *total = (*total - reservation.reserved_amount).max(0);
-            }
-        }
+        self.release_total(&reservation);
+        self.write_back(&reservation).await;
 
-        Ok(reservation.clone())
+        if reservation.status == ReservationStatus::Expired {
+            return Err(ReservationError::Expired(reservation.expires_at));
+        }
+        Ok(reservation)
     }
 
     /// Release a reservation without using quota.
-    pub fn release(&self, reservation_id: Uuid) -> Result<Reservation, ReservationError> {
-        let mut reservations = self
+    pub async fn release(&self, reservation_id: Uuid) -> Result<Reservation, ReservationError> {
+        let reservation = self
             .reservations
-            .write()
-            .map_err(|e| ReservationError::LockError(e.to_string()))?;
-
-        let reservation = reservations
-            .get_mut(&reservation_id)
-            .ok_or(ReservationError::NotFound(reservation_id))?;
-
-        if reservation.status != ReservationStatus::Active {
-            return Err(ReservationError::InvalidStatus(reservation.status));
-        }
+            .update(&reservation_id, |reservation| {
+                if reservation.status != ReservationStatus::Active {
+                    return Err(ReservationError::InvalidStatus(reservation.status));
+                }
 
-        reservation.status = ReservationStatus::Released;
+                reservation.status = ReservationStatus::Released;
+                Ok(reservation.clone())
+            })
+            .ok_or(ReservationError::NotFound(reservation_id))??;
 
         // Update reserved total
-        let total_key = format!(
-            "{}:{}",
-            reservation.organization_id, reservation.metric_code
-        );
-        {
-            let mut totals = self
-                .reserved_totals
-                .write()
-                .map_err(|e| ReservationError::LockError(e.to_string()))?;
-            if let Some(total) = totals.get_mut(&total_key) {
-                *total = (*total - reservation.reserved_amount).max(0);
-            }
-        }
+        self.release_total(&reservation);
+        self.write_back(&reservation).await;
 
-        Ok(reservation.clone())
+        Ok(reservation)
     }
 
     /// Get a reservation by ID.
     pub fn get(&self, reservation_id: Uuid) -> Option<Reservation> {
-        let reservations = self.reservations.read().ok()?;
-        reservations.get(&reservation_id).cloned()
+        self.reservations.get(&reservation_id)
     }
 
     /// Get total reserved for org+metric.
     pub fn get_total_reserved(&self, organization_id: Uuid, metric_code: &str) -> i64 {
         let total_key = format!("{}:{}", organization_id, metric_code);
-        let totals = self.reserved_totals.read().ok();
-        totals
-            .map(|t| *t.get(&total_key).unwrap_or(&0))
+        self.reserved_totals
+            .read(&total_key, |total| total.load(Ordering::Acquire))
             .unwrap_or(0)
     }
 
     /// Expire stale reservations (background task).
-    pub fn expire_stale(&self) -> Vec<Uuid> {
-        let mut expired_ids = Vec::new();
+    ///
+    /// With a repository, stored reservations past their TTL are expired
+    /// too, including ones this process never loaded.
+    pub async fn expire_stale(&self) -> Vec<Uuid> {
         let now = Utc::now();
 
         // Find expired reservations
-        if let Ok(mut reservations) = self.reservations.write() {
-            for (id, reservation) in reservations.iter_mut() {
-                if reservation.status == ReservationStatus::Active && reservation.expires_at < now {
-                    reservation.status = ReservationStatus::Expired;
-                    expired_ids.push(*id);
-                }
+        let mut expired = Vec::new();
+        self.reservations.for_each_mut(|id, reservation| {
+            if reservation.status == ReservationStatus::Active && reservation.expires_at < now {
+                reservation.status = ReservationStatus::Expired;
+                expired.push((*id, reservation.clone()));
             }
-        }
+        });
 
         // Update reserved totals for expired reservations
-        if let Ok(reservations) = self.reservations.read() {
-            if let Ok(mut totals) = self.reserved_totals.write() {
-                for id in &expired_ids {
-                    if let Some(reservation) = reservations.get(id) {
-                        let total_key = format!(
-                            "{}:{}",
-                            reservation.organization_id, reservation.metric_code
-                        );
-                        if let Some(total) = totals.get_mut(&total_key) {
-                            *total = (*total - reservation.reserved_amount).max(0);
+        let mut ids = Vec::with_capacity(expired.len());
+        for (id, reservation) in expired {
+            self.release_total(&reservation);
+            self.write_back(&reservation).await;
+            ids.push(id);
+        }
+
+        if let Some(repository) = &self.repository {
+            match repository.expire_before(now).await {
+                Ok(stored) => {
+                    for id in stored {
+                        if !ids.contains(&id) {
+                            ids.push(id);
                         }
                     }
                 }
+                Err(e) => warn!(error = %e, "Failed to expire stored quota reservations"),
             }
         }
+        ids
+    }
 
-        expired_ids
+    /// Reload the repository's active reservations into memory, expiring
+    /// the ones past their TTL first. Returns how many were reloaded.
+    ///
+    /// Run at startup, before taking reservations. Reservations already in
+    /// memory are left as they are.
+    pub async fn recover(&self) -> Result<usize, ReservationError> {
+        let Some(repository) = &self.repository else {
+            return Ok(0);
+        };
+        let storage = |e: CretoError| ReservationError::Storage(e.to_string());
+        repository
+            .expire_before(Utc::now())
+            .await
+            .map_err(storage)?;
+        let active = repository.list_active().await.map_err(storage)?;
+
+        let mut groups: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+        let mut recovered = 0;
+        for reservation in active {
+            if self.reservations.contains_key(&reservation.id) {
+                continue;
+            }
+            let total_key = format!(
+                "{}:{}",
+                reservation.organization_id, reservation.metric_code
+            );
+            self.reserved_totals
+                .get_or_insert_with(total_key, || Arc::new(AtomicI64::new(0)))
+                .fetch_add(reservation.reserved_amount, Ordering::AcqRel);
+            if let Some(group_id) = reservation.group_id {
+                groups.entry(group_id).or_default().push(reservation.id);
+            }
+            self.reservations.insert(reservation.id, reservation);
+            recovered += 1;
+        }
+        for (group_id, ids) in groups {
+            self.groups.insert(group_id, ids);
+        }
+        Ok(recovered)
     }
 
     /// Get count of active reservations.
     pub fn active_count(&self) -> usize {
+        let mut count = 0;
+        self.reservations.for_each(|_, reservation| {
+            if reservation.status == ReservationStatus::Active {
+                count += 1;
+            }
+        });
+        count
+    }
+
+    /// Return a reservation's amount to its org+metric total. Called once
+    /// per reservation, by whichever transition took it out of `Active`.
+    fn release_total(&self, reservation: &Reservation) {
+        let total_key = format!(
+            "{}:{}",
+            reservation.organization_id, reservation.metric_code
+        );
+        self.reserved_totals.read(&total_key, |total| {
+            let _ = total.fetch_update(Ordering::AcqRel, Ordering::Acquire, |reserved| {
+                Some((reserved - reservation.reserved_amount).max(0))
+            });
+        });
+    }
+
+    /// Store a reservation's new state, logging rather than returning a
+    /// failure.
+    async fn write_back(&self, reservation: &Reservation) {
+        let Some(repository) = &self.repository else {
+            return;
+        };
+        if let Err(e) = repository.save(reservation).await {
+            warn!(
+                reservation_id = %reservation.id,
+                status = ?reservation.status,
+                error = %e,
+                "Failed to store quota reservation"
+            );
+        }
+    }
+}
+
+/// In-memory reservation repository for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryReservationRepository {
+    reservations: RwLock<HashMap<Uuid, Reservation>>,
+}
+
+impl InMemoryReservationRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl ReservationRepository for InMemoryReservationRepository {
+    async fn save(&self, reservation: &Reservation) -> Result<(), CretoError> {
         self.reservations
+            .write()
+            .unwrap()
+            .insert(reservation.id, reservation.clone());
+        Ok(())
+    }
+
+    async fn list_active(&self) -> Result<Vec<Reservation>, CretoError> {
+        let mut active: Vec<_> = self
+            .reservations
             .read()
-            .map(|r| {
-                r.values()
-                    .filter(|r| r.status == ReservationStatus::Active)
-                    .count()
-            })
-            .unwrap_or(0)
+            .unwrap()
+            .values()
+            .filter(|r| r.status == ReservationStatus::Active)
+            .cloned()
+            .collect();
+        active.sort_by_key(|r| (r.created_at, r.id));
+        Ok(active)
+    }
+
+    async fn expire_before(&self, now: DateTime<Utc>) -> Result<Vec<Uuid>, CretoError> {
+        let mut expired = Vec::new();
+        for reservation in self.reservations.write().unwrap().values_mut() {
+            if reservation.status == ReservationStatus::Active && reservation.expires_at < now {
+                reservation.status = ReservationStatus::Expired;
+                expired.push(reservation.id);
+            }
+        }
+        Ok(expired)
     }
 }
 
@@ -400,26 +649,43 @@ impl Default for ReservationStore {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_reserve_quota() {
+    struct UnavailableRepository;
+
+    #[async_trait::async_trait]
+    impl ReservationRepository for UnavailableRepository {
+        async fn save(&self, _reservation: &Reservation) -> Result<(), CretoError> {
+            Err(CretoError::Database("connection refused".into()))
+        }
+
+        async fn list_active(&self) -> Result<Vec<Reservation>, CretoError> {
+            Err(CretoError::Database("connection refused".into()))
+        }
+
+        async fn expire_before(&self, _now: DateTime<Utc>) -> Result<Vec<Uuid>, CretoError> {
+            Err(CretoError::Database("connection refused".into()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reserve_quota() {
         let store = ReservationStore::new();
         let org_id = Uuid::new_v4();
 
         let request = ReserveRequest::new(org_id, "agent1", "api_calls", 100);
-        let reservation = store.reserve(request, 1000).unwrap();
+        let reservation = store.reserve(request, 1000).await.unwrap();
 
         assert_eq!(reservation.status, ReservationStatus::Active);
         assert_eq!(reservation.reserved_amount, 100);
         assert!(reservation.actual_amount.is_none());
     }
 
-    #[test]
-    fn test_insufficient_quota() {
+    #[tokio::test]
+    async fn test_insufficient_quota() {
         let store = ReservationStore::new();
         let org_id = Uuid::new_v4();
 
         let request = ReserveRequest::new(org_id, "agent1", "api_calls", 100);
-        let result = store.reserve(request, 50); // Only 50 available
+        let result = store.reserve(request, 50).await; // Only 50 available
 
         assert!(matches!(
             result,
@@ -427,29 +693,29 @@ mod tests {
         ));
     }
 
-    #[test]
-    fn test_commit_reservation() {
+    #[tokio::test]
+    async fn test_commit_reservation() {
         let store = ReservationStore::new();
         let org_id = Uuid::new_v4();
 
         let request = ReserveRequest::new(org_id, "agent1", "api_calls", 100);
-        let reservation = store.reserve(request, 1000).unwrap();
+        let reservation = store.reserve(request, 1000).await.unwrap();
 
-        let committed = store.commit(reservation.id, 75).unwrap();
+        let committed = store.commit(reservation.id, 75).await.unwrap();
 
         assert_eq!(committed.status, ReservationStatus::Committed);
         assert_eq!(committed.actual_amount, Some(75));
     }
 
-    #[test]
-    fn test_commit_exceeds_reserved() {
+    #[tokio::test]
+    async fn test_commit_exceeds_reserved() {
         let store = ReservationStore::new();
         let org_id = Uuid::new_v4();
 
         let request = ReserveRequest::new(org_id, "agent1", "api_calls", 100);
-        let reservation = store.reserve(request, 1000).unwrap();
+        let reservation = store.reserve(request, 1000).await.unwrap();
 
-        let result = store.commit(reservation.id, 150); // More than reserved
+        let result = store.commit(reservation.id, 150).await; // More than reserved
 
         assert!(matches!(
             result,
@@ -457,31 +723,31 @@ mod tests {
         ));
     }
 
-    #[test]
-    fn test_release_reservation() {
+    #[tokio::test]
+    async fn test_release_reservation() {
         let store = ReservationStore::new();
         let org_id = Uuid::new_v4();
 
         let request = ReserveRequest::new(org_id, "agent1", "api_calls", 100);
-        let reservation = store.reserve(request, 1000).unwrap();
+        let reservation = store.reserve(request, 1000).await.unwrap();
 
-        let released = store.release(reservation.id).unwrap();
+        let released = store.release(reservation.id).await.unwrap();
 
         assert_eq!(released.status, ReservationStatus::Released);
     }
 
-    #[test]
-    fn test_concurrent_reservations() {
+    #[tokio::test]
+    async fn test_concurrent_reservations() {
         let store = ReservationStore::new();
         let org_id = Uuid::new_v4();
 
         // Available: 1000, Reserve 600
         let req1 = ReserveRequest::new(org_id, "agent1", "api_calls", 600);
-        store.reserve(req1, 1000).unwrap();
+        store.reserve(req1, 1000).await.unwrap();
 
         // Now only 400 available, try to reserve 600 more
         let req2 = ReserveRequest::new(org_id, "agent2", "api_calls", 600);
-        let result = store.reserve(req2, 1000);
+        let result = store.reserve(req2, 1000).await;
 
         assert!(matches!(
             result,
@@ -489,16 +755,68 @@ mod tests {
         ));
     }
 
-    #[test]
-    fn test_get_total_reserved() {
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn test_racing_reservations_never_overbook() {
+        let store = Arc::new(ReservationStore::new());
+        let org_id = Uuid::new_v4();
+
+        // 208 attempts of 10 against 1000 available: exactly 100 fit
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let store = Arc::clone(&store);
+                tokio::spawn(async move {
+                    let mut reserved = Vec::new();
+                    for _ in 0..13 {
+                        let request = ReserveRequest::new(org_id, "agent", "api_calls", 10);
+                        if let Ok(reservation) = store.reserve(request, 1000).await {
+                            reserved.push(reservation);
+                        }
+                    }
+                    reserved
+                })
+            })
+            .collect();
+        let mut reserved = Vec::new();
+        for handle in handles {
+            reserved.extend(handle.await.unwrap());
+        }
+        assert_eq!(reserved.len(), 100);
+        assert_eq!(store.get_total_reserved(org_id, "api_calls"), 1000);
+
+        let handles: Vec<_> = reserved
+            .chunks(10)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let store = Arc::clone(&store);
+                let ids: Vec<Uuid> = chunk.iter().map(|r| r.id).collect();
+                tokio::spawn(async move {
+                    for id in ids {
+                        if i % 2 == 0 {
+                            store.commit(id, 5).await.unwrap();
+                        } else {
+                            store.release(id).await.unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(store.get_total_reserved(org_id, "api_calls"), 0);
+        assert_eq!(store.active_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_total_reserved() {
         let store = ReservationStore::new();
         let org_id = Uuid::new_v4();
 
         let req1 = ReserveRequest::new(org_id, "agent1", "api_calls", 100);
         let req2 = ReserveRequest::new(org_id, "agent2", "api_calls", 200);
 
-        store.reserve(req1, 1000).unwrap();
-        store.reserve(req2, 1000).unwrap();
+        store.reserve(req1, 1000).await.unwrap();
+        store.reserve(req2, 1000).await.unwrap();
 
         assert_eq!(store.get_total_reserved(org_id, "api_calls"), 300);
     }
@@ -511,16 +829,16 @@ mod tests {
         assert!(ReservationStatus::Expired.is_terminal());
     }
 
-    #[test]
-    fn test_double_commit_fails() {
+    #[tokio::test]
+    async fn test_double_commit_fails() {
         let store = ReservationStore::new();
         let org_id = Uuid::new_v4();
 
         let request = ReserveRequest::new(org_id, "agent1", "api_calls", 100);
-        let reservation = store.reserve(request, 1000).unwrap();
+        let reservation = store.reserve(request, 1000).await.unwrap();
 
-        store.commit(reservation.id, 50).unwrap();
-        let result = store.commit(reservation.id, 50);
+        store.commit(reservation.id, 50).await.unwrap();
+        let result = store.commit(reservation.id, 50).await;
 
         assert!(matches!(
             result,
@@ -529,4 +847,36 @@ mod tests {
             ))
         ));
     }
+
+    #[tokio::test]
+    async fn test_unstored_reservation_holds_no_quota() {
+        let store = ReservationStore::new().with_repository(Arc::new(UnavailableRepository));
+        let org_id = Uuid::new_v4();
+
+        let request = ReserveRequest::new(org_id, "agent1", "api_calls", 100);
+        let result = store.reserve(request, 1000).await;
+
+        assert!(matches!(result, Err(ReservationError::Storage(_))));
+        assert_eq!(store.get_total_reserved(org_id, "api_calls"), 0);
+        assert_eq!(store.active_count(), 0);
+        assert!(store.recover().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_commit_after_expiry_releases_quota() {
+        let store = ReservationStore::new();
+        let org_id = Uuid::new_v4();
+
+        let request = ReserveRequest::new(org_id, "agent1", "api_calls", 100).with_ttl(0);
+        let reservation = store.reserve(request, 1000).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        let result = store.commit(reservation.id, 50).await;
+        assert!(matches!(result, Err(ReservationError::Expired(_))));
+        assert_eq!(store.get_total_reserved(org_id, "api_calls"), 0);
+        assert_eq!(
+            store.get(reservation.id).unwrap().status,
+            ReservationStatus::Expired
+        );
+    }
 }
@@ -0,0 +1,74 @@
+//! Notices raised when a quota starts a new period.
+//!
+//! The enforcer rolls a quota over on the first check, charge or
+//! reservation after its period ends (or from
+//! [`QuotaEnforcer::roll_over_expired`](super::QuotaEnforcer::roll_over_expired)),
+//! and hands a [`QuotaRollover`] to every registered
+//! [`QuotaRolloverListener`] so the ended period's usage can be archived.
+//! Lifetime quotas never roll over.
+
+use std::sync::{Mutex, PoisonError};
+
+use serde::{Deserialize, Serialize};
+
+use super::types::Quota;
+
+/// A quota's period ended and a new one started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaRollover {
+    /// Enforcer key of the quota (`org:agent:metric`, or `org:*:metric`
+    /// for an organization-wide quota).
+    pub quota_key: String,
+    /// The quota as its period ended, with the period's final usage.
+    pub previous: Quota,
+    /// The quota for the new period.
+    pub current: Quota,
+}
+
+/// Receiver of quota rollovers, e.g. to keep per-period usage history.
+///
+/// With a shared backend, every enforcer that rolls a quota over reports
+/// it, so a listener may see the same period more than once; deduplicate on
+/// `quota_key` and `previous.period_start`.
+pub trait QuotaRolloverListener: Send + Sync {
+    /// Handle a rollover. Called on the check and usage-recording paths;
+    /// must not block.
+    fn on_rollover(&self, rollover: &QuotaRollover);
+}
+
+impl QuotaRolloverListener for tokio::sync::mpsc::UnboundedSender<QuotaRollover> {
+    fn on_rollover(&self, rollover: &QuotaRollover) {
+        if self.send(rollover.clone()).is_err() {
+            tracing::warn!(quota_key = %rollover.quota_key, "Quota rollover receiver dropped");
+        }
+    }
+}
+
+/// Rolled-over quotas waiting to be written to a
+/// [`QuotaRepository`](crate::repository::QuotaRepository).
+#[derive(Debug, Default)]
+pub(crate) struct PendingRollovers {
+    quotas: Mutex<Vec<Quota>>,
+}
+
+impl PendingRollovers {
+    /// Take every queued quota, oldest first.
+    pub(crate) fn take(&self) -> Vec<Quota> {
+        std::mem::take(&mut *self.quotas.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Put quotas that could not be written back in front of the queue.
+    pub(crate) fn requeue(&self, quotas: Vec<Quota>) {
+        let mut queued = self.quotas.lock().unwrap_or_else(PoisonError::into_inner);
+        queued.splice(0..0, quotas);
+    }
+}
+
+impl QuotaRolloverListener for PendingRollovers {
+    fn on_rollover(&self, rollover: &QuotaRollover) {
+        self.quotas
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(rollover.current.clone());
+    }
+}
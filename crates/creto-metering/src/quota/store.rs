@@ -0,0 +1,205 @@
+//! In-memory quota persistence.
+//!
+//! Mirrors [`PgQuotaRepository`](crate::repository::PgQuotaRepository): one
+//! row per quota and period, stamped whenever its counter changes.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoError, OrganizationId};
+use uuid::Uuid;
+
+use super::enforcer::QuotaCounter;
+use super::types::{Quota, QuotaPeriod};
+use crate::repository::QuotaRepository;
+
+/// In-memory quota store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryQuotaRepository {
+    quotas: RwLock<HashMap<Uuid, QuotaCounter>>,
+}
+
+impl InMemoryQuotaRepository {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn find(quotas: &HashMap<Uuid, QuotaCounter>, quota: &Quota) -> Option<Uuid> {
+        quotas
+            .values()
+            .find(|c| {
+                c.quota.organization_id == quota.organization_id
+                    && c.quota.agent_id == quota.agent_id
+                    && c.quota.metric_code == quota.metric_code
+                    && c.quota.period_start == quota.period_start
+            })
+            .map(|c| c.quota.id)
+    }
+}
+
+#[async_trait::async_trait]
+impl QuotaRepository for InMemoryQuotaRepository {
+    async fn get_or_create(
+        &self,
+        org_id: OrganizationId,
+        agent_id: Option<AgentId>,
+        metric_code: &str,
+        period: QuotaPeriod,
+        limit: i64,
+    ) -> Result<Quota, CretoError> {
+        let mut quota = Quota::new(org_id, metric_code, limit, period);
+        quota.agent_id = agent_id;
+
+        let now = Utc::now();
+        let mut quotas = self.quotas.write().unwrap();
+        let id = Self::find(&quotas, &quota).unwrap_or(quota.id);
+        let counter = quotas.entry(id).or_insert(QuotaCounter {
+            quota,
+            updated_at: None,
+        });
+        counter.updated_at = Some(now);
+        Ok(counter.quota.clone())
+    }
+
+    async fn increment_usage(&self, quota_id: Uuid, delta: i64) -> Result<i64, CretoError> {
+        let mut quotas = self.quotas.write().unwrap();
+        let counter = quotas
+            .get_mut(&quota_id)
+            .ok_or_else(|| CretoError::NotFound(format!("Quota {}", quota_id)))?;
+        counter.quota.current_usage += delta;
+        counter.updated_at = Some(Utc::now());
+        Ok(counter.quota.current_usage)
+    }
+
+    async fn get_current(
+        &self,
+        org_id: OrganizationId,
+        agent_id: Option<AgentId>,
+        metric_code: &str,
+    ) -> Result<Option<Quota>, CretoError> {
+        let now = Utc::now();
+        Ok(self
+            .quotas
+            .read()
+            .unwrap()
+            .values()
+            .map(|c| &c.quota)
+            .find(|q| {
+                q.organization_id == org_id
+                    && q.agent_id == agent_id
+                    && q.metric_code == metric_code
+                    && q.period_start <= now
+                    && q.period_end > now
+            })
+            .cloned())
+    }
+
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<Quota>, CretoError> {
+        let mut quotas: Vec<Quota> = self
+            .quotas
+            .read()
+            .unwrap()
+            .values()
+            .filter(|c| c.quota.organization_id == org_id)
+            .map(|c| c.quota.clone())
+            .collect();
+        quotas.sort_by(|a, b| {
+            (&a.metric_code, b.period_start).cmp(&(&b.metric_code, a.period_start))
+        });
+        Ok(quotas)
+    }
+
+    async fn start_next_period(&self, quota: &Quota) -> Result<Quota, CretoError> {
+        let mut quotas = self.quotas.write().unwrap();
+        let id = Self::find(&quotas, quota).unwrap_or(quota.id);
+        let counter = quotas.entry(id).or_insert(QuotaCounter {
+            quota: Quota {
+                id,
+                current_usage: 0,
+                ..quota.clone()
+            },
+            updated_at: None,
+        });
+        counter.quota.carried_debt = quota.carried_debt;
+        counter.quota.max_delegation_depth = quota.max_delegation_depth;
+        counter.quota.delegation_multiplier = quota.delegation_multiplier;
+        counter.quota.burst = quota.burst;
+        counter.updated_at = Some(Utc::now());
+        Ok(Quota {
+            id,
+            current_usage: counter.quota.current_usage,
+            ..quota.clone()
+        })
+    }
+
+    async fn list_active(
+        &self,
+        at: DateTime<Utc>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<QuotaCounter>, CretoError> {
+        let mut active: Vec<QuotaCounter> = self
+            .quotas
+            .read()
+            .unwrap()
+            .values()
+            .filter(|c| c.quota.period_start <= at && c.quota.period_end > at)
+            .cloned()
+            .collect();
+        active.sort_by_key(|c| c.quota.id);
+        Ok(active.into_iter().skip(offset).take(limit).collect())
+    }
+
+    async fn correct_usage(
+        &self,
+        quota: &Quota,
+        usage: i64,
+        unchanged_since: DateTime<Utc>,
+    ) -> Result<bool, CretoError> {
+        let mut quotas = self.quotas.write().unwrap();
+        match quotas.get_mut(&quota.id) {
+            Some(counter)
+                if counter.quota.period_start == quota.period_start
+                    && !counter.updated_at.is_some_and(|at| at >= unchanged_since) =>
+            {
+                counter.quota.current_usage = usage;
+                counter.updated_at = Some(Utc::now());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn migrate_metric_code(
+        &self,
+        old_code: &str,
+        canonical_code: &str,
+    ) -> Result<u64, CretoError> {
+        let now = Utc::now();
+        let mut quotas = self.quotas.write().unwrap();
+        let old: Vec<Uuid> = quotas
+            .values()
+            .filter(|c| c.quota.metric_code == old_code)
+            .map(|c| c.quota.id)
+            .collect();
+
+        for id in &old {
+            let mut counter = quotas.remove(id).unwrap();
+            counter.quota.metric_code = canonical_code.to_string();
+            counter.updated_at = Some(now);
+            match Self::find(&quotas, &counter.quota) {
+                Some(existing) => {
+                    let existing = quotas.get_mut(&existing).unwrap();
+                    existing.quota.current_usage += counter.quota.current_usage;
+                    existing.updated_at = Some(now);
+                }
+                None => {
+                    quotas.insert(*id, counter);
+                }
+            }
+        }
+        Ok(old.len() as u64)
+    }
+}
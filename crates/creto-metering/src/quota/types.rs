@@ -1,7 +1,7 @@
 //! Core quota types and definitions.
 
 use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc};
-use creto_common::{AgentId, OrganizationId};
+use creto_common::{AgentId, CretoError, CretoResult, OrganizationId};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -34,6 +34,19 @@ pub struct Quota {
     /// Optional: Budget in cents for cost-based quotas.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub budget_cents: Option<i64>,
+    /// Optional: Deepest delegation chain allowed to draw from this quota.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_delegation_depth: Option<u8>,
+    /// Optional: Surcharge applied to usage from deep delegation chains.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegation_multiplier: Option<DelegationMultiplier>,
+    /// Optional: Usage allowed over the limit, paid back next period.
+    /// Borrowing is disabled when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<BurstAllowance>,
+    /// Usage borrowed in the previous period, deducted from this period's limit.
+    #[serde(default)]
+    pub carried_debt: i64,
 }
 
 impl Quota {
@@ -59,17 +72,75 @@ impl Quota {
             period_end,
             allow_overage: false,
             budget_cents: None,
+            max_delegation_depth: None,
+            delegation_multiplier: None,
+            burst: None,
+            carried_debt: 0,
+        }
+    }
+
+    /// Deny usage from delegation chains deeper than `max_depth`.
+    pub fn with_max_delegation_depth(mut self, max_depth: u8) -> Self {
+        self.max_delegation_depth = Some(max_depth);
+        self
+    }
+
+    /// Charge `multiplier` times the usage from agents at `from_depth` or deeper.
+    ///
+    /// Fails unless `multiplier` is finite and at least 1.
+    pub fn with_delegation_multiplier(
+        mut self,
+        from_depth: u8,
+        multiplier: f64,
+    ) -> CretoResult<Self> {
+        self.delegation_multiplier = Some(DelegationMultiplier::new(from_depth, multiplier)?);
+        Ok(self)
+    }
+
+    /// Allow borrowing up to `burst` over the limit, paid back next period.
+    pub fn with_burst(mut self, burst: BurstAllowance) -> Self {
+        self.burst = Some(burst);
+        self
+    }
+
+    /// Limit for this period after paying back the previous period's debt.
+    pub fn effective_limit(&self) -> i64 {
+        (self.limit - self.carried_debt).max(0)
+    }
+
+    /// Units that may be borrowed over the effective limit this period.
+    pub fn burst_units(&self) -> i64 {
+        self.burst.map_or(0, |b| b.units(self.limit))
+    }
+
+    /// Usage over the effective limit this period, owed to the next period.
+    pub fn borrowed(&self) -> i64 {
+        if self.burst.is_none() {
+            return 0;
         }
+        (self.current_usage - self.effective_limit()).max(0)
+    }
+
+    /// Check if usage at the given delegation depth may draw from this quota.
+    pub fn allows_delegation_depth(&self, depth: u8) -> bool {
+        !matches!(self.max_delegation_depth, Some(max) if depth > max)
+    }
+
+    /// Amount charged against this quota for usage at the given delegation depth.
+    pub fn charge_for(&self, amount: i64, depth: u8) -> i64 {
+        self.delegation_multiplier
+            .map_or(amount, |m| m.apply(amount, depth))
     }
 
-    /// Check if this quota would be exceeded by adding `amount`.
+    /// Check if this quota would be exceeded by adding `amount`, including
+    /// any burst allowance.
     pub fn would_exceed(&self, amount: i64) -> bool {
-        self.current_usage + amount > self.limit
+        self.current_usage + amount > self.effective_limit() + self.burst_units()
     }
 
-    /// Get remaining quota.
+    /// Get remaining quota, excluding any burst allowance.
     pub fn remaining(&self) -> i64 {
-        (self.limit - self.current_usage).max(0)
+        (self.effective_limit() - self.current_usage).max(0)
     }
 
     /// Get usage as a percentage (0.0 to 1.0+).
@@ -82,20 +153,113 @@ impl Quota {
 
     /// Check if the current period has expired.
     pub fn is_expired(&self) -> bool {
-        Utc::now() > self.period_end
+        self.period_ended(Utc::now())
+    }
+
+    /// Check if the current period has ended by `now`.
+    ///
+    /// Periods end exactly at `period_end`. Lifetime quotas never end.
+    pub fn period_ended(&self, now: DateTime<Utc>) -> bool {
+        self.period != QuotaPeriod::Lifetime && now >= self.period_end
     }
 
     /// Reset the quota for a new period.
     pub fn reset(&mut self) {
-        let now = Utc::now();
+        self.roll_over(Utc::now());
+    }
+
+    /// Start the period containing `now`, carrying borrowed usage as debt.
+    ///
+    /// Debt is not compounded: a period whose debt exceeds its limit has
+    /// nothing available, and only what it borrows itself is carried on.
+    pub fn roll_over(&mut self, now: DateTime<Utc>) {
         let (period_start, period_end) = self.period.calculate_bounds(now);
 
+        self.carried_debt = self.borrowed();
         self.current_usage = 0;
         self.period_start = period_start;
         self.period_end = period_end;
     }
 }
 
+/// How far usage may exceed a quota's limit before it is denied.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum BurstAllowance {
+    /// A fixed number of units over the limit.
+    Units(i64),
+    /// A fraction of the limit (0.1 = 10% over).
+    Percentage(f64),
+}
+
+impl BurstAllowance {
+    /// Burst size in units for a quota with the given limit, rounding down.
+    pub fn units(&self, limit: i64) -> i64 {
+        match self {
+            Self::Units(units) => (*units).max(0),
+            Self::Percentage(fraction) => ((limit as f64 * fraction).floor() as i64).max(0),
+        }
+    }
+}
+
+/// Charging multiplier for usage from deep delegation chains.
+///
+/// Deserializing fails for a multiplier [`validate`](Self::validate) rejects.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawDelegationMultiplier")]
+pub struct DelegationMultiplier {
+    /// First delegation depth the multiplier applies to.
+    pub from_depth: u8,
+    /// Factor applied to the usage amount (e.g. 1.5).
+    pub multiplier: f64,
+}
+
+#[derive(Deserialize)]
+struct RawDelegationMultiplier {
+    from_depth: u8,
+    multiplier: f64,
+}
+
+impl TryFrom<RawDelegationMultiplier> for DelegationMultiplier {
+    type Error = CretoError;
+
+    fn try_from(raw: RawDelegationMultiplier) -> CretoResult<Self> {
+        Self::new(raw.from_depth, raw.multiplier)
+    }
+}
+
+impl DelegationMultiplier {
+    /// Create a multiplier, checking it with [`validate`](Self::validate).
+    pub fn new(from_depth: u8, multiplier: f64) -> CretoResult<Self> {
+        let multiplier = Self {
+            from_depth,
+            multiplier,
+        };
+        multiplier.validate()?;
+        Ok(multiplier)
+    }
+
+    /// Check the multiplier is finite and at least 1, so deep chains are
+    /// never charged less than the usage they report.
+    pub fn validate(&self) -> CretoResult<()> {
+        if !self.multiplier.is_finite() || self.multiplier < 1.0 {
+            return Err(CretoError::ValidationFailed(format!(
+                "delegation multiplier must be finite and at least 1, got {}",
+                self.multiplier
+            )));
+        }
+        Ok(())
+    }
+
+    /// Scale `amount` if `depth` is at or beyond `from_depth`, rounding up.
+    pub fn apply(&self, amount: i64, depth: u8) -> i64 {
+        if depth < self.from_depth {
+            return amount;
+        }
+        (amount as f64 * self.multiplier).ceil() as i64
+    }
+}
+
 /// Time period for quota reset.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -253,6 +417,100 @@ mod tests {
         assert_eq!(quota.usage_percentage(), 1.0);
     }
 
+    #[test]
+    fn test_burst_allowance_units() {
+        assert_eq!(BurstAllowance::Units(50).units(1000), 50);
+        assert_eq!(BurstAllowance::Percentage(0.1).units(1000), 100);
+        assert_eq!(BurstAllowance::Percentage(0.1).units(15), 1);
+        assert_eq!(BurstAllowance::Units(-5).units(1000), 0);
+    }
+
+    #[test]
+    fn test_roll_over_carries_borrowed_usage() {
+        let mut quota = Quota::new(OrganizationId::new(), "tokens", 1000, QuotaPeriod::Daily)
+            .with_burst(BurstAllowance::Units(200));
+        quota.current_usage = 1150;
+        assert!(!quota.would_exceed(50));
+        assert!(quota.would_exceed(51));
+        assert_eq!(quota.borrowed(), 150);
+
+        quota.roll_over(quota.period_end);
+        assert_eq!(quota.current_usage, 0);
+        assert_eq!(quota.carried_debt, 150);
+        assert_eq!(quota.effective_limit(), 850);
+        assert_eq!(quota.remaining(), 850);
+
+        // Repaid in full once the next period stays within its reduced limit
+        quota.current_usage = 850;
+        quota.roll_over(quota.period_end);
+        assert_eq!(quota.carried_debt, 0);
+        assert_eq!(quota.effective_limit(), 1000);
+    }
+
+    #[test]
+    fn test_roll_over_without_burst_forgives_overage() {
+        let mut quota = Quota::new(OrganizationId::new(), "tokens", 1000, QuotaPeriod::Daily);
+        quota.current_usage = 1200;
+        assert_eq!(quota.borrowed(), 0);
+
+        quota.roll_over(quota.period_end);
+        assert_eq!(quota.carried_debt, 0);
+        assert_eq!(quota.effective_limit(), 1000);
+    }
+
+    #[test]
+    fn test_period_ends_exactly_at_period_end() {
+        let quota = Quota::new(OrganizationId::new(), "tokens", 1000, QuotaPeriod::Hourly);
+        assert!(!quota.period_ended(quota.period_end - Duration::microseconds(1)));
+        assert!(quota.period_ended(quota.period_end));
+
+        let lifetime = Quota::new(OrganizationId::new(), "tokens", 1000, QuotaPeriod::Lifetime);
+        assert!(!lifetime.period_ended(lifetime.period_end + Duration::days(1)));
+    }
+
+    #[test]
+    fn test_delegation_multiplier_arithmetic() {
+        let quota = Quota::new(OrganizationId::new(), "tokens", 1000, QuotaPeriod::Daily)
+            .with_delegation_multiplier(2, 1.5)
+            .unwrap();
+
+        assert_eq!(quota.charge_for(100, 0), 100);
+        assert_eq!(quota.charge_for(100, 1), 100);
+        assert_eq!(quota.charge_for(100, 2), 150);
+        assert_eq!(quota.charge_for(100, 7), 150);
+        // Fractional charges round up
+        assert_eq!(quota.charge_for(3, 2), 5);
+    }
+
+    #[test]
+    fn test_delegation_multiplier_rejects_discounts_and_non_finite() {
+        for multiplier in [0.5, 0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                DelegationMultiplier::new(1, multiplier),
+                Err(CretoError::ValidationFailed(_))
+            ));
+        }
+        assert!(DelegationMultiplier::new(1, 1.0).is_ok());
+
+        // Stored values are checked when read back
+        let parsed: Result<DelegationMultiplier, _> =
+            serde_json::from_str(r#"{"from_depth": 1, "multiplier": 0.5}"#);
+        assert!(parsed.is_err());
+        let parsed: DelegationMultiplier =
+            serde_json::from_str(r#"{"from_depth": 2, "multiplier": 1.5}"#).unwrap();
+        assert_eq!(parsed, DelegationMultiplier::new(2, 1.5).unwrap());
+    }
+
+    #[test]
+    fn test_delegation_depth_cap() {
+        let quota = Quota::new(OrganizationId::new(), "tokens", 1000, QuotaPeriod::Daily);
+        assert!(quota.allows_delegation_depth(u8::MAX));
+
+        let quota = quota.with_max_delegation_depth(2);
+        assert!(quota.allows_delegation_depth(2));
+        assert!(!quota.allows_delegation_depth(3));
+    }
+
     #[test]
     fn test_period_bounds_daily() {
         let timestamp = Utc::now();
@@ -0,0 +1,69 @@
+//! Notices raised when a quota's usage reaches its warning threshold.
+//!
+//! The enforcer raises one [`QuotaWarning`] per quota per period, from the
+//! usage-recording path, and hands it to every registered
+//! [`QuotaWarningSink`]. Checks never raise warnings.
+
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, OrganizationId};
+use serde::{Deserialize, Serialize};
+
+use super::types::QuotaPeriod;
+
+/// A quota's usage reached the enforcer's warning threshold.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaWarning {
+    /// Enforcer key of the quota (`org:agent:metric`, or `org:*:metric`
+    /// for an organization-wide quota).
+    pub quota_key: String,
+    /// Organization owning the quota.
+    pub organization_id: OrganizationId,
+    /// Agent the quota applies to (`None` for organization-wide quotas).
+    pub agent_id: Option<AgentId>,
+    /// Metric the quota limits.
+    pub metric_code: String,
+    /// Usage when the threshold was reached.
+    pub current_usage: i64,
+    /// Effective limit for the period.
+    pub limit: i64,
+    /// Threshold that was reached (0.0-1.0 of the limit).
+    pub threshold: f64,
+    /// Quota period.
+    pub period: QuotaPeriod,
+    /// Start of the period the warning is for.
+    pub period_start: DateTime<Utc>,
+    /// When the quota resets.
+    pub resets_at: DateTime<Utc>,
+}
+
+impl QuotaWarning {
+    /// Usage as a fraction of the limit.
+    pub fn usage_percentage(&self) -> f64 {
+        if self.limit > 0 {
+            self.current_usage as f64 / self.limit as f64
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Destination for quota warnings.
+pub trait QuotaWarningSink: Send + Sync {
+    /// Deliver a warning. Called on the usage-recording path; must not block.
+    fn emit(&self, warning: &QuotaWarning);
+}
+
+impl QuotaWarningSink for tokio::sync::mpsc::UnboundedSender<QuotaWarning> {
+    fn emit(&self, warning: &QuotaWarning) {
+        if self.send(warning.clone()).is_err() {
+            tracing::warn!(quota_key = %warning.quota_key, "Quota warning receiver dropped");
+        }
+    }
+}
+
+impl QuotaWarningSink for tokio::sync::broadcast::Sender<QuotaWarning> {
+    fn emit(&self, warning: &QuotaWarning) {
+        // No subscribers is not an error; warnings are advisory
+        let _ = self.send(warning.clone());
+    }
+}
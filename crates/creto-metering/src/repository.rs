@@ -6,12 +6,30 @@
 //! Uses runtime SQL queries to avoid requiring DATABASE_URL at compile time.
 
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, CretoError, OrganizationId};
-use sqlx::{PgPool, Row};
+use creto_common::{
+    types::{Currency, Money},
+    AgentId, CretoError, OrganizationId,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, PgConnection, PgPool, Row};
 use uuid::Uuid;
 
+use crate::adjustments::{AdjustmentStatus, AdjustmentSummary, InvoiceAdjustment};
+use crate::aggregation::UsageSelection;
+use crate::aliases::MetricAlias;
+use crate::anomaly::{AnomalyBaseline, AnomalySeverity};
+use crate::api_keys::ApiKey;
+use crate::auto_top_up::{AutoTopUpRule, TopUpAttempt, TopUpPeriod, TopUpStatus};
+use crate::currency::{ExchangeRate, OrgBillingProfile};
+use crate::drilldown::{AgentUsage, EventCursor};
 use crate::events::{UsageEvent, UsageEventType};
-use crate::quota::{Quota, QuotaPeriod};
+use crate::invoice::{AppliedPricing, Discount, Invoice, InvoiceStatus, LineItem};
+use crate::late_events::{LateEvent, LateEventStatus};
+use crate::pricing::{PricingCatalog, PricingModel};
+use crate::quota::{
+    ExemptionAllowance, Quota, QuotaCounter, QuotaExemption, QuotaPeriod, Reservation,
+    ReservationStatus,
+};
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Enum Serialization Helpers
@@ -68,17 +86,174 @@ impl UsageEventType {
     }
 }
 
+impl LateEventStatus {
+    /// Convert to database string representation.
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            LateEventStatus::Pending => "pending",
+            LateEventStatus::Folded => "folded",
+            LateEventStatus::Rejected => "rejected",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn from_db_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(LateEventStatus::Pending),
+            "folded" => Some(LateEventStatus::Folded),
+            "rejected" => Some(LateEventStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+impl InvoiceStatus {
+    /// Convert to database string representation.
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            InvoiceStatus::Draft => "draft",
+            InvoiceStatus::Issued => "issued",
+            InvoiceStatus::Paid => "paid",
+            InvoiceStatus::Failed => "failed",
+            InvoiceStatus::Voided => "voided",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn from_db_str(s: &str) -> Option<Self> {
+        match s {
+            "draft" => Some(InvoiceStatus::Draft),
+            "issued" => Some(InvoiceStatus::Issued),
+            "paid" => Some(InvoiceStatus::Paid),
+            "failed" => Some(InvoiceStatus::Failed),
+            "voided" => Some(InvoiceStatus::Voided),
+            _ => None,
+        }
+    }
+}
+
+impl AdjustmentStatus {
+    /// Convert to database string representation.
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            AdjustmentStatus::Proposed => "proposed",
+            AdjustmentStatus::Approved => "approved",
+            AdjustmentStatus::Applied => "applied",
+            AdjustmentStatus::Rejected => "rejected",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn from_db_str(s: &str) -> Option<Self> {
+        match s {
+            "proposed" => Some(AdjustmentStatus::Proposed),
+            "approved" => Some(AdjustmentStatus::Approved),
+            "applied" => Some(AdjustmentStatus::Applied),
+            "rejected" => Some(AdjustmentStatus::Rejected),
+            _ => None,
+        }
+    }
+}
+
+impl AnomalySeverity {
+    /// Convert to database string representation.
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            AnomalySeverity::Elevated => "elevated",
+            AnomalySeverity::High => "high",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn from_db_str(s: &str) -> Option<Self> {
+        match s {
+            "elevated" => Some(AnomalySeverity::Elevated),
+            "high" => Some(AnomalySeverity::High),
+            _ => None,
+        }
+    }
+}
+
+impl TopUpPeriod {
+    /// Convert to database string representation.
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            TopUpPeriod::Daily => "daily",
+            TopUpPeriod::Monthly => "monthly",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn from_db_str(s: &str) -> Option<Self> {
+        match s {
+            "daily" => Some(TopUpPeriod::Daily),
+            "monthly" => Some(TopUpPeriod::Monthly),
+            _ => None,
+        }
+    }
+}
+
+impl ReservationStatus {
+    /// Convert to database string representation.
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            ReservationStatus::Active => "active",
+            ReservationStatus::Committed => "committed",
+            ReservationStatus::Released => "released",
+            ReservationStatus::Expired => "expired",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn from_db_str(s: &str) -> Option<Self> {
+        match s {
+            "active" => Some(ReservationStatus::Active),
+            "committed" => Some(ReservationStatus::Committed),
+            "released" => Some(ReservationStatus::Released),
+            "expired" => Some(ReservationStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+impl TopUpStatus {
+    /// Convert to database string representation.
+    pub fn as_db_str(&self) -> &'static str {
+        match self {
+            TopUpStatus::Pending => "pending",
+            TopUpStatus::Charged => "charged",
+            TopUpStatus::Succeeded => "succeeded",
+            TopUpStatus::Failed => "failed",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn from_db_str(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(TopUpStatus::Pending),
+            "charged" => Some(TopUpStatus::Charged),
+            "succeeded" => Some(TopUpStatus::Succeeded),
+            "failed" => Some(TopUpStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Event Repository
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Repository for usage event persistence.
-#[trait_variant::make(EventRepository: Send)]
-pub trait LocalEventRepository {
-    /// Insert a usage event.
-    async fn insert_event(&self, event: &UsageEvent) -> Result<(), CretoError>;
-
-    /// Insert multiple events in a batch.
+///
+/// Object-safe, so the metering service can hold one without a type
+/// parameter. Time ranges include both ends, as [`UsageSelection`] does.
+#[async_trait::async_trait]
+pub trait EventRepository: Send + Sync {
+    /// Insert a usage event. Returns false if an event with its transaction
+    /// ID was already stored.
+    async fn insert_event(&self, event: &UsageEvent) -> Result<bool, CretoError>;
+
+    /// Insert multiple events in a batch, returning how many were new.
     async fn insert_events_batch(&self, events: &[UsageEvent]) -> Result<usize, CretoError>;
 
     /// Find events by organization within a time range.
@@ -90,7 +265,17 @@ pub trait LocalEventRepository {
         limit: i64,
     ) -> Result<Vec<UsageEvent>, CretoError>;
 
+    /// Find all events sharing a correlation ID, oldest first.
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<UsageEvent>, CretoError>;
+
     /// Count events by code within a time range.
+    ///
+    /// A sampled event counts for its sample factor. Only events stored
+    /// under exactly `code` are counted; aliases are followed by the
+    /// selection queries.
     async fn count_by_code(
         &self,
         org_id: OrganizationId,
@@ -100,6 +285,8 @@ pub trait LocalEventRepository {
     ) -> Result<i64, CretoError>;
 
     /// Sum quantities by code within a time range.
+    ///
+    /// Only events stored under exactly `code` are summed.
     async fn sum_by_code(
         &self,
         org_id: OrganizationId,
@@ -107,6 +294,21 @@ pub trait LocalEventRepository {
         start: DateTime<Utc>,
         end: DateTime<Utc>,
     ) -> Result<i64, CretoError>;
+
+    /// Page through the events a selection matches, ordered by timestamp
+    /// then transaction ID, starting after `after`.
+    async fn find_by_selection(
+        &self,
+        selection: &UsageSelection,
+        after: Option<&EventCursor>,
+        limit: i64,
+    ) -> Result<Vec<UsageEvent>, CretoError>;
+
+    /// Count and sum the events a selection matches, per agent.
+    async fn usage_by_agent(
+        &self,
+        selection: &UsageSelection,
+    ) -> Result<Vec<AgentUsage>, CretoError>;
 }
 
 /// PostgreSQL implementation of EventRepository.
@@ -121,14 +323,16 @@ impl PgEventRepository {
     }
 }
 
+#[async_trait::async_trait]
 impl EventRepository for PgEventRepository {
-    async fn insert_event(&self, event: &UsageEvent) -> Result<(), CretoError> {
-        sqlx::query(
+    async fn insert_event(&self, event: &UsageEvent) -> Result<bool, CretoError> {
+        let result = sqlx::query(
             r#"
             INSERT INTO usage_events (
                 transaction_id, organization_id, agent_id, external_subscription_id,
-                event_type, code, quantity, timestamp, properties, delegation_depth
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                event_type, code, quantity, timestamp, properties, delegation_depth,
+                correlation_id, caused_by
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             ON CONFLICT (transaction_id) DO NOTHING
             "#,
         )
@@ -142,11 +346,13 @@ impl EventRepository for PgEventRepository {
         .bind(event.timestamp)
         .bind(&event.properties)
         .bind(event.delegation_depth as i16)
+        .bind(event.correlation_id)
+        .bind(event.caused_by)
         .execute(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(())
+        Ok(result.rows_affected() > 0)
     }
 
     async fn insert_events_batch(&self, events: &[UsageEvent]) -> Result<usize, CretoError> {
@@ -162,8 +368,9 @@ impl EventRepository for PgEventRepository {
                 r#"
                 INSERT INTO usage_events (
                     transaction_id, organization_id, agent_id, external_subscription_id,
-                    event_type, code, quantity, timestamp, properties, delegation_depth
-                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                    event_type, code, quantity, timestamp, properties, delegation_depth,
+                    correlation_id, caused_by
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
                 ON CONFLICT (transaction_id) DO NOTHING
                 "#,
             )
@@ -177,6 +384,8 @@ impl EventRepository for PgEventRepository {
             .bind(event.timestamp)
             .bind(&event.properties)
             .bind(event.delegation_depth as i16)
+            .bind(event.correlation_id)
+            .bind(event.caused_by)
             .execute(&mut *tx)
             .await
             .map_err(|e| CretoError::Database(e.to_string()))?;
@@ -202,9 +411,10 @@ impl EventRepository for PgEventRepository {
         let rows = sqlx::query(
             r#"
             SELECT transaction_id, organization_id, agent_id, external_subscription_id,
-                   event_type, code, quantity, timestamp, properties, delegation_depth
+                   event_type, code, quantity, timestamp, properties, delegation_depth,
+                   correlation_id, caused_by
             FROM usage_events
-            WHERE organization_id = $1 AND timestamp >= $2 AND timestamp < $3
+            WHERE organization_id = $1 AND timestamp >= $2 AND timestamp <= $3
             ORDER BY timestamp DESC
             LIMIT $4
             "#,
@@ -217,28 +427,29 @@ impl EventRepository for PgEventRepository {
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        let mut events = Vec::with_capacity(rows.len());
-        for row in rows {
-            let event_type_str: String = row.get("event_type");
-            let event_type = UsageEventType::from_db_str(&event_type_str).ok_or_else(|| {
-                CretoError::Database(format!("Unknown event type: {}", event_type_str))
-            })?;
-
-            events.push(UsageEvent {
-                transaction_id: row.get("transaction_id"),
-                organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
-                agent_id: AgentId::from_uuid(row.get::<Uuid, _>("agent_id")),
-                external_subscription_id: row.get("external_subscription_id"),
-                event_type,
-                code: row.get("code"),
-                quantity: row.get("quantity"),
-                timestamp: row.get("timestamp"),
-                properties: row.get("properties"),
-                delegation_depth: row.get::<i16, _>("delegation_depth") as u8,
-            });
-        }
+        rows.iter().map(usage_event_from_row).collect()
+    }
+
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<UsageEvent>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT transaction_id, organization_id, agent_id, external_subscription_id,
+                   event_type, code, quantity, timestamp, properties, delegation_depth,
+                   correlation_id, caused_by
+            FROM usage_events
+            WHERE correlation_id = $1
+            ORDER BY timestamp ASC
+            "#,
+        )
+        .bind(correlation_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(events)
+        rows.iter().map(usage_event_from_row).collect()
     }
 
     async fn count_by_code(
@@ -250,9 +461,10 @@ impl EventRepository for PgEventRepository {
     ) -> Result<i64, CretoError> {
         let row = sqlx::query(
             r#"
-            SELECT COUNT(*) as count
+            SELECT COALESCE(SUM(COALESCE((properties->>'sample_factor')::BIGINT, 1)), 0)::BIGINT
+                   AS count
             FROM usage_events
-            WHERE organization_id = $1 AND code = $2 AND timestamp >= $3 AND timestamp < $4
+            WHERE organization_id = $1 AND code = $2 AND timestamp >= $3 AND timestamp <= $4
             "#,
         )
         .bind(org_id.as_uuid())
@@ -277,7 +489,7 @@ impl EventRepository for PgEventRepository {
             r#"
             SELECT COALESCE(SUM(quantity), 0) as total
             FROM usage_events
-            WHERE organization_id = $1 AND code = $2 AND timestamp >= $3 AND timestamp < $4
+            WHERE organization_id = $1 AND code = $2 AND timestamp >= $3 AND timestamp <= $4
             "#,
         )
         .bind(org_id.as_uuid())
@@ -290,6 +502,98 @@ impl EventRepository for PgEventRepository {
 
         Ok(row.get("total"))
     }
+
+    async fn find_by_selection(
+        &self,
+        selection: &UsageSelection,
+        after: Option<&EventCursor>,
+        limit: i64,
+    ) -> Result<Vec<UsageEvent>, CretoError> {
+        let aliases = AliasBounds::of(selection);
+        let rows = sqlx::query(
+            r#"
+            SELECT transaction_id, organization_id, agent_id, external_subscription_id,
+                   event_type, code, quantity, timestamp, properties, delegation_depth,
+                   correlation_id, caused_by
+            FROM usage_events
+            WHERE organization_id = $1 AND timestamp >= $3
+              AND (timestamp < $4 OR (NOT $11 AND timestamp = $4))
+              AND (code = $2 OR EXISTS (
+                  SELECT 1
+                  FROM UNNEST($8::TEXT[], $9::TIMESTAMPTZ[], $10::TIMESTAMPTZ[])
+                      AS alias(old_code, effective_from, effective_until)
+                  WHERE alias.old_code = usage_events.code
+                    AND (alias.effective_from IS NULL OR timestamp >= alias.effective_from)
+                    AND (alias.effective_until IS NULL OR timestamp < alias.effective_until)
+              ))
+              AND ($5::TIMESTAMPTZ IS NULL OR (timestamp, transaction_id) > ($5, $6))
+            ORDER BY timestamp ASC, transaction_id ASC
+            LIMIT $7
+            "#,
+        )
+        .bind(selection.organization_id.as_uuid())
+        .bind(&selection.metric_code)
+        .bind(selection.window_start)
+        .bind(selection.window_end)
+        .bind(after.map(|c| c.timestamp))
+        .bind(after.map(|c| c.transaction_id.as_str()))
+        .bind(limit)
+        .bind(&aliases.old_codes)
+        .bind(&aliases.effective_from)
+        .bind(&aliases.effective_until)
+        .bind(selection.end_exclusive)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(usage_event_from_row).collect()
+    }
+
+    async fn usage_by_agent(
+        &self,
+        selection: &UsageSelection,
+    ) -> Result<Vec<AgentUsage>, CretoError> {
+        let aliases = AliasBounds::of(selection);
+        let rows = sqlx::query(
+            r#"
+            SELECT agent_id, COUNT(*) AS event_count,
+                   COALESCE(SUM(quantity), 0)::BIGINT AS quantity
+            FROM usage_events
+            WHERE organization_id = $1 AND timestamp >= $3
+              AND (timestamp < $4 OR (NOT $8 AND timestamp = $4))
+              AND (code = $2 OR EXISTS (
+                  SELECT 1
+                  FROM UNNEST($5::TEXT[], $6::TIMESTAMPTZ[], $7::TIMESTAMPTZ[])
+                      AS alias(old_code, effective_from, effective_until)
+                  WHERE alias.old_code = usage_events.code
+                    AND (alias.effective_from IS NULL OR timestamp >= alias.effective_from)
+                    AND (alias.effective_until IS NULL OR timestamp < alias.effective_until)
+              ))
+            GROUP BY agent_id
+            ORDER BY quantity DESC
+            "#,
+        )
+        .bind(selection.organization_id.as_uuid())
+        .bind(&selection.metric_code)
+        .bind(selection.window_start)
+        .bind(selection.window_end)
+        .bind(&aliases.old_codes)
+        .bind(&aliases.effective_from)
+        .bind(&aliases.effective_until)
+        .bind(selection.end_exclusive)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|r| AgentUsage {
+                agent_id: AgentId::from_uuid(r.get::<Uuid, _>("agent_id")),
+                event_count: r.get::<i64, _>("event_count") as u64,
+                quantity: r.get("quantity"),
+            })
+            .collect())
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -297,8 +601,11 @@ impl EventRepository for PgEventRepository {
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Repository for quota persistence.
-#[trait_variant::make(QuotaRepository: Send)]
-pub trait LocalQuotaRepository {
+///
+/// Object-safe, so the metering service can take one without a type
+/// parameter.
+#[async_trait::async_trait]
+pub trait QuotaRepository: Send + Sync {
     /// Get or create a quota for the given parameters.
     async fn get_or_create(
         &self,
@@ -322,6 +629,42 @@ pub trait LocalQuotaRepository {
 
     /// List all quotas for an organization.
     async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<Quota>, CretoError>;
+
+    /// Persist a quota rolled over into its next period, including the debt
+    /// carried from the period before.
+    async fn start_next_period(&self, quota: &Quota) -> Result<Quota, CretoError>;
+
+    /// Page through the quotas whose period contains `at`, in a stable
+    /// order, with when each counter last changed.
+    async fn list_active(
+        &self,
+        at: DateTime<Utc>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<QuotaCounter>, CretoError>;
+
+    /// Overwrite a quota's usage counter, unless it changed at or after
+    /// `unchanged_since` or the stored quota is for another period.
+    ///
+    /// Returns whether the counter was corrected.
+    async fn correct_usage(
+        &self,
+        quota: &Quota,
+        usage: i64,
+        unchanged_since: DateTime<Utc>,
+    ) -> Result<bool, CretoError>;
+
+    /// Move quotas stored under `old_code` to `canonical_code`.
+    ///
+    /// A quota whose canonical counterpart already exists for the same
+    /// organization, agent and period is merged into it: its usage is added
+    /// to the counterpart's and it is deleted. Returns the quotas moved or
+    /// merged.
+    async fn migrate_metric_code(
+        &self,
+        old_code: &str,
+        canonical_code: &str,
+    ) -> Result<u64, CretoError>;
 }
 
 /// PostgreSQL implementation of QuotaRepository.
@@ -335,6 +678,7 @@ impl PgQuotaRepository {
     }
 }
 
+#[async_trait::async_trait]
 impl QuotaRepository for PgQuotaRepository {
     async fn get_or_create(
         &self,
@@ -352,7 +696,9 @@ impl QuotaRepository for PgQuotaRepository {
             VALUES ($1, $2, $3, $4, $5, $6, $7)
             ON CONFLICT (organization_id, agent_id, resource, period_start)
             DO UPDATE SET updated_at = NOW()
-            RETURNING id, current_usage
+            RETURNING id, organization_id, agent_id, resource, limit_value, current_usage,
+                      carried_debt, period, period_start, period_end,
+                      max_delegation_depth, delegation_multiplier, burst
             "#,
         )
         .bind(org_id.as_uuid())
@@ -366,19 +712,7 @@ impl QuotaRepository for PgQuotaRepository {
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(Quota {
-            id: row.get("id"),
-            organization_id: org_id,
-            agent_id,
-            metric_code: metric_code.to_string(),
-            limit,
-            period,
-            current_usage: row.get("current_usage"),
-            period_start,
-            period_end,
-            allow_overage: false,
-            budget_cents: None,
-        })
+        quota_from_row(&row)
     }
 
     async fn increment_usage(&self, quota_id: Uuid, delta: i64) -> Result<i64, CretoError> {
@@ -409,7 +743,9 @@ impl QuotaRepository for PgQuotaRepository {
 
         let row = sqlx::query(
             r#"
-            SELECT id, limit_value, current_usage, period, period_start, period_end
+            SELECT id, organization_id, agent_id, resource, limit_value, current_usage,
+                   carried_debt, period, period_start, period_end,
+                   max_delegation_depth, delegation_multiplier, burst
             FROM quotas
             WHERE organization_id = $1
               AND (agent_id = $2 OR (agent_id IS NULL AND $2 IS NULL))
@@ -426,28 +762,15 @@ impl QuotaRepository for PgQuotaRepository {
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(row.map(|r| {
-            let period_str: String = r.get("period");
-            Quota {
-                id: r.get("id"),
-                organization_id: org_id,
-                agent_id,
-                metric_code: metric_code.to_string(),
-                limit: r.get("limit_value"),
-                current_usage: r.get("current_usage"),
-                period: parse_period(&period_str),
-                period_start: r.get("period_start"),
-                period_end: r.get("period_end"),
-                allow_overage: false,
-                budget_cents: None,
-            }
-        }))
+        row.as_ref().map(quota_from_row).transpose()
     }
 
     async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<Quota>, CretoError> {
         let rows = sqlx::query(
             r#"
-            SELECT id, agent_id, resource, limit_value, current_usage, period, period_start, period_end
+            SELECT id, organization_id, agent_id, resource, limit_value, current_usage,
+                   carried_debt, period, period_start, period_end,
+                   max_delegation_depth, delegation_multiplier, burst
             FROM quotas
             WHERE organization_id = $1
             ORDER BY resource, period_start DESC
@@ -458,160 +781,2051 @@ impl QuotaRepository for PgQuotaRepository {
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(rows
-            .into_iter()
-            .map(|r| {
-                let period_str: String = r.get("period");
-                Quota {
-                    id: r.get("id"),
-                    organization_id: org_id,
-                    agent_id: r.get::<Option<Uuid>, _>("agent_id").map(AgentId::from_uuid),
-                    metric_code: r.get("resource"),
-                    limit: r.get("limit_value"),
-                    current_usage: r.get("current_usage"),
-                    period: parse_period(&period_str),
-                    period_start: r.get("period_start"),
-                    period_end: r.get("period_end"),
-                    allow_overage: false,
-                    budget_cents: None,
-                }
-            })
-            .collect())
+        rows.iter().map(quota_from_row).collect()
     }
-}
-
-// ─────────────────────────────────────────────────────────────────────────────
-// Invoice Repository (Simplified)
-// ─────────────────────────────────────────────────────────────────────────────
-
-/// Simplified invoice repository - invoices are complex and will be fleshed out later.
-#[trait_variant::make(InvoiceRepository: Send)]
-pub trait LocalInvoiceRepository {
-    /// Create a new invoice record.
-    async fn create_invoice_record(
-        &self,
-        org_id: OrganizationId,
-        invoice_number: &str,
-        period_start: DateTime<Utc>,
-        period_end: DateTime<Utc>,
-        total_cents: i64,
-    ) -> Result<Uuid, CretoError>;
 
-    /// Get invoice by ID.
-    async fn get_invoice(&self, id: Uuid) -> Result<Option<InvoiceRecord>, CretoError>;
-
-    /// List invoices by organization.
-    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<InvoiceRecord>, CretoError>;
-}
-
-/// Simplified invoice record for database storage.
-#[derive(Debug, Clone)]
-pub struct InvoiceRecord {
-    pub id: Uuid,
-    pub organization_id: OrganizationId,
-    pub invoice_number: String,
-    pub status: String,
-    pub total_cents: i64,
-    pub period_start: DateTime<Utc>,
-    pub period_end: DateTime<Utc>,
-    pub created_at: DateTime<Utc>,
-}
-
-/// PostgreSQL implementation of InvoiceRepository.
-pub struct PgInvoiceRepository {
-    pool: PgPool,
-}
+    async fn start_next_period(&self, quota: &Quota) -> Result<Quota, CretoError> {
+        let delegation_multiplier = quota
+            .delegation_multiplier
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        let burst = quota
+            .burst
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
 
-impl PgInvoiceRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
-    }
-}
-
-impl InvoiceRepository for PgInvoiceRepository {
-    async fn create_invoice_record(
-        &self,
-        org_id: OrganizationId,
-        invoice_number: &str,
-        period_start: DateTime<Utc>,
-        period_end: DateTime<Utc>,
-        total_cents: i64,
-    ) -> Result<Uuid, CretoError> {
         let row = sqlx::query(
             r#"
-            INSERT INTO invoices (
-                organization_id, invoice_number, status, currency,
-                subtotal_cents, tax_cents, discount_cents, total_cents,
-                period_start, period_end
-            ) VALUES ($1, $2, 'draft', 'USD', $3, 0, 0, $3, $4, $5)
-            RETURNING id
+            INSERT INTO quotas (
+                organization_id, agent_id, resource, limit_value, period,
+                period_start, period_end, carried_debt,
+                max_delegation_depth, delegation_multiplier, burst
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (organization_id, agent_id, resource, period_start)
+            DO UPDATE SET
+                carried_debt = EXCLUDED.carried_debt,
+                max_delegation_depth = EXCLUDED.max_delegation_depth,
+                delegation_multiplier = EXCLUDED.delegation_multiplier,
+                burst = EXCLUDED.burst,
+                updated_at = NOW()
+            RETURNING id, current_usage
             "#,
         )
-        .bind(org_id.as_uuid())
-        .bind(invoice_number)
-        .bind(total_cents)
-        .bind(period_start)
-        .bind(period_end)
+        .bind(quota.organization_id.as_uuid())
+        .bind(quota.agent_id.map(|a| *a.as_uuid()))
+        .bind(&quota.metric_code)
+        .bind(quota.limit)
+        .bind(quota.period.as_str())
+        .bind(quota.period_start)
+        .bind(quota.period_end)
+        .bind(quota.carried_debt)
+        .bind(quota.max_delegation_depth.map(i16::from))
+        .bind(delegation_multiplier)
+        .bind(burst)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(row.get("id"))
+        Ok(Quota {
+            id: row.get("id"),
+            current_usage: row.get("current_usage"),
+            ..quota.clone()
+        })
     }
 
-    async fn get_invoice(&self, id: Uuid) -> Result<Option<InvoiceRecord>, CretoError> {
-        let row = sqlx::query(
+    async fn list_active(
+        &self,
+        at: DateTime<Utc>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<QuotaCounter>, CretoError> {
+        let rows = sqlx::query(
             r#"
-            SELECT organization_id, invoice_number, status, total_cents,
-                   period_start, period_end, created_at
-            FROM invoices
-            WHERE id = $1
+            SELECT id, organization_id, agent_id, resource, limit_value, current_usage,
+                   carried_debt, period, period_start, period_end,
+                   max_delegation_depth, delegation_multiplier, burst, updated_at
+            FROM quotas
+            WHERE period_start <= $1 AND period_end > $1
+            ORDER BY id
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(at)
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter()
+            .map(|r| {
+                Ok(QuotaCounter {
+                    quota: quota_from_row(r)?,
+                    updated_at: Some(r.get("updated_at")),
+                })
+            })
+            .collect()
+    }
+
+    async fn correct_usage(
+        &self,
+        quota: &Quota,
+        usage: i64,
+        unchanged_since: DateTime<Utc>,
+    ) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE quotas
+            SET current_usage = $2, updated_at = NOW()
+            WHERE id = $1 AND period_start = $3 AND updated_at < $4
+            "#,
+        )
+        .bind(quota.id)
+        .bind(usage)
+        .bind(quota.period_start)
+        .bind(unchanged_since)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn migrate_metric_code(
+        &self,
+        old_code: &str,
+        canonical_code: &str,
+    ) -> Result<u64, CretoError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let merged = sqlx::query(
+            r#"
+            WITH merged AS (
+                UPDATE quotas c
+                SET current_usage = c.current_usage + o.current_usage, updated_at = NOW()
+                FROM quotas o
+                WHERE o.resource = $1
+                  AND c.resource = $2
+                  AND c.organization_id = o.organization_id
+                  AND c.agent_id IS NOT DISTINCT FROM o.agent_id
+                  AND c.period_start = o.period_start
+                RETURNING o.id
+            )
+            DELETE FROM quotas WHERE id IN (SELECT id FROM merged)
+            "#,
+        )
+        .bind(old_code)
+        .bind(canonical_code)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let moved =
+            sqlx::query("UPDATE quotas SET resource = $2, updated_at = NOW() WHERE resource = $1")
+                .bind(old_code)
+                .bind(canonical_code)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(merged.rows_affected() + moved.rows_affected())
+    }
+}
+
+fn quota_from_row(r: &PgRow) -> Result<Quota, CretoError> {
+    let period_str: String = r.get("period");
+    let max_delegation_depth = r
+        .get::<Option<i16>, _>("max_delegation_depth")
+        .map(|depth| {
+            u8::try_from(depth).map_err(|_| {
+                CretoError::SerializationError(format!("Invalid max delegation depth {depth}"))
+            })
+        })
+        .transpose()?;
+    let delegation_multiplier = r
+        .get::<Option<serde_json::Value>, _>("delegation_multiplier")
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+    let burst = r
+        .get::<Option<serde_json::Value>, _>("burst")
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+    Ok(Quota {
+        id: r.get("id"),
+        organization_id: OrganizationId::from_uuid(r.get::<Uuid, _>("organization_id")),
+        agent_id: r.get::<Option<Uuid>, _>("agent_id").map(AgentId::from_uuid),
+        metric_code: r.get("resource"),
+        limit: r.get("limit_value"),
+        current_usage: r.get("current_usage"),
+        period: parse_period(&period_str),
+        period_start: r.get("period_start"),
+        period_end: r.get("period_end"),
+        allow_overage: false,
+        budget_cents: None,
+        max_delegation_depth,
+        delegation_multiplier,
+        burst,
+        carried_debt: r.get("carried_debt"),
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Quota Exemption Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for quota exemption windows.
+#[trait_variant::make(QuotaExemptionRepository: Send)]
+pub trait LocalQuotaExemptionRepository {
+    /// Store a new exemption.
+    async fn insert_exemption(&self, exemption: &QuotaExemption) -> Result<(), CretoError>;
+
+    /// Delete an exemption, returning whether it existed.
+    async fn delete_exemption(&self, id: Uuid) -> Result<bool, CretoError>;
+
+    /// List exemptions whose window has not ended by `now`, earliest first.
+    async fn list_unexpired(&self, now: DateTime<Utc>) -> Result<Vec<QuotaExemption>, CretoError>;
+
+    /// List an organization's exemptions, earliest first.
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<QuotaExemption>, CretoError>;
+}
+
+/// PostgreSQL implementation of QuotaExemptionRepository.
+pub struct PgQuotaExemptionRepository {
+    pool: PgPool,
+}
+
+impl PgQuotaExemptionRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl QuotaExemptionRepository for PgQuotaExemptionRepository {
+    async fn insert_exemption(&self, exemption: &QuotaExemption) -> Result<(), CretoError> {
+        let (unlimited, multiplier) = match exemption.allowance {
+            ExemptionAllowance::Unlimited => (true, None),
+            ExemptionAllowance::Multiplier(factor) => (false, Some(factor)),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO metering_quota_exemptions (
+                id, organization_id, agent_id, metric_codes, starts_at, ends_at,
+                unlimited, multiplier, reason, approved_by, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(exemption.id)
+        .bind(exemption.organization_id.as_uuid())
+        .bind(exemption.agent_id.map(|a| *a.as_uuid()))
+        .bind(&exemption.metric_codes)
+        .bind(exemption.starts_at)
+        .bind(exemption.ends_at)
+        .bind(unlimited)
+        .bind(multiplier)
+        .bind(&exemption.reason)
+        .bind(&exemption.approved_by)
+        .bind(exemption.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete_exemption(&self, id: Uuid) -> Result<bool, CretoError> {
+        let result = sqlx::query("DELETE FROM metering_quota_exemptions WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_unexpired(&self, now: DateTime<Utc>) -> Result<Vec<QuotaExemption>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, agent_id, metric_codes, starts_at, ends_at,
+                   unlimited, multiplier, reason, approved_by, created_at
+            FROM metering_quota_exemptions
+            WHERE ends_at > $1
+            ORDER BY starts_at ASC
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(quota_exemption_from_row).collect()
+    }
+
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<QuotaExemption>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, agent_id, metric_codes, starts_at, ends_at,
+                   unlimited, multiplier, reason, approved_by, created_at
+            FROM metering_quota_exemptions
+            WHERE organization_id = $1
+            ORDER BY starts_at ASC
+            "#,
+        )
+        .bind(org_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(quota_exemption_from_row).collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Quota Reservation Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for quota reservations, so reserved quota survives a restart.
+///
+/// Object-safe, unlike the other repositories here, so the enforcer's
+/// reservation store can hold one without a type parameter.
+#[async_trait::async_trait]
+pub trait ReservationRepository: Send + Sync {
+    /// Insert a reservation, or overwrite its stored state.
+    async fn save(&self, reservation: &Reservation) -> Result<(), CretoError>;
+
+    /// List active reservations, oldest first.
+    async fn list_active(&self) -> Result<Vec<Reservation>, CretoError>;
+
+    /// Mark active reservations that expired before `now` as expired,
+    /// returning their IDs.
+    async fn expire_before(&self, now: DateTime<Utc>) -> Result<Vec<Uuid>, CretoError>;
+}
+
+/// PostgreSQL implementation of ReservationRepository.
+pub struct PgReservationRepository {
+    pool: PgPool,
+}
+
+impl PgReservationRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ReservationRepository for PgReservationRepository {
+    async fn save(&self, reservation: &Reservation) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            INSERT INTO metering_quota_reservations (
+                id, organization_id, agent_id, metric_code, reserved_amount,
+                actual_amount, status, created_at, expires_at, metadata, group_id
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (id) DO UPDATE SET
+                actual_amount = EXCLUDED.actual_amount,
+                status = EXCLUDED.status,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(reservation.id)
+        .bind(reservation.organization_id)
+        .bind(&reservation.agent_id)
+        .bind(&reservation.metric_code)
+        .bind(reservation.reserved_amount)
+        .bind(reservation.actual_amount)
+        .bind(reservation.status.as_db_str())
+        .bind(reservation.created_at)
+        .bind(reservation.expires_at)
+        .bind(&reservation.metadata)
+        .bind(reservation.group_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_active(&self) -> Result<Vec<Reservation>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, agent_id, metric_code, reserved_amount,
+                   actual_amount, status, created_at, expires_at, metadata, group_id
+            FROM metering_quota_reservations
+            WHERE status = 'active'
+            ORDER BY created_at ASC, id ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(reservation_from_row).collect()
+    }
+
+    async fn expire_before(&self, now: DateTime<Utc>) -> Result<Vec<Uuid>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE metering_quota_reservations
+            SET status = 'expired', updated_at = NOW()
+            WHERE status = 'active' AND expires_at < $1
+            RETURNING id
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows.iter().map(|row| row.get("id")).collect())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Metric Alias Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for metric code aliases.
+#[trait_variant::make(MetricAliasRepository: Send)]
+pub trait LocalMetricAliasRepository {
+    /// Store a new alias.
+    async fn insert_alias(&self, alias: &MetricAlias) -> Result<(), CretoError>;
+
+    /// Delete an alias, returning whether it existed.
+    async fn delete_alias(&self, id: Uuid) -> Result<bool, CretoError>;
+
+    /// List every alias, oldest first.
+    async fn list_aliases(&self) -> Result<Vec<MetricAlias>, CretoError>;
+}
+
+/// PostgreSQL implementation of MetricAliasRepository.
+pub struct PgMetricAliasRepository {
+    pool: PgPool,
+}
+
+impl PgMetricAliasRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl MetricAliasRepository for PgMetricAliasRepository {
+    async fn insert_alias(&self, alias: &MetricAlias) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            INSERT INTO metering_metric_aliases (
+                id, old_code, canonical_code, effective_from, effective_until, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(alias.id)
+        .bind(&alias.old_code)
+        .bind(&alias.canonical_code)
+        .bind(alias.effective_from)
+        .bind(alias.effective_until)
+        .bind(alias.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete_alias(&self, id: Uuid) -> Result<bool, CretoError> {
+        let result = sqlx::query("DELETE FROM metering_metric_aliases WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_aliases(&self) -> Result<Vec<MetricAlias>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, old_code, canonical_code, effective_from, effective_until, created_at
+            FROM metering_metric_aliases
+            ORDER BY created_at ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|r| MetricAlias {
+                id: r.get("id"),
+                old_code: r.get("old_code"),
+                canonical_code: r.get("canonical_code"),
+                effective_from: r.get("effective_from"),
+                effective_until: r.get("effective_until"),
+                created_at: r.get("created_at"),
+            })
+            .collect())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Invoice Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for invoices and their line items.
+///
+/// Object-safe, so the metering service can hold one without a type
+/// parameter.
+#[async_trait::async_trait]
+pub trait InvoiceRepository: Send + Sync {
+    /// Create a new invoice record.
+    async fn create_invoice_record(
+        &self,
+        org_id: OrganizationId,
+        invoice_number: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        currency: Currency,
+        total_cents: i64,
+    ) -> Result<Uuid, CretoError>;
+
+    /// Get invoice by ID.
+    async fn get_invoice(&self, id: Uuid) -> Result<Option<InvoiceRecord>, CretoError>;
+
+    /// List invoices by organization.
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<InvoiceRecord>, CretoError>;
+
+    /// Insert an invoice with its line items, or overwrite the stored one.
+    async fn save_invoice(&self, invoice: &Invoice) -> Result<(), CretoError>;
+
+    /// Load an invoice with its line items.
+    async fn find_invoice(&self, id: Uuid) -> Result<Option<Invoice>, CretoError>;
+}
+
+/// Simplified invoice record for database storage.
+#[derive(Debug, Clone)]
+pub struct InvoiceRecord {
+    pub id: Uuid,
+    pub organization_id: OrganizationId,
+    pub invoice_number: String,
+    pub status: String,
+    pub currency: Currency,
+    pub total_cents: i64,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Invoice fields without a column of their own, kept in `invoices.metadata`.
+#[derive(Serialize, Deserialize)]
+struct InvoiceMetadata {
+    #[serde(default)]
+    discounts: Vec<Discount>,
+    #[serde(default)]
+    adjustments: Vec<AdjustmentSummary>,
+    #[serde(default)]
+    credit_note_for: Option<Uuid>,
+}
+
+/// Line item fields without a column of their own, kept in
+/// `invoice_line_items.metadata`.
+#[derive(Serialize, Deserialize)]
+struct LineItemMetadata {
+    metric_code: String,
+    unit: String,
+    #[serde(default)]
+    pricing: Option<AppliedPricing>,
+}
+
+/// Upsert an invoice and replace its line items inside `conn`'s transaction,
+/// so callers can store it alongside other writes.
+async fn write_invoice(conn: &mut PgConnection, invoice: &Invoice) -> Result<(), CretoError> {
+    let metadata = serde_json::to_value(InvoiceMetadata {
+        discounts: invoice.discounts.clone(),
+        adjustments: invoice.adjustments.clone(),
+        credit_note_for: invoice.credit_note_for,
+    })
+    .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+    let discount_cents = invoice.subtotal.amount + invoice.tax.amount - invoice.total.amount;
+
+    sqlx::query(
+        r#"
+        INSERT INTO invoices (
+            id, organization_id, invoice_number, status, currency,
+            subtotal_cents, tax_cents, discount_cents, total_cents,
+            period_start, period_end, issued_at, paid_at, due_at, metadata
+        ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        ON CONFLICT (id) DO UPDATE SET
+            status = EXCLUDED.status,
+            subtotal_cents = EXCLUDED.subtotal_cents,
+            tax_cents = EXCLUDED.tax_cents,
+            discount_cents = EXCLUDED.discount_cents,
+            total_cents = EXCLUDED.total_cents,
+            issued_at = EXCLUDED.issued_at,
+            paid_at = EXCLUDED.paid_at,
+            due_at = EXCLUDED.due_at,
+            metadata = EXCLUDED.metadata,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(invoice.id)
+    .bind(invoice.organization_id.as_uuid())
+    .bind(&invoice.number)
+    .bind(invoice.status.as_db_str())
+    .bind(invoice.currency.code())
+    .bind(invoice.subtotal.amount)
+    .bind(invoice.tax.amount)
+    .bind(discount_cents)
+    .bind(invoice.total.amount)
+    .bind(invoice.period_start)
+    .bind(invoice.period_end)
+    .bind(invoice.issued_at)
+    .bind(invoice.paid_at)
+    .bind(invoice.due_at)
+    .bind(metadata)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| CretoError::Database(e.to_string()))?;
+
+    // Line items are replaced wholesale; adjustments rewrite them in place
+    sqlx::query("DELETE FROM invoice_line_items WHERE invoice_id = $1")
+        .bind(invoice.id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+    for (position, item) in invoice.line_items.iter().enumerate() {
+        let metadata = serde_json::to_value(LineItemMetadata {
+            metric_code: item.metric_code.clone(),
+            unit: item.unit.clone(),
+            pricing: item.pricing.clone(),
+        })
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        let conversion = item
+            .conversion
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        let source = item
+            .source
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO invoice_line_items (
+                id, invoice_id, description, quantity, unit_amount_cents, amount_cents,
+                currency, conversion, source, metadata, position
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(item.id)
+        .bind(invoice.id)
+        .bind(&item.description)
+        .bind(item.quantity)
+        .bind(item.unit_price.amount)
+        .bind(item.amount.amount)
+        .bind(item.amount.currency.code())
+        .bind(conversion)
+        .bind(source)
+        .bind(metadata)
+        .bind(position as i32)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// PostgreSQL implementation of InvoiceRepository.
+pub struct PgInvoiceRepository {
+    pool: PgPool,
+}
+
+impl PgInvoiceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl InvoiceRepository for PgInvoiceRepository {
+    async fn create_invoice_record(
+        &self,
+        org_id: OrganizationId,
+        invoice_number: &str,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        currency: Currency,
+        total_cents: i64,
+    ) -> Result<Uuid, CretoError> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO invoices (
+                organization_id, invoice_number, status, currency,
+                subtotal_cents, tax_cents, discount_cents, total_cents,
+                period_start, period_end
+            ) VALUES ($1, $2, 'draft', $6, $3, 0, 0, $3, $4, $5)
+            RETURNING id
+            "#,
+        )
+        .bind(org_id.as_uuid())
+        .bind(invoice_number)
+        .bind(total_cents)
+        .bind(period_start)
+        .bind(period_end)
+        .bind(currency.code())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.get("id"))
+    }
+
+    async fn get_invoice(&self, id: Uuid) -> Result<Option<InvoiceRecord>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT organization_id, invoice_number, status, currency, total_cents,
+                   period_start, period_end, created_at
+            FROM invoices
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.map(|r| InvoiceRecord {
+            id,
+            organization_id: OrganizationId::from_uuid(r.get::<Uuid, _>("organization_id")),
+            invoice_number: r.get("invoice_number"),
+            status: r.get("status"),
+            currency: parse_currency(r.get::<&str, _>("currency")),
+            total_cents: r.get("total_cents"),
+            period_start: r.get("period_start"),
+            period_end: r.get("period_end"),
+            created_at: r.get("created_at"),
+        }))
+    }
+
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<InvoiceRecord>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, invoice_number, status, currency, total_cents, period_start, period_end,
+                   created_at
+            FROM invoices
+            WHERE organization_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(org_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| InvoiceRecord {
+                id: r.get("id"),
+                organization_id: org_id,
+                invoice_number: r.get("invoice_number"),
+                status: r.get("status"),
+                currency: parse_currency(r.get::<&str, _>("currency")),
+                total_cents: r.get("total_cents"),
+                period_start: r.get("period_start"),
+                period_end: r.get("period_end"),
+                created_at: r.get("created_at"),
+            })
+            .collect())
+    }
+
+    async fn save_invoice(&self, invoice: &Invoice) -> Result<(), CretoError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        write_invoice(&mut tx, invoice).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))
+    }
+
+    async fn find_invoice(&self, id: Uuid) -> Result<Option<Invoice>, CretoError> {
+        let Some(row) = sqlx::query(
+            r#"
+            SELECT organization_id, invoice_number, status, currency, subtotal_cents,
+                   tax_cents, total_cents, period_start, period_end, issued_at, paid_at,
+                   due_at, metadata
+            FROM invoices
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        let items = sqlx::query(
+            r#"
+            SELECT id, description, quantity, unit_amount_cents, amount_cents, currency,
+                   conversion, source, metadata
+            FROM invoice_line_items
+            WHERE invoice_id = $1
+            ORDER BY position ASC
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let status: String = row.get("status");
+        let metadata: InvoiceMetadata = serde_json::from_value(row.get("metadata"))
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        let currency = parse_currency(row.get::<&str, _>("currency"));
+
+        Ok(Some(Invoice {
+            id,
+            number: row.get("invoice_number"),
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            period_start: row.get("period_start"),
+            period_end: row.get("period_end"),
+            currency,
+            status: InvoiceStatus::from_db_str(&status).ok_or_else(|| {
+                CretoError::Database(format!("Unknown invoice status: {}", status))
+            })?,
+            line_items: items
+                .iter()
+                .map(line_item_from_row)
+                .collect::<Result<_, _>>()?,
+            subtotal: Money::new(row.get("subtotal_cents"), currency),
+            discounts: metadata.discounts,
+            tax: Money::new(row.get("tax_cents"), currency),
+            total: Money::new(row.get("total_cents"), currency),
+            issued_at: row.get("issued_at"),
+            due_at: row.get("due_at"),
+            paid_at: row.get("paid_at"),
+            adjustments: metadata.adjustments,
+            credit_note_for: metadata.credit_note_for,
+        }))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Invoice Adjustment Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for invoice adjustments.
+///
+/// Every write stores the adjustment together with the invoices it changed,
+/// in one unit, so an adjustment is never marked applied without its
+/// invoice and credit note. Implementations must write those invoices where
+/// the service's invoice repository reads them. Object-safe, so the metering
+/// service can take one without a type parameter.
+#[async_trait::async_trait]
+pub trait InvoiceAdjustmentRepository: Send + Sync {
+    /// Store a new adjustment and the invoice it was proposed against.
+    async fn insert_adjustment(
+        &self,
+        adjustment: &InvoiceAdjustment,
+        invoice: &Invoice,
+    ) -> Result<(), CretoError>;
+
+    /// Get an adjustment by ID.
+    async fn get_adjustment(&self, id: Uuid) -> Result<Option<InvoiceAdjustment>, CretoError>;
+
+    /// List an invoice's adjustments, oldest first.
+    async fn list_by_invoice(&self, invoice_id: Uuid)
+        -> Result<Vec<InvoiceAdjustment>, CretoError>;
+
+    /// Overwrite an adjustment and store `invoices` with it if its stored
+    /// status is still `expected`, returning whether anything was written.
+    async fn update_if_status(
+        &self,
+        adjustment: &InvoiceAdjustment,
+        expected: AdjustmentStatus,
+        invoices: &[&Invoice],
+    ) -> Result<bool, CretoError>;
+}
+
+/// PostgreSQL implementation of InvoiceAdjustmentRepository.
+pub struct PgInvoiceAdjustmentRepository {
+    pool: PgPool,
+}
+
+impl PgInvoiceAdjustmentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl InvoiceAdjustmentRepository for PgInvoiceAdjustmentRepository {
+    async fn insert_adjustment(
+        &self,
+        adjustment: &InvoiceAdjustment,
+        invoice: &Invoice,
+    ) -> Result<(), CretoError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO metering_invoice_adjustments (
+                id, invoice_id, organization_id, line_item_id, amount_cents, reason,
+                created_by, status, reviewed_by, rejection_reason, credit_note_id,
+                created_at, reviewed_at, applied_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            "#,
+        )
+        .bind(adjustment.id)
+        .bind(adjustment.invoice_id)
+        .bind(adjustment.organization_id.as_uuid())
+        .bind(adjustment.line_item_id)
+        .bind(adjustment.amount_cents)
+        .bind(&adjustment.reason)
+        .bind(&adjustment.created_by)
+        .bind(adjustment.status.as_db_str())
+        .bind(&adjustment.reviewed_by)
+        .bind(&adjustment.rejection_reason)
+        .bind(adjustment.credit_note_id)
+        .bind(adjustment.created_at)
+        .bind(adjustment.reviewed_at)
+        .bind(adjustment.applied_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        write_invoice(&mut tx, invoice).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))
+    }
+
+    async fn get_adjustment(&self, id: Uuid) -> Result<Option<InvoiceAdjustment>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, invoice_id, organization_id, line_item_id, amount_cents, reason,
+                   created_by, status, reviewed_by, rejection_reason, credit_note_id,
+                   created_at, reviewed_at, applied_at
+            FROM metering_invoice_adjustments
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.as_ref().map(invoice_adjustment_from_row).transpose()
+    }
+
+    async fn list_by_invoice(
+        &self,
+        invoice_id: Uuid,
+    ) -> Result<Vec<InvoiceAdjustment>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, invoice_id, organization_id, line_item_id, amount_cents, reason,
+                   created_by, status, reviewed_by, rejection_reason, credit_note_id,
+                   created_at, reviewed_at, applied_at
+            FROM metering_invoice_adjustments
+            WHERE invoice_id = $1
+            ORDER BY created_at ASC, id ASC
+            "#,
+        )
+        .bind(invoice_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(invoice_adjustment_from_row).collect()
+    }
+
+    async fn update_if_status(
+        &self,
+        adjustment: &InvoiceAdjustment,
+        expected: AdjustmentStatus,
+        invoices: &[&Invoice],
+    ) -> Result<bool, CretoError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE metering_invoice_adjustments
+            SET status = $2, reviewed_by = $3, rejection_reason = $4, credit_note_id = $5,
+                reviewed_at = $6, applied_at = $7
+            WHERE id = $1 AND status = $8
+            "#,
+        )
+        .bind(adjustment.id)
+        .bind(adjustment.status.as_db_str())
+        .bind(&adjustment.reviewed_by)
+        .bind(&adjustment.rejection_reason)
+        .bind(adjustment.credit_note_id)
+        .bind(adjustment.reviewed_at)
+        .bind(adjustment.applied_at)
+        .bind(expected.as_db_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        // Another writer moved the adjustment on; leave its invoices alone
+        if result.rows_affected() != 1 {
+            return Ok(false);
+        }
+
+        for invoice in invoices {
+            write_invoice(&mut tx, invoice).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(true)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Billing Profile Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for organization billing profiles.
+#[trait_variant::make(BillingProfileRepository: Send)]
+pub trait LocalBillingProfileRepository {
+    /// Get the billing profile for an organization.
+    async fn get_profile(
+        &self,
+        org_id: OrganizationId,
+    ) -> Result<Option<OrgBillingProfile>, CretoError>;
+
+    /// Create or update a billing profile.
+    async fn upsert_profile(&self, profile: &OrgBillingProfile) -> Result<(), CretoError>;
+}
+
+/// PostgreSQL implementation of BillingProfileRepository.
+pub struct PgBillingProfileRepository {
+    pool: PgPool,
+}
+
+impl PgBillingProfileRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl BillingProfileRepository for PgBillingProfileRepository {
+    async fn get_profile(
+        &self,
+        org_id: OrganizationId,
+    ) -> Result<Option<OrgBillingProfile>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT billing_currency
+            FROM org_billing_profiles
+            WHERE organization_id = $1
+            "#,
+        )
+        .bind(org_id.as_uuid())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.map(|r| {
+            OrgBillingProfile::new(org_id, parse_currency(r.get::<&str, _>("billing_currency")))
+        }))
+    }
+
+    async fn upsert_profile(&self, profile: &OrgBillingProfile) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            INSERT INTO org_billing_profiles (organization_id, billing_currency)
+            VALUES ($1, $2)
+            ON CONFLICT (organization_id)
+            DO UPDATE SET billing_currency = EXCLUDED.billing_currency, updated_at = NOW()
+            "#,
+        )
+        .bind(profile.organization_id.as_uuid())
+        .bind(profile.billing_currency.code())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Exchange Rate Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for effective-dated exchange rates.
+///
+/// Rates are loaded into a
+/// [`TableExchangeRateProvider`](crate::currency::TableExchangeRateProvider)
+/// for synchronous lookup during invoicing.
+#[trait_variant::make(ExchangeRateRepository: Send)]
+pub trait LocalExchangeRateRepository {
+    /// Insert a new effective-dated rate.
+    async fn insert_rate(&self, rate: &ExchangeRate) -> Result<(), CretoError>;
+
+    /// List all rates, oldest first.
+    async fn list_rates(&self) -> Result<Vec<ExchangeRate>, CretoError>;
+}
+
+/// PostgreSQL implementation of ExchangeRateRepository.
+pub struct PgExchangeRateRepository {
+    pool: PgPool,
+}
+
+impl PgExchangeRateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl ExchangeRateRepository for PgExchangeRateRepository {
+    async fn insert_rate(&self, rate: &ExchangeRate) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            INSERT INTO exchange_rates (from_currency, to_currency, rate, effective_from)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (from_currency, to_currency, effective_from)
+            DO UPDATE SET rate = EXCLUDED.rate
+            "#,
+        )
+        .bind(rate.from.code())
+        .bind(rate.to.code())
+        .bind(rate.rate)
+        .bind(rate.effective_from)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_rates(&self) -> Result<Vec<ExchangeRate>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT from_currency, to_currency, rate, effective_from
+            FROM exchange_rates
+            ORDER BY effective_from ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|r| {
+                let from: String = r.get("from_currency");
+                let to: String = r.get("to_currency");
+                Ok(ExchangeRate {
+                    from: Currency::from_code(&from).ok_or_else(|| {
+                        CretoError::Database(format!("Unknown currency: {}", from))
+                    })?,
+                    to: Currency::from_code(&to)
+                        .ok_or_else(|| CretoError::Database(format!("Unknown currency: {}", to)))?,
+                    rate: r.get("rate"),
+                    effective_from: r.get("effective_from"),
+                })
+            })
+            .collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// API Key Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for hashed per-organization API keys.
+///
+/// Object-safe, so the metering service can take one without a type
+/// parameter.
+#[async_trait::async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    /// Store a newly issued key.
+    async fn insert_key(&self, key: &ApiKey) -> Result<(), CretoError>;
+
+    /// Get a key by ID.
+    async fn get_key(&self, key_id: Uuid) -> Result<Option<ApiKey>, CretoError>;
+
+    /// Find a key by the hash of its plaintext.
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, CretoError>;
+
+    /// List all keys for an organization, oldest first.
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<ApiKey>, CretoError>;
+
+    /// Set when a key stops being valid.
+    async fn set_expiry(&self, key_id: Uuid, expires_at: DateTime<Utc>) -> Result<(), CretoError>;
+
+    /// Mark a key as revoked.
+    async fn revoke(&self, key_id: Uuid, revoked_at: DateTime<Utc>) -> Result<(), CretoError>;
+}
+
+/// PostgreSQL implementation of ApiKeyRepository.
+pub struct PgApiKeyRepository {
+    pool: PgPool,
+}
+
+impl PgApiKeyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ApiKeyRepository for PgApiKeyRepository {
+    async fn insert_key(&self, key: &ApiKey) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            INSERT INTO metering_api_keys (
+                id, organization_id, key_hash, created_at, expires_at, revoked_at
+            ) VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(key.id)
+        .bind(key.organization_id.as_uuid())
+        .bind(&key.key_hash)
+        .bind(key.created_at)
+        .bind(key.expires_at)
+        .bind(key.revoked_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_key(&self, key_id: Uuid) -> Result<Option<ApiKey>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, organization_id, key_hash, created_at, expires_at, revoked_at
+            FROM metering_api_keys
+            WHERE id = $1
+            "#,
+        )
+        .bind(key_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.as_ref().map(api_key_from_row))
+    }
+
+    async fn find_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, organization_id, key_hash, created_at, expires_at, revoked_at
+            FROM metering_api_keys
+            WHERE key_hash = $1
+            "#,
+        )
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.as_ref().map(api_key_from_row))
+    }
+
+    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<ApiKey>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, key_hash, created_at, expires_at, revoked_at
+            FROM metering_api_keys
+            WHERE organization_id = $1
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(org_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows.iter().map(api_key_from_row).collect())
+    }
+
+    async fn set_expiry(&self, key_id: Uuid, expires_at: DateTime<Utc>) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            UPDATE metering_api_keys
+            SET expires_at = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(key_id)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn revoke(&self, key_id: Uuid, revoked_at: DateTime<Utc>) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            UPDATE metering_api_keys
+            SET revoked_at = $2
+            WHERE id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(key_id)
+        .bind(revoked_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Late Event Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for usage events that arrived behind the watermark.
+///
+/// Object-safe, so the metering service can take one without a type
+/// parameter.
+#[async_trait::async_trait]
+pub trait LateEventRepository: Send + Sync {
+    /// Queue a late event.
+    async fn enqueue(&self, late_event: &LateEvent) -> Result<(), CretoError>;
+
+    /// List an organization's pending late events, oldest arrival first.
+    async fn list_pending(&self, org_id: OrganizationId) -> Result<Vec<LateEvent>, CretoError>;
+
+    /// Mark late events as resolved.
+    async fn mark_resolved(
+        &self,
+        ids: &[Uuid],
+        status: LateEventStatus,
+        resolved_at: DateTime<Utc>,
+    ) -> Result<(), CretoError>;
+}
+
+/// PostgreSQL implementation of LateEventRepository.
+pub struct PgLateEventRepository {
+    pool: PgPool,
+}
+
+impl PgLateEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl LateEventRepository for PgLateEventRepository {
+    async fn enqueue(&self, late_event: &LateEvent) -> Result<(), CretoError> {
+        let event = serde_json::to_value(&late_event.event)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO metering_late_events (
+                id, organization_id, code, event, event_timestamp, watermark,
+                received_at, status, resolved_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(late_event.id)
+        .bind(late_event.event.organization_id.as_uuid())
+        .bind(&late_event.event.code)
+        .bind(event)
+        .bind(late_event.event.timestamp)
+        .bind(late_event.watermark)
+        .bind(late_event.received_at)
+        .bind(late_event.status.as_db_str())
+        .bind(late_event.resolved_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_pending(&self, org_id: OrganizationId) -> Result<Vec<LateEvent>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, event, watermark, received_at, status, resolved_at
+            FROM metering_late_events
+            WHERE organization_id = $1 AND status = 'pending'
+            ORDER BY received_at ASC
+            "#,
+        )
+        .bind(org_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(late_event_from_row).collect()
+    }
+
+    async fn mark_resolved(
+        &self,
+        ids: &[Uuid],
+        status: LateEventStatus,
+        resolved_at: DateTime<Utc>,
+    ) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            UPDATE metering_late_events
+            SET status = $2, resolved_at = $3
+            WHERE id = ANY($1) AND status = 'pending'
+            "#,
+        )
+        .bind(ids)
+        .bind(status.as_db_str())
+        .bind(resolved_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Auto Top-Up Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for wallet auto top-up rules and attempts.
+#[trait_variant::make(AutoTopUpRepository: Send)]
+pub trait LocalAutoTopUpRepository {
+    /// Insert or replace a wallet's rule.
+    async fn save_rule(&self, rule: &AutoTopUpRule) -> Result<(), CretoError>;
+
+    /// Get a wallet's rule.
+    async fn get_rule(&self, wallet_id: Uuid) -> Result<Option<AutoTopUpRule>, CretoError>;
+
+    /// List every rule.
+    async fn list_rules(&self) -> Result<Vec<AutoTopUpRule>, CretoError>;
+
+    /// Delete a wallet's rule.
+    async fn delete_rule(&self, wallet_id: Uuid) -> Result<bool, CretoError>;
+
+    /// Record a new attempt unless one with the same idempotency key
+    /// exists. Returns whether the attempt was recorded.
+    async fn claim_attempt(&self, attempt: &TopUpAttempt) -> Result<bool, CretoError>;
+
+    /// Take over an unfinished attempt, setting its claim time to
+    /// `claimed_at` and clearing its error, unless another check took it
+    /// over since `attempt` was read. Returns whether it was taken over.
+    async fn reclaim_attempt(
+        &self,
+        attempt: &TopUpAttempt,
+        claimed_at: DateTime<Utc>,
+    ) -> Result<bool, CretoError>;
+
+    /// Store the progress or outcome of a claimed attempt.
+    async fn complete_attempt(&self, attempt: &TopUpAttempt) -> Result<(), CretoError>;
+
+    /// List a wallet's attempts in a period, in sequence order.
+    async fn list_attempts(
+        &self,
+        wallet_id: Uuid,
+        period_key: &str,
+    ) -> Result<Vec<TopUpAttempt>, CretoError>;
+}
+
+/// PostgreSQL implementation of AutoTopUpRepository.
+pub struct PgAutoTopUpRepository {
+    pool: PgPool,
+}
+
+impl PgAutoTopUpRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl AutoTopUpRepository for PgAutoTopUpRepository {
+    async fn save_rule(&self, rule: &AutoTopUpRule) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            INSERT INTO metering_auto_top_up_rules (
+                wallet_id, organization_id, threshold_cents, top_up_amount_cents,
+                max_top_ups_per_period, period, funding_source, enabled,
+                created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (wallet_id) DO UPDATE SET
+                threshold_cents = EXCLUDED.threshold_cents,
+                top_up_amount_cents = EXCLUDED.top_up_amount_cents,
+                max_top_ups_per_period = EXCLUDED.max_top_ups_per_period,
+                period = EXCLUDED.period,
+                funding_source = EXCLUDED.funding_source,
+                enabled = EXCLUDED.enabled,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(rule.wallet_id)
+        .bind(rule.organization_id.as_uuid())
+        .bind(rule.threshold_cents)
+        .bind(rule.top_up_amount_cents)
+        .bind(rule.max_top_ups_per_period as i32)
+        .bind(rule.period.as_db_str())
+        .bind(&rule.funding_source)
+        .bind(rule.enabled)
+        .bind(rule.created_at)
+        .bind(rule.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_rule(&self, wallet_id: Uuid) -> Result<Option<AutoTopUpRule>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT wallet_id, organization_id, threshold_cents, top_up_amount_cents,
+                   max_top_ups_per_period, period, funding_source, enabled,
+                   created_at, updated_at
+            FROM metering_auto_top_up_rules
+            WHERE wallet_id = $1
+            "#,
+        )
+        .bind(wallet_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.as_ref().map(auto_top_up_rule_from_row).transpose()
+    }
+
+    async fn list_rules(&self) -> Result<Vec<AutoTopUpRule>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT wallet_id, organization_id, threshold_cents, top_up_amount_cents,
+                   max_top_ups_per_period, period, funding_source, enabled,
+                   created_at, updated_at
+            FROM metering_auto_top_up_rules
+            ORDER BY created_at ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(auto_top_up_rule_from_row).collect()
+    }
+
+    async fn delete_rule(&self, wallet_id: Uuid) -> Result<bool, CretoError> {
+        let result = sqlx::query("DELETE FROM metering_auto_top_up_rules WHERE wallet_id = $1")
+            .bind(wallet_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn claim_attempt(&self, attempt: &TopUpAttempt) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO metering_auto_top_ups (
+                id, wallet_id, organization_id, idempotency_key, period_key, sequence,
+                amount_cents, status, payment_id, transaction_id, error,
+                created_at, claimed_at, completed_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            ON CONFLICT (idempotency_key) DO NOTHING
+            "#,
+        )
+        .bind(attempt.id)
+        .bind(attempt.wallet_id)
+        .bind(attempt.organization_id.as_uuid())
+        .bind(&attempt.idempotency_key)
+        .bind(&attempt.period_key)
+        .bind(attempt.sequence as i32)
+        .bind(attempt.amount_cents)
+        .bind(attempt.status.as_db_str())
+        .bind(&attempt.payment_id)
+        .bind(attempt.transaction_id)
+        .bind(&attempt.error)
+        .bind(attempt.created_at)
+        .bind(attempt.claimed_at)
+        .bind(attempt.completed_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn reclaim_attempt(
+        &self,
+        attempt: &TopUpAttempt,
+        claimed_at: DateTime<Utc>,
+    ) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE metering_auto_top_ups
+            SET claimed_at = $3, error = NULL
+            WHERE id = $1 AND claimed_at = $2 AND status IN ('pending', 'charged')
+            "#,
+        )
+        .bind(attempt.id)
+        .bind(attempt.claimed_at)
+        .bind(claimed_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn complete_attempt(&self, attempt: &TopUpAttempt) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            UPDATE metering_auto_top_ups
+            SET status = $2, payment_id = $3, transaction_id = $4, error = $5, completed_at = $6
+            WHERE id = $1
+            "#,
+        )
+        .bind(attempt.id)
+        .bind(attempt.status.as_db_str())
+        .bind(&attempt.payment_id)
+        .bind(attempt.transaction_id)
+        .bind(&attempt.error)
+        .bind(attempt.completed_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_attempts(
+        &self,
+        wallet_id: Uuid,
+        period_key: &str,
+    ) -> Result<Vec<TopUpAttempt>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, wallet_id, organization_id, idempotency_key, period_key, sequence,
+                   amount_cents, status, payment_id, transaction_id, error,
+                   created_at, claimed_at, completed_at
+            FROM metering_auto_top_ups
+            WHERE wallet_id = $1 AND period_key = $2
+            ORDER BY sequence ASC
             "#,
         )
-        .bind(id)
-        .fetch_optional(&self.pool)
+        .bind(wallet_id)
+        .bind(period_key)
+        .fetch_all(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(row.map(|r| InvoiceRecord {
-            id,
-            organization_id: OrganizationId::from_uuid(r.get::<Uuid, _>("organization_id")),
-            invoice_number: r.get("invoice_number"),
-            status: r.get("status"),
-            total_cents: r.get("total_cents"),
-            period_start: r.get("period_start"),
-            period_end: r.get("period_end"),
-            created_at: r.get("created_at"),
-        }))
+        rows.iter().map(top_up_attempt_from_row).collect()
     }
+}
 
-    async fn list_by_org(&self, org_id: OrganizationId) -> Result<Vec<InvoiceRecord>, CretoError> {
+// ─────────────────────────────────────────────────────────────────────────────
+// Anomaly Baseline Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for usage anomaly baselines.
+#[trait_variant::make(AnomalyBaselineRepository: Send)]
+pub trait LocalAnomalyBaselineRepository {
+    /// Insert or replace baselines.
+    async fn save_baselines(&self, baselines: &[AnomalyBaseline]) -> Result<(), CretoError>;
+
+    /// Load every stored baseline.
+    async fn load_baselines(&self) -> Result<Vec<AnomalyBaseline>, CretoError>;
+
+    /// Delete baselines last updated before `updated_before`, returning the
+    /// number deleted.
+    async fn delete_baselines_before(
+        &self,
+        updated_before: DateTime<Utc>,
+    ) -> Result<u64, CretoError>;
+}
+
+/// PostgreSQL implementation of AnomalyBaselineRepository.
+pub struct PgAnomalyBaselineRepository {
+    pool: PgPool,
+}
+
+impl PgAnomalyBaselineRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl AnomalyBaselineRepository for PgAnomalyBaselineRepository {
+    async fn save_baselines(&self, baselines: &[AnomalyBaseline]) -> Result<(), CretoError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        for baseline in baselines {
+            sqlx::query(
+                r#"
+                INSERT INTO metering_anomaly_baselines (
+                    organization_id, agent_id, metric_code, mean, variance, windows,
+                    window_start, window_quantity, suppressed_until, alerted_severity,
+                    updated_at
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                ON CONFLICT (organization_id, agent_id, metric_code) DO UPDATE SET
+                    mean = EXCLUDED.mean,
+                    variance = EXCLUDED.variance,
+                    windows = EXCLUDED.windows,
+                    window_start = EXCLUDED.window_start,
+                    window_quantity = EXCLUDED.window_quantity,
+                    suppressed_until = EXCLUDED.suppressed_until,
+                    alerted_severity = EXCLUDED.alerted_severity,
+                    updated_at = EXCLUDED.updated_at
+                "#,
+            )
+            .bind(baseline.organization_id.as_uuid())
+            .bind(baseline.agent_id.as_uuid())
+            .bind(&baseline.metric_code)
+            .bind(baseline.mean)
+            .bind(baseline.variance)
+            .bind(baseline.windows as i64)
+            .bind(baseline.window_start)
+            .bind(baseline.window_quantity)
+            .bind(baseline.suppressed_until)
+            .bind(baseline.alerted_severity.map(|s| s.as_db_str()))
+            .bind(baseline.updated_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn load_baselines(&self) -> Result<Vec<AnomalyBaseline>, CretoError> {
         let rows = sqlx::query(
             r#"
-            SELECT id, invoice_number, status, total_cents, period_start, period_end, created_at
-            FROM invoices
-            WHERE organization_id = $1
-            ORDER BY created_at DESC
+            SELECT organization_id, agent_id, metric_code, mean, variance, windows,
+                   window_start, window_quantity, suppressed_until, alerted_severity,
+                   updated_at
+            FROM metering_anomaly_baselines
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(anomaly_baseline_from_row).collect()
+    }
+
+    async fn delete_baselines_before(
+        &self,
+        updated_before: DateTime<Utc>,
+    ) -> Result<u64, CretoError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM metering_anomaly_baselines
+            WHERE updated_at < $1
+            "#,
+        )
+        .bind(updated_before)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Pricing Model Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for versioned pricing models and organization pins.
+///
+/// Versions and pins are loaded into a [`PricingCatalog`] for synchronous
+/// lookup during invoicing. Object-safe, so the metering service can hold
+/// one without a type parameter.
+#[async_trait::async_trait]
+pub trait PricingModelRepository: Send + Sync {
+    /// Store a new version, ending the version it supersedes at its
+    /// `effective_from`. Rejects ranges overlapping another version of the
+    /// same metric with [`CretoError::ValidationFailed`].
+    async fn create_version(&self, model: &PricingModel) -> Result<(), CretoError>;
+
+    /// List all versions of a metric, oldest first.
+    async fn list_versions(&self, metric_code: &str) -> Result<Vec<PricingModel>, CretoError>;
+
+    /// The version pricing an organization's usage of a metric at `at`,
+    /// honoring pins.
+    async fn resolve(
+        &self,
+        org_id: OrganizationId,
+        metric_code: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Option<PricingModel>, CretoError>;
+
+    /// Pin (grandfather) an organization to a version.
+    async fn pin(&self, org_id: OrganizationId, model_id: &str) -> Result<(), CretoError>;
+
+    /// Remove an organization's pin for a metric, returning the pinned model ID.
+    async fn migrate(
+        &self,
+        org_id: OrganizationId,
+        metric_code: &str,
+    ) -> Result<Option<String>, CretoError>;
+
+    /// Load every version and pin.
+    async fn load_catalog(&self) -> Result<PricingCatalog, CretoError>;
+}
+
+/// PostgreSQL implementation of PricingModelRepository.
+pub struct PgPricingModelRepository {
+    pool: PgPool,
+}
+
+impl PgPricingModelRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl PricingModelRepository for PgPricingModelRepository {
+    async fn create_version(&self, model: &PricingModel) -> Result<(), CretoError> {
+        let strategy = serde_json::to_value(&model.strategy)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        // Serialize version changes per metric so the overlap check holds
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(&model.metric_code)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, name, metric_code, version, currency, strategy,
+                   effective_from, effective_until, supersedes
+            FROM pricing_model_versions
+            WHERE metric_code = $1
+            ORDER BY effective_from ASC
+            "#,
+        )
+        .bind(&model.metric_code)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let mut catalog = PricingCatalog::new();
+        for row in &rows {
+            catalog.add_version(pricing_model_from_row(row)?)?;
+        }
+        catalog.add_version(model.clone())?;
+
+        if let Some(previous) = model.supersedes.as_deref().and_then(|id| catalog.get(id)) {
+            sqlx::query("UPDATE pricing_model_versions SET effective_until = $2 WHERE id = $1")
+                .bind(&previous.id)
+                .bind(previous.effective_until)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| CretoError::Database(e.to_string()))?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO pricing_model_versions (
+                id, name, metric_code, version, currency, strategy,
+                effective_from, effective_until, supersedes
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(&model.id)
+        .bind(&model.name)
+        .bind(&model.metric_code)
+        .bind(model.version as i32)
+        .bind(model.currency.code())
+        .bind(strategy)
+        .bind(model.effective_from)
+        .bind(model.effective_until)
+        .bind(&model.supersedes)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_versions(&self, metric_code: &str) -> Result<Vec<PricingModel>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, name, metric_code, version, currency, strategy,
+                   effective_from, effective_until, supersedes
+            FROM pricing_model_versions
+            WHERE metric_code = $1
+            ORDER BY effective_from ASC
+            "#,
+        )
+        .bind(metric_code)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(pricing_model_from_row).collect()
+    }
+
+    async fn resolve(
+        &self,
+        org_id: OrganizationId,
+        metric_code: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Option<PricingModel>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT v.id, v.name, v.metric_code, v.version, v.currency, v.strategy,
+                   v.effective_from, v.effective_until, v.supersedes
+            FROM pricing_model_versions v
+            LEFT JOIN pricing_pins p
+              ON p.model_id = v.id AND p.organization_id = $1 AND p.metric_code = $2
+            WHERE v.metric_code = $2
+              AND (p.model_id IS NOT NULL
+                   OR (v.effective_from <= $3
+                       AND (v.effective_until IS NULL OR v.effective_until > $3)))
+            ORDER BY (p.model_id IS NOT NULL) DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(org_id.as_uuid())
+        .bind(metric_code)
+        .bind(at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.as_ref().map(pricing_model_from_row).transpose()
+    }
+
+    async fn pin(&self, org_id: OrganizationId, model_id: &str) -> Result<(), CretoError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO pricing_pins (organization_id, metric_code, model_id, pinned_at)
+            SELECT $1, metric_code, id, NOW()
+            FROM pricing_model_versions
+            WHERE id = $2
+            ON CONFLICT (organization_id, metric_code)
+            DO UPDATE SET model_id = EXCLUDED.model_id, pinned_at = EXCLUDED.pinned_at
+            "#,
+        )
+        .bind(org_id.as_uuid())
+        .bind(model_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(CretoError::NotFound(format!("pricing model {}", model_id)));
+        }
+
+        Ok(())
+    }
+
+    async fn migrate(
+        &self,
+        org_id: OrganizationId,
+        metric_code: &str,
+    ) -> Result<Option<String>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            DELETE FROM pricing_pins
+            WHERE organization_id = $1 AND metric_code = $2
+            RETURNING model_id
             "#,
         )
         .bind(org_id.as_uuid())
+        .bind(metric_code)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.map(|r| r.get("model_id")))
+    }
+
+    async fn load_catalog(&self) -> Result<PricingCatalog, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, name, metric_code, version, currency, strategy,
+                   effective_from, effective_until, supersedes
+            FROM pricing_model_versions
+            ORDER BY effective_from ASC
+            "#,
+        )
         .fetch_all(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(rows
-            .into_iter()
-            .map(|r| InvoiceRecord {
-                id: r.get("id"),
-                organization_id: org_id,
-                invoice_number: r.get("invoice_number"),
-                status: r.get("status"),
-                total_cents: r.get("total_cents"),
-                period_start: r.get("period_start"),
-                period_end: r.get("period_end"),
-                created_at: r.get("created_at"),
-            })
-            .collect())
+        let mut catalog = PricingCatalog::new();
+        for row in &rows {
+            catalog.add_version(pricing_model_from_row(row)?)?;
+        }
+
+        let pins = sqlx::query("SELECT organization_id, model_id FROM pricing_pins")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        for pin in pins {
+            let org_id = OrganizationId::from_uuid(pin.get::<Uuid, _>("organization_id"));
+            catalog.pin(org_id, pin.get("model_id"))?;
+        }
+
+        Ok(catalog)
     }
 }
 
@@ -619,6 +2833,267 @@ impl InvoiceRepository for PgInvoiceRepository {
 // Helper Functions
 // ─────────────────────────────────────────────────────────────────────────────
 
+/// A selection's aliases as parallel arrays, for `UNNEST` in event queries.
+struct AliasBounds {
+    old_codes: Vec<String>,
+    effective_from: Vec<Option<DateTime<Utc>>>,
+    effective_until: Vec<Option<DateTime<Utc>>>,
+}
+
+impl AliasBounds {
+    fn of(selection: &UsageSelection) -> Self {
+        Self {
+            old_codes: selection
+                .aliases
+                .iter()
+                .map(|a| a.old_code.clone())
+                .collect(),
+            effective_from: selection.aliases.iter().map(|a| a.effective_from).collect(),
+            effective_until: selection
+                .aliases
+                .iter()
+                .map(|a| a.effective_until)
+                .collect(),
+        }
+    }
+}
+
+fn anomaly_baseline_from_row(row: &PgRow) -> Result<AnomalyBaseline, CretoError> {
+    let alerted_severity = row
+        .get::<Option<String>, _>("alerted_severity")
+        .map(|s| {
+            AnomalySeverity::from_db_str(&s)
+                .ok_or_else(|| CretoError::Database(format!("Unknown anomaly severity: {}", s)))
+        })
+        .transpose()?;
+
+    Ok(AnomalyBaseline {
+        organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+        agent_id: AgentId::from_uuid(row.get::<Uuid, _>("agent_id")),
+        metric_code: row.get("metric_code"),
+        mean: row.get("mean"),
+        variance: row.get("variance"),
+        windows: row.get::<i64, _>("windows") as u64,
+        window_start: row.get("window_start"),
+        window_quantity: row.get("window_quantity"),
+        suppressed_until: row.get("suppressed_until"),
+        alerted_severity,
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn pricing_model_from_row(row: &PgRow) -> Result<PricingModel, CretoError> {
+    let strategy = serde_json::from_value(row.get("strategy"))
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+    Ok(PricingModel {
+        id: row.get("id"),
+        name: row.get("name"),
+        metric_code: row.get("metric_code"),
+        strategy,
+        currency: parse_currency(row.get("currency")),
+        version: row.get::<i32, _>("version") as u32,
+        effective_from: row.get("effective_from"),
+        effective_until: row.get("effective_until"),
+        supersedes: row.get("supersedes"),
+    })
+}
+
+fn api_key_from_row(row: &PgRow) -> ApiKey {
+    ApiKey {
+        id: row.get("id"),
+        organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+        key_hash: row.get("key_hash"),
+        created_at: row.get("created_at"),
+        expires_at: row.get("expires_at"),
+        revoked_at: row.get("revoked_at"),
+    }
+}
+
+fn line_item_from_row(row: &PgRow) -> Result<LineItem, CretoError> {
+    let currency = parse_currency(row.get::<&str, _>("currency"));
+    let metadata: LineItemMetadata = serde_json::from_value(row.get("metadata"))
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+    let conversion = row
+        .get::<Option<serde_json::Value>, _>("conversion")
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+    let source = row
+        .get::<Option<serde_json::Value>, _>("source")
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+    Ok(LineItem {
+        id: row.get("id"),
+        description: row.get("description"),
+        metric_code: metadata.metric_code,
+        quantity: row.get("quantity"),
+        unit: metadata.unit,
+        unit_price: Money::new(row.get("unit_amount_cents"), currency),
+        amount: Money::new(row.get("amount_cents"), currency),
+        conversion,
+        source,
+        pricing: metadata.pricing,
+    })
+}
+
+fn late_event_from_row(row: &PgRow) -> Result<LateEvent, CretoError> {
+    let status_str: String = row.get("status");
+    let status = LateEventStatus::from_db_str(&status_str).ok_or_else(|| {
+        CretoError::Database(format!("Unknown late event status: {}", status_str))
+    })?;
+    let event = serde_json::from_value(row.get("event"))
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+    Ok(LateEvent {
+        id: row.get("id"),
+        event,
+        watermark: row.get("watermark"),
+        received_at: row.get("received_at"),
+        status,
+        resolved_at: row.get("resolved_at"),
+    })
+}
+
+fn quota_exemption_from_row(row: &PgRow) -> Result<QuotaExemption, CretoError> {
+    let allowance = if row.get::<bool, _>("unlimited") {
+        ExemptionAllowance::Unlimited
+    } else {
+        let multiplier: Option<f64> = row.get("multiplier");
+        ExemptionAllowance::Multiplier(multiplier.ok_or_else(|| {
+            CretoError::Database("Quota exemption has neither multiplier nor unlimited".to_string())
+        })?)
+    };
+
+    Ok(QuotaExemption {
+        id: row.get("id"),
+        organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+        agent_id: row
+            .get::<Option<Uuid>, _>("agent_id")
+            .map(AgentId::from_uuid),
+        metric_codes: row.get("metric_codes"),
+        starts_at: row.get("starts_at"),
+        ends_at: row.get("ends_at"),
+        allowance,
+        reason: row.get("reason"),
+        approved_by: row.get("approved_by"),
+        created_at: row.get("created_at"),
+    })
+}
+
+fn reservation_from_row(row: &PgRow) -> Result<Reservation, CretoError> {
+    let status: String = row.get("status");
+    let status = ReservationStatus::from_db_str(&status)
+        .ok_or_else(|| CretoError::Database(format!("Unknown reservation status: {}", status)))?;
+
+    Ok(Reservation {
+        id: row.get("id"),
+        organization_id: row.get("organization_id"),
+        agent_id: row.get("agent_id"),
+        metric_code: row.get("metric_code"),
+        reserved_amount: row.get("reserved_amount"),
+        actual_amount: row.get("actual_amount"),
+        created_at: row.get("created_at"),
+        expires_at: row.get("expires_at"),
+        status,
+        metadata: row.get("metadata"),
+        group_id: row.get("group_id"),
+    })
+}
+
+fn invoice_adjustment_from_row(row: &PgRow) -> Result<InvoiceAdjustment, CretoError> {
+    let status_str: String = row.get("status");
+    let status = AdjustmentStatus::from_db_str(&status_str).ok_or_else(|| {
+        CretoError::Database(format!("Unknown adjustment status: {}", status_str))
+    })?;
+
+    Ok(InvoiceAdjustment {
+        id: row.get("id"),
+        invoice_id: row.get("invoice_id"),
+        organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+        line_item_id: row.get("line_item_id"),
+        amount_cents: row.get("amount_cents"),
+        reason: row.get("reason"),
+        created_by: row.get("created_by"),
+        status,
+        reviewed_by: row.get("reviewed_by"),
+        rejection_reason: row.get("rejection_reason"),
+        credit_note_id: row.get("credit_note_id"),
+        created_at: row.get("created_at"),
+        reviewed_at: row.get("reviewed_at"),
+        applied_at: row.get("applied_at"),
+    })
+}
+
+fn auto_top_up_rule_from_row(row: &PgRow) -> Result<AutoTopUpRule, CretoError> {
+    let period_str: String = row.get("period");
+    let period = TopUpPeriod::from_db_str(&period_str)
+        .ok_or_else(|| CretoError::Database(format!("Unknown top-up period: {}", period_str)))?;
+
+    Ok(AutoTopUpRule {
+        wallet_id: row.get("wallet_id"),
+        organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+        threshold_cents: row.get("threshold_cents"),
+        top_up_amount_cents: row.get("top_up_amount_cents"),
+        max_top_ups_per_period: row.get::<i32, _>("max_top_ups_per_period") as u32,
+        period,
+        funding_source: row.get("funding_source"),
+        enabled: row.get("enabled"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
+fn top_up_attempt_from_row(row: &PgRow) -> Result<TopUpAttempt, CretoError> {
+    let status_str: String = row.get("status");
+    let status = TopUpStatus::from_db_str(&status_str)
+        .ok_or_else(|| CretoError::Database(format!("Unknown top-up status: {}", status_str)))?;
+
+    Ok(TopUpAttempt {
+        id: row.get("id"),
+        wallet_id: row.get("wallet_id"),
+        organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+        idempotency_key: row.get("idempotency_key"),
+        period_key: row.get("period_key"),
+        sequence: row.get::<i32, _>("sequence") as u32,
+        amount_cents: row.get("amount_cents"),
+        status,
+        payment_id: row.get("payment_id"),
+        transaction_id: row.get("transaction_id"),
+        error: row.get("error"),
+        created_at: row.get("created_at"),
+        claimed_at: row.get("claimed_at"),
+        completed_at: row.get("completed_at"),
+    })
+}
+
+fn usage_event_from_row(row: &PgRow) -> Result<UsageEvent, CretoError> {
+    let event_type_str: String = row.get("event_type");
+    let event_type = UsageEventType::from_db_str(&event_type_str)
+        .ok_or_else(|| CretoError::Database(format!("Unknown event type: {}", event_type_str)))?;
+
+    Ok(UsageEvent {
+        transaction_id: row.get("transaction_id"),
+        organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+        agent_id: AgentId::from_uuid(row.get::<Uuid, _>("agent_id")),
+        external_subscription_id: row.get("external_subscription_id"),
+        event_type,
+        code: row.get("code"),
+        quantity: row.get("quantity"),
+        timestamp: row.get("timestamp"),
+        properties: row.get("properties"),
+        delegation_depth: row.get::<i16, _>("delegation_depth") as u8,
+        correlation_id: row.get("correlation_id"),
+        caused_by: row.get("caused_by"),
+    })
+}
+
+fn parse_currency(s: &str) -> Currency {
+    Currency::from_code(s).unwrap_or_default()
+}
+
 fn parse_period(s: &str) -> QuotaPeriod {
     match s {
         "hourly" => QuotaPeriod::Hourly,
@@ -648,6 +3123,36 @@ mod tests {
         assert_eq!(QuotaPeriod::Monthly.as_str(), "monthly");
     }
 
+    #[test]
+    fn test_reservation_status_roundtrip() {
+        for status in [
+            ReservationStatus::Active,
+            ReservationStatus::Committed,
+            ReservationStatus::Released,
+            ReservationStatus::Expired,
+        ] {
+            assert_eq!(
+                ReservationStatus::from_db_str(status.as_db_str()),
+                Some(status)
+            );
+        }
+        assert_eq!(ReservationStatus::from_db_str("pending"), None);
+    }
+
+    #[test]
+    fn test_invoice_status_roundtrip() {
+        for status in [
+            InvoiceStatus::Draft,
+            InvoiceStatus::Issued,
+            InvoiceStatus::Paid,
+            InvoiceStatus::Failed,
+            InvoiceStatus::Voided,
+        ] {
+            assert_eq!(InvoiceStatus::from_db_str(status.as_db_str()), Some(status));
+        }
+        assert_eq!(InvoiceStatus::from_db_str("sent"), None);
+    }
+
     #[test]
     fn test_parse_period() {
         assert_eq!(parse_period("hourly"), QuotaPeriod::Hourly);
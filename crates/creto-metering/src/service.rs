@@ -6,16 +6,48 @@
 //! - Invoice generation with credits application
 //! - Pricing management
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, CretoResult, OrganizationId};
+use creto_common::{AgentId, CretoResult, OrganizationId, ShutdownCoordinator};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
 
 use crate::{
-    aggregation::AggregationEngine,
+    adjustments::{
+        AdjustmentApplication, AdjustmentApprover, AdjustmentDecision,
+        InMemoryInvoiceAdjustmentRepository, InvoiceAdjustment, InvoiceAdjustmentManager,
+    },
+    aggregation::{AggregationEngine, UsageSelection},
+    aliases::{MetricAlias, MetricAliasRegistry},
+    anomaly::AnomalyDetector,
+    api_keys::{
+        ApiKey, ApiKeyError, ApiKeyManager, AuthenticatedKey, InMemoryApiKeyRepository,
+        IssuedApiKey,
+    },
     credits::{CreditApplication, CreditManager},
-    events::UsageEvent,
-    invoice::{Invoice, InvoiceGenerator, UsageAggregation},
-    pricing::{PricingEngine, PricingModel},
-    quota::{Quota, QuotaCheckResult, QuotaEnforcer, QuotaPeriod},
+    currency::{CurrencyError, OrgBillingProfile},
+    drilldown::{self, EventPagination, LineItemEvents},
+    events::{InMemoryEventRepository, UsageEvent},
+    invoice::{
+        InMemoryInvoiceRepository, Invoice, InvoiceGenerator, InvoicePreview, LineItemSource,
+        ReconciliationReport, UsageAggregation, CREDITS_DISCOUNT_CODE,
+    },
+    late_events::{
+        Admitted, InMemoryLateEventRepository, LateEvent, LateEventPolicy, LateEventQueue,
+        LateEventResolution, WatermarkConfig,
+    },
+    pricing::{PricingEngine, PricingModel, PricingSegment},
+    quota::{
+        PendingRollovers, Quota, QuotaCheckResult, QuotaDenialReason, QuotaEnforcer, QuotaPeriod,
+        QuotaReconciler, QuotaReconciliationReport,
+    },
+    repository::{
+        ApiKeyRepository, EventRepository, InvoiceAdjustmentRepository, InvoiceRepository,
+        LateEventRepository, PricingModelRepository, QuotaRepository,
+    },
+    sampling::{IngestionSampler, SamplingRule},
 };
 
 /// Main entry point for the metering system.
@@ -55,6 +87,60 @@ pub struct MeteringService {
 
     /// In-memory usage storage for aggregation (production: database).
     usage_records: std::sync::RwLock<Vec<UsageRecord>>,
+
+    /// Billing profiles by organization (production: BillingProfileRepository).
+    billing_profiles: std::sync::RwLock<HashMap<OrganizationId, OrgBillingProfile>>,
+
+    /// API keys for the gRPC edge (production: PgApiKeyRepository).
+    api_keys: Arc<ApiKeyManager<dyn ApiKeyRepository>>,
+
+    /// Watermarks and queued late events (production: PgLateEventRepository).
+    late_events: Arc<LateEventQueue<dyn LateEventRepository>>,
+
+    /// Recorded usage events for line item drill-down and quota
+    /// reconciliation (production: PgEventRepository).
+    usage_events: Arc<dyn EventRepository>,
+
+    /// Generated invoices (production: PgInvoiceRepository).
+    invoices: Arc<dyn InvoiceRepository>,
+
+    /// Invoice adjustments, stored with the invoices they change
+    /// (production: PgInvoiceAdjustmentRepository).
+    adjustments: InvoiceAdjustmentManager<dyn InvoiceAdjustmentRepository>,
+
+    /// Serializes this process's adjustments so concurrent ones don't
+    /// overwrite each other's invoice changes. Across processes the
+    /// repository's conditional status updates keep an adjustment from
+    /// being resolved or applied twice.
+    adjustment_lock: tokio::sync::Mutex<()>,
+
+    /// Usage anomaly detection on recorded events (None = disabled).
+    anomaly_detector: Option<Arc<AnomalyDetector>>,
+
+    /// Reconciles quota counters against recorded events.
+    quota_reconciler: QuotaReconciler,
+
+    /// Persisted quota counters, reconciled alongside the enforcer's
+    /// (production: PgQuotaRepository; None = enforcer counters only).
+    quota_repository: Option<Arc<dyn QuotaRepository>>,
+
+    /// Quotas rolled over by the enforcer and not yet written to the
+    /// quota repository.
+    pending_rollovers: Arc<PendingRollovers>,
+
+    /// Stores pricing versions and pins written through the service
+    /// (production: PgPricingModelRepository; None = in-memory catalog only).
+    pricing_repository: Option<Arc<dyn PricingModelRepository>>,
+
+    /// Thins the stored event stream of high-volume metrics.
+    sampler: Arc<IngestionSampler>,
+
+    /// Aliases of renamed metric codes, shared with the quota enforcer and
+    /// pricing engine.
+    metric_aliases: Arc<MetricAliasRegistry>,
+
+    /// Whether ingested events are stored under their canonical code.
+    rewrite_aliased_codes: bool,
 }
 
 /// Internal usage record for aggregation.
@@ -64,34 +150,189 @@ struct UsageRecord {
     /// Kept for per-agent billing breakdown.
     #[allow(dead_code)]
     agent_id: AgentId,
-    metric_code: String,
-    quantity: i64,
-    timestamp: DateTime<Utc>,
+    event: UsageEvent,
+    /// Set for late usage folded in after the fact; billed at this time
+    /// rather than at the event timestamp.
+    correction_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of [`MeteringService::ingest`].
+#[derive(Debug, Clone)]
+pub enum IngestOutcome {
+    /// Recorded against quota and the event's billing period.
+    Accepted,
+    /// Arrived behind the watermark and was queued for resolution.
+    AcceptedLate(Box<LateEvent>),
 }
 
 impl MeteringService {
     /// Create a new metering service with default configuration.
     pub fn new() -> Self {
+        let sampler = Arc::new(IngestionSampler::new());
+        let metric_aliases = Arc::new(MetricAliasRegistry::new());
+        let invoices = Arc::new(InMemoryInvoiceRepository::new());
         Self {
-            quota_enforcer: QuotaEnforcer::new(),
+            quota_enforcer: QuotaEnforcer::new().with_metric_aliases(Arc::clone(&metric_aliases)),
             aggregation_engine: AggregationEngine::new(),
-            pricing_engine: PricingEngine::new(),
+            pricing_engine: PricingEngine::new().with_metric_aliases(Arc::clone(&metric_aliases)),
             invoice_generator: InvoiceGenerator::new(),
             credit_manager: CreditManager::new(),
             usage_records: std::sync::RwLock::new(Vec::new()),
+            billing_profiles: std::sync::RwLock::new(HashMap::new()),
+            api_keys: Arc::new(ApiKeyManager::new(
+                Arc::new(InMemoryApiKeyRepository::new()) as Arc<dyn ApiKeyRepository>,
+            )),
+            late_events: Arc::new(LateEventQueue::new(
+                Arc::new(InMemoryLateEventRepository::new()) as Arc<dyn LateEventRepository>,
+                WatermarkConfig::default(),
+            )),
+            usage_events: Arc::new(InMemoryEventRepository::new()),
+            invoices: Arc::clone(&invoices) as Arc<dyn InvoiceRepository>,
+            adjustments: InvoiceAdjustmentManager::new(Arc::new(
+                InMemoryInvoiceAdjustmentRepository::new(invoices),
+            )
+                as Arc<dyn InvoiceAdjustmentRepository>),
+            adjustment_lock: tokio::sync::Mutex::new(()),
+            anomaly_detector: None,
+            quota_reconciler: QuotaReconciler::default().with_sampler(Arc::clone(&sampler)),
+            quota_repository: None,
+            pending_rollovers: Arc::new(PendingRollovers::default()),
+            pricing_repository: None,
+            sampler,
+            metric_aliases,
+            rewrite_aliased_codes: false,
         }
     }
 
     /// Create with custom invoice configuration.
     pub fn with_invoice_config(due_days: i64, tax_rate: f64) -> Self {
+        let sampler = Arc::new(IngestionSampler::new());
+        let metric_aliases = Arc::new(MetricAliasRegistry::new());
+        let invoices = Arc::new(InMemoryInvoiceRepository::new());
         Self {
-            quota_enforcer: QuotaEnforcer::new(),
+            quota_enforcer: QuotaEnforcer::new().with_metric_aliases(Arc::clone(&metric_aliases)),
             aggregation_engine: AggregationEngine::new(),
-            pricing_engine: PricingEngine::new(),
+            pricing_engine: PricingEngine::new().with_metric_aliases(Arc::clone(&metric_aliases)),
             invoice_generator: InvoiceGenerator::with_config(due_days, tax_rate),
             credit_manager: CreditManager::new(),
             usage_records: std::sync::RwLock::new(Vec::new()),
+            billing_profiles: std::sync::RwLock::new(HashMap::new()),
+            api_keys: Arc::new(ApiKeyManager::new(
+                Arc::new(InMemoryApiKeyRepository::new()) as Arc<dyn ApiKeyRepository>,
+            )),
+            late_events: Arc::new(LateEventQueue::new(
+                Arc::new(InMemoryLateEventRepository::new()) as Arc<dyn LateEventRepository>,
+                WatermarkConfig::default(),
+            )),
+            usage_events: Arc::new(InMemoryEventRepository::new()),
+            invoices: Arc::clone(&invoices) as Arc<dyn InvoiceRepository>,
+            adjustments: InvoiceAdjustmentManager::new(Arc::new(
+                InMemoryInvoiceAdjustmentRepository::new(invoices),
+            )
+                as Arc<dyn InvoiceAdjustmentRepository>)
+            .with_tax_rate(tax_rate),
+            adjustment_lock: tokio::sync::Mutex::new(()),
+            anomaly_detector: None,
+            quota_reconciler: QuotaReconciler::default().with_sampler(Arc::clone(&sampler)),
+            quota_repository: None,
+            pending_rollovers: Arc::new(PendingRollovers::default()),
+            pricing_repository: None,
+            sampler,
+            metric_aliases,
+            rewrite_aliased_codes: false,
+        }
+    }
+
+    /// Store recorded usage events in `repository` instead of in memory.
+    ///
+    /// Set before any usage is recorded; events already stored are not
+    /// copied over.
+    pub fn with_event_repository(mut self, repository: Arc<dyn EventRepository>) -> Self {
+        self.usage_events = repository;
+        self
+    }
+
+    /// Keep API keys in `repository` instead of in memory.
+    pub fn with_api_key_repository(mut self, repository: Arc<dyn ApiKeyRepository>) -> Self {
+        self.api_keys = Arc::new(ApiKeyManager::new(repository));
+        self
+    }
+
+    /// Queue late events in `repository` instead of in memory.
+    pub fn with_late_event_repository(
+        mut self,
+        repository: Arc<dyn LateEventRepository>,
+        config: WatermarkConfig,
+    ) -> Self {
+        self.late_events = Arc::new(LateEventQueue::new(repository, config));
+        self
+    }
+
+    /// Store generated invoices and their adjustments in these repositories
+    /// instead of in memory.
+    ///
+    /// `adjustments` stores adjusted invoices and credit notes itself, so it
+    /// must write where `invoices` reads: for PostgreSQL, the same database.
+    pub fn with_invoice_repositories(
+        mut self,
+        invoices: Arc<dyn InvoiceRepository>,
+        adjustments: Arc<dyn InvoiceAdjustmentRepository>,
+    ) -> Self {
+        self.invoices = invoices;
+        self.adjustments = InvoiceAdjustmentManager::new(adjustments)
+            .with_tax_rate(self.invoice_generator.tax_rate());
+        self
+    }
+
+    /// Feed recorded usage to an anomaly detector.
+    pub fn with_anomaly_detector(mut self, detector: Arc<AnomalyDetector>) -> Self {
+        self.anomaly_detector = Some(detector);
+        self
+    }
+
+    /// Set how quota counters are reconciled against recorded events.
+    ///
+    /// Quotas on sampled metrics are always skipped.
+    pub fn with_quota_reconciler(mut self, reconciler: QuotaReconciler) -> Self {
+        self.quota_reconciler = reconciler.with_sampler(Arc::clone(&self.sampler));
+        self
+    }
+
+    /// Also reconcile the quota counters persisted in `repository`, and
+    /// write every quota period the enforcer starts to it.
+    pub fn with_quota_repository(mut self, repository: Arc<dyn QuotaRepository>) -> Self {
+        if self.quota_repository.is_none() {
+            self.quota_enforcer = self
+                .quota_enforcer
+                .with_rollover_listener(self.pending_rollovers.clone());
         }
+        self.quota_repository = Some(repository);
+        self
+    }
+
+    /// Write pricing versions, pins and migrations through to `repository`.
+    ///
+    /// Call [`MeteringService::load_pricing`] to price from what it already
+    /// holds.
+    pub fn with_pricing_repository(mut self, repository: Arc<dyn PricingModelRepository>) -> Self {
+        self.pricing_repository = Some(repository);
+        self
+    }
+
+    /// Store ingested events under their canonical metric code, keeping the
+    /// code they arrived with in
+    /// [`ALIASED_FROM_PROPERTY`](crate::aliases::ALIASED_FROM_PROPERTY).
+    ///
+    /// Off by default: events are stored as they arrive and aliases are
+    /// resolved when usage is aggregated, limited and priced.
+    pub fn with_alias_rewrite(mut self, enabled: bool) -> Self {
+        self.rewrite_aliased_codes = enabled;
+        self
+    }
+
+    /// Get the anomaly detector, if enabled.
+    pub fn anomaly_detector(&self) -> Option<&Arc<AnomalyDetector>> {
+        self.anomaly_detector.as_ref()
     }
 
     // ─────────────────────────────────────────────────────────────────────────
@@ -132,6 +373,166 @@ impl MeteringService {
             })
     }
 
+    /// Recompute usage for every active quota from recorded events and
+    /// report, or correct, counters that have drifted.
+    pub async fn reconcile_quotas(&self) -> CretoResult<QuotaReconciliationReport> {
+        self.reconcile_quotas_at(Utc::now()).await
+    }
+
+    /// Like [`Self::reconcile_quotas`], summing events up to `snapshot_at`.
+    ///
+    /// Counters charged at or after `snapshot_at` are skipped.
+    pub async fn reconcile_quotas_at(
+        &self,
+        snapshot_at: DateTime<Utc>,
+    ) -> CretoResult<QuotaReconciliationReport> {
+        match &self.quota_repository {
+            Some(quotas) => {
+                self.quota_reconciler
+                    .run_with_repository(
+                        &self.quota_enforcer,
+                        &**quotas,
+                        &*self.usage_events,
+                        snapshot_at,
+                    )
+                    .await
+            }
+            None => {
+                self.quota_reconciler
+                    .run(&self.quota_enforcer, &*self.usage_events, snapshot_at)
+                    .await
+            }
+        }
+    }
+
+    /// Spawn a background task reconciling quotas every `interval`.
+    ///
+    /// The task is registered with `shutdown` and stops between runs.
+    pub fn spawn_quota_reconciler(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+        interval: std::time::Duration,
+    ) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        shutdown.spawn("metering.quota_reconciler", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    if let Err(e) = service.reconcile_quotas().await {
+                        tracing::error!(error = %e, "Quota reconciliation failed");
+                    }
+                }
+            })
+        })
+    }
+
+    /// Start a new period for every quota whose period has ended, and write
+    /// every period started since the last call to the quota repository
+    /// with [`QuotaRepository::start_next_period`].
+    ///
+    /// Returns the quotas written. Without a repository, quotas are only
+    /// rolled over.
+    pub async fn roll_over_quotas(&self) -> CretoResult<Vec<Quota>> {
+        self.quota_enforcer.roll_over_expired(Utc::now());
+        self.persist_quota_rollovers().await
+    }
+
+    /// Spawn a background task rolling quotas over every `interval`.
+    ///
+    /// The task is registered with `shutdown` and stops between runs.
+    pub fn spawn_quota_rollover(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+        interval: std::time::Duration,
+    ) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        shutdown.spawn("metering.quota_rollover", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    if let Err(e) = service.roll_over_quotas().await {
+                        tracing::error!(error = %e, "Quota rollover failed");
+                    }
+                }
+            })
+        })
+    }
+
+    /// Write queued rollovers to the quota repository, keeping those not
+    /// written for the next call.
+    async fn persist_quota_rollovers(&self) -> CretoResult<Vec<Quota>> {
+        let Some(repository) = &self.quota_repository else {
+            return Ok(Vec::new());
+        };
+        let mut pending = self.pending_rollovers.take().into_iter();
+        let mut written = Vec::new();
+        while let Some(quota) = pending.next() {
+            match repository.start_next_period(&quota).await {
+                Ok(stored) => written.push(stored),
+                Err(e) => {
+                    self.pending_rollovers
+                        .requeue(std::iter::once(quota).chain(pending).collect());
+                    return Err(e);
+                }
+            }
+        }
+        Ok(written)
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Metric Aliases
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Register an alias of a renamed metric code.
+    ///
+    /// See [`MetricAliasRegistry::register`]. An alias in effect now moves
+    /// the old code's quotas, with their usage, to the canonical code, both
+    /// in the enforcer and in the quota repository if one is configured.
+    pub async fn register_metric_alias(&self, alias: MetricAlias) -> CretoResult<()> {
+        let (old_code, canonical_code) = (alias.old_code.clone(), alias.canonical_code.clone());
+        let in_effect = alias.covers(Utc::now());
+        self.metric_aliases.register(alias)?;
+        if !in_effect {
+            return Ok(());
+        }
+
+        let migrated = self.quota_enforcer.migrate_aliased_quotas();
+        if let Some(repository) = &self.quota_repository {
+            repository
+                .migrate_metric_code(&old_code, &canonical_code)
+                .await?;
+        }
+        if !migrated.is_empty() {
+            tracing::info!(
+                old_code = %old_code,
+                canonical_code = %canonical_code,
+                quotas = migrated.len(),
+                "Moved quotas to the canonical metric code"
+            );
+        }
+        Ok(())
+    }
+
+    /// Remove a metric alias, returning it if it was registered.
+    pub fn remove_metric_alias(&self, id: Uuid) -> Option<MetricAlias> {
+        self.metric_aliases.remove(id)
+    }
+
+    /// Shared alias registry, for loading aliases from a
+    /// [`MetricAliasRepository`](crate::repository::MetricAliasRepository).
+    pub fn metric_aliases(&self) -> Arc<MetricAliasRegistry> {
+        Arc::clone(&self.metric_aliases)
+    }
+
+    /// Rewrite an event to its canonical code if rewriting is enabled.
+    fn canonicalize(&self, event: UsageEvent) -> UsageEvent {
+        if self.rewrite_aliased_codes {
+            self.metric_aliases.rewrite(event)
+        } else {
+            event
+        }
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Event Ingestion
     // ─────────────────────────────────────────────────────────────────────────
@@ -143,16 +544,24 @@ impl MeteringService {
     /// # Performance
     ///
     /// Target: <10µs for the quota check portion.
-    pub fn check_and_record(
+    pub async fn check_and_record(
         &self,
         organization_id: OrganizationId,
         agent_id: AgentId,
         event: UsageEvent,
     ) -> CretoResult<()> {
+        let event = self.canonicalize(event);
+
         // 1. Check quota (fast path - sync, <10µs target)
         let result = self
             .quota_enforcer
-            .check(&organization_id, &agent_id, &event.code, event.quantity)
+            .check_with_context(
+                &organization_id,
+                &agent_id,
+                &event.code,
+                event.quantity,
+                Some(event.delegation_depth),
+            )
             .map_err(|_e| creto_common::CretoError::QuotaExceeded {
                 resource: event.code.clone(),
                 used: 0,
@@ -160,6 +569,14 @@ impl MeteringService {
             })?;
 
         if !result.allowed {
+            if let Some(reason @ QuotaDenialReason::DelegationDepthExceeded { .. }) =
+                result.denial_reason
+            {
+                return Err(creto_common::CretoError::InvalidUsageEvent(format!(
+                    "{}: {}",
+                    event.code, reason
+                )));
+            }
             return Err(creto_common::CretoError::QuotaExceeded {
                 resource: event.code.clone(),
                 used: result.current_usage as u64,
@@ -167,54 +584,309 @@ impl MeteringService {
             });
         }
 
-        // 2. Record usage in quota enforcer (sync)
+        // 2. Record usage in quota enforcer (sync), charged at the event's delegation depth
         self.quota_enforcer
-            .record_usage(&organization_id, &agent_id, &event.code, event.quantity)
+            .record_usage_with_context(
+                &organization_id,
+                &agent_id,
+                &event.code,
+                event.quantity,
+                Some(event.delegation_depth),
+            )
             .map_err(|_e| creto_common::CretoError::QuotaExceeded {
                 resource: event.code.clone(),
                 used: 0,
                 limit: 0,
             })?;
 
-        // 3. Store for aggregation
-        let record = UsageRecord {
-            organization_id,
-            agent_id,
-            metric_code: event.code,
-            quantity: event.quantity,
-            timestamp: event.timestamp,
-        };
-        self.usage_records.write().unwrap().push(record);
+        // 3. Store for aggregation, sampled now that quota has seen it in full
+        self.store_usage(organization_id, agent_id, event).await?;
 
+        // 4. Persist a period the check started; retried on the next call
+        if let Err(e) = self.persist_quota_rollovers().await {
+            tracing::warn!(error = %e, "Failed to persist quota rollover");
+        }
         Ok(())
     }
 
+    /// Ingest an event, diverting it to the late event queue if it arrived
+    /// behind the watermark.
+    ///
+    /// Late events are not charged against quota; they are billed, or
+    /// dropped, when [`MeteringService::resolve_late_events`] runs.
+    pub async fn ingest(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        event: UsageEvent,
+    ) -> CretoResult<IngestOutcome> {
+        match self.late_events.admit(self.canonicalize(event)).await? {
+            Admitted::OnTime(event) => {
+                self.check_and_record(organization_id, agent_id, event)
+                    .await?;
+                Ok(IngestOutcome::Accepted)
+            }
+            Admitted::Late(late_event) => Ok(IngestOutcome::AcceptedLate(Box::new(late_event))),
+        }
+    }
+
     /// Record usage without quota check (for pre-authorized operations).
-    pub fn record_usage(
+    pub async fn record_usage(
         &self,
         organization_id: OrganizationId,
         agent_id: AgentId,
         event: UsageEvent,
-    ) {
+    ) -> CretoResult<()> {
+        self.store_usage(organization_id, agent_id, self.canonicalize(event))
+            .await
+    }
+
+    /// Store an event for aggregation. An event whose transaction ID is
+    /// already stored is ignored, so invoices and drill-down agree on the
+    /// events billed.
+    async fn store_usage(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        event: UsageEvent,
+    ) -> CretoResult<()> {
+        let Some(event) = self.sampler.sample(event) else {
+            return Ok(());
+        };
+        if !self.usage_events.insert_event(&event).await? {
+            return Ok(());
+        }
+        if let Some(detector) = &self.anomaly_detector {
+            detector.observe(&event);
+        }
         let record = UsageRecord {
             organization_id,
             agent_id,
-            metric_code: event.code,
-            quantity: event.quantity,
-            timestamp: event.timestamp,
+            event,
+            correction_at: None,
         };
         self.usage_records.write().unwrap().push(record);
+        Ok(())
+    }
+
+    /// Find recorded usage events sharing a correlation ID, in recording order.
+    pub fn find_by_correlation(&self, correlation_id: Uuid) -> Vec<UsageEvent> {
+        self.usage_records
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.event.correlation_id == Some(correlation_id))
+            .map(|r| r.event.clone())
+            .collect()
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Ingestion Sampling
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Sample an organization's metric before storage. Quota enforcement
+    /// still sees every event.
+    pub fn set_sampling_rule(&self, rule: SamplingRule) -> CretoResult<()> {
+        self.sampler.set_rule(rule)
+    }
+
+    /// Stop sampling an organization's metric.
+    pub fn remove_sampling_rule(
+        &self,
+        organization_id: OrganizationId,
+        code: &str,
+    ) -> Option<SamplingRule> {
+        self.sampler.remove_rule(organization_id, code)
+    }
+
+    /// Get the ingestion sampler, for reloading rules in bulk.
+    pub fn ingestion_sampler(&self) -> Arc<IngestionSampler> {
+        Arc::clone(&self.sampler)
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // Late Events
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Set how an organization's late events are resolved.
+    pub fn set_late_event_policy(&self, organization_id: OrganizationId, policy: LateEventPolicy) {
+        self.late_events.set_policy(organization_id, policy);
+    }
+
+    /// List an organization's unresolved late events.
+    pub async fn pending_late_events(
+        &self,
+        organization_id: OrganizationId,
+    ) -> CretoResult<Vec<LateEvent>> {
+        self.late_events.pending(organization_id).await
+    }
+
+    /// Resolve an organization's pending late events.
+    ///
+    /// Under [`LateEventPolicy::FoldIntoNextInvoice`] the events are recorded
+    /// as corrections billed at resolution time, so they appear as separate
+    /// line items on the next invoice instead of reopening a closed period.
+    pub async fn resolve_late_events(
+        &self,
+        organization_id: OrganizationId,
+    ) -> CretoResult<LateEventResolution> {
+        let resolution = self
+            .late_events
+            .resolve(organization_id, Utc::now())
+            .await?;
+
+        if resolution.policy == LateEventPolicy::FoldIntoNextInvoice {
+            for late_event in &resolution.events {
+                if !self.usage_events.insert_event(&late_event.event).await? {
+                    continue;
+                }
+                self.usage_records.write().unwrap().push(UsageRecord {
+                    organization_id,
+                    agent_id: late_event.event.agent_id,
+                    event: late_event.event.clone(),
+                    correction_at: late_event.resolved_at,
+                });
+            }
+        }
+
+        Ok(resolution)
+    }
+
+    /// Shared late event queue, for wiring watermark checks onto the gRPC
+    /// ingestion path.
+    pub fn late_event_queue(&self) -> Arc<LateEventQueue<dyn LateEventRepository>> {
+        self.late_events.clone()
     }
 
     // ─────────────────────────────────────────────────────────────────────────
     // Pricing Management
     // ─────────────────────────────────────────────────────────────────────────
 
-    /// Register a pricing model.
+    /// Register a pricing model, replacing every version of its metric.
     pub fn register_pricing_model(&mut self, model: PricingModel) {
         self.invoice_generator.register_pricing_model(model);
     }
 
+    /// Replace the pricing catalog with the versions and pins held by the
+    /// pricing repository. Does nothing without one.
+    pub async fn load_pricing(&mut self) -> CretoResult<()> {
+        if let Some(repository) = &self.pricing_repository {
+            let catalog = repository.load_catalog().await?;
+            self.invoice_generator.set_pricing_catalog(catalog);
+        }
+        Ok(())
+    }
+
+    /// Add a pricing model version; overlapping effective ranges are rejected.
+    ///
+    /// Checked against the catalog before it is stored, and only applied to
+    /// the catalog once the pricing repository has accepted it.
+    pub async fn add_pricing_version(&mut self, model: PricingModel) -> CretoResult<()> {
+        let mut catalog = self.invoice_generator.pricing_catalog().clone();
+        catalog.add_version(model.clone())?;
+        if let Some(repository) = &self.pricing_repository {
+            repository.create_version(&model).await?;
+        }
+        self.invoice_generator.set_pricing_catalog(catalog);
+        Ok(())
+    }
+
+    /// Pin (grandfather) an organization to a pricing model version until it
+    /// is migrated with [`MeteringService::migrate_pricing`].
+    pub async fn pin_pricing_version(
+        &mut self,
+        organization_id: OrganizationId,
+        model_id: &str,
+    ) -> CretoResult<()> {
+        let mut catalog = self.invoice_generator.pricing_catalog().clone();
+        catalog.pin(organization_id, model_id)?;
+        if let Some(repository) = &self.pricing_repository {
+            repository.pin(organization_id, model_id).await?;
+        }
+        self.invoice_generator.set_pricing_catalog(catalog);
+        Ok(())
+    }
+
+    /// Migrate an organization off its pinned version of a metric, returning
+    /// the ID it was pinned to.
+    pub async fn migrate_pricing(
+        &mut self,
+        organization_id: OrganizationId,
+        metric_code: &str,
+    ) -> CretoResult<Option<String>> {
+        if let Some(repository) = &self.pricing_repository {
+            repository.migrate(organization_id, metric_code).await?;
+        }
+        Ok(self
+            .invoice_generator
+            .migrate_pricing(organization_id, metric_code))
+    }
+
+    /// Set the billing profile (and therefore billing currency) for an organization.
+    pub fn set_billing_profile(&self, profile: OrgBillingProfile) {
+        self.billing_profiles
+            .write()
+            .unwrap()
+            .insert(profile.organization_id, profile);
+    }
+
+    /// Get the billing profile for an organization, if one is set.
+    pub fn billing_profile(&self, organization_id: &OrganizationId) -> Option<OrgBillingProfile> {
+        self.billing_profiles
+            .read()
+            .unwrap()
+            .get(organization_id)
+            .cloned()
+    }
+
+    // ─────────────────────────────────────────────────────────────────────────
+    // API Key Management
+    // ─────────────────────────────────────────────────────────────────────────
+
+    /// Issue a new API key for an organization.
+    pub async fn create_api_key(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<IssuedApiKey, ApiKeyError> {
+        self.api_keys.create_key(organization_id).await
+    }
+
+    /// Rotate an API key, keeping the old one valid for `grace`.
+    pub async fn rotate_api_key(
+        &self,
+        key_id: Uuid,
+        grace: chrono::Duration,
+    ) -> Result<IssuedApiKey, ApiKeyError> {
+        self.api_keys.rotate_key(key_id, grace).await
+    }
+
+    /// Revoke an API key immediately.
+    pub async fn revoke_api_key(&self, key_id: Uuid) -> Result<(), ApiKeyError> {
+        self.api_keys.revoke_key(key_id).await
+    }
+
+    /// List an organization's API keys.
+    pub async fn list_api_keys(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<Vec<ApiKey>, ApiKeyError> {
+        self.api_keys.list_keys(organization_id).await
+    }
+
+    /// Resolve a plaintext API key to its organization.
+    pub async fn authenticate_api_key(
+        &self,
+        plaintext: &str,
+    ) -> Result<AuthenticatedKey, ApiKeyError> {
+        self.api_keys.authenticate(plaintext).await
+    }
+
+    /// Shared key manager, for wiring an
+    /// [`ApiKeyAuthLayer`](crate::grpc::ApiKeyAuthLayer) onto the gRPC server.
+    pub fn api_key_manager(&self) -> Arc<ApiKeyManager<dyn ApiKeyRepository>> {
+        self.api_keys.clone()
+    }
+
     // ─────────────────────────────────────────────────────────────────────────
     // Credit Management
     // ─────────────────────────────────────────────────────────────────────────
@@ -241,6 +913,10 @@ impl MeteringService {
     // ─────────────────────────────────────────────────────────────────────────
 
     /// Aggregate usage for a billing period.
+    ///
+    /// Usage is split at pricing model version changes, so each aggregation's
+    /// window is priced by exactly one version. Events recorded under an
+    /// aliased code are aggregated under the canonical code.
     pub fn aggregate_usage(
         &self,
         organization_id: &OrganizationId,
@@ -248,56 +924,137 @@ impl MeteringService {
         period_end: DateTime<Utc>,
     ) -> Vec<UsageAggregation> {
         let records = self.usage_records.read().unwrap();
+        let catalog = self.invoice_generator.pricing_catalog();
+
+        // Pricing segments of the period by metric code
+        let mut segments: HashMap<String, Vec<PricingSegment>> = HashMap::new();
 
-        // Group by metric code
-        let mut aggregations: std::collections::HashMap<String, i64> =
-            std::collections::HashMap::new();
+        // Group by metric code and pricing window, keeping late usage
+        // corrections separate
+        type Window = Option<(DateTime<Utc>, DateTime<Utc>)>;
+        let mut aggregations: HashMap<(String, bool, Window), (i64, u64)> = HashMap::new();
 
         for record in records.iter() {
+            let billed_at = record.correction_at.unwrap_or(record.event.timestamp);
             if &record.organization_id == organization_id
-                && record.timestamp >= period_start
-                && record.timestamp <= period_end
+                && billed_at >= period_start
+                && billed_at <= period_end
             {
-                *aggregations.entry(record.metric_code.clone()).or_insert(0) += record.quantity;
+                let code = self
+                    .metric_aliases
+                    .canonical_code(&record.event.code, record.event.timestamp);
+                let correction = record.correction_at.is_some();
+                let window = if correction {
+                    None
+                } else {
+                    segments
+                        .entry(code.clone())
+                        .or_insert_with(|| {
+                            catalog.segments(*organization_id, &code, period_start, period_end)
+                        })
+                        .iter()
+                        .find(|s| s.start <= billed_at && billed_at <= s.end)
+                        .map(|s| (s.start, s.end))
+                };
+
+                let (quantity, event_count) = aggregations
+                    .entry((code, correction, window))
+                    .or_insert((0, 0));
+                *quantity += record.event.quantity;
+                *event_count += 1;
             }
         }
 
         aggregations
             .into_iter()
-            .map(|(metric_code, quantity)| UsageAggregation {
-                metric_code: metric_code.clone(),
-                description: format!("{} usage", metric_code),
-                quantity,
-                unit: "units".to_string(),
-            })
+            .map(
+                |((metric_code, correction, window), (quantity, event_count))| {
+                    let (window_start, window_end) = window.unwrap_or((period_start, period_end));
+                    UsageAggregation {
+                        description: if correction {
+                            format!("{} late usage correction", metric_code)
+                        } else {
+                            format!("{} usage", metric_code)
+                        },
+                        // Corrections are billed by resolution time, not event
+                        // time, so no event window reproduces them
+                        source: (!correction).then(|| LineItemSource {
+                            selection: UsageSelection::new(
+                                *organization_id,
+                                metric_code.clone(),
+                                window_start,
+                                window_end,
+                            )
+                            .with_aliases(self.metric_aliases.aliases_of(&metric_code)),
+                            event_count,
+                        }),
+                        metric_code,
+                        quantity,
+                        unit: "units".to_string(),
+                        aggregated_at: Utc::now(),
+                    }
+                },
+            )
             .collect()
     }
 
     /// Generate an invoice for a billing period.
-    pub fn generate_invoice(
+    pub async fn generate_invoice(
         &self,
         organization_id: OrganizationId,
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
-    ) -> Invoice {
+    ) -> CretoResult<Invoice> {
         let aggregations = self.aggregate_usage(&organization_id, period_start, period_end);
 
-        self.invoice_generator.generate_from_aggregations(
+        let invoice = self.invoice_generator.generate_from_aggregations(
             organization_id,
             period_start,
             period_end,
             &aggregations,
-        )
+        )?;
+        self.store_invoice(&invoice).await?;
+        Ok(invoice)
+    }
+
+    /// Generate an invoice in the organization's billing currency.
+    ///
+    /// Organizations without a billing profile are billed in USD. Fails with
+    /// the [`CurrencyError`] as a validation error if usage is priced in a
+    /// currency that cannot be converted into the billing currency.
+    pub async fn generate_invoice_in_billing_currency(
+        &self,
+        organization_id: OrganizationId,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> CretoResult<Invoice> {
+        let billing_currency = self
+            .billing_profile(&organization_id)
+            .map(|p| p.billing_currency)
+            .unwrap_or_default();
+        let aggregations = self.aggregate_usage(&organization_id, period_start, period_end);
+
+        let invoice = self.invoice_generator.generate_in_currency(
+            organization_id,
+            billing_currency,
+            period_start,
+            period_end,
+            &aggregations,
+        )?;
+        self.store_invoice(&invoice).await?;
+        Ok(invoice)
     }
 
     /// Generate invoice and apply available credits.
-    pub fn generate_invoice_with_credits(
+    pub async fn generate_invoice_with_credits(
         &self,
         organization_id: OrganizationId,
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
-    ) -> (Invoice, CreditApplication) {
-        let mut invoice = self.generate_invoice(organization_id, period_start, period_end);
+    ) -> CretoResult<(Invoice, CreditApplication)> {
+        let mut invoice = self
+            .generate_invoice(organization_id, period_start, period_end)
+            .await?;
 
         // Apply credits
         let application = self
@@ -323,16 +1080,17 @@ impl MeteringService {
             invoice.apply_discount(credits_discount);
         }
 
-        (invoice, application)
+        self.store_invoice(&invoice).await?;
+        Ok((invoice, application))
     }
 
     /// Complete billing workflow: aggregate, price, apply credits, issue invoice.
-    pub fn run_billing_cycle(
+    pub async fn run_billing_cycle(
         &self,
         organization_id: OrganizationId,
         period_start: DateTime<Utc>,
         period_end: DateTime<Utc>,
-    ) -> BillingResult {
+    ) -> CretoResult<BillingResult> {
         // 1. Aggregate usage
         let aggregations = self.aggregate_usage(&organization_id, period_start, period_end);
 
@@ -342,7 +1100,7 @@ impl MeteringService {
             period_start,
             period_end,
             &aggregations,
-        );
+        )?;
 
         let subtotal = invoice.subtotal.amount;
 
@@ -361,7 +1119,7 @@ impl MeteringService {
 
         if credit_application.credits_applied > 0 {
             let credits_discount = crate::invoice::Discount {
-                code: "CREDITS_APPLIED".to_string(),
+                code: CREDITS_DISCOUNT_CODE.to_string(),
                 discount_type: crate::invoice::DiscountType::FixedAmount {
                     amount_cents: credit_application.credits_applied,
                 },
@@ -371,42 +1129,182 @@ impl MeteringService {
 
         // 4. Issue invoice
         invoice.issue(30); // Default 30-day payment terms
+        self.store_invoice(&invoice).await?;
 
-        BillingResult {
+        Ok(BillingResult {
             invoice,
             usage_count: aggregations.len(),
             subtotal_cents: subtotal,
             credits_applied: credit_application.credits_applied,
             amount_due: credit_application.remaining_to_invoice,
-        }
+        })
     }
-}
 
-impl Default for MeteringService {
-    fn default() -> Self {
-        Self::new()
+    /// Dry-run the billing cycle for a period.
+    ///
+    /// Prices usage in the organization's billing currency and applies the
+    /// current credit balance like [`MeteringService::run_billing_cycle`],
+    /// but nothing is issued and no credits are consumed.
+    pub fn preview_invoice(
+        &self,
+        organization_id: OrganizationId,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> Result<InvoicePreview, CurrencyError> {
+        let billing_currency = self
+            .billing_profile(&organization_id)
+            .map(|p| p.billing_currency)
+            .unwrap_or_default();
+        let aggregations = self.aggregate_usage(&organization_id, period_start, period_end);
+
+        self.invoice_generator.preview(
+            organization_id,
+            billing_currency,
+            period_start,
+            period_end,
+            &aggregations,
+            self.get_credit_balance(&organization_id),
+        )
     }
-}
 
-/// Result of a billing cycle.
-#[derive(Debug)]
-pub struct BillingResult {
-    /// Generated invoice.
-    pub invoice: Invoice,
-    /// Number of usage metrics aggregated.
-    pub usage_count: usize,
-    /// Subtotal before credits.
-    pub subtotal_cents: i64,
-    /// Credits applied.
-    pub credits_applied: i64,
-    /// Final amount due.
-    pub amount_due: i64,
-}
+    /// Get a generated invoice.
+    pub async fn invoice(&self, invoice_id: Uuid) -> CretoResult<Option<Invoice>> {
+        self.invoices.find_invoice(invoice_id).await
+    }
+
+    /// Propose an adjustment to a draft invoice, approving it immediately.
+    ///
+    /// See [`InvoiceAdjustmentManager::propose`].
+    pub async fn propose_invoice_adjustment(
+        &self,
+        adjustment: InvoiceAdjustment,
+    ) -> CretoResult<InvoiceAdjustment> {
+        let _guard = self.adjustment_lock.lock().await;
+        let mut invoice = self.stored_invoice(adjustment.invoice_id).await?;
+        self.adjustments.propose(adjustment, &mut invoice).await
+    }
+
+    /// Propose an adjustment to an invoice and have `approver` decide on it.
+    ///
+    /// See [`InvoiceAdjustmentManager::propose_with_approval`].
+    pub async fn propose_invoice_adjustment_with_approval<A>(
+        &self,
+        adjustment: InvoiceAdjustment,
+        approver: &A,
+    ) -> CretoResult<InvoiceAdjustment>
+    where
+        A: AdjustmentApprover + Sync,
+    {
+        let _guard = self.adjustment_lock.lock().await;
+        let mut invoice = self.stored_invoice(adjustment.invoice_id).await?;
+        self.adjustments
+            .propose_with_approval(adjustment, &mut invoice, approver)
+            .await
+    }
+
+    /// Record a deferred decision on a proposed adjustment.
+    pub async fn resolve_invoice_adjustment(
+        &self,
+        adjustment_id: Uuid,
+        decision: AdjustmentDecision,
+    ) -> CretoResult<InvoiceAdjustment> {
+        let _guard = self.adjustment_lock.lock().await;
+        let mut invoice = self.adjustment_invoice(adjustment_id).await?;
+        self.adjustments
+            .resolve(adjustment_id, decision, &mut invoice)
+            .await
+    }
+
+    /// Apply an approved adjustment, storing the updated invoice and any
+    /// credit note issued along with its status.
+    ///
+    /// Applying an adjustment twice changes nothing the second time.
+    pub async fn apply_invoice_adjustment(
+        &self,
+        adjustment_id: Uuid,
+    ) -> CretoResult<AdjustmentApplication> {
+        let _guard = self.adjustment_lock.lock().await;
+        let mut invoice = self.adjustment_invoice(adjustment_id).await?;
+        self.adjustments.apply(adjustment_id, &mut invoice).await
+    }
+
+    /// List an invoice's adjustments, oldest first.
+    pub async fn invoice_adjustments(
+        &self,
+        invoice_id: Uuid,
+    ) -> CretoResult<Vec<InvoiceAdjustment>> {
+        self.adjustments.list(invoice_id).await
+    }
+
+    async fn stored_invoice(&self, invoice_id: Uuid) -> CretoResult<Invoice> {
+        self.invoice(invoice_id)
+            .await?
+            .ok_or_else(|| creto_common::CretoError::NotFound(format!("Invoice {}", invoice_id)))
+    }
+
+    async fn adjustment_invoice(&self, adjustment_id: Uuid) -> CretoResult<Invoice> {
+        let adjustment = self.adjustments.get(adjustment_id).await?.ok_or_else(|| {
+            creto_common::CretoError::NotFound(format!("Invoice adjustment {}", adjustment_id))
+        })?;
+        self.stored_invoice(adjustment.invoice_id).await
+    }
+
+    async fn store_invoice(&self, invoice: &Invoice) -> CretoResult<()> {
+        self.invoices.save_invoice(invoice).await
+    }
+
+    /// List the usage events behind an invoice line item.
+    ///
+    /// Re-runs the line item's recorded selection against the event store,
+    /// returning a page of events, per-agent subtotals, and a check that the
+    /// events still add up to the invoiced quantity.
+    pub async fn line_item_events(
+        &self,
+        invoice_id: Uuid,
+        line_item_id: Uuid,
+        pagination: &EventPagination,
+    ) -> CretoResult<LineItemEvents> {
+        let invoice = self.stored_invoice(invoice_id).await?;
+        drilldown::line_item_events(&*self.usage_events, &invoice, line_item_id, pagination).await
+    }
+
+    /// Compare an invoice preview against the previous period's invoice.
+    pub fn reconcile_invoice(
+        &self,
+        preview: &InvoicePreview,
+        previous: &Invoice,
+    ) -> ReconciliationReport {
+        self.invoice_generator.reconcile(preview, previous)
+    }
+}
+
+impl Default for MeteringService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Result of a billing cycle.
+#[derive(Debug)]
+pub struct BillingResult {
+    /// Generated invoice.
+    pub invoice: Invoice,
+    /// Number of usage metrics aggregated.
+    pub usage_count: usize,
+    /// Subtotal before credits.
+    pub subtotal_cents: i64,
+    /// Credits applied.
+    pub credits_applied: i64,
+    /// Final amount due.
+    pub amount_due: i64,
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::adjustments::AdjustmentStatus;
     use crate::pricing::{PricingModel, PricingStrategy};
+    use creto_common::types::{Currency, Money};
 
     #[test]
     fn test_metering_service_creation() {
@@ -419,21 +1317,21 @@ mod tests {
         let _ = status;
     }
 
-    #[test]
-    fn test_full_billing_workflow() {
+    #[tokio::test]
+    async fn test_full_billing_workflow() {
         let mut service = MeteringService::new();
         let org_id = OrganizationId::new();
         let agent_id = AgentId::new();
 
         // 1. Setup pricing
-        service.register_pricing_model(PricingModel {
-            id: "api_calls".to_string(),
-            name: "API Call Pricing".to_string(),
-            metric_code: "api_calls".to_string(),
-            strategy: PricingStrategy::PerUnit {
+        service.register_pricing_model(PricingModel::new(
+            "api_calls",
+            "API Call Pricing",
+            "api_calls",
+            PricingStrategy::PerUnit {
                 unit_price_cents: 1, // $0.01 per call
             },
-        });
+        ));
 
         // 2. Setup quota
         service.create_quota(org_id.clone(), "api_calls", 10000, QuotaPeriod::Monthly);
@@ -454,33 +1352,198 @@ mod tests {
                 timestamp: base_time - chrono::Duration::hours(i),
                 properties: Default::default(),
                 delegation_depth: 0,
+                correlation_id: None,
+                caused_by: None,
                 external_subscription_id: None,
             };
-            service.record_usage(org_id.clone(), agent_id.clone(), event);
+            service
+                .record_usage(org_id.clone(), agent_id.clone(), event)
+                .await
+                .unwrap();
         }
 
         // 4. Generate invoice
-        let invoice = service.generate_invoice(org_id.clone(), period_start, period_end);
+        let invoice = service
+            .generate_invoice(org_id.clone(), period_start, period_end)
+            .await
+            .unwrap();
 
         assert_eq!(invoice.line_items.len(), 1);
         assert_eq!(invoice.subtotal.amount, 1000); // 100 * 10 = 1000 units * $0.01 = $10.00
     }
 
-    #[test]
-    fn test_billing_with_credits() {
+    #[tokio::test]
+    async fn test_invoice_adjustment_updates_stored_invoice() {
+        let mut service = MeteringService::with_invoice_config(30, 10.0);
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        service.register_pricing_model(PricingModel::new(
+            "api_calls",
+            "API Call Pricing",
+            "api_calls",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 1,
+            },
+        ));
+        let now = Utc::now();
+        service
+            .record_usage(
+                org_id,
+                agent_id,
+                UsageEvent {
+                    transaction_id: "tx_1".to_string(),
+                    organization_id: org_id,
+                    agent_id,
+                    event_type: crate::events::UsageEventType::ApiCall,
+                    code: "api_calls".to_string(),
+                    quantity: 1_000,
+                    timestamp: now - chrono::Duration::hours(1),
+                    properties: Default::default(),
+                    delegation_depth: 0,
+                    correlation_id: None,
+                    caused_by: None,
+                    external_subscription_id: None,
+                },
+            )
+            .await
+            .unwrap();
+        let invoice = service
+            .generate_invoice(org_id, now - chrono::Duration::days(30), now)
+            .await
+            .unwrap();
+        assert_eq!(invoice.total.amount, 1_100);
+
+        let adjustment = service
+            .propose_invoice_adjustment(InvoiceAdjustment::new(
+                &invoice,
+                -500,
+                "Retried requests billed twice",
+                "support@example.com",
+            ))
+            .await
+            .unwrap();
+        let applied = service
+            .apply_invoice_adjustment(adjustment.id)
+            .await
+            .unwrap();
+        assert!(!applied.replayed);
+
+        let stored = service.invoice(invoice.id).await.unwrap().unwrap();
+        assert_eq!(stored.subtotal.amount, 500);
+        assert_eq!(stored.tax.amount, 50);
+        assert_eq!(stored.total.amount, 550);
+        assert_eq!(stored.adjustments[0].status, AdjustmentStatus::Applied);
+
+        let replayed = service
+            .apply_invoice_adjustment(adjustment.id)
+            .await
+            .unwrap();
+        assert!(replayed.replayed);
+        assert_eq!(
+            service
+                .invoice(invoice.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .total
+                .amount,
+            550
+        );
+        assert_eq!(
+            service.invoice_adjustments(invoice.id).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invoice_adjustment_applied_once_across_processes() {
+        let invoices = Arc::new(InMemoryInvoiceRepository::new());
+        let adjustments = Arc::new(InMemoryInvoiceAdjustmentRepository::new(invoices.clone()));
+        let service = || {
+            let mut service = MeteringService::with_invoice_config(30, 10.0)
+                .with_invoice_repositories(invoices.clone(), adjustments.clone());
+            service.register_pricing_model(PricingModel::new(
+                "api_calls",
+                "API Call Pricing",
+                "api_calls",
+                PricingStrategy::PerUnit {
+                    unit_price_cents: 1,
+                },
+            ));
+            service
+        };
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let now = Utc::now();
+
+        let first = service();
+        first
+            .record_usage(
+                org_id,
+                agent_id,
+                UsageEvent::builder()
+                    .transaction_id("tx_1")
+                    .organization_id(org_id)
+                    .agent_id(agent_id)
+                    .event_type(crate::events::UsageEventType::ApiCall)
+                    .code("api_calls")
+                    .quantity(1_000)
+                    .timestamp(now - chrono::Duration::hours(1))
+                    .build(),
+            )
+            .await
+            .unwrap();
+        let invoice = first
+            .generate_invoice(org_id, now - chrono::Duration::days(30), now)
+            .await
+            .unwrap();
+        let adjustment = first
+            .propose_invoice_adjustment(InvoiceAdjustment::new(
+                &invoice,
+                -500,
+                "Retried requests billed twice",
+                "support@example.com",
+            ))
+            .await
+            .unwrap();
+
+        // A second process sharing the stores applies it first
+        let second = service();
+        assert!(
+            !second
+                .apply_invoice_adjustment(adjustment.id)
+                .await
+                .unwrap()
+                .replayed
+        );
+        assert!(
+            first
+                .apply_invoice_adjustment(adjustment.id)
+                .await
+                .unwrap()
+                .replayed
+        );
+
+        let stored = first.invoice(invoice.id).await.unwrap().unwrap();
+        assert_eq!(stored.total.amount, 550);
+        assert_eq!(stored.adjustments[0].status, AdjustmentStatus::Applied);
+    }
+
+    #[tokio::test]
+    async fn test_billing_with_credits() {
         let mut service = MeteringService::new();
         let org_id = OrganizationId::new();
         let agent_id = AgentId::new();
 
         // Setup pricing
-        service.register_pricing_model(PricingModel {
-            id: "tokens".to_string(),
-            name: "Token Pricing".to_string(),
-            metric_code: "tokens".to_string(),
-            strategy: PricingStrategy::PerUnit {
+        service.register_pricing_model(PricingModel::new(
+            "tokens",
+            "Token Pricing",
+            "tokens",
+            PricingStrategy::PerUnit {
                 unit_price_cents: 1,
             },
-        });
+        ));
 
         // Grant $50 in credits
         service
@@ -501,13 +1564,21 @@ mod tests {
                 timestamp: Utc::now() - chrono::Duration::hours(i),
                 properties: Default::default(),
                 delegation_depth: 0,
+                correlation_id: None,
+                caused_by: None,
                 external_subscription_id: None,
             };
-            service.record_usage(org_id.clone(), agent_id.clone(), event);
+            service
+                .record_usage(org_id.clone(), agent_id.clone(), event)
+                .await
+                .unwrap();
         }
 
         // Run billing cycle
-        let result = service.run_billing_cycle(org_id.clone(), period_start, Utc::now());
+        let result = service
+            .run_billing_cycle(org_id.clone(), period_start, Utc::now())
+            .await
+            .unwrap();
 
         assert_eq!(result.subtotal_cents, 10000); // $100.00
         assert_eq!(result.credits_applied, 5000); // $50.00 credits
@@ -515,8 +1586,8 @@ mod tests {
         assert_eq!(service.get_credit_balance(&org_id), 0); // Credits depleted
     }
 
-    #[test]
-    fn test_quota_enforcement_in_workflow() {
+    #[tokio::test]
+    async fn test_quota_enforcement_in_workflow() {
         let service = MeteringService::new();
         let org_id = OrganizationId::new();
         let agent_id = AgentId::new();
@@ -535,10 +1606,14 @@ mod tests {
             timestamp: Utc::now(),
             properties: Default::default(),
             delegation_depth: 0,
+            correlation_id: None,
+            caused_by: None,
             external_subscription_id: None,
         };
 
-        let result = service.check_and_record(org_id.clone(), agent_id.clone(), event);
+        let result = service
+            .check_and_record(org_id.clone(), agent_id.clone(), event)
+            .await;
         assert!(result.is_ok());
 
         // Should fail when exceeding quota
@@ -552,10 +1627,1216 @@ mod tests {
             timestamp: Utc::now(),
             properties: Default::default(),
             delegation_depth: 0,
+            correlation_id: None,
+            caused_by: None,
             external_subscription_id: None,
         };
 
-        let result2 = service.check_and_record(org_id, agent_id, event2);
+        let result2 = service.check_and_record(org_id, agent_id, event2).await;
         assert!(result2.is_err());
     }
+
+    #[tokio::test]
+    async fn test_check_and_record_charges_by_delegation_depth() {
+        let service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+
+        service.register_quota(
+            &Quota::new(org_id, "tool_calls", 1000, QuotaPeriod::Daily)
+                .with_max_delegation_depth(3)
+                .with_delegation_multiplier(2, 1.5)
+                .unwrap(),
+        );
+
+        let event = |tx: &str, delegation_depth: u8| UsageEvent {
+            transaction_id: tx.to_string(),
+            organization_id: org_id,
+            agent_id,
+            event_type: crate::events::UsageEventType::ApiCall,
+            code: "tool_calls".to_string(),
+            quantity: 100,
+            timestamp: Utc::now(),
+            properties: Default::default(),
+            delegation_depth,
+            correlation_id: None,
+            caused_by: None,
+            external_subscription_id: None,
+        };
+
+        service
+            .check_and_record(org_id, agent_id, event("tx_root", 0))
+            .await
+            .unwrap();
+        service
+            .check_and_record(org_id, agent_id, event("tx_sub", 2))
+            .await
+            .unwrap();
+        let status = service
+            .get_quota_status(&org_id, &agent_id, "tool_calls")
+            .unwrap();
+        assert_eq!(status.current_usage, 250);
+
+        let err = service
+            .check_and_record(org_id, agent_id, event("tx_deep", 4))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            creto_common::CretoError::InvalidUsageEvent(_)
+        ));
+        // Denied events are not recorded
+        assert_eq!(service.usage_records.read().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invoice_in_billing_currency() {
+        use crate::currency::FixedExchangeRateProvider;
+        use std::sync::Arc;
+
+        let mut service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+
+        service.register_pricing_model(PricingModel::new(
+            "api_calls",
+            "API Call Pricing",
+            "api_calls",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 10,
+            },
+        ));
+        service.set_billing_profile(OrgBillingProfile::new(org_id, Currency::EUR));
+
+        let event = UsageEvent {
+            transaction_id: "tx_eur".to_string(),
+            organization_id: org_id,
+            agent_id,
+            event_type: crate::events::UsageEventType::ApiCall,
+            code: "api_calls".to_string(),
+            quantity: 100,
+            timestamp: Utc::now(),
+            properties: Default::default(),
+            delegation_depth: 0,
+            correlation_id: None,
+            caused_by: None,
+            external_subscription_id: None,
+        };
+        service.record_usage(org_id, agent_id, event).await.unwrap();
+
+        let period_start = Utc::now() - chrono::Duration::days(1);
+
+        // No conversion path configured yet.
+        assert!(service
+            .generate_invoice_in_billing_currency(org_id, period_start, Utc::now())
+            .await
+            .is_err());
+
+        service
+            .invoice_generator
+            .set_exchange_rate_provider(Arc::new(FixedExchangeRateProvider::new().with_rate(
+                Currency::USD,
+                Currency::EUR,
+                0.5,
+            )));
+
+        let invoice = service
+            .generate_invoice_in_billing_currency(org_id, period_start, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(invoice.currency, Currency::EUR);
+        assert_eq!(invoice.total.amount, 500); // $10.00 -> 5.00 EUR
+    }
+
+    #[tokio::test]
+    async fn test_usage_split_exactly_at_price_change() {
+        use chrono::TimeZone;
+
+        let mut service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+
+        let start = Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap();
+        let change_at = Utc.with_ymd_and_hms(2026, 4, 10, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2026, 4, 30, 23, 59, 59).unwrap();
+
+        let v1 = PricingModel::new(
+            "calls_v1",
+            "API Calls",
+            "api_calls",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 10,
+            },
+        );
+        let v2 = v1.successor(
+            "calls_v2",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 15,
+            },
+            change_at,
+        );
+        service.add_pricing_version(v1).await.unwrap();
+        service.add_pricing_version(v2).await.unwrap();
+
+        for (i, (day, quantity)) in [(2, 40), (5, 60), (20, 10)].into_iter().enumerate() {
+            let event = UsageEvent {
+                transaction_id: format!("tx_split_{}", i),
+                organization_id: org_id,
+                agent_id,
+                event_type: crate::events::UsageEventType::ApiCall,
+                code: "api_calls".to_string(),
+                quantity,
+                timestamp: Utc.with_ymd_and_hms(2026, 4, day, 12, 0, 0).unwrap(),
+                properties: Default::default(),
+                delegation_depth: 0,
+                correlation_id: None,
+                caused_by: None,
+                external_subscription_id: None,
+            };
+            service.record_usage(org_id, agent_id, event).await.unwrap();
+        }
+
+        let invoice = service.generate_invoice(org_id, start, end).await.unwrap();
+        let mut items: Vec<_> = invoice
+            .line_items
+            .iter()
+            .map(|item| {
+                (
+                    item.pricing.as_ref().unwrap().version,
+                    item.quantity,
+                    item.amount,
+                )
+            })
+            .collect();
+        items.sort_by_key(|(version, ..)| *version);
+
+        // Usage is bucketed by event time, not prorated
+        assert_eq!(
+            items,
+            vec![(1, 100, Money::usd(1000)), (2, 10, Money::usd(150))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pricing_changes_written_through_to_repository() {
+        use crate::pricing::InMemoryPricingModelRepository;
+        use chrono::TimeZone;
+
+        let repository = Arc::new(InMemoryPricingModelRepository::new());
+        let mut service = MeteringService::new().with_pricing_repository(repository.clone());
+        let org_id = OrganizationId::new();
+        let change_at = Utc.with_ymd_and_hms(2026, 4, 10, 0, 0, 0).unwrap();
+
+        let v1 = PricingModel::new(
+            "calls_v1",
+            "API Calls",
+            "api_calls",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 10,
+            },
+        );
+        let v2 = v1.successor(
+            "calls_v2",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 15,
+            },
+            change_at,
+        );
+        service.add_pricing_version(v1.clone()).await.unwrap();
+        service.add_pricing_version(v2).await.unwrap();
+        service
+            .pin_pricing_version(org_id, "calls_v1")
+            .await
+            .unwrap();
+
+        let resolved = repository
+            .resolve(org_id, "api_calls", change_at)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.id, "calls_v1");
+
+        // A restarted service prices from what the repository holds
+        let mut restarted = MeteringService::new().with_pricing_repository(repository.clone());
+        restarted.load_pricing().await.unwrap();
+        let catalog = restarted.invoice_generator.pricing_catalog();
+        assert_eq!(catalog.versions("api_calls").len(), 2);
+        assert_eq!(
+            catalog.resolve(org_id, "api_calls", change_at).unwrap().id,
+            "calls_v1"
+        );
+
+        assert_eq!(
+            restarted
+                .migrate_pricing(org_id, "api_calls")
+                .await
+                .unwrap(),
+            Some("calls_v1".to_string())
+        );
+        let resolved = repository
+            .resolve(org_id, "api_calls", change_at)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.id, "calls_v2");
+
+        // A rejected version reaches neither the catalog nor the repository
+        assert!(service.add_pricing_version(v1).await.is_err());
+        assert_eq!(
+            repository.list_versions("api_calls").await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_by_correlation() {
+        let service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let correlation = creto_common::Correlation::new(Uuid::now_v7());
+
+        let traced = UsageEvent::builder()
+            .organization_id(org_id)
+            .agent_id(agent_id)
+            .event_type(crate::events::UsageEventType::SandboxExecution)
+            .correlation(correlation.derive(Uuid::now_v7()))
+            .build();
+        let untraced = UsageEvent::builder()
+            .organization_id(org_id)
+            .agent_id(agent_id)
+            .event_type(crate::events::UsageEventType::ApiCall)
+            .build();
+        service
+            .record_usage(org_id, agent_id, traced.clone())
+            .await
+            .unwrap();
+        service
+            .record_usage(org_id, agent_id, untraced)
+            .await
+            .unwrap();
+
+        let found = service.find_by_correlation(correlation.correlation_id.unwrap());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].transaction_id, traced.transaction_id);
+        assert_eq!(found[0].caused_by, traced.caused_by);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_lifecycle() {
+        let service = MeteringService::new();
+        let org_id = OrganizationId::new();
+
+        let original = service.create_api_key(org_id).await.unwrap();
+        let rotated = service
+            .rotate_api_key(original.key.id, chrono::Duration::minutes(5))
+            .await
+            .unwrap();
+
+        // Old key stays valid through the grace window
+        for key in [&original, &rotated] {
+            let identity = service.authenticate_api_key(&key.plaintext).await.unwrap();
+            assert_eq!(identity.organization_id, org_id);
+        }
+        assert_eq!(service.list_api_keys(org_id).await.unwrap().len(), 2);
+
+        service.revoke_api_key(rotated.key.id).await.unwrap();
+        assert_eq!(
+            service.authenticate_api_key(&rotated.plaintext).await,
+            Err(ApiKeyError::Revoked)
+        );
+
+        // The shared manager sees the same keys
+        assert!(service
+            .api_key_manager()
+            .authenticate(&original.plaintext)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_api_keys_in_injected_repository_survive_restart() {
+        let repository = Arc::new(InMemoryApiKeyRepository::new());
+        let org_id = OrganizationId::new();
+
+        let issued = MeteringService::new()
+            .with_api_key_repository(repository.clone())
+            .create_api_key(org_id)
+            .await
+            .unwrap();
+
+        let restarted = MeteringService::new().with_api_key_repository(repository.clone());
+        let identity = restarted
+            .authenticate_api_key(&issued.plaintext)
+            .await
+            .unwrap();
+        assert_eq!(identity.key_id, issued.key.id);
+        assert_eq!(repository.list_by_org(org_id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_preview_and_reconcile_against_previous_period() {
+        use crate::invoice::ReconciliationAnomaly;
+
+        let mut service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+
+        service.register_pricing_model(PricingModel::new(
+            "tokens",
+            "Token Pricing",
+            "tokens",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 1,
+            },
+        ));
+
+        let now = Utc::now();
+        let current_start = now - chrono::Duration::days(30);
+        let previous_start = now - chrono::Duration::days(60);
+        let event = |tx: &str, code: &str, quantity: i64, timestamp: DateTime<Utc>| UsageEvent {
+            transaction_id: tx.to_string(),
+            organization_id: org_id,
+            agent_id,
+            event_type: crate::events::UsageEventType::TotalTokens,
+            code: code.to_string(),
+            quantity,
+            timestamp,
+            properties: Default::default(),
+            delegation_depth: 0,
+            correlation_id: None,
+            caused_by: None,
+            external_subscription_id: None,
+        };
+
+        for event in [
+            event(
+                "tx_prev",
+                "tokens",
+                1000,
+                previous_start + chrono::Duration::days(1),
+            ),
+            event("tx_now", "tokens", 1100, now - chrono::Duration::days(1)),
+            event("tx_new", "gpu_seconds", 40, now - chrono::Duration::days(1)),
+        ] {
+            service.record_usage(org_id, agent_id, event).await.unwrap();
+        }
+
+        let previous = service
+            .run_billing_cycle(org_id, previous_start, current_start)
+            .await
+            .unwrap()
+            .invoice;
+        service.grant_credits(org_id, 500, Some("Promo")).unwrap();
+
+        let preview = service.preview_invoice(org_id, current_start, now).unwrap();
+        assert_eq!(preview.invoice.subtotal.amount, 1140);
+        assert_eq!(preview.credits_applied, 500);
+        assert_eq!(preview.invoice.total.amount, 640);
+        // A preview never consumes credits
+        assert_eq!(service.get_credit_balance(&org_id), 500);
+
+        let report = service.reconcile_invoice(&preview, &previous);
+        assert_eq!(report.organization_id, org_id);
+        assert_eq!(
+            report.anomalies,
+            vec![
+                ReconciliationAnomaly::NewLineItem {
+                    metric_code: "gpu_seconds".to_string(),
+                    quantity: 40,
+                    amount: creto_common::types::Money::usd(40),
+                },
+                ReconciliationAnomaly::UnpricedCode {
+                    metric_code: "gpu_seconds".to_string(),
+                    quantity: 40,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_late_events_in_injected_repository_survive_restart() {
+        let repository = Arc::new(InMemoryLateEventRepository::new());
+        let service = || {
+            MeteringService::new()
+                .with_late_event_repository(repository.clone(), WatermarkConfig::default())
+        };
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let now = Utc::now();
+        let tokens = |tx: &str, timestamp: DateTime<Utc>| {
+            UsageEvent::builder()
+                .transaction_id(tx)
+                .organization_id(org_id)
+                .agent_id(agent_id)
+                .event_type(crate::events::UsageEventType::TotalTokens)
+                .code("tokens")
+                .quantity(10)
+                .timestamp(timestamp)
+                .build()
+        };
+
+        let ingesting = service();
+        ingesting
+            .ingest(org_id, agent_id, tokens("tx_now", now))
+            .await
+            .unwrap();
+        let outcome = ingesting
+            .ingest(
+                org_id,
+                agent_id,
+                tokens("tx_late", now - chrono::Duration::days(2)),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(outcome, IngestOutcome::AcceptedLate(_)));
+        drop(ingesting);
+
+        let restarted = service();
+        let pending = restarted.pending_late_events(org_id).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].event.transaction_id, "tx_late");
+    }
+
+    #[tokio::test]
+    async fn test_late_event_billed_as_correction_on_next_invoice() {
+        let mut service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+
+        service.register_pricing_model(PricingModel::new(
+            "tokens",
+            "Token Pricing",
+            "tokens",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 1,
+            },
+        ));
+        service.register_quota(&Quota::new(org_id, "tokens", 1_000, QuotaPeriod::Daily));
+
+        let now = Utc::now();
+        let event = |tx: &str, quantity: i64, timestamp: DateTime<Utc>| UsageEvent {
+            transaction_id: tx.to_string(),
+            organization_id: org_id,
+            agent_id,
+            event_type: crate::events::UsageEventType::TotalTokens,
+            code: "tokens".to_string(),
+            quantity,
+            timestamp,
+            properties: Default::default(),
+            delegation_depth: 0,
+            correlation_id: None,
+            caused_by: None,
+            external_subscription_id: None,
+        };
+
+        let outcome = service
+            .ingest(org_id, agent_id, event("tx_now", 100, now))
+            .await
+            .unwrap();
+        assert!(matches!(outcome, IngestOutcome::Accepted));
+
+        // An agent that was offline for three days flushes its buffer
+        let three_days_ago = now - chrono::Duration::days(3);
+        let outcome = service
+            .ingest(org_id, agent_id, event("tx_offline", 5_000, three_days_ago))
+            .await
+            .unwrap();
+        let IngestOutcome::AcceptedLate(late) = outcome else {
+            panic!("expected late ingestion");
+        };
+        assert_eq!(late.watermark, now);
+        assert_eq!(service.pending_late_events(org_id).await.unwrap().len(), 1);
+
+        // Neither the closed period nor quota sees the late usage
+        let closed = service.aggregate_usage(
+            &org_id,
+            three_days_ago - chrono::Duration::days(1),
+            three_days_ago + chrono::Duration::days(1),
+        );
+        assert!(closed.is_empty());
+        let status = service
+            .get_quota_status(&org_id, &agent_id, "tokens")
+            .unwrap();
+        assert_eq!(status.current_usage, 100);
+
+        let resolution = service.resolve_late_events(org_id).await.unwrap();
+        assert_eq!(resolution.policy, LateEventPolicy::FoldIntoNextInvoice);
+        assert_eq!(resolution.events.len(), 1);
+        assert!(service
+            .pending_late_events(org_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let preview = service
+            .preview_invoice(
+                org_id,
+                now - chrono::Duration::hours(1),
+                Utc::now() + chrono::Duration::hours(1),
+            )
+            .unwrap();
+        let mut items: Vec<(&str, i64)> = preview
+            .invoice
+            .line_items
+            .iter()
+            .map(|item| (item.description.as_str(), item.quantity))
+            .collect();
+        items.sort();
+        assert_eq!(
+            items,
+            vec![
+                ("tokens late usage correction", 5_000),
+                ("tokens usage", 100)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_late_events_are_not_billed() {
+        let service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        service.set_late_event_policy(org_id, LateEventPolicy::Reject);
+
+        let now = Utc::now();
+        for (tx, timestamp) in [("tx_now", now), ("tx_old", now - chrono::Duration::days(3))] {
+            let event = UsageEvent::builder()
+                .transaction_id(tx)
+                .organization_id(org_id)
+                .agent_id(agent_id)
+                .event_type(crate::events::UsageEventType::TotalTokens)
+                .code("tokens")
+                .quantity(10)
+                .timestamp(timestamp)
+                .build();
+            service.ingest(org_id, agent_id, event).await.unwrap();
+        }
+
+        let resolution = service.resolve_late_events(org_id).await.unwrap();
+        assert_eq!(resolution.events.len(), 1);
+        let usage = service.aggregate_usage(
+            &org_id,
+            now - chrono::Duration::days(7),
+            Utc::now() + chrono::Duration::hours(1),
+        );
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].quantity, 10);
+    }
+
+    #[tokio::test]
+    async fn test_recorded_usage_feeds_anomaly_detector() {
+        let sink = Arc::new(crate::anomaly::InMemoryAnomalySink::new());
+        let detector = AnomalyDetector::new(Default::default()).with_sink(sink.clone());
+        let service = MeteringService::new().with_anomaly_detector(Arc::new(detector));
+        let (org_id, agent_id) = (OrganizationId::new(), AgentId::new());
+        service.create_quota(org_id, "api_calls", 1_000_000, QuotaPeriod::Monthly);
+
+        let start = Utc::now() - chrono::Duration::hours(2);
+        let calls = |minute: i64, quantity: i64| {
+            UsageEvent::builder()
+                .organization_id(org_id)
+                .agent_id(agent_id)
+                .event_type(crate::events::UsageEventType::ApiCall)
+                .code("api_calls")
+                .quantity(quantity)
+                .timestamp(start + chrono::Duration::minutes(minute))
+                .build()
+        };
+        for minute in 0..40 {
+            service
+                .check_and_record(org_id, agent_id, calls(minute, 10))
+                .await
+                .unwrap();
+        }
+        assert!(sink.alerts().is_empty());
+
+        service
+            .record_usage(org_id, agent_id, calls(40, 200))
+            .await
+            .unwrap();
+        let alerts = sink.alerts();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].severity, crate::anomaly::AnomalySeverity::High);
+    }
+
+    #[tokio::test]
+    async fn test_line_item_drill_down_and_late_mutation() {
+        let service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let (heavy, light) = (AgentId::new(), AgentId::new());
+        let period_end = Utc::now();
+        let period_start = period_end - chrono::Duration::days(30);
+
+        let tokens = |agent_id: AgentId, quantity: i64, days_ago: i64| {
+            UsageEvent::builder()
+                .organization_id(org_id)
+                .agent_id(agent_id)
+                .event_type(crate::events::UsageEventType::InputTokens)
+                .code("input_tokens")
+                .quantity(quantity)
+                .timestamp(period_end - chrono::Duration::days(days_ago))
+                .build()
+        };
+        for (agent_id, quantity, days_ago) in [
+            (heavy, 900, 20),
+            (light, 100, 15),
+            (heavy, 1200, 10),
+            (light, 50, 1),
+        ] {
+            service
+                .record_usage(org_id, agent_id, tokens(agent_id, quantity, days_ago))
+                .await
+                .unwrap();
+        }
+        // Outside the period
+        service
+            .record_usage(org_id, heavy, tokens(heavy, 5000, 45))
+            .await
+            .unwrap();
+
+        let invoice = service
+            .generate_invoice(org_id, period_start, period_end)
+            .await
+            .unwrap();
+        let line_item = &invoice.line_items[0];
+        assert_eq!(line_item.quantity, 2250);
+
+        let first = service
+            .line_item_events(invoice.id, line_item.id, &EventPagination::first(3))
+            .await
+            .unwrap();
+        assert_eq!(first.events.len(), 3);
+        assert_eq!(first.events[0].quantity, 900);
+        assert!(first.verification.matches);
+        assert_eq!(first.verification.current_quantity, 2250);
+        assert_eq!(first.verification.current_event_count, 4);
+        assert_eq!(first.agent_subtotals[0].agent_id, heavy);
+        assert_eq!(first.agent_subtotals[0].quantity, 2100);
+        assert_eq!(first.agent_subtotals[1].event_count, 2);
+
+        let rest = service
+            .line_item_events(
+                invoice.id,
+                line_item.id,
+                &EventPagination::after(first.next_cursor.unwrap(), 3),
+            )
+            .await
+            .unwrap();
+        assert_eq!(rest.events.len(), 1);
+        assert_eq!(rest.events[0].quantity, 50);
+        assert!(rest.next_cursor.is_none());
+
+        // A late event lands in the invoiced window after billing
+        service
+            .record_usage(org_id, light, tokens(light, 75, 5))
+            .await
+            .unwrap();
+        let drifted = service
+            .line_item_events(invoice.id, line_item.id, &EventPagination::default())
+            .await
+            .unwrap();
+        assert!(!drifted.verification.matches);
+        assert_eq!(drifted.verification.invoiced_quantity, 2250);
+        assert_eq!(drifted.verification.current_quantity, 2325);
+        assert_eq!(drifted.events.len(), 5);
+
+        assert!(service
+            .line_item_events(invoice.id, Uuid::now_v7(), &EventPagination::default())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_drill_down_from_injected_stores_after_restart() {
+        let events = Arc::new(InMemoryEventRepository::new());
+        let invoices = Arc::new(InMemoryInvoiceRepository::new());
+        let adjustments = Arc::new(InMemoryInvoiceAdjustmentRepository::new(invoices.clone()));
+        let service = || {
+            MeteringService::new()
+                .with_event_repository(events.clone())
+                .with_invoice_repositories(invoices.clone(), adjustments.clone())
+        };
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let period_end = Utc::now();
+        let period_start = period_end - chrono::Duration::days(30);
+        let tokens = |tx: &str, quantity: i64, timestamp: DateTime<Utc>| {
+            UsageEvent::builder()
+                .transaction_id(tx)
+                .organization_id(org_id)
+                .agent_id(agent_id)
+                .event_type(crate::events::UsageEventType::InputTokens)
+                .code("input_tokens")
+                .quantity(quantity)
+                .timestamp(timestamp)
+                .build()
+        };
+
+        let billing = service();
+        for event in [
+            tokens("tx_1", 400, period_end - chrono::Duration::days(3)),
+            // A retried delivery of the same event
+            tokens("tx_1", 400, period_end - chrono::Duration::days(3)),
+            // Exactly at the end of the period
+            tokens("tx_2", 600, period_end),
+        ] {
+            billing.record_usage(org_id, agent_id, event).await.unwrap();
+        }
+        let invoice = billing
+            .generate_invoice(org_id, period_start, period_end)
+            .await
+            .unwrap();
+        let line_item = &invoice.line_items[0];
+        assert_eq!(line_item.quantity, 1000);
+        drop(billing);
+
+        let restarted = service();
+        let drill_down = restarted
+            .line_item_events(invoice.id, line_item.id, &EventPagination::default())
+            .await
+            .unwrap();
+        assert!(drill_down.verification.matches);
+        assert_eq!(drill_down.verification.current_event_count, 2);
+        assert_eq!(drill_down.events.len(), 2);
+        assert_eq!(
+            drill_down.source,
+            invoices
+                .find_invoice(invoice.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .line_items[0]
+                .source
+                .clone()
+                .unwrap()
+        );
+        assert_eq!(
+            events
+                .sum_by_code(org_id, "input_tokens", period_start, period_end)
+                .await
+                .unwrap(),
+            1000
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_quotas_corrects_out_of_band_usage() {
+        use crate::quota::{
+            InMemoryReconciliationAuditSink, QuotaReconciliationConfig, ReconciliationMode,
+        };
+
+        let audit = Arc::new(InMemoryReconciliationAuditSink::new());
+        let service = MeteringService::new().with_quota_reconciler(
+            QuotaReconciler::new(QuotaReconciliationConfig {
+                mode: ReconciliationMode::AutoCorrect,
+                page_delay: std::time::Duration::ZERO,
+                ..Default::default()
+            })
+            .with_audit_sink(audit.clone()),
+        );
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        service.create_quota(org_id, "api_calls", 100, QuotaPeriod::Daily);
+
+        let event = |quantity| {
+            UsageEvent::builder()
+                .organization_id(org_id)
+                .agent_id(agent_id)
+                .event_type(crate::events::UsageEventType::ApiCall)
+                .code("api_calls")
+                .quantity(quantity)
+                .build()
+        };
+        service
+            .check_and_record(org_id, agent_id, event(30))
+            .await
+            .unwrap();
+        // Recorded without being charged against the quota
+        service
+            .record_usage(org_id, agent_id, event(60))
+            .await
+            .unwrap();
+
+        let report = service.reconcile_quotas().await.unwrap();
+        assert_eq!(report.corrected(), 1);
+        assert_eq!(report.drifts[0].counter_usage, 30);
+        assert_eq!(report.drifts[0].recomputed_usage, 90);
+        assert_eq!(audit.records().len(), 1);
+
+        // The enforcer now sees the real usage
+        let status = service
+            .get_quota_status(&org_id, &agent_id, "api_calls")
+            .unwrap();
+        assert_eq!(status.current_usage, 90);
+        assert!(service
+            .check_and_record(org_id, agent_id, event(20))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_quotas_walks_persisted_counters() {
+        use crate::quota::{
+            CounterStore, InMemoryQuotaRepository, QuotaReconciliationConfig, ReconciliationMode,
+        };
+
+        let quotas = Arc::new(InMemoryQuotaRepository::new());
+        let service = MeteringService::new()
+            .with_quota_repository(quotas.clone())
+            .with_quota_reconciler(QuotaReconciler::new(QuotaReconciliationConfig {
+                mode: ReconciliationMode::AutoCorrect,
+                page_delay: std::time::Duration::ZERO,
+                ..Default::default()
+            }));
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        // A counter this process's enforcer never loaded
+        let quota = quotas
+            .get_or_create(org_id, None, "api_calls", QuotaPeriod::Daily, 100)
+            .await
+            .unwrap();
+        service
+            .record_usage(
+                org_id,
+                agent_id,
+                UsageEvent::builder()
+                    .organization_id(org_id)
+                    .agent_id(agent_id)
+                    .event_type(crate::events::UsageEventType::ApiCall)
+                    .code("api_calls")
+                    .quantity(40)
+                    .build(),
+            )
+            .await
+            .unwrap();
+
+        let report = service
+            .reconcile_quotas_at(Utc::now() + chrono::Duration::milliseconds(1))
+            .await
+            .unwrap();
+        assert_eq!(report.corrected(), 1);
+        assert_eq!(report.drifts[0].quota_id, quota.id);
+        assert_eq!(report.drifts[0].store, CounterStore::Repository);
+        let stored = quotas
+            .get_current(org_id, None, "api_calls")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.current_usage, 40);
+    }
+
+    #[tokio::test]
+    async fn test_sampling_leaves_quota_enforcement_whole() {
+        use crate::quota::ReconciliationSkip;
+        use crate::sampling::SamplingRule;
+
+        let service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        service.create_quota(org_id, "heartbeats", 100, QuotaPeriod::Daily);
+        service
+            .set_sampling_rule(SamplingRule::every_nth(org_id, "heartbeats", 5))
+            .unwrap();
+
+        let event = || {
+            UsageEvent::builder()
+                .organization_id(org_id)
+                .agent_id(agent_id)
+                .event_type(crate::events::UsageEventType::ApiCall)
+                .code("heartbeats")
+                .quantity(10)
+                .build()
+        };
+        for _ in 0..10 {
+            service
+                .check_and_record(org_id, agent_id, event())
+                .await
+                .unwrap();
+        }
+
+        // Every request was charged, though only two events were stored
+        let status = service
+            .get_quota_status(&org_id, &agent_id, "heartbeats")
+            .unwrap();
+        assert_eq!(status.current_usage, 100);
+        assert!(service
+            .check_and_record(org_id, agent_id, event())
+            .await
+            .is_err());
+
+        let now = Utc::now();
+        let aggregations = service.aggregate_usage(
+            &org_id,
+            now - chrono::Duration::hours(1),
+            now + chrono::Duration::hours(1),
+        );
+        assert_eq!(aggregations.len(), 1);
+        assert_eq!(aggregations[0].quantity, 100);
+        assert_eq!(aggregations[0].source.as_ref().unwrap().event_count, 2);
+
+        // The stored sum is an estimate, so the counter is not reconciled to it
+        let report = service.reconcile_quotas().await.unwrap();
+        assert_eq!(report.skipped[0].reason, ReconciliationSkip::SampledEvents);
+        assert!(report.drifts.is_empty());
+    }
+
+    fn code_event(
+        org_id: OrganizationId,
+        agent_id: AgentId,
+        transaction_id: &str,
+        code: &str,
+        quantity: i64,
+        timestamp: DateTime<Utc>,
+    ) -> UsageEvent {
+        UsageEvent::builder()
+            .transaction_id(transaction_id)
+            .organization_id(org_id)
+            .agent_id(agent_id)
+            .event_type(crate::events::UsageEventType::LlmInference)
+            .code(code)
+            .quantity(quantity)
+            .timestamp(timestamp)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_invoice_spanning_rename_bills_combined_usage_once() {
+        use chrono::TimeZone;
+
+        let mut service = MeteringService::new().with_alias_rewrite(true);
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let start = Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap();
+        let renamed_at = Utc.with_ymd_and_hms(2026, 4, 15, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2026, 4, 30, 23, 59, 59).unwrap();
+
+        service.register_pricing_model(PricingModel::new(
+            "llm_tokens",
+            "LLM Tokens",
+            "llm_tokens",
+            PricingStrategy::PerUnit {
+                unit_price_cents: 2,
+            },
+        ));
+
+        // Recorded under the old code before any alias existed
+        let before = start + chrono::Duration::days(3);
+        service
+            .record_usage(
+                org_id,
+                agent_id,
+                code_event(org_id, agent_id, "tx_old", "tokens", 300, before),
+            )
+            .await
+            .unwrap();
+        service
+            .register_metric_alias(MetricAlias::new("tokens", "llm_tokens"))
+            .await
+            .unwrap();
+
+        // A stale producer still sending the old code is rewritten; the new
+        // code is stored as is
+        let after = renamed_at + chrono::Duration::days(1);
+        service
+            .record_usage(
+                org_id,
+                agent_id,
+                code_event(org_id, agent_id, "tx_stale", "tokens", 50, after),
+            )
+            .await
+            .unwrap();
+        service
+            .record_usage(
+                org_id,
+                agent_id,
+                code_event(org_id, agent_id, "tx_new", "llm_tokens", 150, after),
+            )
+            .await
+            .unwrap();
+
+        let invoice = service.generate_invoice(org_id, start, end).await.unwrap();
+        assert_eq!(invoice.line_items.len(), 1);
+        let line_item = &invoice.line_items[0];
+        assert_eq!(line_item.metric_code, "llm_tokens");
+        assert_eq!(line_item.quantity, 500);
+        assert_eq!(line_item.amount, Money::usd(1000));
+
+        // Drill-down finds the historical events under the old code too
+        let events = service
+            .line_item_events(invoice.id, line_item.id, &EventPagination::first(10))
+            .await
+            .unwrap();
+        assert!(events.verification.matches);
+        let codes: Vec<_> = events.events.iter().map(|e| e.code.as_str()).collect();
+        assert_eq!(codes, vec!["tokens", "llm_tokens", "llm_tokens"]);
+        let stale = events
+            .events
+            .iter()
+            .find(|e| e.transaction_id == "tx_stale")
+            .unwrap();
+        assert_eq!(
+            stale.properties[crate::aliases::ALIASED_FROM_PROPERTY],
+            "tokens"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_old_and_new_codes_share_quota() {
+        let service = MeteringService::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        service
+            .register_metric_alias(MetricAlias::new("tokens", "llm_tokens"))
+            .await
+            .unwrap();
+        service.create_quota(org_id, "llm_tokens", 100, QuotaPeriod::Daily);
+
+        let now = Utc::now();
+        service
+            .check_and_record(
+                org_id,
+                agent_id,
+                code_event(org_id, agent_id, "tx_1", "tokens", 60, now),
+            )
+            .await
+            .unwrap();
+        let status = service
+            .get_quota_status(&org_id, &agent_id, "llm_tokens")
+            .unwrap();
+        assert_eq!(status.remaining, 40);
+
+        // The new code draws on the same allowance
+        let exceeded = service
+            .check_and_record(
+                org_id,
+                agent_id,
+                code_event(org_id, agent_id, "tx_2", "llm_tokens", 60, now),
+            )
+            .await;
+        assert!(matches!(
+            exceeded,
+            Err(creto_common::CretoError::QuotaExceeded { .. })
+        ));
+        service
+            .check_and_record(
+                org_id,
+                agent_id,
+                code_event(org_id, agent_id, "tx_3", "llm_tokens", 40, now),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            service
+                .get_quota_status(&org_id, &agent_id, "tokens")
+                .unwrap()
+                .remaining,
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rolled_over_quotas_written_to_repository() {
+        use crate::quota::{BurstAllowance, InMemoryQuotaRepository};
+
+        let quotas = Arc::new(InMemoryQuotaRepository::new());
+        let service = MeteringService::new().with_quota_repository(quotas.clone());
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        // A quota 50 units into its burst when its period ends
+        let quota = Quota::new(org_id, "tokens", 1000, QuotaPeriod::Hourly)
+            .with_burst(BurstAllowance::Units(200));
+        service.register_quota(&quota);
+        service
+            .quota_enforcer
+            .record_usage(&org_id, &agent_id, "tokens", 1050)
+            .unwrap();
+        service.quota_enforcer.roll_over_expired(quota.period_end);
+
+        let written = service.roll_over_quotas().await.unwrap();
+        assert_eq!(written.len(), 1);
+        assert!(service.roll_over_quotas().await.unwrap().is_empty());
+
+        let stored = quotas.list_by_org(org_id).await.unwrap();
+        let next = stored
+            .iter()
+            .find(|q| q.period_start == quota.period_end)
+            .unwrap();
+        assert_eq!(next.carried_debt, 50);
+        assert_eq!(next.burst, Some(BurstAllowance::Units(200)));
+    }
+
+    #[tokio::test]
+    async fn test_rename_mid_period_carries_usage_over() {
+        use crate::quota::InMemoryQuotaRepository;
+
+        let quotas = Arc::new(InMemoryQuotaRepository::new());
+        let service = MeteringService::new().with_quota_repository(quotas.clone());
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let other_org = OrganizationId::new();
+        let now = Utc::now();
+
+        // Quotas set up and drawn on under the old code
+        service.create_quota(org_id, "tokens", 100, QuotaPeriod::Daily);
+        service.create_quota(other_org, "tokens", 100, QuotaPeriod::Daily);
+        service.create_quota(other_org, "llm_tokens", 500, QuotaPeriod::Daily);
+        for (org, tx) in [(org_id, "tx_1"), (other_org, "tx_2")] {
+            service
+                .check_and_record(
+                    org,
+                    agent_id,
+                    code_event(org, agent_id, tx, "tokens", 70, now),
+                )
+                .await
+                .unwrap();
+        }
+        let stored = quotas
+            .get_or_create(org_id, None, "tokens", QuotaPeriod::Daily, 100)
+            .await
+            .unwrap();
+        quotas.increment_usage(stored.id, 70).await.unwrap();
+
+        service
+            .register_metric_alias(MetricAlias::new("tokens", "llm_tokens"))
+            .await
+            .unwrap();
+
+        // The usage moved with the quota, and both codes see it
+        for code in ["tokens", "llm_tokens"] {
+            let status = service.get_quota_status(&org_id, &agent_id, code).unwrap();
+            assert_eq!(status.current_usage, 70);
+            assert_eq!(status.limit, 100);
+        }
+        let exceeded = service
+            .check_and_record(
+                org_id,
+                agent_id,
+                code_event(org_id, agent_id, "tx_3", "llm_tokens", 40, now),
+            )
+            .await;
+        assert!(matches!(
+            exceeded,
+            Err(creto_common::CretoError::QuotaExceeded { .. })
+        ));
+
+        // A quota already set on the new code keeps its limit and takes on
+        // the old code's usage
+        let status = service
+            .get_quota_status(&other_org, &agent_id, "llm_tokens")
+            .unwrap();
+        assert_eq!((status.current_usage, status.limit), (70, 500));
+
+        // The stored counter moved too
+        assert!(quotas
+            .get_current(org_id, None, "tokens")
+            .await
+            .unwrap()
+            .is_none());
+        let moved = quotas
+            .get_current(org_id, None, "llm_tokens")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((moved.id, moved.current_usage), (stored.id, 70));
+    }
 }
@@ -0,0 +1,146 @@
+//! Metering section of an organization snapshot.
+//!
+//! Summarizes the organization's quotas (closest to exhaustion first), its
+//! credit balance and its most recent anomaly alerts. All of it is read from
+//! in-memory state, so the section is current as of the capture timestamp.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoResult, SectionData, SnapshotContributor, SnapshotRequest};
+use serde::Serialize;
+
+use crate::anomaly::AnomalyAlert;
+use crate::service::MeteringService;
+
+/// Section name used by [`MeteringSnapshotContributor`].
+pub const METERING_SECTION: &str = "metering";
+
+/// A quota in the metering section.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuotaSummary {
+    /// Metric code.
+    pub metric_code: String,
+    /// Agent the quota applies to (`None` for organization-wide).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<AgentId>,
+    /// Limit per period.
+    pub limit: i64,
+    /// Usage in the current period.
+    pub current_usage: i64,
+    /// Usage as a fraction of the limit.
+    pub utilization: f64,
+    /// End of the current period.
+    pub period_end: DateTime<Utc>,
+}
+
+/// The metering section's data.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MeteringSnapshot {
+    /// Quotas registered for the organization.
+    pub quota_count: usize,
+    /// Most utilized quotas, at most `top_n`.
+    pub top_quotas: Vec<QuotaSummary>,
+    /// Credit balance in cents.
+    pub credit_balance_cents: i64,
+    /// Latest anomaly alerts, newest first, at most `top_n`.
+    pub recent_anomalies: Vec<AnomalyAlert>,
+}
+
+/// Contributes the metering section of an organization snapshot.
+pub struct MeteringSnapshotContributor {
+    service: Arc<MeteringService>,
+}
+
+impl MeteringSnapshotContributor {
+    pub fn new(service: Arc<MeteringService>) -> Self {
+        Self { service }
+    }
+
+    /// Build the section data without the async wrapper.
+    pub fn summarize(&self, request: &SnapshotRequest) -> MeteringSnapshot {
+        let org = request.organization_id;
+        let mut quotas: Vec<QuotaSummary> = self
+            .service
+            .quota_enforcer
+            .counters(0, usize::MAX)
+            .into_iter()
+            .map(|counter| counter.quota)
+            .filter(|quota| quota.organization_id == org)
+            .map(|quota| QuotaSummary {
+                utilization: quota.usage_percentage(),
+                metric_code: quota.metric_code,
+                agent_id: quota.agent_id,
+                limit: quota.limit,
+                current_usage: quota.current_usage,
+                period_end: quota.period_end,
+            })
+            .collect();
+        let quota_count = quotas.len();
+        quotas.sort_by(|a, b| {
+            b.utilization
+                .total_cmp(&a.utilization)
+                .then_with(|| a.metric_code.cmp(&b.metric_code))
+        });
+        quotas.truncate(request.top_n);
+
+        MeteringSnapshot {
+            quota_count,
+            top_quotas: quotas,
+            credit_balance_cents: self.service.get_credit_balance(&org),
+            recent_anomalies: self
+                .service
+                .anomaly_detector()
+                .map(|detector| detector.recent_alerts(org, request.top_n))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[async_trait]
+impl SnapshotContributor for MeteringSnapshotContributor {
+    fn section(&self) -> &str {
+        METERING_SECTION
+    }
+
+    async fn contribute(&self, request: &SnapshotRequest) -> CretoResult<SectionData> {
+        SectionData::new(request.as_of, &self.summarize(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quota::{Quota, QuotaPeriod};
+    use creto_common::OrganizationId;
+
+    #[test]
+    fn test_top_quotas_sorted_by_utilization_and_scoped_to_org() {
+        let service = MeteringService::new();
+        let org = OrganizationId::new();
+        let other = OrganizationId::new();
+        for (code, usage) in [("a", 10), ("b", 90), ("c", 50)] {
+            let mut quota = Quota::new(org, code, 100, QuotaPeriod::Daily);
+            quota.current_usage = usage;
+            service.register_quota(&quota);
+        }
+        service.register_quota(&Quota::new(other, "a", 100, QuotaPeriod::Daily));
+
+        let contributor = MeteringSnapshotContributor::new(Arc::new(service));
+        let snapshot = contributor.summarize(&SnapshotRequest {
+            organization_id: org,
+            as_of: Utc::now(),
+            top_n: 2,
+        });
+
+        assert_eq!(snapshot.quota_count, 3);
+        let codes: Vec<&str> = snapshot
+            .top_quotas
+            .iter()
+            .map(|q| q.metric_code.as_str())
+            .collect();
+        assert_eq!(codes, ["b", "c"]);
+        assert!(snapshot.recent_anomalies.is_empty());
+    }
+}
@@ -6,6 +6,7 @@
 use chrono::{DateTime, Duration, Utc};
 use thiserror::Error;
 
+use crate::dedup::DedupConfig;
 use crate::events::UsageEvent;
 
 /// Validation error types for usage events.
@@ -23,16 +24,20 @@ pub enum ValidationError {
     #[error("Quantity exceeds maximum allowed value of {max}")]
     QuantityTooLarge { value: i64, max: i64 },
 
-    #[error("Event timestamp {timestamp} is too far in the future (max {max_hours}h ahead)")]
+    #[error(
+        "Event timestamp {timestamp} is {offset_seconds}s ahead of server time; \
+         the producer clock appears to be off by {offset_seconds}s (max skew {max_skew_seconds}s)"
+    )]
     TimestampTooFuture {
         timestamp: DateTime<Utc>,
-        max_hours: i64,
+        offset_seconds: i64,
+        max_skew_seconds: i64,
     },
 
-    #[error("Event timestamp {timestamp} is too old (max {max_days}d in the past)")]
+    #[error("Event timestamp {timestamp} is too old (max {max_age_seconds}s in the past)")]
     TimestampTooOld {
         timestamp: DateTime<Utc>,
-        max_days: i64,
+        max_age_seconds: i64,
     },
 
     #[error("Metric code is required and must be non-empty")]
@@ -91,10 +96,11 @@ pub struct ValidationConfig {
     pub max_transaction_id_length: usize,
     /// Maximum quantity value per event.
     pub max_quantity: i64,
-    /// Maximum hours in the future for event timestamp.
-    pub max_future_hours: i64,
-    /// Maximum days in the past for event timestamp.
-    pub max_past_days: i64,
+    /// How far ahead of server time an event timestamp may be, to allow for
+    /// producer clock skew.
+    pub max_future_skew: Duration,
+    /// How far behind server time an event timestamp may be.
+    pub max_past_age: Duration,
     /// Maximum size of properties JSON in bytes.
     pub max_properties_bytes: usize,
     /// Maximum delegation depth.
@@ -110,8 +116,8 @@ impl Default for ValidationConfig {
         Self {
             max_transaction_id_length: 255,
             max_quantity: 1_000_000_000, // 1 billion
-            max_future_hours: 1,         // 1 hour ahead
-            max_past_days: 30,           // 30 days back
+            max_future_skew: Duration::hours(1),
+            max_past_age: Duration::days(30),
             max_properties_bytes: 65536, // 64KB
             max_delegation_depth: 10,
             max_external_subscription_id_length: 255,
@@ -126,8 +132,8 @@ impl ValidationConfig {
         Self {
             max_transaction_id_length: 128,
             max_quantity: 100_000_000,
-            max_future_hours: 0,         // No future events
-            max_past_days: 7,            // Only 7 days back
+            max_future_skew: Duration::minutes(5),
+            max_past_age: Duration::days(7),
             max_properties_bytes: 16384, // 16KB
             max_delegation_depth: 5,
             max_external_subscription_id_length: 128,
@@ -140,14 +146,65 @@ impl ValidationConfig {
         Self {
             max_transaction_id_length: 512,
             max_quantity: i64::MAX,
-            max_future_hours: 24,
-            max_past_days: 365,
+            max_future_skew: Duration::hours(24),
+            max_past_age: Duration::days(365),
             max_properties_bytes: 1048576, // 1MB
             max_delegation_depth: 20,
             max_external_subscription_id_length: 512,
             collect_all_errors: true,
         }
     }
+
+    /// Check the acceptance windows against the deduplication retention.
+    ///
+    /// An event seen while up to `max_future_skew` ahead can be replayed
+    /// until it is `max_past_age` old, so the transaction ID must be
+    /// retained for at least the sum of the two. Otherwise a replay that
+    /// validation still accepts is no longer recognized as a duplicate and
+    /// is billed twice.
+    pub fn check_dedup_coverage(&self, dedup: &DedupConfig) -> Result<(), WindowConfigError> {
+        if self.max_future_skew < Duration::zero() {
+            return Err(WindowConfigError::NegativeWindow("max_future_skew"));
+        }
+        if self.max_past_age < Duration::zero() {
+            return Err(WindowConfigError::NegativeWindow("max_past_age"));
+        }
+        let required = (self.max_past_age + self.max_future_skew).num_seconds();
+        let retention = dedup.min_ttl_seconds();
+        if (retention as i64) < required {
+            return Err(WindowConfigError::DedupRetentionTooShort {
+                retention_seconds: retention,
+                required_seconds: required,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Incoherent event acceptance and deduplication windows.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WindowConfigError {
+    #[error("Acceptance window {0} must not be negative")]
+    NegativeWindow(&'static str),
+
+    #[error(
+        "Deduplication retention of {retention_seconds}s is shorter than the \
+         {required_seconds}s an accepted event can be replayed for"
+    )]
+    DedupRetentionTooShort {
+        retention_seconds: u64,
+        required_seconds: i64,
+    },
+}
+
+impl WindowConfigError {
+    /// Return the error code for this error variant.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NegativeWindow(_) => "ENABLE-112",
+            Self::DedupRetentionTooShort { .. } => "ENABLE-113",
+        }
+    }
 }
 
 /// Validator for usage events.
@@ -166,9 +223,34 @@ impl EventValidator {
         Self::new(ValidationConfig::default())
     }
 
+    /// Get the validation configuration.
+    pub fn config(&self) -> &ValidationConfig {
+        &self.config
+    }
+
     /// Validate a usage event.
     pub fn validate(&self, event: &UsageEvent) -> Result<(), ValidationError> {
-        let mut errors = Vec::new();
+        self.validate_at(event, Utc::now())
+    }
+
+    /// Validate a usage event against server time `now`.
+    pub fn validate_at(
+        &self,
+        event: &UsageEvent,
+        now: DateTime<Utc>,
+    ) -> Result<(), ValidationError> {
+        self.validate_into(event, now, &mut Vec::new())
+    }
+
+    /// Validate `event`, collecting errors in `errors` (cleared first) so a
+    /// batch can reuse one buffer.
+    fn validate_into(
+        &self,
+        event: &UsageEvent,
+        now: DateTime<Utc>,
+        errors: &mut Vec<ValidationError>,
+    ) -> Result<(), ValidationError> {
+        errors.clear();
 
         // Transaction ID validation
         if event.transaction_id.is_empty() {
@@ -205,14 +287,14 @@ impl EventValidator {
         }
 
         // Timestamp validation
-        let now = Utc::now();
-        let max_future = now + Duration::hours(self.config.max_future_hours);
-        let max_past = now - Duration::days(self.config.max_past_days);
+        let max_future = now + self.config.max_future_skew;
+        let max_past = now - self.config.max_past_age;
 
         if event.timestamp > max_future {
             let err = ValidationError::TimestampTooFuture {
                 timestamp: event.timestamp,
-                max_hours: self.config.max_future_hours,
+                offset_seconds: (event.timestamp - now).num_seconds(),
+                max_skew_seconds: self.config.max_future_skew.num_seconds(),
             };
             if !self.config.collect_all_errors {
                 return Err(err);
@@ -223,7 +305,7 @@ impl EventValidator {
         if event.timestamp < max_past {
             let err = ValidationError::TimestampTooOld {
                 timestamp: event.timestamp,
-                max_days: self.config.max_past_days,
+                max_age_seconds: self.config.max_past_age.num_seconds(),
             };
             if !self.config.collect_all_errors {
                 return Err(err);
@@ -247,7 +329,7 @@ impl EventValidator {
         }
 
         // Properties size validation
-        let properties_size = event.properties.to_string().len();
+        let properties_size = serialized_len(&event.properties);
         if properties_size > self.config.max_properties_bytes {
             let err = ValidationError::PropertiesTooLarge {
                 size: properties_size,
@@ -284,55 +366,88 @@ impl EventValidator {
             }
         }
 
-        if errors.is_empty() {
-            Ok(())
-        } else if errors.len() == 1 {
-            Err(errors.remove(0))
-        } else {
-            Err(ValidationError::Multiple(errors))
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(ValidationError::Multiple(std::mem::take(errors))),
         }
     }
 
-    /// Validate a batch of events, returning valid events and errors.
+    /// Validate a batch of events.
     pub fn validate_batch(&self, events: &[UsageEvent]) -> BatchValidationResult {
-        let mut valid = Vec::with_capacity(events.len());
-        let mut invalid = Vec::new();
+        self.validate_batch_at(events, Utc::now())
+    }
 
-        for (idx, event) in events.iter().enumerate() {
-            match self.validate(event) {
-                Ok(()) => valid.push(event.clone()),
-                Err(e) => invalid.push((idx, e)),
-            }
+    /// Validate a batch of events against one server time `now`.
+    ///
+    /// Outcomes are index-aligned with `events`. One error buffer is shared
+    /// by the whole batch, so valid events allocate nothing.
+    pub fn validate_batch_at(
+        &self,
+        events: &[UsageEvent],
+        now: DateTime<Utc>,
+    ) -> BatchValidationResult {
+        let mut errors = Vec::new();
+        BatchValidationResult {
+            outcomes: events
+                .iter()
+                .map(|event| self.validate_into(event, now, &mut errors))
+                .collect(),
         }
-
-        BatchValidationResult { valid, invalid }
     }
 }
 
 /// Result of batch validation.
 #[derive(Debug)]
 pub struct BatchValidationResult {
-    /// Events that passed validation.
-    pub valid: Vec<UsageEvent>,
-    /// Index and error for events that failed validation.
-    pub invalid: Vec<(usize, ValidationError)>,
+    /// Outcome for each event, in input order.
+    pub outcomes: Vec<Result<(), ValidationError>>,
 }
 
 impl BatchValidationResult {
     /// Check if all events passed validation.
     pub fn all_valid(&self) -> bool {
-        self.invalid.is_empty()
+        self.outcomes.iter().all(Result::is_ok)
     }
 
     /// Get the count of valid events.
     pub fn valid_count(&self) -> usize {
-        self.valid.len()
+        self.outcomes.iter().filter(|o| o.is_ok()).count()
     }
 
     /// Get the count of invalid events.
     pub fn invalid_count(&self) -> usize {
-        self.invalid.len()
+        self.outcomes.len() - self.valid_count()
+    }
+
+    /// Index and error for events that failed validation.
+    pub fn invalid(&self) -> impl Iterator<Item = (usize, &ValidationError)> {
+        self.outcomes
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, outcome)| outcome.as_ref().err().map(|e| (idx, e)))
+    }
+}
+
+/// Length of `value` serialized as compact JSON, without building the string.
+fn serialized_len(value: &serde_json::Value) -> usize {
+    struct Counter(usize);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
     }
+
+    let mut counter = Counter(0);
+    // Writing to a counter cannot fail and a Value always serializes
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
 }
 
 /// Check if a metric code is valid (alphanumeric and underscores only).
@@ -346,6 +461,7 @@ fn is_valid_metric_code(code: &str) -> bool {
 mod tests {
     use super::*;
     use crate::events::{UsageEvent, UsageEventType};
+    use creto_common::OrganizationId;
 
     fn valid_event() -> UsageEvent {
         UsageEvent::builder()
@@ -416,7 +532,7 @@ mod tests {
     #[test]
     fn test_future_timestamp_fails() {
         let validator = EventValidator::new(ValidationConfig {
-            max_future_hours: 0,
+            max_future_skew: Duration::zero(),
             ..Default::default()
         });
         let mut event = valid_event();
@@ -432,7 +548,7 @@ mod tests {
     #[test]
     fn test_old_timestamp_fails() {
         let validator = EventValidator::new(ValidationConfig {
-            max_past_days: 7,
+            max_past_age: Duration::days(7),
             ..Default::default()
         });
         let mut event = valid_event();
@@ -484,7 +600,50 @@ mod tests {
 
         assert_eq!(result.valid_count(), 2);
         assert_eq!(result.invalid_count(), 1);
-        assert_eq!(result.invalid[0].0, 1); // Index of invalid event
+        assert_eq!(result.invalid().next().unwrap().0, 1); // Index of invalid event
+        assert!(result.outcomes[1].is_err());
+    }
+
+    #[test]
+    fn test_batch_matches_single_event_validation() {
+        let validator = EventValidator::new(ValidationConfig {
+            collect_all_errors: true,
+            max_properties_bytes: 32,
+            ..Default::default()
+        });
+        let now = Utc::now();
+        let events: Vec<UsageEvent> = (0..50)
+            .map(|i| {
+                let mut event = valid_event();
+                match i % 5 {
+                    1 => event.quantity = 0,
+                    2 => event.properties = serde_json::json!({ "padding": "x".repeat(40) }),
+                    3 => {
+                        event.code = String::new();
+                        event.transaction_id = String::new();
+                    }
+                    _ => {}
+                }
+                event
+            })
+            .collect();
+
+        let batch = validator.validate_batch_at(&events, now);
+        assert_eq!(batch.outcomes.len(), events.len());
+        for (event, outcome) in events.iter().zip(&batch.outcomes) {
+            let single = validator.validate_at(event, now);
+            assert_eq!(
+                outcome.as_ref().map_err(|e| e.to_string()),
+                single.as_ref().map_err(|e| e.to_string())
+            );
+        }
+        assert_eq!(batch.invalid_count(), 30);
+    }
+
+    #[test]
+    fn test_serialized_len_matches_to_string() {
+        let value = serde_json::json!({ "endpoint": "/api/v1", "codes": [200, 404], "ok": true });
+        assert_eq!(serialized_len(&value), value.to_string().len());
     }
 
     #[test]
@@ -524,4 +683,82 @@ mod tests {
             Err(ValidationError::DelegationDepthTooDeep { .. })
         ));
     }
+
+    #[test]
+    fn test_future_skew_reports_offset() {
+        let validator = EventValidator::new(ValidationConfig::strict());
+        let now = Utc::now();
+        let mut event = valid_event();
+        event.timestamp = now + Duration::minutes(40);
+
+        let err = validator.validate_at(&event, now).unwrap_err();
+        assert!(matches!(
+            err,
+            ValidationError::TimestampTooFuture {
+                offset_seconds: 2400,
+                max_skew_seconds: 300,
+                ..
+            }
+        ));
+        assert_eq!(err.code(), "ENABLE-104");
+        assert!(err.to_string().contains("off by 2400s"));
+
+        // Within the skew allowance
+        event.timestamp = now + Duration::minutes(4);
+        assert!(validator.validate_at(&event, now).is_ok());
+    }
+
+    #[test]
+    fn test_dedup_retention_must_cover_acceptance_window() {
+        let config = ValidationConfig {
+            max_future_skew: Duration::minutes(5),
+            max_past_age: Duration::days(7),
+            ..Default::default()
+        };
+        let dedup = |ttl_seconds| DedupConfig {
+            ttl_seconds,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            config.check_dedup_coverage(&dedup(86400)),
+            Err(WindowConfigError::DedupRetentionTooShort {
+                retention_seconds: 86400,
+                required_seconds: 7 * 86400 + 300,
+            })
+        );
+        // Covering the past age alone misses events first seen while ahead
+        assert!(config.check_dedup_coverage(&dedup(7 * 86400)).is_err());
+        assert!(config.check_dedup_coverage(&dedup(7 * 86400 + 300)).is_ok());
+
+        // Every organization's override must cover it too
+        let mut overridden = dedup(31 * 86400);
+        overridden
+            .organization_ttl_seconds
+            .insert(OrganizationId::new(), 3600);
+        assert_eq!(
+            config.check_dedup_coverage(&overridden),
+            Err(WindowConfigError::DedupRetentionTooShort {
+                retention_seconds: 3600,
+                required_seconds: 7 * 86400 + 300,
+            })
+        );
+
+        let negative = ValidationConfig {
+            max_future_skew: Duration::minutes(-1),
+            ..Default::default()
+        };
+        assert_eq!(
+            negative.check_dedup_coverage(&DedupConfig::default()),
+            Err(WindowConfigError::NegativeWindow("max_future_skew"))
+        );
+
+        // The defaults are coherent
+        assert!(ValidationConfig::default()
+            .check_dedup_coverage(&DedupConfig::default())
+            .is_ok());
+        assert!(ValidationConfig::strict()
+            .check_dedup_coverage(&DedupConfig::default())
+            .is_ok());
+    }
 }
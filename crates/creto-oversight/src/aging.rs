@@ -0,0 +1,201 @@
+//! Priority escalation of requests nearing their timeout.
+//!
+//! A Normal request can sit behind a steady stream of newer High ones until
+//! it times out undecided. An [`AgingPolicy`] raises a pending request's
+//! priority as its timeout approaches: by default one level once 75% of the
+//! timeout has elapsed, and to Critical at 90%.
+//!
+//! Boosts are applied by
+//! [`OversightService::boost_aging_requests`](crate::service::OversightService::boost_aging_requests),
+//! which the service's timeout worker runs on every pass. Each boost is
+//! recorded in the transition audit trail as a system transition with
+//! reason [`AGING_REASON`] and reviewers are notified again. A boost never
+//! lowers a priority, and the priority the request was created with stays on
+//! the request as [`OversightRequest::original_priority`].
+
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::request::{OversightRequest, Priority};
+
+/// Reason recorded on the audit entry of an aging boost.
+pub const AGING_REASON: &str = "aging";
+
+/// How far a threshold raises a request's priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "priority", rename_all = "snake_case")]
+pub enum PriorityBoost {
+    /// One level above the priority reached so far.
+    OneLevel,
+    /// At least this priority.
+    To(Priority),
+}
+
+/// A point in a request's timeout at which its priority is raised.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AgingThreshold {
+    /// Fraction of the timeout elapsed, in (0, 1].
+    pub elapsed: f64,
+    /// How far the priority is raised.
+    pub boost: PriorityBoost,
+}
+
+/// When pending requests are boosted as they age.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgingPolicy {
+    /// Thresholds, applied in order of `elapsed`.
+    pub thresholds: Vec<AgingThreshold>,
+}
+
+impl Default for AgingPolicy {
+    fn default() -> Self {
+        Self::new()
+            .with_threshold(0.75, PriorityBoost::OneLevel)
+            .with_threshold(0.90, PriorityBoost::To(Priority::Critical))
+    }
+}
+
+impl AgingPolicy {
+    /// Create a policy with no thresholds.
+    pub fn new() -> Self {
+        Self {
+            thresholds: Vec::new(),
+        }
+    }
+
+    /// Add a threshold.
+    pub fn with_threshold(mut self, elapsed: f64, boost: PriorityBoost) -> Self {
+        self.thresholds.push(AgingThreshold { elapsed, boost });
+        self
+    }
+
+    /// Check every threshold is a fraction of the timeout.
+    pub fn validate(&self) -> CretoResult<()> {
+        for threshold in &self.thresholds {
+            if !threshold.elapsed.is_finite() || threshold.elapsed <= 0.0 || threshold.elapsed > 1.0
+            {
+                return Err(CretoError::ValidationFailed(format!(
+                    "Aging threshold must be a fraction of the timeout in (0, 1], got {}",
+                    threshold.elapsed
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Priority `request` should have at `now`, if higher than its current
+    /// one.
+    ///
+    /// Thresholds apply to the priority the request was created with, so
+    /// evaluating again after a boost does not boost twice.
+    pub fn boosted_priority(
+        &self,
+        request: &OversightRequest,
+        now: DateTime<Utc>,
+    ) -> Option<Priority> {
+        let elapsed = elapsed_fraction(request, now);
+        let mut thresholds = self.thresholds.clone();
+        thresholds.sort_by(|a, b| a.elapsed.total_cmp(&b.elapsed));
+
+        let target = thresholds.iter().filter(|t| elapsed >= t.elapsed).fold(
+            request.original_priority(),
+            |priority, t| match t.boost {
+                PriorityBoost::OneLevel => priority.raised(),
+                PriorityBoost::To(to) => priority.max(to),
+            },
+        );
+        (target > request.priority).then_some(target)
+    }
+}
+
+/// A priority raised by aging.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgingBoost {
+    /// Request boosted.
+    pub request_id: Uuid,
+    /// Organization the request belongs to.
+    pub organization_id: OrganizationId,
+    /// Priority before the boost.
+    pub from: Priority,
+    /// Priority after the boost.
+    pub to: Priority,
+    /// Priority the request was created with.
+    pub original: Priority,
+}
+
+/// Fraction of `request`'s timeout elapsed at `now`.
+fn elapsed_fraction(request: &OversightRequest, now: DateTime<Utc>) -> f64 {
+    let total = (request.expires_at - request.created_at).num_milliseconds();
+    if total <= 0 {
+        return 1.0;
+    }
+    (now - request.created_at).num_milliseconds() as f64 / total as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::ActionType;
+    use creto_common::AgentId;
+
+    fn request(priority: Priority) -> OversightRequest {
+        OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        )
+        .with_priority(priority)
+        .with_timeout(1000)
+    }
+
+    #[test]
+    fn test_default_thresholds() {
+        let policy = AgingPolicy::default();
+        let mut normal = request(Priority::Normal);
+        let at = |seconds| normal.created_at + chrono::Duration::seconds(seconds);
+
+        assert_eq!(policy.boosted_priority(&normal, at(749)), None);
+        assert_eq!(
+            policy.boosted_priority(&normal, at(750)),
+            Some(Priority::High)
+        );
+        assert_eq!(
+            policy.boosted_priority(&normal, at(900)),
+            Some(Priority::Critical)
+        );
+
+        // Once boosted, the same threshold does not fire again
+        normal.original_priority = Some(Priority::Normal);
+        normal.priority = Priority::High;
+        assert_eq!(policy.boosted_priority(&normal, at(800)), None);
+
+        // Already above the target: never lowered
+        let critical = request(Priority::Critical);
+        assert_eq!(
+            policy.boosted_priority(&critical, critical.expires_at),
+            None
+        );
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        assert!(AgingPolicy::default().validate().is_ok());
+        assert!(AgingPolicy::new()
+            .with_threshold(0.0, PriorityBoost::OneLevel)
+            .validate()
+            .is_err());
+        assert!(AgingPolicy::new()
+            .with_threshold(1.5, PriorityBoost::OneLevel)
+            .validate()
+            .is_err());
+        assert!(AgingPolicy::new()
+            .with_threshold(f64::NAN, PriorityBoost::OneLevel)
+            .validate()
+            .is_err());
+    }
+}
@@ -5,6 +5,8 @@ use creto_common::UserId;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::directory::ReviewerGroup;
+
 /// An approval decision by a reviewer.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Approval {
@@ -27,7 +29,8 @@ pub struct Approval {
     /// When the decision was made.
     pub decided_at: DateTime<Utc>,
 
-    /// Weight of this approval (for weighted quorum).
+    /// Weight of this approval (for weighted quorum), as resolved for the
+    /// reviewer when they decided.
     #[serde(default = "default_weight")]
     pub weight: u32,
 }
@@ -141,16 +144,78 @@ impl QuorumConfig {
 /// Calculator for quorum decisions.
 pub struct QuorumCalculator {
     config: QuorumConfig,
+    group: Option<ReviewerGroup>,
+    pool: Option<ReviewerPool>,
+}
+
+/// Reviewers assigned to a request and how many of them lapsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ReviewerPool {
+    assigned: u32,
+    lapsed: u32,
 }
 
 impl QuorumCalculator {
     /// Create a new quorum calculator with the given configuration.
     pub fn new(config: QuorumConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            group: None,
+            pool: None,
+        }
+    }
+
+    /// Only count decisions by reviewers who belonged to `group` when they
+    /// decided.
+    ///
+    /// Later membership changes, and the group being disabled, do not
+    /// affect decisions already made.
+    pub fn with_reviewer_group(mut self, group: ReviewerGroup) -> Self {
+        self.group = Some(group);
+        self
+    }
+
+    /// Exclude reviewers who missed their deadline from the quorum.
+    ///
+    /// Of `assigned` reviewers, `lapsed` are no longer required: the
+    /// approvals required drop to at most the number of reviewers left, and
+    /// never below one. Weighted quorums are unaffected.
+    pub fn with_lapsed_reviewers(mut self, assigned: u32, lapsed: u32) -> Self {
+        self.pool = Some(ReviewerPool { assigned, lapsed });
+        self
+    }
+
+    /// Approvals required once lapsed reviewers are excluded.
+    pub fn required_approvals(&self) -> u32 {
+        match self.pool {
+            Some(pool) if pool.lapsed > 0 => self
+                .config
+                .required_approvals
+                .min(pool.assigned.saturating_sub(pool.lapsed))
+                .max(1),
+            _ => self.config.required_approvals,
+        }
     }
 
     /// Evaluate the current approvals against the quorum.
     pub fn evaluate(&self, approvals: &[Approval]) -> QuorumResult {
+        let approvals: Vec<&Approval> = approvals
+            .iter()
+            .filter(|a| match &self.group {
+                Some(group) => group.was_member_at(a.reviewer_id, a.decided_at),
+                None => true,
+            })
+            .collect();
+
+        let breakdown: Vec<WeightedDecision> = approvals
+            .iter()
+            .map(|a| WeightedDecision {
+                reviewer_id: a.reviewer_id,
+                decision: a.decision,
+                weight: a.weight,
+            })
+            .collect();
+
         let approve_count = approvals
             .iter()
             .filter(|a| a.decision == ApprovalDecision::Approve)
@@ -178,6 +243,7 @@ impl QuorumCalculator {
             return QuorumResult::Rejected {
                 approve_count,
                 reject_count,
+                breakdown,
             };
         }
 
@@ -187,12 +253,14 @@ impl QuorumCalculator {
                 return QuorumResult::Rejected {
                     approve_count,
                     reject_count,
+                    breakdown,
                 };
             }
             if approve_weight >= required_weight {
                 return QuorumResult::Approved {
                     approve_count,
                     total_weight: approve_weight,
+                    breakdown,
                 };
             }
             return QuorumResult::Pending {
@@ -200,45 +268,69 @@ impl QuorumCalculator {
                 required: self.config.required_approvals,
                 current_weight: approve_weight,
                 required_weight: Some(required_weight),
+                breakdown,
             };
         }
 
         // Check count-based quorum
-        if approve_count >= self.config.required_approvals {
+        let required = self.required_approvals();
+        if approve_count >= required {
             // For unanimous, check no rejections
             if self.config.require_unanimous && reject_count > 0 {
                 return QuorumResult::Rejected {
                     approve_count,
                     reject_count,
+                    breakdown,
                 };
             }
             return QuorumResult::Approved {
                 approve_count,
                 total_weight: approve_weight,
+                breakdown,
             };
         }
 
         QuorumResult::Pending {
             approve_count,
-            required: self.config.required_approvals,
+            required,
             current_weight: approve_weight,
             required_weight: self.config.required_weight,
+            breakdown,
         }
     }
 }
 
+/// A counted decision and the weight it carried.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightedDecision {
+    /// Reviewer who decided.
+    pub reviewer_id: UserId,
+    /// Their decision.
+    pub decision: ApprovalDecision,
+    /// Weight recorded on their approval.
+    pub weight: u32,
+}
+
 /// Result of a quorum evaluation.
-#[derive(Debug, Clone, PartialEq, Eq)]
+///
+/// Each outcome carries the breakdown of the decisions counted towards it,
+/// one per reviewer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
 pub enum QuorumResult {
     /// Quorum reached, request approved.
     Approved {
         approve_count: u32,
         total_weight: u32,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        breakdown: Vec<WeightedDecision>,
     },
     /// Request rejected.
     Rejected {
         approve_count: u32,
         reject_count: u32,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        breakdown: Vec<WeightedDecision>,
     },
     /// Still waiting for more approvals.
     Pending {
@@ -246,6 +338,8 @@ pub enum QuorumResult {
         required: u32,
         current_weight: u32,
         required_weight: Option<u32>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        breakdown: Vec<WeightedDecision>,
     },
 }
 
@@ -264,6 +358,15 @@ impl QuorumResult {
     pub fn is_pending(&self) -> bool {
         matches!(self, QuorumResult::Pending { .. })
     }
+
+    /// Decisions counted towards the result, with their weights.
+    pub fn breakdown(&self) -> &[WeightedDecision] {
+        match self {
+            QuorumResult::Approved { breakdown, .. }
+            | QuorumResult::Rejected { breakdown, .. }
+            | QuorumResult::Pending { breakdown, .. } => breakdown,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -321,4 +424,69 @@ mod tests {
         ]);
         assert!(result.is_approved());
     }
+
+    #[test]
+    fn test_lapsed_reviewers_reduce_requirement() {
+        let approvals = [
+            make_approval(ApprovalDecision::Approve, 1),
+            make_approval(ApprovalDecision::Approve, 1),
+        ];
+
+        // 3 of 3 with one lapsed: 2 of the remaining 2
+        let calc = QuorumCalculator::new(QuorumConfig::n_of_m(3)).with_lapsed_reviewers(3, 1);
+        assert_eq!(calc.required_approvals(), 2);
+        assert!(calc.evaluate(&approvals).is_approved());
+
+        // 2 of 4 with one lapsed: still 2 of the remaining 3
+        let calc = QuorumCalculator::new(QuorumConfig::n_of_m(2)).with_lapsed_reviewers(4, 1);
+        assert_eq!(calc.required_approvals(), 2);
+
+        // Everyone lapsed: one approval is still required
+        let calc = QuorumCalculator::new(QuorumConfig::n_of_m(2)).with_lapsed_reviewers(2, 2);
+        assert_eq!(
+            calc.evaluate(&[]),
+            QuorumResult::Pending {
+                approve_count: 0,
+                required: 1,
+                current_weight: 0,
+                required_weight: None,
+                breakdown: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_group_quorum_counts_members_at_decision_time() {
+        let now = Utc::now();
+        let (member, former, outsider) = (UserId::new(), UserId::new(), UserId::new());
+        let mut group = ReviewerGroup::new(creto_common::OrganizationId::new(), "finance", now);
+        group.add_member(member, now - chrono::Duration::hours(2));
+        group.add_member(former, now - chrono::Duration::hours(2));
+        let request_id = Uuid::new_v4();
+        let decided = |reviewer| {
+            let mut approval = Approval::new(request_id, reviewer, ApprovalDecision::Approve);
+            approval.decided_at = now - chrono::Duration::hours(1);
+            approval
+        };
+        let approvals = [decided(member), decided(former), decided(outsider)];
+
+        // Removed after deciding: still counts
+        group.remove_member(former, now);
+        let calc =
+            QuorumCalculator::new(QuorumConfig::n_of_m(2)).with_reviewer_group(group.clone());
+        let result = calc.evaluate(&approvals);
+        assert!(matches!(
+            result,
+            QuorumResult::Approved {
+                approve_count: 2,
+                total_weight: 2,
+                ..
+            }
+        ));
+        let counted: Vec<UserId> = result.breakdown().iter().map(|d| d.reviewer_id).collect();
+        assert_eq!(counted, vec![member, former]);
+
+        let calc = QuorumCalculator::new(QuorumConfig::n_of_m(3)).with_reviewer_group(group);
+        assert!(calc.evaluate(&approvals).is_pending());
+    }
 }
@@ -0,0 +1,413 @@
+//! Per-reviewer decision deadlines.
+//!
+//! A request's timeout covers its whole approval lifecycle. With a
+//! multi-reviewer quorum, each reviewer also gets their own deadline: when
+//! they are assigned, directly, through a reviewer group or by claiming the
+//! next request, the [`ReviewerAssignment`] records a `respond_by` derived
+//! from the request's priority.
+//!
+//! [`OversightService::lapse_overdue_assignments`](crate::service::OversightService::lapse_overdue_assignments),
+//! run by the service's timeout worker, marks assignments past their deadline
+//! as [`AssignmentStatus::Lapsed`], notifies the organization's escalation
+//! contact and records the lapse in the transition audit trail with reason
+//! [`LAPSE_REASON`]. What a lapse does to the quorum depends on the
+//! organization's [`LapseMode`]: excluded reviewers no longer count towards
+//! the approvals required, so the request may be decided right away; kept
+//! reviewers are still required and the lapse is only escalated.
+//!
+//! A lapsed reviewer's late decision is rejected with
+//! [`CretoError::AssignmentLapsed`](creto_common::CretoError::AssignmentLapsed)
+//! unless the organization allows late decisions.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{CretoResult, OrganizationId, UserId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::request::{OversightRequest, Priority, RequestStatus};
+
+/// Reason recorded on the audit entry of a lapsed assignment.
+pub const LAPSE_REASON: &str = "assignment_lapsed";
+
+/// How a reviewer came to be assigned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AssignmentSource {
+    /// Assigned by name.
+    Direct,
+    /// Assigned as a member of a reviewer group.
+    Group { name: String },
+    /// Claimed by the reviewer as their next request.
+    Claim,
+}
+
+/// Where an assignment stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentStatus {
+    /// Waiting for the reviewer's decision.
+    Active,
+    /// The reviewer decided.
+    Responded,
+    /// The reviewer missed their deadline.
+    Lapsed,
+}
+
+/// A reviewer assigned to a request, and when they must respond by.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewerAssignment {
+    /// Assignment ID.
+    pub id: Uuid,
+
+    /// Request the reviewer is assigned to.
+    pub request_id: Uuid,
+
+    /// Organization the request belongs to.
+    pub organization_id: OrganizationId,
+
+    /// The assigned reviewer.
+    pub reviewer_id: UserId,
+
+    /// How the reviewer was assigned.
+    pub source: AssignmentSource,
+
+    /// Where the assignment stands.
+    pub status: AssignmentStatus,
+
+    /// When the reviewer was assigned.
+    pub assigned_at: DateTime<Utc>,
+
+    /// When the reviewer's decision is due.
+    pub respond_by: DateTime<Utc>,
+
+    /// When the assignment lapsed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lapsed_at: Option<DateTime<Utc>>,
+}
+
+impl ReviewerAssignment {
+    /// Whether the reviewer still owes a decision past their deadline.
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.status == AssignmentStatus::Active && self.respond_by <= now
+    }
+}
+
+/// What a lapsed reviewer means for the quorum.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LapseMode {
+    /// The reviewer's approval is no longer required; the quorum is
+    /// recalculated over the remaining reviewers.
+    #[default]
+    Exclude,
+    /// The reviewer is still required; the lapse is only escalated.
+    KeepAndEscalate,
+}
+
+/// An organization's reviewer deadline settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewerDeadlinePolicy {
+    /// Seconds a reviewer has to respond, by request priority.
+    pub respond_within: BTreeMap<Priority, u64>,
+
+    /// What a lapse does to the quorum.
+    #[serde(default)]
+    pub lapse_mode: LapseMode,
+
+    /// Whether lapsed reviewers may still decide.
+    #[serde(default)]
+    pub allow_late_decisions: bool,
+
+    /// Who is told when a reviewer lapses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub escalation_contact: Option<UserId>,
+}
+
+impl Default for ReviewerDeadlinePolicy {
+    fn default() -> Self {
+        Self {
+            respond_within: BTreeMap::from([
+                (Priority::Low, 24 * 3600),
+                (Priority::Normal, 8 * 3600),
+                (Priority::High, 4 * 3600),
+                (Priority::Critical, 3600),
+            ]),
+            lapse_mode: LapseMode::default(),
+            allow_late_decisions: false,
+            escalation_contact: None,
+        }
+    }
+}
+
+impl ReviewerDeadlinePolicy {
+    /// Set how long reviewers have to respond at `priority`.
+    pub fn with_deadline(mut self, priority: Priority, seconds: u64) -> Self {
+        self.respond_within.insert(priority, seconds);
+        self
+    }
+
+    /// Set what a lapse does to the quorum.
+    pub fn with_lapse_mode(mut self, mode: LapseMode) -> Self {
+        self.lapse_mode = mode;
+        self
+    }
+
+    /// Let lapsed reviewers still decide.
+    pub fn with_late_decisions(mut self, allow: bool) -> Self {
+        self.allow_late_decisions = allow;
+        self
+    }
+
+    /// Set who is told when a reviewer lapses.
+    pub fn with_escalation_contact(mut self, contact: UserId) -> Self {
+        self.escalation_contact = Some(contact);
+        self
+    }
+
+    /// When a reviewer assigned to `request` at `assigned_at` must respond.
+    ///
+    /// Never later than the request's own timeout; priorities without a
+    /// configured deadline use the timeout.
+    pub fn respond_by(
+        &self,
+        request: &OversightRequest,
+        assigned_at: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        self.respond_within
+            .get(&request.priority)
+            .map(|seconds| assigned_at + Duration::seconds(*seconds as i64))
+            .map_or(request.expires_at, |due| due.min(request.expires_at))
+    }
+}
+
+/// Reviewer deadline settings, with per-organization overrides.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewerDeadlineConfig {
+    /// Settings for organizations without an override.
+    pub default: ReviewerDeadlinePolicy,
+
+    /// Per-organization overrides.
+    #[serde(default)]
+    pub organizations: HashMap<OrganizationId, ReviewerDeadlinePolicy>,
+}
+
+impl ReviewerDeadlineConfig {
+    /// Use `policy` for organizations without an override.
+    pub fn new(policy: ReviewerDeadlinePolicy) -> Self {
+        Self {
+            default: policy,
+            organizations: HashMap::new(),
+        }
+    }
+
+    /// Override the settings of one organization.
+    pub fn with_organization(
+        mut self,
+        organization_id: OrganizationId,
+        policy: ReviewerDeadlinePolicy,
+    ) -> Self {
+        self.organizations.insert(organization_id, policy);
+        self
+    }
+
+    /// Settings that apply to `organization_id`.
+    pub fn policy_for(&self, organization_id: OrganizationId) -> &ReviewerDeadlinePolicy {
+        self.organizations
+            .get(&organization_id)
+            .unwrap_or(&self.default)
+    }
+}
+
+/// A reviewer marked as having missed their deadline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AssignmentLapse {
+    /// The lapsed assignment.
+    pub assignment: ReviewerAssignment,
+
+    /// What the lapse did to the quorum.
+    pub mode: LapseMode,
+
+    /// Status the request was decided with once the quorum was
+    /// recalculated, if it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decided: Option<RequestStatus>,
+}
+
+/// Storage for reviewer assignments.
+#[async_trait]
+pub trait AssignmentStore: Send + Sync {
+    /// Record an assignment. A reviewer already assigned to the request
+    /// keeps their existing assignment, which is returned instead.
+    async fn assign(&self, assignment: &ReviewerAssignment) -> CretoResult<ReviewerAssignment>;
+
+    /// Assignments of a request, oldest first.
+    async fn list_by_request(&self, request_id: Uuid) -> CretoResult<Vec<ReviewerAssignment>>;
+
+    /// Active assignments due at or before `now`.
+    async fn find_overdue(&self, now: DateTime<Utc>) -> CretoResult<Vec<ReviewerAssignment>>;
+
+    /// Mark an assignment responded or lapsed at `at`.
+    async fn update_status(
+        &self,
+        id: Uuid,
+        status: AssignmentStatus,
+        at: DateTime<Utc>,
+    ) -> CretoResult<()>;
+}
+
+/// In-memory assignment store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryAssignmentStore {
+    assignments: RwLock<Vec<ReviewerAssignment>>,
+}
+
+impl InMemoryAssignmentStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AssignmentStore for InMemoryAssignmentStore {
+    async fn assign(&self, assignment: &ReviewerAssignment) -> CretoResult<ReviewerAssignment> {
+        let mut assignments = self.assignments.write().unwrap();
+        if let Some(existing) = assignments.iter().find(|a| {
+            a.request_id == assignment.request_id && a.reviewer_id == assignment.reviewer_id
+        }) {
+            return Ok(existing.clone());
+        }
+        assignments.push(assignment.clone());
+        Ok(assignment.clone())
+    }
+
+    async fn list_by_request(&self, request_id: Uuid) -> CretoResult<Vec<ReviewerAssignment>> {
+        let mut assignments: Vec<ReviewerAssignment> = self
+            .assignments
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|a| a.request_id == request_id)
+            .cloned()
+            .collect();
+        assignments.sort_by_key(|a| a.assigned_at);
+        Ok(assignments)
+    }
+
+    async fn find_overdue(&self, now: DateTime<Utc>) -> CretoResult<Vec<ReviewerAssignment>> {
+        let mut overdue: Vec<ReviewerAssignment> = self
+            .assignments
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|a| a.is_overdue(now))
+            .cloned()
+            .collect();
+        overdue.sort_by_key(|a| a.respond_by);
+        Ok(overdue)
+    }
+
+    async fn update_status(
+        &self,
+        id: Uuid,
+        status: AssignmentStatus,
+        at: DateTime<Utc>,
+    ) -> CretoResult<()> {
+        let mut assignments = self.assignments.write().unwrap();
+        let assignment = assignments
+            .iter_mut()
+            .find(|a| a.id == id)
+            .ok_or_else(|| creto_common::CretoError::NotFound(format!("Assignment {}", id)))?;
+        assignment.status = status;
+        if status == AssignmentStatus::Lapsed {
+            assignment.lapsed_at = Some(at);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::ActionType;
+    use creto_common::AgentId;
+
+    fn request(priority: Priority, timeout: u64) -> OversightRequest {
+        OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "wire".to_string(),
+            },
+            "Wire transfer",
+        )
+        .with_priority(priority)
+        .with_timeout(timeout)
+    }
+
+    #[test]
+    fn test_respond_by_from_priority() {
+        let policy = ReviewerDeadlinePolicy::default().with_deadline(Priority::High, 600);
+        let high = request(Priority::High, 3600);
+        assert_eq!(
+            policy.respond_by(&high, high.created_at),
+            high.created_at + Duration::seconds(600)
+        );
+
+        // Capped at the request's own timeout
+        let normal = request(Priority::Normal, 3600);
+        assert_eq!(
+            policy.respond_by(&normal, normal.created_at),
+            normal.expires_at
+        );
+
+        let mut policy = policy;
+        policy.respond_within.remove(&Priority::Low);
+        let low = request(Priority::Low, 3600);
+        assert_eq!(policy.respond_by(&low, low.created_at), low.expires_at);
+    }
+
+    #[tokio::test]
+    async fn test_store_keeps_first_assignment() {
+        let store = InMemoryAssignmentStore::new();
+        let now = Utc::now();
+        let assignment = ReviewerAssignment {
+            id: Uuid::now_v7(),
+            request_id: Uuid::now_v7(),
+            organization_id: OrganizationId::new(),
+            reviewer_id: UserId::new(),
+            source: AssignmentSource::Direct,
+            status: AssignmentStatus::Active,
+            assigned_at: now,
+            respond_by: now + Duration::hours(1),
+            lapsed_at: None,
+        };
+        store.assign(&assignment).await.unwrap();
+
+        let again = ReviewerAssignment {
+            id: Uuid::now_v7(),
+            source: AssignmentSource::Claim,
+            ..assignment.clone()
+        };
+        assert_eq!(store.assign(&again).await.unwrap(), assignment);
+        assert!(store.find_overdue(now).await.unwrap().is_empty());
+
+        let later = now + Duration::hours(1);
+        assert_eq!(
+            store.find_overdue(later).await.unwrap(),
+            vec![assignment.clone()]
+        );
+        store
+            .update_status(assignment.id, AssignmentStatus::Lapsed, later)
+            .await
+            .unwrap();
+        let stored = store.list_by_request(assignment.request_id).await.unwrap();
+        assert_eq!(stored[0].status, AssignmentStatus::Lapsed);
+        assert_eq!(stored[0].lapsed_at, Some(later));
+        assert!(store.find_overdue(later).await.unwrap().is_empty());
+    }
+}
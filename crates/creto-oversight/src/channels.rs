@@ -1,12 +1,19 @@
 //! Notification channel adapters for routing approval requests.
 
 use async_trait::async_trait;
-use creto_common::{CretoError, CretoResult};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use creto_common::{hmac_sha256, verify_hmac_sha256, CretoError, CretoResult, HMAC_SHA256_LEN};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use thiserror::Error;
 use tokio::sync::RwLock;
+use uuid::Uuid;
 
+use crate::enrichment::{split_sections, ContextSection, SectionStatus};
 use crate::request::OversightRequest;
 
 /// Trait for notification channels.
@@ -18,6 +25,32 @@ pub trait NotificationChannel: Send + Sync {
     /// Send a reminder for a pending request.
     async fn remind(&self, request: &OversightRequest) -> CretoResult<NotificationResult>;
 
+    /// Send one summary covering several requests.
+    ///
+    /// The default sends each request as an individual notification and
+    /// stops at the first failure.
+    async fn digest(&self, requests: &[OversightRequest]) -> CretoResult<NotificationResult> {
+        for request in requests {
+            let result = self.notify(request).await?;
+            if !result.success {
+                return Ok(result);
+            }
+        }
+        Ok(NotificationResult::success(None))
+    }
+
+    /// Tell a reviewer that a request they were notified about was withdrawn.
+    ///
+    /// The default sends nothing.
+    async fn notify_cancelled(
+        &self,
+        request: &OversightRequest,
+        reason: &str,
+    ) -> CretoResult<NotificationResult> {
+        let _ = (request, reason);
+        Ok(NotificationResult::success(None))
+    }
+
     /// Get the channel type.
     fn channel_type(&self) -> ChannelType;
 }
@@ -102,8 +135,15 @@ pub struct SlackMessage {
     /// Message attachments (legacy).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub attachments: Option<Vec<serde_json::Value>>,
+    /// Timestamp of the message to update in place, if this is an update.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts: Option<String>,
 }
 
+/// Request metadata key holding the timestamp of the Slack approval message,
+/// so a withdrawal can update that message instead of posting a new one.
+pub const SLACK_MESSAGE_TS_KEY: &str = "slack_message_ts";
+
 /// Slack button callback payload.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SlackCallback {
@@ -153,6 +193,18 @@ pub enum ApprovalDecision {
     Rejected,
 }
 
+impl From<ApprovalDecision> for crate::approval::ApprovalDecision {
+    fn from(decision: ApprovalDecision) -> Self {
+        match decision {
+            ApprovalDecision::Approved => Self::Approve,
+            ApprovalDecision::Rejected => Self::Reject,
+        }
+    }
+}
+
+/// Maximum number of fields Slack renders in one section block.
+const SLACK_MAX_SECTION_FIELDS: usize = 10;
+
 /// Slack notification channel (stub implementation).
 ///
 /// Enable the `channels` feature for full HTTP client functionality.
@@ -174,16 +226,44 @@ impl SlackChannel {
         let description = &request.description;
 
         // Extract context from the request's context field
-        let context_str = if request.context.is_null() {
+        let (context, sections) = split_sections(&request.context);
+        let context_str = if context.is_null() {
             "N/A".to_string()
         } else {
-            request.context.to_string()
+            context.to_string()
         };
 
         let text = format!("Approval Required: {} by {}", description, agent_id);
 
+        // Template requests show their structured fields instead of raw context
+        let (context_field, field_sections) = match &request.template {
+            Some(template) => (
+                format!("*Template:*\n{} (v{})", template.name, template.version),
+                template
+                    .fields
+                    .chunks(SLACK_MAX_SECTION_FIELDS)
+                    .map(|chunk| {
+                        let fields: Vec<serde_json::Value> = chunk
+                            .iter()
+                            .map(|field| {
+                                json!({
+                                    "type": "mrkdwn",
+                                    "text": format!("*{}:*\n{}", field.label, field.display_value())
+                                })
+                            })
+                            .collect();
+                        json!({
+                            "type": "section",
+                            "fields": fields
+                        })
+                    })
+                    .collect(),
+            ),
+            None => (format!("*Context:*\n{}", context_str), Vec::new()),
+        };
+
         let blocks = if self.config.interactive_buttons {
-            Some(vec![
+            let mut blocks = vec![
                 // Header section
                 json!({
                     "type": "header",
@@ -211,10 +291,14 @@ impl SlackChannel {
                         },
                         {
                             "type": "mrkdwn",
-                            "text": format!("*Context:*\n{}", context_str)
+                            "text": context_field
                         }
                     ]
                 }),
+            ];
+            blocks.extend(field_sections);
+            blocks.extend(sections.iter().flat_map(slack_section_blocks));
+            blocks.extend([
                 // Divider
                 json!({
                     "type": "divider"
@@ -247,7 +331,8 @@ impl SlackChannel {
                         }
                     ]
                 }),
-            ])
+            ]);
+            Some(blocks)
         } else {
             None
         };
@@ -257,6 +342,45 @@ impl SlackChannel {
             text,
             blocks,
             attachments: None,
+            ts: None,
+        }
+    }
+
+    /// Build the message telling reviewers a request was withdrawn.
+    ///
+    /// When the original approval message's timestamp is stored under
+    /// [`SLACK_MESSAGE_TS_KEY`], the message replaces it (dropping the
+    /// approve/reject buttons); otherwise it is posted as a new message.
+    pub fn build_withdrawal_message(
+        &self,
+        request: &OversightRequest,
+        reason: &str,
+    ) -> SlackMessage {
+        let text = format!(
+            "Oversight request withdrawn by the requester: {}",
+            request.description
+        );
+        let blocks = vec![json!({
+            "type": "section",
+            "text": {
+                "type": "mrkdwn",
+                "text": format!(
+                    "*Request withdrawn*\n~{}~\n*Reason:* {}",
+                    request.description, reason
+                )
+            }
+        })];
+
+        SlackMessage {
+            channel: self.config.default_channel.clone(),
+            text,
+            blocks: Some(blocks),
+            attachments: None,
+            ts: request
+                .metadata
+                .get(SLACK_MESSAGE_TS_KEY)
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
         }
     }
 
@@ -336,6 +460,36 @@ impl NotificationChannel for SlackChannel {
         Ok(NotificationResult::success(None))
     }
 
+    async fn notify_cancelled(
+        &self,
+        request: &OversightRequest,
+        reason: &str,
+    ) -> CretoResult<NotificationResult> {
+        let message = self.build_withdrawal_message(request, reason);
+
+        // Stub implementation - chat.update when the original is known, else chat.postMessage
+        let message_id = match &message.ts {
+            Some(ts) => {
+                tracing::info!(
+                    channel = message.channel,
+                    request_id = %request.id,
+                    ts = ts,
+                    "Simulated Slack message update"
+                );
+                ts.clone()
+            }
+            None => {
+                tracing::info!(
+                    channel = message.channel,
+                    request_id = %request.id,
+                    "Simulated Slack withdrawal notification"
+                );
+                format!("slack_{}_{}", request.id, chrono::Utc::now().timestamp())
+            }
+        };
+        Ok(NotificationResult::success(Some(message_id)))
+    }
+
     fn channel_type(&self) -> ChannelType {
         ChannelType::Slack
     }
@@ -357,10 +511,61 @@ pub struct EmailConfig {
     pub dashboard_base_url: String,
     /// HMAC secret for token generation.
     pub token_secret: String,
+    /// Secrets rotated out of `token_secret` whose tokens are still
+    /// accepted until they expire.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous_token_secrets: Vec<String>,
+}
+
+/// Version byte of the current approval token scheme.
+pub const APPROVAL_TOKEN_VERSION: u8 = 1;
+
+/// Length of an HMAC-SHA256 tag.
+const TOKEN_TAG_LEN: usize = HMAC_SHA256_LEN;
+
+/// Why an approval token was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ApprovalTokenError {
+    /// Not a URL-safe base64 envelope, too short, or unreadable claims.
+    #[error("Malformed approval token: {0}")]
+    Malformed(String),
+
+    /// Issued by the unsigned scheme that predates versioned tokens.
+    #[error("Approval token uses the retired legacy format")]
+    Legacy,
+
+    /// Issued by a scheme this build does not know.
+    #[error("Unsupported approval token version {0}")]
+    UnsupportedVersion(u8),
+
+    /// Not signed by any accepted secret, or altered after signing.
+    #[error("Invalid approval token signature")]
+    InvalidSignature,
+
+    /// Signed correctly but past its expiry.
+    #[error("Approval token expired at {expires_at}")]
+    Expired { expires_at: i64 },
+
+    /// Already used to decide its request.
+    #[error("Approval token has already been used")]
+    Consumed,
+}
+
+impl From<ApprovalTokenError> for CretoError {
+    fn from(error: ApprovalTokenError) -> Self {
+        match error {
+            ApprovalTokenError::Expired { .. } => CretoError::ApprovalTimeout { seconds: 0 },
+            other => CretoError::UnauthorizedApprover(other.to_string()),
+        }
+    }
 }
 
 /// Email approval token for secure verification.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The token string is the URL-safe base64 (unpadded) encoding of a version
+/// byte, the JSON-encoded claims, and an HMAC-SHA256 tag over both keyed by
+/// the channel's token secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ApprovalToken {
     /// Request ID.
     pub request_id: String,
@@ -368,8 +573,6 @@ pub struct ApprovalToken {
     pub approver_email: String,
     /// Expiration timestamp (Unix seconds).
     pub expires_at: i64,
-    /// HMAC signature.
-    pub signature: String,
 }
 
 impl ApprovalToken {
@@ -380,150 +583,136 @@ impl ApprovalToken {
         ttl_seconds: i64,
         secret: &str,
     ) -> String {
-        let request_id = request_id.into();
-        let approver_email = approver_email.into();
-        let expires_at = chrono::Utc::now().timestamp() + ttl_seconds;
-
-        // Create simple hash signature (in production, use proper HMAC)
-        let payload = format!("{}:{}:{}", request_id, approver_email, expires_at);
-        let signature = format!("{:x}", md5_hash(&format!("{}:{}", secret, payload)));
-
         let token = ApprovalToken {
-            request_id,
-            approver_email,
-            expires_at,
-            signature,
+            request_id: request_id.into(),
+            approver_email: approver_email.into(),
+            expires_at: chrono::Utc::now().timestamp() + ttl_seconds,
         };
 
-        // Encode as base64
-        let json = serde_json::to_string(&token).unwrap_or_default();
-        base64_encode(&json)
+        let mut envelope = vec![APPROVAL_TOKEN_VERSION];
+        envelope.extend(serde_json::to_vec(&token).unwrap_or_default());
+        let tag = hmac_sha256(secret.as_bytes(), &envelope);
+        envelope.extend_from_slice(&tag);
+        URL_SAFE_NO_PAD.encode(envelope)
     }
 
     /// Verify a token's signature and expiration.
-    pub fn verify(token_str: &str, secret: &str) -> CretoResult<ApprovalToken> {
-        // Decode from base64
-        let json = base64_decode(token_str)
-            .map_err(|e| CretoError::Internal(format!("Invalid token encoding: {}", e)))?;
-
-        let token: ApprovalToken = serde_json::from_slice(&json)
-            .map_err(|e| CretoError::SerializationError(format!("Invalid token format: {}", e)))?;
-
-        // Check expiration
-        let now = chrono::Utc::now().timestamp();
-        if token.expires_at < now {
-            return Err(CretoError::ApprovalTimeout { seconds: 0 });
+    pub fn verify(token_str: &str, secret: &str) -> Result<ApprovalToken, ApprovalTokenError> {
+        Self::verify_with_any(token_str, &[secret])
+    }
+
+    /// Verify a token signed by any of `secrets`, so tokens issued before a
+    /// secret rotation stay valid until they expire.
+    pub fn verify_with_any(
+        token_str: &str,
+        secrets: &[&str],
+    ) -> Result<ApprovalToken, ApprovalTokenError> {
+        let envelope = match URL_SAFE_NO_PAD.decode(token_str) {
+            Ok(envelope) => envelope,
+            // Legacy tokens were standard base64 with padding
+            Err(e) => match STANDARD.decode(token_str) {
+                Ok(legacy) if legacy.first() == Some(&b'{') => {
+                    return Err(ApprovalTokenError::Legacy)
+                }
+                _ => return Err(ApprovalTokenError::Malformed(e.to_string())),
+            },
+        };
+
+        match envelope.first() {
+            Some(&APPROVAL_TOKEN_VERSION) => {}
+            Some(b'{') => return Err(ApprovalTokenError::Legacy),
+            Some(&version) => return Err(ApprovalTokenError::UnsupportedVersion(version)),
+            None => return Err(ApprovalTokenError::Malformed("empty token".to_string())),
         }
+        let Some(signed_len) = envelope.len().checked_sub(TOKEN_TAG_LEN).filter(|n| *n > 1) else {
+            return Err(ApprovalTokenError::Malformed("token truncated".to_string()));
+        };
+        let (signed, tag) = envelope.split_at(signed_len);
 
-        // Verify signature
-        let payload = format!(
-            "{}:{}:{}",
-            token.request_id, token.approver_email, token.expires_at
-        );
-        let expected_signature = format!("{:x}", md5_hash(&format!("{}:{}", secret, payload)));
+        if !secrets
+            .iter()
+            .any(|secret| verify_hmac_sha256(secret.as_bytes(), signed, tag))
+        {
+            return Err(ApprovalTokenError::InvalidSignature);
+        }
 
-        if token.signature != expected_signature {
-            return Err(CretoError::UnauthorizedApprover(
-                "Invalid token signature".to_string(),
-            ));
+        let token: ApprovalToken = serde_json::from_slice(&signed[1..])
+            .map_err(|e| ApprovalTokenError::Malformed(e.to_string()))?;
+        if token.expires_at < chrono::Utc::now().timestamp() {
+            return Err(ApprovalTokenError::Expired {
+                expires_at: token.expires_at,
+            });
         }
 
         Ok(token)
     }
-}
 
-// Simple hash function (for development - use proper crypto in production)
-fn md5_hash(input: &str) -> u128 {
-    let mut hash: u128 = 0;
-    for (i, byte) in input.bytes().enumerate() {
-        hash = hash.wrapping_add((byte as u128).wrapping_mul((i as u128).wrapping_add(1)));
-        hash = hash.wrapping_mul(31);
+    /// The token's signature (hex), identifying it for
+    /// [`ConsumedTokenStore`].
+    pub fn signature(token_str: &str) -> Result<String, ApprovalTokenError> {
+        let envelope = URL_SAFE_NO_PAD
+            .decode(token_str)
+            .map_err(|e| ApprovalTokenError::Malformed(e.to_string()))?;
+        let tag_start = envelope
+            .len()
+            .checked_sub(TOKEN_TAG_LEN)
+            .filter(|n| *n > 1)
+            .ok_or_else(|| ApprovalTokenError::Malformed("token truncated".to_string()))?;
+        Ok(hex::encode(&envelope[tag_start..]))
     }
-    hash
 }
 
-// Simple base64 encoding (for development)
-fn base64_encode(input: &str) -> String {
-    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-    let bytes = input.as_bytes();
-    let mut result = String::new();
-
-    for chunk in bytes.chunks(3) {
-        let b0 = chunk[0] as u32;
-        let b1 = chunk.get(1).map(|&b| b as u32).unwrap_or(0);
-        let b2 = chunk.get(2).map(|&b| b as u32).unwrap_or(0);
-
-        let n = (b0 << 16) | (b1 << 8) | b2;
-
-        result.push(ALPHABET[(n >> 18) as usize & 0x3F] as char);
-        result.push(ALPHABET[(n >> 12) as usize & 0x3F] as char);
+/// Records approval tokens that have been used, so each decides its request
+/// at most once.
+#[async_trait]
+pub trait ConsumedTokenStore: Send + Sync {
+    /// Mark the token with `signature` used. Returns `false` if it already
+    /// was. The record may be dropped once `expires_at` passes, since the
+    /// token is refused as expired from then on.
+    async fn consume(&self, signature: &str, expires_at: DateTime<Utc>) -> CretoResult<bool>;
+
+    /// Whether the token with `signature` has been used.
+    async fn is_consumed(&self, signature: &str) -> CretoResult<bool>;
+
+    /// Make a token usable again after the decision it was consumed for
+    /// failed.
+    async fn release(&self, signature: &str) -> CretoResult<()>;
+}
 
-        if chunk.len() > 1 {
-            result.push(ALPHABET[(n >> 6) as usize & 0x3F] as char);
-        } else {
-            result.push('=');
-        }
+/// In-memory consumed token store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryConsumedTokenStore {
+    consumed: Mutex<HashMap<String, DateTime<Utc>>>,
+}
 
-        if chunk.len() > 2 {
-            result.push(ALPHABET[n as usize & 0x3F] as char);
-        } else {
-            result.push('=');
-        }
+impl InMemoryConsumedTokenStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    result
+    fn consumed(&self) -> MutexGuard<'_, HashMap<String, DateTime<Utc>>> {
+        self.consumed.lock().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
-// Simple base64 decoding (for development)
-fn base64_decode(input: &str) -> Result<Vec<u8>, String> {
-    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
-
-    fn char_to_val(c: char) -> Result<u8, String> {
-        ALPHABET
-            .iter()
-            .position(|&b| b == c as u8)
-            .map(|p| p as u8)
-            .ok_or_else(|| format!("Invalid base64 character: {}", c))
+#[async_trait]
+impl ConsumedTokenStore for InMemoryConsumedTokenStore {
+    async fn consume(&self, signature: &str, expires_at: DateTime<Utc>) -> CretoResult<bool> {
+        let mut consumed = self.consumed();
+        let now = Utc::now();
+        consumed.retain(|_, expires_at| *expires_at >= now);
+        Ok(consumed.insert(signature.to_string(), expires_at).is_none())
     }
 
-    let input = input.trim_end_matches('=');
-    let mut result = Vec::new();
-    let chars: Vec<char> = input.chars().collect();
-
-    for chunk in chars.chunks(4) {
-        if chunk.is_empty() {
-            break;
-        }
-
-        let v0 = char_to_val(chunk[0])? as u32;
-        let v1 = chunk
-            .get(1)
-            .map(|&c| char_to_val(c))
-            .transpose()?
-            .unwrap_or(0) as u32;
-        let v2 = chunk
-            .get(2)
-            .map(|&c| char_to_val(c))
-            .transpose()?
-            .unwrap_or(0) as u32;
-        let v3 = chunk
-            .get(3)
-            .map(|&c| char_to_val(c))
-            .transpose()?
-            .unwrap_or(0) as u32;
-
-        let n = (v0 << 18) | (v1 << 12) | (v2 << 6) | v3;
-
-        result.push((n >> 16) as u8);
-        if chunk.len() > 2 {
-            result.push((n >> 8) as u8);
-        }
-        if chunk.len() > 3 {
-            result.push(n as u8);
-        }
+    async fn is_consumed(&self, signature: &str) -> CretoResult<bool> {
+        Ok(self.consumed().contains_key(signature))
     }
 
-    Ok(result)
+    async fn release(&self, signature: &str) -> CretoResult<()> {
+        self.consumed().remove(signature);
+        Ok(())
+    }
 }
 
 /// Email notification channel (stub implementation).
@@ -553,6 +742,20 @@ impl EmailChannel {
         )
     }
 
+    /// Verify a token from an approval link against the current and
+    /// previous token secrets.
+    pub fn verify_approval_token(&self, token: &str) -> Result<ApprovalToken, ApprovalTokenError> {
+        let secrets: Vec<&str> = std::iter::once(self.config.token_secret.as_str())
+            .chain(
+                self.config
+                    .previous_token_secrets
+                    .iter()
+                    .map(String::as_str),
+            )
+            .collect();
+        ApprovalToken::verify_with_any(token, &secrets)
+    }
+
     /// Build HTML email template for approval request.
     fn build_email_template(
         &self,
@@ -562,12 +765,43 @@ impl EmailChannel {
         let request_id = request.id.to_string();
         let agent_id = request.agent_id.to_string();
         let description = &request.description;
-        let context_str = if request.context.is_null() {
+        let (context, sections) = split_sections(&request.context);
+        let context_str = if context.is_null() {
             "N/A".to_string()
         } else {
-            request.context.to_string()
+            context.to_string()
+        };
+
+        let (mut context_html, mut context_text) = match &request.template {
+            Some(template) => {
+                let mut html = format!(
+                    "<p><strong>Template:</strong> {} (v{})</p>",
+                    html_escape(&template.name),
+                    template.version
+                );
+                let mut text = format!("Template: {} (v{})", template.name, template.version);
+                for field in &template.fields {
+                    html.push_str(&format!(
+                        "\n            <p><strong>{}:</strong> {}</p>",
+                        html_escape(&field.label),
+                        html_escape(&field.display_value())
+                    ));
+                    text.push_str(&format!("\n{}: {}", field.label, field.display_value()));
+                }
+                (html, text)
+            }
+            None => (
+                format!("<p><strong>Context:</strong> {}</p>", context_str),
+                format!("Context: {}", context_str),
+            ),
         };
 
+        for section in &sections {
+            let (html, text) = email_section(section);
+            context_html.push_str(&html);
+            context_text.push_str(&text);
+        }
+
         let approval_url = self.generate_approval_url(&request_id, approver_email);
 
         let subject = format!("Approval Required: {} by {}", description, agent_id);
@@ -595,14 +829,14 @@ impl EmailChannel {
             <p><strong>Request ID:</strong> {}</p>
             <p><strong>Agent:</strong> {}</p>
             <p><strong>Description:</strong> {}</p>
-            <p><strong>Context:</strong> {}</p>
+            {}
         </div>
         <a href="{}" class="button">Review & Approve/Reject</a>
         <p style="color: #6b7280; font-size: 14px;">This link expires in 24 hours.</p>
     </div>
 </body>
 </html>"#,
-            request_id, agent_id, description, context_str, approval_url
+            request_id, agent_id, description, context_html, approval_url
         );
 
         let text_body = format!(
@@ -611,18 +845,101 @@ impl EmailChannel {
 Request ID: {}
 Agent: {}
 Description: {}
-Context: {}
+{}
 
 Click here to review: {}
 
 This link expires in 24 hours."#,
-            request_id, agent_id, description, context_str, approval_url
+            request_id, agent_id, description, context_text, approval_url
         );
 
         (subject, html_body, text_body)
     }
 }
 
+/// Render an enriched context section as Slack blocks: a heading section
+/// followed by its fields, split to respect Slack's per-section limit.
+fn slack_section_blocks(section: &ContextSection) -> Vec<serde_json::Value> {
+    let heading = match (section.status, &section.error) {
+        (SectionStatus::Unavailable, Some(error)) => {
+            format!("*{}:* _unavailable ({})_", section.title, error)
+        }
+        (SectionStatus::Unavailable, None) => format!("*{}:* _unavailable_", section.title),
+        (SectionStatus::Available, _) => format!("*{}*", section.title),
+    };
+
+    let mut blocks = vec![json!({
+        "type": "section",
+        "text": {
+            "type": "mrkdwn",
+            "text": heading
+        }
+    })];
+    blocks.extend(
+        section
+            .fields
+            .chunks(SLACK_MAX_SECTION_FIELDS)
+            .map(|chunk| {
+                let fields: Vec<serde_json::Value> = chunk
+                    .iter()
+                    .map(|field| {
+                        json!({
+                            "type": "mrkdwn",
+                            "text": format!("*{}:*\n{}", field.label, field.value)
+                        })
+                    })
+                    .collect();
+                json!({
+                    "type": "section",
+                    "fields": fields
+                })
+            }),
+    );
+    blocks
+}
+
+/// Render an enriched context section as (HTML, plain text) email fragments.
+fn email_section(section: &ContextSection) -> (String, String) {
+    let mut html = format!("\n            <h3>{}</h3>", html_escape(&section.title));
+    let mut text = format!("\n\n{}", section.title);
+
+    if section.status == SectionStatus::Unavailable {
+        let error = section.error.as_deref().unwrap_or("unknown error");
+        html.push_str(&format!(
+            "\n            <p><em>Unavailable: {}</em></p>",
+            html_escape(error)
+        ));
+        text.push_str(&format!("\n(unavailable: {})", error));
+    }
+
+    for field in &section.fields {
+        html.push_str(&format!(
+            "\n            <p><strong>{}:</strong> {}</p>",
+            html_escape(&field.label),
+            html_escape(&field.value).replace('\n', "<br>")
+        ));
+        text.push_str(&format!("\n{}: {}", field.label, field.value));
+    }
+
+    (html, text)
+}
+
+// Minimal HTML escaping for user-supplied values
+fn html_escape(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
 // Simple URL encoding
 fn urlencoding_encode(input: &str) -> String {
     let mut result = String::new();
@@ -679,6 +996,27 @@ impl NotificationChannel for EmailChannel {
         Ok(NotificationResult::success(None))
     }
 
+    async fn notify_cancelled(
+        &self,
+        request: &OversightRequest,
+        reason: &str,
+    ) -> CretoResult<NotificationResult> {
+        let approver_email = request
+            .metadata
+            .get("approver_email")
+            .and_then(|v| v.as_str())
+            .unwrap_or("approver@example.com");
+
+        tracing::info!(
+            to = approver_email,
+            request_id = %request.id,
+            reason = reason,
+            "Simulated email withdrawal notice"
+        );
+
+        Ok(NotificationResult::success(None))
+    }
+
     fn channel_type(&self) -> ChannelType {
         ChannelType::Email
     }
@@ -728,111 +1066,711 @@ impl NotificationChannel for WebhookChannel {
         Ok(NotificationResult::success(None))
     }
 
+    async fn notify_cancelled(
+        &self,
+        request: &OversightRequest,
+        reason: &str,
+    ) -> CretoResult<NotificationResult> {
+        tracing::info!(
+            url = self.url,
+            request_id = %request.id,
+            reason = reason,
+            "Simulated webhook withdrawal notice"
+        );
+        Ok(NotificationResult::success(None))
+    }
+
     fn channel_type(&self) -> ChannelType {
         ChannelType::Webhook
     }
 }
 
-/// Mock notification channel for testing.
-pub struct MockChannel {
-    /// Stored notifications for verification.
-    notifications: Arc<RwLock<Vec<OversightRequest>>>,
-    /// Stored reminders.
-    reminders: Arc<RwLock<Vec<OversightRequest>>>,
-    /// Whether to simulate failure.
-    should_fail: Arc<RwLock<bool>>,
-    /// Custom failure message.
-    failure_message: Arc<RwLock<Option<String>>>,
+/// Configuration for the Microsoft Teams channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamsConfig {
+    /// Incoming webhook or bot endpoint URL for the approvals channel.
+    pub webhook_url: String,
+    /// Whether to include approve/reject actions on the card.
+    #[serde(default = "default_true")]
+    pub interactive_actions: bool,
 }
 
-impl MockChannel {
-    /// Create a new mock channel.
-    pub fn new() -> Self {
-        Self {
-            notifications: Arc::new(RwLock::new(Vec::new())),
-            reminders: Arc::new(RwLock::new(Vec::new())),
-            should_fail: Arc::new(RwLock::new(false)),
-            failure_message: Arc::new(RwLock::new(None)),
-        }
-    }
-
-    /// Configure the mock to fail on next operation.
-    pub async fn set_should_fail(&self, fail: bool) {
-        *self.should_fail.write().await = fail;
-    }
+/// Teams message carrying an Adaptive Card attachment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamsMessage {
+    /// Activity type; always `message`.
+    #[serde(rename = "type")]
+    pub message_type: String,
+    /// Text shown in notifications and previews.
+    pub summary: String,
+    /// Card attachments.
+    pub attachments: Vec<TeamsAttachment>,
+}
 
-    /// Set custom failure message.
-    pub async fn set_failure_message(&self, message: impl Into<String>) {
-        *self.failure_message.write().await = Some(message.into());
-    }
+/// A card attached to a Teams message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamsAttachment {
+    /// Attachment content type.
+    #[serde(rename = "contentType")]
+    pub content_type: String,
+    /// The card itself.
+    pub content: serde_json::Value,
+}
 
-    /// Get all stored notifications.
-    pub async fn get_notifications(&self) -> Vec<OversightRequest> {
-        self.notifications.read().await.clone()
-    }
+/// Content type of Adaptive Card attachments.
+pub const TEAMS_ADAPTIVE_CARD_CONTENT_TYPE: &str = "application/vnd.microsoft.card.adaptive";
 
-    /// Get all stored reminders.
-    pub async fn get_reminders(&self) -> Vec<OversightRequest> {
-        self.reminders.read().await.clone()
-    }
+/// Adaptive Card schema version the channel renders.
+const TEAMS_CARD_VERSION: &str = "1.4";
 
-    /// Clear all stored notifications and reminders.
-    pub async fn clear(&self) {
-        self.notifications.write().await.clear();
-        self.reminders.write().await.clear();
-        *self.should_fail.write().await = false;
-        *self.failure_message.write().await = None;
-    }
+/// Teams `Action.Submit` callback payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamsCallback {
+    /// Activity type (`message` or `invoke`).
+    #[serde(rename = "type")]
+    pub activity_type: String,
+    /// User who submitted the action.
+    pub from: TeamsUser,
+    /// Data of the submitted action.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<TeamsActionData>,
+}
 
-    /// Get count of notifications sent.
-    pub async fn notification_count(&self) -> usize {
-        self.notifications.read().await.len()
-    }
+/// Teams user info.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamsUser {
+    /// Teams user ID.
+    pub id: String,
+    /// Azure AD object ID, when the tenant provides it.
+    #[serde(rename = "aadObjectId", skip_serializing_if = "Option::is_none")]
+    pub aad_object_id: Option<String>,
+    /// Display name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
 
-    /// Get count of reminders sent.
-    pub async fn reminder_count(&self) -> usize {
-        self.reminders.read().await.len()
-    }
+/// Data embedded in the card's approve/reject actions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamsActionData {
+    /// `approve` or `reject`.
+    pub action: String,
+    /// The request the action decides.
+    pub request_id: String,
+}
 
-    /// Verify a specific notification was sent.
-    pub async fn verify_notification(&self, request_id: &str) -> bool {
-        self.notifications
-            .read()
-            .await
-            .iter()
-            .any(|r| r.id.to_string() == request_id)
-    }
+/// Microsoft Teams notification channel (stub implementation).
+///
+/// Enable the `channels` feature for full HTTP client functionality.
+pub struct TeamsChannel {
+    config: TeamsConfig,
 }
 
-impl Default for MockChannel {
-    fn default() -> Self {
-        Self::new()
+impl TeamsChannel {
+    /// Create a new Teams channel.
+    pub fn new(config: TeamsConfig) -> Self {
+        Self { config }
     }
-}
 
-#[async_trait]
-impl NotificationChannel for MockChannel {
-    async fn notify(&self, request: &OversightRequest) -> CretoResult<NotificationResult> {
-        // Check if should fail
-        if *self.should_fail.read().await {
-            let message = self
-                .failure_message
-                .read()
-                .await
-                .clone()
-                .unwrap_or_else(|| "Mock channel failure".to_string());
-            return Ok(NotificationResult::failure(message));
-        }
+    /// Build a Teams message with an Adaptive Card and approve/reject actions.
+    pub fn build_approval_message(&self, request: &OversightRequest) -> TeamsMessage {
+        let request_id = request.id.to_string();
+        let agent_id = request.agent_id.to_string();
+        let description = &request.description;
 
-        // Store notification
-        self.notifications.write().await.push(request.clone());
+        let (context, sections) = split_sections(&request.context);
+        let context_str = if context.is_null() {
+            "N/A".to_string()
+        } else {
+            context.to_string()
+        };
 
-        // Return success with mock message ID
-        let message_id = format!("mock_msg_{}", uuid::Uuid::new_v4());
-        Ok(NotificationResult::success(Some(message_id)))
-    }
+        let mut facts = vec![
+            teams_fact("Request ID", &request_id),
+            teams_fact("Agent", &agent_id),
+            teams_fact("Description", description),
+        ];
+        // Template requests show their structured fields instead of raw context
+        match &request.template {
+            Some(template) => {
+                facts.push(teams_fact(
+                    "Template",
+                    &format!("{} (v{})", template.name, template.version),
+                ));
+                facts.extend(
+                    template
+                        .fields
+                        .iter()
+                        .map(|field| teams_fact(&field.label, &field.display_value())),
+                );
+            }
+            None => facts.push(teams_fact("Context", &context_str)),
+        }
 
-    async fn remind(&self, request: &OversightRequest) -> CretoResult<NotificationResult> {
+        let mut body = vec![
+            json!({
+                "type": "TextBlock",
+                "text": "🔔 Approval Request",
+                "weight": "Bolder",
+                "size": "Medium"
+            }),
+            json!({
+                "type": "FactSet",
+                "facts": facts
+            }),
+        ];
+        body.extend(sections.iter().flat_map(teams_section_items));
+
+        let mut card = json!({
+            "type": "AdaptiveCard",
+            "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+            "version": TEAMS_CARD_VERSION,
+            "body": body
+        });
+        if self.config.interactive_actions {
+            card["actions"] = json!([
+                {
+                    "type": "Action.Submit",
+                    "title": "✅ Approve",
+                    "style": "positive",
+                    "data": {"action": "approve", "request_id": request_id}
+                },
+                {
+                    "type": "Action.Submit",
+                    "title": "❌ Reject",
+                    "style": "destructive",
+                    "data": {"action": "reject", "request_id": request_id}
+                }
+            ]);
+        }
+
+        TeamsMessage {
+            message_type: "message".to_string(),
+            summary: format!("Approval Required: {} by {}", description, agent_id),
+            attachments: vec![TeamsAttachment {
+                content_type: TEAMS_ADAPTIVE_CARD_CONTENT_TYPE.to_string(),
+                content: card,
+            }],
+        }
+    }
+
+    /// Parse a Teams action submit payload to extract the approval decision.
+    ///
+    /// Returns the request ID, the decision and the submitting user, by
+    /// Azure AD object ID when present and Teams user ID otherwise.
+    pub fn parse_callback(&self, payload: &str) -> CretoResult<(String, ApprovalDecision, String)> {
+        let callback: TeamsCallback = serde_json::from_str(payload).map_err(|e| {
+            CretoError::SerializationError(format!("Invalid Teams callback: {}", e))
+        })?;
+
+        let data = callback
+            .value
+            .ok_or_else(|| CretoError::Internal("No action data in callback".to_string()))?;
+
+        let decision = match data.action.as_str() {
+            "approve" => ApprovalDecision::Approved,
+            "reject" => ApprovalDecision::Rejected,
+            other => {
+                return Err(CretoError::Internal(format!(
+                    "Unknown action type: {}",
+                    other
+                )))
+            }
+        };
+
+        if data.request_id.trim().is_empty() {
+            return Err(CretoError::Internal(
+                "No request ID in callback".to_string(),
+            ));
+        }
+
+        let user_id = callback.from.aad_object_id.unwrap_or(callback.from.id);
+        Ok((data.request_id, decision, user_id))
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for TeamsChannel {
+    async fn notify(&self, request: &OversightRequest) -> CretoResult<NotificationResult> {
+        let message = self.build_approval_message(request);
+
+        // Stub implementation - in production, POST the message with `channels` feature
+        tracing::info!(
+            url = self.config.webhook_url,
+            request_id = %request.id,
+            summary = message.summary,
+            "Simulated Teams notification"
+        );
+
+        let message_id = format!("teams_{}_{}", request.id, chrono::Utc::now().timestamp());
+        Ok(NotificationResult::success(Some(message_id)))
+    }
+
+    async fn remind(&self, request: &OversightRequest) -> CretoResult<NotificationResult> {
+        tracing::info!(
+            url = self.config.webhook_url,
+            request_id = %request.id,
+            "Simulated Teams reminder"
+        );
+
+        Ok(NotificationResult::success(None))
+    }
+
+    async fn notify_cancelled(
+        &self,
+        request: &OversightRequest,
+        reason: &str,
+    ) -> CretoResult<NotificationResult> {
+        tracing::info!(
+            url = self.config.webhook_url,
+            request_id = %request.id,
+            reason = reason,
+            "Simulated Teams withdrawal notice"
+        );
+
+        Ok(NotificationResult::success(None))
+    }
+
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Teams
+    }
+}
+
+/// Render an enriched context section as Adaptive Card elements: a heading
+/// text block followed by a fact set of its fields.
+fn teams_section_items(section: &ContextSection) -> Vec<serde_json::Value> {
+    let heading = match (section.status, &section.error) {
+        (SectionStatus::Unavailable, Some(error)) => {
+            format!("{}: unavailable ({})", section.title, error)
+        }
+        (SectionStatus::Unavailable, None) => format!("{}: unavailable", section.title),
+        (SectionStatus::Available, _) => section.title.clone(),
+    };
+
+    let mut items = vec![json!({
+        "type": "TextBlock",
+        "text": heading,
+        "weight": "Bolder",
+        "isSubtle": section.status == SectionStatus::Unavailable
+    })];
+    if !section.fields.is_empty() {
+        let facts: Vec<serde_json::Value> = section
+            .fields
+            .iter()
+            .map(|field| teams_fact(&field.label, &field.value))
+            .collect();
+        items.push(json!({
+            "type": "FactSet",
+            "facts": facts
+        }));
+    }
+    items
+}
+
+fn teams_fact(title: &str, value: &str) -> serde_json::Value {
+    json!({"title": title, "value": value})
+}
+
+/// Configuration for the SMS channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmsConfig {
+    /// Sender number or short code messages are sent from.
+    pub from_number: String,
+    /// Recipient for requests without an `approver_phone` in their metadata.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_recipient: Option<String>,
+}
+
+/// Request metadata key holding the reviewer's phone number.
+pub const SMS_RECIPIENT_KEY: &str = "approver_phone";
+
+/// Longest SMS the channel sends, in characters (one GSM-7 segment).
+pub const SMS_MAX_LENGTH: usize = 160;
+
+/// Length of the short codes reviewers reply with.
+pub const SMS_SHORT_CODE_LENGTH: usize = 6;
+
+/// Short-code alphabet, without characters easily confused when typed
+/// (0/O, 1/I/L).
+const SMS_SHORT_CODE_ALPHABET: &[u8; 31] = b"23456789ABCDEFGHJKMNPQRSTUVWXYZ";
+
+/// Short codes standing in for request IDs in SMS replies.
+///
+/// A request keeps its code until it is released, so reminders reuse the
+/// code of the original message. Codes are derived from the request ID and
+/// probe forward on a collision.
+#[derive(Debug, Default)]
+pub struct SmsShortCodes {
+    codes: Mutex<SmsShortCodeTable>,
+}
+
+#[derive(Debug, Default)]
+struct SmsShortCodeTable {
+    by_code: HashMap<String, Uuid>,
+    by_request: HashMap<Uuid, String>,
+}
+
+impl SmsShortCodes {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The request's short code, assigning one if it has none.
+    pub fn assign(&self, request_id: Uuid) -> String {
+        let mut table = self.lock();
+        if let Some(code) = table.by_request.get(&request_id) {
+            return code.clone();
+        }
+
+        // The low half of a v7 ID is random, so codes spread evenly
+        let bytes = request_id.as_bytes();
+        let mut seed = u64::from_be_bytes(bytes[8..16].try_into().expect("8 bytes"));
+        let code = loop {
+            let code = short_code(seed);
+            if !table.by_code.contains_key(&code) {
+                break code;
+            }
+            seed = seed.wrapping_add(1);
+        };
+        table.by_code.insert(code.clone(), request_id);
+        table.by_request.insert(request_id, code.clone());
+        code
+    }
+
+    /// The request a short code stands for. Codes match case-insensitively.
+    pub fn resolve(&self, code: &str) -> Option<Uuid> {
+        self.lock()
+            .by_code
+            .get(&code.trim().to_ascii_uppercase())
+            .copied()
+    }
+
+    /// Free a request's short code for reuse.
+    pub fn release(&self, request_id: Uuid) {
+        let mut table = self.lock();
+        if let Some(code) = table.by_request.remove(&request_id) {
+            table.by_code.remove(&code);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SmsShortCodeTable> {
+        self.codes.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn short_code(mut seed: u64) -> String {
+    let base = SMS_SHORT_CODE_ALPHABET.len() as u64;
+    (0..SMS_SHORT_CODE_LENGTH)
+        .map(|_| {
+            let c = SMS_SHORT_CODE_ALPHABET[(seed % base) as usize] as char;
+            seed /= base;
+            c
+        })
+        .collect()
+}
+
+/// SMS notification channel (stub implementation).
+///
+/// Reviewers decide by replying `APPROVE <code>` or `REJECT <code>`; inbound
+/// replies are mapped back to requests with [`SmsChannel::parse_reply`].
+/// Enable the `channels` feature for full HTTP client functionality.
+pub struct SmsChannel {
+    config: SmsConfig,
+    short_codes: Arc<SmsShortCodes>,
+}
+
+impl SmsChannel {
+    /// Create a new SMS channel.
+    pub fn new(config: SmsConfig) -> Self {
+        Self {
+            config,
+            short_codes: Arc::new(SmsShortCodes::new()),
+        }
+    }
+
+    /// Share a short-code table, e.g. with the inbound reply handler.
+    pub fn with_short_codes(mut self, short_codes: Arc<SmsShortCodes>) -> Self {
+        self.short_codes = short_codes;
+        self
+    }
+
+    /// The channel's short-code table.
+    pub fn short_codes(&self) -> &Arc<SmsShortCodes> {
+        &self.short_codes
+    }
+
+    /// Render the approval text, assigning the request a short code.
+    ///
+    /// The description is shortened so the whole text fits in
+    /// [`SMS_MAX_LENGTH`] characters.
+    pub fn build_approval_text(&self, request: &OversightRequest) -> String {
+        self.build_text("Approval needed", request)
+    }
+
+    /// Render the reminder text, reusing the request's short code.
+    pub fn build_reminder_text(&self, request: &OversightRequest) -> String {
+        self.build_text("Reminder - approval still needed", request)
+    }
+
+    fn build_text(&self, heading: &str, request: &OversightRequest) -> String {
+        let code = self.short_codes.assign(request.id);
+        let suffix = format!(". Reply APPROVE {} or REJECT {}", code, code);
+        let prefix = format!("{}: ", heading);
+        let budget = SMS_MAX_LENGTH - prefix.chars().count() - suffix.chars().count();
+        format!(
+            "{}{}{}",
+            prefix,
+            truncate_chars(&request.description, budget),
+            suffix
+        )
+    }
+
+    /// Parse an inbound SMS reply to extract the approval decision.
+    ///
+    /// Accepts `APPROVE <code>` or `REJECT <code>` in any case. Returns the
+    /// request ID, the decision and the sender's number.
+    pub fn parse_reply(
+        &self,
+        body: &str,
+        from: &str,
+    ) -> CretoResult<(String, ApprovalDecision, String)> {
+        let mut words = body.split_whitespace();
+        let (Some(keyword), Some(code), None) = (words.next(), words.next(), words.next()) else {
+            return Err(CretoError::ValidationFailed(format!(
+                "Expected \"APPROVE <code>\" or \"REJECT <code>\", got: {}",
+                body.trim()
+            )));
+        };
+
+        let decision = match keyword.to_ascii_uppercase().as_str() {
+            "APPROVE" => ApprovalDecision::Approved,
+            "REJECT" => ApprovalDecision::Rejected,
+            other => {
+                return Err(CretoError::ValidationFailed(format!(
+                    "Unknown reply keyword: {}",
+                    other
+                )))
+            }
+        };
+
+        let request_id = self
+            .short_codes
+            .resolve(code)
+            .ok_or_else(|| CretoError::NotFound(format!("Unknown short code: {}", code)))?;
+
+        Ok((request_id.to_string(), decision, from.to_string()))
+    }
+
+    fn recipient<'a>(&'a self, request: &'a OversightRequest) -> Option<&'a str> {
+        request
+            .metadata
+            .get(SMS_RECIPIENT_KEY)
+            .and_then(|v| v.as_str())
+            .or(self.config.default_recipient.as_deref())
+    }
+}
+
+/// The first `max` characters of `s`, ending in an ellipsis if cut.
+fn truncate_chars(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    let mut truncated: String = s.chars().take(max.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[async_trait]
+impl NotificationChannel for SmsChannel {
+    async fn notify(&self, request: &OversightRequest) -> CretoResult<NotificationResult> {
+        let Some(to) = self.recipient(request) else {
+            return Ok(NotificationResult::failure("No SMS recipient for request"));
+        };
+        let text = self.build_approval_text(request);
+
+        // Stub implementation - in production, send through the SMS provider
+        tracing::info!(
+            from = self.config.from_number,
+            to = to,
+            request_id = %request.id,
+            length = text.chars().count(),
+            "Simulated SMS notification"
+        );
+
+        let message_id = format!("sms_{}_{}", request.id, chrono::Utc::now().timestamp());
+        Ok(NotificationResult::success(Some(message_id)))
+    }
+
+    async fn remind(&self, request: &OversightRequest) -> CretoResult<NotificationResult> {
+        let Some(to) = self.recipient(request) else {
+            return Ok(NotificationResult::failure("No SMS recipient for request"));
+        };
+        let _text = self.build_reminder_text(request);
+
+        tracing::info!(
+            from = self.config.from_number,
+            to = to,
+            request_id = %request.id,
+            "Simulated SMS reminder"
+        );
+
+        Ok(NotificationResult::success(None))
+    }
+
+    async fn notify_cancelled(
+        &self,
+        request: &OversightRequest,
+        reason: &str,
+    ) -> CretoResult<NotificationResult> {
+        // Replies to a withdrawn request should not resolve any more
+        self.short_codes.release(request.id);
+        let Some(to) = self.recipient(request) else {
+            return Ok(NotificationResult::failure("No SMS recipient for request"));
+        };
+
+        tracing::info!(
+            from = self.config.from_number,
+            to = to,
+            request_id = %request.id,
+            reason = reason,
+            "Simulated SMS withdrawal notice"
+        );
+
+        Ok(NotificationResult::success(None))
+    }
+
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Sms
+    }
+}
+
+/// Mock notification channel for testing.
+pub struct MockChannel {
+    /// Stored notifications for verification.
+    notifications: Arc<RwLock<Vec<OversightRequest>>>,
+    /// Stored reminders.
+    reminders: Arc<RwLock<Vec<OversightRequest>>>,
+    /// Stored digests.
+    digests: Arc<RwLock<Vec<Vec<OversightRequest>>>>,
+    /// Stored withdrawal notices with their reasons.
+    cancellations: Arc<RwLock<Vec<(OversightRequest, String)>>>,
+    /// Channel type reported.
+    channel_type: ChannelType,
+    /// Whether to simulate failure.
+    should_fail: Arc<RwLock<bool>>,
+    /// Custom failure message.
+    failure_message: Arc<RwLock<Option<String>>>,
+}
+
+impl MockChannel {
+    /// Create a new mock channel.
+    pub fn new() -> Self {
+        Self {
+            notifications: Arc::new(RwLock::new(Vec::new())),
+            reminders: Arc::new(RwLock::new(Vec::new())),
+            digests: Arc::new(RwLock::new(Vec::new())),
+            cancellations: Arc::new(RwLock::new(Vec::new())),
+            channel_type: ChannelType::InApp,
+            should_fail: Arc::new(RwLock::new(false)),
+            failure_message: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Report a different channel type (default: in-app).
+    pub fn with_channel_type(mut self, channel_type: ChannelType) -> Self {
+        self.channel_type = channel_type;
+        self
+    }
+
+    /// Configure the mock to fail on next operation.
+    pub async fn set_should_fail(&self, fail: bool) {
+        *self.should_fail.write().await = fail;
+    }
+
+    /// Set custom failure message.
+    pub async fn set_failure_message(&self, message: impl Into<String>) {
+        *self.failure_message.write().await = Some(message.into());
+    }
+
+    /// Get all stored notifications.
+    pub async fn get_notifications(&self) -> Vec<OversightRequest> {
+        self.notifications.read().await.clone()
+    }
+
+    /// Get all stored reminders.
+    pub async fn get_reminders(&self) -> Vec<OversightRequest> {
+        self.reminders.read().await.clone()
+    }
+
+    /// Get all stored digests.
+    pub async fn get_digests(&self) -> Vec<Vec<OversightRequest>> {
+        self.digests.read().await.clone()
+    }
+
+    /// Get all stored withdrawal notices.
+    pub async fn get_cancellations(&self) -> Vec<(OversightRequest, String)> {
+        self.cancellations.read().await.clone()
+    }
+
+    /// Clear all stored notifications, reminders, digests, and withdrawals.
+    pub async fn clear(&self) {
+        self.notifications.write().await.clear();
+        self.reminders.write().await.clear();
+        self.digests.write().await.clear();
+        self.cancellations.write().await.clear();
+        *self.should_fail.write().await = false;
+        *self.failure_message.write().await = None;
+    }
+
+    /// Get count of notifications sent.
+    pub async fn notification_count(&self) -> usize {
+        self.notifications.read().await.len()
+    }
+
+    /// Get count of reminders sent.
+    pub async fn reminder_count(&self) -> usize {
+        self.reminders.read().await.len()
+    }
+
+    /// Verify a specific notification was sent.
+    pub async fn verify_notification(&self, request_id: &str) -> bool {
+        self.notifications
+            .read()
+            .await
+            .iter()
+            .any(|r| r.id.to_string() == request_id)
+    }
+}
+
+impl Default for MockChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for MockChannel {
+    async fn notify(&self, request: &OversightRequest) -> CretoResult<NotificationResult> {
+        // Check if should fail
+        if *self.should_fail.read().await {
+            let message = self
+                .failure_message
+                .read()
+                .await
+                .clone()
+                .unwrap_or_else(|| "Mock channel failure".to_string());
+            return Ok(NotificationResult::failure(message));
+        }
+
+        // Store notification
+        self.notifications.write().await.push(request.clone());
+
+        // Return success with mock message ID
+        let message_id = format!("mock_msg_{}", Uuid::new_v4());
+        Ok(NotificationResult::success(Some(message_id)))
+    }
+
+    async fn remind(&self, request: &OversightRequest) -> CretoResult<NotificationResult> {
         // Check if should fail
         if *self.should_fail.read().await {
             let message = self
@@ -851,8 +1789,33 @@ impl NotificationChannel for MockChannel {
         Ok(NotificationResult::success(None))
     }
 
+    async fn digest(&self, requests: &[OversightRequest]) -> CretoResult<NotificationResult> {
+        if *self.should_fail.read().await {
+            return Ok(NotificationResult::failure("Mock channel failure"));
+        }
+
+        self.digests.write().await.push(requests.to_vec());
+        Ok(NotificationResult::success(None))
+    }
+
+    async fn notify_cancelled(
+        &self,
+        request: &OversightRequest,
+        reason: &str,
+    ) -> CretoResult<NotificationResult> {
+        if *self.should_fail.read().await {
+            return Ok(NotificationResult::failure("Mock channel failure"));
+        }
+
+        self.cancellations
+            .write()
+            .await
+            .push((request.clone(), reason.to_string()));
+        Ok(NotificationResult::success(None))
+    }
+
     fn channel_type(&self) -> ChannelType {
-        ChannelType::InApp
+        self.channel_type
     }
 }
 
@@ -987,6 +1950,116 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_approval_token_tampered_payload() {
+        let secret = "test_secret";
+        let token_str = ApprovalToken::generate("req_1", "user@example.com", 3600, secret);
+        let mut envelope = URL_SAFE_NO_PAD.decode(&token_str).unwrap();
+        let text = String::from_utf8_lossy(&envelope[1..envelope.len() - TOKEN_TAG_LEN]);
+        let swapped = text.replace("req_1", "req_2");
+        envelope.splice(1..1 + swapped.len(), swapped.bytes());
+        let tampered = URL_SAFE_NO_PAD.encode(&envelope);
+
+        assert_eq!(
+            ApprovalToken::verify(&tampered, secret),
+            Err(ApprovalTokenError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_approval_token_truncated() {
+        let secret = "test_secret";
+        let token_str = ApprovalToken::generate("req_1", "user@example.com", 3600, secret);
+
+        for len in [0, 1, 10, 40, token_str.len() - 1] {
+            let result = ApprovalToken::verify(&token_str[..len], secret);
+            assert!(
+                matches!(
+                    result,
+                    Err(ApprovalTokenError::Malformed(_) | ApprovalTokenError::InvalidSignature)
+                ),
+                "{len}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_approval_token_expiry_is_distinct() {
+        let secret = "test_secret";
+        let token_str = ApprovalToken::generate("req_1", "user@example.com", -60, secret);
+
+        let error = ApprovalToken::verify(&token_str, secret).unwrap_err();
+        assert!(matches!(error, ApprovalTokenError::Expired { .. }));
+        assert!(matches!(
+            CretoError::from(error),
+            CretoError::ApprovalTimeout { .. }
+        ));
+    }
+
+    #[test]
+    fn test_approval_token_secret_rotation() {
+        let mut config = EmailConfig {
+            smtp_host: "smtp.example.com".to_string(),
+            smtp_port: 587,
+            from_address: "noreply@example.com".to_string(),
+            reply_to: None,
+            dashboard_base_url: "https://approval.example.com".to_string(),
+            token_secret: "old-secret".to_string(),
+            previous_token_secrets: Vec::new(),
+        };
+        let old_token = EmailChannel::new(config.clone())
+            .generate_approval_url("req_1", "user@example.com")
+            .split_once("token=")
+            .map(|(_, token)| token.to_string())
+            .unwrap();
+
+        config.previous_token_secrets = vec![config.token_secret.clone()];
+        config.token_secret = "new-secret".to_string();
+        let rotated = EmailChannel::new(config.clone());
+        let new_token = ApprovalToken::generate("req_2", "user@example.com", 3600, "new-secret");
+        assert_eq!(
+            rotated
+                .verify_approval_token(&old_token)
+                .unwrap()
+                .request_id,
+            "req_1"
+        );
+        assert_eq!(
+            rotated
+                .verify_approval_token(&new_token)
+                .unwrap()
+                .request_id,
+            "req_2"
+        );
+
+        // Once the old secret is retired its tokens stop verifying
+        config.previous_token_secrets.clear();
+        assert_eq!(
+            EmailChannel::new(config).verify_approval_token(&old_token),
+            Err(ApprovalTokenError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_approval_token_legacy_and_unknown_versions() {
+        let legacy = STANDARD.encode(
+            r#"{"request_id":"req_1","approver_email":"a@b.c","expires_at":9999999999,"signature":"ff"}"#,
+        );
+        assert_eq!(
+            ApprovalToken::verify(&legacy, "secret"),
+            Err(ApprovalTokenError::Legacy)
+        );
+
+        let mut envelope = URL_SAFE_NO_PAD
+            .decode(ApprovalToken::generate("req_1", "a@b.c", 3600, "secret"))
+            .unwrap();
+        envelope[0] = 2;
+        assert_eq!(
+            ApprovalToken::verify(&URL_SAFE_NO_PAD.encode(envelope), "secret"),
+            Err(ApprovalTokenError::UnsupportedVersion(2))
+        );
+    }
+
     #[test]
     fn test_slack_callback_parse_approve() {
         let slack_channel = SlackChannel::new(SlackConfig {
@@ -1038,12 +2111,33 @@ mod tests {
             reply_to: None,
             dashboard_base_url: "https://approval.example.com".to_string(),
             token_secret: "secret123".to_string(),
+            previous_token_secrets: Vec::new(),
         });
 
         let url = email_channel.generate_approval_url("req_123", "approver@example.com");
         assert!(url.starts_with("https://approval.example.com/approval?token="));
     }
 
+    #[test]
+    fn test_slack_withdrawal_updates_original_message() {
+        let channel = SlackChannel::new(SlackConfig {
+            token: "xoxb-test".to_string(),
+            default_channel: "#approvals".to_string(),
+            interactive_buttons: true,
+        });
+        let mut request = create_test_request();
+
+        let message = channel.build_withdrawal_message(&request, "task abandoned");
+        assert_eq!(message.ts, None);
+        assert!(message.blocks.unwrap()[0]
+            .to_string()
+            .contains("task abandoned"));
+
+        request.metadata[SLACK_MESSAGE_TS_KEY] = json!("1718000000.000100");
+        let message = channel.build_withdrawal_message(&request, "task abandoned");
+        assert_eq!(message.ts.as_deref(), Some("1718000000.000100"));
+    }
+
     #[test]
     fn test_slack_message_building() {
         let slack_channel = SlackChannel::new(SlackConfig {
@@ -1063,13 +2157,138 @@ mod tests {
         assert!(!blocks.is_empty());
     }
 
+    fn create_template_request() -> OversightRequest {
+        use crate::template::{FieldType, RequestTemplate, TemplateField};
+        use crate::triggers::ActionTypePattern;
+
+        let template = RequestTemplate::new(
+            OrganizationId::new(),
+            "Weekly vendor payment",
+            ActionTypePattern::Transaction,
+        )
+        .with_field(TemplateField::new("vendor", "Vendor", FieldType::String))
+        .with_field(TemplateField::new(
+            "amount_cents",
+            "Amount (cents)",
+            FieldType::Integer,
+        ));
+        let action = ActionType::Transaction {
+            amount_cents: 125000,
+            currency: "USD".to_string(),
+        };
+        let fields = json!({"vendor": "Acme & Sons", "amount_cents": 125000})
+            .as_object()
+            .unwrap()
+            .clone();
+        let applied = template.apply(&action, &fields).unwrap();
+
+        OversightRequest::new(OrganizationId::new(), AgentId::new(), action, "Pay vendor")
+            .with_context(serde_json::Value::Object(fields))
+            .with_template(applied)
+    }
+
+    #[test]
+    fn test_slack_message_renders_template_fields() {
+        let slack_channel = SlackChannel::new(SlackConfig {
+            token: "xoxb-test".to_string(),
+            default_channel: "#approvals".to_string(),
+            interactive_buttons: true,
+        });
+
+        let message = slack_channel.build_approval_message(&create_template_request());
+        let rendered = serde_json::to_string(&message.blocks.unwrap()).unwrap();
+
+        assert!(rendered.contains("*Template:*\\nWeekly vendor payment (v1)"));
+        assert!(rendered.contains("*Vendor:*\\nAcme & Sons"));
+        assert!(rendered.contains("*Amount (cents):*\\n125000"));
+        assert!(!rendered.contains("*Context:*"));
+    }
+
+    #[test]
+    fn test_email_template_renders_template_fields() {
+        let email_channel = EmailChannel::new(EmailConfig {
+            smtp_host: "smtp.example.com".to_string(),
+            smtp_port: 587,
+            from_address: "noreply@example.com".to_string(),
+            reply_to: None,
+            dashboard_base_url: "https://approval.example.com".to_string(),
+            token_secret: "secret123".to_string(),
+            previous_token_secrets: Vec::new(),
+        });
+
+        let (_subject, html, text) =
+            email_channel.build_email_template(&create_template_request(), "approver@example.com");
+
+        assert!(html.contains("<strong>Vendor:</strong> Acme &amp; Sons"));
+        assert!(html.contains("<strong>Amount (cents):</strong> 125000"));
+        assert!(!html.contains("<strong>Context:</strong>"));
+        assert!(text.contains("Template: Weekly vendor payment (v1)"));
+        assert!(text.contains("Vendor: Acme & Sons"));
+    }
+
+    fn create_enriched_request() -> OversightRequest {
+        use crate::enrichment::{ContextSection, SectionField};
+
+        let sections = vec![
+            ContextSection::new(
+                "execution",
+                "Execution details",
+                vec![
+                    SectionField::new("Runtime", "python3.11"),
+                    SectionField::new("Code", "print('<hi>')\nexit(0)"),
+                ],
+            ),
+            ContextSection::unavailable("quota", "Quota usage", "metering timeout"),
+        ];
+        create_test_request().with_context(json!({
+            "ticket": "OPS-12",
+            "sections": sections,
+        }))
+    }
+
     #[test]
-    fn test_base64_roundtrip() {
-        let original = "Hello, World! This is a test.";
-        let encoded = base64_encode(original);
-        let decoded = base64_decode(&encoded).unwrap();
-        let result = String::from_utf8(decoded).unwrap();
-        assert_eq!(original, result);
+    fn test_slack_message_renders_context_sections() {
+        let slack_channel = SlackChannel::new(SlackConfig {
+            token: "xoxb-test".to_string(),
+            default_channel: "#approvals".to_string(),
+            interactive_buttons: true,
+        });
+
+        let message = slack_channel.build_approval_message(&create_enriched_request());
+        let blocks = message.blocks.unwrap();
+        let rendered = serde_json::to_string(&blocks).unwrap();
+
+        assert!(rendered.contains("*Execution details*"));
+        assert!(rendered.contains("*Runtime:*\\npython3.11"));
+        assert!(rendered.contains("*Quota usage:* _unavailable (metering timeout)_"));
+        // Sections are not repeated in the raw context
+        assert_eq!(
+            blocks[1]["fields"][3]["text"],
+            "*Context:*\n{\"ticket\":\"OPS-12\"}"
+        );
+    }
+
+    #[test]
+    fn test_email_template_renders_context_sections() {
+        let email_channel = EmailChannel::new(EmailConfig {
+            smtp_host: "smtp.example.com".to_string(),
+            smtp_port: 587,
+            from_address: "noreply@example.com".to_string(),
+            reply_to: None,
+            dashboard_base_url: "https://approval.example.com".to_string(),
+            token_secret: "secret123".to_string(),
+            previous_token_secrets: Vec::new(),
+        });
+
+        let (_subject, html, text) =
+            email_channel.build_email_template(&create_enriched_request(), "approver@example.com");
+
+        assert!(html.contains("<h3>Execution details</h3>"));
+        assert!(html.contains("<strong>Code:</strong> print(&#39;&lt;hi&gt;&#39;)<br>exit(0)"));
+        assert!(html.contains("<em>Unavailable: metering timeout</em>"));
+        assert!(!html.contains("\"sections\""));
+        assert!(text.contains("Execution details\nRuntime: python3.11"));
+        assert!(text.contains("Quota usage\n(unavailable: metering timeout)"));
     }
 
     #[test]
@@ -1080,4 +2299,235 @@ mod tests {
         assert!(encoded.contains("%3D"));
         assert!(encoded.contains("%26"));
     }
+
+    fn teams_channel(interactive_actions: bool) -> TeamsChannel {
+        TeamsChannel::new(TeamsConfig {
+            webhook_url: "https://example.webhook.office.com/approvals".to_string(),
+            interactive_actions,
+        })
+    }
+
+    #[test]
+    fn test_teams_card_building() {
+        let request = create_test_request();
+        let message = teams_channel(true).build_approval_message(&request);
+
+        assert_eq!(message.message_type, "message");
+        assert!(message.summary.contains("Test operation"));
+        assert_eq!(message.attachments.len(), 1);
+        let attachment = &message.attachments[0];
+        assert_eq!(attachment.content_type, TEAMS_ADAPTIVE_CARD_CONTENT_TYPE);
+
+        let card = &attachment.content;
+        assert_eq!(card["type"], "AdaptiveCard");
+        assert_eq!(card["body"][1]["facts"][0]["value"], request.id.to_string());
+        let actions = card["actions"].as_array().unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0]["type"], "Action.Submit");
+        assert_eq!(actions[0]["data"]["action"], "approve");
+        assert_eq!(actions[0]["data"]["request_id"], request.id.to_string());
+        assert_eq!(actions[1]["data"]["action"], "reject");
+
+        let message = teams_channel(false).build_approval_message(&request);
+        assert!(message.attachments[0].content.get("actions").is_none());
+    }
+
+    #[test]
+    fn test_teams_card_renders_template_fields_and_sections() {
+        let channel = teams_channel(true);
+
+        let message = channel.build_approval_message(&create_template_request());
+        let rendered = serde_json::to_string(&message.attachments[0].content).unwrap();
+        assert!(rendered.contains(r#"{"title":"Template","value":"Weekly vendor payment (v1)"}"#));
+        assert!(rendered.contains(r#"{"title":"Vendor","value":"Acme & Sons"}"#));
+        assert!(!rendered.contains(r#""title":"Context""#));
+
+        let message = channel.build_approval_message(&create_enriched_request());
+        let rendered = serde_json::to_string(&message.attachments[0].content).unwrap();
+        assert!(rendered.contains(r#""text":"Execution details""#));
+        assert!(rendered.contains(r#"{"title":"Runtime","value":"python3.11"}"#));
+        assert!(rendered.contains("Quota usage: unavailable (metering timeout)"));
+        assert!(rendered.contains("OPS-12"));
+    }
+
+    #[test]
+    fn test_teams_callback_parse() {
+        let channel = teams_channel(true);
+
+        let payload = r#"{
+            "type": "invoke",
+            "from": {"id": "29:1abc", "aadObjectId": "aad-123", "name": "Jo"},
+            "value": {"action": "approve", "request_id": "req_123"}
+        }"#;
+        let (request_id, decision, user_id) = channel.parse_callback(payload).unwrap();
+        assert_eq!(request_id, "req_123");
+        assert_eq!(decision, ApprovalDecision::Approved);
+        assert_eq!(user_id, "aad-123");
+
+        // Without an AAD object ID the Teams user ID identifies the reviewer
+        let payload = r#"{
+            "type": "message",
+            "from": {"id": "29:1abc"},
+            "value": {"action": "reject", "request_id": "req_456"}
+        }"#;
+        let (request_id, decision, user_id) = channel.parse_callback(payload).unwrap();
+        assert_eq!(request_id, "req_456");
+        assert_eq!(decision, ApprovalDecision::Rejected);
+        assert_eq!(user_id, "29:1abc");
+    }
+
+    #[test]
+    fn test_teams_callback_rejects_malformed_payloads() {
+        let channel = teams_channel(true);
+
+        assert!(matches!(
+            channel.parse_callback("not json"),
+            Err(CretoError::SerializationError(_))
+        ));
+        assert!(matches!(
+            channel.parse_callback(r#"{"type": "invoke", "value": {}}"#),
+            Err(CretoError::SerializationError(_))
+        ));
+        for payload in [
+            r#"{"type": "invoke", "from": {"id": "29:1"}}"#,
+            r#"{"type": "invoke", "from": {"id": "29:1"}, "value": {"action": "escalate", "request_id": "r"}}"#,
+            r#"{"type": "invoke", "from": {"id": "29:1"}, "value": {"action": "approve", "request_id": " "}}"#,
+        ] {
+            assert!(
+                matches!(
+                    channel.parse_callback(payload),
+                    Err(CretoError::Internal(_))
+                ),
+                "accepted {}",
+                payload
+            );
+        }
+    }
+
+    fn sms_channel() -> SmsChannel {
+        SmsChannel::new(SmsConfig {
+            from_number: "+15550100".to_string(),
+            default_recipient: Some("+15550199".to_string()),
+        })
+    }
+
+    #[test]
+    fn test_sms_text_fits_one_segment() {
+        let channel = sms_channel();
+        let mut request = create_test_request();
+        request.description = "Transfer funds to the vendor account ".repeat(10);
+
+        let text = channel.build_approval_text(&request);
+        let code = channel.short_codes().assign(request.id);
+        assert_eq!(code.len(), SMS_SHORT_CODE_LENGTH);
+        assert!(text.chars().count() <= SMS_MAX_LENGTH);
+        assert!(text.starts_with("Approval needed: Transfer funds"));
+        assert!(text.contains('…'));
+        assert!(text.ends_with(&format!("Reply APPROVE {} or REJECT {}", code, code)));
+
+        // Reminders reuse the code of the original message
+        let reminder = channel.build_reminder_text(&request);
+        assert!(reminder.chars().count() <= SMS_MAX_LENGTH);
+        assert!(reminder.ends_with(&format!("REJECT {}", code)));
+
+        let short = create_test_request();
+        let text = channel.build_approval_text(&short);
+        assert!(text.starts_with("Approval needed: Test operation. Reply APPROVE"));
+    }
+
+    #[test]
+    fn test_sms_reply_parsing() {
+        let channel = sms_channel();
+        let request = create_test_request();
+        let code = channel.short_codes().assign(request.id);
+
+        let (request_id, decision, from) = channel
+            .parse_reply(&format!("APPROVE {}", code), "+15550199")
+            .unwrap();
+        assert_eq!(request_id, request.id.to_string());
+        assert_eq!(decision, ApprovalDecision::Approved);
+        assert_eq!(from, "+15550199");
+
+        let (request_id, decision, _) = channel
+            .parse_reply(
+                &format!("  reject\t{} \n", code.to_lowercase()),
+                "+15550199",
+            )
+            .unwrap();
+        assert_eq!(request_id, request.id.to_string());
+        assert_eq!(decision, ApprovalDecision::Rejected);
+    }
+
+    #[tokio::test]
+    async fn test_sms_reply_rejects_malformed_replies() {
+        let channel = sms_channel();
+        let request = create_test_request();
+        let code = channel.short_codes().assign(request.id);
+
+        for body in [
+            String::new(),
+            "APPROVE".to_string(),
+            format!("APPROVE {} please", code),
+            format!("YES {}", code),
+        ] {
+            assert!(
+                matches!(
+                    channel.parse_reply(&body, "+1"),
+                    Err(CretoError::ValidationFailed(_))
+                ),
+                "accepted {:?}",
+                body
+            );
+        }
+        assert!(matches!(
+            channel.parse_reply("APPROVE ZZZZZZ", "+1"),
+            Err(CretoError::NotFound(_))
+        ));
+
+        // Withdrawing a request retires its code
+        channel
+            .notify_cancelled(&request, "no longer needed")
+            .await
+            .unwrap();
+        assert!(matches!(
+            channel.parse_reply(&format!("APPROVE {}", code), "+1"),
+            Err(CretoError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_sms_short_codes_are_unique() {
+        let codes = SmsShortCodes::new();
+        // Same random half, so the derived codes collide
+        let first = Uuid::from_u128(0x0000_0000_0000_0000_1234_5678_9abc_def0);
+        let second = Uuid::from_u128(0x1111_1111_1111_1111_1234_5678_9abc_def0);
+
+        let first_code = codes.assign(first);
+        let second_code = codes.assign(second);
+        assert_ne!(first_code, second_code);
+        assert_eq!(codes.assign(first), first_code);
+        assert_eq!(codes.resolve(&first_code), Some(first));
+        assert_eq!(codes.resolve(&second_code), Some(second));
+
+        codes.release(first);
+        assert_eq!(codes.resolve(&first_code), None);
+        assert_eq!(codes.resolve(&second_code), Some(second));
+    }
+
+    #[tokio::test]
+    async fn test_sms_channel_requires_recipient() {
+        let channel = SmsChannel::new(SmsConfig {
+            from_number: "+15550100".to_string(),
+            default_recipient: None,
+        });
+        let mut request = create_test_request();
+
+        let result = channel.notify(&request).await.unwrap();
+        assert!(!result.success);
+
+        request.metadata = json!({ SMS_RECIPIENT_KEY: "+15550123" });
+        let result = channel.notify(&request).await.unwrap();
+        assert!(result.success);
+        assert_eq!(channel.channel_type(), ChannelType::Sms);
+    }
 }
@@ -197,6 +197,68 @@ pub trait CheckpointRepository: Send + Sync {
     async fn delete_before(&self, before: DateTime<Utc>) -> CretoResult<usize>;
 }
 
+/// In-memory checkpoint repository for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryCheckpointRepository {
+    checkpoints: std::sync::RwLock<Vec<Checkpoint>>,
+}
+
+impl InMemoryCheckpointRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl CheckpointRepository for InMemoryCheckpointRepository {
+    async fn create(&self, checkpoint: &Checkpoint) -> CretoResult<Uuid> {
+        self.checkpoints.write().unwrap().push(checkpoint.clone());
+        Ok(checkpoint.id)
+    }
+
+    async fn get(&self, id: Uuid) -> CretoResult<Option<Checkpoint>> {
+        Ok(self
+            .checkpoints
+            .read()
+            .unwrap()
+            .iter()
+            .find(|c| c.id == id)
+            .cloned())
+    }
+
+    async fn get_latest(&self, request_id: Uuid) -> CretoResult<Option<Checkpoint>> {
+        Ok(self
+            .checkpoints
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|c| c.request_id == request_id)
+            .max_by_key(|c| c.timestamp)
+            .cloned())
+    }
+
+    async fn list_by_request(&self, request_id: Uuid) -> CretoResult<Vec<Checkpoint>> {
+        let mut checkpoints: Vec<Checkpoint> = self
+            .checkpoints
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|c| c.request_id == request_id)
+            .cloned()
+            .collect();
+        checkpoints.sort_by_key(|c| c.timestamp);
+        Ok(checkpoints)
+    }
+
+    async fn delete_before(&self, before: DateTime<Utc>) -> CretoResult<usize> {
+        let mut checkpoints = self.checkpoints.write().unwrap();
+        let count = checkpoints.len();
+        checkpoints.retain(|c| c.timestamp >= before);
+        Ok(count - checkpoints.len())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
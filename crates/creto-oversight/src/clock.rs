@@ -0,0 +1,5 @@
+//! Time source for scheduling decisions.
+//!
+//! Re-exported from [`creto_common::clock`], which the other products share.
+
+pub use creto_common::clock::{Clock, SystemClock, TestClock};
@@ -0,0 +1,906 @@
+//! Reviewer groups synced from an external identity provider.
+//!
+//! Templates route requests to a named [`ReviewerGroup`]. Groups can be
+//! maintained locally or reconciled from a [`DirectorySource`] (SCIM, OIDC
+//! groups) by [`ReviewerDirectorySync`]:
+//!
+//! - upstream groups are created locally under their display name, and
+//!   their memberships are added and removed to match;
+//! - members pinned locally are never removed by sync;
+//! - a group deleted upstream is disabled, not deleted, so requests already
+//!   routed to it can still be evaluated.
+//!
+//! Memberships are kept as a history rather than a set. An approval counts
+//! if its reviewer was a member when it was recorded, so membership changes
+//! while a request is open never invalidate approvals already given.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, CretoResult, OrganizationId, ShutdownCoordinator, UserId};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::clock::{Clock, SystemClock};
+
+/// Whether a reviewer group accepts new requests.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewerGroupStatus {
+    /// Requests are routed to the group.
+    #[default]
+    Active,
+    /// Deleted upstream: no new requests are routed to the group, but its
+    /// memberships still apply to requests already in flight.
+    Disabled,
+}
+
+/// A period during which a user belonged to a group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupMembership {
+    /// The member.
+    pub user_id: UserId,
+
+    /// Pinned members are maintained locally and never removed by sync.
+    #[serde(default)]
+    pub pinned: bool,
+
+    /// When the membership started.
+    pub added_at: DateTime<Utc>,
+
+    /// When the membership ended; `None` while current.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removed_at: Option<DateTime<Utc>>,
+}
+
+impl GroupMembership {
+    fn is_current(&self) -> bool {
+        self.removed_at.is_none()
+    }
+
+    fn covers(&self, at: DateTime<Utc>) -> bool {
+        self.added_at <= at && self.removed_at.map(|r| at < r).unwrap_or(true)
+    }
+}
+
+/// A named group of reviewers within an organization.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewerGroup {
+    /// Unique group ID.
+    pub id: Uuid,
+
+    /// Owning organization.
+    pub organization_id: OrganizationId,
+
+    /// Name templates route to. Kept when the upstream group is renamed.
+    pub name: String,
+
+    /// Identifier in the directory the group is synced from; `None` for
+    /// locally maintained groups.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+
+    /// Whether the group accepts new requests.
+    #[serde(default)]
+    pub status: ReviewerGroupStatus,
+
+    /// Current and past memberships.
+    #[serde(default)]
+    pub memberships: Vec<GroupMembership>,
+
+    /// When the group was created.
+    pub created_at: DateTime<Utc>,
+
+    /// When the group was last changed.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ReviewerGroup {
+    /// Create an empty, locally maintained group.
+    pub fn new(
+        organization_id: OrganizationId,
+        name: impl Into<String>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            organization_id,
+            name: name.into(),
+            external_id: None,
+            status: ReviewerGroupStatus::Active,
+            memberships: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Link the group to a directory group.
+    pub fn with_external_id(mut self, external_id: impl Into<String>) -> Self {
+        self.external_id = Some(external_id.into());
+        self
+    }
+
+    /// Whether new requests may be routed to the group.
+    pub fn is_active(&self) -> bool {
+        self.status == ReviewerGroupStatus::Active
+    }
+
+    /// Current members, in the order they joined.
+    pub fn members(&self) -> Vec<UserId> {
+        self.memberships
+            .iter()
+            .filter(|m| m.is_current())
+            .map(|m| m.user_id)
+            .collect()
+    }
+
+    /// Whether `user_id` is currently a member.
+    pub fn is_member(&self, user_id: UserId) -> bool {
+        self.current(user_id).is_some()
+    }
+
+    /// Whether `user_id` was a member at `at`.
+    pub fn was_member_at(&self, user_id: UserId, at: DateTime<Utc>) -> bool {
+        self.memberships
+            .iter()
+            .any(|m| m.user_id == user_id && m.covers(at))
+    }
+
+    /// Whether `user_id` is a current, pinned member.
+    pub fn is_pinned(&self, user_id: UserId) -> bool {
+        self.current(user_id).map(|m| m.pinned).unwrap_or(false)
+    }
+
+    /// Add a member, returning `false` if they already are one.
+    pub fn add_member(&mut self, user_id: UserId, now: DateTime<Utc>) -> bool {
+        if self.is_member(user_id) {
+            return false;
+        }
+        self.memberships.push(GroupMembership {
+            user_id,
+            pinned: false,
+            added_at: now,
+            removed_at: None,
+        });
+        self.updated_at = now;
+        true
+    }
+
+    /// End a membership, returning `false` if `user_id` is not a member.
+    pub fn remove_member(&mut self, user_id: UserId, now: DateTime<Utc>) -> bool {
+        let Some(membership) = self.current_mut(user_id) else {
+            return false;
+        };
+        membership.removed_at = Some(now);
+        self.updated_at = now;
+        true
+    }
+
+    /// Add `user_id` if needed and protect the membership from sync.
+    pub fn pin_member(&mut self, user_id: UserId, now: DateTime<Utc>) {
+        self.add_member(user_id, now);
+        if let Some(membership) = self.current_mut(user_id) {
+            membership.pinned = true;
+        }
+        self.updated_at = now;
+    }
+
+    /// Let sync manage `user_id`'s membership again.
+    pub fn unpin_member(&mut self, user_id: UserId, now: DateTime<Utc>) {
+        if let Some(membership) = self.current_mut(user_id) {
+            membership.pinned = false;
+            self.updated_at = now;
+        }
+    }
+
+    fn current(&self, user_id: UserId) -> Option<&GroupMembership> {
+        self.memberships
+            .iter()
+            .find(|m| m.user_id == user_id && m.is_current())
+    }
+
+    fn current_mut(&mut self, user_id: UserId) -> Option<&mut GroupMembership> {
+        self.memberships
+            .iter_mut()
+            .find(|m| m.user_id == user_id && m.is_current())
+    }
+}
+
+/// Storage for reviewer groups.
+#[async_trait]
+pub trait ReviewerGroupStore: Send + Sync {
+    /// Get a group by name.
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+    ) -> CretoResult<Option<ReviewerGroup>>;
+
+    /// List an organization's groups, including disabled ones.
+    async fn list(&self, organization_id: OrganizationId) -> CretoResult<Vec<ReviewerGroup>>;
+
+    /// Create or replace a group.
+    async fn put(&self, group: ReviewerGroup) -> CretoResult<()>;
+}
+
+/// In-memory reviewer group store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryReviewerGroupStore {
+    groups: Arc<RwLock<HashMap<Uuid, ReviewerGroup>>>,
+}
+
+impl InMemoryReviewerGroupStore {
+    /// Create a new in-memory group store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ReviewerGroupStore for InMemoryReviewerGroupStore {
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+    ) -> CretoResult<Option<ReviewerGroup>> {
+        Ok(self
+            .groups
+            .read()
+            .unwrap()
+            .values()
+            .find(|g| g.organization_id == organization_id && g.name == name)
+            .cloned())
+    }
+
+    async fn list(&self, organization_id: OrganizationId) -> CretoResult<Vec<ReviewerGroup>> {
+        let mut groups: Vec<_> = self
+            .groups
+            .read()
+            .unwrap()
+            .values()
+            .filter(|g| g.organization_id == organization_id)
+            .cloned()
+            .collect();
+        groups.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(groups)
+    }
+
+    async fn put(&self, group: ReviewerGroup) -> CretoResult<()> {
+        let mut groups = self.groups.write().unwrap();
+        if groups.values().any(|g| {
+            g.id != group.id && g.organization_id == group.organization_id && g.name == group.name
+        }) {
+            return Err(CretoError::ValidationFailed(format!(
+                "reviewer group '{}' already exists",
+                group.name
+            )));
+        }
+        groups.insert(group.id, group);
+        Ok(())
+    }
+}
+
+/// A group as listed by a directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirectoryGroup {
+    /// Directory identifier.
+    pub external_id: String,
+    /// Display name.
+    pub display_name: String,
+}
+
+/// External directory of groups and their members.
+#[async_trait]
+pub trait DirectorySource: Send + Sync {
+    /// List all groups.
+    async fn list_groups(&self) -> CretoResult<Vec<DirectoryGroup>>;
+
+    /// List a group's members.
+    async fn list_members(&self, external_id: &str) -> CretoResult<Vec<UserId>>;
+}
+
+/// In-memory directory for testing.
+#[derive(Debug, Default)]
+pub struct MockDirectorySource {
+    groups: RwLock<HashMap<String, (DirectoryGroup, Vec<UserId>)>>,
+}
+
+impl MockDirectorySource {
+    /// Create an empty directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create or replace a group.
+    pub fn set_group(&self, external_id: &str, display_name: &str, members: Vec<UserId>) {
+        self.groups.write().unwrap().insert(
+            external_id.to_string(),
+            (
+                DirectoryGroup {
+                    external_id: external_id.to_string(),
+                    display_name: display_name.to_string(),
+                },
+                members,
+            ),
+        );
+    }
+
+    /// Delete a group.
+    pub fn remove_group(&self, external_id: &str) {
+        self.groups.write().unwrap().remove(external_id);
+    }
+}
+
+#[async_trait]
+impl DirectorySource for MockDirectorySource {
+    async fn list_groups(&self) -> CretoResult<Vec<DirectoryGroup>> {
+        let mut groups: Vec<_> = self
+            .groups
+            .read()
+            .unwrap()
+            .values()
+            .map(|(g, _)| g.clone())
+            .collect();
+        groups.sort_by(|a, b| a.external_id.cmp(&b.external_id));
+        Ok(groups)
+    }
+
+    async fn list_members(&self, external_id: &str) -> CretoResult<Vec<UserId>> {
+        self.groups
+            .read()
+            .unwrap()
+            .get(external_id)
+            .map(|(_, members)| members.clone())
+            .ok_or_else(|| CretoError::NotFound(format!("Directory group {}", external_id)))
+    }
+}
+
+/// SCIM 2.0 directory (`/Groups` endpoint).
+///
+/// Member `value`s must be the reviewers' user IDs; members whose value is
+/// not a UUID are skipped.
+#[cfg(feature = "scim")]
+pub struct ScimDirectorySource {
+    client: reqwest::Client,
+    base_url: String,
+    bearer_token: String,
+    page_size: usize,
+}
+
+#[cfg(feature = "scim")]
+impl ScimDirectorySource {
+    /// Create a source for the SCIM service at `base_url`.
+    pub fn new(
+        base_url: impl Into<String>,
+        bearer_token: impl Into<String>,
+        timeout: Duration,
+    ) -> CretoResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| CretoError::Internal(format!("Failed to build HTTP client: {}", e)))?;
+        Ok(Self {
+            client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            bearer_token: bearer_token.into(),
+            page_size: 100,
+        })
+    }
+
+    /// Set the number of groups requested per page.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    async fn get_json(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> CretoResult<serde_json::Value> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .bearer_auth(&self.bearer_token)
+            .header("Accept", "application/scim+json")
+            .query(query)
+            .send()
+            .await
+            .map_err(|e| CretoError::Internal(format!("SCIM request failed: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(CretoError::Internal(format!(
+                "SCIM request to {} returned {}",
+                path,
+                response.status()
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| CretoError::SerializationError(e.to_string()))
+    }
+}
+
+#[cfg(feature = "scim")]
+#[async_trait]
+impl DirectorySource for ScimDirectorySource {
+    async fn list_groups(&self) -> CretoResult<Vec<DirectoryGroup>> {
+        let mut groups = Vec::new();
+        let mut start_index = 1;
+        loop {
+            let page = self
+                .get_json(
+                    "/Groups",
+                    &[
+                        ("startIndex", start_index.to_string()),
+                        ("count", self.page_size.to_string()),
+                        ("excludedAttributes", "members".to_string()),
+                    ],
+                )
+                .await?;
+            let resources = page["Resources"].as_array().cloned().unwrap_or_default();
+            for resource in &resources {
+                if let (Some(id), Some(name)) =
+                    (resource["id"].as_str(), resource["displayName"].as_str())
+                {
+                    groups.push(DirectoryGroup {
+                        external_id: id.to_string(),
+                        display_name: name.to_string(),
+                    });
+                }
+            }
+
+            let total = page["totalResults"].as_u64().unwrap_or(0) as usize;
+            start_index += resources.len();
+            if resources.is_empty() || start_index > total {
+                return Ok(groups);
+            }
+        }
+    }
+
+    async fn list_members(&self, external_id: &str) -> CretoResult<Vec<UserId>> {
+        let group = self
+            .get_json(&format!("/Groups/{}", external_id), &[])
+            .await?;
+        let members = group["members"].as_array().cloned().unwrap_or_default();
+        Ok(members
+            .iter()
+            .filter_map(|m| {
+                let value = m["value"].as_str()?;
+                match Uuid::parse_str(value) {
+                    Ok(id) => Some(UserId::from_uuid(id)),
+                    Err(_) => {
+                        tracing::warn!(
+                            group = external_id,
+                            member = value,
+                            "Skipping SCIM member without a UUID id"
+                        );
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+}
+
+/// One change made (or, in a dry run, that would be made) by a sync.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupChange {
+    /// Local group name.
+    pub group: String,
+    /// The change.
+    pub change: GroupChangeKind,
+}
+
+/// Kind of sync change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GroupChangeKind {
+    /// A group was created from the directory.
+    Created,
+    /// The group was deleted upstream and has been disabled.
+    Disabled,
+    /// The group reappeared upstream and has been re-enabled.
+    Reenabled,
+    /// A member was added.
+    MemberAdded { user_id: UserId },
+    /// A member was removed.
+    MemberRemoved { user_id: UserId },
+    /// A pinned member absent upstream was kept.
+    PinnedMemberKept { user_id: UserId },
+    /// A local group already uses the directory group's name; the directory
+    /// group was not synced.
+    NameConflict { external_id: String },
+}
+
+/// Outcome of a directory sync.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncReport {
+    /// Whether the changes were only computed, not applied.
+    pub dry_run: bool,
+    /// Changes in the order they were made.
+    pub changes: Vec<GroupChange>,
+}
+
+impl SyncReport {
+    /// Whether nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn push(&mut self, group: &str, change: GroupChangeKind) {
+        self.changes.push(GroupChange {
+            group: group.to_string(),
+            change,
+        });
+    }
+}
+
+/// Reconciles an organization's reviewer groups with a directory.
+pub struct ReviewerDirectorySync {
+    organization_id: OrganizationId,
+    source: Arc<dyn DirectorySource>,
+    store: Arc<dyn ReviewerGroupStore>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ReviewerDirectorySync {
+    /// Create a sync of `source` into `store` for one organization.
+    pub fn new(
+        organization_id: OrganizationId,
+        source: Arc<dyn DirectorySource>,
+        store: Arc<dyn ReviewerGroupStore>,
+    ) -> Self {
+        Self {
+            organization_id,
+            source,
+            store,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Use a different time source.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Apply the directory's groups and memberships to the store.
+    pub async fn sync(&self) -> CretoResult<SyncReport> {
+        self.reconcile(false).await
+    }
+
+    /// Report what [`sync`](Self::sync) would change without changing it.
+    pub async fn dry_run(&self) -> CretoResult<SyncReport> {
+        self.reconcile(true).await
+    }
+
+    async fn reconcile(&self, dry_run: bool) -> CretoResult<SyncReport> {
+        // Read the whole directory first so a failing source changes nothing
+        let mut upstream = Vec::new();
+        for group in self.source.list_groups().await? {
+            let mut members: Vec<UserId> = Vec::new();
+            for user_id in self.source.list_members(&group.external_id).await? {
+                if !members.contains(&user_id) {
+                    members.push(user_id);
+                }
+            }
+            upstream.push((group, members));
+        }
+
+        let now = self.clock.now();
+        let mut report = SyncReport {
+            dry_run,
+            changes: Vec::new(),
+        };
+        let mut local = self.store.list(self.organization_id).await?;
+        let mut changed = Vec::new();
+
+        for (directory_group, members) in &upstream {
+            let existing = local
+                .iter()
+                .position(|g| g.external_id.as_deref() == Some(&directory_group.external_id));
+            let mut group = match existing {
+                Some(i) => local[i].clone(),
+                None if local.iter().any(|g| g.name == directory_group.display_name) => {
+                    report.push(
+                        &directory_group.display_name,
+                        GroupChangeKind::NameConflict {
+                            external_id: directory_group.external_id.clone(),
+                        },
+                    );
+                    continue;
+                }
+                None => {
+                    report.push(&directory_group.display_name, GroupChangeKind::Created);
+                    ReviewerGroup::new(self.organization_id, &directory_group.display_name, now)
+                        .with_external_id(&directory_group.external_id)
+                }
+            };
+            let mut dirty = existing.is_none();
+
+            if !group.is_active() {
+                group.status = ReviewerGroupStatus::Active;
+                group.updated_at = now;
+                report.push(&group.name, GroupChangeKind::Reenabled);
+                dirty = true;
+            }
+            for user_id in members {
+                if group.add_member(*user_id, now) {
+                    report.push(
+                        &group.name,
+                        GroupChangeKind::MemberAdded { user_id: *user_id },
+                    );
+                    dirty = true;
+                }
+            }
+            for user_id in group.members() {
+                if members.contains(&user_id) {
+                    continue;
+                }
+                if group.is_pinned(user_id) {
+                    report.push(&group.name, GroupChangeKind::PinnedMemberKept { user_id });
+                } else {
+                    group.remove_member(user_id, now);
+                    report.push(&group.name, GroupChangeKind::MemberRemoved { user_id });
+                    dirty = true;
+                }
+            }
+
+            if dirty {
+                match existing {
+                    Some(i) => local[i] = group.clone(),
+                    None => local.push(group.clone()),
+                }
+                changed.push(group);
+            }
+        }
+
+        for group in &mut local {
+            let Some(external_id) = &group.external_id else {
+                continue;
+            };
+            if group.is_active() && !upstream.iter().any(|(g, _)| &g.external_id == external_id) {
+                group.status = ReviewerGroupStatus::Disabled;
+                group.updated_at = now;
+                report.push(&group.name, GroupChangeKind::Disabled);
+                changed.push(group.clone());
+            }
+        }
+
+        if !dry_run {
+            for group in changed {
+                self.store.put(group).await?;
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Spawn a periodic directory sync.
+///
+/// Runs [`ReviewerDirectorySync::sync`] every `interval`. The task is
+/// registered with `shutdown` and stops between passes.
+pub fn spawn_directory_sync(
+    sync: Arc<ReviewerDirectorySync>,
+    shutdown: &ShutdownCoordinator,
+    interval: Duration,
+) -> JoinHandle<()> {
+    shutdown.spawn("oversight.directory_sync", move |guard| {
+        guard.run_periodic(interval, move || {
+            let sync = Arc::clone(&sync);
+            async move {
+                match sync.sync().await {
+                    Ok(report) if report.is_empty() => {}
+                    Ok(report) => {
+                        tracing::info!(changes = report.changes.len(), "Synced reviewer groups")
+                    }
+                    Err(e) => tracing::warn!(error = %e, "Reviewer directory sync failed"),
+                }
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+
+    struct Fixture {
+        org: OrganizationId,
+        source: Arc<MockDirectorySource>,
+        store: Arc<InMemoryReviewerGroupStore>,
+        clock: Arc<TestClock>,
+        sync: ReviewerDirectorySync,
+    }
+
+    fn fixture() -> Fixture {
+        let org = OrganizationId::new();
+        let source = Arc::new(MockDirectorySource::new());
+        let store = Arc::new(InMemoryReviewerGroupStore::new());
+        let clock = Arc::new(TestClock::new(Utc::now()));
+        let sync = ReviewerDirectorySync::new(org, source.clone(), store.clone())
+            .with_clock(clock.clone());
+        Fixture {
+            org,
+            source,
+            store,
+            clock,
+            sync,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initial_sync_and_dry_run() {
+        let f = fixture();
+        let (alice, bob) = (UserId::new(), UserId::new());
+        f.source
+            .set_group("g-1", "finance-approvers", vec![alice, bob]);
+        f.source.set_group("g-2", "security", vec![]);
+
+        let preview = f.sync.dry_run().await.unwrap();
+        assert!(preview.dry_run);
+        assert_eq!(preview.changes.len(), 4);
+        assert!(f.store.list(f.org).await.unwrap().is_empty());
+
+        let report = f.sync.sync().await.unwrap();
+        assert!(!report.dry_run);
+        assert_eq!(report.changes, preview.changes);
+        let finance = f
+            .store
+            .get(f.org, "finance-approvers")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(finance.external_id.as_deref(), Some("g-1"));
+        assert_eq!(finance.members(), vec![alice, bob]);
+        assert!(f.store.get(f.org, "security").await.unwrap().is_some());
+
+        // Nothing to do the second time
+        assert!(f.sync.sync().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upstream_removal_and_pinned_member_protection() {
+        let f = fixture();
+        let (alice, bob, carol) = (UserId::new(), UserId::new(), UserId::new());
+        f.source
+            .set_group("g-1", "finance-approvers", vec![alice, bob]);
+        f.sync.sync().await.unwrap();
+
+        let mut group = f
+            .store
+            .get(f.org, "finance-approvers")
+            .await
+            .unwrap()
+            .unwrap();
+        group.pin_member(bob, f.clock.now());
+        group.pin_member(carol, f.clock.now());
+        f.store.put(group).await.unwrap();
+
+        f.clock.advance(chrono::Duration::hours(1));
+        f.source.set_group("g-1", "finance-approvers", vec![]);
+        let report = f.sync.sync().await.unwrap();
+        assert!(report.changes.contains(&GroupChange {
+            group: "finance-approvers".to_string(),
+            change: GroupChangeKind::MemberRemoved { user_id: alice },
+        }));
+        assert!(report.changes.contains(&GroupChange {
+            group: "finance-approvers".to_string(),
+            change: GroupChangeKind::PinnedMemberKept { user_id: bob },
+        }));
+
+        let group = f
+            .store
+            .get(f.org, "finance-approvers")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(group.members(), vec![bob, carol]);
+        assert!(!group.is_member(alice));
+        assert!(group.was_member_at(alice, f.clock.now() - chrono::Duration::minutes(30)));
+    }
+
+    #[tokio::test]
+    async fn test_group_deleted_upstream_is_disabled_then_reenabled() {
+        let f = fixture();
+        let alice = UserId::new();
+        f.source.set_group("g-1", "finance-approvers", vec![alice]);
+        f.sync.sync().await.unwrap();
+
+        f.source.remove_group("g-1");
+        let report = f.sync.sync().await.unwrap();
+        assert_eq!(report.changes[0].change, GroupChangeKind::Disabled);
+        let group = f
+            .store
+            .get(f.org, "finance-approvers")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!group.is_active());
+        assert_eq!(group.members(), vec![alice]);
+        assert!(f.sync.sync().await.unwrap().is_empty());
+
+        f.source.set_group("g-1", "Finance Approvers", vec![alice]);
+        let report = f.sync.sync().await.unwrap();
+        assert_eq!(report.changes[0].change, GroupChangeKind::Reenabled);
+        // Renames upstream keep the local name templates route to
+        let group = f
+            .store
+            .get(f.org, "finance-approvers")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(group.is_active());
+    }
+
+    #[tokio::test]
+    async fn test_local_group_name_conflict_is_left_alone() {
+        let f = fixture();
+        let local = UserId::new();
+        let mut group = ReviewerGroup::new(f.org, "security", f.clock.now());
+        group.add_member(local, f.clock.now());
+        f.store.put(group).await.unwrap();
+
+        f.source.set_group("g-9", "security", vec![UserId::new()]);
+        let report = f.sync.sync().await.unwrap();
+        assert_eq!(
+            report.changes[0].change,
+            GroupChangeKind::NameConflict {
+                external_id: "g-9".to_string()
+            }
+        );
+        let group = f.store.get(f.org, "security").await.unwrap().unwrap();
+        assert_eq!(group.members(), vec![local]);
+        assert!(group.external_id.is_none());
+    }
+
+    #[cfg(feature = "scim")]
+    #[tokio::test]
+    async fn test_scim_source_against_mock_server() {
+        use axum::{extract::Query, routing::get, Json, Router};
+
+        let alice = UserId::new();
+        async fn groups(Query(q): Query<HashMap<String, String>>) -> Json<serde_json::Value> {
+            let start: usize = q["startIndex"].parse().unwrap();
+            let all = [("g-1", "finance"), ("g-2", "security"), ("g-3", "legal")];
+            let page: Vec<_> = all
+                .iter()
+                .skip(start - 1)
+                .take(2)
+                .map(|(id, name)| serde_json::json!({"id": id, "displayName": name}))
+                .collect();
+            Json(serde_json::json!({"totalResults": 3, "startIndex": start, "Resources": page}))
+        }
+        let member = alice.as_uuid().to_string();
+        let app = Router::new().route("/scim/v2/Groups", get(groups)).route(
+            "/scim/v2/Groups/g-1",
+            get(move || async move {
+                Json(serde_json::json!({
+                    "id": "g-1",
+                    "members": [{"value": member}, {"value": "not-a-uuid"}]
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let source = ScimDirectorySource::new(
+            format!("http://{}/scim/v2/", addr),
+            "token",
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_page_size(2);
+        let groups = source.list_groups().await.unwrap();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[2].display_name, "legal");
+        assert_eq!(source.list_members("g-1").await.unwrap(), vec![alice]);
+    }
+}
@@ -0,0 +1,878 @@
+//! Automatic context enrichment for new oversight requests.
+//!
+//! Registered [`ContextEnricher`]s run when a request is created and append
+//! [`ContextSection`]s to its context: what the agent is about to execute,
+//! where it stands against its quotas, and how its recent requests were
+//! decided. An enricher that fails or runs past its timeout leaves its
+//! section marked unavailable instead of blocking the request.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use creto_common::{AgentId, CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::policy::PolicyContext;
+use crate::repository::RequestRepository;
+use crate::request::{ActionType, OversightRequest};
+
+/// Key under which enriched sections are stored in `request.context`.
+pub const CONTEXT_SECTIONS_KEY: &str = "sections";
+
+/// How long an enricher may run before its section is marked unavailable.
+pub const DEFAULT_ENRICHER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A structured block of reviewer context.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContextSection {
+    /// Stable identifier of the enricher that produced this section.
+    pub key: String,
+
+    /// Heading shown to reviewers.
+    pub title: String,
+
+    /// Whether the section could be produced.
+    pub status: SectionStatus,
+
+    /// Label/value pairs, in display order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<SectionField>,
+
+    /// Why the section is unavailable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ContextSection {
+    /// Create an available section.
+    pub fn new(
+        key: impl Into<String>,
+        title: impl Into<String>,
+        fields: Vec<SectionField>,
+    ) -> Self {
+        Self {
+            key: key.into(),
+            title: title.into(),
+            status: SectionStatus::Available,
+            fields,
+            error: None,
+        }
+    }
+
+    /// Create a section whose enricher failed.
+    pub fn unavailable(
+        key: impl Into<String>,
+        title: impl Into<String>,
+        error: impl Into<String>,
+    ) -> Self {
+        Self {
+            key: key.into(),
+            title: title.into(),
+            status: SectionStatus::Unavailable,
+            fields: Vec::new(),
+            error: Some(error.into()),
+        }
+    }
+}
+
+/// Availability of a context section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionStatus {
+    /// The enricher produced the section.
+    Available,
+    /// The enricher failed; the section carries only an error.
+    Unavailable,
+}
+
+/// A label/value pair in a context section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionField {
+    /// Human-readable label.
+    pub label: String,
+    /// Display value.
+    pub value: String,
+}
+
+impl SectionField {
+    /// Create a field.
+    pub fn new(label: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// Split a request context into its caller-supplied part and the enriched
+/// sections. The caller-supplied part is `Null` if nothing else is left.
+///
+/// A `sections` key that does not hold context sections is left in the
+/// caller-supplied part rather than dropped.
+pub fn split_sections(context: &serde_json::Value) -> (serde_json::Value, Vec<ContextSection>) {
+    let Some(object) = context.as_object() else {
+        return (context.clone(), Vec::new());
+    };
+    let Some(sections) = object.get(CONTEXT_SECTIONS_KEY) else {
+        return (context.clone(), Vec::new());
+    };
+
+    let sections = match serde_json::from_value(sections.clone()) {
+        Ok(sections) => sections,
+        Err(e) => {
+            tracing::warn!(error = %e, "Request context has unparsable sections");
+            return (context.clone(), Vec::new());
+        }
+    };
+    let mut rest = object.clone();
+    rest.remove(CONTEXT_SECTIONS_KEY);
+    let rest = if rest.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::Value::Object(rest)
+    };
+    (rest, sections)
+}
+
+/// Append sections to a request's context, turning a non-object context
+/// into `{"details": <previous>}` first.
+fn append_sections(request: &mut OversightRequest, sections: Vec<ContextSection>) {
+    if sections.is_empty() {
+        return;
+    }
+
+    let context = std::mem::take(&mut request.context);
+    let mut object = match context {
+        serde_json::Value::Object(object) => object,
+        serde_json::Value::Null => serde_json::Map::new(),
+        other => {
+            let mut object = serde_json::Map::new();
+            object.insert("details".to_string(), other);
+            object
+        }
+    };
+
+    let list = object
+        .entry(CONTEXT_SECTIONS_KEY)
+        .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+    if !list.is_array() {
+        *list = serde_json::Value::Array(Vec::new());
+    }
+    if let Some(list) = list.as_array_mut() {
+        list.extend(
+            sections
+                .iter()
+                .filter_map(|section| serde_json::to_value(section).ok()),
+        );
+    }
+
+    request.context = serde_json::Value::Object(object);
+}
+
+/// Execution the caller is asking a reviewer to approve.
+///
+/// Mirrors the runtime's execution request without depending on it; resource
+/// limits are passed through as their serialized form.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionReference {
+    /// Execution ID.
+    pub execution_id: Uuid,
+    /// Language/runtime the code runs in.
+    pub runtime: String,
+    /// Code to execute.
+    pub code: String,
+    /// Entry point function, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_point: Option<String>,
+    /// Execution timeout override in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u32>,
+    /// Resource limits of the target sandbox.
+    #[serde(default)]
+    pub resource_limits: serde_json::Value,
+}
+
+/// Caller-supplied inputs for enrichment.
+#[derive(Debug, Clone, Default)]
+pub struct EnrichmentInput {
+    /// Execution behind a `CodeExecution` request.
+    pub execution: Option<ExecutionReference>,
+}
+
+impl EnrichmentInput {
+    /// Inputs carried by the policy context of the action being approved.
+    pub fn from_policy_context(context: &PolicyContext) -> Self {
+        Self {
+            execution: context.execution.clone(),
+        }
+    }
+
+    /// Attach the execution being approved.
+    pub fn with_execution(mut self, execution: ExecutionReference) -> Self {
+        self.execution = Some(execution);
+        self
+    }
+}
+
+/// Adds one section of context to new requests.
+#[async_trait]
+pub trait ContextEnricher: Send + Sync {
+    /// Stable section key.
+    fn key(&self) -> &str;
+
+    /// Section heading.
+    fn title(&self) -> &str;
+
+    /// How long [`enrich`](Self::enrich) may run before the section is
+    /// marked unavailable.
+    fn timeout(&self) -> Duration {
+        DEFAULT_ENRICHER_TIMEOUT
+    }
+
+    /// Produce the section's fields, or `None` if it does not apply to this
+    /// request.
+    async fn enrich(
+        &self,
+        request: &OversightRequest,
+        input: &EnrichmentInput,
+    ) -> CretoResult<Option<Vec<SectionField>>>;
+}
+
+/// Run `enrichers` in order against `request`, appending their sections.
+///
+/// Each enricher is bounded by its [`timeout`](ContextEnricher::timeout).
+/// Returns the number of sections added, including unavailable ones.
+pub async fn enrich_request(
+    enrichers: &[Arc<dyn ContextEnricher>],
+    request: &mut OversightRequest,
+    input: &EnrichmentInput,
+) -> usize {
+    let mut sections = Vec::new();
+    for enricher in enrichers {
+        let timeout = enricher.timeout();
+        let enriched = tokio::time::timeout(timeout, enricher.enrich(request, input))
+            .await
+            .unwrap_or_else(|_| {
+                Err(CretoError::Internal(format!(
+                    "Enricher did not finish within {}ms",
+                    timeout.as_millis()
+                )))
+            });
+        match enriched {
+            Ok(Some(fields)) => sections.push(ContextSection::new(
+                enricher.key(),
+                enricher.title(),
+                fields,
+            )),
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(
+                    request_id = %request.id,
+                    enricher = enricher.key(),
+                    error = %e,
+                    "Context enrichment failed"
+                );
+                sections.push(ContextSection::unavailable(
+                    enricher.key(),
+                    enricher.title(),
+                    e.to_string(),
+                ));
+            }
+        }
+    }
+
+    let added = sections.len();
+    append_sections(request, sections);
+    added
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Execution details
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Shows the code, runtime and resource limits of a `CodeExecution` request.
+pub struct ExecutionDetailsEnricher {
+    max_code_chars: usize,
+}
+
+impl ExecutionDetailsEnricher {
+    /// Create an enricher showing at most 1500 characters of code.
+    pub fn new() -> Self {
+        Self {
+            max_code_chars: 1500,
+        }
+    }
+
+    /// Set how many characters of code to show.
+    pub fn with_max_code_chars(mut self, max: usize) -> Self {
+        self.max_code_chars = max;
+        self
+    }
+}
+
+impl Default for ExecutionDetailsEnricher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!(
+            "{}… ({} more characters)",
+            &text[..end],
+            text[end..].chars().count()
+        ),
+        None => text.to_string(),
+    }
+}
+
+fn display_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[async_trait]
+impl ContextEnricher for ExecutionDetailsEnricher {
+    fn key(&self) -> &str {
+        "execution"
+    }
+
+    fn title(&self) -> &str {
+        "Execution details"
+    }
+
+    async fn enrich(
+        &self,
+        request: &OversightRequest,
+        input: &EnrichmentInput,
+    ) -> CretoResult<Option<Vec<SectionField>>> {
+        let ActionType::CodeExecution {
+            runtime,
+            risk_level,
+        } = &request.action_type
+        else {
+            return Ok(None);
+        };
+        let Some(execution) = &input.execution else {
+            return Ok(None);
+        };
+
+        let runtime = if execution.runtime.is_empty() {
+            runtime
+        } else {
+            &execution.runtime
+        };
+        let mut fields = vec![
+            SectionField::new("Execution ID", execution.execution_id.to_string()),
+            SectionField::new("Runtime", runtime.clone()),
+            SectionField::new("Risk level", risk_level.clone()),
+        ];
+        if let Some(entry_point) = &execution.entry_point {
+            fields.push(SectionField::new("Entry point", entry_point.clone()));
+        }
+        if let Some(timeout) = execution.timeout_seconds {
+            fields.push(SectionField::new("Timeout", format!("{}s", timeout)));
+        }
+        if let Some(limits) = execution.resource_limits.as_object() {
+            fields.extend(limits.iter().filter(|(_, value)| !value.is_null()).map(
+                |(name, value)| SectionField::new(format!("Limit: {}", name), display_json(value)),
+            ));
+        }
+        fields.push(SectionField::new(
+            "Code",
+            truncate_chars(&execution.code, self.max_code_chars),
+        ));
+
+        Ok(Some(fields))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Quota snapshot
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Current usage of one quota-limited metric.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaSnapshot {
+    /// Billable metric code.
+    pub metric_code: String,
+    /// Usage in the current period.
+    pub current_usage: i64,
+    /// Quota limit for the period.
+    pub limit: i64,
+}
+
+impl QuotaSnapshot {
+    /// Usage as a percentage of the limit.
+    pub fn usage_percentage(&self) -> f64 {
+        if self.limit <= 0 {
+            return 0.0;
+        }
+        self.current_usage as f64 / self.limit as f64 * 100.0
+    }
+}
+
+/// Source of quota usage, typically backed by the metering service.
+#[async_trait]
+pub trait QuotaStatusProvider: Send + Sync {
+    /// Current quota usage for an agent.
+    async fn quota_status(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+    ) -> CretoResult<Vec<QuotaSnapshot>>;
+}
+
+/// Shows the agent's current quota usage.
+pub struct QuotaSnapshotEnricher {
+    provider: Arc<dyn QuotaStatusProvider>,
+    metrics: Vec<String>,
+}
+
+impl QuotaSnapshotEnricher {
+    /// Create an enricher showing every metric the provider reports.
+    pub fn new(provider: Arc<dyn QuotaStatusProvider>) -> Self {
+        Self {
+            provider,
+            metrics: Vec::new(),
+        }
+    }
+
+    /// Only show these metrics.
+    pub fn with_metrics(mut self, metrics: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.metrics = metrics.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+#[async_trait]
+impl ContextEnricher for QuotaSnapshotEnricher {
+    fn key(&self) -> &str {
+        "quota"
+    }
+
+    fn title(&self) -> &str {
+        "Quota usage"
+    }
+
+    async fn enrich(
+        &self,
+        request: &OversightRequest,
+        _input: &EnrichmentInput,
+    ) -> CretoResult<Option<Vec<SectionField>>> {
+        let snapshots = self
+            .provider
+            .quota_status(request.organization_id, request.agent_id)
+            .await?;
+
+        let fields: Vec<SectionField> = snapshots
+            .iter()
+            .filter(|s| self.metrics.is_empty() || self.metrics.contains(&s.metric_code))
+            .map(|s| {
+                SectionField::new(
+                    s.metric_code.clone(),
+                    format!(
+                        "{} / {} ({:.0}%)",
+                        s.current_usage,
+                        s.limit,
+                        s.usage_percentage()
+                    ),
+                )
+            })
+            .collect();
+
+        Ok((!fields.is_empty()).then_some(fields))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Request history
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Shows the agent's most recent oversight requests and their outcomes.
+pub struct RequestHistoryEnricher {
+    repository: Arc<dyn RequestRepository>,
+    limit: usize,
+}
+
+impl RequestHistoryEnricher {
+    /// Create an enricher showing the last 5 requests.
+    pub fn new(repository: Arc<dyn RequestRepository>) -> Self {
+        Self {
+            repository,
+            limit: 5,
+        }
+    }
+
+    /// Set how many requests to show.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+}
+
+#[async_trait]
+impl ContextEnricher for RequestHistoryEnricher {
+    fn key(&self) -> &str {
+        "request_history"
+    }
+
+    fn title(&self) -> &str {
+        "Recent requests"
+    }
+
+    async fn enrich(
+        &self,
+        request: &OversightRequest,
+        _input: &EnrichmentInput,
+    ) -> CretoResult<Option<Vec<SectionField>>> {
+        // One extra in case the new request has already been persisted
+        let history = self
+            .repository
+            .list_by_agent(request.agent_id, self.limit as i64 + 1)
+            .await?;
+
+        let fields: Vec<SectionField> = history
+            .iter()
+            .filter(|past| past.id != request.id)
+            .take(self.limit)
+            .map(|past| {
+                let status = serde_json::to_value(past.status)
+                    .map(|v| display_json(&v))
+                    .unwrap_or_default();
+                SectionField::new(
+                    past.created_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+                    format!("{} ({})", past.description, status),
+                )
+            })
+            .collect();
+
+        if fields.is_empty() {
+            return Ok(Some(vec![SectionField::new(
+                "History",
+                "No previous requests",
+            )]));
+        }
+        Ok(Some(fields))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use creto_common::CretoError;
+
+    use crate::request::{Priority, RequestStatus};
+
+    struct FixedQuota(CretoResult<Vec<QuotaSnapshot>>);
+
+    #[async_trait]
+    impl QuotaStatusProvider for FixedQuota {
+        async fn quota_status(
+            &self,
+            _organization_id: OrganizationId,
+            _agent_id: AgentId,
+        ) -> CretoResult<Vec<QuotaSnapshot>> {
+            match &self.0 {
+                Ok(snapshots) => Ok(snapshots.clone()),
+                Err(e) => Err(CretoError::Internal(e.to_string())),
+            }
+        }
+    }
+
+    /// Returns a fixed request history for every agent.
+    struct History(Vec<OversightRequest>);
+
+    #[async_trait]
+    impl RequestRepository for History {
+        async fn create(&self, request: &OversightRequest) -> Result<Uuid, CretoError> {
+            Ok(request.id)
+        }
+
+        async fn get(&self, _id: Uuid) -> Result<Option<OversightRequest>, CretoError> {
+            Ok(None)
+        }
+
+        async fn update_status(&self, _id: Uuid, _status: RequestStatus) -> Result<(), CretoError> {
+            Ok(())
+        }
+
+        async fn update_status_if(
+            &self,
+            _id: Uuid,
+            _expected: RequestStatus,
+            _status: RequestStatus,
+        ) -> Result<bool, CretoError> {
+            Ok(true)
+        }
+
+        async fn update_priority(&self, _id: Uuid, _priority: Priority) -> Result<(), CretoError> {
+            Ok(())
+        }
+
+        async fn list_open(&self) -> Result<Vec<OversightRequest>, CretoError> {
+            Ok(Vec::new())
+        }
+
+        async fn list_pending(
+            &self,
+            _org_id: OrganizationId,
+        ) -> Result<Vec<OversightRequest>, CretoError> {
+            Ok(Vec::new())
+        }
+
+        async fn list_by_agent(
+            &self,
+            _agent_id: AgentId,
+            limit: i64,
+        ) -> Result<Vec<OversightRequest>, CretoError> {
+            Ok(self.0.iter().take(limit as usize).cloned().collect())
+        }
+
+        async fn escalate(
+            &self,
+            _id: Uuid,
+            _level: u32,
+            _reviewers: &[creto_common::UserId],
+            _expires_at: chrono::DateTime<chrono::Utc>,
+        ) -> Result<(), CretoError> {
+            Ok(())
+        }
+
+        async fn record_reminder(
+            &self,
+            _id: Uuid,
+            _at: chrono::DateTime<chrono::Utc>,
+        ) -> Result<u32, CretoError> {
+            Ok(1)
+        }
+
+        async fn set_notified_channels(
+            &self,
+            _id: Uuid,
+            _channels: &[crate::channels::ChannelType],
+        ) -> Result<(), CretoError> {
+            Ok(())
+        }
+
+        async fn find_timed_out(&self) -> Result<Vec<Uuid>, CretoError> {
+            Ok(Vec::new())
+        }
+
+        async fn find_by_correlation(
+            &self,
+            _correlation_id: Uuid,
+        ) -> Result<Vec<OversightRequest>, CretoError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn code_request() -> OversightRequest {
+        OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::CodeExecution {
+                runtime: "python".to_string(),
+                risk_level: "high".to_string(),
+            },
+            "Run data cleanup script",
+        )
+    }
+
+    fn field<'a>(section: &'a ContextSection, label: &str) -> Option<&'a str> {
+        section
+            .fields
+            .iter()
+            .find(|f| f.label == label)
+            .map(|f| f.value.as_str())
+    }
+
+    #[tokio::test]
+    async fn test_execution_details_section() {
+        let enrichers: Vec<Arc<dyn ContextEnricher>> = vec![Arc::new(
+            ExecutionDetailsEnricher::new().with_max_code_chars(10),
+        )];
+        let input = EnrichmentInput::default().with_execution(ExecutionReference {
+            execution_id: Uuid::now_v7(),
+            runtime: "python3.11".to_string(),
+            code: "import os\nos.remove('/tmp/cache')".to_string(),
+            entry_point: None,
+            timeout_seconds: Some(30),
+            resource_limits: serde_json::json!({"memory_bytes": 536870912, "max_connections": null}),
+        });
+
+        let mut request = code_request();
+        assert_eq!(enrich_request(&enrichers, &mut request, &input).await, 1);
+
+        let (rest, sections) = split_sections(&request.context);
+        assert!(rest.is_null());
+        let section = &sections[0];
+        assert_eq!(section.key, "execution");
+        assert_eq!(section.status, SectionStatus::Available);
+        assert_eq!(field(section, "Runtime"), Some("python3.11"));
+        assert_eq!(field(section, "Risk level"), Some("high"));
+        assert_eq!(field(section, "Timeout"), Some("30s"));
+        assert_eq!(field(section, "Limit: memory_bytes"), Some("536870912"));
+        assert_eq!(field(section, "Limit: max_connections"), None);
+        assert_eq!(
+            field(section, "Code"),
+            Some("import os\n… (23 more characters)")
+        );
+
+        // Not a code execution, or no execution supplied: no section
+        let mut request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "x".to_string(),
+            },
+            "Other",
+        );
+        assert_eq!(enrich_request(&enrichers, &mut request, &input).await, 0);
+        let mut request = code_request();
+        let none = EnrichmentInput::default();
+        assert_eq!(enrich_request(&enrichers, &mut request, &none).await, 0);
+        assert!(request.context.is_null());
+    }
+
+    #[tokio::test]
+    async fn test_quota_snapshot_section() {
+        let provider = FixedQuota(Ok(vec![
+            QuotaSnapshot {
+                metric_code: "executions".to_string(),
+                current_usage: 90,
+                limit: 100,
+            },
+            QuotaSnapshot {
+                metric_code: "api_calls".to_string(),
+                current_usage: 10,
+                limit: 1000,
+            },
+        ]));
+        let enrichers: Vec<Arc<dyn ContextEnricher>> = vec![Arc::new(
+            QuotaSnapshotEnricher::new(Arc::new(provider)).with_metrics(["executions"]),
+        )];
+
+        let mut request = code_request();
+        enrich_request(&enrichers, &mut request, &EnrichmentInput::default()).await;
+
+        let (_, sections) = split_sections(&request.context);
+        assert_eq!(sections[0].key, "quota");
+        assert_eq!(
+            sections[0].fields,
+            vec![SectionField::new("executions", "90 / 100 (90%)")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_request_history_section() {
+        let mut request = code_request();
+        let mut approved = OversightRequest::new(
+            request.organization_id,
+            request.agent_id,
+            request.action_type.clone(),
+            "Earlier cleanup",
+        );
+        approved.status = RequestStatus::Approved;
+        let mut rejected = approved.clone();
+        rejected.id = Uuid::now_v7();
+        rejected.description = "Drop tables".to_string();
+        rejected.status = RequestStatus::Rejected;
+
+        let repository = History(vec![request.clone(), rejected, approved]);
+        let enrichers: Vec<Arc<dyn ContextEnricher>> = vec![Arc::new(
+            RequestHistoryEnricher::new(Arc::new(repository)).with_limit(2),
+        )];
+        enrich_request(&enrichers, &mut request, &EnrichmentInput::default()).await;
+
+        let (_, sections) = split_sections(&request.context);
+        let values: Vec<&str> = sections[0]
+            .fields
+            .iter()
+            .map(|f| f.value.as_str())
+            .collect();
+        assert_eq!(
+            values,
+            vec!["Drop tables (rejected)", "Earlier cleanup (approved)"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_enricher_times_out() {
+        struct Stuck;
+
+        #[async_trait]
+        impl ContextEnricher for Stuck {
+            fn key(&self) -> &str {
+                "lineage"
+            }
+
+            fn title(&self) -> &str {
+                "Data lineage"
+            }
+
+            fn timeout(&self) -> Duration {
+                Duration::from_millis(50)
+            }
+
+            async fn enrich(
+                &self,
+                _request: &OversightRequest,
+                _input: &EnrichmentInput,
+            ) -> CretoResult<Option<Vec<SectionField>>> {
+                std::future::pending().await
+            }
+        }
+
+        let enrichers: Vec<Arc<dyn ContextEnricher>> = vec![
+            Arc::new(Stuck),
+            Arc::new(RequestHistoryEnricher::new(Arc::new(History(Vec::new())))),
+        ];
+        let mut request = code_request();
+        let added = enrich_request(&enrichers, &mut request, &EnrichmentInput::default()).await;
+        assert_eq!(added, 2);
+
+        let (_, sections) = split_sections(&request.context);
+        assert_eq!(sections[0].status, SectionStatus::Unavailable);
+        assert!(sections[0].error.as_deref().unwrap().contains("50ms"));
+        assert_eq!(sections[1].status, SectionStatus::Available);
+    }
+
+    #[test]
+    fn test_split_sections_keeps_unparsable_sections_key() {
+        let context = serde_json::json!({"sections": ["intro", "body"], "owner": "data"});
+
+        let (rest, sections) = split_sections(&context);
+        assert_eq!(rest, context);
+        assert!(sections.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failing_enricher_marks_section_unavailable() {
+        let failing = FixedQuota(Err(CretoError::Internal("metering down".to_string())));
+        let enrichers: Vec<Arc<dyn ContextEnricher>> = vec![
+            Arc::new(QuotaSnapshotEnricher::new(Arc::new(failing))),
+            Arc::new(RequestHistoryEnricher::new(Arc::new(History(Vec::new())))),
+        ];
+
+        let mut request = code_request().with_context(serde_json::json!("caller note"));
+        let added = enrich_request(&enrichers, &mut request, &EnrichmentInput::default()).await;
+        assert_eq!(added, 2);
+
+        let (rest, sections) = split_sections(&request.context);
+        assert_eq!(rest, serde_json::json!({"details": "caller note"}));
+        assert_eq!(sections[0].status, SectionStatus::Unavailable);
+        assert!(sections[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("metering down"));
+        assert_eq!(sections[1].status, SectionStatus::Available);
+    }
+}
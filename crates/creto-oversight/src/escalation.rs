@@ -0,0 +1,406 @@
+//! Escalation of requests that were not decided in time.
+//!
+//! An organization's [`EscalationChain`] lists the tiers a request moves
+//! through when nobody decides it: typically reviewer, then team lead, then
+//! organization administrator. A request starts with its own reviewers at
+//! level 0. When it passes its timeout, or a reviewer records
+//! [`ApprovalDecision::Escalate`](crate::approval::ApprovalDecision::Escalate),
+//! [`OversightService`](crate::service::OversightService) moves it to
+//! [`RequestStatus::Escalated`], hands it to the next tier's reviewers with
+//! that tier's (usually shorter) timeout, and notifies them on every
+//! registered channel. Each hop is recorded in the transition audit trail as
+//! a system transition with reason `escalated:<tier name>`.
+//!
+//! Once the last tier times out, the chain is exhausted and the request is
+//! closed as the chain's [`ExhaustedAction`] says, with reason
+//! [`EXHAUSTED_REASON`]. Requests of organizations without a chain time out
+//! as before.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{CretoError, CretoResult, OrganizationId, UserId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::request::{OversightRequest, RequestStatus};
+
+/// Reason prefix recorded on the audit entry of an escalation hop.
+pub const ESCALATION_REASON: &str = "escalated";
+
+/// Reason recorded on the audit entry closing a request whose chain ran out.
+pub const EXHAUSTED_REASON: &str = "escalation_exhausted";
+
+/// One level of an escalation chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscalationTier {
+    /// Name shown in the audit trail, such as `team_lead`.
+    pub name: String,
+
+    /// Reviewers the request is reassigned to.
+    pub reviewers: Vec<UserId>,
+
+    /// Seconds the tier has to decide before the request moves on.
+    pub timeout_seconds: u64,
+}
+
+impl EscalationTier {
+    /// Create a tier.
+    pub fn new(name: impl Into<String>, reviewers: Vec<UserId>, timeout_seconds: u64) -> Self {
+        Self {
+            name: name.into(),
+            reviewers,
+            timeout_seconds,
+        }
+    }
+}
+
+/// How a request is closed once every tier has timed out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExhaustedAction {
+    /// Reject the request.
+    #[default]
+    Reject,
+    /// Approve the request. Only for actions that are safe to let through
+    /// unreviewed.
+    Approve,
+}
+
+impl ExhaustedAction {
+    /// Status the request is closed with.
+    pub fn status(&self) -> RequestStatus {
+        match self {
+            ExhaustedAction::Reject => RequestStatus::Rejected,
+            ExhaustedAction::Approve => RequestStatus::Approved,
+        }
+    }
+
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExhaustedAction::Reject => "reject",
+            ExhaustedAction::Approve => "approve",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "approve" => ExhaustedAction::Approve,
+            _ => ExhaustedAction::Reject,
+        }
+    }
+}
+
+/// An organization's escalation tiers, in order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EscalationChain {
+    /// Organization the chain applies to.
+    pub organization_id: OrganizationId,
+
+    /// Tiers a request moves through, first to last.
+    pub tiers: Vec<EscalationTier>,
+
+    /// How a request is closed once the last tier times out.
+    #[serde(default)]
+    pub on_exhausted: ExhaustedAction,
+}
+
+impl EscalationChain {
+    /// Create an empty chain that rejects requests when exhausted.
+    pub fn new(organization_id: OrganizationId) -> Self {
+        Self {
+            organization_id,
+            tiers: Vec::new(),
+            on_exhausted: ExhaustedAction::default(),
+        }
+    }
+
+    /// Append a tier.
+    pub fn with_tier(mut self, tier: EscalationTier) -> Self {
+        self.tiers.push(tier);
+        self
+    }
+
+    /// Set how a request is closed once the last tier times out.
+    pub fn with_exhausted_action(mut self, action: ExhaustedAction) -> Self {
+        self.on_exhausted = action;
+        self
+    }
+
+    /// Check that every tier has reviewers and a timeout.
+    pub fn validate(&self) -> CretoResult<()> {
+        for tier in &self.tiers {
+            if tier.reviewers.is_empty() {
+                return Err(CretoError::ValidationFailed(format!(
+                    "escalation tier '{}' has no reviewers",
+                    tier.name
+                )));
+            }
+            if tier.timeout_seconds == 0 {
+                return Err(CretoError::ValidationFailed(format!(
+                    "escalation tier '{}' has no timeout",
+                    tier.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Where `request` goes next, escalating at `now`.
+    pub fn next_step(&self, request: &OversightRequest, now: DateTime<Utc>) -> EscalationStep {
+        match self.tiers.get(request.escalation_level as usize) {
+            Some(tier) => EscalationStep::Escalate {
+                level: request.escalation_level + 1,
+                tier: tier.clone(),
+                expires_at: now + Duration::seconds(tier.timeout_seconds as i64),
+            },
+            None => EscalationStep::Exhausted {
+                status: self.on_exhausted.status(),
+            },
+        }
+    }
+}
+
+/// What escalating a request does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EscalationStep {
+    /// Reassign the request to the next tier.
+    Escalate {
+        /// Escalation level the request moves to.
+        level: u32,
+        /// The tier taking over.
+        tier: EscalationTier,
+        /// The request's new timeout.
+        expires_at: DateTime<Utc>,
+    },
+    /// Every tier has had its turn; close the request.
+    Exhausted {
+        /// Status the request is closed with.
+        status: RequestStatus,
+    },
+}
+
+/// Why a request was escalated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EscalationTrigger {
+    /// The request passed its timeout.
+    TimedOut,
+    /// A reviewer asked for the request to be escalated.
+    Requested { reviewer_id: UserId },
+    /// The request's reviewers were reminded as often as allowed.
+    RemindersExhausted,
+}
+
+/// What an escalation pass did to a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EscalationOutcome {
+    /// The request moved to the next tier.
+    Escalated {
+        /// Escalation level the request is now at.
+        level: u32,
+        /// Name of the tier now reviewing it.
+        tier: String,
+        /// The tier's reviewers.
+        reviewers: Vec<UserId>,
+        /// The request's new timeout.
+        expires_at: DateTime<Utc>,
+    },
+    /// The chain ran out and the request was closed.
+    Exhausted {
+        /// Status the request was closed with.
+        status: RequestStatus,
+    },
+    /// The organization has no chain; the request timed out.
+    TimedOut,
+}
+
+/// A request escalated, closed by an exhausted chain, or timed out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Escalation {
+    /// The request.
+    pub request_id: Uuid,
+
+    /// Organization the request belongs to.
+    pub organization_id: OrganizationId,
+
+    /// Why the request was escalated.
+    pub trigger: EscalationTrigger,
+
+    /// What happened to it.
+    pub outcome: EscalationOutcome,
+}
+
+/// Storage for escalation chains, one per organization.
+#[async_trait]
+pub trait EscalationChainStore: Send + Sync {
+    /// The organization's chain, if it has one.
+    async fn get(&self, organization_id: OrganizationId) -> CretoResult<Option<EscalationChain>>;
+
+    /// Create or replace the organization's chain.
+    async fn put(&self, chain: &EscalationChain) -> CretoResult<()>;
+
+    /// Remove the organization's chain. Returns whether it had one.
+    async fn delete(&self, organization_id: OrganizationId) -> CretoResult<bool>;
+}
+
+/// In-memory escalation chain store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryEscalationChainStore {
+    chains: RwLock<HashMap<OrganizationId, EscalationChain>>,
+}
+
+impl InMemoryEscalationChainStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl EscalationChainStore for InMemoryEscalationChainStore {
+    async fn get(&self, organization_id: OrganizationId) -> CretoResult<Option<EscalationChain>> {
+        Ok(self.chains.read().unwrap().get(&organization_id).cloned())
+    }
+
+    async fn put(&self, chain: &EscalationChain) -> CretoResult<()> {
+        self.chains
+            .write()
+            .unwrap()
+            .insert(chain.organization_id, chain.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, organization_id: OrganizationId) -> CretoResult<bool> {
+        Ok(self
+            .chains
+            .write()
+            .unwrap()
+            .remove(&organization_id)
+            .is_some())
+    }
+}
+
+/// Decides where undecided requests go next, from each organization's
+/// escalation chain.
+#[derive(Clone)]
+pub struct EscalationEngine {
+    chains: Arc<dyn EscalationChainStore>,
+}
+
+impl EscalationEngine {
+    /// Create an engine reading chains from `chains`.
+    pub fn new(chains: Arc<dyn EscalationChainStore>) -> Self {
+        Self { chains }
+    }
+
+    /// Get the chain store.
+    pub fn chains(&self) -> &Arc<dyn EscalationChainStore> {
+        &self.chains
+    }
+
+    /// Validate and store an organization's chain.
+    pub async fn set_chain(&self, chain: &EscalationChain) -> CretoResult<()> {
+        chain.validate()?;
+        self.chains.put(chain).await
+    }
+
+    /// Where `request` goes next, or `None` if its organization has no
+    /// chain.
+    pub async fn next_step(
+        &self,
+        request: &OversightRequest,
+        now: DateTime<Utc>,
+    ) -> CretoResult<Option<EscalationStep>> {
+        Ok(self
+            .chains
+            .get(request.organization_id)
+            .await?
+            .map(|chain| chain.next_step(request, now)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use creto_common::AgentId;
+
+    use crate::request::ActionType;
+
+    #[test]
+    fn test_chain_steps_through_tiers_then_exhausts() {
+        let org = OrganizationId::new();
+        let lead = UserId::new();
+        let chain = EscalationChain::new(org)
+            .with_tier(EscalationTier::new("team_lead", vec![lead], 600))
+            .with_tier(EscalationTier::new("org_admin", vec![UserId::new()], 300));
+        let mut request = OversightRequest::new(
+            org,
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        );
+        let now = Utc::now();
+
+        match chain.next_step(&request, now) {
+            EscalationStep::Escalate {
+                level,
+                tier,
+                expires_at,
+            } => {
+                assert_eq!(level, 1);
+                assert_eq!(tier.reviewers, vec![lead]);
+                assert_eq!(expires_at, now + Duration::seconds(600));
+            }
+            step => panic!("unexpected step {:?}", step),
+        }
+
+        request.escalation_level = 2;
+        assert_eq!(
+            chain.next_step(&request, now),
+            EscalationStep::Exhausted {
+                status: RequestStatus::Rejected
+            }
+        );
+        let approving = chain.with_exhausted_action(ExhaustedAction::Approve);
+        assert_eq!(
+            approving.next_step(&request, now),
+            EscalationStep::Exhausted {
+                status: RequestStatus::Approved
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_engine_rejects_invalid_chains() {
+        let engine = EscalationEngine::new(Arc::new(InMemoryEscalationChainStore::new()));
+        let org = OrganizationId::new();
+
+        let empty_tier =
+            EscalationChain::new(org).with_tier(EscalationTier::new("lead", vec![], 60));
+        assert!(engine.set_chain(&empty_tier).await.is_err());
+        let no_timeout = EscalationChain::new(org).with_tier(EscalationTier::new(
+            "lead",
+            vec![UserId::new()],
+            0,
+        ));
+        assert!(engine.set_chain(&no_timeout).await.is_err());
+
+        assert!(engine.chains().get(org).await.unwrap().is_none());
+        let chain = EscalationChain::new(org).with_tier(EscalationTier::new(
+            "lead",
+            vec![UserId::new()],
+            60,
+        ));
+        engine.set_chain(&chain).await.unwrap();
+        assert_eq!(engine.chains().get(org).await.unwrap(), Some(chain));
+        assert!(engine.chains().delete(org).await.unwrap());
+    }
+}
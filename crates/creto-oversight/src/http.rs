@@ -0,0 +1,642 @@
+//! HTTP endpoints behind the links and buttons approval notifications carry.
+//!
+//! [`EmailChannel`] links approvers to `{dashboard_base_url}/approval?token=...`
+//! and Slack buttons post to the app's interactivity URL. The
+//! [`ApprovalEndpoints`] router serves both:
+//!
+//! - `GET /approval?token=...` checks the token and returns the request it
+//!   was issued for.
+//! - `POST /approval/{approve|reject}?token=...` checks the token again and
+//!   submits the decision through [`OversightService::submit_approval`] as
+//!   the token's approver. A token decides its request once; its signature
+//!   is then recorded in a [`ConsumedTokenStore`] and it is refused.
+//! - `POST /slack/callback` submits the decision of a Slack button press,
+//!   parsed by [`SlackChannel::parse_callback`].
+//!
+//! Channels identify approvers by email address or Slack user ID; an
+//! [`ApproverResolver`] maps those to the [`UserId`]s decisions are
+//! recorded under.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use creto_common::{verify_hmac_sha256, CretoError, CretoResult, UserId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::approval::ApprovalDecision;
+use crate::channels::{
+    ApprovalToken, ApprovalTokenError, ConsumedTokenStore, EmailChannel,
+    InMemoryConsumedTokenStore, SlackChannel,
+};
+use crate::request::{OversightRequest, RequestStatus};
+use crate::service::OversightService;
+
+/// Oldest Slack request timestamp accepted, in seconds, against replays.
+const SLACK_MAX_REQUEST_AGE_SECS: i64 = 300;
+
+/// How a channel identifies an approver.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ApproverIdentity {
+    /// Email address, lowercased.
+    Email(String),
+    /// Slack user ID.
+    Slack(String),
+}
+
+impl ApproverIdentity {
+    /// An email identity. Addresses compare case-insensitively.
+    pub fn email(address: &str) -> Self {
+        Self::Email(address.trim().to_ascii_lowercase())
+    }
+}
+
+impl std::fmt::Display for ApproverIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Email(address) => write!(f, "email {}", address),
+            Self::Slack(user_id) => write!(f, "Slack user {}", user_id),
+        }
+    }
+}
+
+/// Maps channel identities to the users decisions are recorded under.
+#[async_trait]
+pub trait ApproverResolver: Send + Sync {
+    /// The user behind `identity`, or `None` if they are not an approver.
+    async fn resolve(&self, identity: &ApproverIdentity) -> CretoResult<Option<UserId>>;
+}
+
+/// In-memory approver resolver for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryApproverResolver {
+    approvers: RwLock<HashMap<ApproverIdentity, UserId>>,
+}
+
+impl InMemoryApproverResolver {
+    /// Create an empty resolver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `identity` to `user_id`, replacing any previous mapping.
+    pub fn add(&self, identity: ApproverIdentity, user_id: UserId) {
+        self.approvers.write().unwrap().insert(identity, user_id);
+    }
+}
+
+#[async_trait]
+impl ApproverResolver for InMemoryApproverResolver {
+    async fn resolve(&self, identity: &ApproverIdentity) -> CretoResult<Option<UserId>> {
+        Ok(self.approvers.read().unwrap().get(identity).copied())
+    }
+}
+
+/// What `GET /approval` returns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalDetails {
+    /// The request the token was issued for.
+    pub request: OversightRequest,
+    /// Approver the token was issued to.
+    pub approver_email: String,
+    /// When the token stops being accepted.
+    pub token_expires_at: DateTime<Utc>,
+}
+
+/// What the decision endpoints return.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionOutcome {
+    /// The decided request.
+    pub request_id: Uuid,
+    /// The decision submitted.
+    pub decision: ApprovalDecision,
+    /// The request's status after the decision.
+    pub status: RequestStatus,
+}
+
+/// Routes serving approval links and Slack callbacks.
+pub struct ApprovalEndpoints {
+    service: Arc<OversightService>,
+    email: Arc<EmailChannel>,
+    approvers: Arc<dyn ApproverResolver>,
+    consumed: Arc<dyn ConsumedTokenStore>,
+    slack: Option<Arc<SlackChannel>>,
+    slack_signing_secret: Option<String>,
+}
+
+impl ApprovalEndpoints {
+    /// Serve approval links issued by `email`, deciding through `service`.
+    ///
+    /// Consumed tokens are remembered in memory until
+    /// [`with_consumed_tokens`](Self::with_consumed_tokens) sets a shared
+    /// store.
+    pub fn new(
+        service: Arc<OversightService>,
+        email: Arc<EmailChannel>,
+        approvers: Arc<dyn ApproverResolver>,
+    ) -> Self {
+        Self {
+            service,
+            email,
+            approvers,
+            consumed: Arc::new(InMemoryConsumedTokenStore::new()),
+            slack: None,
+            slack_signing_secret: None,
+        }
+    }
+
+    /// Record consumed tokens in `store`.
+    pub fn with_consumed_tokens(mut self, store: Arc<dyn ConsumedTokenStore>) -> Self {
+        self.consumed = store;
+        self
+    }
+
+    /// Serve `POST /slack/callback` for `channel`'s buttons.
+    pub fn with_slack(mut self, channel: Arc<SlackChannel>) -> Self {
+        self.slack = Some(channel);
+        self
+    }
+
+    /// Require Slack callbacks to carry a valid `X-Slack-Signature` made
+    /// with the app's signing secret.
+    pub fn with_slack_signing_secret(mut self, secret: impl Into<String>) -> Self {
+        self.slack_signing_secret = Some(secret.into());
+        self
+    }
+
+    /// Build the router.
+    pub fn router(self) -> Router {
+        let slack = self.slack.is_some();
+        let router = Router::new()
+            .route("/approval", get(show_approval))
+            .route("/approval/:decision", post(decide_approval));
+        let router = if slack {
+            router.route("/slack/callback", post(slack_callback))
+        } else {
+            router
+        };
+        router.with_state(Arc::new(self))
+    }
+
+    /// Check a token's signature and expiry, and that it is unused.
+    ///
+    /// Returns the token and its signature.
+    async fn verify(&self, token_str: &str) -> Result<(ApprovalToken, String), ApiError> {
+        let token = self.email.verify_approval_token(token_str)?;
+        let signature = ApprovalToken::signature(token_str)?;
+        if self.consumed.is_consumed(&signature).await? {
+            return Err(ApprovalTokenError::Consumed.into());
+        }
+        Ok((token, signature))
+    }
+
+    async fn load(&self, token: &ApprovalToken) -> Result<OversightRequest, ApiError> {
+        let not_found = || CretoError::ApprovalNotFound(token.request_id.clone());
+        let request_id = Uuid::parse_str(&token.request_id).map_err(|_| not_found())?;
+        Ok(self
+            .service
+            .get_request_status(request_id)
+            .await?
+            .ok_or_else(not_found)?)
+    }
+
+    async fn approver(&self, identity: ApproverIdentity) -> Result<UserId, ApiError> {
+        Ok(self.approvers.resolve(&identity).await?.ok_or_else(|| {
+            CretoError::UnauthorizedApprover(format!("{} is not a known approver", identity))
+        })?)
+    }
+
+    async fn submit(
+        &self,
+        request_id: Uuid,
+        reviewer_id: UserId,
+        decision: ApprovalDecision,
+    ) -> CretoResult<DecisionOutcome> {
+        let result = self
+            .service
+            .submit_approval(request_id, reviewer_id, decision, None)
+            .await?;
+        Ok(DecisionOutcome {
+            request_id: result.request_id,
+            decision,
+            status: result.new_status,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    token: String,
+}
+
+async fn show_approval(
+    State(endpoints): State<Arc<ApprovalEndpoints>>,
+    Query(query): Query<TokenQuery>,
+) -> Result<Json<ApprovalDetails>, ApiError> {
+    let (token, _) = endpoints.verify(&query.token).await?;
+    let request = endpoints.load(&token).await?;
+    Ok(Json(ApprovalDetails {
+        request,
+        token_expires_at: token_expiry(&token),
+        approver_email: token.approver_email,
+    }))
+}
+
+async fn decide_approval(
+    State(endpoints): State<Arc<ApprovalEndpoints>>,
+    Path(decision): Path<String>,
+    Query(query): Query<TokenQuery>,
+) -> Result<Json<DecisionOutcome>, ApiError> {
+    let decision = match decision.as_str() {
+        "approve" => ApprovalDecision::Approve,
+        "reject" => ApprovalDecision::Reject,
+        other => {
+            return Err(ApiError::bad_request(format!(
+                "Unknown decision '{}': expected approve or reject",
+                other
+            )))
+        }
+    };
+    let (token, signature) = endpoints.verify(&query.token).await?;
+    let request = endpoints.load(&token).await?;
+    let reviewer_id = endpoints
+        .approver(ApproverIdentity::email(&token.approver_email))
+        .await?;
+
+    // Consumed before deciding, so concurrent uses cannot both decide
+    if !endpoints
+        .consumed
+        .consume(&signature, token_expiry(&token))
+        .await?
+    {
+        return Err(ApprovalTokenError::Consumed.into());
+    }
+    match endpoints.submit(request.id, reviewer_id, decision).await {
+        Ok(outcome) => Ok(Json(outcome)),
+        Err(e) => {
+            if let Err(release) = endpoints.consumed.release(&signature).await {
+                tracing::warn!(
+                    request_id = %request.id,
+                    error = %release,
+                    "Failed to release approval token after a failed decision"
+                );
+            }
+            Err(e.into())
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SlackForm {
+    payload: String,
+}
+
+async fn slack_callback(
+    State(endpoints): State<Arc<ApprovalEndpoints>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<DecisionOutcome>, ApiError> {
+    let slack = endpoints
+        .slack
+        .as_ref()
+        .expect("Slack route is only served with a Slack channel");
+    if let Some(secret) = &endpoints.slack_signing_secret {
+        verify_slack_signature(secret, &headers, &body, Utc::now().timestamp())?;
+    }
+    let form: SlackForm =
+        serde_urlencoded::from_str(&body).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let (request_id, decision, slack_user) = slack
+        .parse_callback(&form.payload)
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let request_id = Uuid::parse_str(&request_id)
+        .map_err(|_| ApiError::bad_request(format!("Invalid request ID: {}", request_id)))?;
+    let reviewer_id = endpoints
+        .approver(ApproverIdentity::Slack(slack_user))
+        .await?;
+    Ok(Json(
+        endpoints
+            .submit(request_id, reviewer_id, decision.into())
+            .await?,
+    ))
+}
+
+/// Check a Slack request signature: `v0=` and the hex HMAC-SHA256 of
+/// `v0:{timestamp}:{body}` keyed by the signing secret.
+fn verify_slack_signature(
+    secret: &str,
+    headers: &HeaderMap,
+    body: &str,
+    now: i64,
+) -> Result<(), ApiError> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let unauthorized = |message: &str| ApiError::from(CretoError::Unauthorized(message.into()));
+    let timestamp = header("x-slack-request-timestamp")
+        .and_then(|ts| ts.parse::<i64>().ok())
+        .ok_or_else(|| unauthorized("Missing Slack request timestamp"))?;
+    if (now - timestamp).abs() > SLACK_MAX_REQUEST_AGE_SECS {
+        return Err(unauthorized("Stale Slack request"));
+    }
+    let signature = header("x-slack-signature")
+        .and_then(|sig| sig.strip_prefix("v0="))
+        .and_then(|sig| hex::decode(sig).ok())
+        .ok_or_else(|| unauthorized("Missing Slack signature"))?;
+
+    let signed = format!("v0:{}:{}", timestamp, body);
+    if !verify_hmac_sha256(secret.as_bytes(), signed.as_bytes(), &signature) {
+        return Err(unauthorized("Invalid Slack signature"));
+    }
+    Ok(())
+}
+
+fn token_expiry(token: &ApprovalToken) -> DateTime<Utc> {
+    DateTime::from_timestamp(token.expires_at, 0).unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// An error response: a status and a JSON `{code, error}` body.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: &'a str,
+    error: &'a str,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        CretoError::ValidationFailed(message.into()).into()
+    }
+}
+
+impl From<CretoError> for ApiError {
+    fn from(error: CretoError) -> Self {
+        let status = match &error {
+            CretoError::ApprovalNotFound(_) | CretoError::NotFound(_) => StatusCode::NOT_FOUND,
+            CretoError::RequestClosed { .. } | CretoError::AssignmentLapsed { .. } => {
+                StatusCode::CONFLICT
+            }
+            CretoError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            CretoError::UnauthorizedApprover(_)
+            | CretoError::NotAuthorized { .. }
+            | CretoError::AuthorizationDenied(_) => StatusCode::FORBIDDEN,
+            CretoError::ValidationFailed(_) | CretoError::InvalidStateTransition { .. } => {
+                StatusCode::BAD_REQUEST
+            }
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self::new(status, error.code(), error.to_string())
+    }
+}
+
+impl From<ApprovalTokenError> for ApiError {
+    fn from(error: ApprovalTokenError) -> Self {
+        let status = match error {
+            ApprovalTokenError::Expired { .. } | ApprovalTokenError::Consumed => StatusCode::GONE,
+            _ => StatusCode::UNAUTHORIZED,
+        };
+        let message = error.to_string();
+        Self::new(status, CretoError::from(error).code(), message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            code: self.code,
+            error: &self.message,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::{EmailConfig, SlackConfig};
+    use crate::repository::InMemoryRequestRepository;
+    use crate::request::ActionType;
+    use axum::body::Body;
+    use axum::http::Request;
+    use creto_common::{hmac_sha256, AgentId, OrganizationId};
+    use tower::ServiceExt;
+
+    const TOKEN_SECRET: &str = "token-secret";
+    const SLACK_SECRET: &str = "slack-secret";
+
+    struct Fixture {
+        router: Router,
+        request: OversightRequest,
+    }
+
+    async fn fixture() -> Fixture {
+        let service = OversightService::new()
+            .with_request_repository(Arc::new(InMemoryRequestRepository::new()));
+        let request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        );
+        service.create_request(&request).await.unwrap();
+
+        let email = EmailChannel::new(EmailConfig {
+            smtp_host: "localhost".to_string(),
+            smtp_port: 25,
+            from_address: "oversight@example.com".to_string(),
+            reply_to: None,
+            dashboard_base_url: "https://approval.example.com".to_string(),
+            token_secret: TOKEN_SECRET.to_string(),
+            previous_token_secrets: Vec::new(),
+        });
+        let slack = SlackChannel::new(SlackConfig {
+            token: "xoxb-test".to_string(),
+            default_channel: "#approvals".to_string(),
+            interactive_buttons: true,
+        });
+        let alice = UserId::new();
+        let approvers = Arc::new(InMemoryApproverResolver::new());
+        approvers.add(ApproverIdentity::email("Alice@Example.com"), alice);
+        approvers.add(ApproverIdentity::Slack("U123".to_string()), alice);
+
+        let router = ApprovalEndpoints::new(Arc::new(service), Arc::new(email), approvers)
+            .with_slack(Arc::new(slack))
+            .with_slack_signing_secret(SLACK_SECRET)
+            .router();
+        Fixture { router, request }
+    }
+
+    fn issue(request: &OversightRequest, email: &str, ttl_seconds: i64) -> String {
+        ApprovalToken::generate(request.id.to_string(), email, ttl_seconds, TOKEN_SECRET)
+    }
+
+    async fn call(router: &Router, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn show(token: &str) -> Request<Body> {
+        Request::get(format!("/approval?token={}", token))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn decide(decision: &str, token: &str) -> Request<Body> {
+        Request::post(format!("/approval/{}?token={}", decision, token))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn slack_request(action_id: &str, timestamp: i64, secret: &str) -> Request<Body> {
+        let payload = serde_json::json!({
+            "type": "block_actions",
+            "user": {"id": "U123"},
+            "actions": [{"action_id": action_id}],
+            "response_url": "https://hooks.slack.com/actions/1",
+        });
+        let body = serde_urlencoded::to_string([("payload", payload.to_string())]).unwrap();
+        let signed = format!("v0:{}:{}", timestamp, body);
+        let signature = hex::encode(hmac_sha256(secret.as_bytes(), signed.as_bytes()));
+        Request::post("/slack/callback")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("x-slack-request-timestamp", timestamp.to_string())
+            .header("x-slack-signature", format!("v0={}", signature))
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_approval_link_decides_once() {
+        let f = fixture().await;
+        let token = issue(&f.request, "alice@example.com", 3600);
+
+        let (status, details) = call(&f.router, show(&token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(details["request"]["id"], f.request.id.to_string());
+        assert_eq!(details["approver_email"], "alice@example.com");
+
+        let (status, outcome) = call(&f.router, decide("approve", &token)).await;
+        assert_eq!(status, StatusCode::OK);
+        let outcome: DecisionOutcome = serde_json::from_value(outcome).unwrap();
+        assert_eq!(outcome.request_id, f.request.id);
+        assert_eq!(outcome.decision, ApprovalDecision::Approve);
+        assert_eq!(outcome.status, RequestStatus::Approved);
+
+        // The token is spent, whichever way it is used again
+        for request in [show(&token), decide("reject", &token)] {
+            let (status, error) = call(&f.router, request).await;
+            assert_eq!(status, StatusCode::GONE);
+            assert!(error["error"]
+                .as_str()
+                .unwrap()
+                .contains("already been used"));
+        }
+
+        // A fresh token reaches the service, which refuses the closed request
+        let fresh = issue(&f.request, "alice@example.com", 7200);
+        let (status, error) = call(&f.router, decide("reject", &fresh)).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(
+            error["code"],
+            CretoError::RequestClosed {
+                request_id: String::new(),
+                status: String::new(),
+            }
+            .code()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_expired_and_forged_tokens_are_refused() {
+        let f = fixture().await;
+        let expired = issue(&f.request, "alice@example.com", -60);
+        for request in [show(&expired), decide("approve", &expired)] {
+            let (status, error) = call(&f.router, request).await;
+            assert_eq!(status, StatusCode::GONE);
+            assert!(error["error"].as_str().unwrap().contains("expired"));
+        }
+
+        let forged = ApprovalToken::generate(
+            f.request.id.to_string(),
+            "alice@example.com",
+            3600,
+            "wrong-secret",
+        );
+        let (status, _) = call(&f.router, decide("approve", &forged)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let missing = ApprovalToken::generate(
+            Uuid::now_v7().to_string(),
+            "alice@example.com",
+            3600,
+            TOKEN_SECRET,
+        );
+        let (status, _) = call(&f.router, show(&missing)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_refused_decisions_leave_the_token_usable() {
+        let f = fixture().await;
+        let token = issue(&f.request, "alice@example.com", 3600);
+
+        let (status, error) = call(&f.router, decide("maybe", &token)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(error["error"]
+            .as_str()
+            .unwrap()
+            .contains("approve or reject"));
+
+        let stranger = issue(&f.request, "mallory@example.com", 3600);
+        let (status, _) = call(&f.router, decide("approve", &stranger)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        let (status, outcome) = call(&f.router, decide("reject", &token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(outcome["decision"], "reject");
+    }
+
+    #[tokio::test]
+    async fn test_slack_callback_submits_decision() {
+        let f = fixture().await;
+        let now = Utc::now().timestamp();
+        let approve = format!("approve_{}", f.request.id);
+
+        let (status, _) = call(&f.router, slack_request(&approve, now, "wrong-secret")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call(&f.router, slack_request(&approve, now - 3600, SLACK_SECRET)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let unknown = format!("maybe_{}", f.request.id);
+        let (status, _) = call(&f.router, slack_request(&unknown, now, SLACK_SECRET)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, outcome) = call(&f.router, slack_request(&approve, now, SLACK_SECRET)).await;
+        assert_eq!(status, StatusCode::OK);
+        let outcome: DecisionOutcome = serde_json::from_value(outcome).unwrap();
+        assert_eq!(outcome.status, RequestStatus::Approved);
+    }
+}
@@ -33,30 +33,128 @@
 //! Inspired by [HumanLayer](https://github.com/humanlayer/humanlayer) patterns,
 //! rebuilt with Creto Sovereign primitives (NHI, Cedar authorization, audit logging).
 
+pub mod aging;
 pub mod approval;
+pub mod assignments;
 pub mod channels;
 pub mod checkpoint;
+pub mod clock;
 pub mod context;
+pub mod directory;
+pub mod enrichment;
+pub mod escalation;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod lifecycle;
 pub mod metering;
+pub mod notifications;
 pub mod policy;
+pub mod reminders;
 pub mod repository;
 pub mod request;
+pub mod review;
+pub mod routing;
 pub mod service;
+pub mod snapshot;
 pub mod state;
+pub mod template;
+pub mod timeouts;
 pub mod triggers;
+pub mod webhooks;
+pub mod weights;
 
-pub use approval::{Approval, ApprovalDecision, QuorumCalculator, QuorumConfig, QuorumResult};
-pub use checkpoint::{Checkpoint, CheckpointManager, CheckpointRepository, CHECKPOINT_VERSION};
+pub use aging::{AgingBoost, AgingPolicy, AgingThreshold, PriorityBoost, AGING_REASON};
+pub use approval::{
+    Approval, ApprovalDecision, QuorumCalculator, QuorumConfig, QuorumResult, WeightedDecision,
+};
+pub use assignments::{
+    AssignmentLapse, AssignmentSource, AssignmentStatus, AssignmentStore, InMemoryAssignmentStore,
+    LapseMode, ReviewerAssignment, ReviewerDeadlineConfig, ReviewerDeadlinePolicy, LAPSE_REASON,
+};
+pub use channels::{ConsumedTokenStore, InMemoryConsumedTokenStore};
+pub use checkpoint::{
+    Checkpoint, CheckpointManager, CheckpointRepository, InMemoryCheckpointRepository,
+    CHECKPOINT_VERSION,
+};
+pub use clock::{Clock, SystemClock, TestClock};
+#[cfg(feature = "scim")]
+pub use directory::ScimDirectorySource;
+pub use directory::{
+    spawn_directory_sync, DirectoryGroup, DirectorySource, GroupChange, GroupChangeKind,
+    GroupMembership, InMemoryReviewerGroupStore, MockDirectorySource, ReviewerDirectorySync,
+    ReviewerGroup, ReviewerGroupStatus, ReviewerGroupStore, SyncReport,
+};
+pub use enrichment::{
+    split_sections, ContextEnricher, ContextSection, EnrichmentInput, ExecutionDetailsEnricher,
+    ExecutionReference, QuotaSnapshot, QuotaSnapshotEnricher, QuotaStatusProvider,
+    RequestHistoryEnricher, SectionField, SectionStatus,
+};
+pub use escalation::{
+    Escalation, EscalationChain, EscalationChainStore, EscalationEngine, EscalationOutcome,
+    EscalationStep, EscalationTier, EscalationTrigger, ExhaustedAction,
+    InMemoryEscalationChainStore, ESCALATION_REASON, EXHAUSTED_REASON,
+};
+#[cfg(feature = "http")]
+pub use http::{
+    ApprovalDetails, ApprovalEndpoints, ApproverIdentity, ApproverResolver, DecisionOutcome,
+    InMemoryApproverResolver,
+};
+pub use lifecycle::{
+    parse_json_lines, verify_chain, ActivityKind, ActivityLog, InMemoryActivityLog, LifecycleActor,
+    LifecycleDocument, LifecycleEntry, LifecycleEventType, LifecycleExport, LifecycleExportFormat,
+    LifecycleRedactor, RequestActivity, ReviewerPiiRedactor, GENESIS_HASH,
+};
+pub use notifications::{
+    DeferralReason, DeliverySchedule, DigestConfig, InMemoryNotificationStore,
+    NotificationPreferenceStore, NotificationPreferences, NotificationRouter, PendingDelivery,
+    PendingDeliveryStore, QuietHours, RoutingReport, SentNotification, WithdrawalReport,
+};
 pub use policy::{PolicyContext, PolicyDecision, TrustLevel};
+pub use reminders::{
+    ReminderCadence, ReminderPolicy, ReminderReport, ReminderScheduler, SentReminder,
+};
 pub use repository::{
-    ApprovalCounts, ApprovalRepository, PgApprovalRepository, PgCheckpointRepository,
-    PgQuorumConfigRepository, PgRequestRepository, PgStateTransitionRepository, QuorumConfigRecord,
-    QuorumConfigRepository, RequestRepository, StateTransitionRecord, StateTransitionRepository,
+    ApprovalCounts, ApprovalRepository, InMemoryApprovalRepository, InMemoryRequestRepository,
+    InMemoryStateTransitionRepository, PgActivityLogRepository, PgApprovalRepository,
+    PgAssignmentRepository, PgCheckpointRepository, PgConsumedTokenRepository,
+    PgEscalationChainRepository, PgNotificationPreferenceRepository, PgQuorumConfigRepository,
+    PgRequestRepository, PgRequestTemplateRepository, PgReviewerGroupRepository,
+    PgReviewerWeightRepository, PgStateTransitionRepository, PgWebhookRepository,
+    QuorumConfigRecord, QuorumConfigRepository, RequestRepository, StateTransitionRecord,
+    StateTransitionRepository,
 };
 pub use request::{ActionType, OversightRequest, Priority, RequestStatus};
-pub use service::OversightService;
-pub use state::{StateMachine, StateTransition};
+pub use review::{
+    CheckpointSummary, InMemoryReviewerDirectory, ReviewBundle, ReviewerDecision,
+    ReviewerDirectory, ReviewerProfile,
+};
+pub use routing::{
+    ChannelDelivery, ChannelRequirement, ChannelRetryPolicy, ChannelRouter, ChannelRule,
+    ChannelTarget, FanOutReport, RoutePlan,
+};
+pub use service::{CancellationResult, OrgAdminCheck, OversightMetrics, OversightService};
+pub use snapshot::{
+    OversightSnapshot, OversightSnapshotContributor, PendingRequestSummary, OVERSIGHT_SECTION,
+};
+pub use state::{Actor, StateMachine, StateTransition};
+pub use template::{
+    AppliedTemplate, FieldType, InMemoryRequestTemplateStore, InvalidField, RenderedField,
+    RequestTemplate, RequestTemplateStore, TemplateError, TemplateField,
+};
+pub use timeouts::{expire_timed_out_requests, spawn_timeout_worker};
 pub use triggers::{
     ActionTypePattern, MockCedarClient, PolicyEvaluator, PolicyTriggerConfig, TriggerCondition,
-    TriggerMatch, TrustLevelThreshold,
+    TriggerMatch, TrustLevelThreshold, USAGE_ANOMALY_ATTRIBUTE,
+};
+#[cfg(feature = "channels")]
+pub use webhooks::HttpWebhookTransport;
+pub use webhooks::{
+    sign_payload, verify_signature, ApprovalsSummary, CircuitBreakerConfig, CircuitState,
+    DecisionPayload, DeliveryAttempt, DeliveryState, InMemoryWebhookDeliveryLog,
+    InMemoryWebhookEndpointStore, WebhookDelivery, WebhookDeliveryLog, WebhookDispatcher,
+    WebhookEndpoint, WebhookEndpointStore, WebhookFilter, WebhookRetryPolicy, WebhookTransport,
+};
+pub use weights::{
+    resolve_weight, InMemoryReviewerWeightRepository, ReviewerWeight, ReviewerWeightRepository,
+    WeightSubject, DEFAULT_REVIEWER_WEIGHT,
 };
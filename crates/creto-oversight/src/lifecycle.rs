@@ -0,0 +1,616 @@
+//! Chronological audit export of a request's lifecycle.
+//!
+//! [`OversightService::lifecycle_export`](crate::service::OversightService::lifecycle_export)
+//! merges everything recorded about one request — its creation, state
+//! transitions, reviewer decisions, checkpoints and the [`RequestActivity`]
+//! log of notifications, reminders, views, comments and executions — into a
+//! single list of [`LifecycleEntry`]s ordered by time. Entries with the same
+//! timestamp are ordered by event type, in lifecycle order, and then by the
+//! ID of the record they came from, so the same records always export the
+//! same way.
+//!
+//! Each entry carries the SHA-256 hash of the previous entry and of itself,
+//! so removing, reordering or editing an entry breaks the chain
+//! ([`verify_chain`]). The signed JSON form adds an HMAC-SHA256 signature
+//! over the last hash.
+//!
+//! [`LifecycleRedactor`]s run before the chain is computed, so an export
+//! with reviewer identities removed for external sharing still verifies.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{hmac_sha256, verify_hmac_sha256, CretoError, CretoResult, UserId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::approval::Approval;
+use crate::channels::ChannelType;
+use crate::checkpoint::Checkpoint;
+use crate::repository::StateTransitionRecord;
+use crate::request::OversightRequest;
+use crate::state::Actor;
+
+/// `previous_hash` of the first entry in an export.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Something that happened to a request outside its state machine.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActivityKind {
+    /// Reviewers were told about the request.
+    Notified {
+        channel: ChannelType,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reviewer_id: Option<UserId>,
+        /// Whether the request was part of a digest.
+        #[serde(default)]
+        digest: bool,
+    },
+    /// Reviewers were reminded of the pending request.
+    Reminded {
+        channel: ChannelType,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reviewer_id: Option<UserId>,
+    },
+    /// A reviewer opened the request.
+    Viewed,
+    /// A reviewer commented on the request.
+    Commented { text: String },
+    /// The approved action was carried out.
+    Executed {
+        succeeded: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        detail: Option<String>,
+    },
+}
+
+/// A recorded activity on a request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestActivity {
+    /// Activity ID.
+    pub id: Uuid,
+
+    /// Request the activity concerns.
+    pub request_id: Uuid,
+
+    /// Who performed it.
+    pub actor: Actor,
+
+    /// What happened.
+    pub kind: ActivityKind,
+
+    /// When it happened.
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl RequestActivity {
+    /// Record an activity happening now.
+    pub fn new(request_id: Uuid, actor: Actor, kind: ActivityKind) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            request_id,
+            actor,
+            kind,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    /// Set when the activity happened.
+    pub fn at(mut self, occurred_at: DateTime<Utc>) -> Self {
+        self.occurred_at = occurred_at;
+        self
+    }
+}
+
+/// Append-only log of request activities.
+#[async_trait]
+pub trait ActivityLog: Send + Sync {
+    /// Record an activity.
+    async fn record(&self, activity: &RequestActivity) -> CretoResult<()>;
+
+    /// List a request's activities, oldest first.
+    async fn list_by_request(&self, request_id: Uuid) -> CretoResult<Vec<RequestActivity>>;
+}
+
+/// In-memory activity log for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryActivityLog {
+    activities: RwLock<Vec<RequestActivity>>,
+}
+
+impl InMemoryActivityLog {
+    /// Create an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ActivityLog for InMemoryActivityLog {
+    async fn record(&self, activity: &RequestActivity) -> CretoResult<()> {
+        self.activities.write().unwrap().push(activity.clone());
+        Ok(())
+    }
+
+    async fn list_by_request(&self, request_id: Uuid) -> CretoResult<Vec<RequestActivity>> {
+        let mut activities: Vec<RequestActivity> = self
+            .activities
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|a| a.request_id == request_id)
+            .cloned()
+            .collect();
+        activities.sort_by_key(|a| a.occurred_at);
+        Ok(activities)
+    }
+}
+
+/// Kind of lifecycle entry, in the order entries sharing a timestamp appear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleEventType {
+    /// The request was raised.
+    Created,
+    /// Reviewers were notified.
+    Notified,
+    /// Reviewers were reminded.
+    Reminded,
+    /// A reviewer opened the request.
+    Viewed,
+    /// A reviewer commented.
+    Commented,
+    /// A reviewer recorded a decision.
+    Decided,
+    /// The request changed status.
+    Transitioned,
+    /// A checkpoint was taken.
+    Checkpointed,
+    /// The approved action was carried out.
+    Executed,
+}
+
+/// Who caused a lifecycle entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifecycleActor {
+    /// `system`, `user`, `policy` or `agent`.
+    pub kind: String,
+
+    /// User or agent ID, policy ID, or a pseudonym after redaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+impl LifecycleActor {
+    fn system() -> Self {
+        Self {
+            kind: "system".to_string(),
+            id: None,
+        }
+    }
+}
+
+impl From<&Actor> for LifecycleActor {
+    fn from(actor: &Actor) -> Self {
+        let id = match actor {
+            Actor::Policy { policy_id } => Some(policy_id.clone()),
+            other => other.id().map(|id| id.to_string()),
+        };
+        Self {
+            kind: actor.kind().to_string(),
+            id,
+        }
+    }
+}
+
+/// One entry in a lifecycle export.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifecycleEntry {
+    /// Position in the export, from zero.
+    pub sequence: u64,
+
+    /// When the event happened.
+    pub timestamp: DateTime<Utc>,
+
+    /// Who caused it.
+    pub actor: LifecycleActor,
+
+    /// What happened.
+    pub event_type: LifecycleEventType,
+
+    /// ID of the record the entry was built from.
+    pub source_id: Uuid,
+
+    /// Event-specific details.
+    pub detail: serde_json::Value,
+
+    /// Hash of the previous entry, or [`GENESIS_HASH`].
+    pub previous_hash: String,
+
+    /// Hex SHA-256 over `previous_hash` and the entry's other fields.
+    pub hash: String,
+}
+
+impl LifecycleEntry {
+    fn new(
+        timestamp: DateTime<Utc>,
+        actor: LifecycleActor,
+        event_type: LifecycleEventType,
+        source_id: Uuid,
+        detail: serde_json::Value,
+    ) -> Self {
+        Self {
+            sequence: 0,
+            timestamp,
+            actor,
+            event_type,
+            source_id,
+            detail,
+            previous_hash: String::new(),
+            hash: String::new(),
+        }
+    }
+
+    /// Hash the entry as it stands, chained to `previous_hash`.
+    pub fn compute_hash(&self) -> CretoResult<String> {
+        #[derive(Serialize)]
+        struct Hashed<'a> {
+            sequence: u64,
+            timestamp: &'a DateTime<Utc>,
+            actor: &'a LifecycleActor,
+            event_type: LifecycleEventType,
+            source_id: &'a Uuid,
+            detail: &'a serde_json::Value,
+        }
+
+        let body = serde_json::to_vec(&Hashed {
+            sequence: self.sequence,
+            timestamp: &self.timestamp,
+            actor: &self.actor,
+            event_type: self.event_type,
+            source_id: &self.source_id,
+            detail: &self.detail,
+        })
+        .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.previous_hash.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(&body);
+        Ok(hex::encode(hasher.finalize()))
+    }
+}
+
+/// Edits entries before they are chained, for example to remove personal
+/// data before an export leaves the organization.
+///
+/// Redactors may rewrite actors and details or drop entries outright.
+pub trait LifecycleRedactor: Send + Sync {
+    /// Redact entries in place. They are in export order.
+    fn redact(&self, entries: &mut Vec<LifecycleEntry>);
+}
+
+/// Replaces reviewer identities with pseudonyms.
+///
+/// Each user becomes `reviewer-1`, `reviewer-2`, … in order of first
+/// appearance, so an auditor can still tell reviewers apart. Comment text
+/// is kept unless [`with_comments_removed`](Self::with_comments_removed)
+/// is set.
+#[derive(Debug, Clone, Default)]
+pub struct ReviewerPiiRedactor {
+    remove_comments: bool,
+}
+
+impl ReviewerPiiRedactor {
+    /// Pseudonymize reviewers, keeping comment text.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also remove the text of comments and decision reasons.
+    pub fn with_comments_removed(mut self) -> Self {
+        self.remove_comments = true;
+        self
+    }
+}
+
+impl LifecycleRedactor for ReviewerPiiRedactor {
+    fn redact(&self, entries: &mut Vec<LifecycleEntry>) {
+        let mut pseudonyms: HashMap<String, String> = HashMap::new();
+        let mut pseudonym = |id: &str| {
+            let next = pseudonyms.len() + 1;
+            pseudonyms
+                .entry(id.to_string())
+                .or_insert_with(|| format!("reviewer-{next}"))
+                .clone()
+        };
+
+        for entry in entries.iter_mut() {
+            if entry.actor.kind == "user" {
+                entry.actor.id = entry.actor.id.as_deref().map(&mut pseudonym);
+            }
+            let Some(detail) = entry.detail.as_object_mut() else {
+                continue;
+            };
+            if let Some(reviewer) = detail.get("reviewer_id").and_then(|v| v.as_str()) {
+                let replaced = pseudonym(reviewer);
+                detail.insert("reviewer_id".to_string(), replaced.into());
+            }
+            if self.remove_comments {
+                for field in ["text", "reason"] {
+                    if detail.contains_key(field) {
+                        detail.insert(field.to_string(), "[redacted]".into());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Output format of a lifecycle export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleExportFormat {
+    /// One JSON entry per line.
+    JsonLines,
+    /// A single [`LifecycleDocument`] signed with the service's export key.
+    SignedJson,
+}
+
+/// A signed lifecycle export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LifecycleDocument {
+    /// The request exported.
+    pub request_id: Uuid,
+
+    /// When the export was produced.
+    pub generated_at: DateTime<Utc>,
+
+    /// Entries in order.
+    pub entries: Vec<LifecycleEntry>,
+
+    /// Hash of the last entry, or [`GENESIS_HASH`] if there are none.
+    pub head_hash: String,
+
+    /// Hex HMAC-SHA256 of `head_hash` under the export key.
+    pub signature: String,
+}
+
+impl LifecycleDocument {
+    /// Check the chain and the signature.
+    pub fn verify(&self, key: &str) -> bool {
+        let head = self
+            .entries
+            .last()
+            .map_or(GENESIS_HASH, |entry| entry.hash.as_str());
+        if head != self.head_hash || !verify_chain(&self.entries) {
+            return false;
+        }
+        let Ok(expected) = hex::decode(&self.signature) else {
+            return false;
+        };
+        verify_hmac_sha256(key.as_bytes(), self.head_hash.as_bytes(), &expected)
+    }
+}
+
+/// A rendered lifecycle export.
+#[derive(Debug, Clone)]
+pub struct LifecycleExport {
+    /// Format of `body`.
+    pub format: LifecycleExportFormat,
+
+    /// The entries, as rendered.
+    pub entries: Vec<LifecycleEntry>,
+
+    /// Hash of the last entry, or [`GENESIS_HASH`] if there are none.
+    pub head_hash: String,
+
+    /// The export as handed to auditors.
+    pub body: String,
+}
+
+/// Whether every entry is numbered in order and chained to its predecessor.
+pub fn verify_chain(entries: &[LifecycleEntry]) -> bool {
+    let mut previous = GENESIS_HASH;
+    for (index, entry) in entries.iter().enumerate() {
+        if entry.sequence != index as u64 || entry.previous_hash != previous {
+            return false;
+        }
+        match entry.compute_hash() {
+            Ok(hash) if hash == entry.hash => {}
+            _ => return false,
+        }
+        previous = &entry.hash;
+    }
+    true
+}
+
+/// Parse a JSON Lines export back into entries.
+pub fn parse_json_lines(body: &str) -> CretoResult<Vec<LifecycleEntry>> {
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|e| CretoError::SerializationError(e.to_string()))
+        })
+        .collect()
+}
+
+/// Records a lifecycle export is built from.
+#[derive(Debug, Default)]
+pub(crate) struct LifecycleSources {
+    pub transitions: Vec<StateTransitionRecord>,
+    pub approvals: Vec<Approval>,
+    pub checkpoints: Vec<Checkpoint>,
+    pub activities: Vec<RequestActivity>,
+}
+
+/// Merge a request's records into ordered, unchained entries.
+pub(crate) fn merge_entries(
+    request: &OversightRequest,
+    sources: LifecycleSources,
+) -> CretoResult<Vec<LifecycleEntry>> {
+    let mut entries = Vec::new();
+    entries.push(LifecycleEntry::new(
+        request.created_at,
+        LifecycleActor::from(&Actor::Agent {
+            agent_id: request.agent_id,
+        }),
+        LifecycleEventType::Created,
+        request.id,
+        serde_json::json!({
+            "action_type": request.action_type,
+            "description": request.description,
+            "priority": request.priority,
+            "timeout_seconds": request.timeout_seconds,
+        }),
+    ));
+
+    for record in sources.transitions {
+        entries.push(LifecycleEntry::new(
+            record.transitioned_at,
+            LifecycleActor {
+                kind: record.actor_type.clone(),
+                id: record.actor_id.map(|id| id.to_string()),
+            },
+            LifecycleEventType::Transitioned,
+            record.id,
+            serde_json::json!({
+                "from": record.from_status,
+                "to": record.to_status,
+                "reason": record.reason,
+            }),
+        ));
+    }
+
+    for approval in sources.approvals {
+        entries.push(LifecycleEntry::new(
+            approval.decided_at,
+            LifecycleActor::from(&Actor::User {
+                user_id: approval.reviewer_id,
+            }),
+            LifecycleEventType::Decided,
+            approval.id,
+            serde_json::json!({
+                "decision": approval.decision,
+                "reason": approval.reason,
+                "weight": approval.weight,
+            }),
+        ));
+    }
+
+    for checkpoint in sources.checkpoints {
+        entries.push(LifecycleEntry::new(
+            checkpoint.timestamp,
+            LifecycleActor::system(),
+            LifecycleEventType::Checkpointed,
+            checkpoint.id,
+            serde_json::json!({
+                "status": checkpoint.status,
+                "reason": checkpoint.reason,
+            }),
+        ));
+    }
+
+    for activity in sources.activities {
+        let event_type = match &activity.kind {
+            ActivityKind::Notified { .. } => LifecycleEventType::Notified,
+            ActivityKind::Reminded { .. } => LifecycleEventType::Reminded,
+            ActivityKind::Viewed => LifecycleEventType::Viewed,
+            ActivityKind::Commented { .. } => LifecycleEventType::Commented,
+            ActivityKind::Executed { .. } => LifecycleEventType::Executed,
+        };
+        let mut detail = serde_json::to_value(&activity.kind)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        if let Some(fields) = detail.as_object_mut() {
+            fields.remove("type");
+        }
+        entries.push(LifecycleEntry::new(
+            activity.occurred_at,
+            LifecycleActor::from(&activity.actor),
+            event_type,
+            activity.id,
+            detail,
+        ));
+    }
+
+    entries.sort_by(|a, b| {
+        (a.timestamp, a.event_type, a.source_id).cmp(&(b.timestamp, b.event_type, b.source_id))
+    });
+    Ok(entries)
+}
+
+/// Number and chain entries, returning the head hash.
+pub(crate) fn chain_entries(entries: &mut [LifecycleEntry]) -> CretoResult<String> {
+    let mut previous = GENESIS_HASH.to_string();
+    for (index, entry) in entries.iter_mut().enumerate() {
+        entry.sequence = index as u64;
+        entry.previous_hash = previous;
+        entry.hash = entry.compute_hash()?;
+        previous = entry.hash.clone();
+    }
+    Ok(previous)
+}
+
+/// Sign a head hash with the export key.
+pub(crate) fn sign_head(key: &str, head_hash: &str) -> String {
+    hex::encode(hmac_sha256(key.as_bytes(), head_hash.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        actor: Actor,
+        event_type: LifecycleEventType,
+        detail: serde_json::Value,
+    ) -> LifecycleEntry {
+        LifecycleEntry::new(
+            Utc::now(),
+            LifecycleActor::from(&actor),
+            event_type,
+            Uuid::now_v7(),
+            detail,
+        )
+    }
+
+    #[test]
+    fn test_redactor_pseudonymizes_reviewers_consistently() {
+        let (alice, bob) = (UserId::new(), UserId::new());
+        let mut entries = vec![
+            entry(
+                Actor::System,
+                LifecycleEventType::Notified,
+                serde_json::json!({ "channel": "email", "reviewer_id": bob }),
+            ),
+            entry(
+                Actor::User { user_id: alice },
+                LifecycleEventType::Commented,
+                serde_json::json!({ "text": "Call me on 555-0100" }),
+            ),
+            entry(
+                Actor::User { user_id: bob },
+                LifecycleEventType::Decided,
+                serde_json::json!({ "decision": "approve", "reason": "ok" }),
+            ),
+        ];
+
+        ReviewerPiiRedactor::new()
+            .with_comments_removed()
+            .redact(&mut entries);
+
+        assert_eq!(entries[0].detail["reviewer_id"], "reviewer-1");
+        assert_eq!(entries[1].actor.id.as_deref(), Some("reviewer-2"));
+        assert_eq!(entries[1].detail["text"], "[redacted]");
+        assert_eq!(entries[2].actor.id.as_deref(), Some("reviewer-1"));
+        assert_eq!(entries[2].detail["reason"], "[redacted]");
+
+        let head = chain_entries(&mut entries).unwrap();
+        assert!(verify_chain(&entries));
+        assert_eq!(head, entries[2].hash);
+    }
+}
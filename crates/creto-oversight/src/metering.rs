@@ -1,22 +1,36 @@
 //! Metering integration for usage tracking.
 //!
 //! This module provides integration with creto-metering to emit usage events
-//! when oversight requests are created and resolved.
+//! when oversight requests are created and resolved, and to escalate usage
+//! anomaly alerts into oversight requests.
 
 #[cfg(feature = "metering")]
-use creto_common::{AgentId, OrganizationId};
+use creto_common::{AgentId, Correlation, OrganizationId};
 #[cfg(feature = "metering")]
-use creto_metering::{UsageEvent, UsageEventType};
+use creto_metering::{AnomalyAlert, AnomalySeverity, UsageEvent, UsageEventType};
 #[cfg(feature = "metering")]
 use uuid::Uuid;
 
+#[cfg(feature = "metering")]
+use crate::{
+    enrichment::EnrichmentInput,
+    policy::PolicyContext,
+    request::{ActionType, OversightRequest},
+    service::OversightService,
+    triggers::USAGE_ANOMALY_ATTRIBUTE,
+};
+
 /// Create a usage event for an oversight request creation.
+///
+/// Pass `request.correlation().derive(request.id)` as `correlation` so the
+/// event inherits the request's trace.
 #[cfg(feature = "metering")]
 pub fn oversight_request_event(
     org_id: OrganizationId,
     agent_id: AgentId,
     request_id: Uuid,
     delegation_depth: u8,
+    correlation: Correlation,
 ) -> UsageEvent {
     let mut properties = serde_json::Map::new();
     properties.insert(
@@ -35,6 +49,105 @@ pub fn oversight_request_event(
         timestamp: chrono::Utc::now(),
         properties: serde_json::Value::Object(properties),
         delegation_depth,
+        correlation_id: correlation.correlation_id,
+        caused_by: correlation.caused_by,
+    }
+}
+
+/// Turns usage anomaly alerts into oversight requests.
+///
+/// Alerts at or above the minimum severity are evaluated through the
+/// service's policy triggers with the alert under
+/// [`USAGE_ANOMALY_ATTRIBUTE`] in the context attributes, so a
+/// [`TriggerCondition::UsageAnomaly`](crate::triggers::TriggerCondition::UsageAnomaly)
+/// decides whether oversight is required. The detector already suppresses
+/// repeat alerts for a sustained spike, so each escalated alert is a distinct
+/// episode.
+#[cfg(feature = "metering")]
+#[derive(Debug, Clone)]
+pub struct AnomalyEscalator {
+    min_severity: AnomalySeverity,
+}
+
+#[cfg(feature = "metering")]
+impl AnomalyEscalator {
+    /// Escalate high severity alerts only.
+    pub fn new() -> Self {
+        Self {
+            min_severity: AnomalySeverity::High,
+        }
+    }
+
+    /// Set the lowest severity that is escalated.
+    pub fn with_min_severity(mut self, severity: AnomalySeverity) -> Self {
+        self.min_severity = severity;
+        self
+    }
+
+    /// Action the escalated request is for.
+    pub fn action(alert: &AnomalyAlert) -> ActionType {
+        ActionType::Custom {
+            type_id: format!("usage_anomaly:{}", alert.metric_code),
+        }
+    }
+
+    /// Policy context carrying the alert for trigger evaluation.
+    pub fn policy_context(alert: &AnomalyAlert) -> PolicyContext {
+        let mut attributes = serde_json::Map::new();
+        attributes.insert(
+            USAGE_ANOMALY_ATTRIBUTE.to_string(),
+            serde_json::to_value(alert).unwrap_or(serde_json::Value::Null),
+        );
+        PolicyContext {
+            attributes: serde_json::Value::Object(attributes),
+            ..Default::default()
+        }
+    }
+
+    /// Build and enrich the oversight request for an alert.
+    ///
+    /// Returns None if the alert is below the minimum severity or no
+    /// configured trigger matched. The caller persists and routes the request.
+    pub async fn escalate(
+        &self,
+        service: &OversightService,
+        alert: &AnomalyAlert,
+    ) -> Option<OversightRequest> {
+        if alert.severity < self.min_severity {
+            return None;
+        }
+        let mut request = service.build_trigger_request(
+            alert.organization_id,
+            alert.agent_id,
+            Self::action(alert),
+            &Self::policy_context(alert),
+        )?;
+        request.context = serde_json::json!({
+            "usage_anomaly": {
+                "alert_id": alert.id,
+                "metric_code": alert.metric_code,
+                "severity": alert.severity,
+                "window_start": alert.window_start,
+                "window_seconds": alert.window_seconds,
+                "window_quantity": alert.window_quantity,
+                "baseline_mean": alert.baseline_mean,
+                "baseline_stddev": alert.baseline_stddev,
+                "multiple": alert.multiple,
+                "z_score": alert.z_score,
+                "detected_at": alert.detected_at,
+            }
+        });
+        service
+            .enrich_request(&mut request, &EnrichmentInput::default())
+            .await;
+        Some(request)
+    }
+}
+
+#[cfg(feature = "metering")]
+impl Default for AnomalyEscalator {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -85,11 +198,59 @@ mod tests {
         let org_id = OrganizationId::new();
         let agent_id = AgentId::new();
         let request_id = Uuid::now_v7();
+        let correlation = Correlation::new(Uuid::now_v7()).derive(request_id);
 
-        let event = oversight_request_event(org_id, agent_id, request_id, 1);
+        let event = oversight_request_event(org_id, agent_id, request_id, 1, correlation);
 
         assert_eq!(event.event_type, UsageEventType::OversightRequest);
         assert_eq!(event.code, "oversight_request");
         assert_eq!(event.quantity, 1);
+        assert_eq!(event.correlation(), correlation);
+    }
+
+    #[cfg(feature = "metering")]
+    #[tokio::test]
+    async fn test_high_severity_anomaly_escalates_through_trigger() {
+        use crate::triggers::{PolicyTriggerConfig, TriggerCondition};
+        use chrono::Utc;
+
+        let service = OversightService::new().with_triggers(
+            PolicyTriggerConfig::new()
+                .with_condition(TriggerCondition::UsageAnomaly { min_multiple: 10.0 }),
+        );
+        let alert = |severity, multiple| AnomalyAlert {
+            id: Uuid::now_v7(),
+            organization_id: OrganizationId::new(),
+            agent_id: AgentId::new(),
+            metric_code: "llm_tokens".to_string(),
+            severity,
+            window_start: Utc::now(),
+            window_seconds: 60,
+            window_quantity: 2000,
+            baseline_mean: 100.0,
+            baseline_stddev: 5.0,
+            multiple,
+            z_score: Some(380.0),
+            transaction_id: "tx_spike".to_string(),
+            detected_at: Utc::now(),
+        };
+        let escalator = AnomalyEscalator::new();
+
+        let high = alert(AnomalySeverity::High, 20.0);
+        let request = escalator.escalate(&service, &high).await.unwrap();
+        assert_eq!(request.organization_id, high.organization_id);
+        assert_eq!(request.priority, crate::request::Priority::High);
+        assert_eq!(request.context["usage_anomaly"]["multiple"], 20.0);
+        assert_eq!(
+            request.context["usage_anomaly"]["metric_code"],
+            "llm_tokens"
+        );
+
+        // Elevated alerts are not escalated by default
+        let elevated = alert(AnomalySeverity::Elevated, 6.0);
+        assert!(escalator.escalate(&service, &elevated).await.is_none());
+        // ...and below the trigger's multiple nothing matches even if allowed
+        let escalator = escalator.with_min_severity(AnomalySeverity::Elevated);
+        assert!(escalator.escalate(&service, &elevated).await.is_none());
     }
 }
@@ -0,0 +1,1059 @@
+//! Reviewer notification preferences and delivery scheduling.
+//!
+//! Each reviewer can set [`NotificationPreferences`]: which channels to use
+//! per priority, quiet hours during which only critical requests notify
+//! immediately, and a digest mode that batches normal and low priority
+//! notifications. Organizations can set defaults with the same shape, used
+//! for reviewers without preferences and for requests with no assigned
+//! reviewers.
+//!
+//! The [`NotificationRouter`] applies the preferences. Notifications that
+//! cannot go out yet are stored as [`PendingDelivery`] records, so they
+//! survive a restart, and are sent by [`NotificationRouter::deliver_due`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use creto_common::{CretoResult, OrganizationId, UserId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::channels::{ChannelType, NotificationChannel, NotificationResult};
+use crate::clock::{Clock, SystemClock};
+use crate::request::{OversightRequest, Priority};
+
+/// Daily window during which non-critical notifications are held.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// Local time quiet hours begin.
+    pub start: NaiveTime,
+    /// Local time quiet hours end; may be earlier than `start` to span midnight.
+    pub end: NaiveTime,
+    /// Reviewer's IANA time zone, such as `America/New_York`.
+    ///
+    /// Local times follow the zone's daylight saving rules.
+    #[serde(default = "default_timezone")]
+    pub timezone: Tz,
+}
+
+fn default_timezone() -> Tz {
+    Tz::UTC
+}
+
+impl QuietHours {
+    /// Create quiet hours in UTC.
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self {
+            start,
+            end,
+            timezone: Tz::UTC,
+        }
+    }
+
+    /// Set the reviewer's time zone.
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Check whether `at` falls within quiet hours.
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let local = at.with_timezone(&self.timezone).time();
+        if self.start <= self.end {
+            local >= self.start && local < self.end
+        } else {
+            local >= self.start || local < self.end
+        }
+    }
+
+    /// First end of quiet hours after `at`.
+    ///
+    /// An end time skipped by a daylight saving change falls at the first
+    /// local time after the gap; a repeated one at its first occurrence.
+    pub fn next_end(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let date = at.with_timezone(&self.timezone).date_naive();
+        let end = self.resolve(date.and_time(self.end));
+        if end > at {
+            return end;
+        }
+        date.succ_opt()
+            .map_or(end, |next| self.resolve(next.and_time(self.end)))
+    }
+
+    /// Map a local time to UTC, moving times in a daylight saving gap past it.
+    fn resolve(&self, local: NaiveDateTime) -> DateTime<Utc> {
+        let mut candidate = local;
+        // Gaps are at most a few hours; step until a valid local time
+        for _ in 0..24 * 4 {
+            if let Some(resolved) = self.timezone.from_local_datetime(&candidate).earliest() {
+                return resolved.with_timezone(&Utc);
+            }
+            candidate += Duration::minutes(15);
+        }
+        Utc.from_utc_datetime(&local)
+    }
+}
+
+/// Batching of normal and low priority notifications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DigestConfig {
+    /// Hours between digests, aligned to the UTC epoch.
+    pub interval_hours: u32,
+}
+
+impl DigestConfig {
+    /// Send a digest every `interval_hours`.
+    pub fn every_hours(interval_hours: u32) -> Self {
+        Self { interval_hours }
+    }
+
+    /// Check whether notifications of `priority` are batched.
+    pub fn applies_to(&self, priority: Priority) -> bool {
+        matches!(priority, Priority::Normal | Priority::Low)
+    }
+
+    /// First digest time after `at`.
+    pub fn next_delivery(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let interval = i64::from(self.interval_hours.max(1)) * 3600;
+        let next = (at.timestamp().div_euclid(interval) + 1) * interval;
+        DateTime::from_timestamp(next, 0).unwrap_or(at)
+    }
+}
+
+/// Notification preferences for a reviewer, or an organization's defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationPreferences {
+    /// Organization the preferences belong to.
+    pub organization_id: OrganizationId,
+    /// Reviewer the preferences belong to; `None` for organization defaults.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewer_id: Option<UserId>,
+    /// Channels to try per priority, most preferred first.
+    #[serde(default)]
+    pub channels: HashMap<Priority, Vec<ChannelType>>,
+    /// Quiet hours.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+    /// Digest mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<DigestConfig>,
+    /// When the preferences were last written.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NotificationPreferences {
+    /// Create empty preferences for a reviewer.
+    pub fn for_reviewer(organization_id: OrganizationId, reviewer_id: UserId) -> Self {
+        Self {
+            reviewer_id: Some(reviewer_id),
+            ..Self::org_default(organization_id)
+        }
+    }
+
+    /// Create empty organization defaults.
+    pub fn org_default(organization_id: OrganizationId) -> Self {
+        Self {
+            organization_id,
+            reviewer_id: None,
+            channels: HashMap::new(),
+            quiet_hours: None,
+            digest: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Set the channel order for a priority.
+    pub fn with_channels(mut self, priority: Priority, channels: Vec<ChannelType>) -> Self {
+        self.channels.insert(priority, channels);
+        self
+    }
+
+    /// Set quiet hours.
+    pub fn with_quiet_hours(mut self, quiet_hours: QuietHours) -> Self {
+        self.quiet_hours = Some(quiet_hours);
+        self
+    }
+
+    /// Enable digest mode.
+    pub fn with_digest(mut self, digest: DigestConfig) -> Self {
+        self.digest = Some(digest);
+        self
+    }
+
+    /// Preferred channels for a priority (empty = any registered channel).
+    pub fn channels_for(&self, priority: Priority) -> &[ChannelType] {
+        self.channels
+            .get(&priority)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Decide when a notification of `priority` raised at `now` goes out.
+    ///
+    /// Critical notifications always go out immediately. Digest-eligible
+    /// notifications wait for the next digest, pushed past quiet hours if
+    /// the digest falls inside them; anything else raised during quiet hours
+    /// waits for them to end.
+    pub fn schedule(&self, priority: Priority, now: DateTime<Utc>) -> DeliverySchedule {
+        if priority == Priority::Critical {
+            return DeliverySchedule::Immediate;
+        }
+
+        if let Some(digest) = self.digest.filter(|d| d.applies_to(priority)) {
+            let mut deliver_at = digest.next_delivery(now);
+            if let Some(quiet) = self.quiet_hours.as_ref().filter(|q| q.contains(deliver_at)) {
+                deliver_at = quiet.next_end(deliver_at);
+            }
+            return DeliverySchedule::Deferred {
+                deliver_at,
+                reason: DeferralReason::Digest,
+            };
+        }
+
+        match &self.quiet_hours {
+            Some(quiet) if quiet.contains(now) => DeliverySchedule::Deferred {
+                deliver_at: quiet.next_end(now),
+                reason: DeferralReason::QuietHours,
+            },
+            _ => DeliverySchedule::Immediate,
+        }
+    }
+}
+
+/// When a notification goes out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliverySchedule {
+    /// Send now.
+    Immediate,
+    /// Hold until `deliver_at`.
+    Deferred {
+        /// When to send.
+        deliver_at: DateTime<Utc>,
+        /// Why it was held.
+        reason: DeferralReason,
+    },
+}
+
+/// Why a notification was held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeferralReason {
+    /// Raised during quiet hours; sent individually when they end.
+    QuietHours,
+    /// Batched into the next digest.
+    Digest,
+}
+
+/// A notification held for later delivery.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingDelivery {
+    /// Delivery ID.
+    pub id: Uuid,
+    /// Organization of the request.
+    pub organization_id: OrganizationId,
+    /// Recipient; `None` for organization-level routing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewer_id: Option<UserId>,
+    /// Channel to deliver on.
+    pub channel: ChannelType,
+    /// The request to notify about.
+    pub request: OversightRequest,
+    /// Why delivery was held.
+    pub reason: DeferralReason,
+    /// When to deliver.
+    pub deliver_at: DateTime<Utc>,
+    /// When the notification was held.
+    pub queued_at: DateTime<Utc>,
+}
+
+/// Storage for reviewer and organization notification preferences.
+#[async_trait]
+pub trait NotificationPreferenceStore: Send + Sync {
+    /// Get preferences; `reviewer_id: None` gets the organization defaults.
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: Option<UserId>,
+    ) -> CretoResult<Option<NotificationPreferences>>;
+
+    /// Create or replace preferences.
+    async fn put(
+        &self,
+        preferences: NotificationPreferences,
+    ) -> CretoResult<NotificationPreferences>;
+
+    /// Delete preferences, returning whether any existed.
+    async fn delete(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: Option<UserId>,
+    ) -> CretoResult<bool>;
+}
+
+/// Storage for notifications held for later delivery.
+#[async_trait]
+pub trait PendingDeliveryStore: Send + Sync {
+    /// Hold a notification.
+    async fn enqueue(&self, delivery: PendingDelivery) -> CretoResult<()>;
+
+    /// List deliveries due at `now`, earliest first.
+    async fn due(&self, now: DateTime<Utc>) -> CretoResult<Vec<PendingDelivery>>;
+
+    /// Remove delivered notifications.
+    async fn remove(&self, ids: &[Uuid]) -> CretoResult<()>;
+
+    /// Remove every held notification about a request, returning them.
+    async fn remove_for_request(&self, request_id: Uuid) -> CretoResult<Vec<PendingDelivery>>;
+}
+
+/// In-memory preference and delivery store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryNotificationStore {
+    preferences: RwLock<HashMap<(OrganizationId, Option<UserId>), NotificationPreferences>>,
+    deliveries: RwLock<Vec<PendingDelivery>>,
+}
+
+impl InMemoryNotificationStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl NotificationPreferenceStore for InMemoryNotificationStore {
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: Option<UserId>,
+    ) -> CretoResult<Option<NotificationPreferences>> {
+        Ok(self
+            .preferences
+            .read()
+            .unwrap()
+            .get(&(organization_id, reviewer_id))
+            .cloned())
+    }
+
+    async fn put(
+        &self,
+        mut preferences: NotificationPreferences,
+    ) -> CretoResult<NotificationPreferences> {
+        preferences.updated_at = Utc::now();
+        self.preferences.write().unwrap().insert(
+            (preferences.organization_id, preferences.reviewer_id),
+            preferences.clone(),
+        );
+        Ok(preferences)
+    }
+
+    async fn delete(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: Option<UserId>,
+    ) -> CretoResult<bool> {
+        Ok(self
+            .preferences
+            .write()
+            .unwrap()
+            .remove(&(organization_id, reviewer_id))
+            .is_some())
+    }
+}
+
+#[async_trait]
+impl PendingDeliveryStore for InMemoryNotificationStore {
+    async fn enqueue(&self, delivery: PendingDelivery) -> CretoResult<()> {
+        self.deliveries.write().unwrap().push(delivery);
+        Ok(())
+    }
+
+    async fn due(&self, now: DateTime<Utc>) -> CretoResult<Vec<PendingDelivery>> {
+        let mut due: Vec<PendingDelivery> = self
+            .deliveries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|d| d.deliver_at <= now)
+            .cloned()
+            .collect();
+        due.sort_by_key(|d| (d.deliver_at, d.queued_at));
+        Ok(due)
+    }
+
+    async fn remove(&self, ids: &[Uuid]) -> CretoResult<()> {
+        self.deliveries
+            .write()
+            .unwrap()
+            .retain(|d| !ids.contains(&d.id));
+        Ok(())
+    }
+
+    async fn remove_for_request(&self, request_id: Uuid) -> CretoResult<Vec<PendingDelivery>> {
+        let mut deliveries = self.deliveries.write().unwrap();
+        let (removed, kept) = deliveries
+            .drain(..)
+            .partition(|d| d.request.id == request_id);
+        *deliveries = kept;
+        Ok(removed)
+    }
+}
+
+/// A notification that was sent, individually or as a digest.
+#[derive(Debug, Clone)]
+pub struct SentNotification {
+    /// Recipient; `None` for organization-level routing.
+    pub reviewer_id: Option<UserId>,
+    /// Channel used.
+    pub channel: ChannelType,
+    /// Requests covered, more than one for a digest.
+    pub request_ids: Vec<Uuid>,
+    /// Channel result.
+    pub result: NotificationResult,
+}
+
+/// Outcome of routing a request's notifications.
+#[derive(Debug, Clone, Default)]
+pub struct RoutingReport {
+    /// Notifications sent immediately.
+    pub sent: Vec<SentNotification>,
+    /// Notifications held for later delivery.
+    pub queued: Vec<PendingDelivery>,
+}
+
+/// Outcome of telling reviewers a request was withdrawn.
+#[derive(Debug, Clone, Default)]
+pub struct WithdrawalReport {
+    /// Withdrawal notices sent.
+    pub sent: Vec<SentNotification>,
+    /// Held notifications about the request that were dropped unsent.
+    pub dropped: Vec<PendingDelivery>,
+}
+
+/// Routes request notifications to reviewers according to their preferences.
+pub struct NotificationRouter {
+    preferences: Arc<dyn NotificationPreferenceStore>,
+    deliveries: Arc<dyn PendingDeliveryStore>,
+    channels: Vec<Arc<dyn NotificationChannel>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl NotificationRouter {
+    /// Create a router over preference and delivery stores, with no channels.
+    pub fn new(
+        preferences: Arc<dyn NotificationPreferenceStore>,
+        deliveries: Arc<dyn PendingDeliveryStore>,
+    ) -> Self {
+        Self {
+            preferences,
+            deliveries,
+            channels: Vec::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Register a channel.
+    pub fn with_channel(mut self, channel: Arc<dyn NotificationChannel>) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    /// Use a different time source.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Get the preference store.
+    pub fn preferences(&self) -> &Arc<dyn NotificationPreferenceStore> {
+        &self.preferences
+    }
+
+    /// Preferences that apply to a reviewer.
+    ///
+    /// Falls back to the organization defaults, then to empty preferences
+    /// (any channel, no quiet hours, no digest).
+    pub async fn effective_preferences(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: Option<UserId>,
+    ) -> CretoResult<NotificationPreferences> {
+        if reviewer_id.is_some() {
+            if let Some(preferences) = self.preferences.get(organization_id, reviewer_id).await? {
+                return Ok(preferences);
+            }
+        }
+        Ok(self
+            .preferences
+            .get(organization_id, None)
+            .await?
+            .unwrap_or_else(|| NotificationPreferences::org_default(organization_id)))
+    }
+
+    fn channel(&self, channel_type: ChannelType) -> Option<&Arc<dyn NotificationChannel>> {
+        self.channels
+            .iter()
+            .find(|c| c.channel_type() == channel_type)
+    }
+
+    /// First registered channel in the preferred order.
+    fn select_channel(
+        &self,
+        preferences: &NotificationPreferences,
+        priority: Priority,
+    ) -> Option<&Arc<dyn NotificationChannel>> {
+        let preferred = preferences.channels_for(priority);
+        if preferred.is_empty() {
+            return self.channels.first();
+        }
+        preferred.iter().find_map(|&t| self.channel(t))
+    }
+
+    /// Notify a request's assigned reviewers, or the organization when none
+    /// are assigned.
+    pub async fn route(&self, request: &OversightRequest) -> CretoResult<RoutingReport> {
+        let now = self.clock.now();
+        let recipients: Vec<Option<UserId>> = if request.assigned_reviewers.is_empty() {
+            vec![None]
+        } else {
+            request
+                .assigned_reviewers
+                .iter()
+                .copied()
+                .map(Some)
+                .collect()
+        };
+
+        let mut report = RoutingReport::default();
+        for reviewer_id in recipients {
+            let preferences = self
+                .effective_preferences(request.organization_id, reviewer_id)
+                .await?;
+            let Some(channel) = self.select_channel(&preferences, request.priority) else {
+                tracing::warn!(
+                    request_id = %request.id,
+                    reviewer_id = ?reviewer_id,
+                    "No registered channel matches notification preferences"
+                );
+                continue;
+            };
+
+            match preferences.schedule(request.priority, now) {
+                DeliverySchedule::Immediate => {
+                    let result = channel.notify(request).await?;
+                    report.sent.push(SentNotification {
+                        reviewer_id,
+                        channel: channel.channel_type(),
+                        request_ids: vec![request.id],
+                        result,
+                    });
+                }
+                DeliverySchedule::Deferred { deliver_at, reason } => {
+                    let delivery = PendingDelivery {
+                        id: Uuid::now_v7(),
+                        organization_id: request.organization_id,
+                        reviewer_id,
+                        channel: channel.channel_type(),
+                        request: request.clone(),
+                        reason,
+                        deliver_at,
+                        queued_at: now,
+                    };
+                    self.deliveries.enqueue(delivery.clone()).await?;
+                    report.queued.push(delivery);
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Tell a request's recipients that it was withdrawn.
+    ///
+    /// Held notifications about the request are dropped; recipients who
+    /// only had a held notification are not told about the withdrawal,
+    /// since they never heard of the request. Everyone else gets a notice on
+    /// their preferred channel right away, regardless of quiet hours or
+    /// digests. A recipient whose preferences cannot be read is skipped and
+    /// a channel that fails is reported in its result; neither stops the
+    /// others.
+    pub async fn withdraw(
+        &self,
+        request: &OversightRequest,
+        reason: &str,
+    ) -> CretoResult<WithdrawalReport> {
+        let dropped = self.deliveries.remove_for_request(request.id).await?;
+        let recipients: Vec<Option<UserId>> = if request.assigned_reviewers.is_empty() {
+            vec![None]
+        } else {
+            request
+                .assigned_reviewers
+                .iter()
+                .copied()
+                .map(Some)
+                .collect()
+        };
+
+        let mut sent = Vec::new();
+        for reviewer_id in recipients {
+            if dropped.iter().any(|d| d.reviewer_id == reviewer_id) {
+                continue;
+            }
+            let preferences = match self
+                .effective_preferences(request.organization_id, reviewer_id)
+                .await
+            {
+                Ok(preferences) => preferences,
+                Err(e) => {
+                    tracing::warn!(
+                        request_id = %request.id,
+                        reviewer_id = ?reviewer_id,
+                        error = %e,
+                        "Failed to read notification preferences for withdrawal"
+                    );
+                    continue;
+                }
+            };
+            let Some(channel) = self.select_channel(&preferences, request.priority) else {
+                tracing::warn!(
+                    request_id = %request.id,
+                    reviewer_id = ?reviewer_id,
+                    "No registered channel matches notification preferences"
+                );
+                continue;
+            };
+            let result = match channel.notify_cancelled(request, reason).await {
+                Ok(result) => result,
+                Err(e) => NotificationResult::failure(e.to_string()),
+            };
+            sent.push(SentNotification {
+                reviewer_id,
+                channel: channel.channel_type(),
+                request_ids: vec![request.id],
+                result,
+            });
+        }
+
+        Ok(WithdrawalReport { sent, dropped })
+    }
+
+    /// Notify a request's assigned reviewers on every registered channel
+    /// right away, regardless of their preferences, quiet hours or digests.
+    ///
+    /// Used for escalations, which must reach the new reviewers however
+    /// they are configured. A channel that fails is reported in its result
+    /// and does not stop the others.
+    pub async fn broadcast(&self, request: &OversightRequest) -> Vec<SentNotification> {
+        let recipients: Vec<Option<UserId>> = if request.assigned_reviewers.is_empty() {
+            vec![None]
+        } else {
+            request
+                .assigned_reviewers
+                .iter()
+                .copied()
+                .map(Some)
+                .collect()
+        };
+
+        let mut sent = Vec::new();
+        for reviewer_id in recipients {
+            for channel in &self.channels {
+                let result = match channel.notify(request).await {
+                    Ok(result) => result,
+                    Err(e) => NotificationResult::failure(e.to_string()),
+                };
+                sent.push(SentNotification {
+                    reviewer_id,
+                    channel: channel.channel_type(),
+                    request_ids: vec![request.id],
+                    result,
+                });
+            }
+        }
+        sent
+    }
+
+    /// Send every held notification that is due.
+    ///
+    /// Digest deliveries are grouped per recipient and channel into one
+    /// digest. Deliveries whose channel fails or is no longer registered
+    /// stay queued for the next pass; a failed send is reported in its
+    /// result and does not stop the other recipients.
+    pub async fn deliver_due(&self) -> CretoResult<Vec<SentNotification>> {
+        let due = self.deliveries.due(self.clock.now()).await?;
+
+        let mut batches: Vec<(Option<UserId>, ChannelType, Vec<PendingDelivery>)> = Vec::new();
+        for delivery in due {
+            let batch = (delivery.reason == DeferralReason::Digest)
+                .then(|| {
+                    batches.iter().position(|(reviewer_id, channel, items)| {
+                        *reviewer_id == delivery.reviewer_id
+                            && *channel == delivery.channel
+                            && items[0].reason == DeferralReason::Digest
+                            && items[0].organization_id == delivery.organization_id
+                    })
+                })
+                .flatten();
+            match batch {
+                Some(i) => batches[i].2.push(delivery),
+                None => batches.push((delivery.reviewer_id, delivery.channel, vec![delivery])),
+            }
+        }
+
+        let mut sent = Vec::new();
+        let mut delivered = Vec::new();
+        for (reviewer_id, channel_type, items) in batches {
+            let Some(channel) = self.channel(channel_type) else {
+                tracing::warn!(channel = ?channel_type, "Held notification channel not registered");
+                continue;
+            };
+            let requests: Vec<OversightRequest> = items.iter().map(|d| d.request.clone()).collect();
+            let result = if items[0].reason == DeferralReason::Digest {
+                channel.digest(&requests).await
+            } else {
+                channel.notify(&requests[0]).await
+            };
+            let result = result.unwrap_or_else(|e| {
+                tracing::warn!(
+                    reviewer_id = ?reviewer_id,
+                    channel = ?channel_type,
+                    error = %e,
+                    "Held notification delivery failed"
+                );
+                NotificationResult::failure(e.to_string())
+            });
+
+            if result.success {
+                delivered.extend(items.iter().map(|d| d.id));
+            }
+            sent.push(SentNotification {
+                reviewer_id,
+                channel: channel_type,
+                request_ids: requests.iter().map(|r| r.id).collect(),
+                result,
+            });
+        }
+
+        if !delivered.is_empty() {
+            self.deliveries.remove(&delivered).await?;
+        }
+        Ok(sent)
+    }
+}
+
+impl Default for NotificationRouter {
+    fn default() -> Self {
+        let store = Arc::new(InMemoryNotificationStore::new());
+        Self::new(store.clone(), store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channels::MockChannel;
+    use crate::clock::TestClock;
+    use crate::request::ActionType;
+    use chrono::TimeZone;
+    use creto_common::AgentId;
+
+    struct Fixture {
+        router: NotificationRouter,
+        store: Arc<InMemoryNotificationStore>,
+        clock: Arc<TestClock>,
+        slack: Arc<MockChannel>,
+        sms: Arc<MockChannel>,
+        org_id: OrganizationId,
+        reviewer_id: UserId,
+    }
+
+    /// Router at 03:00 UTC with a reviewer whose quiet hours are 22:00-07:00.
+    fn fixture() -> Fixture {
+        let store = Arc::new(InMemoryNotificationStore::new());
+        let clock = Arc::new(TestClock::new(
+            Utc.with_ymd_and_hms(2024, 6, 3, 3, 0, 0).unwrap(),
+        ));
+        let slack = Arc::new(MockChannel::new().with_channel_type(ChannelType::Slack));
+        let sms = Arc::new(MockChannel::new().with_channel_type(ChannelType::Sms));
+        let router = NotificationRouter::new(store.clone(), store.clone())
+            .with_channel(slack.clone())
+            .with_channel(sms.clone())
+            .with_clock(clock.clone());
+
+        Fixture {
+            router,
+            store,
+            clock,
+            slack,
+            sms,
+            org_id: OrganizationId::new(),
+            reviewer_id: UserId::new(),
+        }
+    }
+
+    fn quiet_nights() -> QuietHours {
+        QuietHours::new(
+            NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+        )
+    }
+
+    fn request(f: &Fixture, priority: Priority) -> OversightRequest {
+        let mut request = OversightRequest::new(
+            f.org_id,
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        )
+        .with_priority(priority);
+        request.assigned_reviewers = vec![f.reviewer_id];
+        request
+    }
+
+    /// Channel whose every send errors.
+    struct Unreachable(ChannelType);
+
+    #[async_trait]
+    impl NotificationChannel for Unreachable {
+        async fn notify(&self, _request: &OversightRequest) -> CretoResult<NotificationResult> {
+            Err(creto_common::CretoError::Internal(
+                "connection refused".to_string(),
+            ))
+        }
+
+        async fn remind(&self, _request: &OversightRequest) -> CretoResult<NotificationResult> {
+            Err(creto_common::CretoError::Internal(
+                "connection refused".to_string(),
+            ))
+        }
+
+        async fn notify_cancelled(
+            &self,
+            _request: &OversightRequest,
+            _reason: &str,
+        ) -> CretoResult<NotificationResult> {
+            Err(creto_common::CretoError::Internal(
+                "connection refused".to_string(),
+            ))
+        }
+
+        fn channel_type(&self) -> ChannelType {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_reports_failed_recipient_and_reaches_the_rest() {
+        let f = fixture();
+        let router = NotificationRouter::new(f.store.clone(), f.store.clone())
+            .with_channel(f.slack.clone())
+            .with_channel(Arc::new(Unreachable(ChannelType::Teams)))
+            .with_clock(f.clock.clone());
+        let unreachable = UserId::new();
+        f.store
+            .put(
+                NotificationPreferences::for_reviewer(f.org_id, unreachable)
+                    .with_channels(Priority::Normal, vec![ChannelType::Teams]),
+            )
+            .await
+            .unwrap();
+        let mut request = request(&f, Priority::Normal);
+        request.assigned_reviewers = vec![unreachable, f.reviewer_id];
+
+        let report = router.withdraw(&request, "task abandoned").await.unwrap();
+
+        assert_eq!(report.sent.len(), 2);
+        assert_eq!(report.sent[0].reviewer_id, Some(unreachable));
+        assert!(!report.sent[0].result.success);
+        assert_eq!(report.sent[1].reviewer_id, Some(f.reviewer_id));
+        assert!(report.sent[1].result.success);
+        assert_eq!(f.slack.get_cancellations().await.len(), 1);
+    }
+
+    #[test]
+    fn test_quiet_hours_span_midnight_in_time_zone() {
+        // 22:00-07:00 in New York, at UTC-4 in June
+        let quiet = quiet_nights().with_timezone(chrono_tz::America::New_York);
+        let at = Utc.with_ymd_and_hms(2024, 6, 3, 4, 0, 0).unwrap(); // 00:00 local
+
+        assert!(quiet.contains(at));
+        assert_eq!(
+            quiet.next_end(at),
+            Utc.with_ymd_and_hms(2024, 6, 3, 11, 0, 0).unwrap()
+        );
+        assert!(!quiet.contains(Utc.with_ymd_and_hms(2024, 6, 3, 15, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_quiet_hours_follow_daylight_saving() {
+        let quiet = quiet_nights().with_timezone(chrono_tz::America::New_York);
+
+        // Clocks go back on 2024-11-03: 07:00 local is 12:00 UTC, not 11:00
+        let night = Utc.with_ymd_and_hms(2024, 11, 3, 5, 0, 0).unwrap();
+        assert!(quiet.contains(night));
+        assert_eq!(
+            quiet.next_end(night),
+            Utc.with_ymd_and_hms(2024, 11, 3, 12, 0, 0).unwrap()
+        );
+        assert!(!quiet.contains(Utc.with_ymd_and_hms(2024, 11, 3, 12, 0, 0).unwrap()));
+
+        // An end time skipped by clocks going forward falls after the gap
+        let gap = QuietHours::new(
+            NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(2, 30, 0).unwrap(),
+        )
+        .with_timezone(chrono_tz::America::New_York);
+        let at = Utc.with_ymd_and_hms(2024, 3, 10, 6, 30, 0).unwrap(); // 01:30 EST
+        assert_eq!(
+            gap.next_end(at),
+            Utc.with_ymd_and_hms(2024, 3, 10, 7, 0, 0).unwrap() // 03:00 EDT
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failed_held_delivery_stays_queued_and_others_go_out() {
+        let f = fixture();
+        let router = NotificationRouter::new(f.store.clone(), f.store.clone())
+            .with_channel(f.slack.clone())
+            .with_channel(Arc::new(Unreachable(ChannelType::Teams)))
+            .with_clock(f.clock.clone());
+        let unreachable = UserId::new();
+        for (reviewer_id, channel) in [
+            (unreachable, ChannelType::Teams),
+            (f.reviewer_id, ChannelType::Slack),
+        ] {
+            f.store
+                .put(
+                    NotificationPreferences::for_reviewer(f.org_id, reviewer_id)
+                        .with_quiet_hours(quiet_nights())
+                        .with_channels(Priority::Normal, vec![channel]),
+                )
+                .await
+                .unwrap();
+        }
+        let mut request = request(&f, Priority::Normal);
+        request.assigned_reviewers = vec![unreachable, f.reviewer_id];
+        assert_eq!(router.route(&request).await.unwrap().queued.len(), 2);
+
+        f.clock
+            .set(Utc.with_ymd_and_hms(2024, 6, 3, 7, 0, 0).unwrap());
+        let sent = router.deliver_due().await.unwrap();
+
+        assert_eq!(sent.len(), 2);
+        assert!(!sent[0].result.success);
+        assert!(sent[1].result.success);
+        assert_eq!(f.slack.notification_count().await, 1);
+        let retry = router.deliver_due().await.unwrap();
+        assert_eq!(retry.len(), 1);
+        assert_eq!(retry[0].reviewer_id, Some(unreachable));
+    }
+
+    #[tokio::test]
+    async fn test_normal_request_held_until_quiet_hours_end() {
+        let f = fixture();
+        f.store
+            .put(
+                NotificationPreferences::for_reviewer(f.org_id, f.reviewer_id)
+                    .with_quiet_hours(quiet_nights()),
+            )
+            .await
+            .unwrap();
+
+        let report = f
+            .router
+            .route(&request(&f, Priority::Normal))
+            .await
+            .unwrap();
+        assert!(report.sent.is_empty());
+        let end = Utc.with_ymd_and_hms(2024, 6, 3, 7, 0, 0).unwrap();
+        assert_eq!(report.queued[0].deliver_at, end);
+        assert_eq!(report.queued[0].reason, DeferralReason::QuietHours);
+
+        f.clock.set(end - Duration::seconds(1));
+        assert!(f.router.deliver_due().await.unwrap().is_empty());
+        assert_eq!(f.slack.notification_count().await, 0);
+
+        f.clock.set(end);
+        let sent = f.router.deliver_due().await.unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(f.slack.notification_count().await, 1);
+        assert!(f.router.deliver_due().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_critical_request_sent_during_quiet_hours_on_preferred_channel() {
+        let f = fixture();
+        f.store
+            .put(
+                NotificationPreferences::for_reviewer(f.org_id, f.reviewer_id)
+                    .with_quiet_hours(quiet_nights())
+                    .with_channels(
+                        Priority::Critical,
+                        vec![ChannelType::Teams, ChannelType::Sms, ChannelType::Slack],
+                    ),
+            )
+            .await
+            .unwrap();
+
+        let report = f
+            .router
+            .route(&request(&f, Priority::Critical))
+            .await
+            .unwrap();
+        assert!(report.queued.is_empty());
+        assert_eq!(report.sent[0].channel, ChannelType::Sms);
+        assert_eq!(f.sms.notification_count().await, 1);
+        assert_eq!(f.slack.notification_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_digest_groups_per_reviewer() {
+        let f = fixture();
+        f.clock
+            .set(Utc.with_ymd_and_hms(2024, 6, 3, 13, 0, 0).unwrap());
+        f.store
+            .put(
+                NotificationPreferences::for_reviewer(f.org_id, f.reviewer_id)
+                    .with_digest(DigestConfig::every_hours(4)),
+            )
+            .await
+            .unwrap();
+        // Organization default applies to unassigned requests: no digest
+        f.store
+            .put(
+                NotificationPreferences::org_default(f.org_id)
+                    .with_channels(Priority::Normal, vec![ChannelType::Sms]),
+            )
+            .await
+            .unwrap();
+
+        let first = request(&f, Priority::Normal);
+        let second = request(&f, Priority::Low);
+        let urgent = request(&f, Priority::High);
+        let mut unassigned = request(&f, Priority::Normal);
+        unassigned.assigned_reviewers.clear();
+        for r in [&first, &second, &urgent, &unassigned] {
+            f.router.route(r).await.unwrap();
+        }
+
+        // High priority and the unassigned request went out immediately
+        assert_eq!(f.slack.notification_count().await, 1);
+        assert_eq!(f.sms.notification_count().await, 1);
+
+        f.clock
+            .set(Utc.with_ymd_and_hms(2024, 6, 3, 16, 0, 0).unwrap());
+        let sent = f.router.deliver_due().await.unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].reviewer_id, Some(f.reviewer_id));
+        assert_eq!(sent[0].request_ids, vec![first.id, second.id]);
+
+        let digests = f.slack.get_digests().await;
+        assert_eq!(digests.len(), 1);
+        assert_eq!(digests[0].len(), 2);
+    }
+}
@@ -1,7 +1,9 @@
 //! Policy evaluation for determining oversight requirements.
 
+use creto_common::Correlation;
 use serde::{Deserialize, Serialize};
 
+use crate::enrichment::ExecutionReference;
 use crate::request::ActionType;
 
 /// Result of policy evaluation.
@@ -143,6 +145,17 @@ pub struct PolicyContext {
     /// Additional attributes for policy evaluation.
     #[serde(default)]
     pub attributes: serde_json::Value,
+
+    /// Trace identifiers of the action being evaluated; inherited by any
+    /// oversight request created for it.
+    #[serde(default, skip_serializing_if = "Correlation::is_empty")]
+    pub correlation: Correlation,
+
+    /// Execution behind a `CodeExecution` action, shown to reviewers by
+    /// context enrichers. Not serialized, so the code stays out of request
+    /// metadata.
+    #[serde(skip)]
+    pub execution: Option<ExecutionReference>,
 }
 
 impl Default for PolicyContext {
@@ -153,6 +166,8 @@ impl Default for PolicyContext {
             delegation_depth: 0,
             time_of_day: None,
             attributes: serde_json::Value::Object(serde_json::Map::new()),
+            correlation: Correlation::default(),
+            execution: None,
         }
     }
 }
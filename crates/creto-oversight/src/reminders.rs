@@ -0,0 +1,573 @@
+//! Reminders for requests left waiting on their reviewers.
+//!
+//! The [`ReminderScheduler`] looks for pending and in-review requests at
+//! least [`ReminderPolicy::min_age_seconds`] old and reminds their reviewers
+//! on the channels a [`ChannelRouter`](crate::routing::ChannelRouter)
+//! delivered the request on, or on every registered channel for requests
+//! not routed that way. Each later reminder waits for the policy's
+//! [`ReminderCadence`] after the previous one, as recorded with
+//! [`RequestRepository::record_reminder`], so restarts do not resend
+//! reminders. After [`ReminderPolicy::max_reminders`] the scheduler stops
+//! reminding and, if the policy says so and escalation is configured,
+//! escalates the request when the next reminder would have been due.
+
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{CretoError, CretoResult, ShutdownCoordinator};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::channels::{ChannelType, NotificationChannel, NotificationResult};
+use crate::clock::{Clock, SystemClock};
+use crate::escalation::EscalationTrigger;
+use crate::repository::RequestRepository;
+use crate::request::OversightRequest;
+use crate::service::OversightService;
+
+/// Spacing between consecutive reminders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReminderCadence {
+    /// The same gap between every reminder.
+    Fixed { interval_seconds: u64 },
+    /// Each gap `multiplier` times the one before, starting at
+    /// `initial_seconds` (1h, 4h, 16h, ... for 3600 and 4).
+    Exponential {
+        initial_seconds: u64,
+        multiplier: u32,
+    },
+}
+
+impl ReminderCadence {
+    /// Gap between reminder `sent` and the one after it (`sent` >= 1).
+    pub fn gap_after(&self, sent: u32) -> Duration {
+        let seconds = match *self {
+            ReminderCadence::Fixed { interval_seconds } => interval_seconds,
+            ReminderCadence::Exponential {
+                initial_seconds,
+                multiplier,
+            } => u64::from(multiplier)
+                .checked_pow(sent.saturating_sub(1))
+                .and_then(|factor| initial_seconds.checked_mul(factor))
+                .unwrap_or(u64::MAX),
+        };
+        i64::try_from(seconds)
+            .ok()
+            .and_then(Duration::try_seconds)
+            .unwrap_or(Duration::MAX)
+    }
+}
+
+/// When reviewers are reminded of a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderPolicy {
+    /// Age a request must reach before its first reminder.
+    pub min_age_seconds: u64,
+
+    /// Spacing of later reminders.
+    pub cadence: ReminderCadence,
+
+    /// Most reminders sent per request.
+    pub max_reminders: u32,
+
+    /// Whether to escalate a request once its reminders run out.
+    #[serde(default)]
+    pub escalate_after_max: bool,
+}
+
+impl Default for ReminderPolicy {
+    fn default() -> Self {
+        Self {
+            min_age_seconds: 3600,
+            cadence: ReminderCadence::Exponential {
+                initial_seconds: 3600,
+                multiplier: 4,
+            },
+            max_reminders: 3,
+            escalate_after_max: false,
+        }
+    }
+}
+
+impl ReminderPolicy {
+    /// Set the age of a request's first reminder.
+    pub fn with_min_age(mut self, seconds: u64) -> Self {
+        self.min_age_seconds = seconds;
+        self
+    }
+
+    /// Set the spacing of later reminders.
+    pub fn with_cadence(mut self, cadence: ReminderCadence) -> Self {
+        self.cadence = cadence;
+        self
+    }
+
+    /// Set how many reminders a request gets at most.
+    pub fn with_max_reminders(mut self, max: u32) -> Self {
+        self.max_reminders = max;
+        self
+    }
+
+    /// Escalate requests whose reminders run out.
+    pub fn with_escalation(mut self, escalate: bool) -> Self {
+        self.escalate_after_max = escalate;
+        self
+    }
+
+    /// When `request`'s next reminder, or its escalation once the reminders
+    /// have run out, is due.
+    pub fn next_due(&self, request: &OversightRequest) -> DateTime<Utc> {
+        match (request.reminder_count, request.last_reminded_at) {
+            (0, _) | (_, None) => {
+                request.created_at + Duration::seconds(self.min_age_seconds as i64)
+            }
+            (sent, Some(last)) => last
+                .checked_add_signed(self.cadence.gap_after(sent))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        }
+    }
+}
+
+/// A reminder sent for one request.
+#[derive(Debug, Clone)]
+pub struct SentReminder {
+    /// The request.
+    pub request_id: Uuid,
+    /// Which reminder this was for the request, from 1.
+    pub reminder: u32,
+    /// Result of each channel.
+    pub results: Vec<(ChannelType, NotificationResult)>,
+}
+
+/// Outcome of a reminder pass.
+#[derive(Debug, Clone, Default)]
+pub struct ReminderReport {
+    /// Reminders sent on at least one channel.
+    pub sent: Vec<SentReminder>,
+    /// Requests escalated because their reminders ran out.
+    pub escalated: Vec<Uuid>,
+}
+
+/// Periodically reminds reviewers of requests they have not decided.
+pub struct ReminderScheduler {
+    requests: Arc<dyn RequestRepository>,
+    channels: Vec<Arc<dyn NotificationChannel>>,
+    policy: ReminderPolicy,
+    escalation: Option<Arc<OversightService>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ReminderScheduler {
+    /// Create a scheduler over `requests`, with no channels.
+    pub fn new(requests: Arc<dyn RequestRepository>, policy: ReminderPolicy) -> Self {
+        Self {
+            requests,
+            channels: Vec::new(),
+            policy,
+            escalation: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Register a channel.
+    pub fn with_channel(mut self, channel: Arc<dyn NotificationChannel>) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    /// Escalate requests whose reminders run out through `service`, if the
+    /// policy asks for it.
+    pub fn with_escalation(mut self, service: Arc<OversightService>) -> Self {
+        self.escalation = Some(service);
+        self
+    }
+
+    /// Use a different time source.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Get the reminder policy.
+    pub fn policy(&self) -> &ReminderPolicy {
+        &self.policy
+    }
+
+    /// Remind the reviewers of every open request whose next reminder is due.
+    ///
+    /// A reminder is sent on every channel the request was delivered on
+    /// (see [`OversightService::notify`]); a channel that fails does not
+    /// stop the others, and the reminder counts if any channel succeeded.
+    /// Once a request has had `max_reminders`, its next due slot escalates
+    /// it instead, when configured, and nothing is sent after that. A
+    /// request that fails to update is logged and retried on the next pass.
+    pub async fn send_due_reminders(&self) -> CretoResult<ReminderReport> {
+        let now = self.clock.now();
+        let mut report = ReminderReport::default();
+        for request in self.requests.list_open().await? {
+            if request.status.is_terminal() || self.policy.next_due(&request) > now {
+                continue;
+            }
+            if request.reminder_count >= self.policy.max_reminders {
+                if request.reminder_count == self.policy.max_reminders {
+                    match self.escalate(&request, now).await {
+                        Ok(true) => report.escalated.push(request.id),
+                        Ok(false) => {}
+                        Err(e) => {
+                            tracing::warn!(request_id = %request.id, error = %e, "Failed to escalate unanswered request")
+                        }
+                    }
+                }
+                continue;
+            }
+            match self.remind(&request, now).await {
+                Ok(Some(sent)) => report.sent.push(sent),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(request_id = %request.id, error = %e, "Failed to send reminder")
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    async fn remind(
+        &self,
+        request: &OversightRequest,
+        now: DateTime<Utc>,
+    ) -> CretoResult<Option<SentReminder>> {
+        let mut results = Vec::with_capacity(self.channels.len());
+        for channel in self.channels_for(request) {
+            let result = match channel.remind(request).await {
+                Ok(result) => result,
+                Err(e) => NotificationResult::failure(e.to_string()),
+            };
+            if !result.success {
+                tracing::warn!(
+                    request_id = %request.id,
+                    channel = ?channel.channel_type(),
+                    error = ?result.error,
+                    "Reminder channel failed"
+                );
+            }
+            results.push((channel.channel_type(), result));
+        }
+        if !results.iter().any(|(_, result)| result.success) {
+            return Ok(None);
+        }
+
+        let reminder = self.requests.record_reminder(request.id, now).await?;
+        Ok(Some(SentReminder {
+            request_id: request.id,
+            reminder,
+            results,
+        }))
+    }
+
+    /// Channels to remind a request's reviewers on: the ones it was
+    /// delivered on by channel routing, or every channel if it was not
+    /// routed or none of those channels is registered here.
+    fn channels_for<'a>(
+        &'a self,
+        request: &OversightRequest,
+    ) -> Vec<&'a Arc<dyn NotificationChannel>> {
+        let routed: Vec<_> = self
+            .channels
+            .iter()
+            .filter(|c| request.notified_channels.contains(&c.channel_type()))
+            .collect();
+        if routed.is_empty() {
+            self.channels.iter().collect()
+        } else {
+            routed
+        }
+    }
+
+    /// Escalate a request whose reminders ran out. Returns whether it was
+    /// escalated.
+    ///
+    /// The attempt is recorded as one more reminder either way, so a request
+    /// is only ever escalated once by the scheduler.
+    async fn escalate(&self, request: &OversightRequest, now: DateTime<Utc>) -> CretoResult<bool> {
+        let service = match &self.escalation {
+            Some(service) if self.policy.escalate_after_max => service,
+            _ => return Ok(false),
+        };
+        self.requests.record_reminder(request.id, now).await?;
+        match service
+            .escalate_request(request.id, EscalationTrigger::RemindersExhausted)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(CretoError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Spawn the reminder worker.
+    ///
+    /// Runs [`send_due_reminders`](Self::send_due_reminders) every
+    /// `interval`. The task is registered with `shutdown` and stops between
+    /// passes.
+    pub fn spawn(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+        interval: StdDuration,
+    ) -> JoinHandle<()> {
+        let scheduler = Arc::clone(self);
+        shutdown.spawn("oversight.reminder_worker", move |guard| {
+            guard.run_periodic(interval, move || {
+                let scheduler = Arc::clone(&scheduler);
+                async move {
+                    match scheduler.send_due_reminders().await {
+                        Ok(report) if report.sent.is_empty() && report.escalated.is_empty() => {}
+                        Ok(report) => tracing::info!(
+                            reminded = report.sent.len(),
+                            escalated = report.escalated.len(),
+                            "Reminded reviewers of pending oversight requests"
+                        ),
+                        Err(e) => tracing::warn!(error = %e, "Reminder pass failed"),
+                    }
+                }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use creto_common::{AgentId, OrganizationId, UserId};
+
+    use crate::channels::MockChannel;
+    use crate::clock::TestClock;
+    use crate::repository::InMemoryRequestRepository;
+    use crate::request::{ActionType, RequestStatus};
+
+    struct Fixture {
+        scheduler: ReminderScheduler,
+        requests: Arc<InMemoryRequestRepository>,
+        slack: Arc<MockChannel>,
+        email: Arc<MockChannel>,
+        clock: Arc<TestClock>,
+        request: OversightRequest,
+    }
+
+    async fn fixture(policy: ReminderPolicy) -> Fixture {
+        let mut request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        );
+        request.assigned_reviewers = vec![UserId::new()];
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        requests.create(&request).await.unwrap();
+        let slack = Arc::new(MockChannel::new().with_channel_type(ChannelType::Slack));
+        let email = Arc::new(MockChannel::new().with_channel_type(ChannelType::Email));
+        let clock = Arc::new(TestClock::new(request.created_at));
+        let scheduler = ReminderScheduler::new(requests.clone(), policy)
+            .with_channel(slack.clone())
+            .with_channel(email.clone())
+            .with_clock(clock.clone());
+        Fixture {
+            scheduler,
+            requests,
+            slack,
+            email,
+            clock,
+            request,
+        }
+    }
+
+    #[test]
+    fn test_cadence_gaps() {
+        let fixed = ReminderCadence::Fixed {
+            interval_seconds: 600,
+        };
+        assert_eq!(fixed.gap_after(1), Duration::minutes(10));
+        assert_eq!(fixed.gap_after(5), Duration::minutes(10));
+
+        let exponential = ReminderCadence::Exponential {
+            initial_seconds: 3600,
+            multiplier: 4,
+        };
+        assert_eq!(exponential.gap_after(1), Duration::hours(1));
+        assert_eq!(exponential.gap_after(2), Duration::hours(4));
+        assert_eq!(exponential.gap_after(3), Duration::hours(16));
+        // Saturates rather than overflowing
+        assert!(exponential.gap_after(100) > Duration::days(365));
+    }
+
+    #[tokio::test]
+    async fn test_reminders_follow_cadence_until_cap() {
+        let f = fixture(ReminderPolicy::default().with_max_reminders(3)).await;
+
+        // Before the first hour nothing is due
+        f.clock.advance(Duration::minutes(59));
+        assert!(f
+            .scheduler
+            .send_due_reminders()
+            .await
+            .unwrap()
+            .sent
+            .is_empty());
+
+        // 1h old: first reminder; then 1h and 4h gaps
+        for (wait, reminder) in [
+            (Duration::minutes(1), 1),
+            (Duration::hours(1), 2),
+            (Duration::hours(4), 3),
+        ] {
+            f.clock.advance(wait - Duration::seconds(1));
+            assert!(f
+                .scheduler
+                .send_due_reminders()
+                .await
+                .unwrap()
+                .sent
+                .is_empty());
+            f.clock.advance(Duration::seconds(1));
+            let report = f.scheduler.send_due_reminders().await.unwrap();
+            assert_eq!(report.sent.len(), 1);
+            assert_eq!(report.sent[0].reminder, reminder);
+            assert_eq!(report.sent[0].results.len(), 2);
+        }
+        assert_eq!(f.slack.reminder_count().await, 3);
+        assert_eq!(f.email.reminder_count().await, 3);
+
+        // Capped: no fourth reminder however long the request waits
+        f.clock.advance(Duration::days(7));
+        let report = f.scheduler.send_due_reminders().await.unwrap();
+        assert!(report.sent.is_empty() && report.escalated.is_empty());
+        assert_eq!(f.slack.reminder_count().await, 3);
+        let stored = f.requests.get(f.request.id).await.unwrap().unwrap();
+        assert_eq!(stored.reminder_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_failing_channel_does_not_block_others() {
+        let f = fixture(ReminderPolicy::default()).await;
+        f.slack.set_should_fail(true).await;
+        f.clock.advance(Duration::hours(1));
+
+        let report = f.scheduler.send_due_reminders().await.unwrap();
+        assert_eq!(report.sent.len(), 1);
+        assert!(!report.sent[0].results[0].1.success);
+        assert!(report.sent[0].results[1].1.success);
+        assert_eq!(f.email.reminder_count().await, 1);
+
+        // With every channel down the reminder is not counted and is retried
+        f.email.set_should_fail(true).await;
+        f.clock.advance(Duration::hours(1));
+        assert!(f
+            .scheduler
+            .send_due_reminders()
+            .await
+            .unwrap()
+            .sent
+            .is_empty());
+        f.slack.set_should_fail(false).await;
+        let report = f.scheduler.send_due_reminders().await.unwrap();
+        assert_eq!(report.sent[0].reminder, 2);
+    }
+
+    #[tokio::test]
+    async fn test_decided_requests_are_never_reminded() {
+        let f = fixture(ReminderPolicy::default()).await;
+        let mut rejected = f.request.clone();
+        rejected.id = Uuid::now_v7();
+        f.requests.create(&rejected).await.unwrap();
+        f.requests
+            .update_status(f.request.id, RequestStatus::Approved)
+            .await
+            .unwrap();
+        f.requests
+            .update_status(rejected.id, RequestStatus::Rejected)
+            .await
+            .unwrap();
+
+        f.clock.advance(Duration::days(2));
+        assert!(f
+            .scheduler
+            .send_due_reminders()
+            .await
+            .unwrap()
+            .sent
+            .is_empty());
+        assert_eq!(f.slack.reminder_count().await, 0);
+        assert_eq!(f.email.reminder_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_escalates_once_reminders_run_out() {
+        use crate::escalation::{
+            EscalationChain, EscalationChainStore, EscalationEngine, EscalationTier,
+            InMemoryEscalationChainStore,
+        };
+
+        let f = fixture(
+            ReminderPolicy::default()
+                .with_max_reminders(1)
+                .with_escalation(true),
+        )
+        .await;
+        let chains = Arc::new(InMemoryEscalationChainStore::new());
+        let lead = UserId::new();
+        chains
+            .put(
+                &EscalationChain::new(f.request.organization_id).with_tier(EscalationTier::new(
+                    "team_lead",
+                    vec![lead],
+                    3600,
+                )),
+            )
+            .await
+            .unwrap();
+        let service = Arc::new(
+            OversightService::new()
+                .with_request_repository(f.requests.clone())
+                .with_escalation(EscalationEngine::new(chains))
+                .with_clock(f.clock.clone()),
+        );
+        let scheduler = f.scheduler.with_escalation(service);
+
+        f.clock.advance(Duration::hours(1));
+        assert_eq!(scheduler.send_due_reminders().await.unwrap().sent.len(), 1);
+
+        // The second reminder's slot escalates instead
+        f.clock.advance(Duration::hours(1));
+        let report = scheduler.send_due_reminders().await.unwrap();
+        assert!(report.sent.is_empty());
+        assert_eq!(report.escalated, vec![f.request.id]);
+        let stored = f.requests.get(f.request.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, RequestStatus::Escalated);
+        assert_eq!(stored.assigned_reviewers, vec![lead]);
+
+        f.clock.advance(Duration::days(1));
+        let report = scheduler.send_due_reminders().await.unwrap();
+        assert!(report.sent.is_empty() && report.escalated.is_empty());
+        assert_eq!(f.slack.reminder_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reminder_worker_drains_on_shutdown() {
+        let f = fixture(ReminderPolicy::default().with_min_age(0)).await;
+        let scheduler = Arc::new(f.scheduler);
+        let shutdown = ShutdownCoordinator::new();
+        scheduler.spawn(&shutdown, StdDuration::from_millis(5));
+        tokio::time::sleep(StdDuration::from_millis(20)).await;
+
+        let report = shutdown.shutdown(StdDuration::from_secs(1)).await;
+        assert_eq!(
+            report.completed,
+            vec!["oversight.reminder_worker".to_string()]
+        );
+        assert_eq!(f.slack.reminder_count().await, 1);
+    }
+}
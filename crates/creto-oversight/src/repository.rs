@@ -7,11 +7,26 @@
 
 use chrono::{DateTime, Utc};
 use creto_common::{AgentId, CretoError, OrganizationId, UserId};
+use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
 use uuid::Uuid;
 
 use crate::approval::{Approval, ApprovalDecision};
+use crate::assignments::{AssignmentStatus, AssignmentStore, ReviewerAssignment};
+use crate::channels::{ChannelType, ConsumedTokenStore};
+use crate::directory::{ReviewerGroup, ReviewerGroupStatus, ReviewerGroupStore};
+use crate::escalation::{EscalationChain, EscalationChainStore, ExhaustedAction};
+use crate::lifecycle::{ActivityLog, RequestActivity};
+use crate::notifications::{
+    DeferralReason, DigestConfig, NotificationPreferenceStore, NotificationPreferences,
+    PendingDelivery, PendingDeliveryStore,
+};
 use crate::request::{ActionType, OversightRequest, Priority, RequestStatus};
+use crate::template::{AppliedTemplate, RequestTemplate, RequestTemplateStore};
+use crate::webhooks::{
+    DeliveryState, WebhookDelivery, WebhookDeliveryLog, WebhookEndpoint, WebhookEndpointStore,
+};
+use crate::weights::{ReviewerWeight, ReviewerWeightRepository, WeightSubject};
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Enum Serialization Helpers
@@ -69,6 +84,72 @@ impl Priority {
     }
 }
 
+impl ChannelType {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelType::Slack => "slack",
+            ChannelType::Email => "email",
+            ChannelType::Teams => "teams",
+            ChannelType::Sms => "sms",
+            ChannelType::Webhook => "webhook",
+            ChannelType::InApp => "in_app",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "slack" => ChannelType::Slack,
+            "email" => ChannelType::Email,
+            "teams" => ChannelType::Teams,
+            "sms" => ChannelType::Sms,
+            "webhook" => ChannelType::Webhook,
+            _ => ChannelType::InApp,
+        }
+    }
+}
+
+impl DeferralReason {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeferralReason::QuietHours => "quiet_hours",
+            DeferralReason::Digest => "digest",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "digest" => DeferralReason::Digest,
+            _ => DeferralReason::QuietHours,
+        }
+    }
+}
+
+impl DeliveryState {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryState::Pending => "pending",
+            DeliveryState::Delivered => "delivered",
+            DeliveryState::Failed => "failed",
+            DeliveryState::CircuitOpen => "circuit_open",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "delivered" => DeliveryState::Delivered,
+            "failed" => DeliveryState::Failed,
+            "circuit_open" => DeliveryState::CircuitOpen,
+            _ => DeliveryState::Pending,
+        }
+    }
+}
+
 impl ApprovalDecision {
     /// Convert to database string.
     pub fn as_str(&self) -> &'static str {
@@ -94,6 +175,44 @@ impl ApprovalDecision {
     }
 }
 
+impl AssignmentStatus {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssignmentStatus::Active => "active",
+            AssignmentStatus::Responded => "responded",
+            AssignmentStatus::Lapsed => "lapsed",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "responded" => AssignmentStatus::Responded,
+            "lapsed" => AssignmentStatus::Lapsed,
+            _ => AssignmentStatus::Active,
+        }
+    }
+}
+
+impl ReviewerGroupStatus {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewerGroupStatus::Active => "active",
+            ReviewerGroupStatus::Disabled => "disabled",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "disabled" => ReviewerGroupStatus::Disabled,
+            _ => ReviewerGroupStatus::Active,
+        }
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Request Repository
 // ─────────────────────────────────────────────────────────────────────────────
@@ -110,12 +229,28 @@ pub trait RequestRepository: Send + Sync {
     /// Update request status.
     async fn update_status(&self, id: Uuid, status: RequestStatus) -> Result<(), CretoError>;
 
+    /// Update request status if it is still `expected`, returning whether
+    /// it was updated.
+    async fn update_status_if(
+        &self,
+        id: Uuid,
+        expected: RequestStatus,
+        status: RequestStatus,
+    ) -> Result<bool, CretoError>;
+
     /// List pending requests for an organization.
     async fn list_pending(
         &self,
         org_id: OrganizationId,
     ) -> Result<Vec<OversightRequest>, CretoError>;
 
+    /// Raise or lower a request's priority, keeping the priority it was
+    /// created with as its original.
+    async fn update_priority(&self, id: Uuid, priority: Priority) -> Result<(), CretoError>;
+
+    /// List pending requests across all organizations, oldest first.
+    async fn list_open(&self) -> Result<Vec<OversightRequest>, CretoError>;
+
     /// List requests by agent.
     async fn list_by_agent(
         &self,
@@ -123,8 +258,224 @@ pub trait RequestRepository: Send + Sync {
         limit: i64,
     ) -> Result<Vec<OversightRequest>, CretoError>;
 
-    /// Find timed-out requests.
+    /// Hand a request to an escalation tier: mark it escalated to `level`,
+    /// replace its reviewers and give it a new timeout.
+    async fn escalate(
+        &self,
+        id: Uuid,
+        level: u32,
+        reviewers: &[UserId],
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), CretoError>;
+
+    /// Count a reminder sent at `at`, returning the request's new reminder
+    /// count.
+    async fn record_reminder(&self, id: Uuid, at: DateTime<Utc>) -> Result<u32, CretoError>;
+
+    /// Record the channels a request was delivered on.
+    async fn set_notified_channels(
+        &self,
+        id: Uuid,
+        channels: &[ChannelType],
+    ) -> Result<(), CretoError>;
+
+    /// Find open requests past their timeout.
     async fn find_timed_out(&self) -> Result<Vec<Uuid>, CretoError>;
+
+    /// Find all requests sharing a correlation ID, oldest first.
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<OversightRequest>, CretoError>;
+}
+
+/// In-memory request repository for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryRequestRepository {
+    requests: std::sync::RwLock<std::collections::HashMap<Uuid, OversightRequest>>,
+}
+
+impl InMemoryRequestRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl RequestRepository for InMemoryRequestRepository {
+    async fn create(&self, request: &OversightRequest) -> Result<Uuid, CretoError> {
+        self.requests
+            .write()
+            .unwrap()
+            .insert(request.id, request.clone());
+        Ok(request.id)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<OversightRequest>, CretoError> {
+        Ok(self.requests.read().unwrap().get(&id).cloned())
+    }
+
+    async fn update_status(&self, id: Uuid, status: RequestStatus) -> Result<(), CretoError> {
+        let mut requests = self.requests.write().unwrap();
+        let request = requests
+            .get_mut(&id)
+            .ok_or_else(|| CretoError::ApprovalNotFound(id.to_string()))?;
+        request.status = status;
+        request.updated_at = Utc::now();
+        Ok(())
+    }
+
+    async fn update_status_if(
+        &self,
+        id: Uuid,
+        expected: RequestStatus,
+        status: RequestStatus,
+    ) -> Result<bool, CretoError> {
+        let mut requests = self.requests.write().unwrap();
+        let request = requests
+            .get_mut(&id)
+            .ok_or_else(|| CretoError::ApprovalNotFound(id.to_string()))?;
+        if request.status != expected {
+            return Ok(false);
+        }
+        request.status = status;
+        request.updated_at = Utc::now();
+        Ok(true)
+    }
+
+    async fn update_priority(&self, id: Uuid, priority: Priority) -> Result<(), CretoError> {
+        let mut requests = self.requests.write().unwrap();
+        let request = requests
+            .get_mut(&id)
+            .ok_or_else(|| CretoError::ApprovalNotFound(id.to_string()))?;
+        request.original_priority = Some(request.original_priority());
+        request.priority = priority;
+        request.updated_at = Utc::now();
+        Ok(())
+    }
+
+    async fn list_pending(
+        &self,
+        org_id: OrganizationId,
+    ) -> Result<Vec<OversightRequest>, CretoError> {
+        let mut pending: Vec<OversightRequest> = self
+            .requests
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.organization_id == org_id && r.is_pending())
+            .cloned()
+            .collect();
+        pending.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.created_at.cmp(&b.created_at))
+        });
+        Ok(pending)
+    }
+
+    async fn list_open(&self) -> Result<Vec<OversightRequest>, CretoError> {
+        let mut open: Vec<OversightRequest> = self
+            .requests
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.is_pending())
+            .cloned()
+            .collect();
+        open.sort_by_key(|r| r.created_at);
+        Ok(open)
+    }
+
+    async fn list_by_agent(
+        &self,
+        agent_id: AgentId,
+        limit: i64,
+    ) -> Result<Vec<OversightRequest>, CretoError> {
+        let mut requests: Vec<OversightRequest> = self
+            .requests
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.agent_id == agent_id)
+            .cloned()
+            .collect();
+        requests.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+        requests.truncate(limit.max(0) as usize);
+        Ok(requests)
+    }
+
+    async fn escalate(
+        &self,
+        id: Uuid,
+        level: u32,
+        reviewers: &[UserId],
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), CretoError> {
+        let mut requests = self.requests.write().unwrap();
+        let request = requests
+            .get_mut(&id)
+            .ok_or_else(|| CretoError::ApprovalNotFound(id.to_string()))?;
+        let now = Utc::now();
+        request.status = RequestStatus::Escalated;
+        request.escalation_level = level;
+        request.assigned_reviewers = reviewers.to_vec();
+        request.expires_at = expires_at;
+        request.updated_at = now;
+        Ok(())
+    }
+
+    async fn record_reminder(&self, id: Uuid, at: DateTime<Utc>) -> Result<u32, CretoError> {
+        let mut requests = self.requests.write().unwrap();
+        let request = requests
+            .get_mut(&id)
+            .ok_or_else(|| CretoError::ApprovalNotFound(id.to_string()))?;
+        request.reminder_count += 1;
+        request.last_reminded_at = Some(at);
+        Ok(request.reminder_count)
+    }
+
+    async fn set_notified_channels(
+        &self,
+        id: Uuid,
+        channels: &[ChannelType],
+    ) -> Result<(), CretoError> {
+        let mut requests = self.requests.write().unwrap();
+        let request = requests
+            .get_mut(&id)
+            .ok_or_else(|| CretoError::ApprovalNotFound(id.to_string()))?;
+        request.notified_channels = channels.to_vec();
+        Ok(())
+    }
+
+    async fn find_timed_out(&self) -> Result<Vec<Uuid>, CretoError> {
+        let now = Utc::now();
+        Ok(self
+            .requests
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| !r.status.is_terminal() && r.expires_at < now)
+            .map(|r| r.id)
+            .collect())
+    }
+
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<OversightRequest>, CretoError> {
+        let mut requests: Vec<OversightRequest> = self
+            .requests
+            .read()
+            .unwrap()
+            .values()
+            .filter(|r| r.correlation_id == Some(correlation_id))
+            .cloned()
+            .collect();
+        requests.sort_by_key(|r| r.created_at);
+        Ok(requests)
+    }
 }
 
 /// PostgreSQL implementation of RequestRepository.
@@ -144,13 +495,20 @@ impl RequestRepository for PgRequestRepository {
         // Serialize action_type as JSON for flexibility
         let action_type_json = serde_json::to_value(&request.action_type)
             .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        let template_json = request
+            .template
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
 
         let row = sqlx::query(
             r#"
             INSERT INTO oversight_requests (
                 organization_id, agent_id, action_type, action_data,
-                description, status, priority, context, timeout_at
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                description, status, priority, context, timeout_at,
+                correlation_id, caused_by, template
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
             RETURNING id
             "#,
         )
@@ -163,6 +521,9 @@ impl RequestRepository for PgRequestRepository {
         .bind(request.priority.as_str())
         .bind(&request.context)
         .bind(request.expires_at)
+        .bind(request.correlation_id)
+        .bind(request.caused_by)
+        .bind(&template_json)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
@@ -174,7 +535,10 @@ impl RequestRepository for PgRequestRepository {
         let row = sqlx::query(
             r#"
             SELECT organization_id, agent_id, action_type, action_data,
-                   description, status, priority, context, timeout_at, created_at, updated_at
+                   description, status, priority, context, timeout_at, created_at, updated_at,
+                   correlation_id, caused_by, template, original_priority,
+                   escalation_level, assigned_reviewers, reminder_count, last_reminded_at,
+                   notified_channels
             FROM oversight_requests
             WHERE id = $1
             "#,
@@ -205,8 +569,16 @@ impl RequestRepository for PgRequestRepository {
                     updated_at: r.get("updated_at"),
                     timeout_seconds: 86400, // Default
                     expires_at: r.get("timeout_at"),
-                    assigned_reviewers: vec![],
+                    assigned_reviewers: assigned_reviewers_from_column(&r),
                     metadata: serde_json::Value::Object(serde_json::Map::new()),
+                    correlation_id: r.get("correlation_id"),
+                    caused_by: r.get("caused_by"),
+                    template: template_from_column(&r)?,
+                    original_priority: original_priority_from_column(&r),
+                    escalation_level: r.get::<i32, _>("escalation_level") as u32,
+                    reminder_count: r.get::<i32, _>("reminder_count") as u32,
+                    last_reminded_at: r.get("last_reminded_at"),
+                    notified_channels: notified_channels_from_column(&r),
                 }))
             }
             None => Ok(None),
@@ -230,6 +602,51 @@ impl RequestRepository for PgRequestRepository {
         Ok(())
     }
 
+    async fn update_status_if(
+        &self,
+        id: Uuid,
+        expected: RequestStatus,
+        status: RequestStatus,
+    ) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE oversight_requests
+            SET status = $3, updated_at = NOW()
+            WHERE id = $1 AND status = $2
+            "#,
+        )
+        .bind(id)
+        .bind(expected.as_str())
+        .bind(status.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn update_priority(&self, id: Uuid, priority: Priority) -> Result<(), CretoError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE oversight_requests
+            SET original_priority = COALESCE(original_priority, priority),
+                priority = $2,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(priority.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(CretoError::ApprovalNotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
     async fn list_pending(
         &self,
         org_id: OrganizationId,
@@ -237,7 +654,10 @@ impl RequestRepository for PgRequestRepository {
         let rows = sqlx::query(
             r#"
             SELECT id, agent_id, action_type, action_data, description,
-                   status, priority, context, timeout_at, created_at, updated_at
+                   status, priority, context, timeout_at, created_at, updated_at,
+                   correlation_id, caused_by, template, original_priority,
+                   escalation_level, assigned_reviewers, reminder_count, last_reminded_at,
+                   notified_channels
             FROM oversight_requests
             WHERE organization_id = $1 AND status IN ('pending', 'in_review')
             ORDER BY
@@ -276,8 +696,70 @@ impl RequestRepository for PgRequestRepository {
                 updated_at: r.get("updated_at"),
                 timeout_seconds: 86400,
                 expires_at: r.get("timeout_at"),
-                assigned_reviewers: vec![],
+                assigned_reviewers: assigned_reviewers_from_column(&r),
+                metadata: serde_json::Value::Object(serde_json::Map::new()),
+                correlation_id: r.get("correlation_id"),
+                caused_by: r.get("caused_by"),
+                template: template_from_column(&r)?,
+                original_priority: original_priority_from_column(&r),
+                escalation_level: r.get::<i32, _>("escalation_level") as u32,
+                reminder_count: r.get::<i32, _>("reminder_count") as u32,
+                last_reminded_at: r.get("last_reminded_at"),
+                notified_channels: notified_channels_from_column(&r),
+            });
+        }
+
+        Ok(requests)
+    }
+
+    async fn list_open(&self) -> Result<Vec<OversightRequest>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, agent_id, action_type, action_data, description,
+                   status, priority, context, timeout_at, created_at, updated_at,
+                   correlation_id, caused_by, template, original_priority,
+                   escalation_level, assigned_reviewers, reminder_count, last_reminded_at,
+                   notified_channels
+            FROM oversight_requests
+            WHERE status IN ('pending', 'in_review')
+            ORDER BY created_at ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let mut requests = Vec::with_capacity(rows.len());
+        for r in rows {
+            let action_data: serde_json::Value = r.get("action_data");
+            let action_type: ActionType =
+                serde_json::from_value(action_data).unwrap_or(ActionType::Custom {
+                    type_id: "unknown".to_string(),
+                });
+
+            requests.push(OversightRequest {
+                id: r.get("id"),
+                organization_id: OrganizationId::from_uuid(r.get::<Uuid, _>("organization_id")),
+                agent_id: AgentId::from_uuid(r.get::<Uuid, _>("agent_id")),
+                action_type,
+                description: r.get("description"),
+                context: r.get("context"),
+                status: RequestStatus::parse_db_str(r.get::<&str, _>("status")),
+                priority: Priority::parse_db_str(r.get::<&str, _>("priority")),
+                created_at: r.get("created_at"),
+                updated_at: r.get("updated_at"),
+                timeout_seconds: 86400,
+                expires_at: r.get("timeout_at"),
+                assigned_reviewers: assigned_reviewers_from_column(&r),
                 metadata: serde_json::Value::Object(serde_json::Map::new()),
+                correlation_id: r.get("correlation_id"),
+                caused_by: r.get("caused_by"),
+                template: template_from_column(&r)?,
+                original_priority: original_priority_from_column(&r),
+                escalation_level: r.get::<i32, _>("escalation_level") as u32,
+                reminder_count: r.get::<i32, _>("reminder_count") as u32,
+                last_reminded_at: r.get("last_reminded_at"),
+                notified_channels: notified_channels_from_column(&r),
             });
         }
 
@@ -292,7 +774,10 @@ impl RequestRepository for PgRequestRepository {
         let rows = sqlx::query(
             r#"
             SELECT id, organization_id, action_type, action_data, description,
-                   status, priority, context, timeout_at, created_at, updated_at
+                   status, priority, context, timeout_at, created_at, updated_at,
+                   correlation_id, caused_by, template, original_priority,
+                   escalation_level, assigned_reviewers, reminder_count, last_reminded_at,
+                   notified_channels
             FROM oversight_requests
             WHERE agent_id = $1
             ORDER BY created_at DESC
@@ -326,14 +811,100 @@ impl RequestRepository for PgRequestRepository {
                 updated_at: r.get("updated_at"),
                 timeout_seconds: 86400,
                 expires_at: r.get("timeout_at"),
-                assigned_reviewers: vec![],
+                assigned_reviewers: assigned_reviewers_from_column(&r),
                 metadata: serde_json::Value::Object(serde_json::Map::new()),
+                correlation_id: r.get("correlation_id"),
+                caused_by: r.get("caused_by"),
+                template: template_from_column(&r)?,
+                original_priority: original_priority_from_column(&r),
+                escalation_level: r.get::<i32, _>("escalation_level") as u32,
+                reminder_count: r.get::<i32, _>("reminder_count") as u32,
+                last_reminded_at: r.get("last_reminded_at"),
+                notified_channels: notified_channels_from_column(&r),
             });
         }
 
         Ok(requests)
     }
 
+    async fn escalate(
+        &self,
+        id: Uuid,
+        level: u32,
+        reviewers: &[UserId],
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), CretoError> {
+        let reviewer_ids: Vec<Uuid> = reviewers.iter().map(|r| *r.as_uuid()).collect();
+        let result = sqlx::query(
+            r#"
+            UPDATE oversight_requests
+            SET status = 'escalated',
+                escalation_level = $2,
+                assigned_reviewers = $3,
+                timeout_at = $4,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(level as i32)
+        .bind(&reviewer_ids)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(CretoError::ApprovalNotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn record_reminder(&self, id: Uuid, at: DateTime<Utc>) -> Result<u32, CretoError> {
+        let row = sqlx::query(
+            r#"
+            UPDATE oversight_requests
+            SET reminder_count = reminder_count + 1,
+                last_reminded_at = $2
+            WHERE id = $1
+            RETURNING reminder_count
+            "#,
+        )
+        .bind(id)
+        .bind(at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?
+        .ok_or_else(|| CretoError::ApprovalNotFound(id.to_string()))?;
+
+        Ok(row.get::<i32, _>("reminder_count") as u32)
+    }
+
+    async fn set_notified_channels(
+        &self,
+        id: Uuid,
+        channels: &[ChannelType],
+    ) -> Result<(), CretoError> {
+        let channels: Vec<&str> = channels.iter().map(ChannelType::as_str).collect();
+        let result = sqlx::query(
+            r#"
+            UPDATE oversight_requests
+            SET notified_channels = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(&channels)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(CretoError::ApprovalNotFound(id.to_string()));
+        }
+        Ok(())
+    }
+
     async fn find_timed_out(&self) -> Result<Vec<Uuid>, CretoError> {
         let now = Utc::now();
 
@@ -341,7 +912,7 @@ impl RequestRepository for PgRequestRepository {
             r#"
             SELECT id
             FROM oversight_requests
-            WHERE status = 'pending' AND timeout_at < $1
+            WHERE status IN ('pending', 'in_review', 'escalated') AND timeout_at < $1
             "#,
         )
         .bind(now)
@@ -351,11 +922,69 @@ impl RequestRepository for PgRequestRepository {
 
         Ok(rows.into_iter().map(|r| r.get("id")).collect())
     }
-}
-
-// ─────────────────────────────────────────────────────────────────────────────
-// Approval Repository
-// ─────────────────────────────────────────────────────────────────────────────
+
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<OversightRequest>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, agent_id, action_type, action_data, description,
+                   status, priority, context, timeout_at, created_at, updated_at,
+                   correlation_id, caused_by, template, original_priority,
+                   escalation_level, assigned_reviewers, reminder_count, last_reminded_at,
+                   notified_channels
+            FROM oversight_requests
+            WHERE correlation_id = $1
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(correlation_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let mut requests = Vec::with_capacity(rows.len());
+        for r in rows {
+            let action_data: serde_json::Value = r.get("action_data");
+            let action_type: ActionType =
+                serde_json::from_value(action_data).unwrap_or(ActionType::Custom {
+                    type_id: "unknown".to_string(),
+                });
+
+            requests.push(OversightRequest {
+                id: r.get("id"),
+                organization_id: OrganizationId::from_uuid(r.get::<Uuid, _>("organization_id")),
+                agent_id: AgentId::from_uuid(r.get::<Uuid, _>("agent_id")),
+                action_type,
+                description: r.get("description"),
+                context: r.get("context"),
+                status: RequestStatus::parse_db_str(r.get::<&str, _>("status")),
+                priority: Priority::parse_db_str(r.get::<&str, _>("priority")),
+                created_at: r.get("created_at"),
+                updated_at: r.get("updated_at"),
+                timeout_seconds: 86400,
+                expires_at: r.get("timeout_at"),
+                assigned_reviewers: assigned_reviewers_from_column(&r),
+                metadata: serde_json::Value::Object(serde_json::Map::new()),
+                correlation_id: r.get("correlation_id"),
+                caused_by: r.get("caused_by"),
+                template: template_from_column(&r)?,
+                original_priority: original_priority_from_column(&r),
+                escalation_level: r.get::<i32, _>("escalation_level") as u32,
+                reminder_count: r.get::<i32, _>("reminder_count") as u32,
+                last_reminded_at: r.get("last_reminded_at"),
+                notified_channels: notified_channels_from_column(&r),
+            });
+        }
+
+        Ok(requests)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Approval Repository
+// ─────────────────────────────────────────────────────────────────────────────
 
 /// Repository for approval persistence.
 #[async_trait::async_trait]
@@ -381,6 +1010,76 @@ pub struct ApprovalCounts {
     pub total_weight: i64,
 }
 
+/// In-memory approval repository for testing and development.
+///
+/// Like the PostgreSQL implementation, a reviewer's later decision on a
+/// request replaces their earlier one.
+#[derive(Debug, Default)]
+pub struct InMemoryApprovalRepository {
+    approvals: std::sync::RwLock<Vec<Approval>>,
+}
+
+impl InMemoryApprovalRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl ApprovalRepository for InMemoryApprovalRepository {
+    async fn create(&self, approval: &Approval) -> Result<Uuid, CretoError> {
+        let mut approvals = self.approvals.write().unwrap();
+        match approvals
+            .iter_mut()
+            .find(|a| a.request_id == approval.request_id && a.reviewer_id == approval.reviewer_id)
+        {
+            Some(existing) => {
+                existing.decision = approval.decision;
+                existing.reason = approval.reason.clone();
+                existing.decided_at = Utc::now();
+                Ok(existing.id)
+            }
+            None => {
+                approvals.push(approval.clone());
+                Ok(approval.id)
+            }
+        }
+    }
+
+    async fn list_by_request(&self, request_id: Uuid) -> Result<Vec<Approval>, CretoError> {
+        let mut approvals: Vec<Approval> = self
+            .approvals
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|a| a.request_id == request_id)
+            .cloned()
+            .collect();
+        approvals.sort_by_key(|a| a.decided_at);
+        Ok(approvals)
+    }
+
+    async fn count_by_decision(&self, request_id: Uuid) -> Result<ApprovalCounts, CretoError> {
+        let approvals = self.list_by_request(request_id).await?;
+        Ok(approvals
+            .iter()
+            .fold(ApprovalCounts::default(), |mut counts, a| {
+                match a.decision {
+                    ApprovalDecision::Approve => {
+                        counts.approve += 1;
+                        counts.total_weight += i64::from(a.weight);
+                    }
+                    ApprovalDecision::Reject => counts.reject += 1,
+                    ApprovalDecision::Abstain => counts.abstain += 1,
+                    ApprovalDecision::RequestInfo => counts.request_info += 1,
+                    ApprovalDecision::Escalate => counts.escalate += 1,
+                }
+                counts
+            }))
+    }
+}
+
 /// PostgreSQL implementation of ApprovalRepository.
 pub struct PgApprovalRepository {
     pool: PgPool,
@@ -479,7 +1178,7 @@ impl ApprovalRepository for PgApprovalRepository {
 // ─────────────────────────────────────────────────────────────────────────────
 
 /// Record of a state transition in the audit trail.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StateTransitionRecord {
     pub id: Uuid,
     pub request_id: Uuid,
@@ -504,6 +1203,43 @@ pub trait StateTransitionRepository: Send + Sync {
     ) -> Result<Vec<StateTransitionRecord>, CretoError>;
 }
 
+/// In-memory state transition repository for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryStateTransitionRepository {
+    records: std::sync::RwLock<Vec<StateTransitionRecord>>,
+}
+
+impl InMemoryStateTransitionRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl StateTransitionRepository for InMemoryStateTransitionRepository {
+    async fn create(&self, record: &StateTransitionRecord) -> Result<Uuid, CretoError> {
+        self.records.write().unwrap().push(record.clone());
+        Ok(record.id)
+    }
+
+    async fn list_by_request(
+        &self,
+        request_id: Uuid,
+    ) -> Result<Vec<StateTransitionRecord>, CretoError> {
+        let mut records: Vec<StateTransitionRecord> = self
+            .records
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.request_id == request_id)
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.transitioned_at);
+        Ok(records)
+    }
+}
+
 /// PostgreSQL implementation of StateTransitionRepository.
 pub struct PgStateTransitionRepository {
     pool: PgPool,
@@ -821,46 +1557,1274 @@ impl QuorumConfigRepository for PgQuorumConfigRepository {
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
-// Helper Functions
+// Request Template Repository
 // ─────────────────────────────────────────────────────────────────────────────
 
-fn action_type_to_str(action_type: &ActionType) -> &'static str {
-    match action_type {
-        ActionType::Transaction { .. } => "transaction",
-        ActionType::DataAccess { .. } => "data_access",
-        ActionType::ExternalApi { .. } => "external_api",
-        ActionType::CodeExecution { .. } => "code_execution",
-        ActionType::Communication { .. } => "communication",
-        ActionType::Custom { .. } => "custom",
+/// PostgreSQL implementation of RequestTemplateStore.
+pub struct PgRequestTemplateRepository {
+    pool: PgPool,
+}
+
+impl PgRequestTemplateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn template_from_row(row: &sqlx::postgres::PgRow) -> Result<RequestTemplate, CretoError> {
+        let to_json = |e: serde_json::Error| CretoError::SerializationError(e.to_string());
+        let quorum: Option<serde_json::Value> = row.get("quorum");
+
+        Ok(RequestTemplate {
+            id: row.get("id"),
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            name: row.get("name"),
+            version: row.get::<i32, _>("version") as u32,
+            action_pattern: serde_json::from_value(row.get("action_pattern")).map_err(to_json)?,
+            fields: serde_json::from_value(row.get("fields")).map_err(to_json)?,
+            default_priority: Priority::parse_db_str(row.get::<&str, _>("default_priority")),
+            default_timeout_seconds: row
+                .get::<Option<i64>, _>("default_timeout_seconds")
+                .map(|s| s as u64),
+            quorum: quorum
+                .map(serde_json::from_value)
+                .transpose()
+                .map_err(to_json)?,
+            reviewer_group: row.get("reviewer_group"),
+            updated_at: row.get("updated_at"),
+        })
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Serialized JSONB columns of a request template.
+struct TemplateColumns {
+    action_pattern: serde_json::Value,
+    fields: serde_json::Value,
+    quorum: Option<serde_json::Value>,
+}
 
-    #[test]
-    fn test_request_status_roundtrip() {
-        assert_eq!(
-            RequestStatus::parse_db_str("pending"),
-            RequestStatus::Pending
-        );
-        assert_eq!(RequestStatus::Pending.as_str(), "pending");
+impl TemplateColumns {
+    fn encode(template: &RequestTemplate) -> Result<Self, CretoError> {
+        let to_json = |e: serde_json::Error| CretoError::SerializationError(e.to_string());
+        Ok(Self {
+            action_pattern: serde_json::to_value(&template.action_pattern).map_err(to_json)?,
+            fields: serde_json::to_value(&template.fields).map_err(to_json)?,
+            quorum: template
+                .quorum
+                .as_ref()
+                .map(serde_json::to_value)
+                .transpose()
+                .map_err(to_json)?,
+        })
     }
+}
 
-    #[test]
-    fn test_priority_roundtrip() {
-        assert_eq!(Priority::parse_db_str("high"), Priority::High);
-        assert_eq!(Priority::High.as_str(), "high");
+#[async_trait::async_trait]
+impl RequestTemplateStore for PgRequestTemplateRepository {
+    async fn create(&self, template: RequestTemplate) -> Result<RequestTemplate, CretoError> {
+        let columns = TemplateColumns::encode(&template)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oversight_request_templates (
+                id, organization_id, name, version, action_pattern, fields,
+                default_priority, default_timeout_seconds, quorum, reviewer_group, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(template.id)
+        .bind(template.organization_id.as_uuid())
+        .bind(&template.name)
+        .bind(template.version as i32)
+        .bind(&columns.action_pattern)
+        .bind(&columns.fields)
+        .bind(template.default_priority.as_str())
+        .bind(template.default_timeout_seconds.map(|s| s as i64))
+        .bind(&columns.quorum)
+        .bind(&template.reviewer_group)
+        .bind(template.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(template)
     }
 
-    #[test]
-    fn test_approval_decision_roundtrip() {
-        assert_eq!(
-            ApprovalDecision::parse_db_str("approve"),
-            ApprovalDecision::Approve
-        );
-        assert_eq!(ApprovalDecision::Approve.as_str(), "approve");
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        id: Uuid,
+    ) -> Result<Option<RequestTemplate>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, organization_id, name, version, action_pattern, fields,
+                   default_priority, default_timeout_seconds, quorum, reviewer_group, updated_at
+            FROM oversight_request_templates
+            WHERE organization_id = $1 AND id = $2
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| Self::template_from_row(&r)).transpose()
+    }
+
+    async fn update(&self, template: RequestTemplate) -> Result<RequestTemplate, CretoError> {
+        let columns = TemplateColumns::encode(&template)?;
+
+        let row = sqlx::query(
+            r#"
+            UPDATE oversight_request_templates
+            SET name = $3, action_pattern = $4, fields = $5, default_priority = $6,
+                default_timeout_seconds = $7, quorum = $8, reviewer_group = $9,
+                version = version + 1, updated_at = NOW()
+            WHERE organization_id = $1 AND id = $2
+            RETURNING id, organization_id, name, version, action_pattern, fields,
+                      default_priority, default_timeout_seconds, quorum, reviewer_group, updated_at
+            "#,
+        )
+        .bind(template.organization_id.as_uuid())
+        .bind(template.id)
+        .bind(&template.name)
+        .bind(&columns.action_pattern)
+        .bind(&columns.fields)
+        .bind(template.default_priority.as_str())
+        .bind(template.default_timeout_seconds.map(|s| s as i64))
+        .bind(&columns.quorum)
+        .bind(&template.reviewer_group)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?
+        .ok_or_else(|| CretoError::NotFound(format!("request template {}", template.id)))?;
+
+        Self::template_from_row(&row)
+    }
+
+    async fn list(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<Vec<RequestTemplate>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, name, version, action_pattern, fields,
+                   default_priority, default_timeout_seconds, quorum, reviewer_group, updated_at
+            FROM oversight_request_templates
+            WHERE organization_id = $1
+            ORDER BY name ASC
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::template_from_row).collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Notification Preference Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of NotificationPreferenceStore and
+/// PendingDeliveryStore.
+///
+/// Organization defaults are stored with the nil UUID as reviewer.
+pub struct PgNotificationPreferenceRepository {
+    pool: PgPool,
+}
+
+impl PgNotificationPreferenceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn reviewer_key(reviewer_id: Option<UserId>) -> Uuid {
+        reviewer_id.map(|r| *r.as_uuid()).unwrap_or(Uuid::nil())
+    }
+
+    fn preferences_from_row(
+        row: &sqlx::postgres::PgRow,
+    ) -> Result<NotificationPreferences, CretoError> {
+        let to_json = |e: serde_json::Error| CretoError::SerializationError(e.to_string());
+        let reviewer: Uuid = row.get("reviewer_id");
+        let quiet_hours: Option<serde_json::Value> = row.get("quiet_hours");
+
+        Ok(NotificationPreferences {
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            reviewer_id: (!reviewer.is_nil()).then(|| UserId::from_uuid(reviewer)),
+            channels: serde_json::from_value(row.get("channels")).map_err(to_json)?,
+            quiet_hours: quiet_hours
+                .map(serde_json::from_value)
+                .transpose()
+                .map_err(to_json)?,
+            digest: row
+                .get::<Option<i32>, _>("digest_interval_hours")
+                .map(|h| DigestConfig::every_hours(h as u32)),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    fn delivery_from_row(row: &sqlx::postgres::PgRow) -> Result<PendingDelivery, CretoError> {
+        Ok(PendingDelivery {
+            id: row.get("id"),
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            reviewer_id: row
+                .get::<Option<Uuid>, _>("reviewer_id")
+                .map(UserId::from_uuid),
+            channel: ChannelType::parse_db_str(row.get::<&str, _>("channel")),
+            request: serde_json::from_value(row.get("request"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            reason: DeferralReason::parse_db_str(row.get::<&str, _>("reason")),
+            deliver_at: row.get("deliver_at"),
+            queued_at: row.get("queued_at"),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl NotificationPreferenceStore for PgNotificationPreferenceRepository {
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: Option<UserId>,
+    ) -> Result<Option<NotificationPreferences>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT organization_id, reviewer_id, channels, quiet_hours,
+                   digest_interval_hours, updated_at
+            FROM oversight_notification_preferences
+            WHERE organization_id = $1 AND reviewer_id = $2
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(Self::reviewer_key(reviewer_id))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| Self::preferences_from_row(&r)).transpose()
+    }
+
+    async fn put(
+        &self,
+        preferences: NotificationPreferences,
+    ) -> Result<NotificationPreferences, CretoError> {
+        let to_json = |e: serde_json::Error| CretoError::SerializationError(e.to_string());
+        let channels = serde_json::to_value(&preferences.channels).map_err(to_json)?;
+        let quiet_hours = preferences
+            .quiet_hours
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(to_json)?;
+
+        let row = sqlx::query(
+            r#"
+            INSERT INTO oversight_notification_preferences (
+                organization_id, reviewer_id, channels, quiet_hours,
+                digest_interval_hours, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, NOW())
+            ON CONFLICT (organization_id, reviewer_id) DO UPDATE SET
+                channels = EXCLUDED.channels,
+                quiet_hours = EXCLUDED.quiet_hours,
+                digest_interval_hours = EXCLUDED.digest_interval_hours,
+                updated_at = EXCLUDED.updated_at
+            RETURNING organization_id, reviewer_id, channels, quiet_hours,
+                      digest_interval_hours, updated_at
+            "#,
+        )
+        .bind(preferences.organization_id.as_uuid())
+        .bind(Self::reviewer_key(preferences.reviewer_id))
+        .bind(&channels)
+        .bind(&quiet_hours)
+        .bind(preferences.digest.map(|d| d.interval_hours as i32))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Self::preferences_from_row(&row)
+    }
+
+    async fn delete(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: Option<UserId>,
+    ) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM oversight_notification_preferences
+            WHERE organization_id = $1 AND reviewer_id = $2
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(Self::reviewer_key(reviewer_id))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait::async_trait]
+impl PendingDeliveryStore for PgNotificationPreferenceRepository {
+    async fn enqueue(&self, delivery: PendingDelivery) -> Result<(), CretoError> {
+        let request = serde_json::to_value(&delivery.request)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oversight_pending_notifications (
+                id, organization_id, reviewer_id, channel, request, reason,
+                deliver_at, queued_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(delivery.organization_id.as_uuid())
+        .bind(delivery.reviewer_id.map(|r| *r.as_uuid()))
+        .bind(delivery.channel.as_str())
+        .bind(&request)
+        .bind(delivery.reason.as_str())
+        .bind(delivery.deliver_at)
+        .bind(delivery.queued_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn due(&self, now: DateTime<Utc>) -> Result<Vec<PendingDelivery>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, reviewer_id, channel, request, reason,
+                   deliver_at, queued_at
+            FROM oversight_pending_notifications
+            WHERE deliver_at <= $1
+            ORDER BY deliver_at ASC, queued_at ASC
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::delivery_from_row).collect()
+    }
+
+    async fn remove(&self, ids: &[Uuid]) -> Result<(), CretoError> {
+        sqlx::query("DELETE FROM oversight_pending_notifications WHERE id = ANY($1)")
+            .bind(ids)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn remove_for_request(
+        &self,
+        request_id: Uuid,
+    ) -> Result<Vec<PendingDelivery>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            DELETE FROM oversight_pending_notifications
+            WHERE request->>'id' = $1
+            RETURNING id, organization_id, reviewer_id, channel, request, reason,
+                      deliver_at, queued_at
+            "#,
+        )
+        .bind(request_id.to_string())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::delivery_from_row).collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Webhook Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of WebhookEndpointStore and WebhookDeliveryLog.
+pub struct PgWebhookRepository {
+    pool: PgPool,
+}
+
+impl PgWebhookRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn endpoint_from_row(row: &sqlx::postgres::PgRow) -> Result<WebhookEndpoint, CretoError> {
+        Ok(WebhookEndpoint {
+            id: row.get("id"),
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            url: row.get("url"),
+            secret: row.get("secret"),
+            filter: serde_json::from_value(row.get("filter"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            enabled: row.get("enabled"),
+            created_at: row.get("created_at"),
+        })
+    }
+
+    fn delivery_from_row(row: &sqlx::postgres::PgRow) -> Result<WebhookDelivery, CretoError> {
+        Ok(WebhookDelivery {
+            id: row.get("id"),
+            endpoint_id: row.get("endpoint_id"),
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            request_id: row.get("request_id"),
+            body: row.get("body"),
+            state: DeliveryState::parse_db_str(row.get::<&str, _>("state")),
+            attempts: serde_json::from_value(row.get("attempts"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookEndpointStore for PgWebhookRepository {
+    async fn put(&self, endpoint: WebhookEndpoint) -> Result<WebhookEndpoint, CretoError> {
+        let filter = serde_json::to_value(&endpoint.filter)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oversight_webhook_endpoints (
+                id, organization_id, url, secret, filter, enabled, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (id) DO UPDATE SET
+                url = EXCLUDED.url,
+                secret = EXCLUDED.secret,
+                filter = EXCLUDED.filter,
+                enabled = EXCLUDED.enabled
+            "#,
+        )
+        .bind(endpoint.id)
+        .bind(endpoint.organization_id.as_uuid())
+        .bind(&endpoint.url)
+        .bind(&endpoint.secret)
+        .bind(&filter)
+        .bind(endpoint.enabled)
+        .bind(endpoint.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(endpoint)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<WebhookEndpoint>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, organization_id, url, secret, filter, enabled, created_at
+            FROM oversight_webhook_endpoints
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| Self::endpoint_from_row(&r)).transpose()
+    }
+
+    async fn list(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<Vec<WebhookEndpoint>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, url, secret, filter, enabled, created_at
+            FROM oversight_webhook_endpoints
+            WHERE organization_id = $1
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::endpoint_from_row).collect()
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, CretoError> {
+        let result = sqlx::query("DELETE FROM oversight_webhook_endpoints WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookDeliveryLog for PgWebhookRepository {
+    async fn record(&self, delivery: &WebhookDelivery) -> Result<(), CretoError> {
+        let attempts = serde_json::to_value(&delivery.attempts)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oversight_webhook_deliveries (
+                id, endpoint_id, organization_id, request_id, body, state, attempts,
+                created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (id) DO UPDATE SET
+                state = EXCLUDED.state,
+                attempts = EXCLUDED.attempts,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(delivery.id)
+        .bind(delivery.endpoint_id)
+        .bind(delivery.organization_id.as_uuid())
+        .bind(delivery.request_id)
+        .bind(&delivery.body)
+        .bind(delivery.state.as_str())
+        .bind(&attempts)
+        .bind(delivery.created_at)
+        .bind(delivery.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<WebhookDelivery>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, endpoint_id, organization_id, request_id, body, state, attempts,
+                   created_at, updated_at
+            FROM oversight_webhook_deliveries
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| Self::delivery_from_row(&r)).transpose()
+    }
+
+    async fn list_for_request(&self, request_id: Uuid) -> Result<Vec<WebhookDelivery>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, endpoint_id, organization_id, request_id, body, state, attempts,
+                   created_at, updated_at
+            FROM oversight_webhook_deliveries
+            WHERE request_id = $1
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(request_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::delivery_from_row).collect()
+    }
+
+    async fn list_pending(
+        &self,
+        updated_before: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<WebhookDelivery>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, endpoint_id, organization_id, request_id, body, state, attempts,
+                   created_at, updated_at
+            FROM oversight_webhook_deliveries
+            WHERE state = 'pending' AND updated_at < $1
+            ORDER BY created_at ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(updated_before)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::delivery_from_row).collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Reviewer Group Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of ReviewerGroupStore.
+pub struct PgReviewerGroupRepository {
+    pool: PgPool,
+}
+
+impl PgReviewerGroupRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn group_from_row(row: &sqlx::postgres::PgRow) -> Result<ReviewerGroup, CretoError> {
+        Ok(ReviewerGroup {
+            id: row.get("id"),
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            name: row.get("name"),
+            external_id: row.get("external_id"),
+            status: ReviewerGroupStatus::parse_db_str(row.get::<&str, _>("status")),
+            memberships: serde_json::from_value(row.get("memberships"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl ReviewerGroupStore for PgReviewerGroupRepository {
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+    ) -> Result<Option<ReviewerGroup>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, organization_id, name, external_id, status, memberships,
+                   created_at, updated_at
+            FROM oversight_reviewer_groups
+            WHERE organization_id = $1 AND name = $2
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| Self::group_from_row(&r)).transpose()
+    }
+
+    async fn list(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<Vec<ReviewerGroup>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, name, external_id, status, memberships,
+                   created_at, updated_at
+            FROM oversight_reviewer_groups
+            WHERE organization_id = $1
+            ORDER BY name
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::group_from_row).collect()
+    }
+
+    async fn put(&self, group: ReviewerGroup) -> Result<(), CretoError> {
+        let memberships = serde_json::to_value(&group.memberships)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oversight_reviewer_groups (
+                id, organization_id, name, external_id, status, memberships,
+                created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (id) DO UPDATE SET
+                name = EXCLUDED.name,
+                external_id = EXCLUDED.external_id,
+                status = EXCLUDED.status,
+                memberships = EXCLUDED.memberships,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(group.id)
+        .bind(group.organization_id.as_uuid())
+        .bind(&group.name)
+        .bind(&group.external_id)
+        .bind(group.status.as_str())
+        .bind(&memberships)
+        .bind(group.created_at)
+        .bind(group.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db) if db.is_unique_violation() => CretoError::ValidationFailed(
+                format!("reviewer group '{}' already exists", group.name),
+            ),
+            e => CretoError::Database(e.to_string()),
+        })?;
+
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Request Activity Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of ActivityLog.
+pub struct PgActivityLogRepository {
+    pool: PgPool,
+}
+
+impl PgActivityLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ActivityLog for PgActivityLogRepository {
+    async fn record(&self, activity: &RequestActivity) -> Result<(), CretoError> {
+        let actor = serde_json::to_value(&activity.actor)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        let kind = serde_json::to_value(&activity.kind)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oversight_request_activity (id, request_id, actor, kind, occurred_at)
+            VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(activity.id)
+        .bind(activity.request_id)
+        .bind(actor)
+        .bind(kind)
+        .bind(activity.occurred_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_by_request(&self, request_id: Uuid) -> Result<Vec<RequestActivity>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, actor, kind, occurred_at
+            FROM oversight_request_activity
+            WHERE request_id = $1
+            ORDER BY occurred_at ASC, id ASC
+            "#,
+        )
+        .bind(request_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|r| {
+                Ok(RequestActivity {
+                    id: r.get("id"),
+                    request_id,
+                    actor: serde_json::from_value(r.get("actor"))
+                        .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                    kind: serde_json::from_value(r.get("kind"))
+                        .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                    occurred_at: r.get("occurred_at"),
+                })
+            })
+            .collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Reviewer Assignment Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of AssignmentStore.
+pub struct PgAssignmentRepository {
+    pool: PgPool,
+}
+
+impl PgAssignmentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn assignment_from_row(row: &sqlx::postgres::PgRow) -> Result<ReviewerAssignment, CretoError> {
+        Ok(ReviewerAssignment {
+            id: row.get("id"),
+            request_id: row.get("request_id"),
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            reviewer_id: UserId::from_uuid(row.get::<Uuid, _>("reviewer_id")),
+            source: serde_json::from_value(row.get("source"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            status: AssignmentStatus::parse_db_str(row.get::<&str, _>("status")),
+            assigned_at: row.get("assigned_at"),
+            respond_by: row.get("respond_by"),
+            lapsed_at: row.get("lapsed_at"),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl AssignmentStore for PgAssignmentRepository {
+    async fn assign(
+        &self,
+        assignment: &ReviewerAssignment,
+    ) -> Result<ReviewerAssignment, CretoError> {
+        let source = serde_json::to_value(&assignment.source)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        // The no-op update makes RETURNING yield the existing row on conflict
+        let row = sqlx::query(
+            r#"
+            INSERT INTO oversight_reviewer_assignments (
+                id, request_id, organization_id, reviewer_id, source, status,
+                assigned_at, respond_by
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (request_id, reviewer_id)
+            DO UPDATE SET request_id = oversight_reviewer_assignments.request_id
+            RETURNING id, request_id, organization_id, reviewer_id, source, status,
+                      assigned_at, respond_by, lapsed_at
+            "#,
+        )
+        .bind(assignment.id)
+        .bind(assignment.request_id)
+        .bind(assignment.organization_id.as_uuid())
+        .bind(assignment.reviewer_id.as_uuid())
+        .bind(&source)
+        .bind(assignment.status.as_str())
+        .bind(assignment.assigned_at)
+        .bind(assignment.respond_by)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Self::assignment_from_row(&row)
+    }
+
+    async fn list_by_request(
+        &self,
+        request_id: Uuid,
+    ) -> Result<Vec<ReviewerAssignment>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, request_id, organization_id, reviewer_id, source, status,
+                   assigned_at, respond_by, lapsed_at
+            FROM oversight_reviewer_assignments
+            WHERE request_id = $1
+            ORDER BY assigned_at ASC
+            "#,
+        )
+        .bind(request_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::assignment_from_row).collect()
+    }
+
+    async fn find_overdue(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ReviewerAssignment>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, request_id, organization_id, reviewer_id, source, status,
+                   assigned_at, respond_by, lapsed_at
+            FROM oversight_reviewer_assignments
+            WHERE status = 'active' AND respond_by <= $1
+            ORDER BY respond_by ASC
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::assignment_from_row).collect()
+    }
+
+    async fn update_status(
+        &self,
+        id: Uuid,
+        status: AssignmentStatus,
+        at: DateTime<Utc>,
+    ) -> Result<(), CretoError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE oversight_reviewer_assignments
+            SET status = $2,
+                lapsed_at = CASE WHEN $2 = 'lapsed' THEN $3 ELSE lapsed_at END
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(status.as_str())
+        .bind(at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(CretoError::NotFound(format!("Assignment {}", id)));
+        }
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Escalation Chain Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of EscalationChainStore.
+pub struct PgEscalationChainRepository {
+    pool: PgPool,
+}
+
+impl PgEscalationChainRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl EscalationChainStore for PgEscalationChainRepository {
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<Option<EscalationChain>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT tiers, on_exhausted
+            FROM oversight_escalation_chains
+            WHERE organization_id = $1
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| {
+            Ok(EscalationChain {
+                organization_id,
+                tiers: serde_json::from_value(r.get("tiers"))
+                    .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                on_exhausted: ExhaustedAction::parse_db_str(r.get::<&str, _>("on_exhausted")),
+            })
+        })
+        .transpose()
+    }
+
+    async fn put(&self, chain: &EscalationChain) -> Result<(), CretoError> {
+        let tiers = serde_json::to_value(&chain.tiers)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oversight_escalation_chains (organization_id, tiers, on_exhausted)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (organization_id) DO UPDATE SET
+                tiers = EXCLUDED.tiers,
+                on_exhausted = EXCLUDED.on_exhausted,
+                updated_at = NOW()
+            "#,
+        )
+        .bind(chain.organization_id.as_uuid())
+        .bind(&tiers)
+        .bind(chain.on_exhausted.as_str())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, organization_id: OrganizationId) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM oversight_escalation_chains
+            WHERE organization_id = $1
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Reviewer Weight Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of ReviewerWeightRepository.
+pub struct PgReviewerWeightRepository {
+    pool: PgPool,
+}
+
+impl PgReviewerWeightRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ReviewerWeightRepository for PgReviewerWeightRepository {
+    async fn list(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<Vec<ReviewerWeight>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT subject_type, subject_id, weight, updated_at
+            FROM oversight_reviewer_weights
+            WHERE organization_id = $1
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter()
+            .map(|r| {
+                let subject_id: String = r.get("subject_id");
+                let subject = match r.get::<&str, _>("subject_type") {
+                    "user" => WeightSubject::User(UserId::from_uuid(
+                        Uuid::parse_str(&subject_id)
+                            .map_err(|e| CretoError::Database(e.to_string()))?,
+                    )),
+                    _ => WeightSubject::Role(subject_id),
+                };
+                Ok(ReviewerWeight {
+                    organization_id,
+                    subject,
+                    weight: r.get::<i32, _>("weight") as u32,
+                    updated_at: r.get("updated_at"),
+                })
+            })
+            .collect()
+    }
+
+    async fn put(&self, weight: &ReviewerWeight) -> Result<(), CretoError> {
+        weight.validate()?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO oversight_reviewer_weights
+                (organization_id, subject_type, subject_id, weight, updated_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (organization_id, subject_type, subject_id) DO UPDATE SET
+                weight = EXCLUDED.weight,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(weight.organization_id.as_uuid())
+        .bind(weight.subject.kind())
+        .bind(weight.subject.id())
+        .bind(weight.weight as i32)
+        .bind(weight.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        organization_id: OrganizationId,
+        subject: &WeightSubject,
+    ) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM oversight_reviewer_weights
+            WHERE organization_id = $1 AND subject_type = $2 AND subject_id = $3
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(subject.kind())
+        .bind(subject.id())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Consumed Approval Token Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of ConsumedTokenStore.
+pub struct PgConsumedTokenRepository {
+    pool: PgPool,
+}
+
+impl PgConsumedTokenRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ConsumedTokenStore for PgConsumedTokenRepository {
+    async fn consume(
+        &self,
+        signature: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, CretoError> {
+        sqlx::query("DELETE FROM oversight_consumed_approval_tokens WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO oversight_consumed_approval_tokens (signature, expires_at, consumed_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (signature) DO NOTHING
+            "#,
+        )
+        .bind(signature)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn is_consumed(&self, signature: &str) -> Result<bool, CretoError> {
+        let row = sqlx::query(
+            "SELECT 1 AS consumed FROM oversight_consumed_approval_tokens WHERE signature = $1",
+        )
+        .bind(signature)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.is_some())
+    }
+
+    async fn release(&self, signature: &str) -> Result<(), CretoError> {
+        sqlx::query("DELETE FROM oversight_consumed_approval_tokens WHERE signature = $1")
+            .bind(signature)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Helper Functions
+// ─────────────────────────────────────────────────────────────────────────────
+
+fn action_type_to_str(action_type: &ActionType) -> &'static str {
+    match action_type {
+        ActionType::Transaction { .. } => "transaction",
+        ActionType::DataAccess { .. } => "data_access",
+        ActionType::ExternalApi { .. } => "external_api",
+        ActionType::CodeExecution { .. } => "code_execution",
+        ActionType::Communication { .. } => "communication",
+        ActionType::Custom { .. } => "custom",
+    }
+}
+
+/// Decode the template snapshot column of an oversight request row.
+fn template_from_column(
+    row: &sqlx::postgres::PgRow,
+) -> Result<Option<AppliedTemplate>, CretoError> {
+    row.get::<Option<serde_json::Value>, _>("template")
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| CretoError::SerializationError(e.to_string()))
+}
+
+/// Decode the original priority column of an oversight request row.
+fn original_priority_from_column(row: &sqlx::postgres::PgRow) -> Option<Priority> {
+    row.get::<Option<&str>, _>("original_priority")
+        .map(Priority::parse_db_str)
+}
+
+/// Decode the assigned reviewers column of an oversight request row.
+fn assigned_reviewers_from_column(row: &sqlx::postgres::PgRow) -> Vec<UserId> {
+    row.get::<Vec<Uuid>, _>("assigned_reviewers")
+        .into_iter()
+        .map(UserId::from_uuid)
+        .collect()
+}
+
+/// Decode the notified channels column of an oversight request row.
+fn notified_channels_from_column(row: &sqlx::postgres::PgRow) -> Vec<ChannelType> {
+    row.get::<Vec<String>, _>("notified_channels")
+        .iter()
+        .map(|channel| ChannelType::parse_db_str(channel))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_status_roundtrip() {
+        assert_eq!(
+            RequestStatus::parse_db_str("pending"),
+            RequestStatus::Pending
+        );
+        assert_eq!(RequestStatus::Pending.as_str(), "pending");
+    }
+
+    #[test]
+    fn test_priority_roundtrip() {
+        assert_eq!(Priority::parse_db_str("high"), Priority::High);
+        assert_eq!(Priority::High.as_str(), "high");
+    }
+
+    #[test]
+    fn test_approval_decision_roundtrip() {
+        assert_eq!(
+            ApprovalDecision::parse_db_str("approve"),
+            ApprovalDecision::Approve
+        );
+        assert_eq!(ApprovalDecision::Approve.as_str(), "approve");
+    }
+
+    #[test]
+    fn test_assignment_status_roundtrip() {
+        for status in [
+            AssignmentStatus::Active,
+            AssignmentStatus::Responded,
+            AssignmentStatus::Lapsed,
+        ] {
+            assert_eq!(AssignmentStatus::parse_db_str(status.as_str()), status);
+        }
+    }
+
+    #[test]
+    fn test_reviewer_group_status_roundtrip() {
+        for status in [ReviewerGroupStatus::Active, ReviewerGroupStatus::Disabled] {
+            assert_eq!(ReviewerGroupStatus::parse_db_str(status.as_str()), status);
+        }
     }
 
     #[test]
@@ -1,10 +1,13 @@
 //! Oversight request types.
 
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, OrganizationId, UserId};
+use creto_common::{AgentId, Correlation, OrganizationId, UserId};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::channels::ChannelType;
+use crate::template::AppliedTemplate;
+
 /// A request for human oversight of an agent action.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OversightRequest {
@@ -51,6 +54,39 @@ pub struct OversightRequest {
     /// Metadata for routing and filtering.
     #[serde(default)]
     pub metadata: serde_json::Value,
+
+    /// Trace ID shared with the action that required oversight.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+
+    /// ID of the entity whose action triggered this request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caused_by: Option<Uuid>,
+
+    /// Template version and fields the request was created from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<AppliedTemplate>,
+
+    /// Priority the request was created with, once aging has raised it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_priority: Option<Priority>,
+
+    /// Escalation tiers the request has been handed to (0 = its own reviewers).
+    #[serde(default)]
+    pub escalation_level: u32,
+
+    /// Reminders sent to the request's reviewers.
+    #[serde(default)]
+    pub reminder_count: u32,
+
+    /// When the last reminder was sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_reminded_at: Option<DateTime<Utc>>,
+
+    /// Channels the request was delivered on by channel routing, reused for
+    /// its reminders and escalations.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notified_channels: Vec<ChannelType>,
 }
 
 impl OversightRequest {
@@ -79,6 +115,14 @@ impl OversightRequest {
             expires_at: now + chrono::Duration::seconds(timeout_seconds as i64),
             assigned_reviewers: Vec::new(),
             metadata: serde_json::Value::Object(serde_json::Map::new()),
+            correlation_id: None,
+            caused_by: None,
+            template: None,
+            original_priority: None,
+            escalation_level: 0,
+            reminder_count: 0,
+            last_reminded_at: None,
+            notified_channels: Vec::new(),
         }
     }
 
@@ -101,6 +145,32 @@ impl OversightRequest {
         self
     }
 
+    /// Set the trace identifiers inherited from the originating action.
+    pub fn with_correlation(mut self, correlation: Correlation) -> Self {
+        self.correlation_id = correlation.correlation_id;
+        self.caused_by = correlation.caused_by;
+        self
+    }
+
+    /// Record the template this request was created from.
+    pub fn with_template(mut self, template: AppliedTemplate) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Priority the request was created with, before any aging boost.
+    pub fn original_priority(&self) -> Priority {
+        self.original_priority.unwrap_or(self.priority)
+    }
+
+    /// Get the trace identifiers for this request.
+    pub fn correlation(&self) -> Correlation {
+        Correlation {
+            correlation_id: self.correlation_id,
+            caused_by: self.caused_by,
+        }
+    }
+
     /// Add a reviewer.
     pub fn add_reviewer(&mut self, reviewer: UserId) {
         if !self.assigned_reviewers.contains(&reviewer) {
@@ -225,6 +295,15 @@ impl Priority {
             Priority::Critical => 300, // 5 minutes
         }
     }
+
+    /// The next priority level up; Critical stays Critical.
+    pub fn raised(&self) -> Priority {
+        match self {
+            Priority::Low => Priority::Normal,
+            Priority::Normal => Priority::High,
+            Priority::High | Priority::Critical => Priority::Critical,
+        }
+    }
 }
 
 #[cfg(test)]
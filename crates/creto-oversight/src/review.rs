@@ -0,0 +1,201 @@
+//! Everything an approval screen needs to render one request.
+//!
+//! [`OversightService::review_bundle`](crate::service::OversightService::review_bundle)
+//! gathers the request, its decisions so far, the quorum they are counted
+//! against, its audit trail and enriched context into a single
+//! [`ReviewBundle`], read as of one consistent point so the screen never
+//! shows a quorum result that disagrees with the approvals beside it.
+//!
+//! Reviewers are identified by [`UserId`] in storage; a [`ReviewerDirectory`]
+//! supplies the names shown next to their decisions and decides who may see
+//! an organization's requests at all.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{CretoResult, OrganizationId, UserId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::approval::{Approval, ApprovalDecision, QuorumConfig, QuorumResult};
+use crate::checkpoint::Checkpoint;
+use crate::enrichment::ContextSection;
+use crate::repository::StateTransitionRecord;
+use crate::request::{OversightRequest, RequestStatus};
+
+/// Display information for a reviewer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewerProfile {
+    /// The reviewer's user ID.
+    pub user_id: UserId,
+
+    /// Name shown next to the reviewer's decisions.
+    pub display_name: String,
+
+    /// Contact address, if the directory exposes one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+
+    /// Directory roles, used to resolve the reviewer's quorum weight.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+impl ReviewerProfile {
+    /// Create a profile without an email address.
+    pub fn new(user_id: UserId, display_name: impl Into<String>) -> Self {
+        Self {
+            user_id,
+            display_name: display_name.into(),
+            email: None,
+            roles: Vec::new(),
+        }
+    }
+
+    /// Set the reviewer's email address.
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// Set the reviewer's directory roles.
+    pub fn with_roles(mut self, roles: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.roles = roles.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Looks up the people behind reviewer IDs.
+#[async_trait]
+pub trait ReviewerDirectory: Send + Sync {
+    /// Profile of `user_id` within `organization_id`, or `None` if they are
+    /// not a member of it.
+    async fn lookup(
+        &self,
+        organization_id: OrganizationId,
+        user_id: UserId,
+    ) -> CretoResult<Option<ReviewerProfile>>;
+}
+
+/// In-memory reviewer directory for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryReviewerDirectory {
+    profiles: RwLock<HashMap<(OrganizationId, UserId), ReviewerProfile>>,
+}
+
+impl InMemoryReviewerDirectory {
+    /// Create an empty directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a member of an organization.
+    pub fn add(&self, organization_id: OrganizationId, profile: ReviewerProfile) {
+        self.profiles
+            .write()
+            .unwrap()
+            .insert((organization_id, profile.user_id), profile);
+    }
+}
+
+#[async_trait]
+impl ReviewerDirectory for InMemoryReviewerDirectory {
+    async fn lookup(
+        &self,
+        organization_id: OrganizationId,
+        user_id: UserId,
+    ) -> CretoResult<Option<ReviewerProfile>> {
+        Ok(self
+            .profiles
+            .read()
+            .unwrap()
+            .get(&(organization_id, user_id))
+            .cloned())
+    }
+}
+
+/// A recorded decision and who made it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewerDecision {
+    /// The decision as stored.
+    pub approval: Approval,
+
+    /// The reviewer's profile, or `None` if they have since left the
+    /// organization's directory.
+    pub reviewer: Option<ReviewerProfile>,
+}
+
+/// The latest checkpoint, without its context and state machine payloads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointSummary {
+    /// Checkpoint ID.
+    pub id: Uuid,
+
+    /// Request status when the checkpoint was taken.
+    pub status: RequestStatus,
+
+    /// When the checkpoint was taken.
+    pub timestamp: DateTime<Utc>,
+
+    /// Why the checkpoint was taken.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// Number of transitions in the checkpointed state machine.
+    pub transition_count: usize,
+}
+
+impl From<&Checkpoint> for CheckpointSummary {
+    fn from(checkpoint: &Checkpoint) -> Self {
+        Self {
+            id: checkpoint.id,
+            status: checkpoint.status,
+            timestamp: checkpoint.timestamp,
+            reason: checkpoint.reason.clone(),
+            transition_count: checkpoint.state_machine.transitions.len(),
+        }
+    }
+}
+
+/// Everything an approval screen shows for one request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewBundle {
+    /// The request. Its `context` holds what the agent supplied; enriched
+    /// sections are split out into `context_sections`.
+    pub request: OversightRequest,
+
+    /// Context sections added by enrichers, in display order.
+    pub context_sections: Vec<ContextSection>,
+
+    /// Decisions recorded so far, oldest first.
+    pub approvals: Vec<ReviewerDecision>,
+
+    /// Quorum the decisions are counted against.
+    pub quorum: QuorumConfig,
+
+    /// Where the decisions currently stand against the quorum.
+    pub quorum_result: QuorumResult,
+
+    /// State transitions, oldest first.
+    pub transitions: Vec<StateTransitionRecord>,
+
+    /// Latest checkpoint, if checkpoints are enabled and one was taken.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_checkpoint: Option<CheckpointSummary>,
+
+    /// Seconds until the request times out, or `None` once it is closed.
+    /// Zero when the deadline has passed but the request was not yet expired.
+    pub time_remaining_seconds: Option<i64>,
+
+    /// Whether the caller has recorded a decision.
+    pub caller_has_decided: bool,
+
+    /// The caller's decision, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller_decision: Option<ApprovalDecision>,
+
+    /// When the bundle was assembled.
+    pub generated_at: DateTime<Utc>,
+}
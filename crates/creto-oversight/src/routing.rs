@@ -0,0 +1,623 @@
+//! Rule-based fan-out of request notifications across channels.
+//!
+//! A [`ChannelRouter`] holds the registered channels and an ordered list of
+//! [`ChannelRule`]s matching on organization, priority and action type. Every
+//! rule matching a request adds its channels to the request's [`RoutePlan`],
+//! as required or best-effort; a channel named by more than one matching
+//! rule takes the requirement of the first, and an exclusive rule stops the
+//! rules after it. The planned channels are notified concurrently, each
+//! retried with backoff, and the notification counts as delivered if a
+//! required channel succeeds.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use creto_common::OrganizationId;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::channels::{ChannelType, NotificationChannel, NotificationResult};
+use crate::notifications::SentNotification;
+use crate::request::{OversightRequest, Priority};
+use crate::triggers::ActionTypePattern;
+
+/// Whether a channel's delivery decides if a request was notified.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelRequirement {
+    /// The request counts as notified only if a required channel succeeds.
+    #[default]
+    Required,
+    /// Sent as well, but failures do not count against the notification.
+    BestEffort,
+}
+
+/// Routes requests matching its conditions to a set of channels.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelRule {
+    /// Name reported for requests the rule matched.
+    pub name: String,
+
+    /// Organization the rule applies to (None = every organization).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization_id: Option<OrganizationId>,
+
+    /// Priorities the rule applies to (empty = every priority).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priorities: Vec<Priority>,
+
+    /// Action types the rule applies to.
+    #[serde(default = "any_action")]
+    pub action_type: ActionTypePattern,
+
+    /// Channels to notify.
+    pub channels: Vec<ChannelType>,
+
+    /// Whether the channels are required or best-effort.
+    #[serde(default)]
+    pub requirement: ChannelRequirement,
+
+    /// Whether later rules are skipped for requests this rule matches.
+    #[serde(default)]
+    pub exclusive: bool,
+}
+
+fn any_action() -> ActionTypePattern {
+    ActionTypePattern::Any
+}
+
+impl ChannelRule {
+    /// Create a rule sending every request to `channels`, as required.
+    pub fn new(name: impl Into<String>, channels: Vec<ChannelType>) -> Self {
+        Self {
+            name: name.into(),
+            organization_id: None,
+            priorities: Vec::new(),
+            action_type: ActionTypePattern::Any,
+            channels,
+            requirement: ChannelRequirement::Required,
+            exclusive: false,
+        }
+    }
+
+    /// Only match requests from `organization_id`.
+    pub fn for_organization(mut self, organization_id: OrganizationId) -> Self {
+        self.organization_id = Some(organization_id);
+        self
+    }
+
+    /// Only match requests with one of `priorities`.
+    pub fn with_priorities(mut self, priorities: impl IntoIterator<Item = Priority>) -> Self {
+        self.priorities = priorities.into_iter().collect();
+        self
+    }
+
+    /// Only match requests whose action matches `pattern`.
+    pub fn with_action_type(mut self, pattern: ActionTypePattern) -> Self {
+        self.action_type = pattern;
+        self
+    }
+
+    /// Send on the rule's channels without requiring them to succeed.
+    pub fn best_effort(mut self) -> Self {
+        self.requirement = ChannelRequirement::BestEffort;
+        self
+    }
+
+    /// Skip later rules for requests this rule matches.
+    pub fn exclusive(mut self) -> Self {
+        self.exclusive = true;
+        self
+    }
+
+    /// Check whether the rule applies to a request.
+    pub fn matches(&self, request: &OversightRequest) -> bool {
+        let organization_matches = match self.organization_id {
+            Some(organization_id) => organization_id == request.organization_id,
+            None => true,
+        };
+        organization_matches
+            && (self.priorities.is_empty() || self.priorities.contains(&request.priority))
+            && self.action_type.matches(&request.action_type)
+    }
+}
+
+/// A channel a request is routed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelTarget {
+    /// Channel to notify.
+    pub channel: ChannelType,
+    /// Whether its delivery is required.
+    pub requirement: ChannelRequirement,
+}
+
+/// Channels the rules route a request to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoutePlan {
+    /// Channels to notify, in rule order.
+    pub targets: Vec<ChannelTarget>,
+    /// Names of the rules that matched.
+    pub rules: Vec<String>,
+}
+
+/// Retry schedule for a failed channel delivery.
+#[derive(Debug, Clone)]
+pub struct ChannelRetryPolicy {
+    /// Attempts per channel, including the first.
+    pub max_attempts: u32,
+    /// Wait before the second attempt; doubles for each one after.
+    pub initial_backoff: Duration,
+    /// Upper bound on the wait between attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for ChannelRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ChannelRetryPolicy {
+    /// Wait before attempt number `attempt` (1 = first retry).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Outcome of notifying one channel.
+#[derive(Debug, Clone)]
+pub struct ChannelDelivery {
+    /// Channel notified.
+    pub channel: ChannelType,
+    /// Whether its delivery was required.
+    pub requirement: ChannelRequirement,
+    /// Attempts made (0 if the channel is not registered).
+    pub attempts: u32,
+    /// Result of the last attempt.
+    pub result: NotificationResult,
+}
+
+/// Outcome of fanning a request out to its channels.
+#[derive(Debug, Clone)]
+pub struct FanOutReport {
+    /// The request notified.
+    pub request_id: Uuid,
+    /// Names of the rules that matched.
+    pub rules: Vec<String>,
+    /// Result of each planned channel.
+    pub deliveries: Vec<ChannelDelivery>,
+    /// Whether a required channel succeeded, or, with no required channels,
+    /// any channel did.
+    pub delivered: bool,
+}
+
+impl FanOutReport {
+    fn new(request_id: Uuid, rules: Vec<String>, deliveries: Vec<ChannelDelivery>) -> Self {
+        let required = |d: &&ChannelDelivery| d.requirement == ChannelRequirement::Required;
+        let delivered = if deliveries.iter().any(|d| required(&d)) {
+            deliveries.iter().filter(required).any(|d| d.result.success)
+        } else {
+            deliveries.iter().any(|d| d.result.success)
+        };
+        Self {
+            request_id,
+            rules,
+            deliveries,
+            delivered,
+        }
+    }
+
+    /// Channels that delivered, in plan order.
+    pub fn notified_channels(&self) -> Vec<ChannelType> {
+        self.deliveries
+            .iter()
+            .filter(|d| d.result.success)
+            .map(|d| d.channel)
+            .collect()
+    }
+
+    /// The deliveries as organization-level sent notifications.
+    pub fn sent_notifications(&self) -> Vec<SentNotification> {
+        self.deliveries
+            .iter()
+            .map(|d| SentNotification {
+                reviewer_id: None,
+                channel: d.channel,
+                request_ids: vec![self.request_id],
+                result: d.result.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Fans request notifications out to channels chosen by rules.
+pub struct ChannelRouter {
+    channels: Vec<Arc<dyn NotificationChannel>>,
+    rules: Vec<ChannelRule>,
+    retry: ChannelRetryPolicy,
+}
+
+impl Default for ChannelRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChannelRouter {
+    /// Create a router with no channels or rules.
+    pub fn new() -> Self {
+        Self {
+            channels: Vec::new(),
+            rules: Vec::new(),
+            retry: ChannelRetryPolicy::default(),
+        }
+    }
+
+    /// Register a channel.
+    pub fn with_channel(mut self, channel: Arc<dyn NotificationChannel>) -> Self {
+        self.channels.push(channel);
+        self
+    }
+
+    /// Add a rule after the existing ones.
+    pub fn with_rule(mut self, rule: ChannelRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Set the retry schedule for failed deliveries.
+    pub fn with_retry_policy(mut self, retry: ChannelRetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Get the rules, in evaluation order.
+    pub fn rules(&self) -> &[ChannelRule] {
+        &self.rules
+    }
+
+    /// Channels the rules route a request to. Empty if no rule matches.
+    pub fn plan(&self, request: &OversightRequest) -> RoutePlan {
+        let mut plan = RoutePlan::default();
+        for rule in self.rules.iter().filter(|rule| rule.matches(request)) {
+            for &channel in &rule.channels {
+                if !plan.targets.iter().any(|t| t.channel == channel) {
+                    plan.targets.push(ChannelTarget {
+                        channel,
+                        requirement: rule.requirement,
+                    });
+                }
+            }
+            plan.rules.push(rule.name.clone());
+            if rule.exclusive {
+                break;
+            }
+        }
+        plan
+    }
+
+    /// Notify a request on the channels its rules select.
+    pub async fn notify(&self, request: &OversightRequest) -> FanOutReport {
+        let plan = self.plan(request);
+        if plan.targets.is_empty() {
+            tracing::warn!(request_id = %request.id, "No channel rule matches request");
+        }
+        let deliveries = self.send(request, &plan.targets).await;
+        FanOutReport::new(request.id, plan.rules, deliveries)
+    }
+
+    /// Notify a request on `channels`, all required, bypassing the rules.
+    ///
+    /// Used to reach a request's reviewers again on the channels it was
+    /// first delivered on.
+    pub async fn notify_on(
+        &self,
+        request: &OversightRequest,
+        channels: &[ChannelType],
+    ) -> FanOutReport {
+        let targets: Vec<ChannelTarget> = channels
+            .iter()
+            .map(|&channel| ChannelTarget {
+                channel,
+                requirement: ChannelRequirement::Required,
+            })
+            .collect();
+        let deliveries = self.send(request, &targets).await;
+        FanOutReport::new(request.id, Vec::new(), deliveries)
+    }
+
+    /// Notify every target concurrently, retrying failures.
+    async fn send(
+        &self,
+        request: &OversightRequest,
+        targets: &[ChannelTarget],
+    ) -> Vec<ChannelDelivery> {
+        let request = Arc::new(request.clone());
+        let tasks: Vec<_> = targets
+            .iter()
+            .map(|target| {
+                let channel = self
+                    .channels
+                    .iter()
+                    .find(|c| c.channel_type() == target.channel)
+                    .cloned();
+                let request = Arc::clone(&request);
+                let retry = self.retry.clone();
+                tokio::spawn(async move {
+                    match channel {
+                        Some(channel) => deliver(channel.as_ref(), &request, &retry).await,
+                        None => (0, NotificationResult::failure("Channel not registered")),
+                    }
+                })
+            })
+            .collect();
+
+        let mut deliveries = Vec::with_capacity(targets.len());
+        for (target, task) in targets.iter().zip(tasks) {
+            let (attempts, result) = task
+                .await
+                .unwrap_or_else(|e| (1, NotificationResult::failure(e.to_string())));
+            if !result.success {
+                tracing::warn!(
+                    request_id = %request.id,
+                    channel = ?target.channel,
+                    attempts = attempts,
+                    error = ?result.error,
+                    "Channel notification failed"
+                );
+            }
+            deliveries.push(ChannelDelivery {
+                channel: target.channel,
+                requirement: target.requirement,
+                attempts,
+                result,
+            });
+        }
+        deliveries
+    }
+}
+
+/// Notify one channel, retrying with backoff until it succeeds or the
+/// attempts run out. Returns the attempts made and the last result.
+async fn deliver(
+    channel: &dyn NotificationChannel,
+    request: &OversightRequest,
+    retry: &ChannelRetryPolicy,
+) -> (u32, NotificationResult) {
+    let max_attempts = retry.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let result = match channel.notify(request).await {
+            Ok(result) => result,
+            Err(e) => NotificationResult::failure(e.to_string()),
+        };
+        if result.success || attempt >= max_attempts {
+            return (attempt, result);
+        }
+        tokio::time::sleep(retry.backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use creto_common::{AgentId, CretoError, CretoResult};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::channels::MockChannel;
+    use crate::request::ActionType;
+
+    /// Fails its first `failures` notifications.
+    struct FlakyChannel {
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for FlakyChannel {
+        async fn notify(&self, _request: &OversightRequest) -> CretoResult<NotificationResult> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(CretoError::ChannelError("gateway timeout".to_string()));
+            }
+            Ok(NotificationResult::success(None))
+        }
+
+        async fn remind(&self, _request: &OversightRequest) -> CretoResult<NotificationResult> {
+            Ok(NotificationResult::success(None))
+        }
+
+        fn channel_type(&self) -> ChannelType {
+            ChannelType::Sms
+        }
+    }
+
+    fn fast_retry(max_attempts: u32) -> ChannelRetryPolicy {
+        ChannelRetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+        }
+    }
+
+    fn mock(channel_type: ChannelType) -> Arc<MockChannel> {
+        Arc::new(MockChannel::new().with_channel_type(channel_type))
+    }
+
+    fn request(organization_id: OrganizationId, priority: Priority) -> OversightRequest {
+        let mut request = OversightRequest::new(
+            organization_id,
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        );
+        request.priority = priority;
+        request
+    }
+
+    fn target(channel: ChannelType, requirement: ChannelRequirement) -> ChannelTarget {
+        ChannelTarget {
+            channel,
+            requirement,
+        }
+    }
+
+    #[test]
+    fn test_rule_precedence() {
+        let vip = OrganizationId::new();
+        let router = ChannelRouter::new()
+            .with_rule(
+                ChannelRule::new("vip", vec![ChannelType::Teams])
+                    .for_organization(vip)
+                    .exclusive(),
+            )
+            .with_rule(
+                ChannelRule::new("critical", vec![ChannelType::Slack, ChannelType::Sms])
+                    .with_priorities([Priority::Critical]),
+            )
+            .with_rule(
+                ChannelRule::new(
+                    "critical-fyi",
+                    vec![ChannelType::Slack, ChannelType::Webhook],
+                )
+                .with_priorities([Priority::Critical])
+                .best_effort(),
+            )
+            .with_rule(
+                ChannelRule::new("normal", vec![ChannelType::Email])
+                    .with_priorities([Priority::Low, Priority::Normal]),
+            );
+
+        // Slack keeps the requirement of the first rule naming it
+        let plan = router.plan(&request(OrganizationId::new(), Priority::Critical));
+        assert_eq!(plan.rules, vec!["critical", "critical-fyi"]);
+        assert_eq!(
+            plan.targets,
+            vec![
+                target(ChannelType::Slack, ChannelRequirement::Required),
+                target(ChannelType::Sms, ChannelRequirement::Required),
+                target(ChannelType::Webhook, ChannelRequirement::BestEffort),
+            ]
+        );
+
+        let plan = router.plan(&request(OrganizationId::new(), Priority::Normal));
+        assert_eq!(
+            plan.targets,
+            vec![target(ChannelType::Email, ChannelRequirement::Required)]
+        );
+
+        // An exclusive rule shadows everything after it
+        let plan = router.plan(&request(vip, Priority::Critical));
+        assert_eq!(plan.rules, vec!["vip"]);
+        assert_eq!(
+            plan.targets,
+            vec![target(ChannelType::Teams, ChannelRequirement::Required)]
+        );
+
+        assert!(router
+            .plan(&request(OrganizationId::new(), Priority::High))
+            .targets
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_partial_failure_still_delivers() {
+        let slack = mock(ChannelType::Slack);
+        let sms = mock(ChannelType::Sms);
+        slack.set_should_fail(true).await;
+        let router = ChannelRouter::new()
+            .with_channel(slack.clone())
+            .with_channel(sms.clone())
+            .with_rule(ChannelRule::new(
+                "critical",
+                vec![ChannelType::Slack, ChannelType::Sms],
+            ))
+            .with_retry_policy(fast_retry(3));
+
+        let report = router
+            .notify(&request(OrganizationId::new(), Priority::Critical))
+            .await;
+        assert!(report.delivered);
+        assert_eq!(report.notified_channels(), vec![ChannelType::Sms]);
+        assert_eq!(report.deliveries[0].attempts, 3);
+        assert!(!report.deliveries[0].result.success);
+        assert_eq!(report.deliveries[1].attempts, 1);
+        assert_eq!(sms.notification_count().await, 1);
+
+        sms.set_should_fail(true).await;
+        let report = router
+            .notify(&request(OrganizationId::new(), Priority::Critical))
+            .await;
+        assert!(!report.delivered);
+        assert!(report.notified_channels().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_best_effort_channels_do_not_decide_delivery() {
+        let slack = mock(ChannelType::Slack);
+        let email = mock(ChannelType::Email);
+        let router = ChannelRouter::new()
+            .with_channel(slack.clone())
+            .with_channel(email.clone())
+            .with_rule(ChannelRule::new("primary", vec![ChannelType::Slack]))
+            .with_rule(ChannelRule::new("copy", vec![ChannelType::Email]).best_effort())
+            .with_retry_policy(fast_retry(1));
+        let request = request(OrganizationId::new(), Priority::Normal);
+
+        // A best-effort success does not make up for a failed required channel
+        slack.set_should_fail(true).await;
+        let report = router.notify(&request).await;
+        assert!(!report.delivered);
+        assert_eq!(report.notified_channels(), vec![ChannelType::Email]);
+
+        // A best-effort failure does not spoil a required success
+        slack.set_should_fail(false).await;
+        email.set_should_fail(true).await;
+        let report = router.notify(&request).await;
+        assert!(report.delivered);
+
+        // Unregistered channels fail without being attempted
+        let report = router
+            .notify_on(&request, &[ChannelType::Teams, ChannelType::Slack])
+            .await;
+        assert!(report.delivered);
+        assert_eq!(report.deliveries[0].attempts, 0);
+        assert_eq!(report.notified_channels(), vec![ChannelType::Slack]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_channels_are_retried() {
+        let router = |max_attempts| {
+            ChannelRouter::new()
+                .with_channel(Arc::new(FlakyChannel {
+                    failures: 2,
+                    calls: AtomicU32::new(0),
+                }))
+                .with_rule(ChannelRule::new("sms", vec![ChannelType::Sms]))
+                .with_retry_policy(fast_retry(max_attempts))
+        };
+        let request = request(OrganizationId::new(), Priority::High);
+
+        let report = router(3).notify(&request).await;
+        assert!(report.delivered);
+        assert_eq!(report.deliveries[0].attempts, 3);
+
+        let report = router(2).notify(&request).await;
+        assert!(!report.delivered);
+        assert_eq!(report.deliveries[0].attempts, 2);
+        assert_eq!(
+            report.deliveries[0].result.error.as_deref(),
+            Some("Channel error: gateway timeout")
+        );
+    }
+}
@@ -1,17 +1,95 @@
 //! Main oversight service facade.
 
-use creto_common::{AgentId, CretoError, CretoResult, OrganizationId, UserId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use creto_common::metrics::{CounterVec, GaugeVec, MetricsRegistry};
+use creto_common::{
+    AgentId, AuditActor, AuditEvent, AuditMode, AuditOutcome, AuditSink, CretoError, CretoResult,
+    OrganizationId, ShutdownCoordinator, TracingAuditSink, UserId,
+};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
 use crate::{
+    aging::{AgingBoost, AgingPolicy, AGING_REASON},
     approval::{Approval, ApprovalDecision, QuorumCalculator, QuorumConfig, QuorumResult},
+    assignments::{
+        AssignmentLapse, AssignmentSource, AssignmentStatus, AssignmentStore, LapseMode,
+        ReviewerAssignment, ReviewerDeadlineConfig, LAPSE_REASON,
+    },
     checkpoint::{Checkpoint, CheckpointManager},
+    clock::{Clock, SystemClock},
+    directory::{InMemoryReviewerGroupStore, ReviewerGroupStore},
+    enrichment::{self, ContextEnricher, EnrichmentInput},
+    escalation::{
+        Escalation, EscalationEngine, EscalationOutcome, EscalationStep, EscalationTrigger,
+        ESCALATION_REASON, EXHAUSTED_REASON,
+    },
+    lifecycle::{
+        self, ActivityKind, ActivityLog, LifecycleDocument, LifecycleExport, LifecycleExportFormat,
+        LifecycleRedactor, LifecycleSources, RequestActivity,
+    },
+    notifications::{
+        NotificationPreferences, NotificationRouter, RoutingReport, SentNotification,
+        WithdrawalReport,
+    },
     policy::{PolicyContext, PolicyDecision, PolicyEngine},
+    repository::{
+        ApprovalRepository, RequestRepository, StateTransitionRecord, StateTransitionRepository,
+    },
     request::{ActionType, OversightRequest, RequestStatus},
-    state::{Actor, StateMachine},
+    review::{
+        CheckpointSummary, ReviewBundle, ReviewerDecision, ReviewerDirectory, ReviewerProfile,
+    },
+    routing::{ChannelRouter, FanOutReport},
+    state::{Actor, StateMachine, StateTransition},
+    template::{InMemoryRequestTemplateStore, RequestTemplateStore, TemplateError},
     triggers::{PolicyEvaluator, PolicyTriggerConfig},
+    webhooks::{DecisionPayload, WebhookDispatcher},
+    weights::{ReviewerWeightRepository, DEFAULT_REVIEWER_WEIGHT},
 };
 
+/// Decides whether a user administers an organization's oversight requests.
+///
+/// Administrators may cancel requests on behalf of the agent that raised them.
+#[async_trait]
+pub trait OrgAdminCheck: Send + Sync {
+    /// Whether `user_id` is an administrator of `organization_id`.
+    async fn is_org_admin(
+        &self,
+        organization_id: OrganizationId,
+        user_id: UserId,
+    ) -> CretoResult<bool>;
+}
+
+/// Oversight metrics exported to a [`MetricsRegistry`].
+pub struct OversightMetrics {
+    pending: GaugeVec,
+    approvals: CounterVec,
+}
+
+impl OversightMetrics {
+    /// Register the oversight metrics with `registry`.
+    pub fn new(registry: &MetricsRegistry) -> Self {
+        Self {
+            pending: registry.gauge_vec(
+                "creto_oversight_pending_requests",
+                "Open oversight requests, by status",
+                &["status"],
+            ),
+            approvals: registry.counter_vec(
+                "creto_oversight_approvals_total",
+                "Reviewer decisions submitted, by decision",
+                &["decision"],
+            ),
+        }
+    }
+}
+
 /// Main entry point for the oversight system.
 pub struct OversightService {
     /// Policy engine for determining oversight requirements.
@@ -25,6 +103,72 @@ pub struct OversightService {
 
     /// Policy trigger evaluator for automatic oversight creation.
     pub trigger_evaluator: Option<PolicyEvaluator>,
+
+    /// Organization request templates.
+    pub template_store: Arc<dyn RequestTemplateStore>,
+
+    /// Reviewer notification routing and preferences.
+    pub notifications: NotificationRouter,
+
+    /// Context enrichers run on every new request, in order.
+    pub enrichers: Vec<Arc<dyn ContextEnricher>>,
+
+    /// Outbound decision webhooks (None = disabled).
+    pub webhooks: Option<Arc<WebhookDispatcher>>,
+
+    /// Reviewer groups templates route requests to.
+    pub reviewer_groups: Arc<dyn ReviewerGroupStore>,
+
+    /// Request persistence (None = decisions are not checked against stored requests).
+    pub requests: Option<Arc<dyn RequestRepository>>,
+
+    /// State transition audit trail (None = transitions are not recorded).
+    pub transitions: Option<Arc<dyn StateTransitionRepository>>,
+
+    /// Role check for organization administrators (None = no administrators).
+    pub admin_check: Option<Arc<dyn OrgAdminCheck>>,
+
+    /// Approval persistence (None = review bundles are unavailable).
+    pub approvals: Option<Arc<dyn ApprovalRepository>>,
+
+    /// Reviewer names and organization membership (None = review bundles are unavailable).
+    pub reviewer_directory: Option<Arc<dyn ReviewerDirectory>>,
+
+    /// Notifications, views, comments and executions (None = not recorded).
+    pub activity: Option<Arc<dyn ActivityLog>>,
+
+    /// HMAC key for signed lifecycle exports (None = only JSON Lines exports).
+    pub export_signing_key: Option<String>,
+
+    /// Time source for deadlines shown to reviewers.
+    pub clock: Arc<dyn Clock>,
+
+    /// Priority boosts for requests nearing their timeout (None = disabled).
+    pub aging: Option<AgingPolicy>,
+
+    /// Reviewer assignments (None = reviewers have no individual deadlines).
+    pub assignments: Option<Arc<dyn AssignmentStore>>,
+
+    /// Reviewer deadlines, lapse handling and escalation contacts.
+    pub reviewer_deadlines: ReviewerDeadlineConfig,
+
+    /// Escalation chains for undecided requests (None = requests time out).
+    pub escalation: Option<EscalationEngine>,
+
+    /// Rule-based channel fan-out (None = `notify` is unavailable).
+    pub channel_router: Option<ChannelRouter>,
+
+    /// Role and reviewer quorum weights (None = every decision weighs 1).
+    pub reviewer_weights: Option<Arc<dyn ReviewerWeightRepository>>,
+
+    /// Exported pending-request and decision metrics (None = not exported).
+    pub metrics: Option<OversightMetrics>,
+
+    /// Receiver of audit events for request state changes.
+    pub audit_sink: Arc<dyn AuditSink>,
+
+    /// Whether a failed audit emit blocks the state change.
+    pub audit_mode: AuditMode,
 }
 
 impl OversightService {
@@ -35,6 +179,28 @@ impl OversightService {
             default_quorum: QuorumConfig::default(),
             checkpoint_manager: None,
             trigger_evaluator: None,
+            template_store: Arc::new(InMemoryRequestTemplateStore::new()),
+            notifications: NotificationRouter::default(),
+            enrichers: Vec::new(),
+            webhooks: None,
+            reviewer_groups: Arc::new(InMemoryReviewerGroupStore::new()),
+            requests: None,
+            transitions: None,
+            admin_check: None,
+            approvals: None,
+            reviewer_directory: None,
+            activity: None,
+            export_signing_key: None,
+            clock: Arc::new(SystemClock),
+            aging: None,
+            assignments: None,
+            reviewer_deadlines: ReviewerDeadlineConfig::default(),
+            escalation: None,
+            channel_router: None,
+            reviewer_weights: None,
+            metrics: None,
+            audit_sink: Arc::new(TracingAuditSink),
+            audit_mode: AuditMode::default(),
         }
     }
 
@@ -45,6 +211,28 @@ impl OversightService {
             default_quorum: QuorumConfig::default(),
             checkpoint_manager: Some(checkpoint_manager),
             trigger_evaluator: None,
+            template_store: Arc::new(InMemoryRequestTemplateStore::new()),
+            notifications: NotificationRouter::default(),
+            enrichers: Vec::new(),
+            webhooks: None,
+            reviewer_groups: Arc::new(InMemoryReviewerGroupStore::new()),
+            requests: None,
+            transitions: None,
+            admin_check: None,
+            approvals: None,
+            reviewer_directory: None,
+            activity: None,
+            export_signing_key: None,
+            clock: Arc::new(SystemClock),
+            aging: None,
+            assignments: None,
+            reviewer_deadlines: ReviewerDeadlineConfig::default(),
+            escalation: None,
+            channel_router: None,
+            reviewer_weights: None,
+            metrics: None,
+            audit_sink: Arc::new(TracingAuditSink),
+            audit_mode: AuditMode::default(),
         }
     }
 
@@ -54,6 +242,177 @@ impl OversightService {
         self
     }
 
+    /// Use a different request template store.
+    pub fn with_template_store(mut self, store: Arc<dyn RequestTemplateStore>) -> Self {
+        self.template_store = store;
+        self
+    }
+
+    /// Use a different notification router.
+    pub fn with_notification_router(mut self, router: NotificationRouter) -> Self {
+        self.notifications = router;
+        self
+    }
+
+    /// Register a context enricher.
+    pub fn with_enricher(mut self, enricher: Arc<dyn ContextEnricher>) -> Self {
+        self.enrichers.push(enricher);
+        self
+    }
+
+    /// Send decision webhooks through `dispatcher`.
+    pub fn with_webhooks(mut self, dispatcher: Arc<WebhookDispatcher>) -> Self {
+        self.webhooks = Some(dispatcher);
+        self
+    }
+
+    /// Use a different reviewer group store, such as one kept in sync with a
+    /// directory by [`ReviewerDirectorySync`](crate::directory::ReviewerDirectorySync).
+    pub fn with_reviewer_group_store(mut self, store: Arc<dyn ReviewerGroupStore>) -> Self {
+        self.reviewer_groups = store;
+        self
+    }
+
+    /// Export pending requests and reviewer decisions to `registry`.
+    ///
+    /// Decisions are counted as they are submitted; the pending gauge is
+    /// updated by [`refresh_metrics`](Self::refresh_metrics), which the
+    /// timeout worker calls on every pass.
+    pub fn with_metrics(mut self, registry: &MetricsRegistry) -> Self {
+        self.metrics = Some(OversightMetrics::new(registry));
+        self
+    }
+
+    /// Send audit events for request creation, decisions, escalations,
+    /// timeouts and cancellations to `sink` instead of the tracing log.
+    pub fn with_audit_sink(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit_sink = sink;
+        self
+    }
+
+    /// Set whether a failed audit emit blocks the state change it records.
+    pub fn with_audit_mode(mut self, mode: AuditMode) -> Self {
+        self.audit_mode = mode;
+        self
+    }
+
+    /// Load and check requests against `repository`.
+    pub fn with_request_repository(mut self, repository: Arc<dyn RequestRepository>) -> Self {
+        self.requests = Some(repository);
+        self
+    }
+
+    /// Record state transitions in `repository`.
+    pub fn with_transition_repository(
+        mut self,
+        repository: Arc<dyn StateTransitionRepository>,
+    ) -> Self {
+        self.transitions = Some(repository);
+        self
+    }
+
+    /// Use `check` to recognize organization administrators.
+    pub fn with_admin_check(mut self, check: Arc<dyn OrgAdminCheck>) -> Self {
+        self.admin_check = Some(check);
+        self
+    }
+
+    /// Load recorded decisions from `repository`.
+    pub fn with_approval_repository(mut self, repository: Arc<dyn ApprovalRepository>) -> Self {
+        self.approvals = Some(repository);
+        self
+    }
+
+    /// Resolve reviewer profiles and organization membership through `directory`.
+    pub fn with_reviewer_directory(mut self, directory: Arc<dyn ReviewerDirectory>) -> Self {
+        self.reviewer_directory = Some(directory);
+        self
+    }
+
+    /// Weight decisions by the reviewer's roles and individual weights in
+    /// `repository`, resolving roles through the reviewer directory.
+    pub fn with_reviewer_weights(mut self, repository: Arc<dyn ReviewerWeightRepository>) -> Self {
+        self.reviewer_weights = Some(repository);
+        self
+    }
+
+    /// Record request activity in `log`.
+    pub fn with_activity_log(mut self, log: Arc<dyn ActivityLog>) -> Self {
+        self.activity = Some(log);
+        self
+    }
+
+    /// Sign lifecycle exports with `key`.
+    pub fn with_export_signing_key(mut self, key: impl Into<String>) -> Self {
+        self.export_signing_key = Some(key.into());
+        self
+    }
+
+    /// Use a different time source.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Boost pending requests as their timeout approaches.
+    pub fn with_aging_policy(mut self, policy: AgingPolicy) -> Self {
+        self.aging = Some(policy);
+        self
+    }
+
+    /// Give each assigned reviewer their own deadline, recording
+    /// assignments in `store`.
+    pub fn with_reviewer_deadlines(
+        mut self,
+        store: Arc<dyn AssignmentStore>,
+        config: ReviewerDeadlineConfig,
+    ) -> Self {
+        self.assignments = Some(store);
+        self.reviewer_deadlines = config;
+        self
+    }
+
+    /// Escalate undecided requests through their organization's chain.
+    pub fn with_escalation(mut self, engine: EscalationEngine) -> Self {
+        self.escalation = Some(engine);
+        self
+    }
+
+    /// Fan request notifications out to channels chosen by `router`'s rules.
+    pub fn with_channel_router(mut self, router: ChannelRouter) -> Self {
+        self.channel_router = Some(router);
+        self
+    }
+
+    /// Tell the organization's webhook endpoints that a request was decided.
+    ///
+    /// Call once the request's terminal status is persisted. Delivery
+    /// happens in the background; returns the IDs of the deliveries started,
+    /// which is empty if webhooks are disabled or the request is still open.
+    pub async fn notify_decision(
+        &self,
+        request: &OversightRequest,
+        approvals: &[Approval],
+        quorum: Option<QuorumResult>,
+    ) -> CretoResult<Vec<Uuid>> {
+        let Some(dispatcher) = &self.webhooks else {
+            return Ok(Vec::new());
+        };
+        if !request.status.is_terminal() {
+            return Ok(Vec::new());
+        }
+        let payload = DecisionPayload::new(request, approvals, quorum)?;
+        dispatcher.dispatch(&payload).await
+    }
+
+    /// Run the registered enrichers against a new request.
+    ///
+    /// Enricher failures are recorded as unavailable sections and never
+    /// prevent the request from being created.
+    pub async fn enrich_request(&self, request: &mut OversightRequest, input: &EnrichmentInput) {
+        enrichment::enrich_request(&self.enrichers, request, input).await;
+    }
+
     /// Check if an action requires oversight and create a request if needed.
     ///
     /// This is the main entry point called by agents before executing actions.
@@ -89,7 +448,11 @@ impl OversightService {
                 reason,
                 suggested_reviewers,
             } => {
-                let request = OversightRequest::new(organization_id, agent_id, action, description);
+                let input = EnrichmentInput::from_policy_context(&context);
+                let mut request =
+                    OversightRequest::new(organization_id, agent_id, action, description)
+                        .with_correlation(context.correlation);
+                self.enrich_request(&mut request, &input).await;
 
                 // TODO: Look up actual user IDs from suggested_reviewers roles
                 let _ = suggested_reviewers;
@@ -118,17 +481,36 @@ impl OversightService {
         action: ActionType,
         context: PolicyContext,
     ) -> CretoResult<Option<Uuid>> {
-        // Early return if no trigger evaluator configured
-        let evaluator = match &self.trigger_evaluator {
-            Some(e) => e,
-            None => return Ok(None),
+        let Some(mut request) =
+            self.build_trigger_request(organization_id, agent_id, action, &context)
+        else {
+            return Ok(None);
         };
+        self.enrich_request(
+            &mut request,
+            &EnrichmentInput::from_policy_context(&context),
+        )
+        .await;
 
-        // Check if any triggers match
-        let trigger_match = match evaluator.evaluate(&action, &context) {
-            Some(m) => m,
-            None => return Ok(None),
-        };
+        // TODO: Persist request to database
+        // TODO: Send notifications via channels
+
+        Ok(Some(request.id))
+    }
+
+    /// Build the oversight request a matching policy trigger would create.
+    ///
+    /// The request inherits the trace identifiers in `context.correlation`.
+    /// Returns None if no trigger evaluator is configured or nothing matched.
+    pub fn build_trigger_request(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        action: ActionType,
+        context: &PolicyContext,
+    ) -> Option<OversightRequest> {
+        let evaluator = self.trigger_evaluator.as_ref()?;
+        let trigger_match = evaluator.evaluate(&action, context)?;
 
         // Create oversight request based on trigger
         let description = format!(
@@ -138,7 +520,8 @@ impl OversightService {
 
         let mut request = OversightRequest::new(organization_id, agent_id, action, description)
             .with_priority(trigger_match.priority)
-            .with_timeout(trigger_match.timeout_seconds);
+            .with_timeout(trigger_match.timeout_seconds)
+            .with_correlation(context.correlation);
 
         // Add policy context to request metadata
         let mut metadata = serde_json::Map::new();
@@ -148,7 +531,7 @@ impl OversightService {
         );
         metadata.insert(
             "policy_context".to_string(),
-            serde_json::to_value(&context).unwrap_or(serde_json::Value::Null),
+            serde_json::to_value(context).unwrap_or(serde_json::Value::Null),
         );
         metadata.insert("auto_triggered".to_string(), serde_json::Value::Bool(true));
         request.metadata = serde_json::Value::Object(metadata);
@@ -163,443 +546,4197 @@ impl OversightService {
             }
         }
 
+        Some(request)
+    }
+
+    /// Persist a new request, auditing its creation.
+    ///
+    /// Requests built by [`create_request_from_template`](Self::create_request_from_template)
+    /// or [`build_trigger_request`](Self::build_trigger_request) are saved
+    /// through here.
+    pub async fn create_request(&self, request: &OversightRequest) -> CretoResult<Uuid> {
+        let requests = self.requests.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Creating requests requires a request repository".to_string())
+        })?;
+        let event = self
+            .audit_event(
+                AuditActor::Agent {
+                    agent_id: request.agent_id,
+                },
+                request,
+                "oversight.request.created",
+            )
+            .with_detail(serde_json::json!({
+                "action_type": request.action_type,
+                "priority": request.priority.as_str(),
+                "expires_at": request.expires_at,
+            }));
+        self.audited(Some(event), requests.create(request)).await
+    }
+
+    /// Build an oversight request from an organization template.
+    ///
+    /// The action must match the template's action pattern and `fields` must
+    /// satisfy its schema. The request takes the template's priority and
+    /// timeout, uses the supplied fields as its context, and records the
+    /// template version so later template updates do not affect it.
+    pub async fn create_request_from_template(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        template_id: Uuid,
+        action: ActionType,
+        description: impl Into<String>,
+        fields: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<OversightRequest, TemplateError> {
+        self.create_request_from_template_with_input(
+            organization_id,
+            agent_id,
+            template_id,
+            action,
+            description,
+            fields,
+            &EnrichmentInput::default(),
+        )
+        .await
+    }
+
+    /// Build an oversight request from an organization template, passing
+    /// `input` to the context enrichers.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_request_from_template_with_input(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        template_id: Uuid,
+        action: ActionType,
+        description: impl Into<String>,
+        fields: serde_json::Map<String, serde_json::Value>,
+        input: &EnrichmentInput,
+    ) -> Result<OversightRequest, TemplateError> {
+        let template = self
+            .template_store
+            .get(organization_id, template_id)
+            .await?
+            .ok_or(TemplateError::NotFound { template_id })?;
+        let applied = template.apply(&action, &fields)?;
+
+        let mut request = OversightRequest::new(organization_id, agent_id, action, description)
+            .with_priority(template.default_priority)
+            .with_timeout(template.timeout_seconds())
+            .with_context(serde_json::Value::Object(fields));
+
+        if let (Some(group), Some(metadata)) =
+            (&applied.reviewer_group, request.metadata.as_object_mut())
+        {
+            metadata.insert(
+                "reviewer_group".to_string(),
+                serde_json::Value::String(group.clone()),
+            );
+        }
+        if let Some(group) = &applied.reviewer_group {
+            match self.reviewer_groups.get(organization_id, group).await? {
+                Some(group) if group.is_active() => {
+                    for reviewer in group.members() {
+                        request.add_reviewer(reviewer);
+                    }
+                }
+                _ => tracing::warn!(
+                    template_id = %template_id,
+                    reviewer_group = %group,
+                    "Reviewer group missing or disabled; no reviewers assigned"
+                ),
+            }
+        }
+
+        self.enrich_request(&mut request, input).await;
+
         // TODO: Persist request to database
         // TODO: Send notifications via channels
 
-        Ok(Some(request.id))
+        Ok(request.with_template(applied))
     }
 
-    /// Generate a human-readable description of a trigger condition.
-    fn describe_trigger(&self, condition: &crate::triggers::TriggerCondition) -> String {
-        use crate::triggers::TriggerCondition;
+    /// Quorum that applies to a request.
+    ///
+    /// Requests created from a template use the template's quorum, if it set
+    /// one, as of the version the request was created with.
+    pub fn quorum_for(&self, request: &OversightRequest) -> QuorumConfig {
+        request
+            .template
+            .as_ref()
+            .and_then(|t| t.quorum.clone())
+            .unwrap_or_else(|| self.default_quorum.clone())
+    }
 
-        match condition {
-            TriggerCondition::AmountThreshold {
-                threshold_cents,
-                currency,
-            } => {
-                let amount = *threshold_cents as f64 / 100.0;
-                let curr = currency.as_deref().unwrap_or("any currency");
-                format!("Transaction exceeds ${:.2} {}", amount, curr)
-            }
-            TriggerCondition::ActionType { pattern } => {
-                format!("Action type matches pattern: {:?}", pattern)
-            }
-            TriggerCondition::AgentTier { min_trust_level } => {
-                format!("Agent trust level below {:?}", min_trust_level)
-            }
-            TriggerCondition::TimeWindow { .. } => {
-                "Action attempted during restricted time window".to_string()
-            }
-            TriggerCondition::QuotaUsage {
-                threshold_percentage,
-            } => {
-                format!("Quota usage exceeds {:.0}%", threshold_percentage * 100.0)
-            }
-            TriggerCondition::DelegationDepth { max_depth } => {
-                format!("Delegation depth exceeds {}", max_depth)
-            }
-            TriggerCondition::DataSensitivity { scopes } => {
-                format!("Access to sensitive data: {}", scopes.join(", "))
-            }
-            TriggerCondition::RiskLevel { levels } => {
-                format!("Risk level matches: {}", levels.join(", "))
+    /// Evaluate a request's approvals against its quorum.
+    ///
+    /// For requests routed to a reviewer group, only decisions by reviewers
+    /// who belonged to the group when they decided are counted. The group
+    /// is resolved through the reviewer group store, so it still applies
+    /// after being disabled by a directory sync. With reviewer deadlines
+    /// configured and the organization excluding lapsed reviewers, their
+    /// approvals are no longer required.
+    pub async fn evaluate_quorum(
+        &self,
+        request: &OversightRequest,
+        approvals: &[Approval],
+    ) -> CretoResult<QuorumResult> {
+        let mut calculator = QuorumCalculator::new(self.quorum_for(request));
+        if let Some(store) = &self.assignments {
+            let policy = self.reviewer_deadlines.policy_for(request.organization_id);
+            if policy.lapse_mode == LapseMode::Exclude {
+                let assignments = store.list_by_request(request.id).await?;
+                let lapsed = assignments
+                    .iter()
+                    .filter(|a| a.status == AssignmentStatus::Lapsed)
+                    .count();
+                calculator =
+                    calculator.with_lapsed_reviewers(assignments.len() as u32, lapsed as u32);
             }
         }
+        if let Some(name) = request
+            .template
+            .as_ref()
+            .and_then(|t| t.reviewer_group.as_deref())
+        {
+            let group = self
+                .reviewer_groups
+                .get(request.organization_id, name)
+                .await?
+                .ok_or_else(|| CretoError::NotFound(format!("Reviewer group {}", name)))?;
+            calculator = calculator.with_reviewer_group(group);
+        }
+        Ok(calculator.evaluate(approvals))
     }
 
-    /// Submit an approval decision for a request.
-    pub async fn submit_approval(
+    /// Set notification preferences for a reviewer, or organization defaults
+    /// when `reviewer_id` is `None`.
+    pub async fn set_notification_preferences(
         &self,
-        request_id: Uuid,
-        reviewer_id: UserId,
-        decision: ApprovalDecision,
-        reason: Option<String>,
-    ) -> CretoResult<ApprovalSubmitResult> {
-        // TODO: Load request from database
-        // For now, create a mock
-        let mut state_machine = StateMachine::new();
+        preferences: NotificationPreferences,
+    ) -> CretoResult<NotificationPreferences> {
+        self.notifications.preferences().put(preferences).await
+    }
 
-        // Create approval
-        let mut approval = Approval::new(request_id, reviewer_id, decision);
-        if let Some(r) = reason {
-            approval = approval.with_reason(r);
-        }
+    /// Get stored notification preferences.
+    pub async fn get_notification_preferences(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: Option<UserId>,
+    ) -> CretoResult<Option<NotificationPreferences>> {
+        self.notifications
+            .preferences()
+            .get(organization_id, reviewer_id)
+            .await
+    }
 
-        // TODO: Load all approvals for this request
-        let approvals = vec![approval];
+    /// Delete notification preferences, returning whether any existed.
+    pub async fn delete_notification_preferences(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: Option<UserId>,
+    ) -> CretoResult<bool> {
+        self.notifications
+            .preferences()
+            .delete(organization_id, reviewer_id)
+            .await
+    }
 
-        // Calculate quorum
-        let calculator = QuorumCalculator::new(self.default_quorum.clone());
-        let quorum_result = calculator.evaluate(&approvals);
+    /// Notify a request's reviewers according to their preferences.
+    pub async fn notify_reviewers(&self, request: &OversightRequest) -> CretoResult<RoutingReport> {
+        let report = self.notifications.route(request).await?;
+        self.record_notifications(&report.sent).await?;
+        Ok(report)
+    }
 
-        // Update state based on quorum result
-        let new_status = match &quorum_result {
-            QuorumResult::Approved { .. } => {
-                state_machine.transition(
-                    RequestStatus::Approved,
-                    Actor::User {
-                        user_id: reviewer_id,
-                    },
-                    Some("Quorum reached".to_string()),
-                )?;
-                RequestStatus::Approved
-            }
-            QuorumResult::Rejected { .. } => {
-                state_machine.transition(
-                    RequestStatus::Rejected,
-                    Actor::User {
-                        user_id: reviewer_id,
-                    },
-                    Some("Request rejected".to_string()),
-                )?;
-                RequestStatus::Rejected
-            }
-            QuorumResult::Pending { .. } => {
-                state_machine.transition(
-                    RequestStatus::InReview,
-                    Actor::User {
-                        user_id: reviewer_id,
-                    },
-                    None,
-                )?;
-                RequestStatus::InReview
+    /// Notify a request on the channels the channel router's rules select.
+    ///
+    /// The channels that delivered are saved on the request, when requests
+    /// are persisted, so its escalations and reminders go out on the same
+    /// channels. Check [`FanOutReport::delivered`] for whether a required
+    /// channel succeeded.
+    pub async fn notify(&self, request: &OversightRequest) -> CretoResult<FanOutReport> {
+        let router = self.channel_router.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Channel routing is not configured".to_string())
+        })?;
+        let report = router.notify(request).await;
+        self.record_notifications(&report.sent_notifications())
+            .await?;
+
+        let channels = report.notified_channels();
+        if let Some(requests) = &self.requests {
+            if !channels.is_empty() {
+                requests
+                    .set_notified_channels(request.id, &channels)
+                    .await?;
             }
+        }
+        Ok(report)
+    }
+
+    /// Send held notifications whose quiet hours or digest time has come.
+    pub async fn deliver_due_notifications(&self) -> CretoResult<Vec<SentNotification>> {
+        let sent = self.notifications.deliver_due().await?;
+        self.record_notifications(&sent).await?;
+        Ok(sent)
+    }
+
+    /// Raise the priority of open requests nearing their timeout.
+    ///
+    /// Each request the aging policy boosts is saved with its new priority,
+    /// the boost is recorded in the transition audit trail as a system
+    /// transition with reason [`AGING_REASON`], and its reviewers are
+    /// notified again. A request that fails to update is logged and retried
+    /// on the next pass. Does nothing without an aging policy.
+    pub async fn boost_aging_requests(&self) -> CretoResult<Vec<AgingBoost>> {
+        let Some(policy) = &self.aging else {
+            return Ok(Vec::new());
         };
+        let requests = self.requests.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Priority aging requires a request repository".to_string())
+        })?;
 
-        // TODO: Persist approval and updated request state
-        // TODO: Notify relevant parties
+        let now = self.clock.now();
+        let mut boosts = Vec::new();
+        for mut request in requests.list_open().await? {
+            let Some(priority) = policy.boosted_priority(&request, now) else {
+                continue;
+            };
+            let boost = AgingBoost {
+                request_id: request.id,
+                organization_id: request.organization_id,
+                from: request.priority,
+                to: priority,
+                original: request.original_priority(),
+            };
+            if let Err(e) = self
+                .apply_boost(requests.as_ref(), &mut request, &boost)
+                .await
+            {
+                tracing::warn!(request_id = %request.id, error = %e, "Failed to boost aging request");
+                continue;
+            }
+            boosts.push(boost);
+        }
+        Ok(boosts)
+    }
 
-        Ok(ApprovalSubmitResult {
-            request_id,
-            new_status,
-            quorum_result,
-        })
+    async fn apply_boost(
+        &self,
+        requests: &dyn RequestRepository,
+        request: &mut OversightRequest,
+        boost: &AgingBoost,
+    ) -> CretoResult<()> {
+        requests.update_priority(request.id, boost.to).await?;
+        request.original_priority = Some(boost.original);
+        request.priority = boost.to;
+
+        let transition = StateTransition {
+            id: Uuid::now_v7(),
+            from: request.status,
+            to: request.status,
+            actor: Actor::System,
+            reason: Some(AGING_REASON.to_string()),
+            timestamp: self.clock.now(),
+        };
+        self.record_transition(request.id, &transition).await?;
+
+        // The boost is saved; a failed re-notification does not undo it
+        if let Err(e) = self.notify_reviewers(request).await {
+            tracing::warn!(request_id = %request.id, error = %e, "Failed to notify reviewers of boost");
+        }
+        Ok(())
     }
 
-    /// Get the status of an oversight request.
-    pub async fn get_request_status(
+    /// Assign reviewers to an open request, each with a deadline derived
+    /// from the request's priority.
+    ///
+    /// Reviewers already assigned keep their existing assignment and
+    /// deadline. Returns the assignments of `reviewers`.
+    pub async fn assign_reviewers(
         &self,
         request_id: Uuid,
-    ) -> CretoResult<Option<OversightRequest>> {
-        // TODO: Load from database
-        let _ = request_id;
-        Ok(None)
+        reviewers: &[UserId],
+        source: AssignmentSource,
+    ) -> CretoResult<Vec<ReviewerAssignment>> {
+        let (requests, _) = self.assignment_stores()?;
+        let request = load_request(requests, request_id).await?;
+        ensure_open(&request)?;
+
+        let mut assigned = Vec::with_capacity(reviewers.len());
+        for reviewer_id in reviewers {
+            assigned.push(self.assign(&request, *reviewer_id, source.clone()).await?);
+        }
+        Ok(assigned)
     }
 
-    /// List pending requests for a reviewer.
-    pub async fn list_pending_for_reviewer(
+    /// Assign every current member of a reviewer group to an open request.
+    pub async fn assign_group(
         &self,
-        reviewer_id: UserId,
-    ) -> CretoResult<Vec<OversightRequest>> {
-        // TODO: Query database for requests assigned to this reviewer
-        let _ = reviewer_id;
-        Ok(Vec::new())
+        request_id: Uuid,
+        group_name: &str,
+    ) -> CretoResult<Vec<ReviewerAssignment>> {
+        let (requests, _) = self.assignment_stores()?;
+        let request = load_request(requests, request_id).await?;
+        let group = self
+            .reviewer_groups
+            .get(request.organization_id, group_name)
+            .await?
+            .ok_or_else(|| CretoError::NotFound(format!("Reviewer group {}", group_name)))?;
+        self.assign_reviewers(
+            request_id,
+            &group.members(),
+            AssignmentSource::Group {
+                name: group.name.clone(),
+            },
+        )
+        .await
     }
 
-    /// Save a checkpoint for a request.
+    /// Claim the organization's next pending request for `reviewer_id`.
     ///
-    /// This creates an atomic snapshot of the request's current state,
-    /// including conversation context and state machine history.
-    pub async fn save_checkpoint(
+    /// Picks the highest-priority, oldest pending request the reviewer is
+    /// not yet assigned to. Returns `None` when there is none.
+    pub async fn assign_next(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: UserId,
+    ) -> CretoResult<Option<ReviewerAssignment>> {
+        let (requests, store) = self.assignment_stores()?;
+        for request in requests.list_pending(organization_id).await? {
+            let assignments = store.list_by_request(request.id).await?;
+            if assignments.iter().any(|a| a.reviewer_id == reviewer_id) {
+                continue;
+            }
+            return Ok(Some(
+                self.assign(&request, reviewer_id, AssignmentSource::Claim)
+                    .await?,
+            ));
+        }
+        Ok(None)
+    }
+
+    async fn assign(
         &self,
         request: &OversightRequest,
-        state_machine: &StateMachine,
-        context: serde_json::Value,
-        reason: Option<String>,
-    ) -> CretoResult<Uuid> {
-        let manager = self.checkpoint_manager.as_ref().ok_or_else(|| {
-            CretoError::Configuration("Checkpoint manager not configured".to_string())
-        })?;
+        reviewer_id: UserId,
+        source: AssignmentSource,
+    ) -> CretoResult<ReviewerAssignment> {
+        let (_, store) = self.assignment_stores()?;
+        let now = self.clock.now();
+        let policy = self.reviewer_deadlines.policy_for(request.organization_id);
+        store
+            .assign(&ReviewerAssignment {
+                id: Uuid::now_v7(),
+                request_id: request.id,
+                organization_id: request.organization_id,
+                reviewer_id,
+                source,
+                status: AssignmentStatus::Active,
+                assigned_at: now,
+                respond_by: policy.respond_by(request, now),
+                lapsed_at: None,
+            })
+            .await
+    }
 
-        let mut checkpoint = Checkpoint::new(
-            request.id,
-            state_machine.current(), // Use state machine's current state
-            state_machine,
-            context,
-        );
+    /// Mark assignments past their deadline as lapsed.
+    ///
+    /// For each lapse the organization's escalation contact is notified and
+    /// a system transition with reason `assignment_lapsed:<reviewer id>` is
+    /// recorded in the audit trail. If the organization excludes lapsed
+    /// reviewers and approvals are persisted, the request's quorum is
+    /// recalculated and the request decided if the remaining reviewers
+    /// already reached it. Assignments on closed requests are marked lapsed
+    /// without escalating. An assignment that fails to update is logged and
+    /// retried on the next pass.
+    pub async fn lapse_overdue_assignments(&self) -> CretoResult<Vec<AssignmentLapse>> {
+        if self.assignments.is_none() {
+            return Ok(Vec::new());
+        }
+        let (_, store) = self.assignment_stores()?;
+        let now = self.clock.now();
+        let mut lapses = Vec::new();
+        for assignment in store.find_overdue(now).await? {
+            match self.apply_lapse(store, assignment).await {
+                Ok(lapse) => lapses.push(lapse),
+                Err(e) => tracing::warn!(error = %e, "Failed to lapse reviewer assignment"),
+            }
+        }
+        Ok(lapses)
+    }
 
-        if let Some(r) = reason {
-            checkpoint = checkpoint.with_reason(r);
+    async fn apply_lapse(
+        &self,
+        store: &dyn AssignmentStore,
+        mut assignment: ReviewerAssignment,
+    ) -> CretoResult<AssignmentLapse> {
+        let (requests, _) = self.assignment_stores()?;
+        let now = self.clock.now();
+        store
+            .update_status(assignment.id, AssignmentStatus::Lapsed, now)
+            .await?;
+        assignment.status = AssignmentStatus::Lapsed;
+        assignment.lapsed_at = Some(now);
+
+        let policy = self
+            .reviewer_deadlines
+            .policy_for(assignment.organization_id);
+        let mut lapse = AssignmentLapse {
+            assignment,
+            mode: policy.lapse_mode,
+            decided: None,
+        };
+        let mut request = load_request(requests, lapse.assignment.request_id).await?;
+        if request.status.is_terminal() {
+            return Ok(lapse);
         }
 
-        manager.save_checkpoint(&checkpoint).await
+        let transition = StateTransition {
+            id: Uuid::now_v7(),
+            from: request.status,
+            to: request.status,
+            actor: Actor::System,
+            reason: Some(format!("{}:{}", LAPSE_REASON, lapse.assignment.reviewer_id)),
+            timestamp: now,
+        };
+        self.record_transition(request.id, &transition).await?;
+
+        // The lapse is saved; a failed escalation does not undo it
+        if let Some(contact) = policy.escalation_contact {
+            let mut escalation = request.clone();
+            escalation.assigned_reviewers = vec![contact];
+            if let Err(e) = self.notify_reviewers(&escalation).await {
+                tracing::warn!(request_id = %request.id, error = %e, "Failed to escalate lapsed reviewer");
+            }
+        }
+
+        if lapse.mode == LapseMode::Exclude {
+            lapse.decided = self.decide_after_lapse(requests, &mut request).await?;
+        }
+        Ok(lapse)
     }
 
-    /// Load the latest checkpoint for a request.
-    pub async fn load_checkpoint(&self, request_id: Uuid) -> CretoResult<Option<Checkpoint>> {
-        let manager = self.checkpoint_manager.as_ref().ok_or_else(|| {
-            CretoError::Configuration("Checkpoint manager not configured".to_string())
-        })?;
+    /// Decide a request whose remaining reviewers already reached the
+    /// reduced quorum.
+    async fn decide_after_lapse(
+        &self,
+        requests: &dyn RequestRepository,
+        request: &mut OversightRequest,
+    ) -> CretoResult<Option<RequestStatus>> {
+        let Some(approvals) = &self.approvals else {
+            return Ok(None);
+        };
+        let approvals = approvals.list_by_request(request.id).await?;
+        let quorum_result = self.evaluate_quorum(request, &approvals).await?;
+        let status = match &quorum_result {
+            QuorumResult::Approved { .. } => RequestStatus::Approved,
+            QuorumResult::Rejected { .. } => RequestStatus::Rejected,
+            QuorumResult::Pending { .. } => return Ok(None),
+        };
 
-        manager.load_checkpoint(request_id).await
+        let mut state_machine = StateMachine::from_state(request.status);
+        state_machine.transition(
+            status,
+            Actor::System,
+            Some("Quorum reached after reviewer lapse".to_string()),
+        )?;
+        requests.update_status(request.id, status).await?;
+        if let Some(mut transition) = state_machine.history().last().cloned() {
+            transition.timestamp = self.clock.now();
+            self.record_transition(request.id, &transition).await?;
+        }
+        request.status = status;
+
+        if let Err(e) = self
+            .notify_decision(request, &approvals, Some(quorum_result))
+            .await
+        {
+            tracing::warn!(request_id = %request.id, error = %e, "Failed to send decision webhooks");
+        }
+        Ok(Some(status))
     }
 
-    /// Recover all pending requests after a system restart.
+    fn assignment_stores(&self) -> CretoResult<(&dyn RequestRepository, &dyn AssignmentStore)> {
+        match (&self.requests, &self.assignments) {
+            (Some(requests), Some(assignments)) => Ok((requests.as_ref(), assignments.as_ref())),
+            _ => Err(CretoError::Configuration(
+                "Reviewer assignments require a request repository and an assignment store"
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Escalate open requests past their timeout.
     ///
-    /// This scans for incomplete oversight requests and restores their state
-    /// from the most recent checkpoint, allowing the system to resume where
-    /// it left off.
-    pub async fn recover_pending_requests(&self) -> CretoResult<Vec<RecoveredRequest>> {
-        // TODO: Implement full recovery logic with repository
-        // This is a placeholder showing the intended flow:
+    /// Requests of organizations with an escalation chain are handed to the
+    /// chain's next tier, or closed as the chain says once every tier has
+    /// timed out, as [`escalate_request`](Self::escalate_request) does.
+    /// Requests of other organizations are timed out as
+    /// [`expire_timed_out_requests`](crate::timeouts::expire_timed_out_requests)
+    /// does. A request that fails to update is logged and retried on the
+    /// next pass.
+    pub async fn escalate_timed_out_requests(&self) -> CretoResult<Vec<Escalation>> {
+        let (requests, engine) = self.escalation_stores()?;
+        let mut escalations = Vec::new();
+        for request_id in requests.find_timed_out().await? {
+            match self.escalate_timed_out(requests, engine, request_id).await {
+                Ok(escalation) => escalations.push(escalation),
+                Err(e) => {
+                    tracing::warn!(request_id = %request_id, error = %e, "Failed to escalate timed out request")
+                }
+            }
+        }
+        Ok(escalations)
+    }
 
-        // 1. Query for all requests in non-terminal states
-        // 2. For each request, load the latest checkpoint
-        // 3. Restore state machine from checkpoint
-        // 4. Validate that the checkpoint is compatible
-        // 5. Resume processing from the restored state
+    async fn escalate_timed_out(
+        &self,
+        requests: &dyn RequestRepository,
+        engine: &EscalationEngine,
+        request_id: Uuid,
+    ) -> CretoResult<Escalation> {
+        let request = load_request(requests, request_id).await?;
+        let trigger = EscalationTrigger::TimedOut;
+        match engine.next_step(&request, self.clock.now()).await? {
+            Some(step) => {
+                self.apply_escalation(requests, request, step, trigger)
+                    .await
+            }
+            None => {
+                self.expire_request(requests, &request).await?;
+                Ok(Escalation {
+                    request_id,
+                    organization_id: request.organization_id,
+                    trigger,
+                    outcome: EscalationOutcome::TimedOut,
+                })
+            }
+        }
+    }
 
-        let manager = self.checkpoint_manager.as_ref().ok_or_else(|| {
-            CretoError::Configuration("Checkpoint manager not configured".to_string())
+    /// Time out open requests past their deadline, auditing each.
+    ///
+    /// The service counterpart of
+    /// [`expire_timed_out_requests`](crate::timeouts::expire_timed_out_requests).
+    /// A request that fails to update is logged and retried on the next
+    /// pass. Returns the number of requests timed out.
+    pub async fn expire_timed_out_requests(&self) -> CretoResult<usize> {
+        let requests = self.requests.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Request timeouts require a request repository".to_string())
         })?;
+        let mut expired = 0;
+        for request_id in requests.find_timed_out().await? {
+            let result = match load_request(requests.as_ref(), request_id).await {
+                Ok(request) => self.expire_request(requests.as_ref(), &request).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => expired += 1,
+                Err(e) => {
+                    tracing::warn!(request_id = %request_id, error = %e, "Failed to time out request")
+                }
+            }
+        }
+        Ok(expired)
+    }
 
-        let _ = manager; // Suppress warning until full implementation
+    async fn expire_request(
+        &self,
+        requests: &dyn RequestRepository,
+        request: &OversightRequest,
+    ) -> CretoResult<()> {
+        let event = self
+            .audit_event(AuditActor::System, request, "oversight.request.timed_out")
+            .with_detail(serde_json::json!({ "expires_at": request.expires_at }));
+        self.audited(
+            Some(event),
+            crate::timeouts::expire_request(requests, self.webhooks.as_ref(), request.id),
+        )
+        .await
+    }
 
-        Ok(Vec::new())
+    /// Hand an open request to the next tier of its organization's
+    /// escalation chain now.
+    ///
+    /// The request becomes [`RequestStatus::Escalated`], is reassigned to
+    /// the tier's reviewers with the tier's timeout, and the reviewers are
+    /// notified on the channels the request was delivered on by
+    /// [`notify`](Self::notify), or on every registered channel if it was
+    /// not routed that way. Once every tier has had its
+    /// turn, the request is closed as the chain's
+    /// [`ExhaustedAction`](crate::escalation::ExhaustedAction) says and
+    /// decision webhooks are sent. Either way the change is recorded in the
+    /// transition audit trail as a system transition. Fails with
+    /// [`CretoError::NotFound`] if the organization has no chain.
+    pub async fn escalate_request(
+        &self,
+        request_id: Uuid,
+        trigger: EscalationTrigger,
+    ) -> CretoResult<Escalation> {
+        let (requests, engine) = self.escalation_stores()?;
+        let request = load_request(requests, request_id).await?;
+        ensure_open(&request)?;
+        let step = engine
+            .next_step(&request, self.clock.now())
+            .await?
+            .ok_or_else(|| {
+                CretoError::NotFound(format!(
+                    "Escalation chain for organization {}",
+                    request.organization_id
+                ))
+            })?;
+        self.apply_escalation(requests, request, step, trigger)
+            .await
     }
-}
 
-/// Result of recovering a request from a checkpoint.
-#[derive(Debug, Clone)]
-pub struct RecoveredRequest {
-    /// The request that was recovered.
-    pub request_id: Uuid,
+    async fn apply_escalation(
+        &self,
+        requests: &dyn RequestRepository,
+        mut request: OversightRequest,
+        step: EscalationStep,
+        trigger: EscalationTrigger,
+    ) -> CretoResult<Escalation> {
+        let now = self.clock.now();
+        let mut state_machine = StateMachine::from_state(request.status);
+        let outcome = match step {
+            EscalationStep::Escalate {
+                level,
+                tier,
+                expires_at,
+            } => {
+                state_machine.transition(
+                    RequestStatus::Escalated,
+                    Actor::System,
+                    Some(format!("{}:{}", ESCALATION_REASON, tier.name)),
+                )?;
+                let event = self
+                    .audit_event(
+                        escalation_actor(&trigger),
+                        &request,
+                        "oversight.request.escalated",
+                    )
+                    .with_detail(serde_json::json!({
+                        "level": level,
+                        "tier": tier.name,
+                        "reviewers": tier.reviewers,
+                    }));
+                self.audited(
+                    Some(event),
+                    requests.escalate(request.id, level, &tier.reviewers, expires_at),
+                )
+                .await?;
+                request.escalation_level = level;
+                request.assigned_reviewers = tier.reviewers.clone();
+                request.expires_at = expires_at;
+                EscalationOutcome::Escalated {
+                    level,
+                    tier: tier.name,
+                    reviewers: tier.reviewers,
+                    expires_at,
+                }
+            }
+            EscalationStep::Exhausted { status } => {
+                state_machine.transition(
+                    status,
+                    Actor::System,
+                    Some(EXHAUSTED_REASON.to_string()),
+                )?;
+                let event = self
+                    .audit_event(
+                        escalation_actor(&trigger),
+                        &request,
+                        "oversight.request.escalation_exhausted",
+                    )
+                    .with_detail(serde_json::json!({ "status": status.as_str() }));
+                self.audited(Some(event), requests.update_status(request.id, status))
+                    .await?;
+                EscalationOutcome::Exhausted { status }
+            }
+        };
+        if let Some(mut transition) = state_machine.history().last().cloned() {
+            transition.timestamp = now;
+            self.record_transition(request.id, &transition).await?;
+        }
+        request.status = state_machine.current();
+        request.updated_at = now;
+
+        // The escalation is saved; failed notifications do not undo it
+        if request.status.is_terminal() {
+            if let Err(e) = self.notify_decision(&request, &[], None).await {
+                tracing::warn!(request_id = %request.id, error = %e, "Failed to send decision webhooks");
+            }
+        } else {
+            let sent = match &self.channel_router {
+                Some(router) if !request.notified_channels.is_empty() => router
+                    .notify_on(&request, &request.notified_channels)
+                    .await
+                    .sent_notifications(),
+                _ => self.notifications.broadcast(&request).await,
+            };
+            if let Err(e) = self.record_notifications(&sent).await {
+                tracing::warn!(request_id = %request.id, error = %e, "Failed to record escalation notices");
+            }
+        }
+
+        Ok(Escalation {
+            request_id: request.id,
+            organization_id: request.organization_id,
+            trigger,
+            outcome,
+        })
+    }
+
+    fn escalation_stores(&self) -> CretoResult<(&dyn RequestRepository, &EscalationEngine)> {
+        match (&self.requests, &self.escalation) {
+            (Some(requests), Some(engine)) => Ok((requests.as_ref(), engine)),
+            _ => Err(CretoError::Configuration(
+                "Escalation requires a request repository and an escalation engine".to_string(),
+            )),
+        }
+    }
+
+    /// Spawn the request timeout worker.
+    ///
+    /// Every `interval`, escalates open requests past their deadline with
+    /// [`escalate_timed_out_requests`](Self::escalate_timed_out_requests),
+    /// or without an escalation engine times them out as
+    /// [`expire_timed_out_requests`](crate::timeouts::expire_timed_out_requests)
+    /// does, boosts aging requests with
+    /// [`boost_aging_requests`](Self::boost_aging_requests), and lapses
+    /// reviewers past their own deadline with
+    /// [`lapse_overdue_assignments`](Self::lapse_overdue_assignments). The
+    /// task is registered with `shutdown` and stops between passes.
+    pub fn spawn_timeout_worker(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        shutdown.spawn("oversight.timeout_worker", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    if service.escalation.is_some() {
+                        match service.escalate_timed_out_requests().await {
+                            Ok(escalations) if escalations.is_empty() => {}
+                            Ok(escalations) => {
+                                tracing::info!(
+                                    escalated = escalations.len(),
+                                    "Escalated timed out oversight requests"
+                                )
+                            }
+                            Err(e) => tracing::warn!(error = %e, "Escalation pass failed"),
+                        }
+                    } else if service.requests.is_some() {
+                        match service.expire_timed_out_requests().await {
+                            Ok(0) => {}
+                            Ok(expired) => tracing::info!(expired, "Timed out oversight requests"),
+                            Err(e) => tracing::warn!(error = %e, "Timeout worker pass failed"),
+                        }
+                    }
+                    match service.boost_aging_requests().await {
+                        Ok(boosts) if boosts.is_empty() => {}
+                        Ok(boosts) => {
+                            tracing::info!(
+                                boosted = boosts.len(),
+                                "Boosted aging oversight requests"
+                            )
+                        }
+                        Err(e) => tracing::warn!(error = %e, "Priority aging pass failed"),
+                    }
+                    match service.lapse_overdue_assignments().await {
+                        Ok(lapses) if lapses.is_empty() => {}
+                        Ok(lapses) => {
+                            tracing::info!(lapsed = lapses.len(), "Lapsed overdue reviewers")
+                        }
+                        Err(e) => tracing::warn!(error = %e, "Reviewer deadline pass failed"),
+                    }
+                    if let Err(e) = service.refresh_metrics().await {
+                        tracing::warn!(error = %e, "Oversight metrics refresh failed");
+                    }
+                }
+            })
+        })
+    }
+
+    /// Set the pending-requests gauge from the open requests in the request
+    /// repository.
+    ///
+    /// Does nothing without exported metrics or a request repository.
+    pub async fn refresh_metrics(&self) -> CretoResult<()> {
+        let (Some(metrics), Some(requests)) = (&self.metrics, &self.requests) else {
+            return Ok(());
+        };
+        let open = requests.list_open().await?;
+        for status in [RequestStatus::Pending, RequestStatus::InReview] {
+            let count = open.iter().filter(|r| r.status == status).count();
+            metrics
+                .pending
+                .with_label_values(&[status.as_str()])
+                .set(count as i64);
+        }
+        Ok(())
+    }
+
+    /// Record something that happened to a request in the activity log.
+    ///
+    /// Does nothing if no activity log is configured.
+    pub async fn record_activity(
+        &self,
+        request_id: Uuid,
+        actor: Actor,
+        kind: ActivityKind,
+    ) -> CretoResult<()> {
+        let Some(log) = &self.activity else {
+            return Ok(());
+        };
+        let activity = RequestActivity::new(request_id, actor, kind).at(self.clock.now());
+        log.record(&activity).await
+    }
+
+    /// Record a reviewer's comment on a request.
+    pub async fn add_comment(
+        &self,
+        request_id: Uuid,
+        reviewer_id: UserId,
+        text: impl Into<String>,
+    ) -> CretoResult<()> {
+        if self.activity.is_none() {
+            return Err(CretoError::Configuration(
+                "Comments require an activity log".to_string(),
+            ));
+        }
+        if let Some(requests) = &self.requests {
+            load_request(requests.as_ref(), request_id).await?;
+        }
+        self.record_activity(
+            request_id,
+            Actor::User {
+                user_id: reviewer_id,
+            },
+            ActivityKind::Commented { text: text.into() },
+        )
+        .await
+    }
+
+    async fn record_notifications(&self, sent: &[SentNotification]) -> CretoResult<()> {
+        for notification in sent.iter().filter(|n| n.result.success) {
+            for request_id in &notification.request_ids {
+                self.record_activity(
+                    *request_id,
+                    Actor::System,
+                    ActivityKind::Notified {
+                        channel: notification.channel,
+                        reviewer_id: notification.reviewer_id,
+                        digest: notification.request_ids.len() > 1,
+                    },
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generate a human-readable description of a trigger condition.
+    fn describe_trigger(&self, condition: &crate::triggers::TriggerCondition) -> String {
+        use crate::triggers::TriggerCondition;
+
+        match condition {
+            TriggerCondition::AmountThreshold {
+                threshold_cents,
+                currency,
+            } => {
+                let amount = *threshold_cents as f64 / 100.0;
+                let curr = currency.as_deref().unwrap_or("any currency");
+                format!("Transaction exceeds ${:.2} {}", amount, curr)
+            }
+            TriggerCondition::ActionType { pattern } => {
+                format!("Action type matches pattern: {:?}", pattern)
+            }
+            TriggerCondition::AgentTier { min_trust_level } => {
+                format!("Agent trust level below {:?}", min_trust_level)
+            }
+            TriggerCondition::TimeWindow { .. } => {
+                "Action attempted during restricted time window".to_string()
+            }
+            TriggerCondition::QuotaUsage {
+                threshold_percentage,
+            } => {
+                format!("Quota usage exceeds {:.0}%", threshold_percentage * 100.0)
+            }
+            TriggerCondition::DelegationDepth { max_depth } => {
+                format!("Delegation depth exceeds {}", max_depth)
+            }
+            TriggerCondition::DataSensitivity { scopes } => {
+                format!("Access to sensitive data: {}", scopes.join(", "))
+            }
+            TriggerCondition::RiskLevel { levels } => {
+                format!("Risk level matches: {}", levels.join(", "))
+            }
+            TriggerCondition::UsageAnomaly { min_multiple } => {
+                format!("Usage spike of at least {:.0}x baseline", min_multiple)
+            }
+        }
+    }
+
+    /// Submit an approval decision for a request.
+    ///
+    /// With a request repository configured, decisions on a request that is
+    /// already closed (decided, timed out, or cancelled) fail with
+    /// [`CretoError::RequestClosed`]. With reviewer deadlines configured, a
+    /// reviewer whose assignment lapsed fails with
+    /// [`CretoError::AssignmentLapsed`] unless the organization allows late
+    /// decisions. [`ApprovalDecision::Escalate`] on a request whose
+    /// organization has an escalation chain escalates the request right
+    /// away, as [`escalate_request`](Self::escalate_request) does.
+    ///
+    /// The decision carries the reviewer's weight as resolved now, by their
+    /// own weight or their directory roles (see
+    /// [`resolve_weight`](crate::weights::resolve_weight)), never one
+    /// supplied by the client. With approval persistence configured too, the
+    /// decision is saved and the quorum is evaluated over every reviewer's
+    /// latest decision; a reviewer deciding again replaces their earlier
+    /// decision.
+    pub async fn submit_approval(
+        &self,
+        request_id: Uuid,
+        reviewer_id: UserId,
+        decision: ApprovalDecision,
+        reason: Option<String>,
+    ) -> CretoResult<ApprovalSubmitResult> {
+        let (mut state_machine, assignment, request) = match &self.requests {
+            Some(requests) => {
+                let request = load_request(requests.as_ref(), request_id).await?;
+                ensure_open(&request)?;
+                let assignment = self.check_assignment(&request, reviewer_id).await?;
+                if decision == ApprovalDecision::Escalate {
+                    if let Some(result) = self
+                        .escalate_for_reviewer(&request, reviewer_id, assignment)
+                        .await?
+                    {
+                        self.count_decision(decision);
+                        return Ok(result);
+                    }
+                }
+                (
+                    StateMachine::from_state(request.status),
+                    assignment,
+                    Some(request),
+                )
+            }
+            None => (StateMachine::new(), None, None),
+        };
+
+        // Create approval
+        let mut approval = Approval::new(request_id, reviewer_id, decision);
+        if let Some(r) = reason {
+            approval = approval.with_reason(r);
+        }
+        if let Some(request) = &request {
+            approval.weight = self
+                .reviewer_weight(request.organization_id, reviewer_id)
+                .await?;
+        }
+
+        // Decisions are audited only for stored requests, whose
+        // organization is known
+        let reviewer = AuditActor::User {
+            user_id: reviewer_id,
+        };
+        let decision_event = request.as_ref().map(|request| {
+            self.audit_event(reviewer.clone(), request, "oversight.decision.submitted")
+                .with_detail(serde_json::json!({
+                    "decision": decision.as_str(),
+                    "weight": approval.weight,
+                }))
+        });
+        self.audited(decision_event, async {
+            if let Some(store) = &self.approvals {
+                store.create(&approval).await?;
+            }
+            Ok(())
+        })
+        .await?;
+
+        // Settle the status against the decisions stored so far. If another
+        // decision or a cancellation changed it since it was read, the
+        // request is read again and quorum evaluated again; a request closed
+        // meanwhile fails with RequestClosed.
+        let mut request = request;
+        let (new_status, quorum_result) = loop {
+            let quorum_result = match (&request, &self.approvals) {
+                (Some(request), Some(store)) => {
+                    let mut approvals = store.list_by_request(request_id).await?;
+                    approvals.retain(|a| a.reviewer_id != reviewer_id);
+                    approvals.push(approval.clone());
+                    self.evaluate_quorum(request, &approvals).await?
+                }
+                _ => QuorumCalculator::new(self.default_quorum.clone())
+                    .evaluate(std::slice::from_ref(&approval)),
+            };
+
+            let new_status = match &quorum_result {
+                QuorumResult::Approved { .. } => {
+                    state_machine.transition(
+                        RequestStatus::Approved,
+                        Actor::User {
+                            user_id: reviewer_id,
+                        },
+                        Some("Quorum reached".to_string()),
+                    )?;
+                    RequestStatus::Approved
+                }
+                QuorumResult::Rejected { .. } => {
+                    state_machine.transition(
+                        RequestStatus::Rejected,
+                        Actor::User {
+                            user_id: reviewer_id,
+                        },
+                        Some("Request rejected".to_string()),
+                    )?;
+                    RequestStatus::Rejected
+                }
+                QuorumResult::Pending { .. } => {
+                    if state_machine.can_transition_to(RequestStatus::InReview) {
+                        state_machine.transition(
+                            RequestStatus::InReview,
+                            Actor::User {
+                                user_id: reviewer_id,
+                            },
+                            None,
+                        )?;
+                    }
+                    state_machine.current()
+                }
+            };
+
+            let (Some(requests), Some(current), Some(transition)) = (
+                &self.requests,
+                &request,
+                state_machine.history().last().cloned(),
+            ) else {
+                break (new_status, quorum_result);
+            };
+            let action = match new_status {
+                RequestStatus::Approved => Some("oversight.request.approved"),
+                RequestStatus::Rejected => Some("oversight.request.rejected"),
+                _ => None,
+            };
+            let outcome_event =
+                action.map(|action| self.audit_event(reviewer.clone(), current, action));
+            let settled = self
+                .audited(outcome_event, async {
+                    update_status_from(requests.as_ref(), request_id, current.status, new_status)
+                        .await?;
+                    self.record_transition(request_id, &transition).await
+                })
+                .await;
+            match settled {
+                Ok(()) => break (new_status, quorum_result),
+                Err(CretoError::RequestClosed { .. }) => {
+                    let reloaded = load_request(requests.as_ref(), request_id).await?;
+                    ensure_open(&reloaded)?;
+                    state_machine = StateMachine::from_state(reloaded.status);
+                    request = Some(reloaded);
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        if let (Some(store), Some(assignment)) = (&self.assignments, assignment) {
+            store
+                .update_status(assignment, AssignmentStatus::Responded, self.clock.now())
+                .await?;
+        }
+
+        // TODO: Notify relevant parties
+
+        self.count_decision(decision);
+        Ok(ApprovalSubmitResult {
+            request_id,
+            new_status,
+            quorum_result,
+        })
+    }
+
+    /// Count a submitted decision in the exported metrics, if any.
+    fn count_decision(&self, decision: ApprovalDecision) {
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .approvals
+                .with_label_values(&[decision.as_str()])
+                .inc();
+        }
+    }
+
+    /// Weight of a reviewer's decisions on an organization's requests.
+    async fn reviewer_weight(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: UserId,
+    ) -> CretoResult<u32> {
+        let Some(weights) = &self.reviewer_weights else {
+            return Ok(DEFAULT_REVIEWER_WEIGHT);
+        };
+        let roles = match &self.reviewer_directory {
+            Some(directory) => directory
+                .lookup(organization_id, reviewer_id)
+                .await?
+                .map(|profile| profile.roles)
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        weights.resolve(organization_id, reviewer_id, &roles).await
+    }
+
+    /// Escalate a request at a reviewer's request, if its organization has
+    /// an escalation chain.
+    async fn escalate_for_reviewer(
+        &self,
+        request: &OversightRequest,
+        reviewer_id: UserId,
+        assignment: Option<Uuid>,
+    ) -> CretoResult<Option<ApprovalSubmitResult>> {
+        let (Some(requests), Some(engine)) = (&self.requests, &self.escalation) else {
+            return Ok(None);
+        };
+        let Some(step) = engine.next_step(request, self.clock.now()).await? else {
+            return Ok(None);
+        };
+        let escalation = self
+            .apply_escalation(
+                requests.as_ref(),
+                request.clone(),
+                step,
+                EscalationTrigger::Requested { reviewer_id },
+            )
+            .await?;
+        if let (Some(store), Some(assignment)) = (&self.assignments, assignment) {
+            store
+                .update_status(assignment, AssignmentStatus::Responded, self.clock.now())
+                .await?;
+        }
+
+        let new_status = match escalation.outcome {
+            EscalationOutcome::Escalated { .. } => RequestStatus::Escalated,
+            EscalationOutcome::Exhausted { status } => status,
+            EscalationOutcome::TimedOut => RequestStatus::TimedOut,
+        };
+        Ok(Some(ApprovalSubmitResult {
+            request_id: request.id,
+            new_status,
+            quorum_result: QuorumCalculator::new(self.quorum_for(request)).evaluate(&[]),
+        }))
+    }
+
+    /// Cancel a request on behalf of the agent that raised it.
+    ///
+    /// `actor` must be the originating agent, or a user the configured
+    /// [`OrgAdminCheck`] recognizes as an administrator of the request's
+    /// organization. Only open requests can be cancelled; closed ones fail
+    /// with [`CretoError::RequestClosed`]. The transition is recorded with
+    /// `reason`, held notifications about the request are dropped, and
+    /// reviewers who were already notified are told it was withdrawn.
+    /// Later approvals are rejected with [`CretoError::RequestClosed`].
+    pub async fn cancel_request(
+        &self,
+        request_id: Uuid,
+        actor: Actor,
+        reason: impl Into<String>,
+    ) -> CretoResult<CancellationResult> {
+        let requests = self.requests.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Cancellation requires a request repository".to_string())
+        })?;
+        let reason = reason.into();
+        // Cancel the request as last read; if its status changed since, read
+        // it again
+        let (mut request, transition) = loop {
+            let request = load_request(requests.as_ref(), request_id).await?;
+            self.authorize_cancel(&request, &actor).await?;
+            ensure_open(&request)?;
+
+            let event = self
+                .audit_event(
+                    AuditActor::from(&actor),
+                    &request,
+                    "oversight.request.cancelled",
+                )
+                .with_detail(serde_json::json!({ "reason": reason }));
+            let mut state_machine = StateMachine::from_state(request.status);
+            state_machine.transition(
+                RequestStatus::Cancelled,
+                actor.clone(),
+                Some(reason.clone()),
+            )?;
+            let transition = state_machine.history()[0].clone();
+
+            let cancelled = self
+                .audited(
+                    Some(event),
+                    update_status_from(
+                        requests.as_ref(),
+                        request_id,
+                        request.status,
+                        RequestStatus::Cancelled,
+                    ),
+                )
+                .await;
+            match cancelled {
+                Ok(()) => break (request, transition),
+                Err(CretoError::RequestClosed { .. }) => continue,
+                Err(e) => return Err(e),
+            }
+        };
+        self.record_transition(request_id, &transition).await?;
+        request.status = RequestStatus::Cancelled;
+        request.updated_at = transition.timestamp;
+
+        // The request is already cancelled; notification failures are logged, not returned
+        let notifications = match self.notifications.withdraw(&request, &reason).await {
+            Ok(report) => report,
+            Err(e) => {
+                tracing::warn!(request_id = %request_id, error = %e, "Failed to send withdrawal notices");
+                WithdrawalReport::default()
+            }
+        };
+        if let Err(e) = self.notify_decision(&request, &[], None).await {
+            tracing::warn!(request_id = %request_id, error = %e, "Failed to send cancellation webhooks");
+        }
+
+        Ok(CancellationResult {
+            request,
+            transition,
+            notifications,
+        })
+    }
+
+    /// Everything an approval screen needs to show a request to `caller`.
+    ///
+    /// The caller must be a member of the request's organization in the
+    /// reviewer directory. The request, its decisions and its transitions are
+    /// read as of one point: if the request changes while they are being
+    /// read, they are read again.
+    pub async fn review_bundle(
+        &self,
+        request_id: Uuid,
+        caller: UserId,
+    ) -> CretoResult<ReviewBundle> {
+        let (requests, approvals, directory) = match (
+            &self.requests,
+            &self.approvals,
+            &self.reviewer_directory,
+        ) {
+            (Some(requests), Some(approvals), Some(directory)) => (requests, approvals, directory),
+            _ => return Err(CretoError::Configuration(
+                "Review bundles require request and approval repositories and a reviewer directory"
+                    .to_string(),
+            )),
+        };
+
+        let request = load_request(requests.as_ref(), request_id).await?;
+        if directory
+            .lookup(request.organization_id, caller)
+            .await?
+            .is_none()
+        {
+            return Err(CretoError::NotAuthorized {
+                resource: format!("oversight request {}", request_id),
+                action: "review".to_string(),
+            });
+        }
+        self.record_activity(
+            request_id,
+            Actor::User { user_id: caller },
+            ActivityKind::Viewed,
+        )
+        .await?;
+
+        let (request, decisions, transitions) = self
+            .read_consistently(requests.as_ref(), approvals.as_ref(), request)
+            .await?;
+
+        let mut profiles: HashMap<UserId, Option<ReviewerProfile>> = HashMap::new();
+        let mut reviewed = Vec::with_capacity(decisions.len());
+        for approval in decisions.iter().cloned() {
+            let reviewer = match profiles.get(&approval.reviewer_id) {
+                Some(profile) => profile.clone(),
+                None => {
+                    let profile = directory
+                        .lookup(request.organization_id, approval.reviewer_id)
+                        .await?;
+                    profiles.insert(approval.reviewer_id, profile.clone());
+                    profile
+                }
+            };
+            reviewed.push(ReviewerDecision { approval, reviewer });
+        }
+
+        let quorum_result = self.evaluate_quorum(&request, &decisions).await?;
+        let latest_checkpoint = match &self.checkpoint_manager {
+            Some(manager) => manager
+                .load_checkpoint(request_id)
+                .await?
+                .as_ref()
+                .map(CheckpointSummary::from),
+            None => None,
+        };
+
+        let now = self.clock.now();
+        let time_remaining_seconds = if request.status.is_terminal() {
+            None
+        } else {
+            Some((request.expires_at - now).num_seconds().max(0))
+        };
+        let caller_decision = decisions
+            .iter()
+            .rev()
+            .find(|a| a.reviewer_id == caller)
+            .map(|a| a.decision);
+
+        let (context, context_sections) = enrichment::split_sections(&request.context);
+        let quorum = self.quorum_for(&request);
+        Ok(ReviewBundle {
+            request: OversightRequest { context, ..request },
+            context_sections,
+            approvals: reviewed,
+            quorum,
+            quorum_result,
+            transitions,
+            latest_checkpoint,
+            time_remaining_seconds,
+            caller_has_decided: caller_decision.is_some(),
+            caller_decision,
+            generated_at: now,
+        })
+    }
+
+    /// Export a request's full lifecycle as a hash-chained audit trail.
+    ///
+    /// Merges the request's creation, transitions, decisions, checkpoints
+    /// and recorded activity in time order. See [`crate::lifecycle`].
+    pub async fn lifecycle_export(
+        &self,
+        request_id: Uuid,
+        format: LifecycleExportFormat,
+    ) -> CretoResult<LifecycleExport> {
+        self.build_lifecycle_export(request_id, format, None).await
+    }
+
+    /// Export a request's lifecycle with `redactor` applied before the
+    /// entries are chained, for sharing outside the organization.
+    pub async fn lifecycle_export_redacted(
+        &self,
+        request_id: Uuid,
+        format: LifecycleExportFormat,
+        redactor: &dyn LifecycleRedactor,
+    ) -> CretoResult<LifecycleExport> {
+        self.build_lifecycle_export(request_id, format, Some(redactor))
+            .await
+    }
+
+    async fn build_lifecycle_export(
+        &self,
+        request_id: Uuid,
+        format: LifecycleExportFormat,
+        redactor: Option<&dyn LifecycleRedactor>,
+    ) -> CretoResult<LifecycleExport> {
+        let requests = self.requests.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Lifecycle exports require a request repository".to_string())
+        })?;
+        let signing_key = match format {
+            LifecycleExportFormat::JsonLines => None,
+            LifecycleExportFormat::SignedJson => {
+                Some(self.export_signing_key.as_deref().ok_or_else(|| {
+                    CretoError::Configuration("Export signing key not configured".to_string())
+                })?)
+            }
+        };
+
+        let request = load_request(requests.as_ref(), request_id).await?;
+        let sources = LifecycleSources {
+            transitions: match &self.transitions {
+                Some(transitions) => transitions.list_by_request(request_id).await?,
+                None => Vec::new(),
+            },
+            approvals: match &self.approvals {
+                Some(approvals) => approvals.list_by_request(request_id).await?,
+                None => Vec::new(),
+            },
+            checkpoints: match &self.checkpoint_manager {
+                Some(manager) => manager.list_checkpoints(request_id).await?,
+                None => Vec::new(),
+            },
+            activities: match &self.activity {
+                Some(log) => log.list_by_request(request_id).await?,
+                None => Vec::new(),
+            },
+        };
+
+        let mut entries = lifecycle::merge_entries(&request, sources)?;
+        if let Some(redactor) = redactor {
+            redactor.redact(&mut entries);
+        }
+        let head_hash = lifecycle::chain_entries(&mut entries)?;
+
+        let body = match signing_key {
+            None => {
+                let mut body = String::new();
+                for entry in &entries {
+                    body.push_str(
+                        &serde_json::to_string(entry)
+                            .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                    );
+                    body.push('\n');
+                }
+                body
+            }
+            Some(key) => serde_json::to_string(&LifecycleDocument {
+                request_id,
+                generated_at: self.clock.now(),
+                entries: entries.clone(),
+                head_hash: head_hash.clone(),
+                signature: lifecycle::sign_head(key, &head_hash),
+            })
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+        };
+
+        Ok(LifecycleExport {
+            format,
+            entries,
+            head_hash,
+            body,
+        })
+    }
+
+    /// Read a request's decisions and transitions, retrying until the
+    /// request is unchanged across the reads.
+    async fn read_consistently(
+        &self,
+        requests: &dyn RequestRepository,
+        approvals: &dyn ApprovalRepository,
+        mut request: OversightRequest,
+    ) -> CretoResult<(OversightRequest, Vec<Approval>, Vec<StateTransitionRecord>)> {
+        const ATTEMPTS: usize = 3;
+
+        for _ in 0..ATTEMPTS {
+            let decisions = approvals.list_by_request(request.id).await?;
+            let transitions = match &self.transitions {
+                Some(transitions) => transitions.list_by_request(request.id).await?,
+                None => Vec::new(),
+            };
+            let after = load_request(requests, request.id).await?;
+            if after.status == request.status && after.updated_at == request.updated_at {
+                return Ok((after, decisions, transitions));
+            }
+            request = after;
+        }
+        Err(CretoError::Internal(format!(
+            "oversight request {} kept changing while it was read",
+            request.id
+        )))
+    }
+
+    async fn authorize_cancel(&self, request: &OversightRequest, actor: &Actor) -> CretoResult<()> {
+        let allowed = match actor {
+            Actor::Agent { agent_id } => *agent_id == request.agent_id,
+            Actor::User { user_id } => match &self.admin_check {
+                Some(check) => {
+                    check
+                        .is_org_admin(request.organization_id, *user_id)
+                        .await?
+                }
+                None => false,
+            },
+            Actor::System | Actor::Policy { .. } => false,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(CretoError::NotAuthorized {
+                resource: format!("oversight request {}", request.id),
+                action: "cancel".to_string(),
+            })
+        }
+    }
+
+    /// The reviewer's open assignment on `request`, if any.
+    ///
+    /// Fails with [`CretoError::AssignmentLapsed`] if it lapsed and the
+    /// organization does not allow late decisions.
+    async fn check_assignment(
+        &self,
+        request: &OversightRequest,
+        reviewer_id: UserId,
+    ) -> CretoResult<Option<Uuid>> {
+        let Some(store) = &self.assignments else {
+            return Ok(None);
+        };
+        let assignment = store
+            .list_by_request(request.id)
+            .await?
+            .into_iter()
+            .find(|a| a.reviewer_id == reviewer_id);
+        match assignment {
+            Some(a) if a.status == AssignmentStatus::Lapsed => {
+                let policy = self.reviewer_deadlines.policy_for(request.organization_id);
+                if !policy.allow_late_decisions {
+                    return Err(CretoError::AssignmentLapsed {
+                        request_id: request.id.to_string(),
+                        reviewer_id: reviewer_id.to_string(),
+                    });
+                }
+                Ok(Some(a.id))
+            }
+            Some(a) if a.status == AssignmentStatus::Active => Ok(Some(a.id)),
+            _ => Ok(None),
+        }
+    }
+
+    /// An audit event for `action` on `request`, at the current time.
+    fn audit_event(
+        &self,
+        actor: AuditActor,
+        request: &OversightRequest,
+        action: &str,
+    ) -> AuditEvent {
+        AuditEvent::new(actor, request.organization_id, request.id, action).at(self.clock.now())
+    }
+
+    /// Apply `change`, recording `event` as the audit mode says.
+    ///
+    /// In best-effort mode the event is emitted once the change succeeds and
+    /// a sink failure is only logged. In strict mode the event is emitted
+    /// first and a sink failure fails with [`CretoError::AuditFailed`]
+    /// before anything changes. Either way a failed change is followed by
+    /// a [`AuditOutcome::Failure`] event.
+    async fn audited<T>(
+        &self,
+        event: Option<AuditEvent>,
+        change: impl Future<Output = CretoResult<T>>,
+    ) -> CretoResult<T> {
+        let Some(event) = event else {
+            return change.await;
+        };
+        if self.audit_mode == AuditMode::Strict {
+            self.audit_sink
+                .emit(event.clone())
+                .await
+                .map_err(|e| CretoError::AuditFailed(format!("{}: {}", event.action, e)))?;
+        }
+        match change.await {
+            Ok(value) => {
+                if self.audit_mode == AuditMode::BestEffort {
+                    self.emit_logged(event).await;
+                }
+                Ok(value)
+            }
+            Err(e) => {
+                self.emit_logged(AuditEvent {
+                    id: Uuid::now_v7(),
+                    outcome: AuditOutcome::Failure,
+                    ..event
+                })
+                .await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn emit_logged(&self, event: AuditEvent) {
+        let action = event.action.clone();
+        if let Err(e) = self.audit_sink.emit(event).await {
+            tracing::warn!(action = %action, error = %e, "Failed to emit audit event");
+        }
+    }
+
+    async fn record_transition(
+        &self,
+        request_id: Uuid,
+        transition: &StateTransition,
+    ) -> CretoResult<()> {
+        let Some(transitions) = &self.transitions else {
+            return Ok(());
+        };
+        transitions
+            .create(&StateTransitionRecord {
+                id: transition.id,
+                request_id,
+                from_status: transition.from,
+                to_status: transition.to,
+                actor_type: transition.actor.kind().to_string(),
+                actor_id: transition.actor.id(),
+                reason: transition.reason.clone(),
+                transitioned_at: transition.timestamp,
+            })
+            .await?;
+        Ok(())
+    }
+
+    /// Get the status of an oversight request.
+    ///
+    /// Without a request repository configured, no request is found.
+    pub async fn get_request_status(
+        &self,
+        request_id: Uuid,
+    ) -> CretoResult<Option<OversightRequest>> {
+        match &self.requests {
+            Some(requests) => requests.get(request_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// List pending requests for a reviewer.
+    pub async fn list_pending_for_reviewer(
+        &self,
+        reviewer_id: UserId,
+    ) -> CretoResult<Vec<OversightRequest>> {
+        // TODO: Query database for requests assigned to this reviewer
+        let _ = reviewer_id;
+        Ok(Vec::new())
+    }
+
+    /// Save a checkpoint for a request.
+    ///
+    /// This creates an atomic snapshot of the request's current state,
+    /// including conversation context and state machine history.
+    pub async fn save_checkpoint(
+        &self,
+        request: &OversightRequest,
+        state_machine: &StateMachine,
+        context: serde_json::Value,
+        reason: Option<String>,
+    ) -> CretoResult<Uuid> {
+        let manager = self.checkpoint_manager.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Checkpoint manager not configured".to_string())
+        })?;
+
+        let mut checkpoint = Checkpoint::new(
+            request.id,
+            state_machine.current(), // Use state machine's current state
+            state_machine,
+            context,
+        );
+
+        if let Some(r) = reason {
+            checkpoint = checkpoint.with_reason(r);
+        }
+
+        manager.save_checkpoint(&checkpoint).await
+    }
+
+    /// Load the latest checkpoint for a request.
+    pub async fn load_checkpoint(&self, request_id: Uuid) -> CretoResult<Option<Checkpoint>> {
+        let manager = self.checkpoint_manager.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Checkpoint manager not configured".to_string())
+        })?;
+
+        manager.load_checkpoint(request_id).await
+    }
+
+    /// Recover all pending requests after a system restart.
+    ///
+    /// This scans for incomplete oversight requests and restores their state
+    /// from the most recent checkpoint, allowing the system to resume where
+    /// it left off.
+    pub async fn recover_pending_requests(&self) -> CretoResult<Vec<RecoveredRequest>> {
+        // TODO: Implement full recovery logic with repository
+        // This is a placeholder showing the intended flow:
+
+        // 1. Query for all requests in non-terminal states
+        // 2. For each request, load the latest checkpoint
+        // 3. Restore state machine from checkpoint
+        // 4. Validate that the checkpoint is compatible
+        // 5. Resume processing from the restored state
+
+        let manager = self.checkpoint_manager.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Checkpoint manager not configured".to_string())
+        })?;
+
+        let _ = manager; // Suppress warning until full implementation
+
+        Ok(Vec::new())
+    }
+}
+
+async fn load_request(
+    requests: &dyn RequestRepository,
+    request_id: Uuid,
+) -> CretoResult<OversightRequest> {
+    requests
+        .get(request_id)
+        .await?
+        .ok_or_else(|| CretoError::ApprovalNotFound(request_id.to_string()))
+}
+
+/// Move a request from `expected` to `status`, failing with
+/// [`CretoError::RequestClosed`] if its status is no longer `expected`.
+async fn update_status_from(
+    requests: &dyn RequestRepository,
+    request_id: Uuid,
+    expected: RequestStatus,
+    status: RequestStatus,
+) -> CretoResult<()> {
+    if requests
+        .update_status_if(request_id, expected, status)
+        .await?
+    {
+        return Ok(());
+    }
+    Err(CretoError::RequestClosed {
+        request_id: request_id.to_string(),
+        status: expected.as_str().to_string(),
+    })
+}
+
+fn ensure_open(request: &OversightRequest) -> CretoResult<()> {
+    if request.status.is_terminal() {
+        return Err(CretoError::RequestClosed {
+            request_id: request.id.to_string(),
+            status: request.status.as_str().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Who an escalation is audited as: the reviewer who asked for it, or the
+/// system for timeouts and exhausted reminders.
+fn escalation_actor(trigger: &EscalationTrigger) -> AuditActor {
+    match trigger {
+        EscalationTrigger::Requested { reviewer_id } => AuditActor::User {
+            user_id: *reviewer_id,
+        },
+        EscalationTrigger::TimedOut | EscalationTrigger::RemindersExhausted => AuditActor::System,
+    }
+}
+
+/// Result of cancelling a request.
+#[derive(Debug, Clone)]
+pub struct CancellationResult {
+    /// The request, now cancelled.
+    pub request: OversightRequest,
+    /// The recorded transition to cancelled.
+    pub transition: StateTransition,
+    /// Withdrawal notices sent and held notifications dropped.
+    pub notifications: WithdrawalReport,
+}
+
+/// Result of recovering a request from a checkpoint.
+#[derive(Debug, Clone)]
+pub struct RecoveredRequest {
+    /// The request that was recovered.
+    pub request_id: Uuid,
+
+    /// Restored state machine.
+    pub state_machine: StateMachine,
+
+    /// Restored context.
+    pub context: serde_json::Value,
+
+    /// Checkpoint that was used for recovery.
+    pub checkpoint_id: Uuid,
+
+    /// When the checkpoint was created.
+    pub checkpoint_timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl Default for OversightService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Result of checking whether an action requires oversight.
+#[derive(Debug, Clone)]
+pub enum OversightCheckResult {
+    /// Action is allowed without oversight.
+    Allowed,
+    /// Action requires human approval.
+    RequiresApproval { request_id: Uuid, reason: String },
+    /// Action is denied by policy.
+    Denied { reason: String },
+}
+
+impl OversightCheckResult {
+    /// Check if the action is allowed.
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, OversightCheckResult::Allowed)
+    }
+
+    /// Check if approval is required.
+    pub fn requires_approval(&self) -> bool {
+        matches!(self, OversightCheckResult::RequiresApproval { .. })
+    }
+
+    /// Get the request ID if approval is required.
+    pub fn request_id(&self) -> Option<Uuid> {
+        match self {
+            OversightCheckResult::RequiresApproval { request_id, .. } => Some(*request_id),
+            _ => None,
+        }
+    }
+}
+
+/// Result of submitting an approval.
+#[derive(Debug, Clone)]
+pub struct ApprovalSubmitResult {
+    /// Request ID.
+    pub request_id: Uuid,
+    /// New status after the approval.
+    pub new_status: RequestStatus,
+    /// Current quorum state.
+    pub quorum_result: QuorumResult,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::triggers::TriggerCondition;
+
+    #[tokio::test]
+    async fn test_check_action_allowed() {
+        let service = OversightService::new();
+
+        let result = service
+            .check_action(
+                OrganizationId::new(),
+                AgentId::new(),
+                ActionType::Transaction {
+                    amount_cents: 1000, // $10
+                    currency: "USD".to_string(),
+                },
+                "Small purchase",
+            )
+            .await
+            .unwrap();
+
+        assert!(result.is_allowed());
+    }
+
+    #[tokio::test]
+    async fn test_check_action_requires_approval() {
+        let service = OversightService::new();
+
+        let result = service
+            .check_action(
+                OrganizationId::new(),
+                AgentId::new(),
+                ActionType::Transaction {
+                    amount_cents: 5000000, // $50,000
+                    currency: "USD".to_string(),
+                },
+                "Large transfer",
+            )
+            .await
+            .unwrap();
+
+        assert!(result.requires_approval());
+    }
+
+    #[tokio::test]
+    async fn test_policy_trigger_creates_request() {
+        // Configure service with policy trigger
+        let trigger_config =
+            PolicyTriggerConfig::new().with_condition(TriggerCondition::AmountThreshold {
+                threshold_cents: 1_000_000, // $10,000
+                currency: None,
+            });
+
+        let service = OversightService::new().with_triggers(trigger_config);
+
+        // High-value transaction should trigger oversight
+        let action = ActionType::Transaction {
+            amount_cents: 5_000_000, // $50,000
+            currency: "USD".to_string(),
+        };
+
+        let context = PolicyContext::default();
+        let request_id = service
+            .check_policy_trigger(OrganizationId::new(), AgentId::new(), action, context)
+            .await
+            .unwrap();
+
+        assert!(request_id.is_some());
+    }
+
+    #[test]
+    fn test_trigger_request_inherits_correlation() {
+        let trigger_config =
+            PolicyTriggerConfig::new().with_condition(TriggerCondition::AmountThreshold {
+                threshold_cents: 1_000_000,
+                currency: None,
+            });
+        let service = OversightService::new().with_triggers(trigger_config);
+
+        let action_id = Uuid::now_v7();
+        let context = PolicyContext {
+            correlation: creto_common::Correlation::new(Uuid::now_v7()).derive(action_id),
+            ..Default::default()
+        };
+        let request = service
+            .build_trigger_request(
+                OrganizationId::new(),
+                AgentId::new(),
+                ActionType::Transaction {
+                    amount_cents: 5_000_000,
+                    currency: "USD".to_string(),
+                },
+                &context,
+            )
+            .unwrap();
+
+        assert_eq!(request.correlation(), context.correlation);
+        assert_eq!(request.caused_by, Some(action_id));
+    }
+
+    #[tokio::test]
+    async fn test_policy_trigger_no_match() {
+        // Configure service with policy trigger
+        let trigger_config =
+            PolicyTriggerConfig::new().with_condition(TriggerCondition::AmountThreshold {
+                threshold_cents: 10_000_000, // $100,000
+                currency: None,
+            });
+
+        let service = OversightService::new().with_triggers(trigger_config);
+
+        // Low-value transaction should not trigger
+        let action = ActionType::Transaction {
+            amount_cents: 50_000, // $500
+            currency: "USD".to_string(),
+        };
+
+        let context = PolicyContext::default();
+        let request_id = service
+            .check_policy_trigger(OrganizationId::new(), AgentId::new(), action, context)
+            .await
+            .unwrap();
+
+        assert!(request_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_policy_trigger_without_config() {
+        // Service without trigger configuration
+        let service = OversightService::new();
+
+        let action = ActionType::Transaction {
+            amount_cents: 50_000_000,
+            currency: "USD".to_string(),
+        };
+
+        let context = PolicyContext::default();
+        let request_id = service
+            .check_policy_trigger(OrganizationId::new(), AgentId::new(), action, context)
+            .await
+            .unwrap();
+
+        // Should return None because no trigger evaluator is configured
+        assert!(request_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_trigger_with_multiple_conditions() {
+        let trigger_config = PolicyTriggerConfig::new()
+            .with_condition(TriggerCondition::AmountThreshold {
+                threshold_cents: 1_000_000,
+                currency: None,
+            })
+            .with_condition(TriggerCondition::DataSensitivity {
+                scopes: vec!["pii".to_string()],
+            });
+
+        let service = OversightService::new().with_triggers(trigger_config);
+
+        // Test first condition (amount threshold)
+        let action_txn = ActionType::Transaction {
+            amount_cents: 5_000_000,
+            currency: "USD".to_string(),
+        };
+        let result1 = service
+            .check_policy_trigger(
+                OrganizationId::new(),
+                AgentId::new(),
+                action_txn,
+                PolicyContext::default(),
+            )
+            .await
+            .unwrap();
+        assert!(result1.is_some());
+
+        // Test second condition (data sensitivity)
+        let action_data = ActionType::DataAccess {
+            data_type: "customer".to_string(),
+            scope: "pii".to_string(),
+        };
+        let result2 = service
+            .check_policy_trigger(
+                OrganizationId::new(),
+                AgentId::new(),
+                action_data,
+                PolicyContext::default(),
+            )
+            .await
+            .unwrap();
+        assert!(result2.is_some());
+    }
+
+    #[test]
+    fn test_describe_trigger() {
+        let service = OversightService::new();
+
+        let condition = TriggerCondition::AmountThreshold {
+            threshold_cents: 1_000_000,
+            currency: Some("USD".to_string()),
+        };
+        let description = service.describe_trigger(&condition);
+        assert!(description.contains("$10000.00"));
+        assert!(description.contains("USD"));
+    }
+
+    #[tokio::test]
+    async fn test_template_version_bump_does_not_affect_in_flight_requests() {
+        use crate::request::Priority;
+        use crate::template::{FieldType, RequestTemplate, TemplateField};
+        use crate::triggers::ActionTypePattern;
+
+        let service = OversightService::new();
+        let org = OrganizationId::new();
+        let template = service
+            .template_store
+            .create(
+                RequestTemplate::new(org, "Monthly data export", ActionTypePattern::DataAccess)
+                    .with_field(TemplateField::new("dataset", "Dataset", FieldType::String))
+                    .with_quorum(QuorumConfig::n_of_m(2))
+                    .with_reviewer_group("data-governance"),
+            )
+            .await
+            .unwrap();
+
+        let action = ActionType::DataAccess {
+            data_type: "customers".to_string(),
+            scope: "export".to_string(),
+        };
+        let fields = serde_json::json!({"dataset": "customers"})
+            .as_object()
+            .unwrap()
+            .clone();
+        let in_flight = service
+            .create_request_from_template(
+                org,
+                AgentId::new(),
+                template.id,
+                action.clone(),
+                "Export customers",
+                fields.clone(),
+            )
+            .await
+            .unwrap();
+
+        service
+            .template_store
+            .update(
+                template
+                    .clone()
+                    .with_priority(Priority::Critical)
+                    .with_quorum(QuorumConfig::unanimous()),
+            )
+            .await
+            .unwrap();
+        let later = service
+            .create_request_from_template(
+                org,
+                AgentId::new(),
+                template.id,
+                action.clone(),
+                "Export customers",
+                fields.clone(),
+            )
+            .await
+            .unwrap();
+
+        let applied = in_flight.template.as_ref().unwrap();
+        assert_eq!(applied.version, 1);
+        assert_eq!(in_flight.priority, Priority::Normal);
+        assert_eq!(service.quorum_for(&in_flight).required_approvals, 2);
+        assert_eq!(in_flight.metadata["reviewer_group"], "data-governance");
+
+        assert_eq!(later.template.as_ref().unwrap().version, 2);
+        assert_eq!(later.priority, Priority::Critical);
+        assert!(service.quorum_for(&later).require_unanimous);
+
+        // Templates are scoped to their organization
+        let result = service
+            .create_request_from_template(
+                OrganizationId::new(),
+                AgentId::new(),
+                template.id,
+                action,
+                "Export customers",
+                fields,
+            )
+            .await;
+        assert!(matches!(result, Err(TemplateError::NotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_group_still_evaluates_in_flight_request() {
+        use crate::clock::{Clock, TestClock};
+        use crate::directory::{MockDirectorySource, ReviewerDirectorySync};
+        use crate::template::RequestTemplate;
+        use crate::triggers::ActionTypePattern;
+
+        let org = OrganizationId::new();
+        let groups = Arc::new(InMemoryReviewerGroupStore::new());
+        let directory = Arc::new(MockDirectorySource::new());
+        let clock = Arc::new(TestClock::new(
+            chrono::Utc::now() - chrono::Duration::hours(1),
+        ));
+        let sync = ReviewerDirectorySync::new(org, directory.clone(), groups.clone())
+            .with_clock(clock.clone());
+        let service = OversightService::new().with_reviewer_group_store(groups);
+
+        let (alice, bob) = (UserId::new(), UserId::new());
+        directory.set_group("g-1", "finance-approvers", vec![alice, bob]);
+        sync.sync().await.unwrap();
+
+        let template = service
+            .template_store
+            .create(
+                RequestTemplate::new(org, "Vendor payment", ActionTypePattern::Transaction)
+                    .with_quorum(QuorumConfig::n_of_m(2))
+                    .with_reviewer_group("finance-approvers"),
+            )
+            .await
+            .unwrap();
+        let action = ActionType::Transaction {
+            amount_cents: 250_000,
+            currency: "USD".to_string(),
+        };
+        let create = || {
+            service.create_request_from_template(
+                org,
+                AgentId::new(),
+                template.id,
+                action.clone(),
+                "Pay vendor",
+                serde_json::Map::new(),
+            )
+        };
+        let in_flight = create().await.unwrap();
+        assert_eq!(in_flight.assigned_reviewers.len(), 2);
+        let first = Approval::new(in_flight.id, alice, ApprovalDecision::Approve);
+
+        // Alice leaves, then the group is deleted upstream
+        clock.advance(chrono::Duration::hours(2));
+        directory.set_group("g-1", "finance-approvers", vec![bob]);
+        sync.sync().await.unwrap();
+        directory.remove_group("g-1");
+        sync.sync().await.unwrap();
+
+        // New requests are not routed to the disabled group
+        assert!(create().await.unwrap().assigned_reviewers.is_empty());
+
+        // The in-flight request keeps Alice's approval and can still finish
+        let pending = service
+            .evaluate_quorum(&in_flight, std::slice::from_ref(&first))
+            .await
+            .unwrap();
+        assert!(pending.is_pending());
+        let mut second = Approval::new(in_flight.id, bob, ApprovalDecision::Approve);
+        second.decided_at = clock.now();
+        let mut late = Approval::new(in_flight.id, UserId::new(), ApprovalDecision::Approve);
+        late.decided_at = clock.now();
+        let result = service
+            .evaluate_quorum(&in_flight, &[first, late, second])
+            .await
+            .unwrap();
+        assert!(matches!(
+            result,
+            QuorumResult::Approved {
+                approve_count: 2,
+                total_weight: 2,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_enrichment_failure_does_not_block_template_request() {
+        use crate::enrichment::{split_sections, SectionField, SectionStatus};
+        use crate::template::{FieldType, RequestTemplate, TemplateField};
+        use crate::triggers::ActionTypePattern;
+
+        struct Unreachable;
+
+        #[async_trait::async_trait]
+        impl ContextEnricher for Unreachable {
+            fn key(&self) -> &str {
+                "lineage"
+            }
+
+            fn title(&self) -> &str {
+                "Data lineage"
+            }
+
+            async fn enrich(
+                &self,
+                _request: &OversightRequest,
+                _input: &EnrichmentInput,
+            ) -> CretoResult<Option<Vec<SectionField>>> {
+                Err(CretoError::Internal("catalog unreachable".to_string()))
+            }
+        }
+
+        let service = OversightService::new().with_enricher(Arc::new(Unreachable));
+        let org = OrganizationId::new();
+        let template = service
+            .template_store
+            .create(
+                RequestTemplate::new(org, "Data export", ActionTypePattern::DataAccess)
+                    .with_field(TemplateField::new("dataset", "Dataset", FieldType::String)),
+            )
+            .await
+            .unwrap();
+        let fields = serde_json::json!({"dataset": "customers"})
+            .as_object()
+            .unwrap()
+            .clone();
+
+        let request = service
+            .create_request_from_template(
+                org,
+                AgentId::new(),
+                template.id,
+                ActionType::DataAccess {
+                    data_type: "customers".to_string(),
+                    scope: "export".to_string(),
+                },
+                "Export customers",
+                fields,
+            )
+            .await
+            .unwrap();
+
+        let (rest, sections) = split_sections(&request.context);
+        assert_eq!(rest, serde_json::json!({"dataset": "customers"}));
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].key, "lineage");
+        assert_eq!(sections[0].status, SectionStatus::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn test_trigger_request_enriched_with_execution_details() {
+        use crate::enrichment::{ExecutionDetailsEnricher, ExecutionReference, SectionField};
+        use crate::triggers::ActionTypePattern;
+
+        /// Runs the execution details enricher, keeping what it produced.
+        struct Recording {
+            inner: ExecutionDetailsEnricher,
+            fields: std::sync::Mutex<Option<Vec<SectionField>>>,
+        }
+
+        #[async_trait::async_trait]
+        impl ContextEnricher for Recording {
+            fn key(&self) -> &str {
+                self.inner.key()
+            }
+
+            fn title(&self) -> &str {
+                self.inner.title()
+            }
+
+            async fn enrich(
+                &self,
+                request: &OversightRequest,
+                input: &EnrichmentInput,
+            ) -> CretoResult<Option<Vec<SectionField>>> {
+                let fields = self.inner.enrich(request, input).await?;
+                *self.fields.lock().unwrap() = fields.clone();
+                Ok(fields)
+            }
+        }
+
+        let recording = Arc::new(Recording {
+            inner: ExecutionDetailsEnricher::new(),
+            fields: std::sync::Mutex::new(None),
+        });
+        let service = OversightService::new()
+            .with_triggers(PolicyTriggerConfig::new().with_condition(
+                TriggerCondition::ActionType {
+                    pattern: ActionTypePattern::CodeExecution,
+                },
+            ))
+            .with_enricher(recording.clone());
+        let context = PolicyContext {
+            execution: Some(ExecutionReference {
+                execution_id: Uuid::now_v7(),
+                runtime: "python3.11".to_string(),
+                code: "print('hi')".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let request_id = service
+            .check_policy_trigger(
+                OrganizationId::new(),
+                AgentId::new(),
+                ActionType::CodeExecution {
+                    runtime: "python".to_string(),
+                    risk_level: "high".to_string(),
+                },
+                context,
+            )
+            .await
+            .unwrap();
+
+        assert!(request_id.is_some());
+        let fields = recording.fields.lock().unwrap().clone().unwrap();
+        assert!(fields
+            .iter()
+            .any(|f| f.label == "Runtime" && f.value == "python3.11"));
+    }
+
+    #[tokio::test]
+    async fn test_notification_preferences_crud_and_org_fallback() {
+        use crate::channels::{ChannelType, MockChannel};
+        use crate::notifications::DigestConfig;
+
+        let email = Arc::new(MockChannel::new().with_channel_type(ChannelType::Email));
+        let service = OversightService::new()
+            .with_notification_router(NotificationRouter::default().with_channel(email.clone()));
+        let org_id = OrganizationId::new();
+        let reviewer_id = UserId::new();
+
+        service
+            .set_notification_preferences(
+                NotificationPreferences::for_reviewer(org_id, reviewer_id)
+                    .with_digest(DigestConfig::every_hours(6)),
+            )
+            .await
+            .unwrap();
+        let stored = service
+            .get_notification_preferences(org_id, Some(reviewer_id))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.digest, Some(DigestConfig::every_hours(6)));
+
+        let mut request = OversightRequest::new(
+            org_id,
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "export".to_string(),
+            },
+            "Export report",
+        );
+        request.assigned_reviewers = vec![reviewer_id];
+        let report = service.notify_reviewers(&request).await.unwrap();
+        assert_eq!(report.queued.len(), 1);
+
+        // Without reviewer preferences the organization defaults apply
+        assert!(service
+            .delete_notification_preferences(org_id, Some(reviewer_id))
+            .await
+            .unwrap());
+        let report = service.notify_reviewers(&request).await.unwrap();
+        assert_eq!(report.sent.len(), 1);
+        assert_eq!(report.sent[0].channel, ChannelType::Email);
+        assert_eq!(email.notification_count().await, 1);
+    }
+
+    /// Recognizes a fixed set of administrators.
+    struct Admins(Vec<UserId>);
+
+    #[async_trait]
+    impl OrgAdminCheck for Admins {
+        async fn is_org_admin(
+            &self,
+            _organization_id: OrganizationId,
+            user_id: UserId,
+        ) -> CretoResult<bool> {
+            Ok(self.0.contains(&user_id))
+        }
+    }
+
+    struct CancelFixture {
+        service: OversightService,
+        requests: Arc<crate::repository::InMemoryRequestRepository>,
+        slack: Arc<crate::channels::MockChannel>,
+        admin_id: UserId,
+    }
+
+    fn cancel_fixture() -> CancelFixture {
+        use crate::channels::{ChannelType, MockChannel};
+
+        let requests = Arc::new(crate::repository::InMemoryRequestRepository::new());
+        let slack = Arc::new(MockChannel::new().with_channel_type(ChannelType::Slack));
+        let admin_id = UserId::new();
+        let service = OversightService::new()
+            .with_request_repository(requests.clone())
+            .with_admin_check(Arc::new(Admins(vec![admin_id])))
+            .with_notification_router(NotificationRouter::default().with_channel(slack.clone()));
+        CancelFixture {
+            service,
+            requests,
+            slack,
+            admin_id,
+        }
+    }
+
+    async fn stored_request(f: &CancelFixture, status: RequestStatus) -> OversightRequest {
+        let mut request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        );
+        request.status = status;
+        request.assigned_reviewers = vec![UserId::new()];
+        f.requests.create(&request).await.unwrap();
+        request
+    }
+
+    #[tokio::test]
+    async fn test_cancel_from_each_open_state() {
+        let f = cancel_fixture();
+
+        for status in [
+            RequestStatus::Pending,
+            RequestStatus::InReview,
+            RequestStatus::Escalated,
+        ] {
+            let request = stored_request(&f, status).await;
+            let result = f
+                .service
+                .cancel_request(
+                    request.id,
+                    Actor::Agent {
+                        agent_id: request.agent_id,
+                    },
+                    "task abandoned",
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(result.request.status, RequestStatus::Cancelled);
+            assert_eq!(result.transition.from, status);
+            assert_eq!(result.transition.reason.as_deref(), Some("task abandoned"));
+            let stored = f.requests.get(request.id).await.unwrap().unwrap();
+            assert_eq!(stored.status, RequestStatus::Cancelled);
+        }
+
+        // An organization administrator may cancel for the agent
+        let request = stored_request(&f, RequestStatus::Pending).await;
+        let result = f
+            .service
+            .cancel_request(
+                request.id,
+                Actor::User {
+                    user_id: f.admin_id,
+                },
+                "duplicate",
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.transition.actor.kind(), "user");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_rejected_for_closed_requests_and_other_actors() {
+        let f = cancel_fixture();
+
+        for status in [
+            RequestStatus::Approved,
+            RequestStatus::Rejected,
+            RequestStatus::TimedOut,
+            RequestStatus::Cancelled,
+        ] {
+            let request = stored_request(&f, status).await;
+            let err = f
+                .service
+                .cancel_request(
+                    request.id,
+                    Actor::Agent {
+                        agent_id: request.agent_id,
+                    },
+                    "too late",
+                )
+                .await
+                .unwrap_err();
+            assert!(matches!(err, CretoError::RequestClosed { .. }));
+            let stored = f.requests.get(request.id).await.unwrap().unwrap();
+            assert_eq!(stored.status, status);
+        }
+
+        let request = stored_request(&f, RequestStatus::Pending).await;
+        for actor in [
+            Actor::Agent {
+                agent_id: AgentId::new(),
+            },
+            Actor::User {
+                user_id: UserId::new(),
+            },
+            Actor::System,
+        ] {
+            let err = f
+                .service
+                .cancel_request(request.id, actor, "not mine")
+                .await
+                .unwrap_err();
+            assert!(matches!(err, CretoError::NotAuthorized { .. }));
+        }
+        assert_eq!(f.slack.get_cancellations().await.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_approval_after_cancel_is_rejected() {
+        let f = cancel_fixture();
+        let request = stored_request(&f, RequestStatus::InReview).await;
+        f.service
+            .cancel_request(
+                request.id,
+                Actor::Agent {
+                    agent_id: request.agent_id,
+                },
+                "task abandoned",
+            )
+            .await
+            .unwrap();
+
+        let err = f
+            .service
+            .submit_approval(
+                request.id,
+                request.assigned_reviewers[0],
+                ApprovalDecision::Approve,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, CretoError::RequestClosed { ref status, .. } if status == "cancelled")
+        );
+        assert_eq!(err.code(), "ENABLE-038");
+    }
+
+    /// Request store that applies another writer's status change just
+    /// before the next conditional update.
+    struct Interleaved {
+        inner: Arc<crate::repository::InMemoryRequestRepository>,
+        interloper: std::sync::Mutex<Option<RequestStatus>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RequestRepository for Interleaved {
+        async fn create(&self, request: &OversightRequest) -> Result<Uuid, CretoError> {
+            self.inner.create(request).await
+        }
+
+        async fn get(&self, id: Uuid) -> Result<Option<OversightRequest>, CretoError> {
+            self.inner.get(id).await
+        }
+
+        async fn update_status(&self, id: Uuid, status: RequestStatus) -> Result<(), CretoError> {
+            self.inner.update_status(id, status).await
+        }
+
+        async fn update_status_if(
+            &self,
+            id: Uuid,
+            expected: RequestStatus,
+            status: RequestStatus,
+        ) -> Result<bool, CretoError> {
+            let interloper = self.interloper.lock().unwrap().take();
+            if let Some(interloper) = interloper {
+                self.inner.update_status(id, interloper).await?;
+            }
+            self.inner.update_status_if(id, expected, status).await
+        }
+
+        async fn list_pending(
+            &self,
+            org_id: OrganizationId,
+        ) -> Result<Vec<OversightRequest>, CretoError> {
+            self.inner.list_pending(org_id).await
+        }
+
+        async fn update_priority(
+            &self,
+            id: Uuid,
+            priority: crate::request::Priority,
+        ) -> Result<(), CretoError> {
+            self.inner.update_priority(id, priority).await
+        }
+
+        async fn list_open(&self) -> Result<Vec<OversightRequest>, CretoError> {
+            self.inner.list_open().await
+        }
+
+        async fn list_by_agent(
+            &self,
+            agent_id: AgentId,
+            limit: i64,
+        ) -> Result<Vec<OversightRequest>, CretoError> {
+            self.inner.list_by_agent(agent_id, limit).await
+        }
+
+        async fn escalate(
+            &self,
+            id: Uuid,
+            level: u32,
+            reviewers: &[UserId],
+            expires_at: chrono::DateTime<chrono::Utc>,
+        ) -> Result<(), CretoError> {
+            self.inner.escalate(id, level, reviewers, expires_at).await
+        }
+
+        async fn record_reminder(
+            &self,
+            id: Uuid,
+            at: chrono::DateTime<chrono::Utc>,
+        ) -> Result<u32, CretoError> {
+            self.inner.record_reminder(id, at).await
+        }
+
+        async fn set_notified_channels(
+            &self,
+            id: Uuid,
+            channels: &[crate::channels::ChannelType],
+        ) -> Result<(), CretoError> {
+            self.inner.set_notified_channels(id, channels).await
+        }
+
+        async fn find_timed_out(&self) -> Result<Vec<Uuid>, CretoError> {
+            self.inner.find_timed_out().await
+        }
+
+        async fn find_by_correlation(
+            &self,
+            correlation_id: Uuid,
+        ) -> Result<Vec<OversightRequest>, CretoError> {
+            self.inner.find_by_correlation(correlation_id).await
+        }
+    }
+
+    /// A service whose store sees `interloper` written concurrently with
+    /// the next status change.
+    fn interleaved_service(f: &CancelFixture, interloper: RequestStatus) -> OversightService {
+        OversightService::new()
+            .with_request_repository(Arc::new(Interleaved {
+                inner: f.requests.clone(),
+                interloper: std::sync::Mutex::new(Some(interloper)),
+            }))
+            .with_admin_check(Arc::new(Admins(vec![f.admin_id])))
+    }
+
+    #[tokio::test]
+    async fn test_cancel_loses_race_to_concurrent_decision() {
+        let f = cancel_fixture();
+        let request = stored_request(&f, RequestStatus::InReview).await;
+        let service = interleaved_service(&f, RequestStatus::Approved);
+
+        let err = service
+            .cancel_request(
+                request.id,
+                Actor::Agent {
+                    agent_id: request.agent_id,
+                },
+                "task abandoned",
+            )
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(err, CretoError::RequestClosed { ref status, .. } if status == "approved")
+        );
+        let stored = f.requests.get(request.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, RequestStatus::Approved);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_retries_after_concurrent_escalation() {
+        let f = cancel_fixture();
+        let request = stored_request(&f, RequestStatus::Pending).await;
+        let service = interleaved_service(&f, RequestStatus::Escalated);
+
+        let result = service
+            .cancel_request(
+                request.id,
+                Actor::Agent {
+                    agent_id: request.agent_id,
+                },
+                "task abandoned",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.transition.from, RequestStatus::Escalated);
+        let stored = f.requests.get(request.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, RequestStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_approval_loses_race_to_concurrent_cancel() {
+        let f = cancel_fixture();
+        let request = stored_request(&f, RequestStatus::Pending).await;
+        let service = interleaved_service(&f, RequestStatus::Cancelled);
+
+        let err = service
+            .submit_approval(
+                request.id,
+                request.assigned_reviewers[0],
+                ApprovalDecision::Approve,
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(
+            matches!(err, CretoError::RequestClosed { ref status, .. } if status == "cancelled")
+        );
+        let stored = f.requests.get(request.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, RequestStatus::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_notifies_reviewers_and_drops_held_notifications() {
+        use crate::notifications::DigestConfig;
+
+        let f = cancel_fixture();
+        let notified = UserId::new();
+        let batched = UserId::new();
+        let mut request = stored_request(&f, RequestStatus::Pending).await;
+        request.assigned_reviewers = vec![notified, batched];
+        f.requests.create(&request).await.unwrap();
+        f.service
+            .set_notification_preferences(
+                NotificationPreferences::for_reviewer(request.organization_id, batched)
+                    .with_digest(DigestConfig::every_hours(6)),
+            )
+            .await
+            .unwrap();
+        let report = f.service.notify_reviewers(&request).await.unwrap();
+        assert_eq!((report.sent.len(), report.queued.len()), (1, 1));
+
+        let result = f
+            .service
+            .cancel_request(
+                request.id,
+                Actor::Agent {
+                    agent_id: request.agent_id,
+                },
+                "task abandoned",
+            )
+            .await
+            .unwrap();
+
+        // Only the reviewer who saw the request hears about the withdrawal
+        assert_eq!(result.notifications.sent.len(), 1);
+        assert_eq!(result.notifications.sent[0].reviewer_id, Some(notified));
+        assert_eq!(result.notifications.dropped.len(), 1);
+        assert_eq!(result.notifications.dropped[0].reviewer_id, Some(batched));
+        let cancellations = f.slack.get_cancellations().await;
+        assert_eq!(cancellations.len(), 1);
+        assert_eq!(cancellations[0].0.status, RequestStatus::Cancelled);
+        assert_eq!(cancellations[0].1, "task abandoned");
+
+        // The held digest entry is gone
+        assert!(f
+            .service
+            .deliver_due_notifications()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_review_bundle_mid_quorum() {
+        use crate::checkpoint::InMemoryCheckpointRepository;
+        use crate::clock::TestClock;
+        use crate::enrichment::{ContextSection, SectionField};
+        use crate::repository::{
+            ApprovalRepository, InMemoryApprovalRepository, InMemoryRequestRepository,
+            InMemoryStateTransitionRepository,
+        };
+        use crate::review::{InMemoryReviewerDirectory, ReviewerProfile};
+
+        let org_id = OrganizationId::new();
+        let (alice, bob, departed) = (UserId::new(), UserId::new(), UserId::new());
+        let directory = Arc::new(InMemoryReviewerDirectory::new());
+        directory.add(
+            org_id,
+            ReviewerProfile::new(alice, "Alice").with_email("alice@example.com"),
+        );
+        directory.add(org_id, ReviewerProfile::new(bob, "Bob"));
+
+        let section = ContextSection::new(
+            "execution",
+            "Execution",
+            vec![SectionField::new("Image", "deploy:latest")],
+        );
+        let mut request = OversightRequest::new(
+            org_id,
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        )
+        .with_context(serde_json::json!({
+            "target": "prod",
+            "sections": [section.clone()],
+        }));
+        request.assigned_reviewers = vec![alice, bob, departed];
+
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        let approvals = Arc::new(InMemoryApprovalRepository::new());
+        let transitions = Arc::new(InMemoryStateTransitionRepository::new());
+        let clock = Arc::new(TestClock::new(
+            request.created_at + chrono::Duration::minutes(10),
+        ));
+        let mut service = OversightService::with_checkpoints(CheckpointManager::new(Box::new(
+            InMemoryCheckpointRepository::new(),
+        )))
+        .with_request_repository(requests.clone())
+        .with_approval_repository(approvals.clone())
+        .with_transition_repository(transitions.clone())
+        .with_reviewer_directory(directory)
+        .with_clock(clock.clone());
+        service.default_quorum = QuorumConfig::n_of_m(2);
+
+        requests.create(&request).await.unwrap();
+        let mut machine = StateMachine::new();
+        machine
+            .transition(RequestStatus::InReview, Actor::System, None)
+            .unwrap();
+        service
+            .record_transition(request.id, &machine.history()[0])
+            .await
+            .unwrap();
+        requests
+            .update_status(request.id, RequestStatus::InReview)
+            .await
+            .unwrap();
+        service
+            .save_checkpoint(
+                &request,
+                &machine,
+                serde_json::Value::Null,
+                Some("routed".to_string()),
+            )
+            .await
+            .unwrap();
+
+        approvals
+            .create(&Approval::new(request.id, alice, ApprovalDecision::Approve))
+            .await
+            .unwrap();
+        approvals
+            .create(&Approval::new(
+                request.id,
+                departed,
+                ApprovalDecision::Abstain,
+            ))
+            .await
+            .unwrap();
+
+        let bundle = service.review_bundle(request.id, alice).await.unwrap();
+        assert_eq!(bundle.request.status, RequestStatus::InReview);
+        assert_eq!(
+            bundle.request.context,
+            serde_json::json!({"target": "prod"})
+        );
+        assert_eq!(bundle.context_sections, vec![section]);
+        assert_eq!(bundle.quorum.required_approvals, 2);
+        assert!(matches!(
+            bundle.quorum_result,
+            QuorumResult::Pending {
+                approve_count: 1,
+                required: 2,
+                ..
+            }
+        ));
+        assert_eq!(bundle.approvals.len(), 2);
+        let names: Vec<_> = bundle
+            .approvals
+            .iter()
+            .map(|d| d.reviewer.as_ref().map(|r| r.display_name.as_str()))
+            .collect();
+        assert_eq!(names, vec![Some("Alice"), None]);
+        assert_eq!(bundle.transitions.len(), 1);
+        assert_eq!(bundle.transitions[0].to_status, RequestStatus::InReview);
+        let checkpoint = bundle.latest_checkpoint.as_ref().unwrap();
+        assert_eq!(checkpoint.reason.as_deref(), Some("routed"));
+        assert_eq!(checkpoint.transition_count, 1);
+        assert_eq!(
+            bundle.time_remaining_seconds,
+            Some((request.expires_at - clock.now()).num_seconds())
+        );
+        assert!(bundle.caller_has_decided);
+        assert_eq!(bundle.caller_decision, Some(ApprovalDecision::Approve));
+
+        let json = serde_json::to_value(&bundle).unwrap();
+        assert_eq!(json["approvals"][0]["reviewer"]["display_name"], "Alice");
+        assert_eq!(json["caller_decision"], "approve");
+
+        // Another member sees the same request without a decision of their own
+        let bundle = service.review_bundle(request.id, bob).await.unwrap();
+        assert!(!bundle.caller_has_decided);
+        assert_eq!(bundle.caller_decision, None);
+
+        // Past the deadline the remaining time is zero until the request is expired
+        clock.advance(chrono::Duration::days(30));
+        let bundle = service.review_bundle(request.id, bob).await.unwrap();
+        assert_eq!(bundle.time_remaining_seconds, Some(0));
+
+        // Reviewers who are not members of the organization see nothing
+        let err = service
+            .review_bundle(request.id, UserId::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::NotAuthorized { .. }));
+        let err = service
+            .review_bundle(request.id, departed)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::NotAuthorized { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_export_orders_and_chains_events() {
+        use crate::checkpoint::InMemoryCheckpointRepository;
+        use crate::clock::TestClock;
+        use crate::lifecycle::{
+            parse_json_lines, verify_chain, InMemoryActivityLog, LifecycleDocument,
+            LifecycleEventType, ReviewerPiiRedactor,
+        };
+        use crate::repository::{
+            ApprovalRepository, InMemoryApprovalRepository, InMemoryRequestRepository,
+            InMemoryStateTransitionRepository, StateTransitionRepository,
+        };
+
+        let reviewer = UserId::new();
+        let request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        );
+        // Everything happens in the same instant, so only the tiebreak orders it.
+        let at = request.created_at;
+
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        let approvals = Arc::new(InMemoryApprovalRepository::new());
+        let transitions = Arc::new(InMemoryStateTransitionRepository::new());
+        let service = OversightService::with_checkpoints(CheckpointManager::new(Box::new(
+            InMemoryCheckpointRepository::new(),
+        )))
+        .with_request_repository(requests.clone())
+        .with_approval_repository(approvals.clone())
+        .with_transition_repository(transitions.clone())
+        .with_activity_log(Arc::new(InMemoryActivityLog::new()))
+        .with_export_signing_key("audit-key")
+        .with_clock(Arc::new(TestClock::new(at)));
+
+        requests.create(&request).await.unwrap();
+        let mut machine = StateMachine::new();
+        machine
+            .transition(
+                RequestStatus::Approved,
+                Actor::User { user_id: reviewer },
+                None,
+            )
+            .unwrap();
+        let mut checkpoint = Checkpoint::new(
+            request.id,
+            RequestStatus::Approved,
+            &machine,
+            serde_json::Value::Null,
+        );
+        checkpoint.timestamp = at;
+        service
+            .checkpoint_manager
+            .as_ref()
+            .unwrap()
+            .save_checkpoint(&checkpoint)
+            .await
+            .unwrap();
+        transitions
+            .create(&StateTransitionRecord {
+                id: Uuid::now_v7(),
+                request_id: request.id,
+                from_status: RequestStatus::Pending,
+                to_status: RequestStatus::Approved,
+                actor_type: "user".to_string(),
+                actor_id: Some(*reviewer.as_uuid()),
+                reason: None,
+                transitioned_at: at,
+            })
+            .await
+            .unwrap();
+        let mut approval = Approval::new(request.id, reviewer, ApprovalDecision::Approve)
+            .with_reason("Looks safe");
+        approval.decided_at = at;
+        approvals.create(&approval).await.unwrap();
+        service
+            .record_activity(
+                request.id,
+                Actor::Agent {
+                    agent_id: request.agent_id,
+                },
+                ActivityKind::Executed {
+                    succeeded: true,
+                    detail: None,
+                },
+            )
+            .await
+            .unwrap();
+        service
+            .add_comment(request.id, reviewer, "Checked the rollout plan")
+            .await
+            .unwrap();
+        service
+            .record_activity(
+                request.id,
+                Actor::User { user_id: reviewer },
+                ActivityKind::Viewed,
+            )
+            .await
+            .unwrap();
+
+        let export = service
+            .lifecycle_export(request.id, LifecycleExportFormat::JsonLines)
+            .await
+            .unwrap();
+        let order: Vec<_> = export.entries.iter().map(|e| e.event_type).collect();
+        assert_eq!(
+            order,
+            vec![
+                LifecycleEventType::Created,
+                LifecycleEventType::Viewed,
+                LifecycleEventType::Commented,
+                LifecycleEventType::Decided,
+                LifecycleEventType::Transitioned,
+                LifecycleEventType::Checkpointed,
+                LifecycleEventType::Executed,
+            ]
+        );
+        let parsed = parse_json_lines(&export.body).unwrap();
+        assert_eq!(parsed, export.entries);
+        assert!(verify_chain(&parsed));
+        assert_eq!(export.head_hash, parsed.last().unwrap().hash);
+
+        // The same records always export the same way.
+        let again = service
+            .lifecycle_export(request.id, LifecycleExportFormat::JsonLines)
+            .await
+            .unwrap();
+        assert_eq!(again.body, export.body);
+
+        let mut tampered = parsed.clone();
+        tampered[3].detail["reason"] = "Rubber stamp".into();
+        assert!(!verify_chain(&tampered));
+        let mut dropped = parsed.clone();
+        dropped.remove(2);
+        assert!(!verify_chain(&dropped));
+
+        let signed = service
+            .lifecycle_export(request.id, LifecycleExportFormat::SignedJson)
+            .await
+            .unwrap();
+        let document: LifecycleDocument = serde_json::from_str(&signed.body).unwrap();
+        assert!(document.verify("audit-key"));
+        assert!(!document.verify("other-key"));
+
+        let redacted = service
+            .lifecycle_export_redacted(
+                request.id,
+                LifecycleExportFormat::JsonLines,
+                &ReviewerPiiRedactor::new(),
+            )
+            .await
+            .unwrap();
+        assert!(verify_chain(&redacted.entries));
+        assert!(!redacted.body.contains(&reviewer.to_string()));
+        assert_eq!(redacted.entries[1].actor.id.as_deref(), Some("reviewer-1"));
+        assert_eq!(redacted.entries[4].actor.id.as_deref(), Some("reviewer-1"));
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_export_requires_configuration() {
+        use crate::repository::InMemoryRequestRepository;
+
+        let service = OversightService::new();
+        let result = service
+            .lifecycle_export(Uuid::now_v7(), LifecycleExportFormat::JsonLines)
+            .await;
+        assert!(matches!(result, Err(CretoError::Configuration(_))));
+
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        let request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy",
+        );
+        requests.create(&request).await.unwrap();
+        let service = OversightService::new().with_request_repository(requests);
+        let result = service
+            .lifecycle_export(request.id, LifecycleExportFormat::SignedJson)
+            .await;
+        assert!(matches!(result, Err(CretoError::Configuration(_))));
+
+        let export = service
+            .lifecycle_export(request.id, LifecycleExportFormat::JsonLines)
+            .await
+            .unwrap();
+        assert_eq!(export.entries.len(), 1);
+        assert_eq!(
+            export.entries[0].previous_hash,
+            crate::lifecycle::GENESIS_HASH
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metrics_count_decisions_and_pending_requests() {
+        use crate::repository::InMemoryRequestRepository;
+
+        let registry = MetricsRegistry::new();
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        let service = OversightService::new()
+            .with_request_repository(requests.clone())
+            .with_metrics(&registry);
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            let request = OversightRequest::new(
+                OrganizationId::new(),
+                AgentId::new(),
+                ActionType::Custom {
+                    type_id: "deploy".to_string(),
+                },
+                "Deploy",
+            );
+            requests.create(&request).await.unwrap();
+            ids.push(request.id);
+        }
+
+        service
+            .submit_approval(ids[0], UserId::new(), ApprovalDecision::Approve, None)
+            .await
+            .unwrap();
+        service
+            .submit_approval(ids[1], UserId::new(), ApprovalDecision::Abstain, None)
+            .await
+            .unwrap();
+        service.refresh_metrics().await.unwrap();
+
+        let text = registry.render_metrics();
+        assert!(text.contains("creto_oversight_approvals_total{decision=\"approve\"} 1\n"));
+        assert!(text.contains("creto_oversight_approvals_total{decision=\"abstain\"} 1\n"));
+        assert!(text.contains("# TYPE creto_oversight_pending_requests gauge\n"));
+        assert!(text.contains("creto_oversight_pending_requests{status=\"pending\"} 1\n"));
+        assert!(text.contains("creto_oversight_pending_requests{status=\"in_review\"} 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_aging_boosts_priority_and_renotifies() {
+        use crate::aging::AgingPolicy;
+        use crate::channels::{ChannelType, MockChannel};
+        use crate::clock::TestClock;
+        use crate::repository::{
+            InMemoryRequestRepository, InMemoryStateTransitionRepository, StateTransitionRepository,
+        };
+        use crate::request::Priority;
+
+        let org_id = OrganizationId::new();
+        let new_request = |priority: Priority| {
+            let mut request = OversightRequest::new(
+                org_id,
+                AgentId::new(),
+                ActionType::Custom {
+                    type_id: "deploy".to_string(),
+                },
+                "Deploy to production",
+            )
+            .with_priority(priority)
+            .with_timeout(1000);
+            request.assigned_reviewers = vec![UserId::new()];
+            request
+        };
+        let normal = new_request(Priority::Normal);
+        let mut high = new_request(Priority::High);
+        high.created_at = normal.created_at + chrono::Duration::seconds(500);
+        high.expires_at = high.created_at + chrono::Duration::seconds(1000);
+
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        let transitions = Arc::new(InMemoryStateTransitionRepository::new());
+        let slack = Arc::new(MockChannel::new().with_channel_type(ChannelType::Slack));
+        let clock = Arc::new(TestClock::new(normal.created_at));
+        let service = OversightService::new()
+            .with_request_repository(requests.clone())
+            .with_transition_repository(transitions.clone())
+            .with_notification_router(NotificationRouter::default().with_channel(slack.clone()))
+            .with_aging_policy(AgingPolicy::default())
+            .with_clock(clock.clone());
+        requests.create(&normal).await.unwrap();
+        requests.create(&high).await.unwrap();
+
+        let order =
+            |pending: Vec<OversightRequest>| pending.iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(
+            order(requests.list_pending(org_id).await.unwrap()),
+            vec![high.id, normal.id]
+        );
+
+        // 74% of the Normal request's timeout: nothing to do
+        clock.advance(chrono::Duration::seconds(740));
+        assert!(service.boost_aging_requests().await.unwrap().is_empty());
+        assert_eq!(slack.notification_count().await, 0);
+
+        // 75%: one level up, now tied with High and ahead as the older request
+        clock.advance(chrono::Duration::seconds(10));
+        let boosts = service.boost_aging_requests().await.unwrap();
+        assert_eq!(boosts.len(), 1);
+        assert_eq!(
+            (boosts[0].from, boosts[0].to),
+            (Priority::Normal, Priority::High)
+        );
+        assert_eq!(slack.notification_count().await, 1);
+        assert_eq!(
+            order(requests.list_pending(org_id).await.unwrap()),
+            vec![normal.id, high.id]
+        );
+
+        // Evaluating again at the same threshold does not boost twice
+        assert!(service.boost_aging_requests().await.unwrap().is_empty());
+
+        // 90%: straight to Critical
+        clock.advance(chrono::Duration::seconds(150));
+        let boosts = service.boost_aging_requests().await.unwrap();
+        assert_eq!(boosts.len(), 1);
+        assert_eq!(
+            (boosts[0].from, boosts[0].to),
+            (Priority::High, Priority::Critical)
+        );
+        assert_eq!(slack.notification_count().await, 2);
+
+        let stored = requests.get(normal.id).await.unwrap().unwrap();
+        assert_eq!(stored.priority, Priority::Critical);
+        assert_eq!(stored.original_priority(), Priority::Normal);
+        assert_eq!(stored.status, RequestStatus::Pending);
+
+        let audit = transitions.list_by_request(normal.id).await.unwrap();
+        assert_eq!(audit.len(), 2);
+        for entry in &audit {
+            assert_eq!(entry.actor_type, "system");
+            assert_eq!(entry.reason.as_deref(), Some("aging"));
+            assert_eq!(entry.from_status, RequestStatus::Pending);
+            assert_eq!(entry.to_status, RequestStatus::Pending);
+        }
+        assert!(transitions
+            .list_by_request(high.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_aging_never_lowers_priority() {
+        use crate::aging::{AgingPolicy, PriorityBoost};
+        use crate::clock::TestClock;
+        use crate::repository::InMemoryRequestRepository;
+        use crate::request::Priority;
+
+        let request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        )
+        .with_priority(Priority::Critical)
+        .with_timeout(1000);
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        requests.create(&request).await.unwrap();
+        let clock = Arc::new(TestClock::new(request.created_at));
+        let service = OversightService::new()
+            .with_request_repository(requests.clone())
+            .with_aging_policy(
+                AgingPolicy::new().with_threshold(0.5, PriorityBoost::To(Priority::High)),
+            )
+            .with_clock(clock.clone());
+
+        clock.advance(chrono::Duration::seconds(900));
+        assert!(service.boost_aging_requests().await.unwrap().is_empty());
+        let stored = requests.get(request.id).await.unwrap().unwrap();
+        assert_eq!(stored.priority, Priority::Critical);
+        assert_eq!(stored.original_priority, None);
+    }
+
+    struct DeadlineFixture {
+        service: OversightService,
+        requests: Arc<crate::repository::InMemoryRequestRepository>,
+        transitions: Arc<crate::repository::InMemoryStateTransitionRepository>,
+        approvals: Arc<crate::repository::InMemoryApprovalRepository>,
+        slack: Arc<crate::channels::MockChannel>,
+        clock: Arc<crate::clock::TestClock>,
+        contact: UserId,
+        request: OversightRequest,
+    }
+
+    /// A High request with a 2-of-2 quorum and a one hour reviewer deadline.
+    async fn deadline_fixture(
+        policy: crate::assignments::ReviewerDeadlinePolicy,
+    ) -> DeadlineFixture {
+        use crate::assignments::{InMemoryAssignmentStore, ReviewerDeadlineConfig};
+        use crate::channels::{ChannelType, MockChannel};
+        use crate::clock::TestClock;
+        use crate::repository::{
+            InMemoryApprovalRepository, InMemoryRequestRepository,
+            InMemoryStateTransitionRepository,
+        };
+        use crate::request::Priority;
+
+        let request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "wire".to_string(),
+            },
+            "Wire transfer",
+        )
+        .with_priority(Priority::High)
+        .with_timeout(4 * 3600);
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        requests.create(&request).await.unwrap();
+        let transitions = Arc::new(InMemoryStateTransitionRepository::new());
+        let approvals = Arc::new(InMemoryApprovalRepository::new());
+        let slack = Arc::new(MockChannel::new().with_channel_type(ChannelType::Slack));
+        let clock = Arc::new(TestClock::new(request.created_at));
+        let contact = UserId::new();
+
+        let mut service = OversightService::new()
+            .with_request_repository(requests.clone())
+            .with_transition_repository(transitions.clone())
+            .with_approval_repository(approvals.clone())
+            .with_notification_router(NotificationRouter::default().with_channel(slack.clone()))
+            .with_reviewer_deadlines(
+                Arc::new(InMemoryAssignmentStore::new()),
+                ReviewerDeadlineConfig::default().with_organization(
+                    request.organization_id,
+                    policy
+                        .with_deadline(Priority::High, 3600)
+                        .with_escalation_contact(contact),
+                ),
+            )
+            .with_clock(clock.clone());
+        service.default_quorum = QuorumConfig::n_of_m(2);
+
+        DeadlineFixture {
+            service,
+            requests,
+            transitions,
+            approvals,
+            slack,
+            clock,
+            contact,
+            request,
+        }
+    }
+
+    impl DeadlineFixture {
+        async fn approve(&self, reviewer_id: UserId) -> CretoResult<ApprovalSubmitResult> {
+            let result = self
+                .service
+                .submit_approval(
+                    self.request.id,
+                    reviewer_id,
+                    ApprovalDecision::Approve,
+                    None,
+                )
+                .await?;
+            let mut approval =
+                Approval::new(self.request.id, reviewer_id, ApprovalDecision::Approve);
+            approval.decided_at = self.clock.now();
+            self.approvals.create(&approval).await?;
+            Ok(result)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lapsed_reviewer_reduces_quorum() {
+        use crate::assignments::{AssignmentSource, ReviewerDeadlinePolicy, LAPSE_REASON};
+
+        let f = deadline_fixture(ReviewerDeadlinePolicy::default()).await;
+        let (finance, legal) = (UserId::new(), UserId::new());
+        f.service
+            .assign_reviewers(f.request.id, &[finance], AssignmentSource::Direct)
+            .await
+            .unwrap();
+        f.clock.advance(chrono::Duration::minutes(30));
+        let assigned = f
+            .service
+            .assign_reviewers(f.request.id, &[legal], AssignmentSource::Direct)
+            .await
+            .unwrap();
+        assert_eq!(
+            assigned[0].respond_by,
+            f.clock.now() + chrono::Duration::hours(1)
+        );
+
+        // Legal approves; 1 of 2 is not enough while finance may still respond
+        f.approve(legal).await.unwrap();
+        f.clock.advance(chrono::Duration::minutes(29));
+        assert!(f
+            .service
+            .lapse_overdue_assignments()
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Finance's hour is up: the remaining reviewer's approval is quorum
+        f.clock.advance(chrono::Duration::minutes(1));
+        let lapses = f.service.lapse_overdue_assignments().await.unwrap();
+        assert_eq!(lapses.len(), 1);
+        assert_eq!(lapses[0].assignment.reviewer_id, finance);
+        assert_eq!(lapses[0].decided, Some(RequestStatus::Approved));
+
+        let stored = f.requests.get(f.request.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, RequestStatus::Approved);
+        let audit = f.transitions.list_by_request(f.request.id).await.unwrap();
+        let lapse_reason = format!("{}:{}", LAPSE_REASON, finance);
+        assert!(audit
+            .iter()
+            .any(|t| t.actor_type == "system" && t.reason.as_deref() == Some(&lapse_reason)));
+        assert_eq!(audit.last().unwrap().to_status, RequestStatus::Approved);
+
+        let escalations = f.slack.get_notifications().await;
+        assert_eq!(escalations.len(), 1);
+        assert_eq!(escalations[0].assigned_reviewers, vec![f.contact]);
+    }
+
+    #[tokio::test]
+    async fn test_lapsed_reviewer_kept_and_escalated() {
+        use crate::assignments::{AssignmentSource, LapseMode, ReviewerDeadlinePolicy};
+
+        let f = deadline_fixture(
+            ReviewerDeadlinePolicy::default().with_lapse_mode(LapseMode::KeepAndEscalate),
+        )
+        .await;
+        let (finance, legal) = (UserId::new(), UserId::new());
+        f.service
+            .assign_reviewers(f.request.id, &[finance, legal], AssignmentSource::Direct)
+            .await
+            .unwrap();
+        f.approve(legal).await.unwrap();
+
+        f.clock.advance(chrono::Duration::hours(1));
+        let lapses = f.service.lapse_overdue_assignments().await.unwrap();
+        assert_eq!(lapses.len(), 1);
+        assert_eq!(lapses[0].mode, LapseMode::KeepAndEscalate);
+        assert_eq!(lapses[0].decided, None);
+        assert_eq!(f.slack.notification_count().await, 1);
+
+        // Finance's approval is still required
+        let stored = f.requests.get(f.request.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, RequestStatus::InReview);
+        let approvals = f.approvals.list_by_request(f.request.id).await.unwrap();
+        assert!(matches!(
+            f.service
+                .evaluate_quorum(&stored, &approvals)
+                .await
+                .unwrap(),
+            QuorumResult::Pending {
+                approve_count: 1,
+                required: 2,
+                current_weight: 1,
+                required_weight: None,
+                ..
+            }
+        ));
+
+        // Lapsing is done once
+        assert!(f
+            .service
+            .lapse_overdue_assignments()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_late_decision_rejected_unless_allowed() {
+        use crate::assignments::{AssignmentSource, ReviewerDeadlinePolicy};
+
+        let f = deadline_fixture(ReviewerDeadlinePolicy::default()).await;
+        let finance = UserId::new();
+        let claimed = f
+            .service
+            .assign_next(f.request.organization_id, finance)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(claimed.source, AssignmentSource::Claim);
+        assert!(f
+            .service
+            .assign_next(f.request.organization_id, finance)
+            .await
+            .unwrap()
+            .is_none());
+
+        f.clock.advance(chrono::Duration::hours(1));
+        f.service.lapse_overdue_assignments().await.unwrap();
+        let err = f.approve(finance).await.unwrap_err();
+        assert!(matches!(err, CretoError::AssignmentLapsed { .. }));
+        assert_eq!(err.code(), "ENABLE-042");
+
+        let f = deadline_fixture(ReviewerDeadlinePolicy::default().with_late_decisions(true)).await;
+        f.service
+            .assign_reviewers(f.request.id, &[finance], AssignmentSource::Direct)
+            .await
+            .unwrap();
+        f.clock.advance(chrono::Duration::hours(1));
+        f.service.lapse_overdue_assignments().await.unwrap();
+        assert!(f.approve(finance).await.is_ok());
+    }
+
+    struct EscalationFixture {
+        service: OversightService,
+        requests: Arc<crate::repository::InMemoryRequestRepository>,
+        transitions: Arc<crate::repository::InMemoryStateTransitionRepository>,
+        slack: Arc<crate::channels::MockChannel>,
+        email: Arc<crate::channels::MockChannel>,
+        clock: Arc<crate::clock::TestClock>,
+        chains: Arc<crate::escalation::InMemoryEscalationChainStore>,
+        request: OversightRequest,
+    }
+
+    /// A request past its timeout, with Slack and email registered.
+    ///
+    /// The in-memory repository finds timed out requests by wall clock, so
+    /// the service clock runs a day behind it: any deadline the service sets
+    /// has already passed.
+    async fn escalation_fixture() -> EscalationFixture {
+        use crate::channels::{ChannelType, MockChannel};
+        use crate::clock::TestClock;
+        use crate::escalation::{EscalationEngine, InMemoryEscalationChainStore};
+        use crate::repository::{InMemoryRequestRepository, InMemoryStateTransitionRepository};
+
+        let mut request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        );
+        request.assigned_reviewers = vec![UserId::new()];
+        request.expires_at = request.created_at - chrono::Duration::minutes(1);
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        requests.create(&request).await.unwrap();
+        let transitions = Arc::new(InMemoryStateTransitionRepository::new());
+        let slack = Arc::new(MockChannel::new().with_channel_type(ChannelType::Slack));
+        let email = Arc::new(MockChannel::new().with_channel_type(ChannelType::Email));
+        let clock = Arc::new(TestClock::new(
+            request.created_at - chrono::Duration::days(1),
+        ));
+        let chains = Arc::new(InMemoryEscalationChainStore::new());
+
+        let service = OversightService::new()
+            .with_request_repository(requests.clone())
+            .with_transition_repository(transitions.clone())
+            .with_notification_router(
+                NotificationRouter::default()
+                    .with_channel(slack.clone())
+                    .with_channel(email.clone()),
+            )
+            .with_escalation(EscalationEngine::new(chains.clone()))
+            .with_clock(clock.clone());
 
-    /// Restored state machine.
-    pub state_machine: StateMachine,
+        EscalationFixture {
+            service,
+            requests,
+            transitions,
+            slack,
+            email,
+            clock,
+            chains,
+            request,
+        }
+    }
 
-    /// Restored context.
-    pub context: serde_json::Value,
+    #[tokio::test]
+    async fn test_timed_out_request_escalates_through_chain() {
+        use crate::escalation::{
+            EscalationChain, EscalationChainStore, EscalationOutcome, EscalationTier,
+            EscalationTrigger,
+        };
+        use crate::repository::StateTransitionRepository;
 
-    /// Checkpoint that was used for recovery.
-    pub checkpoint_id: Uuid,
+        let f = escalation_fixture().await;
+        let (lead, admin) = (UserId::new(), UserId::new());
+        f.chains
+            .put(
+                &EscalationChain::new(f.request.organization_id)
+                    .with_tier(EscalationTier::new("team_lead", vec![lead], 3600))
+                    .with_tier(EscalationTier::new("org_admin", vec![admin], 600)),
+            )
+            .await
+            .unwrap();
 
-    /// When the checkpoint was created.
-    pub checkpoint_timestamp: chrono::DateTime<chrono::Utc>,
-}
+        // First hop: the team lead gets the request with an hour to decide
+        let escalations = f.service.escalate_timed_out_requests().await.unwrap();
+        assert_eq!(escalations.len(), 1);
+        assert_eq!(escalations[0].trigger, EscalationTrigger::TimedOut);
+        assert_eq!(
+            escalations[0].outcome,
+            EscalationOutcome::Escalated {
+                level: 1,
+                tier: "team_lead".to_string(),
+                reviewers: vec![lead],
+                expires_at: f.clock.now() + chrono::Duration::hours(1),
+            }
+        );
+        let stored = f.requests.get(f.request.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, RequestStatus::Escalated);
+        assert_eq!(stored.escalation_level, 1);
+        assert_eq!(stored.assigned_reviewers, vec![lead]);
+        assert_eq!(f.slack.get_notifications().await.len(), 1);
+        assert_eq!(f.email.get_notifications().await.len(), 1);
 
-impl Default for OversightService {
-    fn default() -> Self {
-        Self::new()
+        // The lead's hour passes: on to the administrator
+        let escalations = f.service.escalate_timed_out_requests().await.unwrap();
+        assert!(matches!(
+            &escalations[0].outcome,
+            EscalationOutcome::Escalated { level: 2, reviewers, .. } if *reviewers == vec![admin]
+        ));
+        assert_eq!(
+            f.requests
+                .get(f.request.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .expires_at,
+            f.clock.now() + chrono::Duration::minutes(10)
+        );
+        assert_eq!(f.slack.get_notifications().await.len(), 2);
+
+        let audit = f.transitions.list_by_request(f.request.id).await.unwrap();
+        assert_eq!(audit.len(), 2);
+        assert!(audit.iter().all(|t| t.actor_type == "system"));
+        assert_eq!(
+            (audit[0].from_status, audit[0].to_status),
+            (RequestStatus::Pending, RequestStatus::Escalated)
+        );
+        assert_eq!(
+            (audit[1].from_status, audit[1].to_status),
+            (RequestStatus::Escalated, RequestStatus::Escalated)
+        );
+        assert_eq!(audit[0].reason.as_deref(), Some("escalated:team_lead"));
+        assert_eq!(audit[1].reason.as_deref(), Some("escalated:org_admin"));
     }
-}
 
-/// Result of checking whether an action requires oversight.
-#[derive(Debug, Clone)]
-pub enum OversightCheckResult {
-    /// Action is allowed without oversight.
-    Allowed,
-    /// Action requires human approval.
-    RequiresApproval { request_id: Uuid, reason: String },
-    /// Action is denied by policy.
-    Denied { reason: String },
-}
+    #[tokio::test]
+    async fn test_exhausted_chain_closes_request_by_policy() {
+        use crate::escalation::{
+            EscalationChain, EscalationChainStore, EscalationOutcome, EscalationTier,
+            ExhaustedAction, EXHAUSTED_REASON,
+        };
+        use crate::repository::StateTransitionRepository;
 
-impl OversightCheckResult {
-    /// Check if the action is allowed.
-    pub fn is_allowed(&self) -> bool {
-        matches!(self, OversightCheckResult::Allowed)
-    }
+        for (action, status) in [
+            (ExhaustedAction::default(), RequestStatus::Rejected),
+            (ExhaustedAction::Approve, RequestStatus::Approved),
+        ] {
+            let f = escalation_fixture().await;
+            f.chains
+                .put(
+                    &EscalationChain::new(f.request.organization_id)
+                        .with_tier(EscalationTier::new("team_lead", vec![UserId::new()], 60))
+                        .with_exhausted_action(action),
+                )
+                .await
+                .unwrap();
+            // The only tier already had its turn and timed out
+            f.requests
+                .escalate(
+                    f.request.id,
+                    1,
+                    &[UserId::new()],
+                    f.clock.now() - chrono::Duration::minutes(1),
+                )
+                .await
+                .unwrap();
 
-    /// Check if approval is required.
-    pub fn requires_approval(&self) -> bool {
-        matches!(self, OversightCheckResult::RequiresApproval { .. })
-    }
+            let escalations = f.service.escalate_timed_out_requests().await.unwrap();
+            assert_eq!(
+                escalations[0].outcome,
+                EscalationOutcome::Exhausted { status }
+            );
+            assert_eq!(
+                f.requests.get(f.request.id).await.unwrap().unwrap().status,
+                status
+            );
+            let audit = f.transitions.list_by_request(f.request.id).await.unwrap();
+            assert_eq!(audit.len(), 1);
+            assert_eq!(audit[0].actor_type, "system");
+            assert_eq!(audit[0].to_status, status);
+            assert_eq!(audit[0].reason.as_deref(), Some(EXHAUSTED_REASON));
+            assert!(f.slack.get_notifications().await.is_empty());
 
-    /// Get the request ID if approval is required.
-    pub fn request_id(&self) -> Option<Uuid> {
-        match self {
-            OversightCheckResult::RequiresApproval { request_id, .. } => Some(*request_id),
-            _ => None,
+            // Closed requests are not picked up again
+            assert!(f
+                .service
+                .escalate_timed_out_requests()
+                .await
+                .unwrap()
+                .is_empty());
         }
     }
-}
 
-/// Result of submitting an approval.
-#[derive(Debug, Clone)]
-pub struct ApprovalSubmitResult {
-    /// Request ID.
-    pub request_id: Uuid,
-    /// New status after the approval.
-    pub new_status: RequestStatus,
-    /// Current quorum state.
-    pub quorum_result: QuorumResult,
-}
+    #[tokio::test]
+    async fn test_reviewer_escalate_decision_and_orgs_without_chain() {
+        use crate::escalation::{
+            EscalationChain, EscalationChainStore, EscalationOutcome, EscalationTier,
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::triggers::TriggerCondition;
+        let f = escalation_fixture().await;
+        let reviewer = f.request.assigned_reviewers[0];
 
-    #[tokio::test]
-    async fn test_check_action_allowed() {
-        let service = OversightService::new();
+        // Without a chain, the request times out as before and Escalate is
+        // an ordinary decision
+        let escalations = f.service.escalate_timed_out_requests().await.unwrap();
+        assert_eq!(escalations[0].outcome, EscalationOutcome::TimedOut);
+        assert_eq!(
+            f.requests.get(f.request.id).await.unwrap().unwrap().status,
+            RequestStatus::TimedOut
+        );
 
-        let result = service
-            .check_action(
-                OrganizationId::new(),
-                AgentId::new(),
-                ActionType::Transaction {
-                    amount_cents: 1000, // $10
-                    currency: "USD".to_string(),
-                },
-                "Small purchase",
+        let f = escalation_fixture().await;
+        let lead = UserId::new();
+        f.chains
+            .put(
+                &EscalationChain::new(f.request.organization_id).with_tier(EscalationTier::new(
+                    "team_lead",
+                    vec![lead],
+                    600,
+                )),
             )
             .await
             .unwrap();
+        f.clock.set(chrono::Utc::now());
+        let result = f
+            .service
+            .submit_approval(f.request.id, reviewer, ApprovalDecision::Escalate, None)
+            .await
+            .unwrap();
+        assert_eq!(result.new_status, RequestStatus::Escalated);
+        let stored = f.requests.get(f.request.id).await.unwrap().unwrap();
+        assert_eq!(stored.assigned_reviewers, vec![lead]);
 
-        assert!(result.is_allowed());
+        // The lead has ten minutes left, so nothing times out yet
+        assert!(f
+            .service
+            .escalate_timed_out_requests()
+            .await
+            .unwrap()
+            .is_empty());
+        let result = f
+            .service
+            .submit_approval(f.request.id, lead, ApprovalDecision::Approve, None)
+            .await
+            .unwrap();
+        assert_eq!(result.new_status, RequestStatus::Approved);
     }
 
     #[tokio::test]
-    async fn test_check_action_requires_approval() {
-        let service = OversightService::new();
+    async fn test_routed_channels_are_reused_for_escalation() {
+        use crate::channels::{ChannelType, MockChannel};
+        use crate::escalation::{
+            EscalationChain, EscalationChainStore, EscalationEngine, EscalationTier,
+            EscalationTrigger, InMemoryEscalationChainStore,
+        };
+        use crate::repository::InMemoryRequestRepository;
+        use crate::request::Priority;
+        use crate::routing::{ChannelRetryPolicy, ChannelRouter, ChannelRule};
 
-        let result = service
-            .check_action(
-                OrganizationId::new(),
-                AgentId::new(),
-                ActionType::Transaction {
-                    amount_cents: 5000000, // $50,000
-                    currency: "USD".to_string(),
+        let mut request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "deploy".to_string(),
+            },
+            "Deploy to production",
+        );
+        request.priority = Priority::Critical;
+        request.assigned_reviewers = vec![UserId::new()];
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        requests.create(&request).await.unwrap();
+        let chains = Arc::new(InMemoryEscalationChainStore::new());
+        chains
+            .put(
+                &EscalationChain::new(request.organization_id).with_tier(EscalationTier::new(
+                    "team_lead",
+                    vec![UserId::new()],
+                    3600,
+                )),
+            )
+            .await
+            .unwrap();
+        let slack = Arc::new(MockChannel::new().with_channel_type(ChannelType::Slack));
+        let sms = Arc::new(MockChannel::new().with_channel_type(ChannelType::Sms));
+        let email = Arc::new(MockChannel::new().with_channel_type(ChannelType::Email));
+        sms.set_should_fail(true).await;
+
+        let service = OversightService::new()
+            .with_request_repository(requests.clone())
+            .with_escalation(EscalationEngine::new(chains))
+            .with_channel_router(
+                ChannelRouter::new()
+                    .with_channel(slack.clone())
+                    .with_channel(sms.clone())
+                    .with_channel(email.clone())
+                    .with_rule(
+                        ChannelRule::new("critical", vec![ChannelType::Slack, ChannelType::Sms])
+                            .with_priorities([Priority::Critical]),
+                    )
+                    .with_rule(ChannelRule::new("default", vec![ChannelType::Email]).best_effort())
+                    .with_retry_policy(ChannelRetryPolicy {
+                        max_attempts: 1,
+                        ..Default::default()
+                    }),
+            );
+
+        let report = service.notify(&request).await.unwrap();
+        assert!(report.delivered);
+        assert_eq!(report.rules, vec!["critical", "default"]);
+        let stored = requests.get(request.id).await.unwrap().unwrap();
+        assert_eq!(
+            stored.notified_channels,
+            vec![ChannelType::Slack, ChannelType::Email]
+        );
+
+        // The escalation goes out on the channels that delivered, not the rules
+        sms.set_should_fail(false).await;
+        service
+            .escalate_request(
+                request.id,
+                EscalationTrigger::Requested {
+                    reviewer_id: request.assigned_reviewers[0],
                 },
-                "Large transfer",
             )
             .await
             .unwrap();
+        assert_eq!(slack.notification_count().await, 2);
+        assert_eq!(email.notification_count().await, 2);
+        assert_eq!(sms.notification_count().await, 0);
 
-        assert!(result.requires_approval());
+        let unrouted = OversightService::new();
+        assert!(matches!(
+            unrouted.notify(&request).await,
+            Err(CretoError::Configuration(_))
+        ));
     }
 
-    #[tokio::test]
-    async fn test_policy_trigger_creates_request() {
-        // Configure service with policy trigger
-        let trigger_config =
-            PolicyTriggerConfig::new().with_condition(TriggerCondition::AmountThreshold {
-                threshold_cents: 1_000_000, // $10,000
-                currency: None,
-            });
-
-        let service = OversightService::new().with_triggers(trigger_config);
+    struct WeightFixture {
+        service: OversightService,
+        weights: Arc<crate::weights::InMemoryReviewerWeightRepository>,
+        approvals: Arc<crate::repository::InMemoryApprovalRepository>,
+        cfo: UserId,
+        lead: UserId,
+        engineer: UserId,
+        request: OversightRequest,
+    }
 
-        // High-value transaction should trigger oversight
-        let action = ActionType::Transaction {
-            amount_cents: 5_000_000, // $50,000
-            currency: "USD".to_string(),
+    /// A request with a CFO weighing 3 and every other reviewer 1.
+    async fn weight_fixture(quorum: QuorumConfig) -> WeightFixture {
+        use crate::repository::{InMemoryApprovalRepository, InMemoryRequestRepository};
+        use crate::review::{InMemoryReviewerDirectory, ReviewerProfile};
+        use crate::weights::{
+            InMemoryReviewerWeightRepository, ReviewerWeight, ReviewerWeightRepository,
         };
 
-        let context = PolicyContext::default();
-        let request_id = service
-            .check_policy_trigger(OrganizationId::new(), AgentId::new(), action, context)
+        let request = OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Transaction {
+                amount_cents: 5_000_000,
+                currency: "USD".to_string(),
+            },
+            "Pay vendor invoice",
+        );
+        let org = request.organization_id;
+        let (cfo, lead, engineer) = (UserId::new(), UserId::new(), UserId::new());
+        let directory = Arc::new(InMemoryReviewerDirectory::new());
+        directory.add(org, ReviewerProfile::new(cfo, "Carol").with_roles(["cfo"]));
+        directory.add(
+            org,
+            ReviewerProfile::new(lead, "Lee").with_roles(["team_lead"]),
+        );
+        let weights = Arc::new(InMemoryReviewerWeightRepository::new());
+        weights
+            .put(&ReviewerWeight::for_role(org, "cfo", 3))
             .await
             .unwrap();
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        requests.create(&request).await.unwrap();
+        let approvals = Arc::new(InMemoryApprovalRepository::new());
 
-        assert!(request_id.is_some());
+        let mut service = OversightService::new()
+            .with_request_repository(requests)
+            .with_approval_repository(approvals.clone())
+            .with_reviewer_directory(directory)
+            .with_reviewer_weights(weights.clone());
+        service.default_quorum = quorum;
+        WeightFixture {
+            service,
+            weights,
+            approvals,
+            cfo,
+            lead,
+            engineer,
+            request,
+        }
     }
 
     #[tokio::test]
-    async fn test_policy_trigger_no_match() {
-        // Configure service with policy trigger
-        let trigger_config =
-            PolicyTriggerConfig::new().with_condition(TriggerCondition::AmountThreshold {
-                threshold_cents: 10_000_000, // $100,000
-                currency: None,
-            });
+    async fn test_mixed_weights_reach_weighted_quorum() {
+        use crate::approval::WeightedDecision;
 
-        let service = OversightService::new().with_triggers(trigger_config);
+        let f = weight_fixture(QuorumConfig::weighted(4)).await;
 
-        // Low-value transaction should not trigger
-        let action = ActionType::Transaction {
-            amount_cents: 50_000, // $500
-            currency: "USD".to_string(),
-        };
+        let first = f
+            .service
+            .submit_approval(f.request.id, f.lead, ApprovalDecision::Approve, None)
+            .await
+            .unwrap();
+        assert_eq!(first.new_status, RequestStatus::InReview);
+        match first.quorum_result {
+            QuorumResult::Pending {
+                current_weight,
+                required_weight,
+                ..
+            } => {
+                assert_eq!(current_weight, 1);
+                assert_eq!(required_weight, Some(4));
+            }
+            other => panic!("unexpected quorum result {:?}", other),
+        }
 
-        let context = PolicyContext::default();
-        let request_id = service
-            .check_policy_trigger(OrganizationId::new(), AgentId::new(), action, context)
+        let second = f
+            .service
+            .submit_approval(f.request.id, f.cfo, ApprovalDecision::Approve, None)
             .await
             .unwrap();
+        assert_eq!(second.new_status, RequestStatus::Approved);
+        match &second.quorum_result {
+            QuorumResult::Approved { total_weight, .. } => assert_eq!(*total_weight, 4),
+            other => panic!("unexpected quorum result {:?}", other),
+        }
+        let mut breakdown = second.quorum_result.breakdown().to_vec();
+        breakdown.sort_by_key(|d| d.weight);
+        assert_eq!(
+            breakdown,
+            vec![
+                WeightedDecision {
+                    reviewer_id: f.lead,
+                    decision: ApprovalDecision::Approve,
+                    weight: 1,
+                },
+                WeightedDecision {
+                    reviewer_id: f.cfo,
+                    decision: ApprovalDecision::Approve,
+                    weight: 3,
+                },
+            ]
+        );
+    }
 
-        assert!(request_id.is_none());
+    #[tokio::test]
+    async fn test_light_rejection_still_rejects() {
+        let mut quorum = QuorumConfig::weighted(4);
+        quorum.any_rejection_rejects = true;
+        let f = weight_fixture(quorum).await;
+
+        f.service
+            .submit_approval(f.request.id, f.cfo, ApprovalDecision::Approve, None)
+            .await
+            .unwrap();
+        let result = f
+            .service
+            .submit_approval(f.request.id, f.engineer, ApprovalDecision::Reject, None)
+            .await
+            .unwrap();
+        assert_eq!(result.new_status, RequestStatus::Rejected);
+        assert!(result.quorum_result.is_rejected());
     }
 
     #[tokio::test]
-    async fn test_policy_trigger_without_config() {
-        // Service without trigger configuration
-        let service = OversightService::new();
+    async fn test_weight_change_keeps_recorded_weights() {
+        use crate::repository::ApprovalRepository;
+        use crate::weights::{ReviewerWeight, ReviewerWeightRepository};
 
-        let action = ActionType::Transaction {
-            amount_cents: 50_000_000,
-            currency: "USD".to_string(),
-        };
+        let f = weight_fixture(QuorumConfig::weighted(6)).await;
+        let org = f.request.organization_id;
 
-        let context = PolicyContext::default();
-        let request_id = service
-            .check_policy_trigger(OrganizationId::new(), AgentId::new(), action, context)
+        f.service
+            .submit_approval(f.request.id, f.cfo, ApprovalDecision::Approve, None)
+            .await
+            .unwrap();
+        // The CFO's weight drops after they decided
+        f.weights
+            .put(&ReviewerWeight::for_role(org, "cfo", 1))
+            .await
+            .unwrap();
+        f.weights
+            .put(&ReviewerWeight::for_user(org, f.engineer, 2))
             .await
             .unwrap();
 
-        // Should return None because no trigger evaluator is configured
-        assert!(request_id.is_none());
+        let result = f
+            .service
+            .submit_approval(f.request.id, f.engineer, ApprovalDecision::Approve, None)
+            .await
+            .unwrap();
+        assert_eq!(result.new_status, RequestStatus::InReview);
+        match result.quorum_result {
+            QuorumResult::Pending { current_weight, .. } => assert_eq!(current_weight, 5),
+            other => panic!("unexpected quorum result {:?}", other),
+        }
+        let stored = f.approvals.list_by_request(f.request.id).await.unwrap();
+        let cfo = stored.iter().find(|a| a.reviewer_id == f.cfo).unwrap();
+        assert_eq!(cfo.weight, 3);
+    }
+
+    struct FailingAuditSink;
+
+    #[async_trait::async_trait]
+    impl AuditSink for FailingAuditSink {
+        async fn emit(&self, _event: AuditEvent) -> CretoResult<()> {
+            Err(CretoError::Internal("audit backend down".to_string()))
+        }
+    }
+
+    fn audited_service(
+        sink: Arc<dyn AuditSink>,
+    ) -> (
+        OversightService,
+        Arc<crate::repository::InMemoryRequestRepository>,
+    ) {
+        use crate::repository::{InMemoryApprovalRepository, InMemoryRequestRepository};
+
+        let requests = Arc::new(InMemoryRequestRepository::new());
+        let service = OversightService::new()
+            .with_request_repository(requests.clone())
+            .with_approval_repository(Arc::new(InMemoryApprovalRepository::new()))
+            .with_transition_repository(Arc::new(
+                crate::repository::InMemoryStateTransitionRepository::new(),
+            ))
+            .with_audit_sink(sink);
+        (service, requests)
+    }
+
+    fn audit_request() -> OversightRequest {
+        OversightRequest::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ActionType::Custom {
+                type_id: "wire".to_string(),
+            },
+            "Wire transfer",
+        )
     }
 
     #[tokio::test]
-    async fn test_trigger_with_multiple_conditions() {
-        let trigger_config = PolicyTriggerConfig::new()
-            .with_condition(TriggerCondition::AmountThreshold {
-                threshold_cents: 1_000_000,
-                currency: None,
-            })
-            .with_condition(TriggerCondition::DataSensitivity {
-                scopes: vec!["pii".to_string()],
-            });
+    async fn test_approve_flow_audit_events() {
+        use creto_common::InMemoryAuditSink;
 
-        let service = OversightService::new().with_triggers(trigger_config);
+        let sink = Arc::new(InMemoryAuditSink::new());
+        let (service, _) = audited_service(sink.clone());
+        let request = audit_request();
+        let reviewer_id = UserId::new();
 
-        // Test first condition (amount threshold)
-        let action_txn = ActionType::Transaction {
-            amount_cents: 5_000_000,
-            currency: "USD".to_string(),
-        };
-        let result1 = service
-            .check_policy_trigger(
-                OrganizationId::new(),
-                AgentId::new(),
-                action_txn,
-                PolicyContext::default(),
-            )
+        service.create_request(&request).await.unwrap();
+        let result = service
+            .submit_approval(request.id, reviewer_id, ApprovalDecision::Approve, None)
             .await
             .unwrap();
-        assert!(result1.is_some());
+        assert_eq!(result.new_status, RequestStatus::Approved);
 
-        // Test second condition (data sensitivity)
-        let action_data = ActionType::DataAccess {
-            data_type: "customer".to_string(),
-            scope: "pii".to_string(),
-        };
-        let result2 = service
-            .check_policy_trigger(
-                OrganizationId::new(),
-                AgentId::new(),
-                action_data,
-                PolicyContext::default(),
-            )
-            .await
-            .unwrap();
-        assert!(result2.is_some());
+        assert_eq!(
+            sink.actions(),
+            vec![
+                "oversight.request.created",
+                "oversight.decision.submitted",
+                "oversight.request.approved",
+            ]
+        );
+        let events = sink.events();
+        assert!(events.iter().all(|e| e.subject_id == request.id
+            && e.organization_id == request.organization_id
+            && e.outcome == AuditOutcome::Success));
+        assert_eq!(
+            events[0].actor,
+            AuditActor::Agent {
+                agent_id: request.agent_id
+            }
+        );
+        assert_eq!(
+            events[1].actor,
+            AuditActor::User {
+                user_id: reviewer_id
+            }
+        );
+        assert_eq!(events[1].detail["decision"], "approve");
+        assert_eq!(
+            events[2].actor,
+            AuditActor::User {
+                user_id: reviewer_id
+            }
+        );
     }
 
-    #[test]
-    fn test_describe_trigger() {
-        let service = OversightService::new();
+    #[tokio::test]
+    async fn test_timeout_flow_audit_events() {
+        use creto_common::InMemoryAuditSink;
 
-        let condition = TriggerCondition::AmountThreshold {
-            threshold_cents: 1_000_000,
-            currency: Some("USD".to_string()),
-        };
-        let description = service.describe_trigger(&condition);
-        assert!(description.contains("$10000.00"));
-        assert!(description.contains("USD"));
+        let sink = Arc::new(InMemoryAuditSink::new());
+        let (service, requests) = audited_service(sink.clone());
+        let mut request = audit_request();
+        request.expires_at = chrono::Utc::now() - chrono::Duration::minutes(1);
+
+        service.create_request(&request).await.unwrap();
+        assert_eq!(service.expire_timed_out_requests().await.unwrap(), 1);
+
+        assert_eq!(
+            sink.actions(),
+            vec!["oversight.request.created", "oversight.request.timed_out"]
+        );
+        assert_eq!(sink.events()[1].actor, AuditActor::System);
+        let stored = requests.get(request.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, RequestStatus::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn test_audit_sink_failure_blocks_only_in_strict_mode() {
+        let (service, requests) = audited_service(Arc::new(FailingAuditSink));
+        let request = audit_request();
+        service.create_request(&request).await.unwrap();
+        assert!(requests.get(request.id).await.unwrap().is_some());
+
+        let service = service.with_audit_mode(AuditMode::Strict);
+        let request = audit_request();
+        let err = service.create_request(&request).await.unwrap_err();
+        assert!(matches!(err, CretoError::AuditFailed(_)));
+        assert!(requests.get(request.id).await.unwrap().is_none());
     }
 }
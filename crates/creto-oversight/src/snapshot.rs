@@ -0,0 +1,169 @@
+//! Oversight section of an organization snapshot.
+//!
+//! Summarizes the organization's pending requests: how many there are per
+//! priority, and the `top_n` most urgent (highest priority, then oldest).
+//! Request descriptions are declared as free text, so they are stripped
+//! from redacted snapshots.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoResult, SectionData, SnapshotContributor, SnapshotRequest};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::repository::RequestRepository;
+use crate::request::{ActionType, OversightRequest, Priority};
+
+/// Section name used by [`OversightSnapshotContributor`].
+pub const OVERSIGHT_SECTION: &str = "oversight";
+
+/// A pending request in the oversight section.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PendingRequestSummary {
+    /// Request ID.
+    pub id: Uuid,
+    /// Agent that raised the request.
+    pub agent_id: AgentId,
+    /// Action awaiting a decision.
+    pub action_type: ActionType,
+    /// Current priority.
+    pub priority: Priority,
+    /// Description shown to reviewers (free text).
+    pub description: String,
+    /// Seconds since the request was created.
+    pub age_seconds: i64,
+    /// When the request times out.
+    pub expires_at: DateTime<Utc>,
+    /// Reviewers assigned to the request.
+    pub assigned_reviewers: usize,
+}
+
+/// The oversight section's data.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OversightSnapshot {
+    /// Pending requests.
+    pub pending_count: usize,
+    /// Pending requests per priority.
+    pub pending_by_priority: BTreeMap<Priority, usize>,
+    /// Most urgent pending requests, at most `top_n`.
+    pub most_urgent: Vec<PendingRequestSummary>,
+}
+
+impl OversightSnapshot {
+    /// Summarize `pending` as of `as_of`, keeping at most `top_n` requests.
+    pub fn from_pending(
+        mut pending: Vec<OversightRequest>,
+        as_of: DateTime<Utc>,
+        top_n: usize,
+    ) -> Self {
+        let mut pending_by_priority = BTreeMap::new();
+        for request in &pending {
+            *pending_by_priority.entry(request.priority).or_insert(0) += 1;
+        }
+        let pending_count = pending.len();
+        pending.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.created_at.cmp(&b.created_at))
+        });
+        let most_urgent = pending
+            .into_iter()
+            .take(top_n)
+            .map(|request| PendingRequestSummary {
+                id: request.id,
+                agent_id: request.agent_id,
+                action_type: request.action_type,
+                priority: request.priority,
+                description: request.description,
+                age_seconds: (as_of - request.created_at).num_seconds(),
+                expires_at: request.expires_at,
+                assigned_reviewers: request.assigned_reviewers.len(),
+            })
+            .collect();
+        Self {
+            pending_count,
+            pending_by_priority,
+            most_urgent,
+        }
+    }
+}
+
+/// Contributes the oversight section of an organization snapshot.
+pub struct OversightSnapshotContributor {
+    requests: Arc<dyn RequestRepository>,
+}
+
+impl OversightSnapshotContributor {
+    pub fn new(requests: Arc<dyn RequestRepository>) -> Self {
+        Self { requests }
+    }
+}
+
+#[async_trait]
+impl SnapshotContributor for OversightSnapshotContributor {
+    fn section(&self) -> &str {
+        OVERSIGHT_SECTION
+    }
+
+    async fn contribute(&self, request: &SnapshotRequest) -> CretoResult<SectionData> {
+        let pending = self.requests.list_pending(request.organization_id).await?;
+        let snapshot = OversightSnapshot::from_pending(pending, request.as_of, request.top_n);
+        Ok(SectionData::new(request.as_of, &snapshot)?.with_free_text(["description"]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::InMemoryRequestRepository;
+    use creto_common::{OrganizationId, SnapshotAggregator, SnapshotMode, REDACTED};
+
+    #[tokio::test]
+    async fn test_most_urgent_requests_first_and_descriptions_redacted() {
+        let repo = Arc::new(InMemoryRequestRepository::new());
+        let org = OrganizationId::new();
+        let action = ActionType::Custom {
+            type_id: "deploy".to_string(),
+        };
+        let older = OversightRequest::new(org, AgentId::new(), action.clone(), "Deploy to prod");
+        let mut newer = OversightRequest::new(org, AgentId::new(), action.clone(), "Wire funds")
+            .with_priority(Priority::Critical);
+        newer.created_at = older.created_at + chrono::Duration::seconds(1);
+        let low = OversightRequest::new(org, AgentId::new(), action.clone(), "Read logs")
+            .with_priority(Priority::Low);
+        let elsewhere = OversightRequest::new(OrganizationId::new(), AgentId::new(), action, "x");
+        for request in [&older, &newer, &low, &elsewhere] {
+            repo.create(request).await.unwrap();
+        }
+
+        let aggregator = SnapshotAggregator::new()
+            .with_top_n(2)
+            .with_contributor(Arc::new(OversightSnapshotContributor::new(repo)));
+        let snapshot = aggregator.capture(org, SnapshotMode::Full).await;
+        let data = snapshot
+            .section(OVERSIGHT_SECTION)
+            .unwrap()
+            .data
+            .as_ref()
+            .unwrap();
+        assert_eq!(data["pending_count"], 3);
+        assert_eq!(data["pending_by_priority"]["low"], 1);
+        let urgent = data["most_urgent"].as_array().unwrap();
+        assert_eq!(urgent.len(), 2);
+        assert_eq!(urgent[0]["id"], newer.id.to_string());
+        assert_eq!(urgent[1]["description"], "Deploy to prod");
+
+        let redacted = aggregator.capture(org, SnapshotMode::Redacted).await;
+        let data = redacted
+            .section(OVERSIGHT_SECTION)
+            .unwrap()
+            .data
+            .as_ref()
+            .unwrap();
+        assert_eq!(data["most_urgent"][1]["description"], REDACTED);
+        assert_eq!(data["pending_count"], 3);
+    }
+}
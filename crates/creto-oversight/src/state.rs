@@ -1,7 +1,7 @@
 //! State machine for oversight request lifecycle.
 
 use chrono::{DateTime, Utc};
-use creto_common::{CretoError, CretoResult, UserId};
+use creto_common::{AgentId, AuditActor, CretoError, CretoResult, UserId};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -92,6 +92,7 @@ impl StateMachine {
                 | (InReview, TimedOut)
                 | (InReview, Cancelled)
                 // From Escalated
+                | (Escalated, Escalated)
                 | (Escalated, Approved)
                 | (Escalated, Rejected)
                 | (Escalated, TimedOut)
@@ -153,6 +154,44 @@ pub enum Actor {
     User { user_id: UserId },
     /// Cedar policy auto-decision.
     Policy { policy_id: String },
+    /// The agent that raised the request.
+    Agent { agent_id: AgentId },
+}
+
+impl Actor {
+    /// Actor type as stored in the transition audit trail.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Actor::System => "system",
+            Actor::User { .. } => "user",
+            Actor::Policy { .. } => "policy",
+            Actor::Agent { .. } => "agent",
+        }
+    }
+
+    /// ID of the user or agent, if the actor has one.
+    pub fn id(&self) -> Option<Uuid> {
+        match self {
+            Actor::User { user_id } => Some(*user_id.as_uuid()),
+            Actor::Agent { agent_id } => Some(*agent_id.as_uuid()),
+            Actor::System | Actor::Policy { .. } => None,
+        }
+    }
+}
+
+impl From<&Actor> for AuditActor {
+    fn from(actor: &Actor) -> Self {
+        match actor {
+            Actor::System => AuditActor::System,
+            Actor::User { user_id } => AuditActor::User { user_id: *user_id },
+            Actor::Policy { policy_id } => AuditActor::Policy {
+                policy_id: policy_id.clone(),
+            },
+            Actor::Agent { agent_id } => AuditActor::Agent {
+                agent_id: *agent_id,
+            },
+        }
+    }
 }
 
 #[cfg(test)]
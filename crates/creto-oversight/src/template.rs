@@ -0,0 +1,571 @@
+//! Request templates for recurring agent actions.
+//!
+//! A [`RequestTemplate`] describes a class of action an agent performs
+//! repeatedly (a weekly vendor payment, a monthly data export): which actions
+//! it applies to, the typed fields reviewers see, and the priority, timeout,
+//! and quorum to use. Requests created from a template carry an
+//! [`AppliedTemplate`] snapshot of the version used, so later template edits
+//! never change requests that are already in flight.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::approval::QuorumConfig;
+use crate::request::{ActionType, Priority};
+use crate::triggers::ActionTypePattern;
+
+/// Errors from creating requests from templates.
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    /// The template does not exist in the organization.
+    #[error("Request template {template_id} not found")]
+    NotFound { template_id: Uuid },
+
+    /// The action is not covered by the template's action pattern.
+    #[error("Action does not match template {template_id} pattern {pattern:?}")]
+    ActionMismatch {
+        template_id: Uuid,
+        pattern: ActionTypePattern,
+    },
+
+    /// Supplied field values do not satisfy the template schema.
+    #[error(
+        "Invalid fields for template {template_id}: missing [{}], invalid [{}], unknown [{}]",
+        missing.join(", "),
+        invalid.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(", "),
+        unknown.join(", ")
+    )]
+    InvalidFields {
+        template_id: Uuid,
+        /// Required fields that were not supplied.
+        missing: Vec<String>,
+        /// Supplied fields with the wrong type.
+        invalid: Vec<InvalidField>,
+        /// Supplied fields the template does not define.
+        unknown: Vec<String>,
+    },
+
+    /// The template store failed.
+    #[error(transparent)]
+    Storage(#[from] CretoError),
+}
+
+/// A supplied field value that does not match its declared type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvalidField {
+    /// Field name.
+    pub name: String,
+    /// Type the template declares.
+    pub expected: FieldType,
+}
+
+impl std::fmt::Display for InvalidField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (expected {})", self.name, self.expected.as_str())
+    }
+}
+
+/// Value type of a template field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    /// Free text.
+    String,
+    /// Whole number.
+    Integer,
+    /// Any number.
+    Number,
+    /// True or false.
+    Boolean,
+    /// RFC 3339 timestamp.
+    Timestamp,
+}
+
+impl FieldType {
+    /// Name used in error messages.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldType::String => "string",
+            FieldType::Integer => "integer",
+            FieldType::Number => "number",
+            FieldType::Boolean => "boolean",
+            FieldType::Timestamp => "timestamp",
+        }
+    }
+
+    /// Check if a JSON value is of this type.
+    pub fn accepts(&self, value: &serde_json::Value) -> bool {
+        match self {
+            FieldType::String => value.is_string(),
+            FieldType::Integer => value.is_i64() || value.is_u64(),
+            FieldType::Number => value.is_number(),
+            FieldType::Boolean => value.is_boolean(),
+            FieldType::Timestamp => value
+                .as_str()
+                .is_some_and(|s| DateTime::parse_from_rfc3339(s).is_ok()),
+        }
+    }
+}
+
+/// A structured context field defined by a template.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateField {
+    /// Key in the supplied field values.
+    pub name: String,
+    /// Label shown to reviewers.
+    pub label: String,
+    /// Value type.
+    pub field_type: FieldType,
+    /// Whether the field must be supplied.
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl TemplateField {
+    /// Create a required field.
+    pub fn new(name: impl Into<String>, label: impl Into<String>, field_type: FieldType) -> Self {
+        Self {
+            name: name.into(),
+            label: label.into(),
+            field_type,
+            required: true,
+        }
+    }
+
+    /// Make the field optional.
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+}
+
+/// An organization-level template for a recurring class of oversight request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestTemplate {
+    /// Template ID.
+    pub id: Uuid,
+    /// Organization owning the template.
+    pub organization_id: OrganizationId,
+    /// Display name.
+    pub name: String,
+    /// Version, incremented on every update.
+    pub version: u32,
+    /// Actions this template may be used for.
+    pub action_pattern: ActionTypePattern,
+    /// Context fields agents must supply.
+    #[serde(default)]
+    pub fields: Vec<TemplateField>,
+    /// Priority of requests created from this template.
+    pub default_priority: Priority,
+    /// Timeout override; the priority's default applies when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_timeout_seconds: Option<u64>,
+    /// Quorum override; the service default applies when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumConfig>,
+    /// Reviewer group requests are routed to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewer_group: Option<String>,
+    /// When this version was written.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RequestTemplate {
+    /// Create the first version of a template.
+    pub fn new(
+        organization_id: OrganizationId,
+        name: impl Into<String>,
+        action_pattern: ActionTypePattern,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            organization_id,
+            name: name.into(),
+            version: 1,
+            action_pattern,
+            fields: Vec::new(),
+            default_priority: Priority::Normal,
+            default_timeout_seconds: None,
+            quorum: None,
+            reviewer_group: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Add a context field.
+    pub fn with_field(mut self, field: TemplateField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Set the default priority.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.default_priority = priority;
+        self
+    }
+
+    /// Set the default timeout in seconds.
+    pub fn with_timeout(mut self, seconds: u64) -> Self {
+        self.default_timeout_seconds = Some(seconds);
+        self
+    }
+
+    /// Set the quorum for requests created from this template.
+    pub fn with_quorum(mut self, quorum: QuorumConfig) -> Self {
+        self.quorum = Some(quorum);
+        self
+    }
+
+    /// Set the reviewer group.
+    pub fn with_reviewer_group(mut self, group: impl Into<String>) -> Self {
+        self.reviewer_group = Some(group.into());
+        self
+    }
+
+    /// Timeout applied to requests created from this template.
+    pub fn timeout_seconds(&self) -> u64 {
+        self.default_timeout_seconds
+            .unwrap_or_else(|| self.default_priority.default_timeout_seconds())
+    }
+
+    /// Check supplied field values against the template schema.
+    ///
+    /// Null values count as not supplied.
+    pub fn validate(
+        &self,
+        values: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(), TemplateError> {
+        let mut missing = Vec::new();
+        let mut invalid = Vec::new();
+
+        for field in &self.fields {
+            match values.get(&field.name).filter(|v| !v.is_null()) {
+                None if field.required => missing.push(field.name.clone()),
+                None => {}
+                Some(value) if !field.field_type.accepts(value) => invalid.push(InvalidField {
+                    name: field.name.clone(),
+                    expected: field.field_type,
+                }),
+                Some(_) => {}
+            }
+        }
+
+        let mut unknown: Vec<String> = values
+            .keys()
+            .filter(|name| !self.fields.iter().any(|f| &f.name == *name))
+            .cloned()
+            .collect();
+        unknown.sort();
+
+        if missing.is_empty() && invalid.is_empty() && unknown.is_empty() {
+            Ok(())
+        } else {
+            Err(TemplateError::InvalidFields {
+                template_id: self.id,
+                missing,
+                invalid,
+                unknown,
+            })
+        }
+    }
+
+    /// Validate an action and field values, and snapshot this version.
+    pub fn apply(
+        &self,
+        action: &ActionType,
+        values: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<AppliedTemplate, TemplateError> {
+        if !self.action_pattern.matches(action) {
+            return Err(TemplateError::ActionMismatch {
+                template_id: self.id,
+                pattern: self.action_pattern.clone(),
+            });
+        }
+        self.validate(values)?;
+
+        let fields = self
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let value = values.get(&field.name).filter(|v| !v.is_null())?;
+                Some(RenderedField {
+                    name: field.name.clone(),
+                    label: field.label.clone(),
+                    value: value.clone(),
+                })
+            })
+            .collect();
+
+        Ok(AppliedTemplate {
+            template_id: self.id,
+            name: self.name.clone(),
+            version: self.version,
+            fields,
+            quorum: self.quorum.clone(),
+            reviewer_group: self.reviewer_group.clone(),
+        })
+    }
+}
+
+/// The template version and field values a request was created with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedTemplate {
+    /// Template the request was created from.
+    pub template_id: Uuid,
+    /// Template name at creation time.
+    pub name: String,
+    /// Template version at creation time.
+    pub version: u32,
+    /// Supplied fields in template order.
+    pub fields: Vec<RenderedField>,
+    /// Quorum at creation time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumConfig>,
+    /// Reviewer group at creation time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewer_group: Option<String>,
+}
+
+/// A labelled field value for display to reviewers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderedField {
+    /// Field name.
+    pub name: String,
+    /// Label shown to reviewers.
+    pub label: String,
+    /// Supplied value.
+    pub value: serde_json::Value,
+}
+
+impl RenderedField {
+    /// Value formatted for display, without JSON quoting for strings.
+    pub fn display_value(&self) -> String {
+        match &self.value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Storage for organization request templates.
+#[async_trait::async_trait]
+pub trait RequestTemplateStore: Send + Sync {
+    /// Create a new template.
+    async fn create(&self, template: RequestTemplate) -> CretoResult<RequestTemplate>;
+
+    /// Get the current version of a template.
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        id: Uuid,
+    ) -> CretoResult<Option<RequestTemplate>>;
+
+    /// Replace a template's definition, bumping its version.
+    async fn update(&self, template: RequestTemplate) -> CretoResult<RequestTemplate>;
+
+    /// List an organization's templates.
+    async fn list(&self, organization_id: OrganizationId) -> CretoResult<Vec<RequestTemplate>>;
+}
+
+/// In-memory template store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryRequestTemplateStore {
+    templates: Arc<RwLock<HashMap<(OrganizationId, Uuid), RequestTemplate>>>,
+}
+
+impl InMemoryRequestTemplateStore {
+    /// Create a new in-memory template store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl RequestTemplateStore for InMemoryRequestTemplateStore {
+    async fn create(&self, template: RequestTemplate) -> CretoResult<RequestTemplate> {
+        let mut templates = self.templates.write().unwrap();
+        let key = (template.organization_id, template.id);
+        if templates.contains_key(&key) {
+            return Err(CretoError::ValidationFailed(format!(
+                "request template {} already exists",
+                template.id
+            )));
+        }
+        templates.insert(key, template.clone());
+        Ok(template)
+    }
+
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        id: Uuid,
+    ) -> CretoResult<Option<RequestTemplate>> {
+        Ok(self
+            .templates
+            .read()
+            .unwrap()
+            .get(&(organization_id, id))
+            .cloned())
+    }
+
+    async fn update(&self, mut template: RequestTemplate) -> CretoResult<RequestTemplate> {
+        let mut templates = self.templates.write().unwrap();
+        let current = templates
+            .get_mut(&(template.organization_id, template.id))
+            .ok_or_else(|| CretoError::NotFound(format!("request template {}", template.id)))?;
+        template.version = current.version + 1;
+        template.updated_at = Utc::now();
+        *current = template.clone();
+        Ok(template)
+    }
+
+    async fn list(&self, organization_id: OrganizationId) -> CretoResult<Vec<RequestTemplate>> {
+        let mut templates: Vec<RequestTemplate> = self
+            .templates
+            .read()
+            .unwrap()
+            .values()
+            .filter(|t| t.organization_id == organization_id)
+            .cloned()
+            .collect();
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn vendor_payment() -> RequestTemplate {
+        RequestTemplate::new(
+            OrganizationId::new(),
+            "Weekly vendor payment",
+            ActionTypePattern::Transaction,
+        )
+        .with_field(TemplateField::new("vendor", "Vendor", FieldType::String))
+        .with_field(TemplateField::new(
+            "amount_cents",
+            "Amount (cents)",
+            FieldType::Integer,
+        ))
+        .with_field(TemplateField::new("due_at", "Due", FieldType::Timestamp).optional())
+    }
+
+    fn values(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_validate_accepts_matching_fields() {
+        let template = vendor_payment();
+        let fields = values(json!({
+            "vendor": "Acme",
+            "amount_cents": 125000,
+            "due_at": "2024-06-01T00:00:00Z"
+        }));
+        assert!(template.validate(&fields).is_ok());
+
+        // Optional fields may be omitted or null
+        let fields = values(json!({"vendor": "Acme", "amount_cents": 1, "due_at": null}));
+        assert!(template.validate(&fields).is_ok());
+    }
+
+    #[test]
+    fn test_validate_lists_missing_invalid_and_unknown_fields() {
+        let template = vendor_payment();
+        let fields = values(json!({
+            "amount_cents": "lots",
+            "due_at": "next tuesday",
+            "memo": "rush"
+        }));
+
+        match template.validate(&fields).unwrap_err() {
+            TemplateError::InvalidFields {
+                template_id,
+                missing,
+                invalid,
+                unknown,
+            } => {
+                assert_eq!(template_id, template.id);
+                assert_eq!(missing, vec!["vendor".to_string()]);
+                assert_eq!(
+                    invalid,
+                    vec![
+                        InvalidField {
+                            name: "amount_cents".to_string(),
+                            expected: FieldType::Integer,
+                        },
+                        InvalidField {
+                            name: "due_at".to_string(),
+                            expected: FieldType::Timestamp,
+                        },
+                    ]
+                );
+                assert_eq!(unknown, vec!["memo".to_string()]);
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn test_apply_checks_action_pattern() {
+        let template = vendor_payment();
+        let fields = values(json!({"vendor": "Acme", "amount_cents": 100}));
+
+        let action = ActionType::Custom {
+            type_id: "export".to_string(),
+        };
+        assert!(matches!(
+            template.apply(&action, &fields),
+            Err(TemplateError::ActionMismatch { .. })
+        ));
+
+        let action = ActionType::Transaction {
+            amount_cents: 100,
+            currency: "USD".to_string(),
+        };
+        let applied = template.apply(&action, &fields).unwrap();
+        assert_eq!(applied.version, 1);
+        assert_eq!(applied.fields.len(), 2);
+        assert_eq!(applied.fields[0].label, "Vendor");
+        assert_eq!(applied.fields[0].display_value(), "Acme");
+        assert_eq!(applied.fields[1].display_value(), "100");
+    }
+
+    #[tokio::test]
+    async fn test_store_update_bumps_version() {
+        let store = InMemoryRequestTemplateStore::new();
+        let template = store.create(vendor_payment()).await.unwrap();
+
+        let updated = store
+            .update(template.clone().with_priority(Priority::High))
+            .await
+            .unwrap();
+        assert_eq!(updated.version, 2);
+
+        let current = store
+            .get(template.organization_id, template.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(current.version, 2);
+        assert_eq!(current.default_priority, Priority::High);
+        assert!(store
+            .get(OrganizationId::new(), template.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
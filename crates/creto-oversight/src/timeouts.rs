@@ -0,0 +1,232 @@
+//! Expiry of oversight requests that were not decided in time.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use creto_common::{CretoResult, ShutdownCoordinator};
+use tokio::task::JoinHandle;
+
+use crate::{
+    repository::RequestRepository,
+    request::RequestStatus,
+    webhooks::{DecisionPayload, WebhookDispatcher},
+};
+
+/// Mark every open request past its timeout as timed out.
+///
+/// Requests are updated one at a time. If a pass is interrupted, the
+/// requests it did not reach are still open and are picked up by the next
+/// pass. With `webhooks`, each timed out request is sent to the
+/// organization's decision webhooks. Returns the number of requests marked
+/// timed out.
+///
+/// Organizations with an escalation chain should be served by
+/// [`OversightService::escalate_timed_out_requests`](crate::service::OversightService::escalate_timed_out_requests)
+/// instead, which escalates their requests rather than timing them out.
+pub async fn expire_timed_out_requests(
+    requests: &dyn RequestRepository,
+    webhooks: Option<&Arc<WebhookDispatcher>>,
+) -> CretoResult<usize> {
+    let timed_out = requests.find_timed_out().await?;
+    let mut expired = 0;
+
+    for id in timed_out {
+        match expire_request(requests, webhooks, id).await {
+            Ok(()) => expired += 1,
+            Err(e) => tracing::warn!(request_id = %id, error = %e, "Failed to time out request"),
+        }
+    }
+
+    Ok(expired)
+}
+
+/// Mark one request timed out and send it to the decision webhooks.
+///
+/// Fails only if the status update fails; webhook failures are logged.
+pub(crate) async fn expire_request(
+    requests: &dyn RequestRepository,
+    webhooks: Option<&Arc<WebhookDispatcher>>,
+    id: uuid::Uuid,
+) -> CretoResult<()> {
+    requests.update_status(id, RequestStatus::TimedOut).await?;
+    if let Some(dispatcher) = webhooks {
+        if let Err(e) = notify_timed_out(requests, dispatcher, id).await {
+            tracing::warn!(request_id = %id, error = %e, "Failed to send timeout webhooks");
+        }
+    }
+    Ok(())
+}
+
+async fn notify_timed_out(
+    requests: &dyn RequestRepository,
+    dispatcher: &Arc<WebhookDispatcher>,
+    id: uuid::Uuid,
+) -> CretoResult<()> {
+    if let Some(request) = requests.get(id).await? {
+        let payload = DecisionPayload::new(&request, &[], None)?;
+        dispatcher.dispatch(&payload).await?;
+    }
+    Ok(())
+}
+
+/// Spawn the request timeout worker.
+///
+/// Runs [`expire_timed_out_requests`] every `interval`. The task is
+/// registered with `shutdown` and stops between passes.
+pub fn spawn_timeout_worker(
+    requests: Arc<dyn RequestRepository>,
+    webhooks: Option<Arc<WebhookDispatcher>>,
+    shutdown: &ShutdownCoordinator,
+    interval: Duration,
+) -> JoinHandle<()> {
+    shutdown.spawn("oversight.timeout_worker", move |guard| {
+        guard.run_periodic(interval, move || {
+            let requests = Arc::clone(&requests);
+            let webhooks = webhooks.clone();
+            async move {
+                match expire_timed_out_requests(requests.as_ref(), webhooks.as_ref()).await {
+                    Ok(0) => {}
+                    Ok(expired) => tracing::info!(expired, "Timed out oversight requests"),
+                    Err(e) => tracing::warn!(error = %e, "Timeout worker pass failed"),
+                }
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use creto_common::{AgentId, CretoError, OrganizationId, UserId};
+    use uuid::Uuid;
+
+    use crate::request::{OversightRequest, Priority};
+
+    /// Tracks status updates for a fixed set of timed-out request IDs.
+    #[derive(Default)]
+    struct TimedOutRequests {
+        pending: Mutex<Vec<Uuid>>,
+        failing: Option<Uuid>,
+    }
+
+    #[async_trait::async_trait]
+    impl RequestRepository for TimedOutRequests {
+        async fn create(&self, request: &OversightRequest) -> Result<Uuid, CretoError> {
+            Ok(request.id)
+        }
+
+        async fn get(&self, _id: Uuid) -> Result<Option<OversightRequest>, CretoError> {
+            Ok(None)
+        }
+
+        async fn update_status(&self, id: Uuid, status: RequestStatus) -> Result<(), CretoError> {
+            assert_eq!(status, RequestStatus::TimedOut);
+            if self.failing == Some(id) {
+                return Err(CretoError::Database("connection reset".to_string()));
+            }
+            self.pending.lock().unwrap().retain(|p| *p != id);
+            Ok(())
+        }
+
+        async fn update_status_if(
+            &self,
+            _id: Uuid,
+            _expected: RequestStatus,
+            _status: RequestStatus,
+        ) -> Result<bool, CretoError> {
+            unreachable!("timeouts update unconditionally")
+        }
+
+        async fn update_priority(&self, _id: Uuid, _priority: Priority) -> Result<(), CretoError> {
+            Ok(())
+        }
+
+        async fn list_open(&self) -> Result<Vec<OversightRequest>, CretoError> {
+            Ok(Vec::new())
+        }
+
+        async fn list_pending(
+            &self,
+            _org_id: OrganizationId,
+        ) -> Result<Vec<OversightRequest>, CretoError> {
+            Ok(Vec::new())
+        }
+
+        async fn list_by_agent(
+            &self,
+            _agent_id: AgentId,
+            _limit: i64,
+        ) -> Result<Vec<OversightRequest>, CretoError> {
+            Ok(Vec::new())
+        }
+
+        async fn escalate(
+            &self,
+            _id: Uuid,
+            _level: u32,
+            _reviewers: &[UserId],
+            _expires_at: chrono::DateTime<chrono::Utc>,
+        ) -> Result<(), CretoError> {
+            unreachable!("timeouts never escalate")
+        }
+
+        async fn record_reminder(
+            &self,
+            _id: Uuid,
+            _at: chrono::DateTime<chrono::Utc>,
+        ) -> Result<u32, CretoError> {
+            unreachable!("timeouts never remind")
+        }
+
+        async fn set_notified_channels(
+            &self,
+            _id: Uuid,
+            _channels: &[crate::channels::ChannelType],
+        ) -> Result<(), CretoError> {
+            unreachable!("timeouts never notify")
+        }
+
+        async fn find_timed_out(&self) -> Result<Vec<Uuid>, CretoError> {
+            Ok(self.pending.lock().unwrap().clone())
+        }
+
+        async fn find_by_correlation(
+            &self,
+            _correlation_id: Uuid,
+        ) -> Result<Vec<OversightRequest>, CretoError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_updates_are_retried_next_pass() {
+        let failing = Uuid::new_v4();
+        let requests = TimedOutRequests {
+            pending: Mutex::new(vec![Uuid::new_v4(), failing, Uuid::new_v4()]),
+            failing: Some(failing),
+        };
+
+        assert_eq!(expire_timed_out_requests(&requests, None).await.unwrap(), 2);
+        assert_eq!(requests.find_timed_out().await.unwrap(), vec![failing]);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_worker_drains_on_shutdown() {
+        let requests = Arc::new(TimedOutRequests {
+            pending: Mutex::new(vec![Uuid::new_v4()]),
+            failing: None,
+        });
+        let shutdown = ShutdownCoordinator::new();
+        spawn_timeout_worker(requests.clone(), None, &shutdown, Duration::from_millis(5));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        let report = shutdown.shutdown(Duration::from_secs(1)).await;
+        assert_eq!(
+            report.completed,
+            vec!["oversight.timeout_worker".to_string()]
+        );
+        assert!(requests.pending.lock().unwrap().is_empty());
+    }
+}
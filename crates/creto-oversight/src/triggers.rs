@@ -10,6 +10,10 @@ use crate::{
     request::{ActionType, Priority},
 };
 
+/// [`PolicyContext::attributes`] key holding a usage anomaly's details for
+/// [`TriggerCondition::UsageAnomaly`].
+pub const USAGE_ANOMALY_ATTRIBUTE: &str = "usage_anomaly";
+
 /// Configuration for policy-based triggers.
 ///
 /// Defines rules for when oversight requests should be automatically created
@@ -154,6 +158,12 @@ pub enum TriggerCondition {
         /// Risk levels that trigger oversight.
         levels: Vec<String>,
     },
+
+    /// Trigger based on a usage spike reported in the context attributes.
+    UsageAnomaly {
+        /// Minimum usage multiple over the agent's baseline.
+        min_multiple: f64,
+    },
 }
 
 /// Pattern matching for action types.
@@ -176,6 +186,26 @@ pub enum ActionTypePattern {
     Any,
 }
 
+impl ActionTypePattern {
+    /// Check if an action matches this pattern.
+    pub fn matches(&self, action: &ActionType) -> bool {
+        match self {
+            ActionTypePattern::Transaction => matches!(action, ActionType::Transaction { .. }),
+            ActionTypePattern::DataAccess => matches!(action, ActionType::DataAccess { .. }),
+            ActionTypePattern::ExternalApi => matches!(action, ActionType::ExternalApi { .. }),
+            ActionTypePattern::CodeExecution => matches!(action, ActionType::CodeExecution { .. }),
+            ActionTypePattern::Communication => matches!(action, ActionType::Communication { .. }),
+            ActionTypePattern::Custom { type_id } => match action {
+                ActionType::Custom {
+                    type_id: action_type_id,
+                } => action_type_id == type_id,
+                _ => false,
+            },
+            ActionTypePattern::Any => true,
+        }
+    }
+}
+
 /// Trust level threshold for agent tier conditions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -232,7 +262,7 @@ impl PolicyEvaluator {
                 currency,
             } => self.check_amount_threshold(action, *threshold_cents, currency.as_deref()),
 
-            TriggerCondition::ActionType { pattern } => self.check_action_type(action, pattern),
+            TriggerCondition::ActionType { pattern } => pattern.matches(action),
 
             TriggerCondition::AgentTier { min_trust_level } => {
                 self.check_trust_level(context, *min_trust_level)
@@ -257,6 +287,13 @@ impl PolicyEvaluator {
             }
 
             TriggerCondition::RiskLevel { levels } => self.check_risk_level(action, levels),
+
+            TriggerCondition::UsageAnomaly { min_multiple } => context
+                .attributes
+                .get(USAGE_ANOMALY_ATTRIBUTE)
+                .and_then(|anomaly| anomaly.get("multiple"))
+                .and_then(|multiple| multiple.as_f64())
+                .is_some_and(|multiple| multiple >= *min_multiple),
         }
     }
 
@@ -282,24 +319,6 @@ impl PolicyEvaluator {
         }
     }
 
-    /// Check if action matches the pattern.
-    fn check_action_type(&self, action: &ActionType, pattern: &ActionTypePattern) -> bool {
-        match pattern {
-            ActionTypePattern::Transaction => matches!(action, ActionType::Transaction { .. }),
-            ActionTypePattern::DataAccess => matches!(action, ActionType::DataAccess { .. }),
-            ActionTypePattern::ExternalApi => matches!(action, ActionType::ExternalApi { .. }),
-            ActionTypePattern::CodeExecution => matches!(action, ActionType::CodeExecution { .. }),
-            ActionTypePattern::Communication => matches!(action, ActionType::Communication { .. }),
-            ActionTypePattern::Custom { type_id } => match action {
-                ActionType::Custom {
-                    type_id: action_type_id,
-                } => action_type_id == type_id,
-                _ => false,
-            },
-            ActionTypePattern::Any => true,
-        }
-    }
-
     /// Check if agent trust level requires oversight.
     fn check_trust_level(&self, context: &PolicyContext, min_level: TrustLevelThreshold) -> bool {
         use crate::policy::TrustLevel;
@@ -406,6 +425,7 @@ impl PolicyEvaluator {
                     Priority::Normal
                 }
             }
+            TriggerCondition::UsageAnomaly { .. } => Priority::High,
             _ => self.config.default_priority,
         }
     }
@@ -440,6 +460,9 @@ impl PolicyEvaluator {
             TriggerCondition::RiskLevel { .. } => {
                 vec!["security_team".to_string(), "engineering_lead".to_string()]
             }
+            TriggerCondition::UsageAnomaly { .. } => {
+                vec!["billing_admin".to_string(), "security_team".to_string()]
+            }
             _ => Vec::new(),
         }
     }
@@ -683,6 +706,27 @@ mod tests {
         assert!(evaluator.evaluate(&action, &context_low).is_none());
     }
 
+    #[test]
+    fn test_usage_anomaly_trigger() {
+        let config = PolicyTriggerConfig::new()
+            .with_condition(TriggerCondition::UsageAnomaly { min_multiple: 10.0 });
+        let evaluator = PolicyEvaluator::new(config);
+        let action = ActionType::Custom {
+            type_id: "usage_anomaly".to_string(),
+        };
+        let context_with = |multiple: f64| PolicyContext {
+            attributes: serde_json::json!({ USAGE_ANOMALY_ATTRIBUTE: { "multiple": multiple } }),
+            ..Default::default()
+        };
+
+        let matched = evaluator.evaluate(&action, &context_with(20.0)).unwrap();
+        assert_eq!(matched.priority, Priority::High);
+        assert!(evaluator.evaluate(&action, &context_with(6.0)).is_none());
+        assert!(evaluator
+            .evaluate(&action, &PolicyContext::default())
+            .is_none());
+    }
+
     #[test]
     fn test_delegation_depth_trigger() {
         let config = PolicyTriggerConfig::new()
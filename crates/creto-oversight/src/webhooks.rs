@@ -0,0 +1,1350 @@
+//! Outbound decision webhooks.
+//!
+//! Organizations register [`WebhookEndpoint`]s to be told when an oversight
+//! request reaches a terminal status (approved, rejected, timed out or
+//! cancelled), so ticketing or ERP systems can react without polling.
+//!
+//! Each delivery POSTs a JSON [`DecisionPayload`] signed with the endpoint
+//! secret:
+//!
+//! ```text
+//! X-Creto-Timestamp: <unix seconds>
+//! X-Creto-Signature: v1=<hex HMAC-SHA256(secret, "<timestamp>.<body>")>
+//! ```
+//!
+//! Receivers recompute the signature with [`verify_signature`] and should
+//! reject timestamps outside a few minutes of their own clock.
+//!
+//! Deliveries run in the background with exponential backoff. Consecutive
+//! failed attempts against an endpoint open its circuit; while it is open,
+//! new deliveries are logged as [`DeliveryState::CircuitOpen`] without
+//! contacting the endpoint, and after the open period a single trial delivery
+//! is let through. Every attempt is written to the [`WebhookDeliveryLog`], and
+//! [`WebhookDispatcher::redeliver`] re-sends a logged delivery.
+//!
+//! Background deliveries are registered with the [`ShutdownCoordinator`] when
+//! one is attached: on shutdown they stop retrying and stay pending, and
+//! [`WebhookDispatcher::spawn_pending_sweep`] picks up pending deliveries
+//! left behind by a restart.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{
+    hmac_sha256, verify_hmac_sha256, CretoError, CretoResult, OrganizationId, ShutdownCoordinator,
+    ShutdownGuard,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::{
+    approval::{Approval, ApprovalDecision, QuorumResult},
+    request::{ActionType, OversightRequest, RequestStatus},
+    triggers::ActionTypePattern,
+};
+
+/// Header carrying the signature.
+pub const SIGNATURE_HEADER: &str = "X-Creto-Signature";
+/// Header carrying the signing timestamp (Unix seconds).
+pub const TIMESTAMP_HEADER: &str = "X-Creto-Timestamp";
+/// Header carrying the delivery ID, stable across retries.
+pub const DELIVERY_HEADER: &str = "X-Creto-Delivery";
+/// Event name of decision payloads.
+pub const DECISION_EVENT: &str = "oversight.request.decided";
+
+/// Sign a payload body, returning the signature header value.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let signed = format!("{}.{}", timestamp, body);
+    format!(
+        "v1={}",
+        hex::encode(hmac_sha256(secret.as_bytes(), signed.as_bytes()))
+    )
+}
+
+/// Check a signature header value against a payload body.
+pub fn verify_signature(secret: &str, timestamp: i64, body: &str, signature: &str) -> bool {
+    let Some(expected) = signature
+        .strip_prefix("v1=")
+        .and_then(|hex_sig| hex::decode(hex_sig).ok())
+    else {
+        return false;
+    };
+    let signed = format!("{}.{}", timestamp, body);
+    verify_hmac_sha256(secret.as_bytes(), signed.as_bytes(), &expected)
+}
+
+/// Which decisions an endpoint receives. Empty lists match everything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WebhookFilter {
+    /// Terminal statuses to deliver.
+    #[serde(default)]
+    pub statuses: Vec<RequestStatus>,
+    /// Action types to deliver.
+    #[serde(default)]
+    pub action_types: Vec<ActionTypePattern>,
+}
+
+impl WebhookFilter {
+    /// Deliver decisions with this status.
+    pub fn with_status(mut self, status: RequestStatus) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    /// Deliver decisions for actions matching this pattern.
+    pub fn with_action_type(mut self, pattern: ActionTypePattern) -> Self {
+        self.action_types.push(pattern);
+        self
+    }
+
+    /// Check whether a decision passes the filter.
+    pub fn matches(&self, status: RequestStatus, action: &ActionType) -> bool {
+        (self.statuses.is_empty() || self.statuses.contains(&status))
+            && (self.action_types.is_empty() || self.action_types.iter().any(|p| p.matches(action)))
+    }
+}
+
+/// An organization's webhook registration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    /// Endpoint ID.
+    pub id: Uuid,
+    /// Owning organization.
+    pub organization_id: OrganizationId,
+    /// URL deliveries are POSTed to.
+    pub url: String,
+    /// Signing secret.
+    #[serde(skip_serializing, default)]
+    pub secret: String,
+    /// Decisions this endpoint receives.
+    pub filter: WebhookFilter,
+    /// Disabled endpoints receive nothing.
+    pub enabled: bool,
+    /// When the endpoint was registered.
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookEndpoint {
+    /// Register a URL for all of an organization's decisions.
+    pub fn new(
+        organization_id: OrganizationId,
+        url: impl Into<String>,
+        secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            organization_id,
+            url: url.into(),
+            secret: secret.into(),
+            filter: WebhookFilter::default(),
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Restrict the decisions delivered.
+    pub fn with_filter(mut self, filter: WebhookFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+/// Tally of the approvals behind a decision.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApprovalsSummary {
+    /// Approve decisions.
+    pub approve_count: u32,
+    /// Reject decisions.
+    pub reject_count: u32,
+    /// Abstentions, information requests and escalations.
+    pub other_count: u32,
+    /// The individual approvals, in decision order.
+    pub approvals: Vec<Approval>,
+}
+
+impl ApprovalsSummary {
+    /// Summarize a request's approvals.
+    pub fn from_approvals(approvals: &[Approval]) -> Self {
+        let mut summary = Self {
+            approvals: approvals.to_vec(),
+            ..Default::default()
+        };
+        summary.approvals.sort_by_key(|a| a.decided_at);
+        for approval in approvals {
+            match approval.decision {
+                ApprovalDecision::Approve => summary.approve_count += 1,
+                ApprovalDecision::Reject => summary.reject_count += 1,
+                _ => summary.other_count += 1,
+            }
+        }
+        summary
+    }
+}
+
+/// Body of a decision webhook.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionPayload {
+    /// Always [`DECISION_EVENT`].
+    pub event: String,
+    /// Terminal status reached.
+    pub status: RequestStatus,
+    /// The decided request.
+    pub request: OversightRequest,
+    /// Approvals behind the decision.
+    pub approvals: ApprovalsSummary,
+    /// Final quorum evaluation, if the decision came from reviewers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quorum: Option<QuorumResult>,
+    /// When the payload was built.
+    pub decided_at: DateTime<Utc>,
+}
+
+impl DecisionPayload {
+    /// Build the payload for a request in a terminal status.
+    ///
+    /// Fails with [`CretoError::ValidationFailed`] if the request is still
+    /// open.
+    pub fn new(
+        request: &OversightRequest,
+        approvals: &[Approval],
+        quorum: Option<QuorumResult>,
+    ) -> CretoResult<Self> {
+        if !request.status.is_terminal() {
+            return Err(CretoError::ValidationFailed(format!(
+                "Request {} is not decided ({:?})",
+                request.id, request.status
+            )));
+        }
+        Ok(Self {
+            event: DECISION_EVENT.to_string(),
+            status: request.status,
+            request: request.clone(),
+            approvals: ApprovalsSummary::from_approvals(approvals),
+            quorum,
+            decided_at: Utc::now(),
+        })
+    }
+}
+
+/// Where a delivery stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryState {
+    /// Not yet delivered; attempts may be in progress.
+    Pending,
+    /// The endpoint accepted the payload.
+    Delivered,
+    /// Every attempt failed.
+    Failed,
+    /// Skipped because the endpoint's circuit was open.
+    CircuitOpen,
+}
+
+/// One POST to an endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    /// When the attempt was made.
+    pub attempted_at: DateTime<Utc>,
+    /// HTTP status returned, if the endpoint responded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    /// Transport error, if it did not.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DeliveryAttempt {
+    /// Whether the endpoint accepted the payload.
+    pub fn succeeded(&self) -> bool {
+        self.status_code
+            .is_some_and(|code| (200..300).contains(&code))
+    }
+}
+
+/// A decision payload bound for one endpoint, with its attempt history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// Delivery ID.
+    pub id: Uuid,
+    /// Target endpoint.
+    pub endpoint_id: Uuid,
+    /// Owning organization.
+    pub organization_id: OrganizationId,
+    /// Decided request.
+    pub request_id: Uuid,
+    /// Serialized [`DecisionPayload`], sent verbatim on every attempt.
+    pub body: String,
+    /// Current state.
+    pub state: DeliveryState,
+    /// Attempts made, oldest first.
+    pub attempts: Vec<DeliveryAttempt>,
+    /// When the delivery was queued.
+    pub created_at: DateTime<Utc>,
+    /// When the delivery last changed.
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Storage for webhook endpoint registrations.
+#[async_trait]
+pub trait WebhookEndpointStore: Send + Sync {
+    /// Create or replace an endpoint.
+    async fn put(&self, endpoint: WebhookEndpoint) -> CretoResult<WebhookEndpoint>;
+
+    /// Get an endpoint.
+    async fn get(&self, id: Uuid) -> CretoResult<Option<WebhookEndpoint>>;
+
+    /// List an organization's endpoints.
+    async fn list(&self, organization_id: OrganizationId) -> CretoResult<Vec<WebhookEndpoint>>;
+
+    /// Delete an endpoint, returning whether it existed.
+    async fn delete(&self, id: Uuid) -> CretoResult<bool>;
+}
+
+/// Record of webhook deliveries and their attempts.
+#[async_trait]
+pub trait WebhookDeliveryLog: Send + Sync {
+    /// Create or replace a delivery.
+    async fn record(&self, delivery: &WebhookDelivery) -> CretoResult<()>;
+
+    /// Get a delivery.
+    async fn get(&self, id: Uuid) -> CretoResult<Option<WebhookDelivery>>;
+
+    /// List the deliveries for a request, oldest first.
+    async fn list_for_request(&self, request_id: Uuid) -> CretoResult<Vec<WebhookDelivery>>;
+
+    /// List up to `limit` pending deliveries last updated before
+    /// `updated_before`, oldest first.
+    async fn list_pending(
+        &self,
+        updated_before: DateTime<Utc>,
+        limit: usize,
+    ) -> CretoResult<Vec<WebhookDelivery>>;
+}
+
+/// In-memory endpoint store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryWebhookEndpointStore {
+    endpoints: RwLock<HashMap<Uuid, WebhookEndpoint>>,
+}
+
+impl InMemoryWebhookEndpointStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl WebhookEndpointStore for InMemoryWebhookEndpointStore {
+    async fn put(&self, endpoint: WebhookEndpoint) -> CretoResult<WebhookEndpoint> {
+        self.endpoints
+            .write()
+            .await
+            .insert(endpoint.id, endpoint.clone());
+        Ok(endpoint)
+    }
+
+    async fn get(&self, id: Uuid) -> CretoResult<Option<WebhookEndpoint>> {
+        Ok(self.endpoints.read().await.get(&id).cloned())
+    }
+
+    async fn list(&self, organization_id: OrganizationId) -> CretoResult<Vec<WebhookEndpoint>> {
+        let mut endpoints: Vec<_> = self
+            .endpoints
+            .read()
+            .await
+            .values()
+            .filter(|e| e.organization_id == organization_id)
+            .cloned()
+            .collect();
+        endpoints.sort_by_key(|e| e.created_at);
+        Ok(endpoints)
+    }
+
+    async fn delete(&self, id: Uuid) -> CretoResult<bool> {
+        Ok(self.endpoints.write().await.remove(&id).is_some())
+    }
+}
+
+/// In-memory delivery log for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryWebhookDeliveryLog {
+    deliveries: RwLock<HashMap<Uuid, WebhookDelivery>>,
+}
+
+impl InMemoryWebhookDeliveryLog {
+    /// Create an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl WebhookDeliveryLog for InMemoryWebhookDeliveryLog {
+    async fn record(&self, delivery: &WebhookDelivery) -> CretoResult<()> {
+        self.deliveries
+            .write()
+            .await
+            .insert(delivery.id, delivery.clone());
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> CretoResult<Option<WebhookDelivery>> {
+        Ok(self.deliveries.read().await.get(&id).cloned())
+    }
+
+    async fn list_for_request(&self, request_id: Uuid) -> CretoResult<Vec<WebhookDelivery>> {
+        let mut deliveries: Vec<_> = self
+            .deliveries
+            .read()
+            .await
+            .values()
+            .filter(|d| d.request_id == request_id)
+            .cloned()
+            .collect();
+        deliveries.sort_by_key(|d| d.created_at);
+        Ok(deliveries)
+    }
+
+    async fn list_pending(
+        &self,
+        updated_before: DateTime<Utc>,
+        limit: usize,
+    ) -> CretoResult<Vec<WebhookDelivery>> {
+        let mut deliveries: Vec<_> = self
+            .deliveries
+            .read()
+            .await
+            .values()
+            .filter(|d| d.state == DeliveryState::Pending && d.updated_at < updated_before)
+            .cloned()
+            .collect();
+        deliveries.sort_by_key(|d| d.created_at);
+        deliveries.truncate(limit);
+        Ok(deliveries)
+    }
+}
+
+/// Sends a signed payload to an endpoint.
+#[async_trait]
+pub trait WebhookTransport: Send + Sync {
+    /// POST `body` with `headers`, returning the HTTP status code.
+    ///
+    /// Errors are reserved for failures to get a response at all.
+    async fn post(&self, url: &str, headers: &[(&str, String)], body: &str) -> CretoResult<u16>;
+}
+
+/// HTTP transport backed by reqwest.
+#[cfg(feature = "channels")]
+pub struct HttpWebhookTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "channels")]
+impl HttpWebhookTransport {
+    /// Create a transport with a per-request timeout.
+    pub fn new(timeout: Duration) -> CretoResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| CretoError::Internal(format!("Failed to build HTTP client: {}", e)))?;
+        Ok(Self { client })
+    }
+}
+
+#[cfg(feature = "channels")]
+#[async_trait]
+impl WebhookTransport for HttpWebhookTransport {
+    async fn post(&self, url: &str, headers: &[(&str, String)], body: &str) -> CretoResult<u16> {
+        let mut request = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .body(body.to_string());
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| CretoError::Internal(format!("Webhook request failed: {}", e)))?;
+        Ok(response.status().as_u16())
+    }
+}
+
+/// Retry schedule for a delivery.
+#[derive(Debug, Clone)]
+pub struct WebhookRetryPolicy {
+    /// Attempts per delivery, including the first.
+    pub max_attempts: u32,
+    /// Wait before the second attempt; doubles for each one after.
+    pub initial_backoff: Duration,
+    /// Upper bound on the wait between attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for WebhookRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl WebhookRetryPolicy {
+    /// Wait before attempt number `attempt` (1 = first retry).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Per-endpoint circuit breaker settings.
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed attempts that open the circuit.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial delivery.
+    pub open_for: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(300),
+        }
+    }
+}
+
+/// State of an endpoint's circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Deliveries flow normally.
+    Closed,
+    /// Deliveries are skipped.
+    Open,
+    /// The open period elapsed; the next attempt decides.
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// When the half-open trial attempt was let through.
+    trial_started: Option<Instant>,
+}
+
+/// Pending deliveries picked up per sweep pass.
+const PENDING_SWEEP_BATCH: usize = 100;
+
+/// Queues, signs and delivers decision webhooks.
+pub struct WebhookDispatcher {
+    endpoints: Arc<dyn WebhookEndpointStore>,
+    deliveries: Arc<dyn WebhookDeliveryLog>,
+    transport: Arc<dyn WebhookTransport>,
+    retry: WebhookRetryPolicy,
+    breaker: CircuitBreakerConfig,
+    circuits: Mutex<HashMap<Uuid, Circuit>>,
+    shutdown: Option<Arc<ShutdownCoordinator>>,
+    /// Deliveries running in the background in this process.
+    in_flight: Mutex<HashSet<Uuid>>,
+}
+
+impl WebhookDispatcher {
+    /// Create a dispatcher with the default retry and breaker settings.
+    pub fn new(
+        endpoints: Arc<dyn WebhookEndpointStore>,
+        deliveries: Arc<dyn WebhookDeliveryLog>,
+        transport: Arc<dyn WebhookTransport>,
+    ) -> Self {
+        Self {
+            endpoints,
+            deliveries,
+            transport,
+            retry: WebhookRetryPolicy::default(),
+            breaker: CircuitBreakerConfig::default(),
+            circuits: Mutex::new(HashMap::new()),
+            shutdown: None,
+            in_flight: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Use a different retry schedule.
+    pub fn with_retry_policy(mut self, retry: WebhookRetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Use different circuit breaker settings.
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreakerConfig) -> Self {
+        self.breaker = breaker;
+        self
+    }
+
+    /// Register background deliveries with `shutdown`.
+    pub fn with_shutdown(mut self, shutdown: Arc<ShutdownCoordinator>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Get the endpoint store.
+    pub fn endpoints(&self) -> &Arc<dyn WebhookEndpointStore> {
+        &self.endpoints
+    }
+
+    /// Get the delivery log.
+    pub fn deliveries(&self) -> &Arc<dyn WebhookDeliveryLog> {
+        &self.deliveries
+    }
+
+    /// Log a pending delivery for every enabled endpoint whose filter
+    /// matches the decision.
+    pub async fn enqueue(&self, payload: &DecisionPayload) -> CretoResult<Vec<WebhookDelivery>> {
+        let endpoints = self.endpoints.list(payload.request.organization_id).await?;
+        let body = serde_json::to_string(payload)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        let mut queued = Vec::new();
+        for endpoint in endpoints.iter().filter(|e| {
+            e.enabled
+                && e.filter
+                    .matches(payload.status, &payload.request.action_type)
+        }) {
+            let now = Utc::now();
+            let delivery = WebhookDelivery {
+                id: Uuid::now_v7(),
+                endpoint_id: endpoint.id,
+                organization_id: endpoint.organization_id,
+                request_id: payload.request.id,
+                body: body.clone(),
+                state: DeliveryState::Pending,
+                attempts: Vec::new(),
+                created_at: now,
+                updated_at: now,
+            };
+            self.deliveries.record(&delivery).await?;
+            queued.push(delivery);
+        }
+        Ok(queued)
+    }
+
+    /// Queue a decision and deliver it in the background.
+    ///
+    /// Returns the IDs of the deliveries started.
+    pub async fn dispatch(self: &Arc<Self>, payload: &DecisionPayload) -> CretoResult<Vec<Uuid>> {
+        let queued = self.enqueue(payload).await?;
+        let ids: Vec<Uuid> = queued.iter().map(|d| d.id).collect();
+        for delivery in queued {
+            self.spawn_delivery(delivery);
+        }
+        Ok(ids)
+    }
+
+    /// Deliver, in the background, pending deliveries that have not been
+    /// touched for `idle_for` and are not already running in this process.
+    ///
+    /// Picks up deliveries left pending by a restart. Returns the IDs of the
+    /// deliveries started.
+    pub async fn deliver_pending(self: &Arc<Self>, idle_for: Duration) -> CretoResult<Vec<Uuid>> {
+        let cutoff = Utc::now() - chrono::Duration::from_std(idle_for).unwrap_or_default();
+        let pending = self
+            .deliveries
+            .list_pending(cutoff, PENDING_SWEEP_BATCH)
+            .await?;
+
+        let mut started = Vec::new();
+        for delivery in pending {
+            let id = delivery.id;
+            if self.spawn_delivery(delivery) {
+                started.push(id);
+            }
+        }
+        Ok(started)
+    }
+
+    /// Spawn a background task sweeping pending deliveries every `interval`,
+    /// starting immediately. The task is registered with `shutdown`.
+    ///
+    /// A delivery is only picked up once it has been idle for longer than
+    /// both `interval` and twice the maximum retry backoff, so deliveries
+    /// still retrying elsewhere are left alone.
+    pub fn spawn_pending_sweep(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let dispatcher = Arc::clone(self);
+        let idle_for = interval.max(self.retry.max_backoff.saturating_mul(2));
+        shutdown.spawn("oversight.webhook_pending_sweep", move |guard| {
+            guard.run_periodic(interval, move || {
+                let dispatcher = Arc::clone(&dispatcher);
+                async move {
+                    match dispatcher.deliver_pending(idle_for).await {
+                        Ok(started) if !started.is_empty() => {
+                            tracing::info!(
+                                count = started.len(),
+                                "Resumed pending webhook deliveries"
+                            )
+                        }
+                        Ok(_) => {}
+                        Err(e) => tracing::warn!(error = %e, "Pending webhook sweep failed"),
+                    }
+                }
+            })
+        })
+    }
+
+    /// Deliver a logged delivery now, retrying per the retry policy.
+    pub async fn deliver(&self, delivery_id: Uuid) -> CretoResult<WebhookDelivery> {
+        let delivery = self.load(delivery_id).await?;
+        self.run(delivery, false, None).await
+    }
+
+    /// Re-send a logged delivery, whatever its state.
+    ///
+    /// Administrative redelivery ignores an open circuit; a success closes
+    /// it. The original body is sent with a fresh timestamp and signature.
+    pub async fn redeliver(&self, delivery_id: Uuid) -> CretoResult<WebhookDelivery> {
+        let delivery = self.load(delivery_id).await?;
+        self.run(delivery, true, None).await
+    }
+
+    /// Get the state of an endpoint's circuit.
+    pub fn circuit_state(&self, endpoint_id: Uuid) -> CircuitState {
+        let circuits = self.circuits.lock().unwrap();
+        match circuits.get(&endpoint_id).and_then(|c| c.opened_at) {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.breaker.open_for => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Start a background delivery unless one is already running for it.
+    fn spawn_delivery(self: &Arc<Self>, delivery: WebhookDelivery) -> bool {
+        if !self.in_flight.lock().unwrap().insert(delivery.id) {
+            return false;
+        }
+        let dispatcher = Arc::clone(self);
+        let task = move |guard: Option<ShutdownGuard>| async move {
+            let id = delivery.id;
+            if let Err(e) = dispatcher.run(delivery, false, guard.as_ref()).await {
+                tracing::warn!(delivery_id = %id, error = %e, "Webhook delivery could not be logged");
+            }
+            dispatcher.in_flight.lock().unwrap().remove(&id);
+        };
+        match &self.shutdown {
+            Some(shutdown) => {
+                shutdown.spawn("oversight.webhook_delivery", |guard| task(Some(guard)));
+            }
+            None => {
+                tokio::spawn(task(None));
+            }
+        }
+        true
+    }
+
+    async fn load(&self, delivery_id: Uuid) -> CretoResult<WebhookDelivery> {
+        self.deliveries
+            .get(delivery_id)
+            .await?
+            .ok_or_else(|| CretoError::NotFound(format!("Webhook delivery {}", delivery_id)))
+    }
+
+    /// Attempt a delivery until it succeeds, runs out of attempts or meets an
+    /// open circuit. If `shutdown` fires, no further attempts are made and the
+    /// delivery is left pending for the next sweep.
+    async fn run(
+        &self,
+        mut delivery: WebhookDelivery,
+        ignore_circuit: bool,
+        shutdown: Option<&ShutdownGuard>,
+    ) -> CretoResult<WebhookDelivery> {
+        let endpoint = self
+            .endpoints
+            .get(delivery.endpoint_id)
+            .await?
+            .ok_or_else(|| {
+                CretoError::NotFound(format!("Webhook endpoint {}", delivery.endpoint_id))
+            })?;
+        delivery.state = DeliveryState::Pending;
+        let mut interrupted = false;
+
+        for attempt in 0..self.retry.max_attempts.max(1) {
+            if attempt > 0 {
+                let backoff = tokio::time::sleep(self.retry.backoff(attempt));
+                match shutdown {
+                    Some(guard) => tokio::select! {
+                        _ = guard.cancelled() => {}
+                        _ = backoff => {}
+                    },
+                    None => backoff.await,
+                }
+            }
+            if shutdown.is_some_and(|guard| guard.is_cancelled()) {
+                interrupted = true;
+                break;
+            }
+            if !ignore_circuit && !self.admit(endpoint.id) {
+                delivery.state = DeliveryState::CircuitOpen;
+                break;
+            }
+
+            let result = self.attempt(&endpoint, &delivery).await;
+            let succeeded = result.succeeded();
+            delivery.attempts.push(result);
+            delivery.updated_at = Utc::now();
+            if succeeded {
+                self.record_success(endpoint.id);
+                delivery.state = DeliveryState::Delivered;
+                break;
+            }
+            self.record_failure(endpoint.id);
+            self.deliveries.record(&delivery).await?;
+        }
+
+        if delivery.state == DeliveryState::Pending && !interrupted {
+            delivery.state = DeliveryState::Failed;
+        }
+        delivery.updated_at = Utc::now();
+        self.deliveries.record(&delivery).await?;
+
+        match delivery.state {
+            DeliveryState::Delivered => {}
+            DeliveryState::Pending => tracing::info!(
+                delivery_id = %delivery.id,
+                attempts = delivery.attempts.len(),
+                "Webhook delivery interrupted by shutdown; left pending"
+            ),
+            state => tracing::warn!(
+                delivery_id = %delivery.id,
+                endpoint_id = %endpoint.id,
+                ?state,
+                attempts = delivery.attempts.len(),
+                "Webhook delivery did not succeed"
+            ),
+        }
+        Ok(delivery)
+    }
+
+    async fn attempt(
+        &self,
+        endpoint: &WebhookEndpoint,
+        delivery: &WebhookDelivery,
+    ) -> DeliveryAttempt {
+        let attempted_at = Utc::now();
+        let timestamp = attempted_at.timestamp();
+        let headers = [
+            (DELIVERY_HEADER, delivery.id.to_string()),
+            (TIMESTAMP_HEADER, timestamp.to_string()),
+            (
+                SIGNATURE_HEADER,
+                sign_payload(&endpoint.secret, timestamp, &delivery.body),
+            ),
+        ];
+        match self
+            .transport
+            .post(&endpoint.url, &headers, &delivery.body)
+            .await
+        {
+            Ok(status_code) => DeliveryAttempt {
+                attempted_at,
+                status_code: Some(status_code),
+                error: None,
+            },
+            Err(e) => DeliveryAttempt {
+                attempted_at,
+                status_code: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+
+    /// Whether an attempt may be made against an endpoint. Once the open
+    /// period elapses only one trial attempt is admitted until it reports
+    /// back; a trial that never does is replaced after another open period.
+    fn admit(&self, endpoint_id: Uuid) -> bool {
+        let mut circuits = self.circuits.lock().unwrap();
+        let Some(circuit) = circuits.get_mut(&endpoint_id) else {
+            return true;
+        };
+        match circuit.opened_at {
+            None => true,
+            Some(opened_at) if opened_at.elapsed() < self.breaker.open_for => false,
+            Some(_) => match circuit.trial_started {
+                Some(started) if started.elapsed() < self.breaker.open_for => false,
+                _ => {
+                    circuit.trial_started = Some(Instant::now());
+                    true
+                }
+            },
+        }
+    }
+
+    fn record_success(&self, endpoint_id: Uuid) {
+        self.circuits.lock().unwrap().remove(&endpoint_id);
+    }
+
+    fn record_failure(&self, endpoint_id: Uuid) {
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = circuits.entry(endpoint_id).or_default();
+        circuit.consecutive_failures += 1;
+        if circuit.consecutive_failures >= self.breaker.failure_threshold {
+            if circuit.opened_at.is_none() {
+                tracing::warn!(%endpoint_id, "Webhook endpoint circuit opened");
+            }
+            circuit.opened_at = Some(Instant::now());
+            circuit.trial_started = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    use creto_common::{AgentId, UserId};
+
+    /// A request the transport received.
+    #[derive(Debug, Clone)]
+    struct Received {
+        url: String,
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    /// Mock endpoint replying with scripted status codes (200 once the
+    /// script runs out).
+    #[derive(Default)]
+    struct MockEndpoint {
+        replies: Mutex<VecDeque<u16>>,
+        received: Mutex<Vec<Received>>,
+        delay: Duration,
+    }
+
+    impl MockEndpoint {
+        fn replying(replies: impl IntoIterator<Item = u16>) -> Arc<Self> {
+            Self::replying_after(Duration::ZERO, replies)
+        }
+
+        /// Like [`replying`](Self::replying), taking `delay` to answer.
+        fn replying_after(delay: Duration, replies: impl IntoIterator<Item = u16>) -> Arc<Self> {
+            Arc::new(Self {
+                replies: Mutex::new(replies.into_iter().collect()),
+                received: Mutex::default(),
+                delay,
+            })
+        }
+
+        fn received(&self) -> Vec<Received> {
+            self.received.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl WebhookTransport for MockEndpoint {
+        async fn post(
+            &self,
+            url: &str,
+            headers: &[(&str, String)],
+            body: &str,
+        ) -> CretoResult<u16> {
+            self.received.lock().unwrap().push(Received {
+                url: url.to_string(),
+                headers: headers
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.clone()))
+                    .collect(),
+                body: body.to_string(),
+            });
+            if !self.delay.is_zero() {
+                tokio::time::sleep(self.delay).await;
+            }
+            Ok(self.replies.lock().unwrap().pop_front().unwrap_or(200))
+        }
+    }
+
+    fn decided(org_id: OrganizationId, status: RequestStatus) -> OversightRequest {
+        let mut request = OversightRequest::new(
+            org_id,
+            AgentId::new(),
+            ActionType::Transaction {
+                amount_cents: 250_000,
+                currency: "USD".to_string(),
+            },
+            "Wire transfer",
+        );
+        request.status = status;
+        request
+    }
+
+    async fn dispatcher_with(
+        transport: Arc<MockEndpoint>,
+        endpoints: Vec<WebhookEndpoint>,
+    ) -> WebhookDispatcher {
+        let store = Arc::new(InMemoryWebhookEndpointStore::new());
+        for endpoint in endpoints {
+            store.put(endpoint).await.unwrap();
+        }
+        WebhookDispatcher::new(
+            store,
+            Arc::new(InMemoryWebhookDeliveryLog::new()),
+            transport,
+        )
+        .with_retry_policy(WebhookRetryPolicy {
+            max_attempts: 3,
+            ..Default::default()
+        })
+        .with_circuit_breaker(CircuitBreakerConfig {
+            failure_threshold: 3,
+            open_for: Duration::from_secs(60),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_payload_is_signed_and_verifiable() {
+        let org_id = OrganizationId::new();
+        let transport = MockEndpoint::replying([]);
+        let endpoint = WebhookEndpoint::new(org_id, "https://erp.example/hooks", "s3cret");
+        let dispatcher = dispatcher_with(transport.clone(), vec![endpoint]).await;
+
+        let request = decided(org_id, RequestStatus::Approved);
+        let approvals = [Approval::new(
+            request.id,
+            UserId::new(),
+            ApprovalDecision::Approve,
+        )];
+        let quorum = QuorumResult::Approved {
+            approve_count: 1,
+            total_weight: 1,
+            breakdown: Vec::new(),
+        };
+        let payload = DecisionPayload::new(&request, &approvals, Some(quorum.clone())).unwrap();
+        let queued = dispatcher.enqueue(&payload).await.unwrap();
+        let delivery = dispatcher.deliver(queued[0].id).await.unwrap();
+        assert_eq!(delivery.state, DeliveryState::Delivered);
+
+        let received = &transport.received()[0];
+        assert_eq!(received.url, "https://erp.example/hooks");
+        let timestamp: i64 = received.headers[TIMESTAMP_HEADER].parse().unwrap();
+        let signature = &received.headers[SIGNATURE_HEADER];
+        assert!(verify_signature(
+            "s3cret",
+            timestamp,
+            &received.body,
+            signature
+        ));
+        assert!(!verify_signature(
+            "wrong",
+            timestamp,
+            &received.body,
+            signature
+        ));
+        assert!(!verify_signature(
+            "s3cret",
+            timestamp + 1,
+            &received.body,
+            signature
+        ));
+
+        let body: DecisionPayload = serde_json::from_str(&received.body).unwrap();
+        assert_eq!(body.event, DECISION_EVENT);
+        assert_eq!(body.request.id, request.id);
+        assert_eq!(body.approvals.approve_count, 1);
+        assert_eq!(body.quorum, Some(quorum));
+
+        // Open requests have no decision to send
+        let open = decided(org_id, RequestStatus::InReview);
+        assert!(DecisionPayload::new(&open, &[], None).is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_retries_then_succeeds() {
+        let org_id = OrganizationId::new();
+        let transport = MockEndpoint::replying([500, 503]);
+        let endpoint = WebhookEndpoint::new(org_id, "https://erp.example/hooks", "s3cret");
+        let dispatcher = dispatcher_with(transport.clone(), vec![endpoint]).await;
+
+        let payload =
+            DecisionPayload::new(&decided(org_id, RequestStatus::Rejected), &[], None).unwrap();
+        let queued = dispatcher.enqueue(&payload).await.unwrap();
+
+        let started = Instant::now();
+        let delivery = dispatcher.deliver(queued[0].id).await.unwrap();
+        assert_eq!(delivery.state, DeliveryState::Delivered);
+        let codes: Vec<_> = delivery.attempts.iter().map(|a| a.status_code).collect();
+        assert_eq!(codes, [Some(500), Some(503), Some(200)]);
+        // 1s then 2s of backoff
+        assert_eq!(started.elapsed(), Duration::from_secs(3));
+
+        // The same delivery ID is sent on every attempt
+        let ids: Vec<_> = transport
+            .received()
+            .iter()
+            .map(|r| r.headers[DELIVERY_HEADER].clone())
+            .collect();
+        assert!(ids.iter().all(|id| *id == delivery.id.to_string()));
+        let logged = dispatcher.deliveries().get(delivery.id).await.unwrap();
+        assert_eq!(logged.unwrap().attempts.len(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_circuit_opens_after_repeated_failures() {
+        let org_id = OrganizationId::new();
+        let transport = MockEndpoint::replying([500; 3]);
+        let endpoint = WebhookEndpoint::new(org_id, "https://erp.example/hooks", "s3cret");
+        let endpoint_id = endpoint.id;
+        let dispatcher = dispatcher_with(transport.clone(), vec![endpoint]).await;
+        let payload =
+            DecisionPayload::new(&decided(org_id, RequestStatus::Approved), &[], None).unwrap();
+
+        let first = dispatcher.enqueue(&payload).await.unwrap().remove(0);
+        let first = dispatcher.deliver(first.id).await.unwrap();
+        assert_eq!(first.state, DeliveryState::Failed);
+        assert_eq!(dispatcher.circuit_state(endpoint_id), CircuitState::Open);
+
+        // While open, deliveries are skipped without contacting the endpoint
+        let second = dispatcher.enqueue(&payload).await.unwrap().remove(0);
+        let second = dispatcher.deliver(second.id).await.unwrap();
+        assert_eq!(second.state, DeliveryState::CircuitOpen);
+        assert!(second.attempts.is_empty());
+        assert_eq!(transport.received().len(), 3);
+
+        // After the open period a trial delivery goes through and closes it
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert_eq!(
+            dispatcher.circuit_state(endpoint_id),
+            CircuitState::HalfOpen
+        );
+        let third = dispatcher.enqueue(&payload).await.unwrap().remove(0);
+        let third = dispatcher.deliver(third.id).await.unwrap();
+        assert_eq!(third.state, DeliveryState::Delivered);
+        assert_eq!(dispatcher.circuit_state(endpoint_id), CircuitState::Closed);
+
+        // Skipped deliveries can be sent by hand
+        let redelivered = dispatcher.redeliver(second.id).await.unwrap();
+        assert_eq!(redelivered.state, DeliveryState::Delivered);
+        assert_eq!(redelivered.attempts.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_half_open_circuit_admits_one_trial() {
+        let org_id = OrganizationId::new();
+        let transport = MockEndpoint::replying_after(Duration::from_secs(5), [500; 3]);
+        let endpoint = WebhookEndpoint::new(org_id, "https://erp.example/hooks", "s3cret");
+        let endpoint_id = endpoint.id;
+        let dispatcher = dispatcher_with(transport.clone(), vec![endpoint]).await;
+        let payload =
+            DecisionPayload::new(&decided(org_id, RequestStatus::Approved), &[], None).unwrap();
+
+        let failing = dispatcher.enqueue(&payload).await.unwrap().remove(0);
+        dispatcher.deliver(failing.id).await.unwrap();
+        assert_eq!(dispatcher.circuit_state(endpoint_id), CircuitState::Open);
+        tokio::time::advance(Duration::from_secs(61)).await;
+
+        // Two deliveries race for the half-open circuit; only one is sent
+        let first = dispatcher.enqueue(&payload).await.unwrap().remove(0);
+        let second = dispatcher.enqueue(&payload).await.unwrap().remove(0);
+        let (first, second) =
+            tokio::join!(dispatcher.deliver(first.id), dispatcher.deliver(second.id));
+        let mut states = [first.unwrap().state, second.unwrap().state];
+        states.sort_by_key(|s| *s as u8);
+        assert_eq!(
+            states,
+            [DeliveryState::Delivered, DeliveryState::CircuitOpen]
+        );
+        assert_eq!(transport.received().len(), 4);
+        assert_eq!(dispatcher.circuit_state(endpoint_id), CircuitState::Closed);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stale_half_open_trial_is_replaced() {
+        let org_id = OrganizationId::new();
+        let endpoint = WebhookEndpoint::new(org_id, "https://erp.example/hooks", "s3cret");
+        let endpoint_id = endpoint.id;
+        let dispatcher = dispatcher_with(MockEndpoint::replying([]), vec![endpoint]).await;
+        for _ in 0..3 {
+            dispatcher.record_failure(endpoint_id);
+        }
+        assert!(!dispatcher.admit(endpoint_id));
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert!(dispatcher.admit(endpoint_id));
+        assert!(!dispatcher.admit(endpoint_id));
+
+        // A trial that never reports back stops blocking after an open period
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert!(dispatcher.admit(endpoint_id));
+
+        // A failed trial re-opens the circuit
+        dispatcher.record_failure(endpoint_id);
+        assert_eq!(dispatcher.circuit_state(endpoint_id), CircuitState::Open);
+        assert!(!dispatcher.admit(endpoint_id));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_leaves_delivery_pending_for_sweep() {
+        let org_id = OrganizationId::new();
+        let transport = MockEndpoint::replying([500]);
+        let endpoint = WebhookEndpoint::new(org_id, "https://erp.example/hooks", "s3cret");
+        let shutdown = Arc::new(ShutdownCoordinator::new());
+        let dispatcher = Arc::new(
+            dispatcher_with(transport.clone(), vec![endpoint])
+                .await
+                .with_shutdown(shutdown.clone()),
+        );
+        let payload =
+            DecisionPayload::new(&decided(org_id, RequestStatus::Approved), &[], None).unwrap();
+
+        let id = dispatcher.dispatch(&payload).await.unwrap()[0];
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(shutdown.task_count(), 1);
+
+        // Shutdown interrupts the backoff instead of waiting it out
+        let report = shutdown.shutdown(Duration::from_millis(100)).await;
+        assert!(report.is_clean());
+        let mut delivery = dispatcher.deliveries().get(id).await.unwrap().unwrap();
+        assert_eq!(delivery.state, DeliveryState::Pending);
+        assert_eq!(delivery.attempts.len(), 1);
+
+        // After a restart the sweep resumes it, once
+        delivery.updated_at -= chrono::Duration::minutes(5);
+        dispatcher.deliveries().record(&delivery).await.unwrap();
+        let restarted = Arc::new(WebhookDispatcher::new(
+            dispatcher.endpoints().clone(),
+            dispatcher.deliveries().clone(),
+            transport.clone(),
+        ));
+        let idle_for = Duration::from_secs(60);
+        assert_eq!(restarted.deliver_pending(idle_for).await.unwrap(), [id]);
+        assert!(restarted
+            .deliver_pending(idle_for)
+            .await
+            .unwrap()
+            .is_empty());
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        let delivery = restarted.deliveries().get(id).await.unwrap().unwrap();
+        assert_eq!(delivery.state, DeliveryState::Delivered);
+        assert_eq!(delivery.attempts.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_filters_select_endpoints() {
+        let org_id = OrganizationId::new();
+        let transport = MockEndpoint::replying([]);
+        let all = WebhookEndpoint::new(org_id, "https://all.example", "a");
+        let rejections = WebhookEndpoint::new(org_id, "https://rejections.example", "b")
+            .with_filter(WebhookFilter::default().with_status(RequestStatus::Rejected));
+        let code = WebhookEndpoint::new(org_id, "https://code.example", "c").with_filter(
+            WebhookFilter::default().with_action_type(ActionTypePattern::CodeExecution),
+        );
+        let mut disabled = WebhookEndpoint::new(org_id, "https://disabled.example", "d");
+        disabled.enabled = false;
+        let other_org = WebhookEndpoint::new(OrganizationId::new(), "https://other.example", "e");
+        let (all_id, rejections_id) = (all.id, rejections.id);
+        let dispatcher = Arc::new(
+            dispatcher_with(
+                transport.clone(),
+                vec![all, rejections, code, disabled, other_org],
+            )
+            .await,
+        );
+
+        let approved =
+            DecisionPayload::new(&decided(org_id, RequestStatus::Approved), &[], None).unwrap();
+        let queued = dispatcher.enqueue(&approved).await.unwrap();
+        let targets: Vec<_> = queued.iter().map(|d| d.endpoint_id).collect();
+        assert_eq!(targets, [all_id]);
+
+        let rejected = decided(org_id, RequestStatus::Rejected);
+        let payload = DecisionPayload::new(&rejected, &[], None).unwrap();
+        let ids = dispatcher.dispatch(&payload).await.unwrap();
+        assert_eq!(ids.len(), 2);
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+        let logged = dispatcher
+            .deliveries()
+            .list_for_request(rejected.id)
+            .await
+            .unwrap();
+        let mut targets: Vec<_> = logged.iter().map(|d| d.endpoint_id).collect();
+        targets.sort();
+        let mut expected = vec![all_id, rejections_id];
+        expected.sort();
+        assert_eq!(targets, expected);
+    }
+
+    #[cfg(feature = "channels")]
+    #[tokio::test]
+    async fn test_http_transport_against_mock_server() {
+        use axum::{extract::State, http::HeaderMap, routing::post, Router};
+
+        type Captured = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+        async fn receive(
+            State(captured): State<Captured>,
+            headers: HeaderMap,
+            body: String,
+        ) -> axum::http::StatusCode {
+            captured.lock().unwrap().push((headers, body));
+            axum::http::StatusCode::NO_CONTENT
+        }
+
+        let captured: Captured = Arc::default();
+        let app = Router::new()
+            .route("/hooks", post(receive))
+            .with_state(captured.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let org_id = OrganizationId::new();
+        let store = Arc::new(InMemoryWebhookEndpointStore::new());
+        store
+            .put(WebhookEndpoint::new(
+                org_id,
+                format!("http://{}/hooks", addr),
+                "s3cret",
+            ))
+            .await
+            .unwrap();
+        let dispatcher = WebhookDispatcher::new(
+            store,
+            Arc::new(InMemoryWebhookDeliveryLog::new()),
+            Arc::new(HttpWebhookTransport::new(Duration::from_secs(5)).unwrap()),
+        );
+
+        let payload =
+            DecisionPayload::new(&decided(org_id, RequestStatus::TimedOut), &[], None).unwrap();
+        let queued = dispatcher.enqueue(&payload).await.unwrap();
+        let delivery = dispatcher.deliver(queued[0].id).await.unwrap();
+        assert_eq!(delivery.state, DeliveryState::Delivered);
+        assert_eq!(delivery.attempts[0].status_code, Some(204));
+
+        let (headers, body) = captured.lock().unwrap()[0].clone();
+        let header = |name: &str| headers[name].to_str().unwrap().to_string();
+        assert_eq!(header("content-type"), "application/json");
+        assert!(verify_signature(
+            "s3cret",
+            header(TIMESTAMP_HEADER).parse().unwrap(),
+            &body,
+            &header(SIGNATURE_HEADER),
+        ));
+    }
+}
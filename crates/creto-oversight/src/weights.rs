@@ -0,0 +1,246 @@
+//! Role-based reviewer weights for weighted quorums.
+//!
+//! Organizations give roles, or individual reviewers, a weight: a CFO's
+//! approval can count as 3 while a team lead's counts as 1. The service
+//! resolves a reviewer's weight when their decision is submitted and records
+//! it on the [`Approval`](crate::approval::Approval), so changing a weight
+//! later does not change decisions already made.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, CretoResult, OrganizationId, UserId};
+use serde::{Deserialize, Serialize};
+
+/// Weight of a reviewer with no configured weight.
+pub const DEFAULT_REVIEWER_WEIGHT: u32 = 1;
+
+/// Who a weight applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum WeightSubject {
+    /// Every reviewer holding a directory role.
+    Role(String),
+    /// One reviewer.
+    User(UserId),
+}
+
+impl WeightSubject {
+    /// Kind of subject, for storage.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WeightSubject::Role(_) => "role",
+            WeightSubject::User(_) => "user",
+        }
+    }
+
+    /// Role name or user ID, for storage.
+    pub fn id(&self) -> String {
+        match self {
+            WeightSubject::Role(role) => role.clone(),
+            WeightSubject::User(user_id) => user_id.as_uuid().to_string(),
+        }
+    }
+}
+
+/// A weight configured for a role or reviewer in an organization.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewerWeight {
+    /// Organization the weight applies in.
+    pub organization_id: OrganizationId,
+
+    /// Role or reviewer the weight applies to.
+    pub subject: WeightSubject,
+
+    /// Weight of the subject's decisions.
+    pub weight: u32,
+
+    /// When the weight was last set.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ReviewerWeight {
+    /// Weight every reviewer holding `role`.
+    pub fn for_role(organization_id: OrganizationId, role: impl Into<String>, weight: u32) -> Self {
+        Self::new(organization_id, WeightSubject::Role(role.into()), weight)
+    }
+
+    /// Weight one reviewer, overriding their roles.
+    pub fn for_user(organization_id: OrganizationId, user_id: UserId, weight: u32) -> Self {
+        Self::new(organization_id, WeightSubject::User(user_id), weight)
+    }
+
+    fn new(organization_id: OrganizationId, subject: WeightSubject, weight: u32) -> Self {
+        Self {
+            organization_id,
+            subject,
+            weight,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Check that the weight can be stored.
+    pub fn validate(&self) -> CretoResult<()> {
+        if self.weight == 0 {
+            return Err(CretoError::ValidationFailed(
+                "Reviewer weight must be at least 1".to_string(),
+            ));
+        }
+        if matches!(&self.subject, WeightSubject::Role(role) if role.trim().is_empty()) {
+            return Err(CretoError::ValidationFailed(
+                "Reviewer weight role must not be empty".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Weight of a reviewer holding `roles` among an organization's `weights`.
+///
+/// A weight set for the reviewer wins; otherwise the highest weight of their
+/// roles applies, and [`DEFAULT_REVIEWER_WEIGHT`] if none is configured.
+pub fn resolve_weight(weights: &[ReviewerWeight], reviewer_id: UserId, roles: &[String]) -> u32 {
+    let mut role_weight = None;
+    for weight in weights {
+        match &weight.subject {
+            WeightSubject::User(user_id) if *user_id == reviewer_id => return weight.weight,
+            WeightSubject::Role(role) if roles.contains(role) => {
+                role_weight = role_weight.max(Some(weight.weight));
+            }
+            _ => {}
+        }
+    }
+    role_weight.unwrap_or(DEFAULT_REVIEWER_WEIGHT)
+}
+
+/// Stores reviewer weights per organization.
+#[async_trait]
+pub trait ReviewerWeightRepository: Send + Sync {
+    /// List an organization's weights.
+    async fn list(&self, organization_id: OrganizationId) -> CretoResult<Vec<ReviewerWeight>>;
+
+    /// Create or replace the weight of a role or reviewer.
+    async fn put(&self, weight: &ReviewerWeight) -> CretoResult<()>;
+
+    /// Delete the weight of a role or reviewer, returning whether it existed.
+    async fn delete(
+        &self,
+        organization_id: OrganizationId,
+        subject: &WeightSubject,
+    ) -> CretoResult<bool>;
+
+    /// Weight of a reviewer holding `roles`, per [`resolve_weight`].
+    async fn resolve(
+        &self,
+        organization_id: OrganizationId,
+        reviewer_id: UserId,
+        roles: &[String],
+    ) -> CretoResult<u32> {
+        let weights = self.list(organization_id).await?;
+        Ok(resolve_weight(&weights, reviewer_id, roles))
+    }
+}
+
+/// In-memory reviewer weight repository for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryReviewerWeightRepository {
+    weights: RwLock<HashMap<(OrganizationId, WeightSubject), ReviewerWeight>>,
+}
+
+impl InMemoryReviewerWeightRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ReviewerWeightRepository for InMemoryReviewerWeightRepository {
+    async fn list(&self, organization_id: OrganizationId) -> CretoResult<Vec<ReviewerWeight>> {
+        Ok(self
+            .weights
+            .read()
+            .unwrap()
+            .values()
+            .filter(|w| w.organization_id == organization_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn put(&self, weight: &ReviewerWeight) -> CretoResult<()> {
+        weight.validate()?;
+        self.weights.write().unwrap().insert(
+            (weight.organization_id, weight.subject.clone()),
+            weight.clone(),
+        );
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        organization_id: OrganizationId,
+        subject: &WeightSubject,
+    ) -> CretoResult<bool> {
+        Ok(self
+            .weights
+            .write()
+            .unwrap()
+            .remove(&(organization_id, subject.clone()))
+            .is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_prefers_user_then_highest_role() {
+        let org = OrganizationId::new();
+        let (cfo, lead, engineer) = (UserId::new(), UserId::new(), UserId::new());
+        let repo = InMemoryReviewerWeightRepository::new();
+        repo.put(&ReviewerWeight::for_role(org, "cfo", 3))
+            .await
+            .unwrap();
+        repo.put(&ReviewerWeight::for_role(org, "team_lead", 2))
+            .await
+            .unwrap();
+        repo.put(&ReviewerWeight::for_user(org, lead, 5))
+            .await
+            .unwrap();
+        let roles = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            repo.resolve(org, cfo, &roles(&["team_lead", "cfo"]))
+                .await
+                .unwrap(),
+            3
+        );
+        assert_eq!(repo.resolve(org, lead, &roles(&["cfo"])).await.unwrap(), 5);
+        assert_eq!(
+            repo.resolve(org, engineer, &roles(&["engineer"]))
+                .await
+                .unwrap(),
+            DEFAULT_REVIEWER_WEIGHT
+        );
+        // Weights are per organization
+        assert_eq!(
+            repo.resolve(OrganizationId::new(), cfo, &roles(&["cfo"]))
+                .await
+                .unwrap(),
+            DEFAULT_REVIEWER_WEIGHT
+        );
+
+        assert!(repo
+            .delete(org, &WeightSubject::Role("cfo".to_string()))
+            .await
+            .unwrap());
+        assert_eq!(repo.resolve(org, cfo, &roles(&["cfo"])).await.unwrap(), 1);
+        assert!(repo
+            .put(&ReviewerWeight::for_role(org, "cfo", 0))
+            .await
+            .is_err());
+    }
+}
@@ -63,6 +63,7 @@ async fn test_email_template_generation() {
         reply_to: Some("noreply@example.com".to_string()),
         dashboard_base_url: "https://dashboard.example.com".to_string(),
         token_secret: "test-secret-key".to_string(),
+        previous_token_secrets: Vec::new(),
     };
     let channel = EmailChannel::new(config);
 
@@ -93,6 +94,7 @@ fn test_email_verification_token() {
         reply_to: Some("reply@example.com".to_string()),
         dashboard_base_url: "https://dashboard.test.com".to_string(),
         token_secret: "secret123".to_string(),
+        previous_token_secrets: Vec::new(),
     };
 
     assert!(config_with_reply.reply_to.is_some());
@@ -105,6 +107,7 @@ fn test_email_verification_token() {
         reply_to: None,
         dashboard_base_url: "https://dashboard.test.com".to_string(),
         token_secret: "secret123".to_string(),
+        previous_token_secrets: Vec::new(),
     };
 
     assert!(config_without_reply.reply_to.is_none());
@@ -195,6 +198,7 @@ async fn test_multiple_channel_coordination() {
         reply_to: None,
         dashboard_base_url: "https://dashboard.test.com".to_string(),
         token_secret: "secret".to_string(),
+        previous_token_secrets: Vec::new(),
     });
 
     let webhook = WebhookChannel::new("https://example.com/webhook");
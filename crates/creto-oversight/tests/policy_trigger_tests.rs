@@ -186,6 +186,7 @@ async fn test_context_attributes() {
             "department": "engineering",
             "location": "us-west"
         }),
+        ..Default::default()
     };
 
     let action = ActionType::Transaction {
@@ -4,7 +4,8 @@
 
 use creto_common::{AgentId, OrganizationId};
 use creto_runtime::{
-    PoolConfig, ResourceLimits, ResourceUsage, Sandbox, SandboxConfig, SandboxState, WarmPool,
+    PoolConfig, ResourceLimits, ResourceUsage, Sandbox, SandboxConfig, SandboxState,
+    TerminationReason, WarmPool,
 };
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
 use std::time::Duration;
@@ -31,7 +32,7 @@ fn bench_sandbox_creation(c: &mut Criterion) {
     // With custom config
     let custom_config = SandboxConfig {
         runtime: "python3.11".to_string(),
-        limits: ResourceLimits::generous(),
+        limits: Some(ResourceLimits::generous()),
         ..SandboxConfig::default()
     };
 
@@ -130,6 +131,8 @@ fn bench_resource_checks(c: &mut Criterion) {
         network_bytes_sent: 0,
         network_bytes_received: 0,
         connection_count: 10,
+        gpu_time_ms: 0,
+        gpu_memory_bytes: 0,
     };
 
     let usage_exceeds = ResourceUsage {
@@ -143,6 +146,8 @@ fn bench_resource_checks(c: &mut Criterion) {
         network_bytes_sent: 0,
         network_bytes_received: 0,
         connection_count: 10,
+        gpu_time_ms: 0,
+        gpu_memory_bytes: 0,
     };
 
     group.bench_function("check_within_limits_1000", |b| {
@@ -229,7 +234,9 @@ fn bench_state_transitions(c: &mut Criterion) {
                 black_box(sandbox.state);
 
                 // Running -> Terminated
-                sandbox.state = SandboxState::Terminated;
+                sandbox.state = SandboxState::Terminated {
+                    reason: TerminationReason::Requested,
+                };
                 black_box(sandbox.state);
             }
         });
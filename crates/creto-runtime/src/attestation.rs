@@ -9,7 +9,7 @@ use creto_common::{AgentId, CretoResult};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-use crate::sandbox::SandboxId;
+use crate::sandbox::{Sandbox, SandboxId};
 
 /// Platform security technologies for sandbox attestation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -159,6 +159,12 @@ pub struct AttestationPolicy {
     /// Maximum age of attestation in seconds.
     #[serde(default = "default_max_attestation_age")]
     pub max_attestation_age_seconds: u64,
+
+    /// Accept a restored checkpoint whose configuration hash changed (e.g.
+    /// limits re-validated on the restoring host). Image and initialization
+    /// hashes must still match.
+    #[serde(default)]
+    pub allow_config_drift_on_restore: bool,
 }
 
 fn default_max_attestation_age() -> u64 {
@@ -171,6 +177,7 @@ impl Default for AttestationPolicy {
             require_attestation: false,
             allowed_platforms: Vec::new(),
             max_attestation_age_seconds: default_max_attestation_age(),
+            allow_config_drift_on_restore: false,
         }
     }
 }
@@ -182,6 +189,7 @@ impl AttestationPolicy {
             require_attestation: true,
             allowed_platforms: vec![AttestationPlatform::SGX, AttestationPlatform::SEV],
             max_attestation_age_seconds: 300, // 5 minutes
+            allow_config_drift_on_restore: false,
         }
     }
 
@@ -196,15 +204,36 @@ impl AttestationPolicy {
                 AttestationPlatform::SEV,
             ],
             max_attestation_age_seconds: 3600, // 1 hour
+            allow_config_drift_on_restore: false,
         }
     }
 
+    /// Check that this policy is no weaker than `baseline`.
+    ///
+    /// It must require attestation if the baseline does, accept no platform
+    /// the baseline rejects, accept attestations no older than it does, and
+    /// allow configuration drift on restore only if the baseline does.
+    pub fn is_at_least_as_strict_as(&self, baseline: &AttestationPolicy) -> bool {
+        let platforms_ok = baseline.allowed_platforms.is_empty()
+            || (!self.allowed_platforms.is_empty()
+                && self
+                    .allowed_platforms
+                    .iter()
+                    .all(|p| baseline.allowed_platforms.contains(p)));
+
+        (self.require_attestation || !baseline.require_attestation)
+            && platforms_ok
+            && self.max_attestation_age_seconds <= baseline.max_attestation_age_seconds
+            && (!self.allow_config_drift_on_restore || baseline.allow_config_drift_on_restore)
+    }
+
     /// Create a development policy (no attestation required).
     pub fn development() -> Self {
         Self {
             require_attestation: false,
             allowed_platforms: vec![AttestationPlatform::None],
             max_attestation_age_seconds: 86400, // 24 hours
+            allow_config_drift_on_restore: false,
         }
     }
 
@@ -244,6 +273,89 @@ impl AttestationPolicy {
     }
 }
 
+/// One of the hashes an attestation measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestedHash {
+    /// Hash of the container image.
+    Image,
+    /// Hash of the sandbox configuration.
+    Config,
+    /// Hash of the initialization state.
+    Init,
+}
+
+impl fmt::Display for AttestedHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            AttestedHash::Image => "image_hash",
+            AttestedHash::Config => "config_hash",
+            AttestedHash::Init => "init_hash",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The hashes an attestation vouches for, without its signature or
+/// platform evidence.
+///
+/// Checkpoints record these so a restored sandbox can be re-attested on
+/// any host and compared against what was checkpointed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationMeasurements {
+    /// Hash of the container image (BLAKE3).
+    pub image_hash: Vec<u8>,
+    /// Hash of the sandbox configuration (BLAKE3).
+    pub config_hash: Vec<u8>,
+    /// Hash of initialization state (BLAKE3).
+    pub init_hash: Vec<u8>,
+}
+
+impl AttestationMeasurements {
+    /// Measure a sandbox as it would be attested.
+    ///
+    /// The initialization hash covers the environment and mounts the
+    /// sandbox starts with, not host-specific handles, so a sandbox
+    /// measures the same on any host.
+    pub fn of(sandbox: &Sandbox) -> Self {
+        let init = serde_json::to_vec(&(&sandbox.config.environment, &sandbox.config.mounts))
+            .unwrap_or_default();
+        Self {
+            image_hash: blake3::hash(sandbox.config.runtime.as_bytes())
+                .as_bytes()
+                .to_vec(),
+            config_hash: sandbox.config_hash(),
+            init_hash: blake3::hash(&init).as_bytes().to_vec(),
+        }
+    }
+
+    /// The first hash of `fresh` that differs from these measurements.
+    ///
+    /// A changed configuration hash is tolerated when
+    /// `allow_config_drift` is set.
+    pub fn diverged(&self, fresh: &Attestation, allow_config_drift: bool) -> Option<AttestedHash> {
+        if self.image_hash != fresh.image_hash {
+            Some(AttestedHash::Image)
+        } else if self.init_hash != fresh.init_hash {
+            Some(AttestedHash::Init)
+        } else if self.config_hash != fresh.config_hash && !allow_config_drift {
+            Some(AttestedHash::Config)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<&Attestation> for AttestationMeasurements {
+    fn from(attestation: &Attestation) -> Self {
+        Self {
+            image_hash: attestation.image_hash.clone(),
+            config_hash: attestation.config_hash.clone(),
+            init_hash: attestation.init_hash.clone(),
+        }
+    }
+}
+
 /// Trait for generating attestations.
 #[async_trait::async_trait]
 pub trait AttestationGenerator: Send + Sync {
@@ -288,6 +400,7 @@ pub struct MockAttestationProvider {
     /// Kept for future verification implementation.
     #[allow(dead_code)]
     verification_key: Vec<u8>,
+    validity: Duration,
 }
 
 impl MockAttestationProvider {
@@ -298,6 +411,7 @@ impl MockAttestationProvider {
         Self {
             signing_key: vec![0x42; 32],
             verification_key: vec![0x43; 32],
+            validity: Duration::hours(1),
         }
     }
 
@@ -306,9 +420,16 @@ impl MockAttestationProvider {
         Self {
             signing_key,
             verification_key,
+            validity: Duration::hours(1),
         }
     }
 
+    /// Set how long generated attestations stay valid (default one hour).
+    pub fn with_validity(mut self, validity: Duration) -> Self {
+        self.validity = validity;
+        self
+    }
+
     /// Sign data using mock Ed25519-style signature.
     fn sign(&self, data: &[u8]) -> Vec<u8> {
         // Mock signature: BLAKE3 hash of (signing_key || data)
@@ -350,7 +471,7 @@ impl AttestationGenerator for MockAttestationProvider {
             config_hash,
             init_hash,
             platform,
-            Duration::hours(1),
+            self.validity,
         );
 
         // Generate platform evidence for hardware platforms
@@ -462,6 +583,7 @@ mod tests {
             require_attestation: true,
             allowed_platforms: vec![AttestationPlatform::SGX, AttestationPlatform::SEV],
             max_attestation_age_seconds: 3600,
+            allow_config_drift_on_restore: false,
         };
 
         let provider = MockAttestationProvider::new();
@@ -0,0 +1,423 @@
+//! Audit event export for the runtime.
+//!
+//! Runtime components describe security-relevant activity as
+//! [`RuntimeAuditEvent`]s: sandbox lifecycle changes, executions, secret
+//! mounts and leases, attestation results and egress denials. Each event
+//! names the sandbox, agent and organization so it can be correlated with
+//! oversight and metering records, and is sent to the shared
+//! [`AuditSink`] as a [`creto_common::AuditEvent`].
+//!
+//! Events carry identifiers, statuses and secret references only; never
+//! code, output, or secret material.
+//!
+//! High-volume event types (egress denials by default) are rate limited per
+//! sandbox by an [`AuditRateLimiter`]; the number of events dropped is
+//! reported on the next event of that type that gets through.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{AgentId, AuditActor, AuditEvent, AuditOutcome, OrganizationId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::attestation::AttestationPlatform;
+use crate::execution::ExecutionStatus;
+use crate::sandbox::SandboxId;
+use crate::secrets::{LeaseId, SecretGrant};
+
+pub use creto_common::{AuditSink, InMemoryAuditSink};
+
+/// An auditable runtime event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeAuditEvent {
+    /// Organization owning the sandbox.
+    pub organization_id: OrganizationId,
+    /// Sandbox the event concerns.
+    pub sandbox_id: SandboxId,
+    /// Agent running in the sandbox.
+    pub agent_id: AgentId,
+    /// What happened.
+    pub activity: RuntimeActivity,
+}
+
+/// What an audited runtime event records.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum RuntimeActivity {
+    /// A sandbox was created or checked out of the warm pool.
+    SandboxCreated { runtime: String, from_pool: bool },
+    /// An idle sandbox was paused, or hibernated to a checkpoint.
+    SandboxPaused { hibernated: bool },
+    /// A paused or hibernated sandbox was brought back.
+    SandboxResumed { resume_ms: u64 },
+    /// A sandbox was terminated.
+    SandboxTerminated,
+    /// An execution finished. `execution_id` is absent if the execution
+    /// failed before it was assigned one.
+    ExecutionFinished {
+        execution_id: Option<Uuid>,
+        status: ExecutionStatus,
+        error_code: Option<String>,
+        duration_ms: Option<u64>,
+    },
+    /// A secret was mounted into a sandbox.
+    SecretGranted(SecretGrant),
+    /// A sandbox read a mounted secret for the first time.
+    SecretAccessed(SecretGrant),
+    /// A secret mount was removed (execution finished or sandbox terminated).
+    SecretRevoked(SecretGrant),
+    /// A mounted secret was leased from the secret provider.
+    SecretLeased {
+        secret_ref: String,
+        lease_id: LeaseId,
+        expires_at: Option<DateTime<Utc>>,
+    },
+    /// A secret lease was revoked.
+    SecretLeaseRevoked {
+        secret_ref: String,
+        lease_id: LeaseId,
+    },
+    /// An attestation was generated for a sandbox, or generation failed.
+    AttestationGenerated {
+        platform: AttestationPlatform,
+        succeeded: bool,
+    },
+    /// A sandbox's attestation was verified before an execution.
+    AttestationVerified {
+        verified: bool,
+        reason: Option<String>,
+    },
+    /// An egress request was denied by the sandbox's network policy.
+    EgressDenied {
+        destination: String,
+        matched_rule: Option<String>,
+    },
+}
+
+/// Type of a [`RuntimeActivity`], the key for rate limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeAuditKind {
+    SandboxCreated,
+    SandboxPaused,
+    SandboxResumed,
+    SandboxTerminated,
+    ExecutionFinished,
+    SecretGranted,
+    SecretAccessed,
+    SecretRevoked,
+    SecretLeased,
+    SecretLeaseRevoked,
+    AttestationGenerated,
+    AttestationVerified,
+    EgressDenied,
+}
+
+impl RuntimeAuditKind {
+    /// Action name of events of this kind, e.g. `runtime.egress.denied`.
+    pub fn action(&self) -> &'static str {
+        match self {
+            RuntimeAuditKind::SandboxCreated => "runtime.sandbox.created",
+            RuntimeAuditKind::SandboxPaused => "runtime.sandbox.paused",
+            RuntimeAuditKind::SandboxResumed => "runtime.sandbox.resumed",
+            RuntimeAuditKind::SandboxTerminated => "runtime.sandbox.terminated",
+            RuntimeAuditKind::ExecutionFinished => "runtime.execution.finished",
+            RuntimeAuditKind::SecretGranted => "runtime.secret.granted",
+            RuntimeAuditKind::SecretAccessed => "runtime.secret.accessed",
+            RuntimeAuditKind::SecretRevoked => "runtime.secret.revoked",
+            RuntimeAuditKind::SecretLeased => "runtime.secret.leased",
+            RuntimeAuditKind::SecretLeaseRevoked => "runtime.secret.lease_revoked",
+            RuntimeAuditKind::AttestationGenerated => "runtime.attestation.generated",
+            RuntimeAuditKind::AttestationVerified => "runtime.attestation.verified",
+            RuntimeAuditKind::EgressDenied => "runtime.egress.denied",
+        }
+    }
+}
+
+impl RuntimeActivity {
+    /// The activity's type.
+    pub fn kind(&self) -> RuntimeAuditKind {
+        match self {
+            RuntimeActivity::SandboxCreated { .. } => RuntimeAuditKind::SandboxCreated,
+            RuntimeActivity::SandboxPaused { .. } => RuntimeAuditKind::SandboxPaused,
+            RuntimeActivity::SandboxResumed { .. } => RuntimeAuditKind::SandboxResumed,
+            RuntimeActivity::SandboxTerminated => RuntimeAuditKind::SandboxTerminated,
+            RuntimeActivity::ExecutionFinished { .. } => RuntimeAuditKind::ExecutionFinished,
+            RuntimeActivity::SecretGranted(_) => RuntimeAuditKind::SecretGranted,
+            RuntimeActivity::SecretAccessed(_) => RuntimeAuditKind::SecretAccessed,
+            RuntimeActivity::SecretRevoked(_) => RuntimeAuditKind::SecretRevoked,
+            RuntimeActivity::SecretLeased { .. } => RuntimeAuditKind::SecretLeased,
+            RuntimeActivity::SecretLeaseRevoked { .. } => RuntimeAuditKind::SecretLeaseRevoked,
+            RuntimeActivity::AttestationGenerated { .. } => RuntimeAuditKind::AttestationGenerated,
+            RuntimeActivity::AttestationVerified { .. } => RuntimeAuditKind::AttestationVerified,
+            RuntimeActivity::EgressDenied { .. } => RuntimeAuditKind::EgressDenied,
+        }
+    }
+
+    fn outcome(&self) -> AuditOutcome {
+        match self {
+            RuntimeActivity::ExecutionFinished { status, .. }
+                if *status != ExecutionStatus::Completed =>
+            {
+                AuditOutcome::Failure
+            }
+            RuntimeActivity::AttestationGenerated {
+                succeeded: false, ..
+            } => AuditOutcome::Failure,
+            RuntimeActivity::AttestationVerified {
+                verified: false, ..
+            }
+            | RuntimeActivity::EgressDenied { .. } => AuditOutcome::Denied,
+            _ => AuditOutcome::Success,
+        }
+    }
+}
+
+impl RuntimeAuditEvent {
+    /// An event for a sandbox.
+    pub fn new(
+        organization_id: OrganizationId,
+        sandbox_id: SandboxId,
+        agent_id: AgentId,
+        activity: RuntimeActivity,
+    ) -> Self {
+        Self {
+            organization_id,
+            sandbox_id,
+            agent_id,
+            activity,
+        }
+    }
+
+    /// A secret grant event, for the grant's sandbox.
+    pub fn for_grant(grant: &SecretGrant, activity: fn(SecretGrant) -> RuntimeActivity) -> Self {
+        Self::new(
+            grant.organization_id,
+            grant.sandbox_id,
+            grant.agent_id,
+            activity(grant.clone()),
+        )
+    }
+
+    /// The event as a shared audit event at `timestamp`.
+    ///
+    /// The agent is the actor and the sandbox the subject; the detail holds
+    /// the sandbox and agent IDs and the activity's fields.
+    pub fn to_audit_event(&self, timestamp: DateTime<Utc>) -> AuditEvent {
+        let mut detail = serde_json::to_value(&self.activity).unwrap_or_default();
+        if let Some(fields) = detail.as_object_mut() {
+            fields.insert("sandbox_id".to_string(), serde_json::json!(self.sandbox_id));
+            fields.insert("agent_id".to_string(), serde_json::json!(self.agent_id));
+        }
+        AuditEvent::new(
+            AuditActor::Agent {
+                agent_id: self.agent_id,
+            },
+            self.organization_id,
+            self.sandbox_id.as_uuid(),
+            self.activity.kind().action(),
+        )
+        .with_outcome(self.activity.outcome())
+        .at(timestamp)
+        .with_detail(detail)
+    }
+}
+
+/// At most `max_events` events of a type per sandbox in each `window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuditRateLimit {
+    /// Events let through per window.
+    pub max_events: u32,
+    /// Window length.
+    pub window: Duration,
+}
+
+impl AuditRateLimit {
+    /// Create a limit.
+    pub fn new(max_events: u32, window: Duration) -> Self {
+        Self { max_events, window }
+    }
+}
+
+/// Per-type rate limits for runtime audit events.
+///
+/// By default only egress denials are limited, to 100 per sandbox per
+/// minute; every other type is always emitted.
+#[derive(Debug, Clone)]
+pub struct AuditRateLimitConfig {
+    limits: HashMap<RuntimeAuditKind, AuditRateLimit>,
+}
+
+impl Default for AuditRateLimitConfig {
+    fn default() -> Self {
+        Self::unlimited().with_limit(
+            RuntimeAuditKind::EgressDenied,
+            AuditRateLimit::new(100, Duration::minutes(1)),
+        )
+    }
+}
+
+impl AuditRateLimitConfig {
+    /// No limits; every event is emitted.
+    pub fn unlimited() -> Self {
+        Self {
+            limits: HashMap::new(),
+        }
+    }
+
+    /// Limit events of `kind`, replacing any existing limit.
+    pub fn with_limit(mut self, kind: RuntimeAuditKind, limit: AuditRateLimit) -> Self {
+        self.limits.insert(kind, limit);
+        self
+    }
+
+    /// Emit every event of `kind`.
+    pub fn without_limit(mut self, kind: RuntimeAuditKind) -> Self {
+        self.limits.remove(&kind);
+        self
+    }
+
+    /// The limit for `kind`, if any.
+    pub fn limit(&self, kind: RuntimeAuditKind) -> Option<AuditRateLimit> {
+        self.limits.get(&kind).copied()
+    }
+}
+
+/// A sandbox's current window for one event type.
+#[derive(Debug)]
+struct AuditWindow {
+    started: DateTime<Utc>,
+    emitted: u32,
+    suppressed: u64,
+}
+
+/// Applies an [`AuditRateLimitConfig`] with a fixed window per event type
+/// and sandbox, so one noisy sandbox cannot hide another's events.
+#[derive(Debug, Default)]
+pub struct AuditRateLimiter {
+    config: AuditRateLimitConfig,
+    windows: Mutex<HashMap<(RuntimeAuditKind, SandboxId), AuditWindow>>,
+}
+
+impl AuditRateLimiter {
+    /// Create a limiter.
+    pub fn new(config: AuditRateLimitConfig) -> Self {
+        Self {
+            config,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Decide whether to emit `event` at `now`.
+    ///
+    /// Returns `None` if the event should be dropped, otherwise the number
+    /// of events of its type dropped for its sandbox since the last one
+    /// emitted.
+    pub fn admit(&self, event: &RuntimeAuditEvent, now: DateTime<Utc>) -> Option<u64> {
+        let kind = event.activity.kind();
+        let Some(limit) = self.config.limit(kind) else {
+            return Some(0);
+        };
+        let mut windows = self.windows.lock().unwrap();
+        let window = windows
+            .entry((kind, event.sandbox_id))
+            .or_insert(AuditWindow {
+                started: now,
+                emitted: 0,
+                suppressed: 0,
+            });
+        if now - window.started >= limit.window {
+            window.started = now;
+            window.emitted = 0;
+        }
+        if window.emitted >= limit.max_events {
+            window.suppressed += 1;
+            return None;
+        }
+        window.emitted += 1;
+        Some(std::mem::take(&mut window.suppressed))
+    }
+
+    /// Forget a terminated sandbox's windows.
+    pub fn remove_sandbox(&self, sandbox_id: SandboxId) {
+        self.windows
+            .lock()
+            .unwrap()
+            .retain(|(_, id), _| *id != sandbox_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn egress_denied(sandbox_id: SandboxId) -> RuntimeAuditEvent {
+        RuntimeAuditEvent::new(
+            OrganizationId::new(),
+            sandbox_id,
+            AgentId::new(),
+            RuntimeActivity::EgressDenied {
+                destination: "evil.com".to_string(),
+                matched_rule: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_audit_event_carries_correlation_ids() {
+        let event = egress_denied(SandboxId::new());
+        let audit = event.to_audit_event(Utc::now());
+
+        assert_eq!(audit.action, "runtime.egress.denied");
+        assert_eq!(audit.outcome, AuditOutcome::Denied);
+        assert_eq!(audit.organization_id, event.organization_id);
+        assert_eq!(audit.subject_id, event.sandbox_id.as_uuid());
+        assert_eq!(
+            audit.actor,
+            AuditActor::Agent {
+                agent_id: event.agent_id
+            }
+        );
+        assert_eq!(audit.detail["type"], "egress_denied");
+        assert_eq!(audit.detail["destination"], "evil.com");
+        assert_eq!(
+            audit.detail["sandbox_id"],
+            serde_json::json!(event.sandbox_id)
+        );
+        assert_eq!(audit.detail["agent_id"], serde_json::json!(event.agent_id));
+    }
+
+    #[test]
+    fn test_rate_limit_per_sandbox_and_window() {
+        let limiter = AuditRateLimiter::new(AuditRateLimitConfig::unlimited().with_limit(
+            RuntimeAuditKind::EgressDenied,
+            AuditRateLimit::new(2, Duration::seconds(10)),
+        ));
+        let (noisy, quiet) = (SandboxId::new(), SandboxId::new());
+        let start = Utc::now();
+
+        assert_eq!(limiter.admit(&egress_denied(noisy), start), Some(0));
+        assert_eq!(limiter.admit(&egress_denied(noisy), start), Some(0));
+        assert_eq!(limiter.admit(&egress_denied(noisy), start), None);
+        assert_eq!(limiter.admit(&egress_denied(noisy), start), None);
+        assert_eq!(limiter.admit(&egress_denied(quiet), start), Some(0));
+
+        // The next window reports what was dropped
+        let later = start + Duration::seconds(10);
+        assert_eq!(limiter.admit(&egress_denied(noisy), later), Some(2));
+        assert_eq!(limiter.admit(&egress_denied(noisy), later), Some(0));
+
+        // Unlimited kinds always pass
+        let terminated = RuntimeAuditEvent::new(
+            OrganizationId::new(),
+            noisy,
+            AgentId::new(),
+            RuntimeActivity::SandboxTerminated,
+        );
+        for _ in 0..5 {
+            assert_eq!(limiter.admit(&terminated, later), Some(0));
+        }
+    }
+}
@@ -0,0 +1,1189 @@
+//! Brokered channels between sandboxes.
+//!
+//! Sandboxes never hold session keys. When two sandboxes are connected the
+//! runtime asks a [`SessionBroker`] for sessions between their agents and
+//! gives each sandbox a [`ChannelDescriptor`]: a local endpoint and a
+//! short-lived token. Code in the sandbox sends and receives through
+//! [`AgentChannelProxy`] at that endpoint, and the proxy encrypts and
+//! decrypts with the sessions outside the sandbox boundary.
+//!
+//! Each sandbox on a channel has one Unix socket, served by the proxy for
+//! as long as the sandbox is on any channel. Code speaks newline-delimited
+//! JSON on it ([`ChannelRequest`] in, [`ChannelResponse`] out), and can
+//! list its channels there to pick up ones opened after it started.
+//!
+//! Each direction of a channel runs on its own session initiated by the
+//! sending agent, so either sandbox can speak first.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, Weak};
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{AgentId, CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::network::{InMemoryNetworkUsageTracker, NetworkUsageTracker};
+use crate::sandbox::SandboxId;
+
+/// Identifier of an agent channel.
+pub type ChannelId = Uuid;
+
+/// Settings for brokered agent channels.
+#[derive(Debug, Clone)]
+pub struct AgentChannelConfig {
+    /// Directory holding the per-sandbox channel sockets.
+    pub socket_dir: PathBuf,
+
+    /// How long a channel token stays valid.
+    pub token_ttl: Duration,
+
+    /// Undelivered messages held per endpoint before sends are rejected.
+    pub max_queued_messages: usize,
+
+    /// Organization pairs allowed to connect their sandboxes.
+    pub cross_org_peers: HashSet<(OrganizationId, OrganizationId)>,
+}
+
+impl Default for AgentChannelConfig {
+    fn default() -> Self {
+        Self {
+            socket_dir: PathBuf::from("/run/creto/channels"),
+            token_ttl: Duration::minutes(15),
+            max_queued_messages: 256,
+            cross_org_peers: HashSet::new(),
+        }
+    }
+}
+
+impl AgentChannelConfig {
+    /// Allow sandboxes of two organizations to be connected (in either direction).
+    pub fn allow_cross_org(mut self, a: OrganizationId, b: OrganizationId) -> Self {
+        self.cross_org_peers.insert((a, b));
+        self.cross_org_peers.insert((b, a));
+        self
+    }
+
+    /// Whether sandboxes of these organizations may be connected.
+    pub fn permits(&self, a: OrganizationId, b: OrganizationId) -> bool {
+        a == b || self.cross_org_peers.contains(&(a, b))
+    }
+}
+
+/// What a sandbox is given to reach its side of a channel.
+///
+/// Carries no key material; the token only authorizes calls to the proxy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelDescriptor {
+    /// Channel this endpoint belongs to.
+    pub channel_id: ChannelId,
+
+    /// Sandbox the descriptor was issued to.
+    pub sandbox_id: SandboxId,
+
+    /// Sandbox on the other end.
+    pub peer_sandbox_id: SandboxId,
+
+    /// Agent on the other end.
+    pub peer_agent_id: AgentId,
+
+    /// Local socket the sandbox connects to, shared by all its channels.
+    pub endpoint: String,
+
+    /// Token presented on every proxy call.
+    pub token: String,
+
+    /// When the token stops being accepted.
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ChannelDescriptor {
+    /// Whether the token has expired.
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// One side of a channel: a sandbox and the agent running in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelPeer {
+    /// The sandbox.
+    pub sandbox_id: SandboxId,
+
+    /// Agent the sandbox runs as.
+    pub agent_id: AgentId,
+}
+
+/// An open channel between two sandboxes.
+#[derive(Debug, Clone)]
+pub struct AgentChannel {
+    /// Channel identifier.
+    pub id: ChannelId,
+
+    /// Descriptors issued to the two sandboxes, in the order they were given.
+    pub descriptors: [ChannelDescriptor; 2],
+
+    /// When the channel was opened.
+    pub opened_at: DateTime<Utc>,
+}
+
+/// A session on which `sender` can send to `recipient`.
+///
+/// Both agents' ends are held by the broker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BrokeredSession {
+    /// Sending agent.
+    pub sender: AgentId,
+
+    /// Receiving agent.
+    pub recipient: AgentId,
+
+    /// The sender's session ID.
+    pub sender_session: Uuid,
+
+    /// The recipient's session ID.
+    pub recipient_session: Uuid,
+}
+
+/// Establishes sessions between agents and encrypts on their behalf.
+#[async_trait]
+pub trait SessionBroker: Send + Sync {
+    /// Get the open session from `sender` to `recipient`, establishing one if needed.
+    async fn session(&self, sender: AgentId, recipient: AgentId) -> CretoResult<BrokeredSession>;
+
+    /// Encrypt a message as the session's sender. Returns the wire bytes.
+    async fn seal(&self, session: &BrokeredSession, plaintext: &[u8]) -> CretoResult<Vec<u8>>;
+
+    /// Decrypt wire bytes as the session's recipient.
+    async fn open(&self, session: &BrokeredSession, sealed: &[u8]) -> CretoResult<Vec<u8>>;
+
+    /// Close both ends of a session.
+    async fn close(&self, session: &BrokeredSession) -> CretoResult<()>;
+}
+
+/// A request on a sandbox's channel socket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ChannelRequest {
+    /// List the sandbox's channels, with current tokens.
+    Channels,
+    /// Send a message to the peer on the token's channel.
+    Send {
+        /// Channel token.
+        token: String,
+        /// Message, base64-encoded.
+        message: String,
+    },
+    /// Take the next message on the token's channel.
+    Recv {
+        /// Channel token.
+        token: String,
+    },
+}
+
+/// A response on a sandbox's channel socket, one per request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ChannelResponse {
+    /// The sandbox's channels.
+    Channels {
+        /// Descriptors, ordered by channel ID.
+        channels: Vec<ChannelDescriptor>,
+    },
+    /// The message was queued for the peer.
+    Sent,
+    /// The next message, base64-encoded, or none if nothing is waiting.
+    Message {
+        /// Message, base64-encoded.
+        message: Option<String>,
+    },
+    /// The request failed.
+    Error {
+        /// Error code, as [`CretoError::code`] gives it.
+        code: String,
+        /// Error message.
+        message: String,
+    },
+}
+
+/// Client for a sandbox's channel socket, as code in the sandbox uses it.
+pub struct ChannelClient {
+    reader: BufReader<tokio::net::unix::OwnedReadHalf>,
+    writer: tokio::net::unix::OwnedWriteHalf,
+}
+
+impl ChannelClient {
+    /// Connect to a descriptor's endpoint.
+    pub async fn connect(endpoint: &str) -> CretoResult<Self> {
+        let path = endpoint.strip_prefix("unix://").ok_or_else(|| {
+            CretoError::ValidationFailed(format!("Unsupported channel endpoint {}", endpoint))
+        })?;
+        let stream = UnixStream::connect(path)
+            .await
+            .map_err(|e| CretoError::Internal(format!("Channel socket {}: {}", path, e)))?;
+        let (reader, writer) = stream.into_split();
+        Ok(Self {
+            reader: BufReader::new(reader),
+            writer,
+        })
+    }
+
+    /// List the sandbox's channels.
+    pub async fn channels(&mut self) -> CretoResult<Vec<ChannelDescriptor>> {
+        match self.call(&ChannelRequest::Channels).await? {
+            ChannelResponse::Channels { channels } => Ok(channels),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Send a message to the peer on the token's channel.
+    pub async fn send(&mut self, token: &str, message: &[u8]) -> CretoResult<()> {
+        let request = ChannelRequest::Send {
+            token: token.to_string(),
+            message: STANDARD.encode(message),
+        };
+        match self.call(&request).await? {
+            ChannelResponse::Sent => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Take the next message on the token's channel, if any.
+    pub async fn recv(&mut self, token: &str) -> CretoResult<Option<Vec<u8>>> {
+        let request = ChannelRequest::Recv {
+            token: token.to_string(),
+        };
+        match self.call(&request).await? {
+            ChannelResponse::Message { message } => message.map(|m| decode(&m)).transpose(),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn call(&mut self, request: &ChannelRequest) -> CretoResult<ChannelResponse> {
+        let mut line = serde_json::to_vec(request)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        line.push(b'\n');
+        self.writer
+            .write_all(&line)
+            .await
+            .map_err(|e| CretoError::Internal(format!("Channel socket write: {}", e)))?;
+
+        let mut response = String::new();
+        let read = self
+            .reader
+            .read_line(&mut response)
+            .await
+            .map_err(|e| CretoError::Internal(format!("Channel socket read: {}", e)))?;
+        if read == 0 {
+            return Err(CretoError::Internal("Channel socket closed".to_string()));
+        }
+        serde_json::from_str(&response).map_err(|e| CretoError::SerializationError(e.to_string()))
+    }
+}
+
+/// A failure the proxy reported, or a response of the wrong kind.
+fn unexpected(response: ChannelResponse) -> CretoError {
+    match response {
+        ChannelResponse::Error { code, message } => {
+            CretoError::ChannelError(format!("{}: {}", code, message))
+        }
+        other => CretoError::ChannelError(format!("Unexpected response {:?}", other)),
+    }
+}
+
+fn decode(message: &str) -> CretoResult<Vec<u8>> {
+    STANDARD
+        .decode(message)
+        .map_err(|e| CretoError::SerializationError(e.to_string()))
+}
+
+/// A sandbox's side of an open channel.
+struct ChannelEndpoint {
+    member: ChannelPeer,
+    token: String,
+    expires_at: DateTime<Utc>,
+    /// Session this side sends on.
+    outbound: BrokeredSession,
+    /// Sealed messages waiting for this side.
+    inbox: VecDeque<Vec<u8>>,
+    /// Held while a message is decrypted, so it leaves the inbox only
+    /// once it has been opened.
+    receiving: Arc<tokio::sync::Mutex<()>>,
+}
+
+struct OpenChannel {
+    endpoints: [ChannelEndpoint; 2],
+}
+
+#[derive(Default)]
+struct ProxyState {
+    channels: HashMap<ChannelId, OpenChannel>,
+    /// Token to (channel, endpoint index).
+    tokens: HashMap<String, (ChannelId, usize)>,
+    /// Socket server per sandbox on a channel.
+    listeners: HashMap<SandboxId, JoinHandle<()>>,
+}
+
+impl ProxyState {
+    fn endpoint(&self, token: &str, now: DateTime<Utc>) -> CretoResult<(ChannelId, usize)> {
+        let (channel_id, index) = self.tokens.get(token).copied().ok_or_else(invalid_token)?;
+        let channel = self.channels.get(&channel_id).ok_or_else(invalid_token)?;
+        if now >= channel.endpoints[index].expires_at {
+            return Err(invalid_token());
+        }
+        Ok((channel_id, index))
+    }
+
+    fn sessions_in_use(&self) -> HashSet<BrokeredSession> {
+        self.channels
+            .values()
+            .flat_map(|c| c.endpoints.iter().map(|e| e.outbound))
+            .collect()
+    }
+
+    fn on_channel(&self, sandbox_id: SandboxId) -> bool {
+        self.channels.values().any(|c| {
+            c.endpoints
+                .iter()
+                .any(|e| e.member.sandbox_id == sandbox_id)
+        })
+    }
+}
+
+fn invalid_token() -> CretoError {
+    CretoError::Unauthorized("Invalid or expired channel token".to_string())
+}
+
+fn new_token() -> String {
+    format!("cht_{}", Uuid::new_v4().simple())
+}
+
+/// Relays messages between sandboxes over brokered sessions.
+///
+/// Sessions are shared by channels between the same pair of agents and
+/// closed once no channel uses them. Sealed bytes are counted against the
+/// sending and receiving sandboxes in the [`NetworkUsageTracker`].
+///
+/// Must be used within a Tokio runtime, which serves the channel sockets.
+pub struct AgentChannelProxy {
+    broker: Arc<dyn SessionBroker>,
+    config: AgentChannelConfig,
+    usage: Arc<dyn NetworkUsageTracker>,
+    state: RwLock<ProxyState>,
+    /// Held while sockets are started or stopped, so a socket is never
+    /// bound twice or stopped for a channel still being opened.
+    listening: tokio::sync::Mutex<()>,
+}
+
+impl AgentChannelProxy {
+    /// Create a proxy over a session broker.
+    pub fn new(broker: Arc<dyn SessionBroker>, config: AgentChannelConfig) -> Self {
+        Self {
+            broker,
+            config,
+            usage: Arc::new(InMemoryNetworkUsageTracker::new()),
+            state: RwLock::new(ProxyState::default()),
+            listening: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Set the tracker channel traffic is counted in.
+    pub fn with_usage_tracker(mut self, tracker: Arc<dyn NetworkUsageTracker>) -> Self {
+        self.usage = tracker;
+        self
+    }
+
+    /// The channel settings.
+    pub fn config(&self) -> &AgentChannelConfig {
+        &self.config
+    }
+
+    /// The tracker channel traffic is counted in.
+    pub fn usage_tracker(&self) -> &Arc<dyn NetworkUsageTracker> {
+        &self.usage
+    }
+
+    /// Open a channel between two sandboxes, serving each sandbox's socket
+    /// if it isn't already.
+    ///
+    /// Callers are responsible for checking the sandboxes may be connected.
+    pub async fn open(
+        self: &Arc<Self>,
+        a: ChannelPeer,
+        b: ChannelPeer,
+    ) -> CretoResult<AgentChannel> {
+        let _listening = self.listening.lock().await;
+        let listening = async {
+            self.listen(a.sandbox_id).await?;
+            self.listen(b.sandbox_id).await
+        };
+        if let Err(e) = listening.await {
+            self.stop_unused_listeners_locked();
+            return Err(e);
+        }
+        let sessions = async {
+            let a_to_b = self.broker.session(a.agent_id, b.agent_id).await?;
+            let b_to_a = self.broker.session(b.agent_id, a.agent_id).await?;
+            Ok::<_, CretoError>((a_to_b, b_to_a))
+        };
+        let (a_to_b, b_to_a) = match sessions.await {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                self.stop_unused_listeners_locked();
+                return Err(e);
+            }
+        };
+
+        let id = Uuid::now_v7();
+        let opened_at = Utc::now();
+        let expires_at = opened_at + self.config.token_ttl;
+        let endpoint = |member: ChannelPeer, outbound| ChannelEndpoint {
+            member,
+            token: new_token(),
+            expires_at,
+            outbound,
+            inbox: VecDeque::new(),
+            receiving: Arc::default(),
+        };
+        let channel = OpenChannel {
+            endpoints: [endpoint(a, a_to_b), endpoint(b, b_to_a)],
+        };
+        let descriptors = [
+            self.descriptor(id, &channel, 0),
+            self.descriptor(id, &channel, 1),
+        ];
+
+        let mut state = self.state.write().unwrap();
+        for (index, e) in channel.endpoints.iter().enumerate() {
+            state.tokens.insert(e.token.clone(), (id, index));
+        }
+        state.channels.insert(id, channel);
+
+        tracing::info!(
+            channel_id = %id,
+            sandbox_a = %a.sandbox_id,
+            sandbox_b = %b.sandbox_id,
+            "Agent channel opened"
+        );
+
+        Ok(AgentChannel {
+            id,
+            descriptors,
+            opened_at,
+        })
+    }
+
+    /// Descriptors for every channel a sandbox is on.
+    ///
+    /// Tokens past half their lifetime are replaced, so a sandbox handed
+    /// fresh descriptors before each execution never sees one expire
+    /// mid-run.
+    pub fn descriptors(&self, sandbox_id: SandboxId) -> Vec<ChannelDescriptor> {
+        let now = Utc::now();
+        let refresh_after = self.config.token_ttl / 2;
+        let mut state = self.state.write().unwrap();
+        let ProxyState {
+            channels, tokens, ..
+        } = &mut *state;
+
+        let mut descriptors = Vec::new();
+        for (id, channel) in channels.iter_mut() {
+            let Some(index) = channel
+                .endpoints
+                .iter()
+                .position(|e| e.member.sandbox_id == sandbox_id)
+            else {
+                continue;
+            };
+            let endpoint = &mut channel.endpoints[index];
+            if endpoint.expires_at - now < refresh_after {
+                tokens.remove(&endpoint.token);
+                endpoint.token = new_token();
+                endpoint.expires_at = now + self.config.token_ttl;
+                tokens.insert(endpoint.token.clone(), (*id, index));
+            }
+            descriptors.push(self.descriptor(*id, channel, index));
+        }
+        descriptors.sort_by_key(|d| d.channel_id);
+        descriptors
+    }
+
+    /// Send a message to the peer on the token's channel.
+    pub async fn send(&self, token: &str, message: &[u8]) -> CretoResult<()> {
+        let (channel_id, index, session) = {
+            let state = self.state.read().unwrap();
+            let (channel_id, index) = state.endpoint(token, Utc::now())?;
+            let channel = &state.channels[&channel_id];
+            if channel.endpoints[1 - index].inbox.len() >= self.config.max_queued_messages {
+                return Err(CretoError::LimitExceeded(format!(
+                    "Channel {} has {} undelivered messages",
+                    channel_id, self.config.max_queued_messages
+                )));
+            }
+            (channel_id, index, channel.endpoints[index].outbound)
+        };
+
+        let sealed = self.broker.seal(&session, message).await?;
+        let bytes = sealed.len() as u64;
+
+        let sender = {
+            let mut state = self.state.write().unwrap();
+            let channel = state
+                .channels
+                .get_mut(&channel_id)
+                .ok_or_else(|| CretoError::ChannelNotFound(channel_id.to_string()))?;
+            channel.endpoints[1 - index].inbox.push_back(sealed);
+            channel.endpoints[index].member.sandbox_id
+        };
+        self.usage.record(sender, bytes, 0);
+        Ok(())
+    }
+
+    /// Take the next message for the token's side of its channel, if any.
+    ///
+    /// A message that fails to decrypt stays at the head of the inbox.
+    pub async fn recv(&self, token: &str) -> CretoResult<Option<Vec<u8>>> {
+        let receiving = {
+            let state = self.state.read().unwrap();
+            let (channel_id, index) = state.endpoint(token, Utc::now())?;
+            state.channels[&channel_id].endpoints[index]
+                .receiving
+                .clone()
+        };
+        let _receiving = receiving.lock().await;
+
+        let (channel_id, index, recipient, sealed, session) = {
+            let state = self.state.read().unwrap();
+            let (channel_id, index) = state.endpoint(token, Utc::now())?;
+            let channel = &state.channels[&channel_id];
+            let Some(sealed) = channel.endpoints[index].inbox.front().cloned() else {
+                return Ok(None);
+            };
+            (
+                channel_id,
+                index,
+                channel.endpoints[index].member.sandbox_id,
+                sealed,
+                channel.endpoints[1 - index].outbound,
+            )
+        };
+
+        let message = self.broker.open(&session, &sealed).await?;
+        if let Some(channel) = self.state.write().unwrap().channels.get_mut(&channel_id) {
+            channel.endpoints[index].inbox.pop_front();
+        }
+        self.usage.record(recipient, 0, sealed.len() as u64);
+        Ok(Some(message))
+    }
+
+    /// Close a channel. Returns whether it was open.
+    pub async fn close(&self, channel_id: ChannelId) -> CretoResult<bool> {
+        let closed = self.remove(|id, _| *id == channel_id);
+        let found = !closed.is_empty();
+        self.stop_unused_listeners().await;
+        self.release_sessions(closed).await?;
+        Ok(found)
+    }
+
+    /// Close every channel a sandbox is on. Returns the closed channel IDs.
+    pub async fn close_sandbox(&self, sandbox_id: SandboxId) -> CretoResult<Vec<ChannelId>> {
+        let closed = self.remove(|_, channel| {
+            channel
+                .endpoints
+                .iter()
+                .any(|e| e.member.sandbox_id == sandbox_id)
+        });
+        let ids = closed.iter().map(|(id, _)| *id).collect();
+        self.stop_unused_listeners().await;
+        self.release_sessions(closed).await?;
+        Ok(ids)
+    }
+
+    /// IDs of the open channels.
+    pub fn open_channels(&self) -> Vec<ChannelId> {
+        let mut ids: Vec<_> = self
+            .state
+            .read()
+            .unwrap()
+            .channels
+            .keys()
+            .copied()
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    fn remove(
+        &self,
+        matches: impl Fn(&ChannelId, &OpenChannel) -> bool,
+    ) -> Vec<(ChannelId, OpenChannel)> {
+        let mut state = self.state.write().unwrap();
+        let ids: Vec<_> = state
+            .channels
+            .iter()
+            .filter(|(id, channel)| matches(id, channel))
+            .map(|(id, _)| *id)
+            .collect();
+
+        let mut removed = Vec::new();
+        for id in ids {
+            let channel = state.channels.remove(&id).expect("listed above");
+            for e in &channel.endpoints {
+                state.tokens.remove(&e.token);
+            }
+            removed.push((id, channel));
+        }
+        removed
+    }
+
+    /// Close the sessions of removed channels that no open channel still uses.
+    async fn release_sessions(&self, closed: Vec<(ChannelId, OpenChannel)>) -> CretoResult<()> {
+        let in_use = self.state.read().unwrap().sessions_in_use();
+        let mut released = HashSet::new();
+        for (id, channel) in closed {
+            let dropped: usize = channel.endpoints.iter().map(|e| e.inbox.len()).sum();
+            tracing::info!(channel_id = %id, dropped, "Agent channel closed");
+            for e in &channel.endpoints {
+                if !in_use.contains(&e.outbound) && released.insert(e.outbound) {
+                    self.broker.close(&e.outbound).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn descriptor(&self, id: ChannelId, channel: &OpenChannel, index: usize) -> ChannelDescriptor {
+        let (local, remote) = (&channel.endpoints[index], &channel.endpoints[1 - index]);
+        ChannelDescriptor {
+            channel_id: id,
+            sandbox_id: local.member.sandbox_id,
+            peer_sandbox_id: remote.member.sandbox_id,
+            peer_agent_id: remote.member.agent_id,
+            endpoint: format!(
+                "unix://{}",
+                self.socket_path(local.member.sandbox_id).display()
+            ),
+            token: local.token.clone(),
+            expires_at: local.expires_at,
+        }
+    }
+
+    fn socket_path(&self, sandbox_id: SandboxId) -> PathBuf {
+        self.config
+            .socket_dir
+            .join(format!("{}.sock", sandbox_id.as_uuid().simple()))
+    }
+
+    /// Serve a sandbox's socket unless it is already served. The caller
+    /// holds `listening`.
+    async fn listen(self: &Arc<Self>, sandbox_id: SandboxId) -> CretoResult<()> {
+        if self
+            .state
+            .read()
+            .unwrap()
+            .listeners
+            .contains_key(&sandbox_id)
+        {
+            return Ok(());
+        }
+        let path = self.socket_path(sandbox_id);
+        let socket_error = |e: std::io::Error| {
+            CretoError::Internal(format!("Channel socket {}: {}", path.display(), e))
+        };
+        tokio::fs::create_dir_all(&self.config.socket_dir)
+            .await
+            .map_err(socket_error)?;
+        // A socket left by an earlier process would block the bind
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(socket_error(e)),
+            _ => {}
+        }
+        let listener = UnixListener::bind(&path).map_err(socket_error)?;
+
+        let mut state = self.state.write().unwrap();
+        if state.listeners.contains_key(&sandbox_id) {
+            return Ok(());
+        }
+        let proxy = Arc::downgrade(self);
+        let server = tokio::spawn(serve_socket(proxy, sandbox_id, listener));
+        state.listeners.insert(sandbox_id, server);
+        Ok(())
+    }
+
+    /// Stop serving the sockets of sandboxes no longer on any channel.
+    async fn stop_unused_listeners(&self) {
+        let _listening = self.listening.lock().await;
+        self.stop_unused_listeners_locked();
+    }
+
+    fn stop_unused_listeners_locked(&self) {
+        let stopped: Vec<_> = {
+            let mut state = self.state.write().unwrap();
+            let unused: Vec<_> = state
+                .listeners
+                .keys()
+                .filter(|id| !state.on_channel(**id))
+                .copied()
+                .collect();
+            unused
+                .into_iter()
+                .filter_map(|id| state.listeners.remove(&id).map(|server| (id, server)))
+                .collect()
+        };
+        for (sandbox_id, server) in stopped {
+            server.abort();
+            remove_socket(&self.socket_path(sandbox_id));
+        }
+    }
+
+    /// Answer one request from a sandbox's socket.
+    async fn handle(&self, sandbox_id: SandboxId, request: ChannelRequest) -> ChannelResponse {
+        let result = match request {
+            ChannelRequest::Channels => Ok(ChannelResponse::Channels {
+                channels: self.descriptors(sandbox_id),
+            }),
+            ChannelRequest::Send { token, message } => match self.owns(sandbox_id, &token) {
+                Ok(()) => match decode(&message) {
+                    Ok(message) => self
+                        .send(&token, &message)
+                        .await
+                        .map(|()| ChannelResponse::Sent),
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            },
+            ChannelRequest::Recv { token } => match self.owns(sandbox_id, &token) {
+                Ok(()) => self
+                    .recv(&token)
+                    .await
+                    .map(|message| ChannelResponse::Message {
+                        message: message.map(|m| STANDARD.encode(m)),
+                    }),
+                Err(e) => Err(e),
+            },
+        };
+        result.unwrap_or_else(|e| ChannelResponse::Error {
+            code: e.code().to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Check a token was issued to the sandbox whose socket it arrived on.
+    fn owns(&self, sandbox_id: SandboxId, token: &str) -> CretoResult<()> {
+        let state = self.state.read().unwrap();
+        let (channel_id, index) = state.endpoint(token, Utc::now())?;
+        if state.channels[&channel_id].endpoints[index]
+            .member
+            .sandbox_id
+            != sandbox_id
+        {
+            return Err(invalid_token());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AgentChannelProxy {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap();
+        for (sandbox_id, server) in state.listeners.drain() {
+            server.abort();
+            remove_socket(
+                &self
+                    .config
+                    .socket_dir
+                    .join(format!("{}.sock", sandbox_id.as_uuid().simple())),
+            );
+        }
+    }
+}
+
+fn remove_socket(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!(path = %path.display(), error = %e, "Failed to remove channel socket");
+        }
+    }
+}
+
+/// Accept connections on a sandbox's socket until the server is stopped.
+async fn serve_socket(
+    proxy: Weak<AgentChannelProxy>,
+    sandbox_id: SandboxId,
+    listener: UnixListener,
+) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(serve_connection(proxy.clone(), sandbox_id, stream));
+            }
+            Err(e) => {
+                tracing::warn!(sandbox_id = %sandbox_id, error = %e, "Channel socket accept failed");
+            }
+        }
+    }
+}
+
+/// Answer requests on one connection until it closes, the proxy is gone,
+/// or the sandbox leaves its last channel.
+async fn serve_connection(
+    proxy: Weak<AgentChannelProxy>,
+    sandbox_id: SandboxId,
+    stream: UnixStream,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let Some(proxy) = proxy.upgrade() else {
+            return;
+        };
+        if !proxy
+            .state
+            .read()
+            .unwrap()
+            .listeners
+            .contains_key(&sandbox_id)
+        {
+            return;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => proxy.handle(sandbox_id, request).await,
+            Err(e) => ChannelResponse::Error {
+                code: CretoError::SerializationError(String::new())
+                    .code()
+                    .to_string(),
+                message: e.to_string(),
+            },
+        };
+        drop(proxy);
+        let Ok(mut response) = serde_json::to_vec(&response) else {
+            return;
+        };
+        response.push(b'\n');
+        if writer.write_all(&response).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// [`SessionBroker`] backed by creto-messaging.
+///
+/// Holds a [`MessagingService`](creto_messaging::MessagingService) per agent
+/// the runtime brokers for; envelopes are carried as JSON.
+#[cfg(feature = "messaging")]
+#[derive(Default)]
+pub struct MessagingSessionBroker {
+    agents: RwLock<HashMap<AgentId, Arc<creto_messaging::MessagingService>>>,
+    sessions: tokio::sync::Mutex<HashMap<(AgentId, AgentId), BrokeredSession>>,
+}
+
+#[cfg(feature = "messaging")]
+impl MessagingSessionBroker {
+    /// Create a broker with no agents.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Broker for the agent an initialized messaging service belongs to.
+    pub fn register(
+        &self,
+        service: Arc<creto_messaging::MessagingService>,
+    ) -> CretoResult<AgentId> {
+        let agent_id = service.local_agent().ok_or_else(|| {
+            CretoError::ValidationFailed("Messaging service is not initialized".to_string())
+        })?;
+        self.agents.write().unwrap().insert(agent_id, service);
+        Ok(agent_id)
+    }
+
+    fn agent(&self, agent_id: AgentId) -> CretoResult<Arc<creto_messaging::MessagingService>> {
+        self.agents
+            .read()
+            .unwrap()
+            .get(&agent_id)
+            .cloned()
+            .ok_or_else(|| {
+                CretoError::NotFound(format!("Messaging service for agent {}", agent_id))
+            })
+    }
+
+    async fn is_active(&self, session: &BrokeredSession) -> CretoResult<bool> {
+        let active = Some(creto_messaging::SessionState::Active);
+        Ok(self
+            .agent(session.sender)?
+            .session_status(session.sender_session)
+            .await
+            == active
+            && self
+                .agent(session.recipient)?
+                .session_status(session.recipient_session)
+                .await
+                == active)
+    }
+}
+
+#[cfg(feature = "messaging")]
+#[async_trait]
+impl SessionBroker for MessagingSessionBroker {
+    async fn session(&self, sender: AgentId, recipient: AgentId) -> CretoResult<BrokeredSession> {
+        let mut sessions = self.sessions.lock().await;
+        if let Some(session) = sessions.get(&(sender, recipient)) {
+            if self.is_active(session).await? {
+                return Ok(*session);
+            }
+        }
+
+        let (sender_session, params) = self.agent(sender)?.initiate_session(recipient).await?;
+        let recipient_session = self.agent(recipient)?.accept_session(&params).await?;
+        let session = BrokeredSession {
+            sender,
+            recipient,
+            sender_session,
+            recipient_session,
+        };
+        sessions.insert((sender, recipient), session);
+        Ok(session)
+    }
+
+    async fn seal(&self, session: &BrokeredSession, plaintext: &[u8]) -> CretoResult<Vec<u8>> {
+        let envelope = self
+            .agent(session.sender)?
+            .seal(session.sender_session, plaintext)
+            .await?;
+        serde_json::to_vec(&envelope).map_err(|e| CretoError::SerializationError(e.to_string()))
+    }
+
+    async fn open(&self, session: &BrokeredSession, sealed: &[u8]) -> CretoResult<Vec<u8>> {
+        let envelope: creto_messaging::Envelope = serde_json::from_slice(sealed)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        self.agent(session.recipient)?
+            .open(session.recipient_session, &envelope)
+            .await
+    }
+
+    async fn close(&self, session: &BrokeredSession) -> CretoResult<()> {
+        {
+            let mut sessions = self.sessions.lock().await;
+            if sessions.get(&(session.sender, session.recipient)) == Some(session) {
+                sessions.remove(&(session.sender, session.recipient));
+            }
+        }
+        self.agent(session.sender)?
+            .close_session(session.sender_session)
+            .await?;
+        self.agent(session.recipient)?
+            .close_session(session.recipient_session)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Broker that prefixes messages instead of encrypting them.
+    #[derive(Default)]
+    struct RecordingBroker {
+        sessions: Mutex<HashMap<(AgentId, AgentId), BrokeredSession>>,
+        established: Mutex<usize>,
+        closed: Mutex<Vec<BrokeredSession>>,
+        fail_next_open: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl SessionBroker for RecordingBroker {
+        async fn session(
+            &self,
+            sender: AgentId,
+            recipient: AgentId,
+        ) -> CretoResult<BrokeredSession> {
+            let mut sessions = self.sessions.lock().unwrap();
+            let session = sessions.entry((sender, recipient)).or_insert_with(|| {
+                *self.established.lock().unwrap() += 1;
+                BrokeredSession {
+                    sender,
+                    recipient,
+                    sender_session: Uuid::now_v7(),
+                    recipient_session: Uuid::now_v7(),
+                }
+            });
+            Ok(*session)
+        }
+
+        async fn seal(&self, _: &BrokeredSession, plaintext: &[u8]) -> CretoResult<Vec<u8>> {
+            Ok([b"sealed:", plaintext].concat())
+        }
+
+        async fn open(&self, _: &BrokeredSession, sealed: &[u8]) -> CretoResult<Vec<u8>> {
+            if std::mem::take(&mut *self.fail_next_open.lock().unwrap()) {
+                return Err(CretoError::DecryptionFailed("scripted".to_string()));
+            }
+            Ok(sealed[b"sealed:".len()..].to_vec())
+        }
+
+        async fn close(&self, session: &BrokeredSession) -> CretoResult<()> {
+            self.sessions
+                .lock()
+                .unwrap()
+                .remove(&(session.sender, session.recipient));
+            self.closed.lock().unwrap().push(*session);
+            Ok(())
+        }
+    }
+
+    fn peer() -> ChannelPeer {
+        ChannelPeer {
+            sandbox_id: SandboxId::new(),
+            agent_id: AgentId::new(),
+        }
+    }
+
+    /// Settings with sockets in a fresh temporary directory.
+    fn config() -> AgentChannelConfig {
+        AgentChannelConfig {
+            socket_dir: std::env::temp_dir().join(format!("creto-ch-{}", Uuid::new_v4().simple())),
+            ..Default::default()
+        }
+    }
+
+    fn proxy(broker: Arc<RecordingBroker>, config: AgentChannelConfig) -> Arc<AgentChannelProxy> {
+        Arc::new(AgentChannelProxy::new(broker, config))
+    }
+
+    #[tokio::test]
+    async fn test_messages_relayed_through_sockets_and_counted() {
+        let proxy = proxy(Arc::default(), config());
+        let (a, b) = (peer(), peer());
+        let channel = proxy.open(a, b).await.unwrap();
+        let [to_a, to_b] = &channel.descriptors;
+        assert_eq!(to_a.peer_sandbox_id, b.sandbox_id);
+        assert_eq!(to_b.peer_agent_id, a.agent_id);
+        assert_ne!(to_a.endpoint, to_b.endpoint);
+        assert_ne!(to_a.token, to_b.token);
+
+        let mut client_a = ChannelClient::connect(&to_a.endpoint).await.unwrap();
+        let mut client_b = ChannelClient::connect(&to_b.endpoint).await.unwrap();
+        assert_eq!(client_a.channels().await.unwrap(), vec![to_a.clone()]);
+
+        client_b.send(&to_b.token, b"hello").await.unwrap();
+        assert_eq!(client_b.recv(&to_b.token).await.unwrap(), None);
+        assert_eq!(
+            client_a.recv(&to_a.token).await.unwrap().as_deref(),
+            Some(&b"hello"[..])
+        );
+
+        let sealed_len = b"sealed:hello".len() as u64;
+        let tracker = proxy.usage_tracker();
+        assert_eq!(tracker.usage(b.sandbox_id).bytes_sent, sealed_len);
+        assert_eq!(tracker.usage(a.sandbox_id).bytes_received, sealed_len);
+
+        // A token only works on the socket of the sandbox it was issued to
+        let err = client_a.send(&to_b.token, b"spoofed").await.unwrap_err();
+        assert!(matches!(err, CretoError::ChannelError(_)));
+    }
+
+    #[tokio::test]
+    async fn test_sockets_served_until_last_channel_closes() {
+        let broker = Arc::new(RecordingBroker::default());
+        let proxy = proxy(broker.clone(), config());
+        let (a, b) = (peer(), peer());
+        let other_b = ChannelPeer {
+            sandbox_id: SandboxId::new(),
+            ..b
+        };
+
+        let first = proxy.open(a, b).await.unwrap();
+        let second = proxy.open(a, other_b).await.unwrap();
+        assert_eq!(*broker.established.lock().unwrap(), 2);
+        assert_eq!(
+            first.descriptors[0].endpoint,
+            second.descriptors[0].endpoint
+        );
+
+        assert_eq!(
+            proxy.close_sandbox(b.sandbox_id).await.unwrap(),
+            vec![first.id]
+        );
+        assert!(broker.closed.lock().unwrap().is_empty());
+        let err = proxy
+            .send(&first.descriptors[0].token, b"late")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::Unauthorized(_)));
+        assert!(ChannelClient::connect(&first.descriptors[1].endpoint)
+            .await
+            .is_err());
+        let mut client_a = ChannelClient::connect(&first.descriptors[0].endpoint)
+            .await
+            .unwrap();
+        assert_eq!(client_a.channels().await.unwrap().len(), 1);
+
+        assert!(proxy.close(second.id).await.unwrap());
+        assert_eq!(broker.closed.lock().unwrap().len(), 2);
+        assert!(proxy.open_channels().is_empty());
+        assert!(ChannelClient::connect(&second.descriptors[0].endpoint)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_message_kept_when_it_fails_to_decrypt() {
+        let broker = Arc::new(RecordingBroker::default());
+        let proxy = proxy(broker.clone(), config());
+        let channel = proxy.open(peer(), peer()).await.unwrap();
+        let [to_a, to_b] = &channel.descriptors;
+        proxy.send(&to_b.token, b"retry me").await.unwrap();
+
+        *broker.fail_next_open.lock().unwrap() = true;
+        assert!(proxy.recv(&to_a.token).await.is_err());
+        assert_eq!(
+            proxy.usage_tracker().usage(to_a.sandbox_id).bytes_received,
+            0
+        );
+
+        assert_eq!(
+            proxy.recv(&to_a.token).await.unwrap().as_deref(),
+            Some(&b"retry me"[..])
+        );
+        assert_eq!(proxy.recv(&to_a.token).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_expired_tokens_rejected_and_reissued() {
+        let config = AgentChannelConfig {
+            token_ttl: Duration::zero(),
+            ..config()
+        };
+        let proxy = proxy(Arc::default(), config);
+        let (a, b) = (peer(), peer());
+        let channel = proxy.open(a, b).await.unwrap();
+
+        let stale = &channel.descriptors[0];
+        assert!(stale.is_expired(Utc::now()));
+        assert!(proxy.send(&stale.token, b"x").await.is_err());
+
+        let fresh = proxy.descriptors(a.sandbox_id).remove(0);
+        assert_ne!(fresh.token, stale.token);
+    }
+
+    #[test]
+    fn test_cross_org_permission_is_symmetric() {
+        let (x, y, z) = (
+            OrganizationId::new(),
+            OrganizationId::new(),
+            OrganizationId::new(),
+        );
+        let config = AgentChannelConfig::default().allow_cross_org(x, y);
+        assert!(config.permits(x, x));
+        assert!(config.permits(y, x));
+        assert!(!config.permits(x, z));
+    }
+}
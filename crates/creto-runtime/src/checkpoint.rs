@@ -6,15 +6,34 @@
 //! - Disaster recovery and fault tolerance
 //! - Development workflow snapshots
 //! - Cost optimization through sandbox parking
+//!
+//! [`InMemoryCheckpointStore`] can be bounded by [`CheckpointStoreLimits`].
+//! When a new checkpoint would exceed a cap, least recently used checkpoints
+//! move to an optional [`FilesystemCheckpointStore`] spill tier and are
+//! fetched back on demand; whatever still does not fit is handled by the
+//! [`EvictionPolicy`]. The most recent checkpoint of a live sandbox is never
+//! spilled or evicted. Spill files are written and read on the blocking
+//! thread pool, outside the store's lock, and are readable by their owner
+//! only.
+//!
+//! With [`CheckpointMode::Incremental`], a checkpoint records its sandbox's
+//! previous checkpoint as its parent and stores only the fixed-size chunks
+//! whose BLAKE3 hashes the parent's snapshot lacks.
+//! [`CheckpointManager::resolve_snapshot`] rebuilds the full snapshot from
+//! the chain, and a checkpoint cannot be deleted or evicted while another
+//! checkpoint names it as its parent.
 
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, CretoError, CretoResult};
+use creto_common::{AgentId, CretoError, CretoResult, OrganizationId};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
 use uuid::Uuid;
 
-use crate::sandbox::SandboxId;
+use crate::attestation::{AttestationMeasurements, AttestedHash};
+use crate::sandbox::{SandboxConfig, SandboxId};
 
 /// Unique identifier for a checkpoint.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -61,6 +80,15 @@ pub struct Checkpoint {
     /// Agent that owns the sandbox.
     pub agent_id: AgentId,
 
+    /// Organization that owns the sandbox, for a restore to rebuild it
+    /// under (unset on checkpoints of untracked sandboxes).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization_id: Option<OrganizationId>,
+
+    /// Configuration the sandbox ran with, for a restore to rebuild it with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox_config: Option<SandboxConfig>,
+
     /// Serialized state snapshot (opaque blob).
     pub state_snapshot: Vec<u8>,
 
@@ -80,6 +108,22 @@ pub struct Checkpoint {
     /// Compression algorithm used (if any).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub compression: Option<CompressionAlgorithm>,
+
+    /// Hashes of the sandbox's attestation when it was checkpointed (if it
+    /// was attested). A restored sandbox must attest to the same hashes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<AttestationMeasurements>,
+
+    /// Checkpoint this one was taken relative to (incremental checkpoints
+    /// only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<CheckpointId>,
+
+    /// How to rebuild the full snapshot from the parent's (incremental
+    /// checkpoints only). `state_snapshot` then holds just the chunks the
+    /// parent lacks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<ChunkManifest>,
 }
 
 impl Checkpoint {
@@ -95,16 +139,24 @@ impl Checkpoint {
             id: CheckpointId::new(),
             sandbox_id,
             agent_id,
+            organization_id: None,
+            sandbox_config: None,
             state_snapshot,
             filesystem_hash,
             memory_size,
             created_at: Utc::now(),
             metadata: HashMap::new(),
             compression: None,
+            attestation: None,
+            parent: None,
+            manifest: None,
         }
     }
 
     /// Get the size of the checkpoint in bytes.
+    ///
+    /// This is the snapshot as stored, so compressed checkpoints count their
+    /// compressed size.
     pub fn size_bytes(&self) -> u64 {
         self.state_snapshot.len() as u64
     }
@@ -114,6 +166,11 @@ impl Checkpoint {
         self.compression.is_some()
     }
 
+    /// Check if the checkpoint only stores changes relative to a parent.
+    pub fn is_incremental(&self) -> bool {
+        self.parent.is_some()
+    }
+
     /// Add metadata to the checkpoint.
     pub fn with_metadata(mut self, key: String, value: String) -> Self {
         self.metadata.insert(key, value);
@@ -125,6 +182,127 @@ impl Checkpoint {
         self.compression = Some(compression);
         self
     }
+
+    /// The state snapshot with its compression undone.
+    ///
+    /// Incremental checkpoints hold only part of their snapshot, so they
+    /// fail with [`CheckpointError::RestoreFailed`]; rebuild them with
+    /// [`CheckpointManager::resolve_snapshot`].
+    pub fn decompressed_snapshot(&self) -> Result<Vec<u8>, CheckpointError> {
+        if self.is_incremental() {
+            return Err(CheckpointError::RestoreFailed {
+                checkpoint_id: self.id,
+                reason: "Incremental checkpoints must be resolved against their parent".to_string(),
+            });
+        }
+        self.stored_bytes()
+    }
+
+    fn stored_bytes(&self) -> Result<Vec<u8>, CheckpointError> {
+        self.compression
+            .unwrap_or_default()
+            .decompress(&self.state_snapshot)
+    }
+
+    /// Rebuild this incremental checkpoint's snapshot from its parent's.
+    fn apply_to(&self, parent_snapshot: &[u8]) -> Result<Vec<u8>, CheckpointError> {
+        let Some(manifest) = &self.manifest else {
+            return self.stored_bytes();
+        };
+        let restore_failed = |reason: &str| CheckpointError::RestoreFailed {
+            checkpoint_id: self.id,
+            reason: reason.to_string(),
+        };
+        let stored = self.stored_bytes()?;
+        // Chunks by hash: the parent's, then each stored one as it is read
+        let mut known: HashMap<String, &[u8]> = parent_snapshot
+            .chunks(manifest.chunk_size.max(1))
+            .map(|chunk| (chunk_hash(chunk), chunk))
+            .collect();
+        let mut stored_chunks = manifest.stored.iter().peekable();
+        let mut offset = 0;
+        let mut snapshot = Vec::with_capacity(manifest.snapshot_len as usize);
+        for (index, expected) in manifest.chunks.iter().enumerate() {
+            if stored_chunks.next_if_eq(&&index).is_none() {
+                let chunk = known
+                    .get(expected)
+                    .ok_or_else(|| restore_failed("Parent snapshot is missing a chunk"))?;
+                snapshot.extend_from_slice(chunk);
+                continue;
+            }
+            let len = manifest.chunk_len(index);
+            let chunk = stored
+                .get(offset..offset + len)
+                .ok_or_else(|| restore_failed("Stored chunks are truncated"))?;
+            let actual = chunk_hash(chunk);
+            if &actual != expected {
+                return Err(CheckpointError::IntegrityCheckFailed {
+                    checkpoint_id: self.id,
+                    expected: expected.clone(),
+                    actual,
+                });
+            }
+            snapshot.extend_from_slice(chunk);
+            known.insert(actual, chunk);
+            offset += len;
+        }
+        if snapshot.len() as u64 != manifest.snapshot_len {
+            return Err(restore_failed("Rebuilt snapshot has the wrong length"));
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Size of the chunks incremental checkpoints are diffed in.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The chunks of an incremental checkpoint's full snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    /// Size of every chunk but the last.
+    pub chunk_size: usize,
+    /// Length of the full snapshot in bytes.
+    pub snapshot_len: u64,
+    /// BLAKE3 hash (hex) of each chunk of the full snapshot, in order.
+    pub chunks: Vec<String>,
+    /// Ascending indexes of the chunks stored in the checkpoint itself, in
+    /// the order they are stored. The rest come from the parent.
+    pub stored: Vec<usize>,
+}
+
+impl ChunkManifest {
+    /// Split `snapshot` into chunks, keeping those `parent` lacks.
+    ///
+    /// Returns the manifest and the kept chunks' bytes.
+    fn diff(snapshot: &[u8], parent: &[u8]) -> (Self, Vec<u8>) {
+        let known: HashSet<String> = parent.chunks(CHUNK_SIZE).map(chunk_hash).collect();
+        let mut manifest = Self {
+            chunk_size: CHUNK_SIZE,
+            snapshot_len: snapshot.len() as u64,
+            chunks: Vec::new(),
+            stored: Vec::new(),
+        };
+        let mut stored_hashes = HashSet::new();
+        let mut stored = Vec::new();
+        for (index, chunk) in snapshot.chunks(CHUNK_SIZE).enumerate() {
+            let hash = chunk_hash(chunk);
+            if !known.contains(&hash) && stored_hashes.insert(hash.clone()) {
+                manifest.stored.push(index);
+                stored.extend_from_slice(chunk);
+            }
+            manifest.chunks.push(hash);
+        }
+        (manifest, stored)
+    }
+
+    fn chunk_len(&self, index: usize) -> usize {
+        let start = (index * self.chunk_size) as u64;
+        (self.snapshot_len - start).min(self.chunk_size as u64) as usize
+    }
+}
+
+fn chunk_hash(chunk: &[u8]) -> String {
+    blake3::hash(chunk).to_hex().to_string()
 }
 
 /// Compression algorithms supported for checkpoints.
@@ -143,6 +321,49 @@ pub enum CompressionAlgorithm {
     Lz4,
 }
 
+impl CompressionAlgorithm {
+    /// Compress `data` with this algorithm.
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>, CheckpointError> {
+        let error = |e: std::io::Error| CheckpointError::CompressionError {
+            algorithm: self,
+            message: e.to_string(),
+        };
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).map_err(error)?;
+                encoder.finish().map_err(error)
+            }
+            Self::Zstd => zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL).map_err(error),
+            Self::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+        }
+    }
+
+    /// Decompress `data` that was compressed with this algorithm.
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>, CheckpointError> {
+        let error = |message: String| CheckpointError::CompressionError {
+            algorithm: self,
+            message,
+        };
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Gzip => {
+                let mut decompressed = Vec::new();
+                flate2::read::GzDecoder::new(data)
+                    .read_to_end(&mut decompressed)
+                    .map_err(|e| error(e.to_string()))?;
+                Ok(decompressed)
+            }
+            Self::Zstd => zstd::decode_all(data).map_err(|e| error(e.to_string())),
+            Self::Lz4 => {
+                lz4_flex::decompress_size_prepended(data).map_err(|e| error(e.to_string()))
+            }
+        }
+    }
+}
+
 /// Configuration for creating checkpoints.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CheckpointConfig {
@@ -161,6 +382,53 @@ pub struct CheckpointConfig {
     /// Additional metadata to attach to the checkpoint.
     #[serde(default)]
     pub metadata: HashMap<String, String>,
+
+    /// Attestation hashes of the sandbox being checkpointed, to record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation: Option<AttestationMeasurements>,
+
+    /// Organization and agent owning the sandbox being checkpointed, to
+    /// record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<(OrganizationId, AgentId)>,
+
+    /// Configuration of the sandbox being checkpointed, to record.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox_config: Option<SandboxConfig>,
+
+    /// Whether to store the whole snapshot or only what changed since the
+    /// sandbox's previous checkpoint.
+    #[serde(default)]
+    pub mode: CheckpointMode,
+
+    /// Captured sandbox state to store. The development stores record a
+    /// 1KB mock snapshot when unset.
+    #[serde(skip)]
+    pub snapshot: Option<Vec<u8>>,
+}
+
+/// Whether a checkpoint stores its whole snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointMode {
+    /// Store the whole snapshot.
+    #[default]
+    Full,
+    /// Store only the chunks that changed since the sandbox's latest
+    /// checkpoint, which becomes the parent.
+    Incremental {
+        /// Most incremental checkpoints between two full ones. A checkpoint
+        /// that would exceed it is taken in full instead.
+        max_chain_length: usize,
+    },
+}
+
+impl CheckpointMode {
+    /// Whether a checkpoint can extend a chain already `chain_length`
+    /// incremental checkpoints long.
+    fn extends(self, chain_length: usize) -> bool {
+        matches!(self, Self::Incremental { max_chain_length } if chain_length < max_chain_length)
+    }
 }
 
 fn default_true() -> bool {
@@ -174,6 +442,11 @@ impl Default for CheckpointConfig {
             include_memory: true,
             include_filesystem: true,
             metadata: HashMap::new(),
+            attestation: None,
+            owner: None,
+            sandbox_config: None,
+            mode: CheckpointMode::Full,
+            snapshot: None,
         }
     }
 }
@@ -211,6 +484,27 @@ pub enum CheckpointError {
     },
     /// Storage backend error.
     StorageError { message: String },
+    /// The store is at its caps and nothing can be spilled or evicted.
+    CapacityExceeded {
+        memory_checkpoints: usize,
+        memory_bytes: u64,
+    },
+    /// The checkpointed sandbox is still live, so restoring would duplicate it.
+    SandboxStillExists {
+        checkpoint_id: CheckpointId,
+        sandbox_id: SandboxId,
+    },
+    /// The restored sandbox's fresh attestation differs from the hashes
+    /// recorded in the checkpoint.
+    AttestationMismatch {
+        checkpoint_id: CheckpointId,
+        hash: AttestedHash,
+    },
+    /// Incremental checkpoints still name this checkpoint as their parent.
+    HasDependents {
+        checkpoint_id: CheckpointId,
+        dependents: Vec<CheckpointId>,
+    },
 }
 
 impl std::fmt::Display for CheckpointError {
@@ -263,6 +557,47 @@ impl std::fmt::Display for CheckpointError {
             Self::StorageError { message } => {
                 write!(f, "Storage error: {}", message)
             }
+            Self::CapacityExceeded {
+                memory_checkpoints,
+                memory_bytes,
+            } => {
+                write!(
+                    f,
+                    "Checkpoint store is full ({} checkpoints, {} bytes in memory)",
+                    memory_checkpoints, memory_bytes
+                )
+            }
+            Self::SandboxStillExists {
+                checkpoint_id,
+                sandbox_id,
+            } => {
+                write!(
+                    f,
+                    "Cannot restore checkpoint {}: sandbox {} still exists",
+                    checkpoint_id, sandbox_id
+                )
+            }
+            Self::AttestationMismatch {
+                checkpoint_id,
+                hash,
+            } => {
+                write!(
+                    f,
+                    "Attestation of restored checkpoint {} does not match: {} diverged",
+                    checkpoint_id, hash
+                )
+            }
+            Self::HasDependents {
+                checkpoint_id,
+                dependents,
+            } => {
+                write!(
+                    f,
+                    "Checkpoint {} is the parent of {} incremental checkpoint(s)",
+                    checkpoint_id,
+                    dependents.len()
+                )
+            }
         }
     }
 }
@@ -280,6 +615,10 @@ impl CheckpointError {
             Self::IntegrityCheckFailed { .. } => "ENABLE-504",
             Self::CompressionError { .. } => "ENABLE-505",
             Self::StorageError { .. } => "ENABLE-506",
+            Self::CapacityExceeded { .. } => "ENABLE-507",
+            Self::SandboxStillExists { .. } => "ENABLE-508",
+            Self::AttestationMismatch { .. } => "ENABLE-509",
+            Self::HasDependents { .. } => "ENABLE-510",
         }
     }
 }
@@ -290,6 +629,98 @@ impl From<CheckpointError> for CretoError {
     }
 }
 
+/// What a bounded store does when a checkpoint would exceed its caps and
+/// nothing more can be spilled to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    /// Fail the new checkpoint with [`CheckpointError::CapacityExceeded`].
+    #[default]
+    RejectNew,
+    /// Delete the oldest checkpoints to make room.
+    EvictOldest,
+}
+
+/// Caps on the checkpoints an [`InMemoryCheckpointStore`] holds in memory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointStoreLimits {
+    /// Stored bytes held in memory (`None` for unbounded).
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+
+    /// Checkpoints held in memory (`None` for unbounded).
+    #[serde(default)]
+    pub max_checkpoints: Option<usize>,
+
+    /// What to do when a checkpoint does not fit.
+    #[serde(default)]
+    pub eviction: EvictionPolicy,
+
+    /// Utilization (0.0-1.0) above which the warning callback fires.
+    #[serde(default = "default_warning_threshold")]
+    pub warning_threshold: f64,
+}
+
+fn default_warning_threshold() -> f64 {
+    0.8
+}
+
+impl Default for CheckpointStoreLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: None,
+            max_checkpoints: None,
+            eviction: EvictionPolicy::RejectNew,
+            warning_threshold: default_warning_threshold(),
+        }
+    }
+}
+
+impl CheckpointStoreLimits {
+    /// Whether holding `bytes` in `count` checkpoints breaks a cap.
+    fn exceeded_by(&self, bytes: u64, count: usize) -> bool {
+        self.max_bytes.is_some_and(|max| bytes > max)
+            || self.max_checkpoints.is_some_and(|max| count > max)
+    }
+}
+
+/// Utilization of a checkpoint store.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct CheckpointStoreStats {
+    /// Checkpoints held in memory.
+    pub memory_checkpoints: usize,
+    /// Stored bytes held in memory.
+    pub memory_bytes: u64,
+    /// Checkpoints spilled to disk.
+    pub spilled_checkpoints: usize,
+    /// Stored bytes spilled to disk.
+    pub spilled_bytes: u64,
+    /// Configured checkpoint cap.
+    pub max_checkpoints: Option<usize>,
+    /// Configured byte cap.
+    pub max_bytes: Option<u64>,
+    /// Checkpoints deleted to make room.
+    pub evicted: u64,
+    /// Checkpoints rejected because the store was full.
+    pub rejected: u64,
+}
+
+impl CheckpointStoreStats {
+    /// Fraction of the tighter cap in use (0.0 when unbounded).
+    pub fn utilization(&self) -> f64 {
+        let bytes = self
+            .max_bytes
+            .map_or(0.0, |max| self.memory_bytes as f64 / max.max(1) as f64);
+        let count = self.max_checkpoints.map_or(0.0, |max| {
+            self.memory_checkpoints as f64 / max.max(1) as f64
+        });
+        bytes.max(count)
+    }
+}
+
+/// Called when store utilization rises above the warning threshold.
+pub type UtilizationWarning = Arc<dyn Fn(&CheckpointStoreStats) + Send + Sync>;
+
 /// Manager for checkpoint operations.
 #[async_trait::async_trait]
 pub trait CheckpointManager: Send + Sync {
@@ -311,35 +742,627 @@ pub trait CheckpointManager: Send + Sync {
     ) -> CretoResult<Vec<Checkpoint>>;
 
     /// Delete a checkpoint.
+    ///
+    /// Fails with [`CheckpointError::HasDependents`] while incremental
+    /// checkpoints name it as their parent; delete those first.
     async fn delete_checkpoint(&self, checkpoint_id: CheckpointId) -> CretoResult<()>;
 
     /// Get checkpoint metadata.
     async fn get_checkpoint(&self, checkpoint_id: CheckpointId) -> CretoResult<Checkpoint>;
+
+    /// The checkpoint's full, decompressed snapshot, rebuilt from its chain
+    /// of parents if it is incremental.
+    async fn resolve_snapshot(&self, checkpoint_id: CheckpointId) -> CretoResult<Vec<u8>> {
+        let mut chain = vec![self.get_checkpoint(checkpoint_id).await?];
+        while let Some(parent) = chain.last().and_then(|checkpoint| checkpoint.parent) {
+            chain.push(self.get_checkpoint(parent).await?);
+        }
+        let root = chain.pop().expect("chain starts with the checkpoint");
+        let mut snapshot = root.decompressed_snapshot()?;
+        for checkpoint in chain.iter().rev() {
+            snapshot = checkpoint.apply_to(&snapshot)?;
+        }
+        Ok(snapshot)
+    }
+
+    /// Checkpoints that name this one as their parent.
+    async fn dependents(&self, checkpoint_id: CheckpointId) -> CretoResult<Vec<CheckpointId>> {
+        Ok(self
+            .list_checkpoints(None, None)
+            .await?
+            .into_iter()
+            .filter(|checkpoint| checkpoint.parent == Some(checkpoint_id))
+            .map(|checkpoint| checkpoint.id)
+            .collect())
+    }
+
+    /// Store utilization, if the store tracks it.
+    fn store_stats(&self) -> Option<CheckpointStoreStats> {
+        None
+    }
+
+    /// A sandbox was terminated; its latest checkpoint loses eviction
+    /// protection.
+    fn sandbox_ended(&self, _sandbox_id: SandboxId) {}
+}
+
+/// Build the checkpoint the development stores record, relative to
+/// `parent` (its ID and full snapshot) if given.
+fn mock_checkpoint(
+    sandbox_id: SandboxId,
+    config: CheckpointConfig,
+    parent: Option<(CheckpointId, &[u8])>,
+) -> Result<Checkpoint, CheckpointError> {
+    let snapshot = config.snapshot.unwrap_or_else(|| vec![0u8; 1024]); // Mock 1KB snapshot
+    let (stored, manifest) = match parent {
+        Some((_, parent_snapshot)) => {
+            let (manifest, stored) = ChunkManifest::diff(&snapshot, parent_snapshot);
+            (stored, Some(manifest))
+        }
+        None => (snapshot, None),
+    };
+    Ok(Checkpoint {
+        id: CheckpointId::new(),
+        sandbox_id,
+        agent_id: config
+            .owner
+            .map_or_else(AgentId::new, |(_, agent_id)| agent_id), // Mock agent ID when untracked
+        organization_id: config.owner.map(|(organization_id, _)| organization_id),
+        sandbox_config: config.sandbox_config,
+        state_snapshot: config.compression.compress(&stored)?,
+        filesystem_hash: "mock_hash_123".to_string(),
+        memory_size: 1024 * 1024 * 128, // Mock 128MB
+        created_at: Utc::now(),
+        metadata: config.metadata,
+        compression: Some(config.compression),
+        attestation: config.attestation,
+        parent: parent.map(|(id, _)| id),
+        manifest,
+    })
+}
+
+/// Apply the `list_checkpoints` filters and order (newest first).
+fn filter_newest_first(
+    checkpoints: impl IntoIterator<Item = Checkpoint>,
+    sandbox_id: Option<SandboxId>,
+    agent_id: Option<AgentId>,
+) -> Vec<Checkpoint> {
+    let mut result: Vec<Checkpoint> = checkpoints
+        .into_iter()
+        .filter(|cp| {
+            if let Some(sid) = sandbox_id {
+                if cp.sandbox_id != sid {
+                    return false;
+                }
+            }
+            if let Some(aid) = agent_id {
+                if cp.agent_id != aid {
+                    return false;
+                }
+            }
+            true
+        })
+        .collect();
+
+    // Sort by creation time (newest first)
+    result.sort_by_key(|cp| std::cmp::Reverse(cp.created_at));
+    result
+}
+
+/// A checkpoint held in memory.
+#[derive(Debug)]
+struct Resident {
+    checkpoint: Checkpoint,
+    /// Access tick, for least-recently-used spilling.
+    last_used: u64,
+}
+
+/// What the store remembers about a spilled checkpoint.
+#[derive(Debug, Clone, Copy)]
+struct Spilled {
+    sandbox_id: SandboxId,
+    created_at: DateTime<Utc>,
+    size: u64,
+    parent: Option<CheckpointId>,
 }
 
-/// In-memory checkpoint store for testing and development.
 #[derive(Debug, Default)]
+struct StoreState {
+    resident: HashMap<CheckpointId, Resident>,
+    spilled: HashMap<CheckpointId, Spilled>,
+    memory_bytes: u64,
+    spilled_bytes: u64,
+    /// Terminated sandboxes that still have checkpoints.
+    ended: HashSet<SandboxId>,
+    /// Checkpoints being written to or reloaded from the spill tier.
+    in_flight: HashSet<CheckpointId>,
+    tick: u64,
+    evicted: u64,
+    rejected: u64,
+    /// Whether utilization is currently above the warning threshold.
+    warned: bool,
+}
+
+impl StoreState {
+    fn touch(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn insert_resident(&mut self, checkpoint: Checkpoint) {
+        let last_used = self.touch();
+        self.memory_bytes += checkpoint.size_bytes();
+        self.resident.insert(
+            checkpoint.id,
+            Resident {
+                checkpoint,
+                last_used,
+            },
+        );
+    }
+
+    fn remove_resident(&mut self, checkpoint_id: CheckpointId) -> Option<Checkpoint> {
+        let resident = self.resident.remove(&checkpoint_id)?;
+        self.memory_bytes -= resident.checkpoint.size_bytes();
+        Some(resident.checkpoint)
+    }
+
+    /// Move a resident checkpoint whose spill file was written to the
+    /// spill tier, returning `false` if it is no longer resident.
+    fn mark_spilled(&mut self, checkpoint_id: CheckpointId) -> bool {
+        let Some(checkpoint) = self.remove_resident(checkpoint_id) else {
+            return false;
+        };
+        self.spilled_bytes += checkpoint.size_bytes();
+        self.spilled.insert(
+            checkpoint_id,
+            Spilled {
+                sandbox_id: checkpoint.sandbox_id,
+                created_at: checkpoint.created_at,
+                size: checkpoint.size_bytes(),
+                parent: checkpoint.parent,
+            },
+        );
+        true
+    }
+
+    fn remove_spilled(&mut self, checkpoint_id: CheckpointId) -> Option<Spilled> {
+        let spilled = self.spilled.remove(&checkpoint_id)?;
+        self.spilled_bytes -= spilled.size;
+        Some(spilled)
+    }
+
+    /// Stop tracking an ended sandbox once its last checkpoint is gone.
+    fn forget_if_unused(&mut self, sandbox_id: SandboxId) {
+        if self.ended.contains(&sandbox_id) && !self.has_checkpoints(sandbox_id) {
+            self.ended.remove(&sandbox_id);
+        }
+    }
+
+    fn has_checkpoints(&self, sandbox_id: SandboxId) -> bool {
+        self.resident
+            .values()
+            .any(|r| r.checkpoint.sandbox_id == sandbox_id)
+            || self.spilled.values().any(|s| s.sandbox_id == sandbox_id)
+    }
+
+    fn contains(&self, checkpoint_id: CheckpointId) -> bool {
+        self.resident.contains_key(&checkpoint_id) || self.spilled.contains_key(&checkpoint_id)
+    }
+
+    /// Every stored checkpoint, wherever it is held, with its sandbox,
+    /// creation time and parent.
+    fn entries(
+        &self,
+    ) -> impl Iterator<Item = (CheckpointId, SandboxId, DateTime<Utc>, Option<CheckpointId>)> + '_
+    {
+        let resident = self.resident.iter().map(|(id, r)| {
+            let checkpoint = &r.checkpoint;
+            (
+                *id,
+                checkpoint.sandbox_id,
+                checkpoint.created_at,
+                checkpoint.parent,
+            )
+        });
+        let spilled = self
+            .spilled
+            .iter()
+            .map(|(id, s)| (*id, s.sandbox_id, s.created_at, s.parent));
+        resident.chain(spilled)
+    }
+
+    fn parent_of(&self, checkpoint_id: CheckpointId) -> Option<CheckpointId> {
+        match self.resident.get(&checkpoint_id) {
+            Some(resident) => resident.checkpoint.parent,
+            None => self.spilled.get(&checkpoint_id)?.parent,
+        }
+    }
+
+    /// Incremental checkpoints between `checkpoint_id` and the full
+    /// checkpoint its chain starts from.
+    fn chain_length(&self, checkpoint_id: CheckpointId) -> usize {
+        std::iter::successors(self.parent_of(checkpoint_id), |id| self.parent_of(*id)).count()
+    }
+
+    /// The sandbox's most recent checkpoint.
+    fn latest(&self, sandbox_id: SandboxId) -> Option<CheckpointId> {
+        self.entries()
+            .filter(|(_, sandbox, _, _)| *sandbox == sandbox_id)
+            .max_by_key(|(id, _, created_at, _)| (*created_at, id.as_uuid()))
+            .map(|(id, _, _, _)| id)
+    }
+
+    fn dependents(&self, checkpoint_id: CheckpointId) -> Vec<CheckpointId> {
+        self.entries()
+            .filter(|(_, _, _, parent)| *parent == Some(checkpoint_id))
+            .map(|(id, _, _, _)| id)
+            .collect()
+    }
+
+    /// The most recent checkpoint of every live sandbox, wherever it is held.
+    fn protected(&self) -> HashSet<CheckpointId> {
+        let mut newest: HashMap<SandboxId, (DateTime<Utc>, Uuid)> = HashMap::new();
+        for (id, sandbox_id, created_at, _) in self.entries() {
+            if self.ended.contains(&sandbox_id) {
+                continue;
+            }
+            let candidate = (created_at, id.as_uuid());
+            newest
+                .entry(sandbox_id)
+                .and_modify(|latest| *latest = (*latest).max(candidate))
+                .or_insert(candidate);
+        }
+        newest
+            .into_values()
+            .map(|(_, id)| CheckpointId::from_uuid(id))
+            .collect()
+    }
+
+    fn over(&self, limits: &CheckpointStoreLimits) -> bool {
+        limits.exceeded_by(self.memory_bytes, self.resident.len())
+    }
+
+    /// Least recently used checkpoints to spill to bring memory back within
+    /// `limits`, marked in flight. `keep` and checkpoints already in flight
+    /// are never chosen.
+    fn plan_spill(
+        &mut self,
+        keep: CheckpointId,
+        limits: &CheckpointStoreLimits,
+    ) -> Vec<Checkpoint> {
+        if !self.over(limits) {
+            return Vec::new();
+        }
+        let mut candidates = self.movable(keep);
+        candidates.sort_by_key(|id| self.resident[id].last_used);
+        let (mut bytes, mut count) = (self.memory_bytes, self.resident.len());
+        let mut victims = Vec::new();
+        for id in candidates {
+            if !limits.exceeded_by(bytes, count) {
+                break;
+            }
+            let checkpoint = &self.resident[&id].checkpoint;
+            bytes -= checkpoint.size_bytes();
+            count -= 1;
+            victims.push(checkpoint.clone());
+            self.in_flight.insert(id);
+        }
+        victims
+    }
+
+    /// Resident checkpoints that may leave memory to make room for `keep`.
+    fn movable(&self, keep: CheckpointId) -> Vec<CheckpointId> {
+        let protected = self.protected();
+        self.resident
+            .keys()
+            .filter(|id| **id != keep && !protected.contains(id) && !self.in_flight.contains(id))
+            .copied()
+            .collect()
+    }
+
+    /// Bring memory back within `limits` after `keep` was inserted and
+    /// whatever could be spilled was, by deleting the oldest checkpoints if
+    /// `evict` is set. `keep` itself is never moved. On failure nothing has
+    /// been deleted.
+    fn make_room(
+        &mut self,
+        keep: CheckpointId,
+        limits: &CheckpointStoreLimits,
+        evict: bool,
+    ) -> Result<(), CheckpointError> {
+        if !self.over(limits) {
+            return Ok(());
+        }
+
+        let full = CheckpointError::CapacityExceeded {
+            memory_checkpoints: self.resident.len(),
+            memory_bytes: self.memory_bytes,
+        };
+        if !evict || limits.eviction == EvictionPolicy::RejectNew {
+            return Err(full);
+        }
+        // Parents stay until their incremental children are gone
+        let parents: HashSet<CheckpointId> = self
+            .entries()
+            .filter_map(|(_, _, _, parent)| parent)
+            .collect();
+        let mut victims = self.movable(keep);
+        victims.retain(|id| !parents.contains(id));
+        victims.sort_by_key(|id| {
+            let checkpoint = &self.resident[id].checkpoint;
+            (checkpoint.created_at, id.as_uuid())
+        });
+        // Check the victims suffice before deleting any of them
+        let mut needed = 0;
+        let (mut bytes, mut count) = (self.memory_bytes, self.resident.len());
+        for id in &victims {
+            if !limits.exceeded_by(bytes, count) {
+                break;
+            }
+            bytes -= self.resident[id].checkpoint.size_bytes();
+            count -= 1;
+            needed += 1;
+        }
+        if limits.exceeded_by(bytes, count) {
+            return Err(full);
+        }
+        for victim in victims.into_iter().take(needed) {
+            if let Some(checkpoint) = self.remove_resident(victim) {
+                self.evicted += 1;
+                self.forget_if_unused(checkpoint.sandbox_id);
+                tracing::debug!(checkpoint_id = %victim, "Checkpoint evicted");
+            }
+        }
+        Ok(())
+    }
+
+    fn stats(&self, limits: &CheckpointStoreLimits) -> CheckpointStoreStats {
+        CheckpointStoreStats {
+            memory_checkpoints: self.resident.len(),
+            memory_bytes: self.memory_bytes,
+            spilled_checkpoints: self.spilled.len(),
+            spilled_bytes: self.spilled_bytes,
+            max_checkpoints: limits.max_checkpoints,
+            max_bytes: limits.max_bytes,
+            evicted: self.evicted,
+            rejected: self.rejected,
+        }
+    }
+
+    /// Stats to warn with if utilization just rose above the threshold.
+    fn crossed_threshold(
+        &mut self,
+        limits: &CheckpointStoreLimits,
+    ) -> Option<CheckpointStoreStats> {
+        let stats = self.stats(limits);
+        let above = stats.utilization() > limits.warning_threshold;
+        let crossed = above && !self.warned;
+        self.warned = above;
+        crossed.then_some(stats)
+    }
+}
+
+/// In-memory checkpoint store for testing and development.
+///
+/// Unbounded by default; see [`InMemoryCheckpointStore::with_limits`] and
+/// [`InMemoryCheckpointStore::with_spill`]. Sizes are accounted with
+/// [`Checkpoint::size_bytes`], the stored (compressed) snapshot size.
+#[derive(Default)]
 pub struct InMemoryCheckpointStore {
-    checkpoints: Arc<RwLock<HashMap<CheckpointId, Checkpoint>>>,
+    state: Mutex<StoreState>,
+    limits: CheckpointStoreLimits,
+    spill: Option<FilesystemCheckpointStore>,
+    on_warning: Option<UtilizationWarning>,
+}
+
+impl std::fmt::Debug for InMemoryCheckpointStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InMemoryCheckpointStore")
+            .field("limits", &self.limits)
+            .field("spill", &self.spill)
+            .finish_non_exhaustive()
+    }
 }
 
 impl InMemoryCheckpointStore {
     /// Create a new in-memory checkpoint store.
     pub fn new() -> Self {
-        Self {
-            checkpoints: Arc::new(RwLock::new(HashMap::new())),
-        }
+        Self::default()
     }
 
-    /// Get the number of stored checkpoints.
+    /// Bound the checkpoints held in memory.
+    pub fn with_limits(mut self, limits: CheckpointStoreLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Spill least recently used checkpoints to `store` instead of applying
+    /// the eviction policy.
+    pub fn with_spill(mut self, store: FilesystemCheckpointStore) -> Self {
+        self.spill = Some(store);
+        self
+    }
+
+    /// Call `callback` whenever utilization rises above the configured
+    /// warning threshold.
+    pub fn with_utilization_warning(mut self, callback: UtilizationWarning) -> Self {
+        self.on_warning = Some(callback);
+        self
+    }
+
+    /// Get the number of stored checkpoints, in memory or spilled.
     pub fn count(&self) -> usize {
-        self.checkpoints.read().unwrap().len()
+        let state = self.lock();
+        state.resident.len() + state.spilled.len()
     }
 
-    /// Clear all checkpoints.
+    /// Clear all checkpoints, including spilled ones.
     pub fn clear(&self) {
-        self.checkpoints.write().unwrap().clear();
+        let state = std::mem::take(&mut *self.lock());
+        if let Some(spill) = &self.spill {
+            for checkpoint_id in state.spilled.keys() {
+                let _ = spill.remove(*checkpoint_id);
+            }
+        }
+    }
+
+    /// Current utilization.
+    pub fn stats(&self) -> CheckpointStoreStats {
+        self.lock().stats(&self.limits)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, StoreState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn warn_if_crossed(&self, state: &mut StoreState) -> Option<CheckpointStoreStats> {
+        let stats = state.crossed_threshold(&self.limits)?;
+        tracing::warn!(
+            memory_checkpoints = stats.memory_checkpoints,
+            memory_bytes = stats.memory_bytes,
+            utilization = stats.utilization(),
+            "Checkpoint store utilization above threshold"
+        );
+        Some(stats)
+    }
+
+    fn notify(&self, stats: Option<CheckpointStoreStats>) {
+        if let (Some(stats), Some(callback)) = (stats, &self.on_warning) {
+            callback(&stats);
+        }
     }
+
+    /// Spill least recently used checkpoints until memory is back within
+    /// the limits or nothing but `keep` is left to spill.
+    ///
+    /// Files are written outside the lock; a checkpoint deleted meanwhile
+    /// has its file removed again. Failed writes leave checkpoints in memory
+    /// for [`StoreState::make_room`] to deal with.
+    async fn spill_for(&self, keep: CheckpointId) {
+        let Some(spill) = &self.spill else {
+            return;
+        };
+        let victims = self.lock().plan_spill(keep, &self.limits);
+        if victims.is_empty() {
+            return;
+        }
+        let written = match on_disk(spill, move |spill| {
+            victims
+                .into_iter()
+                .map(|checkpoint| (checkpoint.id, spill.save(&checkpoint)))
+                .collect::<Vec<_>>()
+        })
+        .await
+        {
+            Ok(written) => written,
+            Err(e) => {
+                tracing::warn!(error = %e, "Checkpoint spill failed");
+                return;
+            }
+        };
+
+        let mut orphaned = Vec::new();
+        {
+            let mut state = self.lock();
+            for (checkpoint_id, result) in written {
+                state.in_flight.remove(&checkpoint_id);
+                match result {
+                    Ok(()) if state.mark_spilled(checkpoint_id) => {
+                        tracing::debug!(checkpoint_id = %checkpoint_id, "Checkpoint spilled to disk");
+                    }
+                    Ok(()) => orphaned.push(checkpoint_id),
+                    Err(e) => {
+                        tracing::warn!(checkpoint_id = %checkpoint_id, error = %e, "Checkpoint spill failed");
+                    }
+                }
+            }
+        }
+        if !orphaned.is_empty() {
+            let _ = on_disk(spill, move |spill| {
+                for checkpoint_id in orphaned {
+                    let _ = spill.remove(checkpoint_id);
+                }
+            })
+            .await;
+        }
+    }
+
+    /// A checkpoint from memory, or from the spill tier. Reloaded
+    /// checkpoints move back into memory when room can be made by spilling.
+    async fn fetch(&self, checkpoint_id: CheckpointId) -> Result<Checkpoint, CheckpointError> {
+        let spill = {
+            let mut state = self.lock();
+            let tick = state.touch();
+            if let Some(resident) = state.resident.get_mut(&checkpoint_id) {
+                resident.last_used = tick;
+                return Ok(resident.checkpoint.clone());
+            }
+            self.spill
+                .as_ref()
+                .filter(|_| state.spilled.contains_key(&checkpoint_id))
+                .ok_or(CheckpointError::NotFound { checkpoint_id })?
+        };
+        let checkpoint = match on_disk(spill, move |spill| spill.load(checkpoint_id)).await? {
+            Ok(checkpoint) => checkpoint,
+            // Reloaded by another reader since, or deleted
+            Err(CheckpointError::NotFound { .. }) => {
+                return self
+                    .lock()
+                    .resident
+                    .get(&checkpoint_id)
+                    .map(|resident| resident.checkpoint.clone())
+                    .ok_or(CheckpointError::NotFound { checkpoint_id });
+            }
+            Err(e) => return Err(e),
+        };
+
+        {
+            let mut state = self.lock();
+            if state.in_flight.contains(&checkpoint_id)
+                || state.remove_spilled(checkpoint_id).is_none()
+            {
+                return Ok(checkpoint);
+            }
+            state.insert_resident(checkpoint.clone());
+            state.in_flight.insert(checkpoint_id);
+        }
+        self.spill_for(checkpoint_id).await;
+
+        let (reloaded, warning) = {
+            let mut state = self.lock();
+            state.in_flight.remove(&checkpoint_id);
+            let reloaded = if !state.resident.contains_key(&checkpoint_id) {
+                // Deleted meanwhile; its file goes too
+                true
+            } else if state.make_room(checkpoint_id, &self.limits, false).is_ok() {
+                true
+            } else {
+                // No room; leave it on disk
+                state.mark_spilled(checkpoint_id);
+                false
+            };
+            (reloaded, self.warn_if_crossed(&mut state))
+        };
+        self.notify(warning);
+        if reloaded {
+            let _ = on_disk(spill, move |spill| spill.remove(checkpoint_id)).await;
+            tracing::debug!(checkpoint_id = %checkpoint_id, "Spilled checkpoint reloaded");
+        }
+        Ok(checkpoint)
+    }
+}
+
+/// Run blocking spill tier IO on the blocking thread pool.
+async fn on_disk<T, F>(spill: &FilesystemCheckpointStore, io: F) -> Result<T, CheckpointError>
+where
+    T: Send + 'static,
+    F: FnOnce(&FilesystemCheckpointStore) -> T + Send + 'static,
+{
+    let spill = spill.clone();
+    tokio::task::spawn_blocking(move || io(&spill))
+        .await
+        .map_err(storage_error)
 }
 
 #[async_trait::async_trait]
@@ -349,24 +1372,61 @@ impl CheckpointManager for InMemoryCheckpointStore {
         sandbox_id: SandboxId,
         config: CheckpointConfig,
     ) -> CretoResult<CheckpointId> {
+        let parent = {
+            let state = self.lock();
+            state
+                .latest(sandbox_id)
+                .filter(|latest| config.mode.extends(state.chain_length(*latest)))
+        };
+        let parent_snapshot = match parent {
+            Some(parent) => Some((parent, self.resolve_snapshot(parent).await?)),
+            None => None,
+        };
         // Mock checkpoint creation
-        let checkpoint = Checkpoint {
-            id: CheckpointId::new(),
+        let checkpoint = mock_checkpoint(
             sandbox_id,
-            agent_id: AgentId::new(),        // Mock agent ID
-            state_snapshot: vec![0u8; 1024], // Mock 1KB snapshot
-            filesystem_hash: "mock_hash_123".to_string(),
-            memory_size: 1024 * 1024 * 128, // Mock 128MB
-            created_at: Utc::now(),
-            metadata: config.metadata,
-            compression: Some(config.compression),
-        };
-
+            config,
+            parent_snapshot
+                .as_ref()
+                .map(|(id, snapshot)| (*id, snapshot.as_slice())),
+        )?;
         let checkpoint_id = checkpoint.id;
-        self.checkpoints
-            .write()
-            .unwrap()
-            .insert(checkpoint_id, checkpoint);
+
+        {
+            let mut state = self.lock();
+            if parent.is_some_and(|parent| !state.contains(parent)) {
+                return Err(CheckpointError::CreationFailed {
+                    sandbox_id,
+                    reason: "Parent checkpoint was deleted while checkpointing".to_string(),
+                }
+                .into());
+            }
+            if self
+                .limits
+                .max_bytes
+                .is_some_and(|max| checkpoint.size_bytes() > max)
+            {
+                state.rejected += 1;
+                return Err(CheckpointError::CapacityExceeded {
+                    memory_checkpoints: state.resident.len(),
+                    memory_bytes: state.memory_bytes,
+                }
+                .into());
+            }
+            state.insert_resident(checkpoint);
+        }
+        self.spill_for(checkpoint_id).await;
+
+        let mut state = self.lock();
+        if let Err(e) = state.make_room(checkpoint_id, &self.limits, true) {
+            state.remove_resident(checkpoint_id);
+            state.rejected += 1;
+            tracing::warn!(sandbox_id = %sandbox_id, error = %e, "Checkpoint rejected");
+            return Err(e.into());
+        }
+        let warning = self.warn_if_crossed(&mut state);
+        drop(state);
+        self.notify(warning);
 
         tracing::debug!(
             checkpoint_id = %checkpoint_id,
@@ -378,10 +1438,7 @@ impl CheckpointManager for InMemoryCheckpointStore {
     }
 
     async fn restore(&self, checkpoint_id: CheckpointId) -> CretoResult<SandboxId> {
-        let checkpoints = self.checkpoints.read().unwrap();
-        let checkpoint = checkpoints
-            .get(&checkpoint_id)
-            .ok_or(CheckpointError::NotFound { checkpoint_id })?;
+        let checkpoint = self.fetch(checkpoint_id).await?;
 
         tracing::debug!(
             checkpoint_id = %checkpoint_id,
@@ -399,46 +1456,250 @@ impl CheckpointManager for InMemoryCheckpointStore {
         sandbox_id: Option<SandboxId>,
         agent_id: Option<AgentId>,
     ) -> CretoResult<Vec<Checkpoint>> {
-        let checkpoints = self.checkpoints.read().unwrap();
-        let mut result: Vec<Checkpoint> = checkpoints
-            .values()
-            .filter(|cp| {
-                if let Some(sid) = sandbox_id {
-                    if cp.sandbox_id != sid {
-                        return false;
-                    }
-                }
-                if let Some(aid) = agent_id {
-                    if cp.agent_id != aid {
-                        return false;
+        let (mut checkpoints, spilled): (Vec<Checkpoint>, Vec<CheckpointId>) = {
+            let state = self.lock();
+            let resident = state
+                .resident
+                .values()
+                .map(|r| r.checkpoint.clone())
+                .collect();
+            let spilled = state
+                .spilled
+                .iter()
+                .filter(|(_, spilled)| {
+                    sandbox_id.unwrap_or(spilled.sandbox_id) == spilled.sandbox_id
+                })
+                .map(|(checkpoint_id, _)| *checkpoint_id)
+                .collect();
+            (resident, spilled)
+        };
+        if let (Some(spill), false) = (&self.spill, spilled.is_empty()) {
+            let loaded = on_disk(spill, move |spill| {
+                spilled
+                    .into_iter()
+                    .map(|checkpoint_id| spill.load(checkpoint_id))
+                    .collect::<Vec<_>>()
+            })
+            .await?;
+            for checkpoint in loaded {
+                match checkpoint {
+                    Ok(checkpoint) => checkpoints.push(checkpoint),
+                    // Reloaded into memory or deleted since
+                    Err(CheckpointError::NotFound { checkpoint_id }) => {
+                        checkpoints.extend(
+                            self.lock()
+                                .resident
+                                .get(&checkpoint_id)
+                                .map(|r| r.checkpoint.clone()),
+                        );
                     }
+                    Err(e) => return Err(e.into()),
                 }
-                true
-            })
-            .cloned()
-            .collect();
-
-        // Sort by creation time (newest first)
-        result.sort_by(|a, b| b.created_at.cmp(&a.created_at));
-        Ok(result)
+            }
+        }
+        Ok(filter_newest_first(checkpoints, sandbox_id, agent_id))
     }
 
     async fn delete_checkpoint(&self, checkpoint_id: CheckpointId) -> CretoResult<()> {
-        let mut checkpoints = self.checkpoints.write().unwrap();
-        checkpoints
-            .remove(&checkpoint_id)
-            .ok_or(CheckpointError::NotFound { checkpoint_id })?;
+        let on_disk_copy = {
+            let mut state = self.lock();
+            let dependents = state.dependents(checkpoint_id);
+            if !dependents.is_empty() {
+                return Err(CheckpointError::HasDependents {
+                    checkpoint_id,
+                    dependents,
+                }
+                .into());
+            }
+            let (sandbox_id, on_disk_copy) =
+                if let Some(checkpoint) = state.remove_resident(checkpoint_id) {
+                    (checkpoint.sandbox_id, false)
+                } else {
+                    let spilled = state
+                        .remove_spilled(checkpoint_id)
+                        .ok_or(CheckpointError::NotFound { checkpoint_id })?;
+                    (spilled.sandbox_id, true)
+                };
+            state.forget_if_unused(sandbox_id);
+            // Re-arms the warning once utilization drops below the threshold
+            self.warn_if_crossed(&mut state);
+            on_disk_copy
+        };
+
+        if let (Some(spill), true) = (&self.spill, on_disk_copy) {
+            on_disk(spill, move |spill| spill.remove(checkpoint_id)).await??;
+        }
 
         tracing::debug!(checkpoint_id = %checkpoint_id, "Checkpoint deleted");
         Ok(())
     }
 
     async fn get_checkpoint(&self, checkpoint_id: CheckpointId) -> CretoResult<Checkpoint> {
-        let checkpoints = self.checkpoints.read().unwrap();
-        checkpoints
-            .get(&checkpoint_id)
-            .cloned()
-            .ok_or_else(|| CheckpointError::NotFound { checkpoint_id }.into())
+        Ok(self.fetch(checkpoint_id).await?)
+    }
+
+    async fn dependents(&self, checkpoint_id: CheckpointId) -> CretoResult<Vec<CheckpointId>> {
+        Ok(self.lock().dependents(checkpoint_id))
+    }
+
+    fn store_stats(&self) -> Option<CheckpointStoreStats> {
+        Some(self.stats())
+    }
+
+    fn sandbox_ended(&self, sandbox_id: SandboxId) {
+        let mut state = self.lock();
+        if state.has_checkpoints(sandbox_id) {
+            state.ended.insert(sandbox_id);
+        }
+    }
+}
+
+/// Checkpoint store keeping one JSON file per checkpoint in a directory.
+///
+/// Usable on its own or as the spill tier of an [`InMemoryCheckpointStore`].
+#[derive(Debug, Clone)]
+pub struct FilesystemCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FilesystemCheckpointStore {
+    /// Store checkpoints under `dir`, creating it if needed.
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, CheckpointError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(storage_error)?;
+        Ok(Self { dir })
+    }
+
+    /// The directory checkpoints are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write a checkpoint, replacing any previous copy. On Unix the file is
+    /// readable and writable by its owner only.
+    pub fn save(&self, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+        let path = self.path(checkpoint.id);
+        let tmp = path.with_extension("json.tmp");
+        let bytes = serde_json::to_vec(checkpoint).map_err(storage_error)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&tmp).map_err(storage_error)?;
+        file.write_all(&bytes).map_err(storage_error)?;
+        drop(file);
+        std::fs::rename(&tmp, &path).map_err(storage_error)
+    }
+
+    /// Read a checkpoint.
+    pub fn load(&self, checkpoint_id: CheckpointId) -> Result<Checkpoint, CheckpointError> {
+        let bytes = std::fs::read(self.path(checkpoint_id)).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                CheckpointError::NotFound { checkpoint_id }
+            } else {
+                storage_error(e)
+            }
+        })?;
+        serde_json::from_slice(&bytes).map_err(storage_error)
+    }
+
+    /// Delete a checkpoint.
+    pub fn remove(&self, checkpoint_id: CheckpointId) -> Result<(), CheckpointError> {
+        std::fs::remove_file(self.path(checkpoint_id)).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                CheckpointError::NotFound { checkpoint_id }
+            } else {
+                storage_error(e)
+            }
+        })
+    }
+
+    /// Read every stored checkpoint.
+    pub fn load_all(&self) -> Result<Vec<Checkpoint>, CheckpointError> {
+        let mut checkpoints = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).map_err(storage_error)? {
+            let path = entry.map_err(storage_error)?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = std::fs::read(&path).map_err(storage_error)?;
+            checkpoints.push(serde_json::from_slice(&bytes).map_err(storage_error)?);
+        }
+        Ok(checkpoints)
+    }
+
+    fn path(&self, checkpoint_id: CheckpointId) -> PathBuf {
+        self.dir.join(format!("{}.json", checkpoint_id.as_uuid()))
+    }
+}
+
+fn storage_error(e: impl std::fmt::Display) -> CheckpointError {
+    CheckpointError::StorageError {
+        message: e.to_string(),
+    }
+}
+
+#[async_trait::async_trait]
+impl CheckpointManager for FilesystemCheckpointStore {
+    async fn checkpoint(
+        &self,
+        sandbox_id: SandboxId,
+        config: CheckpointConfig,
+    ) -> CretoResult<CheckpointId> {
+        let checkpoints = self.load_all()?;
+        let by_id: HashMap<CheckpointId, &Checkpoint> =
+            checkpoints.iter().map(|cp| (cp.id, cp)).collect();
+        let chain_length = |checkpoint: &Checkpoint| {
+            std::iter::successors(checkpoint.parent, |id| by_id.get(id)?.parent).count()
+        };
+        let parent = checkpoints
+            .iter()
+            .filter(|cp| cp.sandbox_id == sandbox_id)
+            .max_by_key(|cp| (cp.created_at, cp.id.as_uuid()))
+            .filter(|latest| config.mode.extends(chain_length(latest)))
+            .map(|latest| latest.id);
+        let parent_snapshot = match parent {
+            Some(parent) => Some((parent, self.resolve_snapshot(parent).await?)),
+            None => None,
+        };
+        // Mock checkpoint creation
+        let checkpoint = mock_checkpoint(
+            sandbox_id,
+            config,
+            parent_snapshot
+                .as_ref()
+                .map(|(id, snapshot)| (*id, snapshot.as_slice())),
+        )?;
+        self.save(&checkpoint)?;
+        Ok(checkpoint.id)
+    }
+
+    async fn restore(&self, checkpoint_id: CheckpointId) -> CretoResult<SandboxId> {
+        Ok(self.load(checkpoint_id)?.sandbox_id)
+    }
+
+    async fn list_checkpoints(
+        &self,
+        sandbox_id: Option<SandboxId>,
+        agent_id: Option<AgentId>,
+    ) -> CretoResult<Vec<Checkpoint>> {
+        Ok(filter_newest_first(self.load_all()?, sandbox_id, agent_id))
+    }
+
+    async fn delete_checkpoint(&self, checkpoint_id: CheckpointId) -> CretoResult<()> {
+        let dependents = self.dependents(checkpoint_id).await?;
+        if !dependents.is_empty() {
+            return Err(CheckpointError::HasDependents {
+                checkpoint_id,
+                dependents,
+            }
+            .into());
+        }
+        Ok(self.remove(checkpoint_id)?)
+    }
+
+    async fn get_checkpoint(&self, checkpoint_id: CheckpointId) -> CretoResult<Checkpoint> {
+        Ok(self.load(checkpoint_id)?)
     }
 }
 
@@ -446,6 +1707,7 @@ impl CheckpointManager for InMemoryCheckpointStore {
 mod tests {
     use super::*;
     use crate::sandbox::SandboxId;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     #[test]
     fn test_checkpoint_id_creation() {
@@ -598,4 +1860,292 @@ mod tests {
         };
         assert!(err.to_string().contains("cannot be checkpointed"));
     }
+
+    #[tokio::test]
+    async fn test_compressed_snapshots_round_trip() {
+        let store = InMemoryCheckpointStore::new();
+        let sandbox_id = SandboxId::new();
+        for algorithm in [
+            CompressionAlgorithm::None,
+            CompressionAlgorithm::Gzip,
+            CompressionAlgorithm::Zstd,
+            CompressionAlgorithm::Lz4,
+        ] {
+            let config = CheckpointConfig {
+                compression: algorithm,
+                ..Default::default()
+            };
+            let checkpoint_id = store.checkpoint(sandbox_id, config).await.unwrap();
+            let checkpoint = store.get_checkpoint(checkpoint_id).await.unwrap();
+            if algorithm != CompressionAlgorithm::None {
+                assert!(checkpoint.size_bytes() < 1024, "{:?}", algorithm);
+            }
+            assert_eq!(checkpoint.decompressed_snapshot().unwrap(), vec![0u8; 1024]);
+        }
+
+        // A snapshot that does not match its declared algorithm is rejected
+        let corrupt = Checkpoint::new(sandbox_id, AgentId::new(), vec![1, 2, 3], String::new(), 0)
+            .with_compression(CompressionAlgorithm::Zstd);
+        let err = corrupt.decompressed_snapshot().unwrap_err();
+        assert_eq!(err.code(), "ENABLE-505");
+    }
+
+    fn capped(max_checkpoints: usize, eviction: EvictionPolicy) -> InMemoryCheckpointStore {
+        InMemoryCheckpointStore::new().with_limits(CheckpointStoreLimits {
+            max_checkpoints: Some(max_checkpoints),
+            eviction,
+            ..Default::default()
+        })
+    }
+
+    async fn take(store: &InMemoryCheckpointStore, sandbox_id: SandboxId) -> CheckpointId {
+        store
+            .checkpoint(sandbox_id, CheckpointConfig::default())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reject_new_when_full() {
+        let store = InMemoryCheckpointStore::new().with_limits(CheckpointStoreLimits {
+            max_bytes: Some(2048),
+            ..Default::default()
+        });
+        let sandbox_id = SandboxId::new();
+        let first = take(&store, sandbox_id).await;
+        take(&store, sandbox_id).await;
+
+        let err = store
+            .checkpoint(SandboxId::new(), CheckpointConfig::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("full"));
+        assert!(store.get_checkpoint(first).await.is_ok());
+        let stats = store.stats();
+        assert_eq!(stats.memory_checkpoints, 2);
+        assert_eq!(stats.memory_bytes, 2048);
+        assert_eq!(stats.rejected, 1);
+        assert_eq!(stats.evicted, 0);
+    }
+
+    #[tokio::test]
+    async fn test_evict_oldest_spares_latest_checkpoint_of_live_sandboxes() {
+        let store = capped(2, EvictionPolicy::EvictOldest);
+        let (a, b, c) = (SandboxId::new(), SandboxId::new(), SandboxId::new());
+        let a1 = take(&store, a).await;
+        let a2 = take(&store, a).await;
+        let b1 = take(&store, b).await;
+        // a1 is the oldest and no longer a's latest
+        assert!(store.get_checkpoint(a1).await.is_err());
+        assert_eq!(store.count(), 2);
+
+        // a2 and b1 are the latest of live sandboxes: nothing can go
+        assert!(store
+            .checkpoint(c, CheckpointConfig::default())
+            .await
+            .is_err());
+        assert!(store.get_checkpoint(a2).await.is_ok());
+        assert!(store.get_checkpoint(b1).await.is_ok());
+
+        store.sandbox_ended(a);
+        let c1 = take(&store, c).await;
+        assert!(store.get_checkpoint(a2).await.is_err());
+        let ids: Vec<CheckpointId> = store
+            .list_checkpoints(None, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|cp| cp.id)
+            .collect();
+        assert_eq!(ids, [c1, b1]);
+        let stats = store.stats();
+        assert_eq!((stats.evicted, stats.rejected), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_spill_and_reload_round_trip() {
+        let dir = std::env::temp_dir().join(format!("creto-checkpoints-{}", Uuid::now_v7()));
+        let spill = FilesystemCheckpointStore::new(&dir).unwrap();
+        let store = capped(2, EvictionPolicy::RejectNew).with_spill(spill.clone());
+        let sandbox_id = SandboxId::new();
+        let mut config = CheckpointConfig::default();
+        config.metadata.insert("step".to_string(), "1".to_string());
+        let first = store.checkpoint(sandbox_id, config).await.unwrap();
+        let second = take(&store, sandbox_id).await;
+        let third = take(&store, sandbox_id).await;
+
+        // The least recently used checkpoint went to disk
+        let stats = store.stats();
+        assert_eq!(
+            (stats.memory_checkpoints, stats.spilled_checkpoints),
+            (2, 1)
+        );
+        assert_eq!(stats.spilled_bytes, 1024);
+        assert_eq!(spill.load(first).unwrap().metadata["step"], "1");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let path = dir.join(format!("{}.json", first.as_uuid()));
+            let mode = std::fs::metadata(path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(store.list_checkpoints(None, None).await.unwrap().len(), 3);
+
+        // Reading it brings it back, spilling the next least recently used
+        let reloaded = store.get_checkpoint(first).await.unwrap();
+        assert_eq!(reloaded.metadata["step"], "1");
+        assert_eq!(reloaded.sandbox_id, sandbox_id);
+        assert!(spill.load(first).is_err());
+        assert!(spill.load(second).is_ok());
+        assert_eq!(store.restore(second).await.unwrap(), sandbox_id);
+        assert!(spill.load(third).is_err());
+
+        store.delete_checkpoint(first).await.unwrap();
+        store.delete_checkpoint(second).await.unwrap();
+        assert!(spill.load_all().unwrap().is_empty());
+        assert_eq!(store.count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_utilization_warning_fires_once_above_threshold() {
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&warnings);
+        let store = InMemoryCheckpointStore::new()
+            .with_limits(CheckpointStoreLimits {
+                max_checkpoints: Some(4),
+                warning_threshold: 0.5,
+                ..Default::default()
+            })
+            .with_utilization_warning(Arc::new(move |stats| {
+                assert!(stats.utilization() > 0.5);
+                counter.fetch_add(1, Ordering::SeqCst);
+            }));
+        let sandbox_id = SandboxId::new();
+        let first = take(&store, sandbox_id).await;
+        let second = take(&store, sandbox_id).await;
+        assert_eq!(warnings.load(Ordering::SeqCst), 0);
+        take(&store, sandbox_id).await;
+        take(&store, sandbox_id).await;
+        assert_eq!(warnings.load(Ordering::SeqCst), 1);
+        assert_eq!(store.store_stats().unwrap().utilization(), 1.0);
+
+        // Dropping back to the threshold re-arms the warning
+        store.delete_checkpoint(first).await.unwrap();
+        store.delete_checkpoint(second).await.unwrap();
+        take(&store, sandbox_id).await;
+        assert_eq!(warnings.load(Ordering::SeqCst), 2);
+    }
+
+    fn incremental(snapshot: Vec<u8>) -> CheckpointConfig {
+        CheckpointConfig {
+            compression: CompressionAlgorithm::Zstd,
+            mode: CheckpointMode::Incremental {
+                max_chain_length: 5,
+            },
+            snapshot: Some(snapshot),
+            ..Default::default()
+        }
+    }
+
+    /// Checkpoint a sandbox six times, changing part of its state each time,
+    /// and check every checkpoint restores exactly.
+    async fn assert_chain_round_trips(store: &dyn CheckpointManager) {
+        let sandbox_id = SandboxId::new();
+        let mut state: Vec<u8> = (0..8 * CHUNK_SIZE + 1000)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let mut taken = Vec::new();
+        for step in 0..6u8 {
+            if step > 0 {
+                // Rewrite one chunk and grow the tail
+                let start = usize::from(step) * CHUNK_SIZE + 17;
+                state[start..start + 100].fill(step);
+                state.extend_from_slice(&[step; 300]);
+            }
+            let id = store
+                .checkpoint(sandbox_id, incremental(state.clone()))
+                .await
+                .unwrap();
+            taken.push((id, state.clone()));
+        }
+
+        let full = store.get_checkpoint(taken[0].0).await.unwrap();
+        assert!(!full.is_incremental());
+        for pair in taken.windows(2) {
+            let checkpoint = store.get_checkpoint(pair[1].0).await.unwrap();
+            assert_eq!(checkpoint.parent, Some(pair[0].0));
+            // The rewritten chunk and the tail chunk
+            assert_eq!(checkpoint.manifest.as_ref().unwrap().stored.len(), 2);
+            assert!(checkpoint.size_bytes() < full.size_bytes());
+            assert!(checkpoint.decompressed_snapshot().is_err());
+        }
+        for (id, expected) in &taken {
+            assert_eq!(&store.resolve_snapshot(*id).await.unwrap(), expected);
+        }
+
+        // A sixth incremental checkpoint would exceed the chain length
+        let restart = store
+            .checkpoint(sandbox_id, incremental(state.clone()))
+            .await
+            .unwrap();
+        let restart = store.get_checkpoint(restart).await.unwrap();
+        assert_eq!(restart.parent, None);
+        assert_eq!(restart.decompressed_snapshot().unwrap(), state);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_chain_restores_byte_identical() {
+        let dir = std::env::temp_dir().join(format!("creto-checkpoints-{}", Uuid::now_v7()));
+        let spill = FilesystemCheckpointStore::new(dir.join("spill")).unwrap();
+        // Most of the chain is spilled, so resolving reloads parents from disk
+        let store = capped(2, EvictionPolicy::RejectNew).with_spill(spill);
+        assert_chain_round_trips(&store).await;
+        assert!(store.stats().spilled_checkpoints > 0);
+
+        let store = FilesystemCheckpointStore::new(dir.join("store")).unwrap();
+        assert_chain_round_trips(&store).await;
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_parents_outlive_their_incremental_checkpoints() {
+        let store = capped(3, EvictionPolicy::EvictOldest);
+        let (a, b) = (SandboxId::new(), SandboxId::new());
+        let base = store
+            .checkpoint(a, incremental(vec![1; 1024]))
+            .await
+            .unwrap();
+        let child = store
+            .checkpoint(a, incremental(vec![2; 1024]))
+            .await
+            .unwrap();
+        let grandchild = store
+            .checkpoint(a, incremental(vec![3; 1024]))
+            .await
+            .unwrap();
+        assert_eq!(store.dependents(base).await.unwrap(), [child]);
+
+        let err = store.delete_checkpoint(base).await.unwrap_err();
+        assert!(err.to_string().contains("parent of 1 incremental"));
+        let err = CheckpointError::HasDependents {
+            checkpoint_id: base,
+            dependents: vec![child],
+        };
+        assert_eq!(err.code(), "ENABLE-510");
+        assert!(store.delete_checkpoint(child).await.is_err());
+
+        // Eviction skips parents: only the ended sandbox's latest can go
+        store.sandbox_ended(a);
+        take(&store, b).await;
+        assert!(store.get_checkpoint(grandchild).await.is_err());
+        assert!(store.get_checkpoint(child).await.is_ok());
+        assert_eq!(store.resolve_snapshot(child).await.unwrap(), vec![2; 1024]);
+
+        // With its child gone, the base is no longer held back
+        store.delete_checkpoint(child).await.unwrap();
+        assert!(store.dependents(base).await.unwrap().is_empty());
+        store.delete_checkpoint(base).await.unwrap();
+        assert_eq!(store.count(), 1);
+    }
 }
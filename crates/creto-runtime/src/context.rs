@@ -0,0 +1,288 @@
+//! Execution context injected into sandboxes.
+//!
+//! Before each execution the runtime adds read-only environment variables
+//! telling the agent code who it is, so it does not have to call back out
+//! for its own context:
+//!
+//! | Variable | Value |
+//! |----------|-------|
+//! | [`AGENT_ID_ENV`] | Agent the sandbox was created for |
+//! | [`ORG_ID_ENV`] | Organization the sandbox belongs to |
+//! | [`EXECUTION_ID_ENV`] | ID of this execution |
+//! | [`SANDBOX_ID_ENV`] | Sandbox running the execution |
+//! | [`CORRELATION_ID_ENV`] | Trace ID of the request, if it has one |
+//! | [`QUOTA_SNAPSHOT_ENV`] | Remaining quota, if a [`QuotaStatusProvider`] is configured |
+//!
+//! The agent and organization are only known for sandboxes the service
+//! created. The quota snapshot is compact JSON taken once when the execution
+//! starts; it is not updated while the code runs, so usage recorded since
+//! (including by this execution) is not reflected:
+//!
+//! ```json
+//! {"taken_at":"2026-10-16T09:30:00Z","quotas":{"api_calls":{"remaining":950,"limit":1000}}}
+//! ```
+//!
+//! None of these values are secrets: they are injected with
+//! [`EnvVar::secret`] unset, are never fingerprinted for redaction, and may
+//! appear in execution output and logs. A variable the request already sets
+//! keeps the request's value; the collision is reported in
+//! [`ExecutionResult::warnings`](crate::execution::ExecutionResult::warnings).
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+
+use crate::execution::ExecutionRequest;
+use crate::sandbox::EnvVar;
+
+/// Environment variable holding the agent ID.
+pub const AGENT_ID_ENV: &str = "CRETO_AGENT_ID";
+
+/// Environment variable holding the organization ID.
+pub const ORG_ID_ENV: &str = "CRETO_ORG_ID";
+
+/// Environment variable holding the execution ID.
+pub const EXECUTION_ID_ENV: &str = "CRETO_EXECUTION_ID";
+
+/// Environment variable holding the sandbox ID.
+pub const SANDBOX_ID_ENV: &str = "CRETO_SANDBOX_ID";
+
+/// Environment variable holding the request's correlation ID.
+pub const CORRELATION_ID_ENV: &str = "CRETO_CORRELATION_ID";
+
+/// Environment variable holding the point-in-time quota snapshot.
+pub const QUOTA_SNAPSHOT_ENV: &str = "CRETO_QUOTA_SNAPSHOT";
+
+/// Remaining quota for one metric.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaRemaining {
+    /// Billable metric code.
+    pub metric_code: String,
+    /// Usage left in the current period.
+    pub remaining: i64,
+    /// Quota limit for the period.
+    pub limit: i64,
+}
+
+/// Source of remaining quota, typically backed by the metering service.
+#[async_trait]
+pub trait QuotaStatusProvider: Send + Sync {
+    /// Remaining quota of an agent for each of `metrics`.
+    ///
+    /// Metrics without a quota are left out.
+    async fn remaining_quota(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        metrics: &[String],
+    ) -> CretoResult<Vec<QuotaRemaining>>;
+}
+
+/// Remaining quota as injected into [`QUOTA_SNAPSHOT_ENV`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaSnapshot {
+    /// When the snapshot was taken.
+    pub taken_at: DateTime<Utc>,
+    /// Remaining quota and limit per metric code.
+    pub quotas: BTreeMap<String, QuotaEntry>,
+}
+
+/// One metric of a [`QuotaSnapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaEntry {
+    /// Usage left in the current period.
+    pub remaining: i64,
+    /// Quota limit for the period.
+    pub limit: i64,
+}
+
+impl QuotaSnapshot {
+    /// Build a snapshot taken at `taken_at`.
+    pub fn new(taken_at: DateTime<Utc>, quotas: Vec<QuotaRemaining>) -> Self {
+        Self {
+            taken_at,
+            quotas: quotas
+                .into_iter()
+                .map(|q| {
+                    (
+                        q.metric_code,
+                        QuotaEntry {
+                            remaining: q.remaining,
+                            limit: q.limit,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Who an execution runs for, and what quota it had when it started.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionContext {
+    /// Organization the sandbox belongs to, if known.
+    pub organization_id: Option<OrganizationId>,
+    /// Agent the sandbox was created for, if known.
+    pub agent_id: Option<AgentId>,
+    /// Quota snapshot, if a provider is configured.
+    pub quota: Option<QuotaSnapshot>,
+}
+
+impl ExecutionContext {
+    /// The environment variables describing this context for `request`.
+    pub fn env(&self, request: &ExecutionRequest) -> Vec<EnvVar> {
+        let mut vars = Vec::new();
+        if let Some(agent_id) = self.agent_id {
+            vars.push(context_var(AGENT_ID_ENV, agent_id.to_string()));
+        }
+        if let Some(organization_id) = self.organization_id {
+            vars.push(context_var(ORG_ID_ENV, organization_id.to_string()));
+        }
+        vars.push(context_var(EXECUTION_ID_ENV, request.id.to_string()));
+        vars.push(context_var(SANDBOX_ID_ENV, request.sandbox_id.to_string()));
+        if let Some(correlation_id) = request.correlation_id {
+            vars.push(context_var(CORRELATION_ID_ENV, correlation_id.to_string()));
+        }
+        if let Some(quota) = &self.quota {
+            let json = serde_json::to_string(quota).expect("quota snapshot serializes");
+            vars.push(context_var(QUOTA_SNAPSHOT_ENV, json));
+        }
+        vars
+    }
+
+    /// Add this context's variables to `request`.
+    ///
+    /// Variables the request already sets keep the request's value; returns
+    /// a warning for each.
+    pub fn inject(&self, request: &mut ExecutionRequest) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for var in self.env(request) {
+            if request.environment.iter().any(|e| e.name == var.name) {
+                warnings.push(format!(
+                    "Environment variable {} is set by the request; the runtime context value was not injected",
+                    var.name
+                ));
+            } else {
+                request.environment.push(var);
+            }
+        }
+        warnings
+    }
+}
+
+fn context_var(name: &str, value: String) -> EnvVar {
+    EnvVar {
+        name: name.to_string(),
+        value,
+        secret: false,
+    }
+}
+
+/// Remaining quota from the metering service.
+#[cfg(feature = "metering")]
+pub struct MeteringQuotaStatus {
+    metering: std::sync::Arc<creto_metering::MeteringService>,
+}
+
+#[cfg(feature = "metering")]
+impl MeteringQuotaStatus {
+    /// Read quota status from `metering`.
+    pub fn new(metering: std::sync::Arc<creto_metering::MeteringService>) -> Self {
+        Self { metering }
+    }
+}
+
+#[cfg(feature = "metering")]
+#[async_trait]
+impl QuotaStatusProvider for MeteringQuotaStatus {
+    async fn remaining_quota(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        metrics: &[String],
+    ) -> CretoResult<Vec<QuotaRemaining>> {
+        let mut remaining = Vec::with_capacity(metrics.len());
+        for metric_code in metrics {
+            let status =
+                self.metering
+                    .get_quota_status(&organization_id, &agent_id, metric_code)?;
+            // Metrics without a quota are reported as an unlimited allowance
+            if status.limit == i64::MAX {
+                continue;
+            }
+            remaining.push(QuotaRemaining {
+                metric_code: metric_code.clone(),
+                remaining: status.remaining,
+                limit: status.limit,
+            });
+        }
+        Ok(remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::SandboxId;
+    use uuid::Uuid;
+
+    fn names(request: &ExecutionRequest) -> Vec<&str> {
+        request
+            .environment
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_injected_set() {
+        let context = ExecutionContext {
+            organization_id: Some(OrganizationId::new()),
+            agent_id: Some(AgentId::new()),
+            quota: None,
+        };
+        let mut request = ExecutionRequest::new(SandboxId::new(), "print(1)");
+        assert!(context.inject(&mut request).is_empty());
+        assert_eq!(
+            names(&request),
+            vec![AGENT_ID_ENV, ORG_ID_ENV, EXECUTION_ID_ENV, SANDBOX_ID_ENV]
+        );
+        assert!(request.environment.iter().all(|e| !e.secret));
+
+        // Unknown owner: only what the request itself identifies
+        let correlation_id = Uuid::new_v4();
+        let mut request = ExecutionRequest::new(SandboxId::new(), "print(1)");
+        request.correlation_id = Some(correlation_id);
+        ExecutionContext::default().inject(&mut request);
+        assert_eq!(
+            names(&request),
+            vec![EXECUTION_ID_ENV, SANDBOX_ID_ENV, CORRELATION_ID_ENV]
+        );
+        assert_eq!(request.environment[2].value, correlation_id.to_string());
+    }
+
+    #[test]
+    fn test_request_values_win() {
+        let context = ExecutionContext {
+            organization_id: Some(OrganizationId::new()),
+            agent_id: Some(AgentId::new()),
+            quota: None,
+        };
+        let mut request =
+            ExecutionRequest::new(SandboxId::new(), "print(1)").with_env(AGENT_ID_ENV, "custom");
+
+        let warnings = context.inject(&mut request);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains(AGENT_ID_ENV));
+        let agent: Vec<_> = request
+            .environment
+            .iter()
+            .filter(|e| e.name == AGENT_ID_ENV)
+            .collect();
+        assert_eq!(agent.len(), 1);
+        assert_eq!(agent[0].value, "custom");
+    }
+}
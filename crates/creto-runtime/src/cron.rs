@@ -0,0 +1,349 @@
+//! Cron expressions for scheduled executions.
+//!
+//! Standard five-field expressions (`minute hour day-of-month month
+//! day-of-week`), evaluated in UTC. Fields accept `*`, values, ranges
+//! (`1-5`), lists (`1,15`), and steps (`*/15`, `0-30/10`); months and days
+//! of week also accept three-letter names (`JAN`, `MON`), and Sunday is both
+//! `0` and `7`. The `@hourly`, `@daily`, `@weekly`, `@monthly`, and `@yearly`
+//! shorthands are supported.
+//!
+//! As in Vixie cron, when both day-of-month and day-of-week are restricted a
+//! day matches if either does.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use creto_common::CretoError;
+use serde::{Deserialize, Serialize};
+
+const MONTH_NAMES: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const DAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// How far ahead to search for a match (covers Feb 29 schedules).
+const SEARCH_HORIZON_DAYS: i64 = 366 * 8;
+
+/// A parsed cron expression.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CronExpression {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronExpression {
+    /// Parse an expression.
+    pub fn parse(expression: &str) -> Result<Self, CretoError> {
+        let source = expression.trim();
+        let expanded = match source {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(invalid(source, "expected 5 fields"));
+        };
+
+        let mut days_of_week = parse_field(source, day_of_week, 0, 7, &DAY_NAMES)?;
+        // Sunday is both 0 and 7
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            minutes: parse_field(source, minute, 0, 59, &[])?,
+            hours: parse_field(source, hour, 0, 23, &[])?,
+            days_of_month: parse_field(source, day_of_month, 1, 31, &[])?,
+            months: parse_field(source, month, 1, 12, &MONTH_NAMES)?,
+            days_of_week,
+            day_of_month_restricted: !day_of_month.starts_with('*'),
+            day_of_week_restricted: !day_of_week.starts_with('*'),
+        })
+    }
+
+    /// The expression as written.
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// The first matching minute strictly after `after`, if any within the
+    /// search horizon (expressions like `0 0 31 2 *` never match).
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let horizon = t + Duration::days(SEARCH_HORIZON_DAYS);
+
+        while t <= horizon {
+            if !has(self.months, t.month()) {
+                let (year, month) = match t.month() {
+                    12 => (t.year() + 1, 1),
+                    m => (t.year(), m + 1),
+                };
+                t = midnight(NaiveDate::from_ymd_opt(year, month, 1)?);
+                continue;
+            }
+            if !self.day_matches(t) {
+                t = midnight(t.date_naive().succ_opt()?);
+                continue;
+            }
+            if !has(self.hours, t.hour()) {
+                t = t.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if !has(self.minutes, t.minute()) {
+                t += Duration::minutes(1);
+                continue;
+            }
+            return Some(t);
+        }
+        None
+    }
+
+    fn day_matches(&self, t: DateTime<Utc>) -> bool {
+        let day_of_month = has(self.days_of_month, t.day());
+        let day_of_week = has(self.days_of_week, t.weekday().num_days_from_sunday());
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+impl FromStr for CronExpression {
+    type Err = CretoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for CronExpression {
+    type Error = CretoError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<CronExpression> for String {
+    fn from(expression: CronExpression) -> Self {
+        expression.source
+    }
+}
+
+impl fmt::Display for CronExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn has(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).expect("midnight is valid"))
+}
+
+fn invalid(expression: &str, reason: impl fmt::Display) -> CretoError {
+    CretoError::ValidationFailed(format!(
+        "invalid cron expression '{}': {}",
+        expression, reason
+    ))
+}
+
+/// Parse one field into a bitset of allowed values.
+fn parse_field(
+    expression: &str,
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+) -> Result<u64, CretoError> {
+    let value = |s: &str| -> Result<u32, CretoError> {
+        let parsed = names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(s))
+            .map(|i| i as u32 + min)
+            .or_else(|| s.parse().ok())
+            .ok_or_else(|| invalid(expression, format!("bad value '{}'", s)))?;
+        if parsed < min || parsed > max {
+            return Err(invalid(
+                expression,
+                format!("{} out of range {}-{}", parsed, min, max),
+            ));
+        }
+        Ok(parsed)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| invalid(expression, format!("bad step in '{}'", part)))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // `5/15` runs from 5 to the end of the range
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let v = value(range)?;
+                    (v, v)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid(expression, format!("empty range '{}'", range)));
+        }
+
+        for v in (start..=end).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Utc>) -> DateTime<Utc> {
+        CronExpression::parse(expression)
+            .unwrap()
+            .next_after(after)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_next_run_within_day() {
+        assert_eq!(
+            next("0 6 * * *", at(2026, 3, 4, 5, 59)),
+            at(2026, 3, 4, 6, 0)
+        );
+        // Strictly after: a schedule due now fires next time round
+        assert_eq!(
+            next("0 6 * * *", at(2026, 3, 4, 6, 0)),
+            at(2026, 3, 5, 6, 0)
+        );
+        assert_eq!(
+            next("*/15 * * * *", at(2026, 3, 4, 6, 7)),
+            at(2026, 3, 4, 6, 15)
+        );
+        assert_eq!(next("@hourly", at(2026, 3, 4, 6, 7)), at(2026, 3, 4, 7, 0));
+    }
+
+    #[test]
+    fn test_next_run_across_month_and_year_boundaries() {
+        // Day 31 skips months that do not have one
+        assert_eq!(
+            next("0 6 31 * *", at(2026, 4, 15, 0, 0)),
+            at(2026, 5, 31, 6, 0)
+        );
+        assert_eq!(
+            next("0 0 1 * *", at(2026, 1, 31, 12, 0)),
+            at(2026, 2, 1, 0, 0)
+        );
+        assert_eq!(
+            next("59 23 31 12 *", at(2026, 12, 31, 23, 59)),
+            at(2027, 12, 31, 23, 59)
+        );
+        assert_eq!(
+            next("30 2 * * *", at(2026, 12, 31, 3, 0)),
+            at(2027, 1, 1, 2, 30)
+        );
+        // Leap day only
+        assert_eq!(
+            next("0 0 29 2 *", at(2026, 3, 1, 0, 0)),
+            at(2028, 2, 29, 0, 0)
+        );
+        // Never matches
+        assert!(CronExpression::parse("0 0 30 2 *")
+            .unwrap()
+            .next_after(at(2026, 1, 1, 0, 0))
+            .is_none());
+    }
+
+    #[test]
+    fn test_day_of_week_and_names() {
+        // 2026-03-06 is a Friday
+        assert_eq!(
+            next("0 9 * * MON-FRI", at(2026, 3, 6, 10, 0)),
+            at(2026, 3, 9, 9, 0)
+        );
+        assert_eq!(
+            next("0 9 * * 7", at(2026, 3, 6, 10, 0)),
+            at(2026, 3, 8, 9, 0)
+        );
+        assert_eq!(
+            next("0 0 1 jan *", at(2026, 3, 6, 0, 0)),
+            at(2027, 1, 1, 0, 0)
+        );
+        // Restricted day-of-month and day-of-week match either
+        assert_eq!(
+            next("0 0 15 * MON", at(2026, 3, 6, 0, 0)),
+            at(2026, 3, 9, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_invalid_expressions_rejected() {
+        for expression in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "* * * * FUNDAY",
+        ] {
+            assert!(
+                matches!(
+                    CronExpression::parse(expression),
+                    Err(CretoError::ValidationFailed(_))
+                ),
+                "{expression:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let expression = CronExpression::parse("0 6 * * 1-5").unwrap();
+        let json = serde_json::to_string(&expression).unwrap();
+        assert_eq!(json, "\"0 6 * * 1-5\"");
+        assert_eq!(
+            serde_json::from_str::<CronExpression>(&json).unwrap(),
+            expression
+        );
+        assert!(serde_json::from_str::<CronExpression>("\"bad\"").is_err());
+    }
+}
@@ -0,0 +1,668 @@
+//! Mutual exclusion between executions.
+//!
+//! An execution carrying an
+//! [`exclusion_key`](crate::execution::ExecutionRequest::exclusion_key) holds
+//! that key, within its organization, for as long as it runs. A second
+//! execution with the same key:
+//!
+//! - waits behind the holder if it sets
+//!   [`queue`](crate::execution::ExecutionRequest::queue) and the service has
+//!   an admission queue, in priority order and first in first out within a
+//!   priority;
+//! - cancels the holder and runs next if it asks to
+//!   [`preempt`](crate::execution::ExecutionRequest::preempt) and has a
+//!   strictly higher priority;
+//! - otherwise fails with [`CretoError::ExclusionConflict`] naming the holder.
+//!
+//! Ownership is persisted as an [`ExclusionLease`] that expires unless
+//! renewed, so a key held by a service that crashed frees up on its own.
+//! Leases are renewed every
+//! [`heartbeat_interval`](ExclusionConfig::heartbeat_interval) by a task
+//! started with the first persisted lease and stopped once no execution on
+//! the node holds one. A service restarted under the same
+//! [`node_id`](ExclusionConfig::node_id) releases its previous leases
+//! straight away.
+//!
+//! Waiting and preemption only work against holders on the same node. A key
+//! held under another node's unexpired lease always conflicts.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{Clock, CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::queue::ExecutionPriority;
+
+/// Exclusion lock configuration.
+#[derive(Debug, Clone)]
+pub struct ExclusionConfig {
+    /// Identifies this service's leases. Keep it stable across restarts so
+    /// a restarted service can release what it held before.
+    ///
+    /// Defaults to `runtime-` followed by the host name. Set it explicitly
+    /// when several services share a host.
+    pub node_id: String,
+    /// How long a lease lasts without being renewed.
+    pub lease_ttl: Duration,
+    /// How often running holders renew their leases.
+    pub heartbeat_interval: Duration,
+    /// How long a request may wait for a key before failing.
+    pub max_wait: Duration,
+}
+
+impl Default for ExclusionConfig {
+    fn default() -> Self {
+        Self {
+            node_id: format!("runtime-{}", host_name()),
+            lease_ttl: Duration::from_secs(30),
+            heartbeat_interval: Duration::from_secs(10),
+            max_wait: Duration::from_secs(300),
+        }
+    }
+}
+
+/// The host name, from `HOSTNAME` or the kernel, or `localhost`.
+fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Persisted ownership of an exclusion key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExclusionLease {
+    /// Organization the key is scoped to.
+    pub organization_id: OrganizationId,
+    /// The exclusion key.
+    pub key: String,
+    /// Execution holding the key.
+    pub execution_id: Uuid,
+    /// Priority of the holding execution.
+    pub priority: ExecutionPriority,
+    /// Node running the holding execution.
+    pub owner: String,
+    /// When the holder took the key.
+    pub acquired_at: DateTime<Utc>,
+    /// When the lease lapses unless renewed.
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ExclusionLease {
+    /// Whether the lease has lapsed at `now`.
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Storage for exclusion leases.
+#[async_trait]
+pub trait ExclusionLeaseStore: Send + Sync {
+    /// Take the lease's key unless another node holds it under a lease
+    /// unexpired at `now`. Returns the lease in force afterwards, which is
+    /// `lease` itself if it was taken.
+    async fn acquire(
+        &self,
+        lease: &ExclusionLease,
+        now: DateTime<Utc>,
+    ) -> CretoResult<ExclusionLease>;
+
+    /// Extend an execution's lease, returning `false` if it no longer holds it.
+    async fn renew(
+        &self,
+        organization_id: OrganizationId,
+        key: &str,
+        execution_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> CretoResult<bool>;
+
+    /// Drop an execution's lease, returning whether it held it.
+    async fn release(
+        &self,
+        organization_id: OrganizationId,
+        key: &str,
+        execution_id: Uuid,
+    ) -> CretoResult<bool>;
+
+    /// List all leases, expired or not.
+    async fn list(&self) -> CretoResult<Vec<ExclusionLease>>;
+}
+
+/// In-memory lease store for testing and single-node deployments.
+#[derive(Debug, Default)]
+pub struct InMemoryExclusionLeaseStore {
+    leases: RwLock<HashMap<(OrganizationId, String), ExclusionLease>>,
+}
+
+impl InMemoryExclusionLeaseStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ExclusionLeaseStore for InMemoryExclusionLeaseStore {
+    async fn acquire(
+        &self,
+        lease: &ExclusionLease,
+        now: DateTime<Utc>,
+    ) -> CretoResult<ExclusionLease> {
+        let mut leases = self.leases.write().unwrap();
+        let slot = (lease.organization_id, lease.key.clone());
+        match leases.get(&slot) {
+            Some(current) if current.owner != lease.owner && !current.is_expired_at(now) => {
+                Ok(current.clone())
+            }
+            _ => {
+                leases.insert(slot, lease.clone());
+                Ok(lease.clone())
+            }
+        }
+    }
+
+    async fn renew(
+        &self,
+        organization_id: OrganizationId,
+        key: &str,
+        execution_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> CretoResult<bool> {
+        let mut leases = self.leases.write().unwrap();
+        match leases.get_mut(&(organization_id, key.to_string())) {
+            Some(lease) if lease.execution_id == execution_id => {
+                lease.expires_at = expires_at;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn release(
+        &self,
+        organization_id: OrganizationId,
+        key: &str,
+        execution_id: Uuid,
+    ) -> CretoResult<bool> {
+        let mut leases = self.leases.write().unwrap();
+        let slot = (organization_id, key.to_string());
+        if leases
+            .get(&slot)
+            .is_some_and(|lease| lease.execution_id == execution_id)
+        {
+            leases.remove(&slot);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    async fn list(&self) -> CretoResult<Vec<ExclusionLease>> {
+        Ok(self.leases.read().unwrap().values().cloned().collect())
+    }
+}
+
+/// The execution holding a key on this node.
+struct Holder {
+    execution_id: Uuid,
+    priority: ExecutionPriority,
+    /// Whether its lease has been persisted.
+    persisted: bool,
+    /// Fired to cancel the holder.
+    preempt: Option<oneshot::Sender<()>>,
+}
+
+/// An execution waiting for a key.
+struct Waiter {
+    execution_id: Uuid,
+    priority: ExecutionPriority,
+    grant: oneshot::Sender<()>,
+    preempt: oneshot::Sender<()>,
+}
+
+struct KeyState {
+    holder: Holder,
+    /// Waiting executions in grant order.
+    waiters: Vec<Waiter>,
+}
+
+/// Local lock state and the lease store behind it.
+pub(crate) struct ExclusionRegistry {
+    config: ExclusionConfig,
+    store: Arc<dyn ExclusionLeaseStore>,
+    keys: Mutex<HashMap<(OrganizationId, String), KeyState>>,
+    /// The running lease heartbeat, if any holder is persisted.
+    heartbeat: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ExclusionRegistry {
+    pub(crate) fn new(store: Arc<dyn ExclusionLeaseStore>, config: ExclusionConfig) -> Self {
+        Self {
+            config,
+            store,
+            keys: Mutex::new(HashMap::new()),
+            heartbeat: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn store(&self) -> &Arc<dyn ExclusionLeaseStore> {
+        &self.store
+    }
+
+    /// Claim `key` for an execution, or join the line for it.
+    ///
+    /// Fails with [`CretoError::ExclusionConflict`] if the key is held and
+    /// the execution may neither wait (`can_wait`) nor preempt the holder.
+    pub(crate) fn reserve(
+        self: &Arc<Self>,
+        organization_id: OrganizationId,
+        key: &str,
+        execution_id: Uuid,
+        priority: ExecutionPriority,
+        preempt: bool,
+        can_wait: bool,
+    ) -> CretoResult<ExclusionHold> {
+        let (preempt_tx, preempt_rx) = oneshot::channel();
+        let mut hold = ExclusionHold {
+            registry: Arc::clone(self),
+            organization_id,
+            key: key.to_string(),
+            execution_id,
+            priority,
+            granted: None,
+            preempted: Some(preempt_rx),
+            released: false,
+        };
+
+        let mut keys = self.keys.lock().unwrap();
+        let Some(state) = keys.get_mut(&(organization_id, key.to_string())) else {
+            keys.insert(
+                (organization_id, key.to_string()),
+                KeyState {
+                    holder: Holder {
+                        execution_id,
+                        priority,
+                        persisted: false,
+                        preempt: Some(preempt_tx),
+                    },
+                    waiters: Vec::new(),
+                },
+            );
+            return Ok(hold);
+        };
+
+        let preempting = preempt && priority > state.holder.priority;
+        if !preempting && !can_wait {
+            // Not registered anywhere, so dropping it must not release
+            hold.released = true;
+            return Err(CretoError::ExclusionConflict {
+                key: key.to_string(),
+                holder: state.holder.execution_id,
+            });
+        }
+
+        let (grant_tx, grant_rx) = oneshot::channel();
+        let position = state
+            .waiters
+            .iter()
+            .position(|w| w.priority < priority)
+            .unwrap_or(state.waiters.len());
+        state.waiters.insert(
+            position,
+            Waiter {
+                execution_id,
+                priority,
+                grant: grant_tx,
+                preempt: preempt_tx,
+            },
+        );
+        if preempting {
+            if let Some(cancel) = state.holder.preempt.take() {
+                tracing::info!(
+                    organization_id = %organization_id,
+                    key,
+                    holder = %state.holder.execution_id,
+                    preempted_by = %execution_id,
+                    "Preempting exclusion key holder"
+                );
+                let _ = cancel.send(());
+            }
+        }
+        hold.granted = Some(grant_rx);
+        Ok(hold)
+    }
+
+    /// Give up a key locally, handing it to the next live waiter.
+    ///
+    /// Returns whether the execution held the key, as opposed to waiting.
+    fn release_local(
+        &self,
+        organization_id: OrganizationId,
+        key: &str,
+        execution_id: Uuid,
+    ) -> bool {
+        let mut keys = self.keys.lock().unwrap();
+        let slot = (organization_id, key.to_string());
+        let Some(state) = keys.get_mut(&slot) else {
+            return false;
+        };
+        if state.holder.execution_id != execution_id {
+            state.waiters.retain(|w| w.execution_id != execution_id);
+            return false;
+        }
+
+        while !state.waiters.is_empty() {
+            let waiter = state.waiters.remove(0);
+            // A waiter that gave up has dropped its receiver
+            if waiter.grant.send(()).is_ok() {
+                state.holder = Holder {
+                    execution_id: waiter.execution_id,
+                    priority: waiter.priority,
+                    persisted: false,
+                    preempt: Some(waiter.preempt),
+                };
+                return true;
+            }
+        }
+        keys.remove(&slot);
+        true
+    }
+
+    /// Point the local holder at its persisted execution ID and mark it
+    /// persisted.
+    fn mark_persisted(
+        &self,
+        organization_id: OrganizationId,
+        key: &str,
+        previous_id: Uuid,
+        execution_id: Uuid,
+    ) {
+        let mut keys = self.keys.lock().unwrap();
+        if let Some(state) = keys.get_mut(&(organization_id, key.to_string())) {
+            if state.holder.execution_id == previous_id {
+                state.holder.execution_id = execution_id;
+                state.holder.persisted = true;
+            }
+        }
+    }
+
+    /// Renew the leases of every persisted holder on this node.
+    ///
+    /// A holder whose lease was lost, for example because it lapsed and
+    /// another node took the key, is cancelled: exclusion can no longer be
+    /// guaranteed for it. Returns the number of leases renewed.
+    pub(crate) async fn renew_leases(&self, now: DateTime<Utc>) -> CretoResult<usize> {
+        let held: Vec<(OrganizationId, String, Uuid)> = self
+            .keys
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, state)| state.holder.persisted)
+            .map(|((org, key), state)| (*org, key.clone(), state.holder.execution_id))
+            .collect();
+
+        let expires_at =
+            now + chrono::Duration::from_std(self.config.lease_ttl).unwrap_or_default();
+        let mut renewed = 0;
+        for (organization_id, key, execution_id) in held {
+            if self
+                .store
+                .renew(organization_id, &key, execution_id, expires_at)
+                .await?
+            {
+                renewed += 1;
+                continue;
+            }
+            tracing::warn!(
+                organization_id = %organization_id,
+                key,
+                execution_id = %execution_id,
+                "Exclusion lease lost; cancelling holder"
+            );
+            let mut keys = self.keys.lock().unwrap();
+            if let Some(state) = keys.get_mut(&(organization_id, key)) {
+                if state.holder.execution_id == execution_id {
+                    if let Some(cancel) = state.holder.preempt.take() {
+                        let _ = cancel.send(());
+                    }
+                }
+            }
+        }
+        Ok(renewed)
+    }
+
+    /// Start the lease heartbeat unless it is running.
+    ///
+    /// The heartbeat renews leases every `heartbeat_interval` and stops
+    /// once no holder on this node is persisted, or the registry is dropped.
+    fn ensure_heartbeat(self: &Arc<Self>, clock: Arc<dyn Clock>) {
+        let mut heartbeat = self.heartbeat.lock().unwrap();
+        if heartbeat.as_ref().is_some_and(|task| !task.is_finished()) {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let registry = Arc::downgrade(self);
+        let interval = self.config.heartbeat_interval;
+        *heartbeat = Some(runtime.spawn(Self::heartbeat(registry, clock, interval)));
+    }
+
+    async fn heartbeat(registry: Weak<Self>, clock: Arc<dyn Clock>, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            let Some(registry) = registry.upgrade() else {
+                return;
+            };
+            {
+                // Checked under the heartbeat lock, so a lease persisted
+                // after this finds the slot empty and starts a new task
+                let mut heartbeat = registry.heartbeat.lock().unwrap();
+                let keys = registry.keys.lock().unwrap();
+                if !keys.values().any(|state| state.holder.persisted) {
+                    *heartbeat = None;
+                    return;
+                }
+            }
+            if let Err(e) = registry.renew_leases(clock.now()).await {
+                tracing::warn!(error = %e, "Exclusion lease renewal failed");
+            }
+        }
+    }
+
+    /// Release leases this node held before a restart.
+    ///
+    /// Only leases for keys not held locally are touched, so this is safe
+    /// to call while executions are running.
+    pub(crate) async fn recover(&self) -> CretoResult<Vec<ExclusionLease>> {
+        let stale: Vec<ExclusionLease> = {
+            let leases = self.store.list().await?;
+            let keys = self.keys.lock().unwrap();
+            leases
+                .into_iter()
+                .filter(|lease| lease.owner == self.config.node_id)
+                .filter(
+                    |lease| match keys.get(&(lease.organization_id, lease.key.clone())) {
+                        Some(state) => state.holder.execution_id != lease.execution_id,
+                        None => true,
+                    },
+                )
+                .collect()
+        };
+
+        let mut released = Vec::with_capacity(stale.len());
+        for lease in stale {
+            if self
+                .store
+                .release(lease.organization_id, &lease.key, lease.execution_id)
+                .await?
+            {
+                tracing::info!(
+                    organization_id = %lease.organization_id,
+                    key = %lease.key,
+                    execution_id = %lease.execution_id,
+                    "Released exclusion lease left by a previous run"
+                );
+                released.push(lease);
+            }
+        }
+        Ok(released)
+    }
+}
+
+/// A claim on an exclusion key, held or waited for.
+///
+/// Dropping it gives the key up, so it is released on every path out of an
+/// execution, including errors and cancellation.
+pub(crate) struct ExclusionHold {
+    registry: Arc<ExclusionRegistry>,
+    organization_id: OrganizationId,
+    key: String,
+    execution_id: Uuid,
+    priority: ExecutionPriority,
+    /// Resolves when the key is handed over; `None` once held.
+    granted: Option<oneshot::Receiver<()>>,
+    /// Resolves if the holder is preempted.
+    preempted: Option<oneshot::Receiver<()>>,
+    released: bool,
+}
+
+impl ExclusionHold {
+    /// Whether the key is still held by another execution.
+    pub(crate) fn is_waiting(&self) -> bool {
+        self.granted.is_some()
+    }
+
+    /// Wait until the key is handed over.
+    ///
+    /// Fails with [`CretoError::QueueTimeout`] after the configured
+    /// [`max_wait`](ExclusionConfig::max_wait).
+    pub(crate) async fn granted(&mut self) -> CretoResult<()> {
+        let Some(granted) = self.granted.as_mut() else {
+            return Ok(());
+        };
+        let max_wait = self.registry.config.max_wait;
+        match tokio::time::timeout(max_wait, granted).await {
+            Ok(Ok(())) => {
+                self.granted = None;
+                Ok(())
+            }
+            Ok(Err(_)) => Err(CretoError::Internal(format!(
+                "Exclusion key {} was dropped while waiting",
+                self.key
+            ))),
+            Err(_) => Err(CretoError::QueueTimeout {
+                seconds: max_wait.as_secs(),
+            }),
+        }
+    }
+
+    /// Persist ownership for the execution recorded as `execution_id`,
+    /// starting the lease heartbeat.
+    ///
+    /// Fails with [`CretoError::ExclusionConflict`] if another node holds
+    /// the key, giving it up locally.
+    pub(crate) async fn persist(
+        &mut self,
+        execution_id: Uuid,
+        clock: &Arc<dyn Clock>,
+    ) -> CretoResult<()> {
+        let now = clock.now();
+        let previous_id = self.execution_id;
+        let ttl = chrono::Duration::from_std(self.registry.config.lease_ttl).unwrap_or_default();
+        let lease = ExclusionLease {
+            organization_id: self.organization_id,
+            key: self.key.clone(),
+            execution_id,
+            priority: self.priority,
+            owner: self.registry.config.node_id.clone(),
+            acquired_at: now,
+            expires_at: now + ttl,
+        };
+        self.registry
+            .mark_persisted(self.organization_id, &self.key, previous_id, execution_id);
+        self.execution_id = execution_id;
+
+        let current = self.registry.store.acquire(&lease, now).await?;
+        if current.execution_id != execution_id {
+            self.released = true;
+            self.release_now().await;
+            return Err(CretoError::ExclusionConflict {
+                key: self.key.clone(),
+                holder: current.execution_id,
+            });
+        }
+        self.registry.ensure_heartbeat(Arc::clone(clock));
+        Ok(())
+    }
+
+    /// Take the signal fired if this holder is preempted.
+    pub(crate) fn preempted(&mut self) -> Option<oneshot::Receiver<()>> {
+        self.preempted.take()
+    }
+
+    /// The exclusion key.
+    pub(crate) fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Give the key up now.
+    pub(crate) async fn release(mut self) {
+        self.released = true;
+        self.release_now().await;
+    }
+
+    async fn release_now(&self) {
+        if !self
+            .registry
+            .release_local(self.organization_id, &self.key, self.execution_id)
+        {
+            return;
+        }
+        if let Err(e) = self
+            .registry
+            .store
+            .release(self.organization_id, &self.key, self.execution_id)
+            .await
+        {
+            tracing::error!(
+                key = %self.key,
+                execution_id = %self.execution_id,
+                error = %e,
+                "Failed to release exclusion lease; it will lapse"
+            );
+        }
+    }
+}
+
+impl Drop for ExclusionHold {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        let registry = Arc::clone(&self.registry);
+        let (organization_id, key, execution_id) =
+            (self.organization_id, self.key.clone(), self.execution_id);
+        if !registry.release_local(organization_id, &key, execution_id) {
+            return;
+        }
+        // Without a runtime the lease is left to lapse
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                let _ = registry
+                    .store
+                    .release(organization_id, &key, execution_id)
+                    .await;
+            });
+        }
+    }
+}
@@ -1,11 +1,31 @@
 //! Code execution within sandboxes.
+//!
+//! Executors write stdout and stderr to an [`OutputSink`] as the code
+//! produces them. [`run_streaming`] drives an executor, forwarding each
+//! [`ExecutionChunk`] and stopping the execution once it exceeds its wall
+//! time or output limit; buffered execution is the same run with the chunks
+//! collected into the [`ExecutionResult`].
+
+use std::sync::Arc;
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
-use creto_common::CretoResult;
+use creto_common::{Correlation, CretoResult};
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::Stream;
 use uuid::Uuid;
 
-use crate::sandbox::SandboxId;
+use crate::channels::ChannelDescriptor;
+use crate::filesystem::FilesystemChangeSummary;
+use crate::limits::LimitKind;
+use crate::logs::{capture_logs, LogCaptureConfig, LogRecord, LogSummary};
+use crate::queue::ExecutionPriority;
+use crate::redaction::RedactionSummary;
+use crate::resources::{ResourceLimits, ResourceUsage};
+use crate::sampling::{ResourceController, SampledUsage, SamplerConfig, UsageSample, UsageSampler};
+use crate::sandbox::{EnvVar, SandboxId};
 
 /// Request to execute code in a sandbox.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +54,58 @@ pub struct ExecutionRequest {
     /// Whether to capture stdout/stderr.
     #[serde(default = "default_true")]
     pub capture_output: bool,
+
+    /// Trace ID shared with the action that requested this execution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+
+    /// ID of the entity that caused this execution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caused_by: Option<Uuid>,
+
+    /// Scheduling priority when the request waits for a slot.
+    #[serde(default)]
+    pub priority: ExecutionPriority,
+
+    /// Wait in the admission queue instead of failing when at capacity.
+    #[serde(default)]
+    pub queue: bool,
+
+    /// Key no other execution in the organization may hold at the same time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exclusion_key: Option<String>,
+
+    /// Cancel a lower-priority execution holding the exclusion key instead
+    /// of failing.
+    #[serde(default)]
+    pub preempt: bool,
+
+    /// Agent channels the sandbox can use, injected by the runtime.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<ChannelDescriptor>,
+
+    /// Environment variables added for this execution only.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub environment: Vec<EnvVar>,
+
+    /// Files written into the sandbox before the code runs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<InputArtifact>,
+
+    /// Client-chosen key making retries of this request return its first
+    /// result instead of running again (see [`crate::idempotency`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+/// A file made available to one execution.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputArtifact {
+    /// Path inside the sandbox.
+    pub path: String,
+
+    /// File contents.
+    pub content: Vec<u8>,
 }
 
 fn default_true() -> bool {
@@ -51,6 +123,16 @@ impl ExecutionRequest {
             input: serde_json::Value::Null,
             timeout_seconds: None,
             capture_output: true,
+            correlation_id: None,
+            caused_by: None,
+            priority: ExecutionPriority::default(),
+            queue: false,
+            exclusion_key: None,
+            preempt: false,
+            channels: Vec::new(),
+            environment: Vec::new(),
+            artifacts: Vec::new(),
+            idempotency_key: None,
         }
     }
 
@@ -71,6 +153,70 @@ impl ExecutionRequest {
         self.timeout_seconds = Some(seconds);
         self
     }
+
+    /// Set the scheduling priority.
+    pub fn with_priority(mut self, priority: ExecutionPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Wait for a free slot instead of failing when at capacity.
+    pub fn queued(mut self) -> Self {
+        self.queue = true;
+        self
+    }
+
+    /// Hold an exclusion key for the duration of the execution.
+    pub fn with_exclusion_key(mut self, key: impl Into<String>) -> Self {
+        self.exclusion_key = Some(key.into());
+        self
+    }
+
+    /// Preempt a lower-priority holder of the exclusion key.
+    pub fn preempting(mut self) -> Self {
+        self.preempt = true;
+        self
+    }
+
+    /// Run at most once per sandbox under `key`; retries get the first result.
+    pub fn with_idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    /// Add an environment variable for this execution.
+    pub fn with_env(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.environment.push(EnvVar {
+            name: name.into(),
+            value: value.into(),
+            secret: false,
+        });
+        self
+    }
+
+    /// Add a file written into the sandbox at `path` before the code runs.
+    pub fn with_artifact(mut self, path: impl Into<String>, content: impl Into<Vec<u8>>) -> Self {
+        self.artifacts.push(InputArtifact {
+            path: path.into(),
+            content: content.into(),
+        });
+        self
+    }
+
+    /// Set the trace identifiers inherited from the originating action.
+    pub fn with_correlation(mut self, correlation: Correlation) -> Self {
+        self.correlation_id = correlation.correlation_id;
+        self.caused_by = correlation.caused_by;
+        self
+    }
+
+    /// Get the trace identifiers for this request.
+    pub fn correlation(&self) -> Correlation {
+        Correlation {
+            correlation_id: self.correlation_id,
+            caused_by: self.caused_by,
+        }
+    }
 }
 
 /// Result of a code execution.
@@ -100,6 +246,44 @@ pub struct ExecutionResult {
 
     /// Execution timing.
     pub timing: ExecutionTiming,
+
+    /// Resource usage summary (peaks derived from `usage_samples`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_usage: Option<ResourceUsage>,
+
+    /// Resource usage time series captured while executing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub usage_samples: Vec<UsageSample>,
+
+    /// Structured log counts per level (derived from `logs`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_summary: Option<LogSummary>,
+
+    /// Structured log records captured from stdout.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<LogRecord>,
+
+    /// Files created, modified and deleted in the sandbox workdir, when
+    /// filesystem tracking is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fs_changes: Option<FilesystemChangeSummary>,
+
+    /// Secrets and sensitive patterns redacted from the output.
+    #[serde(default, skip_serializing_if = "RedactionSummary::is_empty")]
+    pub redactions: RedactionSummary,
+
+    /// Trace ID inherited from the execution request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<Uuid>,
+
+    /// Inherited from the execution request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caused_by: Option<Uuid>,
+
+    /// Non-fatal problems preparing the execution, such as request
+    /// environment variables shadowing the injected runtime context.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
 impl ExecutionResult {
@@ -113,6 +297,15 @@ impl ExecutionResult {
             stderr: None,
             error: None,
             timing,
+            resource_usage: None,
+            usage_samples: Vec::new(),
+            log_summary: None,
+            logs: Vec::new(),
+            fs_changes: None,
+            redactions: RedactionSummary::default(),
+            correlation_id: None,
+            caused_by: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -126,6 +319,51 @@ impl ExecutionResult {
             stderr: None,
             error: Some(error),
             timing,
+            resource_usage: None,
+            usage_samples: Vec::new(),
+            log_summary: None,
+            logs: Vec::new(),
+            fs_changes: None,
+            redactions: RedactionSummary::default(),
+            correlation_id: None,
+            caused_by: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Attach sampled resource usage.
+    pub fn with_sampled_usage(mut self, sampled: SampledUsage) -> Self {
+        self.resource_usage = Some(sampled.usage);
+        self.usage_samples = sampled.samples;
+        self
+    }
+
+    /// Parse structured log lines from the captured stdout, which is left
+    /// unchanged.
+    ///
+    /// Does nothing if logs were already captured.
+    pub fn with_captured_logs(mut self, config: &LogCaptureConfig) -> Self {
+        if self.log_summary.is_some() {
+            return self;
+        }
+        let captured = capture_logs(self.stdout.as_deref().unwrap_or_default(), config);
+        self.logs = captured.records;
+        self.log_summary = Some(captured.summary);
+        self
+    }
+
+    /// Set the trace identifiers (normally copied from the request).
+    pub fn with_correlation(mut self, correlation: Correlation) -> Self {
+        self.correlation_id = correlation.correlation_id;
+        self.caused_by = correlation.caused_by;
+        self
+    }
+
+    /// Get the trace identifiers for this result.
+    pub fn correlation(&self) -> Correlation {
+        Correlation {
+            correlation_id: self.correlation_id,
+            caused_by: self.caused_by,
         }
     }
 
@@ -166,6 +404,9 @@ pub struct ExecutionError {
     /// Line number where error occurred.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub line_number: Option<u32>,
+    /// Sandbox limit whose breach cancelled the execution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<LimitKind>,
 }
 
 impl ExecutionError {
@@ -176,6 +417,7 @@ impl ExecutionError {
             message: message.into(),
             stack_trace: None,
             line_number: None,
+            limit: None,
         }
     }
 
@@ -187,6 +429,60 @@ impl ExecutionError {
         )
     }
 
+    /// Create an error for an execution stopped at its wall time limit.
+    pub fn wall_time_exceeded(limit: Duration) -> Self {
+        Self::new(
+            "WALL_TIME_EXCEEDED",
+            format!(
+                "Execution exceeded its wall time limit of {} ms",
+                limit.as_millis()
+            ),
+        )
+    }
+
+    /// Create an error for an execution stopped at its output limit.
+    pub fn output_limit_exceeded(limit_bytes: u64) -> Self {
+        Self::new(
+            "OUTPUT_LIMIT_EXCEEDED",
+            format!(
+                "Execution exceeded its output limit of {} bytes",
+                limit_bytes
+            ),
+        )
+    }
+
+    /// Create an error for an execution cancelled because its sandbox was
+    /// terminated for exceeding `limit`.
+    pub fn resource_limit_exceeded(limit: LimitKind) -> Self {
+        let mut error = Self::new(
+            "RESOURCE_LIMIT_EXCEEDED",
+            format!("Sandbox exceeded its {} limit and was terminated", limit),
+        );
+        error.limit = Some(limit);
+        error
+    }
+
+    /// Create an error for an execution refused because the lease on the
+    /// secret mounted as `secret_name` ran out.
+    pub fn secret_lease_expired(secret_name: &str) -> Self {
+        Self::new(
+            "SECRET_LEASE_EXPIRED",
+            format!(
+                "Lease on mounted secret {} expired; mount the secret again",
+                secret_name
+            ),
+        )
+    }
+
+    /// Create an error for an execution refused because the sandbox's
+    /// attestation failed verification against its policy.
+    pub fn attestation_rejected(reason: impl Into<String>) -> Self {
+        Self::new(
+            "ATTESTATION_REJECTED",
+            format!("Sandbox attestation rejected: {}", reason.into()),
+        )
+    }
+
     /// Create a sandbox not found error.
     pub fn sandbox_not_found(sandbox_id: &str) -> Self {
         Self::new(
@@ -210,6 +506,9 @@ pub struct ExecutionTiming {
     /// Total execution duration in milliseconds.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration_ms: Option<u64>,
+    /// Time spent resuming an idle sandbox before the execution could start.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_ms: Option<u64>,
 }
 
 impl ExecutionTiming {
@@ -220,6 +519,7 @@ impl ExecutionTiming {
             started_at: None,
             completed_at: None,
             duration_ms: None,
+            resume_ms: None,
         }
     }
 
@@ -244,45 +544,415 @@ impl Default for ExecutionTiming {
     }
 }
 
+/// A piece of a streamed execution, in the order it happened.
+///
+/// A stream always ends with exactly one [`Result`](ExecutionChunk::Result).
+#[derive(Debug, Clone)]
+pub enum ExecutionChunk {
+    /// Bytes written to stdout.
+    Stdout(Vec<u8>),
+    /// Bytes written to stderr.
+    Stderr(Vec<u8>),
+    /// The execution changed status.
+    Status(ExecutionStatus),
+    /// The final result. Its stdout and stderr hold everything streamed
+    /// before it.
+    Result(Box<ExecutionResult>),
+}
+
+/// Number of chunks an executor can write ahead of the reader.
+const OUTPUT_BUFFER: usize = 64;
+
+/// Where an executor writes output while the code runs.
+///
+/// Sends wait while the reader is behind. Once they return `false` the
+/// reader has stopped and the executor should stop too.
+#[derive(Debug, Clone)]
+pub struct OutputSink {
+    tx: mpsc::Sender<ExecutionChunk>,
+}
+
+impl OutputSink {
+    /// Create a sink and the receiver reading from it.
+    pub fn channel() -> (Self, mpsc::Receiver<ExecutionChunk>) {
+        let (tx, rx) = mpsc::channel(OUTPUT_BUFFER);
+        (Self { tx }, rx)
+    }
+
+    /// Write to stdout.
+    pub async fn stdout(&self, bytes: impl Into<Vec<u8>>) -> bool {
+        self.send(ExecutionChunk::Stdout(bytes.into())).await
+    }
+
+    /// Write to stderr.
+    pub async fn stderr(&self, bytes: impl Into<Vec<u8>>) -> bool {
+        self.send(ExecutionChunk::Stderr(bytes.into())).await
+    }
+
+    /// Report a status change.
+    pub async fn status(&self, status: ExecutionStatus) -> bool {
+        self.send(ExecutionChunk::Status(status)).await
+    }
+
+    async fn send(&self, chunk: ExecutionChunk) -> bool {
+        self.tx.send(chunk).await.is_ok()
+    }
+}
+
+/// Limits enforced while an execution runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamLimits {
+    /// Longest the execution may run (None = unlimited).
+    pub wall_time: Option<Duration>,
+
+    /// Most stdout and stderr bytes it may write, combined (None = unlimited).
+    pub max_output_bytes: Option<u64>,
+}
+
+impl StreamLimits {
+    /// No limits.
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    /// Limits for `request` in a sandbox with `limits`.
+    ///
+    /// A request timeout can shorten the sandbox's wall time but not extend it.
+    pub fn for_request(limits: &ResourceLimits, request: &ExecutionRequest) -> Self {
+        let wall_time_seconds = match request.timeout_seconds {
+            Some(timeout) => timeout.min(limits.wall_time_seconds),
+            None => limits.wall_time_seconds,
+        };
+        Self {
+            wall_time: Some(Duration::from_secs(wall_time_seconds as u64)),
+            max_output_bytes: limits.max_output_bytes,
+        }
+    }
+}
+
+/// Something that can run execution requests.
+///
+/// [`Executor`] is the production implementation; tests substitute scripted
+/// executors (see [`crate::testing::ScriptedExecutor`]).
+#[async_trait::async_trait]
+pub trait CodeExecutor: Send + Sync {
+    /// Execute a request, writing its output to `output` as it is produced.
+    ///
+    /// Output written to `output` must not be repeated in the returned
+    /// result; output the executor can only report once it finishes may be
+    /// returned in the result instead.
+    async fn execute_streaming(
+        &self,
+        request: ExecutionRequest,
+        output: OutputSink,
+    ) -> CretoResult<ExecutionResult>;
+
+    /// Execute a request, buffering its output into the result.
+    async fn execute(&self, request: ExecutionRequest) -> CretoResult<ExecutionResult> {
+        run_streaming(self, request, StreamLimits::unlimited(), |_| {}).await
+    }
+}
+
+/// Output collected while driving an execution, checked against its limit.
+struct CollectedOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    limit: Option<u64>,
+}
+
+impl CollectedOutput {
+    /// Record a chunk, forwarding it cut off at what the limit leaves.
+    ///
+    /// Returns the limit once it is exceeded.
+    fn accept(
+        &mut self,
+        chunk: ExecutionChunk,
+        forward: &mut impl FnMut(ExecutionChunk),
+    ) -> Option<Stopped> {
+        let (mut bytes, stdout) = match chunk {
+            ExecutionChunk::Stdout(bytes) => (bytes, true),
+            ExecutionChunk::Stderr(bytes) => (bytes, false),
+            // The driver reports the final status and result itself
+            ExecutionChunk::Status(status) if !is_final(status) => {
+                forward(ExecutionChunk::Status(status));
+                return None;
+            }
+            _ => return None,
+        };
+        let mut exceeded = None;
+        if let Some(limit) = self.limit {
+            let written = (self.stdout.len() + self.stderr.len()) as u64;
+            let remaining = limit.saturating_sub(written);
+            if bytes.len() as u64 > remaining {
+                bytes.truncate(remaining as usize);
+                exceeded = Some(Stopped::Output(limit));
+            }
+        }
+        if !bytes.is_empty() {
+            if stdout {
+                self.stdout.extend_from_slice(&bytes);
+                forward(ExecutionChunk::Stdout(bytes));
+            } else {
+                self.stderr.extend_from_slice(&bytes);
+                forward(ExecutionChunk::Stderr(bytes));
+            }
+        }
+        exceeded
+    }
+
+    /// Move output the executor returned in `result` through the limit.
+    fn accept_buffered(
+        &mut self,
+        result: &mut ExecutionResult,
+        forward: &mut impl FnMut(ExecutionChunk),
+    ) -> Option<Stopped> {
+        let stdout = result.stdout.take().unwrap_or_default();
+        let stderr = result.stderr.take().unwrap_or_default();
+        self.accept(ExecutionChunk::Stdout(stdout.into_bytes()), forward)
+            .or_else(|| self.accept(ExecutionChunk::Stderr(stderr.into_bytes()), forward))
+    }
+
+    /// Set the result's stdout and stderr to everything collected.
+    fn fill(self, result: &mut ExecutionResult) {
+        let text = |bytes: Vec<u8>| {
+            (!bytes.is_empty()).then(|| String::from_utf8_lossy(&bytes).into_owned())
+        };
+        result.stdout = text(self.stdout);
+        result.stderr = text(self.stderr);
+    }
+}
+
+fn is_final(status: ExecutionStatus) -> bool {
+    !matches!(status, ExecutionStatus::Queued | ExecutionStatus::Running)
+}
+
+/// Why [`run_streaming`] stopped an execution.
+enum Stopped {
+    WallTime(Duration),
+    Output(u64),
+}
+
+/// How an execution driven by [`run_streaming`] ended.
+enum Ended {
+    Finished(Box<ExecutionResult>),
+    Stopped(Stopped),
+}
+
+/// Run `request` on `executor`, passing every chunk to `forward` as it
+/// happens and enforcing `limits`.
+///
+/// `forward` receives [`ExecutionStatus::Running`] first and the final
+/// status last; the [`ExecutionChunk::Result`] is left to the caller, which
+/// gets the result back. Output beyond the limit is cut off at the limit and
+/// the execution is stopped with [`ExecutionError::output_limit_exceeded`];
+/// one running past its wall time is stopped with
+/// [`ExecutionError::wall_time_exceeded`]. Either way the result keeps the
+/// output written before it was stopped. The result's timing covers the run
+/// as observed here, with its duration taken from the monotonic clock.
+pub async fn run_streaming<E: CodeExecutor + ?Sized>(
+    executor: &E,
+    request: ExecutionRequest,
+    limits: StreamLimits,
+    mut forward: impl FnMut(ExecutionChunk) + Send,
+) -> CretoResult<ExecutionResult> {
+    let request_id = request.id;
+    let correlation = request.correlation();
+    let mut collected = CollectedOutput {
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+        limit: limits.max_output_bytes,
+    };
+    let (output, mut chunks) = OutputSink::channel();
+    let mut timing = ExecutionTiming::new();
+    timing.mark_started();
+    let started = tokio::time::Instant::now();
+    forward(ExecutionChunk::Status(ExecutionStatus::Running));
+
+    let execution = executor.execute_streaming(request, output);
+    tokio::pin!(execution);
+    let deadline = async {
+        match limits.wall_time {
+            Some(wall_time) => {
+                tokio::time::sleep(wall_time).await;
+                wall_time
+            }
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(deadline);
+
+    let mut ended = loop {
+        tokio::select! {
+            biased;
+            Some(chunk) = chunks.recv() => {
+                if let Some(stopped) = collected.accept(chunk, &mut forward) {
+                    break Ended::Stopped(stopped);
+                }
+            }
+            result = &mut execution => break Ended::Finished(Box::new(result?)),
+            wall_time = &mut deadline => break Ended::Stopped(Stopped::WallTime(wall_time)),
+        }
+    };
+    if let Ended::Finished(result) = &mut ended {
+        // Output written just before the executor returned, then output it
+        // could only report in the result
+        let mut stopped = None;
+        while let (None, Ok(chunk)) = (&stopped, chunks.try_recv()) {
+            stopped = collected.accept(chunk, &mut forward);
+        }
+        if let Some(stopped) = stopped.or_else(|| collected.accept_buffered(result, &mut forward)) {
+            ended = Ended::Stopped(stopped);
+        }
+    }
+    timing.mark_completed();
+    timing.duration_ms = Some(started.elapsed().as_millis() as u64);
+
+    let mut result = match ended {
+        Ended::Finished(mut result) => {
+            result.timing.started_at = timing.started_at;
+            result.timing.completed_at = timing.completed_at;
+            result.timing.duration_ms = timing.duration_ms;
+            *result
+        }
+        Ended::Stopped(stopped) => {
+            let (status, error) = match stopped {
+                Stopped::WallTime(limit) => (
+                    ExecutionStatus::TimedOut,
+                    ExecutionError::wall_time_exceeded(limit),
+                ),
+                Stopped::Output(limit) => (
+                    ExecutionStatus::Failed,
+                    ExecutionError::output_limit_exceeded(limit),
+                ),
+            };
+            let mut result =
+                ExecutionResult::failure(request_id, error, timing).with_correlation(correlation);
+            result.status = status;
+            result
+        }
+    };
+    collected.fill(&mut result);
+    forward(ExecutionChunk::Status(result.status));
+    Ok(result)
+}
+
+/// Run `request` on `executor` in the background, streaming its chunks.
+///
+/// The stream follows [`run_streaming`] and ends with the result; an
+/// executor error ends it with a failed result carrying the error.
+pub fn stream_execution(
+    executor: Arc<dyn CodeExecutor>,
+    request: ExecutionRequest,
+    limits: StreamLimits,
+) -> impl Stream<Item = ExecutionChunk> + Send + 'static {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let request_id = request.id;
+        let correlation = request.correlation();
+        let forward = tx.clone();
+        let result = run_streaming(executor.as_ref(), request, limits, move |chunk| {
+            let _ = forward.send(chunk);
+        })
+        .await
+        .unwrap_or_else(|e| executor_failure(request_id, &e).with_correlation(correlation));
+        let _ = tx.send(ExecutionChunk::Result(Box::new(result)));
+    });
+    UnboundedReceiverStream::new(rx)
+}
+
+/// Failed result for an execution that ended in an executor or runtime error.
+pub(crate) fn executor_failure(
+    request_id: Uuid,
+    error: &creto_common::CretoError,
+) -> ExecutionResult {
+    let mut timing = ExecutionTiming::new();
+    timing.mark_completed();
+    ExecutionResult::failure(
+        request_id,
+        ExecutionError::new("EXECUTOR_ERROR", error.to_string()),
+        timing,
+    )
+}
+
 /// Executor for running code in sandboxes.
 pub struct Executor {
     // TODO: Add execution queue, worker pool
-    _private: (),
+    /// Resource usage sampling configuration.
+    sampler_config: SamplerConfig,
 }
 
 impl Executor {
     /// Create a new executor.
     pub fn new() -> Self {
-        Self { _private: () }
+        Self {
+            sampler_config: SamplerConfig::default(),
+        }
+    }
+
+    /// Set the resource usage sampling configuration.
+    pub fn with_sampler_config(mut self, config: SamplerConfig) -> Self {
+        self.sampler_config = config;
+        self
+    }
+
+    /// Execute a request while sampling resource usage from `controller`.
+    ///
+    /// The sampled time series and its derived summary are attached to the
+    /// returned result.
+    pub async fn execute_sampled(
+        &self,
+        request: ExecutionRequest,
+        controller: Arc<dyn ResourceController>,
+    ) -> CretoResult<ExecutionResult> {
+        let mut sampler = UsageSampler::new(controller, self.sampler_config.clone());
+        // Take an initial reading so even very short executions have a sample.
+        if let Err(e) = sampler.sample().await {
+            tracing::warn!(error = %e, "Initial resource usage sample failed");
+        }
+
+        let handle = sampler.spawn();
+        let result = self.execute(request).await;
+        let sampled = handle.stop().await;
+
+        result.map(|r| r.with_sampled_usage(sampled))
     }
 
-    /// Execute a request.
+    /// Execute a request, buffering its output into the result.
     pub async fn execute(&self, request: ExecutionRequest) -> CretoResult<ExecutionResult> {
+        CodeExecutor::execute(self, request).await
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl CodeExecutor for Executor {
+    async fn execute_streaming(
+        &self,
+        request: ExecutionRequest,
+        _output: OutputSink,
+    ) -> CretoResult<ExecutionResult> {
         // TODO: Implement actual execution
         // 1. Validate sandbox exists and is ready
         // 2. Acquire sandbox lock
         // 3. Inject input data
-        // 4. Execute code with timeout
-        // 5. Capture output
-        // 6. Release sandbox lock
-        // 7. Return result
+        // 4. Execute code, writing output to the sink as it is produced
+        // 5. Release sandbox lock
+        // 6. Return result
 
         let mut timing = ExecutionTiming::new();
         timing.mark_started();
 
         // Placeholder: Return mock success
         timing.mark_completed();
-        Ok(ExecutionResult::success(
-            request.id,
-            serde_json::json!({"mock": true}),
-            timing,
-        ))
-    }
-}
-
-impl Default for Executor {
-    fn default() -> Self {
-        Self::new()
+        Ok(
+            ExecutionResult::success(request.id, serde_json::json!({"mock": true}), timing)
+                .with_correlation(request.correlation()),
+        )
     }
 }
 
@@ -290,6 +960,8 @@ impl Default for Executor {
 mod tests {
     use super::*;
     use crate::sandbox::SandboxId;
+    use crate::testing::ScriptedExecutor;
+    use tokio_stream::StreamExt;
 
     #[test]
     fn test_execution_request_builder() {
@@ -316,6 +988,215 @@ mod tests {
         assert!(timing.duration_ms.is_some());
     }
 
+    #[tokio::test]
+    async fn test_execute_sampled_attaches_series() {
+        struct FixedController;
+
+        #[async_trait::async_trait]
+        impl ResourceController for FixedController {
+            async fn current_usage(&self) -> CretoResult<ResourceUsage> {
+                Ok(ResourceUsage {
+                    memory_bytes: 4096,
+                    cpu_time_ms: 5,
+                    open_file_count: 2,
+                    ..Default::default()
+                })
+            }
+        }
+
+        let executor = Executor::new();
+        let request = ExecutionRequest::new(SandboxId::new(), "print('hello')");
+        let result = executor
+            .execute_sampled(request, Arc::new(FixedController))
+            .await
+            .unwrap();
+
+        assert!(!result.usage_samples.is_empty());
+        let usage = result.resource_usage.unwrap();
+        assert_eq!(usage.peak_memory_bytes, 4096);
+        assert_eq!(usage.cpu_time_ms, 5);
+    }
+
+    #[tokio::test]
+    async fn test_execute_inherits_correlation() {
+        let correlation = Correlation::new(Uuid::now_v7()).derive(Uuid::now_v7());
+        let request =
+            ExecutionRequest::new(SandboxId::new(), "print('hello')").with_correlation(correlation);
+
+        let result = Executor::new().execute(request).await.unwrap();
+        assert_eq!(result.correlation(), correlation);
+    }
+
+    fn chunk_kinds(chunks: &[ExecutionChunk]) -> Vec<String> {
+        chunks
+            .iter()
+            .map(|chunk| match chunk {
+                ExecutionChunk::Stdout(bytes) => {
+                    format!("out:{}", String::from_utf8_lossy(bytes))
+                }
+                ExecutionChunk::Stderr(bytes) => {
+                    format!("err:{}", String::from_utf8_lossy(bytes))
+                }
+                ExecutionChunk::Status(status) => format!("status:{status:?}"),
+                ExecutionChunk::Result(_) => "result".to_string(),
+            })
+            .collect()
+    }
+
+    async fn collect_stream(
+        executor: ScriptedExecutor,
+        limits: StreamLimits,
+    ) -> Vec<ExecutionChunk> {
+        let request = ExecutionRequest::new(SandboxId::new(), "run()");
+        stream_execution(Arc::new(executor), request, limits)
+            .collect()
+            .await
+    }
+
+    fn final_result(chunks: &[ExecutionChunk]) -> &ExecutionResult {
+        match chunks.last() {
+            Some(ExecutionChunk::Result(result)) => result,
+            other => panic!("expected the stream to end with a result, got {other:?}"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_chunks_in_order_with_timing() {
+        let executor = ScriptedExecutor::new();
+        executor.push_output(
+            Duration::from_millis(100),
+            [
+                ExecutionChunk::Stdout(b"one\n".to_vec()),
+                ExecutionChunk::Stderr(b"warn\n".to_vec()),
+                ExecutionChunk::Stdout(b"two\n".to_vec()),
+            ],
+        );
+
+        let chunks = collect_stream(executor, StreamLimits::unlimited()).await;
+        assert_eq!(
+            chunk_kinds(&chunks),
+            vec![
+                "status:Running",
+                "out:one\n",
+                "err:warn\n",
+                "out:two\n",
+                "status:Completed",
+                "result",
+            ]
+        );
+
+        let result = final_result(&chunks);
+        assert!(result.is_success());
+        assert_eq!(result.stdout.as_deref(), Some("one\ntwo\n"));
+        assert_eq!(result.stderr.as_deref(), Some("warn\n"));
+        assert!(result.timing.started_at.unwrap() <= result.timing.completed_at.unwrap());
+        assert_eq!(result.timing.duration_ms, Some(300));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_truncates_at_output_limit() {
+        let executor = ScriptedExecutor::new();
+        executor.push_output(
+            Duration::from_millis(10),
+            [
+                ExecutionChunk::Stdout(b"0123456789".to_vec()),
+                ExecutionChunk::Stderr(b"abcdefghij".to_vec()),
+                ExecutionChunk::Stdout(b"never".to_vec()),
+            ],
+        );
+        let limits = StreamLimits {
+            max_output_bytes: Some(15),
+            ..StreamLimits::unlimited()
+        };
+
+        let chunks = collect_stream(executor, limits).await;
+        assert_eq!(
+            chunk_kinds(&chunks),
+            vec![
+                "status:Running",
+                "out:0123456789",
+                "err:abcde",
+                "status:Failed",
+                "result",
+            ]
+        );
+
+        let result = final_result(&chunks);
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.error.as_ref().unwrap().code, "OUTPUT_LIMIT_EXCEEDED");
+        assert_eq!(result.stdout.as_deref(), Some("0123456789"));
+        assert_eq!(result.stderr.as_deref(), Some("abcde"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_stops_at_wall_time() {
+        let executor = ScriptedExecutor::new();
+        executor.push_output(
+            Duration::from_secs(1),
+            [
+                ExecutionChunk::Stdout(b"tick\n".to_vec()),
+                ExecutionChunk::Stdout(b"tick\n".to_vec()),
+                ExecutionChunk::Stdout(b"tick\n".to_vec()),
+            ],
+        );
+        let limits = StreamLimits {
+            wall_time: Some(Duration::from_millis(2500)),
+            ..StreamLimits::unlimited()
+        };
+
+        let chunks = collect_stream(executor, limits).await;
+        assert_eq!(
+            chunk_kinds(&chunks),
+            vec![
+                "status:Running",
+                "out:tick\n",
+                "out:tick\n",
+                "status:TimedOut",
+                "result",
+            ]
+        );
+
+        let result = final_result(&chunks);
+        assert_eq!(result.error.as_ref().unwrap().code, "WALL_TIME_EXCEEDED");
+        assert_eq!(result.timing.duration_ms, Some(2500));
+    }
+
+    #[tokio::test]
+    async fn test_buffered_execute_collects_streamed_output() {
+        let executor = ScriptedExecutor::new();
+        executor.push_output(
+            Duration::ZERO,
+            [
+                ExecutionChunk::Stdout(b"hello ".to_vec()),
+                ExecutionChunk::Stdout(b"world".to_vec()),
+            ],
+        );
+
+        let result =
+            CodeExecutor::execute(&executor, ExecutionRequest::new(SandboxId::new(), "run()"))
+                .await
+                .unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.stdout.as_deref(), Some("hello world"));
+    }
+
+    #[test]
+    fn test_stream_limits_for_request() {
+        let limits = ResourceLimits::default().with_max_output(Some(1024));
+        let request = ExecutionRequest::new(SandboxId::new(), "run()").with_timeout(30);
+        let stream = StreamLimits::for_request(&limits, &request);
+        assert_eq!(stream.wall_time, Some(Duration::from_secs(30)));
+        assert_eq!(stream.max_output_bytes, Some(1024));
+
+        // A request cannot extend the sandbox's wall time
+        let request = ExecutionRequest::new(SandboxId::new(), "run()").with_timeout(u32::MAX);
+        let stream = StreamLimits::for_request(&limits, &request);
+        assert_eq!(
+            stream.wall_time,
+            Some(Duration::from_secs(limits.wall_time_seconds as u64))
+        );
+    }
+
     #[test]
     fn test_execution_error() {
         let error = ExecutionError::timeout(300);
@@ -0,0 +1,536 @@
+//! Sandbox filesystem change tracking.
+//!
+//! Before an execution the sandbox workdir is scanned into a
+//! [`FilesystemManifest`] (path, size and BLAKE3 hash of every file); after it
+//! the workdir is scanned again and the two manifests are compared into a
+//! [`FilesystemDiff`]. Scans are bounded by file count, directory entries
+//! visited and hashing budget, and never follow symlinks: a link is recorded as an entry hashed over its
+//! target path, so nothing outside the workdir is ever read.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use creto_common::{CretoError, CretoResult};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::sandbox::SandboxId;
+
+/// Filesystem change tracking settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilesystemTrackingConfig {
+    /// Maximum files recorded per scan; further files are not scanned and
+    /// the manifest is marked truncated.
+    pub max_files: usize,
+
+    /// Maximum directory entries visited per scan, counting directories and
+    /// ignored paths; further entries are not scanned and the manifest is
+    /// marked truncated.
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+
+    /// Files larger than this are recorded by size only.
+    pub max_file_bytes: u64,
+
+    /// Total bytes hashed per scan; once spent, files are recorded by size
+    /// only.
+    pub max_total_bytes: u64,
+
+    /// Paths to skip. A pattern without `/` matches any path component
+    /// (`tmp`, `*.pyc`); one with `/` matches the whole relative path, with
+    /// `*` within a component and `**` across components.
+    pub ignore_patterns: Vec<String>,
+
+    /// Store the full diff so it can be retrieved after the execution.
+    pub persist_diffs: bool,
+}
+
+impl Default for FilesystemTrackingConfig {
+    fn default() -> Self {
+        Self {
+            max_files: 10_000,
+            max_entries: default_max_entries(),
+            max_file_bytes: 64 * 1024 * 1024,
+            max_total_bytes: 512 * 1024 * 1024,
+            ignore_patterns: vec![
+                "tmp".to_string(),
+                ".cache".to_string(),
+                "__pycache__".to_string(),
+                "*.pyc".to_string(),
+            ],
+            persist_diffs: false,
+        }
+    }
+}
+
+fn default_max_entries() -> usize {
+    100_000
+}
+
+impl FilesystemTrackingConfig {
+    /// Check whether a workdir-relative path is ignored.
+    pub fn is_ignored(&self, relative: &str) -> bool {
+        let components: Vec<&str> = relative.split('/').collect();
+        self.ignore_patterns.iter().any(|pattern| {
+            if pattern.contains('/') {
+                let pattern: Vec<&str> = pattern.trim_matches('/').split('/').collect();
+                path_matches(&pattern, &components)
+            } else {
+                components.iter().any(|c| wildcard_matches(pattern, c))
+            }
+        })
+    }
+}
+
+fn path_matches(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| path_matches(rest, &path[skip..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((component, path_rest)) => {
+                wildcard_matches(first, component) && path_matches(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Match a single path component against a pattern where `*` matches any
+/// run of characters.
+fn wildcard_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// What a manifest entry is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    /// Regular file.
+    File,
+    /// Symbolic link (hashed over its target path, never followed).
+    Symlink,
+}
+
+/// A file recorded in a manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    /// Path relative to the workdir, `/`-separated.
+    pub path: String,
+    /// File or symlink.
+    pub kind: FileKind,
+    /// Size in bytes.
+    pub size: u64,
+    /// Hex BLAKE3 hash, absent if the file exceeded the hashing limits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+/// Snapshot of a workdir's files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilesystemManifest {
+    /// Entries keyed by relative path.
+    pub entries: BTreeMap<String, FileEntry>,
+    /// True if the file or entry limit stopped the scan early.
+    pub truncated: bool,
+}
+
+impl FilesystemManifest {
+    /// Scan `root`, applying the config's limits and ignore patterns.
+    ///
+    /// Blocking; run it on a blocking thread.
+    pub fn scan(root: &Path, config: &FilesystemTrackingConfig) -> CretoResult<Self> {
+        let mut manifest = Self::default();
+        let mut hash_budget = config.max_total_bytes;
+        let mut entry_budget = config.max_entries;
+        let mut pending = vec![PathBuf::new()];
+
+        while let Some(relative_dir) = pending.pop() {
+            // One past the budget, to tell a full directory from a cut one
+            let mut children: Vec<_> = std::fs::read_dir(root.join(&relative_dir))
+                .map_err(|e| io_error(&relative_dir, e))?
+                .filter_map(Result::ok)
+                .take(entry_budget.saturating_add(1))
+                .collect();
+            if children.len() > entry_budget {
+                manifest.truncated = true;
+                children.truncate(entry_budget);
+            }
+            entry_budget -= children.len();
+            children.sort_by_key(|c| c.file_name());
+
+            for child in children {
+                let relative = relative_dir.join(child.file_name());
+                let key = relative_key(&relative);
+                if config.is_ignored(&key) {
+                    continue;
+                }
+
+                // symlink_metadata: never traverse a link
+                let Ok(metadata) = std::fs::symlink_metadata(child.path()) else {
+                    continue;
+                };
+                let file_type = metadata.file_type();
+
+                if file_type.is_dir() {
+                    pending.push(relative);
+                    continue;
+                }
+                if manifest.entries.len() >= config.max_files {
+                    manifest.truncated = true;
+                    return Ok(manifest);
+                }
+
+                let entry = if file_type.is_symlink() {
+                    let target =
+                        std::fs::read_link(child.path()).map_err(|e| io_error(&relative, e))?;
+                    let target = target.to_string_lossy();
+                    FileEntry {
+                        path: key.clone(),
+                        kind: FileKind::Symlink,
+                        size: target.len() as u64,
+                        hash: Some(blake3::hash(target.as_bytes()).to_hex().to_string()),
+                    }
+                } else if file_type.is_file() {
+                    let size = metadata.len();
+                    let hash = if size <= config.max_file_bytes && size <= hash_budget {
+                        hash_budget -= size;
+                        Some(hash_file(&child.path(), size).map_err(|e| io_error(&relative, e))?)
+                    } else {
+                        None
+                    };
+                    FileEntry {
+                        path: key.clone(),
+                        kind: FileKind::File,
+                        size,
+                        hash,
+                    }
+                } else {
+                    // Sockets, FIFOs, devices
+                    continue;
+                };
+                manifest.entries.insert(key, entry);
+            }
+        }
+
+        Ok(manifest)
+    }
+}
+
+fn relative_key(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Hash the first `size` bytes of a file, so a file growing during the scan
+/// cannot exceed the hashing budget.
+fn hash_file(path: &Path, size: u64) -> std::io::Result<String> {
+    use std::io::Read;
+
+    let mut hasher = blake3::Hasher::new();
+    let mut file = std::fs::File::open(path)?.take(size);
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+fn io_error(relative: &Path, e: std::io::Error) -> CretoError {
+    CretoError::Internal(format!(
+        "Failed to scan sandbox workdir at {:?}: {}",
+        relative, e
+    ))
+}
+
+/// How a file changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Present only after the execution.
+    Created,
+    /// Present before and after with a different hash, size or kind.
+    Modified,
+    /// Present only before the execution.
+    Deleted,
+}
+
+/// One changed file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    /// Path relative to the workdir.
+    pub path: String,
+    /// Kind of change.
+    pub change: ChangeKind,
+    /// Entry before the execution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<FileEntry>,
+    /// Entry after the execution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<FileEntry>,
+}
+
+impl FileChange {
+    /// Bytes affected: the new size for created and modified files, the old
+    /// size for deleted ones.
+    pub fn bytes_changed(&self) -> u64 {
+        match self.change {
+            ChangeKind::Created | ChangeKind::Modified => self.after.as_ref().map_or(0, |e| e.size),
+            ChangeKind::Deleted => self.before.as_ref().map_or(0, |e| e.size),
+        }
+    }
+}
+
+/// Files an execution created, modified or deleted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilesystemDiff {
+    /// Execution the diff belongs to.
+    pub execution_id: Uuid,
+    /// Changed files, sorted by path.
+    pub changes: Vec<FileChange>,
+    /// True if either scan hit the file limit, so changes may be missing.
+    pub truncated: bool,
+}
+
+impl FilesystemDiff {
+    /// Compare the manifests taken before and after an execution.
+    pub fn between(
+        execution_id: Uuid,
+        before: &FilesystemManifest,
+        after: &FilesystemManifest,
+    ) -> Self {
+        let mut changes = Vec::new();
+
+        for (path, old) in &before.entries {
+            match after.entries.get(path) {
+                None => changes.push(FileChange {
+                    path: path.clone(),
+                    change: ChangeKind::Deleted,
+                    before: Some(old.clone()),
+                    after: None,
+                }),
+                Some(new) if new != old => changes.push(FileChange {
+                    path: path.clone(),
+                    change: ChangeKind::Modified,
+                    before: Some(old.clone()),
+                    after: Some(new.clone()),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (path, new) in &after.entries {
+            if !before.entries.contains_key(path) {
+                changes.push(FileChange {
+                    path: path.clone(),
+                    change: ChangeKind::Created,
+                    before: None,
+                    after: Some(new.clone()),
+                });
+            }
+        }
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self {
+            execution_id,
+            changes,
+            truncated: before.truncated || after.truncated,
+        }
+    }
+
+    /// Counts and bytes changed.
+    pub fn summary(&self) -> FilesystemChangeSummary {
+        let count = |kind| self.changes.iter().filter(|c| c.change == kind).count();
+        FilesystemChangeSummary {
+            created: count(ChangeKind::Created),
+            modified: count(ChangeKind::Modified),
+            deleted: count(ChangeKind::Deleted),
+            bytes_changed: self.changes.iter().map(FileChange::bytes_changed).sum(),
+            truncated: self.truncated,
+        }
+    }
+}
+
+/// Summary of an execution's filesystem changes, attached to its result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilesystemChangeSummary {
+    /// Files created.
+    pub created: usize,
+    /// Files modified.
+    pub modified: usize,
+    /// Files deleted.
+    pub deleted: usize,
+    /// Total bytes across changed files.
+    pub bytes_changed: u64,
+    /// True if the scans were cut short by the file limit.
+    pub truncated: bool,
+}
+
+/// Locates the host directory backing a sandbox's workdir.
+///
+/// Implemented by sandbox backends that expose their filesystem; any
+/// `Fn(SandboxId) -> Option<PathBuf>` also works.
+pub trait WorkdirResolver: Send + Sync {
+    /// Workdir for a sandbox, or None if it cannot be inspected.
+    fn workdir(&self, sandbox_id: SandboxId) -> Option<PathBuf>;
+}
+
+impl<F> WorkdirResolver for F
+where
+    F: Fn(SandboxId) -> Option<PathBuf> + Send + Sync,
+{
+    fn workdir(&self, sandbox_id: SandboxId) -> Option<PathBuf> {
+        self(sandbox_id)
+    }
+}
+
+/// Storage for full filesystem diffs.
+#[async_trait]
+pub trait FilesystemDiffStore: Send + Sync {
+    /// Store an execution's diff, replacing any earlier one.
+    async fn put(&self, diff: &FilesystemDiff) -> CretoResult<()>;
+
+    /// Get an execution's diff.
+    async fn get(&self, execution_id: Uuid) -> CretoResult<Option<FilesystemDiff>>;
+}
+
+/// In-memory diff store for testing and development.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryFilesystemDiffStore {
+    diffs: Arc<RwLock<HashMap<Uuid, FilesystemDiff>>>,
+}
+
+impl InMemoryFilesystemDiffStore {
+    /// Create a new in-memory diff store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl FilesystemDiffStore for InMemoryFilesystemDiffStore {
+    async fn put(&self, diff: &FilesystemDiff) -> CretoResult<()> {
+        self.diffs
+            .write()
+            .unwrap()
+            .insert(diff.execution_id, diff.clone());
+        Ok(())
+    }
+
+    async fn get(&self, execution_id: Uuid) -> CretoResult<Option<FilesystemDiff>> {
+        Ok(self.diffs.read().unwrap().get(&execution_id).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_patterns() {
+        let config = FilesystemTrackingConfig {
+            ignore_patterns: vec![
+                "tmp".to_string(),
+                "*.pyc".to_string(),
+                "build/**/cache".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        assert!(config.is_ignored("tmp"));
+        assert!(config.is_ignored("src/tmp/scratch.txt"));
+        assert!(config.is_ignored("pkg/mod.pyc"));
+        assert!(config.is_ignored("build/cache"));
+        assert!(config.is_ignored("build/a/b/cache"));
+        assert!(!config.is_ignored("tmpfile.txt"));
+        assert!(!config.is_ignored("src/main.py"));
+        assert!(!config.is_ignored("other/build/cache"));
+    }
+
+    #[test]
+    fn test_scan_limits() {
+        let root = std::env::temp_dir().join(format!("creto-fs-{}", Uuid::now_v7()));
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("a.txt"), "alpha").unwrap();
+        std::fs::write(root.join("data/big.bin"), vec![0u8; 64]).unwrap();
+        std::fs::write(root.join("data/c.txt"), "gamma").unwrap();
+
+        let config = FilesystemTrackingConfig {
+            max_file_bytes: 32,
+            ..Default::default()
+        };
+        let manifest = FilesystemManifest::scan(&root, &config).unwrap();
+        assert!(!manifest.truncated);
+        assert_eq!(manifest.entries.len(), 3);
+        assert_eq!(
+            manifest.entries["a.txt"].hash.as_deref(),
+            Some(blake3::hash(b"alpha").to_hex().as_str())
+        );
+        assert_eq!(manifest.entries["data/big.bin"].size, 64);
+        assert_eq!(manifest.entries["data/big.bin"].hash, None);
+
+        let config = FilesystemTrackingConfig {
+            max_files: 2,
+            ..Default::default()
+        };
+        let manifest = FilesystemManifest::scan(&root, &config).unwrap();
+        assert!(manifest.truncated);
+        assert_eq!(manifest.entries.len(), 2);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_bounds_entries_visited() {
+        let root = std::env::temp_dir().join(format!("creto-fs-{}", Uuid::now_v7()));
+        // Directories and ignored files hold no manifest entries but still
+        // cost a visit each
+        for i in 0..20 {
+            std::fs::create_dir_all(root.join(format!("d{i:02}/tmp"))).unwrap();
+            std::fs::write(root.join(format!("d{i:02}/tmp/x")), "x").unwrap();
+        }
+
+        let manifest =
+            FilesystemManifest::scan(&root, &FilesystemTrackingConfig::default()).unwrap();
+        assert!(!manifest.truncated);
+        assert!(manifest.entries.is_empty());
+
+        let config = FilesystemTrackingConfig {
+            max_entries: 10,
+            ..Default::default()
+        };
+        let manifest = FilesystemManifest::scan(&root, &config).unwrap();
+        assert!(manifest.truncated);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_hash_file_stops_at_scanned_size() {
+        let path = std::env::temp_dir().join(format!("creto-fs-{}", Uuid::now_v7()));
+        std::fs::write(&path, "alpha, then appended").unwrap();
+
+        assert_eq!(
+            hash_file(&path, 5).unwrap(),
+            blake3::hash(b"alpha").to_hex().to_string()
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,397 @@
+//! Execution history queries.
+//!
+//! Recorded executions are listed newest first through
+//! [`ExecutionRepository::find`](crate::repository::ExecutionRepository::find)
+//! with an [`ExecutionFilter`] and a [`PageRequest`], and summarized per
+//! organization with
+//! [`ExecutionRepository::stats`](crate::repository::ExecutionRepository::stats).
+//!
+//! Pages are addressed by offset or by an opaque cursor. Cursors are stable
+//! while new executions arrive; offsets shift.
+
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use creto_common::{percentile_cont, AgentId, CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::execution::ExecutionStatus;
+use crate::repository::ExecutionRecord;
+use crate::sandbox::SandboxId;
+
+/// Largest page a repository returns, whatever the request asks for.
+pub const MAX_PAGE_SIZE: u32 = 1000;
+
+/// A half-open time range, `start` inclusive and `end` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeRange {
+    /// Start of the range (inclusive).
+    pub start: DateTime<Utc>,
+    /// End of the range (exclusive).
+    pub end: DateTime<Utc>,
+}
+
+impl TimeRange {
+    /// Create a range.
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self { start, end }
+    }
+
+    /// The `duration` up to `now`, e.g. the last 24 hours.
+    pub fn last(duration: chrono::Duration, now: DateTime<Utc>) -> Self {
+        Self::new(now - duration, now)
+    }
+
+    /// Whether `at` falls in the range.
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.start <= at && at < self.end
+    }
+}
+
+/// Which executions to return. Unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecutionFilter {
+    /// Organization owning the sandbox.
+    pub organization_id: Option<OrganizationId>,
+    /// Agent owning the sandbox.
+    pub agent_id: Option<AgentId>,
+    /// Sandbox the execution ran in.
+    pub sandbox_id: Option<SandboxId>,
+    /// Current status.
+    pub status: Option<ExecutionStatus>,
+    /// When the execution was queued.
+    pub queued: Option<TimeRange>,
+    /// Minimum recorded duration; executions without one never match.
+    pub min_duration_ms: Option<i64>,
+}
+
+impl ExecutionFilter {
+    /// Match everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only executions in an organization's sandboxes.
+    pub fn for_organization(mut self, organization_id: OrganizationId) -> Self {
+        self.organization_id = Some(organization_id);
+        self
+    }
+
+    /// Only executions in an agent's sandboxes.
+    pub fn for_agent(mut self, agent_id: AgentId) -> Self {
+        self.agent_id = Some(agent_id);
+        self
+    }
+
+    /// Only executions in one sandbox.
+    pub fn for_sandbox(mut self, sandbox_id: SandboxId) -> Self {
+        self.sandbox_id = Some(sandbox_id);
+        self
+    }
+
+    /// Only executions with a status.
+    pub fn with_status(mut self, status: ExecutionStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Only executions queued within `range`.
+    pub fn queued_within(mut self, range: TimeRange) -> Self {
+        self.queued = Some(range);
+        self
+    }
+
+    /// Only executions that ran for at least `duration_ms`.
+    pub fn with_min_duration_ms(mut self, duration_ms: i64) -> Self {
+        self.min_duration_ms = Some(duration_ms);
+        self
+    }
+
+    /// Whether `record` matches, given the organization and agent owning
+    /// its sandbox (`None` if unknown, which fails any owner condition).
+    pub fn matches(
+        &self,
+        record: &ExecutionRecord,
+        owner: Option<(OrganizationId, AgentId)>,
+    ) -> bool {
+        let owner_matches = match (self.organization_id, self.agent_id) {
+            (None, None) => true,
+            (organization_id, agent_id) => owner.is_some_and(|(org, agent)| {
+                !matches!(organization_id, Some(o) if o != org)
+                    && !matches!(agent_id, Some(a) if a != agent)
+            }),
+        };
+        owner_matches
+            && !matches!(self.sandbox_id, Some(s) if s != record.sandbox_id)
+            && !matches!(self.status, Some(s) if s != record.status)
+            && !matches!(self.queued, Some(r) if !r.contains(record.queued_at))
+            && match self.min_duration_ms {
+                Some(min) => record.duration_ms.is_some_and(|d| d >= min),
+                None => true,
+            }
+    }
+}
+
+/// Where a page starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PagePosition {
+    /// Skip this many items.
+    Offset(u64),
+    /// Continue after a cursor returned with a previous page.
+    After(String),
+}
+
+/// A page to fetch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRequest {
+    /// Where the page starts.
+    pub position: PagePosition,
+    /// Maximum items to return (capped at [`MAX_PAGE_SIZE`]).
+    pub limit: u32,
+    /// Whether to count every matching item as well.
+    pub include_total: bool,
+}
+
+impl PageRequest {
+    /// The first page.
+    pub fn first(limit: u32) -> Self {
+        Self::at_offset(0, limit)
+    }
+
+    /// The page starting `offset` items in.
+    pub fn at_offset(offset: u64, limit: u32) -> Self {
+        Self {
+            position: PagePosition::Offset(offset),
+            limit,
+            include_total: false,
+        }
+    }
+
+    /// The page following `cursor`.
+    pub fn after(cursor: impl Into<String>, limit: u32) -> Self {
+        Self {
+            position: PagePosition::After(cursor.into()),
+            limit,
+            include_total: false,
+        }
+    }
+
+    /// Also count every matching item.
+    pub fn with_total(mut self) -> Self {
+        self.include_total = true;
+        self
+    }
+
+    /// The limit to apply, between 1 and [`MAX_PAGE_SIZE`].
+    pub fn effective_limit(&self) -> u32 {
+        self.limit.clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        Self::first(50)
+    }
+}
+
+/// A page of results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page<T> {
+    /// Items on this page.
+    pub items: Vec<T>,
+    /// Cursor for the next page, if there are more items.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Number of matching items, if requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+/// Keyset position in an execution listing ordered newest first by queue
+/// time, then ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExecutionCursor {
+    /// Queue time of the last execution returned.
+    pub queued_at: DateTime<Utc>,
+    /// ID of the last execution returned.
+    pub id: Uuid,
+}
+
+impl ExecutionCursor {
+    /// Cursor positioned just after `record`.
+    pub fn after(record: &ExecutionRecord) -> Self {
+        Self {
+            queued_at: record.queued_at,
+            id: record.id,
+        }
+    }
+
+    /// Whether `record` comes after the cursor in listing order.
+    pub fn precedes(&self, record: &ExecutionRecord) -> bool {
+        (record.queued_at, record.id) < (self.queued_at, self.id)
+    }
+
+    /// Encode as an opaque, URL-safe string.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}/{}",
+            self.queued_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            self.id
+        ))
+    }
+
+    /// Decode a string from [`encode`](Self::encode).
+    ///
+    /// Fails with [`CretoError::ValidationFailed`] for anything else.
+    pub fn decode(cursor: &str) -> CretoResult<Self> {
+        let invalid = || CretoError::ValidationFailed(format!("Invalid page cursor: {}", cursor));
+        let decoded = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+        let (queued_at, id) = decoded.split_once('/').ok_or_else(invalid)?;
+        Ok(Self {
+            queued_at: DateTime::parse_from_rfc3339(queued_at)
+                .map_err(|_| invalid())?
+                .with_timezone(&Utc),
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Execution counts and durations for an organization over a time range.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionStats {
+    /// Organization the stats cover.
+    pub organization_id: OrganizationId,
+    /// Queue times covered.
+    pub range: TimeRange,
+    /// Executions queued in the range.
+    pub total: u64,
+    /// Executions per current status; statuses with none are absent.
+    pub by_status: HashMap<ExecutionStatus, u64>,
+    /// Median duration of executions with a recorded duration.
+    pub p50_duration_ms: Option<f64>,
+    /// 95th percentile duration of executions with a recorded duration.
+    pub p95_duration_ms: Option<f64>,
+}
+
+impl ExecutionStats {
+    /// Compute stats from an organization's records in `range`, the way
+    /// PostgreSQL's `percentile_cont` does.
+    pub fn from_records<'a>(
+        organization_id: OrganizationId,
+        range: TimeRange,
+        records: impl IntoIterator<Item = &'a ExecutionRecord>,
+    ) -> Self {
+        let mut by_status = HashMap::new();
+        let mut durations = Vec::new();
+        for record in records {
+            *by_status.entry(record.status).or_insert(0) += 1;
+            durations.extend(record.duration_ms.map(|ms| ms as f64));
+        }
+        durations.sort_by(f64::total_cmp);
+        Self {
+            organization_id,
+            range,
+            total: by_status.values().sum(),
+            by_status,
+            p50_duration_ms: percentile_cont(&durations, 0.5),
+            p95_duration_ms: percentile_cont(&durations, 0.95),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(duration_ms: Option<i64>, status: ExecutionStatus) -> ExecutionRecord {
+        ExecutionRecord {
+            id: Uuid::now_v7(),
+            sandbox_id: SandboxId::new(),
+            status,
+            queued_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            duration_ms,
+            correlation_id: None,
+            caused_by: None,
+        }
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let cursor = ExecutionCursor::after(&record(None, ExecutionStatus::Queued));
+        let encoded = cursor.encode();
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(ExecutionCursor::decode(&encoded).unwrap(), cursor);
+
+        for bad in ["", "not base64!", &URL_SAFE_NO_PAD.encode("2026-01-01/x")] {
+            assert!(matches!(
+                ExecutionCursor::decode(bad),
+                Err(CretoError::ValidationFailed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let (org, agent) = (OrganizationId::new(), AgentId::new());
+        let failed = record(Some(900), ExecutionStatus::Failed);
+        let owner = Some((org, agent));
+
+        assert!(ExecutionFilter::new().matches(&failed, None));
+        let filter = ExecutionFilter::new()
+            .for_agent(agent)
+            .with_status(ExecutionStatus::Failed)
+            .queued_within(TimeRange::last(
+                chrono::Duration::hours(24),
+                failed.queued_at,
+            ));
+        assert!(!filter.matches(&failed, owner));
+        let filter = filter.queued_within(TimeRange::new(
+            failed.queued_at,
+            failed.queued_at + chrono::Duration::seconds(1),
+        ));
+        assert!(filter.matches(&failed, owner));
+        assert!(!filter.matches(&failed, None));
+        assert!(!filter
+            .clone()
+            .for_organization(OrganizationId::new())
+            .matches(&failed, owner));
+        assert!(filter
+            .clone()
+            .with_min_duration_ms(900)
+            .matches(&failed, owner));
+        assert!(!filter.with_min_duration_ms(901).matches(&failed, owner));
+        assert!(!ExecutionFilter::new()
+            .with_min_duration_ms(0)
+            .matches(&record(None, ExecutionStatus::Failed), None));
+    }
+
+    #[test]
+    fn test_stats_percentiles_match_percentile_cont() {
+        let records: Vec<_> = [10, 20, 30, 40, 50, 60, 70, 80, 90, 100]
+            .into_iter()
+            .map(|ms| record(Some(ms), ExecutionStatus::Completed))
+            .chain([record(None, ExecutionStatus::Running)])
+            .collect();
+        let range = TimeRange::last(chrono::Duration::hours(1), Utc::now());
+        let stats = ExecutionStats::from_records(OrganizationId::new(), range, &records);
+
+        assert_eq!(stats.total, 11);
+        assert_eq!(stats.by_status[&ExecutionStatus::Completed], 10);
+        assert_eq!(stats.by_status[&ExecutionStatus::Running], 1);
+        // percentile_cont(0.5) over 10..=100 is 55, (0.95) is 95.5
+        assert_eq!(stats.p50_duration_ms, Some(55.0));
+        assert!((stats.p95_duration_ms.unwrap() - 95.5).abs() < 1e-9);
+
+        let empty = ExecutionStats::from_records(OrganizationId::new(), range, &[]);
+        assert_eq!(empty.total, 0);
+        assert_eq!(empty.p50_duration_ms, None);
+    }
+}
@@ -0,0 +1,389 @@
+//! Idempotent execution requests.
+//!
+//! An execution carrying an
+//! [`idempotency_key`](crate::execution::ExecutionRequest::idempotency_key)
+//! runs at most once per sandbox and key within the
+//! [`ttl`](IdempotencyConfig::ttl). A retry with the same key gets the first
+//! execution's result, whatever its status, without running the code again;
+//! a retry arriving while the first execution is still running waits for
+//! its result. A retry whose payload differs from the first fails with
+//! [`CretoError::IdempotencyConflict`].
+//!
+//! Only results are remembered. An attempt that fails without one, for
+//! example because the service was at capacity, releases the key and the
+//! next retry runs.
+//!
+//! Results are cached in memory and, with an execution repository,
+//! recorded on the execution's row, so a restarted service still replays
+//! them.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, CretoResult};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use crate::execution::{ExecutionRequest, ExecutionResult};
+use crate::sandbox::SandboxId;
+
+/// Idempotency key settings.
+#[derive(Debug, Clone)]
+pub struct IdempotencyConfig {
+    /// How long a result is replayed for its key.
+    pub ttl: Duration,
+    /// Most results cached in memory. The oldest give way first; results
+    /// recorded in the execution repository can still be replayed.
+    pub max_entries: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(24 * 60 * 60),
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// An execution result recorded under an idempotency key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotentExecution {
+    /// Sandbox the key is scoped to.
+    pub sandbox_id: SandboxId,
+    /// The idempotency key.
+    pub key: String,
+    /// [`request_fingerprint`] of the request that produced the result.
+    pub fingerprint: String,
+    /// The result replayed to retries.
+    pub result: ExecutionResult,
+    /// When the result was recorded.
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl IdempotentExecution {
+    /// Whether the result is no longer replayed at `now`.
+    pub fn is_expired_at(&self, now: DateTime<Utc>, ttl: Duration) -> bool {
+        matches!(chrono::Duration::from_std(ttl), Ok(ttl) if self.recorded_at + ttl <= now)
+    }
+}
+
+/// Hash of the parts of a request that decide what it runs.
+///
+/// Trace identifiers and scheduling options are left out, so a retry may
+/// change them.
+pub fn request_fingerprint(request: &ExecutionRequest) -> String {
+    let payload = serde_json::json!({
+        "code": request.code,
+        "entry_point": request.entry_point,
+        "input": request.input,
+        "timeout_seconds": request.timeout_seconds,
+        "environment": request.environment,
+        "artifacts": request.artifacts,
+    });
+    blake3::hash(payload.to_string().as_bytes())
+        .to_hex()
+        .to_string()
+}
+
+/// A request's idempotency key with what is needed to claim it.
+#[derive(Debug, Clone)]
+pub(crate) struct IdempotencyKey {
+    pub(crate) sandbox_id: SandboxId,
+    pub(crate) key: String,
+    fingerprint: String,
+    execution_id: Uuid,
+}
+
+impl IdempotencyKey {
+    /// The request's key, if it has one.
+    pub(crate) fn of(request: &ExecutionRequest) -> Option<Self> {
+        request.idempotency_key.as_ref().map(|key| Self {
+            sandbox_id: request.sandbox_id,
+            key: key.clone(),
+            fingerprint: request_fingerprint(request),
+            execution_id: request.id,
+        })
+    }
+
+    fn slot(&self) -> (SandboxId, String) {
+        (self.sandbox_id, self.key.clone())
+    }
+
+    fn conflict(&self, execution_id: Uuid) -> CretoError {
+        CretoError::IdempotencyConflict {
+            key: self.key.clone(),
+            execution_id,
+        }
+    }
+}
+
+enum Entry {
+    InFlight {
+        fingerprint: String,
+        execution_id: Uuid,
+        done: watch::Receiver<Option<ExecutionResult>>,
+    },
+    Completed(Box<IdempotentExecution>),
+}
+
+/// Outcome of claiming an idempotency key.
+pub(crate) enum Claim<'a> {
+    /// The key is free: run the request and complete the permit.
+    Run(IdempotencyPermit<'a>),
+    /// The key already has a result.
+    Replay(Box<ExecutionResult>),
+    /// Another execution holds the key; it sends its result when done, or
+    /// closes the channel if it ends without one.
+    Wait(watch::Receiver<Option<ExecutionResult>>),
+}
+
+/// In-memory map from sandbox and idempotency key to execution result.
+pub(crate) struct IdempotencyCache {
+    config: IdempotencyConfig,
+    entries: Mutex<HashMap<(SandboxId, String), Entry>>,
+}
+
+impl IdempotencyCache {
+    pub(crate) fn new(config: IdempotencyConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the key is running or has an unexpired result.
+    pub(crate) fn contains(&self, key: &IdempotencyKey, now: DateTime<Utc>) -> bool {
+        match self.entries.lock().unwrap().get(&key.slot()) {
+            Some(Entry::InFlight { .. }) => true,
+            Some(Entry::Completed(record)) => !record.is_expired_at(now, self.config.ttl),
+            None => false,
+        }
+    }
+
+    /// Cache a result loaded from storage, unless the key already has an
+    /// entry or the result has expired.
+    pub(crate) fn restore(&self, record: IdempotentExecution, now: DateTime<Utc>) {
+        if record.is_expired_at(now, self.config.ttl) {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        let slot = (record.sandbox_id, record.key.clone());
+        if !entries.contains_key(&slot) {
+            self.insert(&mut entries, slot, Entry::Completed(Box::new(record)), now);
+        }
+    }
+
+    /// Claim a key, failing if it is held for a different payload.
+    pub(crate) fn claim(&self, key: &IdempotencyKey, now: DateTime<Utc>) -> CretoResult<Claim<'_>> {
+        let mut entries = self.entries.lock().unwrap();
+        let slot = key.slot();
+        match entries.get(&slot) {
+            Some(Entry::InFlight {
+                fingerprint,
+                execution_id,
+                done,
+            }) => {
+                if *fingerprint != key.fingerprint {
+                    return Err(key.conflict(*execution_id));
+                }
+                return Ok(Claim::Wait(done.clone()));
+            }
+            Some(Entry::Completed(record)) if !record.is_expired_at(now, self.config.ttl) => {
+                if record.fingerprint != key.fingerprint {
+                    return Err(key.conflict(record.result.request_id));
+                }
+                return Ok(Claim::Replay(Box::new(record.result.clone())));
+            }
+            _ => {}
+        }
+
+        let (sender, done) = watch::channel(None);
+        let entry = Entry::InFlight {
+            fingerprint: key.fingerprint.clone(),
+            execution_id: key.execution_id,
+            done,
+        };
+        self.insert(&mut entries, slot, entry, now);
+        Ok(Claim::Run(IdempotencyPermit {
+            cache: self,
+            key: key.clone(),
+            sender: Some(sender),
+        }))
+    }
+
+    /// Insert an entry, first making room by dropping expired results and
+    /// then the oldest ones. Running entries are never dropped.
+    fn insert(
+        &self,
+        entries: &mut HashMap<(SandboxId, String), Entry>,
+        slot: (SandboxId, String),
+        entry: Entry,
+        now: DateTime<Utc>,
+    ) {
+        if !entries.contains_key(&slot) && entries.len() >= self.config.max_entries {
+            entries.retain(|_, entry| {
+                !matches!(entry, Entry::Completed(record) if record.is_expired_at(now, self.config.ttl))
+            });
+            while entries.len() >= self.config.max_entries {
+                let oldest = entries
+                    .iter()
+                    .filter_map(|(slot, entry)| match entry {
+                        Entry::Completed(record) => Some((slot, record.recorded_at)),
+                        Entry::InFlight { .. } => None,
+                    })
+                    .min_by_key(|(_, recorded_at)| *recorded_at)
+                    .map(|(slot, _)| slot.clone());
+                match oldest {
+                    Some(slot) => entries.remove(&slot),
+                    None => break,
+                };
+            }
+        }
+        entries.insert(slot, entry);
+    }
+}
+
+/// A claimed idempotency key.
+///
+/// Dropping the permit without completing it releases the key, so waiting
+/// retries claim it again.
+pub(crate) struct IdempotencyPermit<'a> {
+    cache: &'a IdempotencyCache,
+    key: IdempotencyKey,
+    sender: Option<watch::Sender<Option<ExecutionResult>>>,
+}
+
+impl IdempotencyPermit<'_> {
+    /// Record the execution's result, handing it to waiting retries.
+    pub(crate) fn complete(
+        mut self,
+        result: &ExecutionResult,
+        now: DateTime<Utc>,
+    ) -> IdempotentExecution {
+        let record = IdempotentExecution {
+            sandbox_id: self.key.sandbox_id,
+            key: self.key.key.clone(),
+            fingerprint: self.key.fingerprint.clone(),
+            result: result.clone(),
+            recorded_at: now,
+        };
+        self.cache
+            .entries
+            .lock()
+            .unwrap()
+            .insert(self.key.slot(), Entry::Completed(Box::new(record.clone())));
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.send(Some(result.clone()));
+        }
+        record
+    }
+}
+
+impl Drop for IdempotencyPermit<'_> {
+    fn drop(&mut self) {
+        if self.sender.take().is_some() {
+            let mut entries = self.cache.entries.lock().unwrap();
+            if let Some(Entry::InFlight { .. }) = entries.get(&self.key.slot()) {
+                entries.remove(&self.key.slot());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::ExecutionTiming;
+
+    fn key(request: &ExecutionRequest) -> IdempotencyKey {
+        IdempotencyKey::of(request).unwrap()
+    }
+
+    fn result_for(request: &ExecutionRequest) -> ExecutionResult {
+        ExecutionResult::success(request.id, serde_json::json!(42), ExecutionTiming::new())
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_trace_and_scheduling() {
+        let sandbox_id = SandboxId::new();
+        let request = ExecutionRequest::new(sandbox_id, "send()").with_idempotency_key("k");
+        let retry = ExecutionRequest::new(sandbox_id, "send()")
+            .with_idempotency_key("k")
+            .queued();
+        let changed = ExecutionRequest::new(sandbox_id, "send()")
+            .with_idempotency_key("k")
+            .with_input(serde_json::json!({"to": "b"}));
+
+        assert_eq!(request_fingerprint(&request), request_fingerprint(&retry));
+        assert_ne!(request_fingerprint(&request), request_fingerprint(&changed));
+    }
+
+    #[test]
+    fn test_claim_replay_and_conflict() {
+        let cache = IdempotencyCache::new(IdempotencyConfig::default());
+        let now = Utc::now();
+        let sandbox_id = SandboxId::new();
+        let request = ExecutionRequest::new(sandbox_id, "pay()").with_idempotency_key("k");
+
+        let Claim::Run(permit) = cache.claim(&key(&request), now).unwrap() else {
+            panic!("Expected a free key");
+        };
+        assert!(matches!(
+            cache.claim(&key(&request), now).unwrap(),
+            Claim::Wait(_)
+        ));
+        permit.complete(&result_for(&request), now);
+
+        let retry = ExecutionRequest::new(sandbox_id, "pay()").with_idempotency_key("k");
+        let Claim::Replay(replayed) = cache.claim(&key(&retry), now).unwrap() else {
+            panic!("Expected a replay");
+        };
+        assert_eq!(replayed.request_id, request.id);
+
+        let conflicting = ExecutionRequest::new(sandbox_id, "refund()").with_idempotency_key("k");
+        assert!(matches!(
+            cache.claim(&key(&conflicting), now),
+            Err(CretoError::IdempotencyConflict { execution_id, .. }) if execution_id == request.id
+        ));
+
+        // Another sandbox has its own keys
+        let elsewhere = ExecutionRequest::new(SandboxId::new(), "pay()").with_idempotency_key("k");
+        assert!(matches!(
+            cache.claim(&key(&elsewhere), now).unwrap(),
+            Claim::Run(_)
+        ));
+    }
+
+    #[test]
+    fn test_dropped_permit_releases_key_and_oldest_results_evicted() {
+        let cache = IdempotencyCache::new(IdempotencyConfig {
+            ttl: Duration::from_secs(60),
+            max_entries: 2,
+        });
+        let now = Utc::now();
+        let sandbox_id = SandboxId::new();
+        let request = ExecutionRequest::new(sandbox_id, "a()").with_idempotency_key("a");
+
+        let claim = cache.claim(&key(&request), now).unwrap();
+        drop(claim);
+        assert!(!cache.contains(&key(&request), now));
+
+        for (offset, name) in ["a", "b", "c"].into_iter().enumerate() {
+            let request = ExecutionRequest::new(sandbox_id, "a()").with_idempotency_key(name);
+            let at = now + chrono::Duration::seconds(offset as i64);
+            let Claim::Run(permit) = cache.claim(&key(&request), at).unwrap() else {
+                panic!("Expected a free key");
+            };
+            permit.complete(&result_for(&request), at);
+        }
+        let oldest = ExecutionRequest::new(sandbox_id, "a()").with_idempotency_key("a");
+        let newest = ExecutionRequest::new(sandbox_id, "a()").with_idempotency_key("c");
+        assert!(!cache.contains(&key(&oldest), now));
+        assert!(cache.contains(&key(&newest), now));
+        assert!(!cache.contains(&key(&newest), now + chrono::Duration::seconds(62)));
+    }
+}
@@ -0,0 +1,518 @@
+//! Idle sandbox management.
+//!
+//! Interactive sessions keep their sandbox between messages, often for
+//! hours. The service tracks when each sandbox it created last did anything
+//! — an execution finishing, or activity reported through
+//! [`RuntimeService::record_sandbox_activity`](crate::service::RuntimeService::record_sandbox_activity)
+//! — and an idle sweep reclaims the ones left alone:
+//!
+//! - after [`pause_after_seconds`](IdlePolicy::pause_after_seconds) the
+//!   sandbox is paused;
+//! - after [`hibernate_after_seconds`](IdlePolicy::hibernate_after_seconds),
+//!   if set, it is checkpointed and its backend instance terminated.
+//!
+//! The next execution resumes the sandbox first, from the pause or from the
+//! hibernation checkpoint, and reports the time that took as
+//! [`ExecutionTiming::resume_ms`](crate::execution::ExecutionTiming::resume_ms).
+//!
+//! Secrets still mounted when a sandbox pauses are kept or revoked according
+//! to each mount's [`SecretIdlePolicy`](crate::secrets::SecretIdlePolicy);
+//! hibernation revokes them all.
+//!
+//! Sandboxes created with [`keep_warm`](crate::sandbox::SandboxConfig::keep_warm)
+//! are never paused. Each organization may keep at most
+//! [`max_keep_warm`](IdlePolicy::max_keep_warm) of them.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, Clock, CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::CheckpointId;
+use crate::resources::ResourceLimits;
+use crate::sandbox::{Sandbox, SandboxConfig, SandboxId};
+
+/// When idle sandboxes are paused and hibernated.
+///
+/// Set per organization on the
+/// [`OrgRuntimePolicy`](crate::org_policy::OrgRuntimePolicy); organizations
+/// without one use the service default from [`IdleConfig`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdlePolicy {
+    /// Idle time after which a sandbox is paused.
+    pub pause_after_seconds: u64,
+    /// Idle time after which a sandbox is checkpointed and terminated
+    /// (None = never).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hibernate_after_seconds: Option<u64>,
+    /// Keep-warm sandboxes the organization may hold at once.
+    pub max_keep_warm: u32,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        Self {
+            pause_after_seconds: 15 * 60,
+            hibernate_after_seconds: None,
+            max_keep_warm: 5,
+        }
+    }
+}
+
+impl IdlePolicy {
+    /// Hibernate sandboxes idle for `seconds`.
+    pub fn with_hibernation(mut self, seconds: u64) -> Self {
+        self.hibernate_after_seconds = Some(seconds);
+        self
+    }
+
+    /// Check the thresholds are in order.
+    pub fn validate(&self) -> CretoResult<()> {
+        if self.pause_after_seconds == 0 {
+            return Err(CretoError::ValidationFailed(
+                "pause_after_seconds must be positive".to_string(),
+            ));
+        }
+        if let Some(hibernate) = self.hibernate_after_seconds {
+            if hibernate <= self.pause_after_seconds {
+                return Err(CretoError::ValidationFailed(
+                    "hibernate_after_seconds must be greater than pause_after_seconds".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// What a sandbox in `state`, idle for `idle_seconds`, should become.
+    fn next_step(&self, state: &IdleState, idle_seconds: u64) -> Option<IdleStep> {
+        let hibernate = matches!(self.hibernate_after_seconds, Some(h) if idle_seconds >= h);
+        match state {
+            IdleState::Active if hibernate => Some(IdleStep::Hibernate),
+            IdleState::Active if idle_seconds >= self.pause_after_seconds => Some(IdleStep::Pause),
+            IdleState::Paused { .. } if hibernate => Some(IdleStep::Hibernate),
+            _ => None,
+        }
+    }
+}
+
+/// Idle management configuration.
+#[derive(Debug, Clone)]
+pub struct IdleConfig {
+    /// Policy for organizations whose runtime policy sets none.
+    pub default_policy: IdlePolicy,
+    /// How often the idle sweep runs.
+    pub sweep_interval: Duration,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self {
+            default_policy: IdlePolicy::default(),
+            sweep_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Where a tracked sandbox stands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "state")]
+pub enum IdleState {
+    /// Running or ready to run.
+    Active,
+    /// Paused by the idle sweep.
+    Paused { since: DateTime<Utc> },
+    /// Checkpointed and terminated by the idle sweep.
+    Hibernated {
+        checkpoint_id: CheckpointId,
+        since: DateTime<Utc>,
+    },
+}
+
+/// Sandboxes changed by an idle sweep.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdleSweepReport {
+    /// Sandboxes paused.
+    pub paused: Vec<SandboxId>,
+    /// Sandboxes hibernated.
+    pub hibernated: Vec<SandboxId>,
+    /// Sandboxes left as they were because pausing or hibernating failed.
+    pub failed: Vec<SandboxId>,
+}
+
+/// A tracked sandbox as the idle sweep sees it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxActivity {
+    /// Sandbox.
+    pub sandbox_id: SandboxId,
+    /// Agent the sandbox was created for.
+    pub agent_id: AgentId,
+    /// Runtime environment.
+    pub runtime: String,
+    /// Configured resource limits.
+    pub limits: ResourceLimits,
+    /// Whether the sandbox is exempt from idle pausing.
+    pub keep_warm: bool,
+    /// Executions running in the sandbox.
+    pub in_flight: usize,
+    /// Last execution or reported activity.
+    pub last_active: DateTime<Utc>,
+    /// Idle state.
+    #[serde(flatten)]
+    pub state: IdleState,
+}
+
+/// What the sweep does to a sandbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IdleStep {
+    Pause,
+    Hibernate,
+}
+
+/// A sandbox claimed by the sweep, now marked paused.
+#[derive(Debug, Clone)]
+pub(crate) struct IdleClaim {
+    pub step: IdleStep,
+    pub handle: Option<String>,
+    /// State before the claim, restored if the step fails.
+    pub previous: IdleState,
+}
+
+#[derive(Debug)]
+struct TrackedSandbox {
+    organization_id: OrganizationId,
+    agent_id: AgentId,
+    config: SandboxConfig,
+    handle: Option<String>,
+    keep_warm: bool,
+    last_active: DateTime<Utc>,
+    in_flight: usize,
+    state: IdleState,
+}
+
+/// Idle state of the sandboxes the service created.
+///
+/// State changes happen under `transitions`, so an execution resuming a
+/// sandbox waits for a pause or hibernation in progress to finish.
+pub(crate) struct IdleTracker {
+    sandboxes: RwLock<HashMap<SandboxId, TrackedSandbox>>,
+    pub transitions: tokio::sync::Mutex<()>,
+}
+
+impl IdleTracker {
+    pub fn new() -> Self {
+        Self {
+            sandboxes: RwLock::new(HashMap::new()),
+            transitions: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Start tracking a sandbox handed to an agent.
+    pub fn register(&self, sandbox: &Sandbox, now: DateTime<Utc>) {
+        self.sandboxes.write().unwrap().insert(
+            sandbox.id,
+            TrackedSandbox {
+                organization_id: sandbox.organization_id,
+                agent_id: sandbox.agent_id,
+                config: sandbox.config.clone(),
+                handle: sandbox.runtime_handle.clone(),
+                keep_warm: sandbox.config.keep_warm,
+                last_active: now,
+                in_flight: 0,
+                state: IdleState::Active,
+            },
+        );
+    }
+
+    /// Stop tracking a sandbox, returning its last state.
+    pub fn remove(&self, sandbox_id: SandboxId) -> Option<IdleState> {
+        self.sandboxes
+            .write()
+            .unwrap()
+            .remove(&sandbox_id)
+            .map(|tracked| tracked.state)
+    }
+
+    pub fn state(&self, sandbox_id: SandboxId) -> Option<IdleState> {
+        self.sandboxes
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .map(|tracked| tracked.state.clone())
+    }
+
+    /// Whether a sandbox has an execution running.
+    pub fn is_running(&self, sandbox_id: SandboxId) -> bool {
+        self.sandboxes
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .is_some_and(|tracked| tracked.in_flight > 0)
+    }
+
+    pub fn set_state(&self, sandbox_id: SandboxId, state: IdleState) {
+        if let Some(tracked) = self.sandboxes.write().unwrap().get_mut(&sandbox_id) {
+            tracked.state = state;
+        }
+    }
+
+    pub fn set_handle(&self, sandbox_id: SandboxId, handle: Option<String>) {
+        if let Some(tracked) = self.sandboxes.write().unwrap().get_mut(&sandbox_id) {
+            tracked.handle = handle;
+        }
+    }
+
+    pub fn config(&self, sandbox_id: SandboxId) -> Option<SandboxConfig> {
+        self.sandboxes
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .map(|tracked| tracked.config.clone())
+    }
+
+    pub fn handle(&self, sandbox_id: SandboxId) -> Option<String> {
+        self.sandboxes
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .and_then(|tracked| tracked.handle.clone())
+    }
+
+    /// Reset a sandbox's idle time. Returns whether it is tracked.
+    pub fn touch(&self, sandbox_id: SandboxId, now: DateTime<Utc>) -> bool {
+        match self.sandboxes.write().unwrap().get_mut(&sandbox_id) {
+            Some(tracked) => {
+                tracked.last_active = now;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Mark an execution as running in a sandbox, which keeps the sweep
+    /// away from it until the returned guard drops.
+    pub fn begin(
+        self: &Arc<Self>,
+        sandbox_id: SandboxId,
+        clock: &Arc<dyn Clock>,
+    ) -> Option<(IdleActivity, IdleState)> {
+        let mut sandboxes = self.sandboxes.write().unwrap();
+        let tracked = sandboxes.get_mut(&sandbox_id)?;
+        tracked.in_flight += 1;
+        Some((
+            IdleActivity {
+                tracker: Arc::clone(self),
+                clock: Arc::clone(clock),
+                sandbox_id,
+            },
+            tracked.state.clone(),
+        ))
+    }
+
+    /// Tracked sandboxes the sweep may act on, with their organizations.
+    pub fn candidates(&self) -> Vec<(SandboxId, OrganizationId)> {
+        let mut candidates: Vec<_> = self
+            .sandboxes
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, tracked)| !tracked.keep_warm)
+            .filter(|(_, tracked)| !matches!(tracked.state, IdleState::Hibernated { .. }))
+            .map(|(id, tracked)| (*id, tracked.organization_id))
+            .collect();
+        candidates.sort_by_key(|(id, _)| id.as_uuid());
+        candidates
+    }
+
+    /// Claim a sandbox that `policy` says should pause or hibernate, marking
+    /// it paused. Sandboxes running an execution are never claimed.
+    pub fn claim(
+        &self,
+        sandbox_id: SandboxId,
+        policy: &IdlePolicy,
+        now: DateTime<Utc>,
+    ) -> Option<IdleClaim> {
+        let mut sandboxes = self.sandboxes.write().unwrap();
+        let tracked = sandboxes.get_mut(&sandbox_id)?;
+        if tracked.in_flight > 0 || tracked.keep_warm {
+            return None;
+        }
+        let idle_seconds = (now - tracked.last_active).num_seconds().max(0) as u64;
+        let step = policy.next_step(&tracked.state, idle_seconds)?;
+        let previous = tracked.state.clone();
+        if previous == IdleState::Active {
+            tracked.state = IdleState::Paused { since: now };
+        }
+        Some(IdleClaim {
+            step,
+            handle: tracked.handle.clone(),
+            previous,
+        })
+    }
+
+    /// Keep-warm sandboxes an organization holds.
+    pub fn keep_warm_count(&self, organization_id: OrganizationId) -> usize {
+        self.sandboxes
+            .read()
+            .unwrap()
+            .values()
+            .filter(|tracked| tracked.keep_warm && tracked.organization_id == organization_id)
+            .count()
+    }
+
+    /// An organization's tracked sandboxes, most recently active first.
+    pub fn activity(&self, organization_id: OrganizationId) -> Vec<SandboxActivity> {
+        let mut activity: Vec<_> = self
+            .sandboxes
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, tracked)| tracked.organization_id == organization_id)
+            .map(|(id, tracked)| SandboxActivity {
+                sandbox_id: *id,
+                agent_id: tracked.agent_id,
+                runtime: tracked.config.runtime.clone(),
+                limits: tracked.config.effective_limits(),
+                keep_warm: tracked.keep_warm,
+                in_flight: tracked.in_flight,
+                last_active: tracked.last_active,
+                state: tracked.state.clone(),
+            })
+            .collect();
+        activity.sort_by(|a, b| {
+            b.last_active
+                .cmp(&a.last_active)
+                .then_with(|| a.sandbox_id.as_uuid().cmp(&b.sandbox_id.as_uuid()))
+        });
+        activity
+    }
+
+    pub fn organization(&self, sandbox_id: SandboxId) -> Option<OrganizationId> {
+        self.sandboxes
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .map(|tracked| tracked.organization_id)
+    }
+
+    /// Organization and agent a tracked sandbox was created for.
+    pub fn owner(&self, sandbox_id: SandboxId) -> Option<(OrganizationId, AgentId)> {
+        self.sandboxes
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .map(|tracked| (tracked.organization_id, tracked.agent_id))
+    }
+
+    /// Set a sandbox's keep-warm flag if it fits under `limit`.
+    pub fn set_keep_warm(
+        &self,
+        sandbox_id: SandboxId,
+        keep_warm: bool,
+        limit: u32,
+        now: DateTime<Utc>,
+    ) -> CretoResult<()> {
+        let mut sandboxes = self.sandboxes.write().unwrap();
+        let organization_id = sandboxes
+            .get(&sandbox_id)
+            .map(|tracked| tracked.organization_id)
+            .ok_or_else(|| CretoError::SandboxNotFound(sandbox_id.to_string()))?;
+        if keep_warm {
+            let used = sandboxes
+                .iter()
+                .filter(|(id, tracked)| {
+                    **id != sandbox_id
+                        && tracked.keep_warm
+                        && tracked.organization_id == organization_id
+                })
+                .count() as u64;
+            check_keep_warm_quota(used, limit)?;
+        }
+        if let Some(tracked) = sandboxes.get_mut(&sandbox_id) {
+            tracked.keep_warm = keep_warm;
+            tracked.last_active = now;
+        }
+        Ok(())
+    }
+}
+
+/// Fail if another keep-warm sandbox would exceed the organization's limit.
+pub(crate) fn check_keep_warm_quota(used: u64, limit: u32) -> CretoResult<()> {
+    if used >= u64::from(limit) {
+        return Err(CretoError::QuotaExceeded {
+            resource: "keep_warm_sandboxes".to_string(),
+            used,
+            limit: u64::from(limit),
+        });
+    }
+    Ok(())
+}
+
+/// An execution in progress in a tracked sandbox.
+///
+/// Dropping it records the sandbox as active at that moment.
+pub(crate) struct IdleActivity {
+    tracker: Arc<IdleTracker>,
+    clock: Arc<dyn Clock>,
+    sandbox_id: SandboxId,
+}
+
+impl Drop for IdleActivity {
+    fn drop(&mut self) {
+        let now = self.clock.now();
+        if let Some(tracked) = self
+            .tracker
+            .sandboxes
+            .write()
+            .unwrap()
+            .get_mut(&self.sandbox_id)
+        {
+            tracked.in_flight = tracked.in_flight.saturating_sub(1);
+            tracked.last_active = now;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_steps() {
+        let policy = IdlePolicy {
+            pause_after_seconds: 60,
+            hibernate_after_seconds: Some(600),
+            max_keep_warm: 1,
+        };
+        let paused = IdleState::Paused { since: Utc::now() };
+
+        assert_eq!(policy.next_step(&IdleState::Active, 59), None);
+        assert_eq!(
+            policy.next_step(&IdleState::Active, 60),
+            Some(IdleStep::Pause)
+        );
+        assert_eq!(policy.next_step(&paused, 300), None);
+        assert_eq!(policy.next_step(&paused, 600), Some(IdleStep::Hibernate));
+        // A sandbox idle past both thresholds goes straight to hibernation
+        assert_eq!(
+            policy.next_step(&IdleState::Active, 900),
+            Some(IdleStep::Hibernate)
+        );
+        assert_eq!(IdlePolicy::default().next_step(&paused, u64::MAX), None);
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        assert!(IdlePolicy::default().validate().is_ok());
+        assert!(IdlePolicy::default()
+            .with_hibernation(60)
+            .validate()
+            .is_err());
+        assert!(IdlePolicy::default()
+            .with_hibernation(3600)
+            .validate()
+            .is_ok());
+    }
+}
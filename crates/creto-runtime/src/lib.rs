@@ -22,38 +22,150 @@
 //! ```
 
 pub mod attestation;
+pub mod audit;
+pub mod channels;
 pub mod checkpoint;
+pub mod context;
+pub mod cron;
+pub mod exclusion;
 pub mod execution;
+pub mod filesystem;
+pub mod history;
+pub mod idempotency;
+pub mod idle;
+pub mod limits;
+pub mod logs;
 pub mod metering;
 pub mod network;
+pub mod org_policy;
 pub mod pool;
+pub mod provisioning;
+pub mod queue;
+pub mod redaction;
 pub mod repository;
 pub mod resources;
+pub mod sampling;
 pub mod sandbox;
+pub mod schedule;
 pub mod secrets;
 pub mod service;
+pub mod snapshot;
+pub mod testing;
+pub mod triggers;
 
 pub use attestation::{
-    Attestation, AttestationGenerator, AttestationPlatform, AttestationPolicy, AttestationVerifier,
-    MockAttestationProvider,
+    Attestation, AttestationGenerator, AttestationMeasurements, AttestationPlatform,
+    AttestationPolicy, AttestationVerifier, AttestedHash, MockAttestationProvider,
+};
+pub use audit::{
+    AuditRateLimit, AuditRateLimitConfig, AuditRateLimiter, AuditSink, InMemoryAuditSink,
+    RuntimeActivity, RuntimeAuditEvent, RuntimeAuditKind,
+};
+#[cfg(feature = "messaging")]
+pub use channels::MessagingSessionBroker;
+pub use channels::{
+    AgentChannel, AgentChannelConfig, AgentChannelProxy, BrokeredSession, ChannelClient,
+    ChannelDescriptor, ChannelId, ChannelPeer, ChannelRequest, ChannelResponse, SessionBroker,
 };
 pub use checkpoint::{
-    Checkpoint, CheckpointConfig, CheckpointError, CheckpointId, CheckpointManager,
-    CompressionAlgorithm, InMemoryCheckpointStore,
+    Checkpoint, CheckpointConfig, CheckpointError, CheckpointId, CheckpointManager, CheckpointMode,
+    CheckpointStoreLimits, CheckpointStoreStats, ChunkManifest, CompressionAlgorithm,
+    EvictionPolicy, FilesystemCheckpointStore, InMemoryCheckpointStore, UtilizationWarning,
+};
+#[cfg(feature = "metering")]
+pub use context::MeteringQuotaStatus;
+pub use context::{
+    ExecutionContext, QuotaEntry, QuotaRemaining, QuotaSnapshot, QuotaStatusProvider,
+};
+pub use cron::CronExpression;
+pub use exclusion::{
+    ExclusionConfig, ExclusionLease, ExclusionLeaseStore, InMemoryExclusionLeaseStore,
 };
 pub use execution::{
-    ExecutionError, ExecutionRequest, ExecutionResult, ExecutionStatus, ExecutionTiming,
+    run_streaming, stream_execution, CodeExecutor, ExecutionChunk, ExecutionError,
+    ExecutionRequest, ExecutionResult, ExecutionStatus, ExecutionTiming, InputArtifact, OutputSink,
+    StreamLimits,
+};
+pub use filesystem::{
+    ChangeKind, FileChange, FileEntry, FileKind, FilesystemChangeSummary, FilesystemDiff,
+    FilesystemDiffStore, FilesystemManifest, FilesystemTrackingConfig, InMemoryFilesystemDiffStore,
+    WorkdirResolver,
+};
+pub use history::{
+    ExecutionCursor, ExecutionFilter, ExecutionStats, Page, PagePosition, PageRequest, TimeRange,
+    MAX_PAGE_SIZE,
+};
+pub use idempotency::{request_fingerprint, IdempotencyConfig, IdempotentExecution};
+pub use idle::{IdleConfig, IdlePolicy, IdleState, IdleSweepReport, SandboxActivity};
+pub use limits::{
+    InMemoryLimitEventSink, LimitBreach, LimitEnforcementReport, LimitEnforcer,
+    LimitEnforcerConfig, LimitEvent, LimitEventSink, LimitKind,
+};
+pub use logs::{
+    capture_logs, CapturedLogs, ExecutionLogStore, InMemoryExecutionLogStore, LogCaptureConfig,
+    LogLevel, LogPage, LogRecord, LogSummary, STRUCTURED_LOG_PREFIX,
+};
+#[cfg(feature = "metering")]
+pub use metering::MeteringUsageSink;
+pub use metering::{
+    InMemoryUsageEventSink, RuntimeMeteringEvent, SandboxUsageEvent, SandboxUsageSampler,
+    UsageEventSink,
 };
 pub use network::{
-    DnsPolicy, EgressDecision, EgressDestination, EgressRule, NetworkAction, NetworkPolicy,
-    NetworkPolicyEnforcer,
+    DnsPolicy, EffectiveNetworkPolicy, EgressDecision, EgressDestination, EgressRule,
+    InMemoryNetworkPolicyTemplateStore, InMemoryNetworkUsageTracker, IpCidr, NetworkAction,
+    NetworkPolicy, NetworkPolicyEnforcer, NetworkPolicyTemplate, NetworkPolicyTemplateRef,
+    NetworkPolicyTemplateStore, NetworkUsage, NetworkUsageTracker,
+};
+pub use org_policy::{
+    InMemoryOrgRuntimePolicyStore, OrgRuntimePolicy, OrgRuntimePolicyStore, PinnedField,
+};
+pub use pool::{
+    BackendHealthProbe, HealthProbe, PoolConfig, PoolHealth, PoolHealthConfig, PoolHealthReport,
+    RestoreConfig, RestoreOutcome, RestoredSandbox, WarmPool,
+};
+pub use provisioning::{
+    NoopProvisioner, PhaseTiming, ProvisioningError, ProvisioningFailure, ProvisioningPhase,
+    ProvisioningReport, ProvisioningRetryPolicy, ReplenishmentReport, SandboxProvisioner,
+};
+pub use queue::{
+    ExecutionPriority, ExecutionQueue, ExecutionQueueConfig, ExecutionSlot, QueueTicket,
+    QueuedExecution,
+};
+pub use redaction::{
+    RedactionConfig, RedactionEngine, RedactionRule, RedactionSummary, SecretFingerprint,
 };
-pub use pool::{PoolConfig, WarmPool};
 pub use repository::{
-    ExecutionRepository, PgExecutionRepository, PgResourceUsageRepository, PgSandboxRepository,
-    ResourceUsageRepository, SandboxRepository,
+    ExecutionRepository, PgExclusionLeaseRepository, PgExecutionLogRepository,
+    PgExecutionRepository, PgFilesystemDiffRepository, PgNetworkPolicyTemplateRepository,
+    PgOrgRuntimePolicyRepository, PgResourceUsageRepository, PgSandboxRepository,
+    PgScheduleRepository, PgSecretGrantRepository, PgTopicTriggerRepository,
+    ProvisioningFailureRecord, ResourceUsageRepository, SandboxRepository,
+};
+pub use resources::{HostCapabilities, ResourceClass, ResourceLimits, ResourceUsage};
+pub use sampling::{
+    ResourceController, SampledUsage, SamplerConfig, UsageSample, UsageSampler, UsageSeries,
+};
+pub use sandbox::{Sandbox, SandboxConfig, SandboxId, SandboxState, TerminationReason};
+#[cfg(feature = "metering")]
+pub use schedule::MeteringQuotaGate;
+pub use schedule::{
+    CatchUpPolicy, ExecutionSchedule, InMemoryScheduleStore, RunPlan, ScheduleClaim, ScheduleGate,
+    ScheduleId, ScheduleRun, ScheduleRunOutcome, ScheduleRunPage, ScheduleStore, ScheduleTrigger,
+    SchedulerConfig,
+};
+pub use secrets::{
+    AgentSecretSummary, CheckpointedSecret, InMemorySecretGrantStore, LeaseId, LeasedSecret,
+    NoopSecretAccessMonitor, SecretAccess, SecretAccessMonitor, SecretAccessReport, SecretGrant,
+    SecretGrantStore, SecretGrantSummary, SecretIdlePolicy, SecretLeaseConfig, SecretLeaseReport,
+    SecretMount, SecretMountTarget, SecretProvider,
+};
+pub use service::{RuntimeService, RuntimeStats};
+pub use snapshot::{RuntimeSnapshot, RuntimeSnapshotContributor, RUNTIME_SECTION};
+#[cfg(feature = "messaging")]
+pub use triggers::MessagingTopicSource;
+pub use triggers::{
+    InMemoryTopicTriggerStore, StoredTopicTrigger, TopicSource, TopicTrigger, TopicTriggerId,
+    TopicTriggerStore, TriggerDeadLetter, TriggerDelivery, TriggerDispatchConfig, TriggerGate,
+    TriggerMessage, TriggerOutcome, TriggerRetry, TriggerRetryPolicy,
 };
-pub use resources::{ResourceLimits, ResourceUsage};
-pub use sandbox::{Sandbox, SandboxConfig, SandboxId, SandboxState};
-pub use secrets::{SecretMount, SecretProvider};
-pub use service::RuntimeService;
@@ -0,0 +1,433 @@
+//! Sandbox-lifetime resource limit enforcement.
+//!
+//! Request timeouts only bound a single execution. The [`LimitEnforcer`]
+//! tracks every sandbox the service provisions against the
+//! [`cpu_time_ms`](ResourceLimits::cpu_time_ms) and
+//! [`wall_time_seconds`](ResourceLimits::wall_time_seconds) it was created
+//! with, counting CPU time across all its executions and wall time from
+//! when it was provisioned. A limit of zero is not enforced.
+//!
+//! Each enforcement pass
+//! ([`RuntimeService::enforce_limits`](crate::service::RuntimeService::enforce_limits))
+//! reports a sandbox reaching
+//! [`soft_limit_percent`](LimitEnforcerConfig::soft_limit_percent) of a limit
+//! once, as a [`LimitEvent::Warning`], so oversight can step in before the
+//! hard limit. A sandbox at or past a limit is terminated: its running
+//! executions are cancelled with
+//! [`ExecutionError::resource_limit_exceeded`](crate::execution::ExecutionError::resource_limit_exceeded),
+//! a final usage snapshot is recorded and the sandbox ends in
+//! [`TerminationReason::LimitExceeded`](crate::sandbox::TerminationReason::LimitExceeded).
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::resources::ResourceLimits;
+use crate::sandbox::{Sandbox, SandboxId};
+
+/// A sandbox-lifetime limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind {
+    /// Total CPU time, in milliseconds.
+    CpuTime,
+    /// Time since the sandbox was provisioned, in seconds.
+    WallTime,
+}
+
+impl LimitKind {
+    /// Stable name of the limit.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LimitKind::CpuTime => "cpu_time",
+            LimitKind::WallTime => "wall_time",
+        }
+    }
+}
+
+impl std::fmt::Display for LimitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Limit enforcement configuration.
+#[derive(Debug, Clone)]
+pub struct LimitEnforcerConfig {
+    /// Percentage of a limit at which a warning is emitted.
+    pub soft_limit_percent: u8,
+    /// How often the enforcement worker checks limits.
+    pub check_interval: Duration,
+}
+
+impl Default for LimitEnforcerConfig {
+    fn default() -> Self {
+        Self {
+            soft_limit_percent: 80,
+            check_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl LimitEnforcerConfig {
+    /// Warn at `percent` of each limit.
+    pub fn with_soft_limit_percent(mut self, percent: u8) -> Self {
+        self.soft_limit_percent = percent;
+        self
+    }
+}
+
+/// A sandbox's use of one limit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LimitBreach {
+    /// Sandbox.
+    pub sandbox_id: SandboxId,
+    /// Organization that owns the sandbox.
+    pub organization_id: OrganizationId,
+    /// Agent the sandbox runs for.
+    pub agent_id: AgentId,
+    /// Limit concerned.
+    pub limit: LimitKind,
+    /// Amount used (milliseconds of CPU time or seconds of wall time).
+    pub used: u64,
+    /// The limit, in the same unit.
+    pub max: u64,
+}
+
+/// Something an enforcement pass found.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "breach")]
+pub enum LimitEvent {
+    /// A sandbox reached the soft limit.
+    Warning(LimitBreach),
+    /// A sandbox reached the hard limit and is being terminated.
+    Exceeded(LimitBreach),
+}
+
+/// What an enforcement pass did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LimitEnforcementReport {
+    /// Sandboxes that reached a soft limit.
+    pub warned: Vec<LimitBreach>,
+    /// Sandboxes terminated for exceeding a limit.
+    pub terminated: Vec<LimitBreach>,
+    /// Sandboxes past a limit that could not be terminated.
+    pub failed: Vec<SandboxId>,
+}
+
+/// Destination for limit warnings and terminations.
+#[async_trait]
+pub trait LimitEventSink: Send + Sync {
+    /// Record a limit event.
+    async fn emit(&self, event: LimitEvent) -> CretoResult<()>;
+}
+
+/// In-memory limit event sink for testing and development.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryLimitEventSink {
+    events: Arc<RwLock<Vec<LimitEvent>>>,
+}
+
+impl InMemoryLimitEventSink {
+    /// Create a new in-memory sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get all emitted events, oldest first.
+    pub fn events(&self) -> Vec<LimitEvent> {
+        self.events.read().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl LimitEventSink for InMemoryLimitEventSink {
+    async fn emit(&self, event: LimitEvent) -> CretoResult<()> {
+        self.events.write().unwrap().push(event);
+        Ok(())
+    }
+}
+
+/// Limit state of one sandbox.
+struct TrackedSandbox {
+    organization_id: OrganizationId,
+    agent_id: AgentId,
+    cpu_limit_ms: u64,
+    wall_limit_seconds: u64,
+    started_at: DateTime<Utc>,
+    cpu_time_ms: u64,
+    warned: Vec<LimitKind>,
+    exceeded: watch::Sender<Option<LimitKind>>,
+}
+
+impl TrackedSandbox {
+    fn breach(&self, sandbox_id: SandboxId, limit: LimitKind, used: u64, max: u64) -> LimitBreach {
+        LimitBreach {
+            sandbox_id,
+            organization_id: self.organization_id,
+            agent_id: self.agent_id,
+            limit,
+            used,
+            max,
+        }
+    }
+}
+
+/// Tracks sandboxes' CPU time and age against their limits.
+pub struct LimitEnforcer {
+    config: LimitEnforcerConfig,
+    sandboxes: RwLock<HashMap<SandboxId, TrackedSandbox>>,
+}
+
+impl LimitEnforcer {
+    /// Create an enforcer tracking no sandboxes.
+    pub fn new(config: LimitEnforcerConfig) -> Self {
+        Self {
+            config,
+            sandboxes: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Enforcement configuration.
+    pub fn config(&self) -> &LimitEnforcerConfig {
+        &self.config
+    }
+
+    /// Start tracking `sandbox`, its wall time counted from `now`.
+    pub fn register(&self, sandbox: &Sandbox, now: DateTime<Utc>) {
+        let limits: &ResourceLimits = &sandbox.config.effective_limits();
+        let (exceeded, _) = watch::channel(None);
+        self.sandboxes.write().unwrap().insert(
+            sandbox.id,
+            TrackedSandbox {
+                organization_id: sandbox.organization_id,
+                agent_id: sandbox.agent_id,
+                cpu_limit_ms: limits.cpu_time_ms,
+                wall_limit_seconds: limits.wall_time_seconds as u64,
+                started_at: now,
+                cpu_time_ms: 0,
+                warned: Vec::new(),
+                exceeded,
+            },
+        );
+    }
+
+    /// Stop tracking a sandbox.
+    pub fn remove(&self, sandbox_id: SandboxId) {
+        self.sandboxes.write().unwrap().remove(&sandbox_id);
+    }
+
+    /// Whether a sandbox is tracked.
+    pub fn is_tracked(&self, sandbox_id: SandboxId) -> bool {
+        self.sandboxes.read().unwrap().contains_key(&sandbox_id)
+    }
+
+    /// Add CPU time used by a finished execution.
+    pub fn add_cpu_time(&self, sandbox_id: SandboxId, cpu_time_ms: u64) {
+        if let Some(tracked) = self.sandboxes.write().unwrap().get_mut(&sandbox_id) {
+            tracked.cpu_time_ms = tracked.cpu_time_ms.saturating_add(cpu_time_ms);
+        }
+    }
+
+    /// Take a live reading of the sandbox's total CPU time into account.
+    pub fn observe_cpu_time(&self, sandbox_id: SandboxId, total_cpu_time_ms: u64) {
+        if let Some(tracked) = self.sandboxes.write().unwrap().get_mut(&sandbox_id) {
+            tracked.cpu_time_ms = tracked.cpu_time_ms.max(total_cpu_time_ms);
+        }
+    }
+
+    /// CPU time counted for a sandbox so far.
+    pub fn cpu_time_ms(&self, sandbox_id: SandboxId) -> Option<u64> {
+        self.sandboxes
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .map(|tracked| tracked.cpu_time_ms)
+    }
+
+    /// Tracked sandboxes.
+    pub fn sandbox_ids(&self) -> Vec<SandboxId> {
+        self.sandboxes.read().unwrap().keys().copied().collect()
+    }
+
+    /// Check every tracked sandbox at `now`.
+    ///
+    /// A sandbox past a limit yields one [`LimitEvent::Exceeded`] (for CPU
+    /// time if both are past) and is not reported again. Otherwise each
+    /// limit at or above the soft limit yields one [`LimitEvent::Warning`]
+    /// over the sandbox's lifetime.
+    pub fn check(&self, now: DateTime<Utc>) -> Vec<LimitEvent> {
+        let soft_percent = self.config.soft_limit_percent.min(100) as u64;
+        let mut events = Vec::new();
+        let mut sandboxes = self.sandboxes.write().unwrap();
+        for (sandbox_id, tracked) in sandboxes.iter_mut() {
+            if tracked.exceeded.borrow().is_some() {
+                continue;
+            }
+            let age_seconds = now
+                .signed_duration_since(tracked.started_at)
+                .num_seconds()
+                .max(0) as u64;
+            let usage = [
+                (
+                    LimitKind::CpuTime,
+                    tracked.cpu_time_ms,
+                    tracked.cpu_limit_ms,
+                ),
+                (LimitKind::WallTime, age_seconds, tracked.wall_limit_seconds),
+            ];
+
+            let exceeded = usage.iter().find(|(_, used, max)| *max > 0 && used >= max);
+            if let Some(&(limit, used, max)) = exceeded {
+                tracked.exceeded.send_replace(Some(limit));
+                events.push(LimitEvent::Exceeded(tracked.breach(
+                    *sandbox_id,
+                    limit,
+                    used,
+                    max,
+                )));
+                continue;
+            }
+            for (limit, used, max) in usage {
+                let soft = max.saturating_mul(soft_percent) / 100;
+                if max > 0 && used >= soft && !tracked.warned.contains(&limit) {
+                    tracked.warned.push(limit);
+                    events.push(LimitEvent::Warning(tracked.breach(
+                        *sandbox_id,
+                        limit,
+                        used,
+                        max,
+                    )));
+                }
+            }
+        }
+        events
+    }
+
+    /// Wait until a check finds the sandbox past a limit, returning the
+    /// limit.
+    ///
+    /// Never completes for an untracked sandbox, or once the sandbox is no
+    /// longer tracked.
+    pub async fn exceeded(&self, sandbox_id: SandboxId) -> LimitKind {
+        let receiver = self
+            .sandboxes
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .map(|tracked| tracked.exceeded.subscribe());
+        if let Some(mut receiver) = receiver {
+            if let Ok(limit) = receiver.wait_for(Option::is_some).await {
+                if let Some(limit) = *limit {
+                    return limit;
+                }
+            }
+        }
+        std::future::pending().await
+    }
+}
+
+impl Default for LimitEnforcer {
+    fn default() -> Self {
+        Self::new(LimitEnforcerConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::SandboxConfig;
+
+    fn sandbox(cpu_time_ms: u64, wall_time_seconds: u32) -> Sandbox {
+        Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig {
+                limits: Some(
+                    ResourceLimits::default()
+                        .with_cpu_time(cpu_time_ms)
+                        .with_wall_time(wall_time_seconds),
+                ),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_warning_precedes_exceeded_once_each() {
+        let enforcer = LimitEnforcer::default();
+        let sandbox = sandbox(1_000, 3_600);
+        let now = Utc::now();
+        enforcer.register(&sandbox, now);
+
+        enforcer.add_cpu_time(sandbox.id, 500);
+        assert!(enforcer.check(now).is_empty());
+
+        enforcer.add_cpu_time(sandbox.id, 300);
+        let events = enforcer.check(now);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            LimitEvent::Warning(b) if b.limit == LimitKind::CpuTime && b.used == 800 && b.max == 1_000
+        ));
+        // Warned once
+        assert!(enforcer.check(now).is_empty());
+
+        enforcer.observe_cpu_time(sandbox.id, 1_000);
+        let events = enforcer.check(now);
+        assert!(matches!(
+            &events[..],
+            [LimitEvent::Exceeded(b)] if b.limit == LimitKind::CpuTime && b.used == 1_000
+        ));
+        assert!(enforcer.check(now).is_empty());
+    }
+
+    #[test]
+    fn test_wall_time_counts_from_registration() {
+        let enforcer =
+            LimitEnforcer::new(LimitEnforcerConfig::default().with_soft_limit_percent(50));
+        let sandbox = sandbox(0, 60);
+        let start = Utc::now();
+        enforcer.register(&sandbox, start);
+
+        assert!(enforcer
+            .check(start + chrono::Duration::seconds(29))
+            .is_empty());
+        assert!(matches!(
+            &enforcer.check(start + chrono::Duration::seconds(30))[..],
+            [LimitEvent::Warning(b)] if b.limit == LimitKind::WallTime
+        ));
+        assert!(matches!(
+            &enforcer.check(start + chrono::Duration::seconds(61))[..],
+            [LimitEvent::Exceeded(b)] if b.limit == LimitKind::WallTime && b.used == 61 && b.max == 60
+        ));
+
+        // A zero CPU limit is never enforced
+        enforcer.add_cpu_time(sandbox.id, u64::MAX);
+        assert!(enforcer.check(start).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exceeded_resolves_after_check() {
+        let enforcer = Arc::new(LimitEnforcer::default());
+        let sandbox = sandbox(10, 3_600);
+        enforcer.register(&sandbox, Utc::now());
+
+        let waiting = tokio::spawn({
+            let enforcer = Arc::clone(&enforcer);
+            async move { enforcer.exceeded(sandbox.id).await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+
+        enforcer.add_cpu_time(sandbox.id, 10);
+        enforcer.check(Utc::now());
+        assert_eq!(waiting.await.unwrap(), LimitKind::CpuTime);
+    }
+}
@@ -0,0 +1,367 @@
+//! Structured execution logs.
+//!
+//! Code running in a sandbox emits structured log lines on stdout by
+//! prefixing a JSON object with [`STRUCTURED_LOG_PREFIX`]:
+//!
+//! ```text
+//! ::creto-log::{"level":"warn","message":"retrying upload","fields":{"attempt":2}}
+//! ```
+//!
+//! After an execution the prefixed lines are parsed into [`LogRecord`]s.
+//! The captured stdout itself is left as emitted, structured lines included.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::CretoResult;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Prefix marking a structured log line on stdout.
+pub const STRUCTURED_LOG_PREFIX: &str = "::creto-log::";
+
+/// Severity of a log record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    /// Fine-grained tracing.
+    Trace,
+    /// Debugging detail.
+    Debug,
+    /// Normal operation.
+    Info,
+    /// Something unexpected that did not stop the execution.
+    #[serde(alias = "warning")]
+    Warn,
+    /// A failure.
+    Error,
+}
+
+impl LogLevel {
+    /// All levels, least severe first.
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Trace,
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+    ];
+}
+
+/// A structured log line emitted by an execution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// When the line was emitted (capture time if the line had none).
+    pub timestamp: DateTime<Utc>,
+    /// Severity.
+    pub level: LogLevel,
+    /// Log message.
+    pub message: String,
+    /// Additional structured fields.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Wire format of a structured log line.
+#[derive(Deserialize)]
+struct LogLine {
+    #[serde(default)]
+    timestamp: Option<DateTime<Utc>>,
+    #[serde(default = "default_level")]
+    level: LogLevel,
+    message: String,
+    #[serde(default)]
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+fn default_level() -> LogLevel {
+    LogLevel::Info
+}
+
+/// Log record counts for an execution.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogSummary {
+    /// Trace records emitted.
+    pub trace: u64,
+    /// Debug records emitted.
+    pub debug: u64,
+    /// Info records emitted.
+    pub info: u64,
+    /// Warn records emitted.
+    pub warn: u64,
+    /// Error records emitted.
+    pub error: u64,
+    /// Records parsed but not stored because the execution hit its limit.
+    #[serde(default)]
+    pub dropped: u64,
+    /// Prefixed lines that could not be parsed and were kept as raw output.
+    #[serde(default)]
+    pub malformed: u64,
+}
+
+impl LogSummary {
+    /// Number of records emitted at `level`.
+    pub fn count(&self, level: LogLevel) -> u64 {
+        match level {
+            LogLevel::Trace => self.trace,
+            LogLevel::Debug => self.debug,
+            LogLevel::Info => self.info,
+            LogLevel::Warn => self.warn,
+            LogLevel::Error => self.error,
+        }
+    }
+
+    /// Total records emitted, stored or not.
+    pub fn total(&self) -> u64 {
+        self.trace + self.debug + self.info + self.warn + self.error
+    }
+
+    fn increment(&mut self, level: LogLevel) {
+        match level {
+            LogLevel::Trace => self.trace += 1,
+            LogLevel::Debug => self.debug += 1,
+            LogLevel::Info => self.info += 1,
+            LogLevel::Warn => self.warn += 1,
+            LogLevel::Error => self.error += 1,
+        }
+    }
+}
+
+/// Structured log capture configuration.
+#[derive(Debug, Clone)]
+pub struct LogCaptureConfig {
+    /// Maximum records stored per execution; later records are only counted.
+    pub max_records_per_execution: usize,
+}
+
+impl Default for LogCaptureConfig {
+    fn default() -> Self {
+        Self {
+            max_records_per_execution: 1000,
+        }
+    }
+}
+
+/// Structured logs parsed from an execution's stdout.
+#[derive(Debug, Clone, Default)]
+pub struct CapturedLogs {
+    /// Stored records, in emission order.
+    pub records: Vec<LogRecord>,
+    /// Counts over every record emitted.
+    pub summary: LogSummary,
+}
+
+/// Parse the structured log lines in captured stdout.
+///
+/// Never fails: lines that look structured but do not parse are counted as
+/// malformed.
+pub fn capture_logs(stdout: &str, config: &LogCaptureConfig) -> CapturedLogs {
+    let captured_at = Utc::now();
+    let mut captured = CapturedLogs::default();
+
+    for line in stdout.lines() {
+        let Some(payload) = line.strip_prefix(STRUCTURED_LOG_PREFIX) else {
+            continue;
+        };
+
+        match serde_json::from_str::<LogLine>(payload) {
+            Ok(parsed) => {
+                captured.summary.increment(parsed.level);
+                if captured.records.len() < config.max_records_per_execution {
+                    captured.records.push(LogRecord {
+                        timestamp: parsed.timestamp.unwrap_or(captured_at),
+                        level: parsed.level,
+                        message: parsed.message,
+                        fields: parsed.fields,
+                    });
+                } else {
+                    captured.summary.dropped += 1;
+                }
+            }
+            Err(_) => captured.summary.malformed += 1,
+        }
+    }
+
+    captured
+}
+
+/// A page of log records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogPage {
+    /// Records to skip.
+    pub offset: usize,
+    /// Maximum records to return.
+    pub limit: usize,
+}
+
+impl LogPage {
+    /// Create a page.
+    pub fn new(offset: usize, limit: usize) -> Self {
+        Self { offset, limit }
+    }
+}
+
+impl Default for LogPage {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: 100,
+        }
+    }
+}
+
+/// Storage for execution log records.
+#[async_trait]
+pub trait ExecutionLogStore: Send + Sync {
+    /// Store the records captured for an execution.
+    async fn append(&self, execution_id: Uuid, records: &[LogRecord]) -> CretoResult<()>;
+
+    /// List an execution's records in emission order.
+    ///
+    /// With `min_level` set, only records at that level or above are returned.
+    async fn list(
+        &self,
+        execution_id: Uuid,
+        min_level: Option<LogLevel>,
+        page: LogPage,
+    ) -> CretoResult<Vec<LogRecord>>;
+}
+
+/// In-memory log store for testing and development.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryExecutionLogStore {
+    records: Arc<RwLock<HashMap<Uuid, Vec<LogRecord>>>>,
+}
+
+impl InMemoryExecutionLogStore {
+    /// Create a new in-memory log store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ExecutionLogStore for InMemoryExecutionLogStore {
+    async fn append(&self, execution_id: Uuid, records: &[LogRecord]) -> CretoResult<()> {
+        self.records
+            .write()
+            .unwrap()
+            .entry(execution_id)
+            .or_default()
+            .extend_from_slice(records);
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        execution_id: Uuid,
+        min_level: Option<LogLevel>,
+        page: LogPage,
+    ) -> CretoResult<Vec<LogRecord>> {
+        let records = self.records.read().unwrap();
+        Ok(records
+            .get(&execution_id)
+            .into_iter()
+            .flatten()
+            .filter(|r| !matches!(min_level, Some(min) if r.level < min))
+            .skip(page.offset)
+            .take(page.limit)
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structured(level: &str, message: &str) -> String {
+        format!(
+            "{}{{\"level\":\"{}\",\"message\":\"{}\"}}",
+            STRUCTURED_LOG_PREFIX, level, message
+        )
+    }
+
+    #[test]
+    fn test_capture_interleaved_output() {
+        let stdout = [
+            "starting".to_string(),
+            structured("info", "loaded 3 files"),
+            format!(
+                "{}{{\"level\":\"warning\",\"message\":\"slow\",\"fields\":{{\"ms\":812}},\"timestamp\":\"2024-05-01T12:00:00Z\"}}",
+                STRUCTURED_LOG_PREFIX
+            ),
+            format!("{}not json", STRUCTURED_LOG_PREFIX),
+            structured("error", "upload failed"),
+            "done".to_string(),
+        ]
+        .join("\n")
+            + "\n";
+
+        let captured = capture_logs(&stdout, &LogCaptureConfig::default());
+
+        assert_eq!(captured.records.len(), 3);
+        assert_eq!(captured.records[1].level, LogLevel::Warn);
+        assert_eq!(captured.records[1].fields["ms"], 812);
+        assert_eq!(
+            captured.records[1].timestamp,
+            "2024-05-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(captured.summary.info, 1);
+        assert_eq!(captured.summary.warn, 1);
+        assert_eq!(captured.summary.error, 1);
+        assert_eq!(captured.summary.malformed, 1);
+    }
+
+    #[test]
+    fn test_capture_bounds_stored_records() {
+        let stdout = (0..5)
+            .map(|i| structured("debug", &format!("line {}", i)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let config = LogCaptureConfig {
+            max_records_per_execution: 2,
+        };
+
+        let captured = capture_logs(&stdout, &config);
+        assert_eq!(captured.records.len(), 2);
+        assert_eq!(captured.records[1].message, "line 1");
+        assert_eq!(captured.summary.debug, 5);
+        assert_eq!(captured.summary.dropped, 3);
+    }
+
+    #[tokio::test]
+    async fn test_store_filters_by_level_and_pages() {
+        let store = InMemoryExecutionLogStore::new();
+        let execution_id = Uuid::now_v7();
+        let stdout = ["debug", "warn", "info", "error", "warn"]
+            .iter()
+            .enumerate()
+            .map(|(i, level)| structured(level, &format!("m{}", i)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let captured = capture_logs(&stdout, &LogCaptureConfig::default());
+        store.append(execution_id, &captured.records).await.unwrap();
+
+        let warnings = store
+            .list(execution_id, Some(LogLevel::Warn), LogPage::default())
+            .await
+            .unwrap();
+        let messages: Vec<&str> = warnings.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, vec!["m1", "m3", "m4"]);
+
+        let page = store
+            .list(execution_id, Some(LogLevel::Warn), LogPage::new(1, 1))
+            .await
+            .unwrap();
+        assert_eq!(page[0].message, "m3");
+
+        let all = store
+            .list(execution_id, None, LogPage::default())
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 5);
+    }
+}
@@ -2,15 +2,43 @@
 //!
 //! This module provides integration with creto-metering to emit usage events
 //! when sandboxes are created and executions complete.
+//!
+//! Between those points, a [`SandboxUsageSampler`] polls each running
+//! sandbox's [`ResourceController`] at a fixed interval, records every
+//! reading through a [`ResourceUsageRepository`] and reports the CPU time and
+//! memory consumed since the previous reading to a [`UsageEventSink`]. Each
+//! sandbox is sampled by its own task, so a slow repository or sink only
+//! delays that sandbox's samples.
+//!
+//! When an execution completes, the runtime reports its duration, CPU time
+//! and GPU time to the same sink as an [`ExecutionUsageEvent`].
 
-#[cfg(feature = "metering")]
-use creto_common::{AgentId, OrganizationId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, CretoResult, OrganizationId};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use creto_common::Correlation;
 #[cfg(feature = "metering")]
 use creto_metering::{UsageEvent, UsageEventType};
 #[cfg(feature = "metering")]
 use uuid::Uuid;
 
+use crate::execution::ExecutionResult;
+use crate::repository::ResourceUsageRepository;
+use crate::resources::ResourceUsage;
+use crate::sampling::{ResourceController, SamplerConfig};
+use crate::sandbox::{Sandbox, SandboxId};
+
 /// Create a usage event for sandbox execution.
+///
+/// Pass `request.correlation().derive(request.id)` as `correlation` so the
+/// event inherits the execution's trace.
 #[cfg(feature = "metering")]
 pub fn sandbox_execution_event(
     org_id: OrganizationId,
@@ -18,6 +46,7 @@ pub fn sandbox_execution_event(
     sandbox_id: Uuid,
     duration_ms: u64,
     delegation_depth: u8,
+    correlation: Correlation,
 ) -> UsageEvent {
     let mut properties = serde_json::Map::new();
     properties.insert(
@@ -37,6 +66,8 @@ pub fn sandbox_execution_event(
         timestamp: chrono::Utc::now(),
         properties: serde_json::Value::Object(properties),
         delegation_depth,
+        correlation_id: correlation.correlation_id,
+        caused_by: correlation.caused_by,
     }
 }
 
@@ -48,6 +79,7 @@ pub fn cpu_usage_event(
     sandbox_id: Uuid,
     cpu_ms: u64,
     delegation_depth: u8,
+    correlation: Correlation,
 ) -> UsageEvent {
     let mut properties = serde_json::Map::new();
     properties.insert(
@@ -66,9 +98,103 @@ pub fn cpu_usage_event(
         timestamp: chrono::Utc::now(),
         properties: serde_json::Value::Object(properties),
         delegation_depth,
+        correlation_id: correlation.correlation_id,
+        caused_by: correlation.caused_by,
+    }
+}
+
+/// Create memory usage event.
+#[cfg(feature = "metering")]
+pub fn memory_usage_event(
+    org_id: OrganizationId,
+    agent_id: AgentId,
+    sandbox_id: Uuid,
+    mb_seconds: u64,
+    delegation_depth: u8,
+    correlation: Correlation,
+) -> UsageEvent {
+    let mut properties = serde_json::Map::new();
+    properties.insert(
+        "sandbox_id".to_string(),
+        serde_json::json!(sandbox_id.to_string()),
+    );
+
+    UsageEvent {
+        transaction_id: Uuid::now_v7().to_string(),
+        organization_id: org_id,
+        agent_id,
+        external_subscription_id: None,
+        event_type: UsageEventType::MemoryMbSeconds,
+        code: "memory_mb_seconds".to_string(),
+        quantity: mb_seconds as i64,
+        timestamp: chrono::Utc::now(),
+        properties: serde_json::Value::Object(properties),
+        delegation_depth,
+        correlation_id: correlation.correlation_id,
+        caused_by: correlation.caused_by,
     }
 }
 
+/// Create GPU usage event.
+#[cfg(feature = "metering")]
+pub fn gpu_usage_event(
+    org_id: OrganizationId,
+    agent_id: AgentId,
+    sandbox_id: Uuid,
+    gpu_ms: u64,
+    gpu_kind: Option<&str>,
+    delegation_depth: u8,
+    correlation: Correlation,
+) -> UsageEvent {
+    let mut properties = serde_json::Map::new();
+    properties.insert(
+        "sandbox_id".to_string(),
+        serde_json::json!(sandbox_id.to_string()),
+    );
+    if let Some(kind) = gpu_kind {
+        properties.insert("gpu_kind".to_string(), serde_json::json!(kind));
+    }
+
+    UsageEvent {
+        transaction_id: Uuid::now_v7().to_string(),
+        organization_id: org_id,
+        agent_id,
+        external_subscription_id: None,
+        event_type: UsageEventType::GpuMilliseconds,
+        code: "gpu_milliseconds".to_string(),
+        quantity: gpu_ms as i64,
+        timestamp: chrono::Utc::now(),
+        properties: serde_json::Value::Object(properties),
+        delegation_depth,
+        correlation_id: correlation.correlation_id,
+        caused_by: correlation.caused_by,
+    }
+}
+
+/// Create the usage events for a completed execution.
+///
+/// Always emits the execution event; CPU and GPU events are emitted when the
+/// execution reported that usage. GPU time comes from the sampled series,
+/// falling back to the final usage snapshot when no samples were taken.
+#[cfg(feature = "metering")]
+pub fn execution_usage_events(
+    org_id: OrganizationId,
+    agent_id: AgentId,
+    sandbox_id: Uuid,
+    result: &ExecutionResult,
+    gpu_kind: Option<&str>,
+    delegation_depth: u8,
+) -> Vec<UsageEvent> {
+    ExecutionUsageEvent::new(
+        org_id,
+        agent_id,
+        SandboxId::from_uuid(sandbox_id),
+        result,
+        gpu_kind,
+    )
+    .to_usage_events(delegation_depth)
+}
+
 /// Metering event types for sandbox operations.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RuntimeMeteringEvent {
@@ -80,6 +206,8 @@ pub enum RuntimeMeteringEvent {
     CpuTime,
     /// Memory used.
     MemoryUsage,
+    /// GPU time consumed.
+    GpuTime,
 }
 
 impl RuntimeMeteringEvent {
@@ -90,13 +218,664 @@ impl RuntimeMeteringEvent {
             RuntimeMeteringEvent::ExecutionCompleted => "execution_completed",
             RuntimeMeteringEvent::CpuTime => "cpu_milliseconds",
             RuntimeMeteringEvent::MemoryUsage => "memory_mb_seconds",
+            RuntimeMeteringEvent::GpuTime => "gpu_milliseconds",
+        }
+    }
+}
+
+/// Usage a running sandbox accrued between two samples.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxUsageEvent {
+    /// Organization that owns the sandbox.
+    pub organization_id: OrganizationId,
+
+    /// Agent the sandbox runs for.
+    pub agent_id: AgentId,
+
+    /// Sampled sandbox.
+    pub sandbox_id: SandboxId,
+
+    /// What was consumed ([`RuntimeMeteringEvent::CpuTime`] or
+    /// [`RuntimeMeteringEvent::MemoryUsage`]).
+    pub metric: RuntimeMeteringEvent,
+
+    /// Milliseconds of CPU time or megabyte-seconds of memory.
+    pub quantity: u64,
+
+    /// When the sample was taken.
+    pub timestamp: DateTime<Utc>,
+}
+
+#[cfg(feature = "metering")]
+impl SandboxUsageEvent {
+    /// Convert to a metering usage event.
+    ///
+    /// Events of one sandbox share a correlation rooted at its ID.
+    pub fn to_usage_event(&self) -> UsageEvent {
+        let correlation = Correlation::new(self.sandbox_id.as_uuid());
+        let sandbox_id = self.sandbox_id.as_uuid();
+        let mut event = match self.metric {
+            RuntimeMeteringEvent::MemoryUsage => memory_usage_event(
+                self.organization_id,
+                self.agent_id,
+                sandbox_id,
+                self.quantity,
+                0,
+                correlation,
+            ),
+            _ => cpu_usage_event(
+                self.organization_id,
+                self.agent_id,
+                sandbox_id,
+                self.quantity,
+                0,
+                correlation,
+            ),
+        };
+        event.timestamp = self.timestamp;
+        event
+    }
+}
+
+/// Usage of an execution that completed in a sandbox.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionUsageEvent {
+    /// Organization that owns the sandbox.
+    pub organization_id: OrganizationId,
+
+    /// Agent the sandbox runs for.
+    pub agent_id: AgentId,
+
+    /// Sandbox the execution ran in.
+    pub sandbox_id: SandboxId,
+
+    /// Wall time of the execution in milliseconds.
+    pub duration_ms: u64,
+
+    /// CPU time the execution reported, in milliseconds.
+    pub cpu_ms: u64,
+
+    /// GPU time in milliseconds: the sampled series, or the final usage
+    /// snapshot when no samples were taken.
+    pub gpu_ms: u64,
+
+    /// GPU model of the sandbox, if one was required.
+    pub gpu_kind: Option<String>,
+
+    /// Trace of the execution's events, derived from the execution.
+    pub correlation: Correlation,
+
+    /// When the execution completed.
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ExecutionUsageEvent {
+    /// Usage of `result`, an execution in `sandbox_id`.
+    pub fn new(
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        sandbox_id: SandboxId,
+        result: &ExecutionResult,
+        gpu_kind: Option<&str>,
+    ) -> Self {
+        let usage = result.resource_usage.clone().unwrap_or_default();
+        let sampled_gpu_ms: u64 = result.usage_samples.iter().map(|s| s.gpu_ms_delta).sum();
+        let gpu_ms = if sampled_gpu_ms > 0 {
+            sampled_gpu_ms
+        } else {
+            usage.gpu_time_ms
+        };
+        Self {
+            organization_id,
+            agent_id,
+            sandbox_id,
+            duration_ms: result.timing.duration_ms.unwrap_or(0),
+            cpu_ms: usage.cpu_time_ms,
+            gpu_ms,
+            gpu_kind: gpu_kind.map(str::to_string),
+            correlation: result.correlation().derive(result.request_id),
+            timestamp: result.timing.completed_at.unwrap_or_else(Utc::now),
+        }
+    }
+
+    /// Convert to metering usage events.
+    ///
+    /// Always includes the execution event; CPU and GPU events are included
+    /// when the execution used any.
+    #[cfg(feature = "metering")]
+    pub fn to_usage_events(&self, delegation_depth: u8) -> Vec<UsageEvent> {
+        let sandbox_id = self.sandbox_id.as_uuid();
+        let mut events = vec![sandbox_execution_event(
+            self.organization_id,
+            self.agent_id,
+            sandbox_id,
+            self.duration_ms,
+            delegation_depth,
+            self.correlation,
+        )];
+        if self.cpu_ms > 0 {
+            events.push(cpu_usage_event(
+                self.organization_id,
+                self.agent_id,
+                sandbox_id,
+                self.cpu_ms,
+                delegation_depth,
+                self.correlation,
+            ));
+        }
+        if self.gpu_ms > 0 {
+            events.push(gpu_usage_event(
+                self.organization_id,
+                self.agent_id,
+                sandbox_id,
+                self.gpu_ms,
+                self.gpu_kind.as_deref(),
+                delegation_depth,
+                self.correlation,
+            ));
         }
+        for event in &mut events {
+            event.timestamp = self.timestamp;
+        }
+        events
+    }
+}
+
+/// Destination for usage sampled from running sandboxes and reported by
+/// completed executions.
+#[async_trait]
+pub trait UsageEventSink: Send + Sync {
+    /// Report usage accrued since the sandbox's previous sample.
+    async fn emit(&self, event: SandboxUsageEvent) -> CretoResult<()>;
+
+    /// Report the usage of a completed execution.
+    async fn emit_execution(&self, event: ExecutionUsageEvent) -> CretoResult<()>;
+}
+
+/// In-memory usage event sink for testing and development.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryUsageEventSink {
+    events: Arc<RwLock<Vec<SandboxUsageEvent>>>,
+    executions: Arc<RwLock<Vec<ExecutionUsageEvent>>>,
+}
+
+impl InMemoryUsageEventSink {
+    /// Create a new in-memory sink.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get all emitted events, oldest first.
+    pub fn events(&self) -> Vec<SandboxUsageEvent> {
+        self.events.read().unwrap().clone()
+    }
+
+    /// Get all emitted execution usage, oldest first.
+    pub fn executions(&self) -> Vec<ExecutionUsageEvent> {
+        self.executions.read().unwrap().clone()
+    }
+
+    /// Total quantity emitted for `metric` by `sandbox_id`.
+    pub fn total(&self, sandbox_id: SandboxId, metric: RuntimeMeteringEvent) -> u64 {
+        self.events
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|e| e.sandbox_id == sandbox_id && e.metric == metric)
+            .map(|e| e.quantity)
+            .sum()
+    }
+}
+
+#[async_trait]
+impl UsageEventSink for InMemoryUsageEventSink {
+    async fn emit(&self, event: SandboxUsageEvent) -> CretoResult<()> {
+        self.events.write().unwrap().push(event);
+        Ok(())
+    }
+
+    async fn emit_execution(&self, event: ExecutionUsageEvent) -> CretoResult<()> {
+        self.executions.write().unwrap().push(event);
+        Ok(())
+    }
+}
+
+/// Usage event sink that ingests into a [`creto_metering::MeteringService`].
+#[cfg(feature = "metering")]
+pub struct MeteringUsageSink {
+    metering: Arc<creto_metering::MeteringService>,
+}
+
+#[cfg(feature = "metering")]
+impl MeteringUsageSink {
+    /// Ingest sampled and execution usage into `metering`.
+    pub fn new(metering: Arc<creto_metering::MeteringService>) -> Self {
+        Self { metering }
+    }
+}
+
+#[cfg(feature = "metering")]
+#[async_trait]
+impl UsageEventSink for MeteringUsageSink {
+    async fn emit(&self, event: SandboxUsageEvent) -> CretoResult<()> {
+        self.metering
+            .ingest(
+                event.organization_id,
+                event.agent_id,
+                event.to_usage_event(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn emit_execution(&self, event: ExecutionUsageEvent) -> CretoResult<()> {
+        for usage in event.to_usage_events(0) {
+            self.metering
+                .ingest(event.organization_id, event.agent_id, usage)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Byte-milliseconds in one megabyte-second.
+const BYTE_MS_PER_MB_SECOND: u128 = 1024 * 1024 * 1000;
+
+/// Samples the resource usage of running sandboxes in the background.
+///
+/// [`start`](Self::start) spawns one sampling task per sandbox. Each tick
+/// reads the sandbox's controller, records the reading with the repository
+/// and emits the CPU milliseconds and memory megabyte-seconds accrued since
+/// the previous tick. Memory is charged at the level just read for the time
+/// since the previous reading; fractions of a megabyte-second carry over to
+/// the next sample. Failed reads, writes and emits are logged and sampling
+/// carries on.
+///
+/// [`stop`](Self::stop) takes a final sample, so usage up to termination is
+/// billed, and waits for the task to end.
+pub struct SandboxUsageSampler {
+    config: SamplerConfig,
+    repository: Arc<dyn ResourceUsageRepository>,
+    sink: Arc<dyn UsageEventSink>,
+    tasks: Mutex<HashMap<SandboxId, SamplingTask>>,
+}
+
+/// A sandbox's background sampling task.
+struct SamplingTask {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<ResourceUsage>,
+}
+
+impl SandboxUsageSampler {
+    /// Create a sampler writing to `repository` and emitting to `sink`.
+    ///
+    /// Only the configured interval is used; the per-sandbox history lives
+    /// in the repository.
+    pub fn new(
+        config: SamplerConfig,
+        repository: Arc<dyn ResourceUsageRepository>,
+        sink: Arc<dyn UsageEventSink>,
+    ) -> Self {
+        Self {
+            config,
+            repository,
+            sink,
+            tasks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start sampling `sandbox` through `controller`.
+    ///
+    /// Does nothing if the sandbox is already being sampled.
+    pub fn start(&self, sandbox: &Sandbox, controller: Arc<dyn ResourceController>) {
+        self.start_for(
+            sandbox.id,
+            sandbox.organization_id,
+            sandbox.agent_id,
+            controller,
+        );
+    }
+
+    /// Start sampling a sandbox known only by its ID and owner.
+    pub(crate) fn start_for(
+        &self,
+        sandbox_id: SandboxId,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        controller: Arc<dyn ResourceController>,
+    ) {
+        let mut tasks = self.tasks.lock().unwrap();
+        if tasks.contains_key(&sandbox_id) {
+            return;
+        }
+
+        let meter = SandboxMeter {
+            organization_id,
+            agent_id,
+            sandbox_id,
+            controller,
+            repository: Arc::clone(&self.repository),
+            sink: Arc::clone(&self.sink),
+            last_at: None,
+            last_cpu_ms: 0,
+            peak_memory_bytes: 0,
+            memory_byte_ms: 0,
+            last_usage: None,
+        };
+        let (stop, stop_rx) = oneshot::channel();
+        let task = tokio::spawn(meter.run(self.config.interval(), stop_rx));
+        tasks.insert(sandbox_id, SamplingTask { stop, task });
+    }
+
+    /// Whether `sandbox_id` is being sampled.
+    pub fn is_sampling(&self, sandbox_id: SandboxId) -> bool {
+        self.tasks.lock().unwrap().contains_key(&sandbox_id)
+    }
+
+    /// Number of sandboxes being sampled.
+    pub fn sampling_count(&self) -> usize {
+        self.tasks.lock().unwrap().len()
+    }
+
+    /// Stop sampling `sandbox_id` after a final sample.
+    ///
+    /// Returns the last reading, or `None` if the sandbox was not being
+    /// sampled.
+    pub async fn stop(&self, sandbox_id: SandboxId) -> Option<ResourceUsage> {
+        let sampling = self.tasks.lock().unwrap().remove(&sandbox_id)?;
+        let _ = sampling.stop.send(());
+        match sampling.task.await {
+            Ok(usage) => Some(usage),
+            Err(e) => {
+                tracing::warn!(sandbox_id = %sandbox_id, error = %e, "Usage sampling task failed");
+                None
+            }
+        }
+    }
+
+    /// Stop sampling every sandbox, each after a final sample.
+    pub async fn stop_all(&self) {
+        let sandbox_ids: Vec<SandboxId> = self.tasks.lock().unwrap().keys().copied().collect();
+        for sandbox_id in sandbox_ids {
+            self.stop(sandbox_id).await;
+        }
+    }
+}
+
+/// Sampling state for one sandbox.
+struct SandboxMeter {
+    organization_id: OrganizationId,
+    agent_id: AgentId,
+    sandbox_id: SandboxId,
+    controller: Arc<dyn ResourceController>,
+    repository: Arc<dyn ResourceUsageRepository>,
+    sink: Arc<dyn UsageEventSink>,
+    last_at: Option<Instant>,
+    last_cpu_ms: u64,
+    peak_memory_bytes: u64,
+    /// Memory not yet emitted, in byte-milliseconds.
+    memory_byte_ms: u128,
+    last_usage: Option<ResourceUsage>,
+}
+
+impl SandboxMeter {
+    /// Sample every `interval` until stopped, then take a final sample.
+    async fn run(
+        mut self,
+        interval: std::time::Duration,
+        mut stop: oneshot::Receiver<()>,
+    ) -> ResourceUsage {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                biased;
+                _ = &mut stop => break,
+                _ = ticker.tick() => self.sample().await,
+            }
+        }
+        self.sample().await;
+        self.last_usage.unwrap_or_default()
+    }
+
+    async fn sample(&mut self) {
+        let mut usage = match self.controller.current_usage().await {
+            Ok(usage) => usage,
+            Err(e) => {
+                tracing::warn!(sandbox_id = %self.sandbox_id, error = %e, "Sandbox usage sample failed");
+                return;
+            }
+        };
+        let now = Instant::now();
+        let timestamp = Utc::now();
+
+        let elapsed_ms = self.last_at.map_or(0, |last| (now - last).as_millis());
+        self.memory_byte_ms += usage.memory_bytes as u128 * elapsed_ms;
+        let memory_mb_seconds = (self.memory_byte_ms / BYTE_MS_PER_MB_SECOND) as u64;
+        self.memory_byte_ms %= BYTE_MS_PER_MB_SECOND;
+        let cpu_ms = usage.cpu_time_ms.saturating_sub(self.last_cpu_ms);
+        self.last_cpu_ms = self.last_cpu_ms.max(usage.cpu_time_ms);
+        self.peak_memory_bytes = self
+            .peak_memory_bytes
+            .max(usage.peak_memory_bytes)
+            .max(usage.memory_bytes);
+        usage.peak_memory_bytes = self.peak_memory_bytes;
+        self.last_at = Some(now);
+
+        if let Err(e) = self.repository.record(self.sandbox_id, &usage).await {
+            tracing::warn!(sandbox_id = %self.sandbox_id, error = %e, "Failed to record sandbox usage");
+        }
+        for (metric, quantity) in [
+            (RuntimeMeteringEvent::CpuTime, cpu_ms),
+            (RuntimeMeteringEvent::MemoryUsage, memory_mb_seconds),
+        ] {
+            if quantity == 0 {
+                continue;
+            }
+            let event = SandboxUsageEvent {
+                organization_id: self.organization_id,
+                agent_id: self.agent_id,
+                sandbox_id: self.sandbox_id,
+                metric,
+                quantity,
+                timestamp,
+            };
+            if let Err(e) = self.sink.emit(event).await {
+                tracing::warn!(
+                    sandbox_id = %self.sandbox_id,
+                    metric = metric.code(),
+                    error = %e,
+                    "Failed to emit sandbox usage"
+                );
+            }
+        }
+        self.last_usage = Some(usage);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sampling::UsageSample;
+    use crate::sandbox::SandboxConfig;
+    use crate::testing::InMemoryResourceUsageRepository;
+    use creto_common::CretoError;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    const MB: u64 = 1024 * 1024;
+
+    /// Controller reporting 2 MB of memory and 100 ms more CPU per read.
+    #[derive(Default)]
+    struct SteadyController {
+        reads: AtomicU64,
+    }
+
+    #[async_trait]
+    impl ResourceController for SteadyController {
+        async fn current_usage(&self) -> CretoResult<ResourceUsage> {
+            let reads = self.reads.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(ResourceUsage {
+                memory_bytes: 2 * MB,
+                cpu_time_ms: reads * 100,
+                disk_bytes: 4096,
+                ..Default::default()
+            })
+        }
+    }
+
+    struct FailingSink;
+
+    #[async_trait]
+    impl UsageEventSink for FailingSink {
+        async fn emit(&self, _event: SandboxUsageEvent) -> CretoResult<()> {
+            Err(CretoError::Internal("metering unavailable".to_string()))
+        }
+
+        async fn emit_execution(&self, _event: ExecutionUsageEvent) -> CretoResult<()> {
+            Err(CretoError::Internal("metering unavailable".to_string()))
+        }
+    }
+
+    /// Repository whose writes for one sandbox never complete.
+    struct StalledRepository {
+        inner: InMemoryResourceUsageRepository,
+        stalled: SandboxId,
+    }
+
+    #[async_trait]
+    impl ResourceUsageRepository for StalledRepository {
+        async fn record(
+            &self,
+            sandbox_id: SandboxId,
+            usage: &ResourceUsage,
+        ) -> Result<(), CretoError> {
+            if sandbox_id == self.stalled {
+                std::future::pending::<()>().await;
+            }
+            self.inner.record(sandbox_id, usage).await
+        }
+
+        async fn get_latest(
+            &self,
+            sandbox_id: SandboxId,
+        ) -> Result<Option<ResourceUsage>, CretoError> {
+            self.inner.get_latest(sandbox_id).await
+        }
+
+        async fn record_samples(
+            &self,
+            sandbox_id: SandboxId,
+            execution_id: uuid::Uuid,
+            samples: &[UsageSample],
+        ) -> Result<(), CretoError> {
+            self.inner
+                .record_samples(sandbox_id, execution_id, samples)
+                .await
+        }
+
+        async fn get_samples(
+            &self,
+            execution_id: uuid::Uuid,
+        ) -> Result<Vec<UsageSample>, CretoError> {
+            self.inner.get_samples(execution_id).await
+        }
+    }
+
+    fn sandbox() -> Sandbox {
+        Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig::default(),
+        )
+    }
+
+    fn every_second() -> SamplerConfig {
+        SamplerConfig::default().with_interval_ms(1000)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_sampler_records_each_interval_and_final_sample() {
+        let repository = InMemoryResourceUsageRepository::new();
+        let sink = InMemoryUsageEventSink::new();
+        let sampler = SandboxUsageSampler::new(
+            every_second(),
+            Arc::new(repository.clone()),
+            Arc::new(sink.clone()),
+        );
+        let sandbox = sandbox();
+
+        sampler.start(&sandbox, Arc::new(SteadyController::default()));
+        assert!(sampler.is_sampling(sandbox.id));
+        tokio::time::sleep(Duration::from_millis(3500)).await;
+        // Ticks at 0s, 1s, 2s and 3s
+        assert_eq!(repository.snapshots(sandbox.id).len(), 4);
+
+        let last = sampler.stop(sandbox.id).await.unwrap();
+        assert!(!sampler.is_sampling(sandbox.id));
+        let snapshots = repository.snapshots(sandbox.id);
+        assert_eq!(snapshots.len(), 5);
+        assert_eq!(last.cpu_time_ms, 500);
+        assert_eq!(snapshots[4].cpu_time_ms, last.cpu_time_ms);
+        assert_eq!(last.peak_memory_bytes, 2 * MB);
+        assert_eq!(last.disk_bytes, 4096);
+
+        // CPU deltas add up to the last reading; 2 MB for 3.5 s is 7 MB-s
+        assert_eq!(sink.total(sandbox.id, RuntimeMeteringEvent::CpuTime), 500);
+        assert_eq!(sink.total(sandbox.id, RuntimeMeteringEvent::MemoryUsage), 7);
+        assert!(sink
+            .events()
+            .iter()
+            .all(|e| e.organization_id == sandbox.organization_id));
+
+        // No more samples once stopped
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(repository.snapshots(sandbox.id).len(), 5);
+        assert!(sampler.stop(sandbox.id).await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_sink_failures_do_not_stop_sampling() {
+        let repository = InMemoryResourceUsageRepository::new();
+        let sampler = SandboxUsageSampler::new(
+            every_second(),
+            Arc::new(repository.clone()),
+            Arc::new(FailingSink),
+        );
+        let sandbox = sandbox();
+
+        sampler.start(&sandbox, Arc::new(SteadyController::default()));
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+        let last = sampler.stop(sandbox.id).await.unwrap();
+
+        assert_eq!(repository.snapshots(sandbox.id).len(), 4);
+        assert_eq!(last.cpu_time_ms, 400);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stalled_repository_write_does_not_delay_other_sandboxes() {
+        let stalled = sandbox();
+        let healthy = sandbox();
+        let repository = InMemoryResourceUsageRepository::new();
+        let sampler = SandboxUsageSampler::new(
+            every_second(),
+            Arc::new(StalledRepository {
+                inner: repository.clone(),
+                stalled: stalled.id,
+            }),
+            Arc::new(InMemoryUsageEventSink::new()),
+        );
+
+        sampler.start(&stalled, Arc::new(SteadyController::default()));
+        sampler.start(&healthy, Arc::new(SteadyController::default()));
+        assert_eq!(sampler.sampling_count(), 2);
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+
+        assert!(repository.snapshots(stalled.id).is_empty());
+        assert_eq!(repository.snapshots(healthy.id).len(), 3);
+        assert_eq!(sampler.stop(healthy.id).await.unwrap().cpu_time_ms, 400);
+    }
 
     #[test]
     fn test_metering_event_codes() {
@@ -105,5 +884,85 @@ mod tests {
             "sandbox_created"
         );
         assert_eq!(RuntimeMeteringEvent::CpuTime.code(), "cpu_milliseconds");
+        assert_eq!(RuntimeMeteringEvent::GpuTime.code(), "gpu_milliseconds");
+    }
+
+    #[cfg(feature = "metering")]
+    #[tokio::test]
+    async fn test_execute_reports_gpu_usage_to_sink() {
+        use crate::execution::{ExecutionResult, ExecutionTiming};
+        use crate::resources::{HostCapabilities, ResourceLimits, ResourceUsage};
+        use crate::sampling::UsageSample;
+        use crate::sandbox::SandboxConfig;
+        use crate::service::RuntimeService;
+        use crate::testing::ScriptedExecutor;
+
+        let executor = ScriptedExecutor::new();
+        let sink = InMemoryUsageEventSink::new();
+        let service = RuntimeService::new()
+            .with_host_capabilities(HostCapabilities::with_gpus(2, 80 << 30).with_gpu_kind("h100"))
+            .with_usage_sink(Arc::new(sink.clone()))
+            .with_executor(Box::new(executor.clone()));
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = service
+            .create_sandbox(
+                org_id,
+                agent_id,
+                SandboxConfig {
+                    limits: Some(
+                        ResourceLimits::default()
+                            .with_gpus(1, 40 << 30)
+                            .with_gpu_kind("h100"),
+                    ),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        let mut timing = ExecutionTiming::new();
+        timing.mark_started();
+        timing.mark_completed();
+        let mut scripted = ExecutionResult::success(Uuid::nil(), serde_json::json!(null), timing);
+        scripted.resource_usage = Some(ResourceUsage {
+            cpu_time_ms: 40,
+            gpu_time_ms: 900,
+            ..Default::default()
+        });
+        scripted.usage_samples = [250, 500]
+            .into_iter()
+            .enumerate()
+            .map(|(i, gpu_ms_delta)| UsageSample {
+                offset_ms: i as u64 * 100,
+                memory_bytes: 0,
+                cpu_ms_delta: 20,
+                open_fds: 0,
+                gpu_ms_delta,
+            })
+            .collect();
+        executor.push_result(scripted);
+
+        let result = service.execute(sandbox.id, "train()").await.unwrap();
+
+        let executions = sink.executions();
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].organization_id, org_id);
+        assert_eq!(executions[0].agent_id, agent_id);
+        assert_eq!(executions[0].correlation.caused_by, Some(result.request_id));
+        let events = executions[0].to_usage_events(0);
+
+        let gpu = events
+            .iter()
+            .find(|e| e.event_type == UsageEventType::GpuMilliseconds)
+            .unwrap();
+        // Sampled usage wins over the final snapshot
+        assert_eq!(gpu.quantity, 750);
+        assert_eq!(gpu.code, "gpu_milliseconds");
+        assert_eq!(gpu.properties["gpu_kind"], "h100");
+        assert_eq!(gpu.correlation_id, events[0].correlation_id);
+        assert!(events
+            .iter()
+            .any(|e| e.event_type == UsageEventType::CpuMilliseconds && e.quantity == 40));
     }
 }
@@ -2,16 +2,33 @@
 //!
 //! This module provides egress filtering, DNS policy enforcement, and
 //! integration with the Authorization service for network access control.
+//!
+//! Organizations can also define named [`NetworkPolicyTemplate`]s that
+//! sandboxes inherit by reference. A sandbox's effective policy is the
+//! template plus any sandbox-specific rules, with deny rules always taking
+//! precedence over allows.
+//!
+//! Bytes sandboxes move through runtime-mediated connections are counted
+//! by a [`NetworkUsageTracker`].
 
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, CretoResult, OrganizationId};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use crate::sandbox::SandboxId;
 
 /// Network policy defining egress rules and default behavior.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkPolicy {
     /// Default action when no rules match.
     pub default_action: NetworkAction,
-    /// Ordered list of egress rules (first match wins).
+    /// Ordered list of egress rules. For domains the first match wins; for
+    /// addresses a matching deny wins, then the most specific prefix.
     pub egress_rules: Vec<EgressRule>,
     /// DNS resolution policy.
     pub dns_policy: DnsPolicy,
@@ -49,7 +66,7 @@ impl Default for NetworkPolicy {
 }
 
 /// A single egress rule specifying destination and action.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EgressRule {
     /// Destination to match.
     pub destination: EgressDestination,
@@ -86,9 +103,12 @@ impl EgressRule {
 pub enum EgressDestination {
     /// Any destination (wildcard).
     Any,
-    /// IP address or CIDR block (e.g., "10.0.0.0/8").
-    CidrBlock(String),
-    /// Domain with subdomain wildcard (e.g., "*.example.com").
+    /// IP address or CIDR block (e.g., "10.0.0.0/8", "2001:db8::/32").
+    CidrBlock(IpCidr),
+    /// Domain pattern. `*.example.com` matches subdomains at any depth but
+    /// not `example.com` itself; `.example.com` matches both. A `*` label
+    /// elsewhere matches exactly one label (`api.*.example.com`). Without
+    /// wildcards the domain must match exactly.
     Domain(String),
     /// Exact domain match (e.g., "api.example.com").
     DomainExact(String),
@@ -97,71 +117,246 @@ pub enum EgressDestination {
 }
 
 impl EgressDestination {
+    /// A CIDR block destination, rejecting malformed CIDRs.
+    pub fn cidr(cidr: &str) -> CretoResult<Self> {
+        cidr.parse().map(Self::CidrBlock)
+    }
+
     /// Check if this destination matches a given IP address.
     pub fn matches_ip(&self, ip: &IpAddr) -> bool {
         match self {
             EgressDestination::Any => true,
-            EgressDestination::CidrBlock(cidr) => {
-                // Parse CIDR and check if IP is in range
-                Self::ip_in_cidr(ip, cidr)
-            }
+            EgressDestination::CidrBlock(cidr) => cidr.contains(ip),
             _ => false,
         }
     }
 
     /// Check if this destination matches a given domain.
+    ///
+    /// Comparison is case-insensitive, ignores a trailing dot and treats
+    /// Unicode labels as their punycode form. A domain that is not valid
+    /// under IDNA matches nothing but `Any`.
     pub fn matches_domain(&self, domain: &str) -> bool {
+        if *self == EgressDestination::Any {
+            return true;
+        }
+        let Some(domain) = normalize_domain(domain) else {
+            return false;
+        };
+        let labels: Vec<&str> = domain.split('.').collect();
         match self {
-            EgressDestination::Any => true,
             EgressDestination::Domain(pattern) => {
-                // Wildcard matching (e.g., "*.example.com")
-                if let Some(suffix) = pattern.strip_prefix("*.") {
-                    // Match if domain equals suffix OR ends with ".suffix"
-                    domain == suffix || domain.ends_with(&format!(".{}", suffix))
-                } else {
-                    domain == pattern
-                }
+                DomainPattern::parse(pattern).is_some_and(|pattern| pattern.matches(&labels))
+            }
+            EgressDestination::DomainExact(exact) => {
+                normalize_domain(exact).is_some_and(|exact| exact == domain)
             }
-            EgressDestination::DomainExact(exact) => domain == exact,
             _ => false,
         }
     }
 
-    /// Check if IP is in CIDR block (simplified implementation).
-    fn ip_in_cidr(ip: &IpAddr, cidr: &str) -> bool {
-        // Parse CIDR notation
-        let parts: Vec<&str> = cidr.split('/').collect();
-        if parts.len() != 2 {
-            return false;
+    /// Prefix length of the addresses this destination matches; `Any` is
+    /// the least specific.
+    fn ip_prefix_len(&self) -> u8 {
+        match self {
+            EgressDestination::CidrBlock(cidr) => cidr.prefix_len(),
+            _ => 0,
         }
+    }
+}
+
+/// Lowercase ASCII (punycode) form of a domain without its trailing dot,
+/// or `None` if it is not a valid domain.
+fn normalize_domain(domain: &str) -> Option<String> {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    let ascii = idna::domain_to_ascii(domain).ok()?;
+    let valid = !ascii.is_empty() && ascii.split('.').all(|label| !label.is_empty());
+    valid.then_some(ascii)
+}
+
+/// A parsed [`EgressDestination::Domain`] pattern.
+struct DomainPattern {
+    /// Subdomains of `labels` match, at any depth.
+    subdomains: bool,
+    /// `labels` themselves match.
+    apex: bool,
+    /// Normalized labels; `None` is a single-label wildcard.
+    labels: Vec<Option<String>>,
+}
+
+impl DomainPattern {
+    fn parse(pattern: &str) -> Option<Self> {
+        let (subdomains, apex, rest) = if let Some(rest) = pattern.strip_prefix("*.") {
+            (true, false, rest)
+        } else if let Some(rest) = pattern.strip_prefix('.') {
+            (true, true, rest)
+        } else {
+            (false, true, pattern)
+        };
+        let rest = rest.strip_suffix('.').unwrap_or(rest);
+        let labels = rest
+            .split('.')
+            .map(|label| match label {
+                "*" => Some(None),
+                label => normalize_domain(label).map(Some),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            subdomains,
+            apex,
+            labels,
+        })
+    }
+
+    fn matches(&self, domain: &[&str]) -> bool {
+        let depth = self.labels.len();
+        let Some(extra) = domain.len().checked_sub(depth) else {
+            return false;
+        };
+        let allowed = if extra == 0 {
+            self.apex
+        } else {
+            self.subdomains
+        };
+        allowed
+            && self
+                .labels
+                .iter()
+                .zip(&domain[extra..])
+                .all(|(pattern, label)| pattern.as_deref().unwrap_or(label) == *label)
+    }
+}
+
+/// An IPv4 or IPv6 network in CIDR notation.
+///
+/// Parsing rejects out-of-range prefixes and addresses with host bits set;
+/// a bare address is a single-host network. IPv4-mapped IPv6 networks and
+/// addresses are treated as the IPv4 ones they map.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// A network from its base address and prefix length.
+    pub fn new(network: IpAddr, prefix_len: u8) -> CretoResult<Self> {
+        let (network, prefix_len) = match network {
+            IpAddr::V6(v6) if prefix_len >= 96 => match v6.to_ipv4_mapped() {
+                Some(v4) => (IpAddr::V4(v4), prefix_len - 96),
+                None => (network, prefix_len),
+            },
+            _ => (network, prefix_len),
+        };
 
-        let network_addr: IpAddr = match parts[0].parse() {
-            Ok(addr) => addr,
-            Err(_) => return false,
+        let max_len = match network {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
         };
+        if prefix_len > max_len {
+            return Err(CretoError::ValidationFailed(format!(
+                "CIDR prefix /{} exceeds {} bits for {}",
+                prefix_len, max_len, network
+            )));
+        }
 
-        let prefix_len: u8 = match parts[1].parse() {
-            Ok(len) => len,
-            Err(_) => return false,
+        let cidr = Self {
+            network,
+            prefix_len,
         };
+        if cidr.masked(network) != Some(network) {
+            return Err(CretoError::ValidationFailed(format!(
+                "CIDR {}/{} has host bits set",
+                network, prefix_len
+            )));
+        }
+        Ok(cidr)
+    }
 
-        // Only handle IPv4 for now
-        match (ip, network_addr) {
-            (IpAddr::V4(ip_v4), IpAddr::V4(net_v4)) => {
-                let ip_bits = u32::from(*ip_v4);
-                let net_bits = u32::from(net_v4);
-                let mask = if prefix_len == 0 {
-                    0
-                } else {
-                    !0u32 << (32 - prefix_len)
-                };
-                (ip_bits & mask) == (net_bits & mask)
+    /// Base address of the network.
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    /// Number of leading bits that identify the network.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Check if `ip` falls within the network.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.masked(ip.to_canonical()) == Some(self.network)
+    }
+
+    /// `ip` with its host bits cleared, if it is of the network's family.
+    fn masked(&self, ip: IpAddr) -> Option<IpAddr> {
+        match (self.network, ip) {
+            (IpAddr::V4(_), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                Some(IpAddr::V4((u32::from(ip) & mask).into()))
             }
-            _ => false,
+            (IpAddr::V6(_), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                Some(IpAddr::V6((u128::from(ip) & mask).into()))
+            }
+            _ => None,
         }
     }
 }
 
+impl FromStr for IpCidr {
+    type Err = CretoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, prefix) = match s.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = address.parse().map_err(|_| {
+            CretoError::ValidationFailed(format!("Invalid CIDR address in '{}'", s))
+        })?;
+        let prefix_len = match prefix {
+            Some(prefix) => prefix.parse().map_err(|_| {
+                CretoError::ValidationFailed(format!("Invalid CIDR prefix length in '{}'", s))
+            })?,
+            None if network.is_ipv4() => 32,
+            None => 128,
+        };
+        Self::new(network, prefix_len)
+    }
+}
+
+impl TryFrom<String> for IpCidr {
+    type Error = CretoError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<IpCidr> for String {
+    fn from(cidr: IpCidr) -> Self {
+        cidr.to_string()
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+impl fmt::Debug for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
 /// Action to take when a rule matches.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -205,6 +400,299 @@ impl Default for DnsPolicy {
     }
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Policy Templates
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A named, organization-level network policy that sandboxes inherit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkPolicyTemplate {
+    /// Organization owning the template.
+    pub organization_id: OrganizationId,
+    /// Template name (unique per organization).
+    pub name: String,
+    /// Version, incremented on every update.
+    pub version: u32,
+    /// The policy sandboxes inherit.
+    pub policy: NetworkPolicy,
+    /// When this version was written.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NetworkPolicyTemplate {
+    /// Create the first version of a template.
+    pub fn new(
+        organization_id: OrganizationId,
+        name: impl Into<String>,
+        policy: NetworkPolicy,
+    ) -> Self {
+        Self {
+            organization_id,
+            name: name.into(),
+            version: 1,
+            policy,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Resolve the effective policy for a sandbox adding `additional_rules`.
+    ///
+    /// Deny rules from either source are evaluated before any allow or
+    /// authorization rule, so an addition can never re-open a destination the
+    /// template denies. The template's default action and DNS policy apply.
+    pub fn resolve(&self, additional_rules: &[EgressRule]) -> EffectiveNetworkPolicy {
+        let combined: Vec<&EgressRule> = self
+            .policy
+            .egress_rules
+            .iter()
+            .chain(additional_rules)
+            .collect();
+
+        let mut egress_rules: Vec<EgressRule> = Vec::with_capacity(combined.len());
+        let denies = combined.iter().filter(|r| r.action == NetworkAction::Deny);
+        let others = combined.iter().filter(|r| r.action != NetworkAction::Deny);
+        for rule in denies.chain(others) {
+            if !egress_rules.contains(rule) {
+                egress_rules.push((*rule).clone());
+            }
+        }
+
+        EffectiveNetworkPolicy {
+            template_name: self.name.clone(),
+            template_version: self.version,
+            additional_rules: additional_rules.to_vec(),
+            policy: NetworkPolicy {
+                default_action: self.policy.default_action,
+                egress_rules,
+                dns_policy: self.policy.dns_policy.clone(),
+            },
+        }
+    }
+}
+
+/// Reference from a sandbox configuration to an organization template.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkPolicyTemplateRef {
+    /// Name of the template to inherit.
+    pub name: String,
+    /// Sandbox-specific rules layered on top of the template.
+    #[serde(default)]
+    pub additional_rules: Vec<EgressRule>,
+}
+
+impl NetworkPolicyTemplateRef {
+    /// Reference a template without additional rules.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            additional_rules: Vec::new(),
+        }
+    }
+
+    /// Add a sandbox-specific rule.
+    pub fn with_rule(mut self, rule: EgressRule) -> Self {
+        self.additional_rules.push(rule);
+        self
+    }
+}
+
+/// Resolved network policy snapshotted onto a sandbox for audit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveNetworkPolicy {
+    /// Template the policy was resolved from.
+    pub template_name: String,
+    /// Template version at resolution time.
+    pub template_version: u32,
+    /// Sandbox-specific rules that were merged in.
+    #[serde(default)]
+    pub additional_rules: Vec<EgressRule>,
+    /// The merged policy that is enforced.
+    pub policy: NetworkPolicy,
+}
+
+impl EffectiveNetworkPolicy {
+    /// Compute a BLAKE3 hash of the snapshot.
+    pub fn hash(&self) -> Vec<u8> {
+        let bytes = serde_json::to_vec(self).unwrap_or_default();
+        blake3::hash(&bytes).as_bytes().to_vec()
+    }
+}
+
+/// Storage for organization network policy templates.
+#[async_trait::async_trait]
+pub trait NetworkPolicyTemplateStore: Send + Sync {
+    /// Create a new template. Fails if the name is already taken.
+    async fn create(&self, template: NetworkPolicyTemplate) -> CretoResult<NetworkPolicyTemplate>;
+
+    /// Get a template by name.
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+    ) -> CretoResult<Option<NetworkPolicyTemplate>>;
+
+    /// Replace a template's policy, bumping its version.
+    async fn update(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+        policy: NetworkPolicy,
+    ) -> CretoResult<NetworkPolicyTemplate>;
+
+    /// Delete a template.
+    async fn delete(&self, organization_id: OrganizationId, name: &str) -> CretoResult<()>;
+
+    /// List an organization's templates.
+    async fn list(
+        &self,
+        organization_id: OrganizationId,
+    ) -> CretoResult<Vec<NetworkPolicyTemplate>>;
+}
+
+/// In-memory template store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryNetworkPolicyTemplateStore {
+    templates: Arc<RwLock<HashMap<(OrganizationId, String), NetworkPolicyTemplate>>>,
+}
+
+impl InMemoryNetworkPolicyTemplateStore {
+    /// Create a new in-memory template store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn template_not_found(name: &str) -> CretoError {
+    CretoError::NotFound(format!("network policy template '{}'", name))
+}
+
+#[async_trait::async_trait]
+impl NetworkPolicyTemplateStore for InMemoryNetworkPolicyTemplateStore {
+    async fn create(&self, template: NetworkPolicyTemplate) -> CretoResult<NetworkPolicyTemplate> {
+        let mut templates = self.templates.write().unwrap();
+        let key = (template.organization_id, template.name.clone());
+        if templates.contains_key(&key) {
+            return Err(CretoError::ValidationFailed(format!(
+                "network policy template '{}' already exists",
+                template.name
+            )));
+        }
+        templates.insert(key, template.clone());
+        Ok(template)
+    }
+
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+    ) -> CretoResult<Option<NetworkPolicyTemplate>> {
+        Ok(self
+            .templates
+            .read()
+            .unwrap()
+            .get(&(organization_id, name.to_string()))
+            .cloned())
+    }
+
+    async fn update(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+        policy: NetworkPolicy,
+    ) -> CretoResult<NetworkPolicyTemplate> {
+        let mut templates = self.templates.write().unwrap();
+        let template = templates
+            .get_mut(&(organization_id, name.to_string()))
+            .ok_or_else(|| template_not_found(name))?;
+        template.policy = policy;
+        template.version += 1;
+        template.updated_at = Utc::now();
+        Ok(template.clone())
+    }
+
+    async fn delete(&self, organization_id: OrganizationId, name: &str) -> CretoResult<()> {
+        self.templates
+            .write()
+            .unwrap()
+            .remove(&(organization_id, name.to_string()))
+            .map(|_| ())
+            .ok_or_else(|| template_not_found(name))
+    }
+
+    async fn list(
+        &self,
+        organization_id: OrganizationId,
+    ) -> CretoResult<Vec<NetworkPolicyTemplate>> {
+        let mut templates: Vec<_> = self
+            .templates
+            .read()
+            .unwrap()
+            .values()
+            .filter(|t| t.organization_id == organization_id)
+            .cloned()
+            .collect();
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Usage
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Bytes a sandbox has moved over the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkUsage {
+    /// Bytes sent by the sandbox.
+    pub bytes_sent: u64,
+    /// Bytes delivered to the sandbox.
+    pub bytes_received: u64,
+}
+
+/// Per-sandbox network byte counters.
+pub trait NetworkUsageTracker: Send + Sync {
+    /// Add transferred bytes to a sandbox's totals.
+    fn record(&self, sandbox_id: SandboxId, bytes_sent: u64, bytes_received: u64);
+
+    /// Totals recorded for a sandbox so far.
+    fn usage(&self, sandbox_id: SandboxId) -> NetworkUsage;
+}
+
+/// In-memory network usage tracker.
+#[derive(Debug, Default)]
+pub struct InMemoryNetworkUsageTracker {
+    usage: RwLock<HashMap<SandboxId, NetworkUsage>>,
+}
+
+impl InMemoryNetworkUsageTracker {
+    /// Create an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl NetworkUsageTracker for InMemoryNetworkUsageTracker {
+    fn record(&self, sandbox_id: SandboxId, bytes_sent: u64, bytes_received: u64) {
+        let mut usage = self.usage.write().unwrap();
+        let totals = usage.entry(sandbox_id).or_default();
+        totals.bytes_sent += bytes_sent;
+        totals.bytes_received += bytes_received;
+    }
+
+    fn usage(&self, sandbox_id: SandboxId) -> NetworkUsage {
+        self.usage
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Enforcement
+// ─────────────────────────────────────────────────────────────────────────────
+
 /// Network policy enforcer that checks egress requests.
 pub struct NetworkPolicyEnforcer {
     policy: NetworkPolicy,
@@ -216,34 +704,55 @@ impl NetworkPolicyEnforcer {
         Self { policy }
     }
 
+    /// Check an egress destination, an IP address or a domain.
+    ///
+    /// Anything that parses as an address (bracketed IPv6 included) is
+    /// checked with [`check_ip`](Self::check_ip), the rest as a domain.
+    pub fn evaluate(&self, destination: &str) -> EgressDecision {
+        let address = destination
+            .strip_prefix('[')
+            .and_then(|d| d.strip_suffix(']'))
+            .unwrap_or(destination);
+        match address.parse::<IpAddr>() {
+            Ok(ip) => self.check_ip(&ip),
+            Err(_) => self.check_domain(destination),
+        }
+    }
+
     /// Check if an IP address is allowed by the policy.
+    ///
+    /// A matching deny rule always wins, so a narrower deny carves a hole
+    /// in a broader allow and an allow cannot re-open a denied range.
+    /// Otherwise the matching rule with the longest prefix applies, the
+    /// earliest on a tie.
     pub fn check_ip(&self, ip: &IpAddr) -> EgressDecision {
+        let mut best: Option<&EgressRule> = None;
         for rule in &self.policy.egress_rules {
-            if rule.destination.matches_ip(ip) {
-                return EgressDecision {
-                    action: rule.action,
-                    requires_authz: rule.authorization_check,
-                    matched_rule: Some(format!("{:?}", rule.destination)),
-                };
+            if !rule.destination.matches_ip(ip) {
+                continue;
+            }
+            if rule.action == NetworkAction::Deny {
+                return Self::decision(rule);
+            }
+            match best {
+                Some(current)
+                    if current.destination.ip_prefix_len() >= rule.destination.ip_prefix_len() => {}
+                _ => best = Some(rule),
             }
         }
 
-        EgressDecision {
+        best.map(Self::decision).unwrap_or(EgressDecision {
             action: self.policy.default_action,
             requires_authz: false,
             matched_rule: None,
-        }
+        })
     }
 
     /// Check if a domain is allowed by the policy.
     pub fn check_domain(&self, domain: &str) -> EgressDecision {
         for rule in &self.policy.egress_rules {
             if rule.destination.matches_domain(domain) {
-                return EgressDecision {
-                    action: rule.action,
-                    requires_authz: rule.authorization_check,
-                    matched_rule: Some(format!("{:?}", rule.destination)),
-                };
+                return Self::decision(rule);
             }
         }
 
@@ -255,6 +764,11 @@ impl NetworkPolicyEnforcer {
     }
 
     /// Check if a DNS query is allowed.
+    ///
+    /// Queries are matched against the egress rules like
+    /// [`check_domain`](Self::check_domain), and refused only if a deny rule
+    /// matches; the default action does not apply, so connections by
+    /// address still resolve under a default-deny policy.
     pub fn check_dns(&self, query: &str) -> bool {
         if !self.policy.dns_policy.allow_dns {
             return false;
@@ -264,13 +778,32 @@ impl NetworkPolicyEnforcer {
             tracing::debug!("DNS query: {}", query);
         }
 
-        true
+        let decision = self.check_domain(query);
+        decision.matched_rule.is_none() || decision.action != NetworkAction::Deny
     }
 
     /// Get the DNS policy.
     pub fn dns_policy(&self) -> &DnsPolicy {
         &self.policy.dns_policy
     }
+
+    /// Get the policy being enforced.
+    pub fn policy(&self) -> &NetworkPolicy {
+        &self.policy
+    }
+
+    /// Replace the enforced policy (e.g. after a template update).
+    pub fn update_policy(&mut self, policy: NetworkPolicy) {
+        self.policy = policy;
+    }
+
+    fn decision(rule: &EgressRule) -> EgressDecision {
+        EgressDecision {
+            action: rule.action,
+            requires_authz: rule.authorization_check,
+            matched_rule: Some(format!("{:?}", rule.destination)),
+        }
+    }
 }
 
 /// Result of an egress check.
@@ -336,7 +869,7 @@ mod tests {
     fn test_cidr_block_matching() {
         let mut policy = NetworkPolicy::new_default_deny();
         policy.add_rule(EgressRule::new(
-            EgressDestination::CidrBlock("10.0.0.0/8".to_string()),
+            EgressDestination::cidr("10.0.0.0/8").unwrap(),
             NetworkAction::Allow,
         ));
 
@@ -391,9 +924,83 @@ mod tests {
 
         assert!(dest.matches_domain("api.example.com"));
         assert!(dest.matches_domain("www.example.com"));
-        assert!(dest.matches_domain("example.com"));
+        assert!(dest.matches_domain("a.b.c.example.com"));
         assert!(!dest.matches_domain("example.org"));
         assert!(!dest.matches_domain("notexample.com"));
+        assert!(!dest.matches_domain("evilexample.com"));
+        assert!(!dest.matches_domain("api.evilexample.com"));
+        assert!(!dest.matches_domain("example.com.evil.net"));
+
+        // The apex only matches when the rule asks for it
+        assert!(!dest.matches_domain("example.com"));
+        let with_apex = EgressDestination::Domain(".example.com".to_string());
+        assert!(with_apex.matches_domain("example.com"));
+        assert!(with_apex.matches_domain("deep.api.example.com"));
+        assert!(!with_apex.matches_domain("evilexample.com"));
+
+        // Case and trailing dots are normalized on both sides
+        assert!(dest.matches_domain("API.Example.COM."));
+        let shouting = EgressDestination::Domain("*.EXAMPLE.com.".to_string());
+        assert!(shouting.matches_domain("api.example.com"));
+        let exact = EgressDestination::DomainExact("Api.Example.com".to_string());
+        assert!(exact.matches_domain("api.example.com."));
+        assert!(!exact.matches_domain("www.api.example.com"));
+
+        // Malformed domains match nothing
+        assert!(!dest.matches_domain("api..example.com"));
+        assert!(!dest.matches_domain(""));
+    }
+
+    #[test]
+    fn test_single_label_wildcard() {
+        let dest = EgressDestination::Domain("api.*.example.com".to_string());
+
+        assert!(dest.matches_domain("api.eu.example.com"));
+        assert!(dest.matches_domain("API.us.example.com."));
+        assert!(!dest.matches_domain("api.example.com"));
+        assert!(!dest.matches_domain("api.eu.west.example.com"));
+        assert!(!dest.matches_domain("www.eu.example.com"));
+
+        // Combined with a leading wildcard
+        let dest = EgressDestination::Domain("*.*.example.com".to_string());
+        assert!(dest.matches_domain("a.b.example.com"));
+        assert!(dest.matches_domain("a.b.c.example.com"));
+        assert!(!dest.matches_domain("b.example.com"));
+    }
+
+    #[test]
+    fn test_unicode_domains_match_punycode() {
+        let unicode = EgressDestination::Domain("*.bücher.example".to_string());
+        assert!(unicode.matches_domain("shop.xn--bcher-kva.example"));
+        assert!(unicode.matches_domain("Shop.BÜCHER.example."));
+
+        let punycode = EgressDestination::DomainExact("xn--bcher-kva.example".to_string());
+        assert!(punycode.matches_domain("bücher.example"));
+
+        // A lookalike with a Cyrillic "а" is a different domain
+        let dest = EgressDestination::Domain("*.example.com".to_string());
+        assert!(!dest.matches_domain("api.exаmple.com"));
+    }
+
+    #[test]
+    fn test_dns_queries_use_egress_matcher() {
+        let mut policy = NetworkPolicy::new_default_deny();
+        policy.add_rule(EgressRule::new(
+            EgressDestination::Domain("*.tracker.example".to_string()),
+            NetworkAction::Deny,
+        ));
+        policy.add_rule(EgressRule::new(
+            EgressDestination::Domain("*.openai.com".to_string()),
+            NetworkAction::Allow,
+        ));
+        let enforcer = NetworkPolicyEnforcer::new(policy);
+
+        assert!(enforcer.check_dns("api.openai.com"));
+        assert!(!enforcer.check_dns("ads.tracker.example"));
+        assert!(!enforcer.check_dns("ADS.Tracker.Example."));
+        // Not matched by any rule: resolves, though connecting is denied
+        assert!(enforcer.check_dns("tracker.example"));
+        assert!(!enforcer.check_domain("tracker.example").is_allowed());
     }
 
     #[test]
@@ -423,22 +1030,133 @@ mod tests {
         assert_eq!(decision.action, NetworkAction::Allow);
     }
 
+    fn in_cidr(ip: &str, cidr: &str) -> bool {
+        cidr.parse::<IpCidr>()
+            .unwrap()
+            .contains(&ip.parse().unwrap())
+    }
+
     #[test]
     fn test_cidr_parsing() {
         // Valid /24 network
-        let ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 100));
-        assert!(EgressDestination::ip_in_cidr(&ip, "192.168.1.0/24"));
-        assert!(!EgressDestination::ip_in_cidr(&ip, "192.168.2.0/24"));
+        assert!(in_cidr("192.168.1.100", "192.168.1.0/24"));
+        assert!(!in_cidr("192.168.1.100", "192.168.2.0/24"));
 
         // Valid /16 network
-        let ip = IpAddr::V4(Ipv4Addr::new(172, 16, 5, 10));
-        assert!(EgressDestination::ip_in_cidr(&ip, "172.16.0.0/16"));
-        assert!(!EgressDestination::ip_in_cidr(&ip, "172.17.0.0/16"));
+        assert!(in_cidr("172.16.5.10", "172.16.0.0/16"));
+        assert!(!in_cidr("172.16.5.10", "172.17.0.0/16"));
+
+        // Edge case: /32 (single IP), also written as a bare address
+        assert!(in_cidr("10.0.0.1", "10.0.0.1/32"));
+        assert!(!in_cidr("10.0.0.1", "10.0.0.2/32"));
+        assert!(in_cidr("10.0.0.1", "10.0.0.1"));
+
+        // IPv6, and /0 matching its whole family only
+        assert!(in_cidr("2001:db8:1::5", "2001:db8::/32"));
+        assert!(!in_cidr("2001:db9::5", "2001:db8::/32"));
+        assert!(in_cidr("255.1.2.3", "0.0.0.0/0"));
+        assert!(!in_cidr("::1", "0.0.0.0/0"));
+        assert!(!in_cidr("10.0.0.1", "::/0"));
+    }
 
-        // Edge case: /32 (single IP)
-        let ip = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
-        assert!(EgressDestination::ip_in_cidr(&ip, "10.0.0.1/32"));
-        assert!(!EgressDestination::ip_in_cidr(&ip, "10.0.0.2/32"));
+    #[test]
+    fn test_invalid_cidrs_rejected_at_construction() {
+        for invalid in [
+            "10.0.0.0/33",
+            "2001:db8::/129",
+            "300.0.0.0/8",
+            "10.0.0.0/",
+            "10.0.0.0/-1",
+            "example.com/24",
+            "10.0.0.1/8",
+        ] {
+            let err = EgressDestination::cidr(invalid).unwrap_err();
+            assert!(
+                matches!(err, CretoError::ValidationFailed(_)),
+                "{invalid}: {err}"
+            );
+        }
+
+        // Stored policies are validated as they are loaded
+        let valid: EgressRule =
+            serde_json::from_str(r#"{"destination":{"cidr_block":"10.0.0.0/8"},"action":"allow"}"#)
+                .unwrap();
+        assert_eq!(
+            valid.destination,
+            EgressDestination::cidr("10.0.0.0/8").unwrap()
+        );
+        assert!(serde_json::to_string(&valid)
+            .unwrap()
+            .contains(r#""10.0.0.0/8""#));
+        assert!(serde_json::from_str::<EgressRule>(
+            r#"{"destination":{"cidr_block":"10.0.0.0/40"},"action":"allow"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_overlapping_cidr_precedence() {
+        let rule =
+            |cidr: &str, action| EgressRule::new(EgressDestination::cidr(cidr).unwrap(), action);
+        let mut policy = NetworkPolicy::new_default_deny();
+        policy.add_rule(rule("10.0.0.0/8", NetworkAction::Allow));
+        policy.add_rule(rule("10.1.0.0/16", NetworkAction::Deny));
+        policy.add_rule(EgressRule::with_authz(
+            EgressDestination::cidr("10.2.0.0/16").unwrap(),
+        ));
+        policy.add_rule(rule("10.2.3.0/24", NetworkAction::Allow));
+        let enforcer = NetworkPolicyEnforcer::new(policy);
+
+        // The narrower deny wins even though the broad allow comes first
+        assert_eq!(enforcer.evaluate("10.1.2.3").action, NetworkAction::Deny);
+        assert!(enforcer.evaluate("10.9.9.9").is_allowed());
+        // Among non-denies the longest prefix applies
+        assert!(enforcer.evaluate("10.2.9.9").needs_authorization());
+        assert!(enforcer.evaluate("10.2.3.4").is_allowed());
+        // Default-deny fallback outside every range
+        let decision = enforcer.evaluate("192.168.1.1");
+        assert_eq!(decision.action, NetworkAction::Deny);
+        assert_eq!(decision.matched_rule, None);
+
+        // A narrower allow cannot re-open a broader deny
+        let mut policy = NetworkPolicy::new_default_allow();
+        policy.add_rule(rule("10.0.0.0/8", NetworkAction::Deny));
+        policy.add_rule(rule("10.1.0.0/16", NetworkAction::Allow));
+        let enforcer = NetworkPolicyEnforcer::new(policy);
+        assert_eq!(enforcer.evaluate("10.1.2.3").action, NetworkAction::Deny);
+        assert!(enforcer.evaluate("11.0.0.1").is_allowed());
+    }
+
+    #[test]
+    fn test_ipv6_and_ipv4_mapped_addresses() {
+        let mut policy = NetworkPolicy::new_default_deny();
+        policy.add_rule(EgressRule::new(
+            EgressDestination::cidr("10.0.0.0/8").unwrap(),
+            NetworkAction::Allow,
+        ));
+        policy.add_rule(EgressRule::new(
+            EgressDestination::cidr("2001:db8::/32").unwrap(),
+            NetworkAction::Allow,
+        ));
+        policy.add_rule(EgressRule::new(
+            EgressDestination::cidr("::ffff:10.9.0.0/112").unwrap(),
+            NetworkAction::Deny,
+        ));
+        let enforcer = NetworkPolicyEnforcer::new(policy);
+
+        // IPv4-mapped addresses match IPv4 rules, and mapped rules IPv4
+        assert!(enforcer.evaluate("::ffff:10.1.2.3").is_allowed());
+        assert!(!enforcer.evaluate("::ffff:192.168.1.1").is_allowed());
+        assert!(!enforcer.evaluate("10.9.1.1").is_allowed());
+        assert_eq!(
+            EgressDestination::cidr("::ffff:10.9.0.0/112").unwrap(),
+            EgressDestination::cidr("10.9.0.0/16").unwrap()
+        );
+
+        assert!(enforcer.evaluate("2001:db8:ab::1").is_allowed());
+        assert!(enforcer.evaluate("[2001:db8::1]").is_allowed());
+        assert!(!enforcer.evaluate("2001:db9::1").is_allowed());
+        assert!(!enforcer.evaluate("fe80::1").is_allowed());
     }
 
     #[test]
@@ -474,4 +1192,100 @@ mod tests {
         assert_eq!(policy.max_ttl_seconds, 600);
         assert!(policy.log_queries);
     }
+
+    fn corp_template() -> NetworkPolicyTemplate {
+        let mut policy = NetworkPolicy::new_default_deny();
+        policy.add_rule(EgressRule::new(
+            EgressDestination::Domain("*.corp.example.com".to_string()),
+            NetworkAction::Allow,
+        ));
+        policy.add_rule(EgressRule::new(
+            EgressDestination::DomainExact("pastebin.com".to_string()),
+            NetworkAction::Deny,
+        ));
+        NetworkPolicyTemplate::new(OrganizationId::new(), "corp-default", policy)
+    }
+
+    #[test]
+    fn test_template_resolution() {
+        let template = corp_template();
+        let extra = EgressRule::new(
+            EgressDestination::DomainExact("api.openai.com".to_string()),
+            NetworkAction::Allow,
+        );
+
+        let effective = template.resolve(std::slice::from_ref(&extra));
+        assert_eq!(effective.template_name, "corp-default");
+        assert_eq!(effective.template_version, 1);
+        assert_eq!(effective.additional_rules, vec![extra]);
+        assert_eq!(effective.policy.egress_rules.len(), 3);
+
+        let enforcer = NetworkPolicyEnforcer::new(effective.policy);
+        assert!(enforcer.check_domain("git.corp.example.com").is_allowed());
+        assert!(enforcer.check_domain("api.openai.com").is_allowed());
+        assert!(!enforcer.check_domain("example.org").is_allowed());
+    }
+
+    #[test]
+    fn test_resolution_deny_wins() {
+        let template = corp_template();
+
+        // An addition cannot re-open a destination the template denies...
+        let reopen = EgressRule::new(
+            EgressDestination::DomainExact("pastebin.com".to_string()),
+            NetworkAction::Allow,
+        );
+        // ...and an added deny beats a broader template allow.
+        let narrow = EgressRule::new(
+            EgressDestination::DomainExact("hr.corp.example.com".to_string()),
+            NetworkAction::Deny,
+        );
+
+        let effective = template.resolve(&[reopen, narrow]);
+        let enforcer = NetworkPolicyEnforcer::new(effective.policy);
+
+        assert_eq!(
+            enforcer.check_domain("pastebin.com").action,
+            NetworkAction::Deny
+        );
+        assert_eq!(
+            enforcer.check_domain("hr.corp.example.com").action,
+            NetworkAction::Deny
+        );
+        assert!(enforcer.check_domain("git.corp.example.com").is_allowed());
+    }
+
+    #[test]
+    fn test_resolution_deduplicates_rules() {
+        let template = corp_template();
+        let duplicate = template.policy.egress_rules[0].clone();
+
+        let effective = template.resolve(&[duplicate]);
+        assert_eq!(effective.policy.egress_rules.len(), 2);
+        assert_eq!(effective.additional_rules.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_template_store() {
+        let store = InMemoryNetworkPolicyTemplateStore::new();
+        let template = corp_template();
+        let org_id = template.organization_id;
+
+        store.create(template.clone()).await.unwrap();
+        assert!(store.create(template).await.is_err());
+
+        let updated = store
+            .update(org_id, "corp-default", NetworkPolicy::new_default_allow())
+            .await
+            .unwrap();
+        assert_eq!(updated.version, 2);
+        assert_eq!(updated.policy.default_action, NetworkAction::Allow);
+
+        assert_eq!(store.list(org_id).await.unwrap().len(), 1);
+        assert!(store.list(OrganizationId::new()).await.unwrap().is_empty());
+
+        store.delete(org_id, "corp-default").await.unwrap();
+        assert!(store.get(org_id, "corp-default").await.unwrap().is_none());
+        assert!(store.delete(org_id, "corp-default").await.is_err());
+    }
 }
@@ -0,0 +1,349 @@
+//! Organization-wide runtime policy.
+//!
+//! An [`OrgRuntimePolicy`] supplies defaults for the sandbox configurations an
+//! organization's callers request, and can pin security-relevant fields so a
+//! request cannot weaken them. The service merges the policy into every
+//! [`SandboxConfig`] before creating a sandbox; policy updates only affect
+//! sandboxes created afterwards.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use creto_common::{CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+
+use crate::attestation::AttestationPolicy;
+use crate::idle::IdlePolicy;
+use crate::network::{NetworkAction, NetworkPolicyTemplateRef};
+use crate::resources::ResourceLimits;
+use crate::sandbox::SandboxConfig;
+
+/// A policy field that requests cannot weaken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PinnedField {
+    /// Requested limits must fit within the policy limits.
+    Limits,
+    /// The policy template must be used; additions may only deny.
+    NetworkPolicyTemplate,
+    /// Requested attestation must be at least as strict as the policy's.
+    AttestationPolicy,
+}
+
+impl PinnedField {
+    /// Field name reported in policy violations.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PinnedField::Limits => "limits",
+            PinnedField::NetworkPolicyTemplate => "network_policy_template",
+            PinnedField::AttestationPolicy => "attestation_policy",
+        }
+    }
+}
+
+/// Organization-wide sandbox defaults and pinned fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgRuntimePolicy {
+    /// Organization the policy applies to.
+    pub organization_id: OrganizationId,
+    /// Version, incremented on every update.
+    pub version: u32,
+    /// Limits used when the request sets none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<ResourceLimits>,
+    /// Network policy template used when the request names none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_policy_template: Option<NetworkPolicyTemplateRef>,
+    /// Attestation policy used when the request sets none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation_policy: Option<AttestationPolicy>,
+    /// Runtimes sandboxes may use (empty = any).
+    #[serde(default)]
+    pub allowed_runtimes: Vec<String>,
+    /// Fields requests cannot weaken.
+    #[serde(default)]
+    pub pinned: Vec<PinnedField>,
+    /// When idle sandboxes are paused and hibernated (None = service default).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle: Option<IdlePolicy>,
+    /// When this version was written.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl OrgRuntimePolicy {
+    /// Create an empty policy for an organization.
+    pub fn new(organization_id: OrganizationId) -> Self {
+        Self {
+            organization_id,
+            version: 1,
+            limits: None,
+            network_policy_template: None,
+            attestation_policy: None,
+            allowed_runtimes: Vec::new(),
+            pinned: Vec::new(),
+            idle: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Set the default limits.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = Some(limits);
+        self
+    }
+
+    /// Set the default network policy template.
+    pub fn with_network_policy_template(mut self, template: NetworkPolicyTemplateRef) -> Self {
+        self.network_policy_template = Some(template);
+        self
+    }
+
+    /// Set the default attestation policy.
+    pub fn with_attestation_policy(mut self, policy: AttestationPolicy) -> Self {
+        self.attestation_policy = Some(policy);
+        self
+    }
+
+    /// Set the idle policy.
+    pub fn with_idle_policy(mut self, policy: IdlePolicy) -> Self {
+        self.idle = Some(policy);
+        self
+    }
+
+    /// Restrict sandboxes to the given runtimes.
+    pub fn with_allowed_runtimes<I, S>(mut self, runtimes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowed_runtimes = runtimes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Pin a field so requests cannot weaken it.
+    pub fn pin(mut self, field: PinnedField) -> Self {
+        if !self.pinned.contains(&field) {
+            self.pinned.push(field);
+        }
+        self
+    }
+
+    /// Check whether a field is pinned.
+    pub fn is_pinned(&self, field: PinnedField) -> bool {
+        self.pinned.contains(&field)
+    }
+
+    /// Merge the policy into a requested configuration.
+    ///
+    /// Unset fields take the policy defaults. Requests that weaken a pinned
+    /// field, or use a runtime outside the allowed list, fail with
+    /// [`CretoError::PolicyViolation`] naming every offending field.
+    pub fn apply(&self, mut config: SandboxConfig) -> CretoResult<SandboxConfig> {
+        let mut violations = Vec::new();
+
+        if !self.allowed_runtimes.is_empty() && !self.allowed_runtimes.contains(&config.runtime) {
+            violations.push("runtime".to_string());
+        }
+
+        if let Some(limits) = &self.limits {
+            match &config.limits {
+                None => config.limits = Some(limits.clone()),
+                Some(requested)
+                    if self.is_pinned(PinnedField::Limits) && !requested.fits_within(limits) =>
+                {
+                    violations.push(PinnedField::Limits.as_str().to_string());
+                }
+                Some(_) => {}
+            }
+        }
+
+        if let Some(template) = &self.network_policy_template {
+            match config.network_policy_template.take() {
+                None => config.network_policy_template = Some(template.clone()),
+                Some(requested) if self.is_pinned(PinnedField::NetworkPolicyTemplate) => {
+                    let only_denies = requested
+                        .additional_rules
+                        .iter()
+                        .all(|r| r.action == NetworkAction::Deny);
+                    if requested.name != template.name || !only_denies {
+                        violations.push(PinnedField::NetworkPolicyTemplate.as_str().to_string());
+                    }
+                    let mut merged = template.clone();
+                    merged.additional_rules.extend(requested.additional_rules);
+                    config.network_policy_template = Some(merged);
+                }
+                Some(requested) => config.network_policy_template = Some(requested),
+            }
+        }
+
+        if let Some(attestation) = &self.attestation_policy {
+            match &config.attestation_policy {
+                None => config.attestation_policy = Some(attestation.clone()),
+                Some(requested)
+                    if self.is_pinned(PinnedField::AttestationPolicy)
+                        && !requested.is_at_least_as_strict_as(attestation) =>
+                {
+                    violations.push(PinnedField::AttestationPolicy.as_str().to_string());
+                }
+                Some(_) => {}
+            }
+        }
+
+        if !violations.is_empty() {
+            return Err(CretoError::PolicyViolation { fields: violations });
+        }
+        Ok(config)
+    }
+}
+
+/// Storage for organization runtime policies.
+#[async_trait::async_trait]
+pub trait OrgRuntimePolicyStore: Send + Sync {
+    /// Get an organization's current policy.
+    async fn get(&self, organization_id: OrganizationId) -> CretoResult<Option<OrgRuntimePolicy>>;
+
+    /// Store a policy, replacing any existing one and bumping its version.
+    async fn put(&self, policy: OrgRuntimePolicy) -> CretoResult<OrgRuntimePolicy>;
+}
+
+/// In-memory policy store for testing and development.
+#[derive(Debug, Default)]
+pub struct InMemoryOrgRuntimePolicyStore {
+    policies: Arc<RwLock<HashMap<OrganizationId, OrgRuntimePolicy>>>,
+}
+
+impl InMemoryOrgRuntimePolicyStore {
+    /// Create a new in-memory policy store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl OrgRuntimePolicyStore for InMemoryOrgRuntimePolicyStore {
+    async fn get(&self, organization_id: OrganizationId) -> CretoResult<Option<OrgRuntimePolicy>> {
+        Ok(self.policies.read().unwrap().get(&organization_id).cloned())
+    }
+
+    async fn put(&self, mut policy: OrgRuntimePolicy) -> CretoResult<OrgRuntimePolicy> {
+        let mut policies = self.policies.write().unwrap();
+        policy.version = policies
+            .get(&policy.organization_id)
+            .map(|p| p.version + 1)
+            .unwrap_or(1);
+        policy.updated_at = Utc::now();
+        policies.insert(policy.organization_id, policy.clone());
+        Ok(policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{EgressDestination, EgressRule};
+
+    fn llm_only() -> NetworkPolicyTemplateRef {
+        NetworkPolicyTemplateRef::new("llm-allowlist")
+    }
+
+    fn production_policy() -> OrgRuntimePolicy {
+        OrgRuntimePolicy::new(OrganizationId::new())
+            .with_limits(ResourceLimits::minimal())
+            .with_network_policy_template(llm_only())
+            .with_attestation_policy(AttestationPolicy::production())
+            .with_allowed_runtimes(["python3.11", "node20"])
+            .pin(PinnedField::NetworkPolicyTemplate)
+            .pin(PinnedField::AttestationPolicy)
+    }
+
+    #[test]
+    fn test_defaults_fill_unset_fields() {
+        let config = production_policy().apply(SandboxConfig::default()).unwrap();
+
+        assert_eq!(config.limits, Some(ResourceLimits::minimal()));
+        assert_eq!(
+            config.network_policy_template.unwrap().name,
+            "llm-allowlist"
+        );
+        assert!(config.attestation_policy.unwrap().require_attestation);
+    }
+
+    #[test]
+    fn test_unpinned_field_can_be_overridden() {
+        let requested = SandboxConfig {
+            limits: Some(ResourceLimits::generous()),
+            ..Default::default()
+        };
+
+        let config = production_policy().apply(requested).unwrap();
+        assert_eq!(config.limits, Some(ResourceLimits::generous()));
+    }
+
+    #[test]
+    fn test_pinned_fields_reject_weakening() {
+        let requested = SandboxConfig {
+            runtime: "ruby3".to_string(),
+            network_policy_template: Some(NetworkPolicyTemplateRef::new("open-internet")),
+            attestation_policy: Some(AttestationPolicy::default()),
+            ..Default::default()
+        };
+
+        let err = production_policy().apply(requested).unwrap_err();
+        match err {
+            CretoError::PolicyViolation { fields } => assert_eq!(
+                fields,
+                vec!["runtime", "network_policy_template", "attestation_policy"]
+            ),
+            other => panic!("expected policy violation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pinned_fields_accept_tightening() {
+        let policy = production_policy().pin(PinnedField::Limits);
+        let requested = SandboxConfig {
+            limits: Some(ResourceLimits::minimal().with_memory(64 * 1024 * 1024)),
+            network_policy_template: Some(llm_only().with_rule(EgressRule::new(
+                EgressDestination::Domain("api.example.com".to_string()),
+                NetworkAction::Deny,
+            ))),
+            attestation_policy: Some(AttestationPolicy::strict()),
+            ..Default::default()
+        };
+
+        let config = policy.apply(requested).unwrap();
+        assert_eq!(config.effective_limits().memory_bytes, 64 * 1024 * 1024);
+        assert_eq!(
+            config
+                .network_policy_template
+                .unwrap()
+                .additional_rules
+                .len(),
+            1
+        );
+
+        let looser = SandboxConfig {
+            limits: Some(ResourceLimits::generous()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            policy.apply(looser),
+            Err(CretoError::PolicyViolation { fields }) if fields == vec!["limits"]
+        ));
+    }
+
+    #[test]
+    fn test_explicit_default_limits_are_not_treated_as_unset() {
+        let policy = OrgRuntimePolicy::new(OrganizationId::new())
+            .with_limits(ResourceLimits::generous())
+            .pin(PinnedField::Limits);
+        let requested = SandboxConfig {
+            limits: Some(ResourceLimits::default()),
+            ..Default::default()
+        };
+
+        let config = policy.apply(requested).unwrap();
+        assert_eq!(config.limits, Some(ResourceLimits::default()));
+    }
+}
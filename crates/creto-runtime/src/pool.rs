@@ -1,14 +1,27 @@
 //! Warm pool for pre-initialized sandboxes.
+//!
+//! Warm sandboxes can die while they wait (an OOM in the idle init process,
+//! a container runtime restart). With a [`HealthProbe`] set, the pool
+//! probes idle sandboxes on each [`WarmPool::check_health`] sweep and,
+//! if [`PoolHealthConfig::probe_on_acquire`] is set, before handing one out.
+//! A sandbox that fails a probe leaves the ready list until a later probe
+//! passes; after [`PoolHealthConfig::eviction_threshold`] consecutive
+//! failures it is evicted, and the next sweep hands it back to the caller
+//! to destroy.
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use creto_common::CretoResult;
+use creto_common::metrics::{CounterVec, Gauge, MetricsRegistry};
+use creto_common::{health_check, CretoError, CretoResult, HealthResponse, OrganizationId};
 use serde::{Deserialize, Serialize};
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, RwLockWriteGuard};
 
-use crate::sandbox::{Sandbox, SandboxId, SandboxState};
+use crate::checkpoint::{CheckpointError, CheckpointId, CheckpointManager};
+use crate::resources::{ResourceClass, ResourceLimits};
+use crate::sandbox::{Sandbox, SandboxBackend, SandboxConfig, SandboxId, SandboxState};
 
 /// Configuration for the warm pool.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +40,15 @@ pub struct PoolConfig {
 
     /// Supported runtimes with their configs.
     pub runtimes: Vec<RuntimePoolConfig>,
+
+    /// Ceiling on the limits of sandboxes restored from checkpoints. Unset
+    /// accepts any limits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_limits: Option<ResourceLimits>,
+
+    /// Health probing of pooled sandboxes.
+    #[serde(default)]
+    pub health: PoolHealthConfig,
 }
 
 impl Default for PoolConfig {
@@ -48,7 +70,122 @@ impl Default for PoolConfig {
                     max_warm: 3,
                 },
             ],
+            max_limits: None,
+            health: PoolHealthConfig::default(),
+        }
+    }
+}
+
+/// Health probing of pooled sandboxes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolHealthConfig {
+    /// Probe a sandbox before handing it out. Adds a probe to every pool
+    /// hit, so it is off by default and the periodic sweep is relied on.
+    pub probe_on_acquire: bool,
+
+    /// How often the health sweep runs (seconds).
+    pub sweep_interval_seconds: u64,
+
+    /// Consecutive failed probes after which a sandbox is evicted.
+    pub eviction_threshold: u32,
+
+    /// Share of pooled sandboxes failing probes above which the pool
+    /// reports itself degraded.
+    pub max_unhealthy_ratio: f64,
+}
+
+impl Default for PoolHealthConfig {
+    fn default() -> Self {
+        Self {
+            probe_on_acquire: false,
+            sweep_interval_seconds: 30,
+            eviction_threshold: 2,
+            max_unhealthy_ratio: 0.25,
+        }
+    }
+}
+
+/// Checks that a pooled sandbox still works.
+#[async_trait]
+pub trait HealthProbe: Send + Sync {
+    /// Probe a sandbox. An error marks it unhealthy.
+    async fn probe(&self, sandbox: &Sandbox) -> CretoResult<()>;
+}
+
+/// Probe that asks the sandbox backend for the sandbox's state.
+///
+/// A sandbox is healthy if the backend still reports it ready.
+pub struct BackendHealthProbe {
+    backend: Arc<dyn SandboxBackend>,
+}
+
+impl BackendHealthProbe {
+    /// Create a probe checking sandboxes with `backend`.
+    pub fn new(backend: Arc<dyn SandboxBackend>) -> Self {
+        Self { backend }
+    }
+}
+
+#[async_trait]
+impl HealthProbe for BackendHealthProbe {
+    async fn probe(&self, sandbox: &Sandbox) -> CretoResult<()> {
+        let handle = sandbox
+            .runtime_handle
+            .as_deref()
+            .ok_or_else(|| CretoError::SandboxNotFound(sandbox.id.to_string()))?;
+        match self.backend.status(handle).await? {
+            SandboxState::Ready => Ok(()),
+            state => Err(CretoError::Internal(format!(
+                "Pooled sandbox {} is {state:?}",
+                sandbox.id
+            ))),
+        }
+    }
+}
+
+/// What a health sweep found.
+#[derive(Debug, Clone, Default)]
+pub struct PoolHealthReport {
+    /// Sandboxes probed.
+    pub probed: usize,
+    /// Sandboxes that failed their probe.
+    pub unhealthy: Vec<SandboxId>,
+    /// Sandboxes that passed a probe after failing one, and are ready
+    /// again.
+    pub recovered: Vec<SandboxId>,
+    /// Sandboxes evicted since the last sweep, including by acquire-time
+    /// probes. They are out of the pool; the caller destroys them.
+    pub evicted: Vec<Sandbox>,
+    /// Replacement sandboxes provisioned, when the runtime service ran the
+    /// sweep.
+    pub replaced: usize,
+}
+
+/// Health of the pool as a whole.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolHealth {
+    /// Sandboxes in the pool.
+    pub total: usize,
+    /// Sandboxes whose last probe failed.
+    pub unhealthy: usize,
+    /// `unhealthy` as a share of `total`.
+    pub unhealthy_ratio: f64,
+    /// Sandboxes evicted for failing probes.
+    pub evictions: u64,
+    /// Whether the unhealthy ratio is within the configured maximum.
+    pub healthy: bool,
+}
+
+impl PoolHealth {
+    /// Health check response for the pool: `healthy`, or `degraded` when
+    /// too many sandboxes fail probes.
+    pub fn to_health_response(&self) -> HealthResponse {
+        let mut response = health_check();
+        if !self.healthy {
+            response.status = "degraded".to_string();
         }
+        response
     }
 }
 
@@ -78,8 +215,14 @@ pub struct PoolStats {
     pub misses: u64,
     /// Sandboxes evicted due to idle timeout.
     pub evictions: u64,
+    /// Sandboxes evicted for failing health probes.
+    #[serde(default)]
+    pub health_evictions: u64,
     /// Per-runtime breakdown.
     pub by_runtime: HashMap<String, RuntimePoolStats>,
+    /// Per-resource-class breakdown, keyed by class label (`cpu`, `gpu`).
+    #[serde(default)]
+    pub by_class: HashMap<String, RuntimePoolStats>,
 }
 
 /// Per-runtime statistics.
@@ -91,17 +234,131 @@ pub struct RuntimePoolStats {
     pub in_use: usize,
 }
 
+/// How [`WarmPool::restore_from_checkpoint`] rebuilds a sandbox.
+#[derive(Debug, Clone)]
+pub struct RestoreConfig {
+    /// Organization owning the sandbox.
+    pub organization_id: OrganizationId,
+    /// Configuration the sandbox ran with when it was checkpointed.
+    pub sandbox: SandboxConfig,
+    /// Park the sandbox in the pool under its original ID instead of
+    /// handing it back.
+    pub park: bool,
+}
+
+/// A sandbox rebuilt from a checkpoint.
+#[derive(Debug, Clone)]
+pub struct RestoredSandbox {
+    /// The sandbox, paused, under its original ID.
+    pub sandbox: Sandbox,
+    /// Decompressed runtime state to resume it from.
+    pub state_snapshot: Vec<u8>,
+}
+
+/// Where a restored sandbox went.
+#[derive(Debug, Clone)]
+pub enum RestoreOutcome {
+    /// Parked in the pool; claim it with [`WarmPool::acquire_restored`].
+    Parked(SandboxId),
+    /// Handed back to the caller.
+    Detached(Box<RestoredSandbox>),
+}
+
 /// Warm pool for pre-initialized sandboxes.
 ///
 /// Maintains a pool of ready-to-use sandboxes to minimize cold start latency.
 pub struct WarmPool {
     config: PoolConfig,
+    /// Store checkpoints are restored from.
+    checkpoint_store: Option<Arc<dyn CheckpointManager>>,
     /// Sandboxes indexed by ID.
     sandboxes: Arc<RwLock<HashMap<SandboxId, PooledSandbox>>>,
-    /// Ready sandboxes by runtime (for fast acquisition).
-    ready_by_runtime: Arc<RwLock<HashMap<String, Vec<SandboxId>>>>,
+    /// Ready sandboxes by runtime and resource class (for fast acquisition).
+    ready_by_runtime: Arc<RwLock<HashMap<PoolKey, Vec<SandboxId>>>>,
     /// Statistics.
     stats: Arc<RwLock<PoolStats>>,
+    /// Probe run by health sweeps (and on acquire, if configured).
+    health_probe: Option<Arc<dyn HealthProbe>>,
+    /// Sandboxes evicted for failing probes, until the next sweep returns
+    /// them.
+    evicted: Arc<Mutex<Vec<Sandbox>>>,
+    /// Exported pool size and cold start metrics (None = not exported).
+    metrics: Option<PoolMetrics>,
+}
+
+/// Pool metrics exported to a [`MetricsRegistry`].
+struct PoolMetrics {
+    size: Gauge,
+    idle: Gauge,
+    in_use: Gauge,
+    cold_starts: CounterVec,
+}
+
+impl PoolMetrics {
+    fn new(registry: &MetricsRegistry) -> Self {
+        Self {
+            size: registry.gauge("creto_runtime_pool_size", "Sandboxes in the warm pool"),
+            idle: registry.gauge(
+                "creto_runtime_pool_idle_sandboxes",
+                "Warm sandboxes ready to be acquired",
+            ),
+            in_use: registry.gauge(
+                "creto_runtime_pool_in_use_sandboxes",
+                "Pooled sandboxes currently acquired",
+            ),
+            cold_starts: registry.counter_vec(
+                "creto_runtime_pool_cold_starts_total",
+                "Acquires the pool could not serve, so a sandbox had to be cold started",
+                &["runtime"],
+            ),
+        }
+    }
+
+    fn publish(&self, stats: &PoolStats) {
+        self.size.set(stats.total as i64);
+        self.idle.set(stats.ready as i64);
+        self.in_use.set(stats.in_use as i64);
+    }
+}
+
+/// Write access to the pool statistics that publishes them to the exported
+/// gauges when released.
+struct StatsGuard<'a> {
+    stats: RwLockWriteGuard<'a, PoolStats>,
+    metrics: Option<&'a PoolMetrics>,
+}
+
+impl std::ops::Deref for StatsGuard<'_> {
+    type Target = PoolStats;
+
+    fn deref(&self) -> &PoolStats {
+        &self.stats
+    }
+}
+
+impl std::ops::DerefMut for StatsGuard<'_> {
+    fn deref_mut(&mut self) -> &mut PoolStats {
+        &mut self.stats
+    }
+}
+
+impl Drop for StatsGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(metrics) = self.metrics {
+            metrics.publish(&self.stats);
+        }
+    }
+}
+
+/// Ready-list partition: sandboxes are only reused for the same runtime and
+/// resource class, so a CPU request never takes a GPU sandbox (or vice versa).
+type PoolKey = (String, ResourceClass);
+
+fn pool_key(sandbox: &Sandbox) -> PoolKey {
+    (
+        sandbox.config.runtime.clone(),
+        sandbox.config.resource_class(),
+    )
 }
 
 /// A sandbox in the pool with metadata.
@@ -109,6 +366,36 @@ struct PooledSandbox {
     sandbox: Sandbox,
     acquired: bool,
     acquired_at: Option<DateTime<Utc>>,
+    /// Runtime state of a sandbox parked by a checkpoint restore, until it
+    /// is claimed.
+    restored_state: Option<Vec<u8>>,
+    /// Consecutive failed health probes. A sandbox with failures is kept
+    /// off the ready list.
+    probe_failures: u32,
+}
+
+/// How a probe result changed a sandbox.
+enum ProbeOutcome {
+    Healthy,
+    Recovered,
+    Unhealthy,
+    Evicted,
+    /// The sandbox was checked out or removed while it was probed.
+    Stale,
+}
+
+/// Move `ready` and `in_use` counts of a partition by the given amounts.
+fn shift_counts(stats: &mut PoolStats, key: &PoolKey, ready: isize, in_use: isize) {
+    stats.ready = stats.ready.saturating_add_signed(ready);
+    stats.in_use = stats.in_use.saturating_add_signed(in_use);
+    if let Some(runtime_stats) = stats.by_runtime.get_mut(&key.0) {
+        runtime_stats.ready = runtime_stats.ready.saturating_add_signed(ready);
+        runtime_stats.in_use = runtime_stats.in_use.saturating_add_signed(in_use);
+    }
+    if let Some(class_stats) = stats.by_class.get_mut(key.1.label()) {
+        class_stats.ready = class_stats.ready.saturating_add_signed(ready);
+        class_stats.in_use = class_stats.in_use.saturating_add_signed(in_use);
+    }
 }
 
 impl WarmPool {
@@ -116,12 +403,45 @@ impl WarmPool {
     pub fn new(config: PoolConfig) -> Self {
         Self {
             config,
+            checkpoint_store: None,
             sandboxes: Arc::new(RwLock::new(HashMap::new())),
             ready_by_runtime: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(PoolStats::default())),
+            health_probe: None,
+            evicted: Arc::new(Mutex::new(Vec::new())),
+            metrics: None,
         }
     }
 
+    /// Set the probe health sweeps run.
+    pub fn with_health_probe(mut self, probe: Arc<dyn HealthProbe>) -> Self {
+        self.health_probe = Some(probe);
+        self
+    }
+
+    /// Set the store checkpoints are restored from.
+    pub fn with_checkpoint_store(mut self, store: Arc<dyn CheckpointManager>) -> Self {
+        self.checkpoint_store = Some(store);
+        self
+    }
+
+    /// Export pool size, idle and in-use gauges and cold starts (acquires
+    /// the pool missed, by runtime) to `registry`.
+    pub fn with_metrics(mut self, registry: &MetricsRegistry) -> Self {
+        self.metrics = Some(PoolMetrics::new(registry));
+        self
+    }
+
+    /// Pool configuration.
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    /// Store checkpoints are restored from, if one is set.
+    pub fn checkpoint_store(&self) -> Option<&Arc<dyn CheckpointManager>> {
+        self.checkpoint_store.as_ref()
+    }
+
     /// Initialize the pool (pre-warm sandboxes).
     pub async fn initialize(&self) -> CretoResult<()> {
         // TODO: Pre-create sandboxes based on config
@@ -137,43 +457,100 @@ impl WarmPool {
         Ok(())
     }
 
-    /// Acquire a sandbox from the pool.
+    /// Acquire a CPU-only sandbox from the pool.
     ///
     /// Returns a ready sandbox if available, or None if pool is empty.
     pub async fn acquire(&self, runtime: &str) -> Option<Sandbox> {
-        let mut ready_map = self.ready_by_runtime.write().await;
-        let mut sandboxes = self.sandboxes.write().await;
-        let mut stats = self.stats.write().await;
-
-        // Try to get a ready sandbox for this runtime
-        if let Some(ready_list) = ready_map.get_mut(runtime) {
-            if let Some(sandbox_id) = ready_list.pop() {
-                if let Some(pooled) = sandboxes.get_mut(&sandbox_id) {
-                    pooled.acquired = true;
-                    pooled.acquired_at = Some(Utc::now());
-                    stats.hits += 1;
-                    stats.ready -= 1;
-                    stats.in_use += 1;
-
-                    if let Some(runtime_stats) = stats.by_runtime.get_mut(runtime) {
-                        runtime_stats.ready -= 1;
-                        runtime_stats.in_use += 1;
-                    }
+        self.acquire_for(runtime, &ResourceClass::Cpu).await
+    }
+
+    /// Acquire a sandbox of the given resource class from the pool.
+    ///
+    /// Only sandboxes created with the same runtime and class are handed out.
+    /// A sandbox that failed its last health probe never is. With
+    /// [`PoolHealthConfig::probe_on_acquire`] set, each candidate is probed
+    /// first and one that fails is passed over.
+    pub async fn acquire_for(&self, runtime: &str, class: &ResourceClass) -> Option<Sandbox> {
+        self.acquire_where(runtime, class, |_| true).await
+    }
 
-                    return Some(pooled.sandbox.clone());
+    /// Acquire a sandbox created with exactly `limits`.
+    ///
+    /// A pooled sandbox runs under the limits it was created with, so one
+    /// with any other limits is left in the pool.
+    pub async fn acquire_with_limits(
+        &self,
+        runtime: &str,
+        limits: &ResourceLimits,
+    ) -> Option<Sandbox> {
+        self.acquire_where(runtime, &limits.resource_class(), |sandbox| {
+            sandbox.config.effective_limits() == *limits
+        })
+        .await
+    }
+
+    async fn acquire_where(
+        &self,
+        runtime: &str,
+        class: &ResourceClass,
+        accept: impl Fn(&Sandbox) -> bool,
+    ) -> Option<Sandbox> {
+        let key = (runtime.to_string(), class.clone());
+        let probe = self
+            .health_probe
+            .as_ref()
+            .filter(|_| self.config.health.probe_on_acquire);
+        loop {
+            let Some(sandbox) = self.checkout(&key, &accept).await else {
+                self.stats.write().await.misses += 1;
+                if let Some(metrics) = &self.metrics {
+                    metrics.cold_starts.with_label_values(&[runtime]).inc();
+                }
+                return None;
+            };
+            if let Some(probe) = probe {
+                if let Err(e) = probe.probe(&sandbox).await {
+                    tracing::warn!(
+                        sandbox_id = %sandbox.id,
+                        error = %e,
+                        "Pooled sandbox failed its health probe on acquire"
+                    );
+                    self.record_probe(sandbox.id, false, true).await;
+                    continue;
                 }
             }
+            self.stats.write().await.hits += 1;
+            return Some(sandbox);
         }
+    }
 
-        stats.misses += 1;
-        None
+    /// Take the newest ready sandbox `accept` allows off the ready list and
+    /// mark it acquired.
+    async fn checkout(&self, key: &PoolKey, accept: impl Fn(&Sandbox) -> bool) -> Option<Sandbox> {
+        // Same lock order as release/add/remove, or concurrent callers deadlock
+        let mut sandboxes = self.sandboxes.write().await;
+        let mut ready_map = self.ready_by_runtime.write().await;
+        let mut stats = self.stats_mut().await;
+
+        let ready = ready_map.get_mut(key)?;
+        let position = ready.iter().rposition(|id| {
+            sandboxes
+                .get(id)
+                .is_some_and(|pooled| accept(&pooled.sandbox))
+        })?;
+        let sandbox_id = ready.remove(position);
+        let pooled = sandboxes.get_mut(&sandbox_id)?;
+        pooled.acquired = true;
+        pooled.acquired_at = Some(Utc::now());
+        shift_counts(&mut stats, key, -1, 1);
+        Some(pooled.sandbox.clone())
     }
 
     /// Release a sandbox back to the pool.
     pub async fn release(&self, sandbox_id: SandboxId) -> CretoResult<()> {
         let mut sandboxes = self.sandboxes.write().await;
         let mut ready_map = self.ready_by_runtime.write().await;
-        let mut stats = self.stats.write().await;
+        let mut stats = self.stats_mut().await;
 
         if let Some(pooled) = sandboxes.get_mut(&sandbox_id) {
             if pooled.acquired {
@@ -181,21 +558,23 @@ impl WarmPool {
                 pooled.acquired_at = None;
                 pooled.sandbox.last_used_at = Some(Utc::now());
 
-                let runtime = pooled.sandbox.config.runtime.clone();
-
-                // Add back to ready list
-                ready_map
-                    .entry(runtime.clone())
-                    .or_default()
-                    .push(sandbox_id);
+                let key = pool_key(&pooled.sandbox);
+                let class_label = key.1.label();
 
                 stats.in_use -= 1;
                 stats.ready += 1;
 
-                if let Some(runtime_stats) = stats.by_runtime.get_mut(&runtime) {
+                if let Some(runtime_stats) = stats.by_runtime.get_mut(&key.0) {
                     runtime_stats.in_use -= 1;
                     runtime_stats.ready += 1;
                 }
+                if let Some(class_stats) = stats.by_class.get_mut(class_label) {
+                    class_stats.in_use -= 1;
+                    class_stats.ready += 1;
+                }
+
+                // Add back to ready list
+                ready_map.entry(key).or_default().push(sandbox_id);
             }
         }
 
@@ -205,11 +584,133 @@ impl WarmPool {
     /// Add a new sandbox to the pool.
     pub async fn add(&self, sandbox: Sandbox) -> CretoResult<()> {
         let mut sandboxes = self.sandboxes.write().await;
+        self.insert(&mut sandboxes, sandbox, None).await;
+        Ok(())
+    }
+
+    /// Rebuild a checkpointed sandbox, paused, under its original ID.
+    ///
+    /// The checkpoint's full snapshot is resolved from the store (through
+    /// its parents, if it is incremental) and the sandbox's limits are
+    /// checked against the pool's current [`PoolConfig::max_limits`].
+    /// Fails with [`CheckpointError::SandboxStillExists`] if the sandbox is
+    /// still in the pool, rather than creating a second copy. Sandboxes live
+    /// outside the pool are only caught by
+    /// [`RuntimeService::restore_to_pool`](crate::RuntimeService::restore_to_pool).
+    pub async fn restore_from_checkpoint(
+        &self,
+        checkpoint_id: CheckpointId,
+        config: RestoreConfig,
+    ) -> Result<RestoreOutcome, CheckpointError> {
+        let store =
+            self.checkpoint_store
+                .as_ref()
+                .ok_or_else(|| CheckpointError::StorageError {
+                    message: "No checkpoint store configured".to_string(),
+                })?;
+        let restore_failed = |e: CretoError| CheckpointError::RestoreFailed {
+            checkpoint_id,
+            reason: e.to_string(),
+        };
+        let checkpoint = store
+            .get_checkpoint(checkpoint_id)
+            .await
+            .map_err(restore_failed)?;
+
+        if let Some(ceiling) = &self.config.max_limits {
+            if !config.sandbox.effective_limits().fits_within(ceiling) {
+                return Err(CheckpointError::RestoreFailed {
+                    checkpoint_id,
+                    reason: "Sandbox limits exceed the pool's current limits".to_string(),
+                });
+            }
+        }
+        let state_snapshot = store
+            .resolve_snapshot(checkpoint_id)
+            .await
+            .map_err(restore_failed)?;
+
+        let mut sandbox = Sandbox::new(config.organization_id, checkpoint.agent_id, config.sandbox);
+        sandbox.id = checkpoint.sandbox_id;
+        sandbox.state = SandboxState::Paused;
+
+        // Held across the check and the insert so concurrent restores of
+        // the same checkpoint cannot both succeed
+        let mut sandboxes = self.sandboxes.write().await;
+        if sandboxes.contains_key(&sandbox.id) {
+            return Err(CheckpointError::SandboxStillExists {
+                checkpoint_id,
+                sandbox_id: sandbox.id,
+            });
+        }
+
+        tracing::info!(
+            checkpoint_id = %checkpoint_id,
+            sandbox_id = %sandbox.id,
+            parked = config.park,
+            "Restored sandbox from checkpoint"
+        );
+
+        if !config.park {
+            return Ok(RestoreOutcome::Detached(Box::new(RestoredSandbox {
+                sandbox,
+                state_snapshot,
+            })));
+        }
+        let sandbox_id = sandbox.id;
+        self.insert(&mut sandboxes, sandbox, Some(state_snapshot))
+            .await;
+        Ok(RestoreOutcome::Parked(sandbox_id))
+    }
+
+    /// Claim a sandbox parked by [`WarmPool::restore_from_checkpoint`].
+    ///
+    /// Parked sandboxes are never handed out by [`WarmPool::acquire`]; only
+    /// their original ID claims them. The pool's copy is marked ready, as
+    /// the claimant resumes it.
+    pub async fn acquire_restored(&self, sandbox_id: SandboxId) -> Option<RestoredSandbox> {
+        let mut sandboxes = self.sandboxes.write().await;
+        let mut stats = self.stats_mut().await;
+
+        let pooled = sandboxes
+            .get_mut(&sandbox_id)
+            .filter(|pooled| !pooled.acquired)?;
+        let state_snapshot = pooled.restored_state.take()?;
+        let restored = RestoredSandbox {
+            sandbox: pooled.sandbox.clone(),
+            state_snapshot,
+        };
+        pooled.acquired = true;
+        pooled.acquired_at = Some(Utc::now());
+        pooled.sandbox.state = SandboxState::Ready;
+
+        let (runtime, class) = pool_key(&pooled.sandbox);
+        stats.hits += 1;
+        stats.in_use += 1;
+        if let Some(runtime_stats) = stats.by_runtime.get_mut(&runtime) {
+            runtime_stats.in_use += 1;
+        }
+        if let Some(class_stats) = stats.by_class.get_mut(class.label()) {
+            class_stats.in_use += 1;
+        }
+
+        Some(restored)
+    }
+
+    /// Insert a sandbox, listing it as ready if it is.
+    async fn insert(
+        &self,
+        sandboxes: &mut HashMap<SandboxId, PooledSandbox>,
+        sandbox: Sandbox,
+        restored_state: Option<Vec<u8>>,
+    ) {
         let mut ready_map = self.ready_by_runtime.write().await;
-        let mut stats = self.stats.write().await;
+        let mut stats = self.stats_mut().await;
 
         let sandbox_id = sandbox.id;
-        let runtime = sandbox.config.runtime.clone();
+        let key = pool_key(&sandbox);
+        let runtime = key.0.clone();
+        let class_label = key.1.label();
         let is_ready = sandbox.state == SandboxState::Ready;
 
         sandboxes.insert(
@@ -218,14 +719,13 @@ impl WarmPool {
                 sandbox,
                 acquired: false,
                 acquired_at: None,
+                restored_state,
+                probe_failures: 0,
             },
         );
 
         if is_ready {
-            ready_map
-                .entry(runtime.clone())
-                .or_default()
-                .push(sandbox_id);
+            ready_map.entry(key).or_default().push(sandbox_id);
             stats.ready += 1;
         }
 
@@ -235,38 +735,52 @@ impl WarmPool {
         if is_ready {
             runtime_stats.ready += 1;
         }
-
-        Ok(())
+        let class_stats = stats.by_class.entry(class_label.to_string()).or_default();
+        if is_ready {
+            class_stats.ready += 1;
+        }
     }
 
     /// Remove a sandbox from the pool.
     pub async fn remove(&self, sandbox_id: SandboxId) -> Option<Sandbox> {
         let mut sandboxes = self.sandboxes.write().await;
         let mut ready_map = self.ready_by_runtime.write().await;
-        let mut stats = self.stats.write().await;
+        let mut stats = self.stats_mut().await;
 
         if let Some(pooled) = sandboxes.remove(&sandbox_id) {
-            let runtime = &pooled.sandbox.config.runtime;
+            let key = pool_key(&pooled.sandbox);
+            let runtime = &key.0;
 
-            // Remove from ready list if present
-            if let Some(ready_list) = ready_map.get_mut(runtime) {
+            // Remove from ready list if present. Sandboxes parked by a
+            // restore are neither ready nor in use.
+            let mut was_ready = false;
+            if let Some(ready_list) = ready_map.get_mut(&key) {
+                let before = ready_list.len();
                 ready_list.retain(|id| *id != sandbox_id);
+                was_ready = ready_list.len() < before;
             }
 
             stats.total -= 1;
             if pooled.acquired {
                 stats.in_use -= 1;
-            } else {
+            } else if was_ready {
                 stats.ready -= 1;
             }
 
             if let Some(runtime_stats) = stats.by_runtime.get_mut(runtime) {
                 if pooled.acquired {
                     runtime_stats.in_use -= 1;
-                } else {
+                } else if was_ready {
                     runtime_stats.ready -= 1;
                 }
             }
+            if let Some(class_stats) = stats.by_class.get_mut(key.1.label()) {
+                if pooled.acquired {
+                    class_stats.in_use -= 1;
+                } else if was_ready {
+                    class_stats.ready -= 1;
+                }
+            }
 
             return Some(pooled.sandbox);
         }
@@ -279,6 +793,145 @@ impl WarmPool {
         self.stats.read().await.clone()
     }
 
+    /// Lock the statistics for an update that changes pool membership.
+    async fn stats_mut(&self) -> StatsGuard<'_> {
+        StatsGuard {
+            stats: self.stats.write().await,
+            metrics: self.metrics.as_ref(),
+        }
+    }
+
+    /// Probe every idle sandbox in the pool.
+    ///
+    /// Sandboxes parked by a checkpoint restore are not probed. Does nothing
+    /// without a [`HealthProbe`].
+    pub async fn check_health(&self) -> PoolHealthReport {
+        let mut report = PoolHealthReport::default();
+        let Some(probe) = &self.health_probe else {
+            return report;
+        };
+
+        // Probes run without the pool locked, so acquires are not held up
+        let candidates: Vec<Sandbox> = self
+            .sandboxes
+            .read()
+            .await
+            .values()
+            .filter(|pooled| !pooled.acquired && pooled.restored_state.is_none())
+            .map(|pooled| pooled.sandbox.clone())
+            .collect();
+        for sandbox in candidates {
+            let result = probe.probe(&sandbox).await;
+            if let Err(e) = &result {
+                tracing::warn!(
+                    sandbox_id = %sandbox.id,
+                    error = %e,
+                    "Pooled sandbox failed its health probe"
+                );
+            }
+            match self.record_probe(sandbox.id, result.is_ok(), false).await {
+                ProbeOutcome::Stale => continue,
+                ProbeOutcome::Healthy => {}
+                ProbeOutcome::Recovered => report.recovered.push(sandbox.id),
+                ProbeOutcome::Unhealthy | ProbeOutcome::Evicted => {
+                    report.unhealthy.push(sandbox.id)
+                }
+            }
+            report.probed += 1;
+        }
+
+        report.evicted = std::mem::take(&mut *self.evicted.lock().unwrap());
+        report
+    }
+
+    /// Apply a probe result to a sandbox.
+    ///
+    /// `checked_out` is set for acquire-time probes, where the prober holds
+    /// the sandbox; a sweep result for a sandbox acquired meanwhile is
+    /// stale.
+    async fn record_probe(
+        &self,
+        sandbox_id: SandboxId,
+        healthy: bool,
+        checked_out: bool,
+    ) -> ProbeOutcome {
+        let mut sandboxes = self.sandboxes.write().await;
+        let mut ready_map = self.ready_by_runtime.write().await;
+        let mut stats = self.stats_mut().await;
+
+        let Some(pooled) = sandboxes.get_mut(&sandbox_id) else {
+            return ProbeOutcome::Stale;
+        };
+        if pooled.acquired != checked_out {
+            return ProbeOutcome::Stale;
+        }
+        let key = pool_key(&pooled.sandbox);
+
+        if healthy {
+            if pooled.probe_failures == 0 {
+                return ProbeOutcome::Healthy;
+            }
+            pooled.probe_failures = 0;
+            if pooled.sandbox.state == SandboxState::Ready {
+                ready_map.entry(key.clone()).or_default().push(sandbox_id);
+                shift_counts(&mut stats, &key, 1, 0);
+            }
+            return ProbeOutcome::Recovered;
+        }
+
+        pooled.probe_failures += 1;
+        let failures = pooled.probe_failures;
+        if pooled.acquired {
+            pooled.acquired = false;
+            pooled.acquired_at = None;
+            shift_counts(&mut stats, &key, 0, -1);
+        } else if let Some(ready_list) = ready_map.get_mut(&key) {
+            let before = ready_list.len();
+            ready_list.retain(|id| *id != sandbox_id);
+            if ready_list.len() < before {
+                shift_counts(&mut stats, &key, -1, 0);
+            }
+        }
+
+        if failures < self.config.health.eviction_threshold.max(1) {
+            return ProbeOutcome::Unhealthy;
+        }
+        let Some(pooled) = sandboxes.remove(&sandbox_id) else {
+            return ProbeOutcome::Stale;
+        };
+        stats.total -= 1;
+        stats.health_evictions += 1;
+        tracing::warn!(
+            sandbox_id = %sandbox_id,
+            failures,
+            "Evicting pooled sandbox after repeated health probe failures"
+        );
+        self.evicted.lock().unwrap().push(pooled.sandbox);
+        ProbeOutcome::Evicted
+    }
+
+    /// Current health of the pool.
+    pub async fn pool_health(&self) -> PoolHealth {
+        let sandboxes = self.sandboxes.read().await;
+        let total = sandboxes.len();
+        let unhealthy = sandboxes
+            .values()
+            .filter(|pooled| pooled.probe_failures > 0)
+            .count();
+        let unhealthy_ratio = if total == 0 {
+            0.0
+        } else {
+            unhealthy as f64 / total as f64
+        };
+        PoolHealth {
+            total,
+            unhealthy,
+            unhealthy_ratio,
+            evictions: self.stats.read().await.health_evictions,
+            healthy: unhealthy_ratio <= self.config.health.max_unhealthy_ratio,
+        }
+    }
+
     /// Cleanup idle sandboxes.
     pub async fn cleanup_idle(&self) -> CretoResult<Vec<SandboxId>> {
         let sandboxes = self.sandboxes.read().await;
@@ -366,4 +1019,441 @@ mod tests {
         let stats = pool.stats().await;
         assert_eq!(stats.misses, 1);
     }
+
+    #[tokio::test]
+    async fn test_pool_metrics() {
+        use creto_common::{AgentId, OrganizationId};
+
+        let registry = MetricsRegistry::new();
+        let pool = WarmPool::new(PoolConfig::default()).with_metrics(&registry);
+        for _ in 0..2 {
+            let mut sandbox = Sandbox::new(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig {
+                    runtime: "python3.11".to_string(),
+                    ..Default::default()
+                },
+            );
+            sandbox.mark_ready("handle".to_string());
+            pool.add(sandbox).await.unwrap();
+        }
+
+        assert!(pool.acquire("python3.11").await.is_some());
+        assert!(pool.acquire("node20").await.is_none());
+
+        let text = registry.render_metrics();
+        assert!(text.contains("# TYPE creto_runtime_pool_size gauge\ncreto_runtime_pool_size 2\n"));
+        assert!(text.contains("creto_runtime_pool_idle_sandboxes 1\n"));
+        assert!(text.contains("creto_runtime_pool_in_use_sandboxes 1\n"));
+        assert!(text.contains("creto_runtime_pool_cold_starts_total{runtime=\"node20\"} 1\n"));
+        assert!(!text.contains("runtime=\"python3.11\""));
+    }
+
+    #[tokio::test]
+    async fn test_checkout_matches_resource_class() {
+        use crate::resources::ResourceLimits;
+        use creto_common::{AgentId, OrganizationId};
+
+        let pool = WarmPool::new(PoolConfig::default());
+
+        let mut cpu = Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig::default(),
+        );
+        cpu.mark_ready("cpu".to_string());
+        let cpu_id = cpu.id;
+        pool.add(cpu).await.unwrap();
+
+        let gpu_class = ResourceClass::Gpu {
+            count: 1,
+            kind: Some("a100".to_string()),
+        };
+        let mut gpu = Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig {
+                limits: Some(
+                    ResourceLimits::default()
+                        .with_gpus(1, 40 * 1024 * 1024 * 1024)
+                        .with_gpu_kind("a100"),
+                ),
+                ..Default::default()
+            },
+        );
+        gpu.mark_ready("gpu".to_string());
+        let gpu_id = gpu.id;
+        pool.add(gpu).await.unwrap();
+
+        // A different GPU model is a different partition.
+        let other = ResourceClass::Gpu {
+            count: 1,
+            kind: Some("h100".to_string()),
+        };
+        assert!(pool.acquire_for("python3.11", &other).await.is_none());
+
+        let acquired = pool.acquire_for("python3.11", &gpu_class).await.unwrap();
+        assert_eq!(acquired.id, gpu_id);
+        assert!(pool.acquire_for("python3.11", &gpu_class).await.is_none());
+
+        let acquired = pool.acquire("python3.11").await.unwrap();
+        assert_eq!(acquired.id, cpu_id);
+
+        let stats = pool.stats().await;
+        assert_eq!(stats.by_class["gpu"].in_use, 1);
+        assert_eq!(stats.by_class["cpu"].in_use, 1);
+
+        pool.release(gpu_id).await.unwrap();
+        let stats = pool.stats().await;
+        assert_eq!(stats.by_class["gpu"].ready, 1);
+        assert_eq!(stats.by_class["gpu"].in_use, 0);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_with_limits_skips_other_limits() {
+        use creto_common::{AgentId, OrganizationId};
+
+        let pool = WarmPool::new(PoolConfig::default());
+        let mut defaults = Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig::default(),
+        );
+        defaults.mark_ready("defaults".to_string());
+        let defaults_id = defaults.id;
+        pool.add(defaults).await.unwrap();
+
+        let mut minimal = Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig {
+                limits: Some(ResourceLimits::minimal()),
+                ..Default::default()
+            },
+        );
+        minimal.mark_ready("minimal".to_string());
+        let minimal_id = minimal.id;
+        pool.add(minimal).await.unwrap();
+
+        // The newest sandbox does not match, so the older one is taken
+        let acquired = pool
+            .acquire_with_limits("python3.11", &ResourceLimits::default())
+            .await
+            .unwrap();
+        assert_eq!(acquired.id, defaults_id);
+        assert!(pool
+            .acquire_with_limits("python3.11", &ResourceLimits::generous())
+            .await
+            .is_none());
+
+        let acquired = pool
+            .acquire_with_limits("python3.11", &ResourceLimits::minimal())
+            .await
+            .unwrap();
+        assert_eq!(acquired.id, minimal_id);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_acquire_and_release() {
+        use creto_common::{AgentId, OrganizationId};
+
+        let pool = Arc::new(WarmPool::new(PoolConfig::default()));
+        for i in 0..4 {
+            let mut sandbox = Sandbox::new(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            );
+            sandbox.mark_ready(format!("handle_{i}"));
+            pool.add(sandbox).await.unwrap();
+        }
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let pool = Arc::clone(&pool);
+                tokio::spawn(async move {
+                    for _ in 0..500 {
+                        if let Some(sandbox) = pool.acquire("python3.11").await {
+                            pool.release(sandbox.id).await.unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+        let all = async {
+            for task in tasks {
+                task.await.unwrap();
+            }
+        };
+        tokio::time::timeout(std::time::Duration::from_secs(10), all)
+            .await
+            .expect("acquire and release deadlocked");
+
+        let stats = pool.stats().await;
+        assert_eq!((stats.ready, stats.in_use), (4, 0));
+    }
+
+    fn restore_config(sandbox: &Sandbox, park: bool) -> RestoreConfig {
+        RestoreConfig {
+            organization_id: sandbox.organization_id,
+            sandbox: sandbox.config.clone(),
+            park,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_terminate_restore_execute() {
+        use crate::checkpoint::{CheckpointConfig, CompressionAlgorithm, InMemoryCheckpointStore};
+        use creto_common::{AgentId, OrganizationId};
+
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let pool = WarmPool::new(PoolConfig::default()).with_checkpoint_store(store.clone());
+
+        let mut sandbox = Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig::default(),
+        );
+        sandbox.mark_ready("handle".to_string());
+        let sandbox_id = sandbox.id;
+        pool.add(sandbox.clone()).await.unwrap();
+
+        let config = CheckpointConfig {
+            compression: CompressionAlgorithm::Zstd,
+            ..Default::default()
+        };
+        let checkpoint_id = store.checkpoint(sandbox_id, config).await.unwrap();
+        let checkpoint = store.get_checkpoint(checkpoint_id).await.unwrap();
+
+        // Still pooled, so a restore would duplicate it
+        let err = pool
+            .restore_from_checkpoint(checkpoint_id, restore_config(&sandbox, true))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CheckpointError::SandboxStillExists { .. }));
+
+        let mut terminated = pool.remove(sandbox_id).await.unwrap();
+        terminated.mark_terminated();
+        store.sandbox_ended(sandbox_id);
+
+        let outcome = pool
+            .restore_from_checkpoint(checkpoint_id, restore_config(&sandbox, true))
+            .await
+            .unwrap();
+        assert!(matches!(outcome, RestoreOutcome::Parked(id) if id == sandbox_id));
+        let err = pool
+            .restore_from_checkpoint(checkpoint_id, restore_config(&sandbox, true))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "ENABLE-508");
+
+        // Parked sandboxes only go to their original ID
+        assert!(pool.acquire("python3.11").await.is_none());
+        let mut restored = pool.acquire_restored(sandbox_id).await.unwrap();
+        assert!(pool.acquire_restored(sandbox_id).await.is_none());
+        assert_eq!(restored.sandbox.id, sandbox_id);
+        assert_eq!(restored.sandbox.agent_id, checkpoint.agent_id);
+        assert_eq!(restored.sandbox.state, SandboxState::Paused);
+        assert_eq!(restored.state_snapshot, vec![0u8; 1024]);
+
+        assert!(restored.sandbox.state.can_execute());
+        restored.sandbox.mark_running();
+        assert_eq!(pool.stats().await.in_use, 1);
+
+        pool.release(sandbox_id).await.unwrap();
+        let reused = pool.acquire("python3.11").await.unwrap();
+        assert_eq!(reused.id, sandbox_id);
+        assert_eq!(reused.state, SandboxState::Ready);
+    }
+
+    #[tokio::test]
+    async fn test_restore_revalidates_limits() {
+        use crate::checkpoint::{CheckpointConfig, InMemoryCheckpointStore};
+        use creto_common::{AgentId, OrganizationId};
+
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let pool = WarmPool::new(PoolConfig {
+            max_limits: Some(ResourceLimits::minimal()),
+            ..Default::default()
+        })
+        .with_checkpoint_store(store.clone());
+
+        let mut sandbox = Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig {
+                limits: Some(ResourceLimits::generous()),
+                ..Default::default()
+            },
+        );
+        let checkpoint_id = store
+            .checkpoint(sandbox.id, CheckpointConfig::default())
+            .await
+            .unwrap();
+
+        // Checkpointed under looser limits than the pool now allows
+        let err = pool
+            .restore_from_checkpoint(checkpoint_id, restore_config(&sandbox, false))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CheckpointError::RestoreFailed { .. }));
+
+        sandbox.config.limits = Some(ResourceLimits::minimal());
+        let outcome = pool
+            .restore_from_checkpoint(checkpoint_id, restore_config(&sandbox, false))
+            .await
+            .unwrap();
+        let RestoreOutcome::Detached(restored) = outcome else {
+            panic!("expected the sandbox to be handed back");
+        };
+        assert_eq!(restored.sandbox.id, sandbox.id);
+        assert_eq!(restored.sandbox.state, SandboxState::Paused);
+        assert_eq!(pool.stats().await.total, 0);
+    }
+
+    fn ready_sandbox() -> Sandbox {
+        use creto_common::{AgentId, OrganizationId};
+
+        let mut sandbox = Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig::default(),
+        );
+        sandbox.mark_ready(format!("handle_{}", sandbox.id));
+        sandbox
+    }
+
+    fn probed_pool(
+        probe: &crate::testing::ScriptedHealthProbe,
+        health: PoolHealthConfig,
+    ) -> WarmPool {
+        WarmPool::new(PoolConfig {
+            health,
+            ..Default::default()
+        })
+        .with_health_probe(Arc::new(probe.clone()))
+    }
+
+    #[tokio::test]
+    async fn test_health_sweep_quarantines_then_evicts() {
+        let probe = crate::testing::ScriptedHealthProbe::new();
+        let pool = probed_pool(&probe, PoolHealthConfig::default());
+        let broken = ready_sandbox();
+        let healthy = ready_sandbox();
+        let (broken_id, healthy_id) = (broken.id, healthy.id);
+        pool.add(broken).await.unwrap();
+        pool.add(healthy).await.unwrap();
+        probe.fail(broken_id);
+
+        let report = pool.check_health().await;
+        assert_eq!(report.probed, 2);
+        assert_eq!(report.unhealthy, vec![broken_id]);
+        assert!(report.evicted.is_empty());
+        let health = pool.pool_health().await;
+        assert_eq!((health.total, health.unhealthy), (2, 1));
+        assert!(!health.healthy);
+        assert_eq!(health.to_health_response().status, "degraded");
+
+        // The broken sandbox is never handed out
+        let acquired = pool.acquire("python3.11").await.unwrap();
+        assert_eq!(acquired.id, healthy_id);
+        assert!(pool.acquire("python3.11").await.is_none());
+        pool.release(healthy_id).await.unwrap();
+
+        let report = pool.check_health().await;
+        assert_eq!(report.unhealthy, vec![broken_id]);
+        assert_eq!(report.evicted.len(), 1);
+        assert_eq!(report.evicted[0].id, broken_id);
+        assert!(pool.remove(broken_id).await.is_none());
+
+        let stats = pool.stats().await;
+        assert_eq!((stats.total, stats.ready, stats.in_use), (1, 1, 0));
+        assert_eq!(stats.health_evictions, 1);
+        let health = pool.pool_health().await;
+        assert_eq!(
+            (health.total, health.unhealthy, health.evictions),
+            (1, 0, 1)
+        );
+        assert!(health.healthy);
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_passing_probe_again_is_ready() {
+        let probe = crate::testing::ScriptedHealthProbe::new();
+        let pool = probed_pool(&probe, PoolHealthConfig::default());
+        let sandbox = ready_sandbox();
+        let sandbox_id = sandbox.id;
+        pool.add(sandbox).await.unwrap();
+
+        probe.fail(sandbox_id);
+        pool.check_health().await;
+        assert_eq!(pool.stats().await.ready, 0);
+
+        probe.heal(sandbox_id);
+        let report = pool.check_health().await;
+        assert_eq!(report.recovered, vec![sandbox_id]);
+        assert_eq!(pool.stats().await.by_runtime["python3.11"].ready, 1);
+        assert_eq!(pool.acquire("python3.11").await.unwrap().id, sandbox_id);
+    }
+
+    #[tokio::test]
+    async fn test_probe_on_acquire_passes_over_failing_sandbox() {
+        let probe = crate::testing::ScriptedHealthProbe::new();
+        let pool = probed_pool(
+            &probe,
+            PoolHealthConfig {
+                probe_on_acquire: true,
+                eviction_threshold: 1,
+                ..Default::default()
+            },
+        );
+        let healthy = ready_sandbox();
+        let broken = ready_sandbox();
+        let (healthy_id, broken_id) = (healthy.id, broken.id);
+        pool.add(healthy).await.unwrap();
+        pool.add(broken).await.unwrap();
+        probe.fail(broken_id);
+
+        let acquired = pool.acquire("python3.11").await.unwrap();
+        assert_eq!(acquired.id, healthy_id);
+        assert_eq!(probe.probes(), vec![broken_id, healthy_id]);
+        let stats = pool.stats().await;
+        assert_eq!((stats.total, stats.ready, stats.in_use), (1, 0, 1));
+        assert_eq!((stats.hits, stats.misses), (1, 0));
+
+        // Evicted at once; the next sweep hands it over to be destroyed
+        let report = pool.check_health().await;
+        assert_eq!(report.probed, 0);
+        assert_eq!(report.evicted.len(), 1);
+        assert_eq!(report.evicted[0].id, broken_id);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_acquire_does_not_wait_for_health_sweep() {
+        let probe = crate::testing::ScriptedHealthProbe::new()
+            .with_delay(std::time::Duration::from_secs(1));
+        let pool = Arc::new(probed_pool(&probe, PoolHealthConfig::default()));
+        for _ in 0..2 {
+            pool.add(ready_sandbox()).await.unwrap();
+        }
+
+        let sweep = tokio::spawn({
+            let pool = Arc::clone(&pool);
+            async move { pool.check_health().await }
+        });
+        tokio::task::yield_now().await;
+
+        let started = tokio::time::Instant::now();
+        let acquired = pool.acquire("python3.11").await.unwrap();
+        assert_eq!(started.elapsed(), std::time::Duration::ZERO);
+        assert!(probe.probes().is_empty());
+
+        // The sandbox checked out mid-sweep is left alone
+        let report = sweep.await.unwrap();
+        assert!(report.unhealthy.is_empty());
+        assert!(!probe.probes().is_empty());
+        assert_eq!(pool.stats().await.in_use, 1);
+        pool.release(acquired.id).await.unwrap();
+    }
 }
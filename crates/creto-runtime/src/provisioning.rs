@@ -0,0 +1,385 @@
+//! Sandbox provisioning phases, failure classification and retry.
+//!
+//! A fresh sandbox is provisioned in four phases: image resolution, resource
+//! allocation, secret injection and attestation. Each attempt records how
+//! long every phase took in a [`ProvisioningReport`], which is attached to
+//! the sandbox on success and to the [`ProvisioningFailure`] otherwise.
+//!
+//! Failures are classified into a [`ProvisioningError`]. Transient causes
+//! (host pressure, registry timeouts, unavailable dependencies) are retried
+//! by [`RuntimeService::create_sandbox`](crate::service::RuntimeService::create_sandbox)
+//! according to its [`ProvisioningRetryPolicy`]; permanent causes (invalid
+//! configuration, disallowed images) fail immediately, since retrying them
+//! can only fail again.
+
+use chrono::{DateTime, Utc};
+use creto_common::CretoError;
+use serde::{Deserialize, Serialize};
+
+use crate::sandbox::{Sandbox, SandboxConfig};
+
+/// A step in provisioning a fresh sandbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvisioningPhase {
+    /// Resolving and fetching the runtime image.
+    ImageResolution,
+    /// Reserving host resources and starting the sandbox.
+    ResourceAllocation,
+    /// Making the sandbox's secrets available to it.
+    SecretInjection,
+    /// Attesting the sandbox for its agent.
+    Attestation,
+}
+
+impl ProvisioningPhase {
+    /// Database and log representation.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ImageResolution => "image_resolution",
+            Self::ResourceAllocation => "resource_allocation",
+            Self::SecretInjection => "secret_injection",
+            Self::Attestation => "attestation",
+        }
+    }
+
+    /// Parse the database representation.
+    pub fn parse_db_str(s: &str) -> Option<Self> {
+        match s {
+            "image_resolution" => Some(Self::ImageResolution),
+            "resource_allocation" => Some(Self::ResourceAllocation),
+            "secret_injection" => Some(Self::SecretInjection),
+            "attestation" => Some(Self::Attestation),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ProvisioningPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Why a provisioning attempt failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "cause", content = "message", rename_all = "snake_case")]
+pub enum ProvisioningError {
+    /// The host is out of capacity for now (retryable).
+    HostPressure(String),
+    /// The image registry or another dependency timed out (retryable).
+    RegistryTimeout(String),
+    /// A dependency such as the database or attestation service failed (retryable).
+    Unavailable(String),
+    /// The sandbox configuration cannot be provisioned as given (permanent).
+    InvalidConfig(String),
+    /// The runtime image is not allowed on this host (permanent).
+    DisallowedImage(String),
+}
+
+impl ProvisioningError {
+    /// Whether another attempt may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::HostPressure(_) | Self::RegistryTimeout(_) | Self::Unavailable(_)
+        )
+    }
+
+    /// Stable name of the cause.
+    pub fn cause(&self) -> &'static str {
+        match self {
+            Self::HostPressure(_) => "host_pressure",
+            Self::RegistryTimeout(_) => "registry_timeout",
+            Self::Unavailable(_) => "unavailable",
+            Self::InvalidConfig(_) => "invalid_config",
+            Self::DisallowedImage(_) => "disallowed_image",
+        }
+    }
+
+    /// Description of the failure.
+    pub fn message(&self) -> &str {
+        match self {
+            Self::HostPressure(m)
+            | Self::RegistryTimeout(m)
+            | Self::Unavailable(m)
+            | Self::InvalidConfig(m)
+            | Self::DisallowedImage(m) => m,
+        }
+    }
+
+    /// Classify an error returned by a backend, repository or attestation
+    /// generator.
+    ///
+    /// Errors that say nothing about the request itself are assumed to be
+    /// transient.
+    pub fn classify(error: &CretoError) -> Self {
+        let message = error.to_string();
+        match error {
+            CretoError::ResourceLimitExceeded { .. }
+            | CretoError::LimitExceeded(_)
+            | CretoError::QueueTimeout { .. } => Self::HostPressure(message),
+            CretoError::ExecutionTimeout { .. } => Self::RegistryTimeout(message),
+            CretoError::ValidationFailed(_)
+            | CretoError::Configuration(_)
+            | CretoError::PolicyViolation { .. }
+            | CretoError::SerializationError(_)
+            | CretoError::NotFound(_) => Self::InvalidConfig(message),
+            CretoError::AuthorizationDenied(_)
+            | CretoError::NotAuthorized { .. }
+            | CretoError::Unauthorized(_)
+            | CretoError::NetworkEgressDenied { .. } => Self::DisallowedImage(message),
+            _ => Self::Unavailable(message),
+        }
+    }
+}
+
+impl std::fmt::Display for ProvisioningError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.cause(), self.message())
+    }
+}
+
+impl std::error::Error for ProvisioningError {}
+
+/// How long one phase of one attempt took.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    /// The phase.
+    pub phase: ProvisioningPhase,
+    /// Attempt the phase ran in, starting at 1.
+    pub attempt: u32,
+    /// Wall-clock duration in milliseconds.
+    pub duration_ms: u64,
+    /// Whether the phase completed.
+    pub succeeded: bool,
+}
+
+/// Timing of every provisioning attempt for one sandbox request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvisioningReport {
+    /// When the first attempt started.
+    pub started_at: DateTime<Utc>,
+    /// Attempts made, including the last.
+    pub attempts: u32,
+    /// Whether the sandbox came from the warm pool (only attestation ran).
+    #[serde(default)]
+    pub from_pool: bool,
+    /// Phases run, in order, across all attempts.
+    pub phases: Vec<PhaseTiming>,
+    /// Total time in milliseconds, including backoff between attempts.
+    pub total_ms: u64,
+}
+
+impl ProvisioningReport {
+    /// Start a report.
+    pub fn new(started_at: DateTime<Utc>) -> Self {
+        Self {
+            started_at,
+            attempts: 0,
+            from_pool: false,
+            phases: Vec::new(),
+            total_ms: 0,
+        }
+    }
+
+    /// Total time spent in `phase` across all attempts, in milliseconds.
+    pub fn phase_duration_ms(&self, phase: ProvisioningPhase) -> u64 {
+        self.phases
+            .iter()
+            .filter(|t| t.phase == phase)
+            .map(|t| t.duration_ms)
+            .sum()
+    }
+
+    /// Phases run in `attempt`, in order.
+    pub fn attempt_phases(&self, attempt: u32) -> Vec<PhaseTiming> {
+        self.phases
+            .iter()
+            .filter(|t| t.attempt == attempt)
+            .cloned()
+            .collect()
+    }
+}
+
+/// A sandbox request that could not be provisioned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvisioningFailure {
+    /// Phase the last attempt failed in.
+    pub phase: ProvisioningPhase,
+    /// Why the last attempt failed.
+    pub error: ProvisioningError,
+    /// Timing of every attempt.
+    pub report: ProvisioningReport,
+}
+
+impl ProvisioningFailure {
+    /// Whether the request may succeed if made again later.
+    pub fn is_retryable(&self) -> bool {
+        self.error.is_retryable()
+    }
+}
+
+impl std::fmt::Display for ProvisioningFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Sandbox provisioning failed during {} after {} attempt(s): {}",
+            self.phase, self.report.attempts, self.error
+        )
+    }
+}
+
+impl std::error::Error for ProvisioningFailure {}
+
+impl From<ProvisioningFailure> for CretoError {
+    fn from(failure: ProvisioningFailure) -> Self {
+        CretoError::SandboxProvisioningFailed {
+            phase: failure.phase.as_str().to_string(),
+            retryable: failure.is_retryable(),
+            attempts: failure.report.attempts,
+            message: failure.error.to_string(),
+        }
+    }
+}
+
+/// Retry behavior for retryable provisioning failures.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvisioningRetryPolicy {
+    /// Attempts, including the first.
+    pub max_attempts: u32,
+
+    /// Backoff after the first failed attempt in milliseconds.
+    pub initial_backoff_ms: u64,
+
+    /// Maximum backoff in milliseconds.
+    pub max_backoff_ms: u64,
+
+    /// Backoff multiplier.
+    pub multiplier: f64,
+}
+
+impl Default for ProvisioningRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 250,
+            max_backoff_ms: 5_000,
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl ProvisioningRetryPolicy {
+    /// Make a single attempt.
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Backoff after `failures` failed attempts.
+    pub fn backoff_ms(&self, failures: u32) -> u64 {
+        if failures == 0 {
+            return 0;
+        }
+
+        let backoff = self.initial_backoff_ms as f64 * self.multiplier.powi(failures as i32 - 1);
+        backoff.min(self.max_backoff_ms as f64) as u64
+    }
+}
+
+/// Outcome of one warm pool replenishment pass.
+#[derive(Debug, Clone, Default)]
+pub struct ReplenishmentReport {
+    /// Sandboxes added to the pool.
+    pub provisioned: usize,
+    /// Runtimes that could not be topped up, one failure each.
+    pub failures: Vec<ProvisioningFailure>,
+    /// Runtimes skipped or newly suspended after a permanent failure.
+    pub suspended: Vec<String>,
+}
+
+/// Host-specific work in the provisioning phases.
+///
+/// The sandbox backend starts the sandbox during resource allocation; a
+/// provisioner adds the checks and side effects around it. Every method
+/// defaults to doing nothing.
+#[async_trait::async_trait]
+pub trait SandboxProvisioner: Send + Sync {
+    /// Resolve and fetch the runtime image.
+    async fn resolve_image(&self, _config: &SandboxConfig) -> Result<(), ProvisioningError> {
+        Ok(())
+    }
+
+    /// Reserve host resources before the backend starts the sandbox.
+    async fn allocate_resources(&self, _config: &SandboxConfig) -> Result<(), ProvisioningError> {
+        Ok(())
+    }
+
+    /// Make secrets available to a started sandbox.
+    async fn inject_secrets(&self, _sandbox: &Sandbox) -> Result<(), ProvisioningError> {
+        Ok(())
+    }
+}
+
+/// Provisioner with no host-specific work.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopProvisioner;
+
+impl SandboxProvisioner for NoopProvisioner {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classification() {
+        let retryable = [
+            CretoError::ResourceLimitExceeded {
+                resource: "memory".to_string(),
+            },
+            CretoError::ExecutionTimeout { seconds: 30 },
+            CretoError::Database("connection reset".to_string()),
+            CretoError::SandboxCreationFailed("no capacity".to_string()),
+        ];
+        for error in &retryable {
+            assert!(ProvisioningError::classify(error).is_retryable(), "{error}");
+        }
+
+        let permanent = [
+            CretoError::ValidationFailed("bad limits".to_string()),
+            CretoError::PolicyViolation {
+                fields: vec!["runtime".to_string()],
+            },
+            CretoError::AuthorizationDenied("image not allowed".to_string()),
+        ];
+        for error in &permanent {
+            assert!(
+                !ProvisioningError::classify(error).is_retryable(),
+                "{error}"
+            );
+        }
+        assert_eq!(
+            ProvisioningError::classify(&permanent[2]).cause(),
+            "disallowed_image"
+        );
+
+        let json = serde_json::to_value(ProvisioningError::HostPressure("full".into())).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"cause": "host_pressure", "message": "full"})
+        );
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = ProvisioningRetryPolicy::default();
+        assert_eq!(policy.backoff_ms(0), 0);
+        assert_eq!(policy.backoff_ms(1), 250);
+        assert_eq!(policy.backoff_ms(2), 500);
+        assert_eq!(policy.backoff_ms(10), 5_000);
+        assert_eq!(ProvisioningRetryPolicy::disabled().max_attempts, 1);
+    }
+}
@@ -0,0 +1,773 @@
+//! Capacity-aware execution admission.
+//!
+//! With an [`ExecutionQueue`] configured, the service runs at most
+//! `max_concurrent_executions` executions at once. Requests submitted with
+//! [`ExecutionRequest::queue`](crate::execution::ExecutionRequest) set wait
+//! for a free slot instead of failing: they are ordered by priority, first in
+//! first out within a priority, and dispatched as running executions finish.
+//! A request still waiting after `max_queue_time` fails with
+//! [`CretoError::QueueTimeout`].
+//!
+//! Sandbox creations can wait too: a creation that finds the warm pool empty
+//! and the host out of capacity waits in the same queue, by priority, until a
+//! sandbox is released to the pool or terminated, then tries again.
+//!
+//! The queue is bounded overall and per organization, so one organization
+//! cannot fill it.
+//!
+//! Executions in GPU sandboxes are admitted against their own limit,
+//! `max_concurrent_gpu_executions`, so scarce GPU slots neither hold up CPU
+//! work nor get taken by it. Requests of one class only wait behind earlier
+//! requests of the same class.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use creto_common::{CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use crate::execution::ExecutionResult;
+use crate::resources::ResourceClass;
+
+/// Scheduling priority of an execution.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionPriority {
+    /// Background work.
+    Low,
+    /// Default priority.
+    #[default]
+    Normal,
+    /// Dispatched ahead of normal work.
+    High,
+    /// Dispatched ahead of everything else.
+    Critical,
+}
+
+/// Execution admission configuration.
+#[derive(Debug, Clone)]
+pub struct ExecutionQueueConfig {
+    /// CPU-only executions allowed to run at once.
+    pub max_concurrent_executions: usize,
+    /// Executions in GPU sandboxes allowed to run at once.
+    pub max_concurrent_gpu_executions: usize,
+    /// Requests allowed to wait at once.
+    pub max_depth: usize,
+    /// Requests a single organization may have waiting at once.
+    pub max_queued_per_org: usize,
+    /// How long a request may wait before failing.
+    pub max_queue_time: Duration,
+    /// How often a sandbox creation waiting for capacity tries again when
+    /// no sandbox has been released in the meantime.
+    pub sandbox_retry_interval: Duration,
+}
+
+impl Default for ExecutionQueueConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_executions: 16,
+            max_concurrent_gpu_executions: 4,
+            max_depth: 256,
+            max_queued_per_org: 32,
+            max_queue_time: Duration::from_secs(300),
+            sandbox_retry_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// A waiting request.
+struct QueueEntry {
+    id: Uuid,
+    organization_id: OrganizationId,
+    priority: ExecutionPriority,
+    gpu: bool,
+    enqueued_at: Instant,
+    dispatch: oneshot::Sender<()>,
+}
+
+/// A sandbox creation waiting for capacity.
+struct SandboxWaiter {
+    id: Uuid,
+    organization_id: OrganizationId,
+    priority: ExecutionPriority,
+    deadline: Instant,
+    wake: oneshot::Sender<()>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    running: usize,
+    running_gpu: usize,
+    /// Waiting requests in dispatch order.
+    entries: Vec<QueueEntry>,
+    /// Waiting sandbox creations in wake order.
+    sandbox_waiters: Vec<SandboxWaiter>,
+    queued_per_org: HashMap<OrganizationId, usize>,
+}
+
+impl QueueState {
+    fn running_mut(&mut self, gpu: bool) -> &mut usize {
+        if gpu {
+            &mut self.running_gpu
+        } else {
+            &mut self.running
+        }
+    }
+
+    fn waiting(&self, gpu: bool) -> bool {
+        self.entries.iter().any(|e| e.gpu == gpu)
+    }
+
+    fn remove(&mut self, index: usize) -> QueueEntry {
+        let entry = self.entries.remove(index);
+        self.dequeued(entry.organization_id);
+        entry
+    }
+
+    fn remove_sandbox_waiter(&mut self, index: usize) -> SandboxWaiter {
+        let waiter = self.sandbox_waiters.remove(index);
+        self.dequeued(waiter.organization_id);
+        waiter
+    }
+
+    fn depth(&self) -> usize {
+        self.entries.len() + self.sandbox_waiters.len()
+    }
+
+    fn dequeued(&mut self, organization_id: OrganizationId) {
+        if let Some(count) = self.queued_per_org.get_mut(&organization_id) {
+            *count -= 1;
+            if *count == 0 {
+                self.queued_per_org.remove(&organization_id);
+            }
+        }
+    }
+
+    /// Drop requests that have waited too long; their waiters time out.
+    fn expire(&mut self, max_queue_time: Duration) {
+        let mut index = 0;
+        while index < self.entries.len() {
+            if self.entries[index].enqueued_at.elapsed() >= max_queue_time {
+                self.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+/// Bounded priority queue in front of the execution slots.
+pub struct ExecutionQueue {
+    config: ExecutionQueueConfig,
+    state: Mutex<QueueState>,
+}
+
+impl ExecutionQueue {
+    /// Create a queue.
+    pub fn new(config: ExecutionQueueConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(QueueState::default()),
+        }
+    }
+
+    /// Get the configuration.
+    pub fn config(&self) -> &ExecutionQueueConfig {
+        &self.config
+    }
+
+    /// Executions currently holding a slot, CPU and GPU.
+    pub fn running(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.running + state.running_gpu
+    }
+
+    /// GPU executions currently holding a slot.
+    pub fn running_gpu(&self) -> usize {
+        self.state.lock().unwrap().running_gpu
+    }
+
+    fn capacity(&self, gpu: bool) -> usize {
+        if gpu {
+            self.config.max_concurrent_gpu_executions
+        } else {
+            self.config.max_concurrent_executions
+        }
+    }
+
+    /// Claim a slot of the class if one is free and nothing of the class waits.
+    fn claim(self: &Arc<Self>, state: &mut QueueState, gpu: bool) -> Option<ExecutionSlot> {
+        let capacity = self.capacity(gpu);
+        if state.waiting(gpu) || *state.running_mut(gpu) >= capacity {
+            return None;
+        }
+        *state.running_mut(gpu) += 1;
+        Some(ExecutionSlot::new(Arc::clone(self), gpu))
+    }
+
+    /// Requests and sandbox creations currently waiting.
+    pub fn depth(&self) -> usize {
+        self.state.lock().unwrap().depth()
+    }
+
+    /// Fail if the queue, or the organization's share of it, is full.
+    fn check_room(&self, state: &QueueState, organization_id: OrganizationId) -> CretoResult<()> {
+        if state.depth() >= self.config.max_depth {
+            return Err(CretoError::ResourceLimitExceeded {
+                resource: "execution_queue".to_string(),
+            });
+        }
+        let queued = state
+            .queued_per_org
+            .get(&organization_id)
+            .copied()
+            .unwrap_or(0);
+        if queued >= self.config.max_queued_per_org {
+            return Err(CretoError::ResourceLimitExceeded {
+                resource: format!("execution_queue for organization {}", organization_id),
+            });
+        }
+        Ok(())
+    }
+
+    /// Requests an organization has waiting.
+    pub fn queued_for(&self, organization_id: &OrganizationId) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .queued_per_org
+            .get(organization_id)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Take a CPU slot without waiting.
+    ///
+    /// Fails when every slot is busy, or when queued requests are waiting
+    /// for the next free one.
+    pub fn try_acquire(self: &Arc<Self>) -> CretoResult<ExecutionSlot> {
+        self.try_acquire_for(&ResourceClass::Cpu)
+    }
+
+    /// Take a slot of the given class without waiting.
+    pub fn try_acquire_for(self: &Arc<Self>, class: &ResourceClass) -> CretoResult<ExecutionSlot> {
+        let gpu = class.is_gpu();
+        let mut state = self.state.lock().unwrap();
+        state.expire(self.config.max_queue_time);
+        self.claim(&mut state, gpu)
+            .ok_or_else(|| CretoError::ResourceLimitExceeded {
+                resource: if gpu {
+                    "concurrent_gpu_executions".to_string()
+                } else {
+                    "concurrent_executions".to_string()
+                },
+            })
+    }
+
+    /// Take a CPU slot now if one is free, otherwise queue the request.
+    pub fn enqueue(
+        self: &Arc<Self>,
+        id: Uuid,
+        organization_id: OrganizationId,
+        priority: ExecutionPriority,
+    ) -> CretoResult<QueueTicket> {
+        self.enqueue_for(id, organization_id, priority, &ResourceClass::Cpu)
+    }
+
+    /// Take a slot of the given class now if one is free, otherwise queue
+    /// the request.
+    pub fn enqueue_for(
+        self: &Arc<Self>,
+        id: Uuid,
+        organization_id: OrganizationId,
+        priority: ExecutionPriority,
+        class: &ResourceClass,
+    ) -> CretoResult<QueueTicket> {
+        let gpu = class.is_gpu();
+        let mut state = self.state.lock().unwrap();
+        state.expire(self.config.max_queue_time);
+
+        if let Some(slot) = self.claim(&mut state, gpu) {
+            return Ok(QueueTicket::Ready(slot));
+        }
+        self.check_room(&state, organization_id)?;
+
+        let (dispatch, ready) = oneshot::channel();
+        let enqueued_at = Instant::now();
+        let index = state
+            .entries
+            .iter()
+            .position(|e| e.priority < priority)
+            .unwrap_or(state.entries.len());
+        state.entries.insert(
+            index,
+            QueueEntry {
+                id,
+                organization_id,
+                priority,
+                gpu,
+                enqueued_at,
+                dispatch,
+            },
+        );
+        *state.queued_per_org.entry(organization_id).or_insert(0) += 1;
+
+        Ok(QueueTicket::Waiting {
+            id,
+            gpu,
+            queue: Arc::clone(self),
+            deadline: enqueued_at + self.config.max_queue_time,
+            ready,
+        })
+    }
+
+    /// Zero-based position of a waiting request.
+    pub fn position(&self, id: Uuid) -> Option<usize> {
+        self.state
+            .lock()
+            .unwrap()
+            .entries
+            .iter()
+            .position(|e| e.id == id)
+    }
+
+    /// Wait for sandbox capacity on behalf of a creation that found the warm
+    /// pool empty and the host out of capacity.
+    ///
+    /// Creations are woken one at a time, highest priority and earliest
+    /// `deadline` first, as sandboxes are released or terminated; each also
+    /// wakes after `sandbox_retry_interval` to try again by itself. Fails
+    /// with [`CretoError::QueueTimeout`] once `deadline` has passed, and with
+    /// [`CretoError::ResourceLimitExceeded`] if the queue is full.
+    pub async fn wait_for_sandbox(
+        &self,
+        id: Uuid,
+        organization_id: OrganizationId,
+        priority: ExecutionPriority,
+        deadline: Instant,
+    ) -> CretoResult<()> {
+        let timed_out = || CretoError::QueueTimeout {
+            seconds: self.config.max_queue_time.as_secs(),
+        };
+        if Instant::now() >= deadline {
+            return Err(timed_out());
+        }
+
+        let ready = {
+            let mut state = self.state.lock().unwrap();
+            self.check_room(&state, organization_id)?;
+            let (wake, ready) = oneshot::channel();
+            let index = state
+                .sandbox_waiters
+                .iter()
+                .position(|w| {
+                    w.priority < priority || (w.priority == priority && w.deadline > deadline)
+                })
+                .unwrap_or(state.sandbox_waiters.len());
+            state.sandbox_waiters.insert(
+                index,
+                SandboxWaiter {
+                    id,
+                    organization_id,
+                    priority,
+                    deadline,
+                    wake,
+                },
+            );
+            *state.queued_per_org.entry(organization_id).or_insert(0) += 1;
+            ready
+        };
+        // Leaves the queue however the wait ends, including being dropped
+        let _waiting = SandboxWait { queue: self, id };
+
+        let wake_at = deadline.min(Instant::now() + self.config.sandbox_retry_interval);
+        let woken = tokio::time::timeout_at(wake_at, ready).await;
+        if woken.is_err() && Instant::now() >= deadline {
+            return Err(timed_out());
+        }
+        Ok(())
+    }
+
+    /// Wake the first sandbox creation waiting for capacity.
+    ///
+    /// Called when a sandbox is released to the warm pool or terminated.
+    pub fn sandbox_released(&self) {
+        let mut state = self.state.lock().unwrap();
+        while !state.sandbox_waiters.is_empty() {
+            let waiter = state.remove_sandbox_waiter(0);
+            // A closed receiver means the waiter already gave up
+            if waiter.wake.send(()).is_ok() {
+                break;
+            }
+        }
+    }
+
+    /// Remove a waiting request. Returns false if it is no longer waiting.
+    pub fn cancel(&self, id: Uuid) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.entries.iter().position(|e| e.id == id) {
+            Some(index) => {
+                state.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return a slot and hand free slots to waiting requests.
+    fn release(&self, gpu: bool) {
+        let mut state = self.state.lock().unwrap();
+        let running = state.running_mut(gpu);
+        *running = running.saturating_sub(1);
+        state.expire(self.config.max_queue_time);
+
+        // Dispatch in queue order, skipping requests whose class is full
+        let mut index = 0;
+        while index < state.entries.len() {
+            let gpu = state.entries[index].gpu;
+            if *state.running_mut(gpu) >= self.capacity(gpu) {
+                index += 1;
+                continue;
+            }
+            let entry = state.remove(index);
+            // A closed receiver means the waiter already gave up
+            if entry.dispatch.send(()).is_ok() {
+                *state.running_mut(gpu) += 1;
+            }
+        }
+    }
+}
+
+/// A sandbox creation's place in the queue; removed on drop if still there.
+struct SandboxWait<'a> {
+    queue: &'a ExecutionQueue,
+    id: Uuid,
+}
+
+impl Drop for SandboxWait<'_> {
+    fn drop(&mut self) {
+        let mut state = self.queue.state.lock().unwrap();
+        if let Some(index) = state.sandbox_waiters.iter().position(|w| w.id == self.id) {
+            state.remove_sandbox_waiter(index);
+        }
+    }
+}
+
+/// A running execution's claim on the queue; the slot is freed on drop.
+pub struct ExecutionSlot {
+    queue: Arc<ExecutionQueue>,
+    gpu: bool,
+}
+
+impl ExecutionSlot {
+    fn new(queue: Arc<ExecutionQueue>, gpu: bool) -> Self {
+        Self { queue, gpu }
+    }
+
+    /// Whether this is a GPU slot.
+    pub fn is_gpu(&self) -> bool {
+        self.gpu
+    }
+}
+
+impl Drop for ExecutionSlot {
+    fn drop(&mut self) {
+        self.queue.release(self.gpu);
+    }
+}
+
+/// A request's place in the queue.
+pub enum QueueTicket {
+    /// A slot was free on admission.
+    Ready(ExecutionSlot),
+    /// Waiting for a slot.
+    Waiting {
+        /// Request ID.
+        id: Uuid,
+        /// Whether the request waits for a GPU slot.
+        gpu: bool,
+        /// Queue the request is waiting in.
+        queue: Arc<ExecutionQueue>,
+        /// When the request times out.
+        deadline: Instant,
+        /// Signalled when the request is dispatched.
+        ready: oneshot::Receiver<()>,
+    },
+}
+
+impl QueueTicket {
+    /// Wait until the request is dispatched.
+    ///
+    /// Fails with [`CretoError::QueueTimeout`] if no slot frees up in time,
+    /// and with [`CretoError::NotFound`] if the request was cancelled.
+    pub async fn dispatched(self) -> CretoResult<ExecutionSlot> {
+        let (id, gpu, queue, deadline, mut ready) = match self {
+            QueueTicket::Ready(slot) => return Ok(slot),
+            QueueTicket::Waiting {
+                id,
+                gpu,
+                queue,
+                deadline,
+                ready,
+            } => (id, gpu, queue, deadline, ready),
+        };
+
+        let timed_out = || CretoError::QueueTimeout {
+            seconds: queue.config.max_queue_time.as_secs(),
+        };
+        match tokio::time::timeout_at(deadline, &mut ready).await {
+            Ok(Ok(())) => Ok(ExecutionSlot::new(Arc::clone(&queue), gpu)),
+            Ok(Err(_)) if Instant::now() >= deadline => Err(timed_out()),
+            Ok(Err(_)) => Err(CretoError::NotFound(format!(
+                "Queued execution {} was cancelled",
+                id
+            ))),
+            Err(_) => {
+                if queue.cancel(id) {
+                    return Err(timed_out());
+                }
+                // Dispatched between the deadline and the cancel
+                match ready.try_recv() {
+                    Ok(()) => Ok(ExecutionSlot::new(Arc::clone(&queue), gpu)),
+                    Err(_) => Err(timed_out()),
+                }
+            }
+        }
+    }
+}
+
+/// Handle to a submitted execution.
+///
+/// Dropping the handle does not cancel the execution.
+pub struct QueuedExecution {
+    id: Uuid,
+    queue: Option<Arc<ExecutionQueue>>,
+    result: oneshot::Receiver<CretoResult<ExecutionResult>>,
+}
+
+impl QueuedExecution {
+    pub(crate) fn new(
+        id: Uuid,
+        queue: Option<Arc<ExecutionQueue>>,
+        result: oneshot::Receiver<CretoResult<ExecutionResult>>,
+    ) -> Self {
+        Self { id, queue, result }
+    }
+
+    /// Execution request ID.
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Zero-based position in the queue, or `None` once dispatched.
+    pub fn position(&self) -> Option<usize> {
+        self.queue.as_ref().and_then(|q| q.position(self.id))
+    }
+
+    /// Remove the request from the queue without executing it.
+    ///
+    /// Returns false if it had already been dispatched; that execution
+    /// still runs to completion.
+    pub fn cancel(self) -> bool {
+        self.queue.as_ref().is_some_and(|q| q.cancel(self.id))
+    }
+
+    /// Wait for the execution to finish.
+    pub async fn wait(self) -> CretoResult<ExecutionResult> {
+        self.result.await.unwrap_or_else(|_| {
+            Err(CretoError::Internal(format!(
+                "Execution {} was dropped before completing",
+                self.id
+            )))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(max_concurrent_executions: usize) -> Arc<ExecutionQueue> {
+        Arc::new(ExecutionQueue::new(ExecutionQueueConfig {
+            max_concurrent_executions,
+            max_concurrent_gpu_executions: 1,
+            max_depth: 8,
+            max_queued_per_org: 3,
+            max_queue_time: Duration::from_secs(60),
+            sandbox_retry_interval: Duration::from_secs(60),
+        }))
+    }
+
+    fn waiting(
+        queue: &Arc<ExecutionQueue>,
+        organization_id: OrganizationId,
+        priority: ExecutionPriority,
+    ) -> (Uuid, QueueTicket) {
+        let id = Uuid::now_v7();
+        let ticket = queue.enqueue(id, organization_id, priority).unwrap();
+        assert!(matches!(ticket, QueueTicket::Waiting { .. }));
+        (id, ticket)
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_by_priority_then_fifo() {
+        let queue = queue(1);
+        let org_id = OrganizationId::new();
+        let running = queue.try_acquire().unwrap();
+
+        let (low, low_ticket) = waiting(&queue, org_id, ExecutionPriority::Low);
+        let (first, first_ticket) = waiting(&queue, org_id, ExecutionPriority::Normal);
+        let (high, high_ticket) = waiting(&queue, OrganizationId::new(), ExecutionPriority::High);
+        let (second, _second_ticket) = waiting(&queue, org_id, ExecutionPriority::Normal);
+
+        let order: Vec<_> = [high, first, second, low]
+            .iter()
+            .map(|id| queue.position(*id))
+            .collect();
+        assert_eq!(order, vec![Some(0), Some(1), Some(2), Some(3)]);
+        assert!(queue.try_acquire().is_err());
+
+        drop(running);
+        let slot = high_ticket.dispatched().await.unwrap();
+        assert_eq!(queue.position(high), None);
+        assert_eq!(queue.position(first), Some(0));
+        assert_eq!(queue.running(), 1);
+
+        drop(slot);
+        let _slot = first_ticket.dispatched().await.unwrap();
+        assert_eq!(queue.position(low), Some(1));
+        drop(low_ticket);
+    }
+
+    #[tokio::test]
+    async fn test_per_org_cap() {
+        let queue = queue(1);
+        let noisy = OrganizationId::new();
+        let _running = queue.try_acquire().unwrap();
+
+        let _tickets: Vec<_> = (0..3)
+            .map(|_| waiting(&queue, noisy, ExecutionPriority::Normal))
+            .collect();
+        let err = queue
+            .enqueue(Uuid::now_v7(), noisy, ExecutionPriority::Critical)
+            .err()
+            .unwrap();
+        assert!(matches!(err, CretoError::ResourceLimitExceeded { .. }));
+        assert_eq!(queue.queued_for(&noisy), 3);
+
+        // Other organizations can still queue
+        waiting(&queue, OrganizationId::new(), ExecutionPriority::Normal);
+        assert_eq!(queue.depth(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_gpu_slots_counted_separately() {
+        let queue = queue(1);
+        let org_id = OrganizationId::new();
+        let gpu = ResourceClass::Gpu {
+            count: 1,
+            kind: None,
+        };
+
+        let cpu_slot = queue.try_acquire().unwrap();
+        let gpu_slot = queue.try_acquire_for(&gpu).unwrap();
+        assert!(gpu_slot.is_gpu());
+        assert_eq!(queue.running(), 2);
+        assert_eq!(queue.running_gpu(), 1);
+        assert!(queue.try_acquire_for(&gpu).is_err());
+
+        // A waiting GPU request does not block CPU admission
+        let gpu_id = Uuid::now_v7();
+        let gpu_ticket = queue
+            .enqueue_for(gpu_id, org_id, ExecutionPriority::High, &gpu)
+            .unwrap();
+        drop(cpu_slot);
+        let _cpu_slot = queue.try_acquire().unwrap();
+        assert_eq!(queue.position(gpu_id), Some(0));
+
+        drop(gpu_slot);
+        let slot = gpu_ticket.dispatched().await.unwrap();
+        assert!(slot.is_gpu());
+        assert_eq!(queue.running_gpu(), 1);
+        assert_eq!(queue.running(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_times_out() {
+        let queue = Arc::new(ExecutionQueue::new(ExecutionQueueConfig {
+            max_concurrent_executions: 1,
+            max_queue_time: Duration::from_millis(20),
+            ..Default::default()
+        }));
+        let org_id = OrganizationId::new();
+        let _running = queue.try_acquire().unwrap();
+
+        let (id, ticket) = waiting(&queue, org_id, ExecutionPriority::Normal);
+        let err = ticket.dispatched().await.err().unwrap();
+        assert!(matches!(err, CretoError::QueueTimeout { .. }));
+        assert_eq!(queue.position(id), None);
+        assert_eq!(queue.queued_for(&org_id), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_sandbox_waiters_woken_by_priority_within_caps() {
+        let queue = queue(1);
+        let org_id = OrganizationId::new();
+        let _running = queue.try_acquire().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let wait = |priority| {
+            let queue = Arc::clone(&queue);
+            tokio::spawn(async move {
+                queue
+                    .wait_for_sandbox(Uuid::now_v7(), org_id, priority, deadline)
+                    .await
+            })
+        };
+
+        let low = wait(ExecutionPriority::Low);
+        let high = wait(ExecutionPriority::High);
+        tokio::task::yield_now().await;
+        assert_eq!(queue.depth(), 2);
+
+        // Waiting creations count towards the organization's cap
+        let (_, _ticket) = waiting(&queue, org_id, ExecutionPriority::Normal);
+        let err = queue
+            .wait_for_sandbox(
+                Uuid::now_v7(),
+                org_id,
+                ExecutionPriority::Critical,
+                deadline,
+            )
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, CretoError::ResourceLimitExceeded { .. }));
+
+        queue.sandbox_released();
+        high.await.unwrap().unwrap();
+        assert!(!low.is_finished());
+        queue.sandbox_released();
+        low.await.unwrap().unwrap();
+        assert_eq!(queue.depth(), 1);
+
+        // Without a release the wait ends at the deadline
+        let err = queue
+            .wait_for_sandbox(
+                Uuid::now_v7(),
+                org_id,
+                ExecutionPriority::Normal,
+                Instant::now() + Duration::from_secs(1),
+            )
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, CretoError::QueueTimeout { .. }));
+        assert_eq!(queue.queued_for(&org_id), 1);
+    }
+}
@@ -6,13 +6,38 @@
 //! Uses runtime SQL queries to avoid requiring DATABASE_URL at compile time.
 
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, CretoError, OrganizationId};
-use sqlx::{PgPool, Row};
+use creto_common::{AgentId, Correlation, CretoError, OrganizationId};
+use sqlx::{PgPool, Postgres, QueryBuilder, Row};
 use uuid::Uuid;
 
+use crate::exclusion::{ExclusionLease, ExclusionLeaseStore};
 use crate::execution::ExecutionStatus;
-use crate::resources::ResourceUsage;
-use crate::sandbox::{SandboxId, SandboxState};
+use crate::filesystem::{FilesystemDiff, FilesystemDiffStore};
+use crate::history::{
+    ExecutionCursor, ExecutionFilter, ExecutionStats, Page, PagePosition, PageRequest, TimeRange,
+};
+use crate::idempotency::IdempotentExecution;
+use crate::logs::{ExecutionLogStore, LogLevel, LogPage, LogRecord};
+use crate::network::{
+    EffectiveNetworkPolicy, NetworkPolicy, NetworkPolicyTemplate, NetworkPolicyTemplateStore,
+};
+use crate::org_policy::{OrgRuntimePolicy, OrgRuntimePolicyStore};
+use crate::provisioning::{PhaseTiming, ProvisioningError, ProvisioningPhase};
+use crate::queue::ExecutionPriority;
+use crate::resources::{ResourceLimits, ResourceUsage};
+use crate::sampling::UsageSample;
+use crate::sandbox::{
+    NetworkPolicy as SandboxNetworkPolicy, SandboxConfig, SandboxId, SandboxState,
+    TerminationReason,
+};
+use crate::schedule::{
+    CatchUpPolicy, ExecutionSchedule, ScheduleClaim, ScheduleId, ScheduleRun, ScheduleRunPage,
+    ScheduleStore,
+};
+use crate::secrets::{SecretGrant, SecretGrantStore, SecretIdlePolicy, SecretMountTarget};
+use crate::triggers::{
+    StoredTopicTrigger, TopicTriggerId, TopicTriggerStore, TriggerDeadLetter, TriggerRetry,
+};
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Enum Serialization Helpers
@@ -28,7 +53,12 @@ impl SandboxState {
             SandboxState::Paused => "paused",
             SandboxState::Stopped => "stopped",
             SandboxState::Failed => "failed",
-            SandboxState::Terminated => "terminated",
+            SandboxState::Terminated {
+                reason: TerminationReason::Requested,
+            } => "terminated",
+            SandboxState::Terminated {
+                reason: TerminationReason::LimitExceeded,
+            } => "limit_exceeded",
             SandboxState::Checkpointed { .. } => "checkpointed",
         }
     }
@@ -42,12 +72,28 @@ impl SandboxState {
             "paused" => SandboxState::Paused,
             "stopped" => SandboxState::Stopped,
             "failed" => SandboxState::Failed,
-            "terminated" => SandboxState::Terminated,
+            "terminated" => SandboxState::Terminated {
+                reason: TerminationReason::Requested,
+            },
+            "limit_exceeded" => SandboxState::Terminated {
+                reason: TerminationReason::LimitExceeded,
+            },
             _ => SandboxState::Creating,
         }
     }
 }
 
+impl SandboxNetworkPolicy {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SandboxNetworkPolicy::None => "none",
+            SandboxNetworkPolicy::Restricted => "restricted",
+            SandboxNetworkPolicy::Full => "full",
+        }
+    }
+}
+
 impl ExecutionStatus {
     /// Convert to database string.
     pub fn as_str(&self) -> &'static str {
@@ -75,6 +121,52 @@ impl ExecutionStatus {
     }
 }
 
+impl LogLevel {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "trace" => LogLevel::Trace,
+            "debug" => LogLevel::Debug,
+            "warn" => LogLevel::Warn,
+            "error" => LogLevel::Error,
+            _ => LogLevel::Info,
+        }
+    }
+}
+
+impl ExecutionPriority {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExecutionPriority::Low => "low",
+            ExecutionPriority::Normal => "normal",
+            ExecutionPriority::High => "high",
+            ExecutionPriority::Critical => "critical",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "low" => ExecutionPriority::Low,
+            "high" => ExecutionPriority::High,
+            "critical" => ExecutionPriority::Critical,
+            _ => ExecutionPriority::Normal,
+        }
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Sandbox Repository
 // ─────────────────────────────────────────────────────────────────────────────
@@ -88,10 +180,57 @@ pub struct SandboxRecord {
     pub runtime: String,
     pub state: SandboxState,
     pub network_policy: String,
+    pub effective_network_policy: Option<EffectiveNetworkPolicy>,
+    pub runtime_policy_version: Option<u32>,
+    /// Limits the sandbox was provisioned with; `None` for records written
+    /// before limits were persisted.
+    pub resource_limits: Option<ResourceLimits>,
     pub created_at: DateTime<Utc>,
     pub last_used_at: Option<DateTime<Utc>>,
 }
 
+/// A failed provisioning attempt, kept for operators.
+#[derive(Debug, Clone)]
+pub struct ProvisioningFailureRecord {
+    pub id: Uuid,
+    pub organization_id: OrganizationId,
+    pub agent_id: AgentId,
+    /// Sandbox record of the attempt, if one was written.
+    pub sandbox_id: Option<SandboxId>,
+    pub runtime: String,
+    pub attempt: u32,
+    pub phase: ProvisioningPhase,
+    pub error: ProvisioningError,
+    /// Phases of the failed attempt, the last being the one that failed.
+    pub phases: Vec<PhaseTiming>,
+    pub failed_at: DateTime<Utc>,
+}
+
+fn effective_policy_json(
+    policy: Option<&EffectiveNetworkPolicy>,
+) -> Result<Option<serde_json::Value>, CretoError> {
+    policy
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| CretoError::SerializationError(e.to_string()))
+}
+
+fn effective_policy_from_row(row: &sqlx::postgres::PgRow) -> Option<EffectiveNetworkPolicy> {
+    row.get::<Option<serde_json::Value>, _>("effective_network_policy")
+        .and_then(|v| serde_json::from_value(v).ok())
+}
+
+fn runtime_policy_version_from_row(row: &sqlx::postgres::PgRow) -> Option<u32> {
+    row.get::<Option<i32>, _>("runtime_policy_version")
+        .map(|v| v as u32)
+}
+
+fn resource_limits_from_row(row: &sqlx::postgres::PgRow) -> Option<ResourceLimits> {
+    row.get::<Option<serde_json::Value>, _>("resource_limits")
+        .filter(|v| v.as_object().is_some_and(|o| !o.is_empty()))
+        .and_then(|v| serde_json::from_value(v).ok())
+}
+
 /// Repository for sandbox persistence.
 #[async_trait::async_trait]
 pub trait SandboxRepository: Send + Sync {
@@ -102,6 +241,8 @@ pub trait SandboxRepository: Send + Sync {
         agent_id: AgentId,
         runtime: &str,
         network_policy: &str,
+        effective_network_policy: Option<&EffectiveNetworkPolicy>,
+        resource_limits: &ResourceLimits,
     ) -> Result<SandboxId, CretoError>;
 
     /// Get a sandbox by ID.
@@ -110,6 +251,30 @@ pub trait SandboxRepository: Send + Sync {
     /// Update sandbox state.
     async fn update_state(&self, id: SandboxId, state: SandboxState) -> Result<(), CretoError>;
 
+    /// Replace the resolved network policy snapshot (after template propagation).
+    async fn update_network_policy(
+        &self,
+        id: SandboxId,
+        effective_network_policy: &EffectiveNetworkPolicy,
+    ) -> Result<(), CretoError>;
+
+    /// Hand a warm-pool sandbox to its owner, with the network policy it now runs under.
+    async fn assign(
+        &self,
+        id: SandboxId,
+        org_id: OrganizationId,
+        agent_id: AgentId,
+        effective_network_policy: Option<&EffectiveNetworkPolicy>,
+    ) -> Result<(), CretoError>;
+
+    /// Record the configuration produced by merging an organization runtime policy.
+    async fn record_runtime_policy(
+        &self,
+        id: SandboxId,
+        config: &SandboxConfig,
+        policy_version: u32,
+    ) -> Result<(), CretoError>;
+
     /// Mark sandbox as terminated.
     async fn terminate(&self, id: SandboxId) -> Result<(), CretoError>;
 
@@ -119,8 +284,23 @@ pub trait SandboxRepository: Send + Sync {
         org_id: OrganizationId,
     ) -> Result<Vec<SandboxRecord>, CretoError>;
 
+    /// List active sandboxes of every organization.
+    async fn list_active(&self) -> Result<Vec<SandboxRecord>, CretoError>;
+
     /// Find idle sandboxes for cleanup.
     async fn find_idle(&self, idle_since: DateTime<Utc>) -> Result<Vec<SandboxId>, CretoError>;
+
+    /// Record a failed provisioning attempt.
+    async fn record_provisioning_failure(
+        &self,
+        failure: &ProvisioningFailureRecord,
+    ) -> Result<(), CretoError>;
+
+    /// List provisioning failures since a point in time, newest first.
+    async fn list_provisioning_failures(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<ProvisioningFailureRecord>, CretoError>;
 }
 
 /// PostgreSQL implementation of SandboxRepository.
@@ -142,13 +322,18 @@ impl SandboxRepository for PgSandboxRepository {
         agent_id: AgentId,
         runtime: &str,
         network_policy: &str,
+        effective_network_policy: Option<&EffectiveNetworkPolicy>,
+        resource_limits: &ResourceLimits,
     ) -> Result<SandboxId, CretoError> {
+        let limits_json = serde_json::to_value(resource_limits)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
         let row = sqlx::query(
             r#"
             INSERT INTO sandboxes (
                 organization_id, agent_id, runtime, state, config,
-                resource_limits, network_policy
-            ) VALUES ($1, $2, $3, 'creating', '{}', '{}', $4)
+                resource_limits, network_policy, effective_network_policy
+            ) VALUES ($1, $2, $3, 'creating', '{}', $6, $4, $5)
             RETURNING id
             "#,
         )
@@ -156,6 +341,8 @@ impl SandboxRepository for PgSandboxRepository {
         .bind(agent_id.as_uuid())
         .bind(runtime)
         .bind(network_policy)
+        .bind(effective_policy_json(effective_network_policy)?)
+        .bind(&limits_json)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
@@ -167,6 +354,7 @@ impl SandboxRepository for PgSandboxRepository {
         let row = sqlx::query(
             r#"
             SELECT organization_id, agent_id, runtime, state, network_policy,
+                   effective_network_policy, runtime_policy_version, resource_limits,
                    created_at, last_used_at
             FROM sandboxes
             WHERE id = $1
@@ -184,6 +372,9 @@ impl SandboxRepository for PgSandboxRepository {
             runtime: r.get("runtime"),
             state: SandboxState::parse_db_str(r.get::<&str, _>("state")),
             network_policy: r.get("network_policy"),
+            effective_network_policy: effective_policy_from_row(&r),
+            runtime_policy_version: runtime_policy_version_from_row(&r),
+            resource_limits: resource_limits_from_row(&r),
             created_at: r.get("created_at"),
             last_used_at: r.get("last_used_at"),
         }))
@@ -206,6 +397,81 @@ impl SandboxRepository for PgSandboxRepository {
         Ok(())
     }
 
+    async fn update_network_policy(
+        &self,
+        id: SandboxId,
+        effective_network_policy: &EffectiveNetworkPolicy,
+    ) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            UPDATE sandboxes
+            SET effective_network_policy = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id.as_uuid())
+        .bind(effective_policy_json(Some(effective_network_policy))?)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn assign(
+        &self,
+        id: SandboxId,
+        org_id: OrganizationId,
+        agent_id: AgentId,
+        effective_network_policy: Option<&EffectiveNetworkPolicy>,
+    ) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            UPDATE sandboxes
+            SET organization_id = $2, agent_id = $3, effective_network_policy = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(id.as_uuid())
+        .bind(org_id.as_uuid())
+        .bind(agent_id.as_uuid())
+        .bind(effective_policy_json(effective_network_policy)?)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn record_runtime_policy(
+        &self,
+        id: SandboxId,
+        config: &SandboxConfig,
+        policy_version: u32,
+    ) -> Result<(), CretoError> {
+        let config_json = serde_json::to_value(config)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        let limits_json = serde_json::to_value(config.effective_limits())
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            UPDATE sandboxes
+            SET config = $2, resource_limits = $3, runtime_policy_version = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(id.as_uuid())
+        .bind(&config_json)
+        .bind(&limits_json)
+        .bind(policy_version as i32)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
     async fn terminate(&self, id: SandboxId) -> Result<(), CretoError> {
         sqlx::query(
             r#"
@@ -228,9 +494,10 @@ impl SandboxRepository for PgSandboxRepository {
     ) -> Result<Vec<SandboxRecord>, CretoError> {
         let rows = sqlx::query(
             r#"
-            SELECT id, agent_id, runtime, state, network_policy, created_at, last_used_at
+            SELECT id, agent_id, runtime, state, network_policy, effective_network_policy,
+                   runtime_policy_version, resource_limits, created_at, last_used_at
             FROM sandboxes
-            WHERE organization_id = $1 AND state NOT IN ('terminated', 'failed')
+            WHERE organization_id = $1 AND state NOT IN ('terminated', 'limit_exceeded', 'failed')
             ORDER BY created_at DESC
             "#,
         )
@@ -248,6 +515,42 @@ impl SandboxRepository for PgSandboxRepository {
                 runtime: r.get("runtime"),
                 state: SandboxState::parse_db_str(r.get::<&str, _>("state")),
                 network_policy: r.get("network_policy"),
+                effective_network_policy: effective_policy_from_row(&r),
+                runtime_policy_version: runtime_policy_version_from_row(&r),
+                resource_limits: resource_limits_from_row(&r),
+                created_at: r.get("created_at"),
+                last_used_at: r.get("last_used_at"),
+            })
+            .collect())
+    }
+
+    async fn list_active(&self) -> Result<Vec<SandboxRecord>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, agent_id, runtime, state, network_policy,
+                   effective_network_policy, runtime_policy_version, resource_limits,
+                   created_at, last_used_at
+            FROM sandboxes
+            WHERE state NOT IN ('terminated', 'limit_exceeded', 'failed')
+            ORDER BY created_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|r| SandboxRecord {
+                id: SandboxId::from_uuid(r.get::<Uuid, _>("id")),
+                organization_id: OrganizationId::from_uuid(r.get::<Uuid, _>("organization_id")),
+                agent_id: AgentId::from_uuid(r.get::<Uuid, _>("agent_id")),
+                runtime: r.get("runtime"),
+                state: SandboxState::parse_db_str(r.get::<&str, _>("state")),
+                network_policy: r.get("network_policy"),
+                effective_network_policy: effective_policy_from_row(&r),
+                runtime_policy_version: runtime_policy_version_from_row(&r),
+                resource_limits: resource_limits_from_row(&r),
                 created_at: r.get("created_at"),
                 last_used_at: r.get("last_used_at"),
             })
@@ -273,6 +576,90 @@ impl SandboxRepository for PgSandboxRepository {
             .map(|r| SandboxId::from_uuid(r.get::<Uuid, _>("id")))
             .collect())
     }
+
+    async fn record_provisioning_failure(
+        &self,
+        failure: &ProvisioningFailureRecord,
+    ) -> Result<(), CretoError> {
+        let phases = serde_json::to_value(&failure.phases)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO sandbox_provisioning_failures (
+                id, organization_id, agent_id, sandbox_id, runtime, attempt,
+                phase, cause, retryable, message, phases, failed_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            "#,
+        )
+        .bind(failure.id)
+        .bind(failure.organization_id.as_uuid())
+        .bind(failure.agent_id.as_uuid())
+        .bind(failure.sandbox_id.map(|id| id.as_uuid()))
+        .bind(&failure.runtime)
+        .bind(failure.attempt as i32)
+        .bind(failure.phase.as_str())
+        .bind(failure.error.cause())
+        .bind(failure.error.is_retryable())
+        .bind(failure.error.message())
+        .bind(&phases)
+        .bind(failure.failed_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_provisioning_failures(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<ProvisioningFailureRecord>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, agent_id, sandbox_id, runtime, attempt,
+                   phase, cause, message, phases, failed_at
+            FROM sandbox_provisioning_failures
+            WHERE failed_at >= $1
+            ORDER BY failed_at DESC
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|r| {
+                let phase: &str = r.get("phase");
+                let phase = ProvisioningPhase::parse_db_str(phase).ok_or_else(|| {
+                    CretoError::Database(format!("unknown provisioning phase {}", phase))
+                })?;
+                let error = serde_json::from_value(serde_json::json!({
+                    "cause": r.get::<&str, _>("cause"),
+                    "message": r.get::<&str, _>("message"),
+                }))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+                let phases = serde_json::from_value(r.get::<serde_json::Value, _>("phases"))
+                    .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+                Ok(ProvisioningFailureRecord {
+                    id: r.get("id"),
+                    organization_id: OrganizationId::from_uuid(r.get::<Uuid, _>("organization_id")),
+                    agent_id: AgentId::from_uuid(r.get::<Uuid, _>("agent_id")),
+                    sandbox_id: r
+                        .get::<Option<Uuid>, _>("sandbox_id")
+                        .map(SandboxId::from_uuid),
+                    runtime: r.get("runtime"),
+                    attempt: r.get::<i32, _>("attempt") as u32,
+                    phase,
+                    error,
+                    phases,
+                    failed_at: r.get("failed_at"),
+                })
+            })
+            .collect()
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -289,6 +676,8 @@ pub struct ExecutionRecord {
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub duration_ms: Option<i64>,
+    pub correlation_id: Option<Uuid>,
+    pub caused_by: Option<Uuid>,
 }
 
 /// Repository for execution request persistence.
@@ -300,6 +689,7 @@ pub trait ExecutionRepository: Send + Sync {
         sandbox_id: SandboxId,
         code: &str,
         timeout_seconds: i32,
+        correlation: Correlation,
     ) -> Result<Uuid, CretoError>;
 
     /// Get execution request by ID.
@@ -319,6 +709,140 @@ pub trait ExecutionRepository: Send + Sync {
         &self,
         sandbox_id: SandboxId,
     ) -> Result<Vec<ExecutionRecord>, CretoError>;
+
+    /// Find all executions sharing a correlation ID, oldest first.
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<ExecutionRecord>, CretoError>;
+
+    /// Page through executions matching a filter, newest first.
+    async fn find(
+        &self,
+        filter: &ExecutionFilter,
+        page: &PageRequest,
+    ) -> Result<Page<ExecutionRecord>, CretoError>;
+
+    /// Counts by status and duration percentiles for an organization's
+    /// executions queued within a range.
+    async fn stats(
+        &self,
+        organization_id: OrganizationId,
+        range: TimeRange,
+    ) -> Result<ExecutionStats, CretoError>;
+
+    /// Record the result of an execution made under an idempotency key, on
+    /// the execution's row.
+    async fn record_idempotent_result(
+        &self,
+        record: &IdempotentExecution,
+    ) -> Result<(), CretoError>;
+
+    /// Get the latest result recorded under a sandbox's idempotency key.
+    async fn find_idempotent_result(
+        &self,
+        sandbox_id: SandboxId,
+        key: &str,
+    ) -> Result<Option<IdempotentExecution>, CretoError>;
+}
+
+const EXECUTION_HISTORY_COLUMNS: &str = "SELECT e.id, e.sandbox_id, e.status, e.queued_at, \
+     e.started_at, e.completed_at, e.duration_ms, e.correlation_id, e.caused_by";
+
+const EXECUTION_HISTORY_FROM: &str =
+    " FROM execution_requests e JOIN sandboxes s ON s.id = e.sandbox_id";
+
+/// Append a WHERE clause for `filter`, binding each set field.
+fn push_execution_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &ExecutionFilter) {
+    query.push(" WHERE TRUE");
+    if let Some(organization_id) = filter.organization_id {
+        query
+            .push(" AND s.organization_id = ")
+            .push_bind(*organization_id.as_uuid());
+    }
+    if let Some(agent_id) = filter.agent_id {
+        query
+            .push(" AND s.agent_id = ")
+            .push_bind(*agent_id.as_uuid());
+    }
+    if let Some(sandbox_id) = filter.sandbox_id {
+        query
+            .push(" AND e.sandbox_id = ")
+            .push_bind(sandbox_id.as_uuid());
+    }
+    if let Some(status) = filter.status {
+        query.push(" AND e.status = ").push_bind(status.as_str());
+    }
+    if let Some(range) = filter.queued {
+        query
+            .push(" AND e.queued_at >= ")
+            .push_bind(range.start)
+            .push(" AND e.queued_at < ")
+            .push_bind(range.end);
+    }
+    if let Some(min_duration_ms) = filter.min_duration_ms {
+        query
+            .push(" AND e.duration_ms >= ")
+            .push_bind(min_duration_ms);
+    }
+}
+
+/// Build the query for one page, fetching one extra row to tell whether
+/// another page follows.
+fn execution_page_query(
+    filter: &ExecutionFilter,
+    page: &PageRequest,
+) -> Result<QueryBuilder<'static, Postgres>, CretoError> {
+    let mut query = QueryBuilder::new(EXECUTION_HISTORY_COLUMNS);
+    query.push(EXECUTION_HISTORY_FROM);
+    push_execution_filter(&mut query, filter);
+    if let PagePosition::After(cursor) = &page.position {
+        let cursor = ExecutionCursor::decode(cursor)?;
+        query
+            .push(" AND (e.queued_at, e.id) < (")
+            .push_bind(cursor.queued_at)
+            .push(", ")
+            .push_bind(cursor.id)
+            .push(")");
+    }
+    query
+        .push(" ORDER BY e.queued_at DESC, e.id DESC LIMIT ")
+        .push_bind(i64::from(page.effective_limit()) + 1);
+    if let PagePosition::Offset(offset) = page.position {
+        query
+            .push(" OFFSET ")
+            .push_bind(i64::try_from(offset).unwrap_or(i64::MAX));
+    }
+    Ok(query)
+}
+
+/// Build the query counting every execution matching `filter`.
+fn execution_count_query(filter: &ExecutionFilter) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) AS total");
+    query.push(EXECUTION_HISTORY_FROM);
+    push_execution_filter(&mut query, filter);
+    query
+}
+
+/// Build the per-status count query for execution stats.
+fn execution_status_counts_query(filter: &ExecutionFilter) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new("SELECT e.status, COUNT(*) AS count");
+    query.push(EXECUTION_HISTORY_FROM);
+    push_execution_filter(&mut query, filter);
+    query.push(" GROUP BY e.status");
+    query
+}
+
+/// Build the duration percentile query for execution stats.
+fn execution_percentiles_query(filter: &ExecutionFilter) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new(
+        "SELECT percentile_cont(0.5) WITHIN GROUP (ORDER BY e.duration_ms) AS p50, \
+         percentile_cont(0.95) WITHIN GROUP (ORDER BY e.duration_ms) AS p95",
+    );
+    query.push(EXECUTION_HISTORY_FROM);
+    push_execution_filter(&mut query, filter);
+    query.push(" AND e.duration_ms IS NOT NULL");
+    query
 }
 
 /// PostgreSQL implementation of ExecutionRepository.
@@ -339,18 +863,21 @@ impl ExecutionRepository for PgExecutionRepository {
         sandbox_id: SandboxId,
         code: &str,
         timeout_seconds: i32,
+        correlation: Correlation,
     ) -> Result<Uuid, CretoError> {
         let row = sqlx::query(
             r#"
             INSERT INTO execution_requests (
-                sandbox_id, code, timeout_seconds, status
-            ) VALUES ($1, $2, $3, 'queued')
+                sandbox_id, code, timeout_seconds, status, correlation_id, caused_by
+            ) VALUES ($1, $2, $3, 'queued', $4, $5)
             RETURNING id
             "#,
         )
         .bind(sandbox_id.as_uuid())
         .bind(code)
         .bind(timeout_seconds)
+        .bind(correlation.correlation_id)
+        .bind(correlation.caused_by)
         .fetch_one(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
@@ -361,7 +888,8 @@ impl ExecutionRepository for PgExecutionRepository {
     async fn get(&self, id: Uuid) -> Result<Option<ExecutionRecord>, CretoError> {
         let row = sqlx::query(
             r#"
-            SELECT sandbox_id, status, queued_at, started_at, completed_at, duration_ms
+            SELECT sandbox_id, status, queued_at, started_at, completed_at, duration_ms,
+                   correlation_id, caused_by
             FROM execution_requests
             WHERE id = $1
             "#,
@@ -379,6 +907,8 @@ impl ExecutionRepository for PgExecutionRepository {
             started_at: r.get("started_at"),
             completed_at: r.get("completed_at"),
             duration_ms: r.get("duration_ms"),
+            correlation_id: r.get("correlation_id"),
+            caused_by: r.get("caused_by"),
         }))
     }
 
@@ -438,7 +968,8 @@ impl ExecutionRepository for PgExecutionRepository {
     ) -> Result<Vec<ExecutionRecord>, CretoError> {
         let rows = sqlx::query(
             r#"
-            SELECT id, status, queued_at, started_at, completed_at, duration_ms
+            SELECT id, status, queued_at, started_at, completed_at, duration_ms,
+                   correlation_id, caused_by
             FROM execution_requests
             WHERE sandbox_id = $1 AND status IN ('queued', 'running')
             ORDER BY queued_at ASC
@@ -459,95 +990,1616 @@ impl ExecutionRepository for PgExecutionRepository {
                 started_at: r.get("started_at"),
                 completed_at: r.get("completed_at"),
                 duration_ms: r.get("duration_ms"),
+                correlation_id: r.get("correlation_id"),
+                caused_by: r.get("caused_by"),
             })
             .collect())
     }
-}
-
-// ─────────────────────────────────────────────────────────────────────────────
-// Resource Usage Repository
-// ─────────────────────────────────────────────────────────────────────────────
-
-/// Repository for resource usage tracking.
-#[async_trait::async_trait]
-pub trait ResourceUsageRepository: Send + Sync {
-    /// Record a resource usage snapshot.
-    async fn record(&self, sandbox_id: SandboxId, usage: &ResourceUsage) -> Result<(), CretoError>;
-
-    /// Get latest resource usage for a sandbox.
-    async fn get_latest(&self, sandbox_id: SandboxId) -> Result<Option<ResourceUsage>, CretoError>;
-}
-
-/// PostgreSQL implementation of ResourceUsageRepository.
-pub struct PgResourceUsageRepository {
-    pool: PgPool,
-}
-
-impl PgResourceUsageRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
-    }
-}
 
-#[async_trait::async_trait]
-impl ResourceUsageRepository for PgResourceUsageRepository {
-    async fn record(&self, sandbox_id: SandboxId, usage: &ResourceUsage) -> Result<(), CretoError> {
-        sqlx::query(
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<ExecutionRecord>, CretoError> {
+        let rows = sqlx::query(
             r#"
-            INSERT INTO resource_usage (
-                sandbox_id, memory_bytes, peak_memory_bytes, cpu_time_ms,
-                wall_time_ms, disk_bytes, process_count, open_file_count,
-                network_bytes_sent, network_bytes_received, connection_count
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            SELECT id, sandbox_id, status, queued_at, started_at, completed_at, duration_ms,
+                   correlation_id, caused_by
+            FROM execution_requests
+            WHERE correlation_id = $1
+            ORDER BY queued_at ASC
             "#,
         )
-        .bind(sandbox_id.as_uuid())
-        .bind(usage.memory_bytes as i64)
-        .bind(usage.peak_memory_bytes as i64)
-        .bind(usage.cpu_time_ms as i64)
-        .bind(usage.wall_time_ms as i64)
-        .bind(usage.disk_bytes as i64)
-        .bind(usage.process_count as i32)
-        .bind(usage.open_file_count as i32)
-        .bind(usage.network_bytes_sent as i64)
-        .bind(usage.network_bytes_received as i64)
-        .bind(usage.connection_count as i32)
-        .execute(&self.pool)
+        .bind(correlation_id)
+        .fetch_all(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(())
-    }
-
-    async fn get_latest(&self, sandbox_id: SandboxId) -> Result<Option<ResourceUsage>, CretoError> {
-        let row = sqlx::query(
-            r#"
-            SELECT memory_bytes, peak_memory_bytes, cpu_time_ms, wall_time_ms,
-                   disk_bytes, process_count, open_file_count,
-                   network_bytes_sent, network_bytes_received, connection_count
-            FROM resource_usage
-            WHERE sandbox_id = $1
+        Ok(rows
+            .into_iter()
+            .map(|r| ExecutionRecord {
+                id: r.get("id"),
+                sandbox_id: SandboxId::from_uuid(r.get::<Uuid, _>("sandbox_id")),
+                status: ExecutionStatus::parse_db_str(r.get::<&str, _>("status")),
+                queued_at: r.get("queued_at"),
+                started_at: r.get("started_at"),
+                completed_at: r.get("completed_at"),
+                duration_ms: r.get("duration_ms"),
+                correlation_id: r.get("correlation_id"),
+                caused_by: r.get("caused_by"),
+            })
+            .collect())
+    }
+
+    async fn find(
+        &self,
+        filter: &ExecutionFilter,
+        page: &PageRequest,
+    ) -> Result<Page<ExecutionRecord>, CretoError> {
+        let rows = execution_page_query(filter, page)?
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let mut items: Vec<ExecutionRecord> = rows
+            .into_iter()
+            .map(|r| ExecutionRecord {
+                id: r.get("id"),
+                sandbox_id: SandboxId::from_uuid(r.get::<Uuid, _>("sandbox_id")),
+                status: ExecutionStatus::parse_db_str(r.get::<&str, _>("status")),
+                queued_at: r.get("queued_at"),
+                started_at: r.get("started_at"),
+                completed_at: r.get("completed_at"),
+                duration_ms: r.get("duration_ms"),
+                correlation_id: r.get("correlation_id"),
+                caused_by: r.get("caused_by"),
+            })
+            .collect();
+        let limit = page.effective_limit() as usize;
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|r| ExecutionCursor::after(r).encode())
+        } else {
+            None
+        };
+
+        let total = if page.include_total {
+            let row = execution_count_query(filter)
+                .build()
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| CretoError::Database(e.to_string()))?;
+            Some(row.get::<i64, _>("total") as u64)
+        } else {
+            None
+        };
+
+        Ok(Page {
+            items,
+            next_cursor,
+            total,
+        })
+    }
+
+    async fn stats(
+        &self,
+        organization_id: OrganizationId,
+        range: TimeRange,
+    ) -> Result<ExecutionStats, CretoError> {
+        let filter = ExecutionFilter::new()
+            .for_organization(organization_id)
+            .queued_within(range);
+
+        let rows = execution_status_counts_query(&filter)
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+        let mut by_status = std::collections::HashMap::new();
+        for r in rows {
+            *by_status
+                .entry(ExecutionStatus::parse_db_str(r.get::<&str, _>("status")))
+                .or_insert(0) += r.get::<i64, _>("count") as u64;
+        }
+
+        let row = execution_percentiles_query(&filter)
+            .build()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(ExecutionStats {
+            organization_id,
+            range,
+            total: by_status.values().sum(),
+            by_status,
+            p50_duration_ms: row.get("p50"),
+            p95_duration_ms: row.get("p95"),
+        })
+    }
+
+    async fn record_idempotent_result(
+        &self,
+        record: &IdempotentExecution,
+    ) -> Result<(), CretoError> {
+        let result = serde_json::to_value(&record.result)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            UPDATE execution_requests
+            SET idempotency_key = $2, idempotency_fingerprint = $3,
+                idempotent_result = $4, idempotent_recorded_at = $5
+            WHERE id = $1 AND sandbox_id = $6
+            "#,
+        )
+        .bind(record.result.request_id)
+        .bind(&record.key)
+        .bind(&record.fingerprint)
+        .bind(result)
+        .bind(record.recorded_at)
+        .bind(record.sandbox_id.as_uuid())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn find_idempotent_result(
+        &self,
+        sandbox_id: SandboxId,
+        key: &str,
+    ) -> Result<Option<IdempotentExecution>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT idempotency_fingerprint, idempotent_result, idempotent_recorded_at
+            FROM execution_requests
+            WHERE sandbox_id = $1 AND idempotency_key = $2 AND idempotent_result IS NOT NULL
+            ORDER BY idempotent_recorded_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(sandbox_id.as_uuid())
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| {
+            let result = serde_json::from_value(r.get("idempotent_result"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+            Ok(IdempotentExecution {
+                sandbox_id,
+                key: key.to_string(),
+                fingerprint: r.get("idempotency_fingerprint"),
+                result,
+                recorded_at: r.get("idempotent_recorded_at"),
+            })
+        })
+        .transpose()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Resource Usage Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Repository for resource usage tracking.
+#[async_trait::async_trait]
+pub trait ResourceUsageRepository: Send + Sync {
+    /// Record a resource usage snapshot.
+    async fn record(&self, sandbox_id: SandboxId, usage: &ResourceUsage) -> Result<(), CretoError>;
+
+    /// Get latest resource usage for a sandbox.
+    async fn get_latest(&self, sandbox_id: SandboxId) -> Result<Option<ResourceUsage>, CretoError>;
+
+    /// Record the usage time series captured during an execution.
+    async fn record_samples(
+        &self,
+        sandbox_id: SandboxId,
+        execution_id: Uuid,
+        samples: &[UsageSample],
+    ) -> Result<(), CretoError>;
+
+    /// Get the usage time series for an execution.
+    async fn get_samples(&self, execution_id: Uuid) -> Result<Vec<UsageSample>, CretoError>;
+}
+
+/// PostgreSQL implementation of ResourceUsageRepository.
+///
+/// Usage time series can be large, so persisting them is opt-in via
+/// [`PgResourceUsageRepository::with_sample_persistence`].
+pub struct PgResourceUsageRepository {
+    pool: PgPool,
+    persist_samples: bool,
+}
+
+impl PgResourceUsageRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            persist_samples: false,
+        }
+    }
+
+    /// Enable or disable persisting usage time series.
+    pub fn with_sample_persistence(mut self, enabled: bool) -> Self {
+        self.persist_samples = enabled;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl ResourceUsageRepository for PgResourceUsageRepository {
+    async fn record(&self, sandbox_id: SandboxId, usage: &ResourceUsage) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            INSERT INTO resource_usage (
+                sandbox_id, memory_bytes, peak_memory_bytes, cpu_time_ms,
+                wall_time_ms, disk_bytes, process_count, open_file_count,
+                network_bytes_sent, network_bytes_received, connection_count,
+                gpu_time_ms, gpu_memory_bytes
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            "#,
+        )
+        .bind(sandbox_id.as_uuid())
+        .bind(usage.memory_bytes as i64)
+        .bind(usage.peak_memory_bytes as i64)
+        .bind(usage.cpu_time_ms as i64)
+        .bind(usage.wall_time_ms as i64)
+        .bind(usage.disk_bytes as i64)
+        .bind(usage.process_count as i32)
+        .bind(usage.open_file_count as i32)
+        .bind(usage.network_bytes_sent as i64)
+        .bind(usage.network_bytes_received as i64)
+        .bind(usage.connection_count as i32)
+        .bind(usage.gpu_time_ms as i64)
+        .bind(usage.gpu_memory_bytes as i64)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_latest(&self, sandbox_id: SandboxId) -> Result<Option<ResourceUsage>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT memory_bytes, peak_memory_bytes, cpu_time_ms, wall_time_ms,
+                   disk_bytes, process_count, open_file_count,
+                   network_bytes_sent, network_bytes_received, connection_count,
+                   gpu_time_ms, gpu_memory_bytes
+            FROM resource_usage
+            WHERE sandbox_id = $1
             ORDER BY recorded_at DESC
             LIMIT 1
             "#,
         )
-        .bind(sandbox_id.as_uuid())
+        .bind(sandbox_id.as_uuid())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.map(|r| ResourceUsage {
+            memory_bytes: r.get::<i64, _>("memory_bytes") as u64,
+            peak_memory_bytes: r.get::<i64, _>("peak_memory_bytes") as u64,
+            cpu_time_ms: r.get::<i64, _>("cpu_time_ms") as u64,
+            wall_time_ms: r.get::<i64, _>("wall_time_ms") as u64,
+            disk_bytes: r.get::<i64, _>("disk_bytes") as u64,
+            process_count: r.get::<i32, _>("process_count") as u32,
+            open_file_count: r.get::<i32, _>("open_file_count") as u32,
+            network_bytes_sent: r.get::<i64, _>("network_bytes_sent") as u64,
+            network_bytes_received: r.get::<i64, _>("network_bytes_received") as u64,
+            connection_count: r.get::<i32, _>("connection_count") as u32,
+            gpu_time_ms: r.get::<i64, _>("gpu_time_ms") as u64,
+            gpu_memory_bytes: r.get::<i64, _>("gpu_memory_bytes") as u64,
+        }))
+    }
+
+    async fn record_samples(
+        &self,
+        sandbox_id: SandboxId,
+        execution_id: Uuid,
+        samples: &[UsageSample],
+    ) -> Result<(), CretoError> {
+        if !self.persist_samples || samples.is_empty() {
+            return Ok(());
+        }
+
+        let samples_json = serde_json::to_value(samples)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO resource_usage_samples (
+                execution_id, sandbox_id, samples, sample_count
+            ) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (execution_id) DO UPDATE
+            SET samples = EXCLUDED.samples,
+                sample_count = EXCLUDED.sample_count,
+                recorded_at = NOW()
+            "#,
+        )
+        .bind(execution_id)
+        .bind(sandbox_id.as_uuid())
+        .bind(&samples_json)
+        .bind(samples.len() as i32)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get_samples(&self, execution_id: Uuid) -> Result<Vec<UsageSample>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT samples
+            FROM resource_usage_samples
+            WHERE execution_id = $1
+            "#,
+        )
+        .bind(execution_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        match row {
+            Some(r) => serde_json::from_value(r.get::<serde_json::Value, _>("samples"))
+                .map_err(|e| CretoError::SerializationError(e.to_string())),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Network Policy Template Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of NetworkPolicyTemplateStore.
+pub struct PgNetworkPolicyTemplateRepository {
+    pool: PgPool,
+}
+
+impl PgNetworkPolicyTemplateRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn template_from_row(
+        organization_id: OrganizationId,
+        row: &sqlx::postgres::PgRow,
+    ) -> Result<NetworkPolicyTemplate, CretoError> {
+        Ok(NetworkPolicyTemplate {
+            organization_id,
+            name: row.get("name"),
+            version: row.get::<i32, _>("version") as u32,
+            policy: serde_json::from_value(row.get::<serde_json::Value, _>("policy"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl NetworkPolicyTemplateStore for PgNetworkPolicyTemplateRepository {
+    async fn create(
+        &self,
+        template: NetworkPolicyTemplate,
+    ) -> Result<NetworkPolicyTemplate, CretoError> {
+        let policy_json = serde_json::to_value(&template.policy)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO network_policy_templates (
+                organization_id, name, version, policy, updated_at
+            ) VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(template.organization_id.as_uuid())
+        .bind(&template.name)
+        .bind(template.version as i32)
+        .bind(&policy_json)
+        .bind(template.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(template)
+    }
+
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+    ) -> Result<Option<NetworkPolicyTemplate>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT name, version, policy, updated_at
+            FROM network_policy_templates
+            WHERE organization_id = $1 AND name = $2
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| Self::template_from_row(organization_id, &r))
+            .transpose()
+    }
+
+    async fn update(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+        policy: NetworkPolicy,
+    ) -> Result<NetworkPolicyTemplate, CretoError> {
+        let policy_json = serde_json::to_value(&policy)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        let row = sqlx::query(
+            r#"
+            UPDATE network_policy_templates
+            SET policy = $3, version = version + 1, updated_at = NOW()
+            WHERE organization_id = $1 AND name = $2
+            RETURNING name, version, policy, updated_at
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(name)
+        .bind(&policy_json)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?
+        .ok_or_else(|| CretoError::NotFound(format!("network policy template '{}'", name)))?;
+
+        Self::template_from_row(organization_id, &row)
+    }
+
+    async fn delete(&self, organization_id: OrganizationId, name: &str) -> Result<(), CretoError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM network_policy_templates
+            WHERE organization_id = $1 AND name = $2
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(name)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(CretoError::NotFound(format!(
+                "network policy template '{}'",
+                name
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<Vec<NetworkPolicyTemplate>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT name, version, policy, updated_at
+            FROM network_policy_templates
+            WHERE organization_id = $1
+            ORDER BY name
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter()
+            .map(|r| Self::template_from_row(organization_id, r))
+            .collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Organization Runtime Policy Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of OrgRuntimePolicyStore.
+///
+/// The policy is stored as JSON alongside its version so reads never depend
+/// on the shape of individual policy fields.
+pub struct PgOrgRuntimePolicyRepository {
+    pool: PgPool,
+}
+
+impl PgOrgRuntimePolicyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn policy_from_row(row: &sqlx::postgres::PgRow) -> Result<OrgRuntimePolicy, CretoError> {
+        let mut policy: OrgRuntimePolicy =
+            serde_json::from_value(row.get::<serde_json::Value, _>("policy"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        policy.version = row.get::<i32, _>("version") as u32;
+        policy.updated_at = row.get("updated_at");
+        Ok(policy)
+    }
+}
+
+#[async_trait::async_trait]
+impl OrgRuntimePolicyStore for PgOrgRuntimePolicyRepository {
+    async fn get(
+        &self,
+        organization_id: OrganizationId,
+    ) -> Result<Option<OrgRuntimePolicy>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT policy, version, updated_at
+            FROM org_runtime_policies
+            WHERE organization_id = $1
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| Self::policy_from_row(&r)).transpose()
+    }
+
+    async fn put(&self, policy: OrgRuntimePolicy) -> Result<OrgRuntimePolicy, CretoError> {
+        let policy_json = serde_json::to_value(&policy)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        let row = sqlx::query(
+            r#"
+            INSERT INTO org_runtime_policies (organization_id, policy, version, updated_at)
+            VALUES ($1, $2, 1, NOW())
+            ON CONFLICT (organization_id) DO UPDATE
+            SET policy = EXCLUDED.policy,
+                version = org_runtime_policies.version + 1,
+                updated_at = NOW()
+            RETURNING policy, version, updated_at
+            "#,
+        )
+        .bind(policy.organization_id.as_uuid())
+        .bind(&policy_json)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Self::policy_from_row(&row)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Secret Grant Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of SecretGrantStore.
+pub struct PgSecretGrantRepository {
+    pool: PgPool,
+}
+
+impl PgSecretGrantRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn grant_from_row(row: &sqlx::postgres::PgRow) -> SecretGrant {
+        SecretGrant {
+            id: row.get("id"),
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            sandbox_id: SandboxId::from_uuid(row.get::<Uuid, _>("sandbox_id")),
+            agent_id: AgentId::from_uuid(row.get::<Uuid, _>("agent_id")),
+            secret_name: row.get("secret_name"),
+            secret_reference: row.get("secret_reference"),
+            target: SecretMountTarget::parse_db_str(row.get::<&str, _>("target")),
+            access_detection: row.get("access_detection"),
+            idle_policy: SecretIdlePolicy::parse_db_str(row.get::<&str, _>("idle_policy")),
+            granted_at: row.get("granted_at"),
+            first_accessed_at: row.get("first_accessed_at"),
+            revoked_at: row.get("revoked_at"),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SecretGrantStore for PgSecretGrantRepository {
+    async fn record_grant(&self, grant: &SecretGrant) -> Result<(), CretoError> {
+        sqlx::query(
+            r#"
+            INSERT INTO secret_grants (
+                id, organization_id, sandbox_id, agent_id, secret_name,
+                secret_reference, target, access_detection, idle_policy, granted_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(grant.id)
+        .bind(grant.organization_id.as_uuid())
+        .bind(grant.sandbox_id.as_uuid())
+        .bind(grant.agent_id.as_uuid())
+        .bind(&grant.secret_name)
+        .bind(&grant.secret_reference)
+        .bind(grant.target.as_str())
+        .bind(grant.access_detection)
+        .bind(grant.idle_policy.as_str())
+        .bind(grant.granted_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn revoke_sandbox(
+        &self,
+        sandbox_id: SandboxId,
+        revoked_at: DateTime<Utc>,
+    ) -> Result<Vec<SecretGrant>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE secret_grants
+            SET revoked_at = $2
+            WHERE sandbox_id = $1 AND revoked_at IS NULL
+            RETURNING id, organization_id, sandbox_id, agent_id, secret_name,
+                      secret_reference, target, access_detection, idle_policy, granted_at,
+                      first_accessed_at, revoked_at
+            "#,
+        )
+        .bind(sandbox_id.as_uuid())
+        .bind(revoked_at)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        let mut grants: Vec<_> = rows.iter().map(Self::grant_from_row).collect();
+        grants.sort_by_key(|g| (g.granted_at, g.id));
+        Ok(grants)
+    }
+
+    async fn revoke_grant(
+        &self,
+        grant_id: Uuid,
+        revoked_at: DateTime<Utc>,
+    ) -> Result<Option<SecretGrant>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            UPDATE secret_grants
+            SET revoked_at = $2
+            WHERE id = $1 AND revoked_at IS NULL
+            RETURNING id, organization_id, sandbox_id, agent_id, secret_name,
+                      secret_reference, target, access_detection, idle_policy, granted_at,
+                      first_accessed_at, revoked_at
+            "#,
+        )
+        .bind(grant_id)
+        .bind(revoked_at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.as_ref().map(Self::grant_from_row))
+    }
+
+    async fn record_first_access(
+        &self,
+        grant_id: Uuid,
+        accessed_at: DateTime<Utc>,
+    ) -> Result<Option<SecretGrant>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            UPDATE secret_grants
+            SET first_accessed_at = $2
+            WHERE id = $1 AND first_accessed_at IS NULL
+            RETURNING id, organization_id, sandbox_id, agent_id, secret_name,
+                      secret_reference, target, access_detection, idle_policy, granted_at,
+                      first_accessed_at, revoked_at
+            "#,
+        )
+        .bind(grant_id)
+        .bind(accessed_at)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(row.as_ref().map(Self::grant_from_row))
+    }
+
+    async fn list_by_sandbox(&self, sandbox_id: SandboxId) -> Result<Vec<SecretGrant>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, sandbox_id, agent_id, secret_name,
+                   secret_reference, target, access_detection, idle_policy, granted_at,
+                   first_accessed_at, revoked_at
+            FROM secret_grants
+            WHERE sandbox_id = $1
+            ORDER BY granted_at, id
+            "#,
+        )
+        .bind(sandbox_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows.iter().map(Self::grant_from_row).collect())
+    }
+
+    async fn list_by_org(
+        &self,
+        organization_id: OrganizationId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<SecretGrant>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, organization_id, sandbox_id, agent_id, secret_name,
+                   secret_reference, target, access_detection, idle_policy, granted_at,
+                   first_accessed_at, revoked_at
+            FROM secret_grants
+            WHERE organization_id = $1
+              AND granted_at < $3
+              AND (revoked_at IS NULL OR revoked_at >= $2)
+            ORDER BY granted_at, id
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows.iter().map(Self::grant_from_row).collect())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Execution Log Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of ExecutionLogStore.
+pub struct PgExecutionLogRepository {
+    pool: PgPool,
+}
+
+impl PgExecutionLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutionLogStore for PgExecutionLogRepository {
+    async fn append(&self, execution_id: Uuid, records: &[LogRecord]) -> Result<(), CretoError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        // Number this batch after any earlier one, serializing appends to
+        // the same execution so they cannot pick the same sequence numbers
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1::text, 0))")
+            .bind(execution_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+        let next_seq: i32 = sqlx::query(
+            "SELECT COALESCE(MAX(seq) + 1, 0) AS next_seq FROM execution_logs WHERE execution_id = $1",
+        )
+        .bind(execution_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?
+        .get("next_seq");
+
+        for (seq, record) in (next_seq..).zip(records) {
+            let fields_json = serde_json::Value::Object(record.fields.clone());
+            sqlx::query(
+                r#"
+                INSERT INTO execution_logs (
+                    execution_id, seq, level, message, fields, logged_at
+                ) VALUES ($1, $2, $3, $4, $5, $6)
+                "#,
+            )
+            .bind(execution_id)
+            .bind(seq)
+            .bind(record.level.as_str())
+            .bind(&record.message)
+            .bind(&fields_json)
+            .bind(record.timestamp)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list(
+        &self,
+        execution_id: Uuid,
+        min_level: Option<LogLevel>,
+        page: LogPage,
+    ) -> Result<Vec<LogRecord>, CretoError> {
+        let levels: Vec<String> = LogLevel::ALL
+            .into_iter()
+            .filter(|l| !matches!(min_level, Some(min) if *l < min))
+            .map(|l| l.as_str().to_string())
+            .collect();
+
+        let rows = sqlx::query(
+            r#"
+            SELECT level, message, fields, logged_at
+            FROM execution_logs
+            WHERE execution_id = $1 AND level = ANY($2)
+            ORDER BY seq ASC
+            OFFSET $3
+            LIMIT $4
+            "#,
+        )
+        .bind(execution_id)
+        .bind(&levels)
+        .bind(page.offset as i64)
+        .bind(page.limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|r| LogRecord {
+                timestamp: r.get("logged_at"),
+                level: LogLevel::parse_db_str(r.get::<&str, _>("level")),
+                message: r.get("message"),
+                fields: match r.get::<serde_json::Value, _>("fields") {
+                    serde_json::Value::Object(fields) => fields,
+                    _ => serde_json::Map::new(),
+                },
+            })
+            .collect())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Filesystem Diff Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of FilesystemDiffStore.
+pub struct PgFilesystemDiffRepository {
+    pool: PgPool,
+}
+
+impl PgFilesystemDiffRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait::async_trait]
+impl FilesystemDiffStore for PgFilesystemDiffRepository {
+    async fn put(&self, diff: &FilesystemDiff) -> Result<(), CretoError> {
+        let summary = diff.summary();
+        let changes_json = serde_json::to_value(&diff.changes)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO execution_fs_diffs (
+                execution_id, created_count, modified_count, deleted_count,
+                bytes_changed, truncated, changes
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (execution_id) DO UPDATE SET
+                created_count = $2,
+                modified_count = $3,
+                deleted_count = $4,
+                bytes_changed = $5,
+                truncated = $6,
+                changes = $7
+            "#,
+        )
+        .bind(diff.execution_id)
+        .bind(summary.created as i32)
+        .bind(summary.modified as i32)
+        .bind(summary.deleted as i32)
+        .bind(summary.bytes_changed as i64)
+        .bind(diff.truncated)
+        .bind(&changes_json)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, execution_id: Uuid) -> Result<Option<FilesystemDiff>, CretoError> {
+        let row = sqlx::query(
+            r#"
+            SELECT truncated, changes
+            FROM execution_fs_diffs
+            WHERE execution_id = $1
+            "#,
+        )
+        .bind(execution_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| {
+            Ok(FilesystemDiff {
+                execution_id,
+                changes: serde_json::from_value(r.get("changes"))
+                    .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                truncated: r.get("truncated"),
+            })
+        })
+        .transpose()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Schedule Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of ScheduleStore.
+///
+/// Claims use `FOR UPDATE SKIP LOCKED`, so runtime instances sharing the
+/// database claim disjoint sets of due schedules.
+pub struct PgScheduleRepository {
+    pool: PgPool,
+}
+
+impl PgScheduleRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn schedule_from_row(row: &sqlx::postgres::PgRow) -> Result<ExecutionSchedule, CretoError> {
+        Ok(ExecutionSchedule {
+            id: row.get("id"),
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            agent_id: AgentId::from_uuid(row.get::<Uuid, _>("agent_id")),
+            template: serde_json::from_value(row.get("template"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            trigger: serde_json::from_value(row.get("trigger"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            enabled: row.get("enabled"),
+            catch_up: CatchUpPolicy::parse_db_str(row.get::<&str, _>("catch_up")),
+            next_run_at: row.get("next_run_at"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    fn schedule_json(
+        schedule: &ExecutionSchedule,
+    ) -> Result<(serde_json::Value, serde_json::Value), CretoError> {
+        let template = serde_json::to_value(&schedule.template)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        let trigger = serde_json::to_value(&schedule.trigger)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+        Ok((template, trigger))
+    }
+}
+
+const SCHEDULE_COLUMNS: &str = "id, organization_id, agent_id, template, trigger, enabled, \
+     catch_up, next_run_at, created_at, updated_at";
+
+#[async_trait::async_trait]
+impl ScheduleStore for PgScheduleRepository {
+    async fn create(&self, schedule: &ExecutionSchedule) -> Result<(), CretoError> {
+        let (template, trigger) = Self::schedule_json(schedule)?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO execution_schedules (
+                id, organization_id, agent_id, template, trigger, enabled,
+                catch_up, next_run_at, created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(schedule.organization_id.as_uuid())
+        .bind(schedule.agent_id.as_uuid())
+        .bind(&template)
+        .bind(&trigger)
+        .bind(schedule.enabled)
+        .bind(schedule.catch_up.as_str())
+        .bind(schedule.next_run_at)
+        .bind(schedule.created_at)
+        .bind(schedule.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, id: ScheduleId) -> Result<Option<ExecutionSchedule>, CretoError> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM execution_schedules WHERE id = $1",
+            SCHEDULE_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        row.map(|r| Self::schedule_from_row(&r)).transpose()
+    }
+
+    async fn list_by_org(
+        &self,
+        org_id: OrganizationId,
+    ) -> Result<Vec<ExecutionSchedule>, CretoError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM execution_schedules WHERE organization_id = $1 ORDER BY created_at",
+            SCHEDULE_COLUMNS
+        ))
+        .bind(org_id.as_uuid())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::schedule_from_row).collect()
+    }
+
+    async fn update(&self, schedule: &ExecutionSchedule) -> Result<(), CretoError> {
+        let (template, trigger) = Self::schedule_json(schedule)?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE execution_schedules
+            SET template = $2, trigger = $3, enabled = $4, catch_up = $5,
+                next_run_at = $6, updated_at = $7,
+                claim_token = NULL, claim_expires_at = NULL
+            WHERE id = $1
+            "#,
+        )
+        .bind(schedule.id)
+        .bind(&template)
+        .bind(&trigger)
+        .bind(schedule.enabled)
+        .bind(schedule.catch_up.as_str())
+        .bind(schedule.next_run_at)
+        .bind(schedule.updated_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(CretoError::NotFound(format!("Schedule {}", schedule.id)));
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, id: ScheduleId) -> Result<bool, CretoError> {
+        let result = sqlx::query("DELETE FROM execution_schedules WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        lease: chrono::Duration,
+        limit: usize,
+    ) -> Result<Vec<ScheduleClaim>, CretoError> {
+        let token = Uuid::now_v7();
+        let expires_at = now + lease;
+
+        let rows = sqlx::query(&format!(
+            r#"
+            UPDATE execution_schedules
+            SET claim_token = $1, claim_expires_at = $2
+            WHERE id IN (
+                SELECT id FROM execution_schedules
+                WHERE enabled
+                  AND next_run_at <= $3
+                  AND (claim_expires_at IS NULL OR claim_expires_at <= $3)
+                ORDER BY next_run_at
+                LIMIT $4
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING {}
+            "#,
+            SCHEDULE_COLUMNS
+        ))
+        .bind(token)
+        .bind(expires_at)
+        .bind(now)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter()
+            .map(|r| {
+                Ok(ScheduleClaim {
+                    schedule: Self::schedule_from_row(r)?,
+                    token,
+                    expires_at,
+                })
+            })
+            .collect()
+    }
+
+    async fn complete_claim(
+        &self,
+        claim: &ScheduleClaim,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE execution_schedules
+            SET next_run_at = $3, claim_token = NULL, claim_expires_at = NULL
+            WHERE id = $1 AND claim_token = $2 AND enabled
+            "#,
+        )
+        .bind(claim.schedule.id)
+        .bind(claim.token)
+        .bind(next_run_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_run(&self, run: &ScheduleRun) -> Result<(), CretoError> {
+        let outcome = serde_json::to_value(&run.outcome)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO execution_schedule_runs (
+                id, schedule_id, scheduled_for, recorded_at, execution_id, missed, outcome
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(run.id)
+        .bind(run.schedule_id)
+        .bind(run.scheduled_for)
+        .bind(run.recorded_at)
+        .bind(run.execution_id)
+        .bind(run.missed as i32)
+        .bind(&outcome)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_runs(
+        &self,
+        schedule_id: ScheduleId,
+        page: ScheduleRunPage,
+    ) -> Result<Vec<ScheduleRun>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, scheduled_for, recorded_at, execution_id, missed, outcome
+            FROM execution_schedule_runs
+            WHERE schedule_id = $1
+            ORDER BY recorded_at DESC, id DESC
+            OFFSET $2
+            LIMIT $3
+            "#,
+        )
+        .bind(schedule_id)
+        .bind(page.offset as i64)
+        .bind(page.limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter()
+            .map(|r| {
+                Ok(ScheduleRun {
+                    id: r.get("id"),
+                    schedule_id,
+                    scheduled_for: r.get("scheduled_for"),
+                    recorded_at: r.get("recorded_at"),
+                    execution_id: r.get("execution_id"),
+                    missed: r.get::<i32, _>("missed") as u32,
+                    outcome: serde_json::from_value(r.get("outcome"))
+                        .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                })
+            })
+            .collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Topic Trigger Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of TopicTriggerStore.
+///
+/// Retries and dead letters reference their trigger, so deleting a trigger
+/// deletes them and a late write for a deleted trigger stores nothing.
+pub struct PgTopicTriggerRepository {
+    pool: PgPool,
+}
+
+impl PgTopicTriggerRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn trigger_from_row(row: &sqlx::postgres::PgRow) -> Result<StoredTopicTrigger, CretoError> {
+        Ok(StoredTopicTrigger {
+            trigger: serde_json::from_value(row.get("trigger"))
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            subscription_id: row.get("subscription_id"),
+            cursor: row.get("cursor"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+const TOPIC_TRIGGER_COLUMNS: &str = "trigger, subscription_id, cursor, created_at";
+
+#[async_trait::async_trait]
+impl TopicTriggerStore for PgTopicTriggerRepository {
+    async fn create(&self, trigger: &StoredTopicTrigger) -> Result<(), CretoError> {
+        let json = serde_json::to_value(&trigger.trigger)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO topic_triggers (
+                id, organization_id, trigger, subscription_id, cursor, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(trigger.trigger.id)
+        .bind(trigger.trigger.organization_id.as_uuid())
+        .bind(&json)
+        .bind(trigger.subscription_id)
+        .bind(trigger.cursor)
+        .bind(trigger.created_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, id: TopicTriggerId) -> Result<Option<StoredTopicTrigger>, CretoError> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM topic_triggers WHERE id = $1",
+            TOPIC_TRIGGER_COLUMNS
+        ))
+        .bind(id)
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| CretoError::Database(e.to_string()))?;
 
-        Ok(row.map(|r| ResourceUsage {
-            memory_bytes: r.get::<i64, _>("memory_bytes") as u64,
-            peak_memory_bytes: r.get::<i64, _>("peak_memory_bytes") as u64,
-            cpu_time_ms: r.get::<i64, _>("cpu_time_ms") as u64,
-            wall_time_ms: r.get::<i64, _>("wall_time_ms") as u64,
-            disk_bytes: r.get::<i64, _>("disk_bytes") as u64,
-            process_count: r.get::<i32, _>("process_count") as u32,
-            open_file_count: r.get::<i32, _>("open_file_count") as u32,
-            network_bytes_sent: r.get::<i64, _>("network_bytes_sent") as u64,
-            network_bytes_received: r.get::<i64, _>("network_bytes_received") as u64,
-            connection_count: r.get::<i32, _>("connection_count") as u32,
-        }))
+        row.map(|r| Self::trigger_from_row(&r)).transpose()
+    }
+
+    async fn list(&self) -> Result<Vec<StoredTopicTrigger>, CretoError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM topic_triggers ORDER BY created_at, id",
+            TOPIC_TRIGGER_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter().map(Self::trigger_from_row).collect()
+    }
+
+    async fn delete(&self, id: TopicTriggerId) -> Result<bool, CretoError> {
+        let result = sqlx::query("DELETE FROM topic_triggers WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_cursor(&self, id: TopicTriggerId, cursor: Uuid) -> Result<(), CretoError> {
+        sqlx::query("UPDATE topic_triggers SET cursor = $2 WHERE id = $1")
+            .bind(id)
+            .bind(cursor)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn put_retry(&self, retry: &TriggerRetry) -> Result<(), CretoError> {
+        let message = serde_json::to_value(&retry.message)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO topic_trigger_retries (
+                trigger_id, message_id, message, failures, next_attempt_at
+            )
+            SELECT $1, $2, $3, $4, $5
+            WHERE EXISTS (SELECT 1 FROM topic_triggers WHERE id = $1)
+            ON CONFLICT (trigger_id, message_id) DO UPDATE
+            SET failures = EXCLUDED.failures, next_attempt_at = EXCLUDED.next_attempt_at
+            "#,
+        )
+        .bind(retry.trigger_id)
+        .bind(retry.message.id)
+        .bind(&message)
+        .bind(retry.failures as i32)
+        .bind(retry.next_attempt_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete_retry(&self, id: TopicTriggerId, message_id: Uuid) -> Result<(), CretoError> {
+        sqlx::query("DELETE FROM topic_trigger_retries WHERE trigger_id = $1 AND message_id = $2")
+            .bind(id)
+            .bind(message_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn due_retries(
+        &self,
+        id: TopicTriggerId,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<TriggerRetry>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT message, failures, next_attempt_at
+            FROM topic_trigger_retries
+            WHERE trigger_id = $1 AND next_attempt_at <= $2
+            ORDER BY next_attempt_at
+            "#,
+        )
+        .bind(id)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter()
+            .map(|r| {
+                Ok(TriggerRetry {
+                    trigger_id: id,
+                    message: serde_json::from_value(r.get("message"))
+                        .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                    failures: r.get::<i32, _>("failures") as u32,
+                    next_attempt_at: r.get("next_attempt_at"),
+                })
+            })
+            .collect()
+    }
+
+    async fn put_dead_letter(&self, dead_letter: &TriggerDeadLetter) -> Result<(), CretoError> {
+        let message = serde_json::to_value(&dead_letter.message)
+            .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO topic_trigger_dead_letters (
+                trigger_id, message, attempts, last_error, dead_lettered_at
+            )
+            SELECT $1, $2, $3, $4, $5
+            WHERE EXISTS (SELECT 1 FROM topic_triggers WHERE id = $1)
+            "#,
+        )
+        .bind(dead_letter.trigger_id)
+        .bind(&message)
+        .bind(dead_letter.attempts as i32)
+        .bind(&dead_letter.last_error)
+        .bind(dead_letter.dead_lettered_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn list_dead_letters(
+        &self,
+        id: TopicTriggerId,
+    ) -> Result<Vec<TriggerDeadLetter>, CretoError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT message, attempts, last_error, dead_lettered_at
+            FROM topic_trigger_dead_letters
+            WHERE trigger_id = $1
+            ORDER BY dead_lettered_at, id
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        rows.iter()
+            .map(|r| {
+                Ok(TriggerDeadLetter {
+                    trigger_id: id,
+                    message: serde_json::from_value(r.get("message"))
+                        .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+                    attempts: r.get::<i32, _>("attempts") as u32,
+                    last_error: r.get("last_error"),
+                    dead_lettered_at: r.get("dead_lettered_at"),
+                })
+            })
+            .collect()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Exclusion Lease Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// PostgreSQL implementation of ExclusionLeaseStore.
+pub struct PgExclusionLeaseRepository {
+    pool: PgPool,
+}
+
+impl PgExclusionLeaseRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    fn lease_from_row(row: &sqlx::postgres::PgRow) -> ExclusionLease {
+        ExclusionLease {
+            organization_id: OrganizationId::from_uuid(row.get::<Uuid, _>("organization_id")),
+            key: row.get("exclusion_key"),
+            execution_id: row.get("execution_id"),
+            priority: ExecutionPriority::parse_db_str(row.get::<&str, _>("priority")),
+            owner: row.get("owner"),
+            acquired_at: row.get("acquired_at"),
+            expires_at: row.get("expires_at"),
+        }
+    }
+}
+
+const EXCLUSION_LEASE_COLUMNS: &str =
+    "organization_id, exclusion_key, execution_id, priority, owner, acquired_at, expires_at";
+
+#[async_trait::async_trait]
+impl ExclusionLeaseStore for PgExclusionLeaseRepository {
+    async fn acquire(
+        &self,
+        lease: &ExclusionLease,
+        now: DateTime<Utc>,
+    ) -> Result<ExclusionLease, CretoError> {
+        // Take the key if it is free, ours, or lapsed; otherwise read the holder
+        let taken = sqlx::query(&format!(
+            r#"
+            INSERT INTO runtime_exclusion_leases ({cols})
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (organization_id, exclusion_key) DO UPDATE
+            SET execution_id = EXCLUDED.execution_id,
+                priority = EXCLUDED.priority,
+                owner = EXCLUDED.owner,
+                acquired_at = EXCLUDED.acquired_at,
+                expires_at = EXCLUDED.expires_at
+            WHERE runtime_exclusion_leases.owner = EXCLUDED.owner
+               OR runtime_exclusion_leases.expires_at <= $8
+            RETURNING {cols}
+            "#,
+            cols = EXCLUSION_LEASE_COLUMNS
+        ))
+        .bind(lease.organization_id.as_uuid())
+        .bind(&lease.key)
+        .bind(lease.execution_id)
+        .bind(lease.priority.as_str())
+        .bind(&lease.owner)
+        .bind(lease.acquired_at)
+        .bind(lease.expires_at)
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        if let Some(row) = taken {
+            return Ok(Self::lease_from_row(&row));
+        }
+
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM runtime_exclusion_leases \
+             WHERE organization_id = $1 AND exclusion_key = $2",
+            EXCLUSION_LEASE_COLUMNS
+        ))
+        .bind(lease.organization_id.as_uuid())
+        .bind(&lease.key)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(Self::lease_from_row(&row))
+    }
+
+    async fn renew(
+        &self,
+        organization_id: OrganizationId,
+        key: &str,
+        execution_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE runtime_exclusion_leases
+            SET expires_at = $4
+            WHERE organization_id = $1 AND exclusion_key = $2 AND execution_id = $3
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(key)
+        .bind(execution_id)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn release(
+        &self,
+        organization_id: OrganizationId,
+        key: &str,
+        execution_id: Uuid,
+    ) -> Result<bool, CretoError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM runtime_exclusion_leases
+            WHERE organization_id = $1 AND exclusion_key = $2 AND execution_id = $3
+            "#,
+        )
+        .bind(organization_id.as_uuid())
+        .bind(key)
+        .bind(execution_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list(&self) -> Result<Vec<ExclusionLease>, CretoError> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM runtime_exclusion_leases ORDER BY acquired_at",
+            EXCLUSION_LEASE_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CretoError::Database(e.to_string()))?;
+
+        Ok(rows.iter().map(Self::lease_from_row).collect())
     }
 }
 
@@ -572,4 +2624,121 @@ mod tests {
         );
         assert_eq!(ExecutionStatus::Queued.as_str(), "queued");
     }
+
+    #[test]
+    fn test_execution_priority_roundtrip() {
+        for priority in [
+            ExecutionPriority::Low,
+            ExecutionPriority::Normal,
+            ExecutionPriority::High,
+            ExecutionPriority::Critical,
+        ] {
+            assert_eq!(ExecutionPriority::parse_db_str(priority.as_str()), priority);
+        }
+    }
+
+    #[test]
+    fn test_log_level_roundtrip() {
+        for level in LogLevel::ALL {
+            assert_eq!(LogLevel::parse_db_str(level.as_str()), level);
+        }
+    }
+
+    fn where_clause(filter: &ExecutionFilter) -> String {
+        let mut query = QueryBuilder::new("");
+        push_execution_filter(&mut query, filter);
+        query.into_sql()
+    }
+
+    #[test]
+    fn test_execution_filter_sql_empty() {
+        assert_eq!(where_clause(&ExecutionFilter::new()), " WHERE TRUE");
+    }
+
+    #[test]
+    fn test_execution_filter_sql_binds_in_order() {
+        let now = Utc::now();
+        let filter = ExecutionFilter::new()
+            .for_organization(OrganizationId::new())
+            .for_agent(AgentId::new())
+            .for_sandbox(SandboxId::new())
+            .with_status(ExecutionStatus::Failed)
+            .queued_within(TimeRange::last(chrono::Duration::hours(1), now))
+            .with_min_duration_ms(500);
+
+        assert_eq!(
+            where_clause(&filter),
+            " WHERE TRUE AND s.organization_id = $1 AND s.agent_id = $2 \
+             AND e.sandbox_id = $3 AND e.status = $4 \
+             AND e.queued_at >= $5 AND e.queued_at < $6 AND e.duration_ms >= $7"
+        );
+    }
+
+    #[test]
+    fn test_execution_filter_sql_skips_unset_fields() {
+        let filter = ExecutionFilter::new()
+            .for_agent(AgentId::new())
+            .with_min_duration_ms(0);
+        assert_eq!(
+            where_clause(&filter),
+            " WHERE TRUE AND s.agent_id = $1 AND e.duration_ms >= $2"
+        );
+
+        let filter = ExecutionFilter::new().with_status(ExecutionStatus::Queued);
+        assert_eq!(where_clause(&filter), " WHERE TRUE AND e.status = $1");
+    }
+
+    #[test]
+    fn test_execution_page_query_offset_and_cursor() {
+        let filter = ExecutionFilter::new().for_sandbox(SandboxId::new());
+
+        let sql = execution_page_query(&filter, &PageRequest::at_offset(40, 20))
+            .unwrap()
+            .into_sql();
+        assert!(sql.ends_with(
+            " WHERE TRUE AND e.sandbox_id = $1 \
+             ORDER BY e.queued_at DESC, e.id DESC LIMIT $2 OFFSET $3"
+        ));
+
+        let cursor = ExecutionCursor {
+            queued_at: Utc::now(),
+            id: Uuid::now_v7(),
+        };
+        let sql = execution_page_query(&filter, &PageRequest::after(cursor.encode(), 20))
+            .unwrap()
+            .into_sql();
+        assert!(sql.starts_with(EXECUTION_HISTORY_COLUMNS));
+        assert!(sql.ends_with(
+            " WHERE TRUE AND e.sandbox_id = $1 AND (e.queued_at, e.id) < ($2, $3) \
+             ORDER BY e.queued_at DESC, e.id DESC LIMIT $4"
+        ));
+
+        assert!(matches!(
+            execution_page_query(&filter, &PageRequest::after("garbage", 20)),
+            Err(CretoError::ValidationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_execution_stats_queries() {
+        let filter = ExecutionFilter::new()
+            .for_organization(OrganizationId::new())
+            .queued_within(TimeRange::last(chrono::Duration::days(1), Utc::now()));
+        let scope = " WHERE TRUE AND s.organization_id = $1 \
+                     AND e.queued_at >= $2 AND e.queued_at < $3";
+
+        assert_eq!(
+            execution_count_query(&filter).into_sql(),
+            format!(
+                "SELECT COUNT(*) AS total{}{}",
+                EXECUTION_HISTORY_FROM, scope
+            )
+        );
+        assert!(execution_status_counts_query(&filter)
+            .into_sql()
+            .ends_with(&format!("{} GROUP BY e.status", scope)));
+        let percentiles = execution_percentiles_query(&filter).into_sql();
+        assert!(percentiles.contains("percentile_cont(0.95) WITHIN GROUP (ORDER BY e.duration_ms)"));
+        assert!(percentiles.ends_with(&format!("{} AND e.duration_ms IS NOT NULL", scope)));
+    }
 }
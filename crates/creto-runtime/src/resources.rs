@@ -1,9 +1,10 @@
 //! Resource limits and monitoring for sandboxes.
 
+use creto_common::{CretoError, CretoResult};
 use serde::{Deserialize, Serialize};
 
 /// Resource limits for a sandbox.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ResourceLimits {
     /// Maximum memory in bytes.
     pub memory_bytes: u64,
@@ -30,6 +31,27 @@ pub struct ResourceLimits {
     /// Maximum number of network connections.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_connections: Option<u32>,
+
+    /// Number of GPUs attached to the sandbox.
+    #[serde(default)]
+    pub gpu_count: u32,
+
+    /// GPU memory per GPU in bytes.
+    #[serde(default)]
+    pub gpu_memory_bytes: u64,
+
+    /// Required GPU model (e.g., "a100"); any GPU the host offers if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_kind: Option<String>,
+
+    /// Maximum stdout and stderr bytes an execution may write, combined
+    /// (None = unlimited).
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: Option<u64>,
+}
+
+fn default_max_output_bytes() -> Option<u64> {
+    Some(10 * 1024 * 1024) // 10 MB
 }
 
 impl Default for ResourceLimits {
@@ -43,6 +65,10 @@ impl Default for ResourceLimits {
             max_open_files: 256,
             network_bandwidth_bps: None,
             max_connections: Some(100),
+            gpu_count: 0,
+            gpu_memory_bytes: 0,
+            gpu_kind: None,
+            max_output_bytes: default_max_output_bytes(),
         }
     }
 }
@@ -59,6 +85,10 @@ impl ResourceLimits {
             max_open_files: 32,
             network_bandwidth_bps: None,
             max_connections: Some(10),
+            gpu_count: 0,
+            gpu_memory_bytes: 0,
+            gpu_kind: None,
+            max_output_bytes: Some(1024 * 1024), // 1 MB
         }
     }
 
@@ -73,6 +103,10 @@ impl ResourceLimits {
             max_open_files: 1024,
             network_bandwidth_bps: None,
             max_connections: Some(500),
+            gpu_count: 0,
+            gpu_memory_bytes: 0,
+            gpu_kind: None,
+            max_output_bytes: Some(100 * 1024 * 1024), // 100 MB
         }
     }
 
@@ -93,6 +127,168 @@ impl ResourceLimits {
         self.wall_time_seconds = seconds;
         self
     }
+
+    /// Set the output limit (None = unlimited).
+    pub fn with_max_output(mut self, bytes: Option<u64>) -> Self {
+        self.max_output_bytes = bytes;
+        self
+    }
+
+    /// Request `count` GPUs with `memory_bytes` of memory each.
+    pub fn with_gpus(mut self, count: u32, memory_bytes: u64) -> Self {
+        self.gpu_count = count;
+        self.gpu_memory_bytes = memory_bytes;
+        self
+    }
+
+    /// Require a specific GPU model.
+    pub fn with_gpu_kind(mut self, kind: impl Into<String>) -> Self {
+        self.gpu_kind = Some(kind.into());
+        self
+    }
+
+    /// The class of sandbox these limits need.
+    pub fn resource_class(&self) -> ResourceClass {
+        if self.gpu_count == 0 {
+            ResourceClass::Cpu
+        } else {
+            ResourceClass::Gpu {
+                count: self.gpu_count,
+                kind: self.gpu_kind.clone(),
+            }
+        }
+    }
+
+    /// Check that no limit is looser than the corresponding `ceiling` limit.
+    ///
+    /// An unset optional limit is unlimited, so it only fits an unset ceiling.
+    pub fn fits_within(&self, ceiling: &ResourceLimits) -> bool {
+        fn optional_fits<T: PartialOrd>(value: Option<T>, ceiling: Option<T>) -> bool {
+            match (value, ceiling) {
+                (_, None) => true,
+                (Some(value), Some(ceiling)) => value <= ceiling,
+                (None, Some(_)) => false,
+            }
+        }
+
+        self.memory_bytes <= ceiling.memory_bytes
+            && self.cpu_time_ms <= ceiling.cpu_time_ms
+            && self.wall_time_seconds <= ceiling.wall_time_seconds
+            && self.disk_bytes <= ceiling.disk_bytes
+            && self.max_processes <= ceiling.max_processes
+            && self.max_open_files <= ceiling.max_open_files
+            && optional_fits(self.network_bandwidth_bps, ceiling.network_bandwidth_bps)
+            && optional_fits(self.max_connections, ceiling.max_connections)
+            && optional_fits(self.max_output_bytes, ceiling.max_output_bytes)
+            && self.gpu_count <= ceiling.gpu_count
+            && self.gpu_memory_bytes <= ceiling.gpu_memory_bytes
+    }
+}
+
+/// Kind of hardware a sandbox runs on.
+///
+/// Warm sandboxes are only handed to requests of the same class, and GPU
+/// executions are admitted against their own concurrency limit.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "class")]
+pub enum ResourceClass {
+    /// CPU only.
+    #[default]
+    Cpu,
+    /// With GPUs attached.
+    Gpu {
+        /// Number of GPUs.
+        count: u32,
+        /// GPU model, if one was required.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        kind: Option<String>,
+    },
+}
+
+impl ResourceClass {
+    /// Whether GPUs are attached.
+    pub fn is_gpu(&self) -> bool {
+        matches!(self, Self::Gpu { .. })
+    }
+
+    /// Short label for metrics and stats (`cpu` or `gpu`).
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Gpu { .. } => "gpu",
+        }
+    }
+}
+
+/// Hardware the runtime's hosts offer to sandboxes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostCapabilities {
+    /// GPUs available to a single sandbox (0 = CPU-only host).
+    #[serde(default)]
+    pub gpu_count: u32,
+
+    /// Memory per GPU in bytes.
+    #[serde(default)]
+    pub gpu_memory_bytes: u64,
+
+    /// GPU models offered (empty = unspecified, matches any requested kind).
+    #[serde(default)]
+    pub gpu_kinds: Vec<String>,
+}
+
+impl HostCapabilities {
+    /// A host without GPUs.
+    pub fn cpu_only() -> Self {
+        Self::default()
+    }
+
+    /// A host offering `count` GPUs with `memory_bytes` of memory each.
+    pub fn with_gpus(count: u32, memory_bytes: u64) -> Self {
+        Self {
+            gpu_count: count,
+            gpu_memory_bytes: memory_bytes,
+            gpu_kinds: Vec::new(),
+        }
+    }
+
+    /// Advertise a GPU model.
+    pub fn with_gpu_kind(mut self, kind: impl Into<String>) -> Self {
+        self.gpu_kinds.push(kind.into());
+        self
+    }
+
+    /// Check that a sandbox's GPU request can be satisfied.
+    pub fn check(&self, limits: &ResourceLimits) -> CretoResult<()> {
+        let reject = |reason: String| Err(CretoError::ValidationFailed(reason));
+
+        if limits.gpu_count == 0 {
+            if limits.gpu_memory_bytes > 0 || limits.gpu_kind.is_some() {
+                return reject("GPU memory or kind set without requesting a GPU".to_string());
+            }
+            return Ok(());
+        }
+        if self.gpu_count == 0 {
+            return reject("GPU requested but the host has no GPUs".to_string());
+        }
+        if limits.gpu_count > self.gpu_count {
+            return reject(format!(
+                "{} GPUs requested, host offers {}",
+                limits.gpu_count, self.gpu_count
+            ));
+        }
+        if limits.gpu_memory_bytes > self.gpu_memory_bytes {
+            return reject(format!(
+                "{} bytes of GPU memory requested, host GPUs have {}",
+                limits.gpu_memory_bytes, self.gpu_memory_bytes
+            ));
+        }
+        if let Some(kind) = &limits.gpu_kind {
+            if !self.gpu_kinds.is_empty() && !self.gpu_kinds.iter().any(|k| k == kind) {
+                return reject(format!("GPU kind '{}' is not offered by the host", kind));
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Current resource usage of a sandbox.
@@ -127,6 +323,14 @@ pub struct ResourceUsage {
 
     /// Current number of network connections.
     pub connection_count: u32,
+
+    /// Total GPU time used in milliseconds, summed across GPUs.
+    #[serde(default)]
+    pub gpu_time_ms: u64,
+
+    /// Current GPU memory usage in bytes.
+    #[serde(default)]
+    pub gpu_memory_bytes: u64,
 }
 
 impl ResourceUsage {
@@ -346,6 +550,44 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_gpu_requests_checked_against_host() {
+        let gpu = ResourceLimits::default()
+            .with_gpus(1, 16 << 30)
+            .with_gpu_kind("a100");
+        assert_eq!(
+            gpu.resource_class(),
+            ResourceClass::Gpu {
+                count: 1,
+                kind: Some("a100".to_string())
+            }
+        );
+        assert_eq!(
+            ResourceLimits::default().resource_class(),
+            ResourceClass::Cpu
+        );
+
+        let cpu_only = HostCapabilities::cpu_only();
+        assert!(cpu_only.check(&ResourceLimits::default()).is_ok());
+        assert!(matches!(
+            cpu_only.check(&gpu),
+            Err(CretoError::ValidationFailed(_))
+        ));
+
+        let host = HostCapabilities::with_gpus(2, 40 << 30).with_gpu_kind("a100");
+        assert!(host.check(&gpu).is_ok());
+        assert!(host.check(&gpu.clone().with_gpus(4, 16 << 30)).is_err());
+        assert!(host.check(&gpu.clone().with_gpus(1, 80 << 30)).is_err());
+        assert!(host.check(&gpu.with_gpu_kind("h100")).is_err());
+        // Memory without a GPU is a malformed request
+        assert!(host
+            .check(&ResourceLimits {
+                gpu_memory_bytes: 1,
+                ..Default::default()
+            })
+            .is_err());
+    }
+
     #[test]
     fn test_usage_percentages() {
         let limits = ResourceLimits {
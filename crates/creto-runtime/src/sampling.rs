@@ -0,0 +1,464 @@
+//! Time-series resource usage sampling for executions.
+//!
+//! `ResourceUsage` is a point-in-time snapshot, which is not enough to explain
+//! why an execution was killed. The `UsageSampler` polls a `ResourceController`
+//! at a fixed interval and keeps the resulting curve in a bounded
+//! `UsageSeries`, downsampling older samples when the buffer fills up.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use creto_common::CretoResult;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::resources::ResourceUsage;
+
+/// Minimum allowed sampling interval in milliseconds.
+pub const MIN_SAMPLE_INTERVAL_MS: u64 = 100;
+
+/// Source of live resource usage readings for a single sandbox.
+///
+/// Backends implement this on top of cgroups, the VM monitor, or whatever
+/// accounting the isolation layer exposes.
+#[async_trait::async_trait]
+pub trait ResourceController: Send + Sync {
+    /// Read the sandbox's current resource usage.
+    async fn current_usage(&self) -> CretoResult<ResourceUsage>;
+}
+
+/// Configuration for usage sampling.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplerConfig {
+    /// Interval between samples in milliseconds (minimum 100ms).
+    pub interval_ms: u64,
+
+    /// Maximum number of samples retained per execution.
+    pub max_samples: usize,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 1000,
+            max_samples: 600,
+        }
+    }
+}
+
+impl SamplerConfig {
+    /// Set the sampling interval.
+    pub fn with_interval_ms(mut self, interval_ms: u64) -> Self {
+        self.interval_ms = interval_ms;
+        self
+    }
+
+    /// Set the maximum number of retained samples.
+    pub fn with_max_samples(mut self, max_samples: usize) -> Self {
+        self.max_samples = max_samples;
+        self
+    }
+
+    /// Effective sampling interval, clamped to the minimum.
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms.max(MIN_SAMPLE_INTERVAL_MS))
+    }
+}
+
+/// A single point in the usage time series.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageSample {
+    /// Milliseconds since sampling started.
+    pub offset_ms: u64,
+
+    /// Memory usage in bytes.
+    pub memory_bytes: u64,
+
+    /// CPU time consumed since the previous sample in milliseconds.
+    pub cpu_ms_delta: u64,
+
+    /// Number of open file descriptors.
+    pub open_fds: u32,
+
+    /// GPU time consumed since the previous sample in milliseconds.
+    #[serde(default)]
+    pub gpu_ms_delta: u64,
+}
+
+impl UsageSample {
+    /// Fold a later sample into this one.
+    ///
+    /// Gauges keep their maximum and CPU deltas are summed, so peaks and
+    /// totals survive downsampling.
+    fn absorb(&mut self, later: &UsageSample) {
+        self.offset_ms = later.offset_ms;
+        self.memory_bytes = self.memory_bytes.max(later.memory_bytes);
+        self.cpu_ms_delta += later.cpu_ms_delta;
+        self.open_fds = self.open_fds.max(later.open_fds);
+        self.gpu_ms_delta += later.gpu_ms_delta;
+    }
+}
+
+/// Bounded ring of usage samples.
+///
+/// When the buffer is full the older half is downsampled by merging adjacent
+/// pairs (keeping every 2nd sample's position), so recent samples stay at
+/// full resolution while the whole execution remains covered.
+#[derive(Debug, Clone)]
+pub struct UsageSeries {
+    samples: Vec<UsageSample>,
+    max_samples: usize,
+}
+
+impl UsageSeries {
+    /// Create an empty series holding at most `max_samples` samples.
+    pub fn new(max_samples: usize) -> Self {
+        let max_samples = max_samples.max(4);
+        Self {
+            samples: Vec::with_capacity(max_samples),
+            max_samples,
+        }
+    }
+
+    /// Append a sample, downsampling older samples if the buffer is full.
+    pub fn push(&mut self, sample: UsageSample) {
+        if self.samples.len() >= self.max_samples {
+            self.downsample();
+        }
+        self.samples.push(sample);
+    }
+
+    fn downsample(&mut self) {
+        let older = self.samples.len() / 2;
+        let mut compacted = Vec::with_capacity(self.max_samples);
+
+        for pair in self.samples[..older].chunks(2) {
+            let mut merged = pair[0];
+            if let Some(later) = pair.get(1) {
+                merged.absorb(later);
+            }
+            compacted.push(merged);
+        }
+        compacted.extend_from_slice(&self.samples[older..]);
+
+        self.samples = compacted;
+    }
+
+    /// Retained samples in chronological order.
+    pub fn samples(&self) -> &[UsageSample] {
+        &self.samples
+    }
+
+    /// Consume the series and return its samples.
+    pub fn into_samples(self) -> Vec<UsageSample> {
+        self.samples
+    }
+
+    /// Number of retained samples.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Whether no samples have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Peak memory usage across the series.
+    pub fn peak_memory_bytes(&self) -> u64 {
+        self.samples
+            .iter()
+            .map(|s| s.memory_bytes)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Peak open file descriptor count across the series.
+    pub fn peak_open_fds(&self) -> u32 {
+        self.samples.iter().map(|s| s.open_fds).max().unwrap_or(0)
+    }
+
+    /// Total CPU time across the series.
+    pub fn total_cpu_ms(&self) -> u64 {
+        self.samples.iter().map(|s| s.cpu_ms_delta).sum()
+    }
+
+    /// Total GPU time across the series.
+    pub fn total_gpu_ms(&self) -> u64 {
+        self.samples.iter().map(|s| s.gpu_ms_delta).sum()
+    }
+}
+
+/// Final output of a sampling run.
+#[derive(Debug, Clone, Default)]
+pub struct SampledUsage {
+    /// Usage summary; peak and CPU totals are derived from `samples`.
+    pub usage: ResourceUsage,
+
+    /// The retained time series.
+    pub samples: Vec<UsageSample>,
+}
+
+/// Periodically samples a `ResourceController` into a `UsageSeries`.
+pub struct UsageSampler {
+    controller: Arc<dyn ResourceController>,
+    config: SamplerConfig,
+    started: Instant,
+    last_cpu_ms: u64,
+    last_gpu_ms: u64,
+    last_usage: Option<ResourceUsage>,
+    series: UsageSeries,
+}
+
+impl UsageSampler {
+    /// Create a sampler for the given controller.
+    pub fn new(controller: Arc<dyn ResourceController>, config: SamplerConfig) -> Self {
+        let series = UsageSeries::new(config.max_samples);
+        Self {
+            controller,
+            config,
+            started: Instant::now(),
+            last_cpu_ms: 0,
+            last_gpu_ms: 0,
+            last_usage: None,
+            series,
+        }
+    }
+
+    /// Take a single sample now.
+    pub async fn sample(&mut self) -> CretoResult<UsageSample> {
+        let usage = self.controller.current_usage().await?;
+        let offset_ms = self.started.elapsed().as_millis() as u64;
+        Ok(self.record(offset_ms, usage))
+    }
+
+    fn record(&mut self, offset_ms: u64, usage: ResourceUsage) -> UsageSample {
+        let sample = UsageSample {
+            offset_ms,
+            memory_bytes: usage.memory_bytes,
+            cpu_ms_delta: usage.cpu_time_ms.saturating_sub(self.last_cpu_ms),
+            open_fds: usage.open_file_count,
+            gpu_ms_delta: usage.gpu_time_ms.saturating_sub(self.last_gpu_ms),
+        };
+
+        self.last_cpu_ms = self.last_cpu_ms.max(usage.cpu_time_ms);
+        self.last_gpu_ms = self.last_gpu_ms.max(usage.gpu_time_ms);
+        self.last_usage = Some(usage);
+        self.series.push(sample);
+        sample
+    }
+
+    /// Current series.
+    pub fn series(&self) -> &UsageSeries {
+        &self.series
+    }
+
+    /// Start sampling in the background at the configured interval.
+    pub fn spawn(mut self) -> SamplerHandle {
+        let (stop_tx, mut stop_rx) = oneshot::channel();
+        let interval = self.config.interval();
+
+        let task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = &mut stop_rx => break,
+                    _ = ticker.tick() => {
+                        if let Err(e) = self.sample().await {
+                            tracing::warn!(error = %e, "Resource usage sample failed");
+                        }
+                    }
+                }
+            }
+
+            self
+        });
+
+        SamplerHandle {
+            stop: stop_tx,
+            task,
+        }
+    }
+
+    /// Stop sampling and summarize the series.
+    ///
+    /// Peak memory and CPU time in the returned
+    /// `ResourceUsage` are computed from the samples so they always agree
+    /// with the series attached to the execution.
+    pub fn finish(self) -> SampledUsage {
+        let mut usage = self.last_usage.unwrap_or_default();
+        usage.peak_memory_bytes = self.series.peak_memory_bytes();
+        usage.cpu_time_ms = self.series.total_cpu_ms();
+        usage.gpu_time_ms = self.series.total_gpu_ms();
+        usage.wall_time_ms = self.started.elapsed().as_millis() as u64;
+
+        SampledUsage {
+            usage,
+            samples: self.series.into_samples(),
+        }
+    }
+}
+
+/// Handle to a background sampling task.
+pub struct SamplerHandle {
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<UsageSampler>,
+}
+
+impl SamplerHandle {
+    /// Stop the background task and return the collected usage.
+    pub async fn stop(self) -> SampledUsage {
+        let _ = self.stop.send(());
+        match self.task.await {
+            Ok(sampler) => sampler.finish(),
+            Err(e) => {
+                tracing::warn!(error = %e, "Usage sampler task failed");
+                SampledUsage::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Controller that replays a fixed memory curve with 10ms of CPU per tick.
+    struct CurveController {
+        curve: Vec<u64>,
+        tick: AtomicUsize,
+    }
+
+    impl CurveController {
+        fn new(curve: Vec<u64>) -> Self {
+            Self {
+                curve,
+                tick: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ResourceController for CurveController {
+        async fn current_usage(&self) -> CretoResult<ResourceUsage> {
+            let tick = self.tick.fetch_add(1, Ordering::SeqCst);
+            let memory = self.curve[tick.min(self.curve.len() - 1)];
+            Ok(ResourceUsage {
+                memory_bytes: memory,
+                cpu_time_ms: (tick as u64 + 1) * 10,
+                open_file_count: 3,
+                ..Default::default()
+            })
+        }
+    }
+
+    fn sampler_for(curve: Vec<u64>, max_samples: usize) -> UsageSampler {
+        UsageSampler::new(
+            Arc::new(CurveController::new(curve)),
+            SamplerConfig::default().with_max_samples(max_samples),
+        )
+    }
+
+    #[test]
+    fn test_interval_clamped_to_minimum() {
+        let config = SamplerConfig::default().with_interval_ms(10);
+        assert_eq!(config.interval(), Duration::from_millis(100));
+        assert_eq!(SamplerConfig::default().interval(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_samples_follow_curve() {
+        let curve = vec![100, 200, 300, 250];
+        let mut sampler = sampler_for(curve.clone(), 16);
+
+        for _ in 0..curve.len() {
+            sampler.sample().await.unwrap();
+        }
+
+        let memory: Vec<u64> = sampler
+            .series()
+            .samples()
+            .iter()
+            .map(|s| s.memory_bytes)
+            .collect();
+        assert_eq!(memory, curve);
+        assert!(sampler
+            .series()
+            .samples()
+            .iter()
+            .all(|s| s.cpu_ms_delta == 10));
+    }
+
+    #[tokio::test]
+    async fn test_downsampling_preserves_peak_and_cpu_total() {
+        // Spike early so it lands in the half that gets downsampled.
+        let mut curve: Vec<u64> = (0..100).map(|i| 1_000 + i).collect();
+        curve[7] = 9_999_999;
+        let mut sampler = sampler_for(curve.clone(), 20);
+
+        for _ in 0..curve.len() {
+            sampler.sample().await.unwrap();
+        }
+
+        assert!(sampler.series().len() <= 20);
+        assert_eq!(sampler.series().peak_memory_bytes(), 9_999_999);
+
+        let sampled = sampler.finish();
+        assert_eq!(sampled.usage.peak_memory_bytes, 9_999_999);
+        assert_eq!(sampled.usage.cpu_time_ms, 100 * 10);
+        assert_eq!(sampled.usage.memory_bytes, 1_099);
+
+        // Offsets stay monotonic after merging.
+        assert!(sampled
+            .samples
+            .windows(2)
+            .all(|w| w[0].offset_ms <= w[1].offset_ms));
+    }
+
+    #[test]
+    fn test_series_keeps_recent_samples_at_full_resolution() {
+        let mut series = UsageSeries::new(8);
+        for i in 0..9 {
+            series.push(UsageSample {
+                offset_ms: i * 100,
+                memory_bytes: i,
+                cpu_ms_delta: 1,
+                open_fds: 0,
+                gpu_ms_delta: 0,
+            });
+        }
+
+        // First 4 merged into 2, last 4 plus the new sample untouched.
+        assert_eq!(series.len(), 7);
+        assert_eq!(series.total_cpu_ms(), 9);
+        let offsets: Vec<u64> = series.samples().iter().map(|s| s.offset_ms).collect();
+        assert_eq!(offsets, vec![100, 300, 400, 500, 600, 700, 800]);
+    }
+
+    #[tokio::test]
+    async fn test_background_sampler_collects_samples() {
+        let sampler = UsageSampler::new(
+            Arc::new(CurveController::new(vec![64, 128])),
+            SamplerConfig::default().with_interval_ms(MIN_SAMPLE_INTERVAL_MS),
+        );
+
+        let handle = sampler.spawn();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let sampled = handle.stop().await;
+
+        assert!(!sampled.samples.is_empty());
+        assert_eq!(
+            sampled.usage.peak_memory_bytes,
+            sampled
+                .samples
+                .iter()
+                .map(|s| s.memory_bytes)
+                .max()
+                .unwrap()
+        );
+    }
+}
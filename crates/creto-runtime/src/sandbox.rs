@@ -1,13 +1,19 @@
 //! Sandbox environment for isolated agent execution.
 
+use std::sync::Arc;
+
 use chrono::{DateTime, Utc};
-use creto_common::{AgentId, CretoResult, OrganizationId};
+use creto_common::{AgentId, CretoError, CretoResult, OrganizationId};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::attestation::{Attestation, AttestationPolicy};
-use crate::network::NetworkPolicy as DetailedNetworkPolicy;
-use crate::resources::ResourceLimits;
+use crate::network::{
+    EffectiveNetworkPolicy, NetworkPolicy as DetailedNetworkPolicy, NetworkPolicyTemplateRef,
+};
+use crate::provisioning::ProvisioningReport;
+use crate::resources::{HostCapabilities, ResourceClass, ResourceLimits};
+use crate::sampling::ResourceController;
 
 /// Unique identifier for a sandbox instance.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -48,8 +54,10 @@ pub struct SandboxConfig {
     /// Runtime environment (e.g., "python3.11", "node20", "deno").
     pub runtime: String,
 
-    /// Resource limits for the sandbox.
-    pub limits: ResourceLimits,
+    /// Resource limits for the sandbox (None = the organization policy's
+    /// limits, or [`ResourceLimits::default`] without one).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<ResourceLimits>,
 
     /// Network access policy (simple enum).
     pub network_policy: NetworkPolicy,
@@ -58,6 +66,16 @@ pub struct SandboxConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detailed_network_policy: Option<DetailedNetworkPolicy>,
 
+    /// Organization network policy template to inherit (optional).
+    ///
+    /// Takes precedence over `detailed_network_policy` when set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_policy_template: Option<NetworkPolicyTemplateRef>,
+
+    /// Attestation requirements (optional, defaults to no attestation).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attestation_policy: Option<AttestationPolicy>,
+
     /// Filesystem mounts.
     #[serde(default)]
     pub mounts: Vec<Mount>,
@@ -73,6 +91,11 @@ pub struct SandboxConfig {
     /// Whether to enable debugging.
     #[serde(default)]
     pub debug: bool,
+
+    /// Never pause or hibernate the sandbox when idle. Counts against the
+    /// organization's [`max_keep_warm`](crate::idle::IdlePolicy::max_keep_warm).
+    #[serde(default)]
+    pub keep_warm: bool,
 }
 
 fn default_timeout() -> u32 {
@@ -83,17 +106,37 @@ impl Default for SandboxConfig {
     fn default() -> Self {
         Self {
             runtime: "python3.11".to_string(),
-            limits: ResourceLimits::default(),
+            limits: None,
             network_policy: NetworkPolicy::Restricted,
             detailed_network_policy: None,
+            network_policy_template: None,
+            attestation_policy: None,
             mounts: Vec::new(),
             environment: Vec::new(),
             timeout_seconds: default_timeout(),
             debug: false,
+            keep_warm: false,
         }
     }
 }
 
+impl SandboxConfig {
+    /// Limits the sandbox runs under: the chosen ones, or the defaults.
+    pub fn effective_limits(&self) -> ResourceLimits {
+        self.limits.clone().unwrap_or_default()
+    }
+
+    /// Resource class this configuration is scheduled under.
+    pub fn resource_class(&self) -> ResourceClass {
+        self.effective_limits().resource_class()
+    }
+
+    /// Validate the configuration against what the host can provide.
+    pub fn validate(&self, host: &HostCapabilities) -> CretoResult<()> {
+        host.check(&self.effective_limits())
+    }
+}
+
 /// Network access policy for sandboxes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -147,7 +190,10 @@ pub enum SandboxState {
     /// Failed to create or crashed.
     Failed,
     /// Terminated and cleaned up.
-    Terminated,
+    Terminated {
+        #[serde(default)]
+        reason: TerminationReason,
+    },
     /// Checkpointed with the given checkpoint ID.
     Checkpointed { checkpoint_id: String },
 }
@@ -160,7 +206,7 @@ impl SandboxState {
 
     /// Check if the sandbox is terminal (can't be used anymore).
     pub fn is_terminal(&self) -> bool {
-        matches!(self, SandboxState::Failed | SandboxState::Terminated)
+        matches!(self, SandboxState::Failed | SandboxState::Terminated { .. })
     }
 
     /// Check if the sandbox can be checkpointed.
@@ -172,6 +218,17 @@ impl SandboxState {
     }
 }
 
+/// Why a sandbox was terminated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminationReason {
+    /// Terminated on request.
+    #[default]
+    Requested,
+    /// Terminated by the runtime for exceeding a resource limit.
+    LimitExceeded,
+}
+
 /// A sandbox instance.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sandbox {
@@ -208,6 +265,18 @@ pub struct Sandbox {
     /// Policy controlling attestation requirements.
     #[serde(default)]
     pub attestation_policy: AttestationPolicy,
+
+    /// Network policy resolved from the configured template, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_network_policy: Option<EffectiveNetworkPolicy>,
+
+    /// Version of the organization runtime policy merged into `config`, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_policy_version: Option<u32>,
+
+    /// How the sandbox was provisioned for its current agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provisioning: Option<ProvisioningReport>,
 }
 
 impl Sandbox {
@@ -217,14 +286,35 @@ impl Sandbox {
             id: SandboxId::new(),
             organization_id,
             agent_id,
+            attestation_policy: config.attestation_policy.clone().unwrap_or_default(),
             config,
             state: SandboxState::Creating,
             created_at: Utc::now(),
             last_used_at: None,
             runtime_handle: None,
             attestation: None,
-            attestation_policy: AttestationPolicy::default(),
+            effective_network_policy: None,
+            runtime_policy_version: None,
+            provisioning: None,
+        }
+    }
+
+    /// Compute the configuration hash used for attestation.
+    ///
+    /// Covers the sandbox configuration and the resolved network policy
+    /// snapshot, so a template change is visible in the attested hash.
+    pub fn config_hash(&self) -> Vec<u8> {
+        // Unset limits run as the defaults, so both hash alike
+        let config = SandboxConfig {
+            limits: Some(self.config.effective_limits()),
+            ..self.config.clone()
+        };
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&serde_json::to_vec(&config).unwrap_or_default());
+        if let Some(effective) = &self.effective_network_policy {
+            hasher.update(&effective.hash());
         }
+        hasher.finalize().as_bytes().to_vec()
     }
 
     /// Mark sandbox as ready.
@@ -244,9 +334,14 @@ impl Sandbox {
         self.state = SandboxState::Failed;
     }
 
-    /// Mark sandbox as terminated.
+    /// Mark sandbox as terminated on request.
     pub fn mark_terminated(&mut self) {
-        self.state = SandboxState::Terminated;
+        self.mark_terminated_for(TerminationReason::Requested);
+    }
+
+    /// Mark sandbox as terminated for `reason`.
+    pub fn mark_terminated_for(&mut self, reason: TerminationReason) {
+        self.state = SandboxState::Terminated { reason };
         self.runtime_handle = None;
     }
 
@@ -297,6 +392,36 @@ pub trait SandboxBackend: Send + Sync {
 
     /// Get sandbox status.
     async fn status(&self, handle: &str) -> CretoResult<SandboxState>;
+
+    /// Live resource usage readings for a sandbox, if the backend exposes
+    /// them.
+    fn resource_controller(&self, _handle: &str) -> Option<Arc<dyn ResourceController>> {
+        None
+    }
+
+    /// Whether the backend implements [`SandboxBackend::snapshot`] and
+    /// [`SandboxBackend::restore`].
+    ///
+    /// Idle sandboxes are only hibernated on backends that can restore
+    /// them; elsewhere they stay paused.
+    fn supports_restore(&self) -> bool {
+        false
+    }
+
+    /// Capture a sandbox's runtime state.
+    async fn snapshot(&self, _handle: &str) -> CretoResult<Vec<u8>> {
+        Err(CretoError::Internal(
+            "Sandbox backend cannot snapshot sandboxes".to_string(),
+        ))
+    }
+
+    /// Bring up a new sandbox from a snapshot taken by
+    /// [`SandboxBackend::snapshot`].
+    async fn restore(&self, _config: &SandboxConfig, _snapshot: &[u8]) -> CretoResult<String> {
+        Err(CretoError::Internal(
+            "Sandbox backend cannot restore sandboxes".to_string(),
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -331,10 +456,35 @@ mod tests {
         assert_eq!(sandbox.state, SandboxState::Running);
 
         sandbox.mark_terminated();
-        assert_eq!(sandbox.state, SandboxState::Terminated);
+        assert_eq!(
+            sandbox.state,
+            SandboxState::Terminated {
+                reason: TerminationReason::Requested
+            }
+        );
         assert!(sandbox.state.is_terminal());
     }
 
+    #[test]
+    fn test_config_hash_covers_effective_policy() {
+        use crate::network::NetworkPolicyTemplate;
+
+        let mut sandbox = Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig::default(),
+        );
+        let bare = sandbox.config_hash();
+
+        let template = NetworkPolicyTemplate::new(
+            sandbox.organization_id,
+            "default",
+            DetailedNetworkPolicy::new_default_deny(),
+        );
+        sandbox.effective_network_policy = Some(template.resolve(&[]));
+        assert_ne!(sandbox.config_hash(), bare);
+    }
+
     #[test]
     fn test_network_policy() {
         let policy = NetworkPolicy::Restricted;
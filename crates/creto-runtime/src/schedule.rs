@@ -0,0 +1,708 @@
+//! Scheduled and recurring sandbox executions.
+//!
+//! An [`ExecutionSchedule`] runs a copy of its [`ExecutionRequest`] template
+//! on a cron expression or fixed interval. The scheduler worker
+//! ([`RuntimeService::run_due_schedules`](crate::RuntimeService::run_due_schedules))
+//! claims due schedules from the [`ScheduleStore`] under a lease, so several
+//! runtime instances can share one store without double-firing, then
+//! creates each execution through the service's normal execution path.
+//!
+//! Runs missed while no worker was polling (more than
+//! [`SchedulerConfig::misfire_grace`] late) follow the schedule's
+//! [`CatchUpPolicy`]. Every firing, including skipped ones, is recorded as
+//! a [`ScheduleRun`].
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{AgentId, CretoError, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::cron::CronExpression;
+use crate::execution::{ExecutionRequest, ExecutionStatus};
+
+/// Unique identifier for a schedule.
+pub type ScheduleId = Uuid;
+
+/// Upper bound on occurrences counted when a schedule is overdue.
+const MAX_COUNTED_OCCURRENCES: u32 = 1_000;
+
+/// When a schedule fires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    /// On every minute matching a cron expression (UTC).
+    Cron {
+        /// The expression.
+        expression: CronExpression,
+    },
+    /// Every `every_seconds`, counted from `anchor`.
+    Interval {
+        /// Seconds between runs.
+        every_seconds: u64,
+        /// First run; later runs fall on multiples of the interval after it.
+        anchor: DateTime<Utc>,
+    },
+}
+
+impl ScheduleTrigger {
+    /// Fire on a cron expression.
+    pub fn cron(expression: &str) -> CretoResult<Self> {
+        Ok(Self::Cron {
+            expression: CronExpression::parse(expression)?,
+        })
+    }
+
+    /// Fire every `every`, starting at `anchor`.
+    pub fn every(every: Duration, anchor: DateTime<Utc>) -> CretoResult<Self> {
+        if every < Duration::seconds(1) {
+            return Err(CretoError::ValidationFailed(
+                "schedule interval must be at least one second".to_string(),
+            ));
+        }
+        Ok(Self::Interval {
+            every_seconds: every.num_seconds() as u64,
+            anchor,
+        })
+    }
+
+    /// The first firing strictly after `after`, if the trigger fires again.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Cron { expression } => expression.next_after(after),
+            Self::Interval {
+                every_seconds,
+                anchor,
+            } => {
+                if after < *anchor {
+                    return Some(*anchor);
+                }
+                let every = Duration::seconds(*every_seconds as i64);
+                let elapsed = (after - *anchor).num_seconds() / *every_seconds as i64;
+                Some(*anchor + every * (elapsed as i32 + 1))
+            }
+        }
+    }
+}
+
+/// What to do about runs missed while no scheduler was polling.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Drop missed runs and wait for the next on-time one.
+    #[default]
+    Skip,
+    /// Run once to cover all missed runs.
+    RunOnce,
+}
+
+impl CatchUpPolicy {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CatchUpPolicy::Skip => "skip",
+            CatchUpPolicy::RunOnce => "run_once",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "run_once" => CatchUpPolicy::RunOnce,
+            _ => CatchUpPolicy::Skip,
+        }
+    }
+}
+
+/// A recurring execution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionSchedule {
+    /// Unique schedule ID.
+    pub id: ScheduleId,
+
+    /// Owning organization.
+    pub organization_id: OrganizationId,
+
+    /// Agent the executions run for.
+    pub agent_id: AgentId,
+
+    /// Request copied for every run (with a fresh ID).
+    pub template: ExecutionRequest,
+
+    /// When the schedule fires.
+    pub trigger: ScheduleTrigger,
+
+    /// Disabled schedules never fire.
+    pub enabled: bool,
+
+    /// Handling of missed runs.
+    #[serde(default)]
+    pub catch_up: CatchUpPolicy,
+
+    /// Next firing; `None` once the trigger never fires again.
+    pub next_run_at: Option<DateTime<Utc>>,
+
+    /// When the schedule was created.
+    pub created_at: DateTime<Utc>,
+
+    /// When the schedule was last changed.
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ExecutionSchedule {
+    /// Create an enabled schedule whose first run is the trigger's first
+    /// firing after `now`.
+    pub fn new(
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        template: ExecutionRequest,
+        trigger: ScheduleTrigger,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            organization_id,
+            agent_id,
+            template,
+            next_run_at: trigger.next_after(now),
+            trigger,
+            enabled: true,
+            catch_up: CatchUpPolicy::default(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Set the catch-up policy.
+    pub fn with_catch_up(mut self, catch_up: CatchUpPolicy) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
+    /// Decide what a firing at `now` does.
+    ///
+    /// Occurrences more than `grace` before `now` count as missed. The
+    /// latest due occurrence runs if it is on time, or if the policy is
+    /// [`CatchUpPolicy::RunOnce`].
+    pub fn plan_run(&self, now: DateTime<Utc>, grace: Duration) -> RunPlan {
+        let first = self.next_run_at.unwrap_or(now);
+        let (mut latest, mut due) = (first, 1u32);
+        while due < MAX_COUNTED_OCCURRENCES {
+            match self.trigger.next_after(latest) {
+                Some(next) if next <= now => {
+                    latest = next;
+                    due += 1;
+                }
+                _ => break,
+            }
+        }
+
+        let on_time = latest >= now - grace;
+        RunPlan {
+            scheduled_for: latest,
+            missed: if on_time { due - 1 } else { due },
+            execute: on_time || self.catch_up == CatchUpPolicy::RunOnce,
+            next_run_at: self.trigger.next_after(now.max(latest)),
+        }
+    }
+}
+
+/// Outcome of [`ExecutionSchedule::plan_run`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunPlan {
+    /// Latest due occurrence.
+    pub scheduled_for: DateTime<Utc>,
+    /// Occurrences missed (capped at 1000).
+    pub missed: u32,
+    /// Whether to create an execution.
+    pub execute: bool,
+    /// Next firing after this one.
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+/// A due schedule held by one scheduler worker.
+#[derive(Debug, Clone)]
+pub struct ScheduleClaim {
+    /// The schedule as of the claim.
+    pub schedule: ExecutionSchedule,
+    /// Token identifying this claim; updates to the schedule invalidate it.
+    pub token: Uuid,
+    /// When other workers may claim the schedule again.
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Result of one firing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleRunOutcome {
+    /// The execution ran.
+    Completed {
+        /// Final execution status.
+        status: ExecutionStatus,
+    },
+    /// The execution could not be run.
+    Failed {
+        /// Error message.
+        error: String,
+    },
+    /// No execution was created.
+    Skipped {
+        /// Why, e.g. missed runs or a quota denial.
+        reason: String,
+    },
+}
+
+/// History record of one firing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    /// Unique run ID.
+    pub id: Uuid,
+    /// Schedule that fired.
+    pub schedule_id: ScheduleId,
+    /// Occurrence the run was for.
+    pub scheduled_for: DateTime<Utc>,
+    /// When the outcome was recorded.
+    pub recorded_at: DateTime<Utc>,
+    /// Execution created, if any.
+    pub execution_id: Option<Uuid>,
+    /// Occurrences missed before this one.
+    pub missed: u32,
+    /// What happened.
+    pub outcome: ScheduleRunOutcome,
+}
+
+/// A page of schedule runs, most recent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleRunPage {
+    /// Runs to skip.
+    pub offset: usize,
+    /// Maximum runs to return.
+    pub limit: usize,
+}
+
+impl ScheduleRunPage {
+    /// Create a page.
+    pub fn new(offset: usize, limit: usize) -> Self {
+        Self { offset, limit }
+    }
+}
+
+impl Default for ScheduleRunPage {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            limit: 50,
+        }
+    }
+}
+
+/// Scheduler worker settings.
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    /// Identifies this worker in claims.
+    pub worker_id: String,
+    /// How often the background worker polls for due schedules.
+    pub poll_interval: std::time::Duration,
+    /// How long a claim excludes other workers.
+    pub lease: Duration,
+    /// How late a run may start before it counts as missed.
+    pub misfire_grace: Duration,
+    /// Maximum schedules claimed per pass.
+    pub batch_size: usize,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            worker_id: format!("scheduler-{}", Uuid::new_v4()),
+            poll_interval: std::time::Duration::from_secs(15),
+            lease: Duration::minutes(5),
+            misfire_grace: Duration::minutes(1),
+            batch_size: 32,
+        }
+    }
+}
+
+/// Checks run before a scheduled execution is created, such as quota or
+/// approval checks.
+///
+/// An error skips the run and is recorded as its reason.
+#[async_trait]
+pub trait ScheduleGate: Send + Sync {
+    /// Allow or refuse a run of `schedule`.
+    async fn admit(&self, schedule: &ExecutionSchedule) -> CretoResult<()>;
+}
+
+/// Storage for schedules and their run history.
+#[async_trait]
+pub trait ScheduleStore: Send + Sync {
+    /// Store a new schedule.
+    async fn create(&self, schedule: &ExecutionSchedule) -> CretoResult<()>;
+
+    /// Get a schedule.
+    async fn get(&self, id: ScheduleId) -> CretoResult<Option<ExecutionSchedule>>;
+
+    /// List an organization's schedules, oldest first.
+    async fn list_by_org(&self, org_id: OrganizationId) -> CretoResult<Vec<ExecutionSchedule>>;
+
+    /// Replace a schedule, releasing any claim on it.
+    ///
+    /// Fails with [`CretoError::NotFound`] if it does not exist.
+    async fn update(&self, schedule: &ExecutionSchedule) -> CretoResult<()>;
+
+    /// Delete a schedule, returning whether it existed.
+    async fn delete(&self, id: ScheduleId) -> CretoResult<bool>;
+
+    /// Atomically claim up to `limit` enabled schedules due at `now` that
+    /// are not claimed under an unexpired lease.
+    async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        lease: Duration,
+        limit: usize,
+    ) -> CretoResult<Vec<ScheduleClaim>>;
+
+    /// Advance a claimed schedule to `next_run_at` and release the claim.
+    ///
+    /// Returns `false`, changing nothing, if the claim was invalidated or
+    /// the schedule was disabled or deleted since it was claimed.
+    async fn complete_claim(
+        &self,
+        claim: &ScheduleClaim,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> CretoResult<bool>;
+
+    /// Record a run.
+    async fn record_run(&self, run: &ScheduleRun) -> CretoResult<()>;
+
+    /// List a schedule's runs, most recent first.
+    async fn list_runs(
+        &self,
+        schedule_id: ScheduleId,
+        page: ScheduleRunPage,
+    ) -> CretoResult<Vec<ScheduleRun>>;
+}
+
+/// A stored schedule with its current claim.
+struct StoredSchedule {
+    schedule: ExecutionSchedule,
+    claim: Option<(Uuid, DateTime<Utc>)>,
+}
+
+/// In-memory schedule store.
+#[derive(Default)]
+pub struct InMemoryScheduleStore {
+    schedules: RwLock<HashMap<ScheduleId, StoredSchedule>>,
+    runs: RwLock<Vec<ScheduleRun>>,
+}
+
+impl InMemoryScheduleStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ScheduleStore for InMemoryScheduleStore {
+    async fn create(&self, schedule: &ExecutionSchedule) -> CretoResult<()> {
+        self.schedules.write().unwrap().insert(
+            schedule.id,
+            StoredSchedule {
+                schedule: schedule.clone(),
+                claim: None,
+            },
+        );
+        Ok(())
+    }
+
+    async fn get(&self, id: ScheduleId) -> CretoResult<Option<ExecutionSchedule>> {
+        Ok(self
+            .schedules
+            .read()
+            .unwrap()
+            .get(&id)
+            .map(|s| s.schedule.clone()))
+    }
+
+    async fn list_by_org(&self, org_id: OrganizationId) -> CretoResult<Vec<ExecutionSchedule>> {
+        let mut schedules: Vec<_> = self
+            .schedules
+            .read()
+            .unwrap()
+            .values()
+            .filter(|s| s.schedule.organization_id == org_id)
+            .map(|s| s.schedule.clone())
+            .collect();
+        schedules.sort_by_key(|s| s.created_at);
+        Ok(schedules)
+    }
+
+    async fn update(&self, schedule: &ExecutionSchedule) -> CretoResult<()> {
+        let mut schedules = self.schedules.write().unwrap();
+        let stored = schedules
+            .get_mut(&schedule.id)
+            .ok_or_else(|| CretoError::NotFound(format!("Schedule {}", schedule.id)))?;
+        stored.schedule = schedule.clone();
+        stored.claim = None;
+        Ok(())
+    }
+
+    async fn delete(&self, id: ScheduleId) -> CretoResult<bool> {
+        Ok(self.schedules.write().unwrap().remove(&id).is_some())
+    }
+
+    async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        lease: Duration,
+        limit: usize,
+    ) -> CretoResult<Vec<ScheduleClaim>> {
+        let mut schedules = self.schedules.write().unwrap();
+        let mut due: Vec<_> = schedules
+            .values_mut()
+            .filter(|s| {
+                s.schedule.enabled
+                    && matches!(s.schedule.next_run_at, Some(at) if at <= now)
+                    && !matches!(s.claim, Some((_, expires_at)) if expires_at > now)
+            })
+            .collect();
+        due.sort_by_key(|s| s.schedule.next_run_at);
+
+        let token = Uuid::now_v7();
+        let expires_at = now + lease;
+        Ok(due
+            .into_iter()
+            .take(limit)
+            .map(|s| {
+                s.claim = Some((token, expires_at));
+                ScheduleClaim {
+                    schedule: s.schedule.clone(),
+                    token,
+                    expires_at,
+                }
+            })
+            .collect())
+    }
+
+    async fn complete_claim(
+        &self,
+        claim: &ScheduleClaim,
+        next_run_at: Option<DateTime<Utc>>,
+    ) -> CretoResult<bool> {
+        let mut schedules = self.schedules.write().unwrap();
+        match schedules.get_mut(&claim.schedule.id) {
+            Some(s)
+                if s.schedule.enabled && matches!(s.claim, Some((t, _)) if t == claim.token) =>
+            {
+                s.schedule.next_run_at = next_run_at;
+                s.claim = None;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn record_run(&self, run: &ScheduleRun) -> CretoResult<()> {
+        self.runs.write().unwrap().push(run.clone());
+        Ok(())
+    }
+
+    async fn list_runs(
+        &self,
+        schedule_id: ScheduleId,
+        page: ScheduleRunPage,
+    ) -> CretoResult<Vec<ScheduleRun>> {
+        Ok(self
+            .runs
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|r| r.schedule_id == schedule_id)
+            .skip(page.offset)
+            .take(page.limit)
+            .cloned()
+            .collect())
+    }
+}
+
+/// Refuses scheduled and triggered runs once the organization's execution
+/// quota is used up.
+#[cfg(feature = "metering")]
+pub struct MeteringQuotaGate {
+    metering: std::sync::Arc<creto_metering::MeteringService>,
+    metric_code: String,
+}
+
+#[cfg(feature = "metering")]
+impl MeteringQuotaGate {
+    /// Gate on the `sandbox_execution` quota.
+    pub fn new(metering: std::sync::Arc<creto_metering::MeteringService>) -> Self {
+        Self {
+            metering,
+            metric_code: "sandbox_execution".to_string(),
+        }
+    }
+
+    /// Gate on a different metric's quota.
+    pub fn with_metric_code(mut self, metric_code: impl Into<String>) -> Self {
+        self.metric_code = metric_code.into();
+        self
+    }
+
+    /// Refuse once the agent's quota has nothing remaining.
+    pub(crate) fn check(&self, org_id: &OrganizationId, agent_id: &AgentId) -> CretoResult<()> {
+        let status = self
+            .metering
+            .get_quota_status(org_id, agent_id, &self.metric_code)?;
+        if !status.allowed || status.remaining < 1 {
+            return Err(CretoError::QuotaExceeded {
+                resource: self.metric_code.clone(),
+                used: status.current_usage.max(0) as u64,
+                limit: status.limit.max(0) as u64,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "metering")]
+#[async_trait]
+impl ScheduleGate for MeteringQuotaGate {
+    async fn admit(&self, schedule: &ExecutionSchedule) -> CretoResult<()> {
+        self.check(&schedule.organization_id, &schedule.agent_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    fn at(h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 4, h, mi, 0).unwrap()
+    }
+
+    fn hourly(now: DateTime<Utc>) -> ExecutionSchedule {
+        ExecutionSchedule::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            ExecutionRequest::new(crate::sandbox::SandboxId::new(), "print('report')"),
+            ScheduleTrigger::cron("0 * * * *").unwrap(),
+            now,
+        )
+    }
+
+    #[test]
+    fn test_interval_trigger() {
+        let anchor = at(6, 0);
+        let trigger = ScheduleTrigger::every(Duration::minutes(90), anchor).unwrap();
+        assert_eq!(trigger.next_after(at(5, 0)), Some(anchor));
+        assert_eq!(trigger.next_after(anchor), Some(at(7, 30)));
+        assert_eq!(trigger.next_after(at(8, 59)), Some(at(9, 0)));
+        assert!(ScheduleTrigger::every(Duration::zero(), anchor).is_err());
+    }
+
+    #[test]
+    fn test_plan_run_catch_up_policies() {
+        let grace = Duration::minutes(1);
+        let schedule = hourly(at(5, 30));
+        assert_eq!(schedule.next_run_at, Some(at(6, 0)));
+
+        // On time
+        let plan = schedule.plan_run(at(6, 0) + Duration::seconds(20), grace);
+        assert_eq!(plan.scheduled_for, at(6, 0));
+        assert!(plan.execute);
+        assert_eq!(plan.missed, 0);
+        assert_eq!(plan.next_run_at, Some(at(7, 0)));
+
+        // 06:00 and 07:00 missed; 08:00 still within grace
+        let plan = schedule.plan_run(at(8, 0) + Duration::seconds(30), grace);
+        assert_eq!(plan.scheduled_for, at(8, 0));
+        assert!(plan.execute);
+        assert_eq!(plan.missed, 2);
+
+        // Everything missed: skipped, or run once
+        let plan = schedule.plan_run(at(8, 40), grace);
+        assert!(!plan.execute);
+        assert_eq!(plan.missed, 3);
+        assert_eq!(plan.next_run_at, Some(at(9, 0)));
+        let plan = schedule
+            .clone()
+            .with_catch_up(CatchUpPolicy::RunOnce)
+            .plan_run(at(8, 40), grace);
+        assert!(plan.execute);
+        assert_eq!(plan.missed, 3);
+        assert_eq!(plan.scheduled_for, at(8, 0));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_claims_are_exclusive() {
+        let store = Arc::new(InMemoryScheduleStore::new());
+        for _ in 0..20 {
+            store.create(&hourly(at(5, 30))).await.unwrap();
+        }
+
+        let now = at(6, 0);
+        let workers: Vec<_> = (0..8)
+            .map(|_| {
+                let store = Arc::clone(&store);
+                tokio::spawn(
+                    async move { store.claim_due(now, Duration::minutes(5), 4).await.unwrap() },
+                )
+            })
+            .collect();
+
+        let mut claimed = Vec::new();
+        for worker in workers {
+            claimed.extend(worker.await.unwrap().into_iter().map(|c| c.schedule.id));
+        }
+        let total = claimed.len();
+        claimed.sort();
+        claimed.dedup();
+        assert_eq!(claimed.len(), total, "a schedule was claimed twice");
+        assert_eq!(total, 20);
+
+        // Leases hold until they expire
+        assert!(store
+            .claim_due(now + Duration::minutes(4), Duration::minutes(5), 100)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            store
+                .claim_due(now + Duration::minutes(5), Duration::minutes(5), 100)
+                .await
+                .unwrap()
+                .len(),
+            20
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_invalidates_claim() {
+        let store = InMemoryScheduleStore::new();
+        let schedule = hourly(at(5, 30));
+        store.create(&schedule).await.unwrap();
+
+        let claim = store
+            .claim_due(at(6, 0), Duration::minutes(5), 1)
+            .await
+            .unwrap()
+            .remove(0);
+        let mut disabled = schedule.clone();
+        disabled.enabled = false;
+        store.update(&disabled).await.unwrap();
+
+        assert!(!store.complete_claim(&claim, Some(at(7, 0))).await.unwrap());
+        assert_eq!(
+            store.get(schedule.id).await.unwrap().unwrap().next_run_at,
+            Some(at(6, 0))
+        );
+    }
+}
@@ -1,8 +1,26 @@
 //! Secret injection for sandbox execution.
+//!
+//! Every secret mounted into a sandbox is recorded as a [`SecretGrant`] so
+//! auditors can see which executions could read which credentials. Grants
+//! hold a reference to the secret, never its value.
+//!
+//! Mounted secret material is held under a short-lived lease from the
+//! [`SecretProvider`]. The runtime renews leases while the sandbox runs and
+//! revokes them when it terminates, pauses or is checkpointed. Checkpoints
+//! record which secrets were mounted ([`CheckpointedSecret`]), never their
+//! values, so a restore can lease them again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use creto_common::{AgentId, CretoResult, OrganizationId};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::sandbox::SandboxId;
 
 /// A secret to be injected into a sandbox.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +33,15 @@ pub struct SecretMount {
 
     /// Source of the secret.
     pub source: SecretSource,
+
+    /// Report when the sandbox first reads this secret, if the executor
+    /// supports it. Only applies to environment variable and file mounts.
+    #[serde(default)]
+    pub detect_access: bool,
+
+    /// What happens to the mount when the sandbox is paused for being idle.
+    #[serde(default)]
+    pub idle_policy: SecretIdlePolicy,
 }
 
 impl SecretMount {
@@ -24,6 +51,8 @@ impl SecretMount {
             name: name.into(),
             mount_type: SecretMountType::EnvironmentVariable,
             source,
+            detect_access: false,
+            idle_policy: SecretIdlePolicy::default(),
         }
     }
 
@@ -36,8 +65,27 @@ impl SecretMount {
                 mode: 0o600,
             },
             source,
+            detect_access: false,
+            idle_policy: SecretIdlePolicy::default(),
         }
     }
+
+    /// Request first-access detection for this secret.
+    pub fn with_access_detection(mut self) -> Self {
+        self.detect_access = true;
+        self
+    }
+
+    /// Set what happens to the mount when the sandbox is paused.
+    pub fn with_idle_policy(mut self, policy: SecretIdlePolicy) -> Self {
+        self.idle_policy = policy;
+        self
+    }
+
+    /// Whether first-access detection should be enabled for this mount.
+    pub fn wants_access_detection(&self) -> bool {
+        self.detect_access && self.mount_type.target().supports_access_detection()
+    }
 }
 
 /// How to mount a secret in the sandbox.
@@ -60,6 +108,89 @@ pub enum SecretMountType {
     },
 }
 
+impl SecretMountType {
+    /// Get the kind of mount target, without the path details.
+    pub fn target(&self) -> SecretMountTarget {
+        match self {
+            SecretMountType::EnvironmentVariable => SecretMountTarget::EnvironmentVariable,
+            SecretMountType::File { .. } => SecretMountTarget::File,
+            SecretMountType::Directory { .. } => SecretMountTarget::Directory,
+        }
+    }
+}
+
+/// What happens to a mounted secret when its sandbox is paused for being idle.
+///
+/// Hibernation always revokes mounted secrets, since the sandbox instance
+/// is terminated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretIdlePolicy {
+    /// Keep the secret mounted while paused.
+    #[default]
+    Retain,
+    /// Revoke the grant when the sandbox pauses.
+    Revoke,
+}
+
+impl SecretIdlePolicy {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretIdlePolicy::Retain => "retain",
+            SecretIdlePolicy::Revoke => "revoke",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "revoke" => SecretIdlePolicy::Revoke,
+            _ => SecretIdlePolicy::Retain,
+        }
+    }
+}
+
+/// Kind of target a secret was mounted as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretMountTarget {
+    /// Environment variable.
+    EnvironmentVariable,
+    /// Single file.
+    File,
+    /// Directory of files.
+    Directory,
+}
+
+impl SecretMountTarget {
+    /// Convert to database string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretMountTarget::EnvironmentVariable => "environment_variable",
+            SecretMountTarget::File => "file",
+            SecretMountTarget::Directory => "directory",
+        }
+    }
+
+    /// Parse from database string.
+    pub fn parse_db_str(s: &str) -> Self {
+        match s {
+            "file" => SecretMountTarget::File,
+            "directory" => SecretMountTarget::Directory,
+            _ => SecretMountTarget::EnvironmentVariable,
+        }
+    }
+
+    /// Whether executors can report first access for this target.
+    pub fn supports_access_detection(&self) -> bool {
+        matches!(
+            self,
+            SecretMountTarget::EnvironmentVariable | SecretMountTarget::File
+        )
+    }
+}
+
 /// Source of a secret.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
@@ -88,6 +219,20 @@ pub enum SecretSource {
     },
 }
 
+impl SecretSource {
+    /// Stable, non-sensitive reference to this secret for audit records.
+    ///
+    /// Inline values are never included.
+    pub fn reference(&self) -> String {
+        match self {
+            SecretSource::Vault { path, key } => format!("vault:{}#{}", path, key),
+            SecretSource::OrganizationSecret { name } => format!("org:{}", name),
+            SecretSource::AgentCredential { name } => format!("agent:{}", name),
+            SecretSource::Inline { .. } => "inline".to_string(),
+        }
+    }
+}
+
 /// Trait for secret providers.
 #[async_trait]
 pub trait SecretProvider: Send + Sync {
@@ -106,6 +251,124 @@ pub trait SecretProvider: Send + Sync {
         agent_id: AgentId,
         source: &SecretSource,
     ) -> CretoResult<bool>;
+
+    /// Lease a secret for `ttl`.
+    ///
+    /// The provider may grant a shorter TTL. The default resolves the secret
+    /// and grants the full TTL, for providers without native leases.
+    async fn lease(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        source: &SecretSource,
+        ttl: Duration,
+    ) -> CretoResult<LeasedSecret> {
+        let value = self.resolve(organization_id, agent_id, source).await?;
+        Ok(LeasedSecret {
+            lease_id: LeaseId::new(),
+            ttl,
+            value,
+        })
+    }
+
+    /// Extend a lease by `ttl`, returning the TTL granted.
+    async fn renew(&self, _lease_id: LeaseId, ttl: Duration) -> CretoResult<Duration> {
+        Ok(ttl)
+    }
+
+    /// End a lease early, invalidating its material at the source.
+    async fn revoke(&self, _lease_id: LeaseId) -> CretoResult<()> {
+        Ok(())
+    }
+}
+
+/// Unique identifier for a secret lease.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LeaseId(Uuid);
+
+impl LeaseId {
+    /// Create a new random lease ID.
+    pub fn new() -> Self {
+        Self(Uuid::now_v7())
+    }
+
+    /// Get the inner UUID.
+    pub fn as_uuid(&self) -> Uuid {
+        self.0
+    }
+}
+
+impl Default for LeaseId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for LeaseId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "lease_{}", self.0)
+    }
+}
+
+/// Secret material held under a lease.
+#[derive(Debug, Clone)]
+pub struct LeasedSecret {
+    /// Lease to renew and revoke.
+    pub lease_id: LeaseId,
+    /// How long the lease runs before it must be renewed.
+    pub ttl: Duration,
+    /// The secret value.
+    pub value: SecretValue,
+}
+
+/// How long mounted secrets are leased for and when leases are renewed.
+#[derive(Debug, Clone)]
+pub struct SecretLeaseConfig {
+    /// TTL requested for each lease.
+    pub ttl: Duration,
+    /// Renew a lease once it has this little time left.
+    pub renew_before: Duration,
+    /// How often the renewal worker runs.
+    pub renew_interval: Duration,
+}
+
+impl Default for SecretLeaseConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(300),
+            renew_before: Duration::from_secs(60),
+            renew_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A secret mounted in a sandbox when it was checkpointed.
+///
+/// Stored with the checkpoint in place of the material, so a restore can
+/// lease the secret again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointedSecret {
+    /// Organization the secret was leased for.
+    pub organization_id: OrganizationId,
+    /// Agent the secret was leased for.
+    pub agent_id: AgentId,
+    /// The mount. Never has an inline source.
+    pub mount: SecretMount,
+}
+
+impl CheckpointedSecret {
+    /// Checkpoint metadata key the mounted secrets are stored under.
+    pub const METADATA_KEY: &'static str = "secret_mounts";
+}
+
+/// What a lease renewal pass did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SecretLeaseReport {
+    /// Leases renewed.
+    pub renewed: usize,
+    /// Mounts invalidated because their lease ran out, as sandbox and
+    /// mount name.
+    pub expired: Vec<(SandboxId, String)>,
 }
 
 /// A resolved secret value.
@@ -167,6 +430,8 @@ impl std::fmt::Debug for SecretValue {
 /// Composite secret provider that chains multiple providers.
 pub struct ChainedSecretProvider {
     providers: Vec<Box<dyn SecretProvider>>,
+    /// Index of the provider that issued each lease.
+    leases: RwLock<HashMap<LeaseId, usize>>,
 }
 
 impl ChainedSecretProvider {
@@ -174,9 +439,22 @@ impl ChainedSecretProvider {
     pub fn new() -> Self {
         Self {
             providers: Vec::new(),
+            leases: RwLock::new(HashMap::new()),
         }
     }
 
+    /// Provider that issued a lease.
+    fn lease_provider(&self, lease_id: LeaseId) -> CretoResult<&dyn SecretProvider> {
+        let index = self
+            .leases
+            .read()
+            .unwrap()
+            .get(&lease_id)
+            .copied()
+            .ok_or_else(|| creto_common::CretoError::NotFound(format!("secret {}", lease_id)))?;
+        Ok(self.providers[index].as_ref())
+    }
+
     /// Add a provider to the chain.
     pub fn add_provider(&mut self, provider: Box<dyn SecretProvider>) {
         self.providers.push(provider);
@@ -228,11 +506,61 @@ impl SecretProvider for ChainedSecretProvider {
         }
         Ok(true)
     }
+
+    async fn lease(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        source: &SecretSource,
+        ttl: Duration,
+    ) -> CretoResult<LeasedSecret> {
+        // The first provider that has the secret leases it
+        for (index, provider) in self.providers.iter().enumerate() {
+            if let Ok(leased) = provider.lease(organization_id, agent_id, source, ttl).await {
+                self.leases.write().unwrap().insert(leased.lease_id, index);
+                return Ok(leased);
+            }
+        }
+
+        Err(creto_common::CretoError::SecretResolutionFailed {
+            secret_name: format!("{:?}", source),
+            source: None,
+        })
+    }
+
+    async fn renew(&self, lease_id: LeaseId, ttl: Duration) -> CretoResult<Duration> {
+        self.lease_provider(lease_id)?.renew(lease_id, ttl).await
+    }
+
+    async fn revoke(&self, lease_id: LeaseId) -> CretoResult<()> {
+        let provider = self.lease_provider(lease_id)?;
+        provider.revoke(lease_id).await?;
+        self.leases.write().unwrap().remove(&lease_id);
+        Ok(())
+    }
+}
+
+/// Lease issued by a [`MockSecretProvider`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockLease {
+    /// Reference of the leased secret.
+    pub secret_reference: String,
+    /// Times the lease was renewed.
+    pub renewals: u32,
+    /// Whether the lease was revoked.
+    pub revoked: bool,
+    /// Whether the lease was expired at the source; renewals fail.
+    pub expired: bool,
 }
 
 /// Mock secret provider for testing.
+///
+/// Clones share their leases, so a test can keep a clone to inspect the
+/// leases a service took.
+#[derive(Clone)]
 pub struct MockSecretProvider {
     secrets: std::collections::HashMap<String, SecretValue>,
+    leases: Arc<RwLock<HashMap<LeaseId, MockLease>>>,
 }
 
 impl MockSecretProvider {
@@ -240,6 +568,7 @@ impl MockSecretProvider {
     pub fn new() -> Self {
         Self {
             secrets: std::collections::HashMap::new(),
+            leases: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -247,6 +576,29 @@ impl MockSecretProvider {
     pub fn add_secret(&mut self, key: impl Into<String>, value: SecretValue) {
         self.secrets.insert(key.into(), value);
     }
+
+    /// Every lease issued, by ID.
+    pub fn leases(&self) -> HashMap<LeaseId, MockLease> {
+        self.leases.read().unwrap().clone()
+    }
+
+    /// Leases neither revoked nor expired.
+    pub fn active_leases(&self) -> Vec<LeaseId> {
+        self.leases
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, lease)| !lease.revoked && !lease.expired)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Expire a lease at the source, so renewing it fails.
+    pub fn expire(&self, lease_id: LeaseId) {
+        if let Some(lease) = self.leases.write().unwrap().get_mut(&lease_id) {
+            lease.expired = true;
+        }
+    }
 }
 
 impl Default for MockSecretProvider {
@@ -287,6 +639,419 @@ impl SecretProvider for MockSecretProvider {
         // Mock always authorizes
         Ok(true)
     }
+
+    async fn lease(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        source: &SecretSource,
+        ttl: Duration,
+    ) -> CretoResult<LeasedSecret> {
+        let value = self.resolve(organization_id, agent_id, source).await?;
+        let lease_id = LeaseId::new();
+        self.leases.write().unwrap().insert(
+            lease_id,
+            MockLease {
+                secret_reference: source.reference(),
+                renewals: 0,
+                revoked: false,
+                expired: false,
+            },
+        );
+        Ok(LeasedSecret {
+            lease_id,
+            ttl,
+            value,
+        })
+    }
+
+    async fn renew(&self, lease_id: LeaseId, ttl: Duration) -> CretoResult<Duration> {
+        let mut leases = self.leases.write().unwrap();
+        match leases.get_mut(&lease_id) {
+            Some(lease) if !lease.revoked && !lease.expired => {
+                lease.renewals += 1;
+                Ok(ttl)
+            }
+            _ => Err(creto_common::CretoError::NotFound(format!(
+                "secret {}",
+                lease_id
+            ))),
+        }
+    }
+
+    async fn revoke(&self, lease_id: LeaseId) -> CretoResult<()> {
+        if let Some(lease) = self.leases.write().unwrap().get_mut(&lease_id) {
+            lease.revoked = true;
+        }
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Secret Access Tracking
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Record that a sandbox was given access to a secret.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretGrant {
+    /// Unique grant ID.
+    pub id: Uuid,
+    /// Organization owning the sandbox.
+    pub organization_id: OrganizationId,
+    /// Sandbox the secret was mounted into.
+    pub sandbox_id: SandboxId,
+    /// Agent running in the sandbox.
+    pub agent_id: AgentId,
+    /// Mount name (env var or file name).
+    pub secret_name: String,
+    /// Secret reference (see [`SecretSource::reference`]), never the value.
+    pub secret_reference: String,
+    /// How the secret was mounted.
+    pub target: SecretMountTarget,
+    /// Whether the executor is watching for the first read.
+    pub access_detection: bool,
+    /// What happens to the grant when the sandbox is paused.
+    #[serde(default)]
+    pub idle_policy: SecretIdlePolicy,
+    /// When the secret was mounted.
+    pub granted_at: DateTime<Utc>,
+    /// When the sandbox first read the secret, if detected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_accessed_at: Option<DateTime<Utc>>,
+    /// When the mount was removed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl SecretGrant {
+    /// Create a grant for a mount, granted now.
+    pub fn new(
+        organization_id: OrganizationId,
+        sandbox_id: SandboxId,
+        agent_id: AgentId,
+        mount: &SecretMount,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            organization_id,
+            sandbox_id,
+            agent_id,
+            secret_name: mount.name.clone(),
+            secret_reference: mount.source.reference(),
+            target: mount.mount_type.target(),
+            access_detection: false,
+            idle_policy: mount.idle_policy,
+            granted_at: Utc::now(),
+            first_accessed_at: None,
+            revoked_at: None,
+        }
+    }
+
+    /// Check if the grant is still in effect.
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+
+    /// Check if the grant was in effect at any point in `[start, end)`.
+    pub fn overlaps(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.granted_at < end && !matches!(self.revoked_at, Some(r) if r < start)
+    }
+}
+
+/// First read of a mounted secret, as reported by the executor.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretAccess {
+    /// Mount name that was read.
+    pub secret_name: String,
+    /// When it was first read.
+    pub accessed_at: DateTime<Utc>,
+}
+
+/// Hook for executors that can detect when a sandbox reads a secret.
+///
+/// The default implementation does not detect anything.
+#[async_trait]
+pub trait SecretAccessMonitor: Send + Sync {
+    /// Start watching a mounted secret. Returns `false` if unsupported.
+    async fn watch(&self, _sandbox_id: SandboxId, _mount: &SecretMount) -> CretoResult<bool> {
+        Ok(false)
+    }
+
+    /// First reads observed in a sandbox since watching began.
+    async fn first_accesses(&self, _sandbox_id: SandboxId) -> CretoResult<Vec<SecretAccess>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Access monitor for executors without access detection.
+#[derive(Debug, Default)]
+pub struct NoopSecretAccessMonitor;
+
+impl SecretAccessMonitor for NoopSecretAccessMonitor {}
+
+/// Storage for secret grants.
+#[async_trait]
+pub trait SecretGrantStore: Send + Sync {
+    /// Record a new grant.
+    async fn record_grant(&self, grant: &SecretGrant) -> CretoResult<()>;
+
+    /// Revoke all active grants for a sandbox, returning the grants revoked.
+    ///
+    /// Grants that are already revoked are left untouched.
+    async fn revoke_sandbox(
+        &self,
+        sandbox_id: SandboxId,
+        revoked_at: DateTime<Utc>,
+    ) -> CretoResult<Vec<SecretGrant>>;
+
+    /// Revoke a single grant.
+    ///
+    /// Returns the revoked grant, or `None` if it was already revoked.
+    async fn revoke_grant(
+        &self,
+        grant_id: Uuid,
+        revoked_at: DateTime<Utc>,
+    ) -> CretoResult<Option<SecretGrant>>;
+
+    /// Record the first read of a grant's secret.
+    ///
+    /// Returns the updated grant, or `None` if a first access was already
+    /// recorded.
+    async fn record_first_access(
+        &self,
+        grant_id: Uuid,
+        accessed_at: DateTime<Utc>,
+    ) -> CretoResult<Option<SecretGrant>>;
+
+    /// List grants for a sandbox, oldest first.
+    async fn list_by_sandbox(&self, sandbox_id: SandboxId) -> CretoResult<Vec<SecretGrant>>;
+
+    /// List an organization's grants active at any point in `[start, end)`.
+    async fn list_by_org(
+        &self,
+        organization_id: OrganizationId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> CretoResult<Vec<SecretGrant>>;
+}
+
+/// In-memory grant store for testing and development.
+#[derive(Debug, Clone, Default)]
+pub struct InMemorySecretGrantStore {
+    grants: Arc<RwLock<HashMap<Uuid, SecretGrant>>>,
+}
+
+impl InMemorySecretGrantStore {
+    /// Create a new in-memory grant store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn sorted(mut grants: Vec<SecretGrant>) -> Vec<SecretGrant> {
+        grants.sort_by_key(|g| (g.granted_at, g.id));
+        grants
+    }
+}
+
+#[async_trait]
+impl SecretGrantStore for InMemorySecretGrantStore {
+    async fn record_grant(&self, grant: &SecretGrant) -> CretoResult<()> {
+        self.grants.write().unwrap().insert(grant.id, grant.clone());
+        Ok(())
+    }
+
+    async fn revoke_sandbox(
+        &self,
+        sandbox_id: SandboxId,
+        revoked_at: DateTime<Utc>,
+    ) -> CretoResult<Vec<SecretGrant>> {
+        let mut grants = self.grants.write().unwrap();
+        let revoked = grants
+            .values_mut()
+            .filter(|g| g.sandbox_id == sandbox_id && g.is_active())
+            .map(|g| {
+                g.revoked_at = Some(revoked_at);
+                g.clone()
+            })
+            .collect();
+        Ok(Self::sorted(revoked))
+    }
+
+    async fn revoke_grant(
+        &self,
+        grant_id: Uuid,
+        revoked_at: DateTime<Utc>,
+    ) -> CretoResult<Option<SecretGrant>> {
+        let mut grants = self.grants.write().unwrap();
+        let grant = grants.get_mut(&grant_id).ok_or_else(|| {
+            creto_common::CretoError::NotFound(format!("secret grant {}", grant_id))
+        })?;
+        if !grant.is_active() {
+            return Ok(None);
+        }
+        grant.revoked_at = Some(revoked_at);
+        Ok(Some(grant.clone()))
+    }
+
+    async fn record_first_access(
+        &self,
+        grant_id: Uuid,
+        accessed_at: DateTime<Utc>,
+    ) -> CretoResult<Option<SecretGrant>> {
+        let mut grants = self.grants.write().unwrap();
+        let grant = grants.get_mut(&grant_id).ok_or_else(|| {
+            creto_common::CretoError::NotFound(format!("secret grant {}", grant_id))
+        })?;
+        if grant.first_accessed_at.is_some() {
+            return Ok(None);
+        }
+        grant.first_accessed_at = Some(accessed_at);
+        Ok(Some(grant.clone()))
+    }
+
+    async fn list_by_sandbox(&self, sandbox_id: SandboxId) -> CretoResult<Vec<SecretGrant>> {
+        let grants = self.grants.read().unwrap();
+        Ok(Self::sorted(
+            grants
+                .values()
+                .filter(|g| g.sandbox_id == sandbox_id)
+                .cloned()
+                .collect(),
+        ))
+    }
+
+    async fn list_by_org(
+        &self,
+        organization_id: OrganizationId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> CretoResult<Vec<SecretGrant>> {
+        let grants = self.grants.read().unwrap();
+        Ok(Self::sorted(
+            grants
+                .values()
+                .filter(|g| g.organization_id == organization_id && g.overlaps(start, end))
+                .cloned()
+                .collect(),
+        ))
+    }
+}
+
+/// Grants of a single secret within a report period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretGrantSummary {
+    /// Secret reference.
+    pub secret_reference: String,
+    /// Number of grants.
+    pub grant_count: u64,
+    /// Number of grants with a detected read.
+    pub accessed_count: u64,
+    /// Distinct sandboxes the secret was mounted into.
+    pub sandbox_count: u64,
+    /// Distinct agents that were granted the secret.
+    pub agents: Vec<AgentId>,
+}
+
+/// Secrets granted to a single agent within a report period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentSecretSummary {
+    /// Agent.
+    pub agent_id: AgentId,
+    /// Number of grants.
+    pub grant_count: u64,
+    /// Distinct secret references granted.
+    pub secrets: Vec<String>,
+}
+
+/// Summary of which agents could read which secrets over a period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretAccessReport {
+    /// Organization the report covers.
+    pub organization_id: OrganizationId,
+    /// Start of the period (inclusive).
+    pub period_start: DateTime<Utc>,
+    /// End of the period (exclusive).
+    pub period_end: DateTime<Utc>,
+    /// Per-secret summaries, ordered by reference.
+    pub by_secret: Vec<SecretGrantSummary>,
+    /// Per-agent summaries, most grants first.
+    pub by_agent: Vec<AgentSecretSummary>,
+}
+
+impl SecretAccessReport {
+    /// Build a report from the grants active during the period.
+    pub fn from_grants(
+        organization_id: OrganizationId,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+        grants: &[SecretGrant],
+    ) -> Self {
+        let mut by_secret: HashMap<&str, (SecretGrantSummary, Vec<SandboxId>)> = HashMap::new();
+        let mut by_agent: HashMap<AgentId, AgentSecretSummary> = HashMap::new();
+
+        for grant in grants {
+            let (summary, sandboxes) = by_secret
+                .entry(grant.secret_reference.as_str())
+                .or_insert_with(|| {
+                    (
+                        SecretGrantSummary {
+                            secret_reference: grant.secret_reference.clone(),
+                            grant_count: 0,
+                            accessed_count: 0,
+                            sandbox_count: 0,
+                            agents: Vec::new(),
+                        },
+                        Vec::new(),
+                    )
+                });
+            summary.grant_count += 1;
+            if grant.first_accessed_at.is_some() {
+                summary.accessed_count += 1;
+            }
+            if !sandboxes.contains(&grant.sandbox_id) {
+                sandboxes.push(grant.sandbox_id);
+            }
+            if !summary.agents.contains(&grant.agent_id) {
+                summary.agents.push(grant.agent_id);
+            }
+
+            let agent = by_agent
+                .entry(grant.agent_id)
+                .or_insert_with(|| AgentSecretSummary {
+                    agent_id: grant.agent_id,
+                    grant_count: 0,
+                    secrets: Vec::new(),
+                });
+            agent.grant_count += 1;
+            if !agent.secrets.contains(&grant.secret_reference) {
+                agent.secrets.push(grant.secret_reference.clone());
+            }
+        }
+
+        let mut by_secret: Vec<_> = by_secret
+            .into_values()
+            .map(|(mut summary, sandboxes)| {
+                summary.sandbox_count = sandboxes.len() as u64;
+                summary
+            })
+            .collect();
+        by_secret.sort_by(|a, b| a.secret_reference.cmp(&b.secret_reference));
+
+        let mut by_agent: Vec<_> = by_agent.into_values().collect();
+        by_agent.sort_by(|a, b| {
+            b.grant_count
+                .cmp(&a.grant_count)
+                .then_with(|| a.agent_id.as_uuid().cmp(b.agent_id.as_uuid()))
+        });
+
+        Self {
+            organization_id,
+            period_start,
+            period_end,
+            by_secret,
+            by_agent,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -362,4 +1127,120 @@ mod tests {
 
         assert_eq!(value.as_str(), Some("test_value"));
     }
+
+    #[tokio::test]
+    async fn test_chained_provider_routes_leases_to_issuer() {
+        let mut first = MockSecretProvider::new();
+        first.add_secret("openai_key", SecretValue::text("sk-first"));
+        let mut second = MockSecretProvider::new();
+        second.add_secret("db_password", SecretValue::text("pg-second"));
+        let mut chained = ChainedSecretProvider::new();
+        chained.add_provider(Box::new(first.clone()));
+        chained.add_provider(Box::new(second.clone()));
+
+        let ttl = Duration::from_secs(60);
+        let leased = chained
+            .lease(
+                OrganizationId::new(),
+                AgentId::new(),
+                &SecretSource::OrganizationSecret {
+                    name: "db_password".to_string(),
+                },
+                ttl,
+            )
+            .await
+            .unwrap();
+        assert_eq!(leased.value.as_str(), Some("pg-second"));
+        assert!(first.leases().is_empty());
+
+        assert_eq!(chained.renew(leased.lease_id, ttl).await.unwrap(), ttl);
+        assert_eq!(second.leases()[&leased.lease_id].renewals, 1);
+        chained.revoke(leased.lease_id).await.unwrap();
+        assert!(second.leases()[&leased.lease_id].revoked);
+        assert!(chained.renew(leased.lease_id, ttl).await.is_err());
+    }
+
+    #[test]
+    fn test_secret_reference_omits_inline_value() {
+        let inline = SecretSource::Inline {
+            value: "hunter2".to_string(),
+        };
+        assert_eq!(inline.reference(), "inline");
+
+        let vault = SecretSource::Vault {
+            path: "secret/data/myapp".to_string(),
+            key: "token".to_string(),
+        };
+        assert_eq!(vault.reference(), "vault:secret/data/myapp#token");
+    }
+
+    #[test]
+    fn test_access_detection_only_for_env_and_file() {
+        let source = SecretSource::OrganizationSecret {
+            name: "openai_key".to_string(),
+        };
+        assert!(SecretMount::env_var("API_KEY", source.clone())
+            .with_access_detection()
+            .wants_access_detection());
+        assert!(!SecretMount::env_var("API_KEY", source.clone()).wants_access_detection());
+
+        let mut dir = SecretMount::env_var("certs", source).with_access_detection();
+        dir.mount_type = SecretMountType::Directory {
+            path: "/etc/certs".to_string(),
+        };
+        assert!(!dir.wants_access_detection());
+    }
+
+    #[tokio::test]
+    async fn test_grant_store_revokes_and_records_access_once() {
+        let store = InMemorySecretGrantStore::new();
+        let sandbox_id = SandboxId::new();
+        let org_id = OrganizationId::new();
+        let mount = SecretMount::env_var(
+            "API_KEY",
+            SecretSource::OrganizationSecret {
+                name: "openai_key".to_string(),
+            },
+        );
+        let grant = SecretGrant::new(org_id, sandbox_id, AgentId::new(), &mount);
+        store.record_grant(&grant).await.unwrap();
+
+        let accessed_at = Utc::now();
+        assert!(store
+            .record_first_access(grant.id, accessed_at)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(store
+            .record_first_access(grant.id, Utc::now())
+            .await
+            .unwrap()
+            .is_none());
+
+        let revoked = store.revoke_sandbox(sandbox_id, Utc::now()).await.unwrap();
+        assert_eq!(revoked.len(), 1);
+        assert_eq!(revoked[0].first_accessed_at, Some(accessed_at));
+        assert!(store
+            .revoke_sandbox(sandbox_id, Utc::now())
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Revoked grants still count for periods they overlapped
+        let hour = chrono::Duration::hours(1);
+        let start = grant.granted_at - hour;
+        assert_eq!(
+            store
+                .list_by_org(org_id, start, Utc::now() + hour)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(store
+            .list_by_org(org_id, Utc::now() + hour, Utc::now() + hour * 2)
+            .await
+            .unwrap()
+            .is_empty());
+    }
 }
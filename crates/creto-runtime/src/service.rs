@@ -1,28 +1,424 @@
 //! Runtime service facade.
 
-use creto_common::{AgentId, CretoError, CretoResult, OrganizationId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use creto_common::{
+    AgentId, Clock, Correlation, CretoError, CretoResult, OrganizationId, ShutdownCoordinator,
+    ShutdownGuard, SystemClock,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::Stream;
+use uuid::Uuid;
 
 use crate::{
-    checkpoint::{CheckpointConfig, CheckpointId, CheckpointManager, InMemoryCheckpointStore},
-    execution::{ExecutionRequest, ExecutionResult, Executor},
-    pool::{PoolConfig, WarmPool},
-    sandbox::{Sandbox, SandboxConfig, SandboxId, SandboxState},
-    secrets::{SecretMount, SecretProvider},
+    attestation::{
+        Attestation, AttestationGenerator, AttestationMeasurements, AttestationPlatform,
+        AttestationPolicy, AttestationVerifier,
+    },
+    audit::{
+        AuditRateLimitConfig, AuditRateLimiter, AuditSink, RuntimeActivity, RuntimeAuditEvent,
+    },
+    channels::{AgentChannel, AgentChannelProxy, ChannelId, ChannelPeer},
+    checkpoint::{
+        CheckpointConfig, CheckpointError, CheckpointId, CheckpointManager, CheckpointStoreStats,
+        InMemoryCheckpointStore,
+    },
+    context::{ExecutionContext, QuotaSnapshot, QuotaStatusProvider},
+    exclusion::{
+        ExclusionConfig, ExclusionHold, ExclusionLease, ExclusionLeaseStore, ExclusionRegistry,
+        InMemoryExclusionLeaseStore,
+    },
+    execution::{
+        executor_failure, run_streaming, CodeExecutor, ExecutionChunk, ExecutionError,
+        ExecutionRequest, ExecutionResult, ExecutionStatus, ExecutionTiming, Executor,
+        StreamLimits,
+    },
+    filesystem::{
+        FilesystemDiff, FilesystemDiffStore, FilesystemManifest, FilesystemTrackingConfig,
+        InMemoryFilesystemDiffStore, WorkdirResolver,
+    },
+    history::{ExecutionFilter, ExecutionStats, Page, PageRequest, TimeRange},
+    idempotency::{Claim, IdempotencyCache, IdempotencyConfig, IdempotencyKey},
+    idle::{
+        check_keep_warm_quota, IdleClaim, IdleConfig, IdlePolicy, IdleState, IdleStep,
+        IdleSweepReport, IdleTracker, SandboxActivity,
+    },
+    limits::{
+        LimitBreach, LimitEnforcementReport, LimitEnforcer, LimitEnforcerConfig, LimitEvent,
+        LimitEventSink, LimitKind,
+    },
+    logs::{
+        ExecutionLogStore, InMemoryExecutionLogStore, LogCaptureConfig, LogLevel, LogPage,
+        LogRecord,
+    },
+    metering::{ExecutionUsageEvent, SandboxUsageSampler, UsageEventSink},
+    network::{
+        EffectiveNetworkPolicy, EgressDecision, InMemoryNetworkPolicyTemplateStore, NetworkAction,
+        NetworkPolicy, NetworkPolicyEnforcer, NetworkPolicyTemplate, NetworkPolicyTemplateRef,
+        NetworkPolicyTemplateStore, NetworkUsage,
+    },
+    org_policy::{InMemoryOrgRuntimePolicyStore, OrgRuntimePolicy, OrgRuntimePolicyStore},
+    pool::{
+        HealthProbe, PoolConfig, PoolHealth, PoolHealthReport, PoolStats, RestoreConfig,
+        RestoreOutcome, WarmPool,
+    },
+    provisioning::{
+        NoopProvisioner, PhaseTiming, ProvisioningError, ProvisioningFailure, ProvisioningPhase,
+        ProvisioningReport, ProvisioningRetryPolicy, ReplenishmentReport, SandboxProvisioner,
+    },
+    queue::{
+        ExecutionPriority, ExecutionQueue, ExecutionQueueConfig, QueueTicket, QueuedExecution,
+    },
+    redaction::{RedactionEngine, SecretFingerprint},
+    repository::{
+        ExecutionRecord, ExecutionRepository, ProvisioningFailureRecord, ResourceUsageRepository,
+        SandboxRepository,
+    },
+    resources::{HostCapabilities, ResourceClass, ResourceLimits, ResourceUsage},
+    sandbox::{Sandbox, SandboxBackend, SandboxConfig, SandboxId, SandboxState, TerminationReason},
+    schedule::{
+        CatchUpPolicy, ExecutionSchedule, InMemoryScheduleStore, ScheduleGate, ScheduleId,
+        ScheduleRun, ScheduleRunOutcome, ScheduleRunPage, ScheduleStore, ScheduleTrigger,
+        SchedulerConfig,
+    },
+    secrets::{
+        CheckpointedSecret, InMemorySecretGrantStore, LeaseId, NoopSecretAccessMonitor,
+        SecretAccessMonitor, SecretAccessReport, SecretGrant, SecretGrantStore, SecretIdlePolicy,
+        SecretLeaseConfig, SecretLeaseReport, SecretMount, SecretProvider, SecretSource,
+    },
+    triggers::{
+        FailedDelivery, InMemoryTopicTriggerStore, StoredTopicTrigger, TopicSource, TopicTrigger,
+        TopicTriggerId, TopicTriggerStore, TriggerDeadLetter, TriggerDelivery,
+        TriggerDispatchConfig, TriggerGate, TriggerMessage, TriggerOutcome,
+    },
 };
 
+/// Timeout recorded for executions that don't set one (matches the schema default).
+const DEFAULT_TIMEOUT_SECONDS: u32 = 300;
+
+/// Point-in-time view of the service's load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeStats {
+    /// Warm pool statistics.
+    pub pool: PoolStats,
+
+    /// Executions holding an admission slot, if a queue is configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub running_executions: Option<usize>,
+
+    /// Executions waiting for an admission slot, if a queue is configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queued_executions: Option<usize>,
+
+    /// Unexpired exclusion leases across all nodes sharing the lease store.
+    pub exclusion_locks: Vec<ExclusionLease>,
+}
+
+/// Template-derived egress state for a live sandbox.
+struct SandboxEgress {
+    organization_id: OrganizationId,
+    agent_id: AgentId,
+    effective: EffectiveNetworkPolicy,
+    enforcer: NetworkPolicyEnforcer,
+}
+
+/// A sandbox's current attestation and the policy it is verified against.
+#[derive(Clone)]
+struct SandboxAttestation {
+    attestation: Option<Attestation>,
+    policy: AttestationPolicy,
+}
+
+/// A secret mounted in a live sandbox under a lease.
+struct MountedSecret {
+    lease_id: LeaseId,
+    organization_id: OrganizationId,
+    agent_id: AgentId,
+    mount: SecretMount,
+    expires_at: DateTime<Utc>,
+    /// Set once the lease ran out; the mount is no longer usable.
+    expired: bool,
+}
+
+impl MountedSecret {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expired || now >= self.expires_at
+    }
+
+    fn checkpointed(&self) -> CheckpointedSecret {
+        CheckpointedSecret {
+            organization_id: self.organization_id,
+            agent_id: self.agent_id,
+            mount: self.mount.clone(),
+        }
+    }
+}
+
+/// When a lease granted for `ttl` at `now` runs out.
+fn lease_expiry(now: DateTime<Utc>, ttl: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(ttl)
+        .ok()
+        .and_then(|ttl| now.checked_add_signed(ttl))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Scan a sandbox workdir on a blocking thread.
+///
+/// A failed scan only disables change tracking for the execution.
+async fn scan_workdir(
+    config: &FilesystemTrackingConfig,
+    path: &std::path::Path,
+) -> Option<FilesystemManifest> {
+    let (config, path) = (config.clone(), path.to_path_buf());
+    let scanned =
+        tokio::task::spawn_blocking(move || FilesystemManifest::scan(&path, &config)).await;
+    match scanned {
+        Ok(Ok(manifest)) => Some(manifest),
+        Ok(Err(e)) => {
+            tracing::warn!(error = %e, "Workdir scan failed; skipping change tracking");
+            None
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Workdir scan task failed; skipping change tracking");
+            None
+        }
+    }
+}
+
+/// Result recorded for an execution cancelled by a higher-priority holder
+/// of its exclusion key.
+fn preempted_result(execution_id: Uuid, key: &str) -> ExecutionResult {
+    let mut timing = ExecutionTiming::new();
+    timing.mark_completed();
+    let mut result = ExecutionResult::failure(
+        execution_id,
+        ExecutionError::new(
+            "PREEMPTED",
+            format!("Preempted by a higher-priority execution needing exclusion key {key}"),
+        ),
+        timing,
+    );
+    result.status = ExecutionStatus::Cancelled;
+    result
+}
+
+/// Result of an execution cancelled because its sandbox exceeded `limit`.
+fn limit_exceeded_result(execution_id: Uuid, limit: LimitKind) -> ExecutionResult {
+    let mut timing = ExecutionTiming::new();
+    timing.mark_completed();
+    let mut result = ExecutionResult::failure(
+        execution_id,
+        ExecutionError::resource_limit_exceeded(limit),
+        timing,
+    );
+    result.status = ExecutionStatus::Cancelled;
+    result
+}
+
+/// A sandbox request after policy merging and validation.
+struct PreparedSandbox {
+    config: SandboxConfig,
+    effective: Option<EffectiveNetworkPolicy>,
+    policy_version: Option<u32>,
+}
+
+/// Run one provisioning phase, recording how long it took.
+async fn run_phase<T>(
+    report: &mut ProvisioningReport,
+    phase: ProvisioningPhase,
+    work: impl std::future::Future<Output = Result<T, ProvisioningError>>,
+) -> Result<T, (ProvisioningPhase, ProvisioningError)> {
+    let started = std::time::Instant::now();
+    let result = work.await;
+    report.phases.push(PhaseTiming {
+        phase,
+        attempt: report.attempts,
+        duration_ms: started.elapsed().as_millis() as u64,
+        succeeded: result.is_ok(),
+    });
+    result.map_err(|error| (phase, error))
+}
+
 /// Main entry point for the runtime system.
 pub struct RuntimeService {
     /// Warm pool for sandboxes.
     pool: WarmPool,
 
+    /// Hardware sandboxes may request.
+    host: HostCapabilities,
+
+    /// Resource class of sandboxes with GPUs attached (others are CPU-only).
+    sandbox_classes: RwLock<HashMap<SandboxId, ResourceClass>>,
+
+    /// Resource limits of provisioned sandboxes, enforced while they execute.
+    sandbox_limits: RwLock<HashMap<SandboxId, ResourceLimits>>,
+
     /// Code executor.
-    executor: Executor,
+    executor: Box<dyn CodeExecutor>,
+
+    /// Sandbox backend used to provision fresh sandboxes (optional).
+    backend: Option<Box<dyn SandboxBackend>>,
+
+    /// Host-specific work around the backend while provisioning.
+    provisioner: Box<dyn SandboxProvisioner>,
+
+    /// Retries for retryable provisioning failures.
+    provisioning_retry: ProvisioningRetryPolicy,
+
+    /// Warm pool runtimes whose replenishment stopped on a permanent failure.
+    replenishment_suspended: RwLock<HashMap<String, ProvisioningFailure>>,
+
+    /// Backend handles for provisioned sandboxes not held by the pool.
+    runtime_handles: RwLock<HashMap<SandboxId, String>>,
+
+    /// Attestation generator and the platform it attests (optional).
+    attestation: Option<(Box<dyn AttestationGenerator>, AttestationPlatform)>,
+
+    /// Verifies sandbox attestations before every execution (optional).
+    attestation_verifier: Option<Box<dyn AttestationVerifier>>,
+
+    /// Whether expired attestations are regenerated rather than rejected.
+    renew_expired_attestations: bool,
+
+    /// Attestations of provisioned sandboxes, verified while they execute.
+    sandbox_attestations: RwLock<HashMap<SandboxId, SandboxAttestation>>,
 
     /// Secret provider.
     secret_provider: Option<Box<dyn SecretProvider>>,
 
     /// Checkpoint manager.
     checkpoint_manager: Box<dyn CheckpointManager>,
+
+    /// Organization network policy templates.
+    policy_templates: Box<dyn NetworkPolicyTemplateStore>,
+
+    /// Whether template updates are pushed to running sandboxes.
+    propagate_template_updates: bool,
+
+    /// Organization runtime policies merged into requested configurations.
+    runtime_policies: Box<dyn OrgRuntimePolicyStore>,
+
+    /// Egress enforcement for sandboxes created from a template.
+    sandbox_egress: RwLock<HashMap<SandboxId, SandboxEgress>>,
+
+    /// Sandbox persistence (optional).
+    sandbox_repository: Option<Box<dyn SandboxRepository>>,
+
+    /// Execution request persistence (optional).
+    execution_repository: Option<Box<dyn ExecutionRepository>>,
+
+    /// Resource usage persistence (optional).
+    resource_usage_repository: Option<Box<dyn ResourceUsageRepository>>,
+
+    /// Record of which sandboxes were given which secrets.
+    secret_grants: Box<dyn SecretGrantStore>,
+
+    /// Executor hook for detecting secret reads.
+    secret_access_monitor: Box<dyn SecretAccessMonitor>,
+
+    /// Audit event export (optional).
+    audit_sink: Option<Box<dyn AuditSink>>,
+
+    /// Per-type rate limits applied to exported audit events.
+    audit_limiter: AuditRateLimiter,
+
+    /// Background usage sampling of running sandboxes (optional).
+    usage_sampler: Option<Arc<SandboxUsageSampler>>,
+
+    /// Where the usage of completed executions is reported (optional).
+    usage_sink: Option<Arc<dyn UsageEventSink>>,
+
+    /// Structured log capture settings.
+    log_capture: LogCaptureConfig,
+
+    /// Structured execution log storage.
+    execution_logs: Box<dyn ExecutionLogStore>,
+
+    /// Scrubs secrets from execution output before it is stored.
+    redaction: RedactionEngine,
+
+    /// Fingerprints of the secrets mounted into each sandbox.
+    secret_fingerprints: RwLock<HashMap<SandboxId, Vec<SecretFingerprint>>>,
+
+    /// Lease TTL and renewal settings for mounted secrets.
+    secret_lease_config: SecretLeaseConfig,
+
+    /// Leased secrets mounted in each live sandbox.
+    secret_leases: RwLock<HashMap<SandboxId, Vec<MountedSecret>>>,
+
+    /// Secrets of paused sandboxes to lease again on resume.
+    suspended_secrets: RwLock<HashMap<SandboxId, Vec<CheckpointedSecret>>>,
+
+    /// Admission queue bounding concurrent executions (optional).
+    execution_queue: Option<Arc<ExecutionQueue>>,
+
+    /// Registers background executions for coordinated shutdown (optional).
+    shutdown: Option<Arc<ShutdownCoordinator>>,
+
+    /// Results of executions made under idempotency keys.
+    idempotency: IdempotencyCache,
+
+    /// Workdir change tracking settings and workdir lookup (optional).
+    fs_tracking: Option<(FilesystemTrackingConfig, Box<dyn WorkdirResolver>)>,
+
+    /// Full filesystem diff storage.
+    fs_diffs: Box<dyn FilesystemDiffStore>,
+
+    /// Proxy for brokered sandbox-to-sandbox channels (optional).
+    agent_channels: Option<Arc<AgentChannelProxy>>,
+
+    /// Scheduled execution storage.
+    schedules: Box<dyn ScheduleStore>,
+
+    /// Checks run before each scheduled execution (optional).
+    schedule_gate: Option<Box<dyn ScheduleGate>>,
+
+    /// Scheduler worker settings.
+    scheduler: SchedulerConfig,
+
+    /// Source of topic messages for triggers (optional).
+    topic_source: Option<Arc<dyn TopicSource>>,
+
+    /// Registered topic triggers and their dispatch state.
+    topic_triggers: Arc<dyn TopicTriggerStore>,
+
+    /// Held for the length of a trigger dispatch pass.
+    trigger_pass: tokio::sync::Mutex<()>,
+
+    /// Checks run before each triggered execution (optional).
+    trigger_gate: Option<Box<dyn TriggerGate>>,
+
+    /// Trigger dispatcher settings.
+    trigger_dispatch: TriggerDispatchConfig,
+
+    /// Exclusion key holders and their persisted leases.
+    exclusion: Arc<ExclusionRegistry>,
+
+    /// Idle tracking of the sandboxes handed to agents.
+    idle: Arc<IdleTracker>,
+
+    /// Source of the quota snapshot injected into executions, and the
+    /// metrics it covers.
+    quota_status: Option<(Arc<dyn QuotaStatusProvider>, Vec<String>)>,
+
+    /// Default idle policy and sweep interval.
+    idle_config: IdleConfig,
+
+    /// Sandbox-lifetime CPU and wall time limit tracking.
+    limit_enforcer: LimitEnforcer,
+
+    /// Limit warning and termination export (optional).
+    limit_events: Option<Box<dyn LimitEventSink>>,
+
+    /// Time source for schedules, trigger retries and exclusion leases.
+    clock: Arc<dyn Clock>,
 }
 
 impl RuntimeService {
@@ -30,9 +426,65 @@ impl RuntimeService {
     pub fn new() -> Self {
         Self {
             pool: WarmPool::new(PoolConfig::default()),
-            executor: Executor::new(),
+            host: HostCapabilities::cpu_only(),
+            sandbox_classes: RwLock::new(HashMap::new()),
+            sandbox_limits: RwLock::new(HashMap::new()),
+            executor: Box::new(Executor::new()),
+            backend: None,
+            provisioner: Box::new(NoopProvisioner),
+            provisioning_retry: ProvisioningRetryPolicy::default(),
+            replenishment_suspended: RwLock::new(HashMap::new()),
+            runtime_handles: RwLock::new(HashMap::new()),
+            attestation: None,
+            attestation_verifier: None,
+            renew_expired_attestations: true,
+            sandbox_attestations: RwLock::new(HashMap::new()),
             secret_provider: None,
             checkpoint_manager: Box::new(InMemoryCheckpointStore::new()),
+            policy_templates: Box::new(InMemoryNetworkPolicyTemplateStore::new()),
+            propagate_template_updates: false,
+            runtime_policies: Box::new(InMemoryOrgRuntimePolicyStore::new()),
+            sandbox_egress: RwLock::new(HashMap::new()),
+            sandbox_repository: None,
+            execution_repository: None,
+            resource_usage_repository: None,
+            secret_grants: Box::new(InMemorySecretGrantStore::new()),
+            secret_access_monitor: Box::new(NoopSecretAccessMonitor),
+            audit_sink: None,
+            audit_limiter: AuditRateLimiter::default(),
+            usage_sampler: None,
+            usage_sink: None,
+            log_capture: LogCaptureConfig::default(),
+            execution_logs: Box::new(InMemoryExecutionLogStore::new()),
+            redaction: RedactionEngine::default(),
+            secret_fingerprints: RwLock::new(HashMap::new()),
+            secret_lease_config: SecretLeaseConfig::default(),
+            secret_leases: RwLock::new(HashMap::new()),
+            suspended_secrets: RwLock::new(HashMap::new()),
+            execution_queue: None,
+            shutdown: None,
+            idempotency: IdempotencyCache::new(IdempotencyConfig::default()),
+            fs_tracking: None,
+            fs_diffs: Box::new(InMemoryFilesystemDiffStore::new()),
+            agent_channels: None,
+            schedules: Box::new(InMemoryScheduleStore::new()),
+            schedule_gate: None,
+            scheduler: SchedulerConfig::default(),
+            topic_source: None,
+            topic_triggers: Arc::new(InMemoryTopicTriggerStore::new()),
+            trigger_pass: tokio::sync::Mutex::new(()),
+            trigger_gate: None,
+            trigger_dispatch: TriggerDispatchConfig::default(),
+            exclusion: Arc::new(ExclusionRegistry::new(
+                Arc::new(InMemoryExclusionLeaseStore::new()),
+                ExclusionConfig::default(),
+            )),
+            idle: Arc::new(IdleTracker::new()),
+            quota_status: None,
+            idle_config: IdleConfig::default(),
+            limit_enforcer: LimitEnforcer::default(),
+            limit_events: None,
+            clock: Arc::new(SystemClock),
         }
     }
 
@@ -40,12 +492,126 @@ impl RuntimeService {
     pub fn with_pool_config(config: PoolConfig) -> Self {
         Self {
             pool: WarmPool::new(config),
-            executor: Executor::new(),
+            host: HostCapabilities::cpu_only(),
+            sandbox_classes: RwLock::new(HashMap::new()),
+            sandbox_limits: RwLock::new(HashMap::new()),
+            executor: Box::new(Executor::new()),
+            backend: None,
+            provisioner: Box::new(NoopProvisioner),
+            provisioning_retry: ProvisioningRetryPolicy::default(),
+            replenishment_suspended: RwLock::new(HashMap::new()),
+            runtime_handles: RwLock::new(HashMap::new()),
+            attestation: None,
+            attestation_verifier: None,
+            renew_expired_attestations: true,
+            sandbox_attestations: RwLock::new(HashMap::new()),
             secret_provider: None,
             checkpoint_manager: Box::new(InMemoryCheckpointStore::new()),
+            policy_templates: Box::new(InMemoryNetworkPolicyTemplateStore::new()),
+            propagate_template_updates: false,
+            runtime_policies: Box::new(InMemoryOrgRuntimePolicyStore::new()),
+            sandbox_egress: RwLock::new(HashMap::new()),
+            sandbox_repository: None,
+            execution_repository: None,
+            resource_usage_repository: None,
+            secret_grants: Box::new(InMemorySecretGrantStore::new()),
+            secret_access_monitor: Box::new(NoopSecretAccessMonitor),
+            audit_sink: None,
+            audit_limiter: AuditRateLimiter::default(),
+            usage_sampler: None,
+            usage_sink: None,
+            log_capture: LogCaptureConfig::default(),
+            execution_logs: Box::new(InMemoryExecutionLogStore::new()),
+            redaction: RedactionEngine::default(),
+            secret_fingerprints: RwLock::new(HashMap::new()),
+            secret_lease_config: SecretLeaseConfig::default(),
+            secret_leases: RwLock::new(HashMap::new()),
+            suspended_secrets: RwLock::new(HashMap::new()),
+            execution_queue: None,
+            shutdown: None,
+            idempotency: IdempotencyCache::new(IdempotencyConfig::default()),
+            fs_tracking: None,
+            fs_diffs: Box::new(InMemoryFilesystemDiffStore::new()),
+            agent_channels: None,
+            schedules: Box::new(InMemoryScheduleStore::new()),
+            schedule_gate: None,
+            scheduler: SchedulerConfig::default(),
+            topic_source: None,
+            topic_triggers: Arc::new(InMemoryTopicTriggerStore::new()),
+            trigger_pass: tokio::sync::Mutex::new(()),
+            trigger_gate: None,
+            trigger_dispatch: TriggerDispatchConfig::default(),
+            exclusion: Arc::new(ExclusionRegistry::new(
+                Arc::new(InMemoryExclusionLeaseStore::new()),
+                ExclusionConfig::default(),
+            )),
+            idle: Arc::new(IdleTracker::new()),
+            quota_status: None,
+            idle_config: IdleConfig::default(),
+            limit_enforcer: LimitEnforcer::default(),
+            limit_events: None,
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Set the hardware available to sandboxes (defaults to CPU-only).
+    pub fn with_host_capabilities(mut self, host: HostCapabilities) -> Self {
+        self.host = host;
+        self
+    }
+
+    /// Set the code executor.
+    pub fn with_executor(mut self, executor: Box<dyn CodeExecutor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Set the backend used to provision sandboxes on a pool miss.
+    pub fn with_sandbox_backend(mut self, backend: Box<dyn SandboxBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Set the provisioner run around the backend on a pool miss.
+    pub fn with_provisioner(mut self, provisioner: Box<dyn SandboxProvisioner>) -> Self {
+        self.provisioner = provisioner;
+        self
+    }
+
+    /// Set how retryable provisioning failures are retried.
+    pub fn with_provisioning_retry(mut self, policy: ProvisioningRetryPolicy) -> Self {
+        self.provisioning_retry = policy;
+        self
+    }
+
+    /// Attest every sandbox handed out, using `generator` on `platform`.
+    pub fn with_attestation_generator(
+        mut self,
+        generator: Box<dyn AttestationGenerator>,
+        platform: AttestationPlatform,
+    ) -> Self {
+        self.attestation = Some((generator, platform));
+        self
+    }
+
+    /// Verify each sandbox's attestation against its policy before every
+    /// execution.
+    ///
+    /// A sandbox whose attestation fails verification, or whose policy
+    /// requires an attestation it lacks, has its executions refused with
+    /// an `ATTESTATION_REJECTED` error.
+    pub fn with_attestation_verifier(mut self, verifier: Box<dyn AttestationVerifier>) -> Self {
+        self.attestation_verifier = Some(verifier);
+        self
+    }
+
+    /// Set whether expired attestations are regenerated with the
+    /// attestation generator before verification (the default) or rejected.
+    pub fn with_expired_attestation_renewal(mut self, enabled: bool) -> Self {
+        self.renew_expired_attestations = enabled;
+        self
+    }
+
     /// Set the secret provider.
     pub fn with_secret_provider(mut self, provider: Box<dyn SecretProvider>) -> Self {
         self.secret_provider = Some(provider);
@@ -58,254 +624,8222 @@ impl RuntimeService {
         self
     }
 
-    /// Initialize the runtime (pre-warm pools).
-    pub async fn initialize(&self) -> CretoResult<()> {
-        self.pool.initialize().await
+    /// Set the network policy template store.
+    pub fn with_policy_template_store(
+        mut self,
+        store: Box<dyn NetworkPolicyTemplateStore>,
+    ) -> Self {
+        self.policy_templates = store;
+        self
     }
 
-    /// Create a new sandbox.
+    /// Push template updates to running sandboxes (default: new sandboxes only).
+    pub fn with_template_propagation(mut self, enabled: bool) -> Self {
+        self.propagate_template_updates = enabled;
+        self
+    }
+
+    /// Set the organization runtime policy store.
+    pub fn with_runtime_policy_store(mut self, store: Box<dyn OrgRuntimePolicyStore>) -> Self {
+        self.runtime_policies = store;
+        self
+    }
+
+    /// Set the sandbox repository used to persist newly created sandboxes.
+    pub fn with_sandbox_repository(mut self, repository: Box<dyn SandboxRepository>) -> Self {
+        self.sandbox_repository = Some(repository);
+        self
+    }
+
+    /// Set the execution repository used to persist execution requests.
+    pub fn with_execution_repository(mut self, repository: Box<dyn ExecutionRepository>) -> Self {
+        self.execution_repository = Some(repository);
+        self
+    }
+
+    /// Set the repository used to persist execution resource usage.
+    pub fn with_resource_usage_repository(
+        mut self,
+        repository: Box<dyn ResourceUsageRepository>,
+    ) -> Self {
+        self.resource_usage_repository = Some(repository);
+        self
+    }
+
+    /// Sample the usage of sandboxes while they run.
     ///
-    /// Attempts to acquire from warm pool first, creates new if none available.
-    pub async fn create_sandbox(
-        &self,
-        organization_id: OrganizationId,
-        agent_id: AgentId,
-        config: SandboxConfig,
-    ) -> CretoResult<Sandbox> {
-        // Try to acquire from warm pool
-        if let Some(mut sandbox) = self.pool.acquire(&config.runtime).await {
-            // Update ownership
-            sandbox.organization_id = organization_id;
-            sandbox.agent_id = agent_id;
-            tracing::debug!(
-                sandbox_id = %sandbox.id,
-                runtime = %config.runtime,
-                "Acquired sandbox from warm pool"
-            );
-            return Ok(sandbox);
-        }
+    /// Sampling starts once a sandbox is provisioned, if the sandbox backend
+    /// exposes a [`ResourceController`](crate::sampling::ResourceController)
+    /// for it, and stops with a final sample when the sandbox is terminated
+    /// or hibernated.
+    pub fn with_usage_sampler(mut self, sampler: Arc<SandboxUsageSampler>) -> Self {
+        self.usage_sampler = Some(sampler);
+        self
+    }
 
-        // Create new sandbox
-        tracing::debug!(
-            runtime = %config.runtime,
-            "Creating new sandbox (pool miss)"
-        );
+    /// Report the duration, CPU time and GPU time of every completed
+    /// execution to `sink`.
+    ///
+    /// Failed emits are logged; the execution result is returned either way.
+    pub fn with_usage_sink(mut self, sink: Arc<dyn UsageEventSink>) -> Self {
+        self.usage_sink = Some(sink);
+        self
+    }
 
-        let sandbox = Sandbox::new(organization_id, agent_id, config);
+    /// Set how long mounted secrets are leased for and when they are
+    /// renewed.
+    pub fn with_secret_leases(mut self, config: SecretLeaseConfig) -> Self {
+        self.secret_lease_config = config;
+        self
+    }
 
-        // TODO: Actually create the sandbox via backend
-        // let handle = backend.create(&sandbox.config).await?;
-        // sandbox.mark_ready(handle);
+    /// Set the store used to record secret grants.
+    pub fn with_secret_grant_store(mut self, store: Box<dyn SecretGrantStore>) -> Self {
+        self.secret_grants = store;
+        self
+    }
 
-        Ok(sandbox)
+    /// Set the executor hook used to detect secret reads.
+    pub fn with_secret_access_monitor(mut self, monitor: Box<dyn SecretAccessMonitor>) -> Self {
+        self.secret_access_monitor = monitor;
+        self
     }
 
-    /// Execute code in a sandbox.
-    pub async fn execute(
-        &self,
-        sandbox_id: SandboxId,
-        code: impl Into<String>,
-    ) -> CretoResult<ExecutionResult> {
-        let request = ExecutionRequest::new(sandbox_id, code);
-        self.executor.execute(request).await
+    /// Set the sink that receives runtime audit events.
+    pub fn with_audit_sink(mut self, sink: Box<dyn AuditSink>) -> Self {
+        self.audit_sink = Some(sink);
+        self
     }
 
-    /// Execute code with secrets injected.
-    pub async fn execute_with_secrets(
-        &self,
-        sandbox_id: SandboxId,
-        organization_id: OrganizationId,
-        agent_id: AgentId,
-        code: impl Into<String>,
-        secrets: Vec<SecretMount>,
-    ) -> CretoResult<ExecutionResult> {
-        // Resolve secrets
-        if let Some(provider) = &self.secret_provider {
-            for secret in &secrets {
-                // Verify authorization
-                if !provider
-                    .authorize(organization_id, agent_id, &secret.source)
-                    .await?
-                {
-                    return Err(CretoError::NotAuthorized {
-                        resource: format!("secret:{}", secret.name),
-                        action: "access".to_string(),
-                    });
-                }
+    /// Set the per-type rate limits for audit events (by default egress
+    /// denials are limited; see [`AuditRateLimitConfig`]).
+    pub fn with_audit_rate_limits(mut self, config: AuditRateLimitConfig) -> Self {
+        self.audit_limiter = AuditRateLimiter::new(config);
+        self
+    }
 
-                // TODO: Inject secret into sandbox
-                let _value = provider
-                    .resolve(organization_id, agent_id, &secret.source)
-                    .await?;
-                // backend.inject_secret(sandbox_id, &secret.name, value).await?;
-            }
-        }
+    /// Set the structured log capture configuration.
+    pub fn with_log_capture_config(mut self, config: LogCaptureConfig) -> Self {
+        self.log_capture = config;
+        self
+    }
 
-        // Execute
-        let request = ExecutionRequest::new(sandbox_id, code);
-        self.executor.execute(request).await
+    /// Set the engine that redacts secrets from execution output.
+    pub fn with_redaction(mut self, engine: RedactionEngine) -> Self {
+        self.redaction = engine;
+        self
+    }
+
+    /// Set the store used to persist structured execution logs.
+    pub fn with_execution_log_store(mut self, store: Box<dyn ExecutionLogStore>) -> Self {
+        self.execution_logs = store;
+        self
+    }
+
+    /// Bound concurrent executions with an admission queue.
+    pub fn with_execution_queue(mut self, config: ExecutionQueueConfig) -> Self {
+        self.execution_queue = Some(Arc::new(ExecutionQueue::new(config)));
+        self
+    }
+
+    /// Register submitted and streaming executions with `shutdown`.
+    ///
+    /// On shutdown, submitted executions still waiting in the admission
+    /// queue are cancelled; those already running finish.
+    pub fn with_shutdown(mut self, shutdown: Arc<ShutdownCoordinator>) -> Self {
+        self.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Set how long, and how many, idempotent execution results are kept.
+    pub fn with_idempotency_config(mut self, config: IdempotencyConfig) -> Self {
+        self.idempotency = IdempotencyCache::new(config);
+        self
+    }
+
+    /// Track workdir changes made by each execution.
+    ///
+    /// `resolver` locates a sandbox's workdir on the host; sandboxes it
+    /// cannot resolve are not tracked.
+    pub fn with_filesystem_tracking(
+        mut self,
+        config: FilesystemTrackingConfig,
+        resolver: Box<dyn WorkdirResolver>,
+    ) -> Self {
+        self.fs_tracking = Some((config, resolver));
+        self
+    }
+
+    /// Set the filesystem diff store.
+    pub fn with_filesystem_diff_store(mut self, store: Box<dyn FilesystemDiffStore>) -> Self {
+        self.fs_diffs = store;
+        self
+    }
+
+    /// Inject a snapshot of the agent's remaining quota for `metrics` into
+    /// every execution as [`QUOTA_SNAPSHOT_ENV`](crate::context::QUOTA_SNAPSHOT_ENV).
+    pub fn with_quota_status_provider(
+        mut self,
+        provider: Arc<dyn QuotaStatusProvider>,
+        metrics: Vec<String>,
+    ) -> Self {
+        self.quota_status = Some((provider, metrics));
+        self
+    }
+
+    /// Enable brokered channels between sandboxes.
+    pub fn with_agent_channels(mut self, proxy: Arc<AgentChannelProxy>) -> Self {
+        self.agent_channels = Some(proxy);
+        self
     }
 
-    /// Release a sandbox back to the pool.
-    pub async fn release_sandbox(&self, sandbox_id: SandboxId) -> CretoResult<()> {
-        self.pool.release(sandbox_id).await
-    }
+    /// Set the schedule store.
+    pub fn with_schedule_store(mut self, store: Box<dyn ScheduleStore>) -> Self {
+        self.schedules = store;
+        self
+    }
+
+    /// Set the checks run before each scheduled execution.
+    pub fn with_schedule_gate(mut self, gate: Box<dyn ScheduleGate>) -> Self {
+        self.schedule_gate = Some(gate);
+        self
+    }
+
+    /// Set the scheduler worker settings.
+    pub fn with_scheduler_config(mut self, config: SchedulerConfig) -> Self {
+        self.scheduler = config;
+        self
+    }
+
+    /// Set the source of topic messages for triggers.
+    pub fn with_topic_source(mut self, source: Arc<dyn TopicSource>) -> Self {
+        self.topic_source = Some(source);
+        self
+    }
+
+    /// Set the topic trigger storage.
+    pub fn with_topic_trigger_store(mut self, store: Arc<dyn TopicTriggerStore>) -> Self {
+        self.topic_triggers = store;
+        self
+    }
+
+    /// Set the checks run before each triggered execution.
+    pub fn with_trigger_gate(mut self, gate: Box<dyn TriggerGate>) -> Self {
+        self.trigger_gate = Some(gate);
+        self
+    }
+
+    /// Set the trigger dispatcher settings.
+    pub fn with_trigger_dispatch_config(mut self, config: TriggerDispatchConfig) -> Self {
+        self.trigger_dispatch = config;
+        self
+    }
+
+    /// Set the time source used for schedules and trigger retries.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Set where exclusion leases are persisted and how they are kept.
+    ///
+    /// Services sharing a store exclude each other's executions; give each
+    /// a distinct, stable [`node_id`](ExclusionConfig::node_id).
+    pub fn with_exclusion_leases(
+        mut self,
+        store: Arc<dyn ExclusionLeaseStore>,
+        config: ExclusionConfig,
+    ) -> Self {
+        self.exclusion = Arc::new(ExclusionRegistry::new(store, config));
+        self
+    }
+
+    /// Set the default idle policy and how often idle sandboxes are swept.
+    pub fn with_idle_config(mut self, config: IdleConfig) -> Self {
+        self.idle_config = config;
+        self
+    }
+
+    /// Set the probe pool health sweeps run against warm sandboxes.
+    ///
+    /// When and how often probes run is set by the pool configuration's
+    /// [`health`](PoolConfig::health).
+    pub fn with_pool_health_probe(mut self, probe: Arc<dyn HealthProbe>) -> Self {
+        self.pool = self.pool.with_health_probe(probe);
+        self
+    }
+
+    /// Set the store the warm pool restores checkpoints from.
+    pub fn with_pool_checkpoint_store(mut self, store: Arc<dyn CheckpointManager>) -> Self {
+        self.pool = self.pool.with_checkpoint_store(store);
+        self
+    }
+
+    /// Set the soft limit and check interval of limit enforcement.
+    pub fn with_limit_enforcement(mut self, config: LimitEnforcerConfig) -> Self {
+        self.limit_enforcer = LimitEnforcer::new(config);
+        self
+    }
+
+    /// Set where limit warnings and terminations are reported.
+    pub fn with_limit_event_sink(mut self, sink: Box<dyn LimitEventSink>) -> Self {
+        self.limit_events = Some(sink);
+        self
+    }
+
+    /// Initialize the runtime (pre-warm pools, recover exclusion leases,
+    /// restore the limits of sandboxes provisioned before a restart).
+    pub async fn initialize(&self) -> CretoResult<()> {
+        self.recover_exclusion_leases().await?;
+        self.restore_sandbox_limits().await?;
+        self.pool.initialize().await
+    }
+
+    /// Register the limits and resource class of active sandboxes from
+    /// their persisted records, so their executions after a restart are
+    /// admitted, limited and metered as before.
+    async fn restore_sandbox_limits(&self) -> CretoResult<()> {
+        let Some(repository) = &self.sandbox_repository else {
+            return Ok(());
+        };
+        for record in repository.list_active().await? {
+            let Some(limits) = record.resource_limits else {
+                continue;
+            };
+            let class = limits.resource_class();
+            if class.is_gpu() {
+                self.sandbox_classes
+                    .write()
+                    .unwrap()
+                    .entry(record.id)
+                    .or_insert(class);
+            }
+            self.sandbox_limits
+                .write()
+                .unwrap()
+                .entry(record.id)
+                .or_insert(limits);
+        }
+        Ok(())
+    }
+
+    /// Create a new sandbox.
+    ///
+    /// The organization's runtime policy, if any, is merged into `config`
+    /// first; a request that weakens a pinned field fails with
+    /// [`CretoError::PolicyViolation`]. GPU requests the host cannot satisfy
+    /// fail with [`CretoError::ValidationFailed`]. Attempts to acquire from
+    /// the warm pool first (matching the resource class), creates new if
+    /// none available.
+    ///
+    /// Retryable provisioning failures are retried under the service's
+    /// [`ProvisioningRetryPolicy`]; a request that still fails returns
+    /// [`CretoError::SandboxProvisioningFailed`]. Use
+    /// [`create_sandbox_with_retry`](Self::create_sandbox_with_retry) for the
+    /// full provisioning report.
+    pub async fn create_sandbox(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        config: SandboxConfig,
+    ) -> CretoResult<Sandbox> {
+        let prepared = self.prepare_sandbox(organization_id, config).await?;
+        Ok(self
+            .provision_with_retry(
+                organization_id,
+                agent_id,
+                &prepared,
+                &self.provisioning_retry,
+            )
+            .await?)
+    }
+
+    /// Create a new sandbox, waiting for capacity if the host has none.
+    ///
+    /// Like [`create_sandbox`](Self::create_sandbox), except that with an
+    /// admission queue configured, a creation that finds the warm pool empty
+    /// and the host out of capacity waits in the queue at `priority` until a
+    /// sandbox is released or terminated, then tries again. It fails with
+    /// [`CretoError::QueueTimeout`] if no capacity frees up within the
+    /// queue's `max_queue_time`, and counts towards the queue's depth and
+    /// per-organization caps while it waits.
+    pub async fn create_sandbox_queued(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        config: SandboxConfig,
+        priority: ExecutionPriority,
+    ) -> CretoResult<Sandbox> {
+        let prepared = self.prepare_sandbox(organization_id, config).await?;
+        let Some(queue) = &self.execution_queue else {
+            return Ok(self
+                .provision_with_retry(
+                    organization_id,
+                    agent_id,
+                    &prepared,
+                    &self.provisioning_retry,
+                )
+                .await?);
+        };
+
+        let id = Uuid::now_v7();
+        let deadline = tokio::time::Instant::now() + queue.config().max_queue_time;
+        loop {
+            match self
+                .provision_with_retry(
+                    organization_id,
+                    agent_id,
+                    &prepared,
+                    &self.provisioning_retry,
+                )
+                .await
+            {
+                Err(failure) if matches!(failure.error, ProvisioningError::HostPressure(_)) => {
+                    tracing::debug!(
+                        %organization_id,
+                        error = %failure.error,
+                        "No sandbox capacity; waiting in the admission queue"
+                    );
+                    queue
+                        .wait_for_sandbox(id, organization_id, priority, deadline)
+                        .await?;
+                }
+                result => return Ok(result?),
+            }
+        }
+    }
+
+    /// Create a new sandbox under an explicit retry policy.
+    ///
+    /// Pass [`ProvisioningRetryPolicy::disabled`] to make a single attempt.
+    /// Requests rejected before provisioning starts (policy violations,
+    /// unsatisfiable hardware, unknown network templates) fail in the image
+    /// resolution phase without an attempt being made.
+    pub async fn create_sandbox_with_retry(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        config: SandboxConfig,
+        retry: &ProvisioningRetryPolicy,
+    ) -> Result<Sandbox, ProvisioningFailure> {
+        let prepared = match self.prepare_sandbox(organization_id, config).await {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                return Err(ProvisioningFailure {
+                    phase: ProvisioningPhase::ImageResolution,
+                    error: ProvisioningError::classify(&e),
+                    report: ProvisioningReport::new(self.clock.now()),
+                })
+            }
+        };
+        self.provision_with_retry(organization_id, agent_id, &prepared, retry)
+            .await
+    }
+
+    /// Merge the organization policy into a requested configuration and
+    /// check it can be provisioned on this host.
+    async fn prepare_sandbox(
+        &self,
+        organization_id: OrganizationId,
+        config: SandboxConfig,
+    ) -> CretoResult<PreparedSandbox> {
+        let policy = self.runtime_policies.get(organization_id).await?;
+        let mut config = match &policy {
+            Some(policy) => policy.apply(config)?,
+            None => config,
+        };
+        config.limits.get_or_insert_with(ResourceLimits::default);
+        if config.keep_warm {
+            let limit = policy
+                .as_ref()
+                .and_then(|p| p.idle.as_ref())
+                .unwrap_or(&self.idle_config.default_policy)
+                .max_keep_warm;
+            check_keep_warm_quota(self.idle.keep_warm_count(organization_id) as u64, limit)?;
+        }
+        let policy_version = policy.map(|p| p.version);
+        config.validate(&self.host)?;
+
+        // Resolve the template before taking a sandbox so a bad reference fails fast
+        let effective = match &config.network_policy_template {
+            Some(template_ref) => Some(
+                self.resolve_network_policy(organization_id, template_ref)
+                    .await?,
+            ),
+            None => None,
+        };
+
+        Ok(PreparedSandbox {
+            config,
+            effective,
+            policy_version,
+        })
+    }
+
+    /// Provision a prepared sandbox, retrying retryable failures.
+    async fn provision_with_retry(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        prepared: &PreparedSandbox,
+        retry: &ProvisioningRetryPolicy,
+    ) -> Result<Sandbox, ProvisioningFailure> {
+        let started = std::time::Instant::now();
+        let mut report = ProvisioningReport::new(self.clock.now());
+
+        loop {
+            report.attempts += 1;
+            let attempt = report.attempts;
+            let result = self
+                .provision_attempt(organization_id, agent_id, prepared, &mut report)
+                .await;
+            report.total_ms = started.elapsed().as_millis() as u64;
+
+            let (phase, error) = match result {
+                Ok(mut sandbox) => {
+                    self.register_egress(&sandbox);
+                    self.register_class(&sandbox);
+                    self.register_limits(&sandbox);
+                    self.register_attestation(&sandbox);
+                    self.idle.register(&sandbox, self.clock.now());
+                    self.limit_enforcer.register(&sandbox, self.clock.now());
+                    self.start_usage_sampling(
+                        sandbox.id,
+                        sandbox.organization_id,
+                        sandbox.agent_id,
+                        sandbox.runtime_handle.as_deref(),
+                    );
+                    self.emit_audit(RuntimeAuditEvent::new(
+                        sandbox.organization_id,
+                        sandbox.id,
+                        sandbox.agent_id,
+                        RuntimeActivity::SandboxCreated {
+                            runtime: sandbox.config.runtime.clone(),
+                            from_pool: report.from_pool,
+                        },
+                    ))
+                    .await;
+                    sandbox.provisioning = Some(report);
+                    return Ok(sandbox);
+                }
+                Err(failed) => failed,
+            };
+
+            if !error.is_retryable() || attempt >= retry.max_attempts {
+                return Err(ProvisioningFailure {
+                    phase,
+                    error,
+                    report,
+                });
+            }
+            let backoff_ms = retry.backoff_ms(attempt);
+            tracing::warn!(
+                attempt,
+                phase = %phase,
+                error = %error,
+                backoff_ms,
+                "Sandbox provisioning failed; retrying"
+            );
+            tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+        }
+    }
+
+    /// One provisioning attempt: a warm pool checkout, or a fresh sandbox.
+    async fn provision_attempt(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        prepared: &PreparedSandbox,
+        report: &mut ProvisioningReport,
+    ) -> Result<Sandbox, (ProvisioningPhase, ProvisioningError)> {
+        let config = &prepared.config;
+        let attempt = report.attempts;
+
+        // Try to acquire from warm pool
+        if let Some(mut sandbox) = self
+            .pool
+            .acquire_with_limits(&config.runtime, &config.effective_limits())
+            .await
+        {
+            report.from_pool = true;
+            // Update ownership
+            sandbox.organization_id = organization_id;
+            sandbox.agent_id = agent_id;
+            sandbox.effective_network_policy = prepared.effective.clone();
+            // Only the policy applied at checkout changes; the sandbox keeps
+            // the rest of the configuration it was created with
+            if prepared.policy_version.is_some() {
+                sandbox.attestation_policy = config.attestation_policy.clone().unwrap_or_default();
+                sandbox.config.attestation_policy = config.attestation_policy.clone();
+                sandbox.config.network_policy_template = config.network_policy_template.clone();
+                sandbox.runtime_policy_version = prepared.policy_version;
+            }
+            let mut persisted = false;
+            let checked_out = match self.assign_pooled(&sandbox, &mut persisted).await {
+                Ok(()) => {
+                    run_phase(report, ProvisioningPhase::Attestation, async {
+                        self.attest(&mut sandbox)
+                            .await
+                            .map_err(|e| ProvisioningError::classify(&e))
+                    })
+                    .await
+                }
+                Err(error) => Err((ProvisioningPhase::ResourceAllocation, error)),
+            };
+            if let Err((phase, error)) = checked_out {
+                self.record_provisioning_failure(&sandbox, persisted, report, phase, &error)
+                    .await;
+                // Hand the sandbox back rather than leaking it
+                if let Err(e) = self.pool.release(sandbox.id).await {
+                    tracing::error!(
+                        sandbox_id = %sandbox.id,
+                        error = %e,
+                        "Failed to return sandbox to the warm pool"
+                    );
+                }
+                return Err((phase, error));
+            }
+            tracing::debug!(
+                sandbox_id = %sandbox.id,
+                runtime = %config.runtime,
+                "Acquired sandbox from warm pool"
+            );
+            return Ok(sandbox);
+        }
+        report.from_pool = false;
+
+        // Create new sandbox
+        tracing::debug!(
+            runtime = %config.runtime,
+            attempt,
+            "Creating new sandbox (pool miss)"
+        );
+
+        let mut sandbox = Sandbox::new(organization_id, agent_id, config.clone());
+        sandbox.effective_network_policy = prepared.effective.clone();
+        sandbox.runtime_policy_version = prepared.policy_version;
+        self.provision_fresh(&mut sandbox, report, true).await?;
+        Ok(sandbox)
+    }
+
+    /// Run the provisioning phases for a fresh sandbox.
+    ///
+    /// Sandboxes provisioned for the warm pool have no agent yet, so secret
+    /// injection and attestation wait until they are checked out. On failure
+    /// the sandbox is rolled back and the failure recorded.
+    async fn provision_fresh(
+        &self,
+        sandbox: &mut Sandbox,
+        report: &mut ProvisioningReport,
+        for_agent: bool,
+    ) -> Result<(), (ProvisioningPhase, ProvisioningError)> {
+        let mut persisted = false;
+        let result = self
+            .run_provisioning_phases(sandbox, report, for_agent, &mut persisted)
+            .await;
+        if let Err((phase, error)) = &result {
+            self.rollback_provisioning(sandbox).await;
+            self.record_provisioning_failure(sandbox, persisted, report, *phase, error)
+                .await;
+        }
+        result
+    }
+
+    async fn run_provisioning_phases(
+        &self,
+        sandbox: &mut Sandbox,
+        report: &mut ProvisioningReport,
+        for_agent: bool,
+        persisted: &mut bool,
+    ) -> Result<(), (ProvisioningPhase, ProvisioningError)> {
+        run_phase(
+            report,
+            ProvisioningPhase::ImageResolution,
+            self.provisioner.resolve_image(&sandbox.config),
+        )
+        .await?;
+
+        run_phase(
+            report,
+            ProvisioningPhase::ResourceAllocation,
+            self.allocate(sandbox, persisted),
+        )
+        .await?;
+
+        if for_agent {
+            run_phase(
+                report,
+                ProvisioningPhase::SecretInjection,
+                self.provisioner.inject_secrets(sandbox),
+            )
+            .await?;
+
+            run_phase(report, ProvisioningPhase::Attestation, async {
+                self.attest(sandbox)
+                    .await
+                    .map_err(|e| ProvisioningError::classify(&e))
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Write the sandbox record and start the sandbox on the backend.
+    async fn allocate(
+        &self,
+        sandbox: &mut Sandbox,
+        persisted: &mut bool,
+    ) -> Result<(), ProvisioningError> {
+        let classify = |e: CretoError| ProvisioningError::classify(&e);
+
+        if let Some(repository) = &self.sandbox_repository {
+            sandbox.id = repository
+                .create(
+                    sandbox.organization_id,
+                    sandbox.agent_id,
+                    &sandbox.config.runtime,
+                    sandbox.config.network_policy.as_str(),
+                    sandbox.effective_network_policy.as_ref(),
+                    &sandbox.config.effective_limits(),
+                )
+                .await
+                .map_err(classify)?;
+            *persisted = true;
+            if let Some(version) = sandbox.runtime_policy_version {
+                repository
+                    .record_runtime_policy(sandbox.id, &sandbox.config, version)
+                    .await
+                    .map_err(classify)?;
+            }
+        }
+
+        self.provisioner.allocate_resources(&sandbox.config).await?;
+
+        if let Some(backend) = &self.backend {
+            let handle = backend.create(&sandbox.config).await.map_err(classify)?;
+            sandbox.mark_ready(handle.clone());
+            self.runtime_handles
+                .write()
+                .unwrap()
+                .insert(sandbox.id, handle);
+        }
+
+        if let Some(repository) = &self.sandbox_repository {
+            if sandbox.state != SandboxState::Creating {
+                repository
+                    .update_state(sandbox.id, sandbox.state.clone())
+                    .await
+                    .map_err(classify)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Record a warm-pool sandbox's new owner and effective network policy.
+    async fn assign_pooled(
+        &self,
+        sandbox: &Sandbox,
+        persisted: &mut bool,
+    ) -> Result<(), ProvisioningError> {
+        let Some(repository) = &self.sandbox_repository else {
+            return Ok(());
+        };
+        let classify = |e: CretoError| ProvisioningError::classify(&e);
+
+        repository
+            .assign(
+                sandbox.id,
+                sandbox.organization_id,
+                sandbox.agent_id,
+                sandbox.effective_network_policy.as_ref(),
+            )
+            .await
+            .map_err(classify)?;
+        *persisted = true;
+        if let Some(version) = sandbox.runtime_policy_version {
+            repository
+                .record_runtime_policy(sandbox.id, &sandbox.config, version)
+                .await
+                .map_err(classify)?;
+        }
+        Ok(())
+    }
+
+    /// Keep a failed attempt for operators.
+    ///
+    /// Errors are logged rather than returned so the provisioning error is
+    /// what the caller sees.
+    async fn record_provisioning_failure(
+        &self,
+        sandbox: &Sandbox,
+        persisted: bool,
+        report: &ProvisioningReport,
+        phase: ProvisioningPhase,
+        error: &ProvisioningError,
+    ) {
+        tracing::warn!(
+            sandbox_id = %sandbox.id,
+            runtime = %sandbox.config.runtime,
+            attempt = report.attempts,
+            phase = %phase,
+            error = %error,
+            "Sandbox provisioning attempt failed"
+        );
+
+        let Some(repository) = &self.sandbox_repository else {
+            return;
+        };
+        let record = ProvisioningFailureRecord {
+            id: Uuid::now_v7(),
+            organization_id: sandbox.organization_id,
+            agent_id: sandbox.agent_id,
+            sandbox_id: persisted.then_some(sandbox.id),
+            runtime: sandbox.config.runtime.clone(),
+            attempt: report.attempts,
+            phase,
+            error: error.clone(),
+            phases: report.attempt_phases(report.attempts),
+            failed_at: self.clock.now(),
+        };
+        if let Err(e) = repository.record_provisioning_failure(&record).await {
+            tracing::error!(
+                sandbox_id = %sandbox.id,
+                error = %e,
+                "Failed to record provisioning failure"
+            );
+        }
+    }
+
+    /// Remember a GPU sandbox's class for admission control.
+    fn register_class(&self, sandbox: &Sandbox) {
+        let class = sandbox.config.resource_class();
+        if class.is_gpu() {
+            self.sandbox_classes
+                .write()
+                .unwrap()
+                .insert(sandbox.id, class);
+        }
+    }
+
+    /// Remember a sandbox's limits for enforcement while it executes.
+    fn register_limits(&self, sandbox: &Sandbox) {
+        self.sandbox_limits
+            .write()
+            .unwrap()
+            .insert(sandbox.id, sandbox.config.effective_limits());
+    }
+
+    /// Remember a sandbox's attestation for verification while it executes.
+    fn register_attestation(&self, sandbox: &Sandbox) {
+        self.sandbox_attestations.write().unwrap().insert(
+            sandbox.id,
+            SandboxAttestation {
+                attestation: sandbox.attestation.clone(),
+                policy: sandbox.attestation_policy.clone(),
+            },
+        );
+    }
+
+    /// Verify a sandbox's attestation against its policy, returning why it
+    /// was rejected.
+    ///
+    /// Sandboxes this service did not provision, and every sandbox when no
+    /// verifier is configured, pass. An expired attestation is regenerated
+    /// first when renewal is enabled and a generator is configured.
+    async fn verify_attestation(&self, sandbox_id: SandboxId) -> Option<String> {
+        let verifier = self.attestation_verifier.as_ref()?;
+        let SandboxAttestation {
+            attestation,
+            policy,
+        } = self
+            .sandbox_attestations
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .cloned()?;
+        let Some(mut attestation) = attestation else {
+            return policy
+                .require_attestation
+                .then(|| "sandbox has no attestation".to_string());
+        };
+
+        if !attestation.is_valid() && self.renew_expired_attestations {
+            if let Some((generator, _)) = &self.attestation {
+                // Only an authentic attestation is renewed, so tampered
+                // fields are never signed again
+                match verifier.verify(&attestation).await {
+                    Ok(true) => {}
+                    Ok(false) => return Some("attestation signature is invalid".to_string()),
+                    Err(e) => return Some(e.to_string()),
+                }
+                let renewed = generator
+                    .generate(
+                        attestation.sandbox_id,
+                        attestation.agent_id,
+                        attestation.image_hash.clone(),
+                        attestation.config_hash.clone(),
+                        attestation.init_hash.clone(),
+                        attestation.platform,
+                    )
+                    .await;
+                self.audit_sandbox(
+                    sandbox_id,
+                    RuntimeActivity::AttestationGenerated {
+                        platform: attestation.platform,
+                        succeeded: renewed.is_ok(),
+                    },
+                )
+                .await;
+                attestation = match renewed {
+                    Ok(renewed) => renewed,
+                    Err(e) => return Some(format!("failed to renew expired attestation: {}", e)),
+                };
+                if let Some(entry) = self
+                    .sandbox_attestations
+                    .write()
+                    .unwrap()
+                    .get_mut(&sandbox_id)
+                {
+                    entry.attestation = Some(attestation.clone());
+                }
+                tracing::debug!(sandbox_id = %sandbox_id, "Renewed expired sandbox attestation");
+            }
+        }
+
+        match verifier.verify_with_policy(&attestation, &policy).await {
+            Ok(true) => None,
+            Ok(false) => Some("attestation signature is invalid".to_string()),
+            Err(CretoError::ValidationFailed(reason)) => Some(reason),
+            Err(e) => Some(e.to_string()),
+        }
+    }
+
+    /// Start sampling a sandbox's usage, if a sampler is configured and the
+    /// backend can read the sandbox's usage.
+    fn start_usage_sampling(
+        &self,
+        sandbox_id: SandboxId,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        handle: Option<&str>,
+    ) {
+        let (Some(sampler), Some(backend), Some(handle)) =
+            (&self.usage_sampler, &self.backend, handle)
+        else {
+            return;
+        };
+        if let Some(controller) = backend.resource_controller(handle) {
+            sampler.start_for(sandbox_id, organization_id, agent_id, controller);
+        }
+    }
+
+    /// Stop sampling a sandbox's usage after a final sample.
+    async fn stop_usage_sampling(&self, sandbox_id: SandboxId) {
+        if let Some(sampler) = &self.usage_sampler {
+            sampler.stop(sandbox_id).await;
+        }
+    }
+
+    /// Report a completed execution's usage, if a usage sink is configured.
+    ///
+    /// Failures are logged rather than returned so the execution result
+    /// still reaches the caller.
+    async fn emit_execution_usage(&self, sandbox_id: SandboxId, result: &ExecutionResult) {
+        let Some(sink) = &self.usage_sink else {
+            return;
+        };
+        let owner = match self.idle.owner(sandbox_id) {
+            Some(owner) => Some(owner),
+            None => match &self.sandbox_repository {
+                Some(repository) => match repository.get(sandbox_id).await {
+                    Ok(record) => record.map(|r| (r.organization_id, r.agent_id)),
+                    Err(e) => {
+                        tracing::warn!(
+                            sandbox_id = %sandbox_id,
+                            error = %e,
+                            "Failed to look up sandbox owner for usage"
+                        );
+                        None
+                    }
+                },
+                None => None,
+            },
+        };
+        let Some((organization_id, agent_id)) = owner else {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                execution_id = %result.request_id,
+                "Execution usage not reported for sandbox with unknown owner"
+            );
+            return;
+        };
+        let gpu_kind = match self.sandbox_class(sandbox_id) {
+            ResourceClass::Gpu { kind, .. } => kind,
+            ResourceClass::Cpu => None,
+        };
+        let event = ExecutionUsageEvent::new(
+            organization_id,
+            agent_id,
+            sandbox_id,
+            result,
+            gpu_kind.as_deref(),
+        );
+        if let Err(e) = sink.emit_execution(event).await {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                execution_id = %result.request_id,
+                error = %e,
+                "Failed to report execution usage"
+            );
+        }
+    }
+
+    /// Wall time and output limits of a request, from its sandbox's limits
+    /// (the defaults for sandboxes this service did not provision).
+    fn stream_limits(&self, request: &ExecutionRequest) -> StreamLimits {
+        let limits = self
+            .sandbox_limits
+            .read()
+            .unwrap()
+            .get(&request.sandbox_id)
+            .cloned()
+            .unwrap_or_default();
+        StreamLimits::for_request(&limits, request)
+    }
+
+    /// Resource class a sandbox's executions are admitted under.
+    fn sandbox_class(&self, sandbox_id: SandboxId) -> ResourceClass {
+        self.sandbox_classes
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Undo a partially provisioned sandbox.
+    ///
+    /// Errors are logged rather than returned so the provisioning error is
+    /// what the caller sees.
+    async fn rollback_provisioning(&self, sandbox: &mut Sandbox) {
+        sandbox.mark_failed();
+
+        let handle = self.runtime_handles.write().unwrap().remove(&sandbox.id);
+        if let (Some(backend), Some(handle)) = (&self.backend, handle) {
+            if let Err(e) = backend.terminate(&handle).await {
+                tracing::error!(
+                    sandbox_id = %sandbox.id,
+                    error = %e,
+                    "Failed to tear down sandbox after provisioning failure"
+                );
+            }
+        }
+
+        if let Some(repository) = &self.sandbox_repository {
+            if let Err(e) = repository
+                .update_state(sandbox.id, SandboxState::Failed)
+                .await
+            {
+                tracing::error!(
+                    sandbox_id = %sandbox.id,
+                    error = %e,
+                    "Failed to mark sandbox as failed"
+                );
+            }
+        }
+    }
+
+    /// Attach a fresh attestation, if an attestation generator is configured.
+    async fn attest(&self, sandbox: &mut Sandbox) -> CretoResult<()> {
+        let Some((generator, platform)) = &self.attestation else {
+            return Ok(());
+        };
+
+        let measurements = AttestationMeasurements::of(sandbox);
+        let attestation = generator
+            .generate(
+                sandbox.id,
+                sandbox.agent_id,
+                measurements.image_hash,
+                measurements.config_hash,
+                measurements.init_hash,
+                *platform,
+            )
+            .await;
+        self.emit_audit(RuntimeAuditEvent::new(
+            sandbox.organization_id,
+            sandbox.id,
+            sandbox.agent_id,
+            RuntimeActivity::AttestationGenerated {
+                platform: *platform,
+                succeeded: attestation.is_ok(),
+            },
+        ))
+        .await;
+        sandbox.attestation = Some(attestation?);
+        Ok(())
+    }
+
+    /// Resolve a sandbox's effective policy from an organization template.
+    async fn resolve_network_policy(
+        &self,
+        organization_id: OrganizationId,
+        template_ref: &NetworkPolicyTemplateRef,
+    ) -> CretoResult<EffectiveNetworkPolicy> {
+        let template = self
+            .get_network_policy_template(organization_id, &template_ref.name)
+            .await?;
+        Ok(template.resolve(&template_ref.additional_rules))
+    }
+
+    /// Start enforcing a sandbox's template-derived policy.
+    fn register_egress(&self, sandbox: &Sandbox) {
+        if let Some(effective) = &sandbox.effective_network_policy {
+            self.sandbox_egress.write().unwrap().insert(
+                sandbox.id,
+                SandboxEgress {
+                    organization_id: sandbox.organization_id,
+                    agent_id: sandbox.agent_id,
+                    effective: effective.clone(),
+                    enforcer: NetworkPolicyEnforcer::new(effective.policy.clone()),
+                },
+            );
+        }
+    }
+
+    /// Get the template-derived network policy currently applied to a sandbox.
+    pub fn effective_network_policy(
+        &self,
+        sandbox_id: SandboxId,
+    ) -> Option<EffectiveNetworkPolicy> {
+        self.sandbox_egress
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .map(|egress| egress.effective.clone())
+    }
+
+    /// Check domain egress for a sandbox created from a template.
+    ///
+    /// Returns `None` if the sandbox has no template-derived policy. Use
+    /// [`check_egress`](Self::check_egress) to audit denials.
+    pub fn check_egress_domain(
+        &self,
+        sandbox_id: SandboxId,
+        domain: &str,
+    ) -> Option<EgressDecision> {
+        self.sandbox_egress
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .map(|egress| egress.enforcer.check_domain(domain))
+    }
+
+    /// Check egress to an IP address or domain for a sandbox created from a
+    /// template, auditing denials.
+    ///
+    /// Returns `None` if the sandbox has no template-derived policy.
+    /// Denials are rate limited in the audit log per
+    /// [`with_audit_rate_limits`](Self::with_audit_rate_limits).
+    pub async fn check_egress(
+        &self,
+        sandbox_id: SandboxId,
+        destination: &str,
+    ) -> Option<EgressDecision> {
+        let (decision, organization_id, agent_id) = {
+            let egress = self.sandbox_egress.read().unwrap();
+            let egress = egress.get(&sandbox_id)?;
+            (
+                egress.enforcer.evaluate(destination),
+                egress.organization_id,
+                egress.agent_id,
+            )
+        };
+        if decision.action == NetworkAction::Deny {
+            self.emit_audit(RuntimeAuditEvent::new(
+                organization_id,
+                sandbox_id,
+                agent_id,
+                RuntimeActivity::EgressDenied {
+                    destination: destination.to_string(),
+                    matched_rule: decision.matched_rule.clone(),
+                },
+            ))
+            .await;
+        }
+        Some(decision)
+    }
+
+    /// Set an organization's runtime policy, returning it with its new version.
+    ///
+    /// Only sandboxes created afterwards are affected.
+    pub async fn set_org_runtime_policy(
+        &self,
+        policy: OrgRuntimePolicy,
+    ) -> CretoResult<OrgRuntimePolicy> {
+        if let Some(idle) = &policy.idle {
+            idle.validate()?;
+        }
+        let policy = self.runtime_policies.put(policy).await?;
+        tracing::info!(
+            organization_id = %policy.organization_id,
+            version = policy.version,
+            "Updated organization runtime policy"
+        );
+        Ok(policy)
+    }
+
+    /// Get an organization's runtime policy.
+    pub async fn get_org_runtime_policy(
+        &self,
+        organization_id: OrganizationId,
+    ) -> CretoResult<Option<OrgRuntimePolicy>> {
+        self.runtime_policies.get(organization_id).await
+    }
+
+    /// Create an organization network policy template.
+    pub async fn create_network_policy_template(
+        &self,
+        organization_id: OrganizationId,
+        name: impl Into<String>,
+        policy: NetworkPolicy,
+    ) -> CretoResult<NetworkPolicyTemplate> {
+        self.policy_templates
+            .create(NetworkPolicyTemplate::new(organization_id, name, policy))
+            .await
+    }
+
+    /// Get an organization network policy template.
+    pub async fn get_network_policy_template(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+    ) -> CretoResult<NetworkPolicyTemplate> {
+        self.policy_templates
+            .get(organization_id, name)
+            .await?
+            .ok_or_else(|| CretoError::NotFound(format!("network policy template '{}'", name)))
+    }
+
+    /// List an organization's network policy templates.
+    pub async fn list_network_policy_templates(
+        &self,
+        organization_id: OrganizationId,
+    ) -> CretoResult<Vec<NetworkPolicyTemplate>> {
+        self.policy_templates.list(organization_id).await
+    }
+
+    /// Update an organization network policy template.
+    ///
+    /// New sandboxes always pick up the new version. Running sandboxes are
+    /// only re-resolved when template propagation is enabled.
+    pub async fn update_network_policy_template(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+        policy: NetworkPolicy,
+    ) -> CretoResult<NetworkPolicyTemplate> {
+        let template = self
+            .policy_templates
+            .update(organization_id, name, policy)
+            .await?;
+
+        if self.propagate_template_updates {
+            let updated: Vec<(SandboxId, EffectiveNetworkPolicy)> = {
+                let mut egress = self.sandbox_egress.write().unwrap();
+                egress
+                    .iter_mut()
+                    .filter(|(_, e)| {
+                        e.organization_id == organization_id && e.effective.template_name == name
+                    })
+                    .map(|(id, e)| {
+                        e.effective = template.resolve(&e.effective.additional_rules);
+                        e.enforcer.update_policy(e.effective.policy.clone());
+                        (*id, e.effective.clone())
+                    })
+                    .collect()
+            };
+
+            tracing::info!(
+                template = %name,
+                version = template.version,
+                sandboxes = updated.len(),
+                "Propagated network policy template update"
+            );
+
+            if let Some(repository) = &self.sandbox_repository {
+                for (sandbox_id, effective) in &updated {
+                    repository
+                        .update_network_policy(*sandbox_id, effective)
+                        .await?;
+                }
+            }
+        }
+
+        Ok(template)
+    }
+
+    /// Delete an organization network policy template.
+    ///
+    /// Sandboxes already created from it keep their resolved snapshot.
+    pub async fn delete_network_policy_template(
+        &self,
+        organization_id: OrganizationId,
+        name: &str,
+    ) -> CretoResult<()> {
+        self.policy_templates.delete(organization_id, name).await
+    }
+
+    /// Execute code in a sandbox.
+    pub async fn execute(
+        &self,
+        sandbox_id: SandboxId,
+        code: impl Into<String>,
+    ) -> CretoResult<ExecutionResult> {
+        self.execute_with_correlation(sandbox_id, code, Correlation::default())
+            .await
+    }
+
+    /// Execute code in a sandbox, tagging the execution with trace identifiers.
+    pub async fn execute_with_correlation(
+        &self,
+        sandbox_id: SandboxId,
+        code: impl Into<String>,
+        correlation: Correlation,
+    ) -> CretoResult<ExecutionResult> {
+        let request = ExecutionRequest::new(sandbox_id, code).with_correlation(correlation);
+        self.run_execution(None, request).await
+    }
+
+    /// Execute a request on behalf of an organization.
+    ///
+    /// A request with an [`exclusion_key`](ExecutionRequest::exclusion_key)
+    /// holds the key within the organization while it runs. If another
+    /// execution holds it, the request waits for it when
+    /// [`queue`](ExecutionRequest::queue) is set and an admission queue is
+    /// configured, cancels it when [`preempt`](ExecutionRequest::preempt) is
+    /// set and the request has a higher priority, and otherwise fails with
+    /// [`CretoError::ExclusionConflict`].
+    ///
+    /// A request with an
+    /// [`idempotency_key`](ExecutionRequest::idempotency_key) already used
+    /// in its sandbox returns that execution's result, waiting for it if it
+    /// is still running, or fails with [`CretoError::IdempotencyConflict`]
+    /// if its payload differs.
+    pub async fn execute_request(
+        &self,
+        organization_id: OrganizationId,
+        request: ExecutionRequest,
+    ) -> CretoResult<ExecutionResult> {
+        self.run_execution(Some(organization_id), request).await
+    }
+
+    /// Submit an execution to run in the background.
+    ///
+    /// With an admission queue configured, a request marked
+    /// [`queue`](ExecutionRequest::queue) waits for a free slot when the
+    /// service is at capacity; other requests fail immediately. The returned
+    /// handle reports the queue position, cancels a waiting request, and
+    /// resolves with the execution result.
+    ///
+    /// Exclusion and idempotency keys are handled as by
+    /// [`execute_request`](Self::execute_request). A request waiting for its
+    /// exclusion key joins the admission queue only once it holds the key,
+    /// so until then it has no queue position and cannot be cancelled.
+    ///
+    /// A request with an idempotency key is admitted only once it has
+    /// claimed the key, so a retry of an execution that is running or done
+    /// takes no slot; its admission errors resolve the handle instead of
+    /// being returned here.
+    pub fn submit_execution(
+        self: &Arc<Self>,
+        organization_id: OrganizationId,
+        request: ExecutionRequest,
+    ) -> CretoResult<QueuedExecution> {
+        let id = request.id;
+        let run = self.admit_idempotent(organization_id, request, None)?;
+        let (result_tx, result_rx) = oneshot::channel();
+        self.spawn_execution(id, "runtime.submitted_execution", async move {
+            let _ = result_tx.send(run.await);
+        });
+
+        Ok(QueuedExecution::new(
+            id,
+            self.execution_queue.clone(),
+            result_rx,
+        ))
+    }
+
+    /// Run an execution in the background, streaming its output as the code
+    /// writes it.
+    ///
+    /// The request is admitted as by
+    /// [`submit_execution`](Self::submit_execution). The stream starts with
+    /// [`ExecutionStatus::Running`] once it runs, carries stdout and stderr
+    /// chunks with secrets redacted, and ends with the final status and the
+    /// [`ExecutionChunk::Result`] [`execute_request`](Self::execute_request)
+    /// would have returned. An execution running past its sandbox's wall
+    /// time or [`max_output_bytes`](ResourceLimits::max_output_bytes) is
+    /// stopped, ending the stream with the limit's error; a runtime error
+    /// ends it with a failed result carrying the error.
+    ///
+    /// A retry under an idempotency key that already has a result gets only
+    /// the [`ExecutionChunk::Result`].
+    ///
+    /// Secrets split across two chunks are only redacted in the result.
+    pub fn execute_streaming(
+        self: &Arc<Self>,
+        organization_id: OrganizationId,
+        request: ExecutionRequest,
+    ) -> CretoResult<impl Stream<Item = ExecutionChunk> + Send + 'static> {
+        let (chunks_tx, chunks) = mpsc::unbounded_channel();
+        let id = request.id;
+        let correlation = request.correlation();
+        let run = self.admit_idempotent(organization_id, request, Some(chunks_tx.clone()))?;
+        self.spawn_execution(id, "runtime.streaming_execution", async move {
+            let result = run
+                .await
+                .unwrap_or_else(|e| executor_failure(id, &e).with_correlation(correlation));
+            let _ = chunks_tx.send(ExecutionChunk::Result(Box::new(result)));
+        });
+        Ok(UnboundedReceiverStream::new(chunks))
+    }
+
+    /// Run a background execution, registered with the shutdown coordinator
+    /// if there is one.
+    ///
+    /// Shutdown cancels the request if it is still waiting in the admission
+    /// queue; once dispatched it runs to completion.
+    fn spawn_execution<F>(&self, id: Uuid, name: &'static str, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let Some(shutdown) = &self.shutdown else {
+            tokio::spawn(task);
+            return;
+        };
+        let queue = self.execution_queue.clone();
+        shutdown.spawn(name, move |guard: ShutdownGuard| async move {
+            let mut task = std::pin::pin!(task);
+            tokio::select! {
+                biased;
+                _ = &mut task => return,
+                _ = guard.cancelled() => {}
+            }
+            if let Some(queue) = &queue {
+                queue.cancel(id);
+            }
+            task.await;
+        });
+    }
+
+    /// [`admit`](Self::admit) a request, claiming its idempotency key first
+    /// if it has one.
+    ///
+    /// A request without a key is admitted now; a keyed one once the
+    /// returned future has claimed its key.
+    fn admit_idempotent(
+        self: &Arc<Self>,
+        organization_id: OrganizationId,
+        request: ExecutionRequest,
+        output: Option<mpsc::UnboundedSender<ExecutionChunk>>,
+    ) -> CretoResult<impl Future<Output = CretoResult<ExecutionResult>> + Send + 'static> {
+        let key = IdempotencyKey::of(&request);
+        // Ok: admitted now; Err: left for the future to admit
+        let admission = match key {
+            Some(_) => Err((request, output)),
+            None => Ok(self.admit(organization_id, request, output)?),
+        };
+
+        let service = Arc::clone(self);
+        Ok(async move {
+            match admission {
+                Ok(run) => run.await,
+                Err((request, output)) => {
+                    let run = async {
+                        let run = service.admit(organization_id, request, output)?;
+                        run.await
+                    };
+                    service.run_idempotent(key, run).await
+                }
+            }
+        })
+    }
+
+    /// Reserve a request's exclusion key and admission slot, returning the
+    /// future that runs it once both are granted.
+    fn admit(
+        self: &Arc<Self>,
+        organization_id: OrganizationId,
+        request: ExecutionRequest,
+        output: Option<mpsc::UnboundedSender<ExecutionChunk>>,
+    ) -> CretoResult<impl Future<Output = CretoResult<ExecutionResult>> + Send + 'static> {
+        let mut hold = self.reserve_exclusion(Some(organization_id), &request)?;
+        let waiting = hold.as_ref().is_some_and(ExclusionHold::is_waiting);
+        let ticket = if waiting {
+            None
+        } else {
+            self.admission_ticket(organization_id, &request)?
+        };
+
+        let service = Arc::clone(self);
+        Ok(async move {
+            let ticket = match &mut hold {
+                Some(hold) if waiting => {
+                    hold.granted().await?;
+                    service.admission_ticket(organization_id, &request)?
+                }
+                _ => ticket,
+            };
+            let _slot = match ticket {
+                Some(ticket) => Some(ticket.dispatched().await?),
+                None => None,
+            };
+            service.run_admitted(request, hold, output).await
+        })
+    }
+
+    /// Get the admission queue, if one is configured.
+    pub fn execution_queue(&self) -> Option<&Arc<ExecutionQueue>> {
+        self.execution_queue.as_ref()
+    }
+
+    /// Release exclusion leases this node held before a restart, marking
+    /// their executions failed.
+    ///
+    /// Called by [`initialize`](Self::initialize). Returns the released
+    /// leases.
+    pub async fn recover_exclusion_leases(&self) -> CretoResult<Vec<ExclusionLease>> {
+        let released = self.exclusion.recover().await?;
+        if let Some(repository) = &self.execution_repository {
+            for lease in &released {
+                repository
+                    .update_status(lease.execution_id, ExecutionStatus::Failed)
+                    .await?;
+            }
+        }
+        Ok(released)
+    }
+
+    /// Renew the exclusion leases of running executions.
+    ///
+    /// Returns the number renewed. An execution whose lease was lost is
+    /// cancelled. Leases are renewed on their own while held; this renews
+    /// them now.
+    pub async fn renew_exclusion_leases(&self) -> CretoResult<usize> {
+        self.exclusion.renew_leases(self.clock.now()).await
+    }
+
+    /// Current pool, admission and exclusion lock state.
+    pub async fn runtime_stats(&self) -> CretoResult<RuntimeStats> {
+        let now = self.clock.now();
+        let mut exclusion_locks: Vec<ExclusionLease> = self
+            .exclusion
+            .store()
+            .list()
+            .await?
+            .into_iter()
+            .filter(|lease| !lease.is_expired_at(now))
+            .collect();
+        exclusion_locks.sort_by_key(|lease| lease.acquired_at);
+
+        Ok(RuntimeStats {
+            pool: self.pool.stats().await,
+            running_executions: self
+                .execution_queue
+                .as_ref()
+                .map(|queue| queue.running() + queue.running_gpu()),
+            queued_executions: self.execution_queue.as_ref().map(|queue| queue.depth()),
+            exclusion_locks,
+        })
+    }
+
+    /// Admission for a request: a place in the queue if it may wait, or a
+    /// slot now.
+    fn admission_ticket(
+        &self,
+        organization_id: OrganizationId,
+        request: &ExecutionRequest,
+    ) -> CretoResult<Option<QueueTicket>> {
+        let class = self.sandbox_class(request.sandbox_id);
+        Ok(match &self.execution_queue {
+            Some(queue) if request.queue => {
+                Some(queue.enqueue_for(request.id, organization_id, request.priority, &class)?)
+            }
+            Some(queue) => Some(QueueTicket::Ready(queue.try_acquire_for(&class)?)),
+            None => None,
+        })
+    }
+
+    /// Claim a request's exclusion key, if it has one.
+    ///
+    /// The returned hold may still be waiting for the key.
+    fn reserve_exclusion(
+        &self,
+        organization_id: Option<OrganizationId>,
+        request: &ExecutionRequest,
+    ) -> CretoResult<Option<ExclusionHold>> {
+        let Some(key) = &request.exclusion_key else {
+            return Ok(None);
+        };
+        let Some(organization_id) = organization_id else {
+            return Err(CretoError::ValidationFailed(format!(
+                "Exclusion key {key} requires an organization"
+            )));
+        };
+        let can_wait = request.queue && self.execution_queue.is_some();
+        self.exclusion
+            .reserve(
+                organization_id,
+                key,
+                request.id,
+                request.priority,
+                request.preempt,
+                can_wait,
+            )
+            .map(Some)
+    }
+
+    /// Run a request now, failing if the admission queue has no free slot.
+    ///
+    /// Exclusion keys need `organization_id` to scope them.
+    async fn run_execution(
+        &self,
+        organization_id: Option<OrganizationId>,
+        request: ExecutionRequest,
+    ) -> CretoResult<ExecutionResult> {
+        let key = IdempotencyKey::of(&request);
+        let run = self.run_execution_now(organization_id, request);
+        self.run_idempotent(key, run).await
+    }
+
+    /// Run `run` under an idempotency key, unless the key already has a
+    /// result or is held by an execution still running.
+    ///
+    /// A key this service has no entry for is looked up in the execution
+    /// repository, so results outlive a restart. The result of `run` is
+    /// cached and recorded; an error releases the key.
+    async fn run_idempotent(
+        &self,
+        key: Option<IdempotencyKey>,
+        run: impl Future<Output = CretoResult<ExecutionResult>>,
+    ) -> CretoResult<ExecutionResult> {
+        let Some(key) = key else {
+            return run.await;
+        };
+        let permit = loop {
+            let now = self.clock.now();
+            if let Some(repository) = &self.execution_repository {
+                if !self.idempotency.contains(&key, now) {
+                    if let Some(record) = repository
+                        .find_idempotent_result(key.sandbox_id, &key.key)
+                        .await?
+                    {
+                        self.idempotency.restore(record, now);
+                    }
+                }
+            }
+            match self.idempotency.claim(&key, now)? {
+                Claim::Run(permit) => break permit,
+                Claim::Replay(result) => return Ok(*result),
+                Claim::Wait(mut done) => {
+                    if let Ok(result) = done.wait_for(Option::is_some).await {
+                        if let Some(result) = result.clone() {
+                            return Ok(result);
+                        }
+                    }
+                    // The execution ended without a result; claim the key again
+                }
+            }
+        };
+
+        let result = run.await?;
+        let record = permit.complete(&result, self.clock.now());
+        if let Some(repository) = &self.execution_repository {
+            if let Err(e) = repository.record_idempotent_result(&record).await {
+                tracing::warn!(
+                    sandbox_id = %key.sandbox_id,
+                    execution_id = %result.request_id,
+                    error = %e,
+                    "Failed to record idempotent execution result"
+                );
+            }
+        }
+        Ok(result)
+    }
+
+    async fn run_execution_now(
+        &self,
+        organization_id: Option<OrganizationId>,
+        request: ExecutionRequest,
+    ) -> CretoResult<ExecutionResult> {
+        let mut hold = self.reserve_exclusion(organization_id, &request)?;
+        if let Some(hold) = &mut hold {
+            hold.granted().await?;
+        }
+        let _slot = match &self.execution_queue {
+            Some(queue) => Some(queue.try_acquire_for(&self.sandbox_class(request.sandbox_id))?),
+            None => None,
+        };
+        self.run_admitted(request, hold, None).await
+    }
+
+    /// Run a request, persisting it and its resource usage when repositories are set.
+    ///
+    /// `hold` is the request's granted exclusion key; it is persisted once
+    /// the execution has its final ID and released as soon as the code
+    /// stops running. Preempting the holder cancels the execution.
+    ///
+    /// Chunks are sent to `output`, redacted, as the code runs; the caller
+    /// sends the result.
+    ///
+    /// A paused or hibernated sandbox is resumed first. The finished
+    /// execution is audited with its status and duration.
+    async fn run_admitted(
+        &self,
+        request: ExecutionRequest,
+        hold: Option<ExclusionHold>,
+        output: Option<mpsc::UnboundedSender<ExecutionChunk>>,
+    ) -> CretoResult<ExecutionResult> {
+        let sandbox_id = request.sandbox_id;
+        let result = self.run_admitted_unaudited(request, hold, output).await;
+        let activity = match &result {
+            Ok(r) => RuntimeActivity::ExecutionFinished {
+                execution_id: Some(r.request_id),
+                status: r.status,
+                error_code: r.error.as_ref().map(|e| e.code.clone()),
+                duration_ms: r.timing.duration_ms,
+            },
+            Err(e) => RuntimeActivity::ExecutionFinished {
+                execution_id: None,
+                status: ExecutionStatus::Failed,
+                error_code: Some(e.code().to_string()),
+                duration_ms: None,
+            },
+        };
+        self.audit_sandbox(sandbox_id, activity).await;
+        result
+    }
+
+    async fn run_admitted_unaudited(
+        &self,
+        mut request: ExecutionRequest,
+        mut hold: Option<ExclusionHold>,
+        output: Option<mpsc::UnboundedSender<ExecutionChunk>>,
+    ) -> CretoResult<ExecutionResult> {
+        let (_activity, resume_ms) = match self.idle.begin(request.sandbox_id, &self.clock) {
+            Some((activity, IdleState::Active)) => (Some(activity), None),
+            Some((activity, _)) => (Some(activity), self.resume_idle(request.sandbox_id).await?),
+            None => (None, None),
+        };
+        if let Some(name) = self.expired_secret_mount(request.sandbox_id) {
+            let mut timing = ExecutionTiming::new();
+            timing.mark_completed();
+            let correlation = request.correlation();
+            return Ok(ExecutionResult::failure(
+                request.id,
+                ExecutionError::secret_lease_expired(&name),
+                timing,
+            )
+            .with_correlation(correlation));
+        }
+        let rejection = self.verify_attestation(request.sandbox_id).await;
+        if self.attestation_verifier.is_some() {
+            self.audit_sandbox(
+                request.sandbox_id,
+                RuntimeActivity::AttestationVerified {
+                    verified: rejection.is_none(),
+                    reason: rejection.clone(),
+                },
+            )
+            .await;
+        }
+        if let Some(reason) = rejection {
+            tracing::warn!(
+                sandbox_id = %request.sandbox_id,
+                reason = %reason,
+                "Refused execution in sandbox with rejected attestation"
+            );
+            let mut timing = ExecutionTiming::new();
+            timing.mark_completed();
+            let correlation = request.correlation();
+            return Ok(ExecutionResult::failure(
+                request.id,
+                ExecutionError::attestation_rejected(reason),
+                timing,
+            )
+            .with_correlation(correlation));
+        }
+        if let Some(proxy) = &self.agent_channels {
+            request.channels = proxy.descriptors(request.sandbox_id);
+        }
+        if let Some(repository) = &self.execution_repository {
+            request.id = repository
+                .create(
+                    request.sandbox_id,
+                    &request.code,
+                    request.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS) as i32,
+                    request.correlation(),
+                )
+                .await?;
+        }
+        if let Some(hold) = &mut hold {
+            if let Err(e) = hold.persist(request.id, &self.clock).await {
+                if let Some(repository) = &self.execution_repository {
+                    repository
+                        .update_status(request.id, ExecutionStatus::Failed)
+                        .await?;
+                }
+                return Err(e);
+            }
+        }
+        if let Some(repository) = &self.execution_repository {
+            repository.mark_started(request.id).await?;
+        }
+        let mut warnings = self.inject_context(&mut request).await;
+
+        let sandbox_id = request.sandbox_id;
+        let execution_id = request.id;
+        let workdir = self.fs_tracking.as_ref().and_then(|(config, resolver)| {
+            resolver
+                .workdir(sandbox_id)
+                .map(|path| (config.clone(), path))
+        });
+        let before = match &workdir {
+            Some((config, path)) => scan_workdir(config, path).await,
+            None => None,
+        };
+
+        let secrets = self
+            .secret_fingerprints
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .cloned()
+            .unwrap_or_default();
+        let correlation = request.correlation();
+        let preempted = hold.as_mut().and_then(ExclusionHold::preempted);
+        let limits = self.stream_limits(&request);
+        let forward = |chunk| {
+            if let Some(output) = &output {
+                let _ = output.send(self.redact_chunk(chunk, &secrets));
+            }
+        };
+        let execution = run_streaming(self.executor.as_ref(), request, limits, forward);
+        let preempted = async {
+            match preempted {
+                Some(preempted) => {
+                    if preempted.await.is_err() {
+                        std::future::pending::<()>().await;
+                    }
+                }
+                None => std::future::pending().await,
+            }
+        };
+        let outcome = tokio::select! {
+            outcome = execution => outcome,
+            () = preempted => {
+                let key = hold.as_ref().map(ExclusionHold::key).unwrap_or_default();
+                Ok(preempted_result(execution_id, key).with_correlation(correlation))
+            }
+            limit = self.limit_enforcer.exceeded(sandbox_id) => {
+                Ok(limit_exceeded_result(execution_id, limit).with_correlation(correlation))
+            }
+        };
+        if let Some(hold) = hold {
+            hold.release().await;
+        }
+
+        // Redact before anything is persisted, metered or logged, and after
+        // parsing structured logs so values unescaped from them are covered
+        let mut result = outcome.map(|r| {
+            let mut r = r.with_captured_logs(&self.log_capture);
+            self.redaction.redact_result(&mut r, &secrets);
+            if !r.redactions.is_empty() {
+                tracing::warn!(
+                    sandbox_id = %sandbox_id,
+                    execution_id = %execution_id,
+                    redactions = r.redactions.total,
+                    "Redacted sensitive values from execution output"
+                );
+            }
+            r
+        });
+
+        if let (Ok(r), Some(resume_ms)) = (&mut result, resume_ms) {
+            r.timing.resume_ms = Some(resume_ms);
+        }
+        if let Ok(r) = &mut result {
+            warnings.append(&mut r.warnings);
+            r.warnings = warnings;
+        }
+
+        if let (Some((config, path)), Some(before), Ok(r)) = (&workdir, before, &mut result) {
+            if let Some(after) = scan_workdir(config, path).await {
+                let diff = FilesystemDiff::between(execution_id, &before, &after);
+                r.fs_changes = Some(diff.summary());
+                if config.persist_diffs {
+                    // The summary is on the result; only the full diff is lost
+                    if let Err(e) = self.fs_diffs.put(&diff).await {
+                        tracing::warn!(
+                            execution_id = %execution_id,
+                            error = %e,
+                            "Failed to store filesystem diff"
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(repository) = &self.execution_repository {
+            match &result {
+                Ok(r) if r.is_success() => {
+                    let duration_ms = r.timing.duration_ms.unwrap_or(0) as i64;
+                    repository.mark_completed(execution_id, duration_ms).await?;
+                }
+                Ok(r) => repository.update_status(execution_id, r.status).await?,
+                Err(_) => {
+                    repository
+                        .update_status(execution_id, ExecutionStatus::Failed)
+                        .await?
+                }
+            }
+        }
+
+        if let Some(usage) = result.as_ref().ok().and_then(|r| r.resource_usage.as_ref()) {
+            self.limit_enforcer
+                .add_cpu_time(sandbox_id, usage.cpu_time_ms);
+        }
+        if let (Ok(r), Some(repository)) = (&result, &self.resource_usage_repository) {
+            if let Some(usage) = &r.resource_usage {
+                repository.record(sandbox_id, usage).await?;
+            }
+            repository
+                .record_samples(sandbox_id, execution_id, &r.usage_samples)
+                .await?;
+        }
+
+        if let Ok(r) = &result {
+            self.emit_execution_usage(sandbox_id, r).await;
+            // Logs are diagnostics: losing them must not fail the execution
+            if let Err(e) = self.execution_logs.append(execution_id, &r.logs).await {
+                tracing::warn!(
+                    execution_id = %execution_id,
+                    records = r.logs.len(),
+                    error = %e,
+                    "Failed to store execution logs"
+                );
+            }
+        }
+
+        result
+    }
+
+    /// Redact secrets from a streamed stdout or stderr chunk.
+    fn redact_chunk(&self, chunk: ExecutionChunk, secrets: &[SecretFingerprint]) -> ExecutionChunk {
+        let redact = |bytes: Vec<u8>| {
+            let (redacted, summary) = self
+                .redaction
+                .redact(&String::from_utf8_lossy(&bytes), secrets);
+            if summary.is_empty() {
+                bytes
+            } else {
+                redacted.into_bytes()
+            }
+        };
+        match chunk {
+            ExecutionChunk::Stdout(bytes) => ExecutionChunk::Stdout(redact(bytes)),
+            ExecutionChunk::Stderr(bytes) => ExecutionChunk::Stderr(redact(bytes)),
+            chunk => chunk,
+        }
+    }
+
+    /// Add the runtime context variables to `request`, returning a warning
+    /// for each one the request already sets.
+    ///
+    /// A failed quota lookup skips the snapshot rather than the execution.
+    async fn inject_context(&self, request: &mut ExecutionRequest) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut context = ExecutionContext::default();
+        if let Some((organization_id, agent_id)) = self.idle.owner(request.sandbox_id) {
+            context.organization_id = Some(organization_id);
+            context.agent_id = Some(agent_id);
+            if let Some((provider, metrics)) = &self.quota_status {
+                let taken_at = self.clock.now();
+                match provider
+                    .remaining_quota(organization_id, agent_id, metrics)
+                    .await
+                {
+                    Ok(quotas) => context.quota = Some(QuotaSnapshot::new(taken_at, quotas)),
+                    Err(e) => {
+                        tracing::warn!(
+                            sandbox_id = %request.sandbox_id,
+                            error = %e,
+                            "Quota lookup failed; skipping quota snapshot"
+                        );
+                        warnings.push(format!("Quota snapshot unavailable: {e}"));
+                    }
+                }
+            }
+        }
+        warnings.extend(context.inject(request));
+        warnings
+    }
+
+    /// Get the full workdir diff recorded for an execution.
+    ///
+    /// Only available when filesystem tracking is enabled with
+    /// [`persist_diffs`](FilesystemTrackingConfig::persist_diffs) set.
+    pub async fn execution_fs_diff(
+        &self,
+        execution_id: Uuid,
+    ) -> CretoResult<Option<FilesystemDiff>> {
+        self.fs_diffs.get(execution_id).await
+    }
+
+    /// Get an execution's structured log records in emission order.
+    ///
+    /// With `min_level` set, only records at that level or above are returned.
+    pub async fn execution_logs(
+        &self,
+        execution_id: Uuid,
+        min_level: Option<LogLevel>,
+        page: LogPage,
+    ) -> CretoResult<Vec<LogRecord>> {
+        self.execution_logs
+            .list(execution_id, min_level, page)
+            .await
+    }
+
+    /// Page through recorded executions matching `filter`, newest first.
+    ///
+    /// Requires an execution repository.
+    pub async fn find_executions(
+        &self,
+        filter: &ExecutionFilter,
+        page: &PageRequest,
+    ) -> CretoResult<Page<ExecutionRecord>> {
+        self.execution_history()?.find(filter, page).await
+    }
+
+    /// Execution counts by status and duration percentiles for an
+    /// organization's executions queued within `range`.
+    ///
+    /// Requires an execution repository.
+    pub async fn execution_stats(
+        &self,
+        organization_id: OrganizationId,
+        range: TimeRange,
+    ) -> CretoResult<ExecutionStats> {
+        self.execution_history()?
+            .stats(organization_id, range)
+            .await
+    }
+
+    fn execution_history(&self) -> CretoResult<&dyn ExecutionRepository> {
+        self.execution_repository.as_deref().ok_or_else(|| {
+            CretoError::Configuration(
+                "Execution history requires an execution repository".to_string(),
+            )
+        })
+    }
+
+    /// Execute code with secrets injected.
+    ///
+    /// Each mounted secret is recorded as a [`SecretGrant`], revoked when the
+    /// execution finishes, whether it succeeded or not.
+    pub async fn execute_with_secrets(
+        &self,
+        sandbox_id: SandboxId,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        code: impl Into<String>,
+        secrets: Vec<SecretMount>,
+    ) -> CretoResult<ExecutionResult> {
+        // Resolve secrets
+        let mut granted = false;
+        if let Some(provider) = &self.secret_provider {
+            for secret in &secrets {
+                let mounted = self
+                    .mount_secret(
+                        provider.as_ref(),
+                        sandbox_id,
+                        organization_id,
+                        agent_id,
+                        secret,
+                    )
+                    .await;
+                match mounted {
+                    Ok(()) => granted = true,
+                    Err(e) => {
+                        // A lease may have been taken before the failure
+                        self.end_secret_grants_logged(sandbox_id).await;
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        // Execute
+        let request = ExecutionRequest::new(sandbox_id, code);
+        let result = self.run_execution(Some(organization_id), request).await;
+
+        if granted {
+            match &result {
+                Ok(_) => self.end_secret_grants(sandbox_id).await?,
+                Err(_) => self.end_secret_grants_logged(sandbox_id).await,
+            }
+        }
+
+        result
+    }
+
+    /// Authorize, resolve and mount a secret, recording the grant.
+    async fn mount_secret(
+        &self,
+        provider: &dyn SecretProvider,
+        sandbox_id: SandboxId,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        secret: &SecretMount,
+    ) -> CretoResult<()> {
+        // Verify authorization
+        if !provider
+            .authorize(organization_id, agent_id, &secret.source)
+            .await?
+        {
+            return Err(CretoError::NotAuthorized {
+                resource: format!("secret:{}", secret.name),
+                action: "access".to_string(),
+            });
+        }
+
+        // TODO: Inject secret into sandbox
+        self.lease_mount(provider, sandbox_id, organization_id, agent_id, secret)
+            .await?;
+        // backend.inject_secret(sandbox_id, &secret.name, value).await?;
+
+        let mut grant = SecretGrant::new(organization_id, sandbox_id, agent_id, secret);
+        if secret.wants_access_detection() {
+            grant.access_detection = self
+                .secret_access_monitor
+                .watch(sandbox_id, secret)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!(
+                        sandbox_id = %sandbox_id,
+                        secret = %secret.name,
+                        error = %e,
+                        "Secret access detection unavailable"
+                    );
+                    false
+                });
+        }
+
+        self.secret_grants.record_grant(&grant).await?;
+        self.emit_audit(RuntimeAuditEvent::for_grant(
+            &grant,
+            RuntimeActivity::SecretGranted,
+        ))
+        .await;
+        Ok(())
+    }
+
+    /// Lease a secret and track it as mounted in the sandbox.
+    ///
+    /// A mount of the same name is replaced, and its lease revoked.
+    async fn lease_mount(
+        &self,
+        provider: &dyn SecretProvider,
+        sandbox_id: SandboxId,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        secret: &SecretMount,
+    ) -> CretoResult<()> {
+        let leased = provider
+            .lease(
+                organization_id,
+                agent_id,
+                &secret.source,
+                self.secret_lease_config.ttl,
+            )
+            .await?;
+        let fingerprints = self
+            .redaction
+            .fingerprint(&secret.name, leased.value.as_bytes());
+        self.secret_fingerprints
+            .write()
+            .unwrap()
+            .entry(sandbox_id)
+            .or_default()
+            .extend(fingerprints);
+
+        let mounted = MountedSecret {
+            lease_id: leased.lease_id,
+            organization_id,
+            agent_id,
+            mount: secret.clone(),
+            expires_at: lease_expiry(self.clock.now(), leased.ttl),
+            expired: false,
+        };
+        self.emit_audit(RuntimeAuditEvent::new(
+            organization_id,
+            sandbox_id,
+            agent_id,
+            RuntimeActivity::SecretLeased {
+                secret_ref: secret.source.reference(),
+                lease_id: mounted.lease_id,
+                expires_at: Some(mounted.expires_at),
+            },
+        ))
+        .await;
+        let replaced = {
+            let mut leases = self.secret_leases.write().unwrap();
+            let mounts = leases.entry(sandbox_id).or_default();
+            let replaced = mounts
+                .iter()
+                .position(|m| m.mount.name == secret.name)
+                .map(|i| mounts.swap_remove(i));
+            mounts.push(mounted);
+            replaced
+        };
+        if let Some(replaced) = replaced {
+            self.revoke_lease(provider, sandbox_id, &replaced).await;
+        }
+        Ok(())
+    }
+
+    /// Revoke the leases of every secret mounted in a sandbox, returning
+    /// the mounts.
+    async fn revoke_secret_leases(&self, sandbox_id: SandboxId) -> Vec<MountedSecret> {
+        let mounts = self
+            .secret_leases
+            .write()
+            .unwrap()
+            .remove(&sandbox_id)
+            .unwrap_or_default();
+        if let Some(provider) = &self.secret_provider {
+            for mounted in &mounts {
+                self.revoke_lease(provider.as_ref(), sandbox_id, mounted)
+                    .await;
+            }
+        }
+        mounts
+    }
+
+    /// Revoke a mount's lease, logging failures; it lapses at its TTL
+    /// regardless.
+    async fn revoke_lease(
+        &self,
+        provider: &dyn SecretProvider,
+        sandbox_id: SandboxId,
+        mounted: &MountedSecret,
+    ) {
+        let lease_id = mounted.lease_id;
+        if let Err(e) = provider.revoke(lease_id).await {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                lease_id = %lease_id,
+                error = %e,
+                "Failed to revoke secret lease"
+            );
+        }
+        self.emit_audit(RuntimeAuditEvent::new(
+            mounted.organization_id,
+            sandbox_id,
+            mounted.agent_id,
+            RuntimeActivity::SecretLeaseRevoked {
+                secret_ref: mounted.mount.source.reference(),
+                lease_id,
+            },
+        ))
+        .await;
+    }
+
+    /// Lease checkpointed or suspended secrets again for a sandbox.
+    ///
+    /// Grants are not recorded again; the sandbox keeps the grants it had.
+    async fn restore_secret_mounts(
+        &self,
+        sandbox_id: SandboxId,
+        secrets: Vec<CheckpointedSecret>,
+    ) -> CretoResult<()> {
+        if secrets.is_empty() {
+            return Ok(());
+        }
+        let provider = self.secret_provider.as_ref().ok_or_else(|| {
+            CretoError::Configuration(
+                "Sandbox had secrets mounted but no secret provider is configured".to_string(),
+            )
+        })?;
+        for secret in secrets {
+            self.lease_mount(
+                provider.as_ref(),
+                sandbox_id,
+                secret.organization_id,
+                secret.agent_id,
+                &secret.mount,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Name of a mount in the sandbox whose lease ran out, if any.
+    fn expired_secret_mount(&self, sandbox_id: SandboxId) -> Option<String> {
+        let now = self.clock.now();
+        self.secret_leases
+            .read()
+            .unwrap()
+            .get(&sandbox_id)?
+            .iter()
+            .find(|mounted| mounted.is_expired(now))
+            .map(|mounted| mounted.mount.name.clone())
+    }
+
+    /// Renew secret leases that are close to running out.
+    ///
+    /// Leases are only held by running sandboxes: pausing, checkpointing
+    /// and terminating a sandbox revoke them. A lease that could not be
+    /// renewed before it ran out invalidates its mount, so executions in
+    /// the sandbox fail with
+    /// [`ExecutionError::secret_lease_expired`] until it is mounted again.
+    pub async fn renew_secret_leases(&self) -> SecretLeaseReport {
+        let mut report = SecretLeaseReport::default();
+        let Some(provider) = &self.secret_provider else {
+            return report;
+        };
+        let config = &self.secret_lease_config;
+        let now = self.clock.now();
+        let renew_by = lease_expiry(now, config.renew_before);
+
+        let mut due = Vec::new();
+        for (sandbox_id, mounts) in self.secret_leases.write().unwrap().iter_mut() {
+            for mounted in mounts.iter_mut().filter(|m| !m.expired) {
+                if mounted.is_expired(now) {
+                    mounted.expired = true;
+                    report
+                        .expired
+                        .push((*sandbox_id, mounted.mount.name.clone()));
+                } else if mounted.expires_at <= renew_by {
+                    due.push((*sandbox_id, mounted.lease_id));
+                }
+            }
+        }
+        for (sandbox_id, name) in &report.expired {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                secret = %name,
+                "Secret lease expired; mount invalidated"
+            );
+        }
+
+        for (sandbox_id, lease_id) in due {
+            match provider.renew(lease_id, config.ttl).await {
+                Ok(ttl) => {
+                    let mut leases = self.secret_leases.write().unwrap();
+                    let mounted = leases
+                        .get_mut(&sandbox_id)
+                        .and_then(|mounts| mounts.iter_mut().find(|m| m.lease_id == lease_id));
+                    if let Some(mounted) = mounted {
+                        mounted.expires_at = lease_expiry(now, ttl);
+                        report.renewed += 1;
+                    }
+                }
+                Err(e) => tracing::warn!(
+                    sandbox_id = %sandbox_id,
+                    lease_id = %lease_id,
+                    error = %e,
+                    "Failed to renew secret lease"
+                ),
+            }
+        }
+        report
+    }
+
+    /// Spawn the secret lease renewal worker.
+    ///
+    /// Runs [`renew_secret_leases`](Self::renew_secret_leases) every
+    /// `renew_interval` of the lease configuration. The task is registered
+    /// with `shutdown`.
+    pub fn spawn_secret_lease_renewal(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+    ) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        let interval = self.secret_lease_config.renew_interval;
+
+        shutdown.spawn("runtime.secret_leases", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    service.renew_secret_leases().await;
+                }
+            })
+        })
+    }
+
+    /// Record detected first reads, then revoke a sandbox's active grants
+    /// and secret leases.
+    async fn end_secret_grants(&self, sandbox_id: SandboxId) -> CretoResult<()> {
+        self.secret_fingerprints
+            .write()
+            .unwrap()
+            .remove(&sandbox_id);
+        self.suspended_secrets.write().unwrap().remove(&sandbox_id);
+        self.revoke_secret_leases(sandbox_id).await;
+        self.record_secret_accesses(sandbox_id).await?;
+
+        let revoked = self
+            .secret_grants
+            .revoke_sandbox(sandbox_id, Utc::now())
+            .await?;
+        for grant in revoked {
+            self.emit_audit(RuntimeAuditEvent::for_grant(
+                &grant,
+                RuntimeActivity::SecretRevoked,
+            ))
+            .await;
+        }
+        Ok(())
+    }
+
+    /// Like [`Self::end_secret_grants`], for paths already returning an error.
+    async fn end_secret_grants_logged(&self, sandbox_id: SandboxId) {
+        if let Err(e) = self.end_secret_grants(sandbox_id).await {
+            tracing::error!(
+                sandbox_id = %sandbox_id,
+                error = %e,
+                "Failed to revoke secret grants"
+            );
+        }
+    }
+
+    /// Pull first-access timestamps from the executor onto active grants.
+    async fn record_secret_accesses(&self, sandbox_id: SandboxId) -> CretoResult<()> {
+        let accesses = match self.secret_access_monitor.first_accesses(sandbox_id).await {
+            Ok(accesses) if !accesses.is_empty() => accesses,
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                tracing::warn!(
+                    sandbox_id = %sandbox_id,
+                    error = %e,
+                    "Failed to read secret access events"
+                );
+                return Ok(());
+            }
+        };
+
+        let grants = self.secret_grants.list_by_sandbox(sandbox_id).await?;
+        for access in accesses {
+            let grant = grants.iter().find(|g| {
+                g.is_active() && g.access_detection && g.secret_name == access.secret_name
+            });
+            if let Some(grant) = grant {
+                if let Some(updated) = self
+                    .secret_grants
+                    .record_first_access(grant.id, access.accessed_at)
+                    .await?
+                {
+                    self.emit_audit(RuntimeAuditEvent::for_grant(
+                        &updated,
+                        RuntimeActivity::SecretAccessed,
+                    ))
+                    .await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Send an event to the audit sink, if one is configured and the
+    /// event's rate limit lets it through.
+    async fn emit_audit(&self, event: RuntimeAuditEvent) {
+        let Some(sink) = &self.audit_sink else {
+            return;
+        };
+        let now = self.clock.now();
+        let Some(suppressed) = self.audit_limiter.admit(&event, now) else {
+            return;
+        };
+        let mut audit = event.to_audit_event(now);
+        if suppressed > 0 {
+            if let Some(detail) = audit.detail.as_object_mut() {
+                detail.insert("suppressed".to_string(), suppressed.into());
+            }
+        }
+        if let Err(e) = sink.emit(audit).await {
+            tracing::error!(error = %e, "Failed to export runtime audit event");
+        }
+    }
+
+    /// Audit activity in a sandbox, looking up its organization and agent.
+    ///
+    /// Sandboxes the runtime no longer tracks are not audited.
+    async fn audit_sandbox(&self, sandbox_id: SandboxId, activity: RuntimeActivity) {
+        if self.audit_sink.is_none() {
+            return;
+        }
+        match self.idle.owner(sandbox_id) {
+            Some((organization_id, agent_id)) => {
+                self.emit_audit(RuntimeAuditEvent::new(
+                    organization_id,
+                    sandbox_id,
+                    agent_id,
+                    activity,
+                ))
+                .await
+            }
+            None => tracing::debug!(
+                sandbox_id = %sandbox_id,
+                action = activity.kind().action(),
+                "Skipping audit event for untracked sandbox"
+            ),
+        }
+    }
+
+    /// Get the secret grants recorded for a sandbox.
+    pub async fn secret_grants(&self, sandbox_id: SandboxId) -> CretoResult<Vec<SecretGrant>> {
+        self.secret_grants.list_by_sandbox(sandbox_id).await
+    }
+
+    /// Summarize an organization's secret grants per secret and per agent.
+    ///
+    /// Includes every grant in effect at any point in `[period_start, period_end)`.
+    pub async fn secret_access_report(
+        &self,
+        organization_id: OrganizationId,
+        period_start: DateTime<Utc>,
+        period_end: DateTime<Utc>,
+    ) -> CretoResult<SecretAccessReport> {
+        if period_start >= period_end {
+            return Err(CretoError::ValidationFailed(
+                "report period start must be before its end".to_string(),
+            ));
+        }
+
+        let grants = self
+            .secret_grants
+            .list_by_org(organization_id, period_start, period_end)
+            .await?;
+        Ok(SecretAccessReport::from_grants(
+            organization_id,
+            period_start,
+            period_end,
+            &grants,
+        ))
+    }
+
+    /// Release a sandbox back to the pool.
+    ///
+    /// Its agent channels are closed; the next holder may be another agent.
+    pub async fn release_sandbox(&self, sandbox_id: SandboxId) -> CretoResult<()> {
+        // The next holder expects a running sandbox
+        self.resume_idle(sandbox_id).await?;
+        self.idle.remove(sandbox_id);
+        self.close_sandbox_channels(sandbox_id).await;
+        self.pool.release(sandbox_id).await?;
+        self.sandbox_capacity_freed();
+        Ok(())
+    }
+
+    /// Wake a sandbox creation waiting for capacity, if any.
+    fn sandbox_capacity_freed(&self) {
+        if let Some(queue) = &self.execution_queue {
+            queue.sandbox_released();
+        }
+    }
+
+    /// Terminate a sandbox.
+    pub async fn terminate_sandbox(&self, sandbox_id: SandboxId) -> CretoResult<()> {
+        self.terminate_sandbox_for(sandbox_id, TerminationReason::Requested)
+            .await
+    }
+
+    /// Terminate a sandbox, recording `reason` with its final state.
+    async fn terminate_sandbox_for(
+        &self,
+        sandbox_id: SandboxId,
+        reason: TerminationReason,
+    ) -> CretoResult<()> {
+        let owner = self.idle.owner(sandbox_id);
+        self.limit_enforcer.remove(sandbox_id);
+        self.sandbox_egress.write().unwrap().remove(&sandbox_id);
+        self.sandbox_classes.write().unwrap().remove(&sandbox_id);
+        self.sandbox_limits.write().unwrap().remove(&sandbox_id);
+        self.sandbox_attestations
+            .write()
+            .unwrap()
+            .remove(&sandbox_id);
+        if let Some(IdleState::Hibernated { checkpoint_id, .. }) = self.idle.remove(sandbox_id) {
+            self.discard_hibernation_checkpoint(sandbox_id, checkpoint_id)
+                .await;
+        }
+        self.checkpoint_manager.sandbox_ended(sandbox_id);
+        self.close_sandbox_channels(sandbox_id).await;
+        self.end_secret_grants(sandbox_id).await?;
+        self.stop_usage_sampling(sandbox_id).await;
+
+        // Remove from pool and terminate
+        let mut handle = self.runtime_handles.write().unwrap().remove(&sandbox_id);
+        if let Some(mut sandbox) = self.pool.remove(sandbox_id).await {
+            handle = handle.or(sandbox.runtime_handle.take());
+            sandbox.mark_terminated_for(reason);
+        }
+        if let (Some(backend), Some(handle)) = (&self.backend, handle) {
+            if let Err(e) = backend.terminate(&handle).await {
+                // Keep the handle so a retry can still reach the sandbox
+                self.runtime_handles
+                    .write()
+                    .unwrap()
+                    .insert(sandbox_id, handle);
+                return Err(e);
+            }
+        }
+
+        if let Some(repository) = &self.sandbox_repository {
+            repository.terminate(sandbox_id).await?;
+            if reason != TerminationReason::Requested {
+                repository
+                    .update_state(sandbox_id, SandboxState::Terminated { reason })
+                    .await?;
+            }
+        }
+        if let Some((organization_id, agent_id)) = owner {
+            self.emit_audit(RuntimeAuditEvent::new(
+                organization_id,
+                sandbox_id,
+                agent_id,
+                RuntimeActivity::SandboxTerminated,
+            ))
+            .await;
+        }
+        self.audit_limiter.remove_sandbox(sandbox_id);
+        self.sandbox_capacity_freed();
+        Ok(())
+    }
+
+    /// Open a brokered channel between two sandboxes.
+    ///
+    /// Both sandboxes must be running an execution and belong to the same
+    /// organization, unless the channel configuration allows the pair of
+    /// organizations. The agents' sessions are established (or reused) by
+    /// the session broker; each sandbox receives only a descriptor, listed
+    /// on its channel socket and injected into later executions as
+    /// [`ExecutionRequest::channels`].
+    pub async fn open_agent_channel(
+        &self,
+        sandbox_a: SandboxId,
+        sandbox_b: SandboxId,
+    ) -> CretoResult<AgentChannel> {
+        let proxy = self.agent_channels.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Agent channels are not enabled".to_string())
+        })?;
+        let repository = self.sandbox_repository.as_ref().ok_or_else(|| {
+            CretoError::Configuration("Agent channels require a sandbox repository".to_string())
+        })?;
+        if sandbox_a == sandbox_b {
+            return Err(CretoError::ValidationFailed(
+                "Cannot open a channel from a sandbox to itself".to_string(),
+            ));
+        }
+
+        let mut peers = Vec::with_capacity(2);
+        for sandbox_id in [sandbox_a, sandbox_b] {
+            let record = repository
+                .get(sandbox_id)
+                .await?
+                .ok_or_else(|| CretoError::SandboxNotFound(sandbox_id.to_string()))?;
+            peers.push(record);
+        }
+
+        let (a, b) = (&peers[0], &peers[1]);
+        if !proxy.config().permits(a.organization_id, b.organization_id) {
+            return Err(CretoError::AuthorizationDenied(format!(
+                "Sandboxes of organizations {} and {} may not be connected",
+                a.organization_id, b.organization_id
+            )));
+        }
+        for record in &peers {
+            // Executions are tracked in memory; the record says the sandbox is live
+            if record.state.is_terminal() || !self.idle.is_running(record.id) {
+                return Err(CretoError::ValidationFailed(format!(
+                    "Sandbox {} is not running",
+                    record.id
+                )));
+            }
+        }
+
+        proxy
+            .open(
+                ChannelPeer {
+                    sandbox_id: a.id,
+                    agent_id: a.agent_id,
+                },
+                ChannelPeer {
+                    sandbox_id: b.id,
+                    agent_id: b.agent_id,
+                },
+            )
+            .await
+    }
+
+    /// Close an agent channel. Returns whether it was open.
+    pub async fn close_agent_channel(&self, channel_id: ChannelId) -> CretoResult<bool> {
+        match &self.agent_channels {
+            Some(proxy) => proxy.close(channel_id).await,
+            None => Ok(false),
+        }
+    }
+
+    /// Get the agent channel proxy, if channels are enabled.
+    pub fn agent_channels(&self) -> Option<&Arc<AgentChannelProxy>> {
+        self.agent_channels.as_ref()
+    }
+
+    /// Bytes a sandbox has moved over agent channels.
+    pub fn network_usage(&self, sandbox_id: SandboxId) -> NetworkUsage {
+        self.agent_channels
+            .as_ref()
+            .map(|proxy| proxy.usage_tracker().usage(sandbox_id))
+            .unwrap_or_default()
+    }
+
+    /// Close a sandbox's agent channels, logging rather than returning
+    /// failures so teardown always proceeds.
+    async fn close_sandbox_channels(&self, sandbox_id: SandboxId) {
+        let Some(proxy) = &self.agent_channels else {
+            return;
+        };
+        if let Err(e) = proxy.close_sandbox(sandbox_id).await {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                error = %e,
+                "Failed to close agent channel sessions"
+            );
+        }
+    }
+
+    /// Reset a sandbox's idle time, for activity other than executions
+    /// such as output streaming to a client.
+    ///
+    /// Returns `false` if the sandbox is not tracked.
+    pub fn record_sandbox_activity(&self, sandbox_id: SandboxId) -> bool {
+        self.idle.touch(sandbox_id, self.clock.now())
+    }
+
+    /// Idle state of a sandbox handed to an agent, if it is tracked.
+    pub fn sandbox_idle_state(&self, sandbox_id: SandboxId) -> Option<IdleState> {
+        self.idle.state(sandbox_id)
+    }
+
+    /// An organization's tracked sandboxes, most recently active first.
+    pub fn sandbox_activity(&self, organization_id: OrganizationId) -> Vec<SandboxActivity> {
+        self.idle.activity(organization_id)
+    }
+
+    /// Exempt a sandbox from idle pausing, or make it eligible again.
+    ///
+    /// Keeping a sandbox warm fails with [`CretoError::QuotaExceeded`] if
+    /// its organization already holds its
+    /// [`max_keep_warm`](IdlePolicy::max_keep_warm) keep-warm sandboxes. A
+    /// sandbox that is already paused stays paused until its next execution.
+    pub async fn set_keep_warm(&self, sandbox_id: SandboxId, keep_warm: bool) -> CretoResult<()> {
+        let organization_id = self
+            .idle
+            .organization(sandbox_id)
+            .ok_or_else(|| CretoError::SandboxNotFound(sandbox_id.to_string()))?;
+        let policy = self.idle_policy(organization_id).await?;
+        self.idle.set_keep_warm(
+            sandbox_id,
+            keep_warm,
+            policy.max_keep_warm,
+            self.clock.now(),
+        )
+    }
+
+    /// Pause and hibernate sandboxes idle past their organization's thresholds.
+    ///
+    /// Sandboxes running an execution, kept warm, or already hibernated are
+    /// left alone. A sandbox whose pause or hibernation fails stays as it
+    /// was and is retried on the next sweep.
+    pub async fn run_idle_sweep(&self) -> CretoResult<IdleSweepReport> {
+        let mut report = IdleSweepReport::default();
+        let mut policies: HashMap<OrganizationId, IdlePolicy> = HashMap::new();
+
+        for (sandbox_id, organization_id) in self.idle.candidates() {
+            let policy = match policies.get(&organization_id) {
+                Some(policy) => policy.clone(),
+                None => {
+                    let policy = self.idle_policy(organization_id).await?;
+                    policies.insert(organization_id, policy.clone());
+                    policy
+                }
+            };
+
+            let _transition = self.idle.transitions.lock().await;
+            let Some(claim) = self.idle.claim(sandbox_id, &policy, self.clock.now()) else {
+                continue;
+            };
+            // Terminating a sandbox the backend cannot bring back would
+            // lose its state, so such sandboxes are only ever paused
+            let mut step = claim.step;
+            if step == IdleStep::Hibernate
+                && self
+                    .backend
+                    .as_ref()
+                    .is_some_and(|backend| !backend.supports_restore())
+            {
+                if claim.previous != IdleState::Active {
+                    self.idle.set_state(sandbox_id, claim.previous);
+                    continue;
+                }
+                step = IdleStep::Pause;
+            }
+            let outcome = match step {
+                IdleStep::Pause => self.pause_idle(sandbox_id, &claim).await,
+                IdleStep::Hibernate => self.hibernate_idle(sandbox_id, &claim).await,
+            };
+            match (outcome, step) {
+                (Ok(()), IdleStep::Pause) => report.paused.push(sandbox_id),
+                (Ok(()), IdleStep::Hibernate) => report.hibernated.push(sandbox_id),
+                (Err(e), step) => {
+                    tracing::warn!(
+                        sandbox_id = %sandbox_id,
+                        step = ?step,
+                        error = %e,
+                        "Failed to reclaim idle sandbox"
+                    );
+                    self.idle.set_state(sandbox_id, claim.previous);
+                    report.failed.push(sandbox_id);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Spawn the idle sweep worker.
+    ///
+    /// Sweeps every `sweep_interval` of the idle configuration. The task is
+    /// registered with `shutdown`.
+    pub fn spawn_idle_manager(self: &Arc<Self>, shutdown: &ShutdownCoordinator) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        let interval = self.idle_config.sweep_interval;
+
+        shutdown.spawn("runtime.idle_manager", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    if let Err(e) = service.run_idle_sweep().await {
+                        tracing::warn!(error = %e, "Idle sweep failed");
+                    }
+                }
+            })
+        })
+    }
+
+    /// Check every sandbox against its CPU and wall time limits.
+    ///
+    /// Sandboxes whose backend exposes live usage have their CPU time read
+    /// first. Warnings and terminations are reported to the limit event
+    /// sink, if one is set. A sandbox past a limit has its running
+    /// executions cancelled with
+    /// [`ExecutionError::resource_limit_exceeded`], a final usage snapshot
+    /// recorded and is terminated with
+    /// [`TerminationReason::LimitExceeded`]; one that fails to terminate is
+    /// reported in [`LimitEnforcementReport::failed`].
+    pub async fn enforce_limits(&self) -> CretoResult<LimitEnforcementReport> {
+        for sandbox_id in self.limit_enforcer.sandbox_ids() {
+            if let Some(usage) = self.live_usage(sandbox_id).await {
+                self.limit_enforcer
+                    .observe_cpu_time(sandbox_id, usage.cpu_time_ms);
+            }
+        }
+
+        let mut report = LimitEnforcementReport::default();
+        for event in self.limit_enforcer.check(self.clock.now()) {
+            self.emit_limit_event(&event).await;
+            match event {
+                LimitEvent::Warning(breach) => {
+                    tracing::warn!(
+                        sandbox_id = %breach.sandbox_id,
+                        limit = %breach.limit,
+                        used = breach.used,
+                        max = breach.max,
+                        "Sandbox approaching resource limit"
+                    );
+                    report.warned.push(breach);
+                }
+                LimitEvent::Exceeded(breach) => {
+                    let sandbox_id = breach.sandbox_id;
+                    match self.terminate_over_limit(&breach).await {
+                        Ok(()) => report.terminated.push(breach),
+                        Err(e) => {
+                            tracing::warn!(
+                                sandbox_id = %sandbox_id,
+                                error = %e,
+                                "Failed to terminate sandbox over its resource limit"
+                            );
+                            report.failed.push(sandbox_id);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Spawn the limit enforcement worker.
+    ///
+    /// Checks limits every `check_interval` of the limit enforcement
+    /// configuration. The task is registered with `shutdown`.
+    pub fn spawn_limit_enforcer(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+    ) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        let interval = self.limit_enforcer.config().check_interval;
+
+        shutdown.spawn("runtime.limit_enforcer", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    if let Err(e) = service.enforce_limits().await {
+                        tracing::warn!(error = %e, "Limit enforcement failed");
+                    }
+                }
+            })
+        })
+    }
+
+    /// Record a sandbox's final usage, then terminate it for exceeding a
+    /// limit.
+    ///
+    /// Running executions are cancelled as soon as the enforcer marks the
+    /// limit exceeded, before this is called.
+    async fn terminate_over_limit(&self, breach: &LimitBreach) -> CretoResult<()> {
+        let sandbox_id = breach.sandbox_id;
+        tracing::warn!(
+            sandbox_id = %sandbox_id,
+            limit = %breach.limit,
+            used = breach.used,
+            max = breach.max,
+            "Terminating sandbox over its resource limit"
+        );
+        if let Some(repository) = &self.resource_usage_repository {
+            let mut usage = self.live_usage(sandbox_id).await.unwrap_or_default();
+            if let Some(cpu_time_ms) = self.limit_enforcer.cpu_time_ms(sandbox_id) {
+                usage.cpu_time_ms = usage.cpu_time_ms.max(cpu_time_ms);
+            }
+            if breach.limit == LimitKind::WallTime {
+                usage.wall_time_ms = usage.wall_time_ms.max(breach.used * 1000);
+            }
+            repository.record(sandbox_id, &usage).await?;
+        }
+        self.terminate_sandbox_for(sandbox_id, TerminationReason::LimitExceeded)
+            .await
+    }
+
+    /// Current usage of a sandbox, if its backend can read it.
+    ///
+    /// Read failures are logged and treated as unavailable.
+    async fn live_usage(&self, sandbox_id: SandboxId) -> Option<ResourceUsage> {
+        let backend = self.backend.as_ref()?;
+        let handle = self
+            .runtime_handles
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .cloned()?;
+        let controller = backend.resource_controller(&handle)?;
+        match controller.current_usage().await {
+            Ok(usage) => Some(usage),
+            Err(e) => {
+                tracing::warn!(sandbox_id = %sandbox_id, error = %e, "Failed to read sandbox usage");
+                None
+            }
+        }
+    }
+
+    /// Report a limit event, logging failures.
+    async fn emit_limit_event(&self, event: &LimitEvent) {
+        if let Some(sink) = &self.limit_events {
+            if let Err(e) = sink.emit(event.clone()).await {
+                tracing::warn!(error = %e, "Failed to emit limit event");
+            }
+        }
+    }
+
+    /// Idle policy of an organization, or the service default.
+    async fn idle_policy(&self, organization_id: OrganizationId) -> CretoResult<IdlePolicy> {
+        Ok(self
+            .runtime_policies
+            .get(organization_id)
+            .await?
+            .and_then(|policy| policy.idle)
+            .unwrap_or_else(|| self.idle_config.default_policy.clone()))
+    }
+
+    /// Stop an idle sandbox and revoke the secrets whose policy asks for it.
+    async fn pause_idle(&self, sandbox_id: SandboxId, claim: &IdleClaim) -> CretoResult<()> {
+        if let (Some(backend), Some(handle)) = (&self.backend, &claim.handle) {
+            backend.stop(handle).await?;
+        }
+
+        // Leases are not held while paused; retained mounts are leased
+        // again on resume
+        let retained: Vec<_> = self
+            .revoke_secret_leases(sandbox_id)
+            .await
+            .iter()
+            .filter(|mounted| mounted.mount.idle_policy == SecretIdlePolicy::Retain)
+            .map(MountedSecret::checkpointed)
+            .collect();
+        if !retained.is_empty() {
+            self.suspended_secrets
+                .write()
+                .unwrap()
+                .insert(sandbox_id, retained);
+        }
+
+        let now = self.clock.now();
+        for grant in self.secret_grants.list_by_sandbox(sandbox_id).await? {
+            if !grant.is_active() || grant.idle_policy != SecretIdlePolicy::Revoke {
+                continue;
+            }
+            if let Some(revoked) = self.secret_grants.revoke_grant(grant.id, now).await? {
+                self.emit_audit(RuntimeAuditEvent::for_grant(
+                    &revoked,
+                    RuntimeActivity::SecretRevoked,
+                ))
+                .await;
+            }
+        }
+
+        if let Some(repository) = &self.sandbox_repository {
+            repository
+                .update_state(sandbox_id, SandboxState::Paused)
+                .await?;
+        }
+        self.audit_sandbox(
+            sandbox_id,
+            RuntimeActivity::SandboxPaused { hibernated: false },
+        )
+        .await;
+        tracing::info!(sandbox_id = %sandbox_id, "Paused idle sandbox");
+        Ok(())
+    }
+
+    /// Checkpoint an idle sandbox with the backend's snapshot of its state,
+    /// then revoke its secrets and terminate it.
+    ///
+    /// A sandbox taken from the warm pool leaves it; it is restored outside
+    /// the pool.
+    async fn hibernate_idle(&self, sandbox_id: SandboxId, claim: &IdleClaim) -> CretoResult<()> {
+        let mut config = CheckpointConfig {
+            owner: self.idle.owner(sandbox_id),
+            sandbox_config: self.idle.config(sandbox_id),
+            ..Default::default()
+        };
+        config
+            .metadata
+            .insert("reason".to_string(), "idle_hibernation".to_string());
+        if let (Some(backend), Some(handle)) = (&self.backend, &claim.handle) {
+            config.snapshot = Some(backend.snapshot(handle).await?);
+        }
+        let checkpoint_id = self
+            .checkpoint_manager
+            .checkpoint(sandbox_id, config)
+            .await?;
+
+        self.end_secret_grants(sandbox_id).await?;
+        self.stop_usage_sampling(sandbox_id).await;
+        let mut handle = self
+            .runtime_handles
+            .write()
+            .unwrap()
+            .remove(&sandbox_id)
+            .or_else(|| claim.handle.clone());
+        if let Some(mut sandbox) = self.pool.remove(sandbox_id).await {
+            handle = handle.or(sandbox.runtime_handle.take());
+        }
+        if let (Some(backend), Some(handle)) = (&self.backend, handle) {
+            backend.terminate(&handle).await?;
+        }
+        self.idle.set_handle(sandbox_id, None);
+        self.idle.set_state(
+            sandbox_id,
+            IdleState::Hibernated {
+                checkpoint_id,
+                since: self.clock.now(),
+            },
+        );
+
+        if let Some(repository) = &self.sandbox_repository {
+            repository
+                .update_state(
+                    sandbox_id,
+                    SandboxState::Checkpointed {
+                        checkpoint_id: checkpoint_id.to_string(),
+                    },
+                )
+                .await?;
+        }
+        self.audit_sandbox(
+            sandbox_id,
+            RuntimeActivity::SandboxPaused { hibernated: true },
+        )
+        .await;
+        tracing::info!(
+            sandbox_id = %sandbox_id,
+            checkpoint_id = %checkpoint_id,
+            "Hibernated idle sandbox"
+        );
+        Ok(())
+    }
+
+    /// Bring a paused or hibernated sandbox back, returning how long it
+    /// took in milliseconds, or `None` if it was already active.
+    async fn resume_idle(&self, sandbox_id: SandboxId) -> CretoResult<Option<u64>> {
+        let _transition = self.idle.transitions.lock().await;
+        let started = std::time::Instant::now();
+        match self.idle.state(sandbox_id) {
+            None | Some(IdleState::Active) => return Ok(None),
+            Some(IdleState::Paused { .. }) => {
+                if let (Some(backend), Some(handle)) = (&self.backend, self.idle.handle(sandbox_id))
+                {
+                    backend.start(&handle).await?;
+                }
+                let suspended = self
+                    .suspended_secrets
+                    .write()
+                    .unwrap()
+                    .remove(&sandbox_id)
+                    .unwrap_or_default();
+                self.restore_secret_mounts(sandbox_id, suspended).await?;
+            }
+            Some(IdleState::Hibernated { checkpoint_id, .. }) => {
+                self.checkpoint_manager.restore(checkpoint_id).await?;
+                // The hibernated instance was terminated; bring up a new one
+                // from its snapshot. The checkpoint is the only copy of the
+                // state, so it is kept until the restore succeeds.
+                if let (Some(backend), Some(config)) = (&self.backend, self.idle.config(sandbox_id))
+                {
+                    let snapshot = self
+                        .checkpoint_manager
+                        .resolve_snapshot(checkpoint_id)
+                        .await?;
+                    let handle = backend.restore(&config, &snapshot).await?;
+                    self.runtime_handles
+                        .write()
+                        .unwrap()
+                        .insert(sandbox_id, handle.clone());
+                    if let Some((organization_id, agent_id)) = self.idle.owner(sandbox_id) {
+                        self.start_usage_sampling(
+                            sandbox_id,
+                            organization_id,
+                            agent_id,
+                            Some(&handle),
+                        );
+                    }
+                    self.idle.set_handle(sandbox_id, Some(handle));
+                }
+                self.discard_hibernation_checkpoint(sandbox_id, checkpoint_id)
+                    .await;
+            }
+        }
+        self.idle.set_state(sandbox_id, IdleState::Active);
+
+        if let Some(repository) = &self.sandbox_repository {
+            repository
+                .update_state(sandbox_id, SandboxState::Ready)
+                .await?;
+        }
+        let resume_ms = started.elapsed().as_millis() as u64;
+        self.audit_sandbox(sandbox_id, RuntimeActivity::SandboxResumed { resume_ms })
+            .await;
+        tracing::info!(sandbox_id = %sandbox_id, resume_ms, "Resumed idle sandbox");
+        Ok(Some(resume_ms))
+    }
+
+    /// Delete a hibernation checkpoint that is no longer needed.
+    ///
+    /// Errors are logged; a leftover checkpoint only costs storage.
+    async fn discard_hibernation_checkpoint(
+        &self,
+        sandbox_id: SandboxId,
+        checkpoint_id: CheckpointId,
+    ) {
+        if let Err(e) = self
+            .checkpoint_manager
+            .delete_checkpoint(checkpoint_id)
+            .await
+        {
+            tracing::warn!(
+                sandbox_id = %sandbox_id,
+                checkpoint_id = %checkpoint_id,
+                error = %e,
+                "Failed to delete hibernation checkpoint"
+            );
+        }
+    }
+
+    /// Get the warm pool.
+    pub(crate) fn pool(&self) -> &WarmPool {
+        &self.pool
+    }
+
+    /// Get pool statistics.
+    pub async fn pool_stats(&self) -> crate::pool::PoolStats {
+        self.pool.stats().await
+    }
+
+    /// Cleanup idle sandboxes.
+    pub async fn cleanup_idle(&self) -> CretoResult<usize> {
+        let removed = self.pool.cleanup_idle().await?;
+        Ok(removed.len())
+    }
+
+    /// Current health of the warm pool.
+    pub async fn pool_health(&self) -> PoolHealth {
+        self.pool.pool_health().await
+    }
+
+    /// Probe the warm pool, destroy evicted sandboxes and replace them.
+    ///
+    /// Evicted sandboxes are terminated in the backend and their records
+    /// marked terminated; failures are logged. Replacements come from
+    /// [`replenish_pool`](Self::replenish_pool), so they are only made up to
+    /// each runtime's `min_warm`.
+    pub async fn check_pool_health(&self) -> PoolHealthReport {
+        let mut report = self.pool.check_health().await;
+        for sandbox in &report.evicted {
+            self.destroy_evicted(sandbox).await;
+        }
+        if !report.evicted.is_empty() || !report.unhealthy.is_empty() {
+            report.replaced = self.replenish_pool().await.provisioned;
+        }
+        report
+    }
+
+    /// Tear down a sandbox the pool evicted.
+    async fn destroy_evicted(&self, sandbox: &Sandbox) {
+        if let (Some(backend), Some(handle)) = (&self.backend, &sandbox.runtime_handle) {
+            if let Err(e) = backend.terminate(handle).await {
+                tracing::warn!(
+                    sandbox_id = %sandbox.id,
+                    error = %e,
+                    "Failed to terminate evicted pool sandbox"
+                );
+            }
+        }
+        if let Some(repository) = &self.sandbox_repository {
+            if let Err(e) = repository.terminate(sandbox.id).await {
+                tracing::warn!(
+                    sandbox_id = %sandbox.id,
+                    error = %e,
+                    "Failed to record evicted pool sandbox as terminated"
+                );
+            }
+        }
+    }
+
+    /// Spawn the pool health worker.
+    ///
+    /// Runs [`check_pool_health`](Self::check_pool_health) every
+    /// `sweep_interval_seconds` of the pool's health configuration. The task
+    /// is registered with `shutdown`.
+    pub fn spawn_pool_health_checks(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+    ) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        let interval = Duration::from_secs(self.pool.config().health.sweep_interval_seconds.max(1));
+
+        shutdown.spawn("runtime.pool_health", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    let report = service.check_pool_health().await;
+                    if !report.evicted.is_empty() {
+                        tracing::info!(
+                            evicted = report.evicted.len(),
+                            replaced = report.replaced,
+                            "Replaced unhealthy warm pool sandboxes"
+                        );
+                    }
+                }
+            })
+        })
+    }
+
+    /// Top the warm pool up to each runtime's `min_warm`.
+    ///
+    /// Does nothing without a sandbox backend. Retryable failures are
+    /// retried under the service's retry policy and, if they persist, again
+    /// on the next call. A permanent failure suspends replenishment of that
+    /// runtime until [`resume_pool_replenishment`](Self::resume_pool_replenishment),
+    /// since every further attempt would fail the same way.
+    pub async fn replenish_pool(&self) -> ReplenishmentReport {
+        let mut report = ReplenishmentReport::default();
+        if self.backend.is_none() {
+            return report;
+        }
+
+        let stats = self.pool.stats().await;
+        for runtime in &self.pool.config().runtimes {
+            if self
+                .replenishment_suspended
+                .read()
+                .unwrap()
+                .contains_key(&runtime.name)
+            {
+                report.suspended.push(runtime.name.clone());
+                continue;
+            }
+
+            let ready = stats.by_runtime.get(&runtime.name).map_or(0, |s| s.ready);
+            for _ in ready..runtime.min_warm {
+                let config = SandboxConfig {
+                    runtime: runtime.name.clone(),
+                    ..Default::default()
+                };
+                match self.provision_warm(config).await {
+                    Ok(sandbox) => {
+                        if let Err(e) = self.pool.add(sandbox).await {
+                            tracing::warn!(runtime = %runtime.name, error = %e, "Failed to add sandbox to the warm pool");
+                            break;
+                        }
+                        report.provisioned += 1;
+                    }
+                    Err(failure) => {
+                        if !failure.is_retryable() {
+                            tracing::error!(
+                                runtime = %runtime.name,
+                                error = %failure,
+                                "Suspending warm pool replenishment after a permanent failure"
+                            );
+                            self.replenishment_suspended
+                                .write()
+                                .unwrap()
+                                .insert(runtime.name.clone(), failure.clone());
+                            report.suspended.push(runtime.name.clone());
+                        }
+                        report.failures.push(failure);
+                        break;
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// Provision an unowned sandbox for the warm pool.
+    async fn provision_warm(&self, config: SandboxConfig) -> Result<Sandbox, ProvisioningFailure> {
+        let started = std::time::Instant::now();
+        let mut report = ProvisioningReport::new(self.clock.now());
+        loop {
+            report.attempts += 1;
+            let attempt = report.attempts;
+            let mut sandbox = Sandbox::new(OrganizationId::new(), AgentId::new(), config.clone());
+            let result = self.provision_fresh(&mut sandbox, &mut report, false).await;
+            report.total_ms = started.elapsed().as_millis() as u64;
+
+            let (phase, error) = match result {
+                Ok(()) => return Ok(sandbox),
+                Err(failed) => failed,
+            };
+            if !error.is_retryable() || attempt >= self.provisioning_retry.max_attempts {
+                return Err(ProvisioningFailure {
+                    phase,
+                    error,
+                    report,
+                });
+            }
+            tokio::time::sleep(Duration::from_millis(
+                self.provisioning_retry.backoff_ms(attempt),
+            ))
+            .await;
+        }
+    }
+
+    /// Warm pool runtimes whose replenishment is suspended, with the failure
+    /// that suspended each.
+    pub fn suspended_pool_runtimes(&self) -> HashMap<String, ProvisioningFailure> {
+        self.replenishment_suspended.read().unwrap().clone()
+    }
+
+    /// Resume replenishing a runtime, once whatever failed permanently is
+    /// fixed. Returns whether it was suspended.
+    pub fn resume_pool_replenishment(&self, runtime: &str) -> bool {
+        self.replenishment_suspended
+            .write()
+            .unwrap()
+            .remove(runtime)
+            .is_some()
+    }
+
+    /// Spawn the pool maintenance task.
+    ///
+    /// Evicts idle sandboxes and replenishes the pool every
+    /// `cleanup_interval_seconds` of the pool configuration. The task is
+    /// registered with `shutdown` and stops between passes.
+    pub fn spawn_pool_maintenance(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+    ) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        let interval = Duration::from_secs(self.pool.config().cleanup_interval_seconds.max(1));
+
+        shutdown.spawn("runtime.pool_maintenance", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    match service.cleanup_idle().await {
+                        Ok(0) => {}
+                        Ok(evicted) => tracing::debug!(evicted, "Evicted idle pooled sandboxes"),
+                        Err(e) => tracing::warn!(error = %e, "Pool maintenance pass failed"),
+                    }
+                    let replenished = service.replenish_pool().await;
+                    if replenished.provisioned > 0 {
+                        tracing::debug!(
+                            provisioned = replenished.provisioned,
+                            "Replenished warm pool"
+                        );
+                    }
+                }
+            })
+        })
+    }
+
+    /// Create a recurring execution of `template`.
+    pub async fn create_schedule(
+        &self,
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        template: ExecutionRequest,
+        trigger: ScheduleTrigger,
+        catch_up: CatchUpPolicy,
+    ) -> CretoResult<ExecutionSchedule> {
+        let schedule = ExecutionSchedule::new(
+            organization_id,
+            agent_id,
+            template,
+            trigger,
+            self.clock.now(),
+        )
+        .with_catch_up(catch_up);
+        self.schedules.create(&schedule).await?;
+
+        tracing::info!(
+            schedule_id = %schedule.id,
+            organization_id = %organization_id,
+            next_run_at = ?schedule.next_run_at,
+            "Execution schedule created"
+        );
+        Ok(schedule)
+    }
+
+    /// Get a schedule.
+    pub async fn get_schedule(&self, schedule_id: ScheduleId) -> CretoResult<ExecutionSchedule> {
+        self.schedules
+            .get(schedule_id)
+            .await?
+            .ok_or_else(|| CretoError::NotFound(format!("Schedule {}", schedule_id)))
+    }
+
+    /// List an organization's schedules.
+    pub async fn list_schedules(
+        &self,
+        organization_id: OrganizationId,
+    ) -> CretoResult<Vec<ExecutionSchedule>> {
+        self.schedules.list_by_org(organization_id).await
+    }
+
+    /// Change a schedule's template, trigger, or catch-up policy.
+    ///
+    /// The next run is recomputed from now. A run already claimed by a
+    /// scheduler worker does not fire.
+    pub async fn update_schedule(
+        &self,
+        schedule_id: ScheduleId,
+        template: ExecutionRequest,
+        trigger: ScheduleTrigger,
+        catch_up: CatchUpPolicy,
+    ) -> CretoResult<ExecutionSchedule> {
+        let mut schedule = self.get_schedule(schedule_id).await?;
+        let now = self.clock.now();
+        schedule.template = template;
+        schedule.next_run_at = trigger.next_after(now);
+        schedule.trigger = trigger;
+        schedule.catch_up = catch_up;
+        schedule.updated_at = now;
+        self.schedules.update(&schedule).await?;
+        Ok(schedule)
+    }
+
+    /// Enable or disable a schedule.
+    ///
+    /// Disabling stops a run already claimed by a scheduler worker from
+    /// firing. Re-enabling resumes from the next run after now rather than
+    /// catching up.
+    pub async fn set_schedule_enabled(
+        &self,
+        schedule_id: ScheduleId,
+        enabled: bool,
+    ) -> CretoResult<ExecutionSchedule> {
+        let mut schedule = self.get_schedule(schedule_id).await?;
+        let now = self.clock.now();
+        if enabled && !schedule.enabled {
+            schedule.next_run_at = schedule.trigger.next_after(now);
+        }
+        schedule.enabled = enabled;
+        schedule.updated_at = now;
+        self.schedules.update(&schedule).await?;
+        Ok(schedule)
+    }
+
+    /// Delete a schedule. Its run history is kept.
+    pub async fn delete_schedule(&self, schedule_id: ScheduleId) -> CretoResult<()> {
+        if !self.schedules.delete(schedule_id).await? {
+            return Err(CretoError::NotFound(format!("Schedule {}", schedule_id)));
+        }
+        Ok(())
+    }
+
+    /// Get a page of a schedule's runs, most recent first.
+    pub async fn schedule_runs(
+        &self,
+        schedule_id: ScheduleId,
+        page: ScheduleRunPage,
+    ) -> CretoResult<Vec<ScheduleRun>> {
+        self.schedules.list_runs(schedule_id, page).await
+    }
+
+    /// Fire every schedule that is due now.
+    ///
+    /// Due schedules are claimed under a lease so concurrent workers never
+    /// fire the same run. Each run is gated by the schedule gate, if any,
+    /// and executed through the normal execution path; skipped runs are
+    /// recorded with their reason. A run that cannot be recorded is logged
+    /// and still returned, and the remaining due schedules keep firing.
+    pub async fn run_due_schedules(&self) -> CretoResult<Vec<ScheduleRun>> {
+        let now = self.clock.now();
+        let claims = self
+            .schedules
+            .claim_due(now, self.scheduler.lease, self.scheduler.batch_size)
+            .await?;
+
+        let mut runs = Vec::with_capacity(claims.len());
+        for claim in claims {
+            let schedule = &claim.schedule;
+            let plan = schedule.plan_run(now, self.scheduler.misfire_grace);
+
+            // Advance first: a schedule disabled, changed, or deleted since
+            // the claim must not fire
+            if !self
+                .schedules
+                .complete_claim(&claim, plan.next_run_at)
+                .await?
+            {
+                tracing::debug!(schedule_id = %schedule.id, "Schedule changed after claim; not firing");
+                continue;
+            }
+
+            let (execution_id, outcome) = if !plan.execute {
+                (
+                    None,
+                    ScheduleRunOutcome::Skipped {
+                        reason: format!("missed {} scheduled run(s)", plan.missed),
+                    },
+                )
+            } else if let Some(Err(e)) = match &self.schedule_gate {
+                Some(gate) => Some(gate.admit(schedule).await),
+                None => None,
+            } {
+                (
+                    None,
+                    ScheduleRunOutcome::Skipped {
+                        reason: e.to_string(),
+                    },
+                )
+            } else {
+                let mut request = schedule.template.clone();
+                request.id = Uuid::now_v7();
+                let request =
+                    request.with_correlation(schedule.template.correlation().derive(schedule.id));
+                let requested_id = request.id;
+                match self
+                    .run_execution(Some(schedule.organization_id), request)
+                    .await
+                {
+                    Ok(result) => (
+                        Some(result.request_id),
+                        ScheduleRunOutcome::Completed {
+                            status: result.status,
+                        },
+                    ),
+                    Err(e) => (
+                        Some(requested_id),
+                        ScheduleRunOutcome::Failed {
+                            error: e.to_string(),
+                        },
+                    ),
+                }
+            };
+
+            let run = ScheduleRun {
+                id: Uuid::now_v7(),
+                schedule_id: schedule.id,
+                scheduled_for: plan.scheduled_for,
+                recorded_at: self.clock.now(),
+                execution_id,
+                missed: plan.missed,
+                outcome,
+            };
+            if let Err(e) = self.schedules.record_run(&run).await {
+                tracing::error!(
+                    schedule_id = %schedule.id,
+                    error = %e,
+                    "Failed to record schedule run"
+                );
+            }
+            tracing::info!(
+                schedule_id = %schedule.id,
+                worker_id = %self.scheduler.worker_id,
+                outcome = ?run.outcome,
+                "Scheduled execution fired"
+            );
+            runs.push(run);
+        }
+        Ok(runs)
+    }
+
+    /// Spawn the scheduler worker.
+    ///
+    /// Fires due schedules every `poll_interval` of the scheduler
+    /// configuration. The task is registered with `shutdown` and stops
+    /// between passes.
+    pub fn spawn_scheduler(self: &Arc<Self>, shutdown: &ShutdownCoordinator) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        let interval = self.scheduler.poll_interval;
+
+        shutdown.spawn("runtime.scheduler", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    if let Err(e) = service.run_due_schedules().await {
+                        tracing::warn!(error = %e, "Scheduler pass failed");
+                    }
+                }
+            })
+        })
+    }
+
+    /// Register a topic trigger, subscribing to its topic.
+    ///
+    /// Only messages published after registration trigger executions.
+    pub async fn register_topic_trigger(&self, trigger: TopicTrigger) -> CretoResult<()> {
+        let source = self.topic_source.as_ref().ok_or_else(|| {
+            CretoError::ValidationFailed("No topic source configured".to_string())
+        })?;
+        let subscription_id = source.subscribe(&trigger).await?;
+
+        tracing::info!(
+            trigger_id = %trigger.id,
+            topic_id = %trigger.topic_id,
+            organization_id = %trigger.organization_id,
+            agent_id = %trigger.agent_id,
+            "Topic trigger registered"
+        );
+        let stored = StoredTopicTrigger {
+            trigger,
+            subscription_id,
+            cursor: None,
+            created_at: self.clock.now(),
+        };
+        if let Err(e) = self.topic_triggers.create(&stored).await {
+            if let Err(e) = source.unsubscribe(subscription_id).await {
+                tracing::warn!(subscription_id = %subscription_id, error = %e, "Failed to unsubscribe");
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Remove a topic trigger and its subscription, dropping pending
+    /// retries and dead letters.
+    pub async fn remove_topic_trigger(&self, trigger_id: TopicTriggerId) -> CretoResult<bool> {
+        let Some(stored) = self.topic_triggers.get(trigger_id).await? else {
+            return Ok(false);
+        };
+        if !self.topic_triggers.delete(trigger_id).await? {
+            return Ok(false);
+        }
+        if let Some(source) = &self.topic_source {
+            source.unsubscribe(stored.subscription_id).await?;
+        }
+        Ok(true)
+    }
+
+    /// List an organization's topic triggers.
+    pub async fn list_topic_triggers(
+        &self,
+        organization_id: OrganizationId,
+    ) -> CretoResult<Vec<TopicTrigger>> {
+        Ok(self
+            .topic_triggers
+            .list()
+            .await?
+            .into_iter()
+            .filter(|s| s.trigger.organization_id == organization_id)
+            .map(|s| s.trigger)
+            .collect())
+    }
+
+    /// Messages a trigger gave up on, oldest first.
+    pub async fn trigger_dead_letters(
+        &self,
+        trigger_id: TopicTriggerId,
+    ) -> CretoResult<Vec<TriggerDeadLetter>> {
+        self.topic_triggers.list_dead_letters(trigger_id).await
+    }
+
+    /// Deliver new and due-for-retry messages to every topic trigger.
+    ///
+    /// Each matching message is gated by the trigger gate, if any, and
+    /// executed through the normal execution path. Failed attempts are
+    /// retried with backoff, then dead-lettered. Passes are serialized, so
+    /// a message is never delivered twice concurrently; registering,
+    /// listing and removing triggers does not wait for a pass.
+    pub async fn run_topic_triggers(&self) -> CretoResult<Vec<TriggerDelivery>> {
+        let Some(source) = &self.topic_source else {
+            return Ok(Vec::new());
+        };
+        let _pass = self.trigger_pass.lock().await;
+
+        let mut deliveries = Vec::new();
+        for stored in self.topic_triggers.list().await? {
+            let trigger = &stored.trigger;
+            let mut pending: Vec<(TriggerMessage, u32)> = self
+                .topic_triggers
+                .due_retries(trigger.id, self.clock.now())
+                .await?
+                .into_iter()
+                .map(|r| (r.message, r.failures))
+                .collect();
+
+            let mut cursor = None;
+            match source
+                .poll(
+                    stored.subscription_id,
+                    stored.cursor,
+                    self.trigger_dispatch.batch_size,
+                )
+                .await
+            {
+                Ok(messages) => {
+                    cursor = messages.last().map(|m| m.id);
+                    pending.extend(
+                        messages
+                            .into_iter()
+                            .filter(|m| trigger.matches(&m.metadata))
+                            .map(|m| (m, 0)),
+                    );
+                }
+                Err(e) => {
+                    tracing::warn!(trigger_id = %trigger.id, error = %e, "Topic poll failed");
+                }
+            }
+
+            for (message, failures) in pending {
+                deliveries.push(self.deliver_message(trigger, message, failures + 1).await?);
+            }
+            // Advanced only once the batch was attempted, so a restart
+            // mid-batch delivers its messages again rather than losing them
+            if let Some(cursor) = cursor {
+                self.topic_triggers.set_cursor(trigger.id, cursor).await?;
+            }
+        }
+        Ok(deliveries)
+    }
+
+    /// Make one delivery attempt of a message to a trigger, storing the
+    /// retry or dead letter if it fails.
+    async fn deliver_message(
+        &self,
+        trigger: &TopicTrigger,
+        message: TriggerMessage,
+        attempt: u32,
+    ) -> CretoResult<TriggerDelivery> {
+        let (trigger_id, message_id) = (trigger.id, message.id);
+
+        let admitted = match &self.trigger_gate {
+            Some(gate) => gate.admit(trigger, &message).await,
+            None => Ok(()),
+        };
+        let (execution_id, result) = match admitted {
+            Ok(()) => {
+                let request = trigger.request_for(&message);
+                (
+                    Some(request.id),
+                    self.run_execution(Some(trigger.organization_id), request)
+                        .await,
+                )
+            }
+            Err(e) => (None, Err(e)),
+        };
+
+        let now = self.clock.now();
+        let (execution_id, outcome) = match result {
+            Ok(result) => {
+                self.topic_triggers
+                    .delete_retry(trigger_id, message_id)
+                    .await?;
+                (
+                    Some(result.request_id),
+                    TriggerOutcome::Executed {
+                        status: result.status,
+                    },
+                )
+            }
+            Err(e) => {
+                let (failed, outcome) = trigger.after_failure(message, attempt, e.to_string(), now);
+                match failed {
+                    FailedDelivery::Retry(retry) => self.topic_triggers.put_retry(&retry).await?,
+                    FailedDelivery::DeadLetter(dead) => {
+                        self.topic_triggers.put_dead_letter(&dead).await?;
+                        self.topic_triggers
+                            .delete_retry(trigger_id, message_id)
+                            .await?;
+                    }
+                }
+                (execution_id, outcome)
+            }
+        };
+
+        tracing::info!(
+            trigger_id = %trigger_id,
+            message_id = %message_id,
+            attempt,
+            outcome = ?outcome,
+            "Topic trigger fired"
+        );
+        Ok(TriggerDelivery {
+            trigger_id,
+            message_id,
+            attempt,
+            execution_id,
+            recorded_at: now,
+            outcome,
+        })
+    }
+
+    /// Spawn the topic trigger dispatcher.
+    ///
+    /// Delivers messages every `poll_interval` of the dispatch
+    /// configuration. The task is registered with `shutdown` and stops
+    /// between passes.
+    pub fn spawn_trigger_dispatcher(
+        self: &Arc<Self>,
+        shutdown: &ShutdownCoordinator,
+    ) -> JoinHandle<()> {
+        let service = Arc::clone(self);
+        let interval = self.trigger_dispatch.poll_interval;
+
+        shutdown.spawn("runtime.triggers", move |guard| {
+            guard.run_periodic(interval, move || {
+                let service = Arc::clone(&service);
+                async move {
+                    if let Err(e) = service.run_topic_triggers().await {
+                        tracing::warn!(error = %e, "Trigger dispatch pass failed");
+                    }
+                }
+            })
+        })
+    }
+
+    /// Create a checkpoint of a sandbox.
+    ///
+    /// The sandbox must be in a state that allows checkpointing (Ready, Paused, or Stopped).
+    /// Returns the ID of the created checkpoint.
+    ///
+    /// Mounted secret material is never captured. The checkpoint records
+    /// which secrets were mounted (except inline values) and their leases
+    /// are revoked; [`restore`](Self::restore) leases them again.
+    ///
+    /// The hashes of the sandbox's attestation, if it has one, are recorded
+    /// for [`restore_with_config`](Self::restore_with_config) to check.
+    pub async fn checkpoint(&self, sandbox_id: SandboxId) -> CretoResult<CheckpointId> {
+        let mut config = CheckpointConfig {
+            owner: self.idle.owner(sandbox_id),
+            sandbox_config: self.idle.config(sandbox_id),
+            attestation: self
+                .sandbox_attestations
+                .read()
+                .unwrap()
+                .get(&sandbox_id)
+                .and_then(|entry| entry.attestation.as_ref())
+                .map(AttestationMeasurements::from),
+            ..Default::default()
+        };
+        let secrets: Vec<CheckpointedSecret> = self
+            .secret_leases
+            .read()
+            .unwrap()
+            .get(&sandbox_id)
+            .into_iter()
+            .flatten()
+            .filter(|mounted| !matches!(mounted.mount.source, SecretSource::Inline { .. }))
+            .map(MountedSecret::checkpointed)
+            .collect();
+        if !secrets.is_empty() {
+            config.metadata.insert(
+                CheckpointedSecret::METADATA_KEY.to_string(),
+                serde_json::to_string(&secrets)
+                    .map_err(|e| CretoError::SerializationError(e.to_string()))?,
+            );
+        }
+
+        tracing::info!(
+            sandbox_id = %sandbox_id,
+            "Creating checkpoint for sandbox"
+        );
+
+        let checkpoint_id = self
+            .checkpoint_manager
+            .checkpoint(sandbox_id, config)
+            .await?;
+
+        self.revoke_secret_leases(sandbox_id).await;
+
+        tracing::info!(
+            sandbox_id = %sandbox_id,
+            checkpoint_id = %checkpoint_id,
+            "Checkpoint created successfully"
+        );
+
+        Ok(checkpoint_id)
+    }
+
+    /// Restore a sandbox from a checkpoint.
+    ///
+    /// Creates a new sandbox (or reuses an existing one) and restores it to the
+    /// state captured in the checkpoint, under the organization and with the
+    /// configuration the checkpoint recorded. Checkpoints of sandboxes this
+    /// service did not track record neither; restore those with
+    /// [`restore_with_config`](Self::restore_with_config).
+    pub async fn restore(&self, checkpoint_id: CheckpointId) -> CretoResult<Sandbox> {
+        let checkpoint = self
+            .checkpoint_manager
+            .get_checkpoint(checkpoint_id)
+            .await?;
+        let (Some(organization_id), Some(config)) =
+            (checkpoint.organization_id, checkpoint.sandbox_config)
+        else {
+            return Err(CheckpointError::RestoreFailed {
+                checkpoint_id,
+                reason: "Checkpoint does not record the sandbox's organization and \
+                         configuration"
+                    .to_string(),
+            }
+            .into());
+        };
+        self.restore_with_config(checkpoint_id, organization_id, config)
+            .await
+    }
+
+    /// Restore a sandbox from a checkpoint with the configuration it ran with.
+    ///
+    /// The sandbox is rebuilt paused. If the checkpoint recorded attestation
+    /// hashes, it is attested afresh and only leaves the paused state if the
+    /// fresh hashes match; otherwise the restore fails with
+    /// [`CheckpointError::AttestationMismatch`] naming the hash that
+    /// diverged. The configuration hash may differ when the sandbox's
+    /// attestation policy allows config drift on restore.
+    pub async fn restore_with_config(
+        &self,
+        checkpoint_id: CheckpointId,
+        organization_id: OrganizationId,
+        config: SandboxConfig,
+    ) -> CretoResult<Sandbox> {
+        tracing::info!(
+            checkpoint_id = %checkpoint_id,
+            "Restoring sandbox from checkpoint"
+        );
+
+        let sandbox_id = self.checkpoint_manager.restore(checkpoint_id).await?;
+
+        // TODO: Actually create/retrieve the sandbox instance
+        // For now, create a mock sandbox
+        let checkpoint = self
+            .checkpoint_manager
+            .get_checkpoint(checkpoint_id)
+            .await?;
+
+        let mut sandbox = Sandbox::new(organization_id, checkpoint.agent_id, config);
+        sandbox.id = sandbox_id;
+        sandbox.state = SandboxState::Paused;
+        if let Some(recorded) = &checkpoint.attestation {
+            self.reattest_restored(checkpoint_id, recorded, &mut sandbox)
+                .await?;
+        }
+        sandbox.state = SandboxState::Ready;
+        self.register_attestation(&sandbox);
+
+        if let Some(secrets) = checkpoint.metadata.get(CheckpointedSecret::METADATA_KEY) {
+            let secrets: Vec<CheckpointedSecret> = serde_json::from_str(secrets)
+                .map_err(|e| CretoError::SerializationError(e.to_string()))?;
+            self.restore_secret_mounts(sandbox_id, secrets).await?;
+        }
+
+        tracing::info!(
+            checkpoint_id = %checkpoint_id,
+            sandbox_id = %sandbox_id,
+            "Sandbox restored successfully"
+        );
+
+        Ok(sandbox)
+    }
+
+    /// Rebuild a checkpointed sandbox through the warm pool.
+    ///
+    /// [`WarmPool::restore_from_checkpoint`] only knows pooled sandboxes;
+    /// this also fails with [`CheckpointError::SandboxStillExists`] while
+    /// the checkpointed sandbox is live outside the pool, whether the
+    /// backend still holds it or its repository record is not terminated.
+    pub async fn restore_to_pool(
+        &self,
+        checkpoint_id: CheckpointId,
+        config: RestoreConfig,
+    ) -> Result<RestoreOutcome, CheckpointError> {
+        let store = self
+            .pool
+            .checkpoint_store()
+            .ok_or_else(|| CheckpointError::StorageError {
+                message: "No checkpoint store configured".to_string(),
+            })?;
+        let restore_failed = |e: CretoError| CheckpointError::RestoreFailed {
+            checkpoint_id,
+            reason: e.to_string(),
+        };
+        let checkpoint = store
+            .get_checkpoint(checkpoint_id)
+            .await
+            .map_err(restore_failed)?;
+
+        if self
+            .is_live(checkpoint.sandbox_id)
+            .await
+            .map_err(restore_failed)?
+        {
+            return Err(CheckpointError::SandboxStillExists {
+                checkpoint_id,
+                sandbox_id: checkpoint.sandbox_id,
+            });
+        }
+        self.pool
+            .restore_from_checkpoint(checkpoint_id, config)
+            .await
+    }
+
+    /// Whether a sandbox is still running, or recorded as not terminated.
+    async fn is_live(&self, sandbox_id: SandboxId) -> CretoResult<bool> {
+        if self
+            .runtime_handles
+            .read()
+            .unwrap()
+            .contains_key(&sandbox_id)
+        {
+            return Ok(true);
+        }
+        let Some(repository) = &self.sandbox_repository else {
+            return Ok(false);
+        };
+        Ok(matches!(
+            repository.get(sandbox_id).await?,
+            Some(record) if !matches!(record.state, SandboxState::Terminated { .. })
+        ))
+    }
+
+    /// Attest a restored sandbox afresh and check it against the hashes
+    /// recorded in its checkpoint.
+    async fn reattest_restored(
+        &self,
+        checkpoint_id: CheckpointId,
+        recorded: &AttestationMeasurements,
+        sandbox: &mut Sandbox,
+    ) -> CretoResult<()> {
+        self.attest(sandbox).await?;
+        let Some(fresh) = &sandbox.attestation else {
+            return Err(CheckpointError::RestoreFailed {
+                checkpoint_id,
+                reason: "Checkpoint was attested but no attestation generator is configured"
+                    .to_string(),
+            }
+            .into());
+        };
+
+        let allow_drift = sandbox.attestation_policy.allow_config_drift_on_restore;
+        if let Some(hash) = recorded.diverged(fresh, allow_drift) {
+            tracing::warn!(
+                checkpoint_id = %checkpoint_id,
+                sandbox_id = %sandbox.id,
+                hash = %hash,
+                "Restored sandbox attestation does not match its checkpoint"
+            );
+            return Err(CheckpointError::AttestationMismatch {
+                checkpoint_id,
+                hash,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// List checkpoints, optionally filtered by sandbox or agent.
+    pub async fn list_checkpoints(
+        &self,
+        sandbox_id: Option<SandboxId>,
+        agent_id: Option<AgentId>,
+    ) -> CretoResult<Vec<crate::checkpoint::Checkpoint>> {
+        self.checkpoint_manager
+            .list_checkpoints(sandbox_id, agent_id)
+            .await
+    }
+
+    /// Checkpoint store utilization, if the store tracks it.
+    pub fn checkpoint_store_stats(&self) -> Option<CheckpointStoreStats> {
+        self.checkpoint_manager.store_stats()
+    }
+
+    /// Delete a checkpoint.
+    pub async fn delete_checkpoint(&self, checkpoint_id: CheckpointId) -> CretoResult<()> {
+        tracing::info!(
+            checkpoint_id = %checkpoint_id,
+            "Deleting checkpoint"
+        );
+
+        self.checkpoint_manager
+            .delete_checkpoint(checkpoint_id)
+            .await?;
+
+        tracing::info!(
+            checkpoint_id = %checkpoint_id,
+            "Checkpoint deleted successfully"
+        );
+
+        Ok(())
+    }
+}
+
+impl Default for RuntimeService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attestation::MockAttestationProvider;
+    use crate::audit::InMemoryAuditSink;
+    use crate::execution::ExecutionError;
+    use crate::network::{EgressDestination, EgressRule};
+    use crate::resources::{ResourceLimits, ResourceUsage};
+    use crate::secrets::{MockSecretProvider, SecretAccess, SecretMountTarget, SecretValue};
+    use crate::testing::{
+        InMemorySandboxRepository, RuntimeTestHarness, ScriptedExecutor, ScriptedProvisioner,
+    };
+    use std::sync::Arc;
+
+    fn allow(domain: &str) -> EgressRule {
+        EgressRule::new(
+            EgressDestination::DomainExact(domain.to_string()),
+            NetworkAction::Allow,
+        )
+    }
+
+    fn deny(domain: &str) -> EgressRule {
+        EgressRule::new(
+            EgressDestination::DomainExact(domain.to_string()),
+            NetworkAction::Deny,
+        )
+    }
+
+    fn template_config(additional: Vec<EgressRule>) -> SandboxConfig {
+        SandboxConfig {
+            network_policy_template: Some(NetworkPolicyTemplateRef {
+                name: "egress".to_string(),
+                additional_rules: additional,
+            }),
+            ..Default::default()
+        }
+    }
+
+    async fn service_with_template(service: RuntimeService) -> (RuntimeService, OrganizationId) {
+        let org_id = OrganizationId::new();
+        let mut policy = NetworkPolicy::new_default_deny();
+        policy.add_rule(allow("pypi.org"));
+        service
+            .create_network_policy_template(org_id, "egress", policy)
+            .await
+            .unwrap();
+        (service, org_id)
+    }
+
+    #[tokio::test]
+    async fn test_service_creation() {
+        let service = RuntimeService::new();
+        let stats = service.pool_stats().await;
+        assert_eq!(stats.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pool_maintenance_stops_on_shutdown() {
+        let service = Arc::new(RuntimeService::new());
+        let shutdown = ShutdownCoordinator::new();
+
+        let handle = service.spawn_pool_maintenance(&shutdown);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let report = shutdown.shutdown(Duration::from_secs(1)).await;
+        assert_eq!(
+            report.completed,
+            vec!["runtime.pool_maintenance".to_string()]
+        );
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_sandbox() {
+        let service = RuntimeService::new();
+
+        let sandbox = service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(sandbox.config.runtime, "python3.11");
+    }
+
+    #[tokio::test]
+    async fn test_gpu_sandbox_rejected_on_cpu_only_host() {
+        let gpu_config = || SandboxConfig {
+            limits: Some(ResourceLimits::default().with_gpus(1, 16 << 30)),
+            ..Default::default()
+        };
+
+        let service = RuntimeService::new();
+        let err = service
+            .create_sandbox(OrganizationId::new(), AgentId::new(), gpu_config())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::ValidationFailed(_)));
+
+        let service =
+            RuntimeService::new().with_host_capabilities(HostCapabilities::with_gpus(1, 16 << 30));
+        let sandbox = service
+            .create_sandbox(OrganizationId::new(), AgentId::new(), gpu_config())
+            .await
+            .unwrap();
+        assert!(service.sandbox_class(sandbox.id).is_gpu());
+    }
+
+    #[tokio::test]
+    async fn test_execute() {
+        let service = RuntimeService::new();
+
+        let sandbox = service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let result = service.execute(sandbox.id, "print('hello')").await.unwrap();
+        assert!(result.is_success());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_and_restore() {
+        let service = RuntimeService::new();
+        let (organization_id, agent_id) = (OrganizationId::new(), AgentId::new());
+
+        let sandbox = service
+            .create_sandbox(
+                organization_id,
+                agent_id,
+                SandboxConfig {
+                    runtime: "node20".to_string(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // Create checkpoint
+        let checkpoint_id = service.checkpoint(sandbox.id).await.unwrap();
+        assert!(!checkpoint_id.to_string().is_empty());
+
+        // Restore from checkpoint, as the sandbox it was taken of
+        let restored = service.restore(checkpoint_id).await.unwrap();
+        assert_eq!(restored.state, SandboxState::Ready);
+        assert_eq!(restored.organization_id, organization_id);
+        assert_eq!(restored.agent_id, agent_id);
+        assert_eq!(restored.config.runtime, "node20");
+
+        // Nothing recorded for an untracked sandbox, so nothing to guess
+        let untracked = service.checkpoint(SandboxId::new()).await.unwrap();
+        assert!(service.restore(untracked).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list_checkpoints() {
+        let service = RuntimeService::new();
+        let sandbox_id = SandboxId::new();
+
+        // Create multiple checkpoints
+        service.checkpoint(sandbox_id).await.unwrap();
+        service.checkpoint(sandbox_id).await.unwrap();
+
+        let checkpoints = service
+            .list_checkpoints(Some(sandbox_id), None)
+            .await
+            .unwrap();
+        assert_eq!(checkpoints.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_checkpoint() {
+        let service = RuntimeService::new();
+        let sandbox_id = SandboxId::new();
+
+        let checkpoint_id = service.checkpoint(sandbox_id).await.unwrap();
+
+        // Delete should succeed
+        service.delete_checkpoint(checkpoint_id).await.unwrap();
+
+        // Deleting again should fail
+        let result = service.delete_checkpoint(checkpoint_id).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_state_transitions() {
+        let service = RuntimeService::new();
+        let mut sandbox = Sandbox::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            SandboxConfig::default(),
+        );
+
+        // Can't checkpoint while Creating
+        assert!(!sandbox.state.can_checkpoint());
+
+        // Can checkpoint when Ready
+        sandbox.mark_ready("handle".to_string());
+        assert!(sandbox.state.can_checkpoint());
+
+        // Can't checkpoint when Running
+        sandbox.mark_running();
+        assert!(!sandbox.state.can_checkpoint());
+
+        // Can't checkpoint when Failed
+        sandbox.mark_failed();
+        assert!(!sandbox.state.can_checkpoint());
+
+        // Can't checkpoint when Terminated
+        sandbox.mark_terminated();
+        assert!(!sandbox.state.can_checkpoint());
+    }
+
+    #[tokio::test]
+    async fn test_create_sandbox_from_template() {
+        let (service, org_id) = service_with_template(RuntimeService::new()).await;
+
+        let sandbox = service
+            .create_sandbox(
+                org_id,
+                AgentId::new(),
+                template_config(vec![allow("api.github.com"), allow("pypi.org")]),
+            )
+            .await
+            .unwrap();
+
+        let effective = sandbox.effective_network_policy.as_ref().unwrap();
+        assert_eq!(effective.template_name, "egress");
+        assert_eq!(effective.template_version, 1);
+        assert_eq!(effective.policy.egress_rules.len(), 2);
+
+        let decision = service
+            .check_egress_domain(sandbox.id, "api.github.com")
+            .unwrap();
+        assert!(decision.is_allowed());
+        let decision = service.check_egress_domain(sandbox.id, "evil.com").unwrap();
+        assert!(!decision.is_allowed());
+
+        // Sandboxes without a template have no managed policy
+        let plain = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        assert!(plain.effective_network_policy.is_none());
+        assert!(service.check_egress_domain(plain.id, "pypi.org").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_sandbox_unknown_template() {
+        let service = RuntimeService::new();
+
+        let result = service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                template_config(vec![]),
+            )
+            .await;
+        assert!(matches!(result, Err(CretoError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_template_deny_wins_over_additions() {
+        let (service, org_id) = service_with_template(RuntimeService::new()).await;
+
+        let sandbox = service
+            .create_sandbox(
+                org_id,
+                AgentId::new(),
+                template_config(vec![deny("pypi.org")]),
+            )
+            .await
+            .unwrap();
+
+        let decision = service.check_egress_domain(sandbox.id, "pypi.org").unwrap();
+        assert_eq!(decision.action, NetworkAction::Deny);
+    }
+
+    #[tokio::test]
+    async fn test_template_update_without_propagation() {
+        let (service, org_id) = service_with_template(RuntimeService::new()).await;
+
+        let existing = service
+            .create_sandbox(org_id, AgentId::new(), template_config(vec![]))
+            .await
+            .unwrap();
+
+        let updated = service
+            .update_network_policy_template(org_id, "egress", NetworkPolicy::new_default_deny())
+            .await
+            .unwrap();
+        assert_eq!(updated.version, 2);
+
+        // Running sandbox keeps the version it was created with
+        let effective = service.effective_network_policy(existing.id).unwrap();
+        assert_eq!(effective.template_version, 1);
+        assert!(service
+            .check_egress_domain(existing.id, "pypi.org")
+            .unwrap()
+            .is_allowed());
+
+        // New sandboxes get the new version
+        let fresh = service
+            .create_sandbox(org_id, AgentId::new(), template_config(vec![]))
+            .await
+            .unwrap();
+        assert_eq!(fresh.effective_network_policy.unwrap().template_version, 2);
+        assert!(!service
+            .check_egress_domain(fresh.id, "pypi.org")
+            .unwrap()
+            .is_allowed());
+    }
+
+    #[tokio::test]
+    async fn test_template_update_with_propagation() {
+        let repository = InMemorySandboxRepository::new();
+        let (service, org_id) = service_with_template(
+            RuntimeService::new()
+                .with_template_propagation(true)
+                .with_sandbox_repository(Box::new(repository.clone())),
+        )
+        .await;
+
+        let sandbox = service
+            .create_sandbox(
+                org_id,
+                AgentId::new(),
+                template_config(vec![allow("api.github.com")]),
+            )
+            .await
+            .unwrap();
+
+        let mut policy = NetworkPolicy::new_default_deny();
+        policy.add_rule(deny("api.github.com"));
+        service
+            .update_network_policy_template(org_id, "egress", policy)
+            .await
+            .unwrap();
+
+        let effective = service.effective_network_policy(sandbox.id).unwrap();
+        assert_eq!(effective.template_version, 2);
+        assert_eq!(effective.additional_rules, vec![allow("api.github.com")]);
+        assert!(!service
+            .check_egress_domain(sandbox.id, "api.github.com")
+            .unwrap()
+            .is_allowed());
+        assert!(!service
+            .check_egress_domain(sandbox.id, "pypi.org")
+            .unwrap()
+            .is_allowed());
+
+        let record = repository.get(sandbox.id).await.unwrap().unwrap();
+        assert_eq!(record.effective_network_policy.unwrap().template_version, 2);
+    }
+
+    #[tokio::test]
+    async fn test_effective_policy_persisted_with_sandbox() {
+        use crate::pool::RuntimePoolConfig;
+
+        let repository = InMemorySandboxRepository::new();
+        let (service, org_id) = service_with_template(
+            RuntimeService::with_pool_config(PoolConfig {
+                runtimes: vec![RuntimePoolConfig {
+                    name: "python3.11".to_string(),
+                    min_warm: 1,
+                    max_warm: 1,
+                }],
+                ..Default::default()
+            })
+            .with_sandbox_backend(Box::new(crate::testing::MockSandboxBackend::new()))
+            .with_sandbox_repository(Box::new(repository.clone())),
+        )
+        .await;
+
+        let sandbox = service
+            .create_sandbox(
+                org_id,
+                AgentId::new(),
+                template_config(vec![allow("api.github.com")]),
+            )
+            .await
+            .unwrap();
+
+        let record = repository.get(sandbox.id).await.unwrap().unwrap();
+        assert_eq!(record.network_policy, "restricted");
+        let snapshot = record.effective_network_policy.unwrap();
+        assert_eq!(snapshot.template_name, "egress");
+        assert_eq!(snapshot.template_version, 1);
+        assert_eq!(snapshot.additional_rules, vec![allow("api.github.com")]);
+        assert_eq!(
+            snapshot.hash(),
+            sandbox.effective_network_policy.unwrap().hash()
+        );
+
+        // A sandbox checked out of the warm pool is handed over in the repository too
+        assert_eq!(service.replenish_pool().await.provisioned, 1);
+        let agent_id = AgentId::new();
+        let pooled = service
+            .create_sandbox(org_id, agent_id, template_config(vec![allow("pypi.org")]))
+            .await
+            .unwrap();
+        assert!(pooled.provisioning.as_ref().unwrap().from_pool);
+
+        let record = repository.get(pooled.id).await.unwrap().unwrap();
+        assert_eq!(record.organization_id, org_id);
+        assert_eq!(record.agent_id, agent_id);
+        let snapshot = record.effective_network_policy.unwrap();
+        assert_eq!(snapshot.additional_rules, vec![allow("pypi.org")]);
+        assert_eq!(
+            snapshot.hash(),
+            pooled.effective_network_policy.unwrap().hash()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restore_to_pool_refuses_live_sandbox() {
+        use crate::checkpoint::CheckpointConfig;
+
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let repository = InMemorySandboxRepository::new();
+        let service = RuntimeService::new()
+            .with_sandbox_backend(Box::new(crate::testing::MockSandboxBackend::new()))
+            .with_sandbox_repository(Box::new(repository.clone()))
+            .with_pool_checkpoint_store(store.clone());
+
+        let sandbox = service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        assert!(!sandbox.provisioning.as_ref().unwrap().from_pool);
+        let checkpoint_id = store
+            .checkpoint(sandbox.id, CheckpointConfig::default())
+            .await
+            .unwrap();
+        let restore_config = || RestoreConfig {
+            organization_id: sandbox.organization_id,
+            sandbox: sandbox.config.clone(),
+            park: true,
+        };
+
+        // Not pooled, but still running
+        let err = service
+            .restore_to_pool(checkpoint_id, restore_config())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CheckpointError::SandboxStillExists { sandbox_id, .. } if sandbox_id == sandbox.id
+        ));
+
+        service.terminate_sandbox(sandbox.id).await.unwrap();
+        let outcome = service
+            .restore_to_pool(checkpoint_id, restore_config())
+            .await
+            .unwrap();
+        assert!(matches!(outcome, RestoreOutcome::Parked(id) if id == sandbox.id));
+    }
+
+    #[tokio::test]
+    async fn test_network_policy_template_crud() {
+        let (service, org_id) = service_with_template(RuntimeService::new()).await;
+
+        assert!(service
+            .create_network_policy_template(org_id, "egress", NetworkPolicy::default())
+            .await
+            .is_err());
+        assert_eq!(
+            service
+                .list_network_policy_templates(org_id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        service
+            .delete_network_policy_template(org_id, "egress")
+            .await
+            .unwrap();
+        assert!(service
+            .get_network_policy_template(org_id, "egress")
+            .await
+            .is_err());
+    }
+
+    /// Access monitor that reports a fixed set of reads for every sandbox.
+    struct FixedAccessMonitor {
+        accessed: Vec<String>,
+    }
+
+    #[async_trait::async_trait]
+    impl SecretAccessMonitor for FixedAccessMonitor {
+        async fn watch(&self, _sandbox_id: SandboxId, _mount: &SecretMount) -> CretoResult<bool> {
+            Ok(true)
+        }
+
+        async fn first_accesses(&self, _sandbox_id: SandboxId) -> CretoResult<Vec<SecretAccess>> {
+            Ok(self
+                .accessed
+                .iter()
+                .map(|name| SecretAccess {
+                    secret_name: name.clone(),
+                    accessed_at: Utc::now(),
+                })
+                .collect())
+        }
+    }
+
+    fn org_secret(name: &str) -> SecretSource {
+        SecretSource::OrganizationSecret {
+            name: name.to_string(),
+        }
+    }
+
+    fn secrets_service(audit: &InMemoryAuditSink) -> RuntimeService {
+        let mut provider = MockSecretProvider::new();
+        provider.add_secret("openai_key", SecretValue::text("sk-test"));
+        provider.add_secret("db_password", SecretValue::text("pg-test"));
+        RuntimeService::new()
+            .with_secret_provider(Box::new(provider))
+            .with_audit_sink(Box::new(audit.clone()))
+    }
+
+    /// Service leasing secrets from `provider`, with a 60s lease TTL
+    /// renewed in its last 20s.
+    fn leasing_service(
+        provider: &MockSecretProvider,
+        executor: &ScriptedExecutor,
+        clock: &Arc<creto_common::TestClock>,
+    ) -> Arc<RuntimeService> {
+        Arc::new(
+            RuntimeService::new()
+                .with_executor(Box::new(executor.clone()))
+                .with_secret_provider(Box::new(provider.clone()))
+                .with_clock(clock.clone())
+                .with_secret_leases(SecretLeaseConfig {
+                    ttl: Duration::from_secs(60),
+                    renew_before: Duration::from_secs(20),
+                    ..Default::default()
+                }),
+        )
+    }
+
+    fn secrets_provider() -> MockSecretProvider {
+        let mut provider = MockSecretProvider::new();
+        provider.add_secret("openai_key", SecretValue::text("sk-test"));
+        provider
+    }
+
+    /// Start an execution with the OpenAI key mounted, held until the
+    /// returned sender is dropped.
+    async fn held_secret_execution(
+        service: &Arc<RuntimeService>,
+        executor: &ScriptedExecutor,
+        sandbox: &Sandbox,
+    ) -> (
+        oneshot::Sender<()>,
+        JoinHandle<CretoResult<ExecutionResult>>,
+    ) {
+        let release = executor.push_hold();
+        let task = tokio::spawn({
+            let service = Arc::clone(service);
+            let (sandbox_id, org_id, agent_id) =
+                (sandbox.id, sandbox.organization_id, sandbox.agent_id);
+            async move {
+                service
+                    .execute_with_secrets(
+                        sandbox_id,
+                        org_id,
+                        agent_id,
+                        "call_api()",
+                        vec![SecretMount::env_var(
+                            "OPENAI_API_KEY",
+                            org_secret("openai_key"),
+                        )],
+                    )
+                    .await
+            }
+        });
+        started(executor, 1).await;
+        (release, task)
+    }
+
+    #[tokio::test]
+    async fn test_audit_events_never_contain_secret_material() {
+        let audit = InMemoryAuditSink::new();
+        let service = secrets_service(&audit);
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = service
+            .create_sandbox(org_id, agent_id, SandboxConfig::default())
+            .await
+            .unwrap();
+
+        service
+            .execute_with_secrets(
+                sandbox.id,
+                org_id,
+                agent_id,
+                "print(os.environ['OPENAI_API_KEY'])",
+                vec![
+                    SecretMount::env_var("OPENAI_API_KEY", org_secret("openai_key")),
+                    SecretMount::file("db", "/run/secrets/db", org_secret("db_password")),
+                    SecretMount::env_var(
+                        "WEBHOOK_TOKEN",
+                        SecretSource::Inline {
+                            value: "inline-token-value".to_string(),
+                        },
+                    ),
+                ],
+            )
+            .await
+            .unwrap();
+        service.terminate_sandbox(sandbox.id).await.unwrap();
+
+        let actions = audit.actions();
+        for action in [
+            "runtime.secret.leased",
+            "runtime.secret.granted",
+            "runtime.secret.lease_revoked",
+            "runtime.secret.revoked",
+            "runtime.execution.finished",
+        ] {
+            assert!(actions.iter().any(|a| a == action), "missing {action}");
+        }
+        let leased = audit
+            .events()
+            .into_iter()
+            .find(|e| e.action == "runtime.secret.leased")
+            .unwrap();
+        assert_eq!(leased.detail["secret_ref"], "org:openai_key");
+
+        for event in audit.events() {
+            let json = serde_json::to_string(&event).unwrap();
+            for material in ["sk-test", "pg-test", "inline-token-value", "os.environ"] {
+                assert!(
+                    !json.contains(material),
+                    "{} leaks {material}: {json}",
+                    event.action
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_egress_denials_audited_and_rate_limited() {
+        use crate::audit::{AuditRateLimit, AuditRateLimitConfig, RuntimeAuditKind};
+
+        let audit = InMemoryAuditSink::new();
+        let (service, org_id) = service_with_template(
+            RuntimeService::new()
+                .with_audit_sink(Box::new(audit.clone()))
+                .with_audit_rate_limits(AuditRateLimitConfig::default().with_limit(
+                    RuntimeAuditKind::EgressDenied,
+                    AuditRateLimit::new(2, chrono::Duration::minutes(1)),
+                )),
+        )
+        .await;
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), template_config(vec![]))
+            .await
+            .unwrap();
+
+        assert!(service
+            .check_egress(sandbox.id, "pypi.org")
+            .await
+            .unwrap()
+            .is_allowed());
+        for _ in 0..5 {
+            let decision = service.check_egress(sandbox.id, "evil.com").await.unwrap();
+            assert!(!decision.is_allowed());
+        }
+
+        let denials: Vec<_> = audit
+            .events()
+            .into_iter()
+            .filter(|e| e.action == "runtime.egress.denied")
+            .collect();
+        assert_eq!(denials.len(), 2);
+        assert_eq!(denials[0].outcome, creto_common::AuditOutcome::Denied);
+        assert_eq!(denials[0].organization_id, org_id);
+        assert_eq!(denials[0].detail["destination"], "evil.com");
+    }
+
+    #[tokio::test]
+    async fn test_secret_leases_renewed_then_revoked_on_terminate() {
+        let provider = secrets_provider();
+        let executor = ScriptedExecutor::new();
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let service = leasing_service(&provider, &executor, &clock);
+        let sandbox = service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let (release, task) = held_secret_execution(&service, &executor, &sandbox).await;
+        let leases = provider.active_leases();
+        assert_eq!(leases.len(), 1);
+
+        clock.advance(chrono::Duration::seconds(30));
+        assert_eq!(service.renew_secret_leases().await.renewed, 0);
+        clock.advance(chrono::Duration::seconds(15));
+        assert_eq!(service.renew_secret_leases().await.renewed, 1);
+        assert_eq!(provider.leases()[&leases[0]].renewals, 1);
+
+        // Renewed to 45s + 60s, so still valid past the original TTL
+        clock.advance(chrono::Duration::seconds(30));
+        let report = service.renew_secret_leases().await;
+        assert!(report.expired.is_empty());
+
+        service.terminate_sandbox(sandbox.id).await.unwrap();
+        assert!(provider.leases()[&leases[0]].revoked);
+        assert!(provider.active_leases().is_empty());
+        drop(release);
+        task.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_secret_lease_fails_next_execute() {
+        let provider = secrets_provider();
+        let executor = ScriptedExecutor::new();
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let service = leasing_service(&provider, &executor, &clock);
+        let sandbox = service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let (release, task) = held_secret_execution(&service, &executor, &sandbox).await;
+        let lease_id = provider.active_leases()[0];
+        provider.expire(lease_id);
+
+        // Renewal fails at 40s; the lease runs out at 60s
+        clock.advance(chrono::Duration::seconds(40));
+        assert_eq!(
+            service.renew_secret_leases().await,
+            SecretLeaseReport::default()
+        );
+        clock.advance(chrono::Duration::seconds(20));
+        let report = service.renew_secret_leases().await;
+        assert_eq!(
+            report.expired,
+            vec![(sandbox.id, "OPENAI_API_KEY".to_string())]
+        );
+
+        let result = service
+            .execute_request(
+                sandbox.organization_id,
+                ExecutionRequest::new(sandbox.id, "call_api()"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        assert_eq!(result.error.unwrap().code, "SECRET_LEASE_EXPIRED");
+        assert_eq!(executor.requests().len(), 1);
+
+        // The mount goes with the execution that made it
+        drop(release);
+        task.await.unwrap().unwrap();
+        assert!(service
+            .execute_request(
+                sandbox.organization_id,
+                ExecutionRequest::new(sandbox.id, "print(1)"),
+            )
+            .await
+            .unwrap()
+            .is_success());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_records_secret_refs_and_restore_leases_again() {
+        let provider = secrets_provider();
+        let executor = ScriptedExecutor::new();
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let service = leasing_service(&provider, &executor, &clock);
+        let sandbox = service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let (release, task) = held_secret_execution(&service, &executor, &sandbox).await;
+        let original = provider.active_leases()[0];
+        let checkpoint_id = service.checkpoint(sandbox.id).await.unwrap();
+        assert!(provider.leases()[&original].revoked);
+        drop(release);
+        task.await.unwrap().unwrap();
+
+        let checkpoint = service
+            .list_checkpoints(Some(sandbox.id), None)
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(checkpoint.id, checkpoint_id);
+        let serialized = serde_json::to_string(&checkpoint).unwrap();
+        assert!(!serialized.contains("sk-test"));
+        assert!(!checkpoint
+            .state_snapshot
+            .windows(b"sk-test".len())
+            .any(|w| w == b"sk-test"));
+        let recorded: Vec<CheckpointedSecret> =
+            serde_json::from_str(&checkpoint.metadata[CheckpointedSecret::METADATA_KEY]).unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].mount.name, "OPENAI_API_KEY");
+        assert_eq!(recorded[0].mount.source.reference(), "org:openai_key");
+
+        let restored = service.restore(checkpoint_id).await.unwrap();
+        assert_eq!(restored.id, sandbox.id);
+        let leases = provider.active_leases();
+        assert_eq!(leases.len(), 1);
+        assert_ne!(leases[0], original);
+        assert_eq!(
+            provider.leases()[&leases[0]].secret_reference,
+            "org:openai_key"
+        );
+
+        service.terminate_sandbox(sandbox.id).await.unwrap();
+        assert!(provider.active_leases().is_empty());
+    }
+
+    fn count_events(audit: &InMemoryAuditSink) -> (usize, usize, usize) {
+        audit
+            .actions()
+            .iter()
+            .fold((0, 0, 0), |(g, a, r), action| match action.as_str() {
+                "runtime.secret.granted" => (g + 1, a, r),
+                "runtime.secret.accessed" => (g, a + 1, r),
+                "runtime.secret.revoked" => (g, a, r + 1),
+                _ => (g, a, r),
+            })
+    }
+
+    #[tokio::test]
+    async fn test_secret_grants_recorded_and_revoked_once() {
+        let audit = InMemoryAuditSink::new();
+        let service = secrets_service(&audit);
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = service
+            .create_sandbox(org_id, agent_id, SandboxConfig::default())
+            .await
+            .unwrap();
+
+        service
+            .execute_with_secrets(
+                sandbox.id,
+                org_id,
+                agent_id,
+                "print('hello')",
+                vec![
+                    SecretMount::env_var("OPENAI_API_KEY", org_secret("openai_key")),
+                    SecretMount::file("db", "/run/secrets/db", org_secret("db_password")),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let grants = service.secret_grants(sandbox.id).await.unwrap();
+        assert_eq!(grants.len(), 2);
+        assert!(grants.iter().all(|g| g.revoked_at.is_some()));
+        assert_eq!(grants[0].secret_reference, "org:openai_key");
+        assert_eq!(grants[1].target, SecretMountTarget::File);
+        assert_eq!(count_events(&audit), (2, 0, 2));
+
+        // Terminating afterwards must not revoke again
+        service.terminate_sandbox(sandbox.id).await.unwrap();
+        assert_eq!(service.secret_grants(sandbox.id).await.unwrap(), grants);
+        assert_eq!(count_events(&audit), (2, 0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_secret_grants_revoked_when_mount_fails() {
+        let audit = InMemoryAuditSink::new();
+        let service = secrets_service(&audit);
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox_id = SandboxId::new();
+
+        let result = service
+            .execute_with_secrets(
+                sandbox_id,
+                org_id,
+                agent_id,
+                "print('hello')",
+                vec![
+                    SecretMount::env_var("OPENAI_API_KEY", org_secret("openai_key")),
+                    SecretMount::env_var("MISSING", org_secret("does_not_exist")),
+                ],
+            )
+            .await;
+        assert!(result.is_err());
+
+        // The secret mounted before the failure is revoked; the failed one never granted
+        let grants = service.secret_grants(sandbox_id).await.unwrap();
+        assert_eq!(grants.len(), 1);
+        assert!(grants[0].revoked_at.is_some());
+        assert_eq!(count_events(&audit), (1, 0, 1));
+
+        service.terminate_sandbox(sandbox_id).await.unwrap();
+        assert_eq!(count_events(&audit), (1, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_mounted_secrets_redacted_before_logs_are_stored() {
+        let secret = "sk-live-4f9a2c7e1b";
+        let encoded = "c2stbGl2ZS00ZjlhMmM3ZTFi";
+        let mut provider = MockSecretProvider::new();
+        provider.add_secret("openai_key", SecretValue::text(secret));
+
+        let executor = ScriptedExecutor::new();
+        let mut result = ExecutionResult::success(
+            Uuid::nil(),
+            serde_json::Value::Null,
+            crate::execution::ExecutionTiming::new(),
+        );
+        result.stdout = Some(format!(
+            "key={secret}\nb64={encoded}\n::creto-log::{{\"level\":\"info\",\"message\":\"using {secret}\"}}\n"
+        ));
+        executor.push_result(result);
+
+        let service = RuntimeService::new()
+            .with_executor(Box::new(executor))
+            .with_secret_provider(Box::new(provider));
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = service
+            .create_sandbox(org_id, agent_id, SandboxConfig::default())
+            .await
+            .unwrap();
+
+        let result = service
+            .execute_with_secrets(
+                sandbox.id,
+                org_id,
+                agent_id,
+                "print(os.environ['OPENAI_API_KEY'])",
+                vec![SecretMount::env_var(
+                    "OPENAI_API_KEY",
+                    org_secret("openai_key"),
+                )],
+            )
+            .await
+            .unwrap();
+
+        let stdout = result.stdout.as_deref().unwrap();
+        assert_eq!(
+            stdout,
+            "key=[REDACTED:secret:OPENAI_API_KEY]\nb64=[REDACTED:secret:OPENAI_API_KEY]\n\
+             ::creto-log::{\"level\":\"info\",\"message\":\"using [REDACTED:secret:OPENAI_API_KEY]\"}\n"
+        );
+        assert_eq!(result.redactions.total, 4);
+        assert_eq!(result.redactions.by_rule["secret:OPENAI_API_KEY"], 4);
+
+        let logs = service
+            .execution_logs(result.request_id, None, LogPage::default())
+            .await
+            .unwrap();
+        assert_eq!(logs[0].message, "using [REDACTED:secret:OPENAI_API_KEY]");
+
+        // Fingerprints are dropped with the grants
+        assert!(service.secret_fingerprints.read().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_secret_escaped_in_structured_log_is_redacted() {
+        let secret = r#"pa"ss\word-9"#;
+        let mut provider = MockSecretProvider::new();
+        provider.add_secret("db_password", SecretValue::text(secret));
+
+        let executor = ScriptedExecutor::new();
+        let mut result = ExecutionResult::success(
+            Uuid::nil(),
+            serde_json::Value::Null,
+            crate::execution::ExecutionTiming::new(),
+        );
+        result.stdout = Some(
+            r#"::creto-log::{"level":"info","message":"login","fields":{"password":"pa\"ss\\word-9"}}"#
+                .to_string(),
+        );
+        executor.push_result(result);
+
+        let service = RuntimeService::new()
+            .with_executor(Box::new(executor))
+            .with_secret_provider(Box::new(provider));
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = service
+            .create_sandbox(org_id, agent_id, SandboxConfig::default())
+            .await
+            .unwrap();
+
+        let result = service
+            .execute_with_secrets(
+                sandbox.id,
+                org_id,
+                agent_id,
+                "log(password=os.environ['DB_PASSWORD'])",
+                vec![SecretMount::env_var(
+                    "DB_PASSWORD",
+                    org_secret("db_password"),
+                )],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            result.stdout.as_deref().unwrap(),
+            r#"::creto-log::{"level":"info","message":"login","fields":{"password":"[REDACTED:secret:DB_PASSWORD]"}}"#
+        );
+        let logs = service
+            .execution_logs(result.request_id, None, LogPage::default())
+            .await
+            .unwrap();
+        assert_eq!(logs[0].fields["password"], "[REDACTED:secret:DB_PASSWORD]");
+        assert_eq!(result.redactions.by_rule["secret:DB_PASSWORD"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_terminate_revokes_in_flight_grants() {
+        let audit = InMemoryAuditSink::new();
+        let store = InMemorySecretGrantStore::new();
+        let service = RuntimeService::new()
+            .with_secret_grant_store(Box::new(store.clone()))
+            .with_audit_sink(Box::new(audit.clone()));
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = service
+            .create_sandbox(org_id, agent_id, SandboxConfig::default())
+            .await
+            .unwrap();
+
+        // Grant held by an execution that is still running (or crashed)
+        let mount = SecretMount::env_var("OPENAI_API_KEY", org_secret("openai_key"));
+        let grant = SecretGrant::new(org_id, sandbox.id, agent_id, &mount);
+        store.record_grant(&grant).await.unwrap();
+
+        service.terminate_sandbox(sandbox.id).await.unwrap();
+        service.terminate_sandbox(sandbox.id).await.unwrap();
+
+        let grants = service.secret_grants(sandbox.id).await.unwrap();
+        assert!(grants[0].revoked_at.is_some());
+        assert_eq!(count_events(&audit), (0, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn test_secret_access_detection_and_report() {
+        let audit = InMemoryAuditSink::new();
+        let service =
+            secrets_service(&audit).with_secret_access_monitor(Box::new(FixedAccessMonitor {
+                accessed: vec!["OPENAI_API_KEY".to_string(), "db".to_string()],
+            }));
+        let org_id = OrganizationId::new();
+        let (alice, bob) = (AgentId::new(), AgentId::new());
+        let start = Utc::now() - chrono::Duration::minutes(1);
+
+        for agent_id in [alice, bob] {
+            let sandbox = service
+                .create_sandbox(org_id, agent_id, SandboxConfig::default())
+                .await
+                .unwrap();
+            let mut mounts = vec![
+                SecretMount::env_var("OPENAI_API_KEY", org_secret("openai_key"))
+                    .with_access_detection(),
+            ];
+            if agent_id == alice {
+                // Read reported, but detection was not requested
+                mounts.push(SecretMount::file(
+                    "db",
+                    "/run/secrets/db",
+                    org_secret("db_password"),
+                ));
+            }
+            service
+                .execute_with_secrets(sandbox.id, org_id, agent_id, "print('hi')", mounts)
+                .await
+                .unwrap();
+        }
+        assert_eq!(count_events(&audit), (3, 2, 3));
+
+        let report = service
+            .secret_access_report(org_id, start, Utc::now() + chrono::Duration::minutes(1))
+            .await
+            .unwrap();
+        assert_eq!(report.by_secret.len(), 2);
+        assert_eq!(report.by_secret[0].secret_reference, "org:db_password");
+        assert_eq!(report.by_secret[0].accessed_count, 0);
+        let openai = &report.by_secret[1];
+        assert_eq!(openai.grant_count, 2);
+        assert_eq!(openai.accessed_count, 2);
+        assert_eq!(openai.sandbox_count, 2);
+        assert_eq!(openai.agents.len(), 2);
+        assert_eq!(report.by_agent[0].agent_id, alice);
+        assert_eq!(report.by_agent[0].secrets.len(), 2);
+
+        assert!(service
+            .secret_access_report(OrganizationId::new(), start, Utc::now())
+            .await
+            .unwrap()
+            .by_secret
+            .is_empty());
+        assert!(service
+            .secret_access_report(org_id, Utc::now(), start)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_org_runtime_policy_applied_on_create() {
+        use crate::attestation::AttestationPolicy;
+        use crate::org_policy::PinnedField;
+        use crate::resources::ResourceLimits;
+
+        let harness = RuntimeTestHarness::new();
+        let org_id = OrganizationId::new();
+        let policy = harness
+            .service
+            .set_org_runtime_policy(
+                OrgRuntimePolicy::new(org_id)
+                    .with_limits(ResourceLimits::minimal())
+                    .with_attestation_policy(AttestationPolicy::production())
+                    .pin(PinnedField::AttestationPolicy),
+            )
+            .await
+            .unwrap();
+        assert_eq!(policy.version, 1);
+
+        // Defaults fill what the request left unset
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(sandbox.config.limits, Some(ResourceLimits::minimal()));
+        assert!(sandbox.attestation_policy.require_attestation);
+        assert_eq!(sandbox.runtime_policy_version, Some(1));
+        let record = harness.sandboxes.get(sandbox.id).await.unwrap().unwrap();
+        assert_eq!(record.runtime_policy_version, Some(1));
+
+        // Unpinned fields can be overridden
+        let generous = SandboxConfig {
+            limits: Some(ResourceLimits::generous()),
+            ..Default::default()
+        };
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), generous)
+            .await
+            .unwrap();
+        assert_eq!(sandbox.config.limits, Some(ResourceLimits::generous()));
+
+        // Pinned fields cannot be weakened
+        let unattested = SandboxConfig {
+            attestation_policy: Some(AttestationPolicy::default()),
+            ..Default::default()
+        };
+        let err = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), unattested)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CretoError::PolicyViolation { ref fields } if fields == &vec!["attestation_policy"]
+        ));
+
+        // Updates only reach sandboxes created afterwards
+        let first_id = record.id;
+        harness
+            .service
+            .set_org_runtime_policy(OrgRuntimePolicy::new(org_id))
+            .await
+            .unwrap();
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(sandbox.runtime_policy_version, Some(2));
+        assert_eq!(sandbox.config.limits, Some(ResourceLimits::default()));
+        let first = harness.sandboxes.get(first_id).await.unwrap().unwrap();
+        assert_eq!(first.runtime_policy_version, Some(1));
+
+        // Organizations without a policy are unaffected
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(sandbox.runtime_policy_version, None);
+    }
+
+    #[tokio::test]
+    async fn test_pool_checkout_keeps_pooled_config_under_policy() {
+        use crate::attestation::AttestationPolicy;
+        use crate::pool::RuntimePoolConfig;
+        use crate::resources::ResourceLimits;
+
+        let harness = RuntimeTestHarness::with_pool_config(PoolConfig {
+            runtimes: vec![RuntimePoolConfig {
+                name: "python3.11".to_string(),
+                min_warm: 1,
+                max_warm: 1,
+            }],
+            ..Default::default()
+        });
+        assert_eq!(harness.service.replenish_pool().await.provisioned, 1);
+        let org_id = OrganizationId::new();
+        harness
+            .service
+            .set_org_runtime_policy(
+                OrgRuntimePolicy::new(org_id)
+                    .with_limits(ResourceLimits::minimal())
+                    .with_attestation_policy(AttestationPolicy::production()),
+            )
+            .await
+            .unwrap();
+
+        // The policy's limits differ from the pooled sandbox's, so it stays put
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        assert!(!sandbox.provisioning.unwrap().from_pool);
+        assert_eq!(sandbox.config.limits, Some(ResourceLimits::minimal()));
+        assert_eq!(harness.service.pool_stats().await.ready, 1);
+
+        // Explicit default limits are honoured and match the pooled sandbox
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                org_id,
+                AgentId::new(),
+                SandboxConfig {
+                    limits: Some(ResourceLimits::default()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert!(sandbox.provisioning.unwrap().from_pool);
+        assert_eq!(sandbox.config.effective_limits(), ResourceLimits::default());
+        assert!(sandbox.attestation_policy.require_attestation);
+        assert_eq!(sandbox.runtime_policy_version, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_harness_create_execute_terminate() {
+        let harness = RuntimeTestHarness::new();
+        let org_id = OrganizationId::new();
+
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(sandbox.state, SandboxState::Ready);
+        assert_eq!(
+            sandbox.attestation.as_ref().unwrap().config_hash,
+            sandbox.config_hash()
+        );
+        let record = harness.sandboxes.get(sandbox.id).await.unwrap().unwrap();
+        assert_eq!(record.state, SandboxState::Ready);
+
+        let usage = ResourceUsage {
+            peak_memory_bytes: 1 << 20,
+            cpu_time_ms: 40,
+            ..Default::default()
+        };
+        let mut timing = crate::execution::ExecutionTiming::new();
+        timing.mark_started();
+        timing.mark_completed();
+        harness.executor.push_result(
+            ExecutionResult::success(uuid::Uuid::nil(), serde_json::json!({"ok": true}), timing)
+                .with_sampled_usage(crate::sampling::SampledUsage {
+                    usage: usage.clone(),
+                    samples: Vec::new(),
+                }),
+        );
+
+        let result = harness
+            .service
+            .execute(sandbox.id, "print('hello')")
+            .await
+            .unwrap();
+        assert_eq!(result.output, serde_json::json!({"ok": true}));
+
+        // The persisted execution ID is the one the executor saw
+        let executions = harness.executions.all();
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].id, result.request_id);
+        assert_eq!(executions[0].status, ExecutionStatus::Completed);
+        assert_eq!(harness.executor.requests()[0].id, result.request_id);
+        let recorded = harness.usage.get_latest(sandbox.id).await.unwrap().unwrap();
+        assert_eq!(recorded.peak_memory_bytes, usage.peak_memory_bytes);
+        assert_eq!(recorded.cpu_time_ms, usage.cpu_time_ms);
+
+        harness.service.terminate_sandbox(sandbox.id).await.unwrap();
+        assert!(harness.backend.live_handles().is_empty());
+        assert_eq!(harness.backend.terminated_handles().len(), 1);
+        assert!(harness
+            .sandboxes
+            .list_active_by_org(org_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execution_logs_captured_and_filtered() {
+        use crate::logs::STRUCTURED_LOG_PREFIX;
+
+        let harness = RuntimeTestHarness::new().map_service(|service| {
+            service.with_log_capture_config(LogCaptureConfig {
+                max_records_per_execution: 3,
+            })
+        });
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let line = |level: &str, message: &str| {
+            format!(
+                "{}{{\"level\":\"{}\",\"message\":\"{}\"}}",
+                STRUCTURED_LOG_PREFIX, level, message
+            )
+        };
+        let mut scripted = ExecutionResult::success(
+            Uuid::nil(),
+            serde_json::Value::Null,
+            crate::execution::ExecutionTiming::new(),
+        );
+        let scripted_stdout = Some(
+            [
+                "plain output".to_string(),
+                line("info", "starting"),
+                line("warn", "retrying"),
+                format!("{}{{broken", STRUCTURED_LOG_PREFIX),
+                line("error", "failed once"),
+                line("error", "failed twice"),
+            ]
+            .join("\n"),
+        );
+        scripted.stdout = scripted_stdout.clone();
+        harness.executor.push_result(scripted);
+
+        let result = harness.service.execute(sandbox.id, "run()").await.unwrap();
+        // Structured lines stay in the raw output
+        assert_eq!(result.stdout, scripted_stdout);
+        let summary = result.log_summary.as_ref().unwrap();
+        assert_eq!(summary.count(LogLevel::Error), 2);
+        assert_eq!(summary.total(), 4);
+        assert_eq!(summary.dropped, 1);
+        assert_eq!(summary.malformed, 1);
+
+        let problems = harness
+            .service
+            .execution_logs(result.request_id, Some(LogLevel::Warn), LogPage::default())
+            .await
+            .unwrap();
+        let messages: Vec<&str> = problems.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, vec!["retrying", "failed once"]);
+
+        let all = harness
+            .service
+            .execution_logs(result.request_id, None, LogPage::new(0, 10))
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 3);
+    }
+
+    /// Log store whose writes always fail.
+    struct FailingLogStore;
+
+    #[async_trait::async_trait]
+    impl ExecutionLogStore for FailingLogStore {
+        async fn append(&self, _execution_id: Uuid, _records: &[LogRecord]) -> CretoResult<()> {
+            Err(CretoError::Database("connection reset".to_string()))
+        }
+
+        async fn list(
+            &self,
+            _execution_id: Uuid,
+            _min_level: Option<LogLevel>,
+            _page: LogPage,
+        ) -> CretoResult<Vec<LogRecord>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_log_store_failure_does_not_fail_execution() {
+        let harness = RuntimeTestHarness::new()
+            .map_service(|service| service.with_execution_log_store(Box::new(FailingLogStore)));
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        let mut scripted = ExecutionResult::success(
+            Uuid::nil(),
+            serde_json::Value::Null,
+            crate::execution::ExecutionTiming::new(),
+        );
+        scripted.stdout = Some("::creto-log::{\"level\":\"info\",\"message\":\"hi\"}".to_string());
+        harness.executor.push_result(scripted);
+
+        let result = harness.service.execute(sandbox.id, "run()").await.unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.logs.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_backend_terminate_can_be_retried() {
+        let harness = RuntimeTestHarness::new();
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        harness
+            .backend
+            .fail_next_terminate(CretoError::Internal("backend unavailable".to_string()));
+        assert!(harness.service.terminate_sandbox(sandbox.id).await.is_err());
+        assert_eq!(harness.backend.live_handles().len(), 1);
+
+        harness.service.terminate_sandbox(sandbox.id).await.unwrap();
+        assert!(harness.backend.live_handles().is_empty());
+        assert_eq!(harness.backend.terminated_handles().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_harness_records_execution_failures() {
+        let harness = RuntimeTestHarness::new();
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        harness.executor.push_failure(ExecutionError::timeout(30));
+        harness
+            .executor
+            .push_error(CretoError::Internal("executor crashed".to_string()));
+
+        let timed_out = harness.service.execute(sandbox.id, "loop()").await.unwrap();
+        assert_eq!(timed_out.status, ExecutionStatus::Failed);
+        assert!(harness
+            .service
+            .execute(sandbox.id, "crash()")
+            .await
+            .is_err());
+
+        let executions = harness.executions.all();
+        assert_eq!(executions.len(), 2);
+        assert!(executions
+            .iter()
+            .all(|e| e.status == ExecutionStatus::Failed));
+        assert!(harness
+            .executions
+            .list_pending_by_sandbox(sandbox.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_execution_concurrent_duplicates() {
+        let harness = RuntimeTestHarness::new();
+        let org_id = OrganizationId::new();
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        let request =
+            || ExecutionRequest::new(sandbox.id, "charge(100)").with_idempotency_key("charge-1");
+
+        // The retry waits for the running execution; a different payload
+        // under the same key is refused
+        let release = harness.executor.push_hold();
+        let (first, retry, conflicting) = tokio::join!(
+            harness.service.execute_request(org_id, request()),
+            harness.service.execute_request(org_id, request()),
+            async {
+                let conflicting = ExecutionRequest::new(sandbox.id, "charge(200)")
+                    .with_idempotency_key("charge-1");
+                let result = harness.service.execute_request(org_id, conflicting).await;
+                release.send(()).unwrap();
+                result
+            }
+        );
+
+        let first = first.unwrap();
+        assert_eq!(retry.unwrap().request_id, first.request_id);
+        assert!(matches!(
+            conflicting,
+            Err(CretoError::IdempotencyConflict { execution_id, .. }) if execution_id != Uuid::nil()
+        ));
+        assert_eq!(harness.executor.requests().len(), 1);
+        assert_eq!(harness.executions.all().len(), 1);
+
+        // Done: later retries replay the result
+        let replayed = harness
+            .service
+            .execute_request(org_id, request())
+            .await
+            .unwrap();
+        assert_eq!(replayed.request_id, first.request_id);
+        assert_eq!(harness.executor.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_execution_expires_and_errors_release_key() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let harness = RuntimeTestHarness::new().map_service(|service| {
+            service
+                .with_clock(clock.clone())
+                .with_idempotency_config(IdempotencyConfig {
+                    ttl: Duration::from_secs(60),
+                    ..Default::default()
+                })
+        });
+        let org_id = OrganizationId::new();
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        let request =
+            || ExecutionRequest::new(sandbox.id, "send_email()").with_idempotency_key("email");
+
+        // An attempt without a result does not hold the key
+        harness
+            .executor
+            .push_error(CretoError::Internal("executor crashed".to_string()));
+        assert!(harness
+            .service
+            .execute_request(org_id, request())
+            .await
+            .is_err());
+
+        let first = harness
+            .service
+            .execute_request(org_id, request())
+            .await
+            .unwrap();
+        clock.advance(chrono::Duration::seconds(59));
+        let replayed = harness
+            .service
+            .execute_request(org_id, request())
+            .await
+            .unwrap();
+        assert_eq!(replayed.request_id, first.request_id);
+        assert_eq!(harness.executor.requests().len(), 2);
+
+        clock.advance(chrono::Duration::seconds(1));
+        let rerun = harness
+            .service
+            .execute_request(org_id, request())
+            .await
+            .unwrap();
+        assert_ne!(rerun.request_id, first.request_id);
+        assert_eq!(harness.executor.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_idempotent_execution_survives_restart() {
+        let harness = RuntimeTestHarness::new();
+        let org_id = OrganizationId::new();
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        let request = ExecutionRequest::new(sandbox.id, "pay()")
+            .with_input(serde_json::json!({"amount": 5}))
+            .with_idempotency_key("pay-1");
+        let first = harness
+            .service
+            .execute_request(org_id, request.clone())
+            .await
+            .unwrap();
+
+        // A fresh service over the same execution records
+        let restarted = RuntimeService::new()
+            .with_executor(Box::new(harness.executor.clone()))
+            .with_execution_repository(Box::new(harness.executions.clone()));
+        let replayed = restarted
+            .execute_request(org_id, request.clone())
+            .await
+            .unwrap();
+        assert_eq!(replayed.request_id, first.request_id);
+        assert_eq!(replayed.output, first.output);
+        assert_eq!(harness.executor.requests().len(), 1);
+
+        let conflicting = request.with_input(serde_json::json!({"amount": 50}));
+        assert!(matches!(
+            restarted.execute_request(org_id, conflicting).await,
+            Err(CretoError::IdempotencyConflict { execution_id, .. }) if execution_id == first.request_id
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_class_survives_restart() {
+        use crate::metering::InMemoryUsageEventSink;
+
+        let host = HostCapabilities::with_gpus(2, 80 << 30).with_gpu_kind("h100");
+        let harness =
+            RuntimeTestHarness::new().map_service(|s| s.with_host_capabilities(host.clone()));
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                org_id,
+                agent_id,
+                SandboxConfig {
+                    limits: Some(
+                        ResourceLimits::default()
+                            .with_gpus(1, 40 << 30)
+                            .with_gpu_kind("h100"),
+                    ),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        // A fresh service over the same sandbox records
+        let sink = InMemoryUsageEventSink::new();
+        let restarted = RuntimeService::new()
+            .with_host_capabilities(host)
+            .with_executor(Box::new(harness.executor.clone()))
+            .with_sandbox_repository(Box::new(harness.sandboxes.clone()))
+            .with_usage_sink(Arc::new(sink.clone()));
+        restarted.initialize().await.unwrap();
+        restarted.execute(sandbox.id, "train()").await.unwrap();
+
+        let executions = sink.executions();
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].organization_id, org_id);
+        assert_eq!(executions[0].agent_id, agent_id);
+        assert_eq!(executions[0].gpu_kind.as_deref(), Some("h100"));
+    }
+
+    #[tokio::test]
+    async fn test_execution_history_queries() {
+        let harness = RuntimeTestHarness::new();
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, agent_id, SandboxConfig::default())
+            .await
+            .unwrap();
+        let other = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        harness.executor.push_failure(ExecutionError::timeout(30));
+        for code in ["a()", "b()", "c()"] {
+            harness.service.execute(sandbox.id, code).await.unwrap();
+        }
+        harness.service.execute(other.id, "d()").await.unwrap();
+
+        let filter = ExecutionFilter::new().for_agent(agent_id);
+        let page = harness
+            .service
+            .find_executions(&filter, &PageRequest::first(2).with_total())
+            .await
+            .unwrap();
+        assert_eq!(page.total, Some(3));
+        assert_eq!(page.items.len(), 2);
+        let rest = harness
+            .service
+            .find_executions(&filter, &PageRequest::after(page.next_cursor.unwrap(), 2))
+            .await
+            .unwrap();
+        assert_eq!(rest.items.len(), 1);
+        assert_eq!(rest.items[0].status, ExecutionStatus::Failed);
+
+        let range = TimeRange::last(
+            chrono::Duration::hours(1),
+            Utc::now() + chrono::Duration::seconds(1),
+        );
+        let stats = harness
+            .service
+            .execution_stats(org_id, range)
+            .await
+            .unwrap();
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.by_status[&ExecutionStatus::Completed], 2);
+        assert_eq!(stats.by_status[&ExecutionStatus::Failed], 1);
+        assert!(stats.p50_duration_ms.is_some());
+
+        assert!(matches!(
+            RuntimeService::new().execution_stats(org_id, range).await,
+            Err(CretoError::Configuration(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_provisioning_failure_rolls_back() {
+        let harness = RuntimeTestHarness::new();
+        let org_id = OrganizationId::new();
+        harness
+            .backend
+            .fail_next_create(CretoError::SandboxCreationFailed("no capacity".to_string()));
+
+        let result = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await;
+        assert!(result.is_err());
+
+        // The record written before provisioning is marked failed, not left dangling
+        assert!(harness
+            .sandboxes
+            .list_active_by_org(org_id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(harness.backend.live_handles().is_empty());
+
+        // The next attempt succeeds normally
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            harness.sandboxes.list_active_by_org(org_id).await.unwrap()[0].id,
+            sandbox.id
+        );
+    }
+
+    fn attested_harness() -> RuntimeTestHarness {
+        RuntimeTestHarness::new()
+            .map_service(|s| s.with_attestation_verifier(Box::new(MockAttestationProvider::new())))
+    }
+
+    fn attested_config(policy: AttestationPolicy) -> SandboxConfig {
+        SandboxConfig {
+            attestation_policy: Some(policy),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_rejects_attestation_on_wrong_platform() {
+        let harness = attested_harness();
+        harness.add_warm_sandbox("python3.11").await.unwrap();
+
+        // Warm sandboxes are attested for the agent that checks them out
+        let pooled = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                attested_config(AttestationPolicy::production()),
+            )
+            .await
+            .unwrap();
+        assert!(harness
+            .service
+            .execute(pooled.id, "print(1)")
+            .await
+            .unwrap()
+            .is_success());
+
+        // gVisor attestations do not satisfy a hardware-only policy
+        let strict = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                attested_config(AttestationPolicy::strict()),
+            )
+            .await
+            .unwrap();
+        let result = harness
+            .service
+            .execute(strict.id, "print(1)")
+            .await
+            .unwrap();
+        assert_eq!(result.status, ExecutionStatus::Failed);
+        let error = result.error.unwrap();
+        assert_eq!(error.code, "ATTESTATION_REJECTED");
+        assert!(error.message.contains("Platform gVisor not allowed"));
+        assert_eq!(harness.executor.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_execute_rejects_tampered_attestation() {
+        let harness = attested_harness();
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                attested_config(AttestationPolicy::production()),
+            )
+            .await
+            .unwrap();
+
+        if let Some(entry) = harness
+            .service
+            .sandbox_attestations
+            .write()
+            .unwrap()
+            .get_mut(&sandbox.id)
+        {
+            entry.attestation.as_mut().unwrap().config_hash = vec![0; 32];
+        }
+
+        let result = harness
+            .service
+            .execute(sandbox.id, "print(1)")
+            .await
+            .unwrap();
+        let error = result.error.unwrap();
+        assert_eq!(error.code, "ATTESTATION_REJECTED");
+        assert!(error.message.contains("signature is invalid"));
+        assert!(harness.executor.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_attestation_renewed_or_rejected() {
+        // Without renewal an expired attestation is rejected
+        let harness = attested_harness().map_service(|s| {
+            s.with_attestation_generator(
+                Box::new(MockAttestationProvider::new().with_validity(chrono::Duration::zero())),
+                AttestationPlatform::GVisor,
+            )
+            .with_expired_attestation_renewal(false)
+        });
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                attested_config(AttestationPolicy::production()),
+            )
+            .await
+            .unwrap();
+        let result = harness
+            .service
+            .execute(sandbox.id, "print(1)")
+            .await
+            .unwrap();
+        let error = result.error.unwrap();
+        assert_eq!(error.code, "ATTESTATION_REJECTED");
+        assert!(error.message.contains("expired"));
+        assert!(harness.executor.requests().is_empty());
+
+        // With renewal it is regenerated and the execution runs
+        let harness = attested_harness();
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                attested_config(AttestationPolicy::production()),
+            )
+            .await
+            .unwrap();
+        let original = sandbox.attestation.clone().unwrap();
+        let expired = MockAttestationProvider::new()
+            .with_validity(chrono::Duration::zero())
+            .generate(
+                original.sandbox_id,
+                original.agent_id,
+                original.image_hash.clone(),
+                original.config_hash.clone(),
+                original.init_hash.clone(),
+                original.platform,
+            )
+            .await
+            .unwrap();
+        harness
+            .service
+            .sandbox_attestations
+            .write()
+            .unwrap()
+            .get_mut(&sandbox.id)
+            .unwrap()
+            .attestation = Some(expired);
+
+        assert!(harness
+            .service
+            .execute(sandbox.id, "print(1)")
+            .await
+            .unwrap()
+            .is_success());
+        let renewed = harness.service.sandbox_attestations.read().unwrap()[&sandbox.id]
+            .attestation
+            .clone()
+            .unwrap();
+        assert!(renewed.is_valid());
+        assert_eq!(renewed.config_hash, original.config_hash);
+    }
+
+    #[tokio::test]
+    async fn test_restore_reattests_on_same_host() {
+        let harness = attested_harness();
+        let org_id = OrganizationId::new();
+        let config = attested_config(AttestationPolicy::production());
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), config.clone())
+            .await
+            .unwrap();
+        let checkpoint_id = harness.service.checkpoint(sandbox.id).await.unwrap();
+        let checkpoint = harness
+            .service
+            .checkpoint_manager
+            .get_checkpoint(checkpoint_id)
+            .await
+            .unwrap();
+        let original = sandbox.attestation.unwrap();
+        assert_eq!(
+            checkpoint.attestation,
+            Some(AttestationMeasurements::from(&original))
+        );
+        harness.service.terminate_sandbox(sandbox.id).await.unwrap();
+
+        let restored = harness
+            .service
+            .restore_with_config(checkpoint_id, org_id, config)
+            .await
+            .unwrap();
+        assert_eq!(restored.state, SandboxState::Ready);
+        // Attested afresh for the same agent, which the checkpoint recorded
+        let fresh = restored.attestation.unwrap();
+        assert_eq!(fresh.agent_id, original.agent_id);
+        assert!(fresh.created_at >= original.created_at);
+        assert_eq!(
+            AttestationMeasurements::from(&fresh),
+            AttestationMeasurements::from(&original)
+        );
+        assert!(harness
+            .service
+            .execute(restored.id, "print(1)")
+            .await
+            .unwrap()
+            .is_success());
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_tampered_image() {
+        let harness = attested_harness();
+        let org_id = OrganizationId::new();
+        let mut config = attested_config(AttestationPolicy {
+            allow_config_drift_on_restore: true,
+            ..AttestationPolicy::production()
+        });
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), config.clone())
+            .await
+            .unwrap();
+        let checkpoint_id = harness.service.checkpoint(sandbox.id).await.unwrap();
+        harness.service.terminate_sandbox(sandbox.id).await.unwrap();
+
+        // Config drift is allowed, but never on the image
+        config.runtime = "node20".to_string();
+        let err = harness
+            .service
+            .restore_with_config(checkpoint_id, org_id, config)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("image_hash diverged"));
+        assert!(harness
+            .service
+            .sandbox_attestations
+            .read()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restore_allows_config_drift_when_policy_does() {
+        let harness = attested_harness();
+        let org_id = OrganizationId::new();
+
+        for allow_drift in [false, true] {
+            let mut config = attested_config(AttestationPolicy {
+                allow_config_drift_on_restore: allow_drift,
+                ..AttestationPolicy::production()
+            });
+            let sandbox = harness
+                .service
+                .create_sandbox(org_id, AgentId::new(), config.clone())
+                .await
+                .unwrap();
+            let checkpoint_id = harness.service.checkpoint(sandbox.id).await.unwrap();
+            harness.service.terminate_sandbox(sandbox.id).await.unwrap();
+
+            // Limits re-validated on the restoring host
+            config.limits = Some(ResourceLimits::minimal());
+            let restored = harness
+                .service
+                .restore_with_config(checkpoint_id, org_id, config)
+                .await;
+            if allow_drift {
+                let restored = restored.unwrap();
+                assert_eq!(restored.state, SandboxState::Ready);
+                assert_ne!(
+                    restored.attestation.unwrap().config_hash,
+                    sandbox.attestation.unwrap().config_hash
+                );
+            } else {
+                let err = restored.unwrap_err();
+                assert!(err.to_string().contains("config_hash diverged"));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_checkout_falls_back_to_provisioning() {
+        let harness = RuntimeTestHarness::new();
+        let warm_id = harness.add_warm_sandbox("python3.11").await.unwrap();
+        let agent_id = AgentId::new();
+
+        let pooled = harness
+            .service
+            .create_sandbox(OrganizationId::new(), agent_id, SandboxConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(pooled.id, warm_id);
+        // Re-attested for the agent that checked it out
+        assert_eq!(pooled.attestation.unwrap().agent_id, agent_id);
+
+        let fresh = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        assert_ne!(fresh.id, warm_id);
+        assert_eq!(fresh.state, SandboxState::Ready);
+
+        let stats = harness.service.pool_stats().await;
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert_eq!(harness.backend.live_handles().len(), 2);
+
+        harness.service.terminate_sandbox(pooled.id).await.unwrap();
+        harness.service.terminate_sandbox(fresh.id).await.unwrap();
+        assert!(harness.backend.live_handles().is_empty());
+        assert_eq!(harness.service.pool_stats().await.total, 0);
+    }
+
+    #[tokio::test]
+    async fn test_queued_execution_cancelled_before_dispatch() {
+        let executor = ScriptedExecutor::new();
+        let service = Arc::new(
+            RuntimeService::new()
+                .with_executor(Box::new(executor.clone()))
+                .with_execution_queue(ExecutionQueueConfig {
+                    max_concurrent_executions: 1,
+                    ..Default::default()
+                }),
+        );
+        let org_id = OrganizationId::new();
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        let running = service.execution_queue().unwrap().try_acquire().unwrap();
+
+        // At capacity: unqueued requests fail, queued ones wait
+        assert!(matches!(
+            service.execute(sandbox.id, "print('now')").await,
+            Err(CretoError::ResourceLimitExceeded { .. })
+        ));
+        let cancelled = service
+            .submit_execution(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "print('cancelled')").queued(),
+            )
+            .unwrap();
+        let kept = service
+            .submit_execution(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "print('kept')")
+                    .with_priority(ExecutionPriority::Low)
+                    .queued(),
+            )
+            .unwrap();
+        assert_eq!(cancelled.position(), Some(0));
+        assert_eq!(kept.position(), Some(1));
+
+        assert!(cancelled.cancel());
+        assert_eq!(kept.position(), Some(0));
+
+        drop(running);
+        let result = kept.wait().await.unwrap();
+        assert!(result.is_success());
+
+        let codes: Vec<String> = executor.requests().into_iter().map(|r| r.code).collect();
+        assert_eq!(codes, vec!["print('kept')".to_string()]);
+        assert_eq!(service.execution_queue().unwrap().running(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_submitted_executions_drain_on_shutdown() {
+        let executor = ScriptedExecutor::new();
+        let shutdown = Arc::new(ShutdownCoordinator::new());
+        let service = Arc::new(
+            RuntimeService::new()
+                .with_executor(Box::new(executor.clone()))
+                .with_execution_queue(ExecutionQueueConfig {
+                    max_concurrent_executions: 1,
+                    ..Default::default()
+                })
+                .with_shutdown(shutdown.clone()),
+        );
+        let org_id = OrganizationId::new();
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+
+        let release = executor.push_hold();
+        let running = service
+            .submit_execution(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "print('running')"),
+            )
+            .unwrap();
+        while executor.requests().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let waiting = service
+            .submit_execution(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "print('waiting')").queued(),
+            )
+            .unwrap();
+        assert_eq!(waiting.position(), Some(0));
+        assert_eq!(shutdown.task_count(), 2);
+
+        // Shutdown cancels the waiting request and lets the running one finish
+        let draining = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.shutdown(Duration::from_secs(5)).await }
+        });
+        assert!(matches!(waiting.wait().await, Err(CretoError::NotFound(_))));
+        release.send(()).unwrap();
+        assert!(running.wait().await.unwrap().is_success());
+        assert!(draining.await.unwrap().is_clean());
+        assert_eq!(executor.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_queued_sandbox_creation_waits_for_capacity() {
+        let provisioner = ScriptedProvisioner::new();
+        let service = Arc::new(
+            RuntimeService::new()
+                .with_provisioner(Box::new(provisioner.clone()))
+                .with_provisioning_retry(ProvisioningRetryPolicy::disabled())
+                .with_execution_queue(ExecutionQueueConfig {
+                    sandbox_retry_interval: Duration::from_secs(60),
+                    ..Default::default()
+                }),
+        );
+        let org_id = OrganizationId::new();
+        let held = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        let host_full = || ProvisioningError::HostPressure("host full".to_string());
+
+        // Without queueing, a full host fails the request
+        provisioner.fail_next(ProvisioningPhase::ResourceAllocation, host_full());
+        assert!(matches!(
+            service
+                .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+                .await,
+            Err(CretoError::SandboxProvisioningFailed { .. })
+        ));
+
+        // Queued, it waits until a sandbox is terminated
+        provisioner.fail_next(ProvisioningPhase::ResourceAllocation, host_full());
+        let queued = tokio::spawn({
+            let service = Arc::clone(&service);
+            async move {
+                service
+                    .create_sandbox_queued(
+                        org_id,
+                        AgentId::new(),
+                        SandboxConfig::default(),
+                        ExecutionPriority::High,
+                    )
+                    .await
+            }
+        });
+        let queue = service.execution_queue().unwrap();
+        while queue.depth() == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(queue.queued_for(&org_id), 1);
+
+        service.terminate_sandbox(held.id).await.unwrap();
+        let created = queued.await.unwrap().unwrap();
+        assert_ne!(created.id, held.id);
+        assert_eq!(queue.depth(), 0);
+    }
+
+    /// Diff store whose writes always fail.
+    struct FailingDiffStore;
+
+    #[async_trait::async_trait]
+    impl FilesystemDiffStore for FailingDiffStore {
+        async fn put(&self, _diff: &FilesystemDiff) -> CretoResult<()> {
+            Err(CretoError::Database("connection reset".to_string()))
+        }
+
+        async fn get(&self, _execution_id: Uuid) -> CretoResult<Option<FilesystemDiff>> {
+            Ok(None)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_diff_store_failure_does_not_fail_execution() {
+        let workdir = std::env::temp_dir().join(format!("creto-fs-{}", Uuid::now_v7()));
+        std::fs::create_dir_all(&workdir).unwrap();
+        let executor = ScriptedExecutor::new();
+        let effect_workdir = workdir.clone();
+        executor.push_effect(move |_| {
+            std::fs::write(effect_workdir.join("new.txt"), "created").unwrap();
+        });
+
+        let resolved = workdir.clone();
+        let service = RuntimeService::new()
+            .with_executor(Box::new(executor))
+            .with_filesystem_tracking(
+                FilesystemTrackingConfig {
+                    persist_diffs: true,
+                    ..Default::default()
+                },
+                Box::new(move |_| Some(resolved.clone())),
+            )
+            .with_filesystem_diff_store(Box::new(FailingDiffStore));
+        let sandbox = service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let result = service.execute(sandbox.id, "mutate()").await.unwrap();
+        assert!(result.is_success());
+        assert_eq!(result.fs_changes.unwrap().created, 1);
+
+        std::fs::remove_dir_all(&workdir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_execution_fs_diff_tracks_workdir_changes() {
+        use crate::filesystem::{ChangeKind, FileKind};
+
+        let base = std::env::temp_dir().join(format!("creto-fs-{}", Uuid::now_v7()));
+        let workdir = base.join("workdir");
+        let outside = base.join("outside");
+        std::fs::create_dir_all(workdir.join("tmp")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(workdir.join("keep.txt"), "unchanged").unwrap();
+        std::fs::write(workdir.join("modify.txt"), "v1").unwrap();
+        std::fs::write(workdir.join("delete.txt"), "doomed").unwrap();
+        std::fs::write(outside.join("secret.txt"), "host data").unwrap();
+
+        let executor = ScriptedExecutor::new();
+        let (effect_workdir, effect_outside) = (workdir.clone(), outside.clone());
+        executor.push_effect(move |_| {
+            std::fs::write(effect_workdir.join("new.txt"), "created").unwrap();
+            std::fs::write(effect_workdir.join("modify.txt"), "version 2").unwrap();
+            std::fs::remove_file(effect_workdir.join("delete.txt")).unwrap();
+            std::fs::write(effect_workdir.join("tmp/scratch"), "ignored").unwrap();
+            std::os::unix::fs::symlink(&effect_outside, effect_workdir.join("escape")).unwrap();
+            std::fs::write(effect_outside.join("secret.txt"), "changed host data").unwrap();
+        });
+
+        let resolved = workdir.clone();
+        let service = RuntimeService::new()
+            .with_executor(Box::new(executor))
+            .with_filesystem_tracking(
+                FilesystemTrackingConfig {
+                    persist_diffs: true,
+                    ..Default::default()
+                },
+                Box::new(move |_| Some(resolved.clone())),
+            );
+        let sandbox = service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let result = service.execute(sandbox.id, "mutate()").await.unwrap();
+        let diff = service
+            .execution_fs_diff(result.request_id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let changes: Vec<(&str, ChangeKind)> = diff
+            .changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.change))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("delete.txt", ChangeKind::Deleted),
+                ("escape", ChangeKind::Created),
+                ("modify.txt", ChangeKind::Modified),
+                ("new.txt", ChangeKind::Created),
+            ]
+        );
+        // The link is recorded, not followed out of the workdir
+        let escape = diff.changes[1].after.as_ref().unwrap();
+        assert_eq!(escape.kind, FileKind::Symlink);
+        let modified = &diff.changes[2];
+        assert_ne!(
+            modified.before.as_ref().unwrap().hash,
+            modified.after.as_ref().unwrap().hash
+        );
+
+        let summary = result.fs_changes.unwrap();
+        assert_eq!(
+            (summary.created, summary.modified, summary.deleted),
+            (2, 1, 1)
+        );
+        assert_eq!(
+            summary.bytes_changed,
+            "doomed".len() as u64
+                + outside.to_string_lossy().len() as u64
+                + "version 2".len() as u64
+                + "created".len() as u64
+        );
+        assert!(!summary.truncated);
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    struct DenyGate;
+
+    #[async_trait::async_trait]
+    impl ScheduleGate for DenyGate {
+        async fn admit(&self, _schedule: &ExecutionSchedule) -> CretoResult<()> {
+            Err(CretoError::QuotaExceeded {
+                resource: "sandbox_execution".to_string(),
+                used: 100,
+                limit: 100,
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl TriggerGate for DenyGate {
+        async fn admit(
+            &self,
+            _trigger: &TopicTrigger,
+            _message: &TriggerMessage,
+        ) -> CretoResult<()> {
+            Err(CretoError::QuotaExceeded {
+                resource: "sandbox_execution".to_string(),
+                used: 100,
+                limit: 100,
+            })
+        }
+    }
+
+    /// Gate that holds each attempt for a while before refusing it.
+    struct SlowGate;
+
+    #[async_trait::async_trait]
+    impl TriggerGate for SlowGate {
+        async fn admit(
+            &self,
+            _trigger: &TopicTrigger,
+            _message: &TriggerMessage,
+        ) -> CretoResult<()> {
+            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+            Err(CretoError::ValidationFailed("slow gate".to_string()))
+        }
+    }
+
+    fn scheduled_service(clock: &Arc<creto_common::TestClock>) -> RuntimeService {
+        RuntimeService::new()
+            .with_clock(clock.clone())
+            .with_scheduler_config(SchedulerConfig {
+                misfire_grace: chrono::Duration::minutes(1),
+                ..SchedulerConfig::default()
+            })
+    }
+
+    #[tokio::test]
+    async fn test_schedule_fires_and_catches_up() {
+        use chrono::TimeZone;
+        let start = Utc.with_ymd_and_hms(2026, 1, 31, 23, 30, 0).unwrap();
+        let clock = Arc::new(creto_common::TestClock::new(start));
+        let service = scheduled_service(&clock);
+        let template = ExecutionRequest::new(SandboxId::new(), "print('report')");
+
+        let skip = service
+            .create_schedule(
+                OrganizationId::new(),
+                AgentId::new(),
+                template.clone(),
+                ScheduleTrigger::cron("0 0 1 * *").unwrap(),
+                CatchUpPolicy::Skip,
+            )
+            .await
+            .unwrap();
+        let run_once = service
+            .create_schedule(
+                OrganizationId::new(),
+                AgentId::new(),
+                template,
+                ScheduleTrigger::cron("0 0 1 * *").unwrap(),
+                CatchUpPolicy::RunOnce,
+            )
+            .await
+            .unwrap();
+        let feb = Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        assert_eq!(skip.next_run_at, Some(feb));
+
+        assert!(service.run_due_schedules().await.unwrap().is_empty());
+
+        // On time across the month boundary
+        clock.set(feb + chrono::Duration::seconds(10));
+        let runs = service.run_due_schedules().await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(runs.iter().all(|r| matches!(
+            r.outcome,
+            ScheduleRunOutcome::Completed {
+                status: ExecutionStatus::Completed
+            }
+        ) && r.execution_id.is_some()));
+        // Fired once per occurrence
+        assert!(service.run_due_schedules().await.unwrap().is_empty());
+
+        // Scheduler down through March 1 and April 1
+        clock.set(Utc.with_ymd_and_hms(2026, 4, 10, 0, 0, 0).unwrap());
+        let runs = service.run_due_schedules().await.unwrap();
+        let skipped = runs.iter().find(|r| r.schedule_id == skip.id).unwrap();
+        assert_eq!(skipped.missed, 2);
+        assert!(skipped.execution_id.is_none());
+        assert!(matches!(
+            skipped.outcome,
+            ScheduleRunOutcome::Skipped { .. }
+        ));
+        let caught_up = runs.iter().find(|r| r.schedule_id == run_once.id).unwrap();
+        assert_eq!(caught_up.missed, 2);
+        assert_eq!(
+            caught_up.scheduled_for,
+            Utc.with_ymd_and_hms(2026, 4, 1, 0, 0, 0).unwrap()
+        );
+        assert!(caught_up.execution_id.is_some());
+
+        let schedule = service.get_schedule(skip.id).await.unwrap();
+        assert_eq!(
+            schedule.next_run_at,
+            Some(Utc.with_ymd_and_hms(2026, 5, 1, 0, 0, 0).unwrap())
+        );
+        let history = service
+            .schedule_runs(skip.id, ScheduleRunPage::default())
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(matches!(
+            history[0].outcome,
+            ScheduleRunOutcome::Skipped { .. }
+        ));
+        assert_eq!(
+            service
+                .schedule_runs(skip.id, ScheduleRunPage::new(1, 10))
+                .await
+                .unwrap()[0]
+                .scheduled_for,
+            feb
+        );
+    }
+
+    /// Schedule store whose run history cannot be written.
+    struct FailingRunStore(crate::schedule::InMemoryScheduleStore);
+
+    #[async_trait::async_trait]
+    impl ScheduleStore for FailingRunStore {
+        async fn create(&self, schedule: &ExecutionSchedule) -> CretoResult<()> {
+            self.0.create(schedule).await
+        }
+
+        async fn get(&self, id: ScheduleId) -> CretoResult<Option<ExecutionSchedule>> {
+            self.0.get(id).await
+        }
+
+        async fn list_by_org(&self, org_id: OrganizationId) -> CretoResult<Vec<ExecutionSchedule>> {
+            self.0.list_by_org(org_id).await
+        }
+
+        async fn update(&self, schedule: &ExecutionSchedule) -> CretoResult<()> {
+            self.0.update(schedule).await
+        }
+
+        async fn delete(&self, id: ScheduleId) -> CretoResult<bool> {
+            self.0.delete(id).await
+        }
+
+        async fn claim_due(
+            &self,
+            now: DateTime<Utc>,
+            lease: chrono::Duration,
+            limit: usize,
+        ) -> CretoResult<Vec<crate::schedule::ScheduleClaim>> {
+            self.0.claim_due(now, lease, limit).await
+        }
+
+        async fn complete_claim(
+            &self,
+            claim: &crate::schedule::ScheduleClaim,
+            next_run_at: Option<DateTime<Utc>>,
+        ) -> CretoResult<bool> {
+            self.0.complete_claim(claim, next_run_at).await
+        }
+
+        async fn record_run(&self, _run: &ScheduleRun) -> CretoResult<()> {
+            Err(CretoError::Database("connection reset".to_string()))
+        }
+
+        async fn list_runs(
+            &self,
+            schedule_id: ScheduleId,
+            page: ScheduleRunPage,
+        ) -> CretoResult<Vec<ScheduleRun>> {
+            self.0.list_runs(schedule_id, page).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_run_record_does_not_stop_other_schedules() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let service = scheduled_service(&clock).with_schedule_store(Box::new(FailingRunStore(
+            crate::schedule::InMemoryScheduleStore::new(),
+        )));
+        let (org, agent) = (OrganizationId::new(), AgentId::new());
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let trigger =
+                ScheduleTrigger::every(chrono::Duration::minutes(5), clock.now()).unwrap();
+            let schedule = service
+                .create_schedule(
+                    org,
+                    agent,
+                    ExecutionRequest::new(SandboxId::new(), "print('tick')"),
+                    trigger,
+                    CatchUpPolicy::RunOnce,
+                )
+                .await
+                .unwrap();
+            ids.push(schedule.id);
+        }
+
+        clock.advance(chrono::Duration::minutes(5));
+        let runs = service.run_due_schedules().await.unwrap();
+        assert_eq!(runs.len(), 2);
+        for id in ids {
+            assert!(runs.iter().any(|r| r.schedule_id == id));
+            assert!(service.get_schedule(id).await.unwrap().next_run_at > Some(clock.now()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disabled_or_deleted_schedule_does_not_fire_in_flight() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let service = scheduled_service(&clock);
+        let trigger = ScheduleTrigger::every(chrono::Duration::minutes(5), clock.now()).unwrap();
+        let template = ExecutionRequest::new(SandboxId::new(), "print('tick')");
+        let (org, agent) = (OrganizationId::new(), AgentId::new());
+
+        let disabled = service
+            .create_schedule(
+                org,
+                agent,
+                template.clone(),
+                trigger.clone(),
+                CatchUpPolicy::Skip,
+            )
+            .await
+            .unwrap();
+        let deleted = service
+            .create_schedule(org, agent, template, trigger, CatchUpPolicy::Skip)
+            .await
+            .unwrap();
+        assert_eq!(service.list_schedules(org).await.unwrap().len(), 2);
+
+        // Another worker claims both, then they change before it fires
+        clock.advance(chrono::Duration::minutes(5));
+        let claims = service
+            .schedules
+            .claim_due(clock.now(), chrono::Duration::minutes(5), 10)
+            .await
+            .unwrap();
+        assert_eq!(claims.len(), 2);
+        service
+            .set_schedule_enabled(disabled.id, false)
+            .await
+            .unwrap();
+        service.delete_schedule(deleted.id).await.unwrap();
+        for claim in &claims {
+            assert!(!service
+                .schedules
+                .complete_claim(claim, claim.schedule.next_run_at)
+                .await
+                .unwrap());
+        }
+
+        assert!(service.run_due_schedules().await.unwrap().is_empty());
+        assert!(matches!(
+            service.delete_schedule(deleted.id).await,
+            Err(CretoError::NotFound(_))
+        ));
+
+        // Re-enabled schedules resume without catching up
+        clock.advance(chrono::Duration::minutes(12));
+        let resumed = service
+            .set_schedule_enabled(disabled.id, true)
+            .await
+            .unwrap();
+        assert!(resumed.next_run_at.unwrap() > clock.now());
+        assert!(service.run_due_schedules().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_gate_denial_records_skipped_run() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let service = scheduled_service(&clock).with_schedule_gate(Box::new(DenyGate));
+        let schedule = service
+            .create_schedule(
+                OrganizationId::new(),
+                AgentId::new(),
+                ExecutionRequest::new(SandboxId::new(), "print('tick')"),
+                ScheduleTrigger::every(chrono::Duration::minutes(5), clock.now()).unwrap(),
+                CatchUpPolicy::Skip,
+            )
+            .await
+            .unwrap();
+
+        clock.advance(chrono::Duration::minutes(5));
+        let runs = service.run_due_schedules().await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].execution_id.is_none());
+        match &runs[0].outcome {
+            ScheduleRunOutcome::Skipped { reason } => assert!(reason.contains("sandbox_execution")),
+            other => panic!("expected skipped run, got {:?}", other),
+        }
+        // The schedule still advances
+        assert!(
+            service
+                .get_schedule(schedule.id)
+                .await
+                .unwrap()
+                .next_run_at
+                .unwrap()
+                > clock.now()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_topic_message_triggers_one_execution() {
+        use crate::testing::ScriptedTopicSource;
+        use crate::triggers::MESSAGE_PAYLOAD_PATH;
+
+        let source = Arc::new(ScriptedTopicSource::new());
+        let executor = ScriptedExecutor::new();
+        let service = RuntimeService::new()
+            .with_executor(Box::new(executor.clone()))
+            .with_topic_source(source.clone());
+        let topic_id = Uuid::new_v4();
+        let org = OrganizationId::new();
+        let trigger = TopicTrigger::new(
+            org,
+            AgentId::new(),
+            topic_id,
+            ExecutionRequest::new(SandboxId::new(), "handle()"),
+        )
+        .with_filter("kind", "order");
+        let trigger_id = trigger.id;
+        service.register_topic_trigger(trigger).await.unwrap();
+        assert_eq!(service.list_topic_triggers(org).await.unwrap().len(), 1);
+
+        let order = source.publish(topic_id, b"{\"id\":1}", &[("kind", "order")]);
+        source.publish(topic_id, b"{}", &[("kind", "refund")]);
+        source.publish(Uuid::new_v4(), b"{}", &[("kind", "order")]);
+
+        let deliveries = service.run_topic_triggers().await.unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].message_id, order.id);
+        assert_eq!(
+            deliveries[0].outcome,
+            TriggerOutcome::Executed {
+                status: ExecutionStatus::Completed
+            }
+        );
+        let requests = executor.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(Some(requests[0].id), deliveries[0].execution_id);
+        assert_eq!(requests[0].caused_by, Some(order.id));
+        assert_eq!(requests[0].artifacts[0].path, MESSAGE_PAYLOAD_PATH);
+        assert_eq!(requests[0].artifacts[0].content, order.payload);
+
+        // Already-delivered messages are not delivered again
+        assert!(service.run_topic_triggers().await.unwrap().is_empty());
+        assert_eq!(executor.requests().len(), 1);
+
+        assert!(service.remove_topic_trigger(trigger_id).await.unwrap());
+        assert_eq!(source.subscription_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_trigger_quota_denial_retries_then_dead_letters() {
+        use crate::testing::ScriptedTopicSource;
+        use crate::triggers::TriggerRetryPolicy;
+
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let source = Arc::new(ScriptedTopicSource::new());
+        let service = RuntimeService::new()
+            .with_clock(clock.clone())
+            .with_topic_source(source.clone())
+            .with_trigger_gate(Box::new(DenyGate));
+        let topic_id = Uuid::new_v4();
+        let trigger = TopicTrigger::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            topic_id,
+            ExecutionRequest::new(SandboxId::new(), "handle()"),
+        )
+        .with_retry(TriggerRetryPolicy {
+            max_attempts: 2,
+            initial_backoff_ms: 30_000,
+            ..TriggerRetryPolicy::default()
+        });
+        let trigger_id = trigger.id;
+        service.register_topic_trigger(trigger).await.unwrap();
+        let message = source.publish(topic_id, b"{}", &[]);
+
+        let first = service.run_topic_triggers().await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert!(first[0].execution_id.is_none());
+        match &first[0].outcome {
+            TriggerOutcome::Retrying {
+                error,
+                next_attempt_at,
+            } => {
+                assert!(error.contains("sandbox_execution"));
+                assert_eq!(
+                    *next_attempt_at,
+                    clock.now() + chrono::Duration::seconds(30)
+                );
+            }
+            other => panic!("expected retry, got {:?}", other),
+        }
+
+        // Not retried before the backoff elapses
+        assert!(service.run_topic_triggers().await.unwrap().is_empty());
+
+        clock.advance(chrono::Duration::seconds(30));
+        let second = service.run_topic_triggers().await.unwrap();
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].attempt, 2);
+        assert!(matches!(
+            second[0].outcome,
+            TriggerOutcome::DeadLettered { .. }
+        ));
+
+        let dead = service.trigger_dead_letters(trigger_id).await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].message.id, message.id);
+        assert_eq!(dead[0].attempts, 2);
+
+        clock.advance(chrono::Duration::hours(1));
+        assert!(service.run_topic_triggers().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trigger_dispatch_resumes_after_restart() {
+        use crate::testing::ScriptedTopicSource;
+        use crate::triggers::{InMemoryTopicTriggerStore, TriggerRetryPolicy};
+
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let source = Arc::new(ScriptedTopicSource::new());
+        let store = Arc::new(InMemoryTopicTriggerStore::new());
+        let restarted = || {
+            RuntimeService::new()
+                .with_clock(clock.clone())
+                .with_topic_source(source.clone())
+                .with_topic_trigger_store(store.clone())
+                .with_trigger_gate(Box::new(DenyGate))
+        };
+        let topic_id = Uuid::new_v4();
+        let org = OrganizationId::new();
+        let trigger = TopicTrigger::new(
+            org,
+            AgentId::new(),
+            topic_id,
+            ExecutionRequest::new(SandboxId::new(), "handle()"),
+        )
+        .with_retry(TriggerRetryPolicy {
+            max_attempts: 2,
+            initial_backoff_ms: 30_000,
+            ..TriggerRetryPolicy::default()
+        });
+        let trigger_id = trigger.id;
+
+        let service = restarted();
+        service.register_topic_trigger(trigger).await.unwrap();
+        let first = source.publish(topic_id, b"{}", &[]);
+        let first_pass = service.run_topic_triggers().await.unwrap();
+        assert_eq!(first_pass.len(), 1);
+        drop(service);
+
+        // The trigger, its read position and the pending retry survive
+        let service = restarted();
+        assert_eq!(service.list_topic_triggers(org).await.unwrap().len(), 1);
+        let second = source.publish(topic_id, b"{}", &[]);
+        clock.advance(chrono::Duration::seconds(30));
+        let deliveries = service.run_topic_triggers().await.unwrap();
+        let delivered: Vec<_> = deliveries
+            .iter()
+            .map(|d| (d.message_id, d.attempt))
+            .collect();
+        assert_eq!(delivered, vec![(first.id, 2), (second.id, 1)]);
+        drop(service);
+
+        let service = restarted();
+        let dead = service.trigger_dead_letters(trigger_id).await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].message.id, first.id);
+
+        assert!(service.remove_topic_trigger(trigger_id).await.unwrap());
+        assert!(store.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trigger_registry_is_usable_during_dispatch() {
+        use crate::testing::ScriptedTopicSource;
+
+        let source = Arc::new(ScriptedTopicSource::new());
+        let service = Arc::new(
+            RuntimeService::new()
+                .with_topic_source(source.clone())
+                .with_trigger_gate(Box::new(SlowGate)),
+        );
+        let topic_id = Uuid::new_v4();
+        let org = OrganizationId::new();
+        let trigger = TopicTrigger::new(
+            org,
+            AgentId::new(),
+            topic_id,
+            ExecutionRequest::new(SandboxId::new(), "handle()"),
+        );
+        service.register_topic_trigger(trigger).await.unwrap();
+        source.publish(topic_id, b"{}", &[]);
+
+        let dispatch = tokio::spawn({
+            let service = Arc::clone(&service);
+            async move { service.run_topic_triggers().await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        // Answered while the pass waits on the gate
+        let listed = tokio::time::timeout(
+            std::time::Duration::from_millis(100),
+            service.list_topic_triggers(org),
+        )
+        .await
+        .expect("listing waited for the dispatch pass")
+        .unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(!dispatch.is_finished());
+
+        dispatch.await.unwrap().unwrap();
+    }
+
+    /// Attestation generator that always fails.
+    struct FailingAttestation;
+
+    #[async_trait::async_trait]
+    impl AttestationGenerator for FailingAttestation {
+        async fn generate(
+            &self,
+            _sandbox_id: SandboxId,
+            _agent_id: AgentId,
+            _image_hash: Vec<u8>,
+            _config_hash: Vec<u8>,
+            _init_hash: Vec<u8>,
+            _platform: AttestationPlatform,
+        ) -> CretoResult<crate::attestation::Attestation> {
+            Err(CretoError::Internal("attestation service down".to_string()))
+        }
+    }
+
+    fn fast_retry(max_attempts: u32) -> ProvisioningRetryPolicy {
+        ProvisioningRetryPolicy {
+            max_attempts,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+            multiplier: 1.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provisioning_failure_in_each_phase() {
+        let since = Utc::now() - chrono::Duration::seconds(1);
+        let cases = [
+            (
+                ProvisioningPhase::ImageResolution,
+                ProvisioningError::DisallowedImage("unsigned image".to_string()),
+            ),
+            (
+                ProvisioningPhase::ResourceAllocation,
+                ProvisioningError::HostPressure("no free memory".to_string()),
+            ),
+            (
+                ProvisioningPhase::SecretInjection,
+                ProvisioningError::InvalidConfig("unknown secret".to_string()),
+            ),
+        ];
+
+        for (phase, error) in cases {
+            let harness = RuntimeTestHarness::new();
+            harness.provisioner.fail_next(phase, error.clone());
+
+            let failure = harness
+                .service
+                .create_sandbox_with_retry(
+                    OrganizationId::new(),
+                    AgentId::new(),
+                    SandboxConfig::default(),
+                    &ProvisioningRetryPolicy::disabled(),
+                )
+                .await
+                .unwrap_err();
+            assert_eq!(failure.phase, phase);
+            assert_eq!(failure.error, error);
+            assert_eq!(failure.report.attempts, 1);
+            let last = failure.report.phases.last().unwrap();
+            assert_eq!((last.phase, last.succeeded), (phase, false));
+            assert!(failure.report.phases[..failure.report.phases.len() - 1]
+                .iter()
+                .all(|t| t.succeeded));
+
+            let records = harness
+                .sandboxes
+                .list_provisioning_failures(since)
+                .await
+                .unwrap();
+            assert_eq!(records.len(), 1);
+            assert_eq!((records[0].phase, &records[0].error), (phase, &error));
+            // The sandbox record is written during resource allocation
+            assert_eq!(
+                records[0].sandbox_id.is_some(),
+                phase != ProvisioningPhase::ImageResolution
+            );
+            assert!(harness.backend.live_handles().is_empty());
+        }
+
+        // Attestation failures come from the attestation generator
+        let harness = RuntimeTestHarness::new().map_service(|service| {
+            service.with_attestation_generator(
+                Box::new(FailingAttestation),
+                AttestationPlatform::GVisor,
+            )
+        });
+        let err = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CretoError::SandboxProvisioningFailed {
+                ref phase,
+                retryable: true,
+                attempts: 1,
+                ..
+            } if phase == "attestation"
+        ));
+        let records = harness
+            .sandboxes
+            .list_provisioning_failures(since)
+            .await
+            .unwrap();
+        assert_eq!(records[0].phase, ProvisioningPhase::Attestation);
+        assert_eq!(records[0].phases.len(), 4);
+        assert!(harness.backend.live_handles().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retryable_provisioning_failures_are_retried() {
+        let since = Utc::now() - chrono::Duration::seconds(1);
+        let harness = RuntimeTestHarness::new()
+            .map_service(|service| service.with_provisioning_retry(fast_retry(3)));
+        for _ in 0..2 {
+            harness.provisioner.fail_next(
+                ProvisioningPhase::ResourceAllocation,
+                ProvisioningError::HostPressure("no free memory".to_string()),
+            );
+        }
+
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(sandbox.state, SandboxState::Ready);
+        let report = sandbox.provisioning.unwrap();
+        assert_eq!(report.attempts, 3);
+        assert!(!report.from_pool);
+        assert_eq!(report.attempt_phases(3).len(), 4);
+        assert!(report.attempt_phases(3).iter().all(|t| t.succeeded));
+
+        let records = harness
+            .sandboxes
+            .list_provisioning_failures(since)
+            .await
+            .unwrap();
+        let mut attempts: Vec<_> = records.iter().map(|r| r.attempt).collect();
+        attempts.sort();
+        assert_eq!(attempts, vec![1, 2]);
+        assert_eq!(harness.backend.live_handles().len(), 1);
+
+        // Permanent failures are not retried
+        harness.provisioner.fail_next(
+            ProvisioningPhase::ImageResolution,
+            ProvisioningError::DisallowedImage("unsigned image".to_string()),
+        );
+        let err = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CretoError::SandboxProvisioningFailed {
+                retryable: false,
+                attempts: 1,
+                ..
+            }
+        ));
+
+        // Callers can opt out of retries
+        harness.provisioner.fail_next(
+            ProvisioningPhase::ResourceAllocation,
+            ProvisioningError::RegistryTimeout("pull timed out".to_string()),
+        );
+        let failure = harness
+            .service
+            .create_sandbox_with_retry(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+                &ProvisioningRetryPolicy::disabled(),
+            )
+            .await
+            .unwrap_err();
+        assert!(failure.is_retryable());
+        assert_eq!(failure.report.attempts, 1);
+
+        // Requests rejected before provisioning are permanent
+        let failure = harness
+            .service
+            .create_sandbox_with_retry(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig {
+                    network_policy_template: Some(NetworkPolicyTemplateRef {
+                        name: "missing".to_string(),
+                        additional_rules: Vec::new(),
+                    }),
+                    ..Default::default()
+                },
+                &fast_retry(3),
+            )
+            .await
+            .unwrap_err();
+        assert!(!failure.is_retryable());
+        assert_eq!(failure.report.attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pool_replenishment_stops_on_permanent_failure() {
+        use crate::pool::RuntimePoolConfig;
+
+        let harness = RuntimeTestHarness::with_pool_config(PoolConfig {
+            runtimes: vec![
+                RuntimePoolConfig {
+                    name: "python3.11".to_string(),
+                    min_warm: 2,
+                    max_warm: 4,
+                },
+                RuntimePoolConfig {
+                    name: "node20".to_string(),
+                    min_warm: 1,
+                    max_warm: 2,
+                },
+            ],
+            ..Default::default()
+        });
+
+        // The first runtime replenished hits a disallowed image
+        harness.provisioner.fail_next(
+            ProvisioningPhase::ImageResolution,
+            ProvisioningError::DisallowedImage("unsigned image".to_string()),
+        );
+        let report = harness.service.replenish_pool().await;
+        assert_eq!(report.provisioned, 1);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.suspended, vec!["python3.11".to_string()]);
+        assert!(harness
+            .service
+            .suspended_pool_runtimes()
+            .contains_key("python3.11"));
+
+        // Later passes leave the failing runtime alone
+        let calls = harness.provisioner.calls().len();
+        let report = harness.service.replenish_pool().await;
+        assert_eq!(report.provisioned, 0);
+        assert_eq!(report.suspended, vec!["python3.11".to_string()]);
+        assert_eq!(harness.provisioner.calls().len(), calls);
+
+        // A transient failure is tried again on the next pass
+        assert!(harness.service.resume_pool_replenishment("python3.11"));
+        harness.provisioner.fail_next(
+            ProvisioningPhase::ResourceAllocation,
+            ProvisioningError::HostPressure("no free memory".to_string()),
+        );
+        let report = harness.service.replenish_pool().await;
+        assert_eq!(report.provisioned, 0);
+        assert!(report.failures[0].is_retryable());
+        assert!(report.suspended.is_empty());
+
+        let report = harness.service.replenish_pool().await;
+        assert_eq!(report.provisioned, 2);
+        let stats = harness.service.pool_stats().await;
+        assert_eq!(stats.by_runtime["python3.11"].ready, 2);
+        assert_eq!(stats.by_runtime["node20"].ready, 1);
+
+        // Warm sandboxes are attested when checked out
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        let report = sandbox.provisioning.unwrap();
+        assert!(report.from_pool);
+        assert_eq!(report.phases.len(), 1);
+        assert_eq!(report.phases[0].phase, ProvisioningPhase::Attestation);
+        assert!(sandbox.attestation.is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_usage_sampling_follows_sandbox_lifetime() {
+        use crate::metering::{InMemoryUsageEventSink, RuntimeMeteringEvent};
+        use crate::sampling::SamplerConfig;
+
+        let repository = crate::testing::InMemoryResourceUsageRepository::new();
+        let sink = InMemoryUsageEventSink::new();
+        let sampler = Arc::new(SandboxUsageSampler::new(
+            SamplerConfig::default().with_interval_ms(1000),
+            Arc::new(repository.clone()),
+            Arc::new(sink.clone()),
+        ));
+        let harness =
+            RuntimeTestHarness::new().map_service(|s| s.with_usage_sampler(Arc::clone(&sampler)));
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        assert!(sampler.is_sampling(sandbox.id));
+
+        let handle = sandbox.runtime_handle.clone().unwrap();
+        harness.backend.set_usage(
+            &handle,
+            ResourceUsage {
+                cpu_time_ms: 250,
+                memory_bytes: 1024 * 1024,
+                ..Default::default()
+            },
+        );
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        harness.service.terminate_sandbox(sandbox.id).await.unwrap();
+
+        // The final sample is taken before the backend tears the sandbox down
+        assert!(!sampler.is_sampling(sandbox.id));
+        let snapshots = repository.snapshots(sandbox.id);
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(snapshots[2].cpu_time_ms, 250);
+        assert_eq!(sink.total(sandbox.id, RuntimeMeteringEvent::CpuTime), 250);
+    }
+
+    #[tokio::test]
+    async fn test_pool_health_check_replaces_broken_sandbox() {
+        use crate::pool::{PoolHealthConfig, RuntimePoolConfig};
+        use crate::testing::ScriptedHealthProbe;
+
+        let probe = ScriptedHealthProbe::new();
+        let harness = RuntimeTestHarness::with_pool_config(PoolConfig {
+            runtimes: vec![RuntimePoolConfig {
+                name: "python3.11".to_string(),
+                min_warm: 2,
+                max_warm: 4,
+            }],
+            health: PoolHealthConfig {
+                eviction_threshold: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .map_service(|s| s.with_pool_health_probe(Arc::new(probe.clone())));
+        let broken = harness.add_warm_sandbox("python3.11").await.unwrap();
+        let healthy = harness.add_warm_sandbox("python3.11").await.unwrap();
+        probe.fail(broken);
+
+        let report = harness.service.check_pool_health().await;
+        assert_eq!(report.unhealthy, vec![broken]);
+        assert_eq!(report.evicted.len(), 1);
+        assert_eq!(report.replaced, 1);
+        assert_eq!(
+            harness.backend.terminated_handles(),
+            vec![report.evicted[0].runtime_handle.clone().unwrap()]
+        );
+        let record = harness.sandboxes.get(broken).await.unwrap().unwrap();
+        assert!(matches!(record.state, SandboxState::Terminated { .. }));
+
+        let stats = harness.service.pool_stats().await;
+        assert_eq!((stats.total, stats.ready), (2, 2));
+        let health = harness.service.pool_health().await;
+        assert!(health.healthy);
+        assert_eq!(health.evictions, 1);
+
+        // Neither checkout gets the evicted sandbox
+        for _ in 0..2 {
+            let sandbox = harness
+                .service
+                .create_sandbox(
+                    OrganizationId::new(),
+                    AgentId::new(),
+                    SandboxConfig::default(),
+                )
+                .await
+                .unwrap();
+            assert_ne!(sandbox.id, broken);
+        }
+        assert_eq!(harness.service.pool_stats().await.hits, 2);
+        assert!(probe.probes().contains(&healthy));
+    }
+
+    fn limit_harness(
+        clock: &Arc<creto_common::TestClock>,
+        sink: &crate::limits::InMemoryLimitEventSink,
+    ) -> RuntimeTestHarness {
+        let (clock, sink) = (clock.clone(), sink.clone());
+        RuntimeTestHarness::new().map_service(|service| {
+            service
+                .with_clock(clock)
+                .with_limit_event_sink(Box::new(sink))
+        })
+    }
+
+    #[tokio::test]
+    async fn test_limit_warning_then_cpu_termination() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let sink = crate::limits::InMemoryLimitEventSink::new();
+        let harness = limit_harness(&clock, &sink);
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        let handle = sandbox.runtime_handle.clone().unwrap();
+
+        // 80% of the default 60s CPU time and 300s wall time
+        harness.backend.set_usage(
+            &handle,
+            ResourceUsage {
+                cpu_time_ms: 48_000,
+                ..Default::default()
+            },
+        );
+        clock.advance(chrono::Duration::seconds(240));
+        let report = harness.service.enforce_limits().await.unwrap();
+        let mut warned: Vec<_> = report.warned.iter().map(|b| b.limit).collect();
+        warned.sort_by_key(LimitKind::as_str);
+        assert_eq!(warned, vec![LimitKind::CpuTime, LimitKind::WallTime]);
+        assert!(report.terminated.is_empty());
+        assert_eq!(sink.events().len(), 2);
+
+        // Each warning is sent once
+        assert_eq!(
+            harness.service.enforce_limits().await.unwrap(),
+            LimitEnforcementReport::default()
+        );
+
+        harness.backend.set_usage(
+            &handle,
+            ResourceUsage {
+                cpu_time_ms: 60_000,
+                ..Default::default()
+            },
+        );
+        let report = harness.service.enforce_limits().await.unwrap();
+        assert_eq!(report.terminated.len(), 1);
+        assert_eq!(report.terminated[0].limit, LimitKind::CpuTime);
+        assert_eq!(report.terminated[0].used, 60_000);
+        assert!(matches!(
+            sink.events().last(),
+            Some(LimitEvent::Exceeded(breach)) if breach.sandbox_id == sandbox.id
+        ));
+
+        let record = harness.sandboxes.get(sandbox.id).await.unwrap().unwrap();
+        assert_eq!(
+            record.state,
+            SandboxState::Terminated {
+                reason: TerminationReason::LimitExceeded
+            }
+        );
+        assert_eq!(harness.backend.terminated_handles(), vec![handle]);
+        let snapshots = harness.usage.snapshots(sandbox.id);
+        assert_eq!(snapshots.last().unwrap().cpu_time_ms, 60_000);
+        assert_eq!(
+            harness.service.enforce_limits().await.unwrap(),
+            LimitEnforcementReport::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wall_time_limit_cancels_execution_and_leaves_pool() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let sink = crate::limits::InMemoryLimitEventSink::new();
+        let harness = limit_harness(&clock, &sink);
+        let warm_id = harness.add_warm_sandbox("python3.11").await.unwrap();
+        let executor = harness.executor.clone();
+        let service = Arc::new(harness.service);
+
+        let org_id = OrganizationId::new();
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        assert_eq!(sandbox.id, warm_id);
+        assert_eq!(service.pool().stats().await.total, 1);
+
+        let _release = executor.push_hold();
+        let running = service
+            .submit_execution(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "while True: pass"),
+            )
+            .unwrap();
+        started(&executor, 1).await;
+
+        clock.advance(chrono::Duration::seconds(300));
+        let report = service.enforce_limits().await.unwrap();
+        assert_eq!(report.terminated.len(), 1);
+        assert_eq!(report.terminated[0].limit, LimitKind::WallTime);
+
+        let result = running.wait().await.unwrap();
+        assert_eq!(result.status, ExecutionStatus::Cancelled);
+        let error = result.error.unwrap();
+        assert_eq!(error.code, "RESOURCE_LIMIT_EXCEEDED");
+        assert_eq!(error.limit, Some(LimitKind::WallTime));
+
+        assert_eq!(service.pool().stats().await.total, 0);
+        assert!(service.pool().remove(sandbox.id).await.is_none());
+        let record = harness.sandboxes.get(sandbox.id).await.unwrap().unwrap();
+        assert_eq!(
+            record.state,
+            SandboxState::Terminated {
+                reason: TerminationReason::LimitExceeded
+            }
+        );
+        let snapshots = harness.usage.snapshots(sandbox.id);
+        assert_eq!(snapshots.last().unwrap().wall_time_ms, 300_000);
+    }
+
+    /// Wait until `executor` has received `count` requests.
+    async fn started(executor: &ScriptedExecutor, count: usize) {
+        while executor.requests().len() < count {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exclusion_conflict_fails_fast() {
+        let executor = ScriptedExecutor::new();
+        let service = Arc::new(RuntimeService::new().with_executor(Box::new(executor.clone())));
+        let org_id = OrganizationId::new();
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+
+        let release = executor.push_hold();
+        let holder = service
+            .submit_execution(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "migrate()").with_exclusion_key("db:orders"),
+            )
+            .unwrap();
+        started(&executor, 1).await;
+
+        // Even with `queue` set there is no admission queue to wait in
+        let err = service
+            .execute_request(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "migrate()")
+                    .with_exclusion_key("db:orders")
+                    .queued(),
+            )
+            .await
+            .unwrap_err();
+        match err {
+            CretoError::ExclusionConflict { key, holder: id } => {
+                assert_eq!(key, "db:orders");
+                assert_eq!(id, holder.id());
+            }
+            other => panic!("expected an exclusion conflict, got {other:?}"),
+        }
+
+        // Keys are scoped per organization
+        let other_org = service
+            .execute_request(
+                OrganizationId::new(),
+                ExecutionRequest::new(sandbox.id, "migrate()").with_exclusion_key("db:orders"),
+            )
+            .await
+            .unwrap();
+        assert!(other_org.is_success());
+
+        let stats = service.runtime_stats().await.unwrap();
+        assert_eq!(stats.exclusion_locks.len(), 1);
+        assert_eq!(stats.exclusion_locks[0].execution_id, holder.id());
+
+        drop(release);
+        assert!(holder.wait().await.unwrap().is_success());
+        assert!(service
+            .runtime_stats()
+            .await
+            .unwrap()
+            .exclusion_locks
+            .is_empty());
+        assert!(service
+            .execute_request(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "migrate()").with_exclusion_key("db:orders"),
+            )
+            .await
+            .unwrap()
+            .is_success());
+    }
+
+    #[tokio::test]
+    async fn test_execute_streaming_enforces_sandbox_output_limit() {
+        use tokio_stream::StreamExt;
+
+        let executor = ScriptedExecutor::new();
+        let service = Arc::new(RuntimeService::new().with_executor(Box::new(executor.clone())));
+        let org_id = OrganizationId::new();
+        let config = SandboxConfig {
+            limits: Some(ResourceLimits::default().with_max_output(Some(8))),
+            ..Default::default()
+        };
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), config)
+            .await
+            .unwrap();
+        executor.push_output(
+            Duration::ZERO,
+            [
+                ExecutionChunk::Stdout(b"12345".to_vec()),
+                ExecutionChunk::Stdout(b"67890".to_vec()),
+            ],
+        );
+
+        let chunks: Vec<ExecutionChunk> = service
+            .execute_streaming(org_id, ExecutionRequest::new(sandbox.id, "spam()"))
+            .unwrap()
+            .collect()
+            .await;
+        let stdout: Vec<u8> = chunks
+            .iter()
+            .filter_map(|chunk| match chunk {
+                ExecutionChunk::Stdout(bytes) => Some(bytes.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        assert_eq!(stdout, b"12345678");
+        assert!(matches!(
+            chunks[0],
+            ExecutionChunk::Status(ExecutionStatus::Running)
+        ));
+        assert!(matches!(
+            chunks[chunks.len() - 2],
+            ExecutionChunk::Status(ExecutionStatus::Failed)
+        ));
+        match chunks.last() {
+            Some(ExecutionChunk::Result(result)) => {
+                assert_eq!(result.error.as_ref().unwrap().code, "OUTPUT_LIMIT_EXCEEDED");
+                assert_eq!(result.stdout.as_deref(), Some("12345678"));
+            }
+            other => panic!("expected a final result, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exclusion_waiters_run_in_priority_order() {
+        let executor = ScriptedExecutor::new();
+        let service = Arc::new(
+            RuntimeService::new()
+                .with_executor(Box::new(executor.clone()))
+                .with_execution_queue(ExecutionQueueConfig::default()),
+        );
+        let org_id = OrganizationId::new();
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        let submit = |code: &str, priority| {
+            service
+                .submit_execution(
+                    org_id,
+                    ExecutionRequest::new(sandbox.id, code)
+                        .with_exclusion_key("db:orders")
+                        .with_priority(priority)
+                        .queued(),
+                )
+                .unwrap()
+        };
+
+        let release = executor.push_hold();
+        let first = submit("first", ExecutionPriority::Normal);
+        started(&executor, 1).await;
+        let second = submit("second", ExecutionPriority::Normal);
+        let urgent = submit("urgent", ExecutionPriority::High);
+        let third = submit("third", ExecutionPriority::Normal);
+
+        // Nothing else starts while the key is held, despite free slots
+        tokio::task::yield_now().await;
+        assert_eq!(executor.requests().len(), 1);
 
-    /// Terminate a sandbox.
-    pub async fn terminate_sandbox(&self, sandbox_id: SandboxId) -> CretoResult<()> {
-        // Remove from pool and terminate
-        if let Some(mut sandbox) = self.pool.remove(sandbox_id).await {
-            sandbox.mark_terminated();
-            // TODO: Actually terminate via backend
-            // backend.terminate(&sandbox.runtime_handle.unwrap()).await?;
+        drop(release);
+        for waiting in [first, second, urgent, third] {
+            assert!(waiting.wait().await.unwrap().is_success());
         }
-        Ok(())
+        let codes: Vec<String> = executor.requests().into_iter().map(|r| r.code).collect();
+        assert_eq!(codes, vec!["first", "urgent", "second", "third"]);
     }
 
-    /// Get pool statistics.
-    pub async fn pool_stats(&self) -> crate::pool::PoolStats {
-        self.pool.stats().await
-    }
+    #[tokio::test]
+    async fn test_exclusion_preemption_cancels_lower_priority_holder() {
+        let executor = ScriptedExecutor::new();
+        let service = Arc::new(RuntimeService::new().with_executor(Box::new(executor.clone())));
+        let org_id = OrganizationId::new();
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
 
-    /// Cleanup idle sandboxes.
-    pub async fn cleanup_idle(&self) -> CretoResult<usize> {
-        let removed = self.pool.cleanup_idle().await?;
-        Ok(removed.len())
-    }
+        let _release = executor.push_hold();
+        let holder = service
+            .submit_execution(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "backfill()")
+                    .with_exclusion_key("db:orders")
+                    .with_priority(ExecutionPriority::Low),
+            )
+            .unwrap();
+        started(&executor, 1).await;
 
-    /// Create a checkpoint of a sandbox.
-    ///
-    /// The sandbox must be in a state that allows checkpointing (Ready, Paused, or Stopped).
-    /// Returns the ID of the created checkpoint.
-    pub async fn checkpoint(&self, sandbox_id: SandboxId) -> CretoResult<CheckpointId> {
-        // TODO: Get the actual sandbox from pool or repository
-        // For now, use default config
-        let config = CheckpointConfig::default();
+        // Preemption needs a strictly higher priority
+        assert!(matches!(
+            service
+                .execute_request(
+                    org_id,
+                    ExecutionRequest::new(sandbox.id, "backfill()")
+                        .with_exclusion_key("db:orders")
+                        .with_priority(ExecutionPriority::Low)
+                        .preempting(),
+                )
+                .await,
+            Err(CretoError::ExclusionConflict { .. })
+        ));
 
-        tracing::info!(
-            sandbox_id = %sandbox_id,
-            "Creating checkpoint for sandbox"
-        );
+        let preemptor = service
+            .execute_request(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "hotfix()")
+                    .with_exclusion_key("db:orders")
+                    .with_priority(ExecutionPriority::Critical)
+                    .preempting(),
+            )
+            .await
+            .unwrap();
+        assert!(preemptor.is_success());
 
-        let checkpoint_id = self
-            .checkpoint_manager
-            .checkpoint(sandbox_id, config)
-            .await?;
+        let preempted = holder.wait().await.unwrap();
+        assert_eq!(preempted.status, ExecutionStatus::Cancelled);
+        assert_eq!(preempted.error.unwrap().code, "PREEMPTED");
+        assert!(service
+            .runtime_stats()
+            .await
+            .unwrap()
+            .exclusion_locks
+            .is_empty());
+    }
 
-        tracing::info!(
-            sandbox_id = %sandbox_id,
-            checkpoint_id = %checkpoint_id,
-            "Checkpoint created successfully"
+    #[tokio::test]
+    async fn test_exclusion_lease_renewed_while_held() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let store = Arc::new(InMemoryExclusionLeaseStore::new());
+        let executor = ScriptedExecutor::new();
+        let service = Arc::new(
+            RuntimeService::new()
+                .with_clock(clock.clone())
+                .with_executor(Box::new(executor.clone()))
+                .with_exclusion_leases(
+                    store.clone(),
+                    ExclusionConfig {
+                        heartbeat_interval: Duration::from_millis(10),
+                        ..ExclusionConfig::default()
+                    },
+                ),
         );
+        let org_id = OrganizationId::new();
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
 
-        Ok(checkpoint_id)
+        let release = executor.push_hold();
+        let holder = service
+            .submit_execution(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "migrate()").with_exclusion_key("db"),
+            )
+            .unwrap();
+        started(&executor, 1).await;
+        let first_expiry = store.list().await.unwrap()[0].expires_at;
+
+        // Past the original expiry, the heartbeat has pushed the lease out
+        // without anyone spawning it
+        clock.advance(chrono::Duration::seconds(60));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while store.list().await.unwrap()[0].expires_at <= clock.now() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("lease should be renewed");
+        assert!(store.list().await.unwrap()[0].expires_at > first_expiry);
+
+        drop(release);
+        assert!(holder.wait().await.unwrap().is_success());
+        assert!(store.list().await.unwrap().is_empty());
     }
 
-    /// Restore a sandbox from a checkpoint.
-    ///
-    /// Creates a new sandbox (or reuses an existing one) and restores it to the
-    /// state captured in the checkpoint.
-    pub async fn restore(&self, checkpoint_id: CheckpointId) -> CretoResult<Sandbox> {
-        tracing::info!(
-            checkpoint_id = %checkpoint_id,
-            "Restoring sandbox from checkpoint"
+    #[test]
+    fn test_default_node_id_is_stable() {
+        assert_eq!(
+            ExclusionConfig::default().node_id,
+            ExclusionConfig::default().node_id
         );
+    }
 
-        let sandbox_id = self.checkpoint_manager.restore(checkpoint_id).await?;
+    #[tokio::test]
+    async fn test_exclusion_leases_recovered_after_restart() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let store = Arc::new(InMemoryExclusionLeaseStore::new());
+        let executions = crate::testing::InMemoryExecutionRepository::new();
+        let node = |node_id: &str| {
+            RuntimeService::new()
+                .with_clock(clock.clone())
+                .with_execution_repository(Box::new(executions.clone()))
+                .with_exclusion_leases(
+                    store.clone(),
+                    ExclusionConfig {
+                        node_id: node_id.to_string(),
+                        ..ExclusionConfig::default()
+                    },
+                )
+        };
+        let org_id = OrganizationId::new();
+        let sandbox_id = SandboxId::new();
+        let request = || ExecutionRequest::new(sandbox_id, "migrate()").with_exclusion_key("db");
 
-        // TODO: Actually create/retrieve the sandbox instance
-        // For now, create a mock sandbox
-        let checkpoint = self
-            .checkpoint_manager
-            .get_checkpoint(checkpoint_id)
-            .await?;
+        // Node A crashed mid-execution, leaving its lease behind
+        let crashed = executions
+            .create(sandbox_id, "migrate()", 300, Correlation::default())
+            .await
+            .unwrap();
+        executions.mark_started(crashed).await.unwrap();
+        let now = clock.now();
+        store
+            .acquire(
+                &ExclusionLease {
+                    organization_id: org_id,
+                    key: "db".to_string(),
+                    execution_id: crashed,
+                    priority: ExecutionPriority::Normal,
+                    owner: "node-a".to_string(),
+                    acquired_at: now,
+                    expires_at: now + chrono::Duration::seconds(30),
+                },
+                now,
+            )
+            .await
+            .unwrap();
 
-        let mut sandbox = Sandbox::new(
-            OrganizationId::new(),
-            checkpoint.agent_id,
-            SandboxConfig::default(),
+        // Other nodes see the key as held until the lease lapses
+        let node_b = node("node-b");
+        assert!(matches!(
+            node_b.execute_request(org_id, request()).await,
+            Err(CretoError::ExclusionConflict { holder, .. }) if holder == crashed
+        ));
+        assert_eq!(
+            node_b.runtime_stats().await.unwrap().exclusion_locks.len(),
+            1
         );
-        sandbox.id = sandbox_id;
-        sandbox.state = SandboxState::Ready;
 
-        tracing::info!(
-            checkpoint_id = %checkpoint_id,
-            sandbox_id = %sandbox_id,
-            "Sandbox restored successfully"
+        // Node A restarts under the same ID and releases it straight away
+        let node_a = node("node-a");
+        node_a.initialize().await.unwrap();
+        assert_eq!(
+            executions.get(crashed).await.unwrap().unwrap().status,
+            ExecutionStatus::Failed
         );
+        assert!(node_b
+            .runtime_stats()
+            .await
+            .unwrap()
+            .exclusion_locks
+            .is_empty());
+        assert!(node_b
+            .execute_request(org_id, request())
+            .await
+            .unwrap()
+            .is_success());
 
-        Ok(sandbox)
+        // A lease left by a node that never comes back lapses on its own
+        let now = clock.now();
+        let stranded = ExclusionLease {
+            organization_id: org_id,
+            key: "db".to_string(),
+            execution_id: Uuid::now_v7(),
+            priority: ExecutionPriority::Normal,
+            owner: "node-c".to_string(),
+            acquired_at: now,
+            expires_at: now + chrono::Duration::seconds(30),
+        };
+        store.acquire(&stranded, now).await.unwrap();
+        assert!(node_b.execute_request(org_id, request()).await.is_err());
+        clock.advance(chrono::Duration::seconds(31));
+        assert!(node_b
+            .execute_request(org_id, request())
+            .await
+            .unwrap()
+            .is_success());
     }
 
-    /// List checkpoints, optionally filtered by sandbox or agent.
-    pub async fn list_checkpoints(
-        &self,
-        sandbox_id: Option<SandboxId>,
-        agent_id: Option<AgentId>,
-    ) -> CretoResult<Vec<crate::checkpoint::Checkpoint>> {
-        self.checkpoint_manager
-            .list_checkpoints(sandbox_id, agent_id)
-            .await
+    fn idle_harness(clock: &Arc<creto_common::TestClock>) -> RuntimeTestHarness {
+        let clock = clock.clone();
+        RuntimeTestHarness::new().map_service(|service| {
+            service.with_clock(clock).with_idle_config(IdleConfig {
+                default_policy: IdlePolicy {
+                    pause_after_seconds: 60,
+                    hibernate_after_seconds: Some(600),
+                    max_keep_warm: 1,
+                },
+                ..IdleConfig::default()
+            })
+        })
     }
 
-    /// Delete a checkpoint.
-    pub async fn delete_checkpoint(&self, checkpoint_id: CheckpointId) -> CretoResult<()> {
-        tracing::info!(
-            checkpoint_id = %checkpoint_id,
-            "Deleting checkpoint"
+    #[tokio::test]
+    async fn test_idle_sandbox_paused_then_hibernated() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let harness = idle_harness(&clock);
+        let sandbox = harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+        let handle = sandbox.runtime_handle.clone().unwrap();
+
+        clock.advance(chrono::Duration::seconds(59));
+        assert_eq!(
+            harness.service.run_idle_sweep().await.unwrap(),
+            IdleSweepReport::default()
         );
 
-        self.checkpoint_manager
-            .delete_checkpoint(checkpoint_id)
-            .await?;
+        // Streaming output counts as activity
+        assert!(harness.service.record_sandbox_activity(sandbox.id));
+        clock.advance(chrono::Duration::seconds(59));
+        assert!(harness
+            .service
+            .run_idle_sweep()
+            .await
+            .unwrap()
+            .paused
+            .is_empty());
 
-        tracing::info!(
-            checkpoint_id = %checkpoint_id,
-            "Checkpoint deleted successfully"
+        clock.advance(chrono::Duration::seconds(1));
+        let report = harness.service.run_idle_sweep().await.unwrap();
+        assert_eq!(report.paused, vec![sandbox.id]);
+        assert!(matches!(
+            harness.service.sandbox_idle_state(sandbox.id),
+            Some(IdleState::Paused { .. })
+        ));
+        assert_eq!(harness.backend.stopped_handles(), vec![handle.clone()]);
+        let record = harness.sandboxes.get(sandbox.id).await.unwrap().unwrap();
+        assert_eq!(record.state, SandboxState::Paused);
+
+        // Hibernation counts from the last activity, not from the pause
+        clock.advance(chrono::Duration::seconds(540));
+        let report = harness.service.run_idle_sweep().await.unwrap();
+        assert_eq!(report.hibernated, vec![sandbox.id]);
+        let Some(IdleState::Hibernated { checkpoint_id, .. }) =
+            harness.service.sandbox_idle_state(sandbox.id)
+        else {
+            panic!("sandbox should be hibernated");
+        };
+        assert_eq!(harness.backend.terminated_handles(), vec![handle]);
+        let checkpoints = harness
+            .service
+            .list_checkpoints(Some(sandbox.id), None)
+            .await
+            .unwrap();
+        assert_eq!(checkpoints.len(), 1);
+        assert_eq!(checkpoints[0].id, checkpoint_id);
+        assert_eq!(
+            checkpoints[0].metadata.get("reason").map(String::as_str),
+            Some("idle_hibernation")
         );
 
-        Ok(())
-    }
-}
+        // Nothing left to do until the sandbox is used again
+        clock.advance(chrono::Duration::hours(1));
+        assert_eq!(
+            harness.service.run_idle_sweep().await.unwrap(),
+            IdleSweepReport::default()
+        );
 
-impl Default for RuntimeService {
-    fn default() -> Self {
-        Self::new()
+        // Terminating a hibernated sandbox drops its checkpoint
+        harness.service.terminate_sandbox(sandbox.id).await.unwrap();
+        assert!(harness
+            .service
+            .list_checkpoints(Some(sandbox.id), None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(harness.service.sandbox_idle_state(sandbox.id), None);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[tokio::test]
-    async fn test_service_creation() {
-        let service = RuntimeService::new();
-        let stats = service.pool_stats().await;
-        assert_eq!(stats.total, 0);
+    async fn test_audit_events_for_sandbox_lifecycle() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let audit = InMemoryAuditSink::new();
+        let harness = idle_harness(&clock).map_service({
+            let audit = audit.clone();
+            |service| {
+                service
+                    .with_attestation_generator(
+                        Box::new(MockAttestationProvider::new()),
+                        AttestationPlatform::GVisor,
+                    )
+                    .with_attestation_verifier(Box::new(MockAttestationProvider::new()))
+                    .with_audit_sink(Box::new(audit))
+            }
+        });
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, agent_id, SandboxConfig::default())
+            .await
+            .unwrap();
+
+        harness.service.execute(sandbox.id, "run()").await.unwrap();
+        clock.advance(chrono::Duration::seconds(60));
+        harness.service.run_idle_sweep().await.unwrap();
+        harness.service.execute(sandbox.id, "run()").await.unwrap();
+        harness.service.terminate_sandbox(sandbox.id).await.unwrap();
+
+        assert_eq!(
+            audit.actions(),
+            vec![
+                "runtime.attestation.generated",
+                "runtime.sandbox.created",
+                "runtime.attestation.verified",
+                "runtime.execution.finished",
+                "runtime.sandbox.paused",
+                "runtime.sandbox.resumed",
+                "runtime.attestation.verified",
+                "runtime.execution.finished",
+                "runtime.sandbox.terminated",
+            ]
+        );
+        for event in audit.events() {
+            assert_eq!(event.organization_id, org_id);
+            assert_eq!(event.subject_id, sandbox.id.as_uuid());
+            assert_eq!(event.actor, creto_common::AuditActor::Agent { agent_id });
+            assert_eq!(event.outcome, creto_common::AuditOutcome::Success);
+            assert_eq!(event.detail["sandbox_id"], serde_json::json!(sandbox.id));
+            assert_eq!(event.detail["agent_id"], serde_json::json!(agent_id));
+        }
+        let execution = &audit.events()[3];
+        assert_eq!(execution.detail["status"], "completed");
+        assert!(execution.detail["execution_id"].is_string());
+        assert!(execution.detail.get("code").is_none());
+        assert!(execution.detail.get("stdout").is_none());
     }
 
     #[tokio::test]
-    async fn test_create_sandbox() {
-        let service = RuntimeService::new();
-
-        let sandbox = service
-            .create_sandbox(
-                OrganizationId::new(),
-                AgentId::new(),
-                SandboxConfig::default(),
-            )
+    async fn test_execute_resumes_idle_sandbox() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let store = InMemorySecretGrantStore::new();
+        let harness = idle_harness(&clock).map_service({
+            let store = store.clone();
+            |service| service.with_secret_grant_store(Box::new(store))
+        });
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let sandbox = harness
+            .service
+            .create_sandbox(org_id, agent_id, SandboxConfig::default())
             .await
             .unwrap();
+        let handle = sandbox.runtime_handle.clone().unwrap();
 
-        assert_eq!(sandbox.config.runtime, "python3.11");
+        let retained = SecretMount::env_var("DB_URL", org_secret("db_url"));
+        let revoked = SecretMount::env_var("OPENAI_API_KEY", org_secret("openai_key"))
+            .with_idle_policy(SecretIdlePolicy::Revoke);
+        for mount in [&retained, &revoked] {
+            let grant = SecretGrant::new(org_id, sandbox.id, agent_id, mount);
+            store.record_grant(&grant).await.unwrap();
+        }
+
+        clock.advance(chrono::Duration::seconds(60));
+        harness.service.run_idle_sweep().await.unwrap();
+        let grants = harness.service.secret_grants(sandbox.id).await.unwrap();
+        for grant in &grants {
+            let expect_revoked = grant.idle_policy == SecretIdlePolicy::Revoke;
+            assert_eq!(grant.revoked_at.is_some(), expect_revoked);
+        }
+
+        let result = harness.service.execute(sandbox.id, "run()").await.unwrap();
+        assert!(result.timing.resume_ms.is_some());
+        assert_eq!(
+            harness.service.sandbox_idle_state(sandbox.id),
+            Some(IdleState::Active)
+        );
+        assert!(harness.backend.stopped_handles().is_empty());
+        assert_eq!(harness.executor.requests()[0].sandbox_id, sandbox.id);
+        let record = harness.sandboxes.get(sandbox.id).await.unwrap().unwrap();
+        assert_eq!(record.state, SandboxState::Ready);
+
+        // A running sandbox reports no resume
+        let result = harness.service.execute(sandbox.id, "run()").await.unwrap();
+        assert_eq!(result.timing.resume_ms, None);
+
+        // A hibernated sandbox comes back on a new instance
+        clock.advance(chrono::Duration::seconds(600));
+        harness.service.run_idle_sweep().await.unwrap();
+        assert!(matches!(
+            harness.service.sandbox_idle_state(sandbox.id),
+            Some(IdleState::Hibernated { .. })
+        ));
+        let result = harness.service.execute(sandbox.id, "run()").await.unwrap();
+        assert!(result.timing.resume_ms.is_some());
+        assert_eq!(harness.backend.terminated_handles(), vec![handle.clone()]);
+        let live = harness.backend.live_handles();
+        assert_eq!(live.len(), 1);
+        assert_ne!(live[0], handle);
+        assert!(harness
+            .service
+            .list_checkpoints(Some(sandbox.id), None)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The idle clock restarts when the execution ends
+        clock.advance(chrono::Duration::seconds(59));
+        assert!(harness
+            .service
+            .run_idle_sweep()
+            .await
+            .unwrap()
+            .paused
+            .is_empty());
     }
 
     #[tokio::test]
-    async fn test_execute() {
-        let service = RuntimeService::new();
-
-        let sandbox = service
+    async fn test_hibernation_preserves_sandbox_state() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let harness = idle_harness(&clock);
+        let sandbox = harness
+            .service
             .create_sandbox(
                 OrganizationId::new(),
                 AgentId::new(),
@@ -313,16 +8847,55 @@ mod tests {
             )
             .await
             .unwrap();
+        let handle = sandbox.runtime_handle.clone().unwrap();
+        harness.backend.write_memory(&handle, b"counter=41");
 
-        let result = service.execute(sandbox.id, "print('hello')").await.unwrap();
-        assert!(result.is_success());
+        clock.advance(chrono::Duration::seconds(600));
+        let report = harness.service.run_idle_sweep().await.unwrap();
+        assert_eq!(report.hibernated, vec![sandbox.id]);
+        assert!(harness.backend.live_handles().is_empty());
+
+        // A failed restore keeps the sandbox hibernated, with its checkpoint
+        harness
+            .backend
+            .fail_next_restore(CretoError::Internal("restore failed".to_string()));
+        assert!(harness.service.execute(sandbox.id, "run()").await.is_err());
+        assert!(matches!(
+            harness.service.sandbox_idle_state(sandbox.id),
+            Some(IdleState::Hibernated { .. })
+        ));
+        assert_eq!(
+            harness
+                .service
+                .list_checkpoints(Some(sandbox.id), None)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        harness.service.execute(sandbox.id, "run()").await.unwrap();
+        let live = harness.backend.live_handles();
+        assert_eq!(live.len(), 1);
+        assert_eq!(
+            harness.backend.memory(&live[0]).as_deref(),
+            Some(&b"counter=41"[..])
+        );
+        assert!(harness
+            .service
+            .list_checkpoints(Some(sandbox.id), None)
+            .await
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
-    async fn test_checkpoint_and_restore() {
-        let service = RuntimeService::new();
-
-        let sandbox = service
+    async fn test_idle_sandbox_not_hibernated_without_backend_restore() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let harness = idle_harness(&clock);
+        harness.backend.disable_restore();
+        let sandbox = harness
+            .service
             .create_sandbox(
                 OrganizationId::new(),
                 AgentId::new(),
@@ -330,73 +8903,205 @@ mod tests {
             )
             .await
             .unwrap();
+        let handle = sandbox.runtime_handle.clone().unwrap();
 
-        // Create checkpoint
-        let checkpoint_id = service.checkpoint(sandbox.id).await.unwrap();
-        assert!(!checkpoint_id.to_string().is_empty());
-
-        // Restore from checkpoint
-        let restored = service.restore(checkpoint_id).await.unwrap();
-        assert_eq!(restored.state, SandboxState::Ready);
+        // Past the hibernation threshold, it is paused instead
+        clock.advance(chrono::Duration::seconds(600));
+        let report = harness.service.run_idle_sweep().await.unwrap();
+        assert_eq!(report.paused, vec![sandbox.id]);
+        clock.advance(chrono::Duration::seconds(600));
+        assert_eq!(
+            harness.service.run_idle_sweep().await.unwrap(),
+            IdleSweepReport::default()
+        );
+        assert!(matches!(
+            harness.service.sandbox_idle_state(sandbox.id),
+            Some(IdleState::Paused { .. })
+        ));
+        assert!(harness.backend.terminated_handles().is_empty());
+        assert_eq!(harness.backend.stopped_handles(), vec![handle]);
     }
 
     #[tokio::test]
-    async fn test_list_checkpoints() {
-        let service = RuntimeService::new();
-        let sandbox_id = SandboxId::new();
+    async fn test_keep_warm_quota() {
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let harness = idle_harness(&clock);
+        let org_id = OrganizationId::new();
+        let keep_warm = SandboxConfig {
+            keep_warm: true,
+            ..SandboxConfig::default()
+        };
 
-        // Create multiple checkpoints
-        service.checkpoint(sandbox_id).await.unwrap();
-        service.checkpoint(sandbox_id).await.unwrap();
+        let warm = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), keep_warm.clone())
+            .await
+            .unwrap();
+        let err = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), keep_warm.clone())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CretoError::QuotaExceeded { limit: 1, .. }));
+        let other = harness
+            .service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            harness.service.set_keep_warm(other.id, true).await,
+            Err(CretoError::QuotaExceeded { .. })
+        ));
 
-        let checkpoints = service
-            .list_checkpoints(Some(sandbox_id), None)
+        // The quota is per organization, and can be raised by org policy
+        harness
+            .service
+            .create_sandbox(OrganizationId::new(), AgentId::new(), keep_warm.clone())
             .await
             .unwrap();
-        assert_eq!(checkpoints.len(), 2);
-    }
+        let mut policy = OrgRuntimePolicy::new(org_id).with_idle_policy(IdlePolicy {
+            pause_after_seconds: 60,
+            hibernate_after_seconds: None,
+            max_keep_warm: 2,
+        });
+        policy = harness
+            .service
+            .set_org_runtime_policy(policy)
+            .await
+            .unwrap();
+        assert!(policy.idle.is_some());
+        harness.service.set_keep_warm(other.id, true).await.unwrap();
 
-    #[tokio::test]
-    async fn test_delete_checkpoint() {
-        let service = RuntimeService::new();
-        let sandbox_id = SandboxId::new();
+        clock.advance(chrono::Duration::hours(1));
+        assert!(harness
+            .service
+            .run_idle_sweep()
+            .await
+            .unwrap()
+            .paused
+            .is_empty());
+        assert_eq!(
+            harness.service.sandbox_idle_state(warm.id),
+            Some(IdleState::Active)
+        );
+        assert_eq!(
+            harness.service.sandbox_idle_state(other.id),
+            Some(IdleState::Active)
+        );
 
-        let checkpoint_id = service.checkpoint(sandbox_id).await.unwrap();
+        // Dropping the exemption makes the sandbox eligible again
+        harness.service.set_keep_warm(warm.id, false).await.unwrap();
+        clock.advance(chrono::Duration::seconds(60));
+        let report = harness.service.run_idle_sweep().await.unwrap();
+        assert_eq!(report.paused, vec![warm.id]);
+        assert!(matches!(
+            harness.service.set_keep_warm(SandboxId::new(), true).await,
+            Err(CretoError::SandboxNotFound(_))
+        ));
+    }
 
-        // Delete should succeed
-        service.delete_checkpoint(checkpoint_id).await.unwrap();
+    struct FixedQuota(Vec<crate::context::QuotaRemaining>);
 
-        // Deleting again should fail
-        let result = service.delete_checkpoint(checkpoint_id).await;
-        assert!(result.is_err());
+    #[async_trait::async_trait]
+    impl QuotaStatusProvider for FixedQuota {
+        async fn remaining_quota(
+            &self,
+            _organization_id: OrganizationId,
+            _agent_id: AgentId,
+            metrics: &[String],
+        ) -> CretoResult<Vec<crate::context::QuotaRemaining>> {
+            Ok(self
+                .0
+                .iter()
+                .filter(|q| metrics.contains(&q.metric_code))
+                .cloned()
+                .collect())
+        }
     }
 
     #[tokio::test]
-    async fn test_checkpoint_state_transitions() {
-        let service = RuntimeService::new();
-        let mut sandbox = Sandbox::new(
-            OrganizationId::new(),
-            AgentId::new(),
-            SandboxConfig::default(),
-        );
+    async fn test_execution_context_injected() {
+        use crate::context::{
+            QuotaRemaining, AGENT_ID_ENV, EXECUTION_ID_ENV, ORG_ID_ENV, QUOTA_SNAPSHOT_ENV,
+        };
 
-        // Can't checkpoint while Creating
-        assert!(!sandbox.state.can_checkpoint());
+        let executor = ScriptedExecutor::new();
+        let clock = Arc::new(creto_common::TestClock::new(Utc::now()));
+        let provider = FixedQuota(vec![
+            QuotaRemaining {
+                metric_code: "api_calls".to_string(),
+                remaining: 950,
+                limit: 1000,
+            },
+            QuotaRemaining {
+                metric_code: "tokens".to_string(),
+                remaining: 10,
+                limit: 20,
+            },
+        ]);
+        let service = RuntimeService::new()
+            .with_executor(Box::new(executor.clone()))
+            .with_clock(clock.clone())
+            .with_quota_status_provider(Arc::new(provider), vec!["api_calls".to_string()]);
+        let (org_id, agent_id) = (OrganizationId::new(), AgentId::new());
+        let sandbox = service
+            .create_sandbox(org_id, agent_id, SandboxConfig::default())
+            .await
+            .unwrap();
 
-        // Can checkpoint when Ready
-        sandbox.mark_ready("handle".to_string());
-        assert!(sandbox.state.can_checkpoint());
+        let result = service.execute(sandbox.id, "print(1)").await.unwrap();
+        assert!(result.warnings.is_empty());
 
-        // Can't checkpoint when Running
-        sandbox.mark_running();
-        assert!(!sandbox.state.can_checkpoint());
+        let request = &executor.requests()[0];
+        let env = |name: &str| {
+            request
+                .environment
+                .iter()
+                .find(|e| e.name == name)
+                .map(|e| e.value.clone())
+        };
+        assert_eq!(env(AGENT_ID_ENV), Some(agent_id.to_string()));
+        assert_eq!(env(ORG_ID_ENV), Some(org_id.to_string()));
+        assert_eq!(env(EXECUTION_ID_ENV), Some(result.request_id.to_string()));
 
-        // Can't checkpoint when Failed
-        sandbox.mark_failed();
-        assert!(!sandbox.state.can_checkpoint());
+        // Only the configured metrics, as of execution start
+        let snapshot: QuotaSnapshot =
+            serde_json::from_str(&env(QUOTA_SNAPSHOT_ENV).unwrap()).unwrap();
+        assert_eq!(snapshot.taken_at, clock.now());
+        assert_eq!(snapshot.quotas.len(), 1);
+        assert_eq!(snapshot.quotas["api_calls"].remaining, 950);
+        assert_eq!(snapshot.quotas["api_calls"].limit, 1000);
+    }
 
-        // Can't checkpoint when Terminated
-        sandbox.mark_terminated();
-        assert!(!sandbox.state.can_checkpoint());
+    #[tokio::test]
+    async fn test_request_env_overrides_context() {
+        use crate::context::ORG_ID_ENV;
+
+        let executor = ScriptedExecutor::new();
+        let service = RuntimeService::new().with_executor(Box::new(executor.clone()));
+        let org_id = OrganizationId::new();
+        let sandbox = service
+            .create_sandbox(org_id, AgentId::new(), SandboxConfig::default())
+            .await
+            .unwrap();
+
+        let result = service
+            .execute_request(
+                org_id,
+                ExecutionRequest::new(sandbox.id, "print(1)").with_env(ORG_ID_ENV, "override"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains(ORG_ID_ENV));
+
+        let request = &executor.requests()[0];
+        let values: Vec<_> = request
+            .environment
+            .iter()
+            .filter(|e| e.name == ORG_ID_ENV)
+            .map(|e| e.value.as_str())
+            .collect();
+        assert_eq!(values, vec!["override"]);
     }
 }
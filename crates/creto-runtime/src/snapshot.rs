@@ -0,0 +1,130 @@
+//! Runtime section of an organization snapshot.
+//!
+//! Summarizes the sandboxes the service is tracking for the organization:
+//! how many are active, paused and hibernated, how many executions are
+//! running, and the `top_n` most recently active sandboxes with their
+//! configured resource limits.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use creto_common::{CretoResult, SectionData, SnapshotContributor, SnapshotRequest};
+use serde::Serialize;
+
+use crate::idle::{IdleState, SandboxActivity};
+use crate::service::RuntimeService;
+
+/// Section name used by [`RuntimeSnapshotContributor`].
+pub const RUNTIME_SECTION: &str = "runtime";
+
+/// The runtime section's data.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuntimeSnapshot {
+    /// Tracked sandboxes.
+    pub sandbox_count: usize,
+    /// Sandboxes running or ready to run.
+    pub active: usize,
+    /// Sandboxes paused by the idle sweep.
+    pub paused: usize,
+    /// Sandboxes hibernated by the idle sweep.
+    pub hibernated: usize,
+    /// Keep-warm sandboxes.
+    pub keep_warm: usize,
+    /// Executions running across all sandboxes.
+    pub in_flight: usize,
+    /// Most recently active sandboxes, at most `top_n`.
+    pub recent_sandboxes: Vec<SandboxActivity>,
+}
+
+impl RuntimeSnapshot {
+    /// Summarize `activity`, most recently active first, keeping at most
+    /// `top_n` sandboxes.
+    pub fn from_activity(activity: Vec<SandboxActivity>, top_n: usize) -> Self {
+        let count = |f: fn(&SandboxActivity) -> bool| activity.iter().filter(|a| f(a)).count();
+        Self {
+            sandbox_count: activity.len(),
+            active: count(|a| a.state == IdleState::Active),
+            paused: count(|a| matches!(a.state, IdleState::Paused { .. })),
+            hibernated: count(|a| matches!(a.state, IdleState::Hibernated { .. })),
+            keep_warm: count(|a| a.keep_warm),
+            in_flight: activity.iter().map(|a| a.in_flight).sum(),
+            recent_sandboxes: activity.into_iter().take(top_n).collect(),
+        }
+    }
+}
+
+/// Contributes the runtime section of an organization snapshot.
+pub struct RuntimeSnapshotContributor {
+    service: Arc<RuntimeService>,
+}
+
+impl RuntimeSnapshotContributor {
+    pub fn new(service: Arc<RuntimeService>) -> Self {
+        Self { service }
+    }
+}
+
+#[async_trait]
+impl SnapshotContributor for RuntimeSnapshotContributor {
+    fn section(&self) -> &str {
+        RUNTIME_SECTION
+    }
+
+    async fn contribute(&self, request: &SnapshotRequest) -> CretoResult<SectionData> {
+        let activity = self.service.sandbox_activity(request.organization_id);
+        SectionData::new(
+            request.as_of,
+            &RuntimeSnapshot::from_activity(activity, request.top_n),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::SandboxConfig;
+    use crate::testing::RuntimeTestHarness;
+    use creto_common::{AgentId, OrganizationId, SnapshotAggregator, SnapshotMode};
+
+    #[tokio::test]
+    async fn test_runtime_section_counts_org_sandboxes() {
+        let harness = RuntimeTestHarness::new();
+        let org = OrganizationId::new();
+        for _ in 0..3 {
+            harness
+                .service
+                .create_sandbox(org, AgentId::new(), SandboxConfig::default())
+                .await
+                .unwrap();
+        }
+        harness
+            .service
+            .create_sandbox(
+                OrganizationId::new(),
+                AgentId::new(),
+                SandboxConfig::default(),
+            )
+            .await
+            .unwrap();
+
+        let aggregator = SnapshotAggregator::new()
+            .with_top_n(2)
+            .with_contributor(Arc::new(RuntimeSnapshotContributor::new(Arc::new(
+                harness.service,
+            ))));
+        let snapshot = aggregator.capture(org, SnapshotMode::Full).await;
+        let data = snapshot
+            .section(RUNTIME_SECTION)
+            .unwrap()
+            .data
+            .as_ref()
+            .unwrap();
+        assert_eq!(data["sandbox_count"], 3);
+        assert_eq!(data["active"], 3);
+        assert_eq!(data["in_flight"], 0);
+        let recent = data["recent_sandboxes"].as_array().unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0]["state"], "active");
+        assert!(recent[0]["limits"]["memory_bytes"].is_u64());
+    }
+}
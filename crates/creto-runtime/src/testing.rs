@@ -0,0 +1,1491 @@
+//! In-memory repositories and a service harness for testing.
+//!
+//! The repositories mirror the PostgreSQL implementations in
+//! [`crate::repository`] (generated IDs, state filters, ordering) so
+//! [`RuntimeService`] logic can be exercised without a database.
+//! [`RuntimeTestHarness`] wires them together with a [`ScriptedExecutor`],
+//! a [`MockSandboxBackend`], a [`ScriptedProvisioner`], a
+//! [`MockAttestationProvider`] and an [`InMemoryCheckpointStore`].
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+
+use chrono::{DateTime, Utc};
+use creto_common::{AgentId, Correlation, CretoError, CretoResult, OrganizationId};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::attestation::{AttestationPlatform, MockAttestationProvider};
+use crate::checkpoint::InMemoryCheckpointStore;
+use crate::execution::{
+    CodeExecutor, ExecutionChunk, ExecutionError, ExecutionRequest, ExecutionResult,
+    ExecutionStatus, ExecutionTiming, Executor, OutputSink,
+};
+use crate::history::{
+    ExecutionCursor, ExecutionFilter, ExecutionStats, Page, PagePosition, PageRequest, TimeRange,
+};
+use crate::idempotency::IdempotentExecution;
+use crate::network::EffectiveNetworkPolicy;
+use crate::pool::{HealthProbe, PoolConfig};
+use crate::provisioning::{
+    ProvisioningError, ProvisioningPhase, ProvisioningRetryPolicy, SandboxProvisioner,
+};
+use crate::repository::{
+    ExecutionRecord, ExecutionRepository, ProvisioningFailureRecord, ResourceUsageRepository,
+    SandboxRecord, SandboxRepository,
+};
+use crate::resources::{ResourceLimits, ResourceUsage};
+use crate::sampling::{ResourceController, UsageSample};
+use crate::sandbox::{
+    Sandbox, SandboxBackend, SandboxConfig, SandboxId, SandboxState, TerminationReason,
+};
+use crate::service::RuntimeService;
+use crate::triggers::{TopicSource, TopicTrigger, TriggerMessage};
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Sandbox Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// In-memory implementation of SandboxRepository.
+#[derive(Debug, Clone, Default)]
+pub struct InMemorySandboxRepository {
+    records: Arc<RwLock<Vec<SandboxRecord>>>,
+    provisioning_failures: Arc<RwLock<Vec<ProvisioningFailureRecord>>>,
+}
+
+impl InMemorySandboxRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn owner(&self, id: SandboxId) -> Option<(OrganizationId, AgentId)> {
+        self.records
+            .read()
+            .unwrap()
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| (r.organization_id, r.agent_id))
+    }
+
+    fn with_record<T>(&self, id: SandboxId, f: impl FnOnce(&mut SandboxRecord) -> T) -> Option<T> {
+        self.records
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|r| r.id == id)
+            .map(f)
+    }
+}
+
+#[async_trait::async_trait]
+impl SandboxRepository for InMemorySandboxRepository {
+    async fn create(
+        &self,
+        org_id: OrganizationId,
+        agent_id: AgentId,
+        runtime: &str,
+        network_policy: &str,
+        effective_network_policy: Option<&EffectiveNetworkPolicy>,
+        resource_limits: &ResourceLimits,
+    ) -> Result<SandboxId, CretoError> {
+        let id = SandboxId::new();
+        self.records.write().unwrap().push(SandboxRecord {
+            id,
+            organization_id: org_id,
+            agent_id,
+            runtime: runtime.to_string(),
+            state: SandboxState::Creating,
+            network_policy: network_policy.to_string(),
+            effective_network_policy: effective_network_policy.cloned(),
+            runtime_policy_version: None,
+            resource_limits: Some(resource_limits.clone()),
+            created_at: Utc::now(),
+            last_used_at: None,
+        });
+        Ok(id)
+    }
+
+    async fn get(&self, id: SandboxId) -> Result<Option<SandboxRecord>, CretoError> {
+        Ok(self
+            .records
+            .read()
+            .unwrap()
+            .iter()
+            .find(|r| r.id == id)
+            .cloned())
+    }
+
+    async fn update_state(&self, id: SandboxId, state: SandboxState) -> Result<(), CretoError> {
+        self.with_record(id, |r| {
+            r.state = state;
+            r.last_used_at = Some(Utc::now());
+        });
+        Ok(())
+    }
+
+    async fn update_network_policy(
+        &self,
+        id: SandboxId,
+        effective_network_policy: &EffectiveNetworkPolicy,
+    ) -> Result<(), CretoError> {
+        self.with_record(id, |r| {
+            r.effective_network_policy = Some(effective_network_policy.clone());
+        });
+        Ok(())
+    }
+
+    async fn assign(
+        &self,
+        id: SandboxId,
+        org_id: OrganizationId,
+        agent_id: AgentId,
+        effective_network_policy: Option<&EffectiveNetworkPolicy>,
+    ) -> Result<(), CretoError> {
+        self.with_record(id, |r| {
+            r.organization_id = org_id;
+            r.agent_id = agent_id;
+            r.effective_network_policy = effective_network_policy.cloned();
+        });
+        Ok(())
+    }
+
+    async fn record_runtime_policy(
+        &self,
+        id: SandboxId,
+        config: &SandboxConfig,
+        policy_version: u32,
+    ) -> Result<(), CretoError> {
+        self.with_record(id, |r| {
+            r.runtime = config.runtime.clone();
+            r.network_policy = config.network_policy.as_str().to_string();
+            r.runtime_policy_version = Some(policy_version);
+            r.resource_limits = Some(config.effective_limits());
+        });
+        Ok(())
+    }
+
+    async fn terminate(&self, id: SandboxId) -> Result<(), CretoError> {
+        self.with_record(id, |r| {
+            // Keep the reason of a sandbox already terminated by the runtime
+            if !matches!(r.state, SandboxState::Terminated { .. }) {
+                r.state = SandboxState::Terminated {
+                    reason: TerminationReason::Requested,
+                };
+            }
+        });
+        Ok(())
+    }
+
+    async fn list_active_by_org(
+        &self,
+        org_id: OrganizationId,
+    ) -> Result<Vec<SandboxRecord>, CretoError> {
+        let mut records: Vec<_> = self
+            .records
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| {
+                r.organization_id == org_id
+                    && !matches!(
+                        r.state,
+                        SandboxState::Terminated { .. } | SandboxState::Failed
+                    )
+            })
+            .cloned()
+            .collect();
+        // Newest first; later inserts win ties like a serial insert order would
+        records.reverse();
+        records.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+        Ok(records)
+    }
+
+    async fn list_active(&self) -> Result<Vec<SandboxRecord>, CretoError> {
+        let mut records: Vec<_> = self
+            .records
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| {
+                !matches!(
+                    r.state,
+                    SandboxState::Terminated { .. } | SandboxState::Failed
+                )
+            })
+            .cloned()
+            .collect();
+        records.reverse();
+        records.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+        Ok(records)
+    }
+
+    async fn find_idle(&self, idle_since: DateTime<Utc>) -> Result<Vec<SandboxId>, CretoError> {
+        Ok(self
+            .records
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| matches!(r.state, SandboxState::Ready | SandboxState::Paused))
+            .filter(|r| match r.last_used_at {
+                Some(last_used) => last_used < idle_since,
+                None => r.created_at < idle_since,
+            })
+            .map(|r| r.id)
+            .collect())
+    }
+
+    async fn record_provisioning_failure(
+        &self,
+        failure: &ProvisioningFailureRecord,
+    ) -> Result<(), CretoError> {
+        self.provisioning_failures
+            .write()
+            .unwrap()
+            .push(failure.clone());
+        Ok(())
+    }
+
+    async fn list_provisioning_failures(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<ProvisioningFailureRecord>, CretoError> {
+        let mut failures: Vec<_> = self
+            .provisioning_failures
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|f| f.failed_at >= since)
+            .cloned()
+            .collect();
+        failures.reverse();
+        failures.sort_by_key(|f| std::cmp::Reverse(f.failed_at));
+        Ok(failures)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Execution Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// In-memory implementation of ExecutionRepository.
+///
+/// History queries filtering by organization or agent look sandbox owners
+/// up in the repository given to
+/// [`InMemoryExecutionRepository::with_sandboxes`], as the PostgreSQL
+/// implementation joins `sandboxes`.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryExecutionRepository {
+    records: Arc<RwLock<Vec<ExecutionRecord>>>,
+    idempotent: Arc<RwLock<Vec<IdempotentExecution>>>,
+    sandboxes: InMemorySandboxRepository,
+}
+
+impl InMemoryExecutionRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve sandbox owners from `sandboxes`.
+    pub fn with_sandboxes(mut self, sandboxes: InMemorySandboxRepository) -> Self {
+        self.sandboxes = sandboxes;
+        self
+    }
+
+    /// Get every execution record, oldest first.
+    pub fn all(&self) -> Vec<ExecutionRecord> {
+        self.records.read().unwrap().clone()
+    }
+
+    fn with_record(&self, id: Uuid, f: impl FnOnce(&mut ExecutionRecord)) {
+        if let Some(record) = self
+            .records
+            .write()
+            .unwrap()
+            .iter_mut()
+            .find(|r| r.id == id)
+        {
+            f(record);
+        }
+    }
+
+    fn filtered(&self, f: impl Fn(&ExecutionRecord) -> bool) -> Vec<ExecutionRecord> {
+        let mut records: Vec<_> = self
+            .records
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| f(r))
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.queued_at);
+        records
+    }
+}
+
+#[async_trait::async_trait]
+impl ExecutionRepository for InMemoryExecutionRepository {
+    async fn create(
+        &self,
+        sandbox_id: SandboxId,
+        _code: &str,
+        _timeout_seconds: i32,
+        correlation: Correlation,
+    ) -> Result<Uuid, CretoError> {
+        let id = Uuid::new_v4();
+        self.records.write().unwrap().push(ExecutionRecord {
+            id,
+            sandbox_id,
+            status: ExecutionStatus::Queued,
+            queued_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+            duration_ms: None,
+            correlation_id: correlation.correlation_id,
+            caused_by: correlation.caused_by,
+        });
+        Ok(id)
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<ExecutionRecord>, CretoError> {
+        Ok(self
+            .records
+            .read()
+            .unwrap()
+            .iter()
+            .find(|r| r.id == id)
+            .cloned())
+    }
+
+    async fn update_status(&self, id: Uuid, status: ExecutionStatus) -> Result<(), CretoError> {
+        self.with_record(id, |r| r.status = status);
+        Ok(())
+    }
+
+    async fn mark_started(&self, id: Uuid) -> Result<(), CretoError> {
+        self.with_record(id, |r| {
+            r.status = ExecutionStatus::Running;
+            r.started_at = Some(Utc::now());
+        });
+        Ok(())
+    }
+
+    async fn mark_completed(&self, id: Uuid, duration_ms: i64) -> Result<(), CretoError> {
+        self.with_record(id, |r| {
+            r.status = ExecutionStatus::Completed;
+            r.completed_at = Some(Utc::now());
+            r.duration_ms = Some(duration_ms);
+        });
+        Ok(())
+    }
+
+    async fn list_pending_by_sandbox(
+        &self,
+        sandbox_id: SandboxId,
+    ) -> Result<Vec<ExecutionRecord>, CretoError> {
+        Ok(self.filtered(|r| {
+            r.sandbox_id == sandbox_id
+                && matches!(r.status, ExecutionStatus::Queued | ExecutionStatus::Running)
+        }))
+    }
+
+    async fn find_by_correlation(
+        &self,
+        correlation_id: Uuid,
+    ) -> Result<Vec<ExecutionRecord>, CretoError> {
+        Ok(self.filtered(|r| r.correlation_id == Some(correlation_id)))
+    }
+
+    async fn find(
+        &self,
+        filter: &ExecutionFilter,
+        page: &PageRequest,
+    ) -> Result<Page<ExecutionRecord>, CretoError> {
+        let mut matching = self.filtered(|r| filter.matches(r, self.sandboxes.owner(r.sandbox_id)));
+        matching.sort_by_key(|r| std::cmp::Reverse((r.queued_at, r.id)));
+        let total = page.include_total.then_some(matching.len() as u64);
+
+        let skip = match &page.position {
+            PagePosition::Offset(offset) => usize::try_from(*offset).unwrap_or(usize::MAX),
+            PagePosition::After(cursor) => {
+                let cursor = ExecutionCursor::decode(cursor)?;
+                matching.iter().take_while(|r| !cursor.precedes(r)).count()
+            }
+        };
+        let limit = page.effective_limit() as usize;
+        let mut items: Vec<_> = matching.into_iter().skip(skip).take(limit + 1).collect();
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|r| ExecutionCursor::after(r).encode())
+        } else {
+            None
+        };
+
+        Ok(Page {
+            items,
+            next_cursor,
+            total,
+        })
+    }
+
+    async fn stats(
+        &self,
+        organization_id: OrganizationId,
+        range: TimeRange,
+    ) -> Result<ExecutionStats, CretoError> {
+        let filter = ExecutionFilter::new()
+            .for_organization(organization_id)
+            .queued_within(range);
+        let matching = self.filtered(|r| filter.matches(r, self.sandboxes.owner(r.sandbox_id)));
+        Ok(ExecutionStats::from_records(
+            organization_id,
+            range,
+            &matching,
+        ))
+    }
+
+    async fn record_idempotent_result(
+        &self,
+        record: &IdempotentExecution,
+    ) -> Result<(), CretoError> {
+        // Recorded on the execution's row, so only for known executions
+        let known = self
+            .records
+            .read()
+            .unwrap()
+            .iter()
+            .any(|r| r.id == record.result.request_id && r.sandbox_id == record.sandbox_id);
+        if known {
+            self.idempotent.write().unwrap().push(record.clone());
+        }
+        Ok(())
+    }
+
+    async fn find_idempotent_result(
+        &self,
+        sandbox_id: SandboxId,
+        key: &str,
+    ) -> Result<Option<IdempotentExecution>, CretoError> {
+        Ok(self
+            .idempotent
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|r| r.sandbox_id == sandbox_id && r.key == key)
+            .max_by_key(|r| r.recorded_at)
+            .cloned())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Resource Usage Repository
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+struct UsageState {
+    snapshots: Vec<(SandboxId, ResourceUsage)>,
+    samples: HashMap<Uuid, Vec<UsageSample>>,
+}
+
+/// In-memory implementation of ResourceUsageRepository.
+///
+/// As with the PostgreSQL implementation, usage time series are only kept
+/// when enabled via [`InMemoryResourceUsageRepository::with_sample_persistence`].
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryResourceUsageRepository {
+    state: Arc<RwLock<UsageState>>,
+    persist_samples: bool,
+}
+
+impl InMemoryResourceUsageRepository {
+    /// Create an empty repository.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable persisting usage time series.
+    pub fn with_sample_persistence(mut self, enabled: bool) -> Self {
+        self.persist_samples = enabled;
+        self
+    }
+
+    /// Usage snapshots recorded for `sandbox_id`, oldest first.
+    pub fn snapshots(&self, sandbox_id: SandboxId) -> Vec<ResourceUsage> {
+        self.state
+            .read()
+            .unwrap()
+            .snapshots
+            .iter()
+            .filter(|(id, _)| *id == sandbox_id)
+            .map(|(_, usage)| usage.clone())
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl ResourceUsageRepository for InMemoryResourceUsageRepository {
+    async fn record(&self, sandbox_id: SandboxId, usage: &ResourceUsage) -> Result<(), CretoError> {
+        self.state
+            .write()
+            .unwrap()
+            .snapshots
+            .push((sandbox_id, usage.clone()));
+        Ok(())
+    }
+
+    async fn get_latest(&self, sandbox_id: SandboxId) -> Result<Option<ResourceUsage>, CretoError> {
+        Ok(self
+            .state
+            .read()
+            .unwrap()
+            .snapshots
+            .iter()
+            .rev()
+            .find(|(id, _)| *id == sandbox_id)
+            .map(|(_, usage)| usage.clone()))
+    }
+
+    async fn record_samples(
+        &self,
+        _sandbox_id: SandboxId,
+        execution_id: Uuid,
+        samples: &[UsageSample],
+    ) -> Result<(), CretoError> {
+        if !self.persist_samples || samples.is_empty() {
+            return Ok(());
+        }
+        self.state
+            .write()
+            .unwrap()
+            .samples
+            .insert(execution_id, samples.to_vec());
+        Ok(())
+    }
+
+    async fn get_samples(&self, execution_id: Uuid) -> Result<Vec<UsageSample>, CretoError> {
+        Ok(self
+            .state
+            .read()
+            .unwrap()
+            .samples
+            .get(&execution_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Scripted Executor
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Executor that replays canned outcomes, one per call.
+///
+/// Once the script runs out it falls back to [`Executor`]'s mock success.
+#[derive(Clone, Default)]
+pub struct ScriptedExecutor {
+    script: Arc<Mutex<VecDeque<CretoResult<ExecutionResult>>>>,
+    effects: Arc<Mutex<VecDeque<SideEffect>>>,
+    outputs: Arc<Mutex<VecDeque<ScriptedOutput>>>,
+    holds: Arc<Mutex<VecDeque<oneshot::Receiver<()>>>>,
+    requests: Arc<Mutex<Vec<ExecutionRequest>>>,
+}
+
+/// Action a scripted execution performs while "running".
+type SideEffect = Box<dyn FnOnce(&ExecutionRequest) + Send>;
+
+/// Chunks a scripted execution streams, each after waiting `interval`.
+struct ScriptedOutput {
+    interval: std::time::Duration,
+    chunks: Vec<ExecutionChunk>,
+}
+
+impl ScriptedExecutor {
+    /// Create an executor with an empty script.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a canned result.
+    ///
+    /// The request ID and correlation are replaced with the request's.
+    pub fn push_result(&self, result: ExecutionResult) {
+        self.script.lock().unwrap().push_back(Ok(result));
+    }
+
+    /// Queue a failed execution (the code ran and errored).
+    pub fn push_failure(&self, error: ExecutionError) {
+        let mut timing = ExecutionTiming::new();
+        timing.mark_started();
+        timing.mark_completed();
+        self.push_result(ExecutionResult::failure(Uuid::nil(), error, timing));
+    }
+
+    /// Queue an executor error (the execution never produced a result).
+    pub fn push_error(&self, error: CretoError) {
+        self.script.lock().unwrap().push_back(Err(error));
+    }
+
+    /// Queue an action for the next execution to perform before returning,
+    /// such as writing files into the sandbox workdir.
+    pub fn push_effect(&self, effect: impl FnOnce(&ExecutionRequest) + Send + 'static) {
+        self.effects.lock().unwrap().push_back(Box::new(effect));
+    }
+
+    /// Queue output for the next execution to stream before it returns (or
+    /// is held), waiting `interval` before each chunk.
+    pub fn push_output(
+        &self,
+        interval: std::time::Duration,
+        chunks: impl IntoIterator<Item = ExecutionChunk>,
+    ) {
+        self.outputs.lock().unwrap().push_back(ScriptedOutput {
+            interval,
+            chunks: chunks.into_iter().collect(),
+        });
+    }
+
+    /// Keep the next execution running until the returned sender fires or
+    /// is dropped.
+    pub fn push_hold(&self) -> oneshot::Sender<()> {
+        let (release, hold) = oneshot::channel();
+        self.holds.lock().unwrap().push_back(hold);
+        release
+    }
+
+    /// Requests received so far, in call order.
+    pub fn requests(&self) -> Vec<ExecutionRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl CodeExecutor for ScriptedExecutor {
+    async fn execute_streaming(
+        &self,
+        request: ExecutionRequest,
+        output: OutputSink,
+    ) -> CretoResult<ExecutionResult> {
+        self.requests.lock().unwrap().push(request.clone());
+        let effect = self.effects.lock().unwrap().pop_front();
+        if let Some(effect) = effect {
+            effect(&request);
+        }
+        let scripted = self.outputs.lock().unwrap().pop_front();
+        if let Some(scripted) = scripted {
+            for chunk in scripted.chunks {
+                tokio::time::sleep(scripted.interval).await;
+                let sent = match chunk {
+                    ExecutionChunk::Stdout(bytes) => output.stdout(bytes).await,
+                    ExecutionChunk::Stderr(bytes) => output.stderr(bytes).await,
+                    ExecutionChunk::Status(status) => output.status(status).await,
+                    ExecutionChunk::Result(_) => true,
+                };
+                if !sent {
+                    break;
+                }
+            }
+        }
+        let hold = self.holds.lock().unwrap().pop_front();
+        if let Some(hold) = hold {
+            let _ = hold.await;
+        }
+        let outcome = self.script.lock().unwrap().pop_front();
+
+        match outcome {
+            Some(Ok(mut result)) => {
+                result.request_id = request.id;
+                Ok(result.with_correlation(request.correlation()))
+            }
+            Some(Err(e)) => Err(e),
+            None => Executor::new().execute_streaming(request, output).await,
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Scripted Topic Source
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Topic source backed by an in-memory message log.
+///
+/// Subscriptions read every message published to their topic after they
+/// were created; filtering is left to the dispatcher.
+#[derive(Clone, Default)]
+pub struct ScriptedTopicSource {
+    messages: Arc<Mutex<Vec<TriggerMessage>>>,
+    subscriptions: Arc<Mutex<HashMap<Uuid, (Uuid, usize)>>>,
+}
+
+impl ScriptedTopicSource {
+    /// Create a source with no messages.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish a message to a topic.
+    pub fn publish(
+        &self,
+        topic_id: Uuid,
+        payload: &[u8],
+        metadata: &[(&str, &str)],
+    ) -> TriggerMessage {
+        let message = TriggerMessage {
+            id: Uuid::new_v4(),
+            topic_id,
+            payload: payload.to_vec(),
+            metadata: metadata
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            published_at: Utc::now(),
+        };
+        self.messages.lock().unwrap().push(message.clone());
+        message
+    }
+
+    /// Number of live subscriptions.
+    pub fn subscription_count(&self) -> usize {
+        self.subscriptions.lock().unwrap().len()
+    }
+}
+
+#[async_trait::async_trait]
+impl TopicSource for ScriptedTopicSource {
+    async fn subscribe(&self, trigger: &TopicTrigger) -> CretoResult<Uuid> {
+        let id = Uuid::new_v4();
+        let start = self.messages.lock().unwrap().len();
+        self.subscriptions
+            .lock()
+            .unwrap()
+            .insert(id, (trigger.topic_id, start));
+        Ok(id)
+    }
+
+    async fn poll(
+        &self,
+        subscription_id: Uuid,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> CretoResult<Vec<TriggerMessage>> {
+        let (topic_id, start) = *self
+            .subscriptions
+            .lock()
+            .unwrap()
+            .get(&subscription_id)
+            .ok_or_else(|| CretoError::NotFound(format!("Subscription {}", subscription_id)))?;
+        let messages = self.messages.lock().unwrap();
+        let from = after
+            .and_then(|id| messages.iter().position(|m| m.id == id))
+            .map_or(start, |i| i + 1);
+        Ok(messages[from..]
+            .iter()
+            .filter(|m| m.topic_id == topic_id)
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
+    async fn unsubscribe(&self, subscription_id: Uuid) -> CretoResult<()> {
+        self.subscriptions.lock().unwrap().remove(&subscription_id);
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Mock Sandbox Backend
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+struct BackendState {
+    live: HashSet<String>,
+    stopped: HashSet<String>,
+    terminated: Vec<String>,
+    create_failures: VecDeque<CretoError>,
+    terminate_failures: VecDeque<CretoError>,
+    restore_failures: VecDeque<CretoError>,
+    usage: HashMap<String, ResourceUsage>,
+    /// Runtime state of each live handle, carried over by snapshots.
+    memory: HashMap<String, Vec<u8>>,
+    restore_unsupported: bool,
+}
+
+/// Sandbox backend that hands out fake handles and records teardown.
+#[derive(Debug, Clone, Default)]
+pub struct MockSandboxBackend {
+    state: Arc<Mutex<BackendState>>,
+}
+
+impl MockSandboxBackend {
+    /// Create a backend with no sandboxes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the next `create` call fail with `error`.
+    pub fn fail_next_create(&self, error: CretoError) {
+        self.state.lock().unwrap().create_failures.push_back(error);
+    }
+
+    /// Make the next `terminate` call fail with `error`.
+    pub fn fail_next_terminate(&self, error: CretoError) {
+        self.state
+            .lock()
+            .unwrap()
+            .terminate_failures
+            .push_back(error);
+    }
+
+    /// Make the next `restore` call fail with `error`.
+    pub fn fail_next_restore(&self, error: CretoError) {
+        self.state.lock().unwrap().restore_failures.push_back(error);
+    }
+
+    /// Report from now on that sandboxes cannot be snapshotted and restored.
+    pub fn disable_restore(&self) {
+        self.state.lock().unwrap().restore_unsupported = true;
+    }
+
+    /// Set a live handle's runtime state.
+    pub fn write_memory(&self, handle: &str, memory: &[u8]) {
+        self.state
+            .lock()
+            .unwrap()
+            .memory
+            .insert(handle.to_string(), memory.to_vec());
+    }
+
+    /// A live handle's runtime state.
+    pub fn memory(&self, handle: &str) -> Option<Vec<u8>> {
+        self.state.lock().unwrap().memory.get(handle).cloned()
+    }
+
+    /// Handles created and not yet terminated.
+    pub fn live_handles(&self) -> Vec<String> {
+        self.state.lock().unwrap().live.iter().cloned().collect()
+    }
+
+    /// Live handles currently stopped.
+    pub fn stopped_handles(&self) -> Vec<String> {
+        self.state.lock().unwrap().stopped.iter().cloned().collect()
+    }
+
+    /// Handles terminated so far, in order.
+    pub fn terminated_handles(&self) -> Vec<String> {
+        self.state.lock().unwrap().terminated.clone()
+    }
+
+    /// Set the usage reported for `handle` from now on.
+    pub fn set_usage(&self, handle: &str, usage: ResourceUsage) {
+        self.state
+            .lock()
+            .unwrap()
+            .usage
+            .insert(handle.to_string(), usage);
+    }
+
+    fn require_live(&self, handle: &str) -> CretoResult<()> {
+        if self.state.lock().unwrap().live.contains(handle) {
+            Ok(())
+        } else {
+            Err(CretoError::NotFound(format!("sandbox handle {}", handle)))
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SandboxBackend for MockSandboxBackend {
+    async fn create(&self, _config: &SandboxConfig) -> CretoResult<String> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(error) = state.create_failures.pop_front() {
+            return Err(error);
+        }
+        let handle = format!("mock-{}", Uuid::new_v4());
+        state.live.insert(handle.clone());
+        Ok(handle)
+    }
+
+    async fn start(&self, handle: &str) -> CretoResult<()> {
+        self.require_live(handle)?;
+        self.state.lock().unwrap().stopped.remove(handle);
+        Ok(())
+    }
+
+    async fn stop(&self, handle: &str) -> CretoResult<()> {
+        self.require_live(handle)?;
+        self.state
+            .lock()
+            .unwrap()
+            .stopped
+            .insert(handle.to_string());
+        Ok(())
+    }
+
+    async fn terminate(&self, handle: &str) -> CretoResult<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(error) = state.terminate_failures.pop_front() {
+            return Err(error);
+        }
+        if !state.live.remove(handle) {
+            return Err(CretoError::NotFound(format!("sandbox handle {}", handle)));
+        }
+        state.stopped.remove(handle);
+        state.memory.remove(handle);
+        state.terminated.push(handle.to_string());
+        Ok(())
+    }
+
+    async fn execute(&self, handle: &str, _code: &str) -> CretoResult<String> {
+        self.require_live(handle)?;
+        Ok(String::new())
+    }
+
+    async fn status(&self, handle: &str) -> CretoResult<SandboxState> {
+        self.require_live(handle)?;
+        Ok(SandboxState::Ready)
+    }
+
+    fn resource_controller(&self, handle: &str) -> Option<Arc<dyn ResourceController>> {
+        Some(Arc::new(MockUsageController {
+            backend: self.clone(),
+            handle: handle.to_string(),
+        }))
+    }
+
+    fn supports_restore(&self) -> bool {
+        !self.state.lock().unwrap().restore_unsupported
+    }
+
+    async fn snapshot(&self, handle: &str) -> CretoResult<Vec<u8>> {
+        self.require_live(handle)?;
+        Ok(self.memory(handle).unwrap_or_default())
+    }
+
+    async fn restore(&self, config: &SandboxConfig, snapshot: &[u8]) -> CretoResult<String> {
+        if let Some(error) = self.state.lock().unwrap().restore_failures.pop_front() {
+            return Err(error);
+        }
+        let handle = self.create(config).await?;
+        self.write_memory(&handle, snapshot);
+        Ok(handle)
+    }
+}
+
+/// Reads the usage set with [`MockSandboxBackend::set_usage`].
+struct MockUsageController {
+    backend: MockSandboxBackend,
+    handle: String,
+}
+
+#[async_trait::async_trait]
+impl ResourceController for MockUsageController {
+    async fn current_usage(&self) -> CretoResult<ResourceUsage> {
+        self.backend.require_live(&self.handle)?;
+        let state = self.backend.state.lock().unwrap();
+        Ok(state.usage.get(&self.handle).cloned().unwrap_or_default())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Scripted Provisioner
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+struct ProvisionerState {
+    failures: HashMap<ProvisioningPhase, VecDeque<ProvisioningError>>,
+    calls: Vec<ProvisioningPhase>,
+}
+
+/// Provisioner that fails phases on demand and records the phases it ran.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedProvisioner {
+    state: Arc<Mutex<ProvisionerState>>,
+}
+
+impl ScriptedProvisioner {
+    /// Create a provisioner whose phases all succeed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the next run of `phase` fail with `error`.
+    ///
+    /// Only the phases a provisioner takes part in can be scripted: image
+    /// resolution, resource allocation and secret injection.
+    pub fn fail_next(&self, phase: ProvisioningPhase, error: ProvisioningError) {
+        self.state
+            .lock()
+            .unwrap()
+            .failures
+            .entry(phase)
+            .or_default()
+            .push_back(error);
+    }
+
+    /// Phases run so far, in order.
+    pub fn calls(&self) -> Vec<ProvisioningPhase> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    fn run(&self, phase: ProvisioningPhase) -> Result<(), ProvisioningError> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(phase);
+        match state.failures.get_mut(&phase).and_then(VecDeque::pop_front) {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SandboxProvisioner for ScriptedProvisioner {
+    async fn resolve_image(&self, _config: &SandboxConfig) -> Result<(), ProvisioningError> {
+        self.run(ProvisioningPhase::ImageResolution)
+    }
+
+    async fn allocate_resources(&self, _config: &SandboxConfig) -> Result<(), ProvisioningError> {
+        self.run(ProvisioningPhase::ResourceAllocation)
+    }
+
+    async fn inject_secrets(&self, _sandbox: &Sandbox) -> Result<(), ProvisioningError> {
+        self.run(ProvisioningPhase::SecretInjection)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Scripted Health Probe
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+struct HealthProbeState {
+    failing: HashSet<SandboxId>,
+    probes: Vec<SandboxId>,
+    delay: Option<std::time::Duration>,
+}
+
+/// Pool health probe that fails for chosen sandboxes and records what it
+/// probed.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedHealthProbe {
+    state: Arc<Mutex<HealthProbeState>>,
+}
+
+impl ScriptedHealthProbe {
+    /// Create a probe every sandbox passes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make probes of `sandbox_id` fail until [`heal`](Self::heal)ed.
+    pub fn fail(&self, sandbox_id: SandboxId) {
+        self.state.lock().unwrap().failing.insert(sandbox_id);
+    }
+
+    /// Make probes of `sandbox_id` pass again.
+    pub fn heal(&self, sandbox_id: SandboxId) {
+        self.state.lock().unwrap().failing.remove(&sandbox_id);
+    }
+
+    /// Make every probe take `delay`.
+    pub fn with_delay(self, delay: std::time::Duration) -> Self {
+        self.state.lock().unwrap().delay = Some(delay);
+        self
+    }
+
+    /// Sandboxes probed so far, in order.
+    pub fn probes(&self) -> Vec<SandboxId> {
+        self.state.lock().unwrap().probes.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl HealthProbe for ScriptedHealthProbe {
+    async fn probe(&self, sandbox: &Sandbox) -> CretoResult<()> {
+        let delay = self.state.lock().unwrap().delay;
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.probes.push(sandbox.id);
+        if state.failing.contains(&sandbox.id) {
+            return Err(CretoError::Internal(format!(
+                "Scripted probe failure for {}",
+                sandbox.id
+            )));
+        }
+        Ok(())
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Test Harness
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A [`RuntimeService`] wired to in-memory collaborators.
+///
+/// The collaborators share state with the service, so tests can script the
+/// executor or backend and inspect what was persisted.
+pub struct RuntimeTestHarness {
+    /// Service under test.
+    pub service: RuntimeService,
+    /// Sandbox records written by the service.
+    pub sandboxes: InMemorySandboxRepository,
+    /// Execution records written by the service.
+    pub executions: InMemoryExecutionRepository,
+    /// Resource usage written by the service (samples persisted).
+    pub usage: InMemoryResourceUsageRepository,
+    /// Executor used for every execution.
+    pub executor: ScriptedExecutor,
+    /// Backend used to provision sandboxes on a pool miss.
+    pub backend: MockSandboxBackend,
+    /// Provisioner run around the backend on a pool miss.
+    pub provisioner: ScriptedProvisioner,
+}
+
+impl RuntimeTestHarness {
+    /// Create a harness with the default pool configuration.
+    pub fn new() -> Self {
+        Self::with_pool_config(PoolConfig::default())
+    }
+
+    /// Create a harness with a custom pool configuration.
+    pub fn with_pool_config(config: PoolConfig) -> Self {
+        let sandboxes = InMemorySandboxRepository::new();
+        let executions = InMemoryExecutionRepository::new().with_sandboxes(sandboxes.clone());
+        let usage = InMemoryResourceUsageRepository::new().with_sample_persistence(true);
+        let executor = ScriptedExecutor::new();
+        let backend = MockSandboxBackend::new();
+        let provisioner = ScriptedProvisioner::new();
+
+        // Retries are off so a scripted failure reaches the call it was
+        // scripted for; tests of retry behavior turn them back on
+        let service = RuntimeService::with_pool_config(config)
+            .with_executor(Box::new(executor.clone()))
+            .with_sandbox_backend(Box::new(backend.clone()))
+            .with_provisioner(Box::new(provisioner.clone()))
+            .with_provisioning_retry(ProvisioningRetryPolicy::disabled())
+            .with_attestation_generator(
+                Box::new(MockAttestationProvider::new()),
+                AttestationPlatform::GVisor,
+            )
+            .with_checkpoint_manager(Box::new(InMemoryCheckpointStore::new()))
+            .with_sandbox_repository(Box::new(sandboxes.clone()))
+            .with_execution_repository(Box::new(executions.clone()))
+            .with_resource_usage_repository(Box::new(usage.clone()));
+
+        Self {
+            service,
+            sandboxes,
+            executions,
+            usage,
+            executor,
+            backend,
+            provisioner,
+        }
+    }
+
+    /// Further configure the service (e.g. add a secret provider).
+    pub fn map_service(mut self, f: impl FnOnce(RuntimeService) -> RuntimeService) -> Self {
+        self.service = f(self.service);
+        self
+    }
+
+    /// Provision a ready sandbox for `runtime` and put it in the warm pool.
+    pub async fn add_warm_sandbox(&self, runtime: &str) -> CretoResult<SandboxId> {
+        let config = SandboxConfig {
+            runtime: runtime.to_string(),
+            ..Default::default()
+        };
+        let organization_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+
+        let mut sandbox = Sandbox::new(organization_id, agent_id, config);
+        sandbox.id = self
+            .sandboxes
+            .create(
+                organization_id,
+                agent_id,
+                runtime,
+                sandbox.config.network_policy.as_str(),
+                None,
+                &sandbox.config.effective_limits(),
+            )
+            .await?;
+        let handle = self.backend.create(&sandbox.config).await?;
+        sandbox.mark_ready(handle);
+        self.sandboxes
+            .update_state(sandbox.id, SandboxState::Ready)
+            .await?;
+
+        let id = sandbox.id;
+        self.service.pool().add(sandbox).await?;
+        Ok(id)
+    }
+}
+
+impl Default for RuntimeTestHarness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sandbox_repository_filters_and_orders() {
+        let repo = InMemorySandboxRepository::new();
+        let org_id = OrganizationId::new();
+
+        let first = repo
+            .create(
+                org_id,
+                AgentId::new(),
+                "python3.11",
+                "restricted",
+                None,
+                &ResourceLimits::default(),
+            )
+            .await
+            .unwrap();
+        let second = repo
+            .create(
+                org_id,
+                AgentId::new(),
+                "node20",
+                "restricted",
+                None,
+                &ResourceLimits::default(),
+            )
+            .await
+            .unwrap();
+        let failed = repo
+            .create(
+                org_id,
+                AgentId::new(),
+                "node20",
+                "restricted",
+                None,
+                &ResourceLimits::default(),
+            )
+            .await
+            .unwrap();
+        repo.create(
+            OrganizationId::new(),
+            AgentId::new(),
+            "node20",
+            "none",
+            None,
+            &ResourceLimits::default(),
+        )
+        .await
+        .unwrap();
+
+        repo.update_state(failed, SandboxState::Failed)
+            .await
+            .unwrap();
+        repo.update_state(first, SandboxState::Ready).await.unwrap();
+
+        let active: Vec<_> = repo
+            .list_active_by_org(org_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(active, vec![second, first]);
+
+        // Only ready/paused sandboxes are idle candidates
+        let idle = repo
+            .find_idle(Utc::now() + chrono::Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(idle, vec![first]);
+
+        repo.terminate(first).await.unwrap();
+        assert_eq!(
+            repo.get(first).await.unwrap().unwrap().state,
+            SandboxState::Terminated {
+                reason: TerminationReason::Requested
+            }
+        );
+        assert_eq!(repo.list_active_by_org(org_id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_execution_repository_pending_and_correlation() {
+        let repo = InMemoryExecutionRepository::new();
+        let sandbox_id = SandboxId::new();
+        let correlation = Correlation::new(Uuid::now_v7());
+
+        let a = repo
+            .create(sandbox_id, "a", 300, correlation)
+            .await
+            .unwrap();
+        let b = repo
+            .create(sandbox_id, "b", 300, Correlation::default())
+            .await
+            .unwrap();
+        repo.mark_started(a).await.unwrap();
+        repo.mark_completed(b, 12).await.unwrap();
+
+        let pending = repo.list_pending_by_sandbox(sandbox_id).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].status, ExecutionStatus::Running);
+
+        let completed = repo.get(b).await.unwrap().unwrap();
+        assert_eq!(completed.duration_ms, Some(12));
+
+        let correlated = repo
+            .find_by_correlation(correlation.correlation_id.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(correlated.len(), 1);
+        assert_eq!(correlated[0].id, a);
+    }
+
+    #[tokio::test]
+    async fn test_execution_repository_history_pages() {
+        let sandboxes = InMemorySandboxRepository::new();
+        let repo = InMemoryExecutionRepository::new().with_sandboxes(sandboxes.clone());
+        let org_id = OrganizationId::new();
+        let agent_id = AgentId::new();
+        let owned = sandboxes
+            .create(
+                org_id,
+                agent_id,
+                "python3.11",
+                "restricted",
+                None,
+                &ResourceLimits::default(),
+            )
+            .await
+            .unwrap();
+        let other = sandboxes
+            .create(
+                OrganizationId::new(),
+                AgentId::new(),
+                "python3.11",
+                "restricted",
+                None,
+                &ResourceLimits::default(),
+            )
+            .await
+            .unwrap();
+
+        let start = Utc::now();
+        let mut ids = Vec::new();
+        for i in 0..5i64 {
+            let id = Uuid::now_v7();
+            repo.records.write().unwrap().push(ExecutionRecord {
+                id,
+                sandbox_id: owned,
+                status: ExecutionStatus::Completed,
+                queued_at: start + chrono::Duration::seconds(i),
+                started_at: None,
+                completed_at: None,
+                duration_ms: Some(100 * (i + 1)),
+                correlation_id: None,
+                caused_by: None,
+            });
+            ids.push(id);
+        }
+        repo.create(other, "x", 300, Correlation::default())
+            .await
+            .unwrap();
+
+        let filter = ExecutionFilter::new().for_organization(org_id);
+        let first = repo
+            .find(&filter, &PageRequest::first(2).with_total())
+            .await
+            .unwrap();
+        assert_eq!(first.total, Some(5));
+        assert_eq!(
+            first.items.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![ids[4], ids[3]]
+        );
+
+        let second = repo
+            .find(&filter, &PageRequest::after(first.next_cursor.unwrap(), 2))
+            .await
+            .unwrap();
+        assert_eq!(second.total, None);
+        assert_eq!(
+            second.items.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![ids[2], ids[1]]
+        );
+        let by_offset = repo
+            .find(&filter, &PageRequest::at_offset(2, 2))
+            .await
+            .unwrap();
+        assert_eq!(by_offset.items.len(), 2);
+        assert_eq!(by_offset.items[0].id, ids[2]);
+
+        let last = repo
+            .find(&filter, &PageRequest::after(second.next_cursor.unwrap(), 2))
+            .await
+            .unwrap();
+        assert_eq!(last.items.len(), 1);
+        assert_eq!(last.next_cursor, None);
+
+        let slow = repo
+            .find(
+                &filter.clone().with_min_duration_ms(400),
+                &PageRequest::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(slow.items.len(), 2);
+
+        let stats = repo
+            .stats(
+                org_id,
+                TimeRange::new(start, start + chrono::Duration::minutes(1)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(stats.total, 5);
+        assert_eq!(stats.p50_duration_ms, Some(300.0));
+    }
+
+    #[tokio::test]
+    async fn test_usage_samples_are_opt_in() {
+        let sample = UsageSample {
+            offset_ms: 0,
+            memory_bytes: 4096,
+            cpu_ms_delta: 1,
+            open_fds: 3,
+            gpu_ms_delta: 0,
+        };
+        let execution_id = Uuid::now_v7();
+
+        let repo = InMemoryResourceUsageRepository::new();
+        repo.record_samples(SandboxId::new(), execution_id, &[sample])
+            .await
+            .unwrap();
+        assert!(repo.get_samples(execution_id).await.unwrap().is_empty());
+
+        let repo = repo.with_sample_persistence(true);
+        repo.record_samples(SandboxId::new(), execution_id, &[sample])
+            .await
+            .unwrap();
+        assert_eq!(repo.get_samples(execution_id).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scripted_executor_replays_in_order() {
+        let executor = ScriptedExecutor::new();
+        executor.push_failure(ExecutionError::timeout(5));
+        executor.push_error(CretoError::Internal("executor crashed".to_string()));
+
+        let request = ExecutionRequest::new(SandboxId::new(), "print(1)");
+        let failed = executor.execute(request.clone()).await.unwrap();
+        assert_eq!(failed.status, ExecutionStatus::Failed);
+        assert_eq!(failed.request_id, request.id);
+
+        assert!(executor.execute(request.clone()).await.is_err());
+        assert!(executor.execute(request).await.unwrap().is_success());
+        assert_eq!(executor.requests().len(), 3);
+    }
+}
@@ -0,0 +1,669 @@
+//! Sandbox executions triggered by messages published to topics.
+//!
+//! A [`TopicTrigger`] binds a topic (and an optional metadata filter) to an
+//! [`ExecutionRequest`] template run on behalf of an agent. The dispatcher
+//! ([`RuntimeService::run_topic_triggers`](crate::RuntimeService::run_topic_triggers))
+//! polls each trigger's subscription through a [`TopicSource`] and creates
+//! one execution per matching message, with the payload written to
+//! [`MESSAGE_PAYLOAD_PATH`] and the message metadata in the environment.
+//!
+//! Messages are gated like scheduled runs, by a [`TriggerGate`] such as a
+//! quota check. A refused or unrunnable message is retried with backoff
+//! under the trigger's [`TriggerRetryPolicy`], then dead-lettered.
+//! Executions that ran count as delivered whatever their status.
+//!
+//! Triggers, their read positions, pending retries and dead letters live in
+//! a [`TopicTriggerStore`], so dispatch picks up where it left off after a
+//! restart. A message is delivered at least once: the read position only
+//! advances once the messages read have been attempted.
+//!
+//! The runtime does not depend on the messaging crate for this; with the
+//! `messaging` feature, [`MessagingTopicSource`] adapts a
+//! `MessagingService` topic subscription.
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use creto_common::{AgentId, CretoResult, OrganizationId};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::execution::{ExecutionRequest, ExecutionStatus};
+
+/// Unique identifier for a topic trigger.
+pub type TopicTriggerId = Uuid;
+
+/// Path inside the sandbox the triggering message's payload is written to.
+pub const MESSAGE_PAYLOAD_PATH: &str = "/input/message";
+
+/// Environment variable holding the triggering message's ID.
+pub const MESSAGE_ID_ENV: &str = "CRETO_MESSAGE_ID";
+
+/// Environment variable holding the triggering message's topic ID.
+pub const MESSAGE_TOPIC_ENV: &str = "CRETO_MESSAGE_TOPIC";
+
+/// Prefix of the environment variables holding message metadata.
+pub const MESSAGE_METADATA_ENV_PREFIX: &str = "CRETO_MESSAGE_META_";
+
+/// A message read from a topic subscription.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriggerMessage {
+    /// Message ID.
+    pub id: Uuid,
+    /// Topic the message was published to.
+    pub topic_id: Uuid,
+    /// Message payload.
+    pub payload: Vec<u8>,
+    /// Message metadata.
+    pub metadata: HashMap<String, String>,
+    /// When the message was published.
+    pub published_at: DateTime<Utc>,
+}
+
+/// Retry behavior for messages whose execution could not be created.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerRetryPolicy {
+    /// Attempts, including the first, before a message is dead-lettered.
+    pub max_attempts: u32,
+
+    /// Backoff after the first failed attempt in milliseconds.
+    pub initial_backoff_ms: u64,
+
+    /// Maximum backoff in milliseconds.
+    pub max_backoff_ms: u64,
+
+    /// Backoff multiplier.
+    pub multiplier: f64,
+}
+
+impl Default for TriggerRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 1_000,
+            max_backoff_ms: 300_000,
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl TriggerRetryPolicy {
+    /// Backoff after `failures` failed attempts.
+    pub fn backoff_ms(&self, failures: u32) -> u64 {
+        if failures == 0 {
+            return 0;
+        }
+
+        let backoff = self.initial_backoff_ms as f64 * self.multiplier.powi(failures as i32 - 1);
+        backoff.min(self.max_backoff_ms as f64) as u64
+    }
+}
+
+/// Binding from a topic to an execution template.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicTrigger {
+    /// Unique trigger ID.
+    pub id: TopicTriggerId,
+
+    /// Owning organization.
+    pub organization_id: OrganizationId,
+
+    /// Agent the executions run for.
+    pub agent_id: AgentId,
+
+    /// Topic whose messages trigger executions.
+    pub topic_id: Uuid,
+
+    /// Metadata a message must carry to trigger an execution; empty
+    /// matches every message.
+    #[serde(default)]
+    pub filter: HashMap<String, String>,
+
+    /// Request copied for every message (with a fresh ID).
+    pub template: ExecutionRequest,
+
+    /// Handling of messages that could not be executed.
+    #[serde(default)]
+    pub retry: TriggerRetryPolicy,
+}
+
+impl TopicTrigger {
+    /// Create a trigger matching every message on `topic_id`.
+    pub fn new(
+        organization_id: OrganizationId,
+        agent_id: AgentId,
+        topic_id: Uuid,
+        template: ExecutionRequest,
+    ) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            organization_id,
+            agent_id,
+            topic_id,
+            filter: HashMap::new(),
+            template,
+            retry: TriggerRetryPolicy::default(),
+        }
+    }
+
+    /// Only trigger on messages whose metadata has `key` set to `value`.
+    pub fn with_filter(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.filter.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the retry policy.
+    pub fn with_retry(mut self, retry: TriggerRetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Check if a message's metadata matches the filter.
+    pub fn matches(&self, metadata: &HashMap<String, String>) -> bool {
+        self.filter
+            .iter()
+            .all(|(key, value)| metadata.get(key) == Some(value))
+    }
+
+    /// Build the execution request for a message.
+    ///
+    /// The message ID is recorded as the execution's
+    /// [`caused_by`](ExecutionRequest::caused_by) for correlation.
+    pub fn request_for(&self, message: &TriggerMessage) -> ExecutionRequest {
+        let mut request = self.template.clone();
+        request.id = Uuid::now_v7();
+        let mut metadata: Vec<_> = message.metadata.iter().collect();
+        metadata.sort();
+
+        let mut request = request
+            .with_correlation(self.template.correlation().derive(message.id))
+            .with_artifact(MESSAGE_PAYLOAD_PATH, message.payload.clone())
+            .with_env(MESSAGE_ID_ENV, message.id.to_string())
+            .with_env(MESSAGE_TOPIC_ENV, message.topic_id.to_string());
+        for (key, value) in metadata {
+            request = request.with_env(metadata_env_name(key), value.clone());
+        }
+        request
+    }
+}
+
+/// Environment variable name for a metadata key: uppercased, with anything
+/// other than ASCII letters and digits replaced by `_`.
+fn metadata_env_name(key: &str) -> String {
+    let key: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}", MESSAGE_METADATA_ENV_PREFIX, key)
+}
+
+/// Result of one delivery attempt.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerOutcome {
+    /// The execution ran.
+    Executed {
+        /// Final execution status.
+        status: ExecutionStatus,
+    },
+    /// The attempt failed and the message will be retried.
+    Retrying {
+        /// Why the attempt failed, e.g. a quota denial.
+        error: String,
+        /// Earliest time of the next attempt.
+        next_attempt_at: DateTime<Utc>,
+    },
+    /// The attempt failed and the message was dead-lettered.
+    DeadLettered {
+        /// Why the last attempt failed.
+        error: String,
+    },
+}
+
+/// Record of one delivery attempt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerDelivery {
+    /// Trigger the message matched.
+    pub trigger_id: TopicTriggerId,
+    /// Message delivered.
+    pub message_id: Uuid,
+    /// Attempt number, starting at 1.
+    pub attempt: u32,
+    /// Execution created, if the gate admitted the message.
+    pub execution_id: Option<Uuid>,
+    /// When the outcome was recorded.
+    pub recorded_at: DateTime<Utc>,
+    /// What happened.
+    pub outcome: TriggerOutcome,
+}
+
+/// A message that exhausted its retries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerDeadLetter {
+    /// Trigger the message matched.
+    pub trigger_id: TopicTriggerId,
+    /// The message.
+    pub message: TriggerMessage,
+    /// Attempts made.
+    pub attempts: u32,
+    /// Why the last attempt failed.
+    pub last_error: String,
+    /// When the message was dead-lettered.
+    pub dead_lettered_at: DateTime<Utc>,
+}
+
+/// Trigger dispatcher settings.
+#[derive(Debug, Clone)]
+pub struct TriggerDispatchConfig {
+    /// How often the background dispatcher polls subscriptions.
+    pub poll_interval: std::time::Duration,
+    /// Maximum new messages read per trigger per pass.
+    pub batch_size: usize,
+}
+
+impl Default for TriggerDispatchConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: std::time::Duration::from_secs(5),
+            batch_size: 32,
+        }
+    }
+}
+
+/// Source of topic messages for triggers.
+#[async_trait]
+pub trait TopicSource: Send + Sync {
+    /// Subscribe to the trigger's topic, returning the subscription ID.
+    async fn subscribe(&self, trigger: &TopicTrigger) -> CretoResult<Uuid>;
+
+    /// Read up to `limit` messages published after the message `after`, or
+    /// since the subscription was created when `after` is `None`.
+    async fn poll(
+        &self,
+        subscription_id: Uuid,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> CretoResult<Vec<TriggerMessage>>;
+
+    /// Remove a subscription.
+    async fn unsubscribe(&self, subscription_id: Uuid) -> CretoResult<()>;
+}
+
+/// Checks run before a triggered execution is created, such as quota or
+/// approval checks.
+///
+/// An error fails the attempt, which is retried under the trigger's retry
+/// policy.
+#[async_trait]
+pub trait TriggerGate: Send + Sync {
+    /// Allow or refuse an execution of `trigger` for `message`.
+    async fn admit(&self, trigger: &TopicTrigger, message: &TriggerMessage) -> CretoResult<()>;
+}
+
+#[cfg(feature = "metering")]
+#[async_trait]
+impl TriggerGate for crate::schedule::MeteringQuotaGate {
+    async fn admit(&self, trigger: &TopicTrigger, _message: &TriggerMessage) -> CretoResult<()> {
+        self.check(&trigger.organization_id, &trigger.agent_id)
+    }
+}
+
+/// A registered trigger with its subscription and read position.
+#[derive(Debug, Clone)]
+pub struct StoredTopicTrigger {
+    /// The trigger.
+    pub trigger: TopicTrigger,
+    /// Subscription its messages are read from.
+    pub subscription_id: Uuid,
+    /// Last message read from the subscription.
+    pub cursor: Option<Uuid>,
+    /// When the trigger was registered.
+    pub created_at: DateTime<Utc>,
+}
+
+/// A message waiting for another attempt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggerRetry {
+    /// Trigger the message matched.
+    pub trigger_id: TopicTriggerId,
+    /// The message.
+    pub message: TriggerMessage,
+    /// Attempts made so far.
+    pub failures: u32,
+    /// Earliest time of the next attempt.
+    pub next_attempt_at: DateTime<Utc>,
+}
+
+/// What happens to a message after a failed attempt.
+#[derive(Debug, Clone)]
+pub(crate) enum FailedDelivery {
+    /// Attempt it again later.
+    Retry(TriggerRetry),
+    /// Give up on it.
+    DeadLetter(TriggerDeadLetter),
+}
+
+impl TopicTrigger {
+    /// Handling of a message whose attempt number `attempt` failed with
+    /// `error`: another attempt after the retry policy's backoff, or the
+    /// dead-letter queue once attempts are exhausted.
+    pub(crate) fn after_failure(
+        &self,
+        message: TriggerMessage,
+        attempt: u32,
+        error: String,
+        now: DateTime<Utc>,
+    ) -> (FailedDelivery, TriggerOutcome) {
+        if attempt >= self.retry.max_attempts {
+            let dead = TriggerDeadLetter {
+                trigger_id: self.id,
+                message,
+                attempts: attempt,
+                last_error: error.clone(),
+                dead_lettered_at: now,
+            };
+            return (
+                FailedDelivery::DeadLetter(dead),
+                TriggerOutcome::DeadLettered { error },
+            );
+        }
+
+        let next_attempt_at = now + Duration::milliseconds(self.retry.backoff_ms(attempt) as i64);
+        let retry = TriggerRetry {
+            trigger_id: self.id,
+            message,
+            failures: attempt,
+            next_attempt_at,
+        };
+        (
+            FailedDelivery::Retry(retry),
+            TriggerOutcome::Retrying {
+                error,
+                next_attempt_at,
+            },
+        )
+    }
+}
+
+/// Storage for topic triggers and their dispatch state: read positions,
+/// pending retries and dead letters.
+///
+/// Retries are keyed by trigger and message, so storing one again replaces
+/// it.
+#[async_trait]
+pub trait TopicTriggerStore: Send + Sync {
+    /// Store a newly registered trigger.
+    async fn create(&self, trigger: &StoredTopicTrigger) -> CretoResult<()>;
+
+    /// Get a trigger.
+    async fn get(&self, id: TopicTriggerId) -> CretoResult<Option<StoredTopicTrigger>>;
+
+    /// List every trigger, oldest first.
+    async fn list(&self) -> CretoResult<Vec<StoredTopicTrigger>>;
+
+    /// Delete a trigger with its retries and dead letters, returning
+    /// whether it existed.
+    async fn delete(&self, id: TopicTriggerId) -> CretoResult<bool>;
+
+    /// Advance a trigger's read position to the message `cursor`.
+    async fn set_cursor(&self, id: TopicTriggerId, cursor: Uuid) -> CretoResult<()>;
+
+    /// Store a retry, unless its trigger was deleted.
+    async fn put_retry(&self, retry: &TriggerRetry) -> CretoResult<()>;
+
+    /// Remove the retry of a message, if there is one.
+    async fn delete_retry(&self, id: TopicTriggerId, message_id: Uuid) -> CretoResult<()>;
+
+    /// List a trigger's retries due at `now`, oldest first.
+    async fn due_retries(
+        &self,
+        id: TopicTriggerId,
+        now: DateTime<Utc>,
+    ) -> CretoResult<Vec<TriggerRetry>>;
+
+    /// Store a dead letter, unless its trigger was deleted.
+    async fn put_dead_letter(&self, dead_letter: &TriggerDeadLetter) -> CretoResult<()>;
+
+    /// List a trigger's dead letters, oldest first.
+    async fn list_dead_letters(&self, id: TopicTriggerId) -> CretoResult<Vec<TriggerDeadLetter>>;
+}
+
+/// A trigger with its retries and dead letters.
+struct TriggerEntry {
+    stored: StoredTopicTrigger,
+    retries: Vec<TriggerRetry>,
+    dead_letters: Vec<TriggerDeadLetter>,
+}
+
+/// In-memory topic trigger store.
+#[derive(Default)]
+pub struct InMemoryTopicTriggerStore {
+    triggers: RwLock<HashMap<TopicTriggerId, TriggerEntry>>,
+}
+
+impl InMemoryTopicTriggerStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TopicTriggerStore for InMemoryTopicTriggerStore {
+    async fn create(&self, trigger: &StoredTopicTrigger) -> CretoResult<()> {
+        self.triggers.write().unwrap().insert(
+            trigger.trigger.id,
+            TriggerEntry {
+                stored: trigger.clone(),
+                retries: Vec::new(),
+                dead_letters: Vec::new(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn get(&self, id: TopicTriggerId) -> CretoResult<Option<StoredTopicTrigger>> {
+        Ok(self
+            .triggers
+            .read()
+            .unwrap()
+            .get(&id)
+            .map(|e| e.stored.clone()))
+    }
+
+    async fn list(&self) -> CretoResult<Vec<StoredTopicTrigger>> {
+        let mut triggers: Vec<_> = self
+            .triggers
+            .read()
+            .unwrap()
+            .values()
+            .map(|e| e.stored.clone())
+            .collect();
+        triggers.sort_by_key(|t| (t.created_at, t.trigger.id));
+        Ok(triggers)
+    }
+
+    async fn delete(&self, id: TopicTriggerId) -> CretoResult<bool> {
+        Ok(self.triggers.write().unwrap().remove(&id).is_some())
+    }
+
+    async fn set_cursor(&self, id: TopicTriggerId, cursor: Uuid) -> CretoResult<()> {
+        if let Some(entry) = self.triggers.write().unwrap().get_mut(&id) {
+            entry.stored.cursor = Some(cursor);
+        }
+        Ok(())
+    }
+
+    async fn put_retry(&self, retry: &TriggerRetry) -> CretoResult<()> {
+        if let Some(entry) = self.triggers.write().unwrap().get_mut(&retry.trigger_id) {
+            entry.retries.retain(|r| r.message.id != retry.message.id);
+            entry.retries.push(retry.clone());
+        }
+        Ok(())
+    }
+
+    async fn delete_retry(&self, id: TopicTriggerId, message_id: Uuid) -> CretoResult<()> {
+        if let Some(entry) = self.triggers.write().unwrap().get_mut(&id) {
+            entry.retries.retain(|r| r.message.id != message_id);
+        }
+        Ok(())
+    }
+
+    async fn due_retries(
+        &self,
+        id: TopicTriggerId,
+        now: DateTime<Utc>,
+    ) -> CretoResult<Vec<TriggerRetry>> {
+        let mut due: Vec<_> = self
+            .triggers
+            .read()
+            .unwrap()
+            .get(&id)
+            .map(|e| {
+                e.retries
+                    .iter()
+                    .filter(|r| r.next_attempt_at <= now)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        due.sort_by_key(|r| r.next_attempt_at);
+        Ok(due)
+    }
+
+    async fn put_dead_letter(&self, dead_letter: &TriggerDeadLetter) -> CretoResult<()> {
+        if let Some(entry) = self
+            .triggers
+            .write()
+            .unwrap()
+            .get_mut(&dead_letter.trigger_id)
+        {
+            entry.dead_letters.push(dead_letter.clone());
+        }
+        Ok(())
+    }
+
+    async fn list_dead_letters(&self, id: TopicTriggerId) -> CretoResult<Vec<TriggerDeadLetter>> {
+        Ok(self
+            .triggers
+            .read()
+            .unwrap()
+            .get(&id)
+            .map(|e| e.dead_letters.clone())
+            .unwrap_or_default())
+    }
+}
+
+/// [`TopicSource`] over a messaging service's topic subscriptions.
+///
+/// Subscriptions belong to the service's local agent.
+#[cfg(feature = "messaging")]
+pub struct MessagingTopicSource {
+    service: std::sync::Arc<creto_messaging::MessagingService>,
+}
+
+#[cfg(feature = "messaging")]
+impl MessagingTopicSource {
+    /// Read topics through an initialized messaging service.
+    pub fn new(service: std::sync::Arc<creto_messaging::MessagingService>) -> Self {
+        Self { service }
+    }
+}
+
+#[cfg(feature = "messaging")]
+#[async_trait]
+impl TopicSource for MessagingTopicSource {
+    async fn subscribe(&self, trigger: &TopicTrigger) -> CretoResult<Uuid> {
+        let filter = (!trigger.filter.is_empty()).then(|| {
+            trigger
+                .filter
+                .iter()
+                .fold(creto_messaging::SubscriptionFilter::new(), |f, (k, v)| {
+                    f.with_metadata(k.clone(), v.clone())
+                })
+        });
+        let subscription = self.service.subscribe(trigger.topic_id, filter).await?;
+        Ok(subscription.id)
+    }
+
+    async fn poll(
+        &self,
+        subscription_id: Uuid,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> CretoResult<Vec<TriggerMessage>> {
+        let messages = self
+            .service
+            .poll_subscription(subscription_id, after, limit)
+            .await?;
+        Ok(messages
+            .into_iter()
+            .map(|m| TriggerMessage {
+                id: m.id,
+                topic_id: m.topic_id,
+                payload: m.payload.to_vec(),
+                metadata: m.metadata,
+                published_at: m.published_at,
+            })
+            .collect())
+    }
+
+    async fn unsubscribe(&self, subscription_id: Uuid) -> CretoResult<()> {
+        self.service.unsubscribe(subscription_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sandbox::SandboxId;
+
+    fn message(metadata: &[(&str, &str)]) -> TriggerMessage {
+        TriggerMessage {
+            id: Uuid::new_v4(),
+            topic_id: Uuid::new_v4(),
+            payload: b"{\"order\":42}".to_vec(),
+            metadata: metadata
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            published_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_request_for_message() {
+        let trigger = TopicTrigger::new(
+            OrganizationId::new(),
+            AgentId::new(),
+            Uuid::new_v4(),
+            ExecutionRequest::new(SandboxId::new(), "handle()"),
+        )
+        .with_filter("kind", "order");
+        let msg = message(&[("kind", "order"), ("trace-id", "abc")]);
+        assert!(trigger.matches(&msg.metadata));
+        assert!(!trigger.matches(&message(&[("kind", "refund")]).metadata));
+
+        let request = trigger.request_for(&msg);
+        assert_ne!(request.id, trigger.template.id);
+        assert_eq!(request.caused_by, Some(msg.id));
+        assert_eq!(request.artifacts[0].path, MESSAGE_PAYLOAD_PATH);
+        assert_eq!(request.artifacts[0].content, msg.payload);
+        let env: HashMap<_, _> = request
+            .environment
+            .iter()
+            .map(|v| (v.name.as_str(), v.value.as_str()))
+            .collect();
+        assert_eq!(env[MESSAGE_ID_ENV], msg.id.to_string());
+        assert_eq!(env["CRETO_MESSAGE_META_KIND"], "order");
+        assert_eq!(env["CRETO_MESSAGE_META_TRACE_ID"], "abc");
+    }
+}
@@ -0,0 +1,12 @@
+-- Resource usage time series per execution
+-- Only written when sample persistence is enabled on PgResourceUsageRepository.
+
+CREATE TABLE IF NOT EXISTS resource_usage_samples (
+    execution_id UUID PRIMARY KEY REFERENCES execution_requests(id) ON DELETE CASCADE,
+    sandbox_id UUID NOT NULL REFERENCES sandboxes(id) ON DELETE CASCADE,
+    samples JSONB NOT NULL,  -- [{offset_ms, memory_bytes, cpu_ms_delta, open_fds}, ...]
+    sample_count INTEGER NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_resource_usage_samples_sandbox ON resource_usage_samples(sandbox_id);
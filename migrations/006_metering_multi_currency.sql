@@ -0,0 +1,27 @@
+-- Multi-currency billing for Creto Enablement Layer
+
+-- Per-organization billing settings
+CREATE TABLE IF NOT EXISTS org_billing_profiles (
+    organization_id UUID PRIMARY KEY,
+    billing_currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Effective-dated exchange rates (units of to_currency per unit of from_currency)
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    from_currency VARCHAR(3) NOT NULL,
+    to_currency VARCHAR(3) NOT NULL,
+    rate DOUBLE PRECISION NOT NULL CHECK (rate > 0),
+    effective_from TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(from_currency, to_currency, effective_from)
+);
+
+CREATE INDEX idx_exchange_rates_pair ON exchange_rates(from_currency, to_currency, effective_from DESC);
+
+-- Line items record their currency and the conversion snapshot used, if any
+ALTER TABLE invoice_line_items
+    ADD COLUMN IF NOT EXISTS currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    ADD COLUMN IF NOT EXISTS conversion JSONB;
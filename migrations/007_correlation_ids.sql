@@ -0,0 +1,25 @@
+-- Request context propagation across products
+-- correlation_id is shared by everything derived from one originating action;
+-- caused_by points at the direct parent entity.
+
+ALTER TABLE usage_events
+    ADD COLUMN IF NOT EXISTS correlation_id UUID,
+    ADD COLUMN IF NOT EXISTS caused_by UUID;
+
+ALTER TABLE oversight_requests
+    ADD COLUMN IF NOT EXISTS correlation_id UUID,
+    ADD COLUMN IF NOT EXISTS caused_by UUID;
+
+ALTER TABLE execution_requests
+    ADD COLUMN IF NOT EXISTS correlation_id UUID,
+    ADD COLUMN IF NOT EXISTS caused_by UUID;
+
+ALTER TABLE message_envelopes
+    ADD COLUMN IF NOT EXISTS correlation_id UUID,
+    ADD COLUMN IF NOT EXISTS caused_by UUID;
+
+-- Partial indexes: most rows are untraced
+CREATE INDEX idx_usage_events_correlation ON usage_events(correlation_id) WHERE correlation_id IS NOT NULL;
+CREATE INDEX idx_oversight_requests_correlation ON oversight_requests(correlation_id) WHERE correlation_id IS NOT NULL;
+CREATE INDEX idx_execution_requests_correlation ON execution_requests(correlation_id) WHERE correlation_id IS NOT NULL;
+CREATE INDEX idx_message_envelopes_correlation ON message_envelopes(correlation_id) WHERE correlation_id IS NOT NULL;
@@ -0,0 +1,16 @@
+-- Organization-level network policy templates
+-- Sandboxes reference a template by name; the resolved policy (template
+-- version + sandbox additions) is snapshotted onto the sandbox for audit.
+
+CREATE TABLE IF NOT EXISTS network_policy_templates (
+    organization_id UUID NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    policy JSONB NOT NULL,  -- NetworkPolicy serialized
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, name)
+);
+
+ALTER TABLE sandboxes
+    ADD COLUMN IF NOT EXISTS effective_network_policy JSONB;  -- EffectiveNetworkPolicy serialized
@@ -0,0 +1,13 @@
+-- Per-organization API keys for the metering gRPC edge
+-- Only SHA-256 hashes of keys are stored; plaintext is shown once at issue time.
+
+CREATE TABLE IF NOT EXISTS metering_api_keys (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    key_hash CHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ,  -- set on rotation (end of grace window)
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_metering_api_keys_org ON metering_api_keys(organization_id);
@@ -0,0 +1,21 @@
+-- Secret access audit trail
+-- One row per secret mounted into a sandbox. Only a reference to the secret
+-- is stored, never its value.
+
+CREATE TABLE IF NOT EXISTS secret_grants (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    sandbox_id UUID NOT NULL,                -- No FK: grants outlive sandbox rows
+    agent_id UUID NOT NULL,
+    secret_name VARCHAR(255) NOT NULL,       -- Mount name (env var / file name)
+    secret_reference VARCHAR(1024) NOT NULL, -- e.g. vault:path#key, org:name
+    target VARCHAR(32) NOT NULL,             -- environment_variable, file, directory
+    access_detection BOOLEAN NOT NULL DEFAULT FALSE,
+    granted_at TIMESTAMPTZ NOT NULL,
+    first_accessed_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_secret_grants_sandbox ON secret_grants(sandbox_id);
+CREATE INDEX IF NOT EXISTS idx_secret_grants_org_granted ON secret_grants(organization_id, granted_at);
+CREATE INDEX IF NOT EXISTS idx_secret_grants_active ON secret_grants(sandbox_id) WHERE revoked_at IS NULL;
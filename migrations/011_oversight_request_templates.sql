@@ -0,0 +1,23 @@
+-- Organization-level oversight request templates
+-- Requests created from a template snapshot the template version and the
+-- supplied fields, so template updates never alter in-flight requests.
+
+CREATE TABLE IF NOT EXISTS oversight_request_templates (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    action_pattern JSONB NOT NULL,           -- ActionTypePattern serialized
+    fields JSONB NOT NULL DEFAULT '[]',      -- TemplateField list
+    default_priority VARCHAR(10) NOT NULL DEFAULT 'medium',
+    default_timeout_seconds BIGINT,          -- NULL: priority default
+    quorum JSONB,                            -- NULL: organization default
+    reviewer_group VARCHAR(255),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_oversight_request_templates_org ON oversight_request_templates(organization_id);
+
+ALTER TABLE oversight_requests
+    ADD COLUMN IF NOT EXISTS template JSONB;  -- AppliedTemplate serialized
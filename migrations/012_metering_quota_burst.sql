@@ -0,0 +1,6 @@
+-- Quota burst allowance payback
+-- Usage borrowed over a quota's limit is carried into the next period's row
+-- and deducted from that period's effective limit.
+
+ALTER TABLE quotas
+    ADD COLUMN IF NOT EXISTS carried_debt BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,15 @@
+-- Structured execution logs
+-- Lines emitted with the structured log prefix, bounded per execution by the
+-- runtime's log capture configuration. Raw stdout/stderr are unaffected.
+
+CREATE TABLE IF NOT EXISTS execution_logs (
+    execution_id UUID NOT NULL REFERENCES execution_requests(id) ON DELETE CASCADE,
+    seq INTEGER NOT NULL,                -- Emission order within the execution
+    level VARCHAR(10) NOT NULL,          -- trace, debug, info, warn, error
+    message TEXT NOT NULL,
+    fields JSONB NOT NULL DEFAULT '{}',
+    logged_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (execution_id, seq)
+);
+
+CREATE INDEX IF NOT EXISTS idx_execution_logs_level ON execution_logs(execution_id, level);
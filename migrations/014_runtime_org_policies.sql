@@ -0,0 +1,15 @@
+-- Organization-wide runtime policies
+-- Defaults and pinned fields merged into every sandbox configuration an
+-- organization requests. Sandboxes record the policy version they were
+-- created under; updates only apply to new sandboxes.
+
+CREATE TABLE IF NOT EXISTS org_runtime_policies (
+    organization_id UUID PRIMARY KEY,
+    policy JSONB NOT NULL,  -- OrgRuntimePolicy serialized
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE sandboxes
+    ADD COLUMN IF NOT EXISTS runtime_policy_version INTEGER;
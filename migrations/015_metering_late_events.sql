@@ -0,0 +1,20 @@
+-- Late usage events
+-- Events that arrived further behind the per-organization, per-metric
+-- watermark than the allowed lateness. They are kept out of usage_events
+-- until resolved by the organization's late event policy.
+
+CREATE TABLE IF NOT EXISTS metering_late_events (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    code VARCHAR(255) NOT NULL,
+    event JSONB NOT NULL,                -- The usage event as received
+    event_timestamp TIMESTAMPTZ NOT NULL,
+    watermark TIMESTAMPTZ NOT NULL,      -- Watermark when the event arrived
+    received_at TIMESTAMPTZ NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending', -- pending, folded, rejected
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_metering_late_events_pending
+    ON metering_late_events(organization_id, received_at)
+    WHERE status = 'pending';
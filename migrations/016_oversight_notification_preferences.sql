@@ -0,0 +1,27 @@
+-- Reviewer notification preferences and held notifications
+-- Organization defaults are stored with the nil UUID as reviewer_id.
+-- Notifications held for quiet hours or digests are persisted so they are
+-- still delivered after a restart.
+
+CREATE TABLE IF NOT EXISTS oversight_notification_preferences (
+    organization_id UUID NOT NULL,
+    reviewer_id UUID NOT NULL,               -- Nil UUID: organization defaults
+    channels JSONB NOT NULL DEFAULT '{}',    -- Priority -> ordered channel list
+    quiet_hours JSONB,                       -- QuietHours serialized
+    digest_interval_hours INTEGER,           -- NULL: digest mode off
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, reviewer_id)
+);
+
+CREATE TABLE IF NOT EXISTS oversight_pending_notifications (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    reviewer_id UUID,                        -- NULL: organization-level routing
+    channel VARCHAR(20) NOT NULL,
+    request JSONB NOT NULL,                  -- OversightRequest snapshot
+    reason VARCHAR(20) NOT NULL,             -- quiet_hours, digest
+    deliver_at TIMESTAMPTZ NOT NULL,
+    queued_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_oversight_pending_notifications_due ON oversight_pending_notifications(deliver_at);
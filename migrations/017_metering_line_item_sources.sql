@@ -0,0 +1,11 @@
+-- Line item drill-down
+-- Line items record the usage selection (organization, metric code, event
+-- window) and event count they were aggregated from, so the billed events
+-- can be re-derived and checked against the invoiced quantity.
+
+ALTER TABLE invoice_line_items
+    ADD COLUMN IF NOT EXISTS source JSONB;
+
+-- Keyset pagination over a metric's events in a window
+CREATE INDEX IF NOT EXISTS idx_usage_events_org_code_time
+    ON usage_events(organization_id, code, timestamp, transaction_id);
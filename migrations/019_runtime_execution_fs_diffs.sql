@@ -0,0 +1,16 @@
+-- Execution filesystem diffs
+-- Files created, modified and deleted in the sandbox workdir by an
+-- execution, stored when the runtime's filesystem tracking is configured to
+-- persist full diffs. The summary counts are also attached to the execution
+-- result.
+
+CREATE TABLE IF NOT EXISTS execution_fs_diffs (
+    execution_id UUID PRIMARY KEY REFERENCES execution_requests(id) ON DELETE CASCADE,
+    created_count INTEGER NOT NULL DEFAULT 0,
+    modified_count INTEGER NOT NULL DEFAULT 0,
+    deleted_count INTEGER NOT NULL DEFAULT 0,
+    bytes_changed BIGINT NOT NULL DEFAULT 0,
+    truncated BOOLEAN NOT NULL DEFAULT false,    -- Scan hit the file limit
+    changes JSONB NOT NULL DEFAULT '[]',         -- [{path, change, before, after}]
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,19 @@
+-- Usage anomaly baselines
+-- Rolling per-window usage statistics per (organization, agent, metric code),
+-- checkpointed by the anomaly detector so baselines and alert suppression
+-- survive restarts.
+
+CREATE TABLE IF NOT EXISTS metering_anomaly_baselines (
+    organization_id UUID NOT NULL,
+    agent_id UUID NOT NULL,
+    metric_code VARCHAR(255) NOT NULL,
+    mean DOUBLE PRECISION NOT NULL,      -- EWMA of usage per window
+    variance DOUBLE PRECISION NOT NULL,  -- Exponentially weighted variance
+    windows BIGINT NOT NULL,             -- Closed windows folded in
+    window_start TIMESTAMPTZ NOT NULL,   -- Start of the open window
+    window_quantity BIGINT NOT NULL,     -- Usage so far in the open window
+    suppressed_until TIMESTAMPTZ,
+    alerted_severity VARCHAR(20),        -- elevated, high
+    updated_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (organization_id, agent_id, metric_code)
+);
@@ -0,0 +1,30 @@
+-- Decision webhooks
+-- Organizations register endpoints that are POSTed a signed payload when an
+-- oversight request reaches a terminal status. Every delivery and its
+-- attempts are logged for debugging and manual redelivery.
+
+CREATE TABLE IF NOT EXISTS oversight_webhook_endpoints (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,                    -- HMAC-SHA256 signing secret
+    filter JSONB NOT NULL DEFAULT '{}',      -- WebhookFilter: statuses, action types
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_oversight_webhook_endpoints_org ON oversight_webhook_endpoints(organization_id);
+
+CREATE TABLE IF NOT EXISTS oversight_webhook_deliveries (
+    id UUID PRIMARY KEY,
+    endpoint_id UUID NOT NULL REFERENCES oversight_webhook_endpoints(id) ON DELETE CASCADE,
+    organization_id UUID NOT NULL,
+    request_id UUID NOT NULL,
+    body TEXT NOT NULL,                      -- Payload exactly as signed
+    state VARCHAR(20) NOT NULL,              -- pending, delivered, failed, circuit_open
+    attempts JSONB NOT NULL DEFAULT '[]',    -- DeliveryAttempt list
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_oversight_webhook_deliveries_request ON oversight_webhook_deliveries(request_id);
@@ -0,0 +1,29 @@
+-- Versioned pricing models
+-- Every version of a metric's price with its effective range, plus
+-- organizations pinned (grandfathered) to a version until migrated.
+-- Effective ranges of a metric's versions never overlap.
+
+CREATE TABLE IF NOT EXISTS pricing_model_versions (
+    id VARCHAR(255) PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    metric_code VARCHAR(255) NOT NULL,
+    version INTEGER NOT NULL,
+    currency VARCHAR(3) NOT NULL DEFAULT 'USD',
+    strategy JSONB NOT NULL,               -- Serialized PricingStrategy
+    effective_from TIMESTAMPTZ NOT NULL,   -- Inclusive
+    effective_until TIMESTAMPTZ,           -- Exclusive; NULL is open-ended
+    supersedes VARCHAR(255) REFERENCES pricing_model_versions(id),
+    UNIQUE (metric_code, version),
+    CHECK (effective_until IS NULL OR effective_until > effective_from)
+);
+
+CREATE INDEX idx_pricing_model_versions_effective
+    ON pricing_model_versions(metric_code, effective_from);
+
+CREATE TABLE IF NOT EXISTS pricing_pins (
+    organization_id UUID NOT NULL,
+    metric_code VARCHAR(255) NOT NULL,
+    model_id VARCHAR(255) NOT NULL REFERENCES pricing_model_versions(id),
+    pinned_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (organization_id, metric_code)
+);
@@ -0,0 +1,26 @@
+-- Messaging priority lanes and per-recipient mailbox quotas
+-- Envelopes carry a delivery priority (0 = low, 1 = normal, 2 = high) and
+-- are fetched by priority, then age. Each recipient's undelivered backlog is
+-- kept in a counter table, maintained alongside the envelopes, so quota
+-- checks at store time never count rows.
+
+ALTER TABLE message_envelopes
+    ADD COLUMN IF NOT EXISTS priority SMALLINT NOT NULL DEFAULT 1;
+
+CREATE INDEX idx_envelopes_delivery_order
+    ON message_envelopes(recipient_id, priority DESC, created_at)
+    WHERE NOT delivered;
+
+CREATE TABLE IF NOT EXISTS message_mailbox_usage (
+    recipient_id UUID PRIMARY KEY,
+    undelivered_count BIGINT NOT NULL DEFAULT 0,
+    undelivered_bytes BIGINT NOT NULL DEFAULT 0,  -- Total ciphertext bytes
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO message_mailbox_usage (recipient_id, undelivered_count, undelivered_bytes)
+SELECT recipient_id, COUNT(*), COALESCE(SUM(octet_length(ciphertext)), 0)
+FROM message_envelopes
+WHERE NOT delivered
+GROUP BY recipient_id
+ON CONFLICT (recipient_id) DO NOTHING;
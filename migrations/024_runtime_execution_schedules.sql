@@ -0,0 +1,41 @@
+-- Scheduled and recurring executions
+-- A schedule runs a copy of its execution request template on a cron
+-- expression or fixed interval. Scheduler workers claim due schedules by
+-- setting claim_token under a lease (claim_expires_at); a claim is released
+-- when the worker advances next_run_at, and any update to the schedule
+-- invalidates it.
+
+CREATE TABLE IF NOT EXISTS execution_schedules (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    agent_id UUID NOT NULL,
+    template JSONB NOT NULL,                     -- ExecutionRequest
+    trigger JSONB NOT NULL,                      -- {type: cron|interval, ...}
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    catch_up VARCHAR(16) NOT NULL DEFAULT 'skip' CHECK (catch_up IN ('skip', 'run_once')),
+    next_run_at TIMESTAMPTZ,                     -- NULL once the trigger never fires again
+    claim_token UUID,
+    claim_expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_execution_schedules_org
+    ON execution_schedules(organization_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_execution_schedules_due
+    ON execution_schedules(next_run_at)
+    WHERE enabled AND next_run_at IS NOT NULL;
+
+-- Run history is kept after its schedule is deleted
+CREATE TABLE IF NOT EXISTS execution_schedule_runs (
+    id UUID PRIMARY KEY,
+    schedule_id UUID NOT NULL,
+    scheduled_for TIMESTAMPTZ NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    execution_id UUID,                           -- NULL for skipped runs
+    missed INTEGER NOT NULL DEFAULT 0,           -- Occurrences missed before this run
+    outcome JSONB NOT NULL                       -- {type: completed|failed|skipped, ...}
+);
+
+CREATE INDEX IF NOT EXISTS idx_execution_schedule_runs_schedule
+    ON execution_schedule_runs(schedule_id, recorded_at DESC);
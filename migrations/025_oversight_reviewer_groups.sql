@@ -0,0 +1,22 @@
+-- Reviewer groups
+-- Named groups templates route oversight requests to, maintained locally or
+-- synced from an external directory (SCIM/OIDC groups). Memberships are a
+-- history of {user_id, pinned, added_at, removed_at} so approvals are judged
+-- against membership at the time they were given. Groups deleted upstream
+-- are disabled rather than deleted.
+
+CREATE TABLE IF NOT EXISTS oversight_reviewer_groups (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    external_id VARCHAR(255),                -- Directory group ID; NULL for local groups
+    status VARCHAR(16) NOT NULL DEFAULT 'active' CHECK (status IN ('active', 'disabled')),
+    memberships JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (organization_id, name)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_oversight_reviewer_groups_external
+    ON oversight_reviewer_groups(organization_id, external_id)
+    WHERE external_id IS NOT NULL;
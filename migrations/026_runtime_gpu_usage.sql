@@ -0,0 +1,6 @@
+-- GPU usage per resource usage snapshot
+-- Samples in resource_usage_samples carry gpu_ms_delta in their JSONB.
+
+ALTER TABLE resource_usage
+    ADD COLUMN IF NOT EXISTS gpu_time_ms BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS gpu_memory_bytes BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,41 @@
+-- Wallet auto top-up
+-- Rules refill a prepaid wallet from a funding source when its balance drops
+-- below a threshold, at most max_top_ups_per_period times per daily or
+-- monthly period. Each top-up is claimed under an idempotency key
+-- (wallet:period:sequence) before the funding source is charged, so
+-- concurrent checks produce at most one top-up.
+
+CREATE TABLE IF NOT EXISTS metering_auto_top_up_rules (
+    wallet_id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    threshold_cents BIGINT NOT NULL CHECK (threshold_cents >= 0),
+    top_up_amount_cents BIGINT NOT NULL CHECK (top_up_amount_cents > 0),
+    max_top_ups_per_period INTEGER NOT NULL CHECK (max_top_ups_per_period > 0),
+    period VARCHAR(16) NOT NULL CHECK (period IN ('daily', 'monthly')),
+    funding_source VARCHAR(255) NOT NULL,   -- Payment provider reference, e.g. a saved card
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_metering_auto_top_up_rules_org
+    ON metering_auto_top_up_rules(organization_id);
+
+CREATE TABLE IF NOT EXISTS metering_auto_top_ups (
+    id UUID PRIMARY KEY,
+    wallet_id UUID NOT NULL,
+    organization_id UUID NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL UNIQUE,
+    period_key VARCHAR(16) NOT NULL,         -- 2026-10 (monthly) or 2026-10-16 (daily)
+    sequence INTEGER NOT NULL,
+    amount_cents BIGINT NOT NULL,
+    status VARCHAR(16) NOT NULL CHECK (status IN ('pending', 'succeeded', 'failed')),
+    payment_id VARCHAR(255),
+    transaction_id UUID,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_metering_auto_top_ups_wallet_period
+    ON metering_auto_top_ups(wallet_id, period_key, sequence);
@@ -0,0 +1,9 @@
+-- Versioned envelope storage
+-- Each envelope is also kept in its versioned wire form (without the
+-- ciphertext, which stays in its own column for mailbox accounting), so
+-- header and payload fields added by newer nodes survive store-and-forward
+-- through older ones. The individual columns remain for routing and
+-- indexing. NULL for envelopes stored before this migration.
+
+ALTER TABLE message_envelopes
+    ADD COLUMN IF NOT EXISTS wire_envelope JSONB;
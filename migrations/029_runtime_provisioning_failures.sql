@@ -0,0 +1,26 @@
+-- Failed sandbox provisioning attempts, kept for operators
+-- One row per failed attempt; a request that was retried has several.
+-- sandbox_id is NULL when the attempt failed before a sandbox record was
+-- written.
+
+CREATE TABLE IF NOT EXISTS sandbox_provisioning_failures (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    agent_id UUID NOT NULL,
+    sandbox_id UUID,
+    runtime VARCHAR(64) NOT NULL,
+    attempt INTEGER NOT NULL,
+    phase VARCHAR(32) NOT NULL CHECK (phase IN (
+        'image_resolution', 'resource_allocation', 'secret_injection', 'attestation'
+    )),
+    cause VARCHAR(32) NOT NULL,                  -- host_pressure, invalid_config, ...
+    retryable BOOLEAN NOT NULL,
+    message TEXT NOT NULL,
+    phases JSONB NOT NULL DEFAULT '[]',          -- PhaseTiming of the failed attempt
+    failed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_sandbox_provisioning_failures_failed_at
+    ON sandbox_provisioning_failures(failed_at DESC);
+CREATE INDEX IF NOT EXISTS idx_sandbox_provisioning_failures_org
+    ON sandbox_provisioning_failures(organization_id, failed_at DESC);
@@ -0,0 +1,28 @@
+-- Quota exemption windows
+-- An exemption lifts an organization's quotas (optionally one agent's, and
+-- only some metrics) between starts_at and ends_at, by a multiplier or
+-- entirely, e.g. during a data migration. Usage is still recorded while an
+-- exemption is active; checks it allows are flagged so billing can price
+-- the overage.
+
+CREATE TABLE IF NOT EXISTS metering_quota_exemptions (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    agent_id UUID,                              -- NULL exempts every agent
+    metric_codes TEXT[] NOT NULL DEFAULT '{}',  -- Empty exempts every metric
+    starts_at TIMESTAMPTZ NOT NULL,
+    ends_at TIMESTAMPTZ NOT NULL,
+    unlimited BOOLEAN NOT NULL DEFAULT FALSE,
+    multiplier DOUBLE PRECISION CHECK (multiplier >= 1),
+    reason TEXT NOT NULL CHECK (length(trim(reason)) > 0),
+    approved_by VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (ends_at > starts_at),
+    CHECK (unlimited OR multiplier IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_metering_quota_exemptions_org
+    ON metering_quota_exemptions(organization_id, starts_at);
+
+CREATE INDEX IF NOT EXISTS idx_metering_quota_exemptions_ends_at
+    ON metering_quota_exemptions(ends_at);
@@ -0,0 +1,18 @@
+-- Exclusion keys held by running executions
+-- One row per (organization, key). A row whose lease has lapsed belongs to
+-- a holder that stopped renewing it, usually because its service crashed,
+-- and may be taken over.
+
+CREATE TABLE IF NOT EXISTS runtime_exclusion_leases (
+    organization_id UUID NOT NULL,
+    exclusion_key VARCHAR(255) NOT NULL,
+    execution_id UUID NOT NULL,
+    priority VARCHAR(16) NOT NULL CHECK (priority IN ('low', 'normal', 'high', 'critical')),
+    owner VARCHAR(255) NOT NULL,                 -- node_id of the holding service
+    acquired_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (organization_id, exclusion_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_runtime_exclusion_leases_owner
+    ON runtime_exclusion_leases(owner);
@@ -0,0 +1,15 @@
+-- Request activity outside the state machine
+-- Notifications, reminders, views, comments and executions, merged with
+-- state_transitions, approvals and checkpoints into lifecycle exports.
+-- Rows are append-only.
+
+CREATE TABLE IF NOT EXISTS oversight_request_activity (
+    id UUID PRIMARY KEY,
+    request_id UUID NOT NULL REFERENCES oversight_requests(id) ON DELETE CASCADE,
+    actor JSONB NOT NULL,                        -- state::Actor
+    kind JSONB NOT NULL,                         -- lifecycle::ActivityKind, tagged by "type"
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_oversight_request_activity_request
+    ON oversight_request_activity(request_id, occurred_at);
@@ -0,0 +1,7 @@
+-- What happens to a secret grant when its sandbox is paused for being idle
+-- 'retain' keeps the secret mounted while paused; 'revoke' revokes the
+-- grant on pause. Hibernation revokes every grant regardless.
+
+ALTER TABLE secret_grants
+    ADD COLUMN IF NOT EXISTS idle_policy VARCHAR(16) NOT NULL DEFAULT 'retain'
+        CHECK (idle_policy IN ('retain', 'revoke'));
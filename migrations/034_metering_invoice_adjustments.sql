@@ -0,0 +1,31 @@
+-- Invoice adjustments
+-- Disputed charges are corrected by an adjustment rather than by editing
+-- invoice totals. Adjustments move proposed -> approved -> applied (or end
+-- rejected); applying one to a paid invoice issues a credit note, recorded
+-- in credit_note_id. Applying only succeeds from 'approved', so an
+-- adjustment is never applied twice.
+
+CREATE TABLE IF NOT EXISTS metering_invoice_adjustments (
+    id UUID PRIMARY KEY,
+    invoice_id UUID NOT NULL,
+    organization_id UUID NOT NULL,
+    line_item_id UUID,                          -- NULL adjusts the whole invoice
+    amount_cents BIGINT NOT NULL CHECK (amount_cents <> 0),  -- Negative credits the customer
+    reason TEXT NOT NULL CHECK (length(trim(reason)) > 0),
+    created_by VARCHAR(255) NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'proposed'
+        CHECK (status IN ('proposed', 'approved', 'applied', 'rejected')),
+    reviewed_by VARCHAR(255),
+    rejection_reason TEXT,
+    credit_note_id UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    reviewed_at TIMESTAMPTZ,
+    applied_at TIMESTAMPTZ,
+    CHECK (status <> 'applied' OR applied_at IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_metering_invoice_adjustments_invoice
+    ON metering_invoice_adjustments(invoice_id, created_at);
+
+CREATE INDEX IF NOT EXISTS idx_metering_invoice_adjustments_org
+    ON metering_invoice_adjustments(organization_id, created_at);
@@ -0,0 +1,7 @@
+-- Priority aging for oversight requests
+-- Pending requests are boosted as their timeout approaches. The priority a
+-- request was created with is kept alongside the boosted one; NULL means the
+-- request has not been boosted.
+
+ALTER TABLE oversight_requests
+    ADD COLUMN IF NOT EXISTS original_priority VARCHAR(10);  -- low, medium, high, critical
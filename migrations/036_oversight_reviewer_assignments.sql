@@ -0,0 +1,21 @@
+-- Per-reviewer decision deadlines
+-- Each reviewer assigned to a request gets their own respond_by, derived
+-- from the request's priority and never later than the request timeout.
+-- Active assignments past respond_by are marked lapsed by the timeout worker.
+
+CREATE TABLE IF NOT EXISTS oversight_reviewer_assignments (
+    id UUID PRIMARY KEY,
+    request_id UUID NOT NULL REFERENCES oversight_requests(id) ON DELETE CASCADE,
+    organization_id UUID NOT NULL,
+    reviewer_id UUID NOT NULL,
+    source JSONB NOT NULL,                       -- assignments::AssignmentSource, tagged by "type"
+    status VARCHAR(20) NOT NULL DEFAULT 'active', -- active, responded, lapsed
+    assigned_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    respond_by TIMESTAMPTZ NOT NULL,
+    lapsed_at TIMESTAMPTZ,
+    UNIQUE (request_id, reviewer_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_oversight_reviewer_assignments_due
+    ON oversight_reviewer_assignments(respond_by)
+    WHERE status = 'active';
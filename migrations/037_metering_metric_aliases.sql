@@ -0,0 +1,24 @@
+-- Metric code aliases
+-- A renamed metric keeps its history under the old code. An alias maps the
+-- old code to the canonical one for events timestamped within
+-- [effective_from, effective_until); NULL bounds are open. Aggregation,
+-- quota keys and pricing resolve aliased codes to the canonical code.
+-- Aliases resolve in one hop: chains and cycles are rejected when an alias
+-- is registered.
+
+CREATE TABLE IF NOT EXISTS metering_metric_aliases (
+    id UUID PRIMARY KEY,
+    old_code VARCHAR(64) NOT NULL,
+    canonical_code VARCHAR(64) NOT NULL,
+    effective_from TIMESTAMPTZ,
+    effective_until TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (old_code <> canonical_code),
+    CHECK (effective_from IS NULL OR effective_until IS NULL OR effective_until > effective_from)
+);
+
+CREATE INDEX IF NOT EXISTS idx_metering_metric_aliases_old_code
+    ON metering_metric_aliases(old_code);
+
+CREATE INDEX IF NOT EXISTS idx_metering_metric_aliases_canonical_code
+    ON metering_metric_aliases(canonical_code);
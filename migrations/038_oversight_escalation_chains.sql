@@ -0,0 +1,22 @@
+-- Escalation chains for oversight requests
+-- A request nobody decides in time is handed to the next tier of its
+-- organization's chain with a fresh timeout; once every tier has timed out
+-- it is closed as on_exhausted says. escalation_level counts the tiers a
+-- request has been handed to (0 = its original reviewers) and
+-- assigned_reviewers holds the reviewers of the current tier.
+
+ALTER TABLE oversight_requests
+    ADD COLUMN IF NOT EXISTS escalation_level INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS assigned_reviewers UUID[] NOT NULL DEFAULT '{}';
+
+CREATE TABLE IF NOT EXISTS oversight_escalation_chains (
+    organization_id UUID PRIMARY KEY,
+    tiers JSONB NOT NULL,                                -- [escalation::EscalationTier], first to last
+    on_exhausted VARCHAR(10) NOT NULL DEFAULT 'reject', -- reject, approve
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_oversight_requests_open_timeout
+    ON oversight_requests(timeout_at)
+    WHERE status IN ('pending', 'in_review', 'escalated');
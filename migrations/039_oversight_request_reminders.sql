@@ -0,0 +1,9 @@
+-- Reminders for pending oversight requests
+-- The reminder scheduler reminds reviewers of requests left open, at a fixed
+-- or exponential cadence, up to a cap. reminder_count is the number of
+-- reminders sent and last_reminded_at when the last one went out; the next
+-- reminder is due relative to it.
+
+ALTER TABLE oversight_requests
+    ADD COLUMN IF NOT EXISTS reminder_count INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS last_reminded_at TIMESTAMPTZ;
@@ -0,0 +1,7 @@
+-- Channels each oversight request was delivered on
+-- Channel routing fans a request out to the channels its rules select.
+-- notified_channels records the ones that delivered, so reminders and
+-- escalations reach reviewers where the request first did.
+
+ALTER TABLE oversight_requests
+    ADD COLUMN IF NOT EXISTS notified_channels TEXT[] NOT NULL DEFAULT '{}';
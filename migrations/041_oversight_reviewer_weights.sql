@@ -0,0 +1,16 @@
+-- Reviewer weights for weighted quorums
+-- Organizations weight the decisions of a directory role or of a single
+-- reviewer; a reviewer's own weight overrides their roles, and reviewers
+-- with neither count as 1. The service resolves the weight when a decision
+-- is submitted and records it in approvals.weight, so later changes here do
+-- not affect decisions already made.
+
+CREATE TABLE IF NOT EXISTS oversight_reviewer_weights (
+    organization_id UUID NOT NULL,
+    subject_type VARCHAR(10) NOT NULL,  -- role, user
+    subject_id TEXT NOT NULL,           -- role name, or user UUID
+    weight INTEGER NOT NULL CHECK (weight > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, subject_type, subject_id)
+);
@@ -0,0 +1,13 @@
+-- Idempotent envelope delivery receipts
+-- An envelope has at most one receipt of each type, so a recipient that
+-- fetches or acknowledges an envelope twice does not record it twice. The
+-- earliest of any existing duplicates is kept.
+
+DELETE FROM delivery_receipts r
+USING delivery_receipts earlier
+WHERE r.envelope_id = earlier.envelope_id
+  AND r.receipt_type = earlier.receipt_type
+  AND (r.created_at, r.id) > (earlier.created_at, earlier.id);
+
+CREATE UNIQUE INDEX idx_receipts_envelope_type
+    ON delivery_receipts(envelope_id, receipt_type);
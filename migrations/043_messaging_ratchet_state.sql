@@ -0,0 +1,21 @@
+-- Encrypted ratchet state
+-- Ratchet state is sealed with a caller-held key before it is written, so
+-- this table only holds AES-256-GCM ciphertext. Rows are keyed by the
+-- in-process session ID, which messaging_sessions links to once the first
+-- write has happened. The plaintext-sized columns on messaging_sessions
+-- were never written and are superseded by this table.
+
+CREATE TABLE IF NOT EXISTS messaging_ratchet_states (
+    session_id UUID PRIMARY KEY,
+    nonce BYTEA NOT NULL,
+    ciphertext BYTEA NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE messaging_sessions
+    ADD COLUMN IF NOT EXISTS ratchet_state_id UUID
+        REFERENCES messaging_ratchet_states(session_id) ON DELETE SET NULL;
+
+ALTER TABLE messaging_sessions
+    DROP COLUMN IF EXISTS ratchet_state_encrypted,
+    DROP COLUMN IF EXISTS ratchet_state_nonce;
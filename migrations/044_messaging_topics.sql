@@ -0,0 +1,33 @@
+-- Pub/sub topics and subscriptions
+-- Topics keep their access policies and retention settings here so they
+-- survive a restart; retained messages are held in memory only. A topic
+-- name is unique per owner.
+
+CREATE TABLE IF NOT EXISTS messaging_topics (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    owner_agent_id UUID NOT NULL,
+    publish_policy TEXT NOT NULL,
+    subscribe_policy TEXT NOT NULL,
+    retention_max_messages INTEGER,
+    retention_ttl_seconds BIGINT,
+    max_message_size BIGINT NOT NULL,
+    max_subscribers INTEGER,
+    allowed_agents UUID[] NOT NULL DEFAULT '{}',
+    replay_allowed BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT uq_messaging_topics_owner_name UNIQUE (owner_agent_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS messaging_subscriptions (
+    id UUID PRIMARY KEY,
+    topic_id UUID NOT NULL REFERENCES messaging_topics(id) ON DELETE CASCADE,
+    subscriber_agent_id UUID NOT NULL,
+    filter JSONB,
+    start_position JSONB NOT NULL,
+    subscribed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_messaging_subscriptions_topic
+    ON messaging_subscriptions(topic_id);
@@ -0,0 +1,6 @@
+-- Authorization hook failure mode for topics
+-- Topics with an authz_required policy ask an authorization hook; this
+-- records whether they allow or deny access while the hook is failing.
+
+ALTER TABLE messaging_topics
+    ADD COLUMN IF NOT EXISTS authz_failure_mode TEXT NOT NULL DEFAULT 'fail_closed';
@@ -0,0 +1,29 @@
+-- Quota reservations
+-- Reservations hold quota for an operation that has not reported its usage
+-- yet. They were only kept in memory, so a crash left the reserved amount
+-- unaccounted for; the enforcer now writes every reservation through to
+-- this table and reloads the active ones on startup, expiring any whose
+-- TTL passed while it was down.
+
+CREATE TABLE IF NOT EXISTS metering_quota_reservations (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    agent_id TEXT NOT NULL,
+    metric_code VARCHAR(255) NOT NULL,
+    reserved_amount BIGINT NOT NULL CHECK (reserved_amount >= 0),
+    actual_amount BIGINT,                       -- Set on commit
+    status VARCHAR(20) NOT NULL DEFAULT 'active'
+        CHECK (status IN ('active', 'committed', 'released', 'expired')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    metadata JSONB,
+    group_id UUID,                              -- Reserved, committed and released together
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_metering_quota_reservations_active
+    ON metering_quota_reservations(expires_at)
+    WHERE status = 'active';
+
+CREATE INDEX IF NOT EXISTS idx_metering_quota_reservations_org
+    ON metering_quota_reservations(organization_id, metric_code);
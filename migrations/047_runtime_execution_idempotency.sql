@@ -0,0 +1,16 @@
+-- Idempotency keys of execution requests
+-- A retry with the same (sandbox_id, idempotency_key) within the service's
+-- idempotency TTL gets idempotent_result instead of running again.
+-- idempotency_fingerprint is the BLAKE3 hash of the request payload; a
+-- retry with a different payload is refused.
+
+ALTER TABLE execution_requests
+    ADD COLUMN IF NOT EXISTS idempotency_key VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS idempotency_fingerprint VARCHAR(64),
+    ADD COLUMN IF NOT EXISTS idempotent_result JSONB,   -- ExecutionResult replayed to retries
+    ADD COLUMN IF NOT EXISTS idempotent_recorded_at TIMESTAMPTZ;
+
+-- Not unique: a key may be used again once its result has expired
+CREATE INDEX IF NOT EXISTS idx_execution_requests_idempotency
+    ON execution_requests(sandbox_id, idempotency_key, idempotent_recorded_at DESC)
+    WHERE idempotency_key IS NOT NULL;
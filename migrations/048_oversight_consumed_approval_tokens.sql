@@ -0,0 +1,14 @@
+-- Approval tokens that have decided their request
+-- Email approval links are single-use: the HTTP endpoints record a token's
+-- HMAC signature when it is used and refuse it afterwards. Rows can be
+-- dropped once the token expires, since it is refused as expired from then
+-- on.
+
+CREATE TABLE IF NOT EXISTS oversight_consumed_approval_tokens (
+    signature VARCHAR(64) PRIMARY KEY,  -- hex HMAC-SHA256 tag of the token
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_oversight_consumed_approval_tokens_expires
+    ON oversight_consumed_approval_tokens(expires_at);
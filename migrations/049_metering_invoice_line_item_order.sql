@@ -0,0 +1,11 @@
+-- Stored invoices
+-- Invoices are written with their line items so drill-down and adjustments
+-- work from the database. Line items keep their order on the invoice; the
+-- fields without a column (metric code, unit, applied pricing) are kept in
+-- metadata.
+
+ALTER TABLE invoice_line_items
+    ADD COLUMN IF NOT EXISTS position INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_line_items_invoice_position
+    ON invoice_line_items(invoice_id, position);
@@ -0,0 +1,43 @@
+-- Topic triggers and their dispatch state
+-- A trigger runs a copy of its execution request template for each
+-- matching message on a topic. cursor is the last message read from the
+-- trigger's subscription; it advances once the messages read were
+-- attempted, so delivery is at-least-once across restarts. Failed
+-- attempts wait in topic_trigger_retries until they succeed or are moved
+-- to topic_trigger_dead_letters.
+
+CREATE TABLE IF NOT EXISTS topic_triggers (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL,
+    trigger JSONB NOT NULL,                      -- TopicTrigger
+    subscription_id UUID NOT NULL,
+    cursor UUID,                                 -- NULL until a message is read
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_topic_triggers_org
+    ON topic_triggers(organization_id, created_at);
+
+CREATE TABLE IF NOT EXISTS topic_trigger_retries (
+    trigger_id UUID NOT NULL REFERENCES topic_triggers(id) ON DELETE CASCADE,
+    message_id UUID NOT NULL,
+    message JSONB NOT NULL,                      -- TriggerMessage
+    failures INTEGER NOT NULL,
+    next_attempt_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (trigger_id, message_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_topic_trigger_retries_due
+    ON topic_trigger_retries(trigger_id, next_attempt_at);
+
+CREATE TABLE IF NOT EXISTS topic_trigger_dead_letters (
+    id BIGSERIAL PRIMARY KEY,
+    trigger_id UUID NOT NULL REFERENCES topic_triggers(id) ON DELETE CASCADE,
+    message JSONB NOT NULL,                      -- TriggerMessage
+    attempts INTEGER NOT NULL,
+    last_error TEXT NOT NULL,
+    dead_lettered_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_topic_trigger_dead_letters_trigger
+    ON topic_trigger_dead_letters(trigger_id, dead_lettered_at);
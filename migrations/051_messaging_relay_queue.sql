@@ -0,0 +1,25 @@
+-- Cross-region relay queue
+-- Frames a relay node has queued for its peers, forwarded in seq order
+-- and deleted once the peer accepts them, and the return routes of
+-- envelopes relayed to the node, deleted when the envelope's receipt is
+-- queued back to its origin.
+
+CREATE TABLE IF NOT EXISTS messaging_relay_queue (
+    seq BIGSERIAL PRIMARY KEY,
+    frame_id UUID NOT NULL UNIQUE,
+    node VARCHAR(255) NOT NULL,                  -- Relay node the frame leaves from
+    peer VARCHAR(255) NOT NULL,
+    frame JSONB NOT NULL,                        -- RelayFrame
+    queued_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_messaging_relay_queue_node
+    ON messaging_relay_queue(node, seq);
+
+CREATE TABLE IF NOT EXISTS messaging_relay_return_routes (
+    envelope_id UUID NOT NULL,
+    node VARCHAR(255) NOT NULL,                  -- Relay node the envelope was stored on
+    route JSONB NOT NULL,                        -- ReturnRoute
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (envelope_id, node)
+);
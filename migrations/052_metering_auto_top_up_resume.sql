@@ -0,0 +1,15 @@
+-- Resumable auto top-ups
+-- An attempt is 'charged' once the funding source was charged but the
+-- wallet not yet credited, so a retry credits it without charging again.
+-- claimed_at is when a check last took the attempt; an attempt left
+-- unfinished past the claim timeout is taken over by another check.
+
+ALTER TABLE metering_auto_top_ups
+    ADD COLUMN IF NOT EXISTS claimed_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+UPDATE metering_auto_top_ups SET claimed_at = created_at;
+
+ALTER TABLE metering_auto_top_ups
+    DROP CONSTRAINT IF EXISTS metering_auto_top_ups_status_check,
+    ADD CONSTRAINT metering_auto_top_ups_status_check
+        CHECK (status IN ('pending', 'charged', 'succeeded', 'failed'));
@@ -0,0 +1,8 @@
+-- Quota delegation settings
+-- Stored with each period's row so a quota loaded back from the database
+-- limits and surcharges delegated usage as it was defined.
+
+ALTER TABLE quotas
+    ADD COLUMN IF NOT EXISTS max_delegation_depth SMALLINT,
+    -- DelegationMultiplier
+    ADD COLUMN IF NOT EXISTS delegation_multiplier JSONB;
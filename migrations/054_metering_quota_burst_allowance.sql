@@ -0,0 +1,8 @@
+-- Quota burst allowance
+-- Migration 012 added the debt carried between periods but not the
+-- allowance itself, so quotas loaded back from the database could not
+-- borrow. Stored with each period's row, written when a period starts.
+
+ALTER TABLE quotas
+    -- BurstAllowance
+    ADD COLUMN IF NOT EXISTS burst JSONB;
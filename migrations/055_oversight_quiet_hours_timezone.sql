@@ -0,0 +1,20 @@
+-- Quiet hours time zone
+-- Quiet hours stored a fixed UTC offset, which drifts by an hour across
+-- daylight saving changes. They now store an IANA time zone name. Existing
+-- whole-hour offsets become the matching Etc/GMT zone (whose sign is
+-- inverted by convention); other offsets fall back to UTC.
+
+UPDATE oversight_notification_preferences
+SET quiet_hours = (quiet_hours - 'utc_offset_minutes') || jsonb_build_object(
+        'timezone',
+        CASE
+            WHEN (quiet_hours->>'utc_offset_minutes')::INT = 0 THEN 'UTC'
+            WHEN (quiet_hours->>'utc_offset_minutes')::INT % 60 = 0
+                 AND (quiet_hours->>'utc_offset_minutes')::INT BETWEEN -720 AND 840
+            THEN 'Etc/GMT'
+                 || CASE WHEN (quiet_hours->>'utc_offset_minutes')::INT > 0 THEN '-' ELSE '+' END
+                 || ABS((quiet_hours->>'utc_offset_minutes')::INT / 60)::TEXT
+            ELSE 'UTC'
+        END
+    )
+WHERE quiet_hours ? 'utc_offset_minutes';
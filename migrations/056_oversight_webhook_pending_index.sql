@@ -0,0 +1,7 @@
+-- Pending webhook deliveries
+-- Deliveries left pending by a restart are swept and resumed, oldest first.
+-- Only pending rows are indexed; delivered and failed rows are never swept.
+
+CREATE INDEX IF NOT EXISTS idx_oversight_webhook_deliveries_pending
+    ON oversight_webhook_deliveries(updated_at)
+    WHERE state = 'pending';